@@ -0,0 +1,19 @@
+# Titles of the pages
+forgot-title = Forgot Password
+login-title = Login
+register-title = Registration
+reset-title = Reset Password
+
+# Errors of the identity management shown on the pages
+iam-name-taken = User name already in use
+iam-email-taken = Email already in use
+iam-invalid-credentials = Invalid user name or password
+iam-identity-banned = This account has been suspended
+iam-password-policy = The password does not meet the requirements
+iam-session-expired = Your session has expired, please log in again
+iam-email-not-verified = Please verify your email address first
+iam-verification-token-invalid = The verification link is invalid or expired
+iam-reset-token-invalid = The password reset link is invalid or expired
+iam-insufficient-permission = You are not allowed to perform this action
+iam-try-again = The server is busy, please try again
+iam-server-error = Something went wrong, please try again later
@@ -0,0 +1,7 @@
+pub mod proto {
+    tonic::include_proto!("shine.iam");
+}
+
+mod server;
+
+pub use self::server::*;
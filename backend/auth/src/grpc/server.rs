@@ -0,0 +1,199 @@
+use crate::{
+    grpc::proto::{
+        iam_internal_server::{IamInternal, IamInternalServer},
+        CheckPermissionReply, CheckPermissionRequest, IdentityReply, ValidateSessionRequest, ValidateTokenRequest,
+    },
+    iam::{IAMError, IAM},
+};
+use futures::{
+    channel::{mpsc, oneshot},
+    SinkExt, StreamExt,
+};
+use shine_core::{
+    grpc::{GrpcError, InternalApiConfig},
+    requestinfo::RemoteInfo,
+};
+use std::{net::SocketAddr, thread};
+use tonic::{transport::Server, Request, Response, Status};
+
+/// Number of the calls waiting for the IAM
+const CALL_QUEUE_SIZE: usize = 256;
+
+type Reply<T> = oneshot::Sender<Result<T, Status>>;
+
+enum Call {
+    ValidateToken(ValidateTokenRequest, Reply<IdentityReply>),
+    ValidateSession(ValidateSessionRequest, Reply<IdentityReply>),
+    CheckPermission(CheckPermissionRequest, Reply<CheckPermissionReply>),
+}
+
+fn internal_error(err: IAMError) -> Status {
+    log::warn!("Internal api call failed: {:?}", err);
+    Status::internal(format!("{:?}", err))
+}
+
+fn invalid_identity() -> IdentityReply {
+    IdentityReply {
+        valid: false,
+        ..Default::default()
+    }
+}
+
+async fn role_names(iam: &IAM, user_id: &str) -> Result<Vec<String>, Status> {
+    let roles = iam.get_identity_roles(user_id, true).await.map_err(internal_error)?;
+    Ok(roles.into_iter().map(|role| role.role).collect())
+}
+
+async fn validate_token(iam: &IAM, request: ValidateTokenRequest) -> Result<IdentityReply, Status> {
+    match iam.introspect_oauth_token(&request.token).await {
+        Ok(Some(identity)) => Ok(IdentityReply {
+            valid: true,
+            roles: role_names(iam, &identity.user_id).await?,
+            user_id: identity.user_id,
+            client_id: identity.client_id,
+            scopes: identity.scopes,
+            expires: identity.expires.timestamp(),
+        }),
+        Ok(None) => Ok(invalid_identity()),
+        Err(err) => Err(internal_error(err)),
+    }
+}
+
+async fn validate_session(iam: &IAM, request: ValidateSessionRequest) -> Result<IdentityReply, Status> {
+    let remote = RemoteInfo::new(request.agent, request.remote_ip.parse().ok());
+    let fingerprint = iam.get_fingerprint(&remote).await.map_err(internal_error)?;
+    match iam
+        .validate_session(&request.user_id, &request.session_key, &fingerprint)
+        .await
+    {
+        Ok((_, roles)) => Ok(IdentityReply {
+            valid: true,
+            user_id: request.user_id,
+            roles: roles.into_iter().map(|role| role.role).collect(),
+            ..Default::default()
+        }),
+        Err(IAMError::Internal(err)) => Err(internal_error(IAMError::Internal(err))),
+        Err(err) => {
+            log::info!("Session of {} rejected: {:?}", request.user_id, err);
+            Ok(invalid_identity())
+        }
+    }
+}
+
+async fn check_permission(iam: &IAM, request: CheckPermissionRequest) -> Result<CheckPermissionReply, Status> {
+    match iam
+        .check_permission_by_identity(Some(&request.user_id), &request.permission, None)
+        .await
+    {
+        Ok(()) => Ok(CheckPermissionReply { granted: true }),
+        Err(IAMError::InsufficientPermission) | Err(IAMError::IdentityNotFound) => {
+            Ok(CheckPermissionReply { granted: false })
+        }
+        Err(err) => Err(internal_error(err)),
+    }
+}
+
+async fn handle_call(iam: IAM, call: Call) {
+    // the caller may have given up, the reply is dropped in that case
+    match call {
+        Call::ValidateToken(request, reply) => {
+            let _ = reply.send(validate_token(&iam, request).await);
+        }
+        Call::ValidateSession(request, reply) => {
+            let _ = reply.send(validate_session(&iam, request).await);
+        }
+        Call::CheckPermission(request, reply) => {
+            let _ = reply.send(check_permission(&iam, request).await);
+        }
+    }
+}
+
+/// The gRPC service of the IAM. The futures of the IAM are bound to the thread of the runtime,
+/// thus the calls are forwarded through a channel to a local task.
+struct IamGrpcService {
+    calls: mpsc::Sender<Call>,
+}
+
+impl IamGrpcService {
+    async fn call<T, F>(&self, build: F) -> Result<Response<T>, Status>
+    where
+        F: FnOnce(Reply<T>) -> Call,
+    {
+        let (sender, receiver) = oneshot::channel();
+        self.calls
+            .clone()
+            .send(build(sender))
+            .await
+            .map_err(|_| Status::unavailable("IAM is not available"))?;
+        let reply = receiver.await.map_err(|_| Status::internal("IAM call was dropped"))?;
+        reply.map(Response::new)
+    }
+}
+
+#[tonic::async_trait]
+impl IamInternal for IamGrpcService {
+    async fn validate_token(&self, request: Request<ValidateTokenRequest>) -> Result<Response<IdentityReply>, Status> {
+        let request = request.into_inner();
+        self.call(|reply| Call::ValidateToken(request, reply)).await
+    }
+
+    async fn validate_session(
+        &self,
+        request: Request<ValidateSessionRequest>,
+    ) -> Result<Response<IdentityReply>, Status> {
+        let request = request.into_inner();
+        self.call(|reply| Call::ValidateSession(request, reply)).await
+    }
+
+    async fn check_permission(
+        &self,
+        request: Request<CheckPermissionRequest>,
+    ) -> Result<Response<CheckPermissionReply>, Status> {
+        let request = request.into_inner();
+        self.call(|reply| Call::CheckPermission(request, reply)).await
+    }
+}
+
+/// Start the internal api of the IAM on a dedicated thread
+pub fn start_internal_api(iam: IAM, config: &InternalApiConfig) -> Result<(), GrpcError> {
+    let address: SocketAddr = config
+        .bind_address
+        .parse()
+        .map_err(|err| GrpcError::Transport(format!("Invalid address {}: {}", config.bind_address, err)))?;
+    let tls = match &config.tls {
+        Some(tls) => Some(tls.server_tls_config()?),
+        None => {
+            log::warn!("Internal api is not protected by tls");
+            None
+        }
+    };
+
+    thread::Builder::new()
+        .name("iam-grpc".to_owned())
+        .spawn(move || {
+            let mut sys = actix_rt::System::new("iam-grpc");
+            sys.block_on(async move {
+                let (calls, mut receiver) = mpsc::channel::<Call>(CALL_QUEUE_SIZE);
+                actix_rt::spawn(async move {
+                    while let Some(call) = receiver.next().await {
+                        actix_rt::spawn(handle_call(iam.clone(), call));
+                    }
+                });
+
+                let mut server = Server::builder();
+                if let Some(tls) = tls {
+                    server = server.tls_config(tls);
+                }
+                log::info!("Internal api listening on {}", address);
+                if let Err(err) = server
+                    .add_service(IamInternalServer::new(IamGrpcService { calls }))
+                    .serve(address)
+                    .await
+                {
+                    log::error!("Internal api failed: {}", err);
+                }
+            });
+        })
+        .map_err(|err| GrpcError::Transport(format!("Failed to start the internal api: {}", err)))?;
+    Ok(())
+}
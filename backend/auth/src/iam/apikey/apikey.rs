@@ -0,0 +1,119 @@
+use azure_sdk_storage_table::TableEntity;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shine_core::{requestinfo::ApiKeyIdentity, serde_with};
+
+/// Data associated to an api key
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ApiKeyData {
+    pub name: String,
+    pub owner_id: String,
+    /// Comma separated list of the roles granted to the key
+    pub roles: String,
+    pub secret_hash: String,
+
+    #[serde(with = "serde_with::datetime")]
+    pub issued: DateTime<Utc>,
+
+    #[serde(with = "serde_with::opt_datetime")]
+    pub revoked: Option<DateTime<Utc>>,
+}
+
+/// Api key of a service, indexed by the public key id. Only the hash of the secret is stored.
+#[derive(Debug)]
+pub struct ApiKey(TableEntity<ApiKeyData>);
+
+impl ApiKey {
+    pub fn entity_keys(key_id: &str) -> (String, String) {
+        (format!("key-{}", &key_id[0..2]), key_id.to_owned())
+    }
+
+    pub fn new(key_id: &str, name: &str, owner_id: &str, roles: &[String], secret_hash: String) -> Self {
+        let (partition_key, row_key) = Self::entity_keys(key_id);
+        Self(TableEntity {
+            partition_key,
+            row_key,
+            etag: None,
+            timestamp: None,
+            payload: ApiKeyData {
+                name: name.to_owned(),
+                owner_id: owner_id.to_owned(),
+                roles: roles.join(","),
+                secret_hash,
+                issued: Utc::now(),
+                revoked: None,
+            },
+        })
+    }
+
+    pub fn from_entity(entity: TableEntity<ApiKeyData>) -> Self {
+        Self(entity)
+    }
+
+    pub fn into_entity(self) -> TableEntity<ApiKeyData> {
+        self.0
+    }
+
+    pub fn data(&self) -> &ApiKeyData {
+        &self.0.payload
+    }
+
+    pub fn key_id(&self) -> &str {
+        &self.0.row_key
+    }
+
+    pub fn roles(&self) -> Vec<String> {
+        self.0
+            .payload
+            .roles
+            .split(',')
+            .filter(|r| !r.is_empty())
+            .map(|r| r.to_owned())
+            .collect()
+    }
+
+    pub fn is_revoked(&self) -> bool {
+        self.0.payload.revoked.is_some()
+    }
+
+    pub fn revoke(&mut self) {
+        let data = &mut self.0.payload;
+        if data.revoked.is_none() {
+            data.revoked = Some(Utc::now());
+        }
+    }
+
+    pub fn to_identity(&self) -> ApiKeyIdentity {
+        ApiKeyIdentity {
+            key_id: self.key_id().to_owned(),
+            name: self.0.payload.name.clone(),
+            roles: self.roles(),
+        }
+    }
+}
+
+/// Public information of an api key
+#[derive(Debug, Serialize)]
+pub struct ApiKeyInfo {
+    pub key_id: String,
+    pub name: String,
+    pub owner_id: String,
+    pub roles: Vec<String>,
+    pub issued: DateTime<Utc>,
+    pub revoked: Option<DateTime<Utc>>,
+}
+
+impl From<&ApiKey> for ApiKeyInfo {
+    fn from(key: &ApiKey) -> ApiKeyInfo {
+        let data = key.data();
+        ApiKeyInfo {
+            key_id: key.key_id().to_owned(),
+            name: data.name.clone(),
+            owner_id: data.owner_id.clone(),
+            roles: key.roles(),
+            issued: data.issued,
+            revoked: data.revoked,
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use crate::iam::{
+    apikey::{ApiKey, ApiKeyData, ApiKeyStore},
+    identity::StoreFuture,
+    IAMConfig, IAMError,
+};
+use azure_sdk_storage_table::{CloudTable, TableClient};
+use shine_core::azure_utils::{self, table_storage};
+
+/// Api key store using Azure table storage
+#[derive(Clone)]
+pub struct AzureApiKeyStore {
+    db: CloudTable,
+}
+
+impl AzureApiKeyStore {
+    pub async fn new(config: &IAMConfig) -> Result<Self, IAMError> {
+        let client = TableClient::new(&config.storage_account, &config.storage_account_key)?;
+        let db = CloudTable::new(client, "apikeys");
+        db.create_if_not_exists().await?;
+
+        Ok(AzureApiKeyStore { db })
+    }
+}
+
+impl ApiKeyStore for AzureApiKeyStore {
+    fn insert_key(&self, key: ApiKey) -> StoreFuture<'_, ApiKey> {
+        Box::pin(async move {
+            match self.db.insert_entity(key.into_entity()).await {
+                Ok(key) => Ok(ApiKey::from_entity(key)),
+                Err(err) if azure_utils::is_precodition_error(&err) => Err(IAMError::ApiKeyConflict),
+                Err(err) => Err(err.into()),
+            }
+        })
+    }
+
+    fn update_key(&self, key: ApiKey) -> StoreFuture<'_, ApiKey> {
+        Box::pin(async move {
+            let key = self.db.update_entity(key.into_entity()).await?;
+            Ok(ApiKey::from_entity(key))
+        })
+    }
+
+    fn find_key<'a>(&'a self, key_id: &'a str) -> StoreFuture<'a, Option<ApiKey>> {
+        Box::pin(async move {
+            let (p, r) = ApiKey::entity_keys(key_id);
+            let key = self.db.get::<ApiKeyData>(&p, &r, None).await?;
+            Ok(key.map(ApiKey::from_entity))
+        })
+    }
+
+    fn list_keys(&self) -> StoreFuture<'_, Vec<ApiKey>> {
+        Box::pin(async move {
+            let keys = table_storage::query_all::<ApiKeyData>(&self.db, None).await?;
+            Ok(keys.into_iter().map(ApiKey::from_entity).collect())
+        })
+    }
+}
@@ -0,0 +1,128 @@
+use crate::iam::{
+    apikey::{ApiKey, ApiKeyStore, ApiKeyStoreConfig, AzureApiKeyStore, MemoryApiKeyStore},
+    identity::is_valid_token,
+    IAMConfig, IAMError,
+};
+use argon2;
+use data_encoding;
+use rand::{self, seq::SliceRandom, Rng};
+use shine_core::{
+    backoff::{self, Backoff, BackoffError},
+    requestinfo::ApiKeyAuth,
+};
+use std::{sync::Arc, time::Duration};
+
+const KEY_ID_LEN: usize = 16;
+const KEY_ID_ABC: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+const SECRET_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+const SECRET_BASE_ENCODE: data_encoding::Encoding = data_encoding::BASE64URL_NOPAD;
+
+/// Manage the api keys of the services
+#[derive(Clone)]
+pub struct ApiKeyManager {
+    store: Arc<dyn ApiKeyStore>,
+}
+
+impl ApiKeyManager {
+    pub async fn new(config: &IAMConfig) -> Result<Self, IAMError> {
+        let store: Arc<dyn ApiKeyStore> = match &config.apikey_store {
+            ApiKeyStoreConfig::Azure => Arc::new(AzureApiKeyStore::new(config).await?),
+            ApiKeyStoreConfig::Memory => Arc::new(MemoryApiKeyStore::new()),
+        };
+        log::info!("Api key store: {:?}", config.apikey_store);
+
+        Ok(ApiKeyManager { store })
+    }
+
+    fn generate_key_id(&self) -> String {
+        let mut rng = rand::thread_rng();
+        String::from_utf8(
+            (0..KEY_ID_LEN)
+                .map(|_| *KEY_ID_ABC.choose(&mut rng).unwrap())
+                .collect::<Vec<_>>(),
+        )
+        .unwrap()
+    }
+
+    fn generate_secret(&self) -> String {
+        let mut secret = [0u8; SECRET_LEN];
+        rand::thread_rng().fill(&mut secret[..]);
+        SECRET_BASE_ENCODE.encode(&secret)
+    }
+
+    fn hash_secret(secret: &str) -> Result<String, IAMError> {
+        let mut salt = [0u8; SALT_LEN];
+        rand::thread_rng().fill(&mut salt[..]);
+        let config = argon2::Config::default();
+        argon2::hash_encoded(secret.as_bytes(), &salt, &config)
+            .map_err(|err| IAMError::Internal(format!("Argon2 api key hashing failed: {}", err)))
+    }
+
+    async fn try_create_key(
+        &self,
+        name: &str,
+        owner_id: &str,
+        roles: &[String],
+    ) -> Result<(ApiKey, String), BackoffError<IAMError>> {
+        let key_id = self.generate_key_id();
+        let secret = self.generate_secret();
+        let secret_hash = Self::hash_secret(&secret).map_err(IAMError::into_backoff)?;
+
+        let key = ApiKey::new(&key_id, name, owner_id, roles, secret_hash);
+        let key = self.store.insert_key(key).await.map_err(IAMError::into_backoff)?;
+        Ok((key, format!("{}.{}", key_id, secret)))
+    }
+
+    /// Create a new api key. The returned string is the full key including the secret, it
+    /// cannot be queried later.
+    pub async fn create_key(&self, name: &str, owner_id: &str, roles: &[String]) -> Result<(ApiKey, String), IAMError> {
+        let (key, secret) = backoff::Exponential::new(3, Duration::from_micros(10))
+            .async_execute(|_| self.try_create_key(name, owner_id, roles))
+            .await?;
+        log::info!("Api key {} created by {}", key.key_id(), owner_id);
+        Ok((key, secret))
+    }
+
+    async fn find_key(&self, key_id: &str) -> Result<ApiKey, IAMError> {
+        if !is_valid_token(key_id) {
+            return Err(IAMError::ApiKeyNotFound);
+        }
+        self.store.find_key(key_id).await?.ok_or(IAMError::ApiKeyNotFound)
+    }
+
+    pub async fn list_keys(&self) -> Result<Vec<ApiKey>, IAMError> {
+        self.store.list_keys().await
+    }
+
+    pub async fn revoke_key(&self, key_id: &str) -> Result<(), IAMError> {
+        let mut key = self.find_key(key_id).await?;
+        if !key.is_revoked() {
+            key.revoke();
+            self.store.update_key(key).await?;
+            log::info!("Api key {} revoked", key_id);
+        }
+        Ok(())
+    }
+
+    /// Find a key and check the secret. Revoked keys are rejected.
+    pub async fn validate_key(&self, auth: &ApiKeyAuth) -> Result<ApiKey, IAMError> {
+        let key = match self.find_key(auth.key_id()).await {
+            Ok(key) => key,
+            Err(IAMError::ApiKeyNotFound) => return Err(IAMError::ApiKeyInvalid),
+            Err(err) => return Err(err),
+        };
+        if key.is_revoked() {
+            log::info!("Revoked api key {} used", auth.key_id());
+            return Err(IAMError::ApiKeyInvalid);
+        }
+
+        if !argon2::verify_encoded(&key.data().secret_hash, auth.secret().as_bytes())
+            .map_err(|err| IAMError::Internal(format!("Argon2 api key validation failed: {}", err)))?
+        {
+            return Err(IAMError::ApiKeyInvalid);
+        }
+
+        Ok(key)
+    }
+}
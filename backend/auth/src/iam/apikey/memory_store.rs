@@ -0,0 +1,77 @@
+use crate::iam::{
+    apikey::{ApiKey, ApiKeyData, ApiKeyStore},
+    identity::StoreFuture,
+    IAMError,
+};
+use azure_sdk_storage_table::TableEntity;
+use std::{collections::HashMap, sync::Mutex};
+
+fn into_key(key_id: &str, data: ApiKeyData) -> ApiKey {
+    let (partition_key, row_key) = ApiKey::entity_keys(key_id);
+    ApiKey::from_entity(TableEntity {
+        partition_key,
+        row_key,
+        etag: None,
+        timestamp: None,
+        payload: data,
+    })
+}
+
+/// Api key store keeping the keys in the memory of the process, for local development and tests.
+#[derive(Default)]
+pub struct MemoryApiKeyStore {
+    keys: Mutex<HashMap<String, ApiKeyData>>,
+}
+
+impl MemoryApiKeyStore {
+    pub fn new() -> Self {
+        MemoryApiKeyStore::default()
+    }
+
+    fn with_keys<T, F>(&self, f: F) -> StoreFuture<'_, T>
+    where
+        T: 'static,
+        F: FnOnce(&mut HashMap<String, ApiKeyData>) -> Result<T, IAMError>,
+    {
+        let result = {
+            let mut keys = self.keys.lock().unwrap();
+            f(&mut keys)
+        };
+        Box::pin(async move { result })
+    }
+}
+
+impl ApiKeyStore for MemoryApiKeyStore {
+    fn insert_key(&self, key: ApiKey) -> StoreFuture<'_, ApiKey> {
+        self.with_keys(move |keys| {
+            if keys.contains_key(key.key_id()) {
+                return Err(IAMError::ApiKeyConflict);
+            }
+            keys.insert(key.key_id().to_owned(), key.data().clone());
+            Ok(key)
+        })
+    }
+
+    fn update_key(&self, key: ApiKey) -> StoreFuture<'_, ApiKey> {
+        self.with_keys(move |keys| match keys.get_mut(key.key_id()) {
+            Some(data) => {
+                *data = key.data().clone();
+                Ok(key)
+            }
+            None => Err(IAMError::ApiKeyNotFound),
+        })
+    }
+
+    fn find_key<'a>(&'a self, key_id: &'a str) -> StoreFuture<'a, Option<ApiKey>> {
+        self.with_keys(|keys| Ok(keys.get(key_id).map(|data| into_key(key_id, data.clone()))))
+    }
+
+    fn list_keys(&self) -> StoreFuture<'_, Vec<ApiKey>> {
+        self.with_keys(|keys| {
+            Ok(keys
+                .iter()
+                .map(|(key_id, data)| into_key(key_id, data.clone()))
+                .collect())
+        })
+    }
+}
@@ -0,0 +1,11 @@
+mod apikey;
+mod azure_store;
+mod manager;
+mod memory_store;
+mod store;
+
+pub use self::apikey::*;
+pub use self::azure_store::*;
+pub use self::manager::*;
+pub use self::memory_store::*;
+pub use self::store::*;
@@ -0,0 +1,31 @@
+use crate::iam::{apikey::ApiKey, identity::StoreFuture};
+use serde::{Deserialize, Serialize};
+
+/// Storage backend of the api keys
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ApiKeyStoreConfig {
+    /// Azure table storage using the storage account of the IAMConfig
+    Azure,
+    /// Keep the api keys in memory, for local development and tests
+    Memory,
+}
+
+impl Default for ApiKeyStoreConfig {
+    fn default() -> Self {
+        ApiKeyStoreConfig::Azure
+    }
+}
+
+/// Persistence of the api keys. Key generation and secret validation is implemented by the ApiKeyManager.
+pub trait ApiKeyStore {
+    /// Insert a new key, returns ApiKeyConflict if the key id is already in use
+    fn insert_key(&self, key: ApiKey) -> StoreFuture<'_, ApiKey>;
+
+    /// Update an existing key
+    fn update_key(&self, key: ApiKey) -> StoreFuture<'_, ApiKey>;
+
+    fn find_key<'a>(&'a self, key_id: &'a str) -> StoreFuture<'a, Option<ApiKey>>;
+
+    fn list_keys(&self) -> StoreFuture<'_, Vec<ApiKey>>;
+}
@@ -0,0 +1,152 @@
+use crate::iam::{
+    identity::{UserIdentity, ValidatedEmail, ValidatedName, ValidatedPassword},
+    IAMError, IAM,
+};
+use serde::{Deserialize, Serialize};
+use shine_core::backoff::{self, Backoff, BackoffError};
+use std::time::Duration;
+
+/// Maximum number of items in a bulk request
+pub const MAX_BULK_ITEMS: usize = 1000;
+
+/// A user of the bulk import
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportedUser {
+    pub name: String,
+    pub password: String,
+    #[serde(default)]
+    pub email: Option<String>,
+    /// Roles assigned to the user after the registration
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+/// A role assignment of the bulk assignment
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoleAssignment {
+    pub user_id: String,
+    pub role: String,
+}
+
+/// Outcome of an item of a bulk request, the items are processed independently
+#[derive(Debug, Serialize)]
+pub struct BulkItemResult {
+    pub index: usize,
+    /// The id of the identity the item was applied to
+    pub id: Option<String>,
+    pub error: Option<String>,
+}
+
+impl BulkItemResult {
+    fn new(index: usize, result: Result<String, IAMError>) -> BulkItemResult {
+        match result {
+            Ok(id) => BulkItemResult {
+                index,
+                id: Some(id),
+                error: None,
+            },
+            Err(err) => BulkItemResult {
+                index,
+                id: None,
+                error: Some(format!("{:?}", err)),
+            },
+        }
+    }
+}
+
+/// The failures of the stores (ex. throttling) are retried in bulk operations on top of the conflicts
+fn into_bulk_backoff(err: IAMError) -> BackoffError<IAMError> {
+    match err {
+        IAMError::Internal(msg) => BackoffError::Transient(IAMError::Internal(msg)),
+        err => err.into_backoff(),
+    }
+}
+
+fn bulk_backoff() -> backoff::Exponential {
+    backoff::Exponential::new(3, Duration::from_millis(50))
+}
+
+impl IAM {
+    async fn try_create_imported_user(
+        &self,
+        name: &ValidatedName,
+        email: Option<&ValidatedEmail>,
+        password: &ValidatedPassword,
+    ) -> Result<UserIdentity, BackoffError<IAMError>> {
+        self.identity
+            .create_user(name.clone(), email.cloned(), password.clone())
+            .await
+            .map_err(into_bulk_backoff)
+    }
+
+    async fn try_create_role_identity(&self, id: &str) -> Result<(), BackoffError<IAMError>> {
+        self.role.create_identity(id).await.map_err(into_bulk_backoff)
+    }
+
+    async fn try_assign_role(&self, user_id: &str, role: &str) -> Result<(), BackoffError<IAMError>> {
+        self.add_identity_role(user_id, role)
+            .await
+            .map(|_| ())
+            .map_err(into_bulk_backoff)
+    }
+
+    async fn import_user(&self, user: &ImportedUser) -> Result<String, IAMError> {
+        let name = ValidatedName::from_raw(&user.name)
+            .map_err(|err| IAMError::BadRequest(format!("Invalid name: {:?}", err)))?;
+        let email = user
+            .email
+            .as_ref()
+            .map(|email| ValidatedEmail::from_raw(email))
+            .transpose()
+            .map_err(|err| IAMError::BadRequest(format!("Invalid email: {:?}", err)))?;
+        let password = ValidatedPassword::from_raw(&user.password)
+            .map_err(|err| IAMError::BadRequest(format!("Invalid password: {:?}", err)))?;
+        self.check_password_policy(&password).await?;
+
+        let identity = bulk_backoff()
+            .async_execute(|_| self.try_create_imported_user(&name, email.as_ref(), &password))
+            .await?;
+        let id = identity.id().to_owned();
+
+        bulk_backoff()
+            .async_execute(|_| self.try_create_role_identity(&id))
+            .await?;
+        for role in &user.roles {
+            self.assign_role(&id, role).await?;
+        }
+        Ok(id)
+    }
+
+    async fn assign_role(&self, user_id: &str, role: &str) -> Result<String, IAMError> {
+        bulk_backoff()
+            .async_execute(|_| self.try_assign_role(user_id, role))
+            .await?;
+        Ok(user_id.to_owned())
+    }
+
+    /// Register the users without creating a session. The users are processed one by one, the failure
+    /// of a user does not affect the others.
+    pub async fn import_users(&self, users: &[ImportedUser]) -> Vec<BulkItemResult> {
+        let mut results = Vec::with_capacity(users.len());
+        for (index, user) in users.iter().enumerate() {
+            let result = self.import_user(user).await;
+            self.count_operation("import", &result);
+            if let Err(ref err) = result {
+                log::info!("Import of user {} failed: {:?}", user.name, err);
+            }
+            results.push(BulkItemResult::new(index, result));
+        }
+        results
+    }
+
+    /// Assign the roles to the users, the assignments are processed independently
+    pub async fn assign_roles(&self, assignments: &[RoleAssignment]) -> Vec<BulkItemResult> {
+        let mut results = Vec::with_capacity(assignments.len());
+        for (index, assignment) in assignments.iter().enumerate() {
+            let result = self.assign_role(&assignment.user_id, &assignment.role).await;
+            results.push(BulkItemResult::new(index, result));
+        }
+        results
+    }
+}
@@ -0,0 +1,70 @@
+use crate::iam::{
+    entitlement::{Entitlement, EntitlementData, EntitlementStore},
+    identity::StoreFuture,
+    IAMConfig, IAMError,
+};
+use azure_sdk_storage_table::{CloudTable, TableClient};
+use futures::stream::StreamExt;
+use percent_encoding::utf8_percent_encode;
+use shine_core::azure_utils;
+
+/// Entitlement store using Azure table storage, the entitlements of a user share a partition
+#[derive(Clone)]
+pub struct AzureEntitlementStore {
+    db: CloudTable,
+}
+
+impl AzureEntitlementStore {
+    pub async fn new(config: &IAMConfig) -> Result<Self, IAMError> {
+        let client = TableClient::new(&config.storage_account, &config.storage_account_key)?;
+        let db = CloudTable::new(client, "entitlements");
+        db.create_if_not_exists().await?;
+
+        Ok(AzureEntitlementStore { db })
+    }
+}
+
+impl EntitlementStore for AzureEntitlementStore {
+    fn insert_entitlement(&self, entitlement: Entitlement) -> StoreFuture<'_, Entitlement> {
+        Box::pin(async move {
+            match self.db.insert_entity(entitlement.into_entity()).await {
+                Ok(entitlement) => Ok(Entitlement::from_entity(entitlement)),
+                Err(err) if azure_utils::is_precodition_error(&err) => Err(IAMError::EntitlementConflict),
+                Err(err) => Err(err.into()),
+            }
+        })
+    }
+
+    fn update_entitlement(&self, entitlement: Entitlement) -> StoreFuture<'_, Entitlement> {
+        Box::pin(async move {
+            let entitlement = self.db.update_entity(entitlement.into_entity()).await?;
+            Ok(Entitlement::from_entity(entitlement))
+        })
+    }
+
+    fn find_entitlement<'a>(&'a self, user_id: &'a str, content_id: &'a str) -> StoreFuture<'a, Option<Entitlement>> {
+        Box::pin(async move {
+            let (p, r) = Entitlement::entity_keys(user_id, content_id);
+            let entitlement = self.db.get::<EntitlementData>(&p, &r, None).await?;
+            Ok(entitlement.map(Entitlement::from_entity))
+        })
+    }
+
+    fn list_entitlements<'a>(&'a self, user_id: &'a str) -> StoreFuture<'a, Vec<Entitlement>> {
+        Box::pin(async move {
+            let (p, _) = Entitlement::entity_keys(user_id, "");
+            let query = format!("PartitionKey eq '{}'", p);
+            let query = format!(
+                "$filter={}",
+                utf8_percent_encode(&query, percent_encoding::NON_ALPHANUMERIC)
+            );
+
+            let mut result = Vec::new();
+            let mut stream = Box::pin(self.db.stream_query::<EntitlementData>(Some(&query)));
+            while let Some(entitlements) = stream.next().await {
+                result.extend(entitlements?.into_iter().map(Entitlement::from_entity));
+            }
+            Ok(result)
+        })
+    }
+}
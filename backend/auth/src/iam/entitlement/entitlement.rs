@@ -0,0 +1,117 @@
+use azure_sdk_storage_table::TableEntity;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shine_core::serde_with;
+
+/// Data associated to an entitlement
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct EntitlementData {
+    /// Identity who granted the content
+    pub granted_by: String,
+
+    #[serde(with = "serde_with::datetime")]
+    pub granted: DateTime<Utc>,
+
+    #[serde(with = "serde_with::opt_datetime")]
+    pub revoked: Option<DateTime<Utc>>,
+}
+
+/// Ownership of a premium content (DLC, content pack) by a user, indexed by the user and the content id.
+#[derive(Debug)]
+pub struct Entitlement(TableEntity<EntitlementData>);
+
+impl Entitlement {
+    pub fn entity_keys(user_id: &str, content_id: &str) -> (String, String) {
+        (format!("ent-{}", user_id), content_id.to_owned())
+    }
+
+    pub fn new(user_id: &str, content_id: &str, granted_by: &str) -> Self {
+        let (partition_key, row_key) = Self::entity_keys(user_id, content_id);
+        Self(TableEntity {
+            partition_key,
+            row_key,
+            etag: None,
+            timestamp: None,
+            payload: EntitlementData {
+                granted_by: granted_by.to_owned(),
+                granted: Utc::now(),
+                revoked: None,
+            },
+        })
+    }
+
+    pub fn from_entity(entity: TableEntity<EntitlementData>) -> Self {
+        Self(entity)
+    }
+
+    pub fn into_entity(self) -> TableEntity<EntitlementData> {
+        self.0
+    }
+
+    pub fn data(&self) -> &EntitlementData {
+        &self.0.payload
+    }
+
+    pub fn user_id(&self) -> &str {
+        &self.0.partition_key[4..]
+    }
+
+    pub fn content_id(&self) -> &str {
+        &self.0.row_key
+    }
+
+    pub fn is_revoked(&self) -> bool {
+        self.0.payload.revoked.is_some()
+    }
+
+    /// Grant the content again, a revoked entitlement becomes active
+    pub fn regrant(&mut self, granted_by: &str) {
+        let data = &mut self.0.payload;
+        data.granted_by = granted_by.to_owned();
+        data.granted = Utc::now();
+        data.revoked = None;
+    }
+
+    pub fn revoke(&mut self) {
+        let data = &mut self.0.payload;
+        if data.revoked.is_none() {
+            data.revoked = Some(Utc::now());
+        }
+    }
+}
+
+/// Public information of an entitlement
+#[derive(Debug, Serialize)]
+pub struct EntitlementInfo {
+    pub user_id: String,
+    pub content_id: String,
+    pub granted_by: String,
+    pub granted: DateTime<Utc>,
+    pub revoked: Option<DateTime<Utc>>,
+}
+
+impl From<&Entitlement> for EntitlementInfo {
+    fn from(entitlement: &Entitlement) -> EntitlementInfo {
+        let data = entitlement.data();
+        EntitlementInfo {
+            user_id: entitlement.user_id().to_owned(),
+            content_id: entitlement.content_id().to_owned(),
+            granted_by: data.granted_by.clone(),
+            granted: data.granted,
+            revoked: data.revoked,
+        }
+    }
+}
+
+/// Claims of an entitlement token. The token lists the active content of a user and it is verified
+/// by the clients before mounting premium content.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EntitlementClaims {
+    /// The user the token was issued to
+    pub sub: String,
+    /// Content ids owned by the user
+    pub content: Vec<String>,
+    /// Expiration in seconds since the unix epoch
+    pub exp: i64,
+}
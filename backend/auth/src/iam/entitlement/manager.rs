@@ -0,0 +1,125 @@
+use crate::iam::{
+    entitlement::{
+        AzureEntitlementStore, Entitlement, EntitlementClaims, EntitlementStore, EntitlementStoreConfig,
+        MemoryEntitlementStore,
+    },
+    IAMConfig, IAMError,
+};
+use chrono::{Duration, Utc};
+use data_encoding;
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use std::sync::Arc;
+
+const TOKEN_BASE_ENCODE: data_encoding::Encoding = data_encoding::BASE64URL_NOPAD;
+
+/// Manage the premium content owned by the users and issue the signed entitlement tokens.
+#[derive(Clone)]
+pub struct EntitlementManager {
+    store: Arc<dyn EntitlementStore>,
+    signing_key: Option<Arc<Ed25519KeyPair>>,
+    token_time_to_live: Duration,
+}
+
+impl EntitlementManager {
+    pub async fn new(config: &IAMConfig) -> Result<Self, IAMError> {
+        let store: Arc<dyn EntitlementStore> = match &config.entitlement_store {
+            EntitlementStoreConfig::Azure => Arc::new(AzureEntitlementStore::new(config).await?),
+            EntitlementStoreConfig::Memory => Arc::new(MemoryEntitlementStore::new()),
+        };
+        log::info!("Entitlement store: {:?}", config.entitlement_store);
+
+        let signing_key = if config.entitlement_signing_key.is_empty() {
+            log::warn!("Entitlement signing key is not configured, entitlement tokens are disabled");
+            None
+        } else {
+            let seed = data_encoding::BASE64
+                .decode(config.entitlement_signing_key.as_bytes())
+                .map_err(|err| IAMError::Internal(format!("Invalid entitlement signing key: {}", err)))?;
+            let key = Ed25519KeyPair::from_seed_unchecked(&seed)
+                .map_err(|err| IAMError::Internal(format!("Invalid entitlement signing key: {}", err)))?;
+            Some(Arc::new(key))
+        };
+
+        Ok(EntitlementManager {
+            store,
+            signing_key,
+            token_time_to_live: Duration::minutes(i64::from(config.entitlement_token_time_to_live_m)),
+        })
+    }
+
+    /// Public key to verify the entitlement tokens, BASE64 encoded
+    pub fn public_key(&self) -> Option<String> {
+        self.signing_key
+            .as_ref()
+            .map(|key| data_encoding::BASE64.encode(key.public_key().as_ref()))
+    }
+
+    async fn find_entitlement(&self, user_id: &str, content_id: &str) -> Result<Entitlement, IAMError> {
+        self.store
+            .find_entitlement(user_id, content_id)
+            .await?
+            .ok_or(IAMError::EntitlementNotFound)
+    }
+
+    /// Grant a content to a user. Granting an owned content is a no-op, a revoked entitlement is granted again.
+    pub async fn grant(&self, user_id: &str, content_id: &str, granted_by: &str) -> Result<Entitlement, IAMError> {
+        let entitlement = match self.store.find_entitlement(user_id, content_id).await? {
+            Some(entitlement) if !entitlement.is_revoked() => entitlement,
+            Some(mut entitlement) => {
+                entitlement.regrant(granted_by);
+                self.store.update_entitlement(entitlement).await?
+            }
+            None => {
+                self.store
+                    .insert_entitlement(Entitlement::new(user_id, content_id, granted_by))
+                    .await?
+            }
+        };
+        log::info!("Content {} granted to {} by {}", content_id, user_id, granted_by);
+        Ok(entitlement)
+    }
+
+    pub async fn revoke(&self, user_id: &str, content_id: &str) -> Result<(), IAMError> {
+        let mut entitlement = self.find_entitlement(user_id, content_id).await?;
+        if !entitlement.is_revoked() {
+            entitlement.revoke();
+            self.store.update_entitlement(entitlement).await?;
+            log::info!("Content {} revoked from {}", content_id, user_id);
+        }
+        Ok(())
+    }
+
+    pub async fn list(&self, user_id: &str) -> Result<Vec<Entitlement>, IAMError> {
+        self.store.list_entitlements(user_id).await
+    }
+
+    /// Issue a token listing the active entitlements of a user. The token is the BASE64URL encoded
+    /// json claims and the Ed25519 signature of the encoded claims separated by a dot.
+    pub async fn issue_token(&self, user_id: &str) -> Result<(String, EntitlementClaims), IAMError> {
+        let signing_key = self
+            .signing_key
+            .as_ref()
+            .ok_or_else(|| IAMError::Internal("Entitlement signing key is not configured".to_owned()))?;
+
+        let mut content: Vec<String> = self
+            .list(user_id)
+            .await?
+            .iter()
+            .filter(|e| !e.is_revoked())
+            .map(|e| e.content_id().to_owned())
+            .collect();
+        content.sort();
+
+        let claims = EntitlementClaims {
+            sub: user_id.to_owned(),
+            content,
+            exp: (Utc::now() + self.token_time_to_live).timestamp(),
+        };
+        let payload = serde_json::to_vec(&claims)
+            .map_err(|err| IAMError::Internal(format!("Failed to serialize entitlement claims: {}", err)))?;
+        let payload = TOKEN_BASE_ENCODE.encode(&payload);
+        let signature = signing_key.sign(payload.as_bytes());
+        let token = format!("{}.{}", payload, TOKEN_BASE_ENCODE.encode(signature.as_ref()));
+        Ok((token, claims))
+    }
+}
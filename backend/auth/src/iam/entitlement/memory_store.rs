@@ -0,0 +1,87 @@
+use crate::iam::{
+    entitlement::{Entitlement, EntitlementData, EntitlementStore},
+    identity::StoreFuture,
+    IAMError,
+};
+use azure_sdk_storage_table::TableEntity;
+use std::{collections::HashMap, sync::Mutex};
+
+fn into_entitlement(user_id: &str, content_id: &str, data: EntitlementData) -> Entitlement {
+    let (partition_key, row_key) = Entitlement::entity_keys(user_id, content_id);
+    Entitlement::from_entity(TableEntity {
+        partition_key,
+        row_key,
+        etag: None,
+        timestamp: None,
+        payload: data,
+    })
+}
+
+/// Entitlement store keeping the grants in the memory of the process, for local development and tests.
+#[derive(Default)]
+pub struct MemoryEntitlementStore {
+    entitlements: Mutex<HashMap<(String, String), EntitlementData>>,
+}
+
+impl MemoryEntitlementStore {
+    pub fn new() -> Self {
+        MemoryEntitlementStore::default()
+    }
+
+    fn with_entitlements<T, F>(&self, f: F) -> StoreFuture<'_, T>
+    where
+        T: 'static,
+        F: FnOnce(&mut HashMap<(String, String), EntitlementData>) -> Result<T, IAMError>,
+    {
+        let result = {
+            let mut entitlements = self.entitlements.lock().unwrap();
+            f(&mut entitlements)
+        };
+        Box::pin(async move { result })
+    }
+}
+
+impl EntitlementStore for MemoryEntitlementStore {
+    fn insert_entitlement(&self, entitlement: Entitlement) -> StoreFuture<'_, Entitlement> {
+        self.with_entitlements(move |entitlements| {
+            let key = (entitlement.user_id().to_owned(), entitlement.content_id().to_owned());
+            if entitlements.contains_key(&key) {
+                return Err(IAMError::EntitlementConflict);
+            }
+            entitlements.insert(key, entitlement.data().clone());
+            Ok(entitlement)
+        })
+    }
+
+    fn update_entitlement(&self, entitlement: Entitlement) -> StoreFuture<'_, Entitlement> {
+        self.with_entitlements(move |entitlements| {
+            let key = (entitlement.user_id().to_owned(), entitlement.content_id().to_owned());
+            match entitlements.get_mut(&key) {
+                Some(data) => {
+                    *data = entitlement.data().clone();
+                    Ok(entitlement)
+                }
+                None => Err(IAMError::EntitlementNotFound),
+            }
+        })
+    }
+
+    fn find_entitlement<'a>(&'a self, user_id: &'a str, content_id: &'a str) -> StoreFuture<'a, Option<Entitlement>> {
+        self.with_entitlements(|entitlements| {
+            let key = (user_id.to_owned(), content_id.to_owned());
+            Ok(entitlements
+                .get(&key)
+                .map(|data| into_entitlement(user_id, content_id, data.clone())))
+        })
+    }
+
+    fn list_entitlements<'a>(&'a self, user_id: &'a str) -> StoreFuture<'a, Vec<Entitlement>> {
+        self.with_entitlements(|entitlements| {
+            Ok(entitlements
+                .iter()
+                .filter(|((user, _), _)| user == user_id)
+                .map(|((user, content), data)| into_entitlement(user, content, data.clone()))
+                .collect())
+        })
+    }
+}
@@ -0,0 +1,11 @@
+mod azure_store;
+mod entitlement;
+mod manager;
+mod memory_store;
+mod store;
+
+pub use self::azure_store::*;
+pub use self::entitlement::*;
+pub use self::manager::*;
+pub use self::memory_store::*;
+pub use self::store::*;
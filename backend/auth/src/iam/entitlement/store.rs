@@ -0,0 +1,30 @@
+use crate::iam::{entitlement::Entitlement, identity::StoreFuture};
+use serde::{Deserialize, Serialize};
+
+/// Storage backend of the entitlements
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum EntitlementStoreConfig {
+    /// Azure table storage using the storage account of the IAMConfig
+    Azure,
+    /// Keep the entitlements in memory, for local development and tests
+    Memory,
+}
+
+impl Default for EntitlementStoreConfig {
+    fn default() -> Self {
+        EntitlementStoreConfig::Azure
+    }
+}
+
+/// Persistence of the entitlements. Revoked entitlements are kept to preserve the history of the grants.
+pub trait EntitlementStore {
+    fn insert_entitlement(&self, entitlement: Entitlement) -> StoreFuture<'_, Entitlement>;
+
+    fn update_entitlement(&self, entitlement: Entitlement) -> StoreFuture<'_, Entitlement>;
+
+    fn find_entitlement<'a>(&'a self, user_id: &'a str, content_id: &'a str) -> StoreFuture<'a, Option<Entitlement>>;
+
+    /// List all the entitlements of a user including the revoked ones
+    fn list_entitlements<'a>(&'a self, user_id: &'a str) -> StoreFuture<'a, Vec<Entitlement>>;
+}
@@ -1,13 +1,19 @@
+use crate::iam::identity::PasswordPolicyViolation;
 use azure_sdk_core::errors::AzureError;
 use gremlin_client::GremlinError;
+use redis::RedisError;
 use shine_core::{
+    azure_utils::table_storage::TableBatchError,
     backoff::BackoffError,
     idgenerator::IdSequenceError,
     iplocation::IpLocationError,
     kernel::anti_forgery::AntiForgeryError,
     kernel::response::{APIError, PageError},
+    localization::Catalog,
+    mailer::MailerError,
     requestinfo::RequestInfoError,
 };
+use sqlx::Error as SqlxError;
 
 #[derive(Debug)]
 pub enum IAMError {
@@ -23,15 +29,33 @@ pub enum IAMError {
     SequenceIdTaken,
     IdentityIdConflict,
     IdentityNotFound,
+    IdentityBanned,
     PasswordNotMatching,
+    PasswordPolicy(Vec<PasswordPolicyViolation>),
     SessionRequired,
     SessionExpired,
     SessionKeyConflict,
+    EmailNotVerified,
+    VerificationTokenInvalid,
+    VerificationTokenConflict,
+    ResetTokenInvalid,
+    ResetTokenConflict,
+    ApiKeyInvalid,
+    ApiKeyNotFound,
+    ApiKeyConflict,
+    OAuthClientInvalid,
+    OAuthClientNotFound,
+    OAuthClientConflict,
+    OAuthGrantInvalid,
+    OAuthTokenConflict,
+    EntitlementNotFound,
+    EntitlementConflict,
 
     RoleNotFound,
     RoleTaken,
     HasRoleTaken,
     HasRoleCycle(Vec<String>),
+    PermissionTaken,
 
     InsufficientPermission,
 }
@@ -41,11 +65,58 @@ impl IAMError {
         match self {
             IAMError::IdentityIdConflict => BackoffError::Transient(IAMError::IdentityIdConflict),
             IAMError::SessionKeyConflict => BackoffError::Transient(IAMError::SessionKeyConflict),
+            IAMError::VerificationTokenConflict => BackoffError::Transient(IAMError::VerificationTokenConflict),
+            IAMError::ResetTokenConflict => BackoffError::Transient(IAMError::ResetTokenConflict),
+            IAMError::ApiKeyConflict => BackoffError::Transient(IAMError::ApiKeyConflict),
+            IAMError::OAuthClientConflict => BackoffError::Transient(IAMError::OAuthClientConflict),
+            IAMError::OAuthTokenConflict => BackoffError::Transient(IAMError::OAuthTokenConflict),
             e => BackoffError::Permanent(e),
         }
     }
 }
 
+impl IAMError {
+    /// Key in the message catalog and the (english) text of the message shown to the users
+    pub fn user_message(&self) -> (&'static str, &'static str) {
+        match self {
+            IAMError::NameTaken => ("iam-name-taken", "User name already in use"),
+            IAMError::EmailTaken => ("iam-email-taken", "Email already in use"),
+            IAMError::IdentityNotFound | IAMError::PasswordNotMatching => {
+                ("iam-invalid-credentials", "Invalid user name or password")
+            }
+            IAMError::IdentityBanned => ("iam-identity-banned", "This account has been suspended"),
+            IAMError::PasswordPolicy(_) => ("iam-password-policy", "The password does not meet the requirements"),
+            IAMError::SessionRequired | IAMError::SessionExpired => {
+                ("iam-session-expired", "Your session has expired, please log in again")
+            }
+            IAMError::EmailNotVerified => ("iam-email-not-verified", "Please verify your email address first"),
+            IAMError::VerificationTokenInvalid => (
+                "iam-verification-token-invalid",
+                "The verification link is invalid or expired",
+            ),
+            IAMError::ResetTokenInvalid => (
+                "iam-reset-token-invalid",
+                "The password reset link is invalid or expired",
+            ),
+            IAMError::InsufficientPermission => (
+                "iam-insufficient-permission",
+                "You are not allowed to perform this action",
+            ),
+            IAMError::IdentityIdConflict
+            | IAMError::SessionKeyConflict
+            | IAMError::VerificationTokenConflict
+            | IAMError::ResetTokenConflict => ("iam-try-again", "The server is busy, please try again"),
+            _ => ("iam-server-error", "Something went wrong, please try again later"),
+        }
+    }
+
+    /// The message shown to the users in the given language
+    pub fn localized_message(&self, catalog: &Catalog, lang: &str) -> String {
+        let (key, fallback) = self.user_message();
+        catalog.translate_or(lang, key, fallback, &[])
+    }
+}
+
 impl From<IAMError> for PageError {
     fn from(err: IAMError) -> PageError {
         PageError::Internal(format!("{:?}", err))
@@ -61,15 +132,30 @@ impl From<IAMError> for PageError {
             IAMError::SequenceIdTaken => StatusCode::CONFLICT,
             IAMError::IdentityIdConflict => StatusCode::TOO_MANY_REQUESTS,
             IAMError::IdentityNotFound => StatusCode::FORBIDDEN,
+            IAMError::IdentityBanned => StatusCode::FORBIDDEN,
             IAMError::PasswordNotMatching => StatusCode::FORBIDDEN,
             IAMError::SessionRequired => StatusCode::UNAUTHORIZED,
             IAMError::SessionExpired => StatusCode::UNAUTHORIZED,
             IAMError::SessionKeyConflict => StatusCode::TOO_MANY_REQUESTS,
+            IAMError::EmailNotVerified => StatusCode::FORBIDDEN,
+            IAMError::VerificationTokenInvalid => StatusCode::BAD_REQUEST,
+            IAMError::VerificationTokenConflict => StatusCode::TOO_MANY_REQUESTS,
+            IAMError::ResetTokenInvalid => StatusCode::BAD_REQUEST,
+            IAMError::ResetTokenConflict => StatusCode::TOO_MANY_REQUESTS,
+            IAMError::ApiKeyInvalid => StatusCode::UNAUTHORIZED,
+            IAMError::ApiKeyNotFound => StatusCode::NOT_FOUND,
+            IAMError::ApiKeyConflict => StatusCode::TOO_MANY_REQUESTS,
+            IAMError::OAuthClientInvalid => StatusCode::UNAUTHORIZED,
+            IAMError::OAuthClientNotFound => StatusCode::NOT_FOUND,
+            IAMError::OAuthClientConflict => StatusCode::TOO_MANY_REQUESTS,
+            IAMError::OAuthGrantInvalid => StatusCode::BAD_REQUEST,
+            IAMError::OAuthTokenConflict => StatusCode::TOO_MANY_REQUESTS,
 
             IAMError::RoleNotFound => StatusCode::NOT_FOUND,
             IAMError::RoleTaken => StatusCode::CONFLICT,
             IAMError::HasRoleTaken => StatusCode::CONFLICT,
             IAMError::HasRoleCycle(_) => StatusCode::CONFLICT,
+            IAMError::PermissionTaken => StatusCode::CONFLICT,
 
             IAMError::InsufficientPermission => StatusCode::FORBIDDEN,
         }*/
@@ -78,7 +164,12 @@ impl From<IAMError> for PageError {
 
 impl From<IAMError> for APIError {
     fn from(err: IAMError) -> APIError {
-        APIError::Internal(format!("{:?}", err))
+        match err {
+            IAMError::PasswordPolicy(violations) => {
+                APIError::BadRequest(serde_json::to_string(&violations).unwrap_or_default())
+            }
+            err => APIError::Internal(format!("{:?}", err)),
+        }
         /*match *self {
             IAMError::Internal(_) => APIError::Internal,
             IAMError::BadRequest(r) => APIError::BAD_REQUEST,
@@ -91,15 +182,30 @@ impl From<IAMError> for APIError {
             IAMError::SequenceIdTaken => StatusCode::CONFLICT,
             IAMError::IdentityIdConflict => StatusCode::TOO_MANY_REQUESTS,
             IAMError::IdentityNotFound => StatusCode::FORBIDDEN,
+            IAMError::IdentityBanned => StatusCode::FORBIDDEN,
             IAMError::PasswordNotMatching => StatusCode::FORBIDDEN,
             IAMError::SessionRequired => StatusCode::UNAUTHORIZED,
             IAMError::SessionExpired => StatusCode::UNAUTHORIZED,
             IAMError::SessionKeyConflict => StatusCode::TOO_MANY_REQUESTS,
+            IAMError::EmailNotVerified => StatusCode::FORBIDDEN,
+            IAMError::VerificationTokenInvalid => StatusCode::BAD_REQUEST,
+            IAMError::VerificationTokenConflict => StatusCode::TOO_MANY_REQUESTS,
+            IAMError::ResetTokenInvalid => StatusCode::BAD_REQUEST,
+            IAMError::ResetTokenConflict => StatusCode::TOO_MANY_REQUESTS,
+            IAMError::ApiKeyInvalid => StatusCode::UNAUTHORIZED,
+            IAMError::ApiKeyNotFound => StatusCode::NOT_FOUND,
+            IAMError::ApiKeyConflict => StatusCode::TOO_MANY_REQUESTS,
+            IAMError::OAuthClientInvalid => StatusCode::UNAUTHORIZED,
+            IAMError::OAuthClientNotFound => StatusCode::NOT_FOUND,
+            IAMError::OAuthClientConflict => StatusCode::TOO_MANY_REQUESTS,
+            IAMError::OAuthGrantInvalid => StatusCode::BAD_REQUEST,
+            IAMError::OAuthTokenConflict => StatusCode::TOO_MANY_REQUESTS,
 
             IAMError::RoleNotFound => StatusCode::NOT_FOUND,
             IAMError::RoleTaken => StatusCode::CONFLICT,
             IAMError::HasRoleTaken => StatusCode::CONFLICT,
             IAMError::HasRoleCycle(_) => StatusCode::CONFLICT,
+            IAMError::PermissionTaken => StatusCode::CONFLICT,
 
             IAMError::InsufficientPermission => StatusCode::FORBIDDEN,
         }*/
@@ -112,6 +218,12 @@ impl From<AzureError> for IAMError {
     }
 }
 
+impl From<TableBatchError> for IAMError {
+    fn from(err: TableBatchError) -> IAMError {
+        IAMError::Internal(format!("{}", err))
+    }
+}
+
 impl From<IpLocationError> for IAMError {
     fn from(err: IpLocationError) -> IAMError {
         IAMError::Internal(format!("{:?}", err))
@@ -139,8 +251,26 @@ impl From<RequestInfoError> for IAMError {
     }
 }
 
+impl From<MailerError> for IAMError {
+    fn from(err: MailerError) -> IAMError {
+        IAMError::Internal(err.to_string())
+    }
+}
+
 impl From<GremlinError> for IAMError {
     fn from(err: GremlinError) -> IAMError {
         IAMError::Internal(err.to_string())
     }
 }
+
+impl From<SqlxError> for IAMError {
+    fn from(err: SqlxError) -> IAMError {
+        IAMError::Internal(err.to_string())
+    }
+}
+
+impl From<RedisError> for IAMError {
+    fn from(err: RedisError) -> IAMError {
+        IAMError::Internal(err.to_string())
+    }
+}
@@ -0,0 +1,61 @@
+use crate::iam::{identity::Identity, role::InheritedRoles, session::SessionInfo};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// The personal data of an identity, the secrets (password hash, salt) are not included.
+#[derive(Debug, Serialize)]
+pub struct IdentityExportInfo {
+    pub id: String,
+    pub sequence_id: u64,
+    pub name: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    pub deletion_scheduled: Option<DateTime<Utc>>,
+    pub banned: Option<DateTime<Utc>>,
+    pub guest: bool,
+}
+
+impl<T: Identity> From<&T> for IdentityExportInfo {
+    fn from(identity: &T) -> Self {
+        let core = identity.core();
+        IdentityExportInfo {
+            id: core.id.clone(),
+            sequence_id: core.sequence_id,
+            name: core.name.to_raw(),
+            email: core.email.as_ref().map(|email| email.to_raw()),
+            email_verified: identity.email_verified(),
+            deletion_scheduled: core.deletion_scheduled,
+            banned: core.banned,
+            guest: core.guest,
+        }
+    }
+}
+
+/// A session in the data export, the credentials and the network details are not included
+#[derive(Debug, Serialize)]
+pub struct SessionExportInfo {
+    pub id: String,
+    pub agent: String,
+    pub issued: DateTime<Utc>,
+    pub refreshed: DateTime<Utc>,
+}
+
+impl From<SessionInfo> for SessionExportInfo {
+    fn from(session: SessionInfo) -> Self {
+        SessionExportInfo {
+            id: session.id,
+            agent: session.agent,
+            issued: session.issued,
+            refreshed: session.refreshed,
+        }
+    }
+}
+
+/// Everything stored about an identity, the response of a data export request
+#[derive(Debug, Serialize)]
+pub struct IdentityExport {
+    pub exported: DateTime<Utc>,
+    pub identity: IdentityExportInfo,
+    pub sessions: Vec<SessionExportInfo>,
+    pub roles: InheritedRoles,
+}
@@ -17,7 +17,7 @@ pub struct Fingerprint {
 }
 
 impl Fingerprint {
-    pub async fn new<P: IpLocationProvider>(remote: &RemoteInfo, iplocation: &P) -> Result<Self, IAMError> {
+    pub async fn new<P: IpLocationProvider + ?Sized>(remote: &RemoteInfo, iplocation: &P) -> Result<Self, IAMError> {
         log::debug!("Remote info: {:?}", remote);
 
         let location = if let Some(ip) = remote.remote() {
@@ -0,0 +1,504 @@
+use crate::iam::{
+    identity::{
+        CoreIdentity, CoreIdentityData, CoreIdentityIndexedData, EmailVerification, EmailVerificationData, Identity,
+        IdentityCategory, IdentitySearch, IdentitySearchPage, IdentityStore, IndexEmail, IndexIdentity, IndexName,
+        IndexSequence, PasswordReset, PasswordResetData, StoreFuture, UserIdentity, ValidatedEmail, ValidatedName,
+    },
+    IAMConfig, IAMError,
+};
+use azure_sdk_storage_table::{CloudTable, TableClient, TableEntity};
+use chrono::{DateTime, Utc};
+use shine_core::{
+    azure_utils::{
+        self,
+        table_storage::{self, EmptyData},
+    },
+    idgenerator::{IdSequence, SyncCounterConfig},
+    serde_with,
+};
+
+/// Create the filter of the index entities for a prefix search. The index is partitioned by the first two
+/// characters of the keys, thus a non-empty prefix must have at least two characters and it is searched in a
+/// single partition. The empty prefix searches all the partitions of the index.
+fn index_search_filter(partition_prefix: &str, prefix: &str, continuation: Option<&str>) -> String {
+    let partition = |key: &str| format!("{}{}", partition_prefix, key.chars().take(2).collect::<String>());
+
+    let mut filter = if prefix.is_empty() {
+        // '.' follows '-' thus it limits the range to the partitions of the index
+        format!(
+            "PartitionKey gt '{}' and PartitionKey lt '{}.'",
+            partition_prefix,
+            partition_prefix.trim_end_matches('-')
+        )
+    } else {
+        // the keys are safe key encoded, incrementing the last character gives the end of the prefix range
+        let mut end = prefix.to_owned();
+        let last = end.pop().unwrap();
+        end.push(std::char::from_u32(last as u32 + 1).unwrap_or(last));
+        format!(
+            "PartitionKey eq '{}' and RowKey ge '{}' and RowKey lt '{}'",
+            partition(prefix),
+            prefix,
+            end
+        )
+    };
+
+    if let Some(continuation) = continuation {
+        filter = format!(
+            "{} and (PartitionKey gt '{p}' or (PartitionKey eq '{p}' and RowKey gt '{r}'))",
+            filter,
+            p = partition(continuation),
+            r = continuation
+        );
+    }
+    filter
+}
+
+/// Identity store using Azure table storage. The uniqueness of the name, email and sequence id is
+/// ensured by index entities in the same table.
+#[derive(Clone)]
+pub struct AzureIdentityStore {
+    identity_id_generator: IdSequence,
+    db: CloudTable,
+    reset_db: CloudTable,
+}
+
+impl AzureIdentityStore {
+    pub async fn new(config: &IAMConfig) -> Result<Self, IAMError> {
+        let client = TableClient::new(&config.storage_account, &config.storage_account_key)?;
+        let db = CloudTable::new(client.clone(), "identities");
+        db.create_if_not_exists().await?;
+        let reset_db = CloudTable::new(client, "passwordresets");
+        reset_db.create_if_not_exists().await?;
+
+        let identity_id_generator = {
+            let id_config = SyncCounterConfig {
+                storage_account: config.storage_account.clone(),
+                storage_account_key: config.storage_account_key.clone(),
+                starting_value: 1_000_000,
+                table_name: "idcounter".to_string(),
+            };
+            let sequence_config = &config.identity_id_sequence;
+            let id_counter = sequence_config.backend.create_backend(id_config).await?;
+            log::info!("Identity id counter: {:?}", sequence_config.backend);
+            IdSequence::new(id_counter, "identityId").with_granularity(sequence_config.granularity)
+        };
+
+        Ok(AzureIdentityStore {
+            identity_id_generator,
+            db,
+            reset_db,
+        })
+    }
+
+    async fn remove_identity<T>(&self, identity: T)
+    where
+        T: Identity,
+    {
+        let identity = identity.into_entity();
+        let (p, r) = (identity.partition_key.clone(), identity.row_key.clone());
+        self.db
+            .delete_entity(identity)
+            .await
+            .unwrap_or_else(|e| log::error!("Failed to delete identity([{}]/[{}]): {}", p, r, e));
+    }
+
+    async fn find_identity_by_id<T>(&self, id: &str) -> Result<T, IAMError>
+    where
+        T: Identity,
+    {
+        let (p, r) = T::entity_keys(&id);
+        let identity = self.db.get(&p, &r, None).await?;
+        let identity = identity.map(T::from_entity).ok_or(IAMError::IdentityNotFound)?;
+
+        Ok(identity)
+    }
+
+    async fn remove_index<T>(&self, index: T)
+    where
+        T: IndexIdentity,
+    {
+        let index = index.into_entity();
+        self.db
+            .delete_entity(index)
+            .await
+            .unwrap_or_else(|e| log::error!("Failed to delete index: {}", e));
+    }
+
+    async fn find_identity_by_index<T>(&self, p: &str, r: &str) -> Result<T, IAMError>
+    where
+        T: Identity,
+    {
+        let query = table_storage::filter_query(&format!("PartitionKey eq '{}' and RowKey eq '{}'", p, r));
+        let indices = table_storage::query_limited::<CoreIdentityIndexedData>(&self.db, Some(&query), 2).await?;
+        match &indices[..] {
+            [index] => {
+                let identity_id = &index.payload.identity_id;
+                let (p, r) = T::entity_keys(&identity_id);
+                let identity = self.db.get(&p, &r, None).await?.ok_or(IAMError::IdentityNotFound)?;
+                Ok(T::from_entity(identity))
+            }
+            _ => Err(IAMError::IdentityNotFound),
+        }
+    }
+
+    async fn insert_sequence_index<T>(&self, identity: &T) -> Result<IndexSequence, IAMError>
+    where
+        T: Identity,
+    {
+        let sequence_index = IndexSequence::from_identity(identity);
+        match self.db.insert_entity(sequence_index.into_entity()).await {
+            Ok(sequence_index) => Ok(IndexSequence::from_entity(sequence_index)),
+            Err(e) => {
+                if azure_utils::is_precodition_error(&e) {
+                    Err(IAMError::SequenceIdTaken)
+                } else {
+                    Err(IAMError::from(e))
+                }
+            }
+        }
+    }
+
+    async fn insert_name_index<T>(&self, identity: &T) -> Result<IndexName, IAMError>
+    where
+        T: Identity,
+    {
+        let name_index = IndexName::from_identity(identity);
+        match self.db.insert_entity(name_index.into_entity()).await {
+            Ok(name_index) => Ok(IndexName::from_entity(name_index)),
+            Err(e) => {
+                if azure_utils::is_precodition_error(&e) {
+                    Err(IAMError::NameTaken)
+                } else {
+                    Err(IAMError::from(e))
+                }
+            }
+        }
+    }
+
+    async fn insert_email_index<T>(&self, identity: &T) -> Result<Option<IndexEmail>, IAMError>
+    where
+        T: Identity,
+    {
+        if let Some(email_index) = IndexEmail::from_identity(identity) {
+            match self.db.insert_entity(email_index.into_entity()).await {
+                Ok(email_index) => Ok(Some(IndexEmail::from_entity(email_index))),
+                Err(err) => {
+                    if azure_utils::is_precodition_error(&err) {
+                        Err(IAMError::EmailTaken)
+                    } else {
+                        Err(IAMError::from(err))
+                    }
+                }
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
+    async fn insert_user_impl(&self, identity: UserIdentity) -> Result<UserIdentity, IAMError> {
+        let identity = self.db.insert_entity(identity.into_entity()).await.map_err(|err| {
+            if azure_utils::is_precodition_error(&err) {
+                IAMError::IdentityIdConflict
+            } else {
+                IAMError::from(err)
+            }
+        })?;
+        let identity = UserIdentity::from_entity(identity);
+
+        let sequence_index = match self.insert_sequence_index(&identity).await {
+            Ok(index) => index,
+            Err(e) => {
+                log::info!("Creating user failed (sequence_index): {:?}, {:?}", identity, e);
+                self.remove_identity(identity).await;
+                return Err(e);
+            }
+        };
+
+        let name_index = match self.insert_name_index(&identity).await {
+            Ok(index) => index,
+            Err(e) => {
+                log::info!("Creating user failed (name_index): {:?}, {:?}", identity, e);
+                self.remove_identity(identity).await;
+                self.remove_index(sequence_index).await;
+                return Err(e);
+            }
+        };
+
+        let email_index = match self.insert_email_index(&identity).await {
+            Ok(index) => index,
+            Err(e) => {
+                log::info!("Creating user failed (email_index): {:?}, {:?}", identity, e);
+                self.remove_identity(identity).await;
+                self.remove_index(sequence_index).await;
+                self.remove_index(name_index).await;
+                return Err(e);
+            }
+        };
+
+        log::debug!("Name index: {:?}", name_index);
+        log::debug!("Email index: {:?}", email_index);
+        log::debug!("Sequence index: {:?}", sequence_index);
+        Ok(identity)
+    }
+
+    /// Update the user and move the name and email indices if they have been changed. The new indices are
+    /// inserted first to reserve the name and email, the old indices are removed only after the identity was updated.
+    async fn update_user_impl(&self, identity: UserIdentity) -> Result<UserIdentity, IAMError> {
+        let old_identity = self.find_identity_by_id::<UserIdentity>(identity.id()).await?;
+        let name_changed = old_identity.core().name.as_str() != identity.core().name.as_str();
+        let email_changed = old_identity.core().email.as_ref().map(|e| e.as_str())
+            != identity.core().email.as_ref().map(|e| e.as_str());
+        if !name_changed && !email_changed {
+            let identity = self.db.update_entity(identity.into_entity()).await?;
+            return Ok(UserIdentity::from_entity(identity));
+        }
+
+        let name_index = if name_changed {
+            Some(self.insert_name_index(&identity).await?)
+        } else {
+            None
+        };
+
+        let email_index = if email_changed {
+            match self.insert_email_index(&identity).await {
+                Ok(index) => index,
+                Err(e) => {
+                    if let Some(name_index) = name_index {
+                        self.remove_index(name_index).await;
+                    }
+                    return Err(e);
+                }
+            }
+        } else {
+            None
+        };
+
+        let identity = match self.db.update_entity(identity.into_entity()).await {
+            Ok(identity) => UserIdentity::from_entity(identity),
+            Err(e) => {
+                log::info!("Updating user failed: {:?}", e);
+                if let Some(name_index) = name_index {
+                    self.remove_index(name_index).await;
+                }
+                if let Some(email_index) = email_index {
+                    self.remove_index(email_index).await;
+                }
+                return Err(e.into());
+            }
+        };
+
+        if name_changed {
+            self.remove_index(IndexName::from_identity(&old_identity)).await;
+            log::debug!("Name index of {} moved: {:?}", identity.id(), name_index);
+        }
+        if email_changed {
+            if let Some(old_email_index) = IndexEmail::from_identity(&old_identity) {
+                self.remove_index(old_email_index).await;
+            }
+            log::debug!("Email index of {} moved: {:?}", identity.id(), email_index);
+        }
+        Ok(identity)
+    }
+
+    async fn find_identities_to_delete_impl(&self, before: DateTime<Utc>) -> Result<Vec<String>, IAMError> {
+        // dates are stored as sortable strings, the empty string is for the not scheduled identities
+        let query = table_storage::filter_query(&format!(
+            "DeletionScheduled gt '' and DeletionScheduled lt '{}'",
+            before.format(serde_with::DATE_TIME_FORMAT)
+        ));
+        let identities = table_storage::query_all::<CoreIdentityData>(&self.db, Some(&query)).await?;
+        Ok(identities.into_iter().map(|identity| identity.payload.id).collect())
+    }
+
+    async fn search_identities_impl(
+        &self,
+        search: &IdentitySearch,
+        continuation: Option<&str>,
+        limit: usize,
+    ) -> Result<IdentitySearchPage, IAMError> {
+        let filter = match search {
+            IdentitySearch::Name(prefix) => index_search_filter("x_name-", prefix, continuation),
+            IdentitySearch::Email(prefix) => index_search_filter("x_user_email-", prefix, continuation),
+        };
+        let query = format!("{}&$top={}", table_storage::filter_query(&filter), limit);
+        let indices = table_storage::query_limited::<CoreIdentityIndexedData>(&self.db, Some(&query), limit).await?;
+
+        let continuation = if indices.len() == limit {
+            indices.last().map(|index| index.row_key.clone())
+        } else {
+            None
+        };
+        let mut identities = Vec::with_capacity(indices.len());
+        for index in indices {
+            match self
+                .find_identity_by_id::<CoreIdentity>(&index.payload.identity_id)
+                .await
+            {
+                Ok(identity) => identities.push(identity),
+                Err(IAMError::IdentityNotFound) => {
+                    log::warn!("Dangling index ({}) of {}", index.row_key, index.payload.identity_id)
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(IdentitySearchPage {
+            identities,
+            continuation,
+        })
+    }
+
+    async fn delete_user_impl(&self, identity: UserIdentity) -> Result<(), IAMError> {
+        self.remove_index(IndexSequence::from_identity(&identity)).await;
+        self.remove_index(IndexName::from_identity(&identity)).await;
+        if let Some(email_index) = IndexEmail::from_identity(&identity) {
+            self.remove_index(email_index).await;
+        }
+        self.db.delete_entity(identity.into_entity()).await?;
+        Ok(())
+    }
+
+    async fn delete_token<D>(db: &CloudTable, entity: &TableEntity<D>) {
+        // the token entity is returned to the caller, delete it by the keys only
+        let key = TableEntity {
+            partition_key: entity.partition_key.clone(),
+            row_key: entity.row_key.clone(),
+            etag: None,
+            timestamp: None,
+            payload: EmptyData {},
+        };
+        db.delete_entity(key).await.unwrap_or_else(|e| {
+            log::error!(
+                "Failed to delete token ([{}]/[{}]): {}",
+                entity.partition_key,
+                entity.row_key,
+                e
+            )
+        });
+    }
+
+    async fn take_email_verification_impl(&self, token: &str) -> Result<Option<EmailVerification>, IAMError> {
+        let (p, r) = EmailVerification::entity_keys(token);
+        match self.db.get::<EmailVerificationData>(&p, &r, None).await? {
+            Some(verification) => {
+                Self::delete_token(&self.db, &verification).await;
+                Ok(Some(EmailVerification::from_entity(verification)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn take_password_reset_impl(&self, token: &str) -> Result<Option<PasswordReset>, IAMError> {
+        let (p, r) = PasswordReset::entity_keys(token);
+        match self.reset_db.get::<PasswordResetData>(&p, &r, None).await? {
+            Some(reset) => {
+                Self::delete_token(&self.reset_db, &reset).await;
+                Ok(Some(PasswordReset::from_entity(reset)))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl IdentityStore for AzureIdentityStore {
+    fn next_sequence_id(&self) -> StoreFuture<'_, u64> {
+        Box::pin(async move { Ok(self.identity_id_generator.get().await?) })
+    }
+
+    fn is_name_available<'a>(&'a self, name: &'a ValidatedName) -> StoreFuture<'a, bool> {
+        Box::pin(async move {
+            let (partition_key, row_key) = IndexName::entity_keys(name);
+            Ok(self
+                .db
+                .get::<EmptyData>(&partition_key, &row_key, None)
+                .await?
+                .is_none())
+        })
+    }
+
+    fn is_email_available<'a>(&'a self, cat: IdentityCategory, email: &'a ValidatedEmail) -> StoreFuture<'a, bool> {
+        Box::pin(async move {
+            let (partition_key, row_key) = IndexEmail::entity_keys(cat, email);
+            Ok(self
+                .db
+                .get::<EmptyData>(&partition_key, &row_key, None)
+                .await?
+                .is_none())
+        })
+    }
+
+    fn insert_user(&self, identity: UserIdentity) -> StoreFuture<'_, UserIdentity> {
+        Box::pin(self.insert_user_impl(identity))
+    }
+
+    fn update_user(&self, identity: UserIdentity) -> StoreFuture<'_, UserIdentity> {
+        Box::pin(self.update_user_impl(identity))
+    }
+
+    fn find_user_by_id<'a>(&'a self, id: &'a str) -> StoreFuture<'a, UserIdentity> {
+        Box::pin(self.find_identity_by_id(id))
+    }
+
+    fn find_user_by_name<'a>(&'a self, name: &'a ValidatedName) -> StoreFuture<'a, UserIdentity> {
+        Box::pin(async move {
+            let (p, r) = IndexName::entity_keys(name);
+            self.find_identity_by_index(&p, &r).await
+        })
+    }
+
+    fn find_user_by_email<'a>(&'a self, email: &'a ValidatedEmail) -> StoreFuture<'a, UserIdentity> {
+        Box::pin(async move {
+            let (p, r) = IndexEmail::entity_keys(IdentityCategory::User, email);
+            self.find_identity_by_index(&p, &r).await
+        })
+    }
+
+    fn find_core_identity_by_id<'a>(&'a self, id: &'a str) -> StoreFuture<'a, CoreIdentity> {
+        Box::pin(self.find_identity_by_id(id))
+    }
+
+    fn search_identities<'a>(
+        &'a self,
+        search: &'a IdentitySearch,
+        continuation: Option<&'a str>,
+        limit: usize,
+    ) -> StoreFuture<'a, IdentitySearchPage> {
+        Box::pin(self.search_identities_impl(search, continuation, limit))
+    }
+
+    fn find_identities_to_delete(&self, before: DateTime<Utc>) -> StoreFuture<'_, Vec<String>> {
+        Box::pin(self.find_identities_to_delete_impl(before))
+    }
+
+    fn delete_user(&self, identity: UserIdentity) -> StoreFuture<'_, ()> {
+        Box::pin(self.delete_user_impl(identity))
+    }
+
+    fn insert_email_verification(&self, verification: EmailVerification) -> StoreFuture<'_, EmailVerification> {
+        Box::pin(async move {
+            match self.db.insert_entity(verification.into_entity()).await {
+                Ok(verification) => Ok(EmailVerification::from_entity(verification)),
+                Err(err) if azure_utils::is_precodition_error(&err) => Err(IAMError::VerificationTokenConflict),
+                Err(err) => Err(IAMError::from(err)),
+            }
+        })
+    }
+
+    fn take_email_verification<'a>(&'a self, token: &'a str) -> StoreFuture<'a, Option<EmailVerification>> {
+        Box::pin(self.take_email_verification_impl(token))
+    }
+
+    fn insert_password_reset(&self, reset: PasswordReset) -> StoreFuture<'_, PasswordReset> {
+        Box::pin(async move {
+            match self.reset_db.insert_entity(reset.into_entity()).await {
+                Ok(reset) => Ok(PasswordReset::from_entity(reset)),
+                Err(err) if azure_utils::is_precodition_error(&err) => Err(IAMError::ResetTokenConflict),
+                Err(err) => Err(IAMError::from(err)),
+            }
+        })
+    }
+
+    fn take_password_reset<'a>(&'a self, token: &'a str) -> StoreFuture<'a, Option<PasswordReset>> {
+        Box::pin(self.take_password_reset_impl(token))
+    }
+}
@@ -0,0 +1,74 @@
+use azure_sdk_storage_table::TableEntity;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shine_core::serde_with;
+
+/// Data associated to an email verification token
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct EmailVerificationData {
+    pub identity_id: String,
+    pub email: String,
+
+    #[serde(with = "serde_with::datetime")]
+    pub issued: DateTime<Utc>,
+
+    /// The token confirms a change to a new email instead of verifying the current one
+    #[serde(default)]
+    pub email_change: bool,
+}
+
+/// Pending email verification, indexed by the (secret) token sent out in the verification mail
+#[derive(Debug)]
+pub struct EmailVerification(TableEntity<EmailVerificationData>);
+
+impl EmailVerification {
+    pub fn entity_keys(token: &str) -> (String, String) {
+        (format!("x_verify-{}", &token[0..2]), token.to_string())
+    }
+
+    pub fn new(token: &str, id: &str, email: &str) -> Self {
+        Self::with_email_change(token, id, email, false)
+    }
+
+    /// Create a token confirming the change of the email of the identity to the given email
+    pub fn new_email_change(token: &str, id: &str, email: &str) -> Self {
+        Self::with_email_change(token, id, email, true)
+    }
+
+    fn with_email_change(token: &str, id: &str, email: &str, email_change: bool) -> Self {
+        let (partition_key, row_key) = Self::entity_keys(token);
+        Self(TableEntity {
+            partition_key,
+            row_key,
+            etag: None,
+            timestamp: None,
+            payload: EmailVerificationData {
+                identity_id: id.to_owned(),
+                email: email.to_owned(),
+                issued: Utc::now(),
+                email_change,
+            },
+        })
+    }
+
+    pub fn from_entity(entity: TableEntity<EmailVerificationData>) -> Self {
+        Self(entity)
+    }
+
+    pub fn into_entity(self) -> TableEntity<EmailVerificationData> {
+        self.0
+    }
+
+    pub fn data(&self) -> &EmailVerificationData {
+        &self.0.payload
+    }
+
+    pub fn id(&self) -> &str {
+        &self.0.payload.identity_id
+    }
+
+    pub fn token(&self) -> &str {
+        &self.0.row_key
+    }
+}
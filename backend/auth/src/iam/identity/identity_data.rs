@@ -1,6 +1,8 @@
 use super::{ValidatedEmail, ValidatedName};
 use azure_sdk_storage_table::TableEntity;
+use chrono::{DateTime, Utc};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use shine_core::serde_with;
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum IdentityCategory {
@@ -11,7 +13,7 @@ pub enum IdentityCategory {
 }
 
 /// Common data associated to each identity
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct CoreIdentityData {
     pub id: String,
@@ -21,6 +23,18 @@ pub struct CoreIdentityData {
     pub name: ValidatedName,
     pub email: Option<ValidatedEmail>,
     pub email_validated: bool,
+
+    /// The identity is deleted after this date unless the owner signs in again
+    #[serde(default, with = "serde_with::opt_datetime")]
+    pub deletion_scheduled: Option<DateTime<Utc>>,
+
+    /// The date the identity was banned, banned identities cannot sign in
+    #[serde(default, with = "serde_with::opt_datetime")]
+    pub banned: Option<DateTime<Utc>>,
+
+    /// Guest identity without credentials, it can be promoted to a full account keeping the id and roles
+    #[serde(default)]
+    pub guest: bool,
 }
 
 /// Identity data
@@ -68,6 +82,12 @@ pub trait Identity {
     fn id(&self) -> &str {
         &self.core().id
     }
+
+    /// Return if the ownership of the email address was verified
+    fn email_verified(&self) -> bool {
+        let core = self.core();
+        core.email.is_some() && core.email_validated
+    }
 }
 
 #[derive(Debug)]
@@ -45,6 +45,11 @@ impl ValidatedName {
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    /// Restore a value that was validated before it was stored
+    pub(crate) fn from_stored(stored: String) -> ValidatedName {
+        ValidatedName(stored)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -77,6 +82,11 @@ impl ValidatedEmail {
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    /// Restore a value that was validated before it was stored
+    pub(crate) fn from_stored(stored: String) -> ValidatedEmail {
+        ValidatedEmail(stored)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -107,3 +117,12 @@ impl ValidatedPassword {
         &self.0
     }
 }
+
+/// Check the format of a token received from the client before it is used as a storage key. The generated
+/// tokens and ids are ascii (base64url or alphanumeric), the keys are partitioned by the first two characters.
+pub fn is_valid_token(token: &str) -> bool {
+    token.len() >= 2
+        && token
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_')
+}
@@ -1,207 +1,115 @@
 use crate::iam::{
     identity::{
-        CoreIdentity, CoreIdentityIndexedData, Identity, IdentityCategory, IndexEmail, IndexIdentity, IndexName,
-        IndexSequence, UserIdentity, ValidatedEmail, ValidatedName, ValidatedPassword,
+        is_valid_token, AzureIdentityStore, CoreIdentity, EmailVerification, Identity, IdentityCategory,
+        IdentitySearch, IdentitySearchPage, IdentityStore, IdentityStoreConfig, MemoryIdentityStore, PasswordReset,
+        PostgresIdentityStore, UserIdentity, ValidatedEmail, ValidatedName, ValidatedPassword,
     },
     IAMConfig, IAMError,
 };
 use argon2;
-use azure_sdk_storage_table::{CloudTable, Continuation, TableClient};
-use percent_encoding::{self, utf8_percent_encode};
-use rand::{self, seq::SliceRandom};
+use chrono::{Duration as ChronoDuration, Utc};
+use data_encoding;
+use rand::{self, seq::SliceRandom, Rng};
 use shine_core::{
-    azure_utils::{self, table_storage::EmptyData},
+    azure_utils::encode_safe_key,
     backoff::{self, Backoff, BackoffError},
-    idgenerator::{IdSequence, SyncCounterConfig, SyncCounterStore},
 };
-use std::{str, time::Duration};
+use std::{str, sync::Arc, time::Duration};
 
 const ID_LEN: usize = 8;
+const GUEST_NAME_PREFIX: &str = "guest_";
+const GUEST_NAME_LEN: usize = 12;
 const ID_ABC: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
 
 const MAX_SALT_LEN: usize = 32;
 const SALT_ABC: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
 
+const VERIFICATION_TOKEN_LEN: usize = 32;
+const RESET_TOKEN_LEN: usize = 32;
+const TOKEN_BASE_ENCODE: data_encoding::Encoding = data_encoding::BASE64URL_NOPAD;
+
+/// Maximum number of identities returned by a single search
+pub const MAX_SEARCH_LIMIT: usize = 100;
+
+fn hash_password(password: &ValidatedPassword, salt: &str) -> Result<String, IAMError> {
+    let password_config = argon2::Config::default();
+    argon2::hash_encoded(password.as_str().as_bytes(), salt.as_bytes(), &password_config)
+        .map_err(|err| IAMError::Internal(format!("Argon2 password creation failed: {}", err)))
+}
+
+fn check_password(identity: &UserIdentity, password: Option<&ValidatedPassword>) -> Result<(), IAMError> {
+    if let Some(password) = password {
+        // guests have no password, they cannot sign in with credentials
+        if identity.core().guest {
+            return Err(IAMError::PasswordNotMatching);
+        }
+        if !argon2::verify_encoded(&identity.data().password_hash, password.as_str().as_bytes())
+            .map_err(|err| IAMError::Internal(format!("Argon2 password validation failed: {}", err)))?
+        {
+            return Err(IAMError::PasswordNotMatching);
+        }
+    }
+    Ok(())
+}
+
 #[derive(Clone)]
 pub struct IdentityManager {
     password_pepper: String,
-    identity_id_generator: IdSequence,
-    db: CloudTable,
+    email_verification_time_to_live: ChronoDuration,
+    password_reset_time_to_live: ChronoDuration,
+    deletion_grace_period: ChronoDuration,
+    store: Arc<dyn IdentityStore>,
 }
 
 // Handling identites
 impl IdentityManager {
     pub async fn new(config: &IAMConfig) -> Result<Self, IAMError> {
-        let client = TableClient::new(&config.storage_account, &config.storage_account_key)?;
-        let db = CloudTable::new(client, "identities");
-        db.create_if_not_exists().await?;
-
-        let identity_id_generator = {
-            let id_config = SyncCounterConfig {
-                storage_account: config.storage_account.clone(),
-                storage_account_key: config.storage_account_key.clone(),
-                starting_value: 1_000_000,
-                table_name: "idcounter".to_string(),
-            };
-            let id_counter = SyncCounterStore::new(id_config).await?;
-            IdSequence::new(id_counter.clone(), "identityId").with_granularity(10)
+        let store: Arc<dyn IdentityStore> = match &config.identity_store {
+            IdentityStoreConfig::Azure => Arc::new(AzureIdentityStore::new(config).await?),
+            IdentityStoreConfig::Postgres { url, max_connections } => {
+                Arc::new(PostgresIdentityStore::new(url, *max_connections).await?)
+            }
+            IdentityStoreConfig::Memory => Arc::new(MemoryIdentityStore::new()),
         };
+        log::info!("Identity store: {:?}", config.identity_store);
+
+        let email_verification_time_to_live = ChronoDuration::hours(config.email_verification_time_to_live_h as i64);
+        let password_reset_time_to_live = ChronoDuration::minutes(config.password_reset_time_to_live_m as i64);
+        let deletion_grace_period = ChronoDuration::days(config.deletion_grace_period_d as i64);
 
         Ok(IdentityManager {
             password_pepper: config.password_pepper.clone(),
-            identity_id_generator,
-            db,
+            email_verification_time_to_live,
+            password_reset_time_to_live,
+            deletion_grace_period,
+            store,
         })
     }
 
-    async fn remove_identity<T>(&self, identity: T)
-    where
-        T: Identity,
-    {
-        let identity = identity.into_entity();
-        let (p, r) = (identity.partition_key.clone(), identity.row_key.clone());
-        self.db
-            .delete_entity(identity)
-            .await
-            .unwrap_or_else(|e| log::error!("Failed to delete identity([{}]/[{}]): {}", p, r, e));
-    }
-
-    async fn find_identity_by_id<T>(&self, id: &str) -> Result<T, IAMError>
-    where
-        T: Identity,
-    {
-        let (p, r) = T::entity_keys(&id);
-        let identity = self.db.get(&p, &r, None).await?;
-        let identity = identity.map(T::from_entity).ok_or(IAMError::IdentityNotFound)?;
-
-        Ok(identity)
-    }
-
-    async fn remove_index<T>(&self, index: T)
-    where
-        T: IndexIdentity,
-    {
-        let index = index.into_entity();
-        self.db
-            .delete_entity(index)
-            .await
-            .unwrap_or_else(|e| log::error!("Failed to delete index: {}", e));
-    }
-
-    async fn find_identity_by_index<T>(&self, query: &str) -> Result<T, IAMError>
-    where
-        T: Identity,
-    {
-        if let Some(indices) = self
-            .db
-            .execute_query::<CoreIdentityIndexedData>(Some(&query), &mut Continuation::start())
-            .await?
-        {
-            match &indices[..] {
-                [index] => {
-                    let identity_id = &index.payload.identity_id;
-                    let (p, r) = T::entity_keys(&identity_id);
-                    let identity = self.db.get(&p, &r, None).await?.ok_or(IAMError::IdentityNotFound)?;
-                    Ok(T::from_entity(identity))
-                }
-                _ => Err(IAMError::IdentityNotFound),
-            }
-        } else {
-            Err(IAMError::IdentityNotFound)
-        }
-    }
-
-    async fn find_user_by_index(
-        &self,
-        query: &str,
-        password: Option<&ValidatedPassword>,
-    ) -> Result<UserIdentity, IAMError> {
-        let identity = self.find_identity_by_index::<UserIdentity>(query).await?;
-
-        if let Some(password) = password {
-            // check password if provided
-            if !argon2::verify_encoded(&identity.data().password_hash, password.as_str().as_bytes())
-                .map_err(|err| IAMError::Internal(format!("Argon2 password validation failed: {}", err)))?
-            {
-                return Err(IAMError::PasswordNotMatching);
-            }
-        }
-
-        Ok(identity)
-    }
-
-    async fn insert_sequence_index<T>(&self, identity: &T) -> Result<IndexSequence, IAMError>
-    where
-        T: Identity,
-    {
-        let sequence_index = IndexSequence::from_identity(identity);
-        match self.db.insert_entity(sequence_index.into_entity()).await {
-            Ok(sequence_index) => Ok(IndexSequence::from_entity(sequence_index)),
-            Err(e) => {
-                if azure_utils::is_precodition_error(&e) {
-                    Err(IAMError::SequenceIdTaken)
-                } else {
-                    Err(IAMError::from(e))
-                }
-            }
-        }
-    }
-
     /// Return if the given name can be used as a new identity name
     pub async fn is_name_available(&self, name: &ValidatedName) -> Result<bool, IAMError> {
-        let (partition_key, row_key) = IndexName::entity_keys(name);
-        Ok(self
-            .db
-            .get::<EmptyData>(&partition_key, &row_key, None)
-            .await?
-            .is_none())
-    }
-
-    async fn insert_name_index<T>(&self, identity: &T) -> Result<IndexName, IAMError>
-    where
-        T: Identity,
-    {
-        let name_index = IndexName::from_identity(identity);
-        match self.db.insert_entity(name_index.into_entity()).await {
-            Ok(name_index) => Ok(IndexName::from_entity(name_index)),
-            Err(e) => {
-                if azure_utils::is_precodition_error(&e) {
-                    Err(IAMError::NameTaken)
-                } else {
-                    Err(IAMError::from(e))
-                }
-            }
-        }
+        self.store.is_name_available(name).await
     }
 
     /// Return if the given email can be used.
     pub async fn is_email_available(&self, cat: IdentityCategory, email: &ValidatedEmail) -> Result<bool, IAMError> {
-        let (partition_key, row_key) = IndexEmail::entity_keys(cat, email);
-        Ok(self
-            .db
-            .get::<EmptyData>(&partition_key, &row_key, None)
-            .await?
-            .is_none())
-    }
-
-    async fn insert_email_index<T>(&self, identity: &T) -> Result<Option<IndexEmail>, IAMError>
-    where
-        T: Identity,
-    {
-        if let Some(email_index) = IndexEmail::from_identity(identity) {
-            match self.db.insert_entity(email_index.into_entity()).await {
-                Ok(email_index) => Ok(Some(IndexEmail::from_entity(email_index))),
-                Err(err) => {
-                    if azure_utils::is_precodition_error(&err) {
-                        Err(IAMError::EmailTaken)
-                    } else {
-                        Err(IAMError::from(err))
-                    }
-                }
-            }
-        } else {
-            Ok(None)
-        }
+        self.store.is_email_available(cat, email).await
+    }
+
+    fn generate_salt(&self) -> String {
+        let mut rng = rand::thread_rng();
+        String::from_utf8(
+            SALT_ABC
+                .choose_multiple(&mut rng, MAX_SALT_LEN)
+                .cloned()
+                .collect::<Vec<_>>(),
+        )
+        .unwrap()
+    }
+
+    fn generate_id(&self) -> String {
+        let mut rng = rand::thread_rng();
+        String::from_utf8(ID_ABC.choose_multiple(&mut rng, ID_LEN).cloned().collect::<Vec<_>>()).unwrap()
     }
 
     async fn try_create_user_identity(
@@ -211,19 +119,9 @@ impl IdentityManager {
         password: &ValidatedPassword,
         email: Option<&ValidatedEmail>,
     ) -> Result<UserIdentity, BackoffError<IAMError>> {
-        let mut rng = rand::thread_rng();
-        let salt = String::from_utf8(
-            SALT_ABC
-                .choose_multiple(&mut rng, MAX_SALT_LEN)
-                .cloned()
-                .collect::<Vec<_>>(),
-        )
-        .unwrap();
-        let id = String::from_utf8(ID_ABC.choose_multiple(&mut rng, ID_LEN).cloned().collect::<Vec<_>>()).unwrap();
-        let password_config = argon2::Config::default();
-        let password_hash = argon2::hash_encoded(password.as_str().as_bytes(), salt.as_bytes(), &password_config)
-            .map_err(|err| IAMError::Internal(format!("Argon2 password creation failed: {}", err)))
-            .map_err(IAMError::into_backoff)?;
+        let salt = self.generate_salt();
+        let id = self.generate_id();
+        let password_hash = hash_password(password, &salt).map_err(IAMError::into_backoff)?;
 
         log::info!("Created new user id:{}, pwh:{}", id, password_hash);
         let identity = UserIdentity::new(
@@ -235,20 +133,7 @@ impl IdentityManager {
             password_hash,
         );
 
-        let identity = self
-            .db
-            .insert_entity(identity.into_entity())
-            .await
-            .map_err(|err| {
-                if azure_utils::is_precodition_error(&err) {
-                    IAMError::IdentityIdConflict
-                } else {
-                    IAMError::from(err)
-                }
-            })
-            .map_err(IAMError::into_backoff)?;
-
-        Ok(UserIdentity::from_entity(identity))
+        self.store.insert_user(identity).await.map_err(IAMError::into_backoff)
     }
 
     /// Creates a new user identity.
@@ -271,46 +156,91 @@ impl IdentityManager {
         }
 
         let identity = {
-            let sequence_id = self.identity_id_generator.get().await?;
+            let sequence_id = self.store.next_sequence_id().await?;
             backoff::Exponential::new(3, Duration::from_micros(10))
                 .async_execute(|_| self.try_create_user_identity(sequence_id, &name, &password, email.as_ref()))
                 .await?
         };
 
-        let sequence_index = match self.insert_sequence_index(&identity).await {
-            Ok(index) => index,
-            Err(e) => {
-                log::info!("Creating user failed (sequence_index): {:?}, {:?}", identity, e);
-                self.remove_identity(identity).await;
-                return Err(e);
-            }
+        log::info!("New user registered: {:?}", identity);
+        Ok(identity)
+    }
+
+    async fn try_create_guest_identity(&self, sequence_id: u64) -> Result<UserIdentity, BackoffError<IAMError>> {
+        let salt = self.generate_salt();
+        let id = self.generate_id();
+        // the name is random as it cannot be chosen by the guest, it is replaced on promotion
+        let name = {
+            let mut rng = rand::thread_rng();
+            let suffix = ID_ABC
+                .choose_multiple(&mut rng, GUEST_NAME_LEN)
+                .map(|c| *c as char)
+                .collect::<String>();
+            ValidatedName::from_raw(&format!("{}{}", GUEST_NAME_PREFIX, suffix))
+                .map_err(|err| IAMError::Internal(format!("Invalid guest name: {:?}", err)).into_backoff())?
         };
 
-        let name_index = match self.insert_name_index(&identity).await {
-            Ok(index) => index,
-            Err(e) => {
-                log::info!("Creating user failed (name_index): {:?}, {:?}", identity, e);
-                self.remove_identity(identity).await;
-                self.remove_index(sequence_index).await;
-                return Err(e);
-            }
+        log::info!("Created new guest id:{}", id);
+        let mut identity = UserIdentity::new(id, sequence_id, salt, name, None, String::new());
+        identity.data_mut().core.guest = true;
+
+        self.store.insert_user(identity).await.map_err(|err| match err {
+            // retry with a new random name
+            IAMError::NameTaken => BackoffError::Transient(err),
+            err => err.into_backoff(),
+        })
+    }
+
+    /// Creates a new guest identity without credentials.
+    pub async fn create_guest(&self) -> Result<UserIdentity, IAMError> {
+        let identity = {
+            let sequence_id = self.store.next_sequence_id().await?;
+            backoff::Exponential::new(3, Duration::from_micros(10))
+                .async_execute(|_| self.try_create_guest_identity(sequence_id))
+                .await?
         };
 
-        let email_index = match self.insert_email_index(&identity).await {
-            Ok(index) => index,
-            Err(e) => {
-                log::info!("Creating user failed (email_index): {:?}, {:?}", identity, e);
-                self.remove_identity(identity).await;
-                self.remove_index(sequence_index).await;
-                self.remove_index(name_index).await;
-                return Err(e);
+        log::info!("New guest registered: {:?}", identity);
+        Ok(identity)
+    }
+
+    /// Attach the credentials to a guest identity, the id of the identity is preserved.
+    pub async fn promote_guest(
+        &self,
+        id: &str,
+        name: ValidatedName,
+        email: Option<ValidatedEmail>,
+        password: &ValidatedPassword,
+    ) -> Result<UserIdentity, IAMError> {
+        let mut identity = self.find_user_by_id(id).await?;
+        if !identity.core().guest {
+            return Err(IAMError::BadRequest("Identity is not a guest".to_owned()));
+        }
+
+        // preliminary db checks, the uniqueness is ensured by the store
+        if !self.is_name_available(&name).await? {
+            log::info!("User name {} already taken", name.to_raw());
+            return Err(IAMError::NameTaken);
+        }
+        if let Some(ref email) = email {
+            if !self.is_email_available(IdentityCategory::User, email).await? {
+                log::info!("Email {} already taken", email.to_raw());
+                return Err(IAMError::EmailTaken);
             }
-        };
+        }
 
-        log::info!("New user registered: {:?}", identity);
-        log::debug!("Name index: {:?}", name_index);
-        log::debug!("Email index: {:?}", email_index);
-        log::debug!("Sequence index: {:?}", sequence_index);
+        let password_hash = hash_password(password, &identity.core().salt)?;
+        {
+            let data = identity.data_mut();
+            data.core.name = name;
+            data.core.email = email;
+            data.core.email_validated = false;
+            data.core.guest = false;
+            data.password_hash = password_hash;
+        }
+        let identity = self.store.update_user(identity).await?;
+
+        log::info!("Guest {} promoted", id);
         Ok(identity)
     }
 
@@ -321,14 +251,9 @@ impl IdentityManager {
         name: &ValidatedName,
         password: Option<&ValidatedPassword>,
     ) -> Result<UserIdentity, IAMError> {
-        let (p, r) = IndexName::entity_keys(name);
-        let query = format!("PartitionKey eq '{}' and RowKey eq '{}'", p, r);
-        let query = format!(
-            "$filter={}",
-            utf8_percent_encode(&query, percent_encoding::NON_ALPHANUMERIC)
-        );
-
-        self.find_user_by_index(&query, password).await
+        let identity = self.store.find_user_by_name(name).await?;
+        check_password(&identity, password)?;
+        Ok(identity)
     }
 
     /// Find a user identity by email or name.
@@ -338,23 +263,347 @@ impl IdentityManager {
         email: &ValidatedEmail,
         password: Option<&ValidatedPassword>,
     ) -> Result<UserIdentity, IAMError> {
-        let (p, r) = IndexEmail::entity_keys(IdentityCategory::User, email);
-        let query = format!("PartitionKey eq '{}' and RowKey eq '{}'", p, r);
-        let query = format!(
-            "$filter={}",
-            utf8_percent_encode(&query, percent_encoding::NON_ALPHANUMERIC)
-        );
-
-        self.find_user_by_index(&query, password).await
+        let identity = self.store.find_user_by_email(email).await?;
+        check_password(&identity, password)?;
+        Ok(identity)
     }
 
     /// Find a core identity by the id
     pub async fn find_core_identity_by_id(&self, id: &str) -> Result<CoreIdentity, IAMError> {
-        self.find_identity_by_id::<CoreIdentity>(id).await
+        self.store.find_core_identity_by_id(id).await
     }
 
     /// Find a user identity by the id
     pub async fn find_user_by_id(&self, id: &str) -> Result<UserIdentity, IAMError> {
-        self.find_identity_by_id(id).await
+        self.store.find_user_by_id(id).await
+    }
+}
+
+// Handling email verification
+impl IdentityManager {
+    fn generate_verification_token(&self) -> String {
+        let mut token = [0u8; VERIFICATION_TOKEN_LEN];
+        rand::thread_rng().fill(&mut token[..]);
+        TOKEN_BASE_ENCODE.encode(&token)
+    }
+
+    async fn try_create_email_verification(
+        &self,
+        identity: &UserIdentity,
+        email: &ValidatedEmail,
+        email_change: bool,
+    ) -> Result<EmailVerification, BackoffError<IAMError>> {
+        let token = self.generate_verification_token();
+        let verification = if email_change {
+            EmailVerification::new_email_change(&token, identity.id(), email.as_str())
+        } else {
+            EmailVerification::new(&token, identity.id(), email.as_str())
+        };
+        self.store
+            .insert_email_verification(verification)
+            .await
+            .map_err(IAMError::into_backoff)
+    }
+
+    /// Creates a new email verification token for the current email of the identity.
+    /// If the identity has no email or it has been verified already, None is returned.
+    pub async fn create_email_verification(
+        &self,
+        identity: &UserIdentity,
+    ) -> Result<Option<EmailVerification>, IAMError> {
+        let email = match identity.core().email {
+            Some(ref email) if !identity.email_verified() => email,
+            _ => return Ok(None),
+        };
+
+        let verification = backoff::Exponential::new(3, Duration::from_micros(10))
+            .async_execute(|_| self.try_create_email_verification(identity, email, false))
+            .await?;
+        log::debug!("Email verification created: {:?}", verification);
+        Ok(Some(verification))
+    }
+
+    /// Complete an email verification using the token sent out in the verification mail.
+    /// The token is consumed even if the verification fails.
+    pub async fn verify_email(&self, token: &str) -> Result<UserIdentity, IAMError> {
+        let verification = self.take_email_verification(token, false).await?;
+        let email = verification.data().email.clone();
+        let identity_id = verification.id().to_owned();
+
+        let mut identity = self.find_user_by_id(&identity_id).await?;
+        // the email could have been changed since the token was issued
+        if identity.core().email.as_ref().map(|e| e.as_str()) != Some(email.as_str()) {
+            log::info!(
+                "Email verification token for {} refers to an outdated email",
+                identity_id
+            );
+            return Err(IAMError::VerificationTokenInvalid);
+        }
+
+        if !identity.email_verified() {
+            identity.data_mut().core.email_validated = true;
+            identity = self.store.update_user(identity).await?;
+            log::info!("Email verified for {}", identity_id);
+        }
+
+        Ok(identity)
+    }
+
+    /// Take a token and check if it is a valid, not expired token of the requested kind
+    async fn take_email_verification(&self, token: &str, email_change: bool) -> Result<EmailVerification, IAMError> {
+        if !is_valid_token(token) {
+            return Err(IAMError::VerificationTokenInvalid);
+        }
+
+        let verification = match self.store.take_email_verification(token).await? {
+            Some(verification) => verification,
+            None => return Err(IAMError::VerificationTokenInvalid),
+        };
+
+        if verification.data().email_change != email_change {
+            log::info!("Email verification token for {} has a wrong kind", verification.id());
+            return Err(IAMError::VerificationTokenInvalid);
+        }
+        if verification.data().issued + self.email_verification_time_to_live < Utc::now() {
+            log::info!("Email verification token for {} has expired", verification.id());
+            return Err(IAMError::VerificationTokenInvalid);
+        }
+
+        Ok(verification)
+    }
+}
+
+// Handling profile update
+impl IdentityManager {
+    /// Set a new password after validating the current one.
+    pub async fn change_password(
+        &self,
+        id: &str,
+        current_password: &ValidatedPassword,
+        new_password: &ValidatedPassword,
+    ) -> Result<UserIdentity, IAMError> {
+        let mut identity = self.find_user_by_id(id).await?;
+        check_password(&identity, Some(current_password))?;
+
+        let password_hash = hash_password(new_password, &identity.core().salt)?;
+        identity.data_mut().password_hash = password_hash;
+        let identity = self.store.update_user(identity).await?;
+
+        log::info!("Password changed for {}", id);
+        Ok(identity)
+    }
+
+    /// Creates a token to confirm the change of the email. The email of the identity is not altered until
+    /// the token is confirmed, thus the user keeps the old email if the new one is mistyped.
+    pub async fn create_email_change(
+        &self,
+        id: &str,
+        password: &ValidatedPassword,
+        email: &ValidatedEmail,
+    ) -> Result<(UserIdentity, EmailVerification), IAMError> {
+        let identity = self.find_user_by_id(id).await?;
+        check_password(&identity, Some(password))?;
+
+        if identity.core().email.as_ref().map(|e| e.as_str()) == Some(email.as_str()) {
+            return Err(IAMError::BadRequest("Email not changed".to_owned()));
+        }
+        // preliminary check, the uniqueness is ensured when the change is confirmed
+        if !self.is_email_available(identity.core().category, email).await? {
+            log::info!("Email {} already taken", email.to_raw());
+            return Err(IAMError::EmailTaken);
+        }
+
+        let verification = backoff::Exponential::new(3, Duration::from_micros(10))
+            .async_execute(|_| self.try_create_email_verification(&identity, email, true))
+            .await?;
+        log::debug!("Email change created: {:?}", verification);
+        Ok((identity, verification))
+    }
+
+    /// Complete an email change using the token sent out to the new email. As the token could be received
+    /// only through the new email, the email is also verified.
+    /// The token is consumed even if the change fails.
+    pub async fn confirm_email_change(&self, token: &str) -> Result<UserIdentity, IAMError> {
+        let verification = self.take_email_verification(token, true).await?;
+        let email = ValidatedEmail::from_stored(verification.data().email.clone());
+        let identity_id = verification.id().to_owned();
+
+        let mut identity = self.find_user_by_id(&identity_id).await?;
+        {
+            let core = &mut identity.data_mut().core;
+            core.email = Some(email);
+            core.email_validated = true;
+        }
+        let identity = self.store.update_user(identity).await?;
+
+        log::info!("Email changed for {}", identity_id);
+        Ok(identity)
+    }
+}
+
+// Handling password reset
+impl IdentityManager {
+    async fn try_create_password_reset(
+        &self,
+        identity: &UserIdentity,
+    ) -> Result<PasswordReset, BackoffError<IAMError>> {
+        let mut token = [0u8; RESET_TOKEN_LEN];
+        rand::thread_rng().fill(&mut token[..]);
+        let token = TOKEN_BASE_ENCODE.encode(&token);
+
+        let reset = PasswordReset::new(&token, identity.id());
+        self.store
+            .insert_password_reset(reset)
+            .await
+            .map_err(IAMError::into_backoff)
+    }
+
+    /// Creates a new, time limited password reset token for the identity.
+    pub async fn create_password_reset(&self, identity: &UserIdentity) -> Result<PasswordReset, IAMError> {
+        let reset = backoff::Exponential::new(3, Duration::from_micros(10))
+            .async_execute(|_| self.try_create_password_reset(identity))
+            .await?;
+        log::debug!("Password reset created for {}", reset.id());
+        Ok(reset)
+    }
+
+    /// Set a new password using the token sent out in the reset mail.
+    /// The token is consumed even if the reset fails.
+    pub async fn reset_password(&self, token: &str, password: &ValidatedPassword) -> Result<UserIdentity, IAMError> {
+        if !is_valid_token(token) {
+            return Err(IAMError::ResetTokenInvalid);
+        }
+
+        let reset = match self.store.take_password_reset(token).await? {
+            Some(reset) => reset,
+            None => return Err(IAMError::ResetTokenInvalid),
+        };
+        let issued = reset.data().issued;
+        let identity_id = reset.id().to_owned();
+
+        if issued + self.password_reset_time_to_live < Utc::now() {
+            log::info!("Password reset token for {} has expired", identity_id);
+            return Err(IAMError::ResetTokenInvalid);
+        }
+
+        let mut identity = self.find_user_by_id(&identity_id).await?;
+        let password_hash = hash_password(password, &identity.core().salt)?;
+        identity.data_mut().password_hash = password_hash;
+        let identity = self.store.update_user(identity).await?;
+
+        log::info!("Password reset for {}", identity_id);
+        Ok(identity)
+    }
+}
+
+// Handling identity deletion
+impl IdentityManager {
+    /// Schedule the deletion of the identity after the grace period.
+    pub async fn schedule_deletion(
+        &self,
+        id: &str,
+        password: Option<&ValidatedPassword>,
+    ) -> Result<UserIdentity, IAMError> {
+        let mut identity = self.find_user_by_id(id).await?;
+        // the owner has to confirm the deletion by the password, the guests have no password
+        if !identity.core().guest {
+            check_password(&identity, Some(password.ok_or(IAMError::PasswordNotMatching)?))?;
+        }
+        if identity.core().deletion_scheduled.is_none() {
+            identity.data_mut().core.deletion_scheduled = Some(Utc::now() + self.deletion_grace_period);
+            identity = self.store.update_user(identity).await?;
+            log::info!(
+                "Deletion of {} scheduled at {:?}",
+                id,
+                identity.core().deletion_scheduled
+            );
+        }
+        Ok(identity)
+    }
+
+    /// Cancel the scheduled deletion of the identity. If the grace period is over, the identity is considered
+    /// to be deleted and IdentityNotFound is returned.
+    pub async fn cancel_deletion(&self, mut identity: UserIdentity) -> Result<UserIdentity, IAMError> {
+        match identity.core().deletion_scheduled {
+            None => Ok(identity),
+            Some(scheduled) if scheduled < Utc::now() => {
+                log::info!("Identity {} is pending deletion", identity.id());
+                Err(IAMError::IdentityNotFound)
+            }
+            Some(_) => {
+                identity.data_mut().core.deletion_scheduled = None;
+                let identity = self.store.update_user(identity).await?;
+                log::info!("Deletion of {} cancelled", identity.id());
+                Ok(identity)
+            }
+        }
+    }
+
+    /// Find the id of the identities where the grace period of the deletion is over
+    pub async fn find_identities_to_delete(&self) -> Result<Vec<String>, IAMError> {
+        self.store.find_identities_to_delete(Utc::now()).await
+    }
+
+    /// Delete a user permanently
+    pub async fn delete_user(&self, id: &str) -> Result<(), IAMError> {
+        let identity = self.find_user_by_id(id).await?;
+        self.store.delete_user(identity).await?;
+        log::info!("Identity {} deleted", id);
+        Ok(())
+    }
+}
+
+// Handling moderation
+impl IdentityManager {
+    /// Search the users by the prefix of the name or email. As the indices are partitioned by the first two
+    /// characters, the prefix must have at least two characters, only the name search accepts an empty prefix
+    /// to list all the users.
+    pub async fn search_users(
+        &self,
+        search: &IdentitySearch,
+        continuation: Option<&str>,
+        limit: usize,
+    ) -> Result<IdentitySearchPage, IAMError> {
+        let search = match search {
+            IdentitySearch::Name(prefix) => IdentitySearch::Name(encode_safe_key(prefix)),
+            IdentitySearch::Email(prefix) => IdentitySearch::Email(encode_safe_key(prefix)),
+        };
+        let too_short = match &search {
+            IdentitySearch::Name(prefix) => prefix.len() == 1,
+            IdentitySearch::Email(prefix) => prefix.len() < 2,
+        };
+        if too_short {
+            return Err(IAMError::BadRequest("Search prefix too short".to_owned()));
+        }
+        // the continuation is an index key returned by a previous search, thus it is safe key encoded
+        if let Some(continuation) = continuation {
+            if !continuation.chars().all(|c| c.is_ascii_alphanumeric() || c == '@') {
+                return Err(IAMError::BadRequest("Invalid continuation".to_owned()));
+            }
+        }
+
+        let limit = limit.max(1).min(MAX_SEARCH_LIMIT);
+        self.store.search_identities(&search, continuation, limit).await
+    }
+
+    /// Ban or unban an identity. Banned identities cannot sign in.
+    pub async fn set_banned(&self, id: &str, banned: bool) -> Result<UserIdentity, IAMError> {
+        let mut identity = self.find_user_by_id(id).await?;
+        if identity.core().banned.is_some() != banned {
+            identity.data_mut().core.banned = if banned { Some(Utc::now()) } else { None };
+            identity = self.store.update_user(identity).await?;
+            log::info!("Identity {} banned: {}", id, banned);
+        }
+        Ok(identity)
+    }
+
+    /// Return IdentityBanned if the identity is banned
+    pub fn check_banned<T: Identity>(&self, identity: &T) -> Result<(), IAMError> {
+        if identity.core().banned.is_some() {
+            log::info!("Identity {} is banned", identity.id());
+            Err(IAMError::IdentityBanned)
+        } else {
+            Ok(())
+        }
     }
 }
@@ -0,0 +1,317 @@
+use crate::iam::{
+    identity::{
+        CoreIdentity, EmailVerification, EmailVerificationData, Identity, IdentityCategory, IdentityData,
+        IdentityEntity, IdentitySearch, IdentitySearchPage, IdentityStore, PasswordReset, PasswordResetData,
+        StoreFuture, UserIdentity, UserIdentityData, ValidatedEmail, ValidatedName,
+    },
+    IAMError,
+};
+use azure_sdk_storage_table::TableEntity;
+use chrono::{DateTime, Utc};
+use std::{collections::HashMap, sync::Mutex};
+
+const FIRST_SEQUENCE_ID: u64 = 1_000_000;
+
+fn email_index_key(cat: IdentityCategory, email: &ValidatedEmail) -> String {
+    format!("{:?}:{}", cat, email.as_str())
+}
+
+fn into_identity<D: IdentityData>(data: D) -> IdentityEntity<D> {
+    let (partition_key, row_key) = IdentityEntity::<D>::entity_keys(&data.core().id);
+    IdentityEntity(TableEntity {
+        partition_key,
+        row_key,
+        etag: None,
+        timestamp: None,
+        payload: data,
+    })
+}
+
+struct Inner {
+    next_sequence_id: u64,
+    users: HashMap<String, UserIdentityData>,
+    sequence_ids: HashMap<u64, String>,
+    names: HashMap<String, String>,
+    emails: HashMap<String, String>,
+    email_verifications: HashMap<String, EmailVerificationData>,
+    password_resets: HashMap<String, PasswordResetData>,
+}
+
+impl Inner {
+    fn find_user(&self, id: &str) -> Result<UserIdentityData, IAMError> {
+        self.users.get(id).cloned().ok_or(IAMError::IdentityNotFound)
+    }
+
+    fn find_user_by_index(&self, id: Option<&String>) -> Result<UserIdentityData, IAMError> {
+        match id {
+            Some(id) => self.find_user(id),
+            None => Err(IAMError::IdentityNotFound),
+        }
+    }
+
+    /// Check if the name and email of the data is unique ignoring the identity with the given id
+    fn check_unique(&self, data: &UserIdentityData, id: &str) -> Result<(), IAMError> {
+        let core = &data.core;
+        if self.names.get(core.name.as_str()).map_or(false, |owner| owner != id) {
+            return Err(IAMError::NameTaken);
+        }
+        if let Some(ref email) = core.email {
+            let key = email_index_key(core.category, email);
+            if self.emails.get(&key).map_or(false, |owner| owner != id) {
+                return Err(IAMError::EmailTaken);
+            }
+        }
+        Ok(())
+    }
+
+    fn remove_indices(&mut self, data: &UserIdentityData) {
+        let core = &data.core;
+        self.names.remove(core.name.as_str());
+        if let Some(ref email) = core.email {
+            self.emails.remove(&email_index_key(core.category, email));
+        }
+    }
+
+    fn add_indices(&mut self, data: &UserIdentityData) {
+        let core = &data.core;
+        self.names.insert(core.name.as_str().to_owned(), core.id.clone());
+        if let Some(ref email) = core.email {
+            self.emails
+                .insert(email_index_key(core.category, email), core.id.clone());
+        }
+    }
+}
+
+/// Identity store keeping everything in the memory of the process. It is intended for local development
+/// and tests, all the data is lost when the service stops.
+pub struct MemoryIdentityStore {
+    inner: Mutex<Inner>,
+}
+
+impl MemoryIdentityStore {
+    pub fn new() -> Self {
+        MemoryIdentityStore {
+            inner: Mutex::new(Inner {
+                next_sequence_id: FIRST_SEQUENCE_ID,
+                users: HashMap::new(),
+                sequence_ids: HashMap::new(),
+                names: HashMap::new(),
+                emails: HashMap::new(),
+                email_verifications: HashMap::new(),
+                password_resets: HashMap::new(),
+            }),
+        }
+    }
+
+    fn with_inner<T, F>(&self, f: F) -> StoreFuture<'_, T>
+    where
+        T: 'static,
+        F: FnOnce(&mut Inner) -> Result<T, IAMError>,
+    {
+        let result = {
+            let mut inner = self.inner.lock().unwrap();
+            f(&mut inner)
+        };
+        Box::pin(async move { result })
+    }
+}
+
+impl Default for MemoryIdentityStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IdentityStore for MemoryIdentityStore {
+    fn next_sequence_id(&self) -> StoreFuture<'_, u64> {
+        self.with_inner(|inner| {
+            let id = inner.next_sequence_id;
+            inner.next_sequence_id += 1;
+            Ok(id)
+        })
+    }
+
+    fn is_name_available<'a>(&'a self, name: &'a ValidatedName) -> StoreFuture<'a, bool> {
+        self.with_inner(|inner| Ok(!inner.names.contains_key(name.as_str())))
+    }
+
+    fn is_email_available<'a>(&'a self, cat: IdentityCategory, email: &'a ValidatedEmail) -> StoreFuture<'a, bool> {
+        self.with_inner(|inner| Ok(!inner.emails.contains_key(&email_index_key(cat, email))))
+    }
+
+    fn insert_user(&self, identity: UserIdentity) -> StoreFuture<'_, UserIdentity> {
+        self.with_inner(move |inner| {
+            let data = identity.into_data();
+            let id = data.core.id.clone();
+            if inner.users.contains_key(&id) {
+                return Err(IAMError::IdentityIdConflict);
+            }
+            if inner.sequence_ids.contains_key(&data.core.sequence_id) {
+                return Err(IAMError::SequenceIdTaken);
+            }
+            inner.check_unique(&data, &id)?;
+
+            inner.add_indices(&data);
+            inner.sequence_ids.insert(data.core.sequence_id, id.clone());
+            inner.users.insert(id, data.clone());
+            Ok(into_identity(data))
+        })
+    }
+
+    fn update_user(&self, identity: UserIdentity) -> StoreFuture<'_, UserIdentity> {
+        self.with_inner(move |inner| {
+            let data = identity.into_data();
+            let id = data.core.id.clone();
+            let old = inner.find_user(&id)?;
+            inner.check_unique(&data, &id)?;
+
+            inner.remove_indices(&old);
+            inner.add_indices(&data);
+            inner.users.insert(id, data.clone());
+            Ok(into_identity(data))
+        })
+    }
+
+    fn find_user_by_id<'a>(&'a self, id: &'a str) -> StoreFuture<'a, UserIdentity> {
+        self.with_inner(|inner| inner.find_user(id).map(into_identity))
+    }
+
+    fn find_user_by_name<'a>(&'a self, name: &'a ValidatedName) -> StoreFuture<'a, UserIdentity> {
+        self.with_inner(|inner| {
+            inner
+                .find_user_by_index(inner.names.get(name.as_str()))
+                .map(into_identity)
+        })
+    }
+
+    fn find_user_by_email<'a>(&'a self, email: &'a ValidatedEmail) -> StoreFuture<'a, UserIdentity> {
+        self.with_inner(|inner| {
+            let key = email_index_key(IdentityCategory::User, email);
+            inner.find_user_by_index(inner.emails.get(&key)).map(into_identity)
+        })
+    }
+
+    fn find_core_identity_by_id<'a>(&'a self, id: &'a str) -> StoreFuture<'a, CoreIdentity> {
+        self.with_inner(|inner| inner.find_user(id).map(|data| into_identity(data.core)))
+    }
+
+    fn search_identities<'a>(
+        &'a self,
+        search: &'a IdentitySearch,
+        continuation: Option<&'a str>,
+        limit: usize,
+    ) -> StoreFuture<'a, IdentitySearchPage> {
+        self.with_inner(move |inner| {
+            let user_prefix = format!("{:?}:", IdentityCategory::User);
+            let mut keys: Vec<(&str, &String)> = match search {
+                IdentitySearch::Name(prefix) => inner
+                    .names
+                    .iter()
+                    .filter(|(name, _)| name.starts_with(prefix.as_str()))
+                    .map(|(name, id)| (name.as_str(), id))
+                    .collect(),
+                IdentitySearch::Email(prefix) => inner
+                    .emails
+                    .iter()
+                    .filter_map(|(key, id)| key.strip_prefix(&user_prefix).map(|email| (email, id)))
+                    .filter(|(email, _)| email.starts_with(prefix.as_str()))
+                    .collect(),
+            };
+            keys.retain(|(key, _)| continuation.map_or(true, |continuation| *key > continuation));
+            keys.sort();
+            keys.truncate(limit);
+
+            let continuation = if keys.len() == limit {
+                keys.last().map(|(key, _)| (*key).to_owned())
+            } else {
+                None
+            };
+            let identities = keys
+                .iter()
+                .map(|(_, id)| inner.find_user(id).map(|data| into_identity(data.core)))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(IdentitySearchPage {
+                identities,
+                continuation,
+            })
+        })
+    }
+
+    fn find_identities_to_delete(&self, before: DateTime<Utc>) -> StoreFuture<'_, Vec<String>> {
+        self.with_inner(|inner| {
+            Ok(inner
+                .users
+                .values()
+                .filter(|data| data.core.deletion_scheduled.map_or(false, |date| date < before))
+                .map(|data| data.core.id.clone())
+                .collect())
+        })
+    }
+
+    fn delete_user(&self, identity: UserIdentity) -> StoreFuture<'_, ()> {
+        self.with_inner(move |inner| {
+            if let Some(data) = inner.users.remove(identity.id()) {
+                inner.remove_indices(&data);
+                inner.sequence_ids.remove(&data.core.sequence_id);
+            }
+            let id = identity.id();
+            inner.email_verifications.retain(|_, data| data.identity_id != id);
+            inner.password_resets.retain(|_, data| data.identity_id != id);
+            Ok(())
+        })
+    }
+
+    fn insert_email_verification(&self, verification: EmailVerification) -> StoreFuture<'_, EmailVerification> {
+        self.with_inner(move |inner| {
+            if inner.email_verifications.contains_key(verification.token()) {
+                return Err(IAMError::VerificationTokenConflict);
+            }
+            inner
+                .email_verifications
+                .insert(verification.token().to_owned(), verification.data().clone());
+            Ok(verification)
+        })
+    }
+
+    fn take_email_verification<'a>(&'a self, token: &'a str) -> StoreFuture<'a, Option<EmailVerification>> {
+        self.with_inner(|inner| {
+            Ok(inner.email_verifications.remove(token).map(|data| {
+                let (partition_key, row_key) = EmailVerification::entity_keys(token);
+                EmailVerification::from_entity(TableEntity {
+                    partition_key,
+                    row_key,
+                    etag: None,
+                    timestamp: None,
+                    payload: data,
+                })
+            }))
+        })
+    }
+
+    fn insert_password_reset(&self, reset: PasswordReset) -> StoreFuture<'_, PasswordReset> {
+        self.with_inner(move |inner| {
+            if inner.password_resets.contains_key(reset.token()) {
+                return Err(IAMError::ResetTokenConflict);
+            }
+            inner
+                .password_resets
+                .insert(reset.token().to_owned(), reset.data().clone());
+            Ok(reset)
+        })
+    }
+
+    fn take_password_reset<'a>(&'a self, token: &'a str) -> StoreFuture<'a, Option<PasswordReset>> {
+        self.with_inner(|inner| {
+            Ok(inner.password_resets.remove(token).map(|data| {
+                let (partition_key, row_key) = PasswordReset::entity_keys(token);
+                PasswordReset::from_entity(TableEntity {
+                    partition_key,
+                    row_key,
+                    etag: None,
+                    timestamp: None,
+                    payload: data,
+                })
+            }))
+        })
+    }
+}
@@ -1,3 +1,5 @@
+mod azure_store;
+mod email_verification;
 mod identity_data;
 mod index_email;
 mod index_identity;
@@ -5,8 +7,15 @@ mod index_name;
 mod index_sequence;
 mod input_validation;
 mod manager;
+mod memory_store;
+mod password_policy;
+mod password_reset;
+mod postgres_store;
+mod store;
 mod user_identity;
 
+pub use self::azure_store::*;
+pub use self::email_verification::*;
 pub use self::identity_data::*;
 pub use self::index_email::*;
 pub use self::index_identity::*;
@@ -14,4 +23,9 @@ pub use self::index_name::*;
 pub use self::index_sequence::*;
 pub use self::input_validation::*;
 pub use self::manager::*;
+pub use self::memory_store::*;
+pub use self::password_policy::*;
+pub use self::password_reset::*;
+pub use self::postgres_store::*;
+pub use self::store::*;
 pub use self::user_identity::*;
@@ -0,0 +1,200 @@
+use crate::iam::identity::ValidatedPassword;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+
+/// Configuration of the password strength requirements
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PasswordPolicyConfig {
+    /// Minimum estimated entropy in bits
+    #[serde(default = "PasswordPolicyConfig::default_min_entropy")]
+    pub min_entropy: f64,
+    /// Minimum number of the used character classes (lowercase, uppercase, digit, symbol)
+    #[serde(default = "PasswordPolicyConfig::default_min_character_classes")]
+    pub min_character_classes: usize,
+    /// Check the password against the breached passwords of haveibeenpwned.com. Only the first 5
+    /// characters of the SHA-1 hash are sent to the service (k-anonymity).
+    #[serde(default)]
+    pub breach_check: bool,
+}
+
+impl PasswordPolicyConfig {
+    fn default_min_entropy() -> f64 {
+        28.
+    }
+
+    fn default_min_character_classes() -> usize {
+        2
+    }
+}
+
+impl Default for PasswordPolicyConfig {
+    fn default() -> Self {
+        PasswordPolicyConfig {
+            min_entropy: Self::default_min_entropy(),
+            min_character_classes: Self::default_min_character_classes(),
+            breach_check: false,
+        }
+    }
+}
+
+/// A rule of the password policy the password does not satisfy
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum PasswordPolicyViolation {
+    LowEntropy { required: f64, estimated: f64 },
+    CharacterClasses { required: usize, found: usize },
+    Breached { count: u64 },
+}
+
+impl PasswordPolicyViolation {
+    /// Key of the violation used by the templates
+    pub fn key(&self) -> &'static str {
+        match self {
+            PasswordPolicyViolation::LowEntropy { .. } => "too_week",
+            PasswordPolicyViolation::CharacterClasses { .. } => "character_classes",
+            PasswordPolicyViolation::Breached { .. } => "breached",
+        }
+    }
+}
+
+/// Number of the character classes used by the password
+pub fn character_classes(password: &str) -> usize {
+    let lower = password.chars().any(|c| c.is_lowercase());
+    let upper = password.chars().any(|c| c.is_uppercase());
+    let digit = password.chars().any(|c| c.is_numeric());
+    let symbol = password.chars().any(|c| !c.is_alphanumeric());
+    [lower, upper, digit, symbol].iter().filter(|x| **x).count()
+}
+
+/// Estimate the entropy of the password in bits from the size of the used character pool. Repeating a
+/// character does not add to the length to penalize passwords like "aaaaaa".
+pub fn estimate_entropy(password: &str) -> f64 {
+    let mut pool = 0;
+    if password.chars().any(|c| c.is_ascii_lowercase()) {
+        pool += 26;
+    }
+    if password.chars().any(|c| c.is_ascii_uppercase()) {
+        pool += 26;
+    }
+    if password.chars().any(|c| c.is_ascii_digit()) {
+        pool += 10;
+    }
+    if password.chars().any(|c| c.is_ascii() && !c.is_ascii_alphanumeric()) {
+        pool += 33;
+    }
+    if password.chars().any(|c| !c.is_ascii()) {
+        pool += 100;
+    }
+
+    let mut length = 0;
+    let mut prev = None;
+    for c in password.chars() {
+        if prev != Some(c) {
+            length += 1;
+        }
+        prev = Some(c);
+    }
+
+    if pool == 0 {
+        0.
+    } else {
+        length as f64 * (pool as f64).log2()
+    }
+}
+
+/// Parse the response of the range query of haveibeenpwned.com and find the breach count of the suffix
+pub fn parse_breach_count(response: &str, hash_suffix: &str) -> u64 {
+    response
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.trim().splitn(2, ':');
+            match (parts.next(), parts.next()) {
+                (Some(suffix), Some(count)) if suffix.eq_ignore_ascii_case(hash_suffix) => count.parse().ok(),
+                _ => None,
+            }
+        })
+        .next()
+        .unwrap_or(0)
+}
+
+/// Check the password against the configured policy
+#[derive(Clone)]
+pub struct PasswordPolicy {
+    config: PasswordPolicyConfig,
+    client: Client,
+}
+
+impl PasswordPolicy {
+    pub fn new(config: &PasswordPolicyConfig) -> PasswordPolicy {
+        PasswordPolicy {
+            config: config.clone(),
+            client: Client::new(),
+        }
+    }
+
+    pub fn config(&self) -> &PasswordPolicyConfig {
+        &self.config
+    }
+
+    /// Check the local rules of the policy
+    pub fn check_strength(&self, password: &ValidatedPassword) -> Vec<PasswordPolicyViolation> {
+        let mut violations = Vec::new();
+
+        let found = character_classes(password.as_str());
+        if found < self.config.min_character_classes {
+            violations.push(PasswordPolicyViolation::CharacterClasses {
+                required: self.config.min_character_classes,
+                found,
+            });
+        }
+
+        let estimated = estimate_entropy(password.as_str());
+        if estimated < self.config.min_entropy {
+            violations.push(PasswordPolicyViolation::LowEntropy {
+                required: self.config.min_entropy,
+                estimated,
+            });
+        }
+
+        violations
+    }
+
+    /// Query the breach count of the password. On communication error the password is accepted, the
+    /// breach check is a best effort protection.
+    async fn breach_count(&self, password: &ValidatedPassword) -> u64 {
+        let hash = data_encoding::HEXUPPER.encode(&Sha1::digest(password.as_str().as_bytes()));
+        let (prefix, suffix) = hash.split_at(5);
+
+        let response = self
+            .client
+            .get(&format!("https://api.pwnedpasswords.com/range/{}", prefix))
+            .header("Add-Padding", "true")
+            .send()
+            .await;
+        let response = match response {
+            Ok(response) => response.text().await,
+            Err(err) => Err(err),
+        };
+
+        match response {
+            Ok(response) => parse_breach_count(&response, suffix),
+            Err(err) => {
+                log::warn!("Password breach check failed: {}", err);
+                0
+            }
+        }
+    }
+
+    /// Check all the rules of the policy and return the violations
+    pub async fn check(&self, password: &ValidatedPassword) -> Vec<PasswordPolicyViolation> {
+        let mut violations = self.check_strength(password);
+        if violations.is_empty() && self.config.breach_check {
+            let count = self.breach_count(password).await;
+            if count > 0 {
+                violations.push(PasswordPolicyViolation::Breached { count });
+            }
+        }
+        violations
+    }
+}
@@ -0,0 +1,58 @@
+use azure_sdk_storage_table::TableEntity;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shine_core::serde_with;
+
+/// Data associated to a password reset token
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct PasswordResetData {
+    pub identity_id: String,
+
+    #[serde(with = "serde_with::datetime")]
+    pub issued: DateTime<Utc>,
+}
+
+/// Pending password reset, indexed by the (secret) token sent out in the reset mail
+#[derive(Debug)]
+pub struct PasswordReset(TableEntity<PasswordResetData>);
+
+impl PasswordReset {
+    pub fn entity_keys(token: &str) -> (String, String) {
+        (format!("reset-{}", &token[0..2]), token.to_string())
+    }
+
+    pub fn new(token: &str, id: &str) -> Self {
+        let (partition_key, row_key) = Self::entity_keys(token);
+        Self(TableEntity {
+            partition_key,
+            row_key,
+            etag: None,
+            timestamp: None,
+            payload: PasswordResetData {
+                identity_id: id.to_owned(),
+                issued: Utc::now(),
+            },
+        })
+    }
+
+    pub fn from_entity(entity: TableEntity<PasswordResetData>) -> Self {
+        Self(entity)
+    }
+
+    pub fn into_entity(self) -> TableEntity<PasswordResetData> {
+        self.0
+    }
+
+    pub fn data(&self) -> &PasswordResetData {
+        &self.0.payload
+    }
+
+    pub fn id(&self) -> &str {
+        &self.0.payload.identity_id
+    }
+
+    pub fn token(&self) -> &str {
+        &self.0.row_key
+    }
+}
@@ -0,0 +1,438 @@
+use crate::iam::{
+    identity::{
+        CoreIdentity, CoreIdentityData, EmailVerification, EmailVerificationData, Identity, IdentityCategory,
+        IdentityData, IdentityEntity, IdentitySearch, IdentitySearchPage, IdentityStore, PasswordReset,
+        PasswordResetData, StoreFuture, UserIdentity, UserIdentityData, ValidatedEmail, ValidatedName,
+    },
+    IAMError,
+};
+use azure_sdk_storage_table::TableEntity;
+use chrono::{DateTime, Utc};
+use sqlx::{
+    postgres::{PgDatabaseError, PgPool, PgPoolOptions},
+    FromRow,
+};
+
+const SCHEMA: &[&str] = &[
+    "CREATE SEQUENCE IF NOT EXISTS identity_sequence START 1000000",
+    "CREATE TABLE IF NOT EXISTS identities (
+        id TEXT CONSTRAINT identities_pkey PRIMARY KEY,
+        sequence_id BIGINT NOT NULL CONSTRAINT identities_sequence_id_key UNIQUE,
+        salt TEXT NOT NULL,
+        category TEXT NOT NULL,
+        name TEXT NOT NULL CONSTRAINT identities_name_key UNIQUE,
+        email TEXT,
+        email_validated BOOLEAN NOT NULL,
+        password_hash TEXT NOT NULL
+    )",
+    "ALTER TABLE identities ADD COLUMN IF NOT EXISTS deletion_scheduled TIMESTAMPTZ",
+    "ALTER TABLE identities ADD COLUMN IF NOT EXISTS banned TIMESTAMPTZ",
+    "ALTER TABLE identities ADD COLUMN IF NOT EXISTS guest BOOLEAN NOT NULL DEFAULT FALSE",
+    "CREATE UNIQUE INDEX IF NOT EXISTS identities_email_key ON identities (category, email)",
+    "CREATE TABLE IF NOT EXISTS email_verifications (
+        token TEXT CONSTRAINT email_verifications_pkey PRIMARY KEY,
+        identity_id TEXT NOT NULL,
+        email TEXT NOT NULL,
+        issued TIMESTAMPTZ NOT NULL
+    )",
+    "ALTER TABLE email_verifications ADD COLUMN IF NOT EXISTS email_change BOOLEAN NOT NULL DEFAULT FALSE",
+    "CREATE TABLE IF NOT EXISTS password_resets (
+        token TEXT CONSTRAINT password_resets_pkey PRIMARY KEY,
+        identity_id TEXT NOT NULL,
+        issued TIMESTAMPTZ NOT NULL
+    )",
+];
+
+const SELECT_IDENTITY: &str = "SELECT id, sequence_id, salt, category, name, email, email_validated, password_hash, \
+                               deletion_scheduled, banned, guest FROM identities";
+
+#[derive(FromRow)]
+struct IdentityRow {
+    id: String,
+    sequence_id: i64,
+    salt: String,
+    category: String,
+    name: String,
+    email: Option<String>,
+    email_validated: bool,
+    password_hash: String,
+    deletion_scheduled: Option<DateTime<Utc>>,
+    banned: Option<DateTime<Utc>>,
+    guest: bool,
+}
+
+impl IdentityRow {
+    fn into_core(self) -> Result<(CoreIdentityData, String), IAMError> {
+        let category = match self.category.as_str() {
+            "User" => IdentityCategory::User,
+            cat => return Err(IAMError::Internal(format!("Unknown identity category: {}", cat))),
+        };
+        let core = CoreIdentityData {
+            id: self.id,
+            sequence_id: self.sequence_id as u64,
+            salt: self.salt,
+            category,
+            name: ValidatedName::from_stored(self.name),
+            email: self.email.map(ValidatedEmail::from_stored),
+            email_validated: self.email_validated,
+            deletion_scheduled: self.deletion_scheduled,
+            banned: self.banned,
+            guest: self.guest,
+        };
+        Ok((core, self.password_hash))
+    }
+
+    fn into_identity<D>(self, build: impl FnOnce(CoreIdentityData, String) -> D) -> Result<IdentityEntity<D>, IAMError>
+    where
+        D: IdentityData,
+    {
+        let (core, password_hash) = self.into_core()?;
+        let (partition_key, row_key) = IdentityEntity::<D>::entity_keys(&core.id);
+        Ok(IdentityEntity(TableEntity {
+            partition_key,
+            row_key,
+            etag: None,
+            timestamp: None,
+            payload: build(core, password_hash),
+        }))
+    }
+
+    fn into_user(self) -> Result<UserIdentity, IAMError> {
+        self.into_identity(|core, password_hash| UserIdentityData { core, password_hash })
+    }
+
+    fn into_core_identity(self) -> Result<CoreIdentity, IAMError> {
+        self.into_identity(|core, _| core)
+    }
+}
+
+#[derive(FromRow)]
+struct EmailVerificationRow {
+    token: String,
+    identity_id: String,
+    email: String,
+    issued: DateTime<Utc>,
+    email_change: bool,
+}
+
+#[derive(FromRow)]
+struct PasswordResetRow {
+    token: String,
+    identity_id: String,
+    issued: DateTime<Utc>,
+}
+
+/// Map the unique violations to the conflict errors of the store
+fn map_unique_violation(err: sqlx::Error) -> IAMError {
+    if let sqlx::Error::Database(ref db_err) = err {
+        if let Some(pg_err) = db_err.try_downcast_ref::<PgDatabaseError>() {
+            if pg_err.code() == "23505" {
+                match pg_err.constraint() {
+                    Some("identities_pkey") => return IAMError::IdentityIdConflict,
+                    Some("identities_sequence_id_key") => return IAMError::SequenceIdTaken,
+                    Some("identities_name_key") => return IAMError::NameTaken,
+                    Some("identities_email_key") => return IAMError::EmailTaken,
+                    Some("email_verifications_pkey") => return IAMError::VerificationTokenConflict,
+                    Some("password_resets_pkey") => return IAMError::ResetTokenConflict,
+                    _ => {}
+                }
+            }
+        }
+    }
+    IAMError::from(err)
+}
+
+/// Identity store using a Postgres database. The uniqueness of the name, email and sequence id is
+/// ensured by the constraints of the identities table.
+#[derive(Clone)]
+pub struct PostgresIdentityStore {
+    pool: PgPool,
+}
+
+impl PostgresIdentityStore {
+    pub async fn new(url: &str, max_connections: u32) -> Result<Self, IAMError> {
+        let pool = PgPoolOptions::new()
+            .max_connections(max_connections)
+            .connect(url)
+            .await?;
+
+        for statement in SCHEMA {
+            sqlx::query(statement).execute(&pool).await?;
+        }
+
+        Ok(PostgresIdentityStore { pool })
+    }
+
+    async fn find_identity_row(&self, filter: &str, value: &str) -> Result<IdentityRow, IAMError> {
+        let query = format!("{} WHERE {}", SELECT_IDENTITY, filter);
+        sqlx::query_as::<_, IdentityRow>(&query)
+            .bind(value)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or(IAMError::IdentityNotFound)
+    }
+}
+
+impl IdentityStore for PostgresIdentityStore {
+    fn next_sequence_id(&self) -> StoreFuture<'_, u64> {
+        Box::pin(async move {
+            let (id,): (i64,) = sqlx::query_as("SELECT nextval('identity_sequence')")
+                .fetch_one(&self.pool)
+                .await?;
+            Ok(id as u64)
+        })
+    }
+
+    fn is_name_available<'a>(&'a self, name: &'a ValidatedName) -> StoreFuture<'a, bool> {
+        Box::pin(async move {
+            let (taken,): (bool,) = sqlx::query_as("SELECT EXISTS(SELECT 1 FROM identities WHERE name = $1)")
+                .bind(name.as_str())
+                .fetch_one(&self.pool)
+                .await?;
+            Ok(!taken)
+        })
+    }
+
+    fn is_email_available<'a>(&'a self, cat: IdentityCategory, email: &'a ValidatedEmail) -> StoreFuture<'a, bool> {
+        Box::pin(async move {
+            let (taken,): (bool,) =
+                sqlx::query_as("SELECT EXISTS(SELECT 1 FROM identities WHERE category = $1 AND email = $2)")
+                    .bind(format!("{:?}", cat))
+                    .bind(email.as_str())
+                    .fetch_one(&self.pool)
+                    .await?;
+            Ok(!taken)
+        })
+    }
+
+    fn insert_user(&self, identity: UserIdentity) -> StoreFuture<'_, UserIdentity> {
+        Box::pin(async move {
+            {
+                let data = identity.data();
+                let core = &data.core;
+                sqlx::query(
+                    "INSERT INTO identities (id, sequence_id, salt, category, name, email, email_validated, password_hash, guest)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+                )
+                .bind(&core.id)
+                .bind(core.sequence_id as i64)
+                .bind(&core.salt)
+                .bind(format!("{:?}", core.category))
+                .bind(core.name.as_str())
+                .bind(core.email.as_ref().map(|e| e.as_str()))
+                .bind(core.email_validated)
+                .bind(&data.password_hash)
+                .bind(core.guest)
+                .execute(&self.pool)
+                .await
+                .map_err(map_unique_violation)?;
+            }
+            Ok(identity)
+        })
+    }
+
+    fn update_user(&self, identity: UserIdentity) -> StoreFuture<'_, UserIdentity> {
+        Box::pin(async move {
+            let result = {
+                let data = identity.data();
+                let core = &data.core;
+                sqlx::query(
+                    "UPDATE identities SET name = $2, email = $3, email_validated = $4, password_hash = $5, \
+                     deletion_scheduled = $6, banned = $7, guest = $8 WHERE id = $1",
+                )
+                .bind(&core.id)
+                .bind(core.name.as_str())
+                .bind(core.email.as_ref().map(|e| e.as_str()))
+                .bind(core.email_validated)
+                .bind(&data.password_hash)
+                .bind(core.deletion_scheduled)
+                .bind(core.banned)
+                .bind(core.guest)
+                .execute(&self.pool)
+                .await
+                .map_err(map_unique_violation)?
+            };
+            if result.rows_affected() == 0 {
+                return Err(IAMError::IdentityNotFound);
+            }
+            Ok(identity)
+        })
+    }
+
+    fn find_user_by_id<'a>(&'a self, id: &'a str) -> StoreFuture<'a, UserIdentity> {
+        Box::pin(async move { self.find_identity_row("id = $1", id).await?.into_user() })
+    }
+
+    fn find_user_by_name<'a>(&'a self, name: &'a ValidatedName) -> StoreFuture<'a, UserIdentity> {
+        Box::pin(async move { self.find_identity_row("name = $1", name.as_str()).await?.into_user() })
+    }
+
+    fn find_user_by_email<'a>(&'a self, email: &'a ValidatedEmail) -> StoreFuture<'a, UserIdentity> {
+        Box::pin(async move {
+            self.find_identity_row("category = 'User' AND email = $1", email.as_str())
+                .await?
+                .into_user()
+        })
+    }
+
+    fn find_core_identity_by_id<'a>(&'a self, id: &'a str) -> StoreFuture<'a, CoreIdentity> {
+        Box::pin(async move { self.find_identity_row("id = $1", id).await?.into_core_identity() })
+    }
+
+    fn search_identities<'a>(
+        &'a self,
+        search: &'a IdentitySearch,
+        continuation: Option<&'a str>,
+        limit: usize,
+    ) -> StoreFuture<'a, IdentitySearchPage> {
+        Box::pin(async move {
+            // the stored keys are safe key encoded, they contain no LIKE wildcards
+            let (filter, prefix) = match search {
+                IdentitySearch::Name(prefix) => ("name", prefix),
+                IdentitySearch::Email(prefix) => ("email", prefix),
+            };
+            let query = format!(
+                "{} WHERE category = 'User' AND {filter} LIKE $1 AND {filter} > $2 ORDER BY {filter} LIMIT $3",
+                SELECT_IDENTITY,
+                filter = filter
+            );
+            let rows = sqlx::query_as::<_, IdentityRow>(&query)
+                .bind(format!("{}%", prefix))
+                .bind(continuation.unwrap_or(""))
+                .bind(limit as i64)
+                .fetch_all(&self.pool)
+                .await?;
+
+            let identities = rows
+                .into_iter()
+                .map(IdentityRow::into_core_identity)
+                .collect::<Result<Vec<_>, _>>()?;
+            let continuation = if identities.len() == limit {
+                identities.last().map(|identity| match search {
+                    IdentitySearch::Name(_) => identity.core().name.as_str().to_owned(),
+                    IdentitySearch::Email(_) => identity
+                        .core()
+                        .email
+                        .as_ref()
+                        .map(|email| email.as_str().to_owned())
+                        .unwrap_or_default(),
+                })
+            } else {
+                None
+            };
+            Ok(IdentitySearchPage {
+                identities,
+                continuation,
+            })
+        })
+    }
+
+    fn find_identities_to_delete(&self, before: DateTime<Utc>) -> StoreFuture<'_, Vec<String>> {
+        Box::pin(async move {
+            let ids: Vec<(String,)> = sqlx::query_as("SELECT id FROM identities WHERE deletion_scheduled < $1")
+                .bind(before)
+                .fetch_all(&self.pool)
+                .await?;
+            Ok(ids.into_iter().map(|(id,)| id).collect())
+        })
+    }
+
+    fn delete_user(&self, identity: UserIdentity) -> StoreFuture<'_, ()> {
+        Box::pin(async move {
+            let mut tx = self.pool.begin().await?;
+            for query in &[
+                "DELETE FROM email_verifications WHERE identity_id = $1",
+                "DELETE FROM password_resets WHERE identity_id = $1",
+                "DELETE FROM identities WHERE id = $1",
+            ] {
+                sqlx::query(query).bind(identity.id()).execute(&mut tx).await?;
+            }
+            tx.commit().await?;
+            Ok(())
+        })
+    }
+
+    fn insert_email_verification(&self, verification: EmailVerification) -> StoreFuture<'_, EmailVerification> {
+        Box::pin(async move {
+            sqlx::query(
+                "INSERT INTO email_verifications (token, identity_id, email, issued, email_change) \
+                 VALUES ($1, $2, $3, $4, $5)",
+            )
+            .bind(verification.token())
+            .bind(verification.id())
+            .bind(&verification.data().email)
+            .bind(verification.data().issued)
+            .bind(verification.data().email_change)
+            .execute(&self.pool)
+            .await
+            .map_err(map_unique_violation)?;
+            Ok(verification)
+        })
+    }
+
+    fn take_email_verification<'a>(&'a self, token: &'a str) -> StoreFuture<'a, Option<EmailVerification>> {
+        Box::pin(async move {
+            let row = sqlx::query_as::<_, EmailVerificationRow>(
+                "DELETE FROM email_verifications WHERE token = $1 \
+                 RETURNING token, identity_id, email, issued, email_change",
+            )
+            .bind(token)
+            .fetch_optional(&self.pool)
+            .await?;
+
+            Ok(row.map(|row| {
+                let (partition_key, row_key) = EmailVerification::entity_keys(&row.token);
+                EmailVerification::from_entity(TableEntity {
+                    partition_key,
+                    row_key,
+                    etag: None,
+                    timestamp: None,
+                    payload: EmailVerificationData {
+                        identity_id: row.identity_id,
+                        email: row.email,
+                        issued: row.issued,
+                        email_change: row.email_change,
+                    },
+                })
+            }))
+        })
+    }
+
+    fn insert_password_reset(&self, reset: PasswordReset) -> StoreFuture<'_, PasswordReset> {
+        Box::pin(async move {
+            sqlx::query("INSERT INTO password_resets (token, identity_id, issued) VALUES ($1, $2, $3)")
+                .bind(reset.token())
+                .bind(reset.id())
+                .bind(reset.data().issued)
+                .execute(&self.pool)
+                .await
+                .map_err(map_unique_violation)?;
+            Ok(reset)
+        })
+    }
+
+    fn take_password_reset<'a>(&'a self, token: &'a str) -> StoreFuture<'a, Option<PasswordReset>> {
+        Box::pin(async move {
+            let row = sqlx::query_as::<_, PasswordResetRow>(
+                "DELETE FROM password_resets WHERE token = $1 RETURNING token, identity_id, issued",
+            )
+            .bind(token)
+            .fetch_optional(&self.pool)
+            .await?;
+
+            Ok(row.map(|row| {
+                let (partition_key, row_key) = PasswordReset::entity_keys(&row.token);
+                PasswordReset::from_entity(TableEntity {
+                    partition_key,
+                    row_key,
+                    etag: None,
+                    timestamp: None,
+                    payload: PasswordResetData {
+                        identity_id: row.identity_id,
+                        issued: row.issued,
+                    },
+                })
+            }))
+        })
+    }
+}
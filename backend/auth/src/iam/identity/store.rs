@@ -0,0 +1,101 @@
+use crate::iam::{
+    identity::{
+        CoreIdentity, EmailVerification, IdentityCategory, PasswordReset, UserIdentity, ValidatedEmail, ValidatedName,
+    },
+    IAMError,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{future::Future, pin::Pin};
+
+pub type StoreFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, IAMError>> + 'a>>;
+
+/// The index used to search the users. The prefixes are safe key encoded as the indexed values.
+#[derive(Clone, Debug)]
+pub enum IdentitySearch {
+    /// Search by the prefix of the name, an empty prefix lists all the users
+    Name(String),
+    /// Search by the prefix of the email address
+    Email(String),
+}
+
+/// A page of the user search ordered by the searched index
+#[derive(Debug)]
+pub struct IdentitySearchPage {
+    pub identities: Vec<CoreIdentity>,
+    /// Key to continue the search with, None if no more identities are found
+    pub continuation: Option<String>,
+}
+
+/// Storage backend of the identities
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum IdentityStoreConfig {
+    /// Azure table storage using the storage account of the IAMConfig
+    Azure,
+    Postgres {
+        url: String,
+        max_connections: u32,
+    },
+    /// Keep the identities in memory, for local development and tests
+    Memory,
+}
+
+impl Default for IdentityStoreConfig {
+    fn default() -> Self {
+        IdentityStoreConfig::Azure
+    }
+}
+
+/// Persistence of the identities and the related tokens. The business logic (hashing, token generation,
+/// expiry) is implemented by the IdentityManager, the stores only ensure the uniqueness constraints.
+pub trait IdentityStore {
+    /// Generate the next sequence id of the identities
+    fn next_sequence_id(&self) -> StoreFuture<'_, u64>;
+
+    fn is_name_available<'a>(&'a self, name: &'a ValidatedName) -> StoreFuture<'a, bool>;
+
+    fn is_email_available<'a>(&'a self, cat: IdentityCategory, email: &'a ValidatedEmail) -> StoreFuture<'a, bool>;
+
+    /// Insert a new user. On conflict IdentityIdConflict, SequenceIdTaken, NameTaken or EmailTaken
+    /// is returned and no trace of the user is kept.
+    fn insert_user(&self, identity: UserIdentity) -> StoreFuture<'_, UserIdentity>;
+
+    /// Update the data of an existing user
+    fn update_user(&self, identity: UserIdentity) -> StoreFuture<'_, UserIdentity>;
+
+    fn find_user_by_id<'a>(&'a self, id: &'a str) -> StoreFuture<'a, UserIdentity>;
+
+    fn find_user_by_name<'a>(&'a self, name: &'a ValidatedName) -> StoreFuture<'a, UserIdentity>;
+
+    fn find_user_by_email<'a>(&'a self, email: &'a ValidatedEmail) -> StoreFuture<'a, UserIdentity>;
+
+    fn find_core_identity_by_id<'a>(&'a self, id: &'a str) -> StoreFuture<'a, CoreIdentity>;
+
+    /// Find at most limit identities matching the search after the continuation key
+    fn search_identities<'a>(
+        &'a self,
+        search: &'a IdentitySearch,
+        continuation: Option<&'a str>,
+        limit: usize,
+    ) -> StoreFuture<'a, IdentitySearchPage>;
+
+    /// Find the id of the identities with a deletion scheduled before the given date. Stores may return only
+    /// a part of the identities, the rest is found by the subsequent calls.
+    fn find_identities_to_delete(&self, before: DateTime<Utc>) -> StoreFuture<'_, Vec<String>>;
+
+    /// Delete a user with all of its indices
+    fn delete_user(&self, identity: UserIdentity) -> StoreFuture<'_, ()>;
+
+    /// Insert a new email verification, returns VerificationTokenConflict if the token is taken
+    fn insert_email_verification(&self, verification: EmailVerification) -> StoreFuture<'_, EmailVerification>;
+
+    /// Remove and return the email verification of the token
+    fn take_email_verification<'a>(&'a self, token: &'a str) -> StoreFuture<'a, Option<EmailVerification>>;
+
+    /// Insert a new password reset, returns ResetTokenConflict if the token is taken
+    fn insert_password_reset(&self, reset: PasswordReset) -> StoreFuture<'_, PasswordReset>;
+
+    /// Remove and return the password reset of the token
+    fn take_password_reset<'a>(&'a self, token: &'a str) -> StoreFuture<'a, Option<PasswordReset>>;
+}
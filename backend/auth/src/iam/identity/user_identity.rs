@@ -5,7 +5,7 @@ use azure_sdk_storage_table::TableEntity;
 use serde::{Deserialize, Serialize};
 
 /// Data associated to a user identity
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct UserIdentityData {
     #[serde(flatten)]
@@ -46,6 +46,9 @@ impl UserIdentity {
                     category: IdentityCategory::User,
                     email,
                     email_validated: false,
+                    deletion_scheduled: None,
+                    banned: None,
+                    guest: false,
                 },
                 password_hash,
             },
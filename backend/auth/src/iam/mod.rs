@@ -1,36 +1,322 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use shine_core::iplocation::{IpCachedLocation, IpCachedLocationConfig, IpLocationIpDataCo, IpLocationIpDataCoConfig};
-use shine_core::requestinfo::RemoteInfo;
+use shine_core::configloader::ConfigValidator;
+use shine_core::idgenerator::{CounterBackendConfig, IdSequenceConfig};
+use shine_core::iplocation::{
+    IpCachedLocation, IpCachedLocationConfig, IpLocationIpDataCo, IpLocationIpDataCoConfig, IpLocationMaxMind,
+    IpLocationMaxMindConfig, IpLocationProvider, IpNoLocation,
+};
+use shine_core::mailer::{LogMailer, Mail, Mailer, NullMailer, SmtpMailer, SmtpMailerConfig};
+use shine_core::metrics::Metrics;
+use shine_core::pubsub::{PubSub, Subscription};
+use shine_core::requestinfo::{
+    ApiKeyAuth, ApiKeyIdentity, ApiKeyValidator, AutomationClaims, AutomationTokenSigner, BearerAuth, RemoteInfo,
+    RequestInfoError, TokenIdentity, TokenValidator, AUTOMATION_SCOPE_ALL,
+};
+use shine_core::secret::{SecretRule, SecretValidator};
 use std::collections::HashSet;
+use std::future::Future;
 use std::iter::FromIterator;
+use std::pin::Pin;
+use std::sync::Arc;
 use std::time::Duration;
+use tera::{Context, Tera};
 
+pub mod apikey;
+mod bulk;
+pub mod entitlement;
 mod error;
+mod export;
 pub mod fingerprint;
 pub mod identity;
+mod moderation;
+mod notification;
+pub mod oauth;
 pub mod role;
 pub mod session;
 
+pub use self::bulk::*;
 pub use self::error::*;
+pub use self::export::*;
+pub use self::moderation::*;
+pub use self::notification::*;
 
+use apikey::{ApiKeyInfo, ApiKeyManager, ApiKeyStoreConfig};
+use entitlement::{EntitlementInfo, EntitlementManager, EntitlementStoreConfig};
 use fingerprint::Fingerprint;
-use identity::{Identity, IdentityManager, UserIdentity, ValidatedEmail, ValidatedName, ValidatedPassword};
-use role::{InheritedRoles, RoleManager, Roles};
-use session::{Session, SessionManager};
+use identity::{
+    is_valid_token, Identity, IdentityManager, IdentitySearch, IdentityStoreConfig, PasswordPolicy,
+    PasswordPolicyConfig, UserIdentity, ValidatedEmail, ValidatedName, ValidatedPassword,
+};
+use oauth::{OAuthClientInfo, OAuthManager, OAuthStoreConfig};
+use role::{
+    EffectiveRoles, EffectiveRolesDiff, InheritedRoles, Permissions, RoleCacheConfig, RoleManager, RoleStoreConfig,
+    Roles,
+};
+use session::{Session, SessionInfo, SessionManager, SessionStoreConfig};
 
+/// Provider of the location of the remote ip for the fingerprints
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum IpLocationConfig {
+    /// ipdata.co cached in the Azure table storage
+    IpDataCo,
+    /// Local MaxMind GeoLite2 database, the lookup is local thus it is not cached
+    MaxMind { database_path: String },
+    /// No location lookup, for local development and tests
+    Disabled,
+}
+
+impl Default for IpLocationConfig {
+    fn default() -> Self {
+        IpLocationConfig::IpDataCo
+    }
+}
+
+/// Delivery of the mails (verification, password reset, notifications)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum MailerConfig {
+    /// SMTP relay using the smtp settings of the IAMConfig
+    Smtp,
+    /// Log the mails without sending them, for local development
+    Log,
+    /// Drop the mails, for tests
+    Disabled,
+}
+
+impl Default for MailerConfig {
+    fn default() -> Self {
+        MailerConfig::Log
+    }
+}
+
+/// Configuration of the IAM. The external services are optional, with the Memory stores and the
+/// Disabled ip location the service can be started without any of them.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct IAMConfig {
     pub password_pepper: String,
+    #[serde(default)]
     pub storage_account: String,
+    #[serde(default)]
     pub storage_account_key: String,
+    #[serde(default)]
     pub graph_db_host: String,
+    #[serde(default)]
     pub graph_db_port: u16,
+    #[serde(default)]
     pub graph_db_user: String,
+    #[serde(default)]
     pub graph_db_password: String,
+    #[serde(default)]
     pub ipdataco_key: String,
+    #[serde(default)]
+    pub smtp_host: String,
+    #[serde(default = "IAMConfig::default_smtp_port")]
+    pub smtp_port: u16,
+    #[serde(default)]
+    pub smtp_user: String,
+    #[serde(default)]
+    pub smtp_password: String,
+    /// Sender address of the mails
+    #[serde(default)]
+    pub mail_from: String,
     pub session_time_to_live_h: u16,
+    pub email_verification_url: String,
+    pub email_verification_time_to_live_h: u16,
+    /// Url of the confirmation of an email change, the token is appended to the url
+    pub email_change_url: String,
+    pub password_reset_url: String,
+    pub password_reset_time_to_live_m: u16,
+    /// Days before a deleted identity is purged, signing in within this period cancels the deletion
+    #[serde(default = "IAMConfig::default_deletion_grace_period_d")]
+    pub deletion_grace_period_d: u16,
+    #[serde(default)]
+    pub identity_store: IdentityStoreConfig,
+    /// Counter of the sequence ids of the Azure identity store, the other stores use their own sequences
+    #[serde(default)]
+    pub identity_id_sequence: IdSequenceConfig,
+    #[serde(default)]
+    pub session_store: SessionStoreConfig,
+    #[serde(default)]
+    pub role_store: RoleStoreConfig,
+    #[serde(default)]
+    pub role_cache: RoleCacheConfig,
+    #[serde(default)]
+    pub apikey_store: ApiKeyStoreConfig,
+    #[serde(default)]
+    pub oauth_store: OAuthStoreConfig,
+    #[serde(default)]
+    pub entitlement_store: EntitlementStoreConfig,
+    /// BASE64 encoded Ed25519 seed to sign the entitlement tokens, tokens are not issued if empty
+    #[serde(default)]
+    pub entitlement_signing_key: String,
+    /// Minutes before an entitlement token expires
+    #[serde(default = "IAMConfig::default_entitlement_token_time_to_live_m")]
+    pub entitlement_token_time_to_live_m: u16,
+    /// Minutes before an OAuth access token expires
+    #[serde(default = "IAMConfig::default_oauth_token_time_to_live_m")]
+    pub oauth_token_time_to_live_m: u16,
+    #[serde(default)]
+    pub ip_location: IpLocationConfig,
+    /// Number of ip locations kept in memory in front of the persistent cache
+    #[serde(default = "IAMConfig::default_ip_cache_capacity")]
+    pub ip_cache_capacity: usize,
+    /// Minutes an ip location is kept in memory
+    #[serde(default = "IAMConfig::default_ip_cache_time_to_live_m")]
+    pub ip_cache_time_to_live_m: u16,
+    #[serde(default)]
+    pub password_policy: PasswordPolicyConfig,
+    #[serde(default)]
+    pub mailer: MailerConfig,
+    /// Roles assigned to the guest identities, they are kept when a guest is promoted
+    #[serde(default)]
+    pub guest_roles: Vec<String>,
+    /// BASE64 encoded key to sign the automation tokens of the test fixtures and the CI tools, automation
+    /// tokens are rejected if empty
+    #[serde(default)]
+    pub automation_signing_key: String,
+    /// Upper limit of the lifetime of the automation tokens in minutes
+    #[serde(default = "IAMConfig::default_automation_token_max_time_to_live_m")]
+    pub automation_token_max_time_to_live_m: u16,
+}
+
+impl IAMConfig {
+    fn default_deletion_grace_period_d() -> u16 {
+        30
+    }
+
+    fn default_smtp_port() -> u16 {
+        587
+    }
+
+    fn default_oauth_token_time_to_live_m() -> u16 {
+        60
+    }
+
+    fn default_entitlement_token_time_to_live_m() -> u16 {
+        60
+    }
+
+    fn default_automation_token_max_time_to_live_m() -> u16 {
+        60
+    }
+
+    fn default_ip_cache_capacity() -> usize {
+        4096
+    }
+
+    fn default_ip_cache_time_to_live_m() -> u16 {
+        60
+    }
+
+    /// Replace the stores and the external providers by the local implementations, the mails are logged.
+    pub fn use_offline_services(&mut self) {
+        self.identity_store = IdentityStoreConfig::Memory;
+        self.identity_id_sequence.backend = CounterBackendConfig::Memory;
+        self.session_store = SessionStoreConfig::Memory;
+        self.role_store = RoleStoreConfig::Memory;
+        self.role_cache.redis_url = None;
+        self.apikey_store = ApiKeyStoreConfig::Memory;
+        self.oauth_store = OAuthStoreConfig::Memory;
+        self.entitlement_store = EntitlementStoreConfig::Memory;
+        self.ip_location = IpLocationConfig::Disabled;
+        self.mailer = MailerConfig::Log;
+    }
+
+    fn uses_storage_account(&self) -> bool {
+        matches!(self.identity_store, IdentityStoreConfig::Azure)
+            || matches!(self.session_store, SessionStoreConfig::Azure)
+            || matches!(self.apikey_store, ApiKeyStoreConfig::Azure)
+            || matches!(self.oauth_store, OAuthStoreConfig::Azure)
+            || matches!(self.entitlement_store, EntitlementStoreConfig::Azure)
+            || matches!(self.ip_location, IpLocationConfig::IpDataCo)
+    }
+
+    /// Check the constraints of the settings that are not expressed by the types
+    pub fn validate(&self, validator: &mut ConfigValidator) {
+        if self.uses_storage_account() {
+            validator.non_empty("storage_account", &self.storage_account);
+        }
+        if let RoleStoreConfig::Gremlin = self.role_store {
+            validator.non_empty("graph_db_host", &self.graph_db_host);
+            validator.positive("graph_db_port", self.graph_db_port);
+        }
+        validator.positive("session_time_to_live_h", self.session_time_to_live_h);
+        validator.positive(
+            "email_verification_time_to_live_h",
+            self.email_verification_time_to_live_h,
+        );
+        validator.positive("password_reset_time_to_live_m", self.password_reset_time_to_live_m);
+        validator.positive(
+            "entitlement_token_time_to_live_m",
+            self.entitlement_token_time_to_live_m,
+        );
+        validator.positive("oauth_token_time_to_live_m", self.oauth_token_time_to_live_m);
+        validator.positive(
+            "automation_token_max_time_to_live_m",
+            self.automation_token_max_time_to_live_m,
+        );
+        validator.http_url("email_verification_url", &self.email_verification_url);
+        validator.http_url("email_change_url", &self.email_change_url);
+        validator.http_url("password_reset_url", &self.password_reset_url);
+    }
+
+    /// Resolve and validate the secrets required by the configured stores and providers
+    pub async fn check_secrets(&mut self, validator: &mut SecretValidator) {
+        validator
+            .check(
+                "auth.iam.password_pepper",
+                &mut self.password_pepper,
+                SecretRule::text(16).with_min_entropy_bits(48.),
+            )
+            .await;
+        validator
+            .check_optional(
+                "auth.iam.automation_signing_key",
+                &mut self.automation_signing_key,
+                SecretRule::key(32),
+            )
+            .await;
+        validator
+            .check_optional(
+                "auth.iam.entitlement_signing_key",
+                &mut self.entitlement_signing_key,
+                SecretRule::key(32).with_max_len(32),
+            )
+            .await;
 
-    pub test_token: String,
+        if self.uses_storage_account() {
+            validator
+                .check(
+                    "auth.iam.storage_account_key",
+                    &mut self.storage_account_key,
+                    SecretRule::key(64).with_max_len(64),
+                )
+                .await;
+        }
+        if let RoleStoreConfig::Gremlin = self.role_store {
+            validator
+                .check(
+                    "auth.iam.graph_db_password",
+                    &mut self.graph_db_password,
+                    SecretRule::text(1),
+                )
+                .await;
+        }
+        if let IpLocationConfig::IpDataCo = self.ip_location {
+            validator
+                .check("auth.iam.ipdataco_key", &mut self.ipdataco_key, SecretRule::text(1))
+                .await;
+        }
+        if let MailerConfig::Smtp = self.mailer {
+            if !self.smtp_user.is_empty() {
+                validator
+                    .check("auth.iam.smtp_password", &mut self.smtp_password, SecretRule::text(1))
+                    .await;
+            }
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -38,12 +324,26 @@ pub struct IAM {
     identity: IdentityManager,
     session: SessionManager,
     role: RoleManager,
-    iplocation: IpCachedLocation,
-    test_token: String,
+    apikey: ApiKeyManager,
+    oauth: OAuthManager,
+    entitlement: EntitlementManager,
+    iplocation: Arc<dyn IpLocationProvider>,
+    password_policy: PasswordPolicy,
+    mailer: Arc<dyn Mailer>,
+    mail_templates: Arc<Tera>,
+    metrics: Metrics,
+    notifications: PubSub<AccountEvent>,
+    guest_roles: Vec<String>,
+    email_verification_url: String,
+    email_change_url: String,
+    password_reset_url: String,
+    automation_signer: Option<AutomationTokenSigner>,
+    automation_token_max_time_to_live: chrono::Duration,
 }
 
 impl IAM {
-    pub async fn new(config: IAMConfig) -> Result<Self, IAMError> {
+    /// Create the IAM, the mails are rendered using the templates of the service (mail_*.txt)
+    pub async fn new(config: IAMConfig, tera: Tera, metrics: Metrics) -> Result<Self, IAMError> {
         log::debug!("Initialize identity");
         let identity = IdentityManager::new(&config).await?;
 
@@ -53,30 +353,136 @@ impl IAM {
         log::debug!("Initialize role");
         let role = RoleManager::new(&config).await?;
 
+        log::debug!("Initialize api keys");
+        let apikey = ApiKeyManager::new(&config).await?;
+
+        log::debug!("Initialize oauth");
+        let oauth = OAuthManager::new(&config).await?;
+
+        log::debug!("Initialize entitlements");
+        let entitlement = EntitlementManager::new(&config).await?;
+
         log::debug!("Initialize ip location");
-        let cfg = IpLocationIpDataCoConfig {
-            api_key: config.ipdataco_key.clone(),
+        let iplocation: Arc<dyn IpLocationProvider> = match config.ip_location {
+            IpLocationConfig::IpDataCo => {
+                let cfg = IpLocationIpDataCoConfig {
+                    api_key: config.ipdataco_key.clone(),
+                };
+                let provider = IpLocationIpDataCo::new(cfg);
+                let cfg = IpCachedLocationConfig {
+                    storage_account: config.storage_account.clone(),
+                    storage_account_key: config.storage_account_key.clone(),
+                    table_name: "ipcache".to_owned(),
+                    time_to_live: Duration::from_secs(12 * 60 * 60),
+                    memory_capacity: config.ip_cache_capacity,
+                    memory_time_to_live: Duration::from_secs(u64::from(config.ip_cache_time_to_live_m) * 60),
+                };
+                let cache = IpCachedLocation::new(provider, cfg).await?;
+                cache
+                    .register_metrics(metrics.registry())
+                    .map_err(|err| IAMError::Internal(format!("Failed to register ip location metrics: {}", err)))?;
+                Arc::new(cache)
+            }
+            IpLocationConfig::MaxMind { ref database_path } => {
+                let cfg = IpLocationMaxMindConfig {
+                    database_path: database_path.clone(),
+                };
+                Arc::new(IpLocationMaxMind::new(cfg)?)
+            }
+            IpLocationConfig::Disabled => Arc::new(IpNoLocation),
         };
-        let provider = IpLocationIpDataCo::new(cfg);
-        let cfg = IpCachedLocationConfig {
-            storage_account: config.storage_account.clone(),
-            storage_account_key: config.storage_account_key.clone(),
-            table_name: "ipcache".to_owned(),
-            time_to_live: Duration::from_secs(12 * 60 * 60),
+        log::info!("Ip location: {:?}", config.ip_location);
+
+        let password_policy = PasswordPolicy::new(&config.password_policy);
+        log::info!("Password policy: {:?}", config.password_policy);
+
+        log::debug!("Initialize mailer");
+        let mailer: Arc<dyn Mailer> = match config.mailer {
+            MailerConfig::Smtp => {
+                let cfg = SmtpMailerConfig {
+                    host: config.smtp_host.clone(),
+                    port: config.smtp_port,
+                    user: config.smtp_user.clone(),
+                    password: config.smtp_password.clone(),
+                    from: config.mail_from.clone(),
+                    retry_count: 3,
+                };
+                Arc::new(SmtpMailer::new(cfg)?)
+            }
+            MailerConfig::Log => Arc::new(LogMailer),
+            MailerConfig::Disabled => Arc::new(NullMailer),
+        };
+        log::info!("Mailer: {:?}", config.mailer);
+
+        let automation_signer = if config.automation_signing_key.is_empty() {
+            log::info!("Automation signing key is not configured, automation tokens are disabled");
+            None
+        } else {
+            let signer = AutomationTokenSigner::from_base64(&config.automation_signing_key)
+                .map_err(|err| IAMError::Internal(format!("{}", err)))?;
+            Some(signer)
         };
-        let iplocation = IpCachedLocation::new(provider, cfg).await?;
 
         Ok(IAM {
             identity,
             session,
             role,
+            apikey,
+            oauth,
+            entitlement,
             iplocation,
-            test_token: config.test_token.clone(),
+            password_policy,
+            mailer,
+            mail_templates: Arc::new(tera),
+            metrics,
+            notifications: PubSub::default(),
+            guest_roles: config.guest_roles.clone(),
+            email_verification_url: config.email_verification_url.clone(),
+            email_change_url: config.email_change_url.clone(),
+            password_reset_url: config.password_reset_url.clone(),
+            automation_signer,
+            automation_token_max_time_to_live: chrono::Duration::minutes(i64::from(
+                config.automation_token_max_time_to_live_m,
+            )),
         })
     }
 
+    /// Count the result of an operation in the metrics
+    fn count_operation<T>(&self, operation: &str, result: &Result<T, IAMError>) {
+        self.metrics.count_operation("iam", operation, result.is_ok());
+    }
+
+    /// Stream the account notifications of an identity
+    pub fn subscribe_account_events(&self, user_id: &str) -> Subscription<AccountEvent> {
+        self.notifications.subscribe(user_id)
+    }
+
+    fn notify(&self, user_id: &str, event: AccountEvent) {
+        log::debug!("Account event of {}: {:?}", user_id, event);
+        self.notifications.publish(user_id, event);
+    }
+
+    async fn is_known_device(&self, user_id: &str, fingerprint: &Fingerprint) -> Result<bool, IAMError> {
+        let sessions = self.session.get_active_sessions(user_id).await?;
+        Ok(notification::is_known_device(&sessions, fingerprint))
+    }
+
     pub async fn get_fingerprint(&self, remote: &RemoteInfo) -> Result<Fingerprint, IAMError> {
-        Fingerprint::new(remote, &self.iplocation).await
+        Fingerprint::new(remote, &*self.iplocation).await
+    }
+
+    pub fn password_policy(&self) -> &PasswordPolicyConfig {
+        self.password_policy.config()
+    }
+
+    /// Check the password against the password policy, returns PasswordPolicy with the violated rules
+    pub async fn check_password_policy(&self, password: &ValidatedPassword) -> Result<(), IAMError> {
+        let violations = self.password_policy.check(password).await;
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(IAMError::PasswordPolicy(violations))
+        }
     }
 
     pub async fn register_user(
@@ -86,13 +492,189 @@ impl IAM {
         password: ValidatedPassword,
         fingerprint: &Fingerprint,
     ) -> Result<(UserIdentity, InheritedRoles, Session), IAMError> {
-        let identity = self.identity.create_user(name, email, password).await?;
-        let session = self.session.create_session(&identity, fingerprint).await?;
-        self.role.create_identity(identity.id()).await?;
-        // todo: register default user roles
+        let result = async {
+            self.check_password_policy(&password).await?;
+            let identity = self.identity.create_user(name, email, password).await?;
+            let session = self.session.create_session(&identity, fingerprint).await?;
+            self.role.create_identity(identity.id()).await?;
+            // todo: register default user roles
+            let roles = self.role.get_identity_roles(&identity.id(), true).await?;
+
+            // registration is not rolled back on mail failure, verification can be requested again
+            if let Err(err) = self.send_email_verification(&identity).await {
+                log::warn!("Failed to send email verification for {}: {:?}", identity.id(), err);
+            }
+
+            Ok((identity, roles, session))
+        }
+        .await;
+        self.count_operation("register", &result);
+        result
+    }
+
+    /// Create a guest identity with the restricted guest roles for instant play
+    pub async fn register_guest(
+        &self,
+        fingerprint: &Fingerprint,
+    ) -> Result<(UserIdentity, InheritedRoles, Session), IAMError> {
+        let result = async {
+            let identity = self.identity.create_guest().await?;
+            let session = self.session.create_session(&identity, fingerprint).await?;
+            self.role.create_identity(identity.id()).await?;
+            for role in &self.guest_roles {
+                self.role.add_identity_role(identity.id(), role).await?;
+            }
+            let roles = self.role.get_identity_roles(&identity.id(), true).await?;
+
+            Ok((identity, roles, session))
+        }
+        .await;
+        self.count_operation("register_guest", &result);
+        result
+    }
+
+    /// Attach the credentials to a guest identity. The id, the roles and the sessions of the guest are preserved.
+    pub async fn promote_guest(
+        &self,
+        user_id: &str,
+        name: ValidatedName,
+        email: Option<ValidatedEmail>,
+        password: ValidatedPassword,
+    ) -> Result<(UserIdentity, InheritedRoles), IAMError> {
+        self.check_password_policy(&password).await?;
+        let identity = self.identity.promote_guest(user_id, name, email, &password).await?;
         let roles = self.role.get_identity_roles(&identity.id(), true).await?;
 
-        Ok((identity, roles, session))
+        if let Err(err) = self.send_email_verification(&identity).await {
+            log::warn!("Failed to send email verification for {}: {:?}", identity.id(), err);
+        }
+
+        Ok((identity, roles))
+    }
+
+    async fn send_email_verification(&self, identity: &UserIdentity) -> Result<(), IAMError> {
+        if let Some(verification) = self.identity.create_email_verification(identity).await? {
+            let email = identity.core().email.as_ref().map(|e| e.to_raw()).unwrap_or_default();
+            let link = format!("{}{}", self.email_verification_url, verification.token());
+            self.send_mail(
+                "mail_verify_email.txt",
+                identity,
+                &link,
+                email,
+                "Verify your email address",
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Render a mail template with the name of the user and the action link and send it
+    async fn send_mail(
+        &self,
+        template: &str,
+        identity: &UserIdentity,
+        link: &str,
+        to: String,
+        subject: &str,
+    ) -> Result<(), IAMError> {
+        let mut context = Context::new();
+        context.insert("name", &identity.core().name.to_raw());
+        context.insert("link", link);
+        let mail = Mail::from_template(&self.mail_templates, template, &context, to, subject.to_owned())?;
+        self.mailer.send(&mail).await?;
+        Ok(())
+    }
+
+    pub async fn resend_email_verification(&self, identity_id: &str) -> Result<(), IAMError> {
+        let identity = self.identity.find_user_by_id(identity_id).await?;
+        self.send_email_verification(&identity).await
+    }
+
+    pub async fn verify_email(&self, token: &str) -> Result<UserIdentity, IAMError> {
+        self.identity.verify_email(token).await
+    }
+
+    /// Set a new password and invalidate all the other sessions of the user.
+    pub async fn change_password(
+        &self,
+        user_id: &str,
+        session_key: &str,
+        current_password: &ValidatedPassword,
+        new_password: &ValidatedPassword,
+    ) -> Result<(), IAMError> {
+        self.check_password_policy(new_password).await?;
+        let identity = self
+            .identity
+            .change_password(user_id, current_password, new_password)
+            .await?;
+        self.session.invalidate_other_sessions(identity.id(), session_key).await
+    }
+
+    /// Send a confirmation mail to the new email. The email is changed when the link of the mail is visited.
+    pub async fn request_email_change(
+        &self,
+        user_id: &str,
+        password: &ValidatedPassword,
+        email: &ValidatedEmail,
+    ) -> Result<(), IAMError> {
+        let (identity, verification) = self.identity.create_email_change(user_id, password, email).await?;
+        let link = format!("{}{}", self.email_change_url, verification.token());
+        self.send_mail(
+            "mail_change_email.txt",
+            &identity,
+            &link,
+            email.to_raw(),
+            "Confirm your new email address",
+        )
+        .await
+    }
+
+    pub async fn confirm_email_change(&self, token: &str) -> Result<UserIdentity, IAMError> {
+        self.identity.confirm_email_change(token).await
+    }
+
+    /// Send a password reset mail to the owner of the email. To avoid leaking the registered emails
+    /// no error is reported if the email is not found.
+    pub async fn request_password_reset(&self, email: &ValidatedEmail) -> Result<(), IAMError> {
+        let identity = match self.identity.find_user_by_email(email, None).await {
+            Ok(identity) => identity,
+            Err(IAMError::IdentityNotFound) => {
+                log::info!("Password reset requested for unknown email: {}", email.to_raw());
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        };
+
+        let reset = self.identity.create_password_reset(&identity).await?;
+        let link = format!("{}{}", self.password_reset_url, reset.token());
+        self.send_mail(
+            "mail_password_reset.txt",
+            &identity,
+            &link,
+            email.to_raw(),
+            "Password reset",
+        )
+        .await
+    }
+
+    /// Set a new password and invalidate all the sessions of the user.
+    pub async fn reset_password(&self, token: &str, password: &ValidatedPassword) -> Result<(), IAMError> {
+        let result = async {
+            self.check_password_policy(password).await?;
+            let identity = self.identity.reset_password(token, password).await?;
+            self.session.invalidate_all_session(identity.id(), None).await?;
+            self.notify(
+                identity.id(),
+                AccountEvent::ForcedLogout {
+                    reason: LogoutReason::PasswordReset,
+                    session_id: None,
+                },
+            );
+            Ok(())
+        }
+        .await;
+        self.count_operation("reset_password", &result);
+        result
     }
 
     pub async fn login_by_name(
@@ -101,11 +683,22 @@ impl IAM {
         password: &ValidatedPassword,
         fingerprint: &Fingerprint,
     ) -> Result<(UserIdentity, InheritedRoles, Session), IAMError> {
-        let identity = self.identity.find_user_by_name(name, Some(password)).await?;
-        let session = self.session.create_session(&identity, fingerprint).await?;
-        let roles = self.role.get_identity_roles(&identity.id(), true).await?;
+        let result = async {
+            let identity = self.identity.find_user_by_name(name, Some(password)).await?;
+            self.identity.check_banned(&identity)?;
+            let identity = self.identity.cancel_deletion(identity).await?;
+            let known_device = self.is_known_device(identity.id(), fingerprint).await?;
+            let session = self.session.create_session(&identity, fingerprint).await?;
+            let roles = self.role.get_identity_roles(&identity.id(), true).await?;
+            if !known_device {
+                self.notify(identity.id(), AccountEvent::new_device(fingerprint));
+            }
 
-        Ok((identity, roles, session))
+            Ok((identity, roles, session))
+        }
+        .await;
+        self.count_operation("login", &result);
+        result
     }
 
     pub async fn login_by_email(
@@ -114,11 +707,22 @@ impl IAM {
         password: &ValidatedPassword,
         fingerprint: &Fingerprint,
     ) -> Result<(UserIdentity, InheritedRoles, Session), IAMError> {
-        let identity = self.identity.find_user_by_email(email, Some(password)).await?;
-        let session = self.session.create_session(&identity, fingerprint).await?;
-        let roles = self.role.get_identity_roles(&identity.id(), true).await?;
+        let result = async {
+            let identity = self.identity.find_user_by_email(email, Some(password)).await?;
+            self.identity.check_banned(&identity)?;
+            let identity = self.identity.cancel_deletion(identity).await?;
+            let known_device = self.is_known_device(identity.id(), fingerprint).await?;
+            let session = self.session.create_session(&identity, fingerprint).await?;
+            let roles = self.role.get_identity_roles(&identity.id(), true).await?;
+            if !known_device {
+                self.notify(identity.id(), AccountEvent::new_device(fingerprint));
+            }
 
-        Ok((identity, roles, session))
+            Ok((identity, roles, session))
+        }
+        .await;
+        self.count_operation("login", &result);
+        result
     }
 
     pub async fn validate_session(
@@ -127,14 +731,19 @@ impl IAM {
         session_key: &str,
         fingerprint: &Fingerprint,
     ) -> Result<(UserIdentity, InheritedRoles), IAMError> {
-        let _session = self
-            .session
-            .validate_session_with_id_key(user_id, session_key, fingerprint)
-            .await?;
-        let identity = self.identity.find_user_by_id(user_id).await?;
-        let roles = self.role.get_identity_roles(&identity.id(), true).await?;
+        let result = async {
+            let _session = self
+                .session
+                .validate_session_with_id_key(user_id, session_key, fingerprint)
+                .await?;
+            let identity = self.identity.find_user_by_id(user_id).await?;
+            let roles = self.role.get_identity_roles(&identity.id(), true).await?;
 
-        Ok((identity, roles))
+            Ok((identity, roles))
+        }
+        .await;
+        self.count_operation("validate_session", &result);
+        result
     }
 
     pub async fn refresh_session(
@@ -143,14 +752,19 @@ impl IAM {
         session_key: &str,
         fingerprint: &Fingerprint,
     ) -> Result<(UserIdentity, InheritedRoles, Session), IAMError> {
-        let session = self
-            .session
-            .refresh_session_with_id_key(user_id, session_key, fingerprint)
-            .await?;
-        let identity = self.identity.find_user_by_id(user_id).await?;
-        let roles = self.role.get_identity_roles(&identity.id(), true).await?;
+        let result = async {
+            let session = self
+                .session
+                .refresh_session_with_id_key(user_id, session_key, fingerprint)
+                .await?;
+            let identity = self.identity.find_user_by_id(user_id).await?;
+            let roles = self.role.get_identity_roles(&identity.id(), true).await?;
 
-        Ok((identity, roles, session))
+            Ok((identity, roles, session))
+        }
+        .await;
+        self.count_operation("refresh_session", &result);
+        result
     }
 
     pub async fn refresh_session_by_key(
@@ -158,11 +772,16 @@ impl IAM {
         session_key: &str,
         fingerprint: &Fingerprint,
     ) -> Result<(UserIdentity, InheritedRoles, Session), IAMError> {
-        let (user_id, session) = self.session.refresh_session_with_key(session_key, fingerprint).await?;
-        let identity = self.identity.find_user_by_id(&user_id).await?;
-        let roles = self.role.get_identity_roles(&identity.id(), true).await?;
+        let result = async {
+            let (user_id, session) = self.session.refresh_session_with_key(session_key, fingerprint).await?;
+            let identity = self.identity.find_user_by_id(&user_id).await?;
+            let roles = self.role.get_identity_roles(&identity.id(), true).await?;
 
-        Ok((identity, roles, session))
+            Ok((identity, roles, session))
+        }
+        .await;
+        self.count_operation("refresh_session", &result);
+        result
     }
 
     pub async fn invalidate_session(
@@ -172,12 +791,156 @@ impl IAM {
         invalidate_all: bool,
     ) -> Result<(), IAMError> {
         if invalidate_all {
-            self.session.invalidate_all_session(user_id, Some(session_key)).await
+            self.session.invalidate_all_session(user_id, Some(session_key)).await?;
+            self.notify(
+                user_id,
+                AccountEvent::ForcedLogout {
+                    reason: LogoutReason::SignedOutEverywhere,
+                    session_id: None,
+                },
+            );
+            Ok(())
         } else {
             self.session.invalidate_session(user_id, session_key).await
         }
     }
 
+    pub async fn get_sessions(&self, user_id: &str, current_key: &str) -> Result<Vec<SessionInfo>, IAMError> {
+        let sessions = self.session.get_active_sessions(user_id).await?;
+        Ok(sessions
+            .iter()
+            .map(|session| SessionInfo::from_session(session, current_key))
+            .collect())
+    }
+
+    /// Revoke a session of the user by the public id of the session, ex. to sign out a lost device.
+    pub async fn revoke_session(&self, user_id: &str, session_id: &str) -> Result<(), IAMError> {
+        if !is_valid_token(session_id) {
+            log::info!("Invalid session id: {}", session_id);
+            return Err(IAMError::BadRequest("Invalid session id".to_owned()));
+        }
+
+        let sessions = self.session.get_active_sessions(user_id).await?;
+        let session = sessions
+            .iter()
+            .find(|session| session::session_id(session.key()) == session_id)
+            .ok_or_else(|| IAMError::BadRequest("Unknown session".to_owned()))?;
+        self.session.invalidate_session(user_id, session.key()).await?;
+        self.notify(
+            user_id,
+            AccountEvent::ForcedLogout {
+                reason: LogoutReason::SessionRevoked,
+                session_id: Some(session_id.to_owned()),
+            },
+        );
+        Ok(())
+    }
+
+    /// Collect all the data stored about an identity
+    pub async fn export_identity(&self, user_id: &str, current_key: &str) -> Result<IdentityExport, IAMError> {
+        let identity = self.identity.find_user_by_id(user_id).await?;
+        let sessions = self.get_sessions(user_id, current_key).await?;
+        let roles = self.role.get_identity_roles(user_id, true).await?;
+
+        Ok(IdentityExport {
+            exported: Utc::now(),
+            identity: IdentityExportInfo::from(&identity),
+            sessions: sessions.into_iter().map(SessionExportInfo::from).collect(),
+            roles,
+        })
+    }
+
+    /// Schedule the deletion of an identity and sign out from all the sessions. The identity is purged after
+    /// the grace period unless the user signs in again. The password is required except for the guests.
+    /// Returns the date of the deletion.
+    pub async fn request_identity_deletion(
+        &self,
+        user_id: &str,
+        password: Option<&ValidatedPassword>,
+    ) -> Result<DateTime<Utc>, IAMError> {
+        let identity = self.identity.schedule_deletion(user_id, password).await?;
+        self.session.invalidate_all_session(user_id, None).await?;
+        self.notify(
+            user_id,
+            AccountEvent::ForcedLogout {
+                reason: LogoutReason::DeletionRequested,
+                session_id: None,
+            },
+        );
+        identity
+            .core()
+            .deletion_scheduled
+            .ok_or_else(|| IAMError::Internal(format!("Deletion of {} was not scheduled", user_id)))
+    }
+
+    /// Delete an identity permanently with all the related data.
+    /// The identity is removed last, thus on failure the purge can be repeated.
+    async fn delete_identity(&self, user_id: &str) -> Result<(), IAMError> {
+        self.session.delete_all_sessions(user_id).await?;
+        self.role.delete_identity(user_id).await?;
+        self.identity.delete_user(user_id).await
+    }
+
+    /// Delete the identities where the grace period of the deletion is over. Returns the number of the
+    /// deleted identities.
+    pub async fn purge_deleted_identities(&self) -> Result<usize, IAMError> {
+        let ids = self.identity.find_identities_to_delete().await?;
+        let mut count = 0;
+        for id in ids {
+            match self.delete_identity(&id).await {
+                Ok(()) => count += 1,
+                Err(err) => log::warn!("Failed to purge identity {}: {:?}", id, err),
+            }
+        }
+        log::info!("Purged {} identities", count);
+        Ok(count)
+    }
+
+    /// Search the users by the prefix of the name or email, an empty name prefix lists all the users.
+    pub async fn search_identities(
+        &self,
+        search: &IdentitySearch,
+        continuation: Option<&str>,
+        limit: usize,
+    ) -> Result<IdentitySearchResult, IAMError> {
+        let page = self.identity.search_users(search, continuation, limit).await?;
+        Ok(IdentitySearchResult {
+            identities: page.identities.iter().map(IdentityExportInfo::from).collect(),
+            continuation: page.continuation,
+        })
+    }
+
+    pub async fn get_identity_detail(&self, user_id: &str) -> Result<IdentityDetail, IAMError> {
+        let identity = self.identity.find_user_by_id(user_id).await?;
+        let roles = self.role.get_identity_roles(user_id, false).await?;
+        let active_sessions = self.session.get_active_sessions(user_id).await?.len();
+
+        Ok(IdentityDetail {
+            identity: IdentityExportInfo::from(&identity),
+            roles,
+            active_sessions,
+        })
+    }
+
+    /// Ban an identity and sign out from all the sessions. Banned identities cannot sign in until unbanned.
+    pub async fn ban_identity(&self, user_id: &str) -> Result<IdentityExportInfo, IAMError> {
+        let identity = self.identity.set_banned(user_id, true).await?;
+        self.session.invalidate_all_session(user_id, None).await?;
+        self.notify(
+            user_id,
+            AccountEvent::ForcedLogout {
+                reason: LogoutReason::Banned,
+                session_id: None,
+            },
+        );
+        Ok(IdentityExportInfo::from(&identity))
+    }
+
+    pub async fn unban_identity(&self, user_id: &str) -> Result<IdentityExportInfo, IAMError> {
+        let identity = self.identity.set_banned(user_id, false).await?;
+        Ok(IdentityExportInfo::from(&identity))
+    }
+
     pub async fn create_role(&self, role: &str) -> Result<(), IAMError> {
         self.role.create_role(role).await
     }
@@ -198,9 +961,25 @@ impl IAM {
         self.role.disherit_role(role, inherited_role).await
     }
 
+    pub async fn get_effective_roles(&self, role: &str) -> Result<EffectiveRoles, IAMError> {
+        self.role.get_effective_roles(role).await
+    }
+
+    pub async fn diff_effective_roles(&self, role: &str, other: &str) -> Result<EffectiveRolesDiff, IAMError> {
+        self.role.diff_effective_roles(role, other).await
+    }
+
     pub async fn add_identity_role(&self, identity_id: &str, role: &str) -> Result<InheritedRoles, IAMError> {
         let _ = self.identity.find_core_identity_by_id(identity_id).await?;
-        self.role.add_identity_role(identity_id, role).await
+        let roles = self.role.add_identity_role(identity_id, role).await?;
+        self.notify(
+            identity_id,
+            AccountEvent::RoleChanged {
+                role: role.to_owned(),
+                granted: true,
+            },
+        );
+        Ok(roles)
     }
 
     pub async fn get_identity_roles(
@@ -214,41 +993,380 @@ impl IAM {
 
     pub async fn remove_identity_role(&self, identity_id: &str, role: &str) -> Result<InheritedRoles, IAMError> {
         let _ = self.identity.find_core_identity_by_id(identity_id).await?;
-        self.role.remove_identity_role(identity_id, role).await
+        let roles = self.role.remove_identity_role(identity_id, role).await?;
+        self.notify(
+            identity_id,
+            AccountEvent::RoleChanged {
+                role: role.to_owned(),
+                granted: false,
+            },
+        );
+        Ok(roles)
+    }
+
+    /// Create an api key scoped to the given roles. The returned string is the secret key, it is
+    /// not stored and cannot be queried later.
+    pub async fn create_api_key(
+        &self,
+        owner_id: &str,
+        name: &str,
+        roles: &[String],
+    ) -> Result<(ApiKeyInfo, String), IAMError> {
+        let existing_roles = self.role.get_roles().await?;
+        if let Some(role) = roles.iter().find(|r| !existing_roles.contains(r)) {
+            log::info!("Api key with unknown role requested: {}", role);
+            return Err(IAMError::RoleNotFound);
+        }
+
+        let (key, secret) = self.apikey.create_key(name, owner_id, roles).await?;
+        Ok((ApiKeyInfo::from(&key), secret))
+    }
+
+    pub async fn get_api_keys(&self) -> Result<Vec<ApiKeyInfo>, IAMError> {
+        let keys = self.apikey.list_keys().await?;
+        Ok(keys.iter().map(ApiKeyInfo::from).collect())
+    }
+
+    pub async fn revoke_api_key(&self, key_id: &str) -> Result<(), IAMError> {
+        self.apikey.revoke_key(key_id).await
+    }
+
+    pub async fn validate_api_key(&self, auth: &ApiKeyAuth) -> Result<ApiKeyIdentity, IAMError> {
+        let result = async {
+            let key = self.apikey.validate_key(auth).await?;
+            Ok(key.to_identity())
+        }
+        .await;
+        self.count_operation("validate_api_key", &result);
+        result
+    }
+
+    /// Grant a premium content to an identity
+    pub async fn grant_entitlement(
+        &self,
+        identity_id: &str,
+        content_id: &str,
+        granted_by: &str,
+    ) -> Result<EntitlementInfo, IAMError> {
+        let _ = self.identity.find_core_identity_by_id(identity_id).await?;
+        let entitlement = self.entitlement.grant(identity_id, content_id, granted_by).await?;
+        Ok(EntitlementInfo::from(&entitlement))
+    }
+
+    pub async fn revoke_entitlement(&self, identity_id: &str, content_id: &str) -> Result<(), IAMError> {
+        self.entitlement.revoke(identity_id, content_id).await
+    }
+
+    pub async fn get_entitlements(&self, identity_id: &str) -> Result<Vec<EntitlementInfo>, IAMError> {
+        let entitlements = self.entitlement.list(identity_id).await?;
+        Ok(entitlements.iter().map(EntitlementInfo::from).collect())
+    }
+
+    /// Issue a signed token of the active entitlements, the clients verify it before mounting premium content.
+    /// The expiration (seconds since the unix epoch) is returned along the token.
+    pub async fn issue_entitlement_token(&self, identity_id: &str) -> Result<(String, i64), IAMError> {
+        let result = async {
+            let (token, claims) = self.entitlement.issue_token(identity_id).await?;
+            Ok((token, claims.exp))
+        }
+        .await;
+        self.count_operation("issue_entitlement_token", &result);
+        result
+    }
+
+    /// Public key of the entitlement tokens, BASE64 encoded
+    pub fn entitlement_public_key(&self) -> Option<String> {
+        self.entitlement.public_key()
     }
 
-    pub async fn check_permission_by_testing_token(&self, testing_token: Option<&str>) -> Result<(), IAMError> {
-        if let Some(tt) = testing_token {
-            if self.test_token == tt {
-                Ok(())
-            } else {
-                Err(IAMError::InsufficientPermission)
+    /// Register an OAuth client. The scopes are the roles the client may request on behalf of the users.
+    /// For confidential clients the secret is also returned, it is not stored and cannot be queried later.
+    pub async fn register_oauth_client(
+        &self,
+        owner_id: &str,
+        name: &str,
+        public: bool,
+        redirect_uris: &[String],
+        scopes: &[String],
+    ) -> Result<(OAuthClientInfo, Option<String>), IAMError> {
+        let existing_roles = self.role.get_roles().await?;
+        if let Some(role) = scopes.iter().find(|r| !existing_roles.contains(r)) {
+            log::info!("OAuth client with unknown scope requested: {}", role);
+            return Err(IAMError::RoleNotFound);
+        }
+
+        let (client, secret) = self
+            .oauth
+            .register_client(name, owner_id, public, redirect_uris, scopes)
+            .await?;
+        Ok((OAuthClientInfo::from(&client), secret))
+    }
+
+    pub async fn get_oauth_clients(&self) -> Result<Vec<OAuthClientInfo>, IAMError> {
+        let clients = self.oauth.list_clients().await?;
+        Ok(clients.iter().map(OAuthClientInfo::from).collect())
+    }
+
+    pub async fn revoke_oauth_client(&self, client_id: &str) -> Result<(), IAMError> {
+        self.oauth.revoke_client(client_id).await
+    }
+
+    /// Authorize a client on behalf of the user, returns the authorization code. Only the scopes
+    /// (roles) granted to the user can be delegated to the client.
+    pub async fn authorize_oauth_client(
+        &self,
+        user_id: &str,
+        client_id: &str,
+        redirect_uri: &str,
+        scopes: &[String],
+        code_challenge: Option<&str>,
+    ) -> Result<String, IAMError> {
+        let identity = self.identity.find_core_identity_by_id(user_id).await?;
+        self.identity.check_banned(&identity)?;
+        let roles = self.role.get_identity_roles(user_id, true).await?;
+        let roles = HashSet::from_iter(roles.into_iter().map(|r| r.role));
+        let grant = self
+            .oauth
+            .authorize(client_id, redirect_uri, scopes, user_id, &roles, code_challenge)
+            .await?;
+        Ok(grant.code().to_owned())
+    }
+
+    /// Exchange an authorization code for an access token, returns the token and its identity
+    pub async fn exchange_oauth_code(
+        &self,
+        client_id: &str,
+        client_secret: Option<&str>,
+        code: &str,
+        redirect_uri: &str,
+        code_verifier: Option<&str>,
+    ) -> Result<(String, TokenIdentity), IAMError> {
+        let result = async {
+            let token = self
+                .oauth
+                .exchange_code(client_id, client_secret, code, redirect_uri, code_verifier)
+                .await?;
+            let data = token.data();
+            let identity = TokenIdentity {
+                client_id: data.client_id.clone(),
+                user_id: data.identity_id.clone(),
+                scopes: token.scopes(),
+                expires: data.expires,
+            };
+            Ok((token.token().to_owned(), identity))
+        }
+        .await;
+        self.count_operation("oauth_token", &result);
+        result
+    }
+
+    /// Find the identity of an active access token. The tokens of the banned, deleted and to be deleted
+    /// identities are not active.
+    pub async fn introspect_oauth_token(&self, token: &str) -> Result<Option<TokenIdentity>, IAMError> {
+        let token_identity = match self.oauth.introspect(token).await? {
+            Some(token_identity) => token_identity,
+            None => return Ok(None),
+        };
+
+        let identity = match self.identity.find_core_identity_by_id(&token_identity.user_id).await {
+            Ok(identity) => identity,
+            Err(IAMError::IdentityNotFound) => {
+                log::info!("OAuth token of a missing identity {}", token_identity.user_id);
+                return Ok(None);
             }
-        } else {
+            Err(err) => return Err(err),
+        };
+        let core = identity.core();
+        if core.banned.is_some() || core.deletion_scheduled.is_some() {
+            log::info!("OAuth token of an inactive identity {}", token_identity.user_id);
+            return Ok(None);
+        }
+
+        Ok(Some(token_identity))
+    }
+
+    pub async fn revoke_oauth_token(&self, token: &str) -> Result<(), IAMError> {
+        self.oauth.revoke_token(token).await
+    }
+
+    /// Mint an automation token for the given scopes. The lifetime is capped by the configuration, the
+    /// tokens granting all the permissions and the scopes not granted to the issuer are not issued.
+    pub async fn issue_automation_token(
+        &self,
+        issuer_id: &str,
+        scopes: &[String],
+        time_to_live: Option<chrono::Duration>,
+    ) -> Result<(String, AutomationClaims), IAMError> {
+        let signer = self
+            .automation_signer
+            .as_ref()
+            .ok_or_else(|| IAMError::Internal("Automation signing key is not configured".to_owned()))?;
+        if scopes.is_empty()
+            || scopes
+                .iter()
+                .any(|scope| scope.is_empty() || scope == AUTOMATION_SCOPE_ALL)
+        {
+            return Err(IAMError::BadRequest("Invalid automation token scopes".to_owned()));
+        }
+        let time_to_live = time_to_live
+            .unwrap_or(self.automation_token_max_time_to_live)
+            .min(self.automation_token_max_time_to_live);
+        if time_to_live <= chrono::Duration::zero() {
+            return Err(IAMError::BadRequest("Invalid automation token lifetime".to_owned()));
+        }
+
+        // the token cannot grant more than the effective permissions of the issuer
+        let roles = self.role.get_identity_roles(issuer_id, true).await?;
+        let roles = HashSet::<String>::from_iter(roles.into_iter().map(|r| r.role));
+        let permissions = self.role.get_permissions_of_roles(&roles).await?;
+        if let Some(scope) = scopes
+            .iter()
+            .find(|scope| !role::is_permission_granted(&permissions, scope))
+        {
+            log::info!(
+                "Automation token scope {} is not granted to the issuer {}",
+                scope,
+                issuer_id
+            );
+            return Err(IAMError::InsufficientPermission);
+        }
+
+        let (token, claims) = signer.mint(issuer_id, scopes, time_to_live);
+        log::info!(
+            "Automation token {} issued by {} for {:?} until {}",
+            claims.jti,
+            issuer_id,
+            claims.scopes,
+            claims.expires_at()
+        );
+        Ok((token, claims))
+    }
+
+    /// Check if the automation token is valid and grants the required permission.
+    pub async fn check_permission_by_automation_token(&self, token: &str, permission: &str) -> Result<(), IAMError> {
+        let signer = match &self.automation_signer {
+            Some(signer) => signer,
+            None => {
+                log::warn!("Automation token rejected, automation tokens are disabled");
+                return Err(IAMError::InsufficientPermission);
+            }
+        };
+
+        let claims = signer.verify(token, Utc::now()).map_err(|err| {
+            log::info!("Automation token rejected: {}", err);
+            IAMError::InsufficientPermission
+        })?;
+        if claims.has_scope(permission) {
+            log::info!("Permission {} granted by automation token {}", permission, claims.jti);
             Ok(())
+        } else {
+            log::info!(
+                "Permission {} is not granted by automation token {} ({:?})",
+                permission,
+                claims.jti,
+                claims.scopes
+            );
+            Err(IAMError::InsufficientPermission)
         }
     }
 
+    pub async fn add_role_permission(&self, role: &str, permission: &str) -> Result<(), IAMError> {
+        self.role.add_role_permission(role, permission).await
+    }
+
+    pub async fn remove_role_permission(&self, role: &str, permission: &str) -> Result<(), IAMError> {
+        self.role.remove_role_permission(role, permission).await
+    }
+
+    pub async fn get_role_permissions(&self, role: &str, include_inherited: bool) -> Result<Permissions, IAMError> {
+        self.role.get_role_permissions(role, include_inherited).await
+    }
+
+    /// Check if the roles grant the required permission. If an automation token is present, the permission
+    /// is checked against the scopes of the token instead of the roles.
     pub async fn check_permission_by_roles(
         &self,
         roles: Option<&HashSet<String>>,
-        testing_token: Option<&str>,
+        permission: &str,
+        automation_token: Option<&str>,
     ) -> Result<(), IAMError> {
-        self.check_permission_by_testing_token(testing_token).await?;
+        if let Some(token) = automation_token {
+            return self.check_permission_by_automation_token(token, permission).await;
+        }
 
-        let _roles = roles.ok_or(IAMError::SessionRequired)?;
-        //todo: check permissions, role
-        Ok(())
+        let roles = roles.ok_or(IAMError::SessionRequired)?;
+        let permissions = self.role.get_permissions_of_roles(roles).await?;
+        if role::is_permission_granted(&permissions, permission) {
+            Ok(())
+        } else {
+            log::info!("Permission {} is not granted by roles {:?}", permission, roles);
+            Err(IAMError::InsufficientPermission)
+        }
+    }
+
+    /// Check if the identity has a verified email address and its roles grant the required permission. The
+    /// automation tokens are not bound to an identity, they are checked by the scopes only.
+    pub async fn check_permission_by_verified_identity(
+        &self,
+        identity_id: Option<&str>,
+        permission: &str,
+        automation_token: Option<&str>,
+    ) -> Result<(), IAMError> {
+        if let Some(token) = automation_token {
+            return self.check_permission_by_automation_token(token, permission).await;
+        }
+
+        let identity_id = identity_id.ok_or(IAMError::SessionRequired)?;
+        let identity = self.identity.find_core_identity_by_id(identity_id).await?;
+        if !identity.email_verified() {
+            return Err(IAMError::EmailNotVerified);
+        }
+        self.check_permission_by_identity(Some(identity_id), permission, automation_token)
+            .await
     }
 
     pub async fn check_permission_by_identity(
         &self,
         identity_id: Option<&str>,
-        testing_token: Option<&str>,
+        permission: &str,
+        automation_token: Option<&str>,
     ) -> Result<(), IAMError> {
         let identity_id = identity_id.ok_or(IAMError::SessionRequired)?;
         let roles = self.role.get_identity_roles(&identity_id, true).await?;
         let roles = HashSet::from_iter(roles.into_iter().map(|r| r.role));
-        self.check_permission_by_roles(Some(&roles), testing_token).await
+        self.check_permission_by_roles(Some(&roles), permission, automation_token)
+            .await
+    }
+}
+
+impl ApiKeyValidator for IAM {
+    fn validate<'s>(
+        &'s self,
+        auth: &'s ApiKeyAuth,
+    ) -> Pin<Box<dyn Future<Output = Result<ApiKeyIdentity, RequestInfoError>> + 's>> {
+        Box::pin(async move {
+            self.validate_api_key(auth).await.map_err(|err| {
+                log::info!("Api key {} rejected: {:?}", auth.key_id(), err);
+                RequestInfoError::ApiKeyRejected
+            })
+        })
+    }
+}
+
+impl TokenValidator for IAM {
+    fn validate<'s>(
+        &'s self,
+        auth: &'s BearerAuth,
+    ) -> Pin<Box<dyn Future<Output = Result<TokenIdentity, RequestInfoError>> + 's>> {
+        Box::pin(async move {
+            match self.introspect_oauth_token(auth.token()).await {
+                Ok(Some(identity)) => Ok(identity),
+                Ok(None) => Err(RequestInfoError::TokenRejected),
+                Err(err) => {
+                    log::info!("Access token rejected: {:?}", err);
+                    Err(RequestInfoError::TokenRejected)
+                }
+            }
+        })
     }
 }
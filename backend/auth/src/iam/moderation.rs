@@ -0,0 +1,18 @@
+use crate::iam::{role::InheritedRoles, IdentityExportInfo};
+use serde::Serialize;
+
+/// A page of the user search
+#[derive(Debug, Serialize)]
+pub struct IdentitySearchResult {
+    pub identities: Vec<IdentityExportInfo>,
+    /// Pass it to the next search to get the next page, None if there are no more users
+    pub continuation: Option<String>,
+}
+
+/// The details of an identity for the moderators
+#[derive(Debug, Serialize)]
+pub struct IdentityDetail {
+    pub identity: IdentityExportInfo,
+    pub roles: InheritedRoles,
+    pub active_sessions: usize,
+}
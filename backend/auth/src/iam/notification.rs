@@ -0,0 +1,89 @@
+use crate::iam::{
+    fingerprint::Fingerprint,
+    session::{self, Session},
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub enum LogoutReason {
+    Banned,
+    DeletionRequested,
+    PasswordReset,
+    /// The user signed out from all the devices
+    SignedOutEverywhere,
+    SessionRevoked,
+}
+
+/// Notification of the changes of an account streamed to the sessions of the identity
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AccountEvent {
+    /// Sign in from a device (agent and country) without an active session
+    #[serde(rename_all = "camelCase")]
+    NewDevice {
+        agent: String,
+        remote_ip: String,
+        remote_country: String,
+        time: DateTime<Utc>,
+    },
+    #[serde(rename_all = "camelCase")]
+    RoleChanged { role: String, granted: bool },
+    /// Sessions were invalidated, None if all the sessions of the identity are affected
+    #[serde(rename_all = "camelCase")]
+    ForcedLogout {
+        reason: LogoutReason,
+        /// The public id of the session, the session keys are never sent
+        session_id: Option<String>,
+    },
+}
+
+impl AccountEvent {
+    pub(crate) fn new_device(fingerprint: &Fingerprint) -> AccountEvent {
+        AccountEvent::NewDevice {
+            agent: fingerprint.agent().to_owned(),
+            remote_ip: fingerprint
+                .remote()
+                .map(|ip| ip.to_string())
+                .unwrap_or_else(|| "unknown".to_owned()),
+            remote_country: fingerprint_country(fingerprint),
+            time: Utc::now(),
+        }
+    }
+
+    /// Name of the event in the event stream
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            AccountEvent::NewDevice { .. } => "newDevice",
+            AccountEvent::RoleChanged { .. } => "roleChanged",
+            AccountEvent::ForcedLogout { .. } => "forcedLogout",
+        }
+    }
+
+    /// Check if the session with the given key was invalidated by the event
+    pub fn ends_session(&self, key: &str) -> bool {
+        match self {
+            AccountEvent::ForcedLogout { session_id, .. } => session_id
+                .as_ref()
+                .map(|id| *id == session::session_id(key))
+                .unwrap_or(true),
+            _ => false,
+        }
+    }
+}
+
+fn fingerprint_country(fingerprint: &Fingerprint) -> String {
+    fingerprint
+        .location()
+        .map(|l| l.country.to_owned())
+        .unwrap_or_else(|| "unknown".to_owned())
+}
+
+/// A device is known if there is an active session with the same agent from the same country
+pub(crate) fn is_known_device(sessions: &[Session], fingerprint: &Fingerprint) -> bool {
+    let country = fingerprint_country(fingerprint);
+    sessions.iter().any(|session| {
+        let data = session.data();
+        data.agent() == fingerprint.agent() && data.remote_country() == country
+    })
+}
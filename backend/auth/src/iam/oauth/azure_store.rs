@@ -0,0 +1,146 @@
+use crate::iam::{
+    identity::StoreFuture,
+    oauth::{AccessToken, AccessTokenData, OAuthClient, OAuthClientData, OAuthGrant, OAuthGrantData, OAuthStore},
+    IAMConfig, IAMError,
+};
+use azure_sdk_storage_table::{CloudTable, TableClient, TableEntity};
+use shine_core::azure_utils::{
+    self,
+    table_storage::{self, EmptyData},
+};
+
+fn empty_entity<D>(entity: &TableEntity<D>) -> TableEntity<EmptyData> {
+    TableEntity {
+        partition_key: entity.partition_key.clone(),
+        row_key: entity.row_key.clone(),
+        etag: None,
+        timestamp: None,
+        payload: EmptyData {},
+    }
+}
+
+/// OAuth store using Azure table storage
+#[derive(Clone)]
+pub struct AzureOAuthStore {
+    client_db: CloudTable,
+    grant_db: CloudTable,
+    token_db: CloudTable,
+}
+
+impl AzureOAuthStore {
+    pub async fn new(config: &IAMConfig) -> Result<Self, IAMError> {
+        let client = TableClient::new(&config.storage_account, &config.storage_account_key)?;
+        let client_db = CloudTable::new(client.clone(), "oauthclients");
+        client_db.create_if_not_exists().await?;
+        let grant_db = CloudTable::new(client.clone(), "oauthgrants");
+        grant_db.create_if_not_exists().await?;
+        let token_db = CloudTable::new(client, "oauthtokens");
+        token_db.create_if_not_exists().await?;
+
+        Ok(AzureOAuthStore {
+            client_db,
+            grant_db,
+            token_db,
+        })
+    }
+
+    async fn take_grant_impl(&self, code: &str) -> Result<Option<OAuthGrant>, IAMError> {
+        let (p, r) = OAuthGrant::entity_keys(code);
+        match self.grant_db.get::<OAuthGrantData>(&p, &r, None).await? {
+            Some(grant) => {
+                // a code can be exchanged only once, if the delete fails someone else has taken it
+                match self.grant_db.delete_entity(empty_entity(&grant)).await {
+                    Ok(_) => Ok(Some(OAuthGrant::from_entity(grant))),
+                    Err(err) => {
+                        log::info!("Failed to take grant ([{}]/[{}]): {}", p, r, err);
+                        Ok(None)
+                    }
+                }
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl OAuthStore for AzureOAuthStore {
+    fn insert_client(&self, client: OAuthClient) -> StoreFuture<'_, OAuthClient> {
+        Box::pin(async move {
+            match self.client_db.insert_entity(client.into_entity()).await {
+                Ok(client) => Ok(OAuthClient::from_entity(client)),
+                Err(err) if azure_utils::is_precodition_error(&err) => Err(IAMError::OAuthClientConflict),
+                Err(err) => Err(err.into()),
+            }
+        })
+    }
+
+    fn update_client(&self, client: OAuthClient) -> StoreFuture<'_, OAuthClient> {
+        Box::pin(async move {
+            let client = self.client_db.update_entity(client.into_entity()).await?;
+            Ok(OAuthClient::from_entity(client))
+        })
+    }
+
+    fn find_client<'a>(&'a self, client_id: &'a str) -> StoreFuture<'a, Option<OAuthClient>> {
+        Box::pin(async move {
+            let (p, r) = OAuthClient::entity_keys(client_id);
+            let client = self.client_db.get::<OAuthClientData>(&p, &r, None).await?;
+            Ok(client.map(OAuthClient::from_entity))
+        })
+    }
+
+    fn list_clients(&self) -> StoreFuture<'_, Vec<OAuthClient>> {
+        Box::pin(async move {
+            let clients = table_storage::query_all::<OAuthClientData>(&self.client_db, None).await?;
+            Ok(clients.into_iter().map(OAuthClient::from_entity).collect())
+        })
+    }
+
+    fn insert_grant(&self, grant: OAuthGrant) -> StoreFuture<'_, OAuthGrant> {
+        Box::pin(async move {
+            match self.grant_db.insert_entity(grant.into_entity()).await {
+                Ok(grant) => Ok(OAuthGrant::from_entity(grant)),
+                Err(err) if azure_utils::is_precodition_error(&err) => Err(IAMError::OAuthTokenConflict),
+                Err(err) => Err(err.into()),
+            }
+        })
+    }
+
+    fn take_grant<'a>(&'a self, code: &'a str) -> StoreFuture<'a, Option<OAuthGrant>> {
+        Box::pin(self.take_grant_impl(code))
+    }
+
+    fn insert_token(&self, token: AccessToken) -> StoreFuture<'_, AccessToken> {
+        Box::pin(async move {
+            match self.token_db.insert_entity(token.into_entity()).await {
+                Ok(token) => Ok(AccessToken::from_entity(token)),
+                Err(err) if azure_utils::is_precodition_error(&err) => Err(IAMError::OAuthTokenConflict),
+                Err(err) => Err(err.into()),
+            }
+        })
+    }
+
+    fn find_token<'a>(&'a self, token: &'a str) -> StoreFuture<'a, Option<AccessToken>> {
+        Box::pin(async move {
+            let (p, r) = AccessToken::entity_keys(token);
+            let token = self.token_db.get::<AccessTokenData>(&p, &r, None).await?;
+            Ok(token.map(AccessToken::from_entity))
+        })
+    }
+
+    fn delete_token<'a>(&'a self, token: &'a str) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            let (partition_key, row_key) = AccessToken::entity_keys(token);
+            let key = TableEntity {
+                partition_key,
+                row_key,
+                etag: None,
+                timestamp: None,
+                payload: EmptyData {},
+            };
+            self.token_db.delete_entity(key).await.unwrap_or_else(|e| {
+                log::info!("Failed to delete access token: {}", e);
+            });
+            Ok(())
+        })
+    }
+}
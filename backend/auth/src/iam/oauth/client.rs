@@ -0,0 +1,146 @@
+use azure_sdk_storage_table::TableEntity;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shine_core::serde_with;
+
+/// Data associated to an OAuth client
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct OAuthClientData {
+    pub name: String,
+    pub owner_id: String,
+    /// Hash of the client secret, empty for the public clients (ex. games) that have to use PKCE
+    pub secret_hash: String,
+    /// Space separated list of the allowed redirect uris
+    pub redirect_uris: String,
+    /// Comma separated list of the scopes (roles) the client may request
+    pub scopes: String,
+
+    #[serde(with = "serde_with::datetime")]
+    pub issued: DateTime<Utc>,
+
+    #[serde(with = "serde_with::opt_datetime")]
+    pub revoked: Option<DateTime<Utc>>,
+}
+
+/// A registered OAuth client, indexed by the public client id. Only the hash of the secret is stored.
+#[derive(Debug)]
+pub struct OAuthClient(TableEntity<OAuthClientData>);
+
+impl OAuthClient {
+    pub fn entity_keys(client_id: &str) -> (String, String) {
+        (format!("client-{}", &client_id[0..2]), client_id.to_owned())
+    }
+
+    pub fn new(
+        client_id: &str,
+        name: &str,
+        owner_id: &str,
+        secret_hash: String,
+        redirect_uris: &[String],
+        scopes: &[String],
+    ) -> Self {
+        let (partition_key, row_key) = Self::entity_keys(client_id);
+        Self(TableEntity {
+            partition_key,
+            row_key,
+            etag: None,
+            timestamp: None,
+            payload: OAuthClientData {
+                name: name.to_owned(),
+                owner_id: owner_id.to_owned(),
+                secret_hash,
+                redirect_uris: redirect_uris.join(" "),
+                scopes: scopes.join(","),
+                issued: Utc::now(),
+                revoked: None,
+            },
+        })
+    }
+
+    pub fn from_entity(entity: TableEntity<OAuthClientData>) -> Self {
+        Self(entity)
+    }
+
+    pub fn into_entity(self) -> TableEntity<OAuthClientData> {
+        self.0
+    }
+
+    pub fn data(&self) -> &OAuthClientData {
+        &self.0.payload
+    }
+
+    pub fn client_id(&self) -> &str {
+        &self.0.row_key
+    }
+
+    /// Public clients cannot keep a secret, they have to prove the ownership of a grant with PKCE
+    pub fn is_public(&self) -> bool {
+        self.0.payload.secret_hash.is_empty()
+    }
+
+    pub fn redirect_uris(&self) -> Vec<String> {
+        self.0
+            .payload
+            .redirect_uris
+            .split(' ')
+            .filter(|r| !r.is_empty())
+            .map(|r| r.to_owned())
+            .collect()
+    }
+
+    /// Check if the redirect uri is registered, the uris are compared by exact match
+    pub fn has_redirect_uri(&self, uri: &str) -> bool {
+        self.0.payload.redirect_uris.split(' ').any(|r| r == uri)
+    }
+
+    pub fn scopes(&self) -> Vec<String> {
+        self.0
+            .payload
+            .scopes
+            .split(',')
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_owned())
+            .collect()
+    }
+
+    pub fn is_revoked(&self) -> bool {
+        self.0.payload.revoked.is_some()
+    }
+
+    pub fn revoke(&mut self) {
+        let data = &mut self.0.payload;
+        if data.revoked.is_none() {
+            data.revoked = Some(Utc::now());
+        }
+    }
+}
+
+/// Public information of an OAuth client
+#[derive(Debug, Serialize)]
+pub struct OAuthClientInfo {
+    pub client_id: String,
+    pub name: String,
+    pub owner_id: String,
+    pub public: bool,
+    pub redirect_uris: Vec<String>,
+    pub scopes: Vec<String>,
+    pub issued: DateTime<Utc>,
+    pub revoked: Option<DateTime<Utc>>,
+}
+
+impl From<&OAuthClient> for OAuthClientInfo {
+    fn from(client: &OAuthClient) -> OAuthClientInfo {
+        let data = client.data();
+        OAuthClientInfo {
+            client_id: client.client_id().to_owned(),
+            name: data.name.clone(),
+            owner_id: data.owner_id.clone(),
+            public: client.is_public(),
+            redirect_uris: client.redirect_uris(),
+            scopes: client.scopes(),
+            issued: data.issued,
+            revoked: data.revoked,
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use azure_sdk_storage_table::TableEntity;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shine_core::serde_with;
+
+fn split_scopes(scopes: &str) -> Vec<String> {
+    scopes
+        .split(',')
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_owned())
+        .collect()
+}
+
+/// Data associated to an authorization code
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct OAuthGrantData {
+    pub client_id: String,
+    pub identity_id: String,
+    pub redirect_uri: String,
+    /// Comma separated list of the granted scopes
+    pub scopes: String,
+    /// PKCE (S256) code challenge, empty if not used
+    pub code_challenge: String,
+
+    #[serde(with = "serde_with::datetime")]
+    pub issued: DateTime<Utc>,
+}
+
+/// Authorization code issued to a client, indexed by the (secret) code. The code can be exchanged for
+/// an access token only once.
+#[derive(Debug)]
+pub struct OAuthGrant(TableEntity<OAuthGrantData>);
+
+impl OAuthGrant {
+    pub fn entity_keys(code: &str) -> (String, String) {
+        (format!("grant-{}", &code[0..2]), code.to_owned())
+    }
+
+    pub fn new(
+        code: &str,
+        client_id: &str,
+        identity_id: &str,
+        redirect_uri: &str,
+        scopes: &[String],
+        code_challenge: Option<&str>,
+    ) -> Self {
+        let (partition_key, row_key) = Self::entity_keys(code);
+        Self(TableEntity {
+            partition_key,
+            row_key,
+            etag: None,
+            timestamp: None,
+            payload: OAuthGrantData {
+                client_id: client_id.to_owned(),
+                identity_id: identity_id.to_owned(),
+                redirect_uri: redirect_uri.to_owned(),
+                scopes: scopes.join(","),
+                code_challenge: code_challenge.unwrap_or_default().to_owned(),
+                issued: Utc::now(),
+            },
+        })
+    }
+
+    pub fn from_entity(entity: TableEntity<OAuthGrantData>) -> Self {
+        Self(entity)
+    }
+
+    pub fn into_entity(self) -> TableEntity<OAuthGrantData> {
+        self.0
+    }
+
+    pub fn data(&self) -> &OAuthGrantData {
+        &self.0.payload
+    }
+
+    pub fn code(&self) -> &str {
+        &self.0.row_key
+    }
+
+    pub fn scopes(&self) -> Vec<String> {
+        split_scopes(&self.0.payload.scopes)
+    }
+}
+
+/// Data associated to an access token
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AccessTokenData {
+    pub client_id: String,
+    pub identity_id: String,
+    /// Comma separated list of the granted scopes
+    pub scopes: String,
+
+    #[serde(with = "serde_with::datetime")]
+    pub issued: DateTime<Utc>,
+
+    #[serde(with = "serde_with::datetime")]
+    pub expires: DateTime<Utc>,
+}
+
+/// Bearer access token, indexed by the (secret) token
+#[derive(Debug)]
+pub struct AccessToken(TableEntity<AccessTokenData>);
+
+impl AccessToken {
+    pub fn entity_keys(token: &str) -> (String, String) {
+        (format!("token-{}", &token[0..2]), token.to_owned())
+    }
+
+    pub fn new(token: &str, grant: &OAuthGrant, expires: DateTime<Utc>) -> Self {
+        let (partition_key, row_key) = Self::entity_keys(token);
+        let grant = grant.data();
+        Self(TableEntity {
+            partition_key,
+            row_key,
+            etag: None,
+            timestamp: None,
+            payload: AccessTokenData {
+                client_id: grant.client_id.clone(),
+                identity_id: grant.identity_id.clone(),
+                scopes: grant.scopes.clone(),
+                issued: Utc::now(),
+                expires,
+            },
+        })
+    }
+
+    pub fn from_entity(entity: TableEntity<AccessTokenData>) -> Self {
+        Self(entity)
+    }
+
+    pub fn into_entity(self) -> TableEntity<AccessTokenData> {
+        self.0
+    }
+
+    pub fn data(&self) -> &AccessTokenData {
+        &self.0.payload
+    }
+
+    pub fn token(&self) -> &str {
+        &self.0.row_key
+    }
+
+    pub fn scopes(&self) -> Vec<String> {
+        split_scopes(&self.0.payload.scopes)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.0.payload.expires < Utc::now()
+    }
+}
@@ -0,0 +1,306 @@
+use crate::iam::{
+    identity::is_valid_token,
+    oauth::{AccessToken, AzureOAuthStore, MemoryOAuthStore, OAuthClient, OAuthGrant, OAuthStore, OAuthStoreConfig},
+    IAMConfig, IAMError,
+};
+use argon2;
+use chrono::{Duration as ChronoDuration, Utc};
+use data_encoding;
+use rand::{self, seq::SliceRandom, Rng};
+use sha2::{Digest, Sha256};
+use shine_core::{
+    backoff::{self, Backoff, BackoffError},
+    requestinfo::TokenIdentity,
+};
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+const CLIENT_ID_LEN: usize = 16;
+const CLIENT_ID_ABC: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+const SECRET_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+const SECRET_BASE_ENCODE: data_encoding::Encoding = data_encoding::BASE64URL_NOPAD;
+const CODE_TIME_TO_LIVE_M: i64 = 10;
+
+/// Manage the OAuth clients and the authorization code flow of the authorization server
+#[derive(Clone)]
+pub struct OAuthManager {
+    store: Arc<dyn OAuthStore>,
+    token_time_to_live: ChronoDuration,
+}
+
+impl OAuthManager {
+    pub async fn new(config: &IAMConfig) -> Result<Self, IAMError> {
+        let store: Arc<dyn OAuthStore> = match &config.oauth_store {
+            OAuthStoreConfig::Azure => Arc::new(AzureOAuthStore::new(config).await?),
+            OAuthStoreConfig::Memory => Arc::new(MemoryOAuthStore::new()),
+        };
+        log::info!("OAuth store: {:?}", config.oauth_store);
+
+        Ok(OAuthManager {
+            store,
+            token_time_to_live: ChronoDuration::minutes(config.oauth_token_time_to_live_m as i64),
+        })
+    }
+
+    fn generate_client_id(&self) -> String {
+        let mut rng = rand::thread_rng();
+        String::from_utf8(
+            (0..CLIENT_ID_LEN)
+                .map(|_| *CLIENT_ID_ABC.choose(&mut rng).unwrap())
+                .collect::<Vec<_>>(),
+        )
+        .unwrap()
+    }
+
+    fn generate_secret(&self) -> String {
+        let mut secret = [0u8; SECRET_LEN];
+        rand::thread_rng().fill(&mut secret[..]);
+        SECRET_BASE_ENCODE.encode(&secret)
+    }
+
+    fn hash_secret(secret: &str) -> Result<String, IAMError> {
+        let mut salt = [0u8; SALT_LEN];
+        rand::thread_rng().fill(&mut salt[..]);
+        let config = argon2::Config::default();
+        argon2::hash_encoded(secret.as_bytes(), &salt, &config)
+            .map_err(|err| IAMError::Internal(format!("Argon2 client secret hashing failed: {}", err)))
+    }
+
+    /// Check the PKCE verifier against the S256 challenge
+    fn verify_code_challenge(challenge: &str, verifier: &str) -> bool {
+        let digest = Sha256::digest(verifier.as_bytes());
+        SECRET_BASE_ENCODE.encode(&digest) == challenge
+    }
+
+    async fn try_register_client(
+        &self,
+        name: &str,
+        owner_id: &str,
+        public: bool,
+        redirect_uris: &[String],
+        scopes: &[String],
+    ) -> Result<(OAuthClient, Option<String>), BackoffError<IAMError>> {
+        let client_id = self.generate_client_id();
+        let (secret, secret_hash) = if public {
+            (None, String::new())
+        } else {
+            let secret = self.generate_secret();
+            let secret_hash = Self::hash_secret(&secret).map_err(IAMError::into_backoff)?;
+            (Some(secret), secret_hash)
+        };
+
+        let client = OAuthClient::new(&client_id, name, owner_id, secret_hash, redirect_uris, scopes);
+        let client = self.store.insert_client(client).await.map_err(IAMError::into_backoff)?;
+        Ok((client, secret))
+    }
+
+    /// Register a new client. For confidential clients the secret is returned, it cannot be queried later.
+    pub async fn register_client(
+        &self,
+        name: &str,
+        owner_id: &str,
+        public: bool,
+        redirect_uris: &[String],
+        scopes: &[String],
+    ) -> Result<(OAuthClient, Option<String>), IAMError> {
+        if redirect_uris.is_empty() {
+            return Err(IAMError::BadRequest("Missing redirect uri".to_owned()));
+        }
+        if let Some(uri) = redirect_uris
+            .iter()
+            .find(|uri| uri.contains(char::is_whitespace) || !uri.contains("://"))
+        {
+            return Err(IAMError::BadRequest(format!("Invalid redirect uri: {}", uri)));
+        }
+
+        let (client, secret) = backoff::Exponential::new(3, Duration::from_micros(10))
+            .async_execute(|_| self.try_register_client(name, owner_id, public, redirect_uris, scopes))
+            .await?;
+        log::info!("OAuth client {} registered by {}", client.client_id(), owner_id);
+        Ok((client, secret))
+    }
+
+    async fn find_client(&self, client_id: &str) -> Result<OAuthClient, IAMError> {
+        if !is_valid_token(client_id) {
+            return Err(IAMError::OAuthClientNotFound);
+        }
+        self.store
+            .find_client(client_id)
+            .await?
+            .ok_or(IAMError::OAuthClientNotFound)
+    }
+
+    /// Find a client that is not revoked
+    async fn find_active_client(&self, client_id: &str) -> Result<OAuthClient, IAMError> {
+        match self.find_client(client_id).await {
+            Ok(client) if !client.is_revoked() => Ok(client),
+            Ok(_) | Err(IAMError::OAuthClientNotFound) => Err(IAMError::OAuthClientInvalid),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub async fn list_clients(&self) -> Result<Vec<OAuthClient>, IAMError> {
+        self.store.list_clients().await
+    }
+
+    /// Revoke a client, the issued tokens are rejected by the introspection
+    pub async fn revoke_client(&self, client_id: &str) -> Result<(), IAMError> {
+        let mut client = self.find_client(client_id).await?;
+        if !client.is_revoked() {
+            client.revoke();
+            self.store.update_client(client).await?;
+            log::info!("OAuth client {} revoked", client_id);
+        }
+        Ok(())
+    }
+
+    async fn try_create_grant(
+        &self,
+        client: &OAuthClient,
+        identity_id: &str,
+        redirect_uri: &str,
+        scopes: &[String],
+        code_challenge: Option<&str>,
+    ) -> Result<OAuthGrant, BackoffError<IAMError>> {
+        let code = self.generate_secret();
+        let grant = OAuthGrant::new(
+            &code,
+            client.client_id(),
+            identity_id,
+            redirect_uri,
+            scopes,
+            code_challenge,
+        );
+        self.store.insert_grant(grant).await.map_err(IAMError::into_backoff)
+    }
+
+    /// Issue an authorization code for the user. The granted scopes are the requested scopes allowed both
+    /// for the client and the user, the empty request grants all of them.
+    pub async fn authorize(
+        &self,
+        client_id: &str,
+        redirect_uri: &str,
+        requested_scopes: &[String],
+        identity_id: &str,
+        identity_roles: &HashSet<String>,
+        code_challenge: Option<&str>,
+    ) -> Result<OAuthGrant, IAMError> {
+        let client = self.find_active_client(client_id).await?;
+        if !client.has_redirect_uri(redirect_uri) {
+            log::info!(
+                "OAuth client {} used unregistered redirect uri: {}",
+                client_id,
+                redirect_uri
+            );
+            return Err(IAMError::OAuthClientInvalid);
+        }
+        if client.is_public() && code_challenge.is_none() {
+            return Err(IAMError::BadRequest("Public clients must use PKCE".to_owned()));
+        }
+
+        let scopes: Vec<String> = client
+            .scopes()
+            .into_iter()
+            .filter(|scope| identity_roles.contains(scope))
+            .filter(|scope| requested_scopes.is_empty() || requested_scopes.contains(scope))
+            .collect();
+
+        let grant = backoff::Exponential::new(3, Duration::from_micros(10))
+            .async_execute(|_| self.try_create_grant(&client, identity_id, redirect_uri, &scopes, code_challenge))
+            .await?;
+        log::info!(
+            "OAuth client {} authorized by {} for {:?}",
+            client_id,
+            identity_id,
+            scopes
+        );
+        Ok(grant)
+    }
+
+    async fn try_create_token(&self, grant: &OAuthGrant) -> Result<AccessToken, BackoffError<IAMError>> {
+        let token = self.generate_secret();
+        let token = AccessToken::new(&token, grant, Utc::now() + self.token_time_to_live);
+        self.store.insert_token(token).await.map_err(IAMError::into_backoff)
+    }
+
+    /// Exchange an authorization code for an access token. Confidential clients are authenticated by the
+    /// secret, if the grant was created with a code challenge the verifier is also required.
+    pub async fn exchange_code(
+        &self,
+        client_id: &str,
+        client_secret: Option<&str>,
+        code: &str,
+        redirect_uri: &str,
+        code_verifier: Option<&str>,
+    ) -> Result<AccessToken, IAMError> {
+        let client = self.find_active_client(client_id).await?;
+        if !client.is_public() {
+            let secret = client_secret.ok_or(IAMError::OAuthClientInvalid)?;
+            if !argon2::verify_encoded(&client.data().secret_hash, secret.as_bytes())
+                .map_err(|err| IAMError::Internal(format!("Argon2 client secret validation failed: {}", err)))?
+            {
+                return Err(IAMError::OAuthClientInvalid);
+            }
+        }
+
+        if !is_valid_token(code) {
+            return Err(IAMError::OAuthGrantInvalid);
+        }
+        // the code is consumed even if the exchange fails
+        let grant = self.store.take_grant(code).await?.ok_or(IAMError::OAuthGrantInvalid)?;
+        let data = grant.data();
+        if data.client_id != client_id || data.redirect_uri != redirect_uri {
+            log::info!("OAuth code of {} used by {}", data.client_id, client_id);
+            return Err(IAMError::OAuthGrantInvalid);
+        }
+        if data.issued + ChronoDuration::minutes(CODE_TIME_TO_LIVE_M) < Utc::now() {
+            return Err(IAMError::OAuthGrantInvalid);
+        }
+        if !data.code_challenge.is_empty() {
+            let verifier = code_verifier.ok_or(IAMError::OAuthGrantInvalid)?;
+            if !Self::verify_code_challenge(&data.code_challenge, verifier) {
+                return Err(IAMError::OAuthGrantInvalid);
+            }
+        }
+
+        let token = backoff::Exponential::new(3, Duration::from_micros(10))
+            .async_execute(|_| self.try_create_token(&grant))
+            .await?;
+        Ok(token)
+    }
+
+    /// Find an active token. Expired tokens and the tokens of the revoked clients are rejected.
+    pub async fn introspect(&self, token: &str) -> Result<Option<TokenIdentity>, IAMError> {
+        if !is_valid_token(token) {
+            return Ok(None);
+        }
+        let token = match self.store.find_token(token).await? {
+            Some(token) => token,
+            None => return Ok(None),
+        };
+        if token.is_expired() {
+            self.store.delete_token(token.token()).await?;
+            return Ok(None);
+        }
+        let data = token.data();
+        match self.find_active_client(&data.client_id).await {
+            Ok(_) => {}
+            Err(IAMError::OAuthClientInvalid) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+
+        Ok(Some(TokenIdentity {
+            client_id: data.client_id.clone(),
+            user_id: data.identity_id.clone(),
+            scopes: token.scopes(),
+            expires: data.expires,
+        }))
+    }
+
+    pub async fn revoke_token(&self, token: &str) -> Result<(), IAMError> {
+        if !is_valid_token(token) {
+            return Ok(());
+        }
+        self.store.delete_token(token).await
+    }
+}
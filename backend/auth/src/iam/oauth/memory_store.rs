@@ -0,0 +1,140 @@
+use crate::iam::{
+    identity::StoreFuture,
+    oauth::{AccessToken, AccessTokenData, OAuthClient, OAuthClientData, OAuthGrant, OAuthGrantData, OAuthStore},
+    IAMError,
+};
+use azure_sdk_storage_table::TableEntity;
+use std::{collections::HashMap, sync::Mutex};
+
+fn into_entity<D>(keys: (String, String), payload: D) -> TableEntity<D> {
+    let (partition_key, row_key) = keys;
+    TableEntity {
+        partition_key,
+        row_key,
+        etag: None,
+        timestamp: None,
+        payload,
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    clients: HashMap<String, OAuthClientData>,
+    grants: HashMap<String, OAuthGrantData>,
+    tokens: HashMap<String, AccessTokenData>,
+}
+
+/// OAuth store keeping the clients and tokens in the memory of the process, for local development and tests.
+/// The expired tokens are not removed, they are only rejected by the manager.
+#[derive(Default)]
+pub struct MemoryOAuthStore {
+    inner: Mutex<Inner>,
+}
+
+impl MemoryOAuthStore {
+    pub fn new() -> Self {
+        MemoryOAuthStore::default()
+    }
+
+    fn with_inner<T, F>(&self, f: F) -> StoreFuture<'_, T>
+    where
+        T: 'static,
+        F: FnOnce(&mut Inner) -> Result<T, IAMError>,
+    {
+        let result = {
+            let mut inner = self.inner.lock().unwrap();
+            f(&mut inner)
+        };
+        Box::pin(async move { result })
+    }
+}
+
+impl OAuthStore for MemoryOAuthStore {
+    fn insert_client(&self, client: OAuthClient) -> StoreFuture<'_, OAuthClient> {
+        self.with_inner(move |inner| {
+            if inner.clients.contains_key(client.client_id()) {
+                return Err(IAMError::OAuthClientConflict);
+            }
+            inner
+                .clients
+                .insert(client.client_id().to_owned(), client.data().clone());
+            Ok(client)
+        })
+    }
+
+    fn update_client(&self, client: OAuthClient) -> StoreFuture<'_, OAuthClient> {
+        self.with_inner(move |inner| match inner.clients.get_mut(client.client_id()) {
+            Some(data) => {
+                *data = client.data().clone();
+                Ok(client)
+            }
+            None => Err(IAMError::OAuthClientNotFound),
+        })
+    }
+
+    fn find_client<'a>(&'a self, client_id: &'a str) -> StoreFuture<'a, Option<OAuthClient>> {
+        self.with_inner(|inner| {
+            Ok(inner
+                .clients
+                .get(client_id)
+                .map(|data| OAuthClient::from_entity(into_entity(OAuthClient::entity_keys(client_id), data.clone()))))
+        })
+    }
+
+    fn list_clients(&self) -> StoreFuture<'_, Vec<OAuthClient>> {
+        self.with_inner(|inner| {
+            Ok(inner
+                .clients
+                .iter()
+                .map(|(client_id, data)| {
+                    OAuthClient::from_entity(into_entity(OAuthClient::entity_keys(client_id), data.clone()))
+                })
+                .collect())
+        })
+    }
+
+    fn insert_grant(&self, grant: OAuthGrant) -> StoreFuture<'_, OAuthGrant> {
+        self.with_inner(move |inner| {
+            if inner.grants.contains_key(grant.code()) {
+                return Err(IAMError::OAuthTokenConflict);
+            }
+            inner.grants.insert(grant.code().to_owned(), grant.data().clone());
+            Ok(grant)
+        })
+    }
+
+    fn take_grant<'a>(&'a self, code: &'a str) -> StoreFuture<'a, Option<OAuthGrant>> {
+        self.with_inner(|inner| {
+            Ok(inner
+                .grants
+                .remove(code)
+                .map(|data| OAuthGrant::from_entity(into_entity(OAuthGrant::entity_keys(code), data))))
+        })
+    }
+
+    fn insert_token(&self, token: AccessToken) -> StoreFuture<'_, AccessToken> {
+        self.with_inner(move |inner| {
+            if inner.tokens.contains_key(token.token()) {
+                return Err(IAMError::OAuthTokenConflict);
+            }
+            inner.tokens.insert(token.token().to_owned(), token.data().clone());
+            Ok(token)
+        })
+    }
+
+    fn find_token<'a>(&'a self, token: &'a str) -> StoreFuture<'a, Option<AccessToken>> {
+        self.with_inner(|inner| {
+            Ok(inner
+                .tokens
+                .get(token)
+                .map(|data| AccessToken::from_entity(into_entity(AccessToken::entity_keys(token), data.clone()))))
+        })
+    }
+
+    fn delete_token<'a>(&'a self, token: &'a str) -> StoreFuture<'a, ()> {
+        self.with_inner(|inner| {
+            inner.tokens.remove(token);
+            Ok(())
+        })
+    }
+}
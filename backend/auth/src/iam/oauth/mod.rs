@@ -0,0 +1,13 @@
+mod azure_store;
+mod client;
+mod grant;
+mod manager;
+mod memory_store;
+mod store;
+
+pub use self::azure_store::*;
+pub use self::client::*;
+pub use self::grant::*;
+pub use self::manager::*;
+pub use self::memory_store::*;
+pub use self::store::*;
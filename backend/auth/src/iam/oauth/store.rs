@@ -0,0 +1,48 @@
+use crate::iam::{
+    identity::StoreFuture,
+    oauth::{AccessToken, OAuthClient, OAuthGrant},
+};
+use serde::{Deserialize, Serialize};
+
+/// Storage backend of the OAuth clients and tokens
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum OAuthStoreConfig {
+    /// Azure table storage using the storage account of the IAMConfig
+    Azure,
+    /// Keep the clients and tokens in memory, for local development and tests
+    Memory,
+}
+
+impl Default for OAuthStoreConfig {
+    fn default() -> Self {
+        OAuthStoreConfig::Azure
+    }
+}
+
+/// Persistence of the OAuth clients, authorization codes and access tokens.
+/// Secret and token generation is implemented by the OAuthManager.
+pub trait OAuthStore {
+    /// Insert a new client, returns OAuthClientConflict if the client id is already in use
+    fn insert_client(&self, client: OAuthClient) -> StoreFuture<'_, OAuthClient>;
+
+    /// Update an existing client
+    fn update_client(&self, client: OAuthClient) -> StoreFuture<'_, OAuthClient>;
+
+    fn find_client<'a>(&'a self, client_id: &'a str) -> StoreFuture<'a, Option<OAuthClient>>;
+
+    fn list_clients(&self) -> StoreFuture<'_, Vec<OAuthClient>>;
+
+    /// Insert a new authorization code, returns OAuthTokenConflict if the code is already in use
+    fn insert_grant(&self, grant: OAuthGrant) -> StoreFuture<'_, OAuthGrant>;
+
+    /// Find and remove an authorization code
+    fn take_grant<'a>(&'a self, code: &'a str) -> StoreFuture<'a, Option<OAuthGrant>>;
+
+    /// Insert a new access token, returns OAuthTokenConflict if the token is already in use
+    fn insert_token(&self, token: AccessToken) -> StoreFuture<'_, AccessToken>;
+
+    fn find_token<'a>(&'a self, token: &'a str) -> StoreFuture<'a, Option<AccessToken>>;
+
+    fn delete_token<'a>(&'a self, token: &'a str) -> StoreFuture<'a, ()>;
+}
@@ -0,0 +1,219 @@
+use crate::iam::{role::InheritedRoles, IAMError};
+use futures::StreamExt;
+use redis::{aio::ConnectionManager, Client};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Configuration of the in-memory cache of the role graph
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RoleCacheConfig {
+    /// Seconds the resolved roles of an identity are kept, 0 disables the cache
+    #[serde(default = "RoleCacheConfig::default_time_to_live_s")]
+    pub time_to_live_s: u64,
+    /// Redis used to broadcast the invalidations between the instances, local only if not set
+    #[serde(default)]
+    pub redis_url: Option<String>,
+    #[serde(default = "RoleCacheConfig::default_channel")]
+    pub channel: String,
+}
+
+impl RoleCacheConfig {
+    fn default_time_to_live_s() -> u64 {
+        60
+    }
+
+    fn default_channel() -> String {
+        "role_cache".to_owned()
+    }
+}
+
+impl Default for RoleCacheConfig {
+    fn default() -> Self {
+        RoleCacheConfig {
+            time_to_live_s: RoleCacheConfig::default_time_to_live_s(),
+            redis_url: None,
+            channel: RoleCacheConfig::default_channel(),
+        }
+    }
+}
+
+/// Invalidation message shared between the instances
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum Invalidation {
+    Identity { id: String },
+    All,
+}
+
+struct Entry {
+    created: Instant,
+    roles: InheritedRoles,
+}
+
+struct Inner {
+    entries: HashMap<String, Entry>,
+    /// Incremented on each invalidation to drop the results of the queries started before it
+    generation: u64,
+}
+
+/// Cache of the roles of the identities including the inherited roles. The entries expire
+/// after the time to live and they are invalidated explicitly when the role graph is modified.
+#[derive(Clone)]
+pub struct RoleCache {
+    inner: Arc<Mutex<Inner>>,
+    time_to_live: Duration,
+    publisher: Option<(ConnectionManager, String)>,
+}
+
+impl RoleCache {
+    pub async fn new(config: &RoleCacheConfig) -> Result<RoleCache, IAMError> {
+        let mut cache = RoleCache {
+            inner: Arc::new(Mutex::new(Inner {
+                entries: HashMap::new(),
+                generation: 0,
+            })),
+            time_to_live: Duration::from_secs(config.time_to_live_s),
+            publisher: None,
+        };
+
+        if let Some(url) = &config.redis_url {
+            let client = Client::open(url.as_str())?;
+            let connection = ConnectionManager::new(client.clone()).await?;
+            cache.publisher = Some((connection, config.channel.clone()));
+            actix_rt::spawn(cache.clone().listen(client, config.channel.clone()));
+        }
+
+        Ok(cache)
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.time_to_live.as_secs() > 0
+    }
+
+    fn with_inner<F: FnOnce(&mut Inner) -> R, R>(&self, f: F) -> R {
+        let mut inner = self.inner.lock().unwrap();
+        f(&mut *inner)
+    }
+
+    /// Return the cached roles of an identity and the generation to be used when the missing
+    /// entry is inserted.
+    pub fn get(&self, identity_id: &str) -> (Option<InheritedRoles>, u64) {
+        if !self.is_enabled() {
+            return (None, 0);
+        }
+        let time_to_live = self.time_to_live;
+        self.with_inner(|inner| {
+            let roles = match inner.entries.get(identity_id) {
+                Some(entry) if entry.created.elapsed() < time_to_live => Some(entry.roles.clone()),
+                Some(_) => {
+                    inner.entries.remove(identity_id);
+                    None
+                }
+                None => None,
+            };
+            (roles, inner.generation)
+        })
+    }
+
+    /// Store the roles unless the cache was invalidated since the generation was queried
+    pub fn insert(&self, identity_id: &str, roles: &InheritedRoles, generation: u64) {
+        if !self.is_enabled() {
+            return;
+        }
+        let time_to_live = self.time_to_live;
+        self.with_inner(|inner| {
+            if inner.generation != generation {
+                return;
+            }
+            inner.entries.retain(|_, entry| entry.created.elapsed() < time_to_live);
+            inner.entries.insert(
+                identity_id.to_owned(),
+                Entry {
+                    created: Instant::now(),
+                    roles: roles.clone(),
+                },
+            );
+        });
+    }
+
+    fn apply(&self, invalidation: &Invalidation) {
+        self.with_inner(|inner| {
+            inner.generation += 1;
+            match invalidation {
+                Invalidation::Identity { id } => {
+                    inner.entries.remove(id);
+                }
+                Invalidation::All => inner.entries.clear(),
+            }
+        });
+    }
+
+    async fn publish(&self, invalidation: Invalidation) {
+        self.apply(&invalidation);
+
+        if let Some((connection, channel)) = &self.publisher {
+            let message = match serde_json::to_string(&invalidation) {
+                Ok(message) => message,
+                Err(err) => {
+                    log::error!("Failed to serialize role cache invalidation: {}", err);
+                    return;
+                }
+            };
+            let mut con = connection.clone();
+            let published: Result<usize, _> = redis::cmd("PUBLISH")
+                .arg(channel)
+                .arg(message)
+                .query_async(&mut con)
+                .await;
+            if let Err(err) = published {
+                log::warn!("Failed to publish role cache invalidation: {}", err);
+            }
+        }
+    }
+
+    /// Drop the roles of an identity on all the instances
+    pub async fn invalidate_identity(&self, identity_id: &str) {
+        self.publish(Invalidation::Identity {
+            id: identity_id.to_owned(),
+        })
+        .await
+    }
+
+    /// Drop all the cached roles on all the instances, used when the role graph itself is modified
+    pub async fn invalidate_all(&self) {
+        self.publish(Invalidation::All).await
+    }
+
+    async fn subscribe(&self, client: &Client, channel: &str) -> Result<(), IAMError> {
+        let mut pubsub = client.get_async_connection().await?.into_pubsub();
+        pubsub.subscribe(channel).await?;
+        log::info!("Subscribed to the role cache invalidations on {}", channel);
+        // the invalidations published while the subscription was lost are unknown
+        self.apply(&Invalidation::All);
+
+        let mut messages = pubsub.on_message();
+        while let Some(message) = messages.next().await {
+            let payload: String = message.get_payload()?;
+            match serde_json::from_str::<Invalidation>(&payload) {
+                Ok(invalidation) => self.apply(&invalidation),
+                Err(err) => log::warn!("Invalid role cache invalidation ({}): {}", payload, err),
+            }
+        }
+        Ok(())
+    }
+
+    /// Keep listening to the invalidations of the other instances, reconnecting on failure
+    async fn listen(self, client: Client, channel: String) {
+        loop {
+            if let Err(err) = self.subscribe(&client, &channel).await {
+                log::warn!("Role cache invalidation channel failed: {:?}", err);
+            }
+            self.apply(&Invalidation::All);
+            actix_rt::time::delay_for(Duration::from_secs(5)).await;
+        }
+    }
+}
@@ -0,0 +1,357 @@
+use crate::iam::{
+    identity::StoreFuture,
+    role::{Permissions, RoleStore, Roles},
+    IAMConfig, IAMError,
+};
+use gremlin_client::{aio::GremlinClient, ConnectionOptions, GraphSON, GremlinError};
+use shine_core::gremlin_utils::{query_value, query_vec};
+
+/// Role store using a Gremlin graph database. The roles and the permissions are the vertices and the
+/// inheritance and grants are the edges of the graph.
+#[derive(Clone)]
+pub struct GremlinRoleStore {
+    db: GremlinClient,
+}
+
+impl GremlinRoleStore {
+    pub async fn new(config: &IAMConfig) -> Result<Self, IAMError> {
+        let connection = ConnectionOptions::builder()
+            .host(&config.graph_db_host)
+            .port(config.graph_db_port)
+            .ssl(true)
+            .credentials(&config.graph_db_user, &config.graph_db_password)
+            .serializer(GraphSON::V2)
+            .build();
+        let db = GremlinClient::connect(connection).await?;
+
+        Ok(GremlinRoleStore { db })
+    }
+
+    async fn create_role_impl(&self, role: &str) -> Result<(), IAMError> {
+        let response = query_value::<String>(
+            &self.db,
+            r#"g.v().has('role','name',role).fold()
+                .coalesce(
+                    // if role already present return 'conflict'
+                    unfold().constant('conflict'),
+
+                    // create new role, return 'done'
+                    addV('role').property('name',role).constant('done')
+                )
+            "#,
+            &[("role", &role)],
+        )
+        .await?;
+
+        match response.as_str() {
+            "conflict" => Err(IAMError::RoleTaken),
+            "done" => Ok(()),
+            r => Err(GremlinError::Generic(format!("Unexpected query response: {}", r)).into()),
+        }
+    }
+
+    async fn get_roles_impl(&self) -> Result<Roles, IAMError> {
+        Ok(query_vec::<String>(
+            &self.db,
+            r#"
+                g.v().hasLabel('role').values('name');
+            "#,
+            &[],
+        )
+        .await?)
+    }
+
+    async fn delete_role_impl(&self, role: &str) -> Result<(), IAMError> {
+        let response = query_value::<String>(
+            &self.db,
+            r#"
+                g.V().has('role','name', role)
+                    .sideEffect(drop()).fold()
+                    .coalesce(
+                        unfold().constant('done'),
+                        constant('missing')
+                    )
+            "#,
+            &[("role", &role)],
+        )
+        .await?;
+
+        match response.as_str() {
+            "missing" => Err(IAMError::RoleNotFound),
+            "done" => Ok(()),
+            r => Err(GremlinError::Generic(format!("Unexpected query response: {}", r)).into()),
+        }
+    }
+
+    async fn inherit_role_impl(&self, role: &str, inherited_role: &str) -> Result<(), IAMError> {
+        let response = query_vec::<String>(
+            &self.db,
+            r#"
+                g.v().has('role','name',inherited_role)
+                .coalesce(
+                    // if the new edge creates a cycle, return the path 
+                    __.repeat(out('has_role').dedup()).until(has('role','name',role))
+                        .path().by('name').limit(1).unfold(),
+                        
+                    // if edge is already present, return 'conflict'
+                    __.in('has_role').has('role','name',role).constant('conflict'),
+
+                    // create the new edge, return 'done' 
+                    __.addE('has_role').from(v().has('role','name',role)).constant('done')
+                )
+            "#,
+            &[("role", &role), ("inherited_role", &inherited_role)],
+        )
+        .await?;
+
+        match response.len() {
+            0 => Err(IAMError::RoleNotFound),
+            1 => {
+                let response = response.first().unwrap();
+                match response.as_str() {
+                    "conflict" => Err(IAMError::HasRoleTaken),
+                    "done" => Ok(()),
+                    r => Err(GremlinError::Generic(format!("Unexpected query response: {}", r)).into()),
+                }
+            }
+            _ => Err(IAMError::HasRoleCycle(response)),
+        }
+    }
+
+    async fn disherit_role_impl(&self, role: &str, inherited_role: &str) -> Result<(), IAMError> {
+        let _ = self
+            .db
+            .execute(
+                r#"g.V().has('role','name',role)
+                    .out('has_role').has('role','name',inherited_role)
+                    .drop()"#,
+                &[("role", &role), ("inherited_role", &inherited_role)],
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn add_role_permission_impl(&self, role: &str, permission: &str) -> Result<(), IAMError> {
+        let _ = query_value::<String>(
+            &self.db,
+            r#"
+                g.V().has('permission','name',permission).fold()
+                    .coalesce(unfold(), addV('permission').property('name',permission))
+                    .constant('done')
+            "#,
+            &[("permission", &permission)],
+        )
+        .await?;
+
+        let response = query_vec::<String>(
+            &self.db,
+            r#"
+                g.v().has('role','name',role)
+                .coalesce(
+                    // if permission is already granted, return 'conflict'
+                    __.out('grants').has('permission','name',permission).constant('conflict'),
+
+                    // create the new edge, return 'done'
+                    __.addE('grants').to(v().has('permission','name',permission)).constant('done')
+                )
+            "#,
+            &[("role", &role), ("permission", &permission)],
+        )
+        .await?;
+
+        match response.first().map(|r| r.as_str()) {
+            None => Err(IAMError::RoleNotFound),
+            Some("conflict") => Err(IAMError::PermissionTaken),
+            Some("done") => Ok(()),
+            Some(r) => Err(GremlinError::Generic(format!("Unexpected query response: {}", r)).into()),
+        }
+    }
+
+    async fn remove_role_permission_impl(&self, role: &str, permission: &str) -> Result<(), IAMError> {
+        let _ = self
+            .db
+            .execute(
+                r#"g.V().has('role','name',role)
+                    .outE('grants').where(inV().has('permission','name',permission))
+                    .drop()"#,
+                &[("role", &role), ("permission", &permission)],
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn get_role_permissions_impl(&self, role: &str, include_inherited: bool) -> Result<Permissions, IAMError> {
+        let permissions = if include_inherited {
+            query_vec::<String>(
+                &self.db,
+                r#"
+                    g.V().has('role','name',role)
+                        .union(identity(), repeat(out('has_role')).emit())
+                        .out('grants').dedup().values('name')
+                "#,
+                &[("role", &role)],
+            )
+            .await?
+        } else {
+            query_vec::<String>(
+                &self.db,
+                r#"
+                    g.V().has('role','name',role).out('grants').values('name')
+                "#,
+                &[("role", &role)],
+            )
+            .await?
+        };
+        Ok(permissions.into_iter().collect())
+    }
+
+    async fn create_identity_impl(&self, identity: &str) -> Result<(), IAMError> {
+        let response = query_value::<String>(
+            &self.db,
+            r#"g.v().has('identity','name',identity).fold()
+                .coalesce(
+                    // if identity already present return 'conflict'
+                    unfold().constant('conflict'),
+
+                    // create new identity, return 'done'
+                    addV('identity').property('name',identity).constant('done')
+                )
+            "#,
+            &[("identity", &identity)],
+        )
+        .await?;
+
+        match response.as_str() {
+            "conflict" => Err(IAMError::Internal(format!("Identity {} already registered", identity))),
+            "done" => Ok(()),
+            r => Err(GremlinError::Generic(format!("Unexpected query response: {}", r)).into()),
+        }
+    }
+
+    async fn add_identity_role_impl(&self, identity: &str, role: &str) -> Result<(), IAMError> {
+        let response = query_vec::<String>(
+            &self.db,
+            r#"
+                g.v().has('role','name',role)
+                .coalesce(
+                    // if role is already assigned, return 'conflict'
+                    __.in('has_role').has('identity','name',identity).constant('conflict'),
+
+                    // create the new edge, return 'done'
+                    __.addE('has_role').from(v().has('identity','name',identity)).constant('done')
+                )
+            "#,
+            &[("identity", &identity), ("role", &role)],
+        )
+        .await?;
+
+        match response.first().map(|r| r.as_str()) {
+            None => Err(IAMError::RoleNotFound),
+            Some("conflict") => Err(IAMError::HasRoleTaken),
+            Some("done") => Ok(()),
+            Some(r) => Err(GremlinError::Generic(format!("Unexpected query response: {}", r)).into()),
+        }
+    }
+
+    async fn remove_identity_role_impl(&self, identity: &str, role: &str) -> Result<(), IAMError> {
+        let _ = self
+            .db
+            .execute(
+                r#"g.V().has('identity','name',identity)
+                    .outE('has_role').where(inV().has('role','name',role))
+                    .drop()"#,
+                &[("identity", &identity), ("role", &role)],
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn get_identity_roles_impl(&self, identity: &str) -> Result<Roles, IAMError> {
+        Ok(query_vec::<String>(
+            &self.db,
+            r#"
+                g.V().has('identity','name',identity).out('has_role').values('name')
+            "#,
+            &[("identity", &identity)],
+        )
+        .await?)
+    }
+
+    async fn get_inherited_roles_impl(&self, role: &str) -> Result<Roles, IAMError> {
+        Ok(query_vec::<String>(
+            &self.db,
+            r#"
+                g.V().has('role','name',role).out('has_role').values('name')
+            "#,
+            &[("role", &role)],
+        )
+        .await?)
+    }
+}
+
+impl RoleStore for GremlinRoleStore {
+    fn create_role<'a>(&'a self, role: &'a str) -> StoreFuture<'a, ()> {
+        Box::pin(self.create_role_impl(role))
+    }
+
+    fn get_roles(&self) -> StoreFuture<'_, Roles> {
+        Box::pin(self.get_roles_impl())
+    }
+
+    fn delete_role<'a>(&'a self, role: &'a str) -> StoreFuture<'a, ()> {
+        Box::pin(self.delete_role_impl(role))
+    }
+
+    fn inherit_role<'a>(&'a self, role: &'a str, inherited_role: &'a str) -> StoreFuture<'a, ()> {
+        Box::pin(self.inherit_role_impl(role, inherited_role))
+    }
+
+    fn disherit_role<'a>(&'a self, role: &'a str, inherited_role: &'a str) -> StoreFuture<'a, ()> {
+        Box::pin(self.disherit_role_impl(role, inherited_role))
+    }
+
+    fn add_role_permission<'a>(&'a self, role: &'a str, permission: &'a str) -> StoreFuture<'a, ()> {
+        Box::pin(self.add_role_permission_impl(role, permission))
+    }
+
+    fn remove_role_permission<'a>(&'a self, role: &'a str, permission: &'a str) -> StoreFuture<'a, ()> {
+        Box::pin(self.remove_role_permission_impl(role, permission))
+    }
+
+    fn get_role_permissions<'a>(&'a self, role: &'a str, include_inherited: bool) -> StoreFuture<'a, Permissions> {
+        Box::pin(self.get_role_permissions_impl(role, include_inherited))
+    }
+
+    fn create_identity<'a>(&'a self, identity: &'a str) -> StoreFuture<'a, ()> {
+        Box::pin(self.create_identity_impl(identity))
+    }
+
+    fn delete_identity<'a>(&'a self, identity: &'a str) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            let _ = self
+                .db
+                .execute(
+                    r#"g.V().has('identity','name',identity).drop()"#,
+                    &[("identity", &identity)],
+                )
+                .await?;
+            Ok(())
+        })
+    }
+
+    fn add_identity_role<'a>(&'a self, identity: &'a str, role: &'a str) -> StoreFuture<'a, ()> {
+        Box::pin(self.add_identity_role_impl(identity, role))
+    }
+
+    fn remove_identity_role<'a>(&'a self, identity: &'a str, role: &'a str) -> StoreFuture<'a, ()> {
+        Box::pin(self.remove_identity_role_impl(identity, role))
+    }
+
+    fn get_identity_roles<'a>(&'a self, identity: &'a str) -> StoreFuture<'a, Roles> {
+        Box::pin(self.get_identity_roles_impl(identity))
+    }
+
+    fn get_inherited_roles<'a>(&'a self, role: &'a str) -> StoreFuture<'a, Roles> {
+        Box::pin(self.get_inherited_roles_impl(role))
+    }
+}
@@ -1,13 +1,18 @@
-use crate::iam::{IAMConfig, IAMError};
-use gremlin_client::{aio::GremlinClient, ConnectionOptions, GraphSON, GremlinError};
+use crate::iam::{
+    role::{GremlinRoleStore, MemoryRoleStore, RoleCache, RoleStore, RoleStoreConfig},
+    IAMConfig, IAMError,
+};
 use serde::Serialize;
-use shine_core::gremlin_utils::{query_value, query_vec};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+};
 
 /// A vector of a roles
 pub type Roles = Vec<String>;
 
 /// Role with inheritance information
-#[derive(Debug, Serialize)]
+#[derive(Clone, Debug, Serialize)]
 pub struct InheritedRole {
     pub role: String,
     pub inherited_from: Option<String>,
@@ -16,168 +21,321 @@ pub struct InheritedRole {
 /// A vector of roles with inheritance information
 pub type InheritedRoles = Vec<InheritedRole>;
 
-/// Manage the role database
+/// Role reachable from a role through the inheritance
+#[derive(Clone, Debug, Serialize)]
+pub struct EffectiveRole {
+    pub role: String,
+    /// The shortest inheritance chain from the queried role to this role (both included)
+    pub path: Vec<String>,
+}
+
+/// The flattened role set of a role
+#[derive(Debug, Serialize)]
+pub struct EffectiveRoles {
+    pub role: String,
+    pub roles: Vec<EffectiveRole>,
+}
+
+/// Difference of the flattened role sets of two roles
+#[derive(Debug, Serialize)]
+pub struct EffectiveRolesDiff {
+    pub role: String,
+    pub other: String,
+    /// Roles reachable only from the role
+    pub only_role: Vec<String>,
+    /// Roles reachable only from the other role
+    pub only_other: Vec<String>,
+    pub common: Vec<String>,
+}
+
+/// A set of permissions
+pub type Permissions = HashSet<String>;
+
+/// Check the format of a permission. A permission is a dot separated list of lower case
+/// segments (ex. "asset.write"). The last segment can be a wildcard ("asset.*") and the
+/// single "*" grants everything.
+pub fn validate_permission(permission: &str) -> Result<(), IAMError> {
+    if permission == "*" {
+        return Ok(());
+    }
+
+    let segments: Vec<_> = permission.split('.').collect();
+    let last = segments.len() - 1;
+    let is_valid = segments.iter().enumerate().all(|(i, segment)| {
+        (i == last && i > 0 && *segment == "*")
+            || (!segment.is_empty()
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_'))
+    });
+
+    if is_valid {
+        Ok(())
+    } else {
+        Err(IAMError::BadRequest(format!("Invalid permission: {}", permission)))
+    }
+}
+
+/// Check if the required permission is granted by any of the permissions taking wildcards into account.
+pub fn is_permission_granted(granted: &Permissions, required: &str) -> bool {
+    if granted.contains(required) || granted.contains("*") {
+        return true;
+    }
+
+    let mut prefix = required;
+    while let Some(pos) = prefix.rfind('.') {
+        prefix = &prefix[..pos];
+        if granted.contains(&format!("{}.*", prefix)) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Manage the role database. The resolved roles of the identities are cached, the modifications
+/// of the role graph invalidate the affected entries.
 #[derive(Clone)]
 pub struct RoleManager {
-    db: GremlinClient,
+    store: Arc<dyn RoleStore>,
+    cache: RoleCache,
 }
 
 // Handling identites
 impl RoleManager {
     pub async fn new(config: &IAMConfig) -> Result<Self, IAMError> {
-        let connection = ConnectionOptions::builder()
-            .host(&config.graph_db_host)
-            .port(config.graph_db_port)
-            .ssl(true)
-            .credentials(&config.graph_db_user, &config.graph_db_password)
-            .serializer(GraphSON::V2)
-            .build();
-        let db = GremlinClient::connect(connection).await?;
+        let store: Arc<dyn RoleStore> = match &config.role_store {
+            RoleStoreConfig::Gremlin => Arc::new(GremlinRoleStore::new(config).await?),
+            RoleStoreConfig::Memory => Arc::new(MemoryRoleStore::new()),
+        };
+        log::info!("Role store: {:?}", config.role_store);
+        let cache = RoleCache::new(&config.role_cache).await?;
 
-        Ok(RoleManager { db: db })
+        Ok(RoleManager { store, cache })
     }
 
     pub async fn create_role(&self, role: &str) -> Result<(), IAMError> {
-        let response = query_value::<String>(
-            &self.db,
-            r#"g.v().has('role','name',role).fold()
-                .coalesce(
-                    // if role already present return 'conflict'
-                    unfold().constant('conflict'),
-
-                    // create new role, return 'done'
-                    addV('role').property('name',role).constant('done')
-                )
-            "#,
-            &[("role", &role)],
-        )
-        .await?;
-
-        match response.as_str() {
-            "conflict" => Err(IAMError::RoleTaken),
-            "done" => Ok(()),
-            r => Err(GremlinError::Generic(format!("Unexpected query response: {}", r)).into()),
-        }
+        self.store.create_role(role).await
     }
 
     pub async fn get_roles(&self) -> Result<Roles, IAMError> {
-        Ok(query_vec::<String>(
-            &self.db,
-            r#"
-                g.v().hasLabel('role').values('name');
-            "#,
-            &[],
-        )
-        .await?)
+        self.store.get_roles().await
     }
 
     pub async fn delete_role(&self, role: &str) -> Result<(), IAMError> {
-        let response = query_value::<String>(
-            &self.db,
-            r#"
-                g.V().has('role','name', role)
-                    .sideEffect(drop()).fold()
-                    .coalesce(
-                        unfold().constant('done'),
-                        constant('missing')
-                    )
-            "#,
-            &[("role", &role)],
-        )
-        .await?;
-
-        match response.as_str() {
-            "missing" => Err(IAMError::RoleNotFound),
-            "done" => Ok(()),
-            r => Err(GremlinError::Generic(format!("Unexpected query response: {}", r)).into()),
-        }
+        self.store.delete_role(role).await?;
+        self.cache.invalidate_all().await;
+        Ok(())
     }
 
-    pub async fn inherit_role(&self, role: &str, inherited_role: &str) -> Result<(), IAMError> {
-        let response = query_vec::<String>(
-            &self.db,
-            r#"
-                g.v().has('role','name',inherited_role)
-                .coalesce(
-                    // if the new edge creates a cycle, return the path 
-                    __.repeat(out('has_role').dedup()).until(has('role','name',role))
-                        .path().by('name').limit(1).unfold(),
-                        
-                    // if edge is already present, return 'conflict'
-                    __.in('has_role').has('role','name',role).constant('conflict'),
-
-                    // create the new edge, return 'done' 
-                    __.addE('has_role').from(v().has('role','name',role)).constant('done')
-                )
-            "#,
-            &[("role", &role), ("inherited_role", &inherited_role)],
-        )
-        .await?;
-
-        match response.len() {
-            0 => Err(IAMError::RoleNotFound),
-            1 => {
-                let response = response.first().unwrap();
-                match response.as_str() {
-                    "conflict" => Err(IAMError::HasRoleTaken),
-                    "done" => Ok(()),
-                    r => Err(GremlinError::Generic(format!("Unexpected query response: {}", r)).into()),
+    /// Breadth first search of the inheritance from start to end, returns the path with both end included
+    async fn find_inheritance_path(&self, start: &str, end: &str) -> Result<Option<Vec<String>>, IAMError> {
+        let mut parents = HashMap::<String, String>::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(start.to_owned());
+        while let Some(current) = queue.pop_front() {
+            for next in self.store.get_inherited_roles(&current).await? {
+                if next == end {
+                    let mut path = vec![end.to_owned(), current.clone()];
+                    let mut node = &current;
+                    while let Some(parent) = parents.get(node) {
+                        path.push(parent.clone());
+                        node = parent;
+                    }
+                    path.reverse();
+                    return Ok(Some(path));
+                }
+                if next != start && !parents.contains_key(&next) {
+                    parents.insert(next.clone(), current.clone());
+                    queue.push_back(next);
                 }
             }
-            _ => Err(IAMError::HasRoleCycle(response)),
         }
+        Ok(None)
+    }
+
+    /// Make role inherit the inherited_role. The edge is rejected with HasRoleCycle and the path closing
+    /// the cycle if the inherited_role already inherits the role, independent of the checks of the store.
+    pub async fn inherit_role(&self, role: &str, inherited_role: &str) -> Result<(), IAMError> {
+        if role == inherited_role {
+            return Err(IAMError::HasRoleCycle(vec![inherited_role.to_owned(), role.to_owned()]));
+        }
+        if let Some(path) = self.find_inheritance_path(inherited_role, role).await? {
+            return Err(IAMError::HasRoleCycle(path));
+        }
+        self.store.inherit_role(role, inherited_role).await?;
+        self.cache.invalidate_all().await;
+        Ok(())
     }
 
     pub async fn disherit_role(&self, role: &str, inherited_role: &str) -> Result<(), IAMError> {
-        let _ = self
-            .db
-            .execute(
-                r#"g.V().has('role','name',role)
-                    .out('has_role').has('role','name',inherited_role)
-                    .drop()"#,
-                &[("role", &role), ("inherited_role", &inherited_role)],
-            )
-            .await?;
+        self.store.disherit_role(role, inherited_role).await?;
+        self.cache.invalidate_all().await;
         Ok(())
     }
 
-    pub async fn create_identity(&self, identity: &str) -> Result<(), IAMError> {
-        let response = query_value::<String>(
-            &self.db,
-            r#"g.v().has('identity','name',identity).fold()
-                .coalesce(
-                    // if identity already present return 'conflict'
-                    unfold().constant('conflict'),
-
-                    // create new identity, return 'done'
-                    addV('identity').property('name',identity).constant('done')
-                )
-            "#,
-            &[("identity", &identity)],
-        )
-        .await?;
-
-        match response.as_str() {
-            "conflict" => Err(IAMError::Internal(format!("Identity {} already registered", identity))),
-            "done" => Ok(()),
-            r => Err(GremlinError::Generic(format!("Unexpected query response: {}", r)).into()),
+    pub async fn add_role_permission(&self, role: &str, permission: &str) -> Result<(), IAMError> {
+        validate_permission(permission)?;
+        self.store.add_role_permission(role, permission).await
+    }
+
+    pub async fn remove_role_permission(&self, role: &str, permission: &str) -> Result<(), IAMError> {
+        self.store.remove_role_permission(role, permission).await
+    }
+
+    /// Get the role and all the roles inherited directly or indirectly with the shortest inheritance path.
+    pub async fn get_effective_roles(&self, role: &str) -> Result<EffectiveRoles, IAMError> {
+        if !self.store.get_roles().await?.iter().any(|r| r == role) {
+            return Err(IAMError::RoleNotFound);
+        }
+
+        let mut roles = vec![EffectiveRole {
+            role: role.to_owned(),
+            path: vec![role.to_owned()],
+        }];
+        let mut visited: HashSet<String> = Some(role.to_owned()).into_iter().collect();
+        let mut next = 0;
+        while next < roles.len() {
+            let current = roles[next].clone();
+            for inherited in self.store.get_inherited_roles(&current.role).await? {
+                if visited.insert(inherited.clone()) {
+                    let mut path = current.path.clone();
+                    path.push(inherited.clone());
+                    roles.push(EffectiveRole { role: inherited, path });
+                }
+            }
+            next += 1;
         }
+
+        Ok(EffectiveRoles {
+            role: role.to_owned(),
+            roles,
+        })
+    }
+
+    /// Compare the flattened role sets of two roles
+    pub async fn diff_effective_roles(&self, role: &str, other: &str) -> Result<EffectiveRolesDiff, IAMError> {
+        let roles: HashSet<String> = self
+            .get_effective_roles(role)
+            .await?
+            .roles
+            .into_iter()
+            .map(|r| r.role)
+            .collect();
+        let other_roles: HashSet<String> = self
+            .get_effective_roles(other)
+            .await?
+            .roles
+            .into_iter()
+            .map(|r| r.role)
+            .collect();
+
+        let sorted = |set: HashSet<&String>| {
+            let mut list: Vec<String> = set.into_iter().cloned().collect();
+            list.sort();
+            list
+        };
+        Ok(EffectiveRolesDiff {
+            role: role.to_owned(),
+            other: other.to_owned(),
+            only_role: sorted(roles.difference(&other_roles).collect()),
+            only_other: sorted(other_roles.difference(&roles).collect()),
+            common: sorted(roles.intersection(&other_roles).collect()),
+        })
     }
 
-    pub async fn add_identity_role(&self, _identity_id: &str, _role: &str) -> Result<InheritedRoles, IAMError> {
-        unimplemented!()
+    /// Get the permissions of a role, optionally including the permissions of the inherited roles.
+    pub async fn get_role_permissions(&self, role: &str, include_inherited: bool) -> Result<Permissions, IAMError> {
+        self.store.get_role_permissions(role, include_inherited).await
     }
 
+    /// Get all the permissions granted by the roles including the inherited roles.
+    pub async fn get_permissions_of_roles<'a, I>(&self, roles: I) -> Result<Permissions, IAMError>
+    where
+        I: IntoIterator<Item = &'a String>,
+    {
+        let mut permissions = Permissions::new();
+        for role in roles {
+            permissions.extend(self.get_role_permissions(role, true).await?);
+        }
+        Ok(permissions)
+    }
+
+    pub async fn create_identity(&self, identity: &str) -> Result<(), IAMError> {
+        self.store.create_identity(identity).await
+    }
+
+    pub async fn delete_identity(&self, identity: &str) -> Result<(), IAMError> {
+        self.store.delete_identity(identity).await?;
+        self.cache.invalidate_identity(identity).await;
+        Ok(())
+    }
+
+    /// Assign a role to the identity and return the directly assigned roles
+    pub async fn add_identity_role(&self, identity_id: &str, role: &str) -> Result<InheritedRoles, IAMError> {
+        self.store.add_identity_role(identity_id, role).await?;
+        self.cache.invalidate_identity(identity_id).await;
+        self.get_identity_roles(identity_id, false).await
+    }
+
+    /// Get the roles of the identity, optionally including the inherited roles with the role they are
+    /// inherited from.
     pub async fn get_identity_roles(
         &self,
-        _identity_id: &str,
-        _include_inherited: bool,
+        identity_id: &str,
+        include_inherited: bool,
     ) -> Result<InheritedRoles, IAMError> {
-        //unimplemented!()
-        Ok(InheritedRoles::default())
+        let (cached, generation) = self.cache.get(identity_id);
+        let roles = match cached {
+            Some(roles) => roles,
+            None => {
+                let roles = self.resolve_identity_roles(identity_id).await?;
+                self.cache.insert(identity_id, &roles, generation);
+                roles
+            }
+        };
+
+        if include_inherited {
+            Ok(roles)
+        } else {
+            Ok(roles.into_iter().filter(|role| role.inherited_from.is_none()).collect())
+        }
+    }
+
+    /// Query the roles of the identity from the store including the inherited roles. The directly
+    /// assigned roles come first.
+    async fn resolve_identity_roles(&self, identity_id: &str) -> Result<InheritedRoles, IAMError> {
+        let direct_roles = self.store.get_identity_roles(identity_id).await?;
+        let mut visited: HashSet<String> = direct_roles.iter().cloned().collect();
+        let mut roles: InheritedRoles = direct_roles
+            .into_iter()
+            .map(|role| InheritedRole {
+                role,
+                inherited_from: None,
+            })
+            .collect();
+
+        // breadth first traversal of the inheritance, each role is listed once
+        let mut next = 0;
+        while next < roles.len() {
+            let role = roles[next].role.clone();
+            for inherited in self.store.get_inherited_roles(&role).await? {
+                if visited.insert(inherited.clone()) {
+                    roles.push(InheritedRole {
+                        role: inherited,
+                        inherited_from: Some(role.clone()),
+                    });
+                }
+            }
+            next += 1;
+        }
+
+        Ok(roles)
     }
 
-    pub async fn remove_identity_role(&self, _identity_id: &str, _role: &str) -> Result<InheritedRoles, IAMError> {
-        unimplemented!()
+    /// Remove a role from the identity and return the directly assigned roles
+    pub async fn remove_identity_role(&self, identity_id: &str, role: &str) -> Result<InheritedRoles, IAMError> {
+        self.store.remove_identity_role(identity_id, role).await?;
+        self.cache.invalidate_identity(identity_id).await;
+        self.get_identity_roles(identity_id, false).await
     }
 }
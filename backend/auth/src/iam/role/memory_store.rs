@@ -0,0 +1,243 @@
+use crate::iam::{
+    identity::StoreFuture,
+    role::{Permissions, RoleStore, Roles},
+    IAMError,
+};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Mutex,
+};
+
+#[derive(Default)]
+struct RoleNode {
+    /// The directly inherited roles
+    inherited: HashSet<String>,
+    /// The directly granted permissions
+    permissions: HashSet<String>,
+}
+
+#[derive(Default)]
+struct Inner {
+    roles: HashMap<String, RoleNode>,
+    /// The identities with the directly assigned roles
+    identities: HashMap<String, HashSet<String>>,
+}
+
+impl Inner {
+    fn role_mut(&mut self, role: &str) -> Result<&mut RoleNode, IAMError> {
+        self.roles.get_mut(role).ok_or(IAMError::RoleNotFound)
+    }
+
+    /// Find the inheritance path from start to end using a breadth first search
+    fn find_path(&self, start: &str, end: &str) -> Option<Vec<String>> {
+        let mut parents = HashMap::<&str, &str>::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+        while let Some(current) = queue.pop_front() {
+            for next in self.roles[current].inherited.iter().map(|r| r.as_str()) {
+                if next == end {
+                    let mut path = vec![end.to_owned(), current.to_owned()];
+                    let mut node = current;
+                    while let Some(&parent) = parents.get(node) {
+                        path.push(parent.to_owned());
+                        node = parent;
+                    }
+                    path.reverse();
+                    return Some(path);
+                }
+                if next != start && !parents.contains_key(next) {
+                    parents.insert(next, current);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    /// Collect the role and all the roles inherited directly or indirectly
+    fn collect_inherited<'a>(&'a self, role: &'a str) -> HashSet<&'a str> {
+        let mut visited = HashSet::new();
+        let mut stack = vec![role];
+        while let Some(current) = stack.pop() {
+            if visited.insert(current) {
+                if let Some(node) = self.roles.get(current) {
+                    stack.extend(node.inherited.iter().map(|r| r.as_str()));
+                }
+            }
+        }
+        visited
+    }
+}
+
+/// Role store keeping the role graph in the memory of the process, for local development and tests.
+#[derive(Default)]
+pub struct MemoryRoleStore {
+    inner: Mutex<Inner>,
+}
+
+impl MemoryRoleStore {
+    pub fn new() -> Self {
+        MemoryRoleStore::default()
+    }
+
+    fn with_inner<T, F>(&self, f: F) -> StoreFuture<'_, T>
+    where
+        T: 'static,
+        F: FnOnce(&mut Inner) -> Result<T, IAMError>,
+    {
+        let result = {
+            let mut inner = self.inner.lock().unwrap();
+            f(&mut inner)
+        };
+        Box::pin(async move { result })
+    }
+}
+
+impl RoleStore for MemoryRoleStore {
+    fn create_role<'a>(&'a self, role: &'a str) -> StoreFuture<'a, ()> {
+        self.with_inner(|inner| {
+            if inner.roles.contains_key(role) {
+                return Err(IAMError::RoleTaken);
+            }
+            inner.roles.insert(role.to_owned(), RoleNode::default());
+            Ok(())
+        })
+    }
+
+    fn get_roles(&self) -> StoreFuture<'_, Roles> {
+        self.with_inner(|inner| Ok(inner.roles.keys().cloned().collect()))
+    }
+
+    fn delete_role<'a>(&'a self, role: &'a str) -> StoreFuture<'a, ()> {
+        self.with_inner(|inner| {
+            if inner.roles.remove(role).is_none() {
+                return Err(IAMError::RoleNotFound);
+            }
+            for node in inner.roles.values_mut() {
+                node.inherited.remove(role);
+            }
+            for roles in inner.identities.values_mut() {
+                roles.remove(role);
+            }
+            Ok(())
+        })
+    }
+
+    fn inherit_role<'a>(&'a self, role: &'a str, inherited_role: &'a str) -> StoreFuture<'a, ()> {
+        self.with_inner(|inner| {
+            if !inner.roles.contains_key(role) || !inner.roles.contains_key(inherited_role) {
+                return Err(IAMError::RoleNotFound);
+            }
+            if role == inherited_role {
+                return Err(IAMError::HasRoleCycle(vec![inherited_role.to_owned(), role.to_owned()]));
+            }
+            if let Some(path) = inner.find_path(inherited_role, role) {
+                return Err(IAMError::HasRoleCycle(path));
+            }
+            if !inner.role_mut(role)?.inherited.insert(inherited_role.to_owned()) {
+                return Err(IAMError::HasRoleTaken);
+            }
+            Ok(())
+        })
+    }
+
+    fn disherit_role<'a>(&'a self, role: &'a str, inherited_role: &'a str) -> StoreFuture<'a, ()> {
+        self.with_inner(|inner| {
+            if let Some(node) = inner.roles.get_mut(role) {
+                node.inherited.remove(inherited_role);
+            }
+            Ok(())
+        })
+    }
+
+    fn add_role_permission<'a>(&'a self, role: &'a str, permission: &'a str) -> StoreFuture<'a, ()> {
+        self.with_inner(|inner| {
+            if !inner.role_mut(role)?.permissions.insert(permission.to_owned()) {
+                return Err(IAMError::PermissionTaken);
+            }
+            Ok(())
+        })
+    }
+
+    fn remove_role_permission<'a>(&'a self, role: &'a str, permission: &'a str) -> StoreFuture<'a, ()> {
+        self.with_inner(|inner| {
+            if let Some(node) = inner.roles.get_mut(role) {
+                node.permissions.remove(permission);
+            }
+            Ok(())
+        })
+    }
+
+    fn get_role_permissions<'a>(&'a self, role: &'a str, include_inherited: bool) -> StoreFuture<'a, Permissions> {
+        self.with_inner(|inner| {
+            let roles = if include_inherited {
+                inner.collect_inherited(role)
+            } else {
+                Some(role).into_iter().collect()
+            };
+            Ok(roles
+                .into_iter()
+                .filter_map(|role| inner.roles.get(role))
+                .flat_map(|node| node.permissions.iter().cloned())
+                .collect())
+        })
+    }
+
+    fn create_identity<'a>(&'a self, identity: &'a str) -> StoreFuture<'a, ()> {
+        self.with_inner(|inner| {
+            if inner.identities.insert(identity.to_owned(), HashSet::new()).is_some() {
+                return Err(IAMError::Internal(format!("Identity {} already registered", identity)));
+            }
+            Ok(())
+        })
+    }
+
+    fn delete_identity<'a>(&'a self, identity: &'a str) -> StoreFuture<'a, ()> {
+        self.with_inner(|inner| {
+            inner.identities.remove(identity);
+            Ok(())
+        })
+    }
+
+    fn add_identity_role<'a>(&'a self, identity: &'a str, role: &'a str) -> StoreFuture<'a, ()> {
+        self.with_inner(|inner| {
+            if !inner.roles.contains_key(role) {
+                return Err(IAMError::RoleNotFound);
+            }
+            let roles = inner.identities.get_mut(identity).ok_or(IAMError::IdentityNotFound)?;
+            if !roles.insert(role.to_owned()) {
+                return Err(IAMError::HasRoleTaken);
+            }
+            Ok(())
+        })
+    }
+
+    fn remove_identity_role<'a>(&'a self, identity: &'a str, role: &'a str) -> StoreFuture<'a, ()> {
+        self.with_inner(|inner| {
+            if let Some(roles) = inner.identities.get_mut(identity) {
+                roles.remove(role);
+            }
+            Ok(())
+        })
+    }
+
+    fn get_identity_roles<'a>(&'a self, identity: &'a str) -> StoreFuture<'a, Roles> {
+        self.with_inner(|inner| {
+            Ok(inner
+                .identities
+                .get(identity)
+                .map(|roles| roles.iter().cloned().collect())
+                .unwrap_or_default())
+        })
+    }
+
+    fn get_inherited_roles<'a>(&'a self, role: &'a str) -> StoreFuture<'a, Roles> {
+        self.with_inner(|inner| {
+            Ok(inner
+                .roles
+                .get(role)
+                .map(|node| node.inherited.iter().cloned().collect())
+                .unwrap_or_default())
+        })
+    }
+}
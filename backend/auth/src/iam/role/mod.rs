@@ -1,3 +1,12 @@
+mod cache;
+mod gremlin_store;
 mod manager;
+mod memory_store;
+pub mod permission;
+mod store;
 
+pub use self::cache::*;
+pub use self::gremlin_store::*;
 pub use self::manager::*;
+pub use self::memory_store::*;
+pub use self::store::*;
@@ -0,0 +1,21 @@
+//! Permissions used by the IAM endpoints
+
+pub const ROLE_READ: &str = "role.read";
+pub const ROLE_WRITE: &str = "role.write";
+pub const USER_ROLE_READ: &str = "user.role.read";
+pub const USER_ROLE_WRITE: &str = "user.role.write";
+pub const USER_PURGE: &str = "user.purge";
+pub const USER_READ: &str = "user.read";
+pub const USER_BAN: &str = "user.ban";
+pub const USER_IMPORT: &str = "user.import";
+pub const APIKEY_READ: &str = "apikey.read";
+pub const APIKEY_WRITE: &str = "apikey.write";
+pub const OAUTH_READ: &str = "oauth.read";
+pub const OAUTH_WRITE: &str = "oauth.write";
+pub const ENTITLEMENT_READ: &str = "entitlement.read";
+pub const ENTITLEMENT_WRITE: &str = "entitlement.write";
+pub const AUTOMATION_TOKEN_ISSUE: &str = "automation.issue";
+
+/// Scopes of the automation tokens for the endpoints that are available only for the test fixtures
+pub const AUTOMATION_USER_REGISTER: &str = "automation.user.register";
+pub const AUTOMATION_USER_LOGIN: &str = "automation.user.login";
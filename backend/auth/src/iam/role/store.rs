@@ -0,0 +1,63 @@
+use crate::iam::{
+    identity::StoreFuture,
+    role::{Permissions, Roles},
+};
+use serde::{Deserialize, Serialize};
+
+/// Storage backend of the roles
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum RoleStoreConfig {
+    /// Gremlin graph database using the graph_db settings of the IAMConfig
+    Gremlin,
+    /// Keep the roles in memory, for local development and tests
+    Memory,
+}
+
+impl Default for RoleStoreConfig {
+    fn default() -> Self {
+        RoleStoreConfig::Gremlin
+    }
+}
+
+/// Persistence of the role graph: the roles, their inheritance and the granted permissions.
+/// The permissions are validated by the RoleManager before reaching the store.
+pub trait RoleStore {
+    /// Create a new role, returns RoleTaken if the role already exists
+    fn create_role<'a>(&'a self, role: &'a str) -> StoreFuture<'a, ()>;
+
+    fn get_roles(&self) -> StoreFuture<'_, Roles>;
+
+    /// Delete a role with all of its relations, returns RoleNotFound if the role does not exist
+    fn delete_role<'a>(&'a self, role: &'a str) -> StoreFuture<'a, ()>;
+
+    /// Make role inherit the inherited_role. Returns HasRoleTaken if the edge is already present and
+    /// HasRoleCycle with the path if the new edge would create a cycle.
+    fn inherit_role<'a>(&'a self, role: &'a str, inherited_role: &'a str) -> StoreFuture<'a, ()>;
+
+    fn disherit_role<'a>(&'a self, role: &'a str, inherited_role: &'a str) -> StoreFuture<'a, ()>;
+
+    /// Grant a permission to the role, returns PermissionTaken if it has been granted already
+    fn add_role_permission<'a>(&'a self, role: &'a str, permission: &'a str) -> StoreFuture<'a, ()>;
+
+    fn remove_role_permission<'a>(&'a self, role: &'a str, permission: &'a str) -> StoreFuture<'a, ()>;
+
+    fn get_role_permissions<'a>(&'a self, role: &'a str, include_inherited: bool) -> StoreFuture<'a, Permissions>;
+
+    /// Register an identity, so roles can be assigned to it
+    fn create_identity<'a>(&'a self, identity: &'a str) -> StoreFuture<'a, ()>;
+
+    /// Delete an identity with all of its role assignments
+    fn delete_identity<'a>(&'a self, identity: &'a str) -> StoreFuture<'a, ()>;
+
+    /// Assign a role to an identity, returns HasRoleTaken if the role is assigned already
+    fn add_identity_role<'a>(&'a self, identity: &'a str, role: &'a str) -> StoreFuture<'a, ()>;
+
+    fn remove_identity_role<'a>(&'a self, identity: &'a str, role: &'a str) -> StoreFuture<'a, ()>;
+
+    /// Get the roles directly assigned to an identity
+    fn get_identity_roles<'a>(&'a self, identity: &'a str) -> StoreFuture<'a, Roles>;
+
+    /// Get the roles directly inherited by a role
+    fn get_inherited_roles<'a>(&'a self, role: &'a str) -> StoreFuture<'a, Roles>;
+}
@@ -0,0 +1,130 @@
+use crate::iam::{
+    identity::StoreFuture,
+    session::{Session, SessionData, SessionIndex, SessionIndexData, SessionStore},
+    IAMConfig, IAMError,
+};
+use azure_sdk_storage_table::{CloudTable, TableClient, TableEntity};
+use shine_core::azure_utils::{
+    self,
+    table_storage::{self, EmptyData, TableBatchClient},
+};
+
+/// Session store using Azure table storage. The uniqueness of the keys is ensured by an index entity.
+#[derive(Clone)]
+pub struct AzureSessionStore {
+    db: CloudTable,
+    batch: TableBatchClient,
+}
+
+impl AzureSessionStore {
+    pub async fn new(config: &IAMConfig) -> Result<Self, IAMError> {
+        let client = TableClient::new(&config.storage_account, &config.storage_account_key)?;
+        let db = CloudTable::new(client.clone(), "sessions");
+        db.create_if_not_exists().await?;
+        let batch = TableBatchClient::new(&config.storage_account, &config.storage_account_key, "sessions")?;
+
+        Ok(AzureSessionStore { db, batch })
+    }
+
+    async fn remove_index(&self, session: SessionIndex) {
+        let session = session.into_entity();
+        let (p, r) = (session.partition_key.clone(), session.row_key.clone());
+        self.db
+            .delete_entity(session)
+            .await
+            .unwrap_or_else(|e| log::error!("Failed to delete session index ([{}]/[{}]): {}", p, r, e));
+    }
+
+    async fn insert_session_impl(&self, session: Session) -> Result<Session, IAMError> {
+        // fisrt insert index, it also ensures key uniqueness.
+        let session_index = {
+            let index = SessionIndex::new(session.key(), session.id());
+            let index = match self.db.insert_entity(index.into_entity()).await {
+                Ok(index) => index,
+                Err(err) if azure_utils::is_precodition_error(&err) => return Err(IAMError::SessionKeyConflict),
+                Err(err) => return Err(err.into()),
+            };
+            SessionIndex::from_entity(index)
+        };
+
+        let session = match self.db.insert_entity(session.into_entity()).await {
+            Ok(session) => Session::from_entity(session),
+            Err(err) => {
+                self.remove_index(session_index).await;
+                return Err(err.into());
+            }
+        };
+
+        log::debug!("Session index: {:?}", session_index);
+        Ok(session)
+    }
+}
+
+impl SessionStore for AzureSessionStore {
+    fn insert_session(&self, session: Session) -> StoreFuture<'_, Session> {
+        Box::pin(self.insert_session_impl(session))
+    }
+
+    fn update_session(&self, session: Session) -> StoreFuture<'_, Session> {
+        Box::pin(async move {
+            match self.db.update_entity(session.into_entity()).await {
+                Ok(session) => Ok(Session::from_entity(session)),
+                Err(err) if azure_utils::is_precodition_error(&err) => Err(IAMError::SessionKeyConflict),
+                Err(err) => Err(err.into()),
+            }
+        })
+    }
+
+    fn find_session<'a>(&'a self, id: &'a str, key: &'a str) -> StoreFuture<'a, Option<Session>> {
+        Box::pin(async move {
+            let (p, r) = Session::entity_keys(id, key);
+            let session = self.db.get::<SessionData>(&p, &r, None).await?;
+            Ok(session.map(Session::from_entity))
+        })
+    }
+
+    fn find_session_id<'a>(&'a self, key: &'a str) -> StoreFuture<'a, Option<String>> {
+        Box::pin(async move {
+            let (p, r) = SessionIndex::entity_keys(key);
+            let index = self.db.get::<SessionIndexData>(&p, &r, None).await?;
+            Ok(index.map(|index| SessionIndex::from_entity(index).id().to_owned()))
+        })
+    }
+
+    fn find_enabled_sessions<'a>(&'a self, id: &'a str) -> StoreFuture<'a, Vec<Session>> {
+        Box::pin(async move {
+            let query = table_storage::filter_query(&format!("PartitionKey eq 'id-{}' and Disabled eq ''", id));
+            let sessions = table_storage::query_all::<SessionData>(&self.db, Some(&query)).await?;
+            Ok(sessions.into_iter().map(Session::from_entity).collect())
+        })
+    }
+
+    fn delete_sessions<'a>(&'a self, id: &'a str) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            let query = table_storage::filter_query(&format!("PartitionKey eq 'id-{}'", id));
+            let sessions = table_storage::query_all::<SessionData>(&self.db, Some(&query)).await?;
+
+            // the indices are spread over the partitions of the keys, the sessions of an identity share a
+            // partition and they are deleted in a few transactions
+            let indices: Vec<_> = sessions
+                .iter()
+                .map(|session| {
+                    let (p, r) = SessionIndex::entity_keys(&session.row_key);
+                    TableEntity {
+                        partition_key: p,
+                        row_key: r,
+                        etag: None,
+                        timestamp: None,
+                        payload: EmptyData {},
+                    }
+                })
+                .collect();
+            self.batch
+                .delete_entities(&indices)
+                .await
+                .unwrap_or_else(|e| log::error!("Failed to delete session indices of {}: {}", id, e));
+            self.batch.delete_entities(&sessions).await?;
+            Ok(())
+        })
+    }
+}
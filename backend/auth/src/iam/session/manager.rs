@@ -1,39 +1,36 @@
 use crate::iam::{
     identity::{Identity, UserIdentity},
-    session::{Session, SessionData, SessionIndex, SessionIndexData},
+    session::{AzureSessionStore, MemorySessionStore, RedisSessionStore, Session, SessionStore, SessionStoreConfig},
     Fingerprint, IAMConfig, IAMError,
 };
-use azure_sdk_storage_table::{CloudTable, TableClient};
 use chrono::{DateTime, Duration as ChronoDuration, Utc};
 use data_encoding;
-use futures::stream::StreamExt;
-use percent_encoding::utf8_percent_encode;
 use rand::Rng;
-use shine_core::{
-    azure_utils,
-    backoff::{self, Backoff, BackoffError},
-};
-use std::time::Duration;
+use shine_core::backoff::{self, Backoff, BackoffError};
+use std::{sync::Arc, time::Duration};
 
 const SESSION_KEY_LEN: usize = 32;
 const KEY_BASE_ENCODE: data_encoding::Encoding = data_encoding::BASE64URL_NOPAD;
 
 #[derive(Clone)]
 pub struct SessionManager {
-    db: CloudTable,
+    store: Arc<dyn SessionStore>,
     time_to_live: ChronoDuration,
 }
 
 // Handling identites
 impl SessionManager {
     pub async fn new(config: &IAMConfig) -> Result<Self, IAMError> {
-        let client = TableClient::new(&config.storage_account, &config.storage_account_key)?;
-        let db = CloudTable::new(client.clone(), "sessions");
-        db.create_if_not_exists().await?;
-
         let time_to_live = ChronoDuration::hours(config.session_time_to_live_h as i64);
 
-        Ok(SessionManager { db, time_to_live })
+        let store: Arc<dyn SessionStore> = match &config.session_store {
+            SessionStoreConfig::Azure => Arc::new(AzureSessionStore::new(config).await?),
+            SessionStoreConfig::Redis { url } => Arc::new(RedisSessionStore::new(url, time_to_live).await?),
+            SessionStoreConfig::Memory => Arc::new(MemorySessionStore::new(time_to_live)),
+        };
+        log::info!("Session store: {:?}", config.session_store);
+
+        Ok(SessionManager { store, time_to_live })
     }
 
     fn genrate_session_key(&self) -> String {
@@ -46,15 +43,6 @@ impl SessionManager {
         Utc::now() - self.time_to_live
     }
 
-    async fn remove_index(&self, session: SessionIndex) {
-        let session = session.into_entity();
-        let (p, r) = (session.partition_key.clone(), session.row_key.clone());
-        self.db
-            .delete_entity(session)
-            .await
-            .unwrap_or_else(|e| log::error!("Failed to delete session index ([{}]/[{}]): {}", p, r, e));
-    }
-
     async fn try_insert_session(
         &self,
         identity: &UserIdentity,
@@ -64,28 +52,10 @@ impl SessionManager {
         let key = self.genrate_session_key();
         log::debug!("Created new session key [{}] for {}", key, id);
 
-        // fisrt insert index, it also ensures key uniqueness.
-        let session_index = {
-            let index = SessionIndex::new(&key, id);
-            let index = match self.db.insert_entity(index.into_entity()).await {
-                Ok(index) => index,
-                Err(err) if azure_utils::is_precodition_error(&err) => return Err(IAMError::SessionKeyConflict),
-                Err(err) => return Err(err.into()),
-            };
-            SessionIndex::from_entity(index)
-        };
-
         let session = Session::new(id.to_owned(), key, fingerprint);
-        let session = match self.db.insert_entity(session.into_entity()).await {
-            Ok(session) => Session::from_entity(session),
-            Err(err) => {
-                self.remove_index(session_index).await;
-                return Err(err.into());
-            }
-        };
+        let session = self.store.insert_session(session).await?;
 
         log::info!("New session for {}", id);
-        log::debug!("Session index: {:?}", session_index);
         log::debug!("Session: {:?}", session);
 
         Ok(session)
@@ -118,39 +88,24 @@ impl SessionManager {
     }
 
     async fn find_session_by_id_key(&self, id: &str, key: &str) -> Result<Session, IAMError> {
-        let (p, r) = Session::entity_keys(id, key);
-        match self.db.get::<SessionData>(&p, &r, None).await {
-            Ok(Some(session)) => {
-                if session.payload.disable_date().is_some() {
-                    Err(IAMError::SessionExpired)
-                } else {
-                    Ok(Session::from_entity(session))
-                }
-            }
-            Ok(None) => Err(IAMError::SessionExpired),
-            Err(err) => Err(err.into()),
+        match self.store.find_session(id, key).await? {
+            Some(session) if session.is_disabled() => Err(IAMError::SessionExpired),
+            Some(session) => Ok(session),
+            None => Err(IAMError::SessionExpired),
         }
     }
 
     async fn find_session_by_key(&self, key: &str) -> Result<(String, Session), IAMError> {
-        let (p, r) = SessionIndex::entity_keys(key);
-        let index = match self.db.get::<SessionIndexData>(&p, &r, None).await {
-            Ok(Some(index)) => SessionIndex::from_entity(index),
-            Ok(None) => return Err(IAMError::SessionExpired),
-            Err(err) => return Err(err.into()),
+        let id = match self.store.find_session_id(key).await? {
+            Some(id) => id,
+            None => return Err(IAMError::SessionExpired),
         };
 
-        self.find_session_by_id_key(index.id(), index.key())
-            .await
-            .map(|session| (index.id().to_owned(), session))
+        self.find_session_by_id_key(&id, key).await.map(|session| (id, session))
     }
 
     async fn update_session(&self, session: Session) -> Result<Session, IAMError> {
-        match self.db.update_entity(session.into_entity()).await {
-            Ok(session) => Ok(Session::from_entity(session)),
-            Err(err) if azure_utils::is_precodition_error(&err) => Err(IAMError::SessionKeyConflict),
-            Err(err) => Err(err.into()),
-        }
+        self.store.update_session(session).await
     }
 
     /// Refresh the session when both the id and the key is known.
@@ -262,49 +217,79 @@ impl SessionManager {
             .await
     }
 
-    /// Invalidate all the sessions for an id
-    pub async fn invalidate_all_session(&self, id: &str, active_key: Option<&str>) -> Result<(), IAMError> {
+    /// Get the active (not disabled and not expired) sessions of an id
+    pub async fn get_active_sessions(&self, id: &str) -> Result<Vec<Session>, IAMError> {
+        let minimum_refresh_date = self.get_minimum_refresh_date();
+        let result: Vec<_> = self
+            .store
+            .find_enabled_sessions(id)
+            .await?
+            .into_iter()
+            .filter(|session| !session.is_disabled() && session.data().refresh_date() >= minimum_refresh_date)
+            .collect();
+
+        log::debug!("Active sessions of {}: {}", id, result.len());
+        Ok(result)
+    }
+
+    /// Invalidate all the sessions of an id except the one with the active key. Returns SessionKeyConflict if
+    /// some of the sessions could not be invalidated due to conflicting updates.
+    async fn invalidate_sessions_except(&self, id: &str, active_key: Option<&str>) -> Result<(), IAMError> {
         // query all the active session
-        let query = format!("PartitionKey eq 'id-{}' and Disabled eq ''", id);
-        let query = format!(
-            "$filter={}",
-            utf8_percent_encode(&query, percent_encoding::NON_ALPHANUMERIC)
-        );
+        let sessions = self.store.find_enabled_sessions(id).await?;
+        log::debug!("Sessions to invalidate: {:?}", sessions);
 
         let mut has_conflict = false;
-        let mut stream = Box::pin(self.db.stream_query::<SessionData>(Some(&query)));
-        while let Some(Ok(sessions)) = stream.next().await {
-            log::debug!("Sessions to invalidate: {:?}", sessions);
-
-            // perform the invalidation one-by-one with backoff to ensure a refresh won't keep the key alive.
-            // due to conflicting updates.
-            // The active key (if provided) is invalidated after all other sessions are invalidate to keep the
-            // session alive on any error
-            for session in sessions.into_iter() {
-                let session = Session::from_entity(session);
-                if let Some(key) = active_key {
-                    if key == session.key() {
-                        // skip the active key
-                        continue;
-                    }
+        // perform the invalidation one-by-one with backoff to ensure a refresh won't keep the key alive.
+        // due to conflicting updates.
+        for session in sessions.into_iter() {
+            if let Some(key) = active_key {
+                if key == session.key() {
+                    // skip the active key
+                    continue;
                 }
-
-                match self.invalidate_session(session.id(), session.key()).await {
-                    Ok(_) => {}
-                    Err(IAMError::SessionKeyConflict) => {
-                        has_conflict = true;
-                    }
-                    Err(err) => return Err(err),
-                };
             }
+
+            match self.invalidate_session(session.id(), session.key()).await {
+                Ok(_) => {}
+                Err(IAMError::SessionKeyConflict) => {
+                    has_conflict = true;
+                }
+                Err(IAMError::SessionExpired) => {
+                    // expired or invalidated in the meantime
+                }
+                Err(err) => return Err(err),
+            };
         }
 
         if has_conflict {
             Err(IAMError::SessionKeyConflict)
-        } else if let Some(key) = active_key {
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Invalidate all the sessions for an id.
+    /// The active key (if provided) is invalidated after all other sessions are invalidate to keep the
+    /// session alive on any error
+    pub async fn invalidate_all_session(&self, id: &str, active_key: Option<&str>) -> Result<(), IAMError> {
+        self.invalidate_sessions_except(id, active_key).await?;
+        if let Some(key) = active_key {
             self.invalidate_session(id, key).await
         } else {
             Ok(())
         }
     }
+
+    /// Invalidate all the sessions for an id but keep the active session alive, ex. after a password change.
+    pub async fn invalidate_other_sessions(&self, id: &str, active_key: &str) -> Result<(), IAMError> {
+        self.invalidate_sessions_except(id, Some(active_key)).await
+    }
+
+    /// Delete all the sessions of the identity permanently
+    pub async fn delete_all_sessions(&self, id: &str) -> Result<(), IAMError> {
+        self.store.delete_sessions(id).await?;
+        log::info!("Sessions of {} deleted", id);
+        Ok(())
+    }
 }
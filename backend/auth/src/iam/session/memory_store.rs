@@ -0,0 +1,110 @@
+use crate::iam::{
+    identity::StoreFuture,
+    session::{Session, SessionData, SessionStore},
+    IAMError,
+};
+use chrono::{Duration as ChronoDuration, Utc};
+use std::{collections::HashMap, sync::Mutex};
+
+struct StoredSession {
+    id: String,
+    data: SessionData,
+}
+
+/// Session store keeping the sessions in the memory of the process, for local development and tests.
+/// Similar to the Redis store, the disabled sessions are removed and the expired sessions are dropped
+/// lazily.
+pub struct MemorySessionStore {
+    sessions: Mutex<HashMap<String, StoredSession>>,
+    time_to_live: ChronoDuration,
+}
+
+impl MemorySessionStore {
+    pub fn new(time_to_live: ChronoDuration) -> Self {
+        MemorySessionStore {
+            sessions: Mutex::new(HashMap::new()),
+            time_to_live,
+        }
+    }
+
+    fn with_sessions<T, F>(&self, f: F) -> StoreFuture<'_, T>
+    where
+        T: 'static,
+        F: FnOnce(&mut HashMap<String, StoredSession>) -> Result<T, IAMError>,
+    {
+        let result = {
+            let mut sessions = self.sessions.lock().unwrap();
+            let minimum_refresh_date = Utc::now() - self.time_to_live;
+            sessions.retain(|_, session| session.data.refresh_date() >= minimum_refresh_date);
+            f(&mut sessions)
+        };
+        Box::pin(async move { result })
+    }
+}
+
+impl SessionStore for MemorySessionStore {
+    fn insert_session(&self, session: Session) -> StoreFuture<'_, Session> {
+        self.with_sessions(move |sessions| {
+            if sessions.contains_key(session.key()) {
+                return Err(IAMError::SessionKeyConflict);
+            }
+            sessions.insert(
+                session.key().to_owned(),
+                StoredSession {
+                    id: session.id().to_owned(),
+                    data: session.data().clone(),
+                },
+            );
+            Ok(session)
+        })
+    }
+
+    fn update_session(&self, session: Session) -> StoreFuture<'_, Session> {
+        self.with_sessions(move |sessions| {
+            // update only the existing sessions, thus a refresh cannot resurrect a removed session
+            if !sessions
+                .get(session.key())
+                .map_or(false, |stored| stored.id == session.id())
+            {
+                return Err(IAMError::SessionExpired);
+            }
+
+            if session.is_disabled() {
+                sessions.remove(session.key());
+            } else if let Some(stored) = sessions.get_mut(session.key()) {
+                stored.data = session.data().clone();
+            }
+            Ok(session)
+        })
+    }
+
+    fn find_session<'a>(&'a self, id: &'a str, key: &'a str) -> StoreFuture<'a, Option<Session>> {
+        self.with_sessions(|sessions| {
+            Ok(sessions
+                .get(key)
+                .filter(|stored| stored.id == id)
+                .map(|stored| Session::from_data(id, key, stored.data.clone())))
+        })
+    }
+
+    fn find_session_id<'a>(&'a self, key: &'a str) -> StoreFuture<'a, Option<String>> {
+        self.with_sessions(|sessions| Ok(sessions.get(key).map(|stored| stored.id.clone())))
+    }
+
+    fn find_enabled_sessions<'a>(&'a self, id: &'a str) -> StoreFuture<'a, Vec<Session>> {
+        self.with_sessions(|sessions| {
+            Ok(sessions
+                .iter()
+                .filter(|(_, stored)| stored.id == id)
+                .map(|(key, stored)| Session::from_data(id, key, stored.data.clone()))
+                .collect())
+        })
+    }
+
+    fn delete_sessions<'a>(&'a self, id: &'a str) -> StoreFuture<'a, ()> {
+        self.with_sessions(|sessions| {
+            sessions.retain(|_, stored| stored.id != id);
+            Ok(())
+        })
+    }
+}
@@ -1,7 +1,15 @@
+mod azure_store;
 mod manager;
+mod memory_store;
+mod redis_store;
 mod session;
 mod session_index;
+mod store;
 
+pub use self::azure_store::*;
 pub use self::manager::*;
+pub use self::memory_store::*;
+pub use self::redis_store::*;
 pub use self::session::*;
 pub use self::session_index::*;
+pub use self::store::*;
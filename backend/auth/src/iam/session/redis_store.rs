@@ -0,0 +1,236 @@
+use crate::iam::{
+    identity::StoreFuture,
+    session::{Session, SessionData, SessionStore},
+    IAMError,
+};
+use chrono::{Duration as ChronoDuration, Utc};
+use redis::{aio::ConnectionManager, Client};
+
+fn session_key(id: &str, key: &str) -> String {
+    format!("session:{}:{}", id, key)
+}
+
+fn index_key(key: &str) -> String {
+    format!("session_key:{}", key)
+}
+
+fn sessions_of_key(id: &str) -> String {
+    format!("sessions:{}", id)
+}
+
+/// Session store using Redis. The sessions are not disabled in place, but removed and the
+/// expired sessions are dropped by the TTL of the keys without any further maintenance.
+#[derive(Clone)]
+pub struct RedisSessionStore {
+    connection: ConnectionManager,
+    time_to_live: ChronoDuration,
+}
+
+impl RedisSessionStore {
+    pub async fn new(url: &str, time_to_live: ChronoDuration) -> Result<Self, IAMError> {
+        let client = Client::open(url)?;
+        let connection = ConnectionManager::new(client).await?;
+        Ok(RedisSessionStore {
+            connection,
+            time_to_live,
+        })
+    }
+
+    /// Seconds until the session expires counted from the last refresh
+    fn remaining_seconds(&self, session: &Session) -> usize {
+        let expire = session.data().refresh_date() + self.time_to_live;
+        (expire - Utc::now()).num_seconds().max(1) as usize
+    }
+
+    fn serialize(session: &Session) -> Result<String, IAMError> {
+        serde_json::to_string(session.data())
+            .map_err(|err| IAMError::Internal(format!("Failed to serialize session: {}", err)))
+    }
+
+    fn deserialize(id: &str, key: &str, data: &str) -> Result<Session, IAMError> {
+        let data: SessionData = serde_json::from_str(data)
+            .map_err(|err| IAMError::Internal(format!("Failed to deserialize session: {}", err)))?;
+        Ok(Session::from_data(id, key, data))
+    }
+
+    async fn insert_session_impl(&self, session: Session) -> Result<Session, IAMError> {
+        let mut con = self.connection.clone();
+        let (id, key) = (session.id(), session.key());
+        let ttl = self.remaining_seconds(&session);
+
+        // fisrt insert index, it also ensures key uniqueness.
+        let inserted: Option<String> = redis::cmd("SET")
+            .arg(index_key(key))
+            .arg(id)
+            .arg("NX")
+            .arg("EX")
+            .arg(ttl)
+            .query_async(&mut con)
+            .await?;
+        if inserted.is_none() {
+            return Err(IAMError::SessionKeyConflict);
+        }
+
+        redis::pipe()
+            .atomic()
+            .cmd("SET")
+            .arg(session_key(id, key))
+            .arg(Self::serialize(&session)?)
+            .arg("EX")
+            .arg(ttl)
+            .ignore()
+            .cmd("SADD")
+            .arg(sessions_of_key(id))
+            .arg(key)
+            .ignore()
+            .cmd("EXPIRE")
+            .arg(sessions_of_key(id))
+            .arg(self.time_to_live.num_seconds().max(1) as usize)
+            .ignore()
+            .query_async::<_, ()>(&mut con)
+            .await?;
+
+        Ok(session)
+    }
+
+    async fn remove_session(&self, session: Session) -> Result<Session, IAMError> {
+        let mut con = self.connection.clone();
+        let (id, key) = (session.id(), session.key());
+        redis::pipe()
+            .atomic()
+            .cmd("DEL")
+            .arg(session_key(id, key))
+            .arg(index_key(key))
+            .ignore()
+            .cmd("SREM")
+            .arg(sessions_of_key(id))
+            .arg(key)
+            .ignore()
+            .query_async::<_, ()>(&mut con)
+            .await?;
+        Ok(session)
+    }
+
+    async fn update_session_impl(&self, session: Session) -> Result<Session, IAMError> {
+        if session.is_disabled() {
+            return self.remove_session(session).await;
+        }
+
+        let mut con = self.connection.clone();
+        let (id, key) = (session.id(), session.key());
+        let ttl = self.remaining_seconds(&session);
+
+        // update only the existing sessions, thus a refresh cannot resurrect a removed session
+        let (updated,): (Option<String>,) = redis::pipe()
+            .atomic()
+            .cmd("SET")
+            .arg(session_key(id, key))
+            .arg(Self::serialize(&session)?)
+            .arg("XX")
+            .arg("EX")
+            .arg(ttl)
+            .cmd("EXPIRE")
+            .arg(index_key(key))
+            .arg(ttl)
+            .ignore()
+            .cmd("EXPIRE")
+            .arg(sessions_of_key(id))
+            .arg(self.time_to_live.num_seconds().max(1) as usize)
+            .ignore()
+            .query_async(&mut con)
+            .await?;
+
+        if updated.is_none() {
+            Err(IAMError::SessionExpired)
+        } else {
+            Ok(session)
+        }
+    }
+
+    async fn find_session_impl(&self, id: &str, key: &str) -> Result<Option<Session>, IAMError> {
+        let mut con = self.connection.clone();
+        let data: Option<String> = redis::cmd("GET")
+            .arg(session_key(id, key))
+            .query_async(&mut con)
+            .await?;
+        data.map(|data| Self::deserialize(id, key, &data)).transpose()
+    }
+
+    async fn find_enabled_sessions_impl(&self, id: &str) -> Result<Vec<Session>, IAMError> {
+        let mut con = self.connection.clone();
+        let keys: Vec<String> = redis::cmd("SMEMBERS")
+            .arg(sessions_of_key(id))
+            .query_async(&mut con)
+            .await?;
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let session_keys: Vec<_> = keys.iter().map(|key| session_key(id, key)).collect();
+        let data: Vec<Option<String>> = redis::cmd("MGET").arg(session_keys).query_async(&mut con).await?;
+
+        let mut result = Vec::new();
+        let mut expired = Vec::new();
+        for (key, data) in keys.iter().zip(data.iter()) {
+            match data {
+                Some(data) => result.push(Self::deserialize(id, key, data)?),
+                None => expired.push(key),
+            }
+        }
+
+        if !expired.is_empty() {
+            log::debug!("Removing {} expired session keys of {}", expired.len(), id);
+            redis::cmd("SREM")
+                .arg(sessions_of_key(id))
+                .arg(expired)
+                .query_async::<_, ()>(&mut con)
+                .await?;
+        }
+
+        Ok(result)
+    }
+}
+
+impl SessionStore for RedisSessionStore {
+    fn insert_session(&self, session: Session) -> StoreFuture<'_, Session> {
+        Box::pin(self.insert_session_impl(session))
+    }
+
+    fn update_session(&self, session: Session) -> StoreFuture<'_, Session> {
+        Box::pin(self.update_session_impl(session))
+    }
+
+    fn find_session<'a>(&'a self, id: &'a str, key: &'a str) -> StoreFuture<'a, Option<Session>> {
+        Box::pin(self.find_session_impl(id, key))
+    }
+
+    fn find_session_id<'a>(&'a self, key: &'a str) -> StoreFuture<'a, Option<String>> {
+        Box::pin(async move {
+            let mut con = self.connection.clone();
+            Ok(redis::cmd("GET").arg(index_key(key)).query_async(&mut con).await?)
+        })
+    }
+
+    fn find_enabled_sessions<'a>(&'a self, id: &'a str) -> StoreFuture<'a, Vec<Session>> {
+        Box::pin(self.find_enabled_sessions_impl(id))
+    }
+
+    fn delete_sessions<'a>(&'a self, id: &'a str) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            let mut con = self.connection.clone();
+            let keys: Vec<String> = redis::cmd("SMEMBERS")
+                .arg(sessions_of_key(id))
+                .query_async(&mut con)
+                .await?;
+
+            let mut pipe = redis::pipe();
+            pipe.atomic();
+            for key in &keys {
+                pipe.cmd("DEL").arg(session_key(id, key)).arg(index_key(key)).ignore();
+            }
+            pipe.cmd("DEL").arg(sessions_of_key(id)).ignore();
+            pipe.query_async::<_, ()>(&mut con).await?;
+            Ok(())
+        })
+    }
+}
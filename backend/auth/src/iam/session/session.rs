@@ -1,11 +1,13 @@
 use crate::iam::fingerprint::Fingerprint;
 use azure_sdk_storage_table::TableEntity;
 use chrono::{DateTime, Utc};
+use data_encoding::BASE64URL_NOPAD;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use shine_core::{kernel::identity::SessionKey, serde_with};
 
 /// Data associated to a session
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct SessionData {
     agent: String,
@@ -80,6 +82,22 @@ impl SessionData {
     pub fn disable_date(&self) -> Option<DateTime<Utc>> {
         self.disabled
     }
+
+    pub fn agent(&self) -> &str {
+        &self.agent
+    }
+
+    pub fn remote_ip(&self) -> &str {
+        &self.remote_ip
+    }
+
+    pub fn remote_continent(&self) -> &str {
+        &self.remote_continent
+    }
+
+    pub fn remote_country(&self) -> &str {
+        &self.remote_country
+    }
 }
 
 /// The session of a user. Only users may have a session, other type of identites cannot log in and thus cannot
@@ -126,6 +144,18 @@ impl Session {
         Self(entity)
     }
 
+    /// Create Self from the data stored outside of table storage
+    pub fn from_data(id: &str, key: &str, data: SessionData) -> Self {
+        let (partition_key, row_key) = Self::entity_keys(id, key);
+        Self(TableEntity {
+            partition_key,
+            row_key,
+            etag: None,
+            timestamp: None,
+            payload: data,
+        })
+    }
+
     pub fn into_entity(self) -> TableEntity<SessionData> {
         self.0
     }
@@ -171,3 +201,45 @@ impl From<Session> for SessionKey {
         SessionKey::new(session.key().to_string())
     }
 }
+
+/// Length of the truncated digest of the session key used as the public id of the session
+const SESSION_ID_LEN: usize = 16;
+
+/// The public id of a session. The session key is a credential thus it is never shown, the sessions are
+/// identified by a truncated digest of the key instead.
+pub fn session_id(key: &str) -> String {
+    let digest = Sha256::digest(key.as_bytes());
+    BASE64URL_NOPAD.encode(&digest[..SESSION_ID_LEN])
+}
+
+/// Public information of an active session, the fingerprint of the device that created it.
+#[derive(Debug, Serialize)]
+pub struct SessionInfo {
+    pub id: String,
+    pub agent: String,
+    pub remote_ip: String,
+    pub remote_continent: String,
+    pub remote_country: String,
+    pub issued: DateTime<Utc>,
+    pub refreshed: DateTime<Utc>,
+    pub refresh_count: u64,
+    /// If this session was used for the request
+    pub current: bool,
+}
+
+impl SessionInfo {
+    pub fn from_session(session: &Session, current_key: &str) -> SessionInfo {
+        let data = session.data();
+        SessionInfo {
+            id: session_id(session.key()),
+            agent: data.agent.clone(),
+            remote_ip: data.remote_ip.clone(),
+            remote_continent: data.remote_continent.clone(),
+            remote_country: data.remote_country.clone(),
+            issued: data.issued,
+            refreshed: data.refreshed,
+            refresh_count: data.refresh_count,
+            current: session.key() == current_key,
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use crate::iam::{identity::StoreFuture, session::Session};
+use serde::{Deserialize, Serialize};
+
+/// Storage backend of the sessions
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum SessionStoreConfig {
+    /// Azure table storage using the storage account of the IAMConfig
+    Azure,
+    /// Redis where the sessions are expired by the TTL of the keys
+    Redis { url: String },
+    /// Keep the sessions in memory, for local development and tests
+    Memory,
+}
+
+impl Default for SessionStoreConfig {
+    fn default() -> Self {
+        SessionStoreConfig::Azure
+    }
+}
+
+/// Persistence of the user sessions. The validation of the sessions (fingerprint, expiry) is implemented
+/// by the SessionManager.
+pub trait SessionStore {
+    /// Insert a new session, returns SessionKeyConflict if the key is already in use
+    fn insert_session(&self, session: Session) -> StoreFuture<'_, Session>;
+
+    /// Update an existing session. On a concurrent modification SessionKeyConflict is returned.
+    /// Stores may drop the disabled sessions instead of updating them.
+    fn update_session(&self, session: Session) -> StoreFuture<'_, Session>;
+
+    fn find_session<'a>(&'a self, id: &'a str, key: &'a str) -> StoreFuture<'a, Option<Session>>;
+
+    /// Find the identity id owning the session key
+    fn find_session_id<'a>(&'a self, key: &'a str) -> StoreFuture<'a, Option<String>>;
+
+    /// Find the sessions of an identity that has not been disabled yet
+    fn find_enabled_sessions<'a>(&'a self, id: &'a str) -> StoreFuture<'a, Vec<Session>>;
+
+    /// Delete all the sessions of an identity including the disabled ones
+    fn delete_sessions<'a>(&'a self, id: &'a str) -> StoreFuture<'a, ()>;
+}
@@ -1,15 +1,51 @@
 use super::iam::{
-    identity::{ValidatedEmail, ValidatedName, ValidatedPassword},
-    IAMError,
+    identity::{Identity, IdentitySearch, ValidatedEmail, ValidatedName, ValidatedPassword},
+    role::permission,
+    session, AccountEvent, IAMError, ImportedUser, RoleAssignment, MAX_BULK_ITEMS,
 };
 use super::utils::create_user_id;
 use super::State;
-use actix_web::{web, HttpResponse};
+use actix_web::{http::header, web, web::Bytes, Error as ActixError, HttpResponse};
+use chrono::{DateTime, Utc};
+use futures::{future, stream, StreamExt};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use serde::{Deserialize, Serialize};
-use shine_core::kernel::anti_forgery::{AntiForgeryIssuer, AntiForgerySession};
+use shine_core::kernel::anti_forgery::{
+    AntiForgeryIdentity, AntiForgeryIssuer, AntiForgerySession, AntiForgeryValidator,
+};
 use shine_core::kernel::identity::{IdentityCookie, IdentitySession, SessionKey, UserId};
 use shine_core::kernel::response::{APIError, APIResult};
-use shine_core::requestinfo::{BasicAuth, RemoteInfo, TestingToken};
+use shine_core::requestinfo::{ApiKeyIdentity, AutomationToken, BasicAuth, IntrospectionResponse, RemoteInfo};
+use std::time::Duration;
+
+/// Period of the comments sent on the notification stream to keep the connection alive
+const NOTIFICATION_KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// Guard of the handlers, check if the user of the session has the required permission.
+async fn require_permission(
+    state: &State,
+    user_id: Option<&UserId>,
+    automation_token: &AutomationToken,
+    permission: &str,
+) -> Result<(), IAMError> {
+    state
+        .iam()
+        .check_permission_by_roles(user_id.map(|u| u.roles()), permission, automation_token.token())
+        .await
+}
+
+/// Guard of the handlers issuing credentials, the user of the session must also have a verified email address.
+async fn require_verified_permission(
+    state: &State,
+    user_id: Option<&UserId>,
+    automation_token: &AutomationToken,
+    permission: &str,
+) -> Result<(), IAMError> {
+    state
+        .iam()
+        .check_permission_by_verified_identity(user_id.map(|u| u.user_id()), permission, automation_token.token())
+        .await
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RegistrationParams {
@@ -23,25 +59,21 @@ pub async fn register_user(
     state: web::Data<State>,
     identity_session: IdentitySession,
     remote_info: RemoteInfo,
-    testing_token: TestingToken,
+    automation_token: AutomationToken,
     params: web::Json<RegistrationParams>,
 ) -> APIResult {
     let params = params.into_inner();
     let fingerprint = state.iam().get_fingerprint(&remote_info).await?;
-    log::info!(
-        "register_user[{:?},{:?}]: {:?}",
-        testing_token.token(),
-        fingerprint,
-        params
-    );
+    log::info!("register_user[{:?}]: {:?}", fingerprint, params.name);
 
-    if testing_token.is_valid() {
-        state
-            .iam()
-            .check_permission_by_testing_token(testing_token.token())
-            .await?;
-    } else {
-        return Err(APIError::FunctionNotSupported);
+    match automation_token.token() {
+        Some(token) => {
+            state
+                .iam()
+                .check_permission_by_automation_token(token, permission::AUTOMATION_USER_REGISTER)
+                .await?
+        }
+        None => return Err(APIError::FunctionNotSupported),
     }
 
     IdentityCookie::clear(&identity_session);
@@ -58,11 +90,63 @@ pub async fn register_user(
     Ok(HttpResponse::Ok().finish())
 }
 
+pub async fn register_guest(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    remote_info: RemoteInfo,
+) -> APIResult {
+    let fingerprint = state.iam().get_fingerprint(&remote_info).await?;
+    log::info!("register_guest[{:?}]", fingerprint);
+
+    IdentityCookie::clear(&identity_session);
+
+    let (identity, roles, session) = state.iam().register_guest(&fingerprint).await?;
+
+    create_user_id(identity, roles)?.to_session(&identity_session)?;
+    SessionKey::from(session).to_session(&identity_session)?;
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PromoteGuestParams {
+    name: String,
+    password: String,
+    email: Option<String>,
+    af: String,
+}
+
+pub async fn promote_guest(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    af_session: AntiForgerySession,
+    params: web::Json<PromoteGuestParams>,
+) -> APIResult {
+    let params = params.into_inner();
+    let user_id = UserId::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
+    log::info!("promote_guest {:?}", user_id);
+
+    AntiForgeryValidator::validate(&af_session, &params.af, AntiForgeryIdentity::Ignore).map_err(IAMError::from)?;
+    let name = ValidatedName::from_raw(&params.name)?;
+    let email = params.email.map(|email| ValidatedEmail::from_raw(&email)).transpose()?;
+    let password = ValidatedPassword::from_raw(&params.password)?;
+
+    let (identity, roles) = state
+        .iam()
+        .promote_guest(user_id.user_id(), name, email, password)
+        .await?;
+
+    // the name of the user has changed
+    create_user_id(identity, roles)?.to_session(&identity_session)?;
+
+    Ok(HttpResponse::Ok().finish())
+}
+
 pub async fn login_basic_auth(
     state: web::Data<State>,
     identity_session: IdentitySession,
     remote_info: RemoteInfo,
-    testing_token: TestingToken,
+    automation_token: AutomationToken,
     auth: BasicAuth,
 ) -> APIResult {
     let user_id = auth.user_id();
@@ -70,13 +154,14 @@ pub async fn login_basic_auth(
     let password = ValidatedPassword::from_raw(&password)?;
     let fingerprint = state.iam().get_fingerprint(&remote_info).await?;
 
-    if testing_token.is_valid() {
-        state
-            .iam()
-            .check_permission_by_testing_token(testing_token.token())
-            .await?;
-    } else {
-        return Err(APIError::FunctionNotSupported);
+    match automation_token.token() {
+        Some(token) => {
+            state
+                .iam()
+                .check_permission_by_automation_token(token, permission::AUTOMATION_USER_LOGIN)
+                .await?
+        }
+        None => return Err(APIError::FunctionNotSupported),
     }
 
     IdentityCookie::clear(&identity_session);
@@ -201,17 +286,235 @@ pub async fn logout(
     Ok(HttpResponse::Ok().finish())
 }
 
+pub async fn get_sessions(state: web::Data<State>, identity_session: IdentitySession) -> APIResult {
+    let session_key = SessionKey::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
+    let user_id = UserId::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
+    log::info!("get_sessions {:?}, {:?}", user_id, session_key);
+
+    let sessions = state.iam().get_sessions(user_id.user_id(), session_key.key()).await?;
+    Ok(HttpResponse::Ok().json(sessions))
+}
+
+enum NotificationItem {
+    Event(AccountEvent),
+    KeepAlive,
+}
+
+/// Stream the account notifications of the current identity as server-sent events. The stream is closed
+/// when the session of the connection is invalidated.
+pub async fn stream_notifications(state: web::Data<State>, identity_session: IdentitySession) -> APIResult {
+    let session_key = SessionKey::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
+    let user_id = UserId::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
+    log::info!("stream_notifications {:?}", user_id);
+
+    let key = session_key.key().to_owned();
+    let events = state
+        .iam()
+        .subscribe_account_events(user_id.user_id())
+        .map(NotificationItem::Event);
+    let keep_alive = actix_rt::time::interval(NOTIFICATION_KEEP_ALIVE).map(|_| NotificationItem::KeepAlive);
+    let body = stream::select(events, keep_alive).scan(false, move |closed, item| {
+        if *closed {
+            return future::ready(None);
+        }
+        let chunk = match item {
+            NotificationItem::Event(event) => {
+                *closed = event.ends_session(&key);
+                match serde_json::to_string(&event) {
+                    Ok(data) => format!("event: {}\ndata: {}\n\n", event.event_name(), data),
+                    Err(err) => {
+                        log::warn!("Failed to serialize account event: {:?}", err);
+                        return future::ready(Some(Ok::<_, ActixError>(Bytes::new())));
+                    }
+                }
+            }
+            NotificationItem::KeepAlive => ":\n\n".to_owned(),
+        };
+        future::ready(Some(Ok(Bytes::from(chunk))))
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .header(header::CACHE_CONTROL, "no-cache")
+        .streaming(Box::pin(body)))
+}
+
+pub async fn revoke_session(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    query: web::Path<String>,
+) -> APIResult {
+    let session_key = SessionKey::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
+    let user_id = UserId::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
+    log::info!("revoke_session {:?}, {:?}, {}", user_id, session_key, query);
+
+    state.iam().revoke_session(user_id.user_id(), &query).await?;
+    if session::session_id(session_key.key()) == query.as_str() {
+        IdentityCookie::clear(&identity_session);
+    }
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn export_user(state: web::Data<State>, identity_session: IdentitySession) -> APIResult {
+    let session_key = SessionKey::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
+    let user_id = UserId::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
+    log::info!("export_user {:?}, {:?}", user_id, session_key);
+
+    let export = state
+        .iam()
+        .export_identity(user_id.user_id(), session_key.key())
+        .await?;
+    Ok(HttpResponse::Ok()
+        .header("Content-Disposition", "attachment; filename=\"export.json\"")
+        .json(export))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteUserParams {
+    /// The current password to confirm the deletion, not required for the guests
+    password: Option<String>,
+    af: String,
+}
+
+pub async fn delete_user(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    af_session: AntiForgerySession,
+    params: web::Json<DeleteUserParams>,
+) -> APIResult {
+    let user_id = UserId::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
+    log::info!("delete_user {:?}", user_id);
+
+    AntiForgeryValidator::validate(&af_session, &params.af, AntiForgeryIdentity::Ignore).map_err(IAMError::from)?;
+    let password = params
+        .password
+        .as_ref()
+        .map(|password| ValidatedPassword::from_raw(password))
+        .transpose()?;
+
+    #[derive(Serialize)]
+    struct Response {
+        deletion_scheduled: DateTime<Utc>,
+    };
+
+    let deletion_scheduled = state
+        .iam()
+        .request_identity_deletion(user_id.user_id(), password.as_ref())
+        .await?;
+    IdentityCookie::clear(&identity_session);
+    Ok(HttpResponse::Ok().json(Response { deletion_scheduled }))
+}
+
+pub async fn purge_users(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    automation_token: AutomationToken,
+) -> APIResult {
+    let user_id = UserId::from_session(&identity_session)?;
+    log::info!("purge_users {:?}", user_id);
+
+    require_permission(&state, user_id.as_ref(), &automation_token, permission::USER_PURGE).await?;
+
+    #[derive(Serialize)]
+    struct Response {
+        purged: usize,
+    };
+
+    let purged = state.iam().purge_deleted_identities().await?;
+    Ok(HttpResponse::Ok().json(Response { purged }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchUsersQuery {
+    name: Option<String>,
+    email: Option<String>,
+    continuation: Option<String>,
+    limit: Option<usize>,
+}
+
+pub async fn search_users(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    automation_token: AutomationToken,
+    params: web::Query<SearchUsersQuery>,
+) -> APIResult {
+    let user_id = UserId::from_session(&identity_session)?;
+    log::info!("search_users {:?}, {:?}", user_id, params);
+
+    require_permission(&state, user_id.as_ref(), &automation_token, permission::USER_READ).await?;
+
+    let params = params.into_inner();
+    let search = match (params.name, params.email) {
+        (Some(_), Some(_)) => {
+            return Err(IAMError::BadRequest("Search by either name or email".to_owned()).into());
+        }
+        (_, Some(email)) => IdentitySearch::Email(email),
+        (name, None) => IdentitySearch::Name(name.unwrap_or_default()),
+    };
+    let result = state
+        .iam()
+        .search_identities(&search, params.continuation.as_deref(), params.limit.unwrap_or(20))
+        .await?;
+    Ok(HttpResponse::Ok().json(result))
+}
+
+pub async fn get_user(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    automation_token: AutomationToken,
+    query: web::Path<String>,
+) -> APIResult {
+    let user_id = UserId::from_session(&identity_session)?;
+    log::info!("get_user {:?}, {:?}", user_id, query);
+
+    require_permission(&state, user_id.as_ref(), &automation_token, permission::USER_READ).await?;
+    let detail = state.iam().get_identity_detail(&query).await?;
+    Ok(HttpResponse::Ok().json(detail))
+}
+
+pub async fn ban_user(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    automation_token: AutomationToken,
+    query: web::Path<String>,
+) -> APIResult {
+    let user_id = UserId::from_session(&identity_session)?;
+    log::info!("ban_user {:?}, {:?}", user_id, query);
+
+    require_permission(&state, user_id.as_ref(), &automation_token, permission::USER_BAN).await?;
+    let identity = state.iam().ban_identity(&query).await?;
+    Ok(HttpResponse::Ok().json(identity))
+}
+
+pub async fn unban_user(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    automation_token: AutomationToken,
+    query: web::Path<String>,
+) -> APIResult {
+    let user_id = UserId::from_session(&identity_session)?;
+    log::info!("unban_user {:?}, {:?}", user_id, query);
+
+    require_permission(&state, user_id.as_ref(), &automation_token, permission::USER_BAN).await?;
+    let identity = state.iam().unban_identity(&query).await?;
+    Ok(HttpResponse::Ok().json(identity))
+}
+
 #[derive(Serialize)]
 struct RolesResponse {
     roles: Vec<String>,
 }
 
-pub async fn get_roles(state: web::Data<State>, identity_session: IdentitySession) -> APIResult {
+pub async fn get_roles(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    automation_token: AutomationToken,
+) -> APIResult {
     let session_key = SessionKey::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
     let user_id = UserId::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
     log::info!("get_roles {:?}, {:?}", user_id, session_key);
 
-    //todo: check permission
+    require_permission(&state, Some(&user_id), &automation_token, permission::ROLE_READ).await?;
     let roles = state.iam().get_roles().await?;
     Ok(HttpResponse::Ok().json(RolesResponse { roles }))
 }
@@ -219,7 +522,7 @@ pub async fn get_roles(state: web::Data<State>, identity_session: IdentitySessio
 pub async fn create_role(
     state: web::Data<State>,
     identity_session: IdentitySession,
-    testing_token: TestingToken,
+    automation_token: AutomationToken,
     query: web::Path<String>,
 ) -> APIResult {
     let session_key = SessionKey::from_session(&identity_session)?;
@@ -228,14 +531,11 @@ pub async fn create_role(
         "create_role[{:?},{:?},{:?}] {}",
         user_id,
         session_key,
-        testing_token,
+        automation_token,
         query
     );
 
-    state
-        .iam()
-        .check_permission_by_identity(user_id.as_ref().map(|u| u.user_id()), testing_token.token())
-        .await?;
+    require_verified_permission(&state, user_id.as_ref(), &automation_token, permission::ROLE_WRITE).await?;
 
     state.iam().create_role(&query).await?;
     Ok(HttpResponse::Ok().finish())
@@ -244,13 +544,14 @@ pub async fn create_role(
 pub async fn delete_role(
     state: web::Data<State>,
     identity_session: IdentitySession,
+    automation_token: AutomationToken,
     query: web::Path<String>,
 ) -> APIResult {
     let session_key = SessionKey::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
     let user_id = UserId::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
     log::info!("delete_role {:?}, {:?}, {}", user_id, session_key, query);
 
-    //todo: check permission
+    require_permission(&state, Some(&user_id), &automation_token, permission::ROLE_WRITE).await?;
     state.iam().delete_role(&query).await?;
     Ok(HttpResponse::Ok().finish())
 }
@@ -258,13 +559,14 @@ pub async fn delete_role(
 pub async fn inherit_role(
     state: web::Data<State>,
     identity_session: IdentitySession,
+    automation_token: AutomationToken,
     query: web::Path<(String, String)>,
 ) -> APIResult {
     let session_key = SessionKey::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
     let user_id = UserId::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
     log::info!("inherit_role {:?}, {:?}, {:?}", user_id, session_key, query);
 
-    //todo: check permission
+    require_permission(&state, Some(&user_id), &automation_token, permission::ROLE_WRITE).await?;
     state.iam().inherit_role(&query.0, &query.1).await?;
     Ok(HttpResponse::Ok().finish())
 }
@@ -272,41 +574,175 @@ pub async fn inherit_role(
 pub async fn disherit_role(
     state: web::Data<State>,
     identity_session: IdentitySession,
+    automation_token: AutomationToken,
     query: web::Path<(String, String)>,
 ) -> APIResult {
     let session_key = SessionKey::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
     let user_id = UserId::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
     log::info!("disherit_role {:?}, {:?}, {:?}", user_id, session_key, query);
 
-    //todo: check permission
+    require_permission(&state, Some(&user_id), &automation_token, permission::ROLE_WRITE).await?;
     state.iam().disherit_role(&query.0, &query.1).await?;
     Ok(HttpResponse::Ok().finish())
 }
 
+#[derive(Debug, Deserialize)]
+pub struct EffectiveRolesQuery {
+    /// Compare the flattened role set with the one of this role
+    #[serde(default)]
+    diff: Option<String>,
+}
+
+/// Get the flattened role set of a role with the inheritance paths, or the difference to the flattened
+/// role set of another role, to debug the role graph
+pub async fn get_effective_roles(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    automation_token: AutomationToken,
+    query: web::Path<String>,
+    params: web::Query<EffectiveRolesQuery>,
+) -> APIResult {
+    let user_id = UserId::from_session(&identity_session)?;
+    log::info!("get_effective_roles {:?}, {:?}, {:?}", user_id, query, params);
+
+    require_permission(&state, user_id.as_ref(), &automation_token, permission::ROLE_READ).await?;
+    match &params.diff {
+        Some(other) => {
+            let diff = state.iam().diff_effective_roles(&query, other).await?;
+            Ok(HttpResponse::Ok().json(diff))
+        }
+        None => {
+            let roles = state.iam().get_effective_roles(&query).await?;
+            Ok(HttpResponse::Ok().json(roles))
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct PermissionsResponse {
+    permissions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PermissionsQuery {
+    #[serde(default)]
+    inherited: bool,
+}
+
+pub async fn get_role_permissions(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    automation_token: AutomationToken,
+    query: web::Path<String>,
+    params: web::Query<PermissionsQuery>,
+) -> APIResult {
+    let user_id = UserId::from_session(&identity_session)?;
+    log::info!("get_role_permissions {:?}, {:?}, {:?}", user_id, query, params);
+
+    require_permission(&state, user_id.as_ref(), &automation_token, permission::ROLE_READ).await?;
+    let permissions = state.iam().get_role_permissions(&query, params.inherited).await?;
+    let mut permissions: Vec<_> = permissions.into_iter().collect();
+    permissions.sort();
+    Ok(HttpResponse::Ok().json(PermissionsResponse { permissions }))
+}
+
+pub async fn add_role_permission(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    automation_token: AutomationToken,
+    query: web::Path<(String, String)>,
+) -> APIResult {
+    let user_id = UserId::from_session(&identity_session)?;
+    log::info!("add_role_permission {:?}, {:?}", user_id, query);
+
+    require_permission(&state, user_id.as_ref(), &automation_token, permission::ROLE_WRITE).await?;
+    state.iam().add_role_permission(&query.0, &query.1).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn remove_role_permission(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    automation_token: AutomationToken,
+    query: web::Path<(String, String)>,
+) -> APIResult {
+    let user_id = UserId::from_session(&identity_session)?;
+    log::info!("remove_role_permission {:?}, {:?}", user_id, query);
+
+    require_permission(&state, user_id.as_ref(), &automation_token, permission::ROLE_WRITE).await?;
+    state.iam().remove_role_permission(&query.0, &query.1).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
 pub async fn get_user_roles(
     state: web::Data<State>,
     identity_session: IdentitySession,
+    automation_token: AutomationToken,
     query: web::Path<String>,
 ) -> APIResult {
     let session_key = SessionKey::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
     let user_id = UserId::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
     log::info!("get_user_roles {:?}, {:?}, {:?}", user_id, session_key, query);
 
-    //todo: check permission
+    require_permission(&state, Some(&user_id), &automation_token, permission::USER_ROLE_READ).await?;
     let roles = state.iam().get_identity_roles(&query, true).await?;
     Ok(HttpResponse::Ok().json(roles))
 }
 
+fn check_bulk_size(len: usize) -> Result<(), IAMError> {
+    if len > MAX_BULK_ITEMS {
+        Err(IAMError::BadRequest(format!(
+            "Too many items, limit: {}",
+            MAX_BULK_ITEMS
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// Register multiple users without creating sessions, returns the result of each user
+pub async fn import_users(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    automation_token: AutomationToken,
+    params: web::Json<Vec<ImportedUser>>,
+) -> APIResult {
+    let user_id = UserId::from_session(&identity_session)?;
+    log::info!("import_users {:?}, {} users", user_id, params.len());
+
+    require_permission(&state, user_id.as_ref(), &automation_token, permission::USER_IMPORT).await?;
+    check_bulk_size(params.len())?;
+    let results = state.iam().import_users(&params).await;
+    Ok(HttpResponse::Ok().json(results))
+}
+
+/// Assign multiple roles, returns the result of each assignment
+pub async fn bulk_assign_roles(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    automation_token: AutomationToken,
+    params: web::Json<Vec<RoleAssignment>>,
+) -> APIResult {
+    let user_id = UserId::from_session(&identity_session)?;
+    log::info!("bulk_assign_roles {:?}, {} assignments", user_id, params.len());
+
+    require_permission(&state, user_id.as_ref(), &automation_token, permission::USER_ROLE_WRITE).await?;
+    check_bulk_size(params.len())?;
+    let results = state.iam().assign_roles(&params).await;
+    Ok(HttpResponse::Ok().json(results))
+}
+
 pub async fn add_user_role(
     state: web::Data<State>,
     identity_session: IdentitySession,
+    automation_token: AutomationToken,
     query: web::Path<(String, String)>,
 ) -> APIResult {
     let session_key = SessionKey::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
     let user_id = UserId::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
     log::info!("add_user_role {:?}, {:?}, {:?}", user_id, session_key, query);
 
-    //todo: check permission
+    require_permission(&state, Some(&user_id), &automation_token, permission::USER_ROLE_WRITE).await?;
     let roles = state.iam().add_identity_role(&query.0, &query.1).await?;
     Ok(HttpResponse::Ok().json(roles))
 }
@@ -314,17 +750,559 @@ pub async fn add_user_role(
 pub async fn remove_user_role(
     state: web::Data<State>,
     identity_session: IdentitySession,
+    automation_token: AutomationToken,
     query: web::Path<(String, String)>,
 ) -> APIResult {
     let session_key = SessionKey::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
     let user_id = UserId::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
     log::info!("remove_user_role {:?}, {:?}, {:?}", user_id, session_key, query);
 
-    //todo: check permission
+    require_permission(&state, Some(&user_id), &automation_token, permission::USER_ROLE_WRITE).await?;
     let roles = state.iam().remove_identity_role(&query.0, &query.1).await?;
     Ok(HttpResponse::Ok().json(roles))
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiKeyParams {
+    name: String,
+    #[serde(default)]
+    roles: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct ApiKeyResponse {
+    key_id: String,
+    key: String,
+}
+
+pub async fn create_api_key(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    automation_token: AutomationToken,
+    params: web::Json<ApiKeyParams>,
+) -> APIResult {
+    let user_id = UserId::from_session(&identity_session)?;
+    log::info!("create_api_key[{:?},{:?}] {:?}", user_id, automation_token, params);
+
+    let user_id = user_id.ok_or(IAMError::SessionRequired)?;
+    require_verified_permission(&state, Some(&user_id), &automation_token, permission::APIKEY_WRITE).await?;
+
+    let params = params.into_inner();
+    let (info, key) = state
+        .iam()
+        .create_api_key(user_id.user_id(), &params.name, &params.roles)
+        .await?;
+    Ok(HttpResponse::Ok().json(ApiKeyResponse {
+        key_id: info.key_id,
+        key,
+    }))
+}
+
+pub async fn get_api_keys(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    automation_token: AutomationToken,
+) -> APIResult {
+    let user_id = UserId::from_session(&identity_session)?;
+    log::info!("get_api_keys[{:?},{:?}]", user_id, automation_token);
+
+    require_permission(&state, user_id.as_ref(), &automation_token, permission::APIKEY_READ).await?;
+
+    let keys = state.iam().get_api_keys().await?;
+    Ok(HttpResponse::Ok().json(keys))
+}
+
+pub async fn revoke_api_key(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    automation_token: AutomationToken,
+    query: web::Path<String>,
+) -> APIResult {
+    let user_id = UserId::from_session(&identity_session)?;
+    log::info!("revoke_api_key[{:?},{:?}] {}", user_id, automation_token, query);
+
+    require_permission(&state, user_id.as_ref(), &automation_token, permission::APIKEY_WRITE).await?;
+
+    state.iam().revoke_api_key(&query).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn get_my_entitlements(state: web::Data<State>, identity_session: IdentitySession) -> APIResult {
+    let user_id = UserId::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
+    log::info!("get_my_entitlements {:?}", user_id);
+
+    let entitlements = state.iam().get_entitlements(user_id.user_id()).await?;
+    Ok(HttpResponse::Ok().json(entitlements))
+}
+
+#[derive(Serialize)]
+struct EntitlementTokenResponse {
+    token: String,
+    expires: i64,
+}
+
+pub async fn create_entitlement_token(state: web::Data<State>, identity_session: IdentitySession) -> APIResult {
+    let user_id = UserId::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
+    log::info!("create_entitlement_token {:?}", user_id);
+
+    let (token, expires) = state.iam().issue_entitlement_token(user_id.user_id()).await?;
+    Ok(HttpResponse::Ok().json(EntitlementTokenResponse { token, expires }))
+}
+
+#[derive(Serialize)]
+struct EntitlementKeyResponse {
+    public_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AutomationTokenParams {
+    scopes: Vec<String>,
+    time_to_live_m: Option<u16>,
+}
+
+#[derive(Serialize)]
+struct AutomationTokenResponse {
+    token: String,
+    expires: i64,
+}
+
+/// Mint a short-lived automation token for the test fixtures and the CI tools. Only a signed in user
+/// can mint tokens, an automation token cannot be used to mint further tokens.
+pub async fn create_automation_token(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    params: web::Json<AutomationTokenParams>,
+) -> APIResult {
+    let user_id = UserId::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
+    let params = params.into_inner();
+    log::info!("create_automation_token[{:?}] {:?}", user_id, params);
+
+    state
+        .iam()
+        .check_permission_by_roles(Some(user_id.roles()), permission::AUTOMATION_TOKEN_ISSUE, None)
+        .await?;
+    let time_to_live = params
+        .time_to_live_m
+        .map(|minutes| chrono::Duration::minutes(i64::from(minutes)));
+    let (token, claims) = state
+        .iam()
+        .issue_automation_token(user_id.user_id(), &params.scopes, time_to_live)
+        .await?;
+    Ok(HttpResponse::Ok().json(AutomationTokenResponse {
+        token,
+        expires: claims.exp,
+    }))
+}
+
+pub async fn get_entitlement_key(state: web::Data<State>) -> APIResult {
+    let public_key = state
+        .iam()
+        .entitlement_public_key()
+        .ok_or_else(|| APIError::RespourceNotFound("Entitlement tokens are disabled".to_owned()))?;
+    Ok(HttpResponse::Ok().json(EntitlementKeyResponse { public_key }))
+}
+
+pub async fn get_user_entitlements(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    automation_token: AutomationToken,
+    query: web::Path<String>,
+) -> APIResult {
+    let user_id = UserId::from_session(&identity_session)?;
+    log::info!("get_user_entitlements[{:?},{:?}] {}", user_id, automation_token, query);
+
+    require_permission(
+        &state,
+        user_id.as_ref(),
+        &automation_token,
+        permission::ENTITLEMENT_READ,
+    )
+    .await?;
+
+    let entitlements = state.iam().get_entitlements(&query).await?;
+    Ok(HttpResponse::Ok().json(entitlements))
+}
+
+pub async fn grant_entitlement(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    automation_token: AutomationToken,
+    query: web::Path<(String, String)>,
+) -> APIResult {
+    let user_id = UserId::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
+    log::info!("grant_entitlement[{:?},{:?}] {:?}", user_id, automation_token, query);
+
+    require_permission(&state, Some(&user_id), &automation_token, permission::ENTITLEMENT_WRITE).await?;
+
+    let entitlement = state
+        .iam()
+        .grant_entitlement(&query.0, &query.1, user_id.user_id())
+        .await?;
+    Ok(HttpResponse::Ok().json(entitlement))
+}
+
+pub async fn revoke_entitlement(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    automation_token: AutomationToken,
+    query: web::Path<(String, String)>,
+) -> APIResult {
+    let user_id = UserId::from_session(&identity_session)?;
+    log::info!("revoke_entitlement[{:?},{:?}] {:?}", user_id, automation_token, query);
+
+    require_permission(
+        &state,
+        user_id.as_ref(),
+        &automation_token,
+        permission::ENTITLEMENT_WRITE,
+    )
+    .await?;
+
+    state.iam().revoke_entitlement(&query.0, &query.1).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OAuthClientParams {
+    name: String,
+    #[serde(default)]
+    public: bool,
+    redirect_uris: Vec<String>,
+    #[serde(default)]
+    scopes: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct OAuthClientResponse {
+    client_id: String,
+    client_secret: Option<String>,
+}
+
+pub async fn register_oauth_client(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    automation_token: AutomationToken,
+    params: web::Json<OAuthClientParams>,
+) -> APIResult {
+    let user_id = UserId::from_session(&identity_session)?;
+    log::info!(
+        "register_oauth_client[{:?},{:?}] {:?}",
+        user_id,
+        automation_token,
+        params
+    );
+
+    let user_id = user_id.ok_or(IAMError::SessionRequired)?;
+    require_verified_permission(&state, Some(&user_id), &automation_token, permission::OAUTH_WRITE).await?;
+
+    let params = params.into_inner();
+    let (info, client_secret) = state
+        .iam()
+        .register_oauth_client(
+            user_id.user_id(),
+            &params.name,
+            params.public,
+            &params.redirect_uris,
+            &params.scopes,
+        )
+        .await?;
+    Ok(HttpResponse::Ok().json(OAuthClientResponse {
+        client_id: info.client_id,
+        client_secret,
+    }))
+}
+
+pub async fn get_oauth_clients(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    automation_token: AutomationToken,
+) -> APIResult {
+    let user_id = UserId::from_session(&identity_session)?;
+    log::info!("get_oauth_clients[{:?},{:?}]", user_id, automation_token);
+
+    require_permission(&state, user_id.as_ref(), &automation_token, permission::OAUTH_READ).await?;
+
+    let clients = state.iam().get_oauth_clients().await?;
+    Ok(HttpResponse::Ok().json(clients))
+}
+
+pub async fn revoke_oauth_client(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    automation_token: AutomationToken,
+    query: web::Path<String>,
+) -> APIResult {
+    let user_id = UserId::from_session(&identity_session)?;
+    log::info!("revoke_oauth_client[{:?},{:?}] {}", user_id, automation_token, query);
+
+    require_permission(&state, user_id.as_ref(), &automation_token, permission::OAUTH_WRITE).await?;
+
+    state.iam().revoke_oauth_client(&query).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OAuthAuthorizeParams {
+    client_id: String,
+    redirect_uri: String,
+    /// Space separated list of the requested scopes
+    #[serde(default)]
+    scope: String,
+    state: Option<String>,
+    code_challenge: Option<String>,
+    code_challenge_method: Option<String>,
+    af: String,
+}
+
+/// Consent of the signed in user to the authorization request of a client. The anti-forgery token
+/// protects the consent from cross-site requests.
+pub async fn authorize_oauth_client(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    af_session: AntiForgerySession,
+    params: web::Json<OAuthAuthorizeParams>,
+) -> APIResult {
+    let params = params.into_inner();
+    let user_id = UserId::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
+    log::info!("authorize_oauth_client {:?}, {:?}", user_id, params.client_id);
+
+    AntiForgeryValidator::validate(&af_session, &params.af, AntiForgeryIdentity::Ignore).map_err(IAMError::from)?;
+    match params.code_challenge_method.as_deref() {
+        None | Some("S256") => {}
+        Some(method) => {
+            return Err(APIError::BadRequest(format!(
+                "Unsupported code challenge method: {}",
+                method
+            )))
+        }
+    }
+    let scopes: Vec<String> = params.scope.split_whitespace().map(|s| s.to_owned()).collect();
+
+    let code = state
+        .iam()
+        .authorize_oauth_client(
+            user_id.user_id(),
+            &params.client_id,
+            &params.redirect_uri,
+            &scopes,
+            params.code_challenge.as_deref(),
+        )
+        .await?;
+
+    let separator = if params.redirect_uri.contains('?') { '&' } else { '?' };
+    let mut redirect = format!("{}{}code={}", params.redirect_uri, separator, code);
+    if let Some(client_state) = &params.state {
+        redirect.push_str("&state=");
+        redirect.extend(utf8_percent_encode(client_state, NON_ALPHANUMERIC));
+    }
+
+    #[derive(Serialize)]
+    struct Response {
+        redirect: String,
+    };
+    Ok(HttpResponse::Ok().json(Response { redirect }))
+}
+
+#[derive(Deserialize)]
+pub struct OAuthTokenParams {
+    grant_type: String,
+    code: String,
+    redirect_uri: String,
+    client_id: String,
+    client_secret: Option<String>,
+    code_verifier: Option<String>,
+}
+
+pub async fn create_oauth_token(state: web::Data<State>, params: web::Form<OAuthTokenParams>) -> APIResult {
+    let params = params.into_inner();
+    log::info!("create_oauth_token {:?}", params.client_id);
+
+    if params.grant_type != "authorization_code" {
+        return Err(APIError::BadRequest(format!(
+            "Unsupported grant type: {}",
+            params.grant_type
+        )));
+    }
+
+    let (access_token, identity) = state
+        .iam()
+        .exchange_oauth_code(
+            &params.client_id,
+            params.client_secret.as_deref(),
+            &params.code,
+            &params.redirect_uri,
+            params.code_verifier.as_deref(),
+        )
+        .await?;
+
+    #[derive(Serialize)]
+    struct Response {
+        access_token: String,
+        token_type: &'static str,
+        expires_in: i64,
+        scope: String,
+    };
+    Ok(HttpResponse::Ok().json(Response {
+        access_token,
+        token_type: "Bearer",
+        expires_in: (identity.expires - Utc::now()).num_seconds().max(0),
+        scope: identity.scopes.join(" "),
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct OAuthTokenQueryParams {
+    token: String,
+}
+
+/// Token introspection for the resource servers (ex. gamestate), the caller is authenticated by an api key.
+pub async fn introspect_oauth_token(
+    state: web::Data<State>,
+    api_key: ApiKeyIdentity,
+    params: web::Form<OAuthTokenQueryParams>,
+) -> APIResult {
+    log::info!("introspect_oauth_token {:?}", api_key.key_id);
+
+    let response = match state.iam().introspect_oauth_token(&params.token).await? {
+        Some(identity) => IntrospectionResponse::from_identity(&identity),
+        None => IntrospectionResponse::inactive(),
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+pub async fn revoke_oauth_token(state: web::Data<State>, params: web::Form<OAuthTokenQueryParams>) -> APIResult {
+    log::info!("revoke_oauth_token");
+
+    state.iam().revoke_oauth_token(&params.token).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn verify_email(state: web::Data<State>, query: web::Path<String>) -> APIResult {
+    log::info!("verify_email");
+
+    let identity = state.iam().verify_email(&query).await?;
+    log::info!("Email verified for {}", identity.id());
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn resend_email_verification(state: web::Data<State>, identity_session: IdentitySession) -> APIResult {
+    let user_id = UserId::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
+    log::info!("resend_email_verification {:?}", user_id);
+
+    state.iam().resend_email_verification(user_id.user_id()).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[derive(Deserialize)]
+pub struct ChangePasswordParams {
+    current_password: String,
+    password: String,
+    af: String,
+}
+
+pub async fn change_password(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    af_session: AntiForgerySession,
+    params: web::Json<ChangePasswordParams>,
+) -> APIResult {
+    let params = params.into_inner();
+    let session_key = SessionKey::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
+    let user_id = UserId::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
+    log::info!("change_password {:?}", user_id);
+
+    AntiForgeryValidator::validate(&af_session, &params.af, AntiForgeryIdentity::Ignore).map_err(IAMError::from)?;
+    let current_password = ValidatedPassword::from_raw(&params.current_password)?;
+    let password = ValidatedPassword::from_raw(&params.password)?;
+
+    state
+        .iam()
+        .change_password(user_id.user_id(), session_key.key(), &current_password, &password)
+        .await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[derive(Deserialize)]
+pub struct ChangeEmailParams {
+    email: String,
+    password: String,
+    af: String,
+}
+
+pub async fn change_email(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    af_session: AntiForgerySession,
+    params: web::Json<ChangeEmailParams>,
+) -> APIResult {
+    let params = params.into_inner();
+    let user_id = UserId::from_session(&identity_session)?.ok_or(IAMError::SessionRequired)?;
+    log::info!("change_email {:?}, {:?}", user_id, params.email);
+
+    AntiForgeryValidator::validate(&af_session, &params.af, AntiForgeryIdentity::Ignore).map_err(IAMError::from)?;
+    let email = ValidatedEmail::from_raw(&params.email)?;
+    let password = ValidatedPassword::from_raw(&params.password)?;
+
+    state
+        .iam()
+        .request_email_change(user_id.user_id(), &password, &email)
+        .await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn confirm_email_change(state: web::Data<State>, query: web::Path<String>) -> APIResult {
+    log::info!("confirm_email_change");
+
+    let identity = state.iam().confirm_email_change(&query).await?;
+    log::info!("Email changed for {}", identity.id());
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForgotPasswordParams {
+    email: String,
+    af: String,
+}
+
+pub async fn forgot_password(
+    state: web::Data<State>,
+    af_session: AntiForgerySession,
+    params: web::Json<ForgotPasswordParams>,
+) -> APIResult {
+    let params = params.into_inner();
+    log::info!("forgot_password {:?}", params.email);
+
+    AntiForgeryValidator::validate(&af_session, &params.af, AntiForgeryIdentity::Ignore).map_err(IAMError::from)?;
+    let email = ValidatedEmail::from_raw(&params.email)?;
+
+    state.iam().request_password_reset(&email).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[derive(Deserialize)]
+pub struct ResetPasswordParams {
+    token: String,
+    password: String,
+    af: String,
+}
+
+pub async fn reset_password(
+    state: web::Data<State>,
+    identity_session: IdentitySession,
+    af_session: AntiForgerySession,
+    params: web::Json<ResetPasswordParams>,
+) -> APIResult {
+    let params = params.into_inner();
+    log::info!("reset_password");
+
+    AntiForgeryValidator::validate(&af_session, &params.af, AntiForgeryIdentity::Ignore).map_err(IAMError::from)?;
+    let password = ValidatedPassword::from_raw(&params.password)?;
+
+    state.iam().reset_password(&params.token, &password).await?;
+    IdentityCookie::clear(&identity_session);
+    Ok(HttpResponse::Ok().finish())
+}
+
 pub async fn create_af_token(af_session: AntiForgerySession, identity_session: IdentitySession) -> APIResult {
     let user_id = UserId::from_session(&identity_session)?.map(|u| u.name().to_owned());
     log::info!("create_af_token");
@@ -3,20 +3,32 @@ use actix_web::web;
 use data_encoding::{DecodeError, BASE64};
 use serde::{Deserialize, Serialize};
 use shine_core::{
+    configloader::{ConfigSchema, ConfigValidator},
+    grpc::{GrpcError, InternalApiConfig},
     kernel::{anti_forgery::AntiForgeryCookie, identity::IdentityCookie},
-    recaptcha::Recaptcha,
-    signed_cookie::SignedCookie,
+    localization::{Catalog, LocalizationConfig, LocalizationError},
+    metrics::{Metrics, RequestMetrics},
+    ratelimit::{RateLimit, RateLimitConfig, RateLimiter},
+    recaptcha::{CaptchaConfig, CaptchaProvider},
+    requestinfo::{ApiKeyValidatorRef, TokenValidatorRef},
+    secret::{SecretRule, SecretValidator, SecretWatcher},
+    signed_cookie::{CookieSecurity, SignedCookie, SignedCookieConfiguration, SignedCookieError},
 };
 use std::{
     cell::{Ref, RefCell},
     fmt,
+    future::Future,
+    pin::Pin,
     rc::Rc,
+    sync::Arc,
 };
 use tera::{Error as TeraError, Tera};
 
+mod grpc;
 mod iam;
 mod iam_handler;
 mod login;
+mod password_reset;
 mod registration;
 mod trace_middleware;
 mod utils;
@@ -24,6 +36,9 @@ mod utils;
 use self::iam::{IAMConfig, IAMError, IAM};
 use self::trace_middleware::Trace;
 
+/// Size limit of the json payload of the bulk requests
+const BULK_PAYLOAD_LIMIT: usize = 1024 * 1024;
+
 pub const DEFAULT_PAGE: &str = "google.com";
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -31,10 +46,78 @@ pub struct AuthConfig {
     pub iam: IAMConfig,
     pub tera_templates: String,
     pub web_folder: String,
+    /// Secret of the captcha provider
     pub recaptcha_secret: String,
+    /// Site key of the captcha provider
     pub recaptcha_site_key: String,
+    #[serde(default)]
+    pub captcha: CaptchaConfig,
     pub id_session_secret: String,
     pub af_session_secret: String,
+    /// Attributes and previous secrets of the identity cookie
+    #[serde(default)]
+    pub id_session: SignedCookieConfiguration,
+    /// Attributes and previous secrets of the anti-forgery cookie
+    #[serde(default)]
+    pub af_session: SignedCookieConfiguration,
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    /// Message catalog of the pages and the error messages
+    #[serde(default)]
+    pub localization: LocalizationConfig,
+}
+
+impl AuthConfig {
+    /// Run without any external service: the stores are kept in memory, the captcha is not checked,
+    /// the ip location is disabled and the mails are logged.
+    pub fn use_offline_services(&mut self) {
+        self.captcha = CaptchaConfig::Disabled;
+        self.iam.use_offline_services();
+    }
+}
+
+impl ConfigSchema for AuthConfig {
+    fn validate(&self, validator: &mut ConfigValidator) {
+        validator.non_empty("tera_templates", &self.tera_templates);
+        validator.non_empty("web_folder", &self.web_folder);
+        validator.non_empty("localization.default_language", &self.localization.default_language);
+        validator.scope("iam", |validator| self.iam.validate(validator));
+    }
+
+    /// Resolve the secrets given by reference and validate all the secrets in use. The session secrets are
+    /// keys of at least 32 bytes as required by the cookie signing.
+    fn check_secrets<'a>(&'a mut self, validator: &'a mut SecretValidator) -> Pin<Box<dyn Future<Output = ()> + 'a>> {
+        Box::pin(async move {
+            if !matches!(self.captcha, CaptchaConfig::Disabled) {
+                validator
+                    .check("auth.recaptcha_secret", &mut self.recaptcha_secret, SecretRule::text(1))
+                    .await;
+            }
+            validator
+                .check(
+                    "auth.id_session_secret",
+                    &mut self.id_session_secret,
+                    SecretRule::key(32),
+                )
+                .await;
+            validator
+                .check(
+                    "auth.af_session_secret",
+                    &mut self.af_session_secret,
+                    SecretRule::key(32),
+                )
+                .await;
+            for (i, secret) in self.id_session.previous_secrets.iter_mut().enumerate() {
+                let name = format!("auth.id_session.previous_secrets[{}]", i);
+                validator.check(&name, secret, SecretRule::key(32)).await;
+            }
+            for (i, secret) in self.af_session.previous_secrets.iter_mut().enumerate() {
+                let name = format!("auth.af_session.previous_secrets[{}]", i);
+                validator.check(&name, secret, SecretRule::key(32)).await;
+            }
+            self.iam.check_secrets(validator).await;
+        })
+    }
 }
 
 #[derive(Debug)]
@@ -42,6 +125,8 @@ pub enum AuthCreateError {
     ConfigureTera(TeraError),
     ConfigureIAM(IAMError),
     ConfigureDecodeSecret(DecodeError),
+    ConfigureCookie(SignedCookieError),
+    ConfigureLocalization(LocalizationError),
 }
 
 impl fmt::Display for AuthCreateError {
@@ -50,6 +135,8 @@ impl fmt::Display for AuthCreateError {
             AuthCreateError::ConfigureTera(err) => write!(f, "Error in tera configuration: {:?}", err),
             AuthCreateError::ConfigureIAM(err) => write!(f, "Error in IAM configuration: {:?}", err),
             AuthCreateError::ConfigureDecodeSecret(err) => write!(f, "Error during secret configuration: {:?}", err),
+            AuthCreateError::ConfigureCookie(err) => write!(f, "Error in cookie configuration: {}", err),
+            AuthCreateError::ConfigureLocalization(err) => write!(f, "Error in localization: {}", err),
         }
     }
 }
@@ -57,20 +144,22 @@ impl fmt::Display for AuthCreateError {
 struct StateInner {
     web_root: String,
     tera: RefCell<Tera>,
+    catalog: Catalog,
     iam: IAM,
-    recaptcha: Recaptcha,
+    captcha: Arc<dyn CaptchaProvider>,
 }
 
 #[derive(Clone)]
 pub struct State(Rc<StateInner>);
 
 impl State {
-    pub fn new(web_root: String, tera: Tera, iam: IAM, recaptcha: Recaptcha) -> Self {
+    pub fn new(web_root: String, tera: Tera, catalog: Catalog, iam: IAM, captcha: Arc<dyn CaptchaProvider>) -> Self {
         Self(Rc::new(StateInner {
             web_root,
             tera: RefCell::new(tera),
+            catalog,
             iam,
-            recaptcha,
+            captcha,
         }))
     }
 
@@ -93,36 +182,77 @@ impl State {
             })
     }
 
+    pub fn catalog(&self) -> &Catalog {
+        &self.0.catalog
+    }
+
     pub fn iam(&self) -> &IAM {
         &self.0.iam
     }
 
-    pub fn recaptcha(&self) -> &Recaptcha {
-        &self.0.recaptcha
+    pub fn captcha(&self) -> &dyn CaptchaProvider {
+        &*self.0.captcha
+    }
+}
+
+/// Rate limiters of the brute-forceable endpoints, each endpoint has its own buckets
+#[derive(Clone)]
+struct RateLimiters {
+    login: RateLimiter,
+    register: RateLimiter,
+    guest: RateLimiter,
+    refresh_key: RateLimiter,
+    oauth_token: RateLimiter,
+}
+
+impl RateLimiters {
+    fn new(config: &RateLimitConfig) -> RateLimiters {
+        RateLimiters {
+            login: RateLimiter::new(config.clone()),
+            register: RateLimiter::new(config.clone()),
+            guest: RateLimiter::new(config.clone()),
+            refresh_key: RateLimiter::new(config.clone()),
+            oauth_token: RateLimiter::new(config.clone()),
+        }
     }
 }
 
 #[derive(Clone)]
 pub struct AuthService {
     tera: Tera,
+    catalog: Catalog,
     iam: IAM,
-    recaptcha: Recaptcha,
+    captcha: Arc<dyn CaptchaProvider>,
     web_folder: String,
     web_root: String,
-    id_session_secret: Vec<u8>,
-    af_session_secret: Vec<u8>,
+    id_session: (CookieSecurity, SignedCookieConfiguration),
+    af_session: (CookieSecurity, SignedCookieConfiguration),
+    rate_limiters: RateLimiters,
+    metrics: Metrics,
 }
 
 impl AuthService {
-    pub fn create(sys: &mut SystemRunner, config: &AuthConfig, web_root: &str) -> Result<AuthService, AuthCreateError> {
+    pub fn create(
+        sys: &mut SystemRunner,
+        config: &AuthConfig,
+        web_root: &str,
+        metrics: &Metrics,
+    ) -> Result<AuthService, AuthCreateError> {
         log::info!("Parsing tera templates");
-        let tera = Tera::new(&config.tera_templates).map_err(|err| AuthCreateError::ConfigureTera(err.into()))?;
+        let mut tera = Tera::new(&config.tera_templates).map_err(|err| AuthCreateError::ConfigureTera(err.into()))?;
+        let catalog = Catalog::new(&config.localization).map_err(AuthCreateError::ConfigureLocalization)?;
+        catalog.register_tera_function(&mut tera);
 
-        let recaptcha = Recaptcha::new(config.recaptcha_secret.clone(), config.recaptcha_site_key.clone());
+        let captcha: Arc<dyn CaptchaProvider> = Arc::from(
+            config
+                .captcha
+                .create_provider(config.recaptcha_secret.clone(), config.recaptcha_site_key.clone()),
+        );
+        log::info!("Captcha: {:?}", config.captcha);
 
         let iam_config = config.iam.clone();
         let iam = sys
-            .block_on(IAM::new(iam_config))
+            .block_on(IAM::new(iam_config, tera.clone(), metrics.clone()))
             .map_err(|err| AuthCreateError::ConfigureIAM(err.into()))?;
         let id_session_secret = BASE64
             .decode(config.id_session_secret.as_bytes())
@@ -130,31 +260,94 @@ impl AuthService {
         let af_session_secret = BASE64
             .decode(config.af_session_secret.as_bytes())
             .map_err(|err| AuthCreateError::ConfigureDecodeSecret(err.into()))?;
+        let id_session = config
+            .id_session
+            .security(&id_session_secret)
+            .map_err(AuthCreateError::ConfigureCookie)?;
+        let af_session = config
+            .af_session
+            .security(&af_session_secret)
+            .map_err(AuthCreateError::ConfigureCookie)?;
 
         Ok(AuthService {
             iam,
             tera,
-            recaptcha,
+            catalog,
+            captcha,
             web_folder: config.web_folder.clone(),
             web_root: web_root.to_owned(),
-            id_session_secret,
-            af_session_secret,
+            id_session: (id_session, config.id_session.clone()),
+            af_session: (af_session, config.af_session.clone()),
+            rate_limiters: RateLimiters::new(&config.rate_limit),
+            metrics: metrics.clone(),
         })
     }
 
+    /// Validator of the api keys for the service-to-service authentication.
+    pub fn api_key_validator(&self) -> ApiKeyValidatorRef {
+        Arc::new(self.iam.clone())
+    }
+
+    /// Validator of the OAuth access tokens issued by the service.
+    pub fn token_validator(&self) -> TokenValidatorRef {
+        Arc::new(self.iam.clone())
+    }
+
+    /// Start the internal gRPC api used by the other backend services on a dedicated thread.
+    pub fn start_internal_api(&self, config: &InternalApiConfig) -> Result<(), GrpcError> {
+        grpc::start_internal_api(self.iam.clone(), config)
+    }
+
+    /// Handle the rotation of the secrets fetched from the SecretProvider. The cookie secrets are added to the
+    /// keyrings thus the existing sessions remain valid, the other secrets take effect when the service is
+    /// restarted.
+    pub fn watch_secret_rotation(&self, watcher: &SecretWatcher) {
+        for (config_name, (security, configuration)) in &[
+            ("auth.id_session_secret", self.id_session.clone()),
+            ("auth.af_session_secret", self.af_session.clone()),
+        ] {
+            let security = security.clone();
+            let configuration = configuration.clone();
+            watcher.on_rotation(config_name, move |rotation| {
+                if let Err(err) = configuration.rotate(&security, &rotation.value) {
+                    log::error!("Failed to rotate {}: {}", rotation.config_name, err);
+                }
+            });
+        }
+
+        watcher.on_rotation("auth.iam.storage_account_key", |rotation| {
+            log::warn!(
+                "Secret {} has been rotated, restart the service to apply it",
+                rotation.config_name
+            )
+        });
+    }
+
     pub fn configure(&self, services: &mut web::ServiceConfig) {
         let state = State::new(
             self.web_root.clone(),
             self.tera.clone(),
+            self.catalog.clone(),
             self.iam.clone(),
-            self.recaptcha.clone(),
+            self.captcha.clone(),
         );
 
+        // api keys are validated application wide, thus other services can also use the ApiKeyIdentity extractor
+        services.data(self.api_key_validator());
+        services.data(self.token_validator());
+
         services.service(
             web::scope(&self.web_root)
                 .wrap(Trace::new(state.clone()))
-                .wrap(SignedCookie::new(IdentityCookie::write(&self.id_session_secret), ()))
-                .wrap(SignedCookie::new(AntiForgeryCookie::new(&self.af_session_secret), ()))
+                .wrap(RequestMetrics::new(self.metrics.clone(), "auth"))
+                .wrap(SignedCookie::new(
+                    IdentityCookie::with_configuration(self.id_session.0.clone(), self.id_session.1.clone(), false),
+                    (),
+                ))
+                .wrap(SignedCookie::new(
+                    AntiForgeryCookie::with_configuration(self.af_session.0.clone(), self.af_session.1.clone()),
+                    (),
+                ))
                 .data(state)
                 .service(actix_files::Files::new("/static", &self.web_folder))
                 .service(
@@ -168,25 +361,109 @@ impl AuthService {
                             web::resource("{lang}/login.html")
                                 .route(web::get().to(login::get_login_page))
                                 .route(web::post().to(login::post_login_page)),
+                        )
+                        .service(
+                            web::resource("{lang}/forgot.html").route(web::get().to(password_reset::get_forgot_page)),
+                        )
+                        .service(
+                            web::resource("{lang}/reset.html").route(web::get().to(password_reset::get_reset_page)),
                         ),
                 )
                 .service(
                     web::scope("api")
                         .service(web::resource("af").route(web::post().to(iam_handler::create_af_token)))
+                        .service(
+                            web::resource("entitlements/key").route(web::get().to(iam_handler::get_entitlement_key)),
+                        )
+                        .service(
+                            web::resource("automation/tokens")
+                                .route(web::post().to(iam_handler::create_automation_token)),
+                        )
                         .service(
                             web::scope("users")
-                                .service(web::resource("login").route(web::post().to(iam_handler::login_basic_auth)))
-                                .service(web::resource("register").route(web::post().to(iam_handler::register_user)))
+                                .service(
+                                    web::resource("login")
+                                        .wrap(RateLimit::new(self.rate_limiters.login.clone()))
+                                        .route(web::post().to(iam_handler::login_basic_auth)),
+                                )
+                                .service(
+                                    web::resource("register")
+                                        .wrap(RateLimit::new(self.rate_limiters.register.clone()))
+                                        .route(web::post().to(iam_handler::register_user)),
+                                )
+                                .service(
+                                    web::resource("guest")
+                                        .wrap(RateLimit::new(self.rate_limiters.guest.clone()))
+                                        .route(web::post().to(iam_handler::register_guest)),
+                                )
+                                .service(web::resource("promote").route(web::post().to(iam_handler::promote_guest)))
                                 .service(web::resource("refresh").route(web::post().to(iam_handler::refresh_session)))
                                 .service(web::resource("validate").route(web::post().to(iam_handler::validate_session)))
                                 .service(
                                     web::resource("refresh_key")
+                                        .wrap(RateLimit::new(self.rate_limiters.refresh_key.clone()))
                                         .route(web::post().to(iam_handler::refresh_session_by_key)),
                                 )
                                 .service(web::resource("logout").route(web::post().to(iam_handler::logout)))
+                                .service(web::resource("forgot").route(web::post().to(iam_handler::forgot_password)))
+                                .service(web::resource("reset").route(web::post().to(iam_handler::reset_password)))
+                                .service(
+                                    web::resource("verify")
+                                        .route(web::post().to(iam_handler::resend_email_verification)),
+                                )
+                                .service(
+                                    web::resource("verify/{token}").route(web::get().to(iam_handler::verify_email)),
+                                )
+                                .service(web::resource("password").route(web::post().to(iam_handler::change_password)))
+                                .service(web::resource("email").route(web::post().to(iam_handler::change_email)))
+                                .service(
+                                    web::resource("email/{token}")
+                                        .route(web::get().to(iam_handler::confirm_email_change)),
+                                )
+                                .service(web::resource("sessions").route(web::get().to(iam_handler::get_sessions)))
+                                .service(web::resource("me").route(web::delete().to(iam_handler::delete_user)))
+                                .service(web::resource("me/export").route(web::get().to(iam_handler::export_user)))
+                                .service(
+                                    web::resource("me/notifications")
+                                        .route(web::get().to(iam_handler::stream_notifications)),
+                                )
+                                .service(
+                                    web::resource("me/entitlements")
+                                        .route(web::get().to(iam_handler::get_my_entitlements)),
+                                )
+                                .service(
+                                    web::resource("me/entitlements/token")
+                                        .route(web::post().to(iam_handler::create_entitlement_token)),
+                                )
+                                .service(web::resource("purge").route(web::post().to(iam_handler::purge_users)))
+                                .service(
+                                    web::resource("import")
+                                        .data(web::JsonConfig::default().limit(BULK_PAYLOAD_LIMIT))
+                                        .route(web::post().to(iam_handler::import_users)),
+                                )
+                                .service(web::resource("search").route(web::get().to(iam_handler::search_users)))
+                                .service(
+                                    web::resource("sessions/{session}")
+                                        .route(web::delete().to(iam_handler::revoke_session)),
+                                )
+                                .service(web::resource("/{user}").route(web::get().to(iam_handler::get_user)))
+                                .service(
+                                    web::resource("/{user}/ban")
+                                        .route(web::post().to(iam_handler::ban_user))
+                                        .route(web::delete().to(iam_handler::unban_user)),
+                                )
                                 .service(
                                     web::resource("/{user}/roles").route(web::get().to(iam_handler::get_user_roles)),
                                 )
+                                .service(
+                                    web::resource("/{user}/entitlements")
+                                        .route(web::get().to(iam_handler::get_user_entitlements)),
+                                )
+                                .service(
+                                    web::resource("/{user}/entitlements/{content}")
+                                        .route(web::post().to(iam_handler::grant_entitlement))
+                                        .route(web::delete().to(iam_handler::revoke_entitlement)),
+                                )
                                 .service(
                                     web::resource("/{user}/roles/{role}")
                                         .route(web::post().to(iam_handler::add_user_role)),
@@ -196,14 +473,69 @@ impl AuthService {
                                         .route(web::delete().to(iam_handler::remove_user_role)),
                                 ),
                         )
+                        .service(
+                            web::scope("keys")
+                                .service(
+                                    web::resource("")
+                                        .route(web::get().to(iam_handler::get_api_keys))
+                                        .route(web::post().to(iam_handler::create_api_key)),
+                                )
+                                .service(web::resource("/{key}").route(web::delete().to(iam_handler::revoke_api_key))),
+                        )
+                        .service(
+                            web::scope("oauth")
+                                .service(
+                                    web::resource("clients")
+                                        .route(web::get().to(iam_handler::get_oauth_clients))
+                                        .route(web::post().to(iam_handler::register_oauth_client)),
+                                )
+                                .service(
+                                    web::resource("clients/{client}")
+                                        .route(web::delete().to(iam_handler::revoke_oauth_client)),
+                                )
+                                .service(
+                                    web::resource("authorize")
+                                        .route(web::post().to(iam_handler::authorize_oauth_client)),
+                                )
+                                .service(
+                                    web::resource("token")
+                                        .wrap(RateLimit::new(self.rate_limiters.oauth_token.clone()))
+                                        .route(web::post().to(iam_handler::create_oauth_token)),
+                                )
+                                .service(
+                                    web::resource("introspect")
+                                        .route(web::post().to(iam_handler::introspect_oauth_token)),
+                                )
+                                .service(
+                                    web::resource("revoke").route(web::post().to(iam_handler::revoke_oauth_token)),
+                                ),
+                        )
                         .service(
                             web::scope("roles")
                                 .service(web::resource("").route(web::get().to(iam_handler::get_roles)))
+                                .service(
+                                    web::resource("/bulk_assign")
+                                        .data(web::JsonConfig::default().limit(BULK_PAYLOAD_LIMIT))
+                                        .route(web::post().to(iam_handler::bulk_assign_roles)),
+                                )
                                 .service(
                                     web::resource("/{role}")
                                         .route(web::post().to(iam_handler::create_role))
                                         .route(web::delete().to(iam_handler::delete_role)),
                                 )
+                                .service(
+                                    web::resource("/{role}/effective")
+                                        .route(web::get().to(iam_handler::get_effective_roles)),
+                                )
+                                .service(
+                                    web::resource("/{role}/permissions")
+                                        .route(web::get().to(iam_handler::get_role_permissions)),
+                                )
+                                .service(
+                                    web::resource("/{role}/permissions/{permission}")
+                                        .route(web::post().to(iam_handler::add_role_permission))
+                                        .route(web::delete().to(iam_handler::remove_role_permission)),
+                                )
                                 .service(
                                     web::resource("/{role}/inherit/{inherited_role}")
                                         .route(web::post().to(iam_handler::inherit_role))
@@ -16,6 +16,9 @@ use shine_core::{
 };
 use tera::Tera;
 
+/// Action scope of the anti-forgery tokens of the login form
+const AF_SCOPE: &str = "login";
+
 #[derive(Debug)]
 pub enum LoginError {
     Username,
@@ -41,6 +44,7 @@ pub struct LoginRedirect {
 struct Keys {
     af: String,
     recaptcha_site_key: String,
+    captcha_provider: &'static str,
 }
 
 async fn validate_input(
@@ -56,7 +60,11 @@ async fn validate_input(
         .map_err(|_err| errors.push(LoginError::Password))
         .ok();
 
-    if let Err(_err) = state.recaptcha().check_response(&params.recaptcha_response).await {
+    if let Err(_err) = state
+        .captcha()
+        .check_response(&params.recaptcha_response, "login")
+        .await
+    {
         errors.push(LoginError::Recaptcha);
     }
 
@@ -82,6 +90,7 @@ fn gen_page(
     context.insert("user", "");
     context.insert("af_token", &keys.af);
     context.insert("recaptcha_site_key", &keys.recaptcha_site_key);
+    context.insert("captcha_provider", keys.captcha_provider);
 
     if let Some(ref redirect) = redirect.redirect {
         context.insert("redirect", &redirect);
@@ -115,14 +124,17 @@ fn gen_page(
 
 pub async fn get_login_page(
     state: web::Data<State>,
+    req: HttpRequest,
     af_session: AntiForgerySession,
-    lang: web::Path<String>,
+    path_lang: web::Path<String>,
     redirect: web::Query<LoginRedirect>,
 ) -> PageResult {
+    let lang = state.catalog().negotiate(&req, Some(path_lang.as_str()));
     log::info!("get_login_page {:?}", redirect);
     let keys = Keys {
-        af: AntiForgeryIssuer::issue(&af_session, None),
-        recaptcha_site_key: state.recaptcha().site_key().to_owned(),
+        af: AntiForgeryIssuer::issue_scoped(&af_session, AF_SCOPE, None),
+        recaptcha_site_key: state.captcha().site_key().to_owned(),
+        captcha_provider: state.captcha().name(),
     };
     gen_page(state.web_root(), &*state.tera(), &*lang, &keys, &*redirect, None)
 }
@@ -133,20 +145,23 @@ pub async fn post_login_page(
     remote_info: RemoteInfo,
     identity_session: IdentitySession,
     af_session: AntiForgerySession,
-    lang: web::Path<String>,
+    path_lang: web::Path<String>,
     redirect: web::Query<LoginRedirect>,
     login_params: web::Form<LoginParams>,
 ) -> PageResult {
+    let lang = state.catalog().negotiate(&req, Some(path_lang.as_str()));
     let params = login_params.into_inner();
     let fingerprint = state.iam().get_fingerprint(&remote_info).await?;
     log::info!("post_login_page {:?} {:?} {:?}", redirect, params, fingerprint);
 
     let keys = Keys {
-        af: AntiForgeryValidator::validate(&af_session, &params.af, AntiForgeryIdentity::Ignore).map_err(|err| {
-            let uri = format!("login.html?{}", req.query_string());
-            PageError::RedirectOnError(format!("AF error: {:?}", err), Redirect::SeeOther(uri))
-        })?,
-        recaptcha_site_key: state.recaptcha().site_key().to_owned(),
+        af: AntiForgeryValidator::validate_scoped(&af_session, Some(AF_SCOPE), &params.af, AntiForgeryIdentity::Ignore)
+            .map_err(|err| {
+                let uri = format!("login.html?{}", req.query_string());
+                PageError::RedirectOnError(format!("AF error: {:?}", err), Redirect::SeeOther(uri))
+            })?,
+        recaptcha_site_key: state.captcha().site_key().to_owned(),
+        captcha_provider: state.captcha().name(),
     };
 
     IdentityCookie::clear(&identity_session);
@@ -186,7 +201,7 @@ pub async fn post_login_page(
             let errors = match err {
                 IAMError::IdentityNotFound => vec![LoginError::Username],
                 IAMError::PasswordNotMatching => vec![LoginError::Password],
-                err => vec![LoginError::Server(format!("server_error:{:?}", err))],
+                err => vec![LoginError::Server(err.localized_message(state.catalog(), &lang))],
             };
             gen_page(
                 state.web_root(),
@@ -0,0 +1,60 @@
+use super::State;
+use actix_web::{web, HttpRequest, HttpResponse};
+use serde::Deserialize;
+use shine_core::kernel::{
+    anti_forgery::{AntiForgeryIssuer, AntiForgerySession},
+    response::{PageError, PageResult},
+};
+use tera::Tera;
+
+#[derive(Debug, Deserialize)]
+pub struct ResetQuery {
+    token: Option<String>,
+}
+
+fn gen_page(web_root: &str, tera: &Tera, page: &str, lang: &str, af: &str, token: Option<&str>) -> PageResult {
+    let mut context = tera::Context::new();
+    context.insert("root", &format!("/{}", web_root));
+    context.insert("lang", lang);
+    context.insert("af_token", af);
+    context.insert("token", token.unwrap_or(""));
+
+    let html = tera.render(page, &context).map_err(|err| {
+        log::error!("Tera render error: {:?}", err);
+        PageError::Internal("Template error".to_owned())
+    })?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+pub async fn get_forgot_page(
+    state: web::Data<State>,
+    req: HttpRequest,
+    af_session: AntiForgerySession,
+    path_lang: web::Path<String>,
+) -> PageResult {
+    let lang = state.catalog().negotiate(&req, Some(path_lang.as_str()));
+    log::info!("get_forgot_page");
+    let af = AntiForgeryIssuer::issue(&af_session, None);
+    gen_page(state.web_root(), &*state.tera(), "forgot.html", &*lang, &af, None)
+}
+
+pub async fn get_reset_page(
+    state: web::Data<State>,
+    req: HttpRequest,
+    af_session: AntiForgerySession,
+    path_lang: web::Path<String>,
+    query: web::Query<ResetQuery>,
+) -> PageResult {
+    let lang = state.catalog().negotiate(&req, Some(path_lang.as_str()));
+    log::info!("get_reset_page");
+    let af = AntiForgeryIssuer::issue(&af_session, None);
+    gen_page(
+        state.web_root(),
+        &*state.tera(),
+        "reset.html",
+        &*lang,
+        &af,
+        query.token.as_deref(),
+    )
+}
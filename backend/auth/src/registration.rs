@@ -1,7 +1,7 @@
 use super::iam::{
     identity::{
-        EmailValidationError, NameValidationError, PasswordValidationError, ValidatedEmail, ValidatedName,
-        ValidatedPassword,
+        EmailValidationError, NameValidationError, PasswordPolicyConfig, PasswordPolicyViolation,
+        PasswordValidationError, ValidatedEmail, ValidatedName, ValidatedPassword,
     },
     IAMError,
 };
@@ -19,6 +19,9 @@ use shine_core::{
 };
 use tera::Tera;
 
+/// Action scope of the anti-forgery tokens of the register form
+const AF_SCOPE: &str = "register";
+
 #[derive(Debug)]
 pub enum RegistrationError {
     UsernameTooShort,
@@ -31,6 +34,7 @@ pub enum RegistrationError {
     PasswordTooShort,
     PasswordTooLong,
     PasswordTooWeek,
+    PasswordPolicy(Vec<PasswordPolicyViolation>),
     Recaptcha,
     TermsMissing,
     Server(String),
@@ -55,6 +59,7 @@ pub struct RegisterRedirect {
 struct Keys {
     af: String,
     recaptcha_site_key: String,
+    captcha_provider: &'static str,
 }
 
 async fn validate_input(
@@ -104,7 +109,11 @@ async fn validate_input(
         errors.push(RegistrationError::TermsMissing);
     }
 
-    if let Err(_err) = state.recaptcha().check_response(&params.recaptcha_response).await {
+    if let Err(_err) = state
+        .captcha()
+        .check_response(&params.recaptcha_response, "register")
+        .await
+    {
         errors.push(RegistrationError::Recaptcha);
     }
 
@@ -118,6 +127,7 @@ async fn validate_input(
 fn gen_page(
     web_root: &str,
     tera: &Tera,
+    password_policy: &PasswordPolicyConfig,
     lang: &str,
     keys: &Keys,
     redirect: &RegisterRedirect,
@@ -131,12 +141,18 @@ fn gen_page(
     context.insert("user_max_len", &format!("{}", ValidatedName::MAX_LEN));
     context.insert("password_min_len", &format!("{}", ValidatedPassword::MIN_LEN));
     context.insert("password_max_len", &format!("{}", ValidatedPassword::MAX_LEN));
+    context.insert(
+        "password_min_classes",
+        &format!("{}", password_policy.min_character_classes),
+    );
+    context.insert("password_policy", &Vec::<PasswordPolicyViolation>::new());
 
     context.insert("user", "");
     context.insert("email", "");
     context.insert("password", "");
     context.insert("af_token", &keys.af);
     context.insert("recaptcha_site_key", &keys.recaptcha_site_key);
+    context.insert("captcha_provider", keys.captcha_provider);
 
     if let Some(ref redirect) = redirect.redirect {
         context.insert("redirect", &redirect);
@@ -173,6 +189,12 @@ fn gen_page(
                 RegistrationError::PasswordTooLong => context.insert("password_validity", "err:too_long"),
                 RegistrationError::PasswordTooShort => context.insert("password_validity", "err:too_short"),
                 RegistrationError::PasswordTooWeek => context.insert("password_validity", "err:too_week"),
+                RegistrationError::PasswordPolicy(ref violations) => {
+                    if let Some(violation) = violations.first() {
+                        context.insert("password_validity", &format!("err:{}", violation.key()));
+                    }
+                    context.insert("password_policy", violations);
+                }
                 RegistrationError::Recaptcha => context.insert("recaptcha_validity", "err:missing"),
                 RegistrationError::TermsMissing => context.insert("terms_validity", "err:missing"),
                 RegistrationError::Server(ref err) => context.insert("server_validity", &format!("err:{}", err)),
@@ -190,16 +212,27 @@ fn gen_page(
 
 pub async fn get_register_page(
     state: web::Data<State>,
+    req: HttpRequest,
     af_session: AntiForgerySession,
-    lang: web::Path<String>,
+    path_lang: web::Path<String>,
     redirect: web::Query<RegisterRedirect>,
 ) -> PageResult {
+    let lang = state.catalog().negotiate(&req, Some(path_lang.as_str()));
     log::info!("get_register_page");
     let keys = Keys {
-        af: AntiForgeryIssuer::issue(&af_session, None),
-        recaptcha_site_key: state.recaptcha().site_key().to_owned(),
+        af: AntiForgeryIssuer::issue_scoped(&af_session, AF_SCOPE, None),
+        recaptcha_site_key: state.captcha().site_key().to_owned(),
+        captcha_provider: state.captcha().name(),
     };
-    gen_page(state.web_root(), &*state.tera(), &*lang, &keys, &*redirect, None)
+    gen_page(
+        state.web_root(),
+        &*state.tera(),
+        state.iam().password_policy(),
+        &*lang,
+        &keys,
+        &*redirect,
+        None,
+    )
 }
 
 pub async fn post_register_page(
@@ -208,20 +241,23 @@ pub async fn post_register_page(
     remote_info: RemoteInfo,
     identity_session: IdentitySession,
     af_session: AntiForgerySession,
-    lang: web::Path<String>,
+    path_lang: web::Path<String>,
     redirect: web::Query<RegisterRedirect>,
     registration_params: web::Form<RegistrationParams>,
 ) -> PageResult {
+    let lang = state.catalog().negotiate(&req, Some(path_lang.as_str()));
     let params = registration_params.into_inner();
     let fingerprint = state.iam().get_fingerprint(&remote_info).await?;
     log::info!("post_register_user {:?} {:?}", params, fingerprint);
 
     let keys = Keys {
-        af: AntiForgeryValidator::validate(&af_session, &params.af, AntiForgeryIdentity::Ignore).map_err(|err| {
-            let uri = format!("register.html?{}", req.query_string());
-            PageError::RedirectOnError(format!("AF error: {:?}", err), Redirect::SeeOther(uri))
-        })?,
-        recaptcha_site_key: state.recaptcha().site_key().to_owned(),
+        af: AntiForgeryValidator::validate_scoped(&af_session, Some(AF_SCOPE), &params.af, AntiForgeryIdentity::Ignore)
+            .map_err(|err| {
+                let uri = format!("register.html?{}", req.query_string());
+                PageError::RedirectOnError(format!("AF error: {:?}", err), Redirect::SeeOther(uri))
+            })?,
+        recaptcha_site_key: state.captcha().site_key().to_owned(),
+        captcha_provider: state.captcha().name(),
     };
 
     IdentityCookie::clear(&identity_session);
@@ -232,6 +268,7 @@ pub async fn post_register_page(
             return gen_page(
                 state.web_root(),
                 &*state.tera(),
+                state.iam().password_policy(),
                 &*lang,
                 &keys,
                 &*redirect,
@@ -248,11 +285,13 @@ pub async fn post_register_page(
             let errors = match err {
                 IAMError::NameTaken => vec![RegistrationError::UsernameAlreadyTaken],
                 IAMError::EmailTaken => vec![RegistrationError::EmailAlreadyTaken],
-                err => vec![RegistrationError::Server(format!("server_error:{:?}", err))],
+                IAMError::PasswordPolicy(violations) => vec![RegistrationError::PasswordPolicy(violations)],
+                err => vec![RegistrationError::Server(err.localized_message(state.catalog(), &lang))],
             };
             return gen_page(
                 state.web_root(),
                 &*state.tera(),
+                state.iam().password_policy(),
                 &*lang,
                 &keys,
                 &*redirect,
@@ -0,0 +1,11 @@
+tr = {
+    title: "Forgot Password",
+    success: "If the email is registered, a password reset link has been sent to it.",
+
+    errors: {
+        missing_email: "Please provide your email address",
+        server: "Failed to request password reset, please try again later.",
+    },
+
+    translate: (t) => t
+}
@@ -20,7 +20,9 @@ tr = {
             missing: "Provide password",
             too_short: "Too short, required min length: " + cfg.password.min_len,
             too_long: "Too long, required max length: " + cfg.password.max_len,
-            too_week: "Password is too week"
+            too_week: "Password is too week",
+            character_classes: "Use at least " + cfg.password.min_classes + " of lowercase, uppercase, digit and symbol characters",
+            breached: "This password appeared in a data breach, choose a different one"
         },
 
         terms: {
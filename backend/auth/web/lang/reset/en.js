@@ -0,0 +1,12 @@
+tr = {
+    title: "Reset Password",
+    success: "Your password has been changed, please login with the new password.",
+
+    errors: {
+        missing_password: "Provide password",
+        password_mismatch: "Passwords do not match",
+        server: "The reset link is invalid or has expired.",
+    },
+
+    translate: (t) => t
+}
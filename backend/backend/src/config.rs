@@ -1,27 +1,67 @@
-use config::{self, ConfigError};
 use serde::{Deserialize, Serialize};
 use shine_auth::AuthConfig;
+use shine_core::configloader::{ConfigArgs, ConfigLoadError, ConfigLoader, ConfigSchema, ConfigValidator};
+use shine_core::grpc::InternalApiConfig;
+use shine_core::secret::{SecretProviderConfig, SecretValidator};
 use shine_gamestate::GameStateConfig;
 use shine_web::WebConfig;
 use std::env;
-use std::path::Path;
+use std::future::Future;
+use std::pin::Pin;
+
+/// Settings of the dev-offline mode used when they are not given explicitly, these are not secrets and
+/// must never be used in production.
+const DEV_OFFLINE_DEFAULTS: &[(&str, &str)] = &[
+    ("auth.recaptcha_secret", ""),
+    ("auth.recaptcha_site_key", ""),
+    (
+        "auth.id_session_secret",
+        "ZGV2LW9mZmxpbmUgaWRlbnRpdHkgc2Vzc2lvbiBzZWNyZXQsIG5vdCBmb3IgcHJvZHVjdGlvbiB1c2UhISEhIQ==",
+    ),
+    (
+        "auth.af_session_secret",
+        "ZGV2LW9mZmxpbmUgYW50aS1mb3JnZXJ5IHNlc3Npb24gc2VjcmV0LCBub3QgZm9yIHByb2R1Y3Rpb24gdXNlISE=",
+    ),
+    ("auth.iam.password_pepper", "dev-offline-pepper"),
+    (
+        "auth.iam.automation_signing_key",
+        "ZGV2LW9mZmxpbmUgYXV0b21hdGlvbiBzaWduaW5nIGtleSwgbm90IGZvciBwcm9kdWN0aW9uIHVzZSEh",
+    ),
+];
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Config {
+    /// Replace the external services (storage, captcha, ip location, mail) by local implementations, thus
+    /// the backend can be started without any credential.
+    #[serde(default)]
+    pub dev_offline: bool,
     pub bind_host: String,
     pub bind_port: u16,
     pub worker_count: usize,
+    /// The internal gRPC api between the services, disabled if not set
+    #[serde(default)]
+    pub internal_api: Option<InternalApiConfig>,
     pub auth: AuthConfig,
     pub web: WebConfig,
     pub gamestate: GameStateConfig,
+    /// Provider of the secrets given as `secret:<name>`
+    #[serde(default)]
+    pub secret_provider: Option<SecretProviderConfig>,
+    /// Minutes between the checks of the secrets of the provider for rotation
+    #[serde(default = "Config::default_secret_rotation_check_m")]
+    pub secret_rotation_check_m: u64,
 }
 
 impl Config {
-    pub fn new() -> Result<Self, ConfigError> {
-        use config::{Environment, File, FileFormat};
-        let mut s = config::Config::new();
+    fn default_secret_rotation_check_m() -> u64 {
+        10
+    }
 
-        s.merge(File::from_str(
+    /// Load the configuration from the files and overrides given on the command line and from the environment
+    pub fn new() -> Result<Self, ConfigLoadError> {
+        let args = ConfigArgs::parse(env::args().skip(1))?;
+        let mut loader = ConfigLoader::from_args(&args)?;
+        loader.set_defaults_json(
             r#"
             {
                 "bind_host": "0.0.0.0",
@@ -29,23 +69,40 @@ impl Config {
                 "worker_count": "4"
             }
             "#,
-            FileFormat::Json,
-        ))?;
-
-        s.merge(Environment::new().separator("--"))?;
+        )?;
 
-        if let Some(config_file) = env::args().skip(1).next() {
-            log::info!("Loading cofig file {:?}", config_file);
-            match s.merge(File::from(Path::new(&config_file))) {
-                Ok(_) => {}
-                Err(err) => log::warn!("Faild to parse secret config: {}", err),
-            };
+        let dev_offline = loader.get_bool("dev_offline").unwrap_or(false);
+        if dev_offline {
+            for (key, value) in DEV_OFFLINE_DEFAULTS {
+                loader.set_default(key, *value)?;
+            }
         }
 
-        s.try_into()
+        let mut config: Config = loader.load()?;
+        if dev_offline {
+            config.auth.use_offline_services();
+            config.gamestate.use_offline_services();
+        }
+        Ok(config)
     }
 
     pub fn get_bind_address(&self) -> String {
         format!("{}:{}", self.bind_host, self.bind_port)
     }
 }
+
+impl ConfigSchema for Config {
+    fn validate(&self, validator: &mut ConfigValidator) {
+        validator.non_empty("bind_host", &self.bind_host);
+        validator.positive("worker_count", self.worker_count);
+        validator.positive("secret_rotation_check_m", self.secret_rotation_check_m);
+        validator.scope("auth", |validator| self.auth.validate(validator));
+    }
+
+    fn check_secrets<'a>(&'a mut self, validator: &'a mut SecretValidator) -> Pin<Box<dyn Future<Output = ()> + 'a>> {
+        Box::pin(async move {
+            self.auth.check_secrets(validator).await;
+            self.gamestate.check_secrets(validator).await;
+        })
+    }
+}
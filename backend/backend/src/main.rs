@@ -1,8 +1,11 @@
-use actix_web::{middleware, App, HttpServer};
+use actix_web::{middleware, web, App, HttpServer};
 use shine_auth::AuthService;
+use shine_core::configloader::ConfigLoader;
+use shine_core::metrics::{self, Metrics};
+use shine_core::secret::SecretWatcher;
 use shine_gamestate::GameStateService;
 use shine_web::WebService;
-use std::env;
+use std::{env, process, time::Duration};
 
 mod config;
 
@@ -24,17 +27,70 @@ pub fn main() {
 
     let mut sys = actix_rt::System::new("Auth");
 
-    let service_config = config::Config::new().expect("Service configuration failed");
+    let mut service_config = match config::Config::new() {
+        Ok(service_config) => service_config,
+        Err(err) => {
+            for message in err.messages() {
+                log::error!("{}", message);
+            }
+            log::error!("{}", err);
+            process::exit(1);
+        }
+    };
     log::info!("{:#?}", service_config);
+    let secret_provider = service_config
+        .secret_provider
+        .as_ref()
+        .map(|provider| provider.create_provider().expect("Secret provider creation failed"));
+    let secret_references = match sys.block_on(ConfigLoader::resolve_secrets(
+        &mut service_config,
+        secret_provider.clone(),
+    )) {
+        Ok(references) => references,
+        Err(err) => {
+            for message in err.messages() {
+                log::error!("{}", message);
+            }
+            log::error!("{}", err);
+            process::exit(1);
+        }
+    };
+    if service_config.dev_offline {
+        log::warn!("Running in dev-offline mode, the data is not persisted and the captcha is not checked");
+    }
 
-    let auth = AuthService::create(&mut sys, &service_config.auth, "auth").expect("Auth service creation failed");
-    let web = WebService::create(&mut sys, &service_config.web, "web").expect("Web service creation failed");
-    let gamestate = GameStateService::create(&mut sys, &service_config.gamestate, "gamestate")
-        .expect("GameState service creation failed");
+    let metrics = Metrics::new().expect("Metrics creation failed");
+    let auth =
+        AuthService::create(&mut sys, &service_config.auth, "auth", &metrics).expect("Auth service creation failed");
+    let web = WebService::create(&mut sys, &service_config.web, "web", &metrics).expect("Web service creation failed");
+    if let Some(internal_api) = &service_config.internal_api {
+        auth.start_internal_api(internal_api)
+            .expect("Internal api start failed");
+    }
+    let gamestate = GameStateService::create(
+        &mut sys,
+        &service_config.gamestate,
+        "gamestate",
+        &metrics,
+        service_config.internal_api.as_ref(),
+    )
+    .expect("GameState service creation failed");
+
+    if let Some(secret_provider) = secret_provider {
+        let watcher = SecretWatcher::new(secret_provider);
+        for reference in secret_references {
+            watcher.watch(reference);
+        }
+        auth.watch_secret_rotation(&watcher);
+        gamestate.watch_secret_rotation(&watcher);
+        watcher.start(Duration::from_secs(service_config.secret_rotation_check_m * 60));
+    }
 
     let _ = HttpServer::new(move || {
         App::new()
             .wrap(middleware::Logger::default())
+            .data(metrics.clone())
+            .service(web::resource("/metrics").route(web::get().to(metrics::get_metrics)))
             .configure(|cfg| web.configure(cfg))
             .configure(|cfg| auth.configure(cfg))
             .configure(|cfg| gamestate.configure(cfg))
@@ -3,12 +3,14 @@ use shine_core::idgenerator::{IdSequence, IdSequenceError, SaltedIdSequence, Syn
 use std::collections::HashSet;
 use std::env;
 use std::str::FromStr;
+use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 enum Method {
     Store,
     Sequence(u64),
     SaltedSequence(u64),
+    Batch(u64, u64),
 }
 
 async fn store_counter(
@@ -36,7 +38,7 @@ async fn sequence_counter(
     granularity: u64,
 ) -> Result<(usize, Vec<String>), IdSequenceError> {
     let counter = SyncCounterStore::new(cfg).await?;
-    let counter = IdSequence::new(counter, "cnt").with_granularity(granularity);
+    let counter = IdSequence::new(Arc::new(counter), "cnt").with_granularity(granularity);
     let mut ids = vec![];
 
     for c in 0..per_thread_count {
@@ -56,7 +58,7 @@ async fn salted_sequence_counter(
     granularity: u64,
 ) -> Result<(usize, Vec<String>), IdSequenceError> {
     let counter = SyncCounterStore::new(cfg).await?;
-    let counter = SaltedIdSequence::new(counter, "cnt").with_granularity(granularity);
+    let counter = SaltedIdSequence::new(Arc::new(counter), "cnt").with_granularity(granularity);
     let mut ids = vec![];
 
     for c in 0..per_thread_count {
@@ -69,6 +71,27 @@ async fn salted_sequence_counter(
     Ok((t, ids))
 }
 
+async fn batch_counter(
+    t: usize,
+    cfg: SyncCounterConfig,
+    per_thread_count: usize,
+    granularity: u64,
+    batch_size: u64,
+) -> Result<(usize, Vec<String>), IdSequenceError> {
+    let counter = SyncCounterStore::new(cfg).await?;
+    let counter = IdSequence::new(Arc::new(counter), "cnt").with_granularity(granularity);
+    let mut ids = vec![];
+
+    for c in 0..(per_thread_count as u64 + batch_size - 1) / batch_size {
+        match counter.get_batch(batch_size).await {
+            Ok(batch) => ids.extend(batch.iter().map(|id| id.to_string())),
+            Err(e) => println!("{},{} -> {:?}", t, c, e),
+        };
+    }
+
+    Ok((t, ids))
+}
+
 fn main() {
     pretty_env_logger::init();
 
@@ -118,6 +141,26 @@ fn main() {
                     .help("Sets the allocation granularity"),
             ),
         )
+        .subcommand(
+            SubCommand::with_name("batch")
+                .about("Use IdSequence with batch allocation")
+                .arg(
+                    Arg::with_name("granularity")
+                        .short("g")
+                        .long("granularity")
+                        .default_value("100")
+                        .takes_value(true)
+                        .help("Sets the allocation granularity"),
+                )
+                .arg(
+                    Arg::with_name("size")
+                        .short("s")
+                        .long("size")
+                        .default_value("25")
+                        .takes_value(true)
+                        .help("Number of ids allocated at once"),
+                ),
+        )
         .get_matches();
 
     let thread_count = usize::from_str(matches.value_of("threads").unwrap()).unwrap();
@@ -129,6 +172,11 @@ fn main() {
         Method::Sequence(u64::from_str(matches.value_of("granularity").unwrap()).unwrap())
     } else if let Some(matches) = matches.subcommand_matches("salted") {
         Method::SaltedSequence(u64::from_str(matches.value_of("granularity").unwrap()).unwrap())
+    } else if let Some(matches) = matches.subcommand_matches("batch") {
+        Method::Batch(
+            u64::from_str(matches.value_of("granularity").unwrap()).unwrap(),
+            u64::from_str(matches.value_of("size").unwrap()).unwrap(),
+        )
     } else {
         return eprintln!("invalid subcommand");
     };
@@ -148,6 +196,7 @@ fn main() {
                 Method::Store => rt.block_on(store_counter(t, cfg, per_thread_count)),
                 Method::Sequence(g) => rt.block_on(sequence_counter(t, cfg, per_thread_count, g)),
                 Method::SaltedSequence(g) => rt.block_on(salted_sequence_counter(t, cfg, per_thread_count, g)),
+                Method::Batch(g, s) => rt.block_on(batch_counter(t, cfg, per_thread_count, g, s)),
             }
         }));
     }
@@ -1,7 +1,7 @@
 use clap::{App, Arg, SubCommand};
 use shine_core::iplocation::{
     IpCachedLocation, IpCachedLocationConfig, IpLocation, IpLocationError, IpLocationIpDataCo,
-    IpLocationIpDataCoConfig, IpLocationProvider,
+    IpLocationIpDataCoConfig, IpLocationMaxMind, IpLocationMaxMindConfig, IpLocationProvider,
 };
 use std::net::IpAddr;
 use std::time::Duration;
@@ -29,6 +29,17 @@ fn main() {
                     .help("Sets the api key"),
             ),
         )
+        .subcommand(
+            SubCommand::with_name("maxmind")
+                .about("Use a local MaxMind database")
+                .arg(
+                    Arg::with_name("database")
+                        .short("d")
+                        .long("database")
+                        .takes_value(true)
+                        .help("Sets the path of the mmdb file"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("cached_ipdataco")
                 .about("Use ipdata.co")
@@ -64,6 +75,12 @@ fn main() {
             api_key: key.to_owned(),
         };
         Box::new(IpLocationIpDataCo::new(cfg))
+    } else if let Some(matches) = matches.subcommand_matches("maxmind") {
+        let database = matches.value_of("database").unwrap();
+        let cfg = IpLocationMaxMindConfig {
+            database_path: database.to_owned(),
+        };
+        Box::new(IpLocationMaxMind::new(cfg).unwrap())
     } else if let Some(matches) = matches.subcommand_matches("cached_ipdataco") {
         let key = matches.value_of("key").unwrap();
         let storage_account = matches.value_of("storage_account").unwrap().to_owned();
@@ -77,6 +94,8 @@ fn main() {
             storage_account_key: storage_account_secret,
             table_name: "ipcache".to_owned(),
             time_to_live: Duration::from_secs(12 * 60 * 60),
+            memory_capacity: 1024,
+            memory_time_to_live: Duration::from_secs(60 * 60),
         };
         Box::new(rt.block_on(IpCachedLocation::new(provider, cfg)).unwrap())
     } else {
@@ -1,3 +1,5 @@
+pub mod table_storage;
+
 use azure_sdk_core::errors::AzureError;
 use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
 use std::str::Utf8Error;
@@ -31,10 +33,3 @@ pub fn decode_safe_key(input: &str) -> Result<String, Utf8Error> {
     let input = input.replace("@", "%");
     percent_decode_str(&input).decode_utf8().map(|d| d.to_string())
 }
-
-pub mod table_storage {
-    use serde::{Deserialize, Serialize};
-
-    #[derive(Debug, Serialize, Deserialize)]
-    pub struct EmptyData {}
-}
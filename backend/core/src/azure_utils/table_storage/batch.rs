@@ -0,0 +1,299 @@
+use super::TableBatchError;
+use azure_sdk_storage_table::TableEntity;
+use chrono::Utc;
+use data_encoding::BASE64;
+use hmac::{Hmac, Mac, NewMac};
+use rand::{distributions::Alphanumeric, Rng};
+use reqwest::{header, Client};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::iter;
+
+/// Maximum number of the operations of an entity group transaction
+pub const MAX_BATCH_SIZE: usize = 100;
+
+const STORAGE_VERSION: &str = "2019-02-02";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BatchOperationKind {
+    /// Insert a new entity, fails if the entity exists
+    Insert,
+    /// Merge the properties into an existing entity
+    Merge,
+    /// Insert the entity or merge the properties into the existing one
+    InsertOrMerge,
+    /// Delete an existing entity
+    Delete,
+}
+
+/// An operation of an entity group transaction
+#[derive(Debug, Clone)]
+pub struct BatchOperation {
+    kind: BatchOperationKind,
+    partition_key: String,
+    row_key: String,
+    etag: Option<String>,
+    body: Option<String>,
+}
+
+impl BatchOperation {
+    fn with_payload<T>(kind: BatchOperationKind, entity: &TableEntity<T>) -> Result<BatchOperation, TableBatchError>
+    where
+        T: Serialize,
+    {
+        let mut body = match serde_json::to_value(&entity.payload)? {
+            Value::Object(body) => body,
+            _ => return Err(TableBatchError::Serialize("Entity is not an object".to_owned())),
+        };
+        body.insert("PartitionKey".to_owned(), Value::String(entity.partition_key.clone()));
+        body.insert("RowKey".to_owned(), Value::String(entity.row_key.clone()));
+
+        Ok(BatchOperation {
+            kind,
+            partition_key: entity.partition_key.clone(),
+            row_key: entity.row_key.clone(),
+            etag: entity.etag.clone(),
+            body: Some(serde_json::to_string(&body)?),
+        })
+    }
+
+    pub fn insert<T: Serialize>(entity: &TableEntity<T>) -> Result<BatchOperation, TableBatchError> {
+        Self::with_payload(BatchOperationKind::Insert, entity)
+    }
+
+    /// Merge into an existing entity, the etag of the entity is checked if it is set.
+    pub fn merge<T: Serialize>(entity: &TableEntity<T>) -> Result<BatchOperation, TableBatchError> {
+        Self::with_payload(BatchOperationKind::Merge, entity)
+    }
+
+    pub fn insert_or_merge<T: Serialize>(entity: &TableEntity<T>) -> Result<BatchOperation, TableBatchError> {
+        Self::with_payload(BatchOperationKind::InsertOrMerge, entity)
+    }
+
+    /// Delete an existing entity, the etag of the entity is checked if it is set.
+    pub fn delete<T>(entity: &TableEntity<T>) -> BatchOperation {
+        BatchOperation {
+            kind: BatchOperationKind::Delete,
+            partition_key: entity.partition_key.clone(),
+            row_key: entity.row_key.clone(),
+            etag: entity.etag.clone(),
+            body: None,
+        }
+    }
+
+    pub fn partition_key(&self) -> &str {
+        &self.partition_key
+    }
+
+    fn write_request(&self, payload: &mut String, table_url: &str) {
+        let resource = format!(
+            "{}(PartitionKey='{}',RowKey='{}')",
+            table_url,
+            self.partition_key.replace('\'', "''"),
+            self.row_key.replace('\'', "''")
+        );
+        let (method, url, if_match) = match self.kind {
+            BatchOperationKind::Insert => ("POST", table_url.to_owned(), None),
+            BatchOperationKind::Merge => ("MERGE", resource, Some(self.etag.as_deref().unwrap_or("*"))),
+            BatchOperationKind::InsertOrMerge => ("MERGE", resource, None),
+            BatchOperationKind::Delete => ("DELETE", resource, Some(self.etag.as_deref().unwrap_or("*"))),
+        };
+
+        payload.push_str("Content-Type: application/http\r\n");
+        payload.push_str("Content-Transfer-Encoding: binary\r\n\r\n");
+        payload.push_str(&format!("{} {} HTTP/1.1\r\n", method, url));
+        payload.push_str("Accept: application/json;odata=minimalmetadata\r\n");
+        payload.push_str("DataServiceVersion: 3.0;\r\n");
+        if let Some(if_match) = if_match {
+            payload.push_str(&format!("If-Match: {}\r\n", if_match));
+        }
+        match &self.body {
+            Some(body) => {
+                payload.push_str("Content-Type: application/json\r\n");
+                payload.push_str("Prefer: return-no-content\r\n\r\n");
+                payload.push_str(body);
+                payload.push_str("\r\n");
+            }
+            None => payload.push_str("\r\n"),
+        }
+    }
+}
+
+fn boundary(prefix: &str) -> String {
+    let mut rng = rand::thread_rng();
+    let id: String = iter::repeat(()).map(|()| rng.sample(Alphanumeric)).take(16).collect();
+    format!("{}_{}", prefix, id)
+}
+
+/// Find the first failed response of a changeset. On failure the service returns a single response for
+/// the changeset with the index of the failed operation at the start of the error message.
+fn find_failure(body: &str) -> Option<TableBatchError> {
+    let status = body
+        .lines()
+        .filter(|line| line.starts_with("HTTP/1.1 "))
+        .filter_map(|line| line.split(' ').nth(1).and_then(|code| code.parse::<u16>().ok()))
+        .find(|&status| status >= 300)?;
+
+    let message = body
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line.trim()).ok())
+        .find_map(|error| {
+            error
+                .pointer("/odata.error/message/value")
+                .and_then(|message| message.as_str())
+                .map(|message| message.to_owned())
+        })
+        .unwrap_or_default();
+    let index = message.split(':').next().and_then(|index| index.parse::<usize>().ok());
+
+    Some(TableBatchError::Rejected { status, index, message })
+}
+
+/// Entity group transactions on a table. The operations of a transaction must be in the same partition and
+/// either all or none of them take effect.
+#[derive(Clone)]
+pub struct TableBatchClient {
+    client: Client,
+    account: String,
+    key: Vec<u8>,
+    table_name: String,
+}
+
+impl TableBatchClient {
+    pub fn new(account: &str, key: &str, table_name: &str) -> Result<TableBatchClient, TableBatchError> {
+        let key = BASE64
+            .decode(key.as_bytes())
+            .map_err(|err| TableBatchError::Key(format!("{}", err)))?;
+        Ok(TableBatchClient {
+            client: Client::new(),
+            account: account.to_owned(),
+            key,
+            table_name: table_name.to_owned(),
+        })
+    }
+
+    fn account_url(&self) -> String {
+        format!("https://{}.table.core.windows.net", self.account)
+    }
+
+    /// Shared Key Lite authorization of the table service
+    fn authorization(&self, date: &str, resource: &str) -> Result<String, TableBatchError> {
+        let string_to_sign = format!("{}\n/{}/{}", date, self.account, resource);
+        let mut mac =
+            Hmac::<Sha256>::new_varkey(&self.key).map_err(|err| TableBatchError::Key(format!("{:?}", err)))?;
+        mac.update(string_to_sign.as_bytes());
+        let signature = BASE64.encode(&mac.finalize().into_bytes());
+        Ok(format!("SharedKeyLite {}:{}", self.account, signature))
+    }
+
+    /// Execute the operations of a single partition as one transaction.
+    pub async fn execute_transaction(&self, operations: &[BatchOperation]) -> Result<(), TableBatchError> {
+        if operations.is_empty() {
+            return Ok(());
+        }
+        if operations.len() > MAX_BATCH_SIZE {
+            return Err(TableBatchError::Rejected {
+                status: 400,
+                index: Some(MAX_BATCH_SIZE),
+                message: format!("At most {} operations are allowed in a transaction", MAX_BATCH_SIZE),
+            });
+        }
+        if let Some(index) = operations
+            .iter()
+            .position(|op| op.partition_key != operations[0].partition_key)
+        {
+            return Err(TableBatchError::Rejected {
+                status: 400,
+                index: Some(index),
+                message: "Operations of a transaction must be in the same partition".to_owned(),
+            });
+        }
+
+        let batch = boundary("batch");
+        let changeset = boundary("changeset");
+        let table_url = format!("{}/{}", self.account_url(), self.table_name);
+
+        let mut payload = String::new();
+        payload.push_str(&format!("--{}\r\n", batch));
+        payload.push_str(&format!(
+            "Content-Type: multipart/mixed; boundary={}\r\n\r\n",
+            changeset
+        ));
+        for operation in operations {
+            payload.push_str(&format!("--{}\r\n", changeset));
+            operation.write_request(&mut payload, &table_url);
+        }
+        payload.push_str(&format!("--{}--\r\n", changeset));
+        payload.push_str(&format!("--{}--\r\n", batch));
+
+        let date = Utc::now().format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+        let response = self
+            .client
+            .post(&format!("{}/$batch", self.account_url()))
+            .header(header::AUTHORIZATION, self.authorization(&date, "$batch")?)
+            .header("x-ms-date", &date)
+            .header("x-ms-version", STORAGE_VERSION)
+            .header("DataServiceVersion", "3.0;")
+            .header("MaxDataServiceVersion", "3.0;NetFx")
+            .header(header::CONTENT_TYPE, format!("multipart/mixed; boundary={}", batch))
+            .body(payload)
+            .send()
+            .await?;
+
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(TableBatchError::Rejected {
+                status: status.as_u16(),
+                index: None,
+                message: body,
+            });
+        }
+        match find_failure(&body) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Execute the operations grouped by the partitions into transactions of at most MAX_BATCH_SIZE operations.
+    /// The transactions are independent, on failure the preceding transactions are not rolled back.
+    pub async fn execute(&self, operations: Vec<BatchOperation>) -> Result<(), TableBatchError> {
+        let mut partitions = BTreeMap::<String, Vec<BatchOperation>>::new();
+        for operation in operations {
+            partitions
+                .entry(operation.partition_key.clone())
+                .or_default()
+                .push(operation);
+        }
+
+        for (_, operations) in partitions {
+            for transaction in operations.chunks(MAX_BATCH_SIZE) {
+                self.execute_transaction(transaction).await?;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn insert_entities<T: Serialize>(&self, entities: &[TableEntity<T>]) -> Result<(), TableBatchError> {
+        let operations = entities
+            .iter()
+            .map(BatchOperation::insert)
+            .collect::<Result<Vec<_>, _>>()?;
+        self.execute(operations).await
+    }
+
+    pub async fn merge_entities<T: Serialize>(&self, entities: &[TableEntity<T>]) -> Result<(), TableBatchError> {
+        let operations = entities
+            .iter()
+            .map(BatchOperation::merge)
+            .collect::<Result<Vec<_>, _>>()?;
+        self.execute(operations).await
+    }
+
+    pub async fn delete_entities<T>(&self, entities: &[TableEntity<T>]) -> Result<(), TableBatchError> {
+        self.execute(entities.iter().map(BatchOperation::delete).collect())
+            .await
+    }
+}
@@ -0,0 +1,73 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum TableBatchError {
+    /// Invalid storage account key
+    Key(String),
+    /// An entity could not be serialized
+    Serialize(String),
+    /// Request failed or the response could not be processed
+    Http(String),
+    /// The transaction was rejected and none of the operations took effect. The index is the operation of
+    /// the transaction that caused the failure if the service reported it.
+    Rejected {
+        status: u16,
+        index: Option<usize>,
+        message: String,
+    },
+}
+
+impl TableBatchError {
+    /// The transaction was rejected with CONFLICT (409), ex. an inserted entity already exists
+    pub fn is_conflict(&self) -> bool {
+        match self {
+            TableBatchError::Rejected { status, .. } => *status == 409,
+            _ => false,
+        }
+    }
+
+    /// The transaction was rejected with PRECONDITION_FAILED (412), ex. an etag condition failed
+    pub fn is_precondition(&self) -> bool {
+        match self {
+            TableBatchError::Rejected { status, .. } => *status == 412,
+            _ => false,
+        }
+    }
+
+    /// The transaction was rejected with NOT_FOUND (404), ex. a merged or deleted entity does not exist
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            TableBatchError::Rejected { status, .. } => *status == 404,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for TableBatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TableBatchError::Key(e) => write!(f, "Invalid storage key: {}", e),
+            TableBatchError::Serialize(e) => write!(f, "Serialization failed: {}", e),
+            TableBatchError::Http(e) => write!(f, "Batch request failed: {}", e),
+            TableBatchError::Rejected { status, index, message } => match index {
+                Some(index) => write!(f, "Batch rejected with {} at operation {}: {}", status, index, message),
+                None => write!(f, "Batch rejected with {}: {}", status, message),
+            },
+        }
+    }
+}
+
+impl Error for TableBatchError {}
+
+impl From<reqwest::Error> for TableBatchError {
+    fn from(err: reqwest::Error) -> TableBatchError {
+        TableBatchError::Http(format!("{}", err))
+    }
+}
+
+impl From<serde_json::Error> for TableBatchError {
+    fn from(err: serde_json::Error) -> TableBatchError {
+        TableBatchError::Serialize(format!("{}", err))
+    }
+}
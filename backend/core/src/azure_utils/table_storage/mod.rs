@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+mod batch;
+mod error;
+mod query;
+
+pub use self::batch::*;
+pub use self::error::*;
+pub use self::query::*;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmptyData {}
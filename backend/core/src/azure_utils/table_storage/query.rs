@@ -0,0 +1,46 @@
+use azure_sdk_core::errors::AzureError;
+use azure_sdk_storage_table::{CloudTable, TableEntity};
+use futures::stream::StreamExt;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::de::DeserializeOwned;
+
+/// Create the query string of a filter expression
+pub fn filter_query(filter: &str) -> String {
+    format!("$filter={}", utf8_percent_encode(filter, NON_ALPHANUMERIC))
+}
+
+/// Query all the matching entities, the continuation tokens of the segments are followed until the last segment.
+pub async fn query_all<T>(table: &CloudTable, query: Option<&str>) -> Result<Vec<TableEntity<T>>, AzureError>
+where
+    T: DeserializeOwned,
+{
+    let mut entities = Vec::new();
+    let mut segments = Box::pin(table.stream_query::<T>(query));
+    while let Some(segment) = segments.next().await {
+        entities.extend(segment?);
+    }
+    Ok(entities)
+}
+
+/// Query the first `limit` matching entities. A segment may be shorter than the requested size (ex. at
+/// partition boundaries or after a timeout of the service), thus the segments are followed until the limit is
+/// reached.
+pub async fn query_limited<T>(
+    table: &CloudTable,
+    query: Option<&str>,
+    limit: usize,
+) -> Result<Vec<TableEntity<T>>, AzureError>
+where
+    T: DeserializeOwned,
+{
+    let mut entities = Vec::new();
+    let mut segments = Box::pin(table.stream_query::<T>(query));
+    while entities.len() < limit {
+        match segments.next().await {
+            Some(segment) => entities.extend(segment?),
+            None => break,
+        }
+    }
+    entities.truncate(limit);
+    Ok(entities)
+}
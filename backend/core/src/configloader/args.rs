@@ -0,0 +1,47 @@
+use super::ConfigLoadError;
+
+const SET_FLAG: &str = "--set";
+
+/// Configuration related command line arguments: the positional arguments are the configuration files
+/// merged in order and `--set <path>=<value>` overrides a single value.
+#[derive(Debug, Default, Clone)]
+pub struct ConfigArgs {
+    pub files: Vec<String>,
+    pub overrides: Vec<(String, String)>,
+}
+
+impl ConfigArgs {
+    pub fn parse<I>(args: I) -> Result<ConfigArgs, ConfigLoadError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut parsed = ConfigArgs::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == SET_FLAG {
+                let value = args
+                    .next()
+                    .ok_or_else(|| ConfigLoadError::Args(format!("Missing <path>=<value> after {}", SET_FLAG)))?;
+                parsed.overrides.push(Self::parse_override(&value)?);
+            } else if arg.starts_with(SET_FLAG) && arg[SET_FLAG.len()..].starts_with('=') {
+                parsed.overrides.push(Self::parse_override(&arg[SET_FLAG.len() + 1..])?);
+            } else if arg.starts_with("--") {
+                return Err(ConfigLoadError::Args(format!("Unknown argument: {}", arg)));
+            } else {
+                parsed.files.push(arg);
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn parse_override(value: &str) -> Result<(String, String), ConfigLoadError> {
+        let mut parts = value.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some(path), Some(value)) if !path.is_empty() => Ok((path.to_owned(), value.to_owned())),
+            _ => Err(ConfigLoadError::Args(format!(
+                "Invalid override, <path>=<value> expected: {}",
+                value
+            ))),
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use crate::secret::SecretError;
+use config::ConfigError;
+use std::error::Error;
+use std::fmt;
+
+/// A constraint violated by a configuration value
+#[derive(Debug)]
+pub struct ConfigIssue {
+    /// Path of the value, ex. auth.iam.session_time_to_live_h
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigLoadError {
+    /// Invalid command line
+    Args(String),
+    /// A configuration source could not be read or merged
+    Source(ConfigError),
+    /// The configuration does not match the expected structure
+    Schema { path: String, message: String },
+    /// The configuration values violate some constraints
+    Invalid(Vec<ConfigIssue>),
+    /// Some secrets could not be resolved or are invalid
+    Secrets(Vec<SecretError>),
+}
+
+impl ConfigLoadError {
+    /// The individual problems of the Invalid and Secrets errors
+    pub fn messages(&self) -> Vec<String> {
+        match self {
+            ConfigLoadError::Invalid(issues) => issues.iter().map(|issue| format!("{}", issue)).collect(),
+            ConfigLoadError::Secrets(errors) => errors.iter().map(|err| format!("{}", err)).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigLoadError::Args(err) => write!(f, "Invalid arguments: {}", err),
+            ConfigLoadError::Source(err) => write!(f, "Configuration could not be loaded: {}", err),
+            ConfigLoadError::Schema { path, message } => write!(f, "Configuration error at {}: {}", path, message),
+            ConfigLoadError::Invalid(issues) => write!(f, "Configuration is invalid with {} issue(s)", issues.len()),
+            ConfigLoadError::Secrets(errors) => write!(f, "Secret validation failed with {} error(s)", errors.len()),
+        }
+    }
+}
+
+impl Error for ConfigLoadError {}
+
+impl From<ConfigError> for ConfigLoadError {
+    fn from(err: ConfigError) -> ConfigLoadError {
+        ConfigLoadError::Source(err)
+    }
+}
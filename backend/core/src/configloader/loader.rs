@@ -0,0 +1,108 @@
+use super::{ConfigArgs, ConfigLoadError, ConfigSchema, ConfigValidator};
+use crate::secret::{SecretProvider, SecretReference, SecretValidator};
+use config::{Config, Environment, File, FileFormat, Value};
+use std::path::Path;
+use std::sync::Arc;
+
+const ENV_SEPARATOR: &str = "--";
+
+/// Layered configuration. The layers in the order of precedence:
+/// - the overrides, ex. from the command line
+/// - the environment variables, the sections are separated by `--`, ex. auth--iam--smtp_host
+/// - the configuration files in the order they were added
+/// - the defaults
+pub struct ConfigLoader {
+    config: Config,
+}
+
+impl ConfigLoader {
+    pub fn new() -> ConfigLoader {
+        ConfigLoader { config: Config::new() }
+    }
+
+    /// Create a loader with the files and overrides of the command line and the environment variables
+    pub fn from_args(args: &ConfigArgs) -> Result<ConfigLoader, ConfigLoadError> {
+        let mut loader = ConfigLoader::new();
+        for file in &args.files {
+            loader.add_file(file)?;
+        }
+        loader.add_env(None)?;
+        for (path, value) in &args.overrides {
+            loader.set_override(path, value)?;
+        }
+        Ok(loader)
+    }
+
+    pub fn add_file(&mut self, path: &str) -> Result<&mut Self, ConfigLoadError> {
+        log::info!("Loading config file {:?}", path);
+        self.config.merge(File::from(Path::new(path)))?;
+        Ok(self)
+    }
+
+    /// Add the environment variables with the given prefix, ex. with the `shine` prefix
+    /// `shine--auth--iam--smtp_host` sets auth.iam.smtp_host
+    pub fn add_env(&mut self, prefix: Option<&str>) -> Result<&mut Self, ConfigLoadError> {
+        let env = match prefix {
+            Some(prefix) => Environment::with_prefix(prefix),
+            None => Environment::new(),
+        };
+        self.config.merge(env.separator(ENV_SEPARATOR))?;
+        Ok(self)
+    }
+
+    pub fn set_defaults_json(&mut self, json: &str) -> Result<&mut Self, ConfigLoadError> {
+        let defaults = Config::new().with_merged(File::from_str(json, FileFormat::Json))?;
+        for (key, value) in defaults.collect()? {
+            self.config.set_default(&key, value)?;
+        }
+        Ok(self)
+    }
+
+    pub fn set_default<T: Into<Value>>(&mut self, path: &str, value: T) -> Result<&mut Self, ConfigLoadError> {
+        self.config.set_default(path, value)?;
+        Ok(self)
+    }
+
+    pub fn set_override<T: Into<Value>>(&mut self, path: &str, value: T) -> Result<&mut Self, ConfigLoadError> {
+        self.config.set(path, value)?;
+        Ok(self)
+    }
+
+    pub fn get_bool(&self, path: &str) -> Option<bool> {
+        self.config.get_bool(path).ok()
+    }
+
+    /// Deserialize and validate the configuration, the errors report the path of the offending values
+    pub fn load<T: ConfigSchema>(&self) -> Result<T, ConfigLoadError> {
+        let root = Value::new(None, self.config.collect()?);
+        let config: T = serde_path_to_error::deserialize(root).map_err(|err| ConfigLoadError::Schema {
+            path: err.path().to_string(),
+            message: format!("{}", err.inner()),
+        })?;
+
+        let mut validator = ConfigValidator::new();
+        config.validate(&mut validator);
+        validator.finish().map_err(ConfigLoadError::Invalid)?;
+        Ok(config)
+    }
+
+    /// Resolve the secrets given as `file:<path>`, `env:<variable>` or `secret:<name>` and validate all of
+    /// them. The secrets fetched from the provider are returned.
+    pub async fn resolve_secrets<T: ConfigSchema>(
+        config: &mut T,
+        provider: Option<Arc<dyn SecretProvider>>,
+    ) -> Result<Vec<SecretReference>, ConfigLoadError> {
+        let mut validator = match provider {
+            Some(provider) => SecretValidator::with_provider(provider),
+            None => SecretValidator::new(),
+        };
+        config.check_secrets(&mut validator).await;
+        validator.finish().map_err(ConfigLoadError::Secrets)
+    }
+}
+
+impl Default for ConfigLoader {
+    fn default() -> Self {
+        ConfigLoader::new()
+    }
+}
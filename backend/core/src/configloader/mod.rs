@@ -0,0 +1,9 @@
+mod args;
+mod error;
+mod loader;
+mod schema;
+
+pub use self::args::*;
+pub use self::error::*;
+pub use self::loader::*;
+pub use self::schema::*;
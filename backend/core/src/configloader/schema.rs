@@ -0,0 +1,91 @@
+use super::ConfigIssue;
+use crate::secret::SecretValidator;
+use reqwest::Url;
+use serde::de::DeserializeOwned;
+use std::future::{self, Future};
+use std::pin::Pin;
+
+/// A configuration loaded by the ConfigLoader
+pub trait ConfigSchema: DeserializeOwned {
+    /// Check the constraints that are not expressed by the types
+    fn validate(&self, _validator: &mut ConfigValidator) {}
+
+    /// Resolve the secret references and validate the secrets in use
+    fn check_secrets<'a>(&'a mut self, _validator: &'a mut SecretValidator) -> Pin<Box<dyn Future<Output = ()> + 'a>> {
+        Box::pin(future::ready(()))
+    }
+}
+
+/// Collect the constraint violations of a configuration along with the path of the values
+#[derive(Default)]
+pub struct ConfigValidator {
+    scope: Vec<String>,
+    issues: Vec<ConfigIssue>,
+}
+
+impl ConfigValidator {
+    pub fn new() -> ConfigValidator {
+        ConfigValidator::default()
+    }
+
+    fn path(&self, field: &str) -> String {
+        self.scope
+            .iter()
+            .map(|s| s.as_str())
+            .chain(Some(field))
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+
+    /// Validate a nested section, the paths of the issues are prefixed by the name of the section
+    pub fn scope<F>(&mut self, name: &str, validate: F)
+    where
+        F: FnOnce(&mut ConfigValidator),
+    {
+        self.scope.push(name.to_owned());
+        validate(self);
+        self.scope.pop();
+    }
+
+    pub fn add_issue<M: ToString>(&mut self, field: &str, message: M) {
+        let path = self.path(field);
+        self.issues.push(ConfigIssue {
+            path,
+            message: message.to_string(),
+        });
+    }
+
+    pub fn check<M: ToString>(&mut self, condition: bool, field: &str, message: M) {
+        if !condition {
+            self.add_issue(field, message);
+        }
+    }
+
+    pub fn non_empty(&mut self, field: &str, value: &str) {
+        self.check(!value.is_empty(), field, "must not be empty");
+    }
+
+    pub fn positive<T>(&mut self, field: &str, value: T)
+    where
+        T: PartialOrd + Default,
+    {
+        self.check(value > T::default(), field, "must be greater than zero");
+    }
+
+    /// Check an absolute http(s) url
+    pub fn http_url(&mut self, field: &str, value: &str) {
+        match Url::parse(value) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {}
+            Ok(url) => self.add_issue(field, format!("http or https url expected, got {}", url.scheme())),
+            Err(err) => self.add_issue(field, format!("invalid url: {}", err)),
+        }
+    }
+
+    pub fn finish(self) -> Result<(), Vec<ConfigIssue>> {
+        if self.issues.is_empty() {
+            Ok(())
+        } else {
+            Err(self.issues)
+        }
+    }
+}
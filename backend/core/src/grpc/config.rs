@@ -0,0 +1,63 @@
+use super::GrpcError;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use tonic::transport::{Certificate, ClientTlsConfig, Identity, ServerTlsConfig};
+
+/// Mutual TLS of the internal api. Both sides present a certificate signed by the same (private) CA.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GrpcTlsConfig {
+    /// PEM file of the CA used to verify the peer
+    pub ca_cert_path: String,
+    /// PEM file of the certificate of the service
+    pub cert_path: String,
+    /// PEM file of the private key of the service
+    pub key_path: String,
+    /// Name of the server in its certificate, the host of the endpoint is used if not set
+    #[serde(default)]
+    pub domain_name: Option<String>,
+}
+
+impl GrpcTlsConfig {
+    fn read(path: &str) -> Result<Vec<u8>, GrpcError> {
+        fs::read(path).map_err(|err| GrpcError::Certificate(format!("Failed to read {}: {}", path, err)))
+    }
+
+    fn identity(&self) -> Result<Identity, GrpcError> {
+        Ok(Identity::from_pem(
+            Self::read(&self.cert_path)?,
+            Self::read(&self.key_path)?,
+        ))
+    }
+
+    /// Server configuration requiring a client certificate signed by the CA
+    pub fn server_tls_config(&self) -> Result<ServerTlsConfig, GrpcError> {
+        let mut config = ServerTlsConfig::new();
+        config
+            .identity(self.identity()?)
+            .client_ca_root(Certificate::from_pem(Self::read(&self.ca_cert_path)?));
+        Ok(config)
+    }
+
+    pub fn client_tls_config(&self) -> Result<ClientTlsConfig, GrpcError> {
+        let mut config = ClientTlsConfig::new();
+        config
+            .ca_certificate(Certificate::from_pem(Self::read(&self.ca_cert_path)?))
+            .identity(self.identity()?);
+        if let Some(domain_name) = &self.domain_name {
+            config.domain_name(domain_name.clone());
+        }
+        Ok(config)
+    }
+}
+
+/// The internal gRPC api between the backend services
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InternalApiConfig {
+    /// Address the internal api of the auth service is bound to, ex. 0.0.0.0:50051
+    pub bind_address: String,
+    /// Url of the internal api used by the other services, ex. https://auth.internal:50051
+    pub endpoint: String,
+    /// Plain http/2 is used without tls, only for local development
+    #[serde(default)]
+    pub tls: Option<GrpcTlsConfig>,
+}
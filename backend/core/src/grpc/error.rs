@@ -0,0 +1,38 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum GrpcError {
+    /// Certificate or key could not be loaded
+    Certificate(String),
+
+    /// Invalid endpoint or connection failure
+    Transport(String),
+
+    /// The remote call failed
+    Call(String),
+}
+
+impl fmt::Display for GrpcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GrpcError::Certificate(ref e) => write!(f, "Certificate error: {}", e),
+            GrpcError::Transport(ref e) => write!(f, "Transport error: {}", e),
+            GrpcError::Call(ref e) => write!(f, "Call failed: {}", e),
+        }
+    }
+}
+
+impl Error for GrpcError {}
+
+impl From<tonic::Status> for GrpcError {
+    fn from(status: tonic::Status) -> GrpcError {
+        GrpcError::Call(format!("{:?}: {}", status.code(), status.message()))
+    }
+}
+
+impl From<tonic::transport::Error> for GrpcError {
+    fn from(err: tonic::transport::Error) -> GrpcError {
+        GrpcError::Transport(format!("{}", err))
+    }
+}
@@ -0,0 +1,5 @@
+mod config;
+mod error;
+
+pub use self::config::*;
+pub use self::error::*;
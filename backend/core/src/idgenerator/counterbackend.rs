@@ -0,0 +1,77 @@
+use crate::idgenerator::{
+    IdSequenceError, MemoryCounterStore, PostgresCounterStore, SyncCounterConfig, SyncCounterStore,
+};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::ops::Range;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Persistent counters the id sequences reserve their ranges from
+pub trait CounterBackend: Sync + Send {
+    /// Reserve the next `count` values of a counter. The reservation is persisted before the range is
+    /// returned, thus a crash may leave gaps in the sequence but a value is never returned twice.
+    fn reserve<'s>(
+        &'s self,
+        sequence_id: &'s str,
+        count: u64,
+    ) -> Pin<Box<dyn Future<Output = Result<Range<u64>, IdSequenceError>> + 's>>;
+}
+
+/// Storage of the counters
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum CounterBackendConfig {
+    /// Azure table storage using the storage account of the owner service
+    Azure,
+    /// A counter table in a Postgres database
+    Postgres { url: String, max_connections: u32 },
+    /// Keep the counters in memory, for local development and tests
+    Memory,
+}
+
+impl Default for CounterBackendConfig {
+    fn default() -> Self {
+        CounterBackendConfig::Azure
+    }
+}
+
+impl CounterBackendConfig {
+    /// Create the backend. The table name and the starting value of the counter config are used by all
+    /// the backends, the storage account only by the Azure backend.
+    pub async fn create_backend(&self, counter: SyncCounterConfig) -> Result<Arc<dyn CounterBackend>, IdSequenceError> {
+        Ok(match self {
+            CounterBackendConfig::Azure => Arc::new(SyncCounterStore::new(counter).await?),
+            CounterBackendConfig::Postgres { url, max_connections } => Arc::new(
+                PostgresCounterStore::new(url, *max_connections, &counter.table_name, counter.starting_value).await?,
+            ),
+            CounterBackendConfig::Memory => Arc::new(MemoryCounterStore::new(counter.starting_value)),
+        })
+    }
+}
+
+/// Configuration of an id sequence
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IdSequenceConfig {
+    #[serde(default)]
+    pub backend: CounterBackendConfig,
+    /// Number of ids reserved from the backend at once. The unused ids of a reservation are lost when the
+    /// service stops, larger values reduce the load of the backend at the price of larger gaps.
+    #[serde(default = "IdSequenceConfig::default_granularity")]
+    pub granularity: u64,
+}
+
+impl IdSequenceConfig {
+    fn default_granularity() -> u64 {
+        100
+    }
+}
+
+impl Default for IdSequenceConfig {
+    fn default() -> Self {
+        IdSequenceConfig {
+            backend: CounterBackendConfig::default(),
+            granularity: IdSequenceConfig::default_granularity(),
+        }
+    }
+}
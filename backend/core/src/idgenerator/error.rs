@@ -1,4 +1,5 @@
 use azure_sdk_core::errors::AzureError;
+use sqlx::Error as SqlxError;
 use std::error::Error;
 use std::fmt;
 
@@ -10,6 +11,10 @@ pub enum IdSequenceError {
     SequenceEnded,
     /// Failed to generate id as some conflicts could not be resolved
     Conflit,
+    /// The clock is behind the last generated id by more than the tolerated milliseconds
+    ClockSkew(u64),
+    /// Worker id is out of the supported range
+    InvalidWorkerId(u16),
 }
 
 impl fmt::Display for IdSequenceError {
@@ -18,6 +23,8 @@ impl fmt::Display for IdSequenceError {
             IdSequenceError::DB(ref e) => write!(f, "DB, {}", e),
             IdSequenceError::SequenceEnded => write!(f, "Sequence is out of id"),
             IdSequenceError::Conflit => write!(f, "Could not generate id due to DB conflicts"),
+            IdSequenceError::ClockSkew(ms) => write!(f, "Clock moved backwards by {}ms", ms),
+            IdSequenceError::InvalidWorkerId(id) => write!(f, "Worker id {} is out of range", id),
         }
     }
 }
@@ -29,3 +36,9 @@ impl From<AzureError> for IdSequenceError {
         IdSequenceError::DB(format!("{:?}", err))
     }
 }
+
+impl From<SqlxError> for IdSequenceError {
+    fn from(err: SqlxError) -> IdSequenceError {
+        IdSequenceError::DB(format!("{:?}", err))
+    }
+}
@@ -1,29 +1,31 @@
-use crate::idgenerator::{IdSequenceError, SyncCounterStore};
+use crate::idgenerator::{CounterBackend, IdSequenceError};
 use futures::lock::Mutex;
 use std::ops::Range;
 use std::sync::Arc;
 
+/// Sequence of unique ids. The ids are reserved from the backend in ranges of the granularity and
+/// served from memory, thus the ids are increasing within an instance but not across the instances.
 #[derive(Clone)]
 pub struct IdSequence {
     name: String,
     granularity: u64,
-    counter_store: SyncCounterStore,
+    backend: Arc<dyn CounterBackend>,
     range: Arc<Mutex<Range<u64>>>,
 }
 
 impl IdSequence {
-    pub fn new<S: Into<String>>(counter_store: SyncCounterStore, name: S) -> IdSequence {
+    pub fn new<S: Into<String>>(backend: Arc<dyn CounterBackend>, name: S) -> IdSequence {
         IdSequence {
             name: name.into(),
             granularity: 100,
-            counter_store,
+            backend,
             range: Arc::new(Mutex::new(0u64..0u64)),
         }
     }
 
     pub fn with_granularity(self, granularity: u64) -> Self {
         IdSequence {
-            granularity: granularity,
+            granularity: granularity.max(1),
             ..self
         }
     }
@@ -33,8 +35,26 @@ impl IdSequence {
         if let Some(id) = l.next() {
             Ok(id)
         } else {
-            *l = self.counter_store.get_range(&self.name, self.granularity).await?;
-            Ok(l.next().unwrap())
+            *l = self.backend.reserve(&self.name, self.granularity).await?;
+            l.next().ok_or(IdSequenceError::SequenceEnded)
         }
     }
+
+    /// Allocate multiple ids at once. The missing ids are reserved from the backend in a single request
+    /// rounded up to the granularity, the remaining ids are kept for the later allocations.
+    pub async fn get_batch(&self, count: u64) -> Result<Vec<u64>, IdSequenceError> {
+        let mut l = self.range.lock().await;
+        let mut ids: Vec<u64> = l.by_ref().take(count as usize).collect();
+
+        let missing = count - ids.len() as u64;
+        if missing > 0 {
+            let reserved = ((missing + self.granularity - 1) / self.granularity) * self.granularity;
+            *l = self.backend.reserve(&self.name, reserved).await?;
+            ids.extend(l.by_ref().take(missing as usize));
+            if ids.len() as u64 != count {
+                return Err(IdSequenceError::SequenceEnded);
+            }
+        }
+        Ok(ids)
+    }
 }
@@ -0,0 +1,40 @@
+use crate::idgenerator::{CounterBackend, IdSequenceError};
+use std::collections::HashMap;
+use std::future::Future;
+use std::ops::Range;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+/// Counters kept in memory, for local development and tests
+#[derive(Clone)]
+pub struct MemoryCounterStore {
+    starting_value: u64,
+    counters: Arc<Mutex<HashMap<String, u64>>>,
+}
+
+impl MemoryCounterStore {
+    pub fn new(starting_value: u64) -> MemoryCounterStore {
+        MemoryCounterStore {
+            starting_value,
+            counters: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn reserve_range(&self, sequence_id: &str, count: u64) -> Range<u64> {
+        let mut counters = self.counters.lock().unwrap();
+        let value = counters.entry(sequence_id.to_owned()).or_insert(self.starting_value);
+        let start = *value;
+        *value += count;
+        start..*value
+    }
+}
+
+impl CounterBackend for MemoryCounterStore {
+    fn reserve<'s>(
+        &'s self,
+        sequence_id: &'s str,
+        count: u64,
+    ) -> Pin<Box<dyn Future<Output = Result<Range<u64>, IdSequenceError>> + 's>> {
+        Box::pin(async move { Ok(self.reserve_range(sequence_id, count)) })
+    }
+}
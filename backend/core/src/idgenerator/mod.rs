@@ -1,9 +1,17 @@
+mod counterbackend;
 mod error;
 mod idsequence;
+mod memorycounterstore;
+mod postgrescounterstore;
 mod saltedidsequence;
+mod snowflakeidgenerator;
 mod synccounterstore;
 
+pub use self::counterbackend::*;
 pub use self::error::*;
 pub use self::idsequence::*;
+pub use self::memorycounterstore::*;
+pub use self::postgrescounterstore::*;
 pub use self::saltedidsequence::*;
+pub use self::snowflakeidgenerator::*;
 pub use self::synccounterstore::*;
@@ -0,0 +1,72 @@
+use crate::idgenerator::{CounterBackend, IdSequenceError};
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use std::future::Future;
+use std::ops::Range;
+use std::pin::Pin;
+
+/// Counters stored as the rows of a Postgres table. A reservation is a single upsert, thus concurrent
+/// reservations of the instances are serialized by the database without retries.
+#[derive(Clone)]
+pub struct PostgresCounterStore {
+    pool: PgPool,
+    reserve_query: String,
+    starting_value: i64,
+}
+
+impl PostgresCounterStore {
+    pub async fn new(
+        url: &str,
+        max_connections: u32,
+        table_name: &str,
+        starting_value: u64,
+    ) -> Result<Self, IdSequenceError> {
+        let pool = PgPoolOptions::new()
+            .max_connections(max_connections)
+            .connect(url)
+            .await?;
+
+        let schema = format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                sequence_id TEXT PRIMARY KEY,
+                value BIGINT NOT NULL
+            )",
+            table_name
+        );
+        sqlx::query(&schema).execute(&pool).await?;
+
+        // the counter is raised to the starting value both for the new and the existing sequences
+        let reserve_query = format!(
+            "INSERT INTO {t} (sequence_id, value) VALUES ($1, $2 + $3)
+                ON CONFLICT (sequence_id) DO UPDATE SET value = GREATEST({t}.value, $2) + $3
+                RETURNING value",
+            t = table_name
+        );
+
+        Ok(PostgresCounterStore {
+            pool,
+            reserve_query,
+            starting_value: starting_value as i64,
+        })
+    }
+
+    async fn reserve_range(&self, sequence_id: &str, count: u64) -> Result<Range<u64>, IdSequenceError> {
+        let (end,): (i64,) = sqlx::query_as(&self.reserve_query)
+            .bind(sequence_id)
+            .bind(self.starting_value)
+            .bind(count as i64)
+            .fetch_one(&self.pool)
+            .await?;
+        let end = end as u64;
+        Ok((end - count)..end)
+    }
+}
+
+impl CounterBackend for PostgresCounterStore {
+    fn reserve<'s>(
+        &'s self,
+        sequence_id: &'s str,
+        count: u64,
+    ) -> Pin<Box<dyn Future<Output = Result<Range<u64>, IdSequenceError>> + 's>> {
+        Box::pin(self.reserve_range(sequence_id, count))
+    }
+}
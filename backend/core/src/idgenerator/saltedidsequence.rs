@@ -1,4 +1,4 @@
-use crate::idgenerator::{IdSequenceError, SyncCounterStore};
+use crate::idgenerator::{CounterBackend, IdSequenceError};
 use futures::lock::Mutex;
 use rand::{distributions::Alphanumeric, Rng};
 use std::iter;
@@ -9,23 +9,23 @@ use std::sync::Arc;
 pub struct SaltedIdSequence {
     name: String,
     granularity: u64,
-    counter_store: SyncCounterStore,
+    backend: Arc<dyn CounterBackend>,
     range: Arc<Mutex<Range<u64>>>,
 }
 
 impl SaltedIdSequence {
-    pub fn new<S: Into<String>>(counter_store: SyncCounterStore, name: S) -> SaltedIdSequence {
+    pub fn new<S: Into<String>>(backend: Arc<dyn CounterBackend>, name: S) -> SaltedIdSequence {
         SaltedIdSequence {
             name: name.into(),
             granularity: 100,
-            counter_store,
+            backend,
             range: Arc::new(Mutex::new(0u64..0u64)),
         }
     }
 
     pub fn with_granularity(self, granularity: u64) -> Self {
         SaltedIdSequence {
-            granularity: granularity,
+            granularity: granularity.max(1),
             ..self
         }
     }
@@ -35,8 +35,8 @@ impl SaltedIdSequence {
         if let Some(id) = l.next() {
             Ok(id)
         } else {
-            *l = self.counter_store.get_range(&self.name, self.granularity).await?;
-            Ok(l.next().unwrap())
+            *l = self.backend.reserve(&self.name, self.granularity).await?;
+            l.next().ok_or(IdSequenceError::SequenceEnded)
         }
     }
 
@@ -0,0 +1,110 @@
+use crate::idgenerator::IdSequenceError;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+const WORKER_ID_BITS: u32 = 10;
+const SEQUENCE_BITS: u32 = 12;
+const MAX_WORKER_ID: u16 = (1 << WORKER_ID_BITS) - 1;
+const MAX_SEQUENCE: u64 = (1 << SEQUENCE_BITS) - 1;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SnowflakeConfig {
+    /// Unique id of the generating instance in the range of [0,1023]
+    pub worker_id: u16,
+    /// Start of the time of the ids in milliseconds since the unix epoch
+    #[serde(default = "SnowflakeConfig::default_epoch_ms")]
+    pub epoch_ms: u64,
+    /// Milliseconds the clock may move backwards (or the ids may run ahead of the clock when more than 4096
+    /// ids are requested in a millisecond) before the generation fails.
+    #[serde(default = "SnowflakeConfig::default_max_clock_skew_ms")]
+    pub max_clock_skew_ms: u64,
+}
+
+impl SnowflakeConfig {
+    fn default_epoch_ms() -> u64 {
+        // 2020-01-01T00:00:00Z
+        1_577_836_800_000
+    }
+
+    fn default_max_clock_skew_ms() -> u64 {
+        1000
+    }
+}
+
+struct Inner {
+    last_timestamp: u64,
+    sequence: u64,
+}
+
+/// Generator of 64 bit time ordered ids without storage. An id is composed of 41 bits of milliseconds since
+/// the epoch, 10 bits of worker id and 12 bits of sequence, thus the ids are unique as long as the worker
+/// ids of the instances are unique and they are roughly ordered by the time of creation across the instances.
+/// When the clock moves backwards the generator keeps counting from the last timestamp until the clock
+/// catches up, ids are never repeated.
+#[derive(Clone)]
+pub struct SnowflakeIdGenerator {
+    worker_id: u64,
+    epoch_ms: u64,
+    max_clock_skew_ms: u64,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl SnowflakeIdGenerator {
+    pub fn new(config: &SnowflakeConfig) -> Result<SnowflakeIdGenerator, IdSequenceError> {
+        if config.worker_id > MAX_WORKER_ID {
+            return Err(IdSequenceError::InvalidWorkerId(config.worker_id));
+        }
+
+        Ok(SnowflakeIdGenerator {
+            worker_id: config.worker_id as u64,
+            epoch_ms: config.epoch_ms,
+            max_clock_skew_ms: config.max_clock_skew_ms,
+            inner: Arc::new(Mutex::new(Inner {
+                last_timestamp: 0,
+                sequence: 0,
+            })),
+        })
+    }
+
+    fn now(&self) -> u64 {
+        (Utc::now().timestamp_millis() as u64).saturating_sub(self.epoch_ms)
+    }
+
+    pub fn get(&self) -> Result<u64, IdSequenceError> {
+        let now = self.now();
+        let mut inner = self.inner.lock().unwrap();
+
+        if now > inner.last_timestamp {
+            inner.last_timestamp = now;
+            inner.sequence = 0;
+        } else if inner.sequence < MAX_SEQUENCE {
+            inner.sequence += 1;
+        } else {
+            // sequence is exhausted, borrow the next millisecond
+            inner.last_timestamp += 1;
+            inner.sequence = 0;
+        }
+
+        let skew = inner.last_timestamp - now;
+        if skew > self.max_clock_skew_ms {
+            log::warn!("Clock is behind the last generated id by {}ms", skew);
+            return Err(IdSequenceError::ClockSkew(skew));
+        }
+
+        Ok((inner.last_timestamp << (WORKER_ID_BITS + SEQUENCE_BITS))
+            | (self.worker_id << SEQUENCE_BITS)
+            | inner.sequence)
+    }
+
+    /// Creation time of an id generated with the same epoch
+    pub fn created_at(&self, id: u64) -> DateTime<Utc> {
+        let timestamp = (id >> (WORKER_ID_BITS + SEQUENCE_BITS)) + self.epoch_ms;
+        Utc.timestamp_millis(timestamp as i64)
+    }
+
+    /// Worker id of the instance that generated an id
+    pub fn worker_id(id: u64) -> u16 {
+        ((id >> SEQUENCE_BITS) & MAX_WORKER_ID as u64) as u16
+    }
+}
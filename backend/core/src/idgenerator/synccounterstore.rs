@@ -1,13 +1,18 @@
-use super::IdSequenceError;
+use super::{CounterBackend, IdSequenceError};
+use crate::azure_utils;
 use crate::backoff::{self, Backoff, BackoffError};
+use azure_sdk_core::errors::AzureError;
 use azure_sdk_storage_table::{CloudTable, TableClient};
 use core::ops::Range;
 use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Duration;
 
 const PARTITION_KEY: &str = "counter";
 
+/// Settings of the counters. The table name and the starting value are used by all the counter backends.
 #[derive(Debug, Clone)]
 pub struct SyncCounterConfig {
     pub storage_account: String,
@@ -29,6 +34,17 @@ struct Inner {
     counters: CloudTable,
 }
 
+/// The instances reserving the same counter concurrently are detected by the etag of the entity,
+/// the conflicting reservations are retried.
+fn into_backoff(err: AzureError) -> BackoffError<IdSequenceError> {
+    if azure_utils::is_conflict_error(&err) || azure_utils::is_precodition_error(&err) {
+        BackoffError::Transient(IdSequenceError::Conflit)
+    } else {
+        BackoffError::Permanent(IdSequenceError::from(err))
+    }
+}
+
+/// Counters stored in an Azure table
 #[derive(Clone)]
 pub struct SyncCounterStore(Arc<Inner>);
 
@@ -51,7 +67,7 @@ impl SyncCounterStore {
             .counters
             .get::<Counter>(PARTITION_KEY, sequence_id, None)
             .await
-            .map_err(|err| BackoffError::Permanent(IdSequenceError::from(err)))?
+            .map_err(into_backoff)?
         {
             None => {
                 let start = self.0.starting_value;
@@ -59,7 +75,7 @@ impl SyncCounterStore {
                     .counters
                     .insert(PARTITION_KEY, sequence_id, Counter { value: start + count })
                     .await
-                    .map_err(|err| BackoffError::Permanent(IdSequenceError::from(err)))
+                    .map_err(into_backoff)
                     .map(|ok| start..(ok.payload.value))
             }
             Some(mut entity) => {
@@ -74,14 +90,14 @@ impl SyncCounterStore {
                     .counters
                     .update_entity(entity)
                     .await
-                    .map_err(|err| BackoffError::Permanent(IdSequenceError::from(err)))
+                    .map_err(into_backoff)
                     .map(|ok| start..(ok.payload.value))
             }
         }
     }
 
     pub async fn get_range(&self, sequence_id: &str, count: u64) -> Result<Range<u64>, IdSequenceError> {
-        backoff::Exponential::new(10, Duration::from_micros(10))
+        backoff::Exponential::new(10, Duration::from_millis(10))
             .async_execute(|_| self.get_range_step(sequence_id, count))
             .await
     }
@@ -95,3 +111,13 @@ impl SyncCounterStore {
         }
     }
 }
+
+impl CounterBackend for SyncCounterStore {
+    fn reserve<'s>(
+        &'s self,
+        sequence_id: &'s str,
+        count: u64,
+    ) -> Pin<Box<dyn Future<Output = Result<Range<u64>, IdSequenceError>> + 's>> {
+        Box::pin(self.get_range(sequence_id, count))
+    }
+}
@@ -2,12 +2,14 @@ use super::{IpLocation, IpLocationError, IpLocationProvider};
 use crate::serde_with;
 use azure_sdk_storage_table::{CloudTable, TableClient};
 use chrono::{DateTime, Utc};
+use lru::LruCache;
+use prometheus::{IntCounter, IntCounterVec, Opts, Registry};
 use serde::{Deserialize, Serialize};
 use std::future::Future;
 use std::net::IpAddr;
 use std::pin::Pin;
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone)]
 pub struct IpCachedLocationConfig {
@@ -16,6 +18,22 @@ pub struct IpCachedLocationConfig {
     pub table_name: String,
 
     pub time_to_live: Duration,
+
+    /// Number of locations kept in the in-process cache, 0 disables the in-process layer
+    pub memory_capacity: usize,
+    /// Time to live of the in-process cache
+    pub memory_time_to_live: Duration,
+}
+
+/// Hit and miss counters of the cache layers
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IpCachedLocationStats {
+    /// Lookups served from the in-process cache
+    pub memory_hit: u64,
+    /// Lookups served from the persistent cache
+    pub persistent_hit: u64,
+    /// Lookups forwarded to the provider
+    pub miss: u64,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -48,10 +66,40 @@ impl CachedData {
     }
 }
 
+/// Lookup counters by the layer serving the location
+struct Counters {
+    lookups: IntCounterVec,
+    memory_hit: IntCounter,
+    persistent_hit: IntCounter,
+    miss: IntCounter,
+}
+
+impl Counters {
+    fn new() -> Result<Counters, IpLocationError> {
+        let lookups = IntCounterVec::new(
+            Opts::new(
+                "ip_location_lookups_total",
+                "Number of the ip location lookups by the serving layer",
+            ),
+            &["layer"],
+        )
+        .map_err(|err| IpLocationError::Internal(format!("Failed to create ip location counters: {}", err)))?;
+        Ok(Counters {
+            memory_hit: lookups.with_label_values(&["memory"]),
+            persistent_hit: lookups.with_label_values(&["persistent"]),
+            miss: lookups.with_label_values(&["provider"]),
+            lookups,
+        })
+    }
+}
+
 struct Inner {
     provider: Box<dyn IpLocationProvider>,
     ttl: Duration,
     cache: CloudTable,
+    memory_ttl: Duration,
+    memory: Option<Mutex<LruCache<IpAddr, (Instant, IpLocation)>>>,
+    counters: Counters,
 }
 
 #[derive(Clone)]
@@ -67,26 +115,80 @@ impl IpCachedLocation {
 
         cache.create_if_not_exists().await?;
 
+        let memory = if config.memory_capacity > 0 {
+            Some(Mutex::new(LruCache::new(config.memory_capacity)))
+        } else {
+            None
+        };
+
         Ok(IpCachedLocation(Arc::new(Inner {
             provider: Box::new(provider),
             ttl: config.time_to_live,
             cache,
+            memory_ttl: config.memory_time_to_live,
+            memory,
+            counters: Counters::new()?,
         })))
     }
 
+    /// Register the hit and miss counters into the prometheus registry
+    pub fn register_metrics(&self, registry: &Registry) -> Result<(), prometheus::Error> {
+        registry.register(Box::new(self.0.counters.lookups.clone()))
+    }
+
+    /// Get the hit and miss counters of the cache
+    pub fn stats(&self) -> IpCachedLocationStats {
+        let counters = &self.0.counters;
+        IpCachedLocationStats {
+            memory_hit: counters.memory_hit.get(),
+            persistent_hit: counters.persistent_hit.get(),
+            miss: counters.miss.get(),
+        }
+    }
+
+    fn find_memory(&self, ip: &IpAddr) -> Option<IpLocation> {
+        let mut memory = self.0.memory.as_ref()?.lock().unwrap();
+        let expired = match memory.get(ip) {
+            Some((issued, loc)) if issued.elapsed() < self.0.memory_ttl => return Some(loc.clone()),
+            Some(_) => true,
+            None => false,
+        };
+        if expired {
+            let _ = memory.pop(ip);
+        }
+        None
+    }
+
+    fn store_memory(&self, ip: &IpAddr, loc: &IpLocation) {
+        if let Some(memory) = &self.0.memory {
+            memory.lock().unwrap().put(*ip, (Instant::now(), loc.clone()));
+        }
+    }
+
     async fn find_location(&self, ip: &IpAddr) -> Result<IpLocation, IpLocationError> {
-        // look up the cache
+        // look up the in-process cache
+        if let Some(loc) = self.find_memory(ip) {
+            self.0.counters.memory_hit.inc();
+            return Ok(loc);
+        }
+
+        // look up the persistent cache
         let row_key = ip.to_string();
         let partition_key = format!("{}", &row_key[0..2]);
         if let Ok(Some(loc)) = self.0.cache.get::<CachedData>(&partition_key, &row_key, None).await {
             let age = (Utc::now() - loc.payload.issued).to_std().unwrap_or(self.0.ttl);
             if age < self.0.ttl {
-                return Ok(loc.payload.into_location());
+                self.0.counters.persistent_hit.inc();
+                let loc = loc.payload.into_location();
+                self.store_memory(ip, &loc);
+                return Ok(loc);
             }
         }
 
         // query form the provider
+        self.0.counters.miss.inc();
         let loc = self.0.provider.get_location(&ip).await?;
+        self.store_memory(ip, &loc);
 
         // update cache
         if let Err(err) = self
@@ -0,0 +1,81 @@
+use super::{IpLocation, IpLocationError, IpLocationProvider};
+use futures::future::ready;
+use maxminddb::{geoip2, MaxMindDBError, Reader};
+use std::future::Future;
+use std::net::IpAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+
+pub struct IpLocationMaxMindConfig {
+    /// Path of the GeoLite2 (or GeoIP2) country or city database (mmdb)
+    pub database_path: String,
+}
+
+/// Ip location provider using a local MaxMind database, see https://dev.maxmind.com/geoip/geoip2/geolite2/
+#[derive(Clone)]
+pub struct IpLocationMaxMind {
+    reader: Arc<Reader<Vec<u8>>>,
+}
+
+impl IpLocationMaxMind {
+    pub fn new(config: IpLocationMaxMindConfig) -> Result<IpLocationMaxMind, IpLocationError> {
+        let reader = Reader::open_readfile(&config.database_path).map_err(|err| {
+            IpLocationError::Internal(format!(
+                "Failed to open MaxMind database {}: {:?}",
+                config.database_path, err
+            ))
+        })?;
+        log::info!(
+            "MaxMind database {} loaded, type: {}, build: {}",
+            config.database_path,
+            reader.metadata.database_type,
+            reader.metadata.build_epoch
+        );
+        Ok(IpLocationMaxMind {
+            reader: Arc::new(reader),
+        })
+    }
+
+    fn is_private(ip: &IpAddr) -> bool {
+        match ip {
+            IpAddr::V4(ip) => ip.is_private() || ip.is_loopback() || ip.is_link_local() || ip.is_unspecified(),
+            IpAddr::V6(ip) => ip.is_loopback() || ip.is_unspecified(),
+        }
+    }
+
+    fn find_location(&self, ip: &IpAddr) -> Result<IpLocation, IpLocationError> {
+        if Self::is_private(ip) {
+            return Err(IpLocationError::Private);
+        }
+
+        let response: geoip2::Country = self.reader.lookup(*ip).map_err(|err| match err {
+            MaxMindDBError::AddressNotFoundError(_) => IpLocationError::Unknown,
+            err => IpLocationError::Internal(format!("MaxMind lookup failed: {:?}", err)),
+        })?;
+
+        let country = response
+            .country
+            .and_then(|country| country.iso_code)
+            .ok_or(IpLocationError::Unknown)?;
+        let continent = response
+            .continent
+            .and_then(|continent| continent.code)
+            .ok_or(IpLocationError::Unknown)?;
+
+        Ok(IpLocation {
+            country,
+            continent,
+            extended: None,
+        })
+    }
+}
+
+impl IpLocationProvider for IpLocationMaxMind {
+    fn get_location<'s>(
+        &'s self,
+        ip: &'s IpAddr,
+    ) -> Pin<Box<dyn Future<Output = Result<IpLocation, IpLocationError>> + 's>> {
+        // lookup is a local, in-memory operation, no need for an async query
+        Box::pin(ready(self.find_location(ip)))
+    }
+}
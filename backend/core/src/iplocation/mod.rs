@@ -5,11 +5,13 @@ use std::pin::Pin;
 mod cached_location;
 mod error;
 mod ipdataco_location;
+mod maxmind_location;
 mod no_location;
 
 pub use self::cached_location::*;
 pub use self::error::*;
 pub use self::ipdataco_location::*;
+pub use self::maxmind_location::*;
 pub use self::no_location::*;
 
 /// Geo-location of an ip addres
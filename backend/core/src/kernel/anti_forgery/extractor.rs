@@ -0,0 +1,103 @@
+use super::{AntiForgeryError, AntiForgeryIdentity, AntiForgerySession, AntiForgeryValidator};
+use actix_web::{
+    dev::Payload,
+    web::{Form, FormConfig},
+    Error as ActixError, FromRequest, HttpRequest,
+};
+use futures::future::LocalBoxFuture;
+use serde::de::DeserializeOwned;
+use std::ops::Deref;
+
+/// Header of the double-submit anti-forgery token sent by the SPA clients
+pub const ANTI_FORGERY_HEADER: &str = "x-sh-af-token";
+
+/// Parameters of a POST request protected by an anti-forgery token
+pub trait AntiForgeryParams {
+    /// The token posted along with the form
+    fn af_token(&self) -> &str;
+
+    /// The action scope the token was issued for
+    fn af_scope(&self) -> Option<&str> {
+        None
+    }
+
+    fn af_identity(&self) -> AntiForgeryIdentity {
+        AntiForgeryIdentity::Ignore
+    }
+}
+
+/// Form extractor validating the anti-forgery token of the posted parameters. Rejects the request with
+/// `403 Forbidden` if the token is missing, expired or was issued for a different scope.
+pub struct AntiForgeryForm<T>(pub T);
+
+impl<T> AntiForgeryForm<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for AntiForgeryForm<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> FromRequest for AntiForgeryForm<T>
+where
+    T: AntiForgeryParams + DeserializeOwned + 'static,
+{
+    type Config = FormConfig;
+    type Error = ActixError;
+    type Future = LocalBoxFuture<'static, Result<Self, ActixError>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let session = AntiForgerySession::from_request(req, &mut Payload::None);
+        let form = Form::<T>::from_request(req, payload);
+        Box::pin(async move {
+            let session = session.await?;
+            let params = form.await?.into_inner();
+            AntiForgeryValidator::validate_scoped(
+                &session,
+                params.af_scope(),
+                params.af_token(),
+                params.af_identity(),
+            )?;
+            Ok(AntiForgeryForm(params))
+        })
+    }
+}
+
+/// Double-submit variant for the JSON API: the token issued into the anti-forgery cookie is echoed back by
+/// the client in the `x-sh-af-token` header.
+pub struct AntiForgeryHeader {
+    token: String,
+}
+
+impl AntiForgeryHeader {
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+}
+
+impl FromRequest for AntiForgeryHeader {
+    type Config = ();
+    type Error = ActixError;
+    type Future = LocalBoxFuture<'static, Result<Self, ActixError>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let session = AntiForgerySession::from_request(req, &mut Payload::None);
+        let token = req
+            .headers()
+            .get(ANTI_FORGERY_HEADER)
+            .and_then(|token| token.to_str().ok())
+            .map(|token| token.to_owned());
+        Box::pin(async move {
+            let session = session.await?;
+            let token = token.ok_or(AntiForgeryError::Missing)?;
+            let token = AntiForgeryValidator::validate(&session, &token, AntiForgeryIdentity::Ignore)?;
+            Ok(AntiForgeryHeader { token })
+        })
+    }
+}
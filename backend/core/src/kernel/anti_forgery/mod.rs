@@ -1,3 +1,5 @@
+mod extractor;
+pub use self::extractor::*;
 mod session;
 pub use self::session::*;
 mod token;
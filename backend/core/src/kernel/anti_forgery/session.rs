@@ -1,16 +1,26 @@
-use crate::signed_cookie::{CookieSecurity, Key, Session, SignedCookieOptions};
+use crate::signed_cookie::{
+    CookieKeyring, CookieSecurity, SameSite, Session, SignedCookieConfiguration, SignedCookieOptions,
+};
 
 pub type AntiForgerySession = Session<AntiForgeryCookie, ()>;
 
 pub struct AntiForgeryCookie {
     security: CookieSecurity,
+    configuration: SignedCookieConfiguration,
 }
 
 impl AntiForgeryCookie {
     pub fn new(key: &[u8]) -> AntiForgeryCookie {
-        let key = Key::from_master(key);
         AntiForgeryCookie {
-            security: CookieSecurity::Signed(key),
+            security: CookieSecurity::signed(CookieKeyring::from_secret(key)),
+            configuration: SignedCookieConfiguration::default(),
+        }
+    }
+
+    pub fn with_configuration(security: CookieSecurity, configuration: SignedCookieConfiguration) -> AntiForgeryCookie {
+        AntiForgeryCookie {
+            security,
+            configuration,
         }
     }
 
@@ -37,6 +47,14 @@ impl SignedCookieOptions for AntiForgeryCookie {
     }
 
     fn secure(&self) -> bool {
-        false
+        self.configuration.secure_or(false)
+    }
+
+    fn same_site(&self) -> Option<SameSite> {
+        self.configuration.same_site()
+    }
+
+    fn max_age(&self) -> Option<time::Duration> {
+        self.configuration.max_age()
     }
 }
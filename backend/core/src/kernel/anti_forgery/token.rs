@@ -1,12 +1,19 @@
 use super::AntiForgerySession;
-use actix_web::Error as ActixError;
+use actix_web::{http::StatusCode, Error as ActixError, ResponseError};
+use chrono::{Duration, Utc};
 use rand::{self, seq::SliceRandom};
 use serde::{Deserialize, Serialize};
-use std::mem;
+use std::{fmt, mem};
 
 const TOKEN_LEN: usize = 8;
 const TOKEN_ABC: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
 
+/// Session key of the tokens without an action scope
+const DEFAULT_SCOPE_KEY: &str = "d";
+
+/// Time to live of the tokens issued without an explicit expiry
+pub const DEFAULT_TOKEN_TIME_TO_LIVE_M: i64 = 60;
+
 #[derive(Debug, Clone)]
 pub enum AntiForgeryError {
     Missing,
@@ -15,6 +22,33 @@ pub enum AntiForgeryError {
     Internal(String),
 }
 
+impl fmt::Display for AntiForgeryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AntiForgeryError::Missing => write!(f, "Missing anti-forgery token"),
+            AntiForgeryError::Expired => write!(f, "Anti-forgery token expired"),
+            AntiForgeryError::InvalidToken => write!(f, "Invalid anti-forgery token"),
+            AntiForgeryError::Internal(err) => write!(f, "Anti-forgery check failed: {}", err),
+        }
+    }
+}
+
+impl ResponseError for AntiForgeryError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AntiForgeryError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::FORBIDDEN,
+        }
+    }
+}
+
+fn scope_key(scope: Option<&str>) -> String {
+    match scope {
+        Some(scope) => format!("{}.{}", DEFAULT_SCOPE_KEY, scope),
+        None => DEFAULT_SCOPE_KEY.to_owned(),
+    }
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub enum AntiForgeryIdentity {
     Ignore,
@@ -26,10 +60,13 @@ pub enum AntiForgeryIdentity {
 struct AntiForgeryData {
     token: String,
     identity: AntiForgeryIdentity,
+    /// Expiration as unix timestamp in seconds, the tokens of the earlier versions have no expiry
+    #[serde(default)]
+    expires_at: Option<i64>,
 }
 
 impl AntiForgeryData {
-    fn new(identity: AntiForgeryIdentity) -> Self {
+    fn new(identity: AntiForgeryIdentity, time_to_live: Duration) -> Self {
         let mut rng = rand::thread_rng();
         let token = String::from_utf8(
             TOKEN_ABC
@@ -39,7 +76,17 @@ impl AntiForgeryData {
         )
         .unwrap();
 
-        AntiForgeryData { token, identity }
+        AntiForgeryData {
+            token,
+            identity,
+            expires_at: Some((Utc::now() + time_to_live).timestamp()),
+        }
+    }
+
+    fn is_expired(&self) -> bool {
+        self.expires_at
+            .map(|expires_at| expires_at < Utc::now().timestamp())
+            .unwrap_or(false)
     }
 }
 
@@ -48,12 +95,17 @@ impl Default for AntiForgeryData {
         AntiForgeryData {
             token: Default::default(),
             identity: AntiForgeryIdentity::None,
+            expires_at: None,
         }
     }
 }
 
+/// Issue an anti-forgery token stored in the cookie session. The tokens of the different action scopes
+/// (ex. "login", "register") are stored side by side, thus a token is accepted only by the form it was
+/// issued for.
 pub struct AntiForgeryIssuer<'a> {
     data: AntiForgeryData,
+    scope: Option<String>,
     session: &'a AntiForgerySession,
 }
 
@@ -64,11 +116,24 @@ impl<'a> AntiForgeryIssuer<'a> {
                 identity
                     .map(|i| AntiForgeryIdentity::Identity(i))
                     .unwrap_or(AntiForgeryIdentity::None),
+                Duration::minutes(DEFAULT_TOKEN_TIME_TO_LIVE_M),
             ),
+            scope: None,
             session: session,
         }
     }
 
+    /// Bind the token to an action scope
+    pub fn with_scope(mut self, scope: &str) -> Self {
+        self.scope = Some(scope.to_owned());
+        self
+    }
+
+    pub fn with_time_to_live(mut self, time_to_live: Duration) -> Self {
+        self.data.expires_at = Some((Utc::now() + time_to_live).timestamp());
+        self
+    }
+
     pub fn token(&self) -> &str {
         &self.data.token
     }
@@ -78,13 +143,20 @@ impl<'a> AntiForgeryIssuer<'a> {
         let token = af.token().to_owned();
         token
     }
+
+    /// Issue a token accepted only by the validation of the same scope
+    pub fn issue_scoped<'b>(session: &'b AntiForgerySession, scope: &str, identity: Option<String>) -> String {
+        let af = Self::new(session, identity).with_scope(scope);
+        let token = af.token().to_owned();
+        token
+    }
 }
 
 impl<'a> Drop for AntiForgeryIssuer<'a> {
     fn drop(&mut self) {
-        log::info!("Set AF cookie: {:?}", self.data);
+        log::info!("Set AF cookie ({:?}): {:?}", self.scope, self.data);
         let data = mem::replace(&mut self.data, AntiForgeryData::default());
-        if let Err(err) = self.session.set("d", data) {
+        if let Err(err) = self.session.set(&scope_key(self.scope.as_deref()), data) {
             log::error!("Failed to set AF cookie: {}", err);
         }
     }
@@ -101,7 +173,15 @@ impl<'a> AntiForgeryValidator<'a> {
         session: &'b AntiForgerySession,
         identity: AntiForgeryIdentity,
     ) -> Result<AntiForgeryValidator<'b>, ActixError> {
-        let data = session.get::<AntiForgeryData>("d")?;
+        Self::with_scope(session, None, identity)
+    }
+
+    pub fn with_scope<'b>(
+        session: &'b AntiForgerySession,
+        scope: Option<&str>,
+        identity: AntiForgeryIdentity,
+    ) -> Result<AntiForgeryValidator<'b>, ActixError> {
+        let data = session.get::<AntiForgeryData>(&scope_key(scope))?;
         Ok(AntiForgeryValidator {
             data,
             _session: session,
@@ -118,6 +198,9 @@ impl<'a> AntiForgeryValidator<'a> {
             } else if data.token != token {
                 log::info!("AF token missmatch: {:?}, {:?}", token, data.token);
                 Err(AntiForgeryError::InvalidToken)
+            } else if data.is_expired() {
+                log::info!("AF token expired at {:?}", data.expires_at);
+                Err(AntiForgeryError::Expired)
             } else {
                 Ok(data.token.clone())
             }
@@ -131,7 +214,17 @@ impl<'a> AntiForgeryValidator<'a> {
         token: &str,
         identity: AntiForgeryIdentity,
     ) -> Result<String, AntiForgeryError> {
-        let af = Self::new(session, identity)
+        Self::validate_scoped(session, None, token, identity)
+    }
+
+    /// Validate a token issued for the given scope
+    pub fn validate_scoped<'b>(
+        session: &'b AntiForgerySession,
+        scope: Option<&str>,
+        token: &str,
+        identity: AntiForgeryIdentity,
+    ) -> Result<String, AntiForgeryError> {
+        let af = Self::with_scope(session, scope, identity)
             .map_err(|err| AntiForgeryError::Internal(format!("Actix error: {:?}", err)))?;
         af.validate_token(token)
     }
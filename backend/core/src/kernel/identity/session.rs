@@ -1,29 +1,44 @@
-use crate::signed_cookie::{CookieSecurity, Key, Session, SignedCookieOptions};
+use crate::signed_cookie::{
+    CookieKeyring, CookieSecurity, SameSite, Session, SignedCookieConfiguration, SignedCookieOptions,
+};
 
 pub type IdentitySession = Session<IdentityCookie, ()>;
 
 pub struct IdentityCookie {
     security: CookieSecurity,
+    configuration: SignedCookieConfiguration,
     read_only: bool,
 }
 
 impl IdentityCookie {
     pub fn write(key: &[u8]) -> IdentityCookie {
-        let key = Key::from_master(key);
         IdentityCookie {
-            security: CookieSecurity::Signed(key),
+            security: CookieSecurity::signed(CookieKeyring::from_secret(key)),
+            configuration: SignedCookieConfiguration::default(),
             read_only: false,
         }
     }
 
     pub fn read(key: &[u8]) -> IdentityCookie {
-        let key = Key::from_master(key);
         IdentityCookie {
-            security: CookieSecurity::Signed(key),
+            security: CookieSecurity::signed(CookieKeyring::from_secret(key)),
+            configuration: SignedCookieConfiguration::default(),
             read_only: true,
         }
     }
 
+    pub fn with_configuration(
+        security: CookieSecurity,
+        configuration: SignedCookieConfiguration,
+        read_only: bool,
+    ) -> IdentityCookie {
+        IdentityCookie {
+            security,
+            configuration,
+            read_only,
+        }
+    }
+
     pub fn clear(session: &IdentitySession) {
         session.clear()
     }
@@ -47,7 +62,15 @@ impl SignedCookieOptions for IdentityCookie {
     }
 
     fn secure(&self) -> bool {
-        false
+        self.configuration.secure_or(false)
+    }
+
+    fn same_site(&self) -> Option<SameSite> {
+        self.configuration.same_site()
+    }
+
+    fn max_age(&self) -> Option<time::Duration> {
+        self.configuration.max_age()
     }
 
     /*fn domain(&self) -> &str {
@@ -1,11 +1,19 @@
 pub mod azure_utils;
 pub mod backoff;
+pub mod configloader;
 pub mod gremlin_utils;
+pub mod grpc;
 pub mod idgenerator;
 pub mod iplocation;
 pub mod kernel;
+pub mod localization;
+pub mod mailer;
+pub mod metrics;
+pub mod pubsub;
+pub mod ratelimit;
 pub mod recaptcha;
 pub mod requestinfo;
+pub mod secret;
 pub mod serde;
 pub mod serde_with;
 pub mod signed_cookie;
@@ -0,0 +1,241 @@
+use super::LocalizationError;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, path::Path, sync::Arc};
+use tera::{Tera, Value};
+
+const LOCALE_EXTENSION: &str = "ftl";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LocalizationConfig {
+    /// Folder of the `<lang>.ftl` message files, without it only the built-in (english) messages are used
+    pub folder: Option<String>,
+    #[serde(default = "LocalizationConfig::default_language")]
+    pub default_language: String,
+}
+
+impl LocalizationConfig {
+    fn default_language() -> String {
+        "en".to_owned()
+    }
+}
+
+impl Default for LocalizationConfig {
+    fn default() -> Self {
+        LocalizationConfig {
+            folder: None,
+            default_language: Self::default_language(),
+        }
+    }
+}
+
+type Messages = HashMap<String, String>;
+
+struct Inner {
+    default_language: String,
+    locales: HashMap<String, Messages>,
+}
+
+/// Translated messages of the supported languages. The message files use the simple message syntax of Fluent:
+/// `key = value` lines, `#` comments, indented continuation lines and `{ $arg }` placeables.
+#[derive(Clone)]
+pub struct Catalog(Arc<Inner>);
+
+impl Catalog {
+    pub fn empty(default_language: &str) -> Catalog {
+        Catalog(Arc::new(Inner {
+            default_language: default_language.to_owned(),
+            locales: HashMap::new(),
+        }))
+    }
+
+    pub fn new(config: &LocalizationConfig) -> Result<Catalog, LocalizationError> {
+        match &config.folder {
+            Some(folder) => Catalog::load(folder, &config.default_language),
+            None => Ok(Catalog::empty(&config.default_language)),
+        }
+    }
+
+    /// Load all the `<lang>.ftl` files of a folder
+    pub fn load(folder: &str, default_language: &str) -> Result<Catalog, LocalizationError> {
+        let io_error = |err| LocalizationError::Io(folder.to_owned(), err);
+        let mut locales = HashMap::new();
+        for entry in fs::read_dir(folder).map_err(io_error)? {
+            let path = entry.map_err(io_error)?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(LOCALE_EXTENSION) {
+                continue;
+            }
+            if let Some(lang) = path.file_stem().and_then(|lang| lang.to_str()) {
+                let messages = Self::load_file(&path)?;
+                log::info!("Locale {} loaded with {} messages", lang, messages.len());
+                locales.insert(lang.to_lowercase(), messages);
+            }
+        }
+
+        if !locales.contains_key(default_language) {
+            log::warn!(
+                "No messages for the default language {} in {}",
+                default_language,
+                folder
+            );
+        }
+
+        Ok(Catalog(Arc::new(Inner {
+            default_language: default_language.to_owned(),
+            locales,
+        })))
+    }
+
+    fn load_file(path: &Path) -> Result<Messages, LocalizationError> {
+        let file = path.to_string_lossy().into_owned();
+        let content = fs::read_to_string(path).map_err(|err| LocalizationError::Io(file.clone(), err))?;
+        Self::parse(&file, &content)
+    }
+
+    /// Parse the content of a message file
+    pub fn parse(file: &str, content: &str) -> Result<HashMap<String, String>, LocalizationError> {
+        let mut messages = HashMap::new();
+        let mut current: Option<(String, String)> = None;
+
+        for (i, line) in content.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with(char::is_whitespace) {
+                match &mut current {
+                    Some((_, value)) => {
+                        if !value.is_empty() {
+                            value.push('\n');
+                        }
+                        value.push_str(trimmed);
+                    }
+                    None => {
+                        return Err(LocalizationError::Parse {
+                            file: file.to_owned(),
+                            line: i + 1,
+                            message: "Continuation line without a message".to_owned(),
+                        })
+                    }
+                }
+                continue;
+            }
+
+            let mut parts = line.splitn(2, '=');
+            match (parts.next().map(|key| key.trim()), parts.next()) {
+                (Some(key), Some(value)) if !key.is_empty() => {
+                    if let Some((key, value)) = current.take() {
+                        messages.insert(key, value);
+                    }
+                    current = Some((key.to_owned(), value.trim().to_owned()));
+                }
+                _ => {
+                    return Err(LocalizationError::Parse {
+                        file: file.to_owned(),
+                        line: i + 1,
+                        message: "Expected `key = value`".to_owned(),
+                    })
+                }
+            }
+        }
+
+        if let Some((key, value)) = current.take() {
+            messages.insert(key, value);
+        }
+        Ok(messages)
+    }
+
+    pub fn default_language(&self) -> &str {
+        &self.0.default_language
+    }
+
+    pub fn is_supported(&self, lang: &str) -> bool {
+        lang == self.0.default_language || self.0.locales.contains_key(lang)
+    }
+
+    /// Find the message in the given language falling back to the default language
+    pub fn find(&self, lang: &str, key: &str) -> Option<&str> {
+        self.0
+            .locales
+            .get(lang)
+            .and_then(|messages| messages.get(key))
+            .or_else(|| {
+                self.0
+                    .locales
+                    .get(&self.0.default_language)
+                    .and_then(|messages| messages.get(key))
+            })
+            .map(|message| message.as_str())
+    }
+
+    /// Translate a message and substitute the `{ $arg }` placeables. If the message is not found
+    /// the fallback is used.
+    pub fn translate_or(&self, lang: &str, key: &str, fallback: &str, args: &[(&str, &str)]) -> String {
+        let message = self.find(lang, key).unwrap_or(fallback);
+        Self::format(message, args)
+    }
+
+    /// Translate a message, the key is returned if the message is not found.
+    pub fn translate(&self, lang: &str, key: &str, args: &[(&str, &str)]) -> String {
+        self.translate_or(lang, key, key, args)
+    }
+
+    fn format(message: &str, args: &[(&str, &str)]) -> String {
+        let mut result = String::with_capacity(message.len());
+        let mut rest = message;
+        while let Some(start) = rest.find('{') {
+            result.push_str(&rest[..start]);
+            let end = match rest[start..].find('}') {
+                Some(end) => start + end,
+                None => {
+                    rest = &rest[start..];
+                    break;
+                }
+            };
+            let placeable = rest[start + 1..end].trim();
+            let value = placeable
+                .strip_prefix('$')
+                .and_then(|name| args.iter().find(|(arg, _)| *arg == name))
+                .map(|(_, value)| *value);
+            match value {
+                Some(value) => result.push_str(value),
+                None => result.push_str(&rest[start..=end]),
+            }
+            rest = &rest[end + 1..];
+        }
+        result.push_str(rest);
+        result
+    }
+
+    /// Register the `tr(key, lang, default, ..args)` function translating the messages in the templates.
+    pub fn register_tera_function(&self, tera: &mut Tera) {
+        let catalog = self.clone();
+        tera.register_function("tr", move |args: &HashMap<String, Value>| {
+            let key = args
+                .get("key")
+                .and_then(|key| key.as_str())
+                .ok_or_else(|| tera::Error::msg("tr: missing key"))?;
+            let lang = args
+                .get("lang")
+                .and_then(|lang| lang.as_str())
+                .unwrap_or_else(|| catalog.default_language());
+            let fallback = args
+                .get("default")
+                .and_then(|fallback| fallback.as_str())
+                .unwrap_or(key);
+            let params: Vec<(String, String)> = args
+                .iter()
+                .filter(|(name, _)| !["key", "lang", "default"].contains(&name.as_str()))
+                .map(|(name, value)| match value {
+                    Value::String(value) => (name.clone(), value.clone()),
+                    value => (name.clone(), value.to_string()),
+                })
+                .collect();
+            let params: Vec<(&str, &str)> = params
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str()))
+                .collect();
+            Ok(Value::String(catalog.translate_or(lang, key, fallback, &params)))
+        });
+    }
+}
@@ -0,0 +1,22 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum LocalizationError {
+    /// The catalog folder or a locale file could not be read
+    Io(String, io::Error),
+    /// Syntax error in a locale file
+    Parse { file: String, line: usize, message: String },
+}
+
+impl fmt::Display for LocalizationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LocalizationError::Io(path, err) => write!(f, "Failed to read {}: {}", path, err),
+            LocalizationError::Parse { file, line, message } => write!(f, "{}:{}: {}", file, line, message),
+        }
+    }
+}
+
+impl Error for LocalizationError {}
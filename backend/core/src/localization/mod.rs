@@ -0,0 +1,7 @@
+mod catalog;
+mod error;
+mod negotiation;
+
+pub use self::catalog::*;
+pub use self::error::*;
+pub use self::negotiation::*;
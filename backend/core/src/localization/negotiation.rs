@@ -0,0 +1,79 @@
+use super::Catalog;
+use actix_web::{http::header, HttpMessage, HttpRequest};
+
+/// Name of the cookie storing the language selected by the user
+pub const LANGUAGE_COOKIE: &str = "lang";
+
+/// Parse the Accept-Language header into the language tags in the order of preference
+pub fn parse_accept_language(value: &str) -> Vec<String> {
+    let mut languages: Vec<(String, f32)> = value
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';');
+            let tag = parts.next()?.trim().to_lowercase();
+            if tag.is_empty() || tag == "*" {
+                return None;
+            }
+            let quality = parts
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .next()
+                .and_then(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.);
+            if quality > 0. {
+                Some((tag, quality))
+            } else {
+                None
+            }
+        })
+        .collect();
+    // stable sort keeps the order of the header for the equal qualities
+    languages.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    languages.into_iter().map(|(tag, _)| tag).collect()
+}
+
+impl Catalog {
+    /// Find the supported language of a tag, ex. "en-US" is served by "en" if there is no "en-us" locale.
+    pub fn match_language(&self, tag: &str) -> Option<String> {
+        let tag = tag.trim().to_lowercase();
+        if self.is_supported(&tag) {
+            return Some(tag);
+        }
+        let primary = tag.split(|c| c == '-' || c == '_').next().unwrap_or("");
+        if !primary.is_empty() && self.is_supported(primary) {
+            Some(primary.to_owned())
+        } else {
+            None
+        }
+    }
+
+    /// Select the language of a request in the order of: the language of the path, the language cookie,
+    /// the Accept-Language header and the default language.
+    pub fn negotiate(&self, req: &HttpRequest, path_language: Option<&str>) -> String {
+        if let Some(lang) = path_language.and_then(|lang| self.match_language(lang)) {
+            return lang;
+        }
+
+        if let Some(lang) = req
+            .cookie(LANGUAGE_COOKIE)
+            .and_then(|cookie| self.match_language(cookie.value()))
+        {
+            return lang;
+        }
+
+        if let Some(lang) = req
+            .headers()
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| {
+                parse_accept_language(value)
+                    .iter()
+                    .filter_map(|tag| self.match_language(tag))
+                    .next()
+            })
+        {
+            return lang;
+        }
+
+        self.default_language().to_owned()
+    }
+}
@@ -0,0 +1,26 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum MailerError {
+    /// Invalid mail (address, content)
+    InvalidMail(String),
+
+    /// Mail template rendering failed
+    Template(String),
+
+    /// Mail delivery failed
+    Delivery(String),
+}
+
+impl fmt::Display for MailerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MailerError::InvalidMail(ref e) => write!(f, "Invalid mail: {}", e),
+            MailerError::Template(ref e) => write!(f, "Mail template error: {}", e),
+            MailerError::Delivery(ref e) => write!(f, "Mail delivery failed: {}", e),
+        }
+    }
+}
+
+impl Error for MailerError {}
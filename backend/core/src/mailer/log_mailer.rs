@@ -0,0 +1,15 @@
+use super::{Mail, Mailer, MailerError};
+use futures::future::ready;
+use std::future::Future;
+use std::pin::Pin;
+
+/// Mailer that only logs the mails without sending them
+#[derive(Clone)]
+pub struct LogMailer;
+
+impl Mailer for LogMailer {
+    fn send<'s>(&'s self, mail: &'s Mail) -> Pin<Box<dyn Future<Output = Result<(), MailerError>> + 's>> {
+        log::info!("Sending mail to {}: {}\n{}", mail.to, mail.subject, mail.body);
+        Box::pin(ready(Ok(())))
+    }
+}
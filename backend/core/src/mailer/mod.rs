@@ -0,0 +1,43 @@
+use std::future::Future;
+use std::pin::Pin;
+use tera::{Context, Tera};
+
+mod error;
+mod log_mailer;
+mod null_mailer;
+mod smtp_mailer;
+
+pub use self::error::*;
+pub use self::log_mailer::*;
+pub use self::null_mailer::*;
+pub use self::smtp_mailer::*;
+
+/// A single mail to be delivered
+#[derive(Clone, Debug)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+impl Mail {
+    /// Create a mail with the body rendered from a Tera template
+    pub fn from_template(
+        tera: &Tera,
+        template: &str,
+        context: &Context,
+        to: String,
+        subject: String,
+    ) -> Result<Mail, MailerError> {
+        let body = tera.render(template, context).map_err(|err| {
+            log::error!("Tera render error: {:?}", err);
+            MailerError::Template(format!("Failed to render {}: {}", template, err))
+        })?;
+        Ok(Mail { to, subject, body })
+    }
+}
+
+/// Trait to deliver mails
+pub trait Mailer: Sync + Send {
+    fn send<'s>(&'s self, mail: &'s Mail) -> Pin<Box<dyn Future<Output = Result<(), MailerError>> + 's>>;
+}
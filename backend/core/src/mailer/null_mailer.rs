@@ -0,0 +1,14 @@
+use super::{Mail, Mailer, MailerError};
+use futures::future::ready;
+use std::future::Future;
+use std::pin::Pin;
+
+/// Mailer that drops the mails, for tests and for services without mail delivery
+#[derive(Clone)]
+pub struct NullMailer;
+
+impl Mailer for NullMailer {
+    fn send<'s>(&'s self, _mail: &'s Mail) -> Pin<Box<dyn Future<Output = Result<(), MailerError>> + 's>> {
+        Box::pin(ready(Ok(())))
+    }
+}
@@ -0,0 +1,86 @@
+use super::{Mail, Mailer, MailerError};
+use crate::backoff::{self, Backoff, BackoffError};
+use lettre::{
+    transport::smtp::{authentication::Credentials, Error as SmtpError},
+    AsyncSmtpTransport, Message, Tokio02Connector, Tokio02Transport,
+};
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+pub struct SmtpMailerConfig {
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    pub password: String,
+    /// Sender address of the mails, ex. "Shine <noreply@example.com>"
+    pub from: String,
+    /// Number of retries of the transient delivery failures
+    pub retry_count: usize,
+}
+
+/// Mailer delivering the mails through an SMTP relay. Transient failures are retried with an
+/// exponential backoff.
+#[derive(Clone)]
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio02Connector>,
+    from: String,
+    retry_count: usize,
+}
+
+impl SmtpMailer {
+    pub fn new(config: SmtpMailerConfig) -> Result<SmtpMailer, MailerError> {
+        let transport = AsyncSmtpTransport::<Tokio02Connector>::relay(&config.host)
+            .map_err(|err| MailerError::Delivery(format!("Invalid relay {}: {}", config.host, err)))?
+            .port(config.port)
+            .credentials(Credentials::new(config.user, config.password))
+            .build();
+
+        Ok(SmtpMailer {
+            transport,
+            from: config.from,
+            retry_count: config.retry_count,
+        })
+    }
+
+    fn create_message(&self, mail: &Mail) -> Result<Message, MailerError> {
+        let from = self
+            .from
+            .parse()
+            .map_err(|err| MailerError::InvalidMail(format!("Invalid sender {}: {}", self.from, err)))?;
+        let to = mail
+            .to
+            .parse()
+            .map_err(|err| MailerError::InvalidMail(format!("Invalid recipient {}: {}", mail.to, err)))?;
+        Message::builder()
+            .from(from)
+            .to(to)
+            .subject(mail.subject.clone())
+            .body(mail.body.clone())
+            .map_err(|err| MailerError::InvalidMail(err.to_string()))
+    }
+
+    async fn send_step(&self, message: &Message) -> Result<(), BackoffError<MailerError>> {
+        match self.transport.send(message.clone()).await {
+            Ok(_) => Ok(()),
+            Err(err @ SmtpError::Transient(_)) | Err(err @ SmtpError::Io(_)) => {
+                log::warn!("Mail delivery failed, retrying: {}", err);
+                Err(BackoffError::Transient(MailerError::Delivery(err.to_string())))
+            }
+            Err(err) => Err(BackoffError::Permanent(MailerError::Delivery(err.to_string()))),
+        }
+    }
+
+    async fn send_impl(&self, mail: &Mail) -> Result<(), MailerError> {
+        let message = self.create_message(mail)?;
+        backoff::Exponential::new(self.retry_count, Duration::from_millis(500))
+            .async_execute(|_| self.send_step(&message))
+            .await
+    }
+}
+
+impl Mailer for SmtpMailer {
+    fn send<'s>(&'s self, mail: &'s Mail) -> Pin<Box<dyn Future<Output = Result<(), MailerError>> + 's>> {
+        Box::pin(self.send_impl(mail))
+    }
+}
@@ -0,0 +1,33 @@
+use actix_web::{HttpResponse, ResponseError};
+use std::fmt;
+
+/// Errors that can occur during metrics collection
+#[derive(Debug)]
+pub enum MetricsError {
+    /// Failed to create or register a metric
+    Registry(String),
+
+    /// Failed to encode the collected metrics
+    Encode(String),
+}
+
+impl fmt::Display for MetricsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MetricsError::Registry(err) => write!(f, "Metric registration failed: {}", err),
+            MetricsError::Encode(err) => write!(f, "Metric encoding failed: {}", err),
+        }
+    }
+}
+
+impl ResponseError for MetricsError {
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::InternalServerError().finish()
+    }
+}
+
+impl From<prometheus::Error> for MetricsError {
+    fn from(err: prometheus::Error) -> MetricsError {
+        MetricsError::Registry(err.to_string())
+    }
+}
@@ -0,0 +1,90 @@
+use super::Metrics;
+use actix_service::{Service, Transform};
+use actix_web::{
+    dev::{ServiceRequest, ServiceResponse},
+    Error,
+};
+use futures::future::{ok, FutureExt, LocalBoxFuture, Ready};
+use std::{
+    task::{Context, Poll},
+    time::Instant,
+};
+
+/// Request counter and latency middleware factory
+#[derive(Clone)]
+pub struct RequestMetrics {
+    metrics: Metrics,
+    service: String,
+}
+
+impl RequestMetrics {
+    pub fn new(metrics: Metrics, service: &str) -> RequestMetrics {
+        RequestMetrics {
+            metrics,
+            service: service.to_owned(),
+        }
+    }
+}
+
+impl<S, B: 'static> Transform<S> for RequestMetrics
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequestMetricsMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RequestMetricsMiddleware {
+            metrics: self.metrics.clone(),
+            name: self.service.clone(),
+            service,
+        })
+    }
+}
+
+/// Request counter and latency middleware
+pub struct RequestMetricsMiddleware<S> {
+    metrics: Metrics,
+    name: String,
+    service: S,
+}
+
+impl<S, B: 'static> Service for RequestMetricsMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let metrics = self.metrics.clone();
+        let name = self.name.clone();
+        let method = req.method().to_string();
+        let start = Instant::now();
+
+        let fut = self.service.call(req);
+
+        async move {
+            let res = fut.await;
+            let status = match &res {
+                Ok(res) => res.status(),
+                Err(err) => err.as_response_error().status_code(),
+            };
+            metrics.observe_request(&name, &method, status.as_u16(), start.elapsed());
+            res
+        }
+        .boxed_local()
+    }
+}
@@ -0,0 +1,90 @@
+mod error;
+mod middleware;
+
+pub use self::error::*;
+pub use self::middleware::*;
+
+use actix_web::{web, HttpResponse};
+use prometheus::{Encoder, HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder};
+use std::sync::Arc;
+use std::time::Duration;
+
+struct Inner {
+    registry: Registry,
+    requests: IntCounterVec,
+    request_latency: HistogramVec,
+    operations: IntCounterVec,
+}
+
+/// Prometheus metrics shared by the services. The services can register their own metrics into the registry.
+#[derive(Clone)]
+pub struct Metrics(Arc<Inner>);
+
+impl Metrics {
+    pub fn new() -> Result<Metrics, MetricsError> {
+        let registry = Registry::new_custom(Some("shine".to_owned()), None)?;
+
+        let requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "Number of the served http requests"),
+            &["service", "method", "status"],
+        )?;
+        registry.register(Box::new(requests.clone()))?;
+
+        let request_latency = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "Latency of the http requests"),
+            &["service", "method"],
+        )?;
+        registry.register(Box::new(request_latency.clone()))?;
+
+        let operations = IntCounterVec::new(
+            Opts::new("operations_total", "Number of the service operations by result"),
+            &["service", "operation", "result"],
+        )?;
+        registry.register(Box::new(operations.clone()))?;
+
+        Ok(Metrics(Arc::new(Inner {
+            registry,
+            requests,
+            request_latency,
+            operations,
+        })))
+    }
+
+    pub fn registry(&self) -> &Registry {
+        &self.0.registry
+    }
+
+    /// Record a served request
+    pub fn observe_request(&self, service: &str, method: &str, status: u16, elapsed: Duration) {
+        self.0
+            .requests
+            .with_label_values(&[service, method, &status.to_string()])
+            .inc();
+        self.0
+            .request_latency
+            .with_label_values(&[service, method])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Count a (business) operation of a service, ex. login, registration
+    pub fn count_operation(&self, service: &str, operation: &str, success: bool) {
+        let result = if success { "success" } else { "failure" };
+        self.0.operations.with_label_values(&[service, operation, result]).inc();
+    }
+
+    /// Encode the metrics in the prometheus text format
+    pub fn encode(&self) -> Result<(String, Vec<u8>), MetricsError> {
+        let encoder = TextEncoder::new();
+        let mut buffer = Vec::new();
+        encoder
+            .encode(&self.0.registry.gather(), &mut buffer)
+            .map_err(|err| MetricsError::Encode(err.to_string()))?;
+        Ok((encoder.format_type().to_owned(), buffer))
+    }
+}
+
+/// Handler to expose the metrics for the prometheus scraper
+pub async fn get_metrics(metrics: web::Data<Metrics>) -> Result<HttpResponse, MetricsError> {
+    let (content_type, body) = metrics.encode()?;
+    Ok(HttpResponse::Ok().content_type(content_type).body(body))
+}
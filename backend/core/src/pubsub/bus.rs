@@ -0,0 +1,113 @@
+use futures::{
+    channel::mpsc::{self, Receiver, Sender},
+    stream::Stream,
+    task::{Context, Poll},
+};
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+/// Number of the undelivered messages of a subscriber, further messages are dropped until the
+/// subscriber catches up
+const DEFAULT_CAPACITY: usize = 64;
+
+/// Stream of the messages published to a topic. The subscription is removed when it is dropped.
+pub struct Subscription<T> {
+    receiver: Receiver<T>,
+}
+
+impl<T> Stream for Subscription<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+/// In-process publish/subscribe bus with string topics. The bus is shared by the workers, messages
+/// are not persisted and are delivered only to the subscribers of the topic at the time of publishing.
+pub struct PubSub<T> {
+    capacity: usize,
+    topics: Arc<Mutex<HashMap<String, Vec<Sender<T>>>>>,
+}
+
+impl<T> Clone for PubSub<T> {
+    fn clone(&self) -> Self {
+        PubSub {
+            capacity: self.capacity,
+            topics: self.topics.clone(),
+        }
+    }
+}
+
+impl<T> Default for PubSub<T>
+where
+    T: Clone + Send + 'static,
+{
+    fn default() -> Self {
+        PubSub::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl<T> PubSub<T>
+where
+    T: Clone + Send + 'static,
+{
+    pub fn new(capacity: usize) -> PubSub<T> {
+        PubSub {
+            capacity,
+            topics: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn subscribe(&self, topic: &str) -> Subscription<T> {
+        let (sender, receiver) = mpsc::channel(self.capacity);
+        let mut topics = self.topics.lock().unwrap();
+        topics.entry(topic.to_owned()).or_insert_with(Vec::new).push(sender);
+        Subscription { receiver }
+    }
+
+    /// Send a message to the subscribers of a topic. Returns the number of the subscribers the message
+    /// was delivered to.
+    pub fn publish(&self, topic: &str, message: T) -> usize {
+        let mut topics = self.topics.lock().unwrap();
+        let subscribers = match topics.get_mut(topic) {
+            Some(subscribers) => subscribers,
+            None => return 0,
+        };
+
+        let mut delivered = 0;
+        let mut alive = Vec::with_capacity(subscribers.len());
+        for mut subscriber in subscribers.drain(..) {
+            match subscriber.try_send(message.clone()) {
+                Ok(()) => {
+                    delivered += 1;
+                    alive.push(subscriber);
+                }
+                Err(err) if err.is_full() => {
+                    log::warn!("Subscriber of {} is lagging, message dropped", topic);
+                    alive.push(subscriber);
+                }
+                // dropped subscription
+                Err(_) => {}
+            }
+        }
+        *subscribers = alive;
+
+        if subscribers.is_empty() {
+            topics.remove(topic);
+        }
+        delivered
+    }
+
+    /// Number of the subscribers of a topic
+    pub fn subscriber_count(&self, topic: &str) -> usize {
+        let topics = self.topics.lock().unwrap();
+        topics
+            .get(topic)
+            .map(|subscribers| subscribers.iter().filter(|s| !s.is_closed()).count())
+            .unwrap_or(0)
+    }
+}
@@ -0,0 +1,3 @@
+mod bus;
+
+pub use self::bus::*;
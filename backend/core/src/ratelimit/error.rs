@@ -0,0 +1,27 @@
+use actix_web::{http::header, HttpResponse, ResponseError};
+use std::fmt;
+
+/// Errors that can occur during rate limiting
+#[derive(Debug)]
+pub enum RateLimitError {
+    /// Request limit exceeded, retry is allowed after the given number of seconds
+    TooManyRequests(u64),
+}
+
+impl fmt::Display for RateLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RateLimitError::TooManyRequests(retry) => write!(f, "Too many requests, retry after {}s", retry),
+        }
+    }
+}
+
+impl ResponseError for RateLimitError {
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            RateLimitError::TooManyRequests(retry) => HttpResponse::TooManyRequests()
+                .header(header::RETRY_AFTER, retry.to_string())
+                .finish(),
+        }
+    }
+}
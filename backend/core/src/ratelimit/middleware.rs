@@ -0,0 +1,122 @@
+use super::{RateLimitKey, RateLimiter};
+use crate::requestinfo::BasicAuth;
+use actix_service::{Service, Transform};
+use actix_web::{
+    dev::{ServiceRequest, ServiceResponse},
+    http::header,
+    Error,
+};
+use futures::future::{err, ok, Either, Ready};
+use std::{
+    net,
+    task::{Context, Poll},
+};
+
+/// Rate limiter middleware factory
+#[derive(Clone)]
+pub struct RateLimit {
+    limiter: RateLimiter,
+}
+
+impl RateLimit {
+    pub fn new(limiter: RateLimiter) -> RateLimit {
+        RateLimit { limiter }
+    }
+}
+
+impl<S, B: 'static> Transform<S> for RateLimit
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RateLimitMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RateLimitMiddleware {
+            limiter: self.limiter.clone(),
+            service,
+        })
+    }
+}
+
+/// Rate limiter middleware
+pub struct RateLimitMiddleware<S> {
+    limiter: RateLimiter,
+    service: S,
+}
+
+impl<S> RateLimitMiddleware<S> {
+    /// The ip of the client. The X-Forwarded-For header is used only if the peer is a trusted proxy, then the
+    /// address appended by the last untrusted hop is selected as the header can be set by the client.
+    fn client_ip(&self, req: &ServiceRequest) -> String {
+        let peer = match req.peer_addr() {
+            Some(peer) => peer.ip(),
+            None => return String::new(),
+        };
+        if !self.limiter.is_trusted_proxy(&peer) {
+            return peer.to_string();
+        }
+
+        // the hops are appended, the last header and the last address is the closest to us
+        let forwarded = req
+            .headers()
+            .get_all("x-forwarded-for")
+            .filter_map(|header| header.to_str().ok())
+            .collect::<Vec<_>>();
+        let mut ip = peer;
+        for hop in forwarded.iter().rev().flat_map(|header| header.split(',').rev()) {
+            match hop.trim().parse::<net::IpAddr>() {
+                Ok(hop) if self.limiter.is_trusted_proxy(&hop) => ip = hop,
+                Ok(hop) => return hop.to_string(),
+                Err(_) => break,
+            }
+        }
+        ip.to_string()
+    }
+
+    fn client_key(&self, req: &ServiceRequest) -> String {
+        let user = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|header| BasicAuth::from_header(header).ok())
+            .map(|auth| auth.user_id().to_owned())
+            .filter(|user| !user.is_empty());
+
+        match (self.limiter.key(), user) {
+            (RateLimitKey::Ip, _) | (_, None) => self.client_ip(req),
+            (RateLimitKey::User, Some(user)) => format!("user:{}", user),
+            (RateLimitKey::IpAndUser, Some(user)) => format!("{}/{}", self.client_ip(req), user),
+        }
+    }
+}
+
+impl<S, B: 'static> Service for RateLimitMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let key = self.client_key(&req);
+        match self.limiter.check(&key) {
+            Ok(()) => Either::Left(self.service.call(req)),
+            Err(e) => {
+                log::info!("Rate limit exceeded for [{}] on {}", key, req.path());
+                Either::Right(err(e.into()))
+            }
+        }
+    }
+}
@@ -0,0 +1,7 @@
+mod error;
+mod middleware;
+mod token_bucket;
+
+pub use self::error::*;
+pub use self::middleware::*;
+pub use self::token_bucket::*;
@@ -0,0 +1,127 @@
+use super::RateLimitError;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+/// Select the key used to identify the client of a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RateLimitKey {
+    /// Limit by the remote ip
+    Ip,
+    /// Limit by the user given in the basic authorization header, by the remote ip without the header
+    User,
+    /// Limit by the remote ip and user pair, by the remote ip without the authorization header
+    IpAndUser,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    /// Number of requests allowed in a burst
+    pub capacity: u32,
+    /// Number of requests restored in each minute
+    pub refill_per_minute: u32,
+    pub key: RateLimitKey,
+    /// Maximum number of the tracked clients, the idle buckets are purged first then the least recently
+    /// used ones are evicted
+    pub max_entries: usize,
+    /// Address of the reverse proxies allowed to report the client ip in the X-Forwarded-For header
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> RateLimitConfig {
+        RateLimitConfig {
+            capacity: 10,
+            refill_per_minute: 10,
+            key: RateLimitKey::Ip,
+            max_entries: 10000,
+            trusted_proxies: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+struct Inner {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+}
+
+/// Token bucket based rate limiter shared by all the workers.
+#[derive(Clone)]
+pub struct RateLimiter(Arc<Inner>);
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> RateLimiter {
+        RateLimiter(Arc::new(Inner {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        }))
+    }
+
+    pub fn key(&self) -> RateLimitKey {
+        self.0.config.key
+    }
+
+    pub fn is_trusted_proxy(&self, ip: &IpAddr) -> bool {
+        self.0.config.trusted_proxies.contains(ip)
+    }
+
+    fn refill_per_sec(&self) -> f64 {
+        (self.0.config.refill_per_minute as f64 / 60.).max(std::f64::EPSILON)
+    }
+
+    /// Consume a token for the given client.
+    pub fn check(&self, client: &str) -> Result<(), RateLimitError> {
+        let capacity = self.0.config.capacity as f64;
+        let refill_per_sec = self.refill_per_sec();
+        let now = Instant::now();
+
+        let mut buckets = self.0.buckets.lock().unwrap();
+        if buckets.len() >= self.0.config.max_entries && !buckets.contains_key(client) {
+            // drop the buckets that have been refilled completely, they are identical to a new bucket
+            buckets.retain(|_, bucket| {
+                let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+                bucket.tokens + elapsed * refill_per_sec < capacity
+            });
+            log::debug!("Rate limiter purged, remaining clients: {}", buckets.len());
+
+            if buckets.len() >= self.0.config.max_entries {
+                let oldest = buckets
+                    .iter()
+                    .min_by_key(|(_, bucket)| bucket.last_refill)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    log::debug!("Rate limiter is full, evicting [{}]", oldest);
+                    buckets.remove(&oldest);
+                }
+            }
+        }
+
+        let bucket = buckets.entry(client.to_owned()).or_insert_with(|| TokenBucket {
+            tokens: capacity,
+            last_refill: now,
+        });
+
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * refill_per_sec).min(capacity);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1. {
+            bucket.tokens -= 1.;
+            Ok(())
+        } else {
+            let retry_after = ((1. - bucket.tokens) / refill_per_sec).ceil() as u64;
+            Err(RateLimitError::TooManyRequests(retry_after))
+        }
+    }
+}
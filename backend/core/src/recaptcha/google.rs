@@ -0,0 +1,100 @@
+use super::{CaptchaProvider, RecaptchaError};
+use reqwest::Client;
+use serde::Deserialize;
+use std::{future::Future, pin::Pin};
+
+#[derive(Debug, Deserialize)]
+struct RecaptchaResponse {
+    success: bool,
+    /// Score of the v3 api, 1.0 is very likely a good interaction
+    score: Option<f32>,
+    /// Action of the v3 api
+    action: Option<String>,
+    #[serde(rename = "error-codes")]
+    error_codes: Option<Vec<String>>,
+}
+
+/// Google reCAPTCHA. Without a minimum score the v2 pass/fail verdict is used, with a minimum score the
+/// v3 score and action are also checked.
+#[derive(Clone)]
+pub struct Recaptcha {
+    client: Client,
+    secret: String,
+    site_key: String,
+    min_score: Option<f32>,
+}
+
+impl Recaptcha {
+    pub fn new(secret: String, site_key: String) -> Recaptcha {
+        Recaptcha {
+            client: Client::new(),
+            secret,
+            site_key,
+            min_score: None,
+        }
+    }
+
+    /// Use the v3 api and reject the responses below the given score
+    pub fn with_min_score(self, min_score: f32) -> Recaptcha {
+        Recaptcha {
+            min_score: Some(min_score),
+            ..self
+        }
+    }
+
+    pub fn site_key(&self) -> &str {
+        &self.site_key
+    }
+
+    pub async fn check_response(&self, response: &str, action: &str) -> Result<(), RecaptchaError> {
+        let response = self
+            .client
+            .post("https://www.google.com/recaptcha/api/siteverify")
+            .form(&[("secret", self.secret.as_str()), ("response", response)])
+            .send()
+            .await?;
+
+        let response = response.json::<RecaptchaResponse>().await?;
+        if !response.success {
+            let error = response.error_codes.map(|e| e.join(", ")).unwrap_or("".to_owned());
+            log::info!("recaptcha failed: {:?}", error);
+            return Err(RecaptchaError::Rejected(error));
+        }
+
+        if let Some(min_score) = self.min_score {
+            let score = response.score.unwrap_or(0.);
+            if score < min_score {
+                log::info!("recaptcha score too low: {} < {}", score, min_score);
+                return Err(RecaptchaError::Rejected(format!("score: {}", score)));
+            }
+            if response.action.as_deref() != Some(action) {
+                log::info!("recaptcha action mismatch: {:?}, expected: {}", response.action, action);
+                return Err(RecaptchaError::Rejected("action-mismatch".to_owned()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl CaptchaProvider for Recaptcha {
+    fn name(&self) -> &'static str {
+        if self.min_score.is_some() {
+            "recaptcha_v3"
+        } else {
+            "recaptcha"
+        }
+    }
+
+    fn site_key(&self) -> &str {
+        &self.site_key
+    }
+
+    fn check_response<'a>(
+        &'a self,
+        response: &'a str,
+        action: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<(), RecaptchaError>> + 'a>> {
+        Box::pin(Recaptcha::check_response(self, response, action))
+    }
+}
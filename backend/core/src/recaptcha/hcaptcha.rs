@@ -0,0 +1,69 @@
+use super::{CaptchaProvider, RecaptchaError};
+use reqwest::Client;
+use serde::Deserialize;
+use std::{future::Future, pin::Pin};
+
+#[derive(Debug, Deserialize)]
+struct HCaptchaResponse {
+    success: bool,
+    #[serde(rename = "error-codes")]
+    error_codes: Option<Vec<String>>,
+}
+
+/// hCaptcha, the widget also fills the g-recaptcha-response field thus the pages can be used unchanged.
+#[derive(Clone)]
+pub struct HCaptcha {
+    client: Client,
+    secret: String,
+    site_key: String,
+}
+
+impl HCaptcha {
+    pub fn new(secret: String, site_key: String) -> HCaptcha {
+        HCaptcha {
+            client: Client::new(),
+            secret,
+            site_key,
+        }
+    }
+
+    pub async fn check_response(&self, response: &str) -> Result<(), RecaptchaError> {
+        let response = self
+            .client
+            .post("https://hcaptcha.com/siteverify")
+            .form(&[
+                ("secret", self.secret.as_str()),
+                ("response", response),
+                ("sitekey", self.site_key.as_str()),
+            ])
+            .send()
+            .await?;
+
+        let response = response.json::<HCaptchaResponse>().await?;
+        if response.success {
+            Ok(())
+        } else {
+            let error = response.error_codes.map(|e| e.join(", ")).unwrap_or_default();
+            log::info!("hcaptcha failed: {:?}", error);
+            Err(RecaptchaError::Rejected(error))
+        }
+    }
+}
+
+impl CaptchaProvider for HCaptcha {
+    fn name(&self) -> &'static str {
+        "hcaptcha"
+    }
+
+    fn site_key(&self) -> &str {
+        &self.site_key
+    }
+
+    fn check_response<'a>(
+        &'a self,
+        response: &'a str,
+        _action: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<(), RecaptchaError>> + 'a>> {
+        Box::pin(HCaptcha::check_response(self, response))
+    }
+}
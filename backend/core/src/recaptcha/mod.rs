@@ -1,5 +1,14 @@
-use reqwest::{Client, Error as ReqwestError};
-use serde::Deserialize;
+use reqwest::Error as ReqwestError;
+use serde::{Deserialize, Serialize};
+use std::{future::Future, pin::Pin};
+
+mod google;
+mod hcaptcha;
+mod no_captcha;
+
+pub use self::google::*;
+pub use self::hcaptcha::*;
+pub use self::no_captcha::*;
 
 #[derive(Debug, Clone)]
 pub enum RecaptchaError {
@@ -14,48 +23,51 @@ impl From<ReqwestError> for RecaptchaError {
     }
 }
 
-#[derive(Debug, Deserialize)]
-struct RecaptchaResponse {
-    success: bool,
-    #[serde(rename = "error-codes")]
-    error_codes: Option<Vec<String>>,
-}
+/// Server side verification of the captcha responses of the pages
+pub trait CaptchaProvider {
+    /// Name of the provider, the pages use it to load the matching widget
+    fn name(&self) -> &'static str;
+
+    fn site_key(&self) -> &str;
 
-#[derive(Clone)]
-pub struct Recaptcha {
-    client: Client,
-    secret: String,
-    site_key: String,
+    /// Verify the response token. The action is the name of the page, it is checked only by the
+    /// providers supporting it (ex. reCAPTCHA v3).
+    fn check_response<'a>(
+        &'a self,
+        response: &'a str,
+        action: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<(), RecaptchaError>> + 'a>>;
 }
 
-impl Recaptcha {
-    pub fn new(secret: String, site_key: String) -> Recaptcha {
-        Recaptcha {
-            client: Client::new(),
-            secret,
-            site_key,
-        }
-    }
+/// Selection of the captcha provider, the secret and site key are given separately
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum CaptchaConfig {
+    /// Google reCAPTCHA v2 checkbox
+    Recaptcha,
+    /// Google reCAPTCHA v3 with the minimum accepted score in the range of [0,1]
+    RecaptchaV3 { min_score: f32 },
+    /// hCaptcha
+    HCaptcha,
+    /// Accept any response, for local development
+    Disabled,
+}
 
-    pub fn site_key(&self) -> &str {
-        &self.site_key
+impl Default for CaptchaConfig {
+    fn default() -> Self {
+        CaptchaConfig::Recaptcha
     }
+}
 
-    pub async fn check_response(&self, response: &str) -> Result<(), RecaptchaError> {
-        let response = self
-            .client
-            .post("https://www.google.com/recaptcha/api/siteverify")
-            .form(&[("secret", self.secret.as_str()), ("response", response)])
-            .send()
-            .await?;
-
-        let response = response.json::<RecaptchaResponse>().await?;
-        if response.success {
-            Ok(())
-        } else {
-            let error = response.error_codes.map(|e| e.join(", ")).unwrap_or("".to_owned());
-            log::info!("recaptcha failed: {:?}", error);
-            Err(RecaptchaError::Rejected(error))
+impl CaptchaConfig {
+    pub fn create_provider(&self, secret: String, site_key: String) -> Box<dyn CaptchaProvider> {
+        match self {
+            CaptchaConfig::Recaptcha => Box::new(Recaptcha::new(secret, site_key)),
+            CaptchaConfig::RecaptchaV3 { min_score } => {
+                Box::new(Recaptcha::new(secret, site_key).with_min_score(*min_score))
+            }
+            CaptchaConfig::HCaptcha => Box::new(HCaptcha::new(secret, site_key)),
+            CaptchaConfig::Disabled => Box::new(NoCaptcha),
         }
     }
 }
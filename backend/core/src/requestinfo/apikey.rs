@@ -0,0 +1,109 @@
+use super::RequestInfoError;
+use actix_web::{dev::Payload, web, FromRequest, HttpRequest};
+use futures::future::{err, ready, FutureExt, LocalBoxFuture, Ready};
+use std::{future::Future, pin::Pin, sync::Arc};
+
+/// Header carrying the api key of a service
+pub const API_KEY_HEADER: &str = "x-sh-api-key";
+
+/// Api key credential of a service. The key has the form `<key id>.<secret>`, the id
+/// is public and used for lookup, the secret is only stored as a hash.
+#[derive(Clone)]
+pub struct ApiKeyAuth {
+    key_id: String,
+    secret: String,
+}
+
+impl ApiKeyAuth {
+    pub fn new<I: Into<String>, S: Into<String>>(key_id: I, secret: S) -> ApiKeyAuth {
+        ApiKeyAuth {
+            key_id: key_id.into(),
+            secret: secret.into(),
+        }
+    }
+
+    pub fn parse(key: &str) -> Result<Self, RequestInfoError> {
+        let mut parts = key.splitn(2, '.');
+        let key_id = parts
+            .next()
+            .filter(|id| !id.is_empty())
+            .ok_or(RequestInfoError::Invalid)?;
+        let secret = parts
+            .next()
+            .filter(|secret| !secret.is_empty())
+            .ok_or(RequestInfoError::MissingField("secret"))?;
+        Ok(ApiKeyAuth::new(key_id, secret))
+    }
+
+    pub fn parse_request(req: &HttpRequest) -> Result<Self, RequestInfoError> {
+        let header = req.headers().get(API_KEY_HEADER).ok_or(RequestInfoError::Header)?;
+        Self::parse(header.to_str()?)
+    }
+
+    /// Returns the public id of the key.
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// Returns the secret part of the key.
+    pub fn secret(&self) -> &str {
+        &self.secret
+    }
+}
+
+impl FromRequest for ApiKeyAuth {
+    type Config = ();
+    type Error = RequestInfoError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(ApiKeyAuth::parse_request(req))
+    }
+}
+
+/// The service identity authenticated by an api key.
+#[derive(Debug, Clone)]
+pub struct ApiKeyIdentity {
+    pub key_id: String,
+    pub name: String,
+    pub roles: Vec<String>,
+}
+
+impl ApiKeyIdentity {
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+}
+
+/// Validate api keys. To use the ApiKeyIdentity extractor an `ApiKeyValidatorRef` have to be
+/// registered as application data.
+pub trait ApiKeyValidator {
+    fn validate<'s>(
+        &'s self,
+        auth: &'s ApiKeyAuth,
+    ) -> Pin<Box<dyn Future<Output = Result<ApiKeyIdentity, RequestInfoError>> + 's>>;
+}
+
+pub type ApiKeyValidatorRef = Arc<dyn ApiKeyValidator>;
+
+impl FromRequest for ApiKeyIdentity {
+    type Config = ();
+    type Error = RequestInfoError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let auth = match ApiKeyAuth::parse_request(req) {
+            Ok(auth) => auth,
+            Err(e) => return err(e).boxed_local(),
+        };
+        let validator = match req.app_data::<web::Data<ApiKeyValidatorRef>>() {
+            Some(validator) => validator.get_ref().clone(),
+            None => {
+                log::error!("No api key validator was registered");
+                return err(RequestInfoError::ApiKeyRejected).boxed_local();
+            }
+        };
+
+        async move { validator.validate(&auth).await }.boxed_local()
+    }
+}
@@ -0,0 +1,185 @@
+use super::RequestInfoError;
+use actix_web::{dev::Payload, FromRequest, HttpRequest};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use data_encoding::BASE64URL_NOPAD;
+use futures::future::{ready, Ready};
+use hmac::{Hmac, Mac, NewMac};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::{fmt, iter};
+
+/// Header of the automation tokens
+pub const AUTOMATION_TOKEN_HEADER: &str = "x-sh-automation-token";
+
+/// Scope granting all the permissions
+pub const AUTOMATION_SCOPE_ALL: &str = "*";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AutomationTokenError {
+    /// Token is not in the `claims.signature` format or the claims cannot be parsed
+    Malformed,
+    /// Signature is not matching
+    InvalidSignature,
+    /// Token is expired
+    Expired,
+    /// Signing key is not a valid BASE64 string
+    InvalidKey,
+}
+
+impl fmt::Display for AutomationTokenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AutomationTokenError::Malformed => write!(f, "Malformed automation token"),
+            AutomationTokenError::InvalidSignature => write!(f, "Invalid automation token signature"),
+            AutomationTokenError::Expired => write!(f, "Automation token expired"),
+            AutomationTokenError::InvalidKey => write!(f, "Invalid automation token signing key"),
+        }
+    }
+}
+
+/// Claims of an automation token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutomationClaims {
+    /// The identity that has minted the token
+    pub sub: String,
+    /// The permissions granted by the token
+    pub scopes: Vec<String>,
+    /// Issued at, unix timestamp
+    pub iat: i64,
+    /// Expiration, unix timestamp
+    pub exp: i64,
+    /// Unique id of the token to trace its use in the logs
+    pub jti: String,
+}
+
+impl AutomationClaims {
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        Utc.timestamp(self.exp, 0)
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        now.timestamp() >= self.exp
+    }
+
+    /// Check if the permission is granted by the scopes
+    pub fn has_scope(&self, permission: &str) -> bool {
+        self.scopes
+            .iter()
+            .any(|scope| scope == AUTOMATION_SCOPE_ALL || scope == permission)
+    }
+}
+
+/// Mint and verify the short-lived automation tokens used by the test fixtures and the CI tools.
+/// The token is the BASE64URL encoded json claims and the HMAC-SHA256 of the encoded claims separated by a dot.
+#[derive(Clone)]
+pub struct AutomationTokenSigner {
+    key: Vec<u8>,
+}
+
+impl AutomationTokenSigner {
+    pub fn new(key: &[u8]) -> AutomationTokenSigner {
+        AutomationTokenSigner { key: key.to_vec() }
+    }
+
+    /// Create a signer from a BASE64 encoded key
+    pub fn from_base64(key: &str) -> Result<AutomationTokenSigner, AutomationTokenError> {
+        let key = data_encoding::BASE64
+            .decode(key.as_bytes())
+            .map_err(|_| AutomationTokenError::InvalidKey)?;
+        Ok(AutomationTokenSigner::new(&key))
+    }
+
+    fn signature(&self, payload: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_varkey(&self.key).expect("HMAC accepts keys of any size");
+        mac.update(payload.as_bytes());
+        mac
+    }
+
+    /// Mint a new token for the given scopes
+    pub fn mint(&self, subject: &str, scopes: &[String], time_to_live: Duration) -> (String, AutomationClaims) {
+        let now = Utc::now();
+        let mut rng = rand::thread_rng();
+        let jti: String = iter::repeat(()).map(|()| rng.sample(Alphanumeric)).take(16).collect();
+        let claims = AutomationClaims {
+            sub: subject.to_owned(),
+            scopes: scopes.to_vec(),
+            iat: now.timestamp(),
+            exp: (now + time_to_live).timestamp(),
+            jti,
+        };
+
+        let payload = BASE64URL_NOPAD.encode(&serde_json::to_vec(&claims).expect("Claims are serializable"));
+        let signature = BASE64URL_NOPAD.encode(&self.signature(&payload).finalize().into_bytes());
+        (format!("{}.{}", payload, signature), claims)
+    }
+
+    /// Check the signature and the expiration of a token
+    pub fn verify(&self, token: &str, now: DateTime<Utc>) -> Result<AutomationClaims, AutomationTokenError> {
+        let mut parts = token.splitn(2, '.');
+        let (payload, signature) = match (parts.next(), parts.next()) {
+            (Some(payload), Some(signature)) => (payload, signature),
+            _ => return Err(AutomationTokenError::Malformed),
+        };
+
+        let signature = BASE64URL_NOPAD
+            .decode(signature.as_bytes())
+            .map_err(|_| AutomationTokenError::Malformed)?;
+        self.signature(payload)
+            .verify(&signature)
+            .map_err(|_| AutomationTokenError::InvalidSignature)?;
+
+        let claims = BASE64URL_NOPAD
+            .decode(payload.as_bytes())
+            .map_err(|_| AutomationTokenError::Malformed)?;
+        let claims: AutomationClaims = serde_json::from_slice(&claims).map_err(|_| AutomationTokenError::Malformed)?;
+        if claims.is_expired(now) {
+            return Err(AutomationTokenError::Expired);
+        }
+        Ok(claims)
+    }
+}
+
+/// The (unverified) automation token of a request
+#[derive(Clone)]
+pub struct AutomationToken {
+    token: Option<String>,
+}
+
+impl fmt::Debug for AutomationToken {
+    /// The token is a credential, only its presence is logged
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AutomationToken")
+            .field("present", &self.is_present())
+            .finish()
+    }
+}
+
+impl AutomationToken {
+    pub fn is_present(&self) -> bool {
+        self.token.is_some()
+    }
+
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    pub fn parse_request(req: &HttpRequest) -> Result<Self, RequestInfoError> {
+        let token = if let Some(token) = req.headers().get(AUTOMATION_TOKEN_HEADER) {
+            Some(token.to_str()?.to_string())
+        } else {
+            None
+        };
+        Ok(AutomationToken { token })
+    }
+}
+
+impl FromRequest for AutomationToken {
+    type Config = ();
+    type Error = RequestInfoError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(AutomationToken::parse_request(req))
+    }
+}
@@ -1,4 +1,7 @@
-use actix_web::{http::header, ResponseError};
+use actix_web::{
+    http::{header, StatusCode},
+    ResponseError,
+};
 use std::{fmt, str};
 
 /// Possible errors while parsing `Authorization` header.
@@ -18,6 +21,10 @@ pub enum RequestInfoError {
     Base64DecodeError(data_encoding::DecodeError),
     /// Malformed UTF-8 string
     Utf8Error(str::Utf8Error),
+    /// Api key is unknown, revoked or the secret is not matching
+    ApiKeyRejected,
+    /// Bearer access token is unknown, expired or revoked
+    TokenRejected,
 }
 
 impl fmt::Display for RequestInfoError {
@@ -30,11 +37,21 @@ impl fmt::Display for RequestInfoError {
             RequestInfoError::ToStrError(e) => write!(f, "{}", e),
             RequestInfoError::Base64DecodeError(e) => write!(f, "{}", e),
             RequestInfoError::Utf8Error(e) => write!(f, "{}", e),
+            RequestInfoError::ApiKeyRejected => write!(f, "Api key rejected"),
+            RequestInfoError::TokenRejected => write!(f, "Access token rejected"),
         }
     }
 }
 
-impl ResponseError for RequestInfoError {}
+impl ResponseError for RequestInfoError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            RequestInfoError::ApiKeyRejected => StatusCode::UNAUTHORIZED,
+            RequestInfoError::TokenRejected => StatusCode::UNAUTHORIZED,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
 
 impl From<header::ToStrError> for RequestInfoError {
     fn from(e: header::ToStrError) -> Self {
@@ -1,11 +1,15 @@
+mod apikey;
+mod automation_token;
 mod basicauth;
 mod bearerauth;
 mod error;
 mod remoteinfo;
-mod testing_token;
+mod token;
 
+pub use self::apikey::*;
+pub use self::automation_token::*;
 pub use self::basicauth::*;
 pub use self::bearerauth::*;
 pub use self::error::*;
 pub use self::remoteinfo::*;
-pub use self::testing_token::*;
+pub use self::token::*;
@@ -13,6 +13,11 @@ pub struct RemoteInfo {
 }
 
 impl RemoteInfo {
+    /// Create the remote info of a request forwarded by another service
+    pub fn new(agent: String, remote: Option<IpAddr>) -> Self {
+        RemoteInfo { agent, remote }
+    }
+
     /// Returns the ip of the client.
     pub fn remote(&self) -> Option<&IpAddr> {
         self.remote.as_ref()
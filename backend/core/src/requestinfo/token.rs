@@ -0,0 +1,156 @@
+use super::{ApiKeyAuth, BearerAuth, RequestInfoError, API_KEY_HEADER};
+use actix_web::{dev::Payload, http::header, web, FromRequest, HttpRequest};
+use chrono::{DateTime, TimeZone, Utc};
+use futures::future::{err, FutureExt, LocalBoxFuture};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::{future::Future, pin::Pin, sync::Arc};
+
+/// The user and client authenticated by an OAuth bearer access token.
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenIdentity {
+    pub client_id: String,
+    pub user_id: String,
+    pub scopes: Vec<String>,
+    pub expires: DateTime<Utc>,
+}
+
+impl TokenIdentity {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+}
+
+/// Validate bearer access tokens. To use the TokenIdentity extractor a `TokenValidatorRef` have to be
+/// registered as application data.
+pub trait TokenValidator {
+    fn validate<'s>(
+        &'s self,
+        auth: &'s BearerAuth,
+    ) -> Pin<Box<dyn Future<Output = Result<TokenIdentity, RequestInfoError>> + 's>>;
+}
+
+pub type TokenValidatorRef = Arc<dyn TokenValidator>;
+
+impl FromRequest for TokenIdentity {
+    type Config = ();
+    type Error = RequestInfoError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let auth = match req.headers().get(header::AUTHORIZATION) {
+            Some(header) => match BearerAuth::from_header(header) {
+                Ok(auth) => auth,
+                Err(e) => return err(e).boxed_local(),
+            },
+            None => return err(RequestInfoError::Header).boxed_local(),
+        };
+        let validator = match req.app_data::<web::Data<TokenValidatorRef>>() {
+            Some(validator) => validator.get_ref().clone(),
+            None => {
+                log::error!("No token validator was registered");
+                return err(RequestInfoError::TokenRejected).boxed_local();
+            }
+        };
+
+        async move { validator.validate(&auth).await }.boxed_local()
+    }
+}
+
+/// Token introspection response, defined in [RFC7662](https://tools.ietf.org/html/rfc7662)
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct IntrospectionResponse {
+    pub active: bool,
+    /// Space separated list of the scopes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    /// Expiration as unix timestamp
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<i64>,
+}
+
+impl IntrospectionResponse {
+    pub fn inactive() -> Self {
+        IntrospectionResponse::default()
+    }
+
+    pub fn from_identity(identity: &TokenIdentity) -> Self {
+        IntrospectionResponse {
+            active: true,
+            scope: Some(identity.scopes.join(" ")),
+            client_id: Some(identity.client_id.clone()),
+            sub: Some(identity.user_id.clone()),
+            exp: Some(identity.expires.timestamp()),
+        }
+    }
+
+    pub fn into_identity(self) -> Option<TokenIdentity> {
+        if !self.active {
+            return None;
+        }
+        Some(TokenIdentity {
+            client_id: self.client_id?,
+            user_id: self.sub?,
+            scopes: self
+                .scope
+                .unwrap_or_default()
+                .split(' ')
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_owned())
+                .collect(),
+            expires: Utc.timestamp(self.exp?, 0),
+        })
+    }
+}
+
+/// Validate the tokens of a remote authorization server using the introspection endpoint.
+/// The service authenticates itself by an api key.
+pub struct IntrospectionTokenValidator {
+    client: Client,
+    introspection_url: String,
+    api_key: ApiKeyAuth,
+}
+
+impl IntrospectionTokenValidator {
+    pub fn new(introspection_url: String, api_key: &str) -> Result<Self, RequestInfoError> {
+        Ok(IntrospectionTokenValidator {
+            client: Client::new(),
+            introspection_url,
+            api_key: ApiKeyAuth::parse(api_key)?,
+        })
+    }
+
+    async fn introspect(&self, token: &str) -> Result<IntrospectionResponse, reqwest::Error> {
+        self.client
+            .post(&self.introspection_url)
+            .header(
+                API_KEY_HEADER,
+                format!("{}.{}", self.api_key.key_id(), self.api_key.secret()),
+            )
+            .form(&[("token", token)])
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<IntrospectionResponse>()
+            .await
+    }
+}
+
+impl TokenValidator for IntrospectionTokenValidator {
+    fn validate<'s>(
+        &'s self,
+        auth: &'s BearerAuth,
+    ) -> Pin<Box<dyn Future<Output = Result<TokenIdentity, RequestInfoError>> + 's>> {
+        Box::pin(async move {
+            let response = self.introspect(auth.token()).await.map_err(|err| {
+                log::warn!("Token introspection failed: {}", err);
+                RequestInfoError::TokenRejected
+            })?;
+            response.into_identity().ok_or(RequestInfoError::TokenRejected)
+        })
+    }
+}
@@ -0,0 +1,21 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum SecretError {
+    /// The file or the environment variable referenced by the secret could not be read
+    Source { name: String, reason: String },
+    /// The secret does not meet the requirements
+    Invalid { name: String, reason: String },
+}
+
+impl fmt::Display for SecretError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SecretError::Source { name, reason } => write!(f, "Secret {} could not be read: {}", name, reason),
+            SecretError::Invalid { name, reason } => write!(f, "Secret {} is invalid: {}", name, reason),
+        }
+    }
+}
+
+impl Error for SecretError {}
@@ -0,0 +1,9 @@
+mod error;
+mod provider;
+mod rotation;
+mod validator;
+
+pub use self::error::*;
+pub use self::provider::*;
+pub use self::rotation::*;
+pub use self::validator::*;
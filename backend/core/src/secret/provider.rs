@@ -0,0 +1,274 @@
+use super::SecretError;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use std::{env, fs};
+
+pub type SecretFuture<'a> = Pin<Box<dyn Future<Output = Result<String, SecretError>> + 'a>>;
+
+/// Source of the secrets referenced by name from the configuration as `secret:<name>`
+pub trait SecretProvider: Sync + Send {
+    /// Get the current value of a secret
+    fn get_secret<'a>(&'a self, name: &'a str) -> SecretFuture<'a>;
+}
+
+/// Credential of the Key Vault access
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum KeyVaultCredential {
+    /// Managed identity of the host (App Service, VM, AKS pod identity)
+    ManagedIdentity,
+    /// Service principal, the client secret can also be a `file:` or `env:` reference
+    ClientSecret {
+        tenant_id: String,
+        client_id: String,
+        client_secret: String,
+    },
+}
+
+impl Default for KeyVaultCredential {
+    fn default() -> Self {
+        KeyVaultCredential::ManagedIdentity
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum SecretProviderConfig {
+    /// Environment variables, the name of the secret is upper-cased, '-' and '.' are replaced by '_' and the
+    /// prefix is prepended
+    Env {
+        #[serde(default)]
+        prefix: String,
+    },
+    /// Files of a directory named after the secrets, ex. the mounted secrets of a container
+    File { directory: String },
+    /// Azure Key Vault, '_' and '.' in the names of the secrets are replaced by '-'
+    KeyVault {
+        vault_url: String,
+        #[serde(default)]
+        credential: KeyVaultCredential,
+    },
+}
+
+impl SecretProviderConfig {
+    pub fn create_provider(&self) -> Result<Arc<dyn SecretProvider>, SecretError> {
+        Ok(match self {
+            SecretProviderConfig::Env { prefix } => Arc::new(EnvSecretProvider::new(prefix)),
+            SecretProviderConfig::File { directory } => Arc::new(FileSecretProvider::new(directory)),
+            SecretProviderConfig::KeyVault { vault_url, credential } => {
+                let credential = match credential {
+                    KeyVaultCredential::ClientSecret {
+                        tenant_id,
+                        client_id,
+                        client_secret,
+                    } => {
+                        let client_secret =
+                            resolve_reference("secret_provider.credential.client_secret", client_secret)?
+                                .unwrap_or_else(|| client_secret.clone());
+                        KeyVaultCredential::ClientSecret {
+                            tenant_id: tenant_id.clone(),
+                            client_id: client_id.clone(),
+                            client_secret,
+                        }
+                    }
+                    credential => credential.clone(),
+                };
+                Arc::new(KeyVaultSecretProvider::new(vault_url, credential))
+            }
+        })
+    }
+}
+
+const FILE_PREFIX: &str = "file:";
+const ENV_PREFIX: &str = "env:";
+
+/// Resolve a `file:<path>` or `env:<variable>` reference. Returns None if the value is not a reference.
+pub fn resolve_reference(name: &str, value: &str) -> Result<Option<String>, SecretError> {
+    if value.starts_with(FILE_PREFIX) {
+        FileSecretProvider::read(name, &value[FILE_PREFIX.len()..]).map(Some)
+    } else if value.starts_with(ENV_PREFIX) {
+        EnvSecretProvider::read(name, &value[ENV_PREFIX.len()..]).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+pub struct EnvSecretProvider {
+    prefix: String,
+}
+
+impl EnvSecretProvider {
+    pub fn new(prefix: &str) -> EnvSecretProvider {
+        EnvSecretProvider {
+            prefix: prefix.to_owned(),
+        }
+    }
+
+    fn read(name: &str, variable: &str) -> Result<String, SecretError> {
+        env::var(variable).map_err(|err| SecretError::Source {
+            name: name.to_owned(),
+            reason: format!("failed to read environment variable {}: {}", variable, err),
+        })
+    }
+}
+
+impl SecretProvider for EnvSecretProvider {
+    fn get_secret<'a>(&'a self, name: &'a str) -> SecretFuture<'a> {
+        let variable = format!("{}{}", self.prefix, name.to_uppercase().replace(&['-', '.'][..], "_"));
+        Box::pin(async move { Self::read(name, &variable) })
+    }
+}
+
+pub struct FileSecretProvider {
+    directory: String,
+}
+
+impl FileSecretProvider {
+    pub fn new(directory: &str) -> FileSecretProvider {
+        FileSecretProvider {
+            directory: directory.to_owned(),
+        }
+    }
+
+    fn read(name: &str, path: &str) -> Result<String, SecretError> {
+        let secret = fs::read_to_string(path).map_err(|err| SecretError::Source {
+            name: name.to_owned(),
+            reason: format!("failed to read file {}: {}", path, err),
+        })?;
+        Ok(secret.trim_end_matches(&['\r', '\n'][..]).to_owned())
+    }
+}
+
+impl SecretProvider for FileSecretProvider {
+    fn get_secret<'a>(&'a self, name: &'a str) -> SecretFuture<'a> {
+        let path = format!("{}/{}", self.directory.trim_end_matches('/'), name);
+        Box::pin(async move { Self::read(name, &path) })
+    }
+}
+
+/// Secrets stored in Azure Key Vault. The access token is cached until it is about to expire.
+pub struct KeyVaultSecretProvider {
+    client: Client,
+    vault_url: String,
+    credential: KeyVaultCredential,
+    token: Mutex<Option<(String, Instant)>>,
+}
+
+impl KeyVaultSecretProvider {
+    pub fn new(vault_url: &str, credential: KeyVaultCredential) -> KeyVaultSecretProvider {
+        KeyVaultSecretProvider {
+            client: Client::new(),
+            vault_url: vault_url.trim_end_matches('/').to_owned(),
+            credential,
+            token: Mutex::new(None),
+        }
+    }
+
+    async fn request_token(&self) -> Result<(String, Duration), String> {
+        #[derive(Deserialize)]
+        struct TokenResponse {
+            access_token: String,
+            // string for the managed identity, number for the client credentials
+            expires_in: serde_json::Value,
+        }
+
+        let request = match &self.credential {
+            KeyVaultCredential::ManagedIdentity => self
+                .client
+                .get("http://169.254.169.254/metadata/identity/oauth2/token")
+                .header("Metadata", "true")
+                .query(&[("api-version", "2018-02-01"), ("resource", "https://vault.azure.net")]),
+            KeyVaultCredential::ClientSecret {
+                tenant_id,
+                client_id,
+                client_secret,
+            } => self
+                .client
+                .post(&format!(
+                    "https://login.microsoftonline.com/{}/oauth2/v2.0/token",
+                    tenant_id
+                ))
+                .form(&[
+                    ("grant_type", "client_credentials"),
+                    ("client_id", client_id.as_str()),
+                    ("client_secret", client_secret.as_str()),
+                    ("scope", "https://vault.azure.net/.default"),
+                ]),
+        };
+
+        let response = request.send().await.map_err(|err| format!("{}", err))?;
+        if !response.status().is_success() {
+            return Err(format!("token request failed with {}", response.status()));
+        }
+        let token = response
+            .json::<TokenResponse>()
+            .await
+            .map_err(|err| format!("{}", err))?;
+        let expires_in = match &token.expires_in {
+            serde_json::Value::Number(n) => n.as_u64(),
+            serde_json::Value::String(s) => s.parse().ok(),
+            _ => None,
+        }
+        .unwrap_or(300);
+        Ok((token.access_token, Duration::from_secs(expires_in)))
+    }
+
+    async fn access_token(&self) -> Result<String, String> {
+        if let Some((token, expires)) = &*self.token.lock().unwrap() {
+            if *expires > Instant::now() {
+                return Ok(token.clone());
+            }
+        }
+
+        let (token, expires_in) = self.request_token().await?;
+        // renew a minute before the expiration
+        let expires = Instant::now() + expires_in.checked_sub(Duration::from_secs(60)).unwrap_or_default();
+        *self.token.lock().unwrap() = Some((token.clone(), expires));
+        Ok(token)
+    }
+
+    async fn get_secret_impl(&self, name: &str) -> Result<String, SecretError> {
+        #[derive(Deserialize)]
+        struct SecretBundle {
+            value: String,
+        }
+
+        let source_error = |reason: String| SecretError::Source {
+            name: name.to_owned(),
+            reason,
+        };
+
+        let token = self.access_token().await.map_err(source_error)?;
+        let secret_name = name.replace(&['_', '.'][..], "-");
+        let response = self
+            .client
+            .get(&format!("{}/secrets/{}", self.vault_url, secret_name))
+            .query(&[("api-version", "7.1")])
+            .bearer_auth(token)
+            .send()
+            .await
+            .map_err(|err| source_error(format!("{}", err)))?;
+        if !response.status().is_success() {
+            return Err(source_error(format!(
+                "key vault request for {} failed with {}",
+                secret_name,
+                response.status()
+            )));
+        }
+        let secret = response
+            .json::<SecretBundle>()
+            .await
+            .map_err(|err| source_error(format!("{}", err)))?;
+        Ok(secret.value)
+    }
+}
+
+impl SecretProvider for KeyVaultSecretProvider {
+    fn get_secret<'a>(&'a self, name: &'a str) -> SecretFuture<'a> {
+        Box::pin(self.get_secret_impl(name))
+    }
+}
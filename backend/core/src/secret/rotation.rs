@@ -0,0 +1,114 @@
+use super::{SecretProvider, SecretReference, SecretValidator};
+use crate::pubsub::{PubSub, Subscription};
+use actix_web::rt;
+use futures::stream::StreamExt;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// New value of a rotated secret
+#[derive(Clone)]
+pub struct SecretRotation {
+    /// Path of the secret in the configuration
+    pub config_name: String,
+    pub value: String,
+}
+
+/// Poll the SecretProvider for the secrets fetched at startup and publish the new values when they change.
+/// The topics of the rotation events are the paths of the secrets in the configuration. The new values are
+/// validated with the rules of the startup check, the invalid values are reported and they are not published.
+#[derive(Clone)]
+pub struct SecretWatcher {
+    provider: Arc<dyn SecretProvider>,
+    bus: PubSub<SecretRotation>,
+    watched: Arc<Mutex<HashMap<String, SecretReference>>>,
+}
+
+impl SecretWatcher {
+    pub fn new(provider: Arc<dyn SecretProvider>) -> SecretWatcher {
+        SecretWatcher {
+            provider,
+            bus: PubSub::default(),
+            watched: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Watch a secret resolved by the SecretValidator
+    pub fn watch(&self, reference: SecretReference) {
+        let mut watched = self.watched.lock().unwrap();
+        watched.insert(reference.config_name.clone(), reference);
+    }
+
+    /// Subscribe to the rotation of a secret given by its path in the configuration
+    pub fn subscribe(&self, config_name: &str) -> Subscription<SecretRotation> {
+        self.bus.subscribe(config_name)
+    }
+
+    /// Call the handler on the current arbiter for each rotation of a secret given by its path in the
+    /// configuration
+    pub fn on_rotation<F>(&self, config_name: &str, handler: F)
+    where
+        F: Fn(SecretRotation) + 'static,
+    {
+        let mut subscription = self.subscribe(config_name);
+        rt::spawn(async move {
+            while let Some(rotation) = subscription.next().await {
+                handler(rotation);
+            }
+        });
+    }
+
+    /// Fetch the watched secrets and publish the changed ones
+    pub async fn poll(&self) {
+        let references: Vec<_> = {
+            let watched = self.watched.lock().unwrap();
+            watched.values().cloned().collect()
+        };
+
+        for reference in references {
+            let value = match self.provider.get_secret(&reference.secret_name).await {
+                Ok(value) => value,
+                Err(err) => {
+                    log::warn!("Failed to check the rotation of {}: {}", reference.config_name, err);
+                    continue;
+                }
+            };
+
+            {
+                let mut watched = self.watched.lock().unwrap();
+                let secret = match watched.get_mut(&reference.config_name) {
+                    Some(secret) if secret.value != value => secret,
+                    _ => continue,
+                };
+                if let Err(err) = SecretValidator::validate(&reference.config_name, &value, &reference.rule) {
+                    log::error!("Rotated secret is rejected: {}", err);
+                    continue;
+                }
+                secret.value = value.clone();
+            }
+
+            log::info!("Secret {} has been rotated", reference.config_name);
+            self.bus.publish(
+                &reference.config_name,
+                SecretRotation {
+                    config_name: reference.config_name.clone(),
+                    value,
+                },
+            );
+        }
+    }
+
+    /// Poll the provider periodically on the current arbiter
+    pub fn start(&self, interval: Duration) {
+        let watcher = self.clone();
+        rt::spawn(async move {
+            let mut timer = tokio::time::interval(interval);
+            // the first tick completes immediately, the secrets have just been fetched
+            timer.tick().await;
+            loop {
+                timer.tick().await;
+                watcher.poll().await;
+            }
+        });
+    }
+}
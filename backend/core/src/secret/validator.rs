@@ -0,0 +1,220 @@
+use super::{resolve_reference, SecretError, SecretProvider};
+use data_encoding::BASE64;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const PROVIDER_PREFIX: &str = "secret:";
+
+/// Encoding of a secret
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretFormat {
+    /// Used as given
+    Text,
+    /// BASE64 encoded binary, the requirements apply to the decoded bytes
+    Base64,
+}
+
+/// Requirements of a secret
+#[derive(Debug, Clone)]
+pub struct SecretRule {
+    pub format: SecretFormat,
+    /// Minimum length in characters (Text) or in decoded bytes (Base64)
+    pub min_len: usize,
+    /// Maximum length, not checked if not set
+    pub max_len: Option<usize>,
+    /// Minimum of the estimated entropy in bits
+    pub min_entropy_bits: f64,
+}
+
+impl SecretRule {
+    /// Keys used for signing and encryption
+    pub fn key(min_bytes: usize) -> SecretRule {
+        SecretRule {
+            format: SecretFormat::Base64,
+            min_len: min_bytes,
+            max_len: None,
+            min_entropy_bits: 128.,
+        }
+    }
+
+    /// Passwords, tokens and keys of the external services
+    pub fn text(min_len: usize) -> SecretRule {
+        SecretRule {
+            format: SecretFormat::Text,
+            min_len,
+            max_len: None,
+            min_entropy_bits: 0.,
+        }
+    }
+
+    pub fn with_max_len(self, max_len: usize) -> SecretRule {
+        SecretRule {
+            max_len: Some(max_len),
+            ..self
+        }
+    }
+
+    pub fn with_min_entropy_bits(self, min_entropy_bits: f64) -> SecretRule {
+        SecretRule {
+            min_entropy_bits,
+            ..self
+        }
+    }
+}
+
+/// Estimate the entropy of a secret from the frequency of its symbols. It is an upper bound for random
+/// secrets and it is a rough estimate for the human chosen ones, but it reveals the repetitive placeholders.
+fn estimate_entropy_bits(data: &[u8]) -> f64 {
+    let mut frequency = HashMap::new();
+    for b in data {
+        *frequency.entry(b).or_insert(0usize) += 1;
+    }
+
+    let len = data.len() as f64;
+    let per_symbol: f64 = frequency
+        .values()
+        .map(|&count| {
+            let p = count as f64 / len;
+            -p * p.log2()
+        })
+        .sum();
+    per_symbol * len
+}
+
+/// A secret of the configuration given by the name of the secret in the SecretProvider
+#[derive(Clone)]
+pub struct SecretReference {
+    /// Path of the secret in the configuration
+    pub config_name: String,
+    /// Name of the secret in the provider
+    pub secret_name: String,
+    pub rule: SecretRule,
+    /// The value fetched from the provider
+    pub value: String,
+}
+
+/// Resolve the secrets of the configuration and check them against their requirements. A secret can be given
+/// inline, or by reference as `file:<path>`, `env:<variable>` or `secret:<name>` where the last one is
+/// fetched from the SecretProvider. All the secrets are checked and the failures are collected, thus every
+/// problem of the configuration is reported at once.
+#[derive(Default)]
+pub struct SecretValidator {
+    provider: Option<Arc<dyn SecretProvider>>,
+    references: Vec<SecretReference>,
+    errors: Vec<SecretError>,
+}
+
+impl SecretValidator {
+    pub fn new() -> SecretValidator {
+        SecretValidator::default()
+    }
+
+    pub fn with_provider(provider: Arc<dyn SecretProvider>) -> SecretValidator {
+        SecretValidator {
+            provider: Some(provider),
+            ..Default::default()
+        }
+    }
+
+    async fn resolve(&mut self, name: &str, value: &str, rule: &SecretRule) -> Result<Option<String>, SecretError> {
+        if value.starts_with(PROVIDER_PREFIX) {
+            let secret_name = &value[PROVIDER_PREFIX.len()..];
+            let provider = self.provider.clone().ok_or_else(|| SecretError::Source {
+                name: name.to_owned(),
+                reason: format!("no secret provider is configured to get {}", secret_name),
+            })?;
+            let secret = provider.get_secret(secret_name).await?;
+            self.references.push(SecretReference {
+                config_name: name.to_owned(),
+                secret_name: secret_name.to_owned(),
+                rule: rule.clone(),
+                value: secret.clone(),
+            });
+            Ok(Some(secret))
+        } else {
+            resolve_reference(name, value)
+        }
+    }
+
+    pub fn validate(name: &str, value: &str, rule: &SecretRule) -> Result<(), SecretError> {
+        let invalid = |reason: String| SecretError::Invalid {
+            name: name.to_owned(),
+            reason,
+        };
+
+        let data = match rule.format {
+            SecretFormat::Text => value.as_bytes().to_vec(),
+            SecretFormat::Base64 => BASE64
+                .decode(value.as_bytes())
+                .map_err(|err| invalid(format!("not a valid BASE64 string: {}", err)))?,
+        };
+        let unit = match rule.format {
+            SecretFormat::Text => "characters",
+            SecretFormat::Base64 => "bytes",
+        };
+        let len = match rule.format {
+            SecretFormat::Text => value.chars().count(),
+            SecretFormat::Base64 => data.len(),
+        };
+
+        if len == 0 {
+            return Err(invalid("missing".to_owned()));
+        }
+        if len < rule.min_len {
+            return Err(invalid(format!(
+                "too short, {} {} given, at least {} required",
+                len, unit, rule.min_len
+            )));
+        }
+        if let Some(max_len) = rule.max_len {
+            if len > max_len {
+                return Err(invalid(format!(
+                    "too long, {} {} given, at most {} allowed",
+                    len, unit, max_len
+                )));
+            }
+        }
+        let entropy = estimate_entropy_bits(&data);
+        if entropy < rule.min_entropy_bits {
+            return Err(invalid(format!(
+                "too predictable, estimated entropy is {:.0} bits, at least {:.0} required",
+                entropy, rule.min_entropy_bits
+            )));
+        }
+        Ok(())
+    }
+
+    /// Resolve a required secret in place and validate it.
+    pub async fn check(&mut self, name: &str, value: &mut String, rule: SecretRule) {
+        let result = match self.resolve(name, value, &rule).await {
+            Ok(secret) => {
+                if let Some(secret) = secret {
+                    *value = secret;
+                }
+                Self::validate(name, value, &rule)
+            }
+            Err(err) => Err(err),
+        };
+
+        if let Err(err) = result {
+            self.errors.push(err);
+        }
+    }
+
+    /// Resolve an optional secret in place and validate it only if it is set.
+    pub async fn check_optional(&mut self, name: &str, value: &mut String, rule: SecretRule) {
+        if !value.is_empty() {
+            self.check(name, value, rule).await;
+        }
+    }
+
+    /// Complete the validation. On success the secrets fetched from the provider are returned, they can be
+    /// watched for rotation.
+    pub fn finish(self) -> Result<Vec<SecretReference>, Vec<SecretError>> {
+        if self.errors.is_empty() {
+            Ok(self.references)
+        } else {
+            Err(self.errors)
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use super::{CookieKey, CookieKeyring, CookieMode, CookieSecurity, SameSite, SignedCookieError};
+use data_encoding::BASE64;
+use serde::{Deserialize, Serialize};
+
+/// Serializable version of the SameSite cookie attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CookieSameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+impl From<CookieSameSite> for SameSite {
+    fn from(same_site: CookieSameSite) -> SameSite {
+        match same_site {
+            CookieSameSite::Strict => SameSite::Strict,
+            CookieSameSite::Lax => SameSite::Lax,
+            CookieSameSite::None => SameSite::None,
+        }
+    }
+}
+
+/// Configurable attributes of a signed cookie. The unset attributes fall back to the defaults of the cookie.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SignedCookieConfiguration {
+    #[serde(default)]
+    pub mode: CookieMode,
+    /// BASE64 encoded keys of the previous secrets, accepted for verification only
+    #[serde(default)]
+    pub previous_secrets: Vec<String>,
+    #[serde(default)]
+    pub same_site: Option<CookieSameSite>,
+    #[serde(default)]
+    pub secure: Option<bool>,
+    /// The max-age of the cookie in seconds, if not set a session cookie is created
+    #[serde(default)]
+    pub max_age: Option<i64>,
+    /// The number of the keys kept for verification when the secret is rotated at runtime, defaults to 3
+    #[serde(default)]
+    pub max_keys: Option<usize>,
+}
+
+impl SignedCookieConfiguration {
+    /// Create the cookie security from the (decoded) current secret and the previous secrets.
+    pub fn security(&self, secret: &[u8]) -> Result<CookieSecurity, SignedCookieError> {
+        let mut keyring = CookieKeyring::from_secret(secret);
+        for previous in &self.previous_secrets {
+            let previous = BASE64
+                .decode(previous.as_bytes())
+                .map_err(SignedCookieError::DecodeSecret)?;
+            keyring = keyring.with_previous_key(CookieKey::from_secret(&previous));
+        }
+        Ok(CookieSecurity::new(self.mode, keyring))
+    }
+
+    /// Make the (BASE64 encoded) secret the signing key of the security, the cookies signed by the
+    /// previous keys remain valid.
+    pub fn rotate(&self, security: &CookieSecurity, secret: &str) -> Result<(), SignedCookieError> {
+        let secret = BASE64
+            .decode(secret.as_bytes())
+            .map_err(SignedCookieError::DecodeSecret)?;
+        let key = CookieKey::from_secret(&secret);
+        log::info!("Rotating cookie key to {}", key.id());
+        security.keyring().rotate(key, self.max_keys());
+        Ok(())
+    }
+
+    pub fn max_keys(&self) -> usize {
+        self.max_keys.unwrap_or(3)
+    }
+
+    pub fn secure_or(&self, default: bool) -> bool {
+        self.secure.unwrap_or(default)
+    }
+
+    pub fn same_site(&self) -> Option<SameSite> {
+        self.same_site.map(SameSite::from)
+    }
+
+    pub fn max_age(&self) -> Option<time::Duration> {
+        self.max_age.map(time::Duration::seconds)
+    }
+}
@@ -6,14 +6,186 @@ use actix_web::{
     http::{header::SET_COOKIE, HeaderValue},
     Error, HttpMessage,
 };
+use data_encoding::HEXLOWER;
 use futures::future::{ok, Ready};
-use std::{collections::HashMap, rc::Rc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    rc::Rc,
+    sync::{Arc, RwLock},
+};
 
 pub use actix_web::cookie::{Key, SameSite};
 
-pub enum CookieSecurity {
-    Signed(Key),
-    Private(Key),
+/// The protection of the cookie content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CookieMode {
+    /// The content is signed, it is readable by the client but any modification is detected
+    Signed,
+    /// The content is encrypted and authenticated (AEAD), it is neither readable nor modifiable by the client
+    Private,
+}
+
+impl Default for CookieMode {
+    fn default() -> CookieMode {
+        CookieMode::Signed
+    }
+}
+
+/// Separates the id of the signing key from the signed content in the cookie value
+const KEY_ID_SEPARATOR: char = '~';
+
+/// A cookie key with an id derived from the secret. The id is embedded in the cookies, thus the verification
+/// key can be found without trying all the keys.
+#[derive(Clone)]
+pub struct CookieKey {
+    id: String,
+    key: Key,
+}
+
+impl CookieKey {
+    pub fn from_secret(secret: &[u8]) -> CookieKey {
+        let id = HEXLOWER.encode(&Sha256::digest(secret))[..8].to_owned();
+        CookieKey {
+            id,
+            key: Key::from_master(secret),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn key(&self) -> &Key {
+        &self.key
+    }
+}
+
+/// The keys of a cookie ordered from the newest to the oldest. The cookies are always created with the newest
+/// key, but all the keys are accepted for verification to allow key rotation without invalidating the
+/// existing sessions. The keyring is shared by the clones, a rotation takes effect on all the workers.
+#[derive(Clone)]
+pub struct CookieKeyring {
+    keys: Arc<RwLock<Vec<CookieKey>>>,
+}
+
+impl CookieKeyring {
+    pub fn new(current: CookieKey) -> CookieKeyring {
+        CookieKeyring {
+            keys: Arc::new(RwLock::new(vec![current])),
+        }
+    }
+
+    pub fn from_secret(secret: &[u8]) -> CookieKeyring {
+        CookieKeyring::new(CookieKey::from_secret(secret))
+    }
+
+    /// Add a key accepted for verification only
+    pub fn with_previous_key(self, key: CookieKey) -> CookieKeyring {
+        {
+            let mut keys = self.keys.write().unwrap();
+            if keys.iter().all(|k| k.id != key.id) {
+                keys.push(key);
+            }
+        }
+        self
+    }
+
+    /// The key used to sign the new cookies
+    pub fn current(&self) -> CookieKey {
+        self.keys.read().unwrap()[0].clone()
+    }
+
+    pub fn key_ids(&self) -> Vec<String> {
+        self.keys.read().unwrap().iter().map(|k| k.id.clone()).collect()
+    }
+
+    /// Make the key the current one, the previous keys are kept for verification up to max_keys keys in total
+    pub fn rotate(&self, key: CookieKey, max_keys: usize) {
+        let mut keys = self.keys.write().unwrap();
+        keys.retain(|k| k.id != key.id);
+        keys.insert(0, key);
+        keys.truncate(max_keys.max(1));
+    }
+}
+
+/// The protection and the keys of a cookie
+#[derive(Clone)]
+pub struct CookieSecurity {
+    mode: CookieMode,
+    keyring: CookieKeyring,
+}
+
+impl CookieSecurity {
+    pub fn new(mode: CookieMode, keyring: CookieKeyring) -> CookieSecurity {
+        CookieSecurity { mode, keyring }
+    }
+
+    pub fn signed(keyring: CookieKeyring) -> CookieSecurity {
+        CookieSecurity::new(CookieMode::Signed, keyring)
+    }
+
+    pub fn private(keyring: CookieKeyring) -> CookieSecurity {
+        CookieSecurity::new(CookieMode::Private, keyring)
+    }
+
+    pub fn mode(&self) -> CookieMode {
+        self.mode
+    }
+
+    pub fn keyring(&self) -> &CookieKeyring {
+        &self.keyring
+    }
+
+    fn verify_with(&self, cookie: &Cookie<'static>, value: &str, key: &Key) -> Option<Cookie<'static>> {
+        let mut content = cookie.clone();
+        content.set_value(value.to_owned());
+        let mut jar = CookieJar::new();
+        jar.add_original(content);
+        match self.mode {
+            CookieMode::Signed => jar.signed(key).get(cookie.name()),
+            CookieMode::Private => jar.private(key).get(cookie.name()),
+        }
+    }
+
+    /// Verify (and decrypt) a cookie. On success the cookie is returned along with a flag indicating if it was
+    /// not signed by the current key and thus the cookie shall be renewed. For the cookies without a key id
+    /// all the keys are tried.
+    fn verify(&self, cookie: &Cookie<'static>) -> Option<(Cookie<'static>, bool)> {
+        let keys = self.keyring.keys.read().unwrap();
+        let value = cookie.value();
+        let tagged = value.find(KEY_ID_SEPARATOR).and_then(|pos| {
+            let id = &value[..pos];
+            keys.iter()
+                .position(|k| k.id == id)
+                .map(|index| (index, &value[pos + KEY_ID_SEPARATOR.len_utf8()..]))
+        });
+
+        match tagged {
+            Some((index, content)) => self
+                .verify_with(cookie, content, &keys[index].key)
+                .map(|cookie| (cookie, index != 0)),
+            None => keys
+                .iter()
+                .find_map(|k| self.verify_with(cookie, value, &k.key))
+                .map(|cookie| (cookie, true)),
+        }
+    }
+
+    /// Sign (and encrypt) a cookie with the current key and prefix the value with the id of the key.
+    fn seal(&self, cookie: Cookie<'static>) -> Option<Cookie<'static>> {
+        let current = self.keyring.current();
+        let mut jar = CookieJar::new();
+        match self.mode {
+            CookieMode::Signed => jar.signed(&current.key).add(cookie),
+            CookieMode::Private => jar.private(&current.key).add(cookie),
+        }
+        let mut sealed = jar.delta().next()?.clone();
+        let value = format!("{}{}{}", current.id, KEY_ID_SEPARATOR, sealed.value());
+        sealed.set_value(value);
+        Some(sealed)
+    }
 }
 
 /// Configure a signed cookie (session). To allow easy access from request handler
@@ -99,17 +271,18 @@ where
         config: Rc<C>,
     ) -> Result<SessionData<O, C>, SignedCookieError> {
         let name = cookie.name();
-        let mut jar = CookieJar::new();
-        jar.add_original(cookie.clone());
-
-        let cookie = match options.security() {
-            &CookieSecurity::Signed(ref key) => jar.signed(&key).get(name),
-            &CookieSecurity::Private(ref key) => jar.private(&key).get(name),
-        }
-        .ok_or(SignedCookieError::Verification)?;
+        let (cookie, rotated) = options
+            .security()
+            .verify(cookie)
+            .ok_or(SignedCookieError::Verification)?;
 
         let values = serde_json::from_str::<HashMap<String, String>>(cookie.value())?;
-        Ok(SessionData::new(name.to_owned(), values, config))
+        let data = SessionData::new(name.to_owned(), values, config);
+        if rotated && !options.read_only() {
+            log::info!("Cookie [{}] verified with a previous key, renewing", name);
+            data.renew();
+        }
+        Ok(data)
     }
 
     fn set_cookie_options(cookie: &mut Cookie, options: &O) {
@@ -136,16 +309,9 @@ where
             cookie.set_max_age(max_age);
         }
 
-        let mut jar = CookieJar::new();
-        match options.security() {
-            CookieSecurity::Signed(key) => jar.signed(key).add(cookie),
-            CookieSecurity::Private(key) => jar.private(key).add(cookie),
-        }
-
-        for cookie in jar.delta() {
-            let val = HeaderValue::from_str(&cookie.encoded().to_string())?;
-            res.headers_mut().append(SET_COOKIE, val);
-        }
+        let cookie = options.security().seal(cookie).ok_or(SignedCookieError::Verification)?;
+        let val = HeaderValue::from_str(&cookie.encoded().to_string())?;
+        res.headers_mut().append(SET_COOKIE, val);
 
         Ok(())
     }
@@ -1,4 +1,5 @@
 use actix_web::ResponseError;
+use data_encoding::DecodeError;
 use serde_json::error::Error as JsonError;
 use std::fmt;
 
@@ -10,6 +11,9 @@ pub enum SignedCookieError {
 
     /// Signature verification failed
     Verification,
+
+    /// Failed to decode a secret of the configuration
+    DecodeSecret(DecodeError),
 }
 
 impl fmt::Display for SignedCookieError {
@@ -17,6 +21,7 @@ impl fmt::Display for SignedCookieError {
         match self {
             SignedCookieError::Serialize(err) => write!(f, "Serialization error: {}", err),
             SignedCookieError::Verification => write!(f, "Signature verification failed"),
+            SignedCookieError::DecodeSecret(err) => write!(f, "Failed to decode secret: {}", err),
         }
     }
 }
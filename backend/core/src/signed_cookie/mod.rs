@@ -1,8 +1,10 @@
+mod configuration;
 mod cookie;
 mod error;
 mod middleware;
 mod session;
 
+pub use self::configuration::*;
 pub use self::cookie::*;
 pub use self::error::*;
 pub use self::middleware::*;
@@ -0,0 +1,439 @@
+use crate::{config::automation_token, ClientError, TestServer};
+use actix_codec::Framed;
+use awc::{
+    ws::{Codec, Frame, Message},
+    BoxedSocket,
+};
+use futures::{SinkExt, StreamExt};
+use reqwest::{header, Client, Method, RequestBuilder, Response};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use shine_core::requestinfo::AUTOMATION_TOKEN_HEADER;
+use shine_gamestate::{
+    lobby::{JoinParams, Lobby, StartLobbyParams},
+    room::RoomUpdate,
+    turn::{NewMatch, TurnMatch, TurnSubmission},
+};
+use std::time::Duration;
+
+const REDIRECT_URI: &str = "http://localhost/e2e/callback";
+
+/// Time to wait for a message of a websocket
+const RECEIVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+async fn check_status(response: Response) -> Result<Response, ClientError> {
+    let status = response.status();
+    if status.is_success() {
+        Ok(response)
+    } else {
+        let body = response.text().await.unwrap_or_default();
+        Err(ClientError::Status(status, body))
+    }
+}
+
+async fn receive_json<T: DeserializeOwned>(request: RequestBuilder) -> Result<T, ClientError> {
+    let response = check_status(request.send().await?).await?;
+    Ok(response.json::<T>().await?)
+}
+
+/// The game registered as an OAuth client in the auth service, the players sign in to the gamestate through it
+#[derive(Clone, Debug)]
+pub struct GameApp {
+    pub client_id: String,
+    pub client_secret: Option<String>,
+}
+
+impl GameApp {
+    /// Register the game, the owner of the client is a dedicated account of the test
+    pub async fn register(server: &TestServer) -> Result<GameApp, ClientError> {
+        let owner = GameClient::new(server)?;
+        owner.register("e2e-owner", "Owner-Password-0123").await?;
+
+        #[derive(Serialize)]
+        struct Params<'a> {
+            name: &'a str,
+            redirect_uris: Vec<&'a str>,
+        }
+
+        #[derive(Deserialize)]
+        struct Registered {
+            client_id: String,
+            client_secret: Option<String>,
+        }
+
+        let registered: Registered = receive_json(
+            owner
+                .auth(Method::POST, "oauth/clients")
+                .header(AUTOMATION_TOKEN_HEADER, automation_token(&["oauth.write"]))
+                .json(&Params {
+                    name: "e2e-game",
+                    redirect_uris: vec![REDIRECT_URI],
+                }),
+        )
+        .await?;
+
+        Ok(GameApp {
+            client_id: registered.client_id,
+            client_secret: registered.client_secret,
+        })
+    }
+}
+
+/// A headless game client driving the public apis the same way as the game does: the account is
+/// managed through the cookie session of the auth service, the gamestate is accessed by the bearer token
+/// issued to the game.
+pub struct GameClient {
+    http: Client,
+    auth_url: String,
+    gamestate_url: String,
+    gamestate_ws_url: String,
+    token: Option<String>,
+    user_id: Option<String>,
+}
+
+impl GameClient {
+    pub fn new(server: &TestServer) -> Result<GameClient, ClientError> {
+        Ok(GameClient {
+            http: Client::builder().cookie_store(true).build()?,
+            auth_url: server.auth_url(),
+            gamestate_url: server.gamestate_url(),
+            gamestate_ws_url: server.gamestate_ws_url(),
+            token: None,
+            user_id: None,
+        })
+    }
+
+    fn auth(&self, method: Method, path: &str) -> RequestBuilder {
+        self.http.request(method, &format!("{}/{}", self.auth_url, path))
+    }
+
+    fn token(&self) -> Result<&str, ClientError> {
+        self.token
+            .as_deref()
+            .ok_or_else(|| ClientError::Protocol("Not signed in to the gamestate".to_owned()))
+    }
+
+    fn gamestate(&self, method: Method, path: &str) -> Result<RequestBuilder, ClientError> {
+        Ok(self
+            .http
+            .request(method, &format!("{}/{}", self.gamestate_url, path))
+            .bearer_auth(self.token()?))
+    }
+
+    /// The user id of the signed in player
+    pub fn user_id(&self) -> Result<&str, ClientError> {
+        self.user_id
+            .as_deref()
+            .ok_or_else(|| ClientError::Protocol("Not signed in to the gamestate".to_owned()))
+    }
+
+    async fn af_token(&self) -> Result<String, ClientError> {
+        #[derive(Deserialize)]
+        struct Token {
+            token: String,
+        }
+
+        let token: Token = receive_json(self.auth(Method::POST, "af")).await?;
+        Ok(token.token)
+    }
+
+    /// Register a new user and open a session for it
+    pub async fn register(&self, name: &str, password: &str) -> Result<(), ClientError> {
+        #[derive(Serialize)]
+        struct Params<'a> {
+            name: &'a str,
+            password: &'a str,
+            email: Option<&'a str>,
+            af: &'a str,
+        }
+
+        let af = self.af_token().await?;
+        let request = self
+            .auth(Method::POST, "users/register")
+            .header(AUTOMATION_TOKEN_HEADER, automation_token(&["automation.user.register"]))
+            .json(&Params {
+                name,
+                password,
+                email: None,
+                af: &af,
+            });
+        check_status(request.send().await?).await?;
+        Ok(())
+    }
+
+    /// Open a session of an existing user
+    pub async fn login(&self, name: &str, password: &str) -> Result<(), ClientError> {
+        let request = self
+            .auth(Method::POST, "users/login")
+            .header(AUTOMATION_TOKEN_HEADER, automation_token(&["automation.user.login"]))
+            .basic_auth(name, Some(password));
+        check_status(request.send().await?).await?;
+        Ok(())
+    }
+
+    /// The id of the user of the session
+    pub async fn session_user_id(&self) -> Result<String, ClientError> {
+        #[derive(Deserialize)]
+        struct SessionUser {
+            id: String,
+        }
+
+        let user: SessionUser = receive_json(self.auth(Method::POST, "users/validate")).await?;
+        Ok(user.id)
+    }
+
+    /// Create a role with the given permissions and assign it to the user. The changes are made by an automation
+    /// token, the roles of the session are updated on the next login.
+    pub async fn grant_role(&self, user_id: &str, role: &str, permissions: &[&str]) -> Result<(), ClientError> {
+        let request = self
+            .auth(Method::POST, &format!("roles/{}", role))
+            .header(AUTOMATION_TOKEN_HEADER, automation_token(&["role.write"]));
+        check_status(request.send().await?).await?;
+        for permission in permissions {
+            let request = self
+                .auth(Method::POST, &format!("roles/{}/permissions/{}", role, permission))
+                .header(AUTOMATION_TOKEN_HEADER, automation_token(&["role.write"]));
+            check_status(request.send().await?).await?;
+        }
+        let request = self
+            .auth(Method::POST, &format!("users/{}/roles/{}", user_id, role))
+            .header(AUTOMATION_TOKEN_HEADER, automation_token(&["user.role.write"]));
+        check_status(request.send().await?).await?;
+        Ok(())
+    }
+
+    /// Mint an automation token by the user of the session
+    pub async fn create_automation_token(&self, scopes: &[&str]) -> Result<String, ClientError> {
+        #[derive(Serialize)]
+        struct Params<'a> {
+            scopes: &'a [&'a str],
+        }
+
+        #[derive(Deserialize)]
+        struct Token {
+            token: String,
+        }
+
+        let token: Token = receive_json(self.auth(Method::POST, "automation/tokens").json(&Params { scopes })).await?;
+        Ok(token.token)
+    }
+
+    /// Authorize the game for the user of the session and exchange the authorization code for an access token
+    pub async fn sign_in(&mut self, app: &GameApp) -> Result<(), ClientError> {
+        #[derive(Serialize)]
+        struct AuthorizeParams<'a> {
+            client_id: &'a str,
+            redirect_uri: &'a str,
+            af: &'a str,
+        }
+
+        #[derive(Deserialize)]
+        struct Authorized {
+            redirect: String,
+        }
+
+        #[derive(Deserialize)]
+        struct AccessToken {
+            access_token: String,
+        }
+
+        #[derive(Deserialize)]
+        struct Me {
+            user_id: String,
+        }
+
+        let af = self.af_token().await?;
+        let authorized: Authorized = receive_json(self.auth(Method::POST, "oauth/authorize").json(&AuthorizeParams {
+            client_id: &app.client_id,
+            redirect_uri: REDIRECT_URI,
+            af: &af,
+        }))
+        .await?;
+        let code = authorized
+            .redirect
+            .split(|c| c == '?' || c == '&')
+            .find_map(|param| param.strip_prefix("code="))
+            .ok_or_else(|| ClientError::Protocol(format!("Missing code in redirect: {}", authorized.redirect)))?
+            .to_owned();
+
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code.as_str()),
+            ("redirect_uri", REDIRECT_URI),
+            ("client_id", app.client_id.as_str()),
+        ];
+        if let Some(secret) = &app.client_secret {
+            form.push(("client_secret", secret.as_str()));
+        }
+        let token: AccessToken = receive_json(self.auth(Method::POST, "oauth/token").form(&form)).await?;
+        self.token = Some(token.access_token);
+
+        let me: Me = receive_json(self.gamestate(Method::GET, "me")?).await?;
+        log::info!("Signed in to the gamestate as {}", me.user_id);
+        self.user_id = Some(me.user_id);
+        Ok(())
+    }
+
+    /// Join the best matching lobby or create a new one
+    pub async fn matchmake(&self, skill: u32) -> Result<Lobby, ClientError> {
+        receive_json(self.gamestate(Method::POST, "matchmaking")?.json(&JoinParams { skill })).await
+    }
+
+    pub async fn get_lobby(&self, lobby_id: &str) -> Result<Lobby, ClientError> {
+        receive_json(self.gamestate(Method::GET, &format!("lobbies/{}", lobby_id))?).await
+    }
+
+    /// Start the game of the owned lobby in a new room
+    pub async fn start_lobby(&self, lobby_id: &str, simulation: &str) -> Result<Lobby, ClientError> {
+        let params = StartLobbyParams {
+            simulation: simulation.to_owned(),
+        };
+        receive_json(
+            self.gamestate(Method::POST, &format!("lobbies/{}/start", lobby_id))?
+                .json(&params),
+        )
+        .await
+    }
+
+    /// Connect to a game room hosted by the gamestate service
+    pub async fn join_room(&self, room_id: &str) -> Result<RoomConnection, ClientError> {
+        let url = format!("{}/rooms/{}/ws", self.gamestate_ws_url, room_id);
+        let (_, framed) = awc::Client::new()
+            .ws(url)
+            .header(header::AUTHORIZATION, format!("Bearer {}", self.token()?))
+            .connect()
+            .await
+            .map_err(|err| ClientError::WebSocket(format!("{}", err)))?;
+        Ok(RoomConnection {
+            framed,
+            tick: 0,
+            state: Value::Null,
+        })
+    }
+
+    pub async fn create_match(&self, params: &NewMatch) -> Result<TurnMatch, ClientError> {
+        receive_json(self.gamestate(Method::POST, "matches")?.json(params)).await
+    }
+
+    pub async fn get_match(&self, match_id: &str) -> Result<TurnMatch, ClientError> {
+        receive_json(self.gamestate(Method::GET, &format!("matches/{}", match_id))?).await
+    }
+
+    pub async fn submit_turn(&self, match_id: &str, submission: &TurnSubmission) -> Result<TurnMatch, ClientError> {
+        receive_json(
+            self.gamestate(Method::POST, &format!("matches/{}/turn", match_id))?
+                .json(submission),
+        )
+        .await
+    }
+}
+
+/// Apply a json merge patch (RFC 7386) on the state
+fn merge_patch(target: &mut Value, patch: &Value) {
+    match patch {
+        Value::Object(patch) => {
+            if !target.is_object() {
+                *target = Value::Object(Map::new());
+            }
+            let target = target.as_object_mut().unwrap();
+            for (key, value) in patch {
+                if value.is_null() {
+                    target.remove(key);
+                } else {
+                    merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+                }
+            }
+        }
+        patch => *target = patch.clone(),
+    }
+}
+
+/// Websocket connection to a game room. The state of the room is rebuilt from the snapshot and the deltas.
+pub struct RoomConnection {
+    framed: Framed<BoxedSocket, Codec>,
+    tick: u64,
+    state: Value,
+}
+
+impl RoomConnection {
+    /// The last tick received
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// The state of the room as of the last tick received
+    pub fn state(&self) -> &Value {
+        &self.state
+    }
+
+    pub async fn send_input(&mut self, input: &Value) -> Result<(), ClientError> {
+        let text = serde_json::to_string(input)?;
+        self.framed
+            .send(Message::Text(text))
+            .await
+            .map_err(|err| ClientError::WebSocket(format!("{}", err)))
+    }
+
+    /// Receive the next update of the room and apply it on the state
+    pub async fn next_update(&mut self) -> Result<RoomUpdate, ClientError> {
+        loop {
+            let frame = actix_rt::time::timeout(RECEIVE_TIMEOUT, self.framed.next())
+                .await
+                .map_err(|_| ClientError::Timeout)?;
+            let update = match frame {
+                Some(Ok(Frame::Text(text))) => serde_json::from_slice::<RoomUpdate>(&text)?,
+                Some(Ok(Frame::Ping(msg))) => {
+                    self.framed
+                        .send(Message::Pong(msg))
+                        .await
+                        .map_err(|err| ClientError::WebSocket(format!("{}", err)))?;
+                    continue;
+                }
+                Some(Ok(Frame::Close(reason))) => {
+                    return Err(ClientError::WebSocket(format!("Room closed: {:?}", reason)))
+                }
+                Some(Ok(_)) => continue,
+                Some(Err(err)) => return Err(ClientError::WebSocket(format!("{}", err))),
+                None => return Err(ClientError::WebSocket("Room closed".to_owned())),
+            };
+
+            match &update {
+                RoomUpdate::Snapshot { tick, state } => {
+                    self.tick = *tick;
+                    self.state = state.clone();
+                }
+                RoomUpdate::Delta { tick, patch } => {
+                    self.tick = *tick;
+                    merge_patch(&mut self.state, patch);
+                }
+                _ => {}
+            }
+            return Ok(update);
+        }
+    }
+
+    /// Receive the updates until the state of the room satisfies the condition
+    pub async fn wait_for_state<F>(&mut self, condition: F) -> Result<&Value, ClientError>
+    where
+        F: Fn(&Value) -> bool,
+    {
+        while !condition(&self.state) {
+            match self.next_update().await? {
+                RoomUpdate::Kicked { reason } => {
+                    return Err(ClientError::Protocol(format!("Kicked from the room: {:?}", reason)))
+                }
+                RoomUpdate::Closed => return Err(ClientError::Protocol("Room closed".to_owned())),
+                _ => {}
+            }
+        }
+        Ok(&self.state)
+    }
+
+    /// Leave the room
+    pub async fn close(mut self) -> Result<(), ClientError> {
+        self.framed
+            .send(Message::Close(None))
+            .await
+            .map_err(|err| ClientError::WebSocket(format!("{}", err)))
+    }
+}
@@ -0,0 +1,99 @@
+use chrono::Duration;
+use serde_json::json;
+use shine_auth::AuthConfig;
+use shine_core::requestinfo::AutomationTokenSigner;
+use shine_gamestate::GameStateConfig;
+
+/// Key of the test servers to sign the automation tokens granting access to the testing endpoints
+const AUTOMATION_SIGNING_KEY: &str = "ZTJlIGF1dG9tYXRpb24gdG9rZW4gc2lnbmluZyBrZXkgdXNlZCBieSB0aGUgdGVzdHMgb25seSEh";
+
+const ID_SESSION_SECRET: &str = "ZTJlIGlkZW50aXR5IHNlc3Npb24gc2VjcmV0IHVzZWQgYnkgdGhlIHRlc3Qgc2VydmVycyBvbmx5ISEhISEh";
+const AF_SESSION_SECRET: &str =
+    "ZTJlIGFudGktZm9yZ2VyeSBzZXNzaW9uIHNlY3JldCB1c2VkIGJ5IHRoZSB0ZXN0IHNlcnZlcnMgb25seSEhIQ==";
+
+/// Configuration of the services started by the TestServer
+#[derive(Clone, Debug)]
+pub struct TestConfig {
+    pub auth: AuthConfig,
+    pub gamestate: GameStateConfig,
+}
+
+impl TestConfig {
+    fn templates() -> String {
+        format!("{}/../auth/tera_web/*", env!("CARGO_MANIFEST_DIR"))
+    }
+
+    fn web_folder() -> String {
+        format!("{}/../auth/web", env!("CARGO_MANIFEST_DIR"))
+    }
+
+    /// All the stores are kept in memory and the external providers (mail, ip location) are disabled.
+    /// The cookies are not secure as the services are reached over plain http.
+    pub fn auth() -> AuthConfig {
+        serde_json::from_value(json!({
+            "iam": {
+                "password_pepper": "e2e-pepper",
+                "session_time_to_live_h": 1,
+                "email_verification_url": "http://localhost/verify",
+                "email_verification_time_to_live_h": 1,
+                "email_change_url": "http://localhost/email",
+                "password_reset_url": "http://localhost/reset",
+                "password_reset_time_to_live_m": 10,
+                "identity_store": { "type": "Memory" },
+                "session_store": { "type": "Memory" },
+                "role_store": { "type": "Memory" },
+                "apikey_store": { "type": "Memory" },
+                "oauth_store": { "type": "Memory" },
+                "entitlement_store": { "type": "Memory" },
+                "ip_location": { "type": "Disabled" },
+                "mailer": { "type": "Disabled" },
+                "automation_signing_key": AUTOMATION_SIGNING_KEY
+            },
+            "tera_templates": TestConfig::templates(),
+            "web_folder": TestConfig::web_folder(),
+            "recaptcha_secret": "",
+            "recaptcha_site_key": "",
+            "id_session_secret": ID_SESSION_SECRET,
+            "af_session_secret": AF_SESSION_SECRET,
+            "id_session": { "secure": false },
+            "af_session": { "secure": false },
+            "rate_limit": {
+                "capacity": 1000,
+                "refill_per_minute": 1000,
+                "key": "Ip",
+                "max_entries": 1000
+            }
+        }))
+        .expect("Invalid auth test configuration")
+    }
+
+    /// All the stores are kept in memory and the rooms are hosted by the service
+    pub fn gamestate() -> GameStateConfig {
+        serde_json::from_value(json!({
+            "tera_templates": TestConfig::templates(),
+            "web_folder": TestConfig::web_folder(),
+            "settings_store": { "type": "Memory" },
+            "leaderboard_store": { "type": "Memory" },
+            "save_store": { "type": "Memory" },
+            "turn_store": { "type": "Memory" }
+        }))
+        .expect("Invalid gamestate test configuration")
+    }
+}
+
+/// Mint a short-lived automation token of the test servers for the given scopes
+pub fn automation_token(scopes: &[&str]) -> String {
+    let scopes: Vec<String> = scopes.iter().map(|scope| (*scope).to_owned()).collect();
+    let signer = AutomationTokenSigner::from_base64(AUTOMATION_SIGNING_KEY).expect("Invalid automation signing key");
+    let (token, _) = signer.mint("e2e", &scopes, Duration::minutes(10));
+    token
+}
+
+impl Default for TestConfig {
+    fn default() -> TestConfig {
+        TestConfig {
+            auth: TestConfig::auth(),
+            gamestate: TestConfig::gamestate(),
+        }
+    }
+}
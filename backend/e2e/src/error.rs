@@ -0,0 +1,41 @@
+use reqwest::StatusCode;
+use std::{error, fmt};
+
+#[derive(Debug)]
+pub enum ClientError {
+    Http(reqwest::Error),
+    /// The service responded with an unexpected status
+    Status(StatusCode, String),
+    WebSocket(String),
+    Json(serde_json::Error),
+    /// The response is valid but not the one the flow expects
+    Protocol(String),
+    Timeout,
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClientError::Http(err) => write!(f, "Http error: {}", err),
+            ClientError::Status(status, body) => write!(f, "Unexpected status {}: {}", status, body),
+            ClientError::WebSocket(err) => write!(f, "Websocket error: {}", err),
+            ClientError::Json(err) => write!(f, "Json error: {}", err),
+            ClientError::Protocol(err) => write!(f, "Protocol error: {}", err),
+            ClientError::Timeout => write!(f, "Timeout"),
+        }
+    }
+}
+
+impl error::Error for ClientError {}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(err: reqwest::Error) -> ClientError {
+        ClientError::Http(err)
+    }
+}
+
+impl From<serde_json::Error> for ClientError {
+    fn from(err: serde_json::Error) -> ClientError {
+        ClientError::Json(err)
+    }
+}
@@ -0,0 +1,24 @@
+//! End-to-end test support for the backend. The auth and gamestate services are started with in-memory
+//! backends on a local port and the flows of the game are driven over the real http and websocket apis
+//! by headless clients.
+
+mod client;
+mod config;
+mod error;
+mod server;
+
+pub use self::client::*;
+pub use self::config::*;
+pub use self::error::*;
+pub use self::server::*;
+
+/// Initialize the logging of the tests, it can be called multiple times
+pub fn init_logger() {
+    let _ = pretty_env_logger::formatted_builder()
+        .is_test(true)
+        .filter_level(log::LevelFilter::Info)
+        .filter_module("shine_auth", log::LevelFilter::Debug)
+        .filter_module("shine_gamestate", log::LevelFilter::Debug)
+        .filter_module("shine_e2e", log::LevelFilter::Trace)
+        .try_init();
+}
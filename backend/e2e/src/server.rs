@@ -0,0 +1,94 @@
+use crate::TestConfig;
+use actix_rt::System;
+use actix_web::{App, HttpServer};
+use shine_auth::AuthService;
+use shine_core::metrics::Metrics;
+use shine_gamestate::GameStateService;
+use std::{
+    net::SocketAddr,
+    sync::mpsc,
+    thread::{self, JoinHandle},
+};
+
+/// The auth and gamestate services running on a dedicated thread and a random local port, the
+/// same way as they are composed by the backend. The services are stopped when the server is dropped.
+pub struct TestServer {
+    address: SocketAddr,
+    system: System,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl TestServer {
+    pub fn start() -> TestServer {
+        TestServer::start_with(TestConfig::default())
+    }
+
+    pub fn start_with(config: TestConfig) -> TestServer {
+        let (sender, receiver) = mpsc::channel();
+
+        let thread = thread::spawn(move || {
+            let mut sys = System::new("e2e");
+
+            let metrics = Metrics::new().expect("Metrics creation failed");
+            let auth =
+                AuthService::create(&mut sys, &config.auth, "auth", &metrics).expect("Auth service creation failed");
+            let gamestate = GameStateService::create(&mut sys, &config.gamestate, "gamestate", &metrics, None)
+                .expect("GameState service creation failed");
+
+            let server = HttpServer::new(move || {
+                App::new()
+                    .configure(|cfg| auth.configure(cfg))
+                    .configure(|cfg| gamestate.configure(cfg))
+            })
+            .workers(1)
+            .disable_signals()
+            .bind("127.0.0.1:0")
+            .expect("Server bind failed");
+            let address = server.addrs()[0];
+            let _ = server.run();
+
+            log::info!("Test server listening on {}", address);
+            sender
+                .send((address, System::current()))
+                .expect("Test server start was abandoned");
+            let _ = sys.run();
+        });
+
+        let (address, system) = receiver.recv().expect("Test server failed to start");
+        TestServer {
+            address,
+            system,
+            thread: Some(thread),
+        }
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    /// Root of the api of the auth service
+    pub fn auth_url(&self) -> String {
+        format!("{}/auth/api", self.url())
+    }
+
+    /// Root of the api of the gamestate service
+    pub fn gamestate_url(&self) -> String {
+        format!("{}/gamestate/api", self.url())
+    }
+
+    /// Root of the websockets of the gamestate service
+    pub fn gamestate_ws_url(&self) -> String {
+        format!("ws://{}/gamestate/api", self.address)
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.system.stop();
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                log::error!("Test server thread panicked");
+            }
+        }
+    }
+}
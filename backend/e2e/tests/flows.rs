@@ -0,0 +1,164 @@
+use serde_json::json;
+use shine_e2e::{init_logger, GameApp, GameClient, TestServer};
+use shine_gamestate::{
+    room::SHARED_STATE_SIMULATION,
+    turn::{MatchStatus, NewMatch, TurnSubmission},
+};
+use std::error::Error;
+
+type TestResult = Result<(), Box<dyn Error>>;
+
+async fn signed_in_player(server: &TestServer, app: &GameApp, name: &str) -> Result<GameClient, Box<dyn Error>> {
+    let mut player = GameClient::new(server)?;
+    player.register(name, &format!("{}-Password-0123", name)).await?;
+    player.sign_in(app).await?;
+    Ok(player)
+}
+
+#[actix_rt::test]
+async fn register_login_and_sign_in() -> TestResult {
+    init_logger();
+    let server = TestServer::start();
+    let app = GameApp::register(&server).await?;
+
+    let mut registered = GameClient::new(&server)?;
+    registered.register("alice", "Alice-Password-0123").await?;
+    registered.sign_in(&app).await?;
+
+    let mut returning = GameClient::new(&server)?;
+    assert!(returning.login("alice", "wrong-password").await.is_err());
+    returning.login("alice", "Alice-Password-0123").await?;
+    returning.sign_in(&app).await?;
+
+    assert_eq!(registered.user_id()?, returning.user_id()?);
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn automation_token_scopes() -> TestResult {
+    init_logger();
+    let server = TestServer::start();
+
+    let issuer = GameClient::new(&server)?;
+    issuer.register("issuer", "Issuer-Password-0123").await?;
+    let issuer_id = issuer.session_user_id().await?;
+    issuer
+        .grant_role(&issuer_id, "automation", &["automation.issue", "user.read"])
+        .await?;
+    issuer.login("issuer", "Issuer-Password-0123").await?;
+
+    issuer.create_automation_token(&["user.read"]).await?;
+    // the issuer cannot grant more than its own permissions
+    assert!(issuer.create_automation_token(&["user.purge"]).await.is_err());
+    assert!(issuer.create_automation_token(&["user.*"]).await.is_err());
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn matchmake_and_play() -> TestResult {
+    init_logger();
+    let server = TestServer::start();
+    let app = GameApp::register(&server).await?;
+    let alice = signed_in_player(&server, &app, "alice").await?;
+    let bob = signed_in_player(&server, &app, "bob").await?;
+
+    let lobby = alice.matchmake(1000).await?;
+    assert_eq!(lobby.owner_id, alice.user_id()?);
+    let joined = bob.matchmake(1010).await?;
+    assert_eq!(joined.id, lobby.id);
+    assert_eq!(joined.players.len(), 2);
+
+    assert!(bob.start_lobby(&lobby.id, SHARED_STATE_SIMULATION).await.is_err());
+    let started = alice.start_lobby(&lobby.id, SHARED_STATE_SIMULATION).await?;
+    let room_id = started.room_id.clone().expect("Started lobby without a room");
+    assert_eq!(bob.get_lobby(&lobby.id).await?.room_id.as_ref(), Some(&room_id));
+
+    let mut alice_room = alice.join_room(&room_id).await?;
+    let mut bob_room = bob.join_room(&room_id).await?;
+    let alice_id = alice.user_id()?.to_owned();
+    let bob_id = bob.user_id()?.to_owned();
+    alice_room
+        .wait_for_state(|state| state["players"].get(&bob_id).is_some())
+        .await?;
+
+    alice_room.send_input(&json!({ "action": "wave" })).await?;
+    let state = bob_room
+        .wait_for_state(|state| state["players"][&alice_id]["action"] == "wave")
+        .await?;
+    assert!(state["players"].get(&bob_id).is_some());
+
+    let tick = bob_room.tick();
+    bob_room
+        .wait_for_state(|state| state["tick"].as_u64() > Some(tick))
+        .await?;
+
+    alice_room.close().await?;
+    bob_room
+        .wait_for_state(|state| state["players"].get(&alice_id).is_none())
+        .await?;
+    bob_room.close().await?;
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn turn_based_match() -> TestResult {
+    init_logger();
+    let server = TestServer::start();
+    let app = GameApp::register(&server).await?;
+    let alice = signed_in_player(&server, &app, "alice").await?;
+    let bob = signed_in_player(&server, &app, "bob").await?;
+
+    let created = alice
+        .create_match(&NewMatch {
+            players: vec![bob.user_id()?.to_owned()],
+            state: json!({ "board": [] }),
+            turn_time_s: None,
+        })
+        .await?;
+    assert_eq!(created.current_player(), Some(alice.user_id()?));
+
+    assert!(bob
+        .submit_turn(
+            &created.id,
+            &TurnSubmission {
+                version: created.version,
+                state: json!({ "board": ["bob"] }),
+                winners: None,
+            },
+        )
+        .await
+        .is_err());
+
+    let played = alice
+        .submit_turn(
+            &created.id,
+            &TurnSubmission {
+                version: created.version,
+                state: json!({ "board": ["alice"] }),
+                winners: None,
+            },
+        )
+        .await?;
+
+    let seen = bob.get_match(&created.id).await?;
+    assert_eq!(seen.turn, played.turn);
+    assert_eq!(seen.current_player(), Some(bob.user_id()?));
+    assert_eq!(seen.state, json!({ "board": ["alice"] }));
+
+    let finished = bob
+        .submit_turn(
+            &created.id,
+            &TurnSubmission {
+                version: seen.version,
+                state: json!({ "board": ["alice", "bob"] }),
+                winners: Some(vec![bob.user_id()?.to_owned()]),
+            },
+        )
+        .await?;
+    assert_eq!(finished.status, MatchStatus::Finished);
+    assert_eq!(
+        alice.get_match(&created.id).await?.winners,
+        vec![bob.user_id()?.to_owned()]
+    );
+    Ok(())
+}
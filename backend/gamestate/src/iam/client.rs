@@ -0,0 +1,101 @@
+use crate::iam::proto::{
+    iam_internal_client::IamInternalClient, CheckPermissionRequest, ValidateSessionRequest, ValidateTokenRequest,
+};
+use chrono::{TimeZone, Utc};
+use shine_core::{
+    grpc::{GrpcError, InternalApiConfig},
+    requestinfo::{RemoteInfo, TokenIdentity},
+};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tonic::transport::{Channel, Endpoint};
+
+/// Client of the internal api of the auth service. The connection is established on the first call
+/// and it is shared by the workers.
+#[derive(Clone)]
+pub struct IamClient {
+    endpoint: Endpoint,
+    client: Arc<Mutex<Option<IamInternalClient<Channel>>>>,
+}
+
+impl IamClient {
+    pub fn new(config: &InternalApiConfig) -> Result<IamClient, GrpcError> {
+        let mut endpoint = Endpoint::from_shared(config.endpoint.clone())
+            .map_err(|err| GrpcError::Transport(format!("Invalid endpoint {}: {}", config.endpoint, err)))?
+            .timeout(Duration::from_secs(5));
+        if let Some(tls) = &config.tls {
+            endpoint = endpoint.tls_config(tls.client_tls_config()?);
+        }
+        Ok(IamClient {
+            endpoint,
+            client: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    async fn client(&self) -> Result<IamInternalClient<Channel>, GrpcError> {
+        if let Some(client) = self.client.lock().unwrap().clone() {
+            return Ok(client);
+        }
+        let channel = self.endpoint.connect().await?;
+        let client = IamInternalClient::new(channel);
+        *self.client.lock().unwrap() = Some(client.clone());
+        Ok(client)
+    }
+
+    /// Validate an access token, None if the token was rejected
+    pub async fn validate_token(&self, token: &str) -> Result<Option<TokenIdentity>, GrpcError> {
+        let reply = self
+            .client()
+            .await?
+            .validate_token(ValidateTokenRequest {
+                token: token.to_owned(),
+            })
+            .await?
+            .into_inner();
+        if !reply.valid {
+            return Ok(None);
+        }
+        Ok(Some(TokenIdentity {
+            client_id: reply.client_id,
+            user_id: reply.user_id,
+            scopes: reply.scopes,
+            expires: Utc.timestamp(reply.expires, 0),
+        }))
+    }
+
+    /// Validate the session of a user, returns the roles of the user or None if the session was rejected
+    pub async fn validate_session(
+        &self,
+        user_id: &str,
+        session_key: &str,
+        remote: &RemoteInfo,
+    ) -> Result<Option<Vec<String>>, GrpcError> {
+        let reply = self
+            .client()
+            .await?
+            .validate_session(ValidateSessionRequest {
+                user_id: user_id.to_owned(),
+                session_key: session_key.to_owned(),
+                agent: remote.agent().to_owned(),
+                remote_ip: remote.remote().map(|ip| ip.to_string()).unwrap_or_default(),
+            })
+            .await?
+            .into_inner();
+        Ok(if reply.valid { Some(reply.roles) } else { None })
+    }
+
+    pub async fn check_permission(&self, user_id: &str, permission: &str) -> Result<bool, GrpcError> {
+        let reply = self
+            .client()
+            .await?
+            .check_permission(CheckPermissionRequest {
+                user_id: user_id.to_owned(),
+                permission: permission.to_owned(),
+            })
+            .await?
+            .into_inner();
+        Ok(reply.granted)
+    }
+}
@@ -0,0 +1,9 @@
+pub mod proto {
+    tonic::include_proto!("shine.iam");
+}
+
+mod client;
+mod token_validator;
+
+pub use self::client::*;
+pub use self::token_validator::*;
@@ -0,0 +1,32 @@
+use crate::iam::IamClient;
+use shine_core::requestinfo::{BearerAuth, RequestInfoError, TokenIdentity, TokenValidator};
+use std::{future::Future, pin::Pin};
+
+/// Validate the access tokens through the internal api of the auth service
+pub struct GrpcTokenValidator {
+    client: IamClient,
+}
+
+impl GrpcTokenValidator {
+    pub fn new(client: IamClient) -> GrpcTokenValidator {
+        GrpcTokenValidator { client }
+    }
+}
+
+impl TokenValidator for GrpcTokenValidator {
+    fn validate<'s>(
+        &'s self,
+        auth: &'s BearerAuth,
+    ) -> Pin<Box<dyn Future<Output = Result<TokenIdentity, RequestInfoError>> + 's>> {
+        Box::pin(async move {
+            match self.client.validate_token(auth.token()).await {
+                Ok(Some(identity)) => Ok(identity),
+                Ok(None) => Err(RequestInfoError::TokenRejected),
+                Err(err) => {
+                    log::warn!("Token validation failed: {}", err);
+                    Err(RequestInfoError::TokenRejected)
+                }
+            }
+        })
+    }
+}
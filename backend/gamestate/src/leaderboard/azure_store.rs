@@ -0,0 +1,86 @@
+use crate::{
+    leaderboard::{
+        rank_scores, LeaderboardError, LeaderboardStore, RankedScore, ScoreData, ScoreEntry, ScoreQuery, StoreFuture,
+    },
+    GameStateConfig,
+};
+use azure_sdk_storage_table::{CloudTable, TableClient};
+use futures::stream::StreamExt;
+use percent_encoding::utf8_percent_encode;
+use shine_core::azure_utils;
+
+/// Leaderboard store using Azure table storage. A board is stored in a single partition and the
+/// ranking is computed in memory as the table storage has no server side ordering.
+#[derive(Clone)]
+pub struct AzureLeaderboardStore {
+    db: CloudTable,
+}
+
+impl AzureLeaderboardStore {
+    pub async fn new(config: &GameStateConfig) -> Result<Self, LeaderboardError> {
+        let client = TableClient::new(&config.storage_account, &config.storage_account_key)?;
+        let db = CloudTable::new(client, "leaderboards");
+        db.create_if_not_exists().await?;
+
+        Ok(AzureLeaderboardStore { db })
+    }
+}
+
+impl LeaderboardStore for AzureLeaderboardStore {
+    fn insert_score(&self, entry: ScoreEntry) -> StoreFuture<'_, ScoreEntry> {
+        Box::pin(async move {
+            match self.db.insert_entity(entry.into_entity()).await {
+                Ok(entry) => Ok(ScoreEntry::from_entity(entry)),
+                Err(err) if azure_utils::is_conflict_error(&err) => Err(LeaderboardError::ScoreConflict),
+                Err(err) => Err(err.into()),
+            }
+        })
+    }
+
+    fn update_score(&self, entry: ScoreEntry) -> StoreFuture<'_, ScoreEntry> {
+        Box::pin(async move {
+            // the etag of the entity is checked
+            match self.db.update_entity(entry.into_entity()).await {
+                Ok(entry) => Ok(ScoreEntry::from_entity(entry)),
+                Err(err) if azure_utils::is_precodition_error(&err) => Err(LeaderboardError::ScoreConflict),
+                Err(err) => Err(err.into()),
+            }
+        })
+    }
+
+    fn find_score<'a>(&'a self, board_id: &'a str, user_id: &'a str) -> StoreFuture<'a, Option<ScoreEntry>> {
+        Box::pin(async move {
+            let (p, r) = ScoreEntry::entity_keys(board_id, user_id);
+            let entry = self.db.get::<ScoreData>(&p, &r, None).await?;
+            Ok(entry.map(ScoreEntry::from_entity))
+        })
+    }
+
+    fn ranked_scores<'a>(&'a self, query: ScoreQuery<'a>) -> StoreFuture<'a, Vec<RankedScore>> {
+        Box::pin(async move {
+            let (p, _) = ScoreEntry::entity_keys(query.board_id, "");
+            let filter = format!("PartitionKey eq '{}'", p);
+            let filter = format!(
+                "$filter={}",
+                utf8_percent_encode(&filter, percent_encoding::NON_ALPHANUMERIC)
+            );
+
+            let mut entries = Vec::new();
+            let mut stream = Box::pin(self.db.stream_query::<ScoreData>(Some(&filter)));
+            while let Some(page) = stream.next().await {
+                entries.extend(page?.into_iter().map(ScoreEntry::from_entity).filter(|entry| {
+                    query
+                        .users
+                        .map(|users| users.iter().any(|u| u == entry.user_id()))
+                        .unwrap_or(true)
+                }));
+            }
+
+            Ok(rank_scores(entries, query.order)
+                .into_iter()
+                .skip(query.offset as usize)
+                .take(query.count as usize)
+                .collect())
+        })
+    }
+}
@@ -0,0 +1,41 @@
+use azure_sdk_core::errors::AzureError;
+use shine_core::kernel::response::APIError;
+use sqlx::Error as SqlxError;
+
+#[derive(Debug)]
+pub enum LeaderboardError {
+    /// Database related error
+    Internal(String),
+    BadRequest(String),
+    BoardNotFound(String),
+    /// The score is out of the range allowed by the board
+    ScoreRejected(i64),
+    /// The score of the user was submitted concurrently
+    ScoreConflict,
+}
+
+impl From<AzureError> for LeaderboardError {
+    fn from(err: AzureError) -> LeaderboardError {
+        LeaderboardError::Internal(format!("Azure error: {:?}", err))
+    }
+}
+
+impl From<SqlxError> for LeaderboardError {
+    fn from(err: SqlxError) -> LeaderboardError {
+        LeaderboardError::Internal(format!("Sql error: {}", err))
+    }
+}
+
+impl From<LeaderboardError> for APIError {
+    fn from(err: LeaderboardError) -> APIError {
+        match err {
+            LeaderboardError::BadRequest(msg) => APIError::BadRequest(msg),
+            LeaderboardError::BoardNotFound(board) => {
+                APIError::RespourceNotFound(format!("Leaderboard {} not found", board))
+            }
+            LeaderboardError::ScoreRejected(score) => APIError::BadRequest(format!("Score {} rejected", score)),
+            LeaderboardError::ScoreConflict => APIError::Conflict("Score submitted concurrently".to_owned()),
+            err => APIError::Internal(format!("{:?}", err)),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use crate::State;
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use shine_core::{kernel::response::APIResult, requestinfo::TokenIdentity};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScoreParams {
+    pub score: i64,
+}
+
+/// Submit a score for the user of the bearer token
+pub async fn submit_score(
+    state: web::Data<State>,
+    identity: TokenIdentity,
+    board_id: web::Path<String>,
+    params: web::Json<ScoreParams>,
+) -> APIResult {
+    log::info!("submit_score {:?} {} {:?}", identity.user_id, board_id, params);
+    let submission = state
+        .leaderboards()
+        .submit_score(&board_id, &identity.user_id, params.score)
+        .await?;
+    Ok(HttpResponse::Ok().json(submission))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScoresQuery {
+    #[serde(default)]
+    pub offset: u64,
+    #[serde(default = "ScoresQuery::default_count")]
+    pub count: u64,
+    /// Comma separated list of the users to rank along the user of the token (friend scope)
+    #[serde(default)]
+    pub friends: Option<String>,
+}
+
+impl ScoresQuery {
+    fn default_count() -> u64 {
+        20
+    }
+}
+
+/// Return a page of the ranked scores of a board
+pub async fn get_scores(
+    state: web::Data<State>,
+    identity: TokenIdentity,
+    board_id: web::Path<String>,
+    query: web::Query<ScoresQuery>,
+) -> APIResult {
+    let query = query.into_inner();
+    let users = query.friends.map(|friends| {
+        let mut users: Vec<String> = friends
+            .split(',')
+            .filter(|u| !u.is_empty())
+            .map(|u| u.to_owned())
+            .collect();
+        users.push(identity.user_id.clone());
+        users.sort();
+        users.dedup();
+        users
+    });
+
+    let page = state
+        .leaderboards()
+        .get_scores(&board_id, users.as_deref(), query.offset, query.count)
+        .await?;
+    Ok(HttpResponse::Ok().json(page))
+}
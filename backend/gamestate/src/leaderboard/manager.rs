@@ -0,0 +1,193 @@
+use crate::{
+    leaderboard::{
+        AzureLeaderboardStore, LeaderboardError, LeaderboardStore, LeaderboardStoreConfig, MemoryLeaderboardStore,
+        PostgresLeaderboardStore, RankedScore, ScoreEntry, ScoreOrder, ScoreQuery,
+    },
+    GameStateConfig,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shine_core::backoff::{self, Backoff, BackoffError};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+/// Maximum number of entries returned in a page
+pub const MAX_PAGE_SIZE: u64 = 100;
+/// Maximum number of users in a friend scoped query
+pub const MAX_SCOPE_SIZE: usize = 100;
+
+/// Rules of a score board
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BoardConfig {
+    #[serde(default)]
+    pub order: ScoreOrder,
+    /// Scores below this limit are rejected
+    #[serde(default)]
+    pub min_score: Option<i64>,
+    /// Scores above this limit are rejected
+    #[serde(default)]
+    pub max_score: Option<i64>,
+}
+
+/// Result of a score submission
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScoreSubmission {
+    /// The best score of the user on the board
+    pub best: i64,
+    pub submitted: DateTime<Utc>,
+    /// If the submitted score became the best score of the user
+    pub improved: bool,
+}
+
+/// A page of the ranked scores
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaderboardPage {
+    pub entries: Vec<RankedScore>,
+    /// Offset of the next page, None if there are no more entries
+    pub next_offset: Option<u64>,
+}
+
+impl LeaderboardError {
+    pub fn into_backoff(self) -> BackoffError<LeaderboardError> {
+        match self {
+            LeaderboardError::ScoreConflict => BackoffError::Transient(LeaderboardError::ScoreConflict),
+            e => BackoffError::Permanent(e),
+        }
+    }
+}
+
+/// Manage the score boards of the games. Only the best score of a user is kept on a board.
+#[derive(Clone)]
+pub struct LeaderboardManager {
+    store: Arc<dyn LeaderboardStore>,
+    boards: Arc<HashMap<String, BoardConfig>>,
+}
+
+impl LeaderboardManager {
+    pub async fn new(config: &GameStateConfig) -> Result<Self, LeaderboardError> {
+        let store: Arc<dyn LeaderboardStore> = match &config.leaderboard_store {
+            LeaderboardStoreConfig::Azure => Arc::new(AzureLeaderboardStore::new(config).await?),
+            LeaderboardStoreConfig::Postgres { url, max_connections } => {
+                Arc::new(PostgresLeaderboardStore::new(url, *max_connections).await?)
+            }
+            LeaderboardStoreConfig::Memory => Arc::new(MemoryLeaderboardStore::new()),
+        };
+        log::info!("Leaderboard store: {:?}", config.leaderboard_store);
+        log::info!("Leaderboards: {:?}", config.leaderboards.keys().collect::<Vec<_>>());
+
+        Ok(LeaderboardManager {
+            store,
+            boards: Arc::new(config.leaderboards.clone()),
+        })
+    }
+
+    fn board(&self, board_id: &str) -> Result<&BoardConfig, LeaderboardError> {
+        self.boards
+            .get(board_id)
+            .ok_or_else(|| LeaderboardError::BoardNotFound(board_id.to_owned()))
+    }
+
+    async fn try_submit_score(
+        &self,
+        board_id: &str,
+        board: &BoardConfig,
+        user_id: &str,
+        score: i64,
+    ) -> Result<ScoreSubmission, BackoffError<LeaderboardError>> {
+        let stored = self
+            .store
+            .find_score(board_id, user_id)
+            .await
+            .map_err(LeaderboardError::into_backoff)?;
+
+        let (entry, improved) = match stored {
+            Some(stored) if !board.order.is_better(score, stored.score()) => (stored, false),
+            Some(mut stored) => {
+                stored.update(score);
+                let entry = self
+                    .store
+                    .update_score(stored)
+                    .await
+                    .map_err(LeaderboardError::into_backoff)?;
+                (entry, true)
+            }
+            None => {
+                let entry = self
+                    .store
+                    .insert_score(ScoreEntry::new(board_id, user_id, score))
+                    .await
+                    .map_err(LeaderboardError::into_backoff)?;
+                (entry, true)
+            }
+        };
+
+        Ok(ScoreSubmission {
+            best: entry.score(),
+            submitted: entry.data().submitted,
+            improved,
+        })
+    }
+
+    /// Submit a score of a user. The score is validated against the rules of the board and it is stored
+    /// only if it is better than the previous best of the user.
+    pub async fn submit_score(
+        &self,
+        board_id: &str,
+        user_id: &str,
+        score: i64,
+    ) -> Result<ScoreSubmission, LeaderboardError> {
+        let board = self.board(board_id)?;
+        if board.min_score.map(|min| score < min).unwrap_or(false)
+            || board.max_score.map(|max| score > max).unwrap_or(false)
+        {
+            log::info!("Score {} of {} rejected on {}", score, user_id, board_id);
+            return Err(LeaderboardError::ScoreRejected(score));
+        }
+
+        backoff::Exponential::new(3, Duration::from_millis(10))
+            .async_execute(|_| self.try_submit_score(board_id, board, user_id, score))
+            .await
+    }
+
+    /// Return a page of the ranked scores. If users are given, only their scores are ranked (friend scope).
+    pub async fn get_scores(
+        &self,
+        board_id: &str,
+        users: Option<&[String]>,
+        offset: u64,
+        count: u64,
+    ) -> Result<LeaderboardPage, LeaderboardError> {
+        let board = self.board(board_id)?;
+        if let Some(users) = users {
+            if users.len() > MAX_SCOPE_SIZE {
+                return Err(LeaderboardError::BadRequest(format!(
+                    "At most {} users can be queried",
+                    MAX_SCOPE_SIZE
+                )));
+            }
+        }
+        let count = count.min(MAX_PAGE_SIZE);
+
+        // query one more entry to find if there is a next page
+        let mut entries = self
+            .store
+            .ranked_scores(ScoreQuery {
+                board_id,
+                order: board.order,
+                users,
+                offset,
+                count: count + 1,
+            })
+            .await?;
+        let next_offset = if entries.len() as u64 > count {
+            entries.truncate(count as usize);
+            Some(offset + count)
+        } else {
+            None
+        };
+
+        Ok(LeaderboardPage { entries, next_offset })
+    }
+}
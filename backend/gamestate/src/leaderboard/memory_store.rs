@@ -0,0 +1,99 @@
+use crate::leaderboard::{
+    rank_scores, LeaderboardError, LeaderboardStore, RankedScore, ScoreData, ScoreEntry, ScoreQuery, StoreFuture,
+};
+use azure_sdk_storage_table::TableEntity;
+use std::{collections::HashMap, sync::Mutex};
+
+fn into_entry(board_id: &str, user_id: &str, data: ScoreData, version: u64) -> ScoreEntry {
+    let (partition_key, row_key) = ScoreEntry::entity_keys(board_id, user_id);
+    ScoreEntry::from_entity(TableEntity {
+        partition_key,
+        row_key,
+        etag: Some(version.to_string()),
+        timestamp: None,
+        payload: data,
+    })
+}
+
+type Scores = HashMap<(String, String), (ScoreData, u64)>;
+
+/// Leaderboard store keeping the scores in the memory of the process, for local development and tests.
+/// The etag is emulated by a version counter.
+#[derive(Default)]
+pub struct MemoryLeaderboardStore {
+    scores: Mutex<Scores>,
+}
+
+impl MemoryLeaderboardStore {
+    pub fn new() -> Self {
+        MemoryLeaderboardStore::default()
+    }
+
+    fn with_scores<T, F>(&self, f: F) -> StoreFuture<'_, T>
+    where
+        T: 'static,
+        F: FnOnce(&mut Scores) -> Result<T, LeaderboardError>,
+    {
+        let result = {
+            let mut scores = self.scores.lock().unwrap();
+            f(&mut scores)
+        };
+        Box::pin(async move { result })
+    }
+}
+
+impl LeaderboardStore for MemoryLeaderboardStore {
+    fn insert_score(&self, entry: ScoreEntry) -> StoreFuture<'_, ScoreEntry> {
+        self.with_scores(move |scores| {
+            let key = (entry.board_id().to_owned(), entry.user_id().to_owned());
+            if scores.contains_key(&key) {
+                return Err(LeaderboardError::ScoreConflict);
+            }
+            scores.insert(key, (entry.data().clone(), 0));
+            Ok(into_entry(entry.board_id(), entry.user_id(), entry.data().clone(), 0))
+        })
+    }
+
+    fn update_score(&self, entry: ScoreEntry) -> StoreFuture<'_, ScoreEntry> {
+        self.with_scores(move |scores| {
+            let key = (entry.board_id().to_owned(), entry.user_id().to_owned());
+            match scores.get_mut(&key) {
+                Some((data, version)) => {
+                    if entry.etag() != Some(&version.to_string()) {
+                        return Err(LeaderboardError::ScoreConflict);
+                    }
+                    *data = entry.data().clone();
+                    *version += 1;
+                    Ok(into_entry(entry.board_id(), entry.user_id(), data.clone(), *version))
+                }
+                None => Err(LeaderboardError::ScoreConflict),
+            }
+        })
+    }
+
+    fn find_score<'a>(&'a self, board_id: &'a str, user_id: &'a str) -> StoreFuture<'a, Option<ScoreEntry>> {
+        self.with_scores(|scores| {
+            let key = (board_id.to_owned(), user_id.to_owned());
+            Ok(scores
+                .get(&key)
+                .map(|(data, version)| into_entry(board_id, user_id, data.clone(), *version)))
+        })
+    }
+
+    fn ranked_scores<'a>(&'a self, query: ScoreQuery<'a>) -> StoreFuture<'a, Vec<RankedScore>> {
+        self.with_scores(|scores| {
+            let entries = scores
+                .iter()
+                .filter(|((board, user), _)| {
+                    board == query.board_id && query.users.map(|users| users.contains(user)).unwrap_or(true)
+                })
+                .map(|((board, user), (data, version))| into_entry(board, user, data.clone(), *version))
+                .collect();
+            Ok(rank_scores(entries, query.order)
+                .into_iter()
+                .skip(query.offset as usize)
+                .take(query.count as usize)
+                .collect())
+        })
+    }
+}
@@ -0,0 +1,17 @@
+mod azure_store;
+mod error;
+mod handler;
+mod manager;
+mod memory_store;
+mod postgres_store;
+mod score;
+mod store;
+
+pub use self::azure_store::*;
+pub use self::error::*;
+pub use self::handler::*;
+pub use self::manager::*;
+pub use self::memory_store::*;
+pub use self::postgres_store::*;
+pub use self::score::*;
+pub use self::store::*;
@@ -0,0 +1,174 @@
+use crate::leaderboard::{
+    LeaderboardError, LeaderboardStore, RankedScore, ScoreData, ScoreEntry, ScoreOrder, ScoreQuery, StoreFuture,
+};
+use azure_sdk_storage_table::TableEntity;
+use chrono::{DateTime, Utc};
+use sqlx::{
+    postgres::{PgDatabaseError, PgPool, PgPoolOptions},
+    FromRow,
+};
+
+const SCHEMA: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS leaderboard_scores (
+        board_id TEXT NOT NULL,
+        user_id TEXT NOT NULL,
+        score BIGINT NOT NULL,
+        submitted TIMESTAMPTZ NOT NULL,
+        version BIGINT NOT NULL,
+        CONSTRAINT leaderboard_scores_pkey PRIMARY KEY (board_id, user_id)
+    )",
+    "CREATE INDEX IF NOT EXISTS leaderboard_scores_rank ON leaderboard_scores (board_id, score, submitted)",
+];
+
+#[derive(FromRow)]
+struct ScoreRow {
+    board_id: String,
+    user_id: String,
+    score: i64,
+    submitted: DateTime<Utc>,
+    version: i64,
+}
+
+impl ScoreRow {
+    fn into_entry(self) -> ScoreEntry {
+        let (partition_key, row_key) = ScoreEntry::entity_keys(&self.board_id, &self.user_id);
+        ScoreEntry::from_entity(TableEntity {
+            partition_key,
+            row_key,
+            etag: Some(self.version.to_string()),
+            timestamp: None,
+            payload: ScoreData {
+                score: self.score,
+                submitted: self.submitted,
+            },
+        })
+    }
+}
+
+#[derive(FromRow)]
+struct RankedRow {
+    rank: i64,
+    user_id: String,
+    score: i64,
+    submitted: DateTime<Utc>,
+}
+
+fn map_unique_violation(err: sqlx::Error) -> LeaderboardError {
+    if let sqlx::Error::Database(ref db_err) = err {
+        if let Some(pg_err) = db_err.try_downcast_ref::<PgDatabaseError>() {
+            if pg_err.code() == "23505" && pg_err.constraint() == Some("leaderboard_scores_pkey") {
+                return LeaderboardError::ScoreConflict;
+            }
+        }
+    }
+    LeaderboardError::from(err)
+}
+
+/// Leaderboard store using a Postgres database, the ranking is computed by the database. The etag is
+/// emulated by a version column.
+#[derive(Clone)]
+pub struct PostgresLeaderboardStore {
+    pool: PgPool,
+}
+
+impl PostgresLeaderboardStore {
+    pub async fn new(url: &str, max_connections: u32) -> Result<Self, LeaderboardError> {
+        let pool = PgPoolOptions::new()
+            .max_connections(max_connections)
+            .connect(url)
+            .await?;
+
+        for statement in SCHEMA {
+            sqlx::query(statement).execute(&pool).await?;
+        }
+
+        Ok(PostgresLeaderboardStore { pool })
+    }
+}
+
+impl LeaderboardStore for PostgresLeaderboardStore {
+    fn insert_score(&self, entry: ScoreEntry) -> StoreFuture<'_, ScoreEntry> {
+        Box::pin(async move {
+            let row = sqlx::query_as::<_, ScoreRow>(
+                "INSERT INTO leaderboard_scores (board_id, user_id, score, submitted, version)
+                VALUES ($1, $2, $3, $4, 0) RETURNING board_id, user_id, score, submitted, version",
+            )
+            .bind(entry.board_id())
+            .bind(entry.user_id())
+            .bind(entry.score())
+            .bind(entry.data().submitted)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(map_unique_violation)?;
+            Ok(row.into_entry())
+        })
+    }
+
+    fn update_score(&self, entry: ScoreEntry) -> StoreFuture<'_, ScoreEntry> {
+        Box::pin(async move {
+            let version: i64 = entry
+                .etag()
+                .and_then(|etag| etag.parse().ok())
+                .ok_or(LeaderboardError::ScoreConflict)?;
+            let row = sqlx::query_as::<_, ScoreRow>(
+                "UPDATE leaderboard_scores SET score = $3, submitted = $4, version = version + 1 \
+                 WHERE board_id = $1 AND user_id = $2 AND version = $5 \
+                 RETURNING board_id, user_id, score, submitted, version",
+            )
+            .bind(entry.board_id())
+            .bind(entry.user_id())
+            .bind(entry.score())
+            .bind(entry.data().submitted)
+            .bind(version)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or(LeaderboardError::ScoreConflict)?;
+            Ok(row.into_entry())
+        })
+    }
+
+    fn find_score<'a>(&'a self, board_id: &'a str, user_id: &'a str) -> StoreFuture<'a, Option<ScoreEntry>> {
+        Box::pin(async move {
+            let row = sqlx::query_as::<_, ScoreRow>(
+                "SELECT board_id, user_id, score, submitted, version FROM leaderboard_scores \
+                 WHERE board_id = $1 AND user_id = $2",
+            )
+            .bind(board_id)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+            Ok(row.map(ScoreRow::into_entry))
+        })
+    }
+
+    fn ranked_scores<'a>(&'a self, query: ScoreQuery<'a>) -> StoreFuture<'a, Vec<RankedScore>> {
+        Box::pin(async move {
+            let order = match query.order {
+                ScoreOrder::Descending => "score DESC",
+                ScoreOrder::Ascending => "score ASC",
+            };
+            let sql = format!(
+                "SELECT ROW_NUMBER() OVER (ORDER BY {order}, submitted ASC) AS rank, user_id, score, submitted \
+                 FROM leaderboard_scores WHERE board_id = $1 AND ($2::TEXT[] IS NULL OR user_id = ANY($2)) \
+                 ORDER BY {order}, submitted ASC OFFSET $3 LIMIT $4",
+                order = order
+            );
+            let rows = sqlx::query_as::<_, RankedRow>(&sql)
+                .bind(query.board_id)
+                .bind(query.users.map(|users| users.to_vec()))
+                .bind(query.offset as i64)
+                .bind(query.count as i64)
+                .fetch_all(&self.pool)
+                .await?;
+            Ok(rows
+                .into_iter()
+                .map(|row| RankedScore {
+                    rank: row.rank as u64,
+                    user_id: row.user_id,
+                    score: row.score,
+                    submitted: row.submitted,
+                })
+                .collect())
+        })
+    }
+}
@@ -0,0 +1,131 @@
+use azure_sdk_storage_table::TableEntity;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shine_core::serde_with;
+
+/// Ordering of the scores of a board
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScoreOrder {
+    /// Higher scores are better (points)
+    Descending,
+    /// Lower scores are better (lap times)
+    Ascending,
+}
+
+impl Default for ScoreOrder {
+    fn default() -> Self {
+        ScoreOrder::Descending
+    }
+}
+
+impl ScoreOrder {
+    /// Return if score a is better than score b
+    pub fn is_better(self, a: i64, b: i64) -> bool {
+        match self {
+            ScoreOrder::Descending => a > b,
+            ScoreOrder::Ascending => a < b,
+        }
+    }
+}
+
+/// Best score of a user on a board
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ScoreData {
+    pub score: i64,
+
+    #[serde(with = "serde_with::datetime")]
+    pub submitted: DateTime<Utc>,
+}
+
+/// Score entry of a board, indexed by the board and the user. Only the best score of a user is kept.
+#[derive(Debug)]
+pub struct ScoreEntry(TableEntity<ScoreData>);
+
+impl ScoreEntry {
+    pub fn entity_keys(board_id: &str, user_id: &str) -> (String, String) {
+        (format!("board-{}", board_id), user_id.to_owned())
+    }
+
+    pub fn new(board_id: &str, user_id: &str, score: i64) -> Self {
+        let (partition_key, row_key) = Self::entity_keys(board_id, user_id);
+        Self(TableEntity {
+            partition_key,
+            row_key,
+            etag: None,
+            timestamp: None,
+            payload: ScoreData {
+                score,
+                submitted: Utc::now(),
+            },
+        })
+    }
+
+    pub fn from_entity(entity: TableEntity<ScoreData>) -> Self {
+        Self(entity)
+    }
+
+    pub fn into_entity(self) -> TableEntity<ScoreData> {
+        self.0
+    }
+
+    pub fn data(&self) -> &ScoreData {
+        &self.0.payload
+    }
+
+    pub fn board_id(&self) -> &str {
+        &self.0.partition_key[6..]
+    }
+
+    pub fn user_id(&self) -> &str {
+        &self.0.row_key
+    }
+
+    pub fn etag(&self) -> Option<&str> {
+        self.0.etag.as_deref()
+    }
+
+    pub fn score(&self) -> i64 {
+        self.0.payload.score
+    }
+
+    /// Replace the score keeping the etag for the optimistic concurrency
+    pub fn update(&mut self, score: i64) {
+        self.0.payload = ScoreData {
+            score,
+            submitted: Utc::now(),
+        };
+    }
+}
+
+/// A score with the position on the board. Equal scores are ranked by the submission time.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RankedScore {
+    pub rank: u64,
+    pub user_id: String,
+    pub score: i64,
+    pub submitted: DateTime<Utc>,
+}
+
+/// Sort the entries of a board and assign the ranks, used by the stores without server side ordering
+pub fn rank_scores(mut entries: Vec<ScoreEntry>, order: ScoreOrder) -> Vec<RankedScore> {
+    entries.sort_by(|a, b| {
+        let (a, b) = (a.data(), b.data());
+        match order {
+            ScoreOrder::Descending => b.score.cmp(&a.score),
+            ScoreOrder::Ascending => a.score.cmp(&b.score),
+        }
+        .then(a.submitted.cmp(&b.submitted))
+    });
+    entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| RankedScore {
+            rank: i as u64 + 1,
+            user_id: entry.user_id().to_owned(),
+            score: entry.score(),
+            submitted: entry.data().submitted,
+        })
+        .collect()
+}
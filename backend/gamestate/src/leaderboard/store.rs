@@ -0,0 +1,51 @@
+use crate::leaderboard::{LeaderboardError, RankedScore, ScoreEntry, ScoreOrder};
+use serde::{Deserialize, Serialize};
+use std::{future::Future, pin::Pin};
+
+pub type StoreFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, LeaderboardError>> + 'a>>;
+
+/// Storage backend of the leaderboards
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum LeaderboardStoreConfig {
+    /// Azure table storage using the storage account of the GameStateConfig. The ranking is computed
+    /// in memory, suitable for boards of moderate size.
+    Azure,
+    Postgres {
+        url: String,
+        max_connections: u32,
+    },
+    /// Keep the scores in memory, for local development and tests
+    Memory,
+}
+
+impl Default for LeaderboardStoreConfig {
+    fn default() -> Self {
+        LeaderboardStoreConfig::Azure
+    }
+}
+
+/// Select a page of the ranked scores
+#[derive(Clone, Debug)]
+pub struct ScoreQuery<'a> {
+    pub board_id: &'a str,
+    pub order: ScoreOrder,
+    /// Rank only the scores of these users (friend scope), None to rank the whole board
+    pub users: Option<&'a [String]>,
+    pub offset: u64,
+    pub count: u64,
+}
+
+/// Persistence of the scores. Validation and best score selection is implemented by the LeaderboardManager.
+pub trait LeaderboardStore {
+    /// Insert the first score of a user, returns ScoreConflict if the user already has a score
+    fn insert_score(&self, entry: ScoreEntry) -> StoreFuture<'_, ScoreEntry>;
+
+    /// Update the score, returns ScoreConflict if the score was modified since it was read
+    fn update_score(&self, entry: ScoreEntry) -> StoreFuture<'_, ScoreEntry>;
+
+    fn find_score<'a>(&'a self, board_id: &'a str, user_id: &'a str) -> StoreFuture<'a, Option<ScoreEntry>>;
+
+    /// Return a page of the ranked scores
+    fn ranked_scores<'a>(&'a self, query: ScoreQuery<'a>) -> StoreFuture<'a, Vec<RankedScore>>;
+}
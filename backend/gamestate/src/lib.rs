@@ -1,82 +1,451 @@
 use actix_files;
 use actix_rt::SystemRunner;
-use actix_web::web;
+use actix_web::{web, HttpResponse};
 use serde::{Deserialize, Serialize};
+use shine_core::{
+    grpc::{GrpcError, InternalApiConfig},
+    kernel::response::APIResult,
+    metrics::{Metrics, RequestMetrics},
+    requestinfo::{TokenIdentity, TokenValidatorRef},
+    secret::{SecretRule, SecretValidator, SecretWatcher},
+};
 use std::{
     cell::{Ref, RefCell},
+    collections::HashMap,
     fmt,
     rc::Rc,
+    sync::Arc,
 };
 use tera::{Error as TeraError, Tera};
 
+pub mod iam;
+pub mod leaderboard;
+pub mod lobby;
+pub mod party;
+pub mod region;
+pub mod room;
+pub mod saves;
+pub mod settings;
+pub mod turn;
+pub mod voice;
+
+use self::iam::{GrpcTokenValidator, IamClient};
+use self::leaderboard::{BoardConfig, LeaderboardError, LeaderboardManager, LeaderboardStoreConfig};
+use self::lobby::{LobbyConfig, LobbyManager};
+use self::party::{PartyConfig, PartyManager};
+use self::region::{RegionManager, RegionsConfig};
+use self::room::{
+    ExternalRoomAllocator, LocalRoomAllocator, RoomAllocator, RoomAllocatorConfig, RoomConfig, RoomManager,
+};
+use self::saves::{SaveConfig, SaveError, SaveManager, SaveStoreConfig};
+use self::settings::{SettingsError, SettingsManager, SettingsStoreConfig};
+use self::turn::{TurnConfig, TurnError, TurnManager, TurnStoreConfig};
+use self::voice::{VoiceConfig, VoiceManager};
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct GameStateConfig {
     pub tera_templates: String,
     pub web_folder: String,
+    #[serde(default)]
+    pub storage_account: String,
+    #[serde(default)]
+    pub storage_account_key: String,
+    #[serde(default)]
+    pub settings_store: SettingsStoreConfig,
+    #[serde(default)]
+    pub leaderboard_store: LeaderboardStoreConfig,
+    /// The score boards by id
+    #[serde(default)]
+    pub leaderboards: HashMap<String, BoardConfig>,
+    #[serde(default)]
+    pub save_store: SaveStoreConfig,
+    #[serde(default)]
+    pub saves: SaveConfig,
+    #[serde(default)]
+    pub lobby: LobbyConfig,
+    #[serde(default)]
+    pub party: PartyConfig,
+    #[serde(default)]
+    pub room: RoomConfig,
+    #[serde(default)]
+    pub room_allocator: RoomAllocatorConfig,
+    #[serde(default)]
+    pub regions: RegionsConfig,
+    #[serde(default)]
+    pub turn_store: TurnStoreConfig,
+    #[serde(default)]
+    pub turn: TurnConfig,
+    #[serde(default)]
+    pub voice: VoiceConfig,
+}
+
+impl GameStateConfig {
+    /// Run without any external service: the stores are kept in memory and the rooms are hosted by the
+    /// service.
+    pub fn use_offline_services(&mut self) {
+        self.settings_store = SettingsStoreConfig::Memory;
+        self.leaderboard_store = LeaderboardStoreConfig::Memory;
+        self.save_store = SaveStoreConfig::Memory;
+        self.turn_store = TurnStoreConfig::Memory;
+        self.room_allocator = RoomAllocatorConfig::Local;
+    }
+
+    /// Resolve and validate the secrets required by the configured stores
+    pub async fn check_secrets(&mut self, validator: &mut SecretValidator) {
+        if matches!(self.settings_store, SettingsStoreConfig::Azure)
+            || matches!(self.leaderboard_store, LeaderboardStoreConfig::Azure)
+        {
+            validator
+                .check(
+                    "gamestate.storage_account_key",
+                    &mut self.storage_account_key,
+                    SecretRule::key(64).with_max_len(64),
+                )
+                .await;
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum GameStateCreateError {
     ConfigureTera(TeraError),
+    ConfigureSettings(SettingsError),
+    ConfigureLeaderboard(LeaderboardError),
+    ConfigureSaves(SaveError),
+    ConfigureTurns(TurnError),
+    ConfigureInternalApi(GrpcError),
 }
 
 impl fmt::Display for GameStateCreateError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             GameStateCreateError::ConfigureTera(err) => write!(f, "Error in tera configuration: {:?}", err),
+            GameStateCreateError::ConfigureSettings(err) => write!(f, "Error in settings configuration: {:?}", err),
+            GameStateCreateError::ConfigureLeaderboard(err) => {
+                write!(f, "Error in leaderboard configuration: {:?}", err)
+            }
+            GameStateCreateError::ConfigureSaves(err) => write!(f, "Error in save configuration: {:?}", err),
+            GameStateCreateError::ConfigureTurns(err) => write!(f, "Error in turn configuration: {:?}", err),
+            GameStateCreateError::ConfigureInternalApi(err) => {
+                write!(f, "Error in internal api configuration: {}", err)
+            }
         }
     }
 }
 
 struct Inner {
     tera: RefCell<Tera>,
+    settings: SettingsManager,
+    leaderboards: LeaderboardManager,
+    saves: SaveManager,
+    lobbies: LobbyManager,
+    parties: PartyManager,
+    rooms: RoomManager,
+    allocator: Arc<dyn RoomAllocator>,
+    regions: RegionManager,
+    turns: TurnManager,
+    voices: VoiceManager,
+    iam: Option<IamClient>,
 }
 
 #[derive(Clone)]
 pub struct State(Rc<Inner>);
 
 impl State {
-    pub fn new(tera: Tera) -> Self {
+    pub fn new(
+        tera: Tera,
+        settings: SettingsManager,
+        leaderboards: LeaderboardManager,
+        saves: SaveManager,
+        lobbies: LobbyManager,
+        parties: PartyManager,
+        rooms: RoomManager,
+        allocator: Arc<dyn RoomAllocator>,
+        regions: RegionManager,
+        turns: TurnManager,
+        voices: VoiceManager,
+        iam: Option<IamClient>,
+    ) -> Self {
         Self(Rc::new(Inner {
             tera: RefCell::new(tera),
+            settings,
+            leaderboards,
+            saves,
+            lobbies,
+            parties,
+            rooms,
+            allocator,
+            regions,
+            turns,
+            voices,
+            iam,
         }))
     }
 
     pub fn tera(&self) -> Ref<Tera> {
         self.0.tera.borrow()
     }
+
+    pub fn settings(&self) -> &SettingsManager {
+        &self.0.settings
+    }
+
+    pub fn leaderboards(&self) -> &LeaderboardManager {
+        &self.0.leaderboards
+    }
+
+    pub fn saves(&self) -> &SaveManager {
+        &self.0.saves
+    }
+
+    pub fn lobbies(&self) -> &LobbyManager {
+        &self.0.lobbies
+    }
+
+    pub fn parties(&self) -> &PartyManager {
+        &self.0.parties
+    }
+
+    pub fn rooms(&self) -> &RoomManager {
+        &self.0.rooms
+    }
+
+    /// Host of the new rooms, either this service or the dedicated game servers
+    pub fn allocator(&self) -> &dyn RoomAllocator {
+        &*self.0.allocator
+    }
+
+    pub fn regions(&self) -> &RegionManager {
+        &self.0.regions
+    }
+
+    pub fn turns(&self) -> &TurnManager {
+        &self.0.turns
+    }
+
+    pub fn voices(&self) -> &VoiceManager {
+        &self.0.voices
+    }
+
+    /// Client of the internal api of the auth service, None if the internal api is not configured
+    pub fn iam(&self) -> Option<&IamClient> {
+        self.0.iam.as_ref()
+    }
+}
+
+/// Return the identity of the bearer token. The token validator is registered application wide by the
+/// auth service, or the tokens are validated through the internal api of the auth service when it is configured.
+async fn get_me(identity: TokenIdentity) -> APIResult {
+    log::info!("get_me {:?}", identity.user_id);
+    Ok(HttpResponse::Ok().json(identity))
 }
 
 #[derive(Clone)]
 pub struct GameStateService {
     tera: Tera,
+    settings: SettingsManager,
+    leaderboards: LeaderboardManager,
+    saves: SaveManager,
+    lobbies: LobbyManager,
+    parties: PartyManager,
+    rooms: RoomManager,
+    allocator: Arc<dyn RoomAllocator>,
+    regions: RegionManager,
+    turns: TurnManager,
+    voices: VoiceManager,
+    iam: Option<IamClient>,
+    metrics: Metrics,
     web_folder: String,
     web_root: String,
 }
 
 impl GameStateService {
     pub fn create(
-        _sys: &mut SystemRunner,
+        sys: &mut SystemRunner,
         config: &GameStateConfig,
         web_root: &str,
+        metrics: &Metrics,
+        internal_api: Option<&InternalApiConfig>,
     ) -> Result<GameStateService, GameStateCreateError> {
         log::info!("Parsing tera templates");
         let tera = Tera::new(&config.tera_templates).map_err(|err| GameStateCreateError::ConfigureTera(err.into()))?;
+        let settings = sys
+            .block_on(SettingsManager::new(config))
+            .map_err(GameStateCreateError::ConfigureSettings)?;
+        let leaderboards = sys
+            .block_on(LeaderboardManager::new(config))
+            .map_err(GameStateCreateError::ConfigureLeaderboard)?;
+        let saves = sys
+            .block_on(SaveManager::new(config))
+            .map_err(GameStateCreateError::ConfigureSaves)?;
+        let turns = sys
+            .block_on(TurnManager::new(config))
+            .map_err(GameStateCreateError::ConfigureTurns)?;
+        let rooms = RoomManager::new(&config.room, metrics);
+        let allocator: Arc<dyn RoomAllocator> = match &config.room_allocator {
+            RoomAllocatorConfig::Local => Arc::new(LocalRoomAllocator::new(rooms.clone())),
+            RoomAllocatorConfig::External(external) => Arc::new(ExternalRoomAllocator::new(external)),
+        };
+        log::info!("Room allocator: {:?}", config.room_allocator);
+        let regions = RegionManager::new(&config.regions, allocator.clone());
+        let parties = PartyManager::new(&config.party);
+        let voices = VoiceManager::new(&config.voice, parties.clone());
+        let iam = internal_api
+            .map(IamClient::new)
+            .transpose()
+            .map_err(GameStateCreateError::ConfigureInternalApi)?;
 
         Ok(GameStateService {
             tera,
+            settings,
+            leaderboards,
+            saves,
+            lobbies: LobbyManager::new(&config.lobby),
+            parties,
+            rooms,
+            allocator,
+            regions,
+            turns,
+            voices,
+            iam,
+            metrics: metrics.clone(),
             web_folder: config.web_folder.clone(),
             web_root: web_root.to_owned(),
         })
     }
 
+    /// Handle the rotation of the storage key fetched from the SecretProvider. The running service keeps the
+    /// key of the startup, the rotated one takes effect when the service is restarted.
+    pub fn watch_secret_rotation(&self, watcher: &SecretWatcher) {
+        watcher.on_rotation("gamestate.storage_account_key", |rotation| {
+            log::warn!(
+                "Secret {} has been rotated, restart the service to apply it",
+                rotation.config_name
+            )
+        });
+    }
+
     pub fn configure(&self, services: &mut web::ServiceConfig) {
-        let state = State::new(self.tera.clone());
+        let state = State::new(
+            self.tera.clone(),
+            self.settings.clone(),
+            self.leaderboards.clone(),
+            self.saves.clone(),
+            self.lobbies.clone(),
+            self.parties.clone(),
+            self.rooms.clone(),
+            self.allocator.clone(),
+            self.regions.clone(),
+            self.turns.clone(),
+            self.voices.clone(),
+            self.iam.clone(),
+        );
+
+        // one byte of slack so the quota check of the manager reports the oversized saves
+        let save_payload_limit = self.saves.config().max_size as usize + 1;
+
+        let mut scope = web::scope(&self.web_root);
+        if let Some(iam) = &self.iam {
+            let validator: TokenValidatorRef = Arc::new(GrpcTokenValidator::new(iam.clone()));
+            scope = scope.data(validator);
+        }
 
         services.service(
-            web::scope(&self.web_root)
+            scope
+                .wrap(RequestMetrics::new(self.metrics.clone(), "gamestate"))
                 .data(state.clone())
-                .service(actix_files::Files::new("/static", &self.web_folder)),
+                .service(actix_files::Files::new("/static", &self.web_folder))
+                .service(
+                    web::scope("api")
+                        .service(web::resource("me").route(web::get().to(get_me)))
+                        .service(
+                            web::resource("settings")
+                                .route(web::get().to(settings::get_settings))
+                                .route(web::put().to(settings::put_settings)),
+                        )
+                        .service(web::resource("leaderboards/{board}").route(web::get().to(leaderboard::get_scores)))
+                        .service(
+                            web::resource("leaderboards/{board}/scores")
+                                .route(web::post().to(leaderboard::submit_score)),
+                        )
+                        .service(web::resource("saves").route(web::get().to(saves::list_saves)))
+                        .service(
+                            web::resource("saves/{slot}")
+                                .data(web::PayloadConfig::new(save_payload_limit))
+                                .route(web::get().to(saves::get_save))
+                                .route(web::put().to(saves::put_save))
+                                .route(web::delete().to(saves::delete_save)),
+                        )
+                        .service(
+                            web::resource("lobbies")
+                                .route(web::get().to(lobby::list_lobbies))
+                                .route(web::post().to(lobby::create_lobby)),
+                        )
+                        .service(web::resource("lobbies/leave").route(web::post().to(lobby::leave_lobby)))
+                        .service(web::resource("lobbies/{id}").route(web::get().to(lobby::get_lobby)))
+                        .service(web::resource("lobbies/{id}/join").route(web::post().to(lobby::join_lobby)))
+                        .service(web::resource("lobbies/{id}/start").route(web::post().to(lobby::start_lobby)))
+                        .service(web::resource("lobbies/{id}/ws").route(web::get().to(lobby::lobby_socket)))
+                        .service(web::resource("matchmaking").route(web::post().to(lobby::matchmake)))
+                        .service(
+                            web::resource("parties")
+                                .route(web::get().to(party::get_party))
+                                .route(web::post().to(party::create_party)),
+                        )
+                        .service(web::resource("parties/leave").route(web::post().to(party::leave_party)))
+                        .service(web::resource("parties/chat").route(web::post().to(party::party_chat)))
+                        .service(web::resource("parties/events").route(web::get().to(party::party_events)))
+                        .service(web::resource("parties/matchmaking").route(web::post().to(party::matchmake_party)))
+                        .service(web::resource("parties/invites/{user}").route(web::post().to(party::invite_to_party)))
+                        .service(
+                            web::resource("parties/members/{user}/kick")
+                                .route(web::post().to(party::kick_party_member)),
+                        )
+                        .service(
+                            web::resource("parties/members/{user}/promote")
+                                .route(web::post().to(party::promote_party_member)),
+                        )
+                        .service(web::resource("parties/{id}/join").route(web::post().to(party::join_party)))
+                        .service(web::resource("parties/{id}/decline").route(web::post().to(party::decline_party)))
+                        .service(web::resource("relations").route(web::get().to(party::get_relations)))
+                        .service(
+                            web::resource("relations/friends/{user}")
+                                .route(web::put().to(party::add_friend))
+                                .route(web::delete().to(party::remove_friend)),
+                        )
+                        .service(
+                            web::resource("relations/blocks/{user}")
+                                .route(web::put().to(party::block_user))
+                                .route(web::delete().to(party::unblock_user)),
+                        )
+                        .service(web::resource("regions").route(web::get().to(region::list_regions)))
+                        .service(web::resource("regions/ping").route(web::get().to(region::ping)))
+                        .service(web::resource("regions/health").route(web::get().to(region::get_region_health)))
+                        .service(web::resource("rooms").route(web::post().to(room::create_room)))
+                        .service(web::resource("rooms/allocate").route(web::post().to(region::allocate_room)))
+                        .service(web::resource("rooms/fleet").route(web::get().to(room::get_fleet_health)))
+                        .service(web::resource("rooms/fleet/drain").route(web::post().to(room::drain_fleet)))
+                        .service(web::resource("rooms/{id}/ws").route(web::get().to(room::room_socket)))
+                        .service(web::resource("rooms/{id}/spectate").route(web::get().to(room::room_spectate_socket)))
+                        .service(
+                            web::resource("matches")
+                                .route(web::get().to(turn::list_matches))
+                                .route(web::post().to(turn::create_match)),
+                        )
+                        .service(web::resource("matches/events").route(web::get().to(turn::turn_events)))
+                        .service(web::resource("matches/ws").route(web::get().to(turn::turn_socket)))
+                        .service(web::resource("matches/{id}").route(web::get().to(turn::get_match)))
+                        .service(web::resource("matches/{id}/turn").route(web::post().to(turn::submit_turn)))
+                        .service(web::resource("matches/{id}/resign").route(web::post().to(turn::resign_match)))
+                        .service(web::resource("voice/party/ws").route(web::get().to(voice::party_voice_socket)))
+                        .service(web::resource("voice/rooms/{id}/ws").route(web::get().to(voice::room_voice_socket)))
+                        .service(web::resource("voice/mutes").route(web::get().to(voice::get_voice_mutes)))
+                        .service(
+                            web::resource("voice/mutes/{user}")
+                                .route(web::put().to(voice::mute_user))
+                                .route(web::delete().to(voice::unmute_user)),
+                        ),
+                ),
         );
     }
 }
@@ -0,0 +1,34 @@
+use shine_core::kernel::response::APIError;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum LobbyError {
+    BadRequest(String),
+    LobbyNotFound,
+    LobbyFull,
+    AlreadyInLobby,
+    NotInLobby,
+    /// The skill of the player is out of the skill band of the lobby
+    SkillOutOfBand,
+    /// Only the owner can start the game
+    NotOwner,
+    AlreadyStarted,
+}
+
+impl fmt::Display for LobbyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl From<LobbyError> for APIError {
+    fn from(err: LobbyError) -> APIError {
+        match err {
+            LobbyError::BadRequest(msg) => APIError::BadRequest(msg),
+            LobbyError::LobbyNotFound => APIError::RespourceNotFound("Lobby not found".to_owned()),
+            LobbyError::NotInLobby => APIError::BadRequest("Not in a lobby".to_owned()),
+            LobbyError::NotOwner => APIError::Forbidden,
+            err => APIError::Conflict(format!("{:?}", err)),
+        }
+    }
+}
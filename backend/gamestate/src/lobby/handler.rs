@@ -0,0 +1,121 @@
+use crate::{
+    lobby::{Lobby, LobbyError, LobbySocket, MatchRules},
+    State,
+};
+use actix_web::{web, Error as ActixError, HttpRequest, HttpResponse};
+use actix_web_actors::ws;
+use serde::{Deserialize, Serialize};
+use shine_core::{kernel::response::APIResult, requestinfo::TokenIdentity};
+
+/// Skill of the player. It is provided by the client until the ratings are tracked by the service.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JoinParams {
+    #[serde(default)]
+    pub skill: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateLobbyParams {
+    #[serde(default)]
+    pub skill: u32,
+    #[serde(default)]
+    pub rules: Option<MatchRules>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StartLobbyParams {
+    pub simulation: String,
+}
+
+/// Fill the number of spectators from the game room of the lobby
+fn with_spectators(state: &State, mut lobby: Lobby) -> Lobby {
+    if let Some(room_id) = &lobby.room_id {
+        lobby.spectators = state.rooms().spectator_count(room_id);
+    }
+    lobby
+}
+
+pub async fn create_lobby(
+    state: web::Data<State>,
+    identity: TokenIdentity,
+    params: web::Json<CreateLobbyParams>,
+) -> APIResult {
+    let params = params.into_inner();
+    let lobby = state
+        .lobbies()
+        .create_lobby(&identity.user_id, params.skill, params.rules)?;
+    Ok(HttpResponse::Ok().json(lobby))
+}
+
+pub async fn list_lobbies(state: web::Data<State>, _identity: TokenIdentity) -> APIResult {
+    let lobbies: Vec<_> = state
+        .lobbies()
+        .list_lobbies()
+        .into_iter()
+        .map(|lobby| with_spectators(&state, lobby))
+        .collect();
+    Ok(HttpResponse::Ok().json(lobbies))
+}
+
+pub async fn get_lobby(state: web::Data<State>, _identity: TokenIdentity, lobby_id: web::Path<String>) -> APIResult {
+    let lobby = state.lobbies().get_lobby(&lobby_id)?;
+    Ok(HttpResponse::Ok().json(with_spectators(&state, lobby)))
+}
+
+/// Start the game of a lobby in a new room, the players and spectators connect to the room of the lobby
+pub async fn start_lobby(
+    state: web::Data<State>,
+    identity: TokenIdentity,
+    lobby_id: web::Path<String>,
+    params: web::Json<StartLobbyParams>,
+) -> APIResult {
+    let lobby = state.lobbies().get_lobby(&lobby_id)?;
+    if lobby.owner_id != identity.user_id {
+        return Err(LobbyError::NotOwner.into());
+    }
+    if lobby.room_id.is_some() {
+        return Err(LobbyError::AlreadyStarted.into());
+    }
+
+    let room = state.allocator().allocate(&params.simulation).await?;
+    let lobby = state
+        .lobbies()
+        .attach_room(&lobby_id, &identity.user_id, &room.room_id, room.endpoint.as_deref())?;
+    Ok(HttpResponse::Ok().json(lobby))
+}
+
+pub async fn join_lobby(
+    state: web::Data<State>,
+    identity: TokenIdentity,
+    lobby_id: web::Path<String>,
+    params: web::Json<JoinParams>,
+) -> APIResult {
+    let lobby = state.lobbies().join_lobby(&lobby_id, &identity.user_id, params.skill)?;
+    Ok(HttpResponse::Ok().json(lobby))
+}
+
+pub async fn leave_lobby(state: web::Data<State>, identity: TokenIdentity) -> APIResult {
+    state.lobbies().leave_lobby(&identity.user_id)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Join the best matching lobby or create a new one
+pub async fn matchmake(state: web::Data<State>, identity: TokenIdentity, params: web::Json<JoinParams>) -> APIResult {
+    let lobby = state.lobbies().matchmake(&identity.user_id, params.skill)?;
+    Ok(HttpResponse::Ok().json(lobby))
+}
+
+/// Stream the changes of a lobby over a websocket
+pub async fn lobby_socket(
+    req: HttpRequest,
+    stream: web::Payload,
+    state: web::Data<State>,
+    _identity: TokenIdentity,
+    lobby_id: web::Path<String>,
+) -> Result<HttpResponse, ActixError> {
+    ws::start(
+        LobbySocket::new(lobby_id.into_inner(), state.lobbies().clone()),
+        &req,
+        stream,
+    )
+}
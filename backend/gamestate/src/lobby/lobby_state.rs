@@ -0,0 +1,231 @@
+use actix::Message;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Matchmaking rules of a lobby
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchRules {
+    /// Number of players required to start the game
+    pub min_players: usize,
+    pub max_players: usize,
+    /// Maximum difference of the skill of a joining player from the average skill of the lobby
+    #[serde(default)]
+    pub skill_band: Option<u32>,
+    /// Number of the teams, the players are distributed evenly and the members of a party are kept together
+    #[serde(default = "MatchRules::default_teams")]
+    pub teams: usize,
+}
+
+impl MatchRules {
+    fn default_teams() -> usize {
+        1
+    }
+}
+
+impl Default for MatchRules {
+    fn default() -> MatchRules {
+        MatchRules {
+            min_players: 2,
+            max_players: 8,
+            skill_band: Some(200),
+            teams: MatchRules::default_teams(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LobbyPlayer {
+    pub user_id: String,
+    pub skill: u32,
+    pub joined: DateTime<Utc>,
+    #[serde(default)]
+    pub team: usize,
+    /// The party the player joined with
+    #[serde(default)]
+    pub party_id: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LobbyStatus {
+    /// Waiting for more players
+    Open,
+    /// The minimum number of players has joined, more players may still join
+    Ready,
+    Full,
+}
+
+/// A group of players waiting for a game. Lobbies are kept in memory, the first player is the owner.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Lobby {
+    pub id: String,
+    pub owner_id: String,
+    pub rules: MatchRules,
+    pub players: Vec<LobbyPlayer>,
+    pub status: LobbyStatus,
+    pub created: DateTime<Utc>,
+    pub last_activity: DateTime<Utc>,
+    /// The game room of the lobby once the game has started
+    #[serde(default)]
+    pub room_id: Option<String>,
+    /// Root of the api of the game server hosting the room, None if the room is hosted by this service
+    #[serde(default)]
+    pub room_endpoint: Option<String>,
+    /// Number of the spectators of the game room, filled when the lobby is queried
+    #[serde(default)]
+    pub spectators: usize,
+}
+
+impl Lobby {
+    pub(crate) fn new(id: String, owner_id: &str, skill: u32, rules: MatchRules) -> Lobby {
+        let mut lobby = Lobby::empty(id, owner_id, rules);
+        lobby.add_player(owner_id, skill);
+        lobby
+    }
+
+    /// Create a lobby for a party, the first member is the owner
+    pub(crate) fn new_party(id: String, party_id: &str, members: &[String], skill: u32, rules: MatchRules) -> Lobby {
+        let mut lobby = Lobby::empty(id, &members[0], rules);
+        lobby.add_party(party_id, members, skill);
+        lobby
+    }
+
+    fn empty(id: String, owner_id: &str, rules: MatchRules) -> Lobby {
+        let now = Utc::now();
+        Lobby {
+            id,
+            owner_id: owner_id.to_owned(),
+            rules,
+            players: Vec::new(),
+            status: LobbyStatus::Open,
+            created: now,
+            last_activity: now,
+            room_id: None,
+            room_endpoint: None,
+            spectators: 0,
+        }
+    }
+
+    pub fn average_skill(&self) -> u32 {
+        if self.players.is_empty() {
+            0
+        } else {
+            let total: u64 = self.players.iter().map(|p| p.skill as u64).sum();
+            (total / self.players.len() as u64) as u32
+        }
+    }
+
+    /// Check if a player with the given skill fits into the skill band of the lobby
+    pub fn accepts_skill(&self, skill: u32) -> bool {
+        match self.rules.skill_band {
+            Some(band) => (self.average_skill() as i64 - skill as i64).abs() <= band as i64,
+            None => true,
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.players.len() >= self.rules.max_players
+    }
+
+    /// Maximum number of the players in a team
+    pub fn team_size(&self) -> usize {
+        let teams = self.rules.teams.max(1);
+        (self.rules.max_players + teams - 1) / teams
+    }
+
+    pub fn team_count(&self, team: usize) -> usize {
+        self.players.iter().filter(|p| p.team == team).count()
+    }
+
+    /// Find the least populated team with room for the given number of players
+    pub fn free_team(&self, count: usize) -> Option<usize> {
+        if self.players.len() + count > self.rules.max_players {
+            return None;
+        }
+        let team_size = self.team_size();
+        (0..self.rules.teams.max(1))
+            .map(|team| (team, self.team_count(team)))
+            .filter(|(_, players)| players + count <= team_size)
+            .min_by_key(|(_, players)| *players)
+            .map(|(team, _)| team)
+    }
+
+    pub fn contains(&self, user_id: &str) -> bool {
+        self.players.iter().any(|p| p.user_id == user_id)
+    }
+
+    fn update_status(&mut self) {
+        self.status = if self.is_full() {
+            LobbyStatus::Full
+        } else if self.players.len() >= self.rules.min_players {
+            LobbyStatus::Ready
+        } else {
+            LobbyStatus::Open
+        };
+        self.last_activity = Utc::now();
+    }
+
+    pub(crate) fn add_player(&mut self, user_id: &str, skill: u32) {
+        let team = self.free_team(1).unwrap_or(0);
+        self.players.push(LobbyPlayer {
+            user_id: user_id.to_owned(),
+            skill,
+            joined: Utc::now(),
+            team,
+            party_id: None,
+        });
+        self.update_status();
+    }
+
+    /// Add the members of a party to the same team, the caller has to check the room with free_team
+    pub(crate) fn add_party(&mut self, party_id: &str, members: &[String], skill: u32) {
+        let team = self.free_team(members.len()).unwrap_or(0);
+        let now = Utc::now();
+        for user_id in members {
+            self.players.push(LobbyPlayer {
+                user_id: user_id.clone(),
+                skill,
+                joined: now,
+                team,
+                party_id: Some(party_id.to_owned()),
+            });
+        }
+        self.update_status();
+    }
+
+    /// Remove a player and pass the ownership to the longest waiting player if the owner left.
+    pub(crate) fn remove_player(&mut self, user_id: &str) {
+        self.players.retain(|p| p.user_id != user_id);
+        if self.owner_id == user_id {
+            if let Some(player) = self.players.first() {
+                self.owner_id = player.user_id.clone();
+            }
+        }
+        self.update_status();
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum LobbyCloseReason {
+    /// All the players left
+    Empty,
+    /// No activity within the idle timeout
+    Expired,
+}
+
+/// Change of a lobby broadcasted to the subscribers
+#[derive(Clone, Debug, Serialize, Deserialize, Message)]
+#[serde(tag = "type", rename_all = "camelCase")]
+#[rtype(result = "()")]
+pub enum LobbyUpdate {
+    Updated {
+        lobby: Lobby,
+    },
+    #[serde(rename_all = "camelCase")]
+    Closed {
+        lobby_id: String,
+        reason: LobbyCloseReason,
+    },
+}
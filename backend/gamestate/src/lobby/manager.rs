@@ -0,0 +1,345 @@
+use crate::lobby::{Lobby, LobbyCloseReason, LobbyError, LobbyUpdate, MatchRules};
+use actix::Recipient;
+use chrono::{Duration, Utc};
+use rand::{self, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+const LOBBY_ID_LEN: usize = 12;
+const LOBBY_ID_ABC: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LobbyConfig {
+    /// Rules of the lobbies created by the matchmaking
+    #[serde(default)]
+    pub default_rules: MatchRules,
+    /// Upper limit of the players of the lobbies created by the users
+    pub max_players: usize,
+    /// Lobbies without activity are closed after this period
+    pub idle_timeout_s: u32,
+}
+
+impl Default for LobbyConfig {
+    fn default() -> LobbyConfig {
+        LobbyConfig {
+            default_rules: MatchRules::default(),
+            max_players: 16,
+            idle_timeout_s: 300,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    lobbies: HashMap<String, Lobby>,
+    /// The lobby of the players
+    player_lobby: HashMap<String, String>,
+    subscribers: HashMap<String, Vec<Recipient<LobbyUpdate>>>,
+}
+
+impl Inner {
+    fn broadcast(&mut self, lobby_id: &str, update: LobbyUpdate) {
+        if let Some(subscribers) = self.subscribers.get_mut(lobby_id) {
+            // drop the disconnected subscribers
+            subscribers.retain(|s| s.do_send(update.clone()).is_ok());
+        }
+    }
+
+    fn close(&mut self, lobby_id: &str, reason: LobbyCloseReason) {
+        if let Some(lobby) = self.lobbies.remove(lobby_id) {
+            log::info!("Lobby {} closed: {:?}", lobby_id, reason);
+            for player in &lobby.players {
+                self.player_lobby.remove(&player.user_id);
+            }
+            self.broadcast(
+                lobby_id,
+                LobbyUpdate::Closed {
+                    lobby_id: lobby_id.to_owned(),
+                    reason,
+                },
+            );
+            self.subscribers.remove(lobby_id);
+        }
+    }
+
+    fn purge_expired(&mut self, idle_timeout: Duration) {
+        let limit = Utc::now() - idle_timeout;
+        let expired: Vec<_> = self
+            .lobbies
+            .values()
+            .filter(|lobby| lobby.last_activity < limit)
+            .map(|lobby| lobby.id.clone())
+            .collect();
+        for lobby_id in expired {
+            self.close(&lobby_id, LobbyCloseReason::Expired);
+        }
+    }
+
+    fn join(&mut self, lobby_id: &str, user_id: &str, skill: u32) -> Result<Lobby, LobbyError> {
+        if self.player_lobby.contains_key(user_id) {
+            return Err(LobbyError::AlreadyInLobby);
+        }
+        let lobby = self.lobbies.get_mut(lobby_id).ok_or(LobbyError::LobbyNotFound)?;
+        if lobby.is_full() {
+            return Err(LobbyError::LobbyFull);
+        }
+        if !lobby.accepts_skill(skill) {
+            return Err(LobbyError::SkillOutOfBand);
+        }
+        lobby.add_player(user_id, skill);
+        let lobby = lobby.clone();
+        self.player_lobby.insert(user_id.to_owned(), lobby_id.to_owned());
+        log::info!("Player {} joined lobby {}", user_id, lobby_id);
+        self.broadcast(lobby_id, LobbyUpdate::Updated { lobby: lobby.clone() });
+        Ok(lobby)
+    }
+
+    fn join_party(
+        &mut self,
+        lobby_id: &str,
+        party_id: &str,
+        members: &[String],
+        skill: u32,
+    ) -> Result<Lobby, LobbyError> {
+        if members.iter().any(|user_id| self.player_lobby.contains_key(user_id)) {
+            return Err(LobbyError::AlreadyInLobby);
+        }
+        let lobby = self.lobbies.get_mut(lobby_id).ok_or(LobbyError::LobbyNotFound)?;
+        if lobby.free_team(members.len()).is_none() {
+            return Err(LobbyError::LobbyFull);
+        }
+        if !lobby.accepts_skill(skill) {
+            return Err(LobbyError::SkillOutOfBand);
+        }
+        lobby.add_party(party_id, members, skill);
+        let lobby = lobby.clone();
+        for user_id in members {
+            self.player_lobby.insert(user_id.clone(), lobby_id.to_owned());
+        }
+        log::info!("Party {} joined lobby {}", party_id, lobby_id);
+        self.broadcast(lobby_id, LobbyUpdate::Updated { lobby: lobby.clone() });
+        Ok(lobby)
+    }
+}
+
+/// Manage the lobbies and the matchmaking. The lobbies are transient, they are kept in the memory and shared
+/// by the workers. The changes are broadcasted to the subscribers (websocket connections).
+#[derive(Clone)]
+pub struct LobbyManager {
+    config: Arc<LobbyConfig>,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl LobbyManager {
+    pub fn new(config: &LobbyConfig) -> LobbyManager {
+        LobbyManager {
+            config: Arc::new(config.clone()),
+            inner: Arc::new(Mutex::new(Inner::default())),
+        }
+    }
+
+    fn with_inner<T, F: FnOnce(&mut Inner) -> T>(&self, f: F) -> T {
+        let mut inner = self.inner.lock().unwrap();
+        inner.purge_expired(Duration::seconds(self.config.idle_timeout_s as i64));
+        f(&mut inner)
+    }
+
+    fn generate_lobby_id(&self) -> String {
+        let mut rng = rand::thread_rng();
+        String::from_utf8(
+            (0..LOBBY_ID_LEN)
+                .map(|_| *LOBBY_ID_ABC.choose(&mut rng).unwrap())
+                .collect::<Vec<_>>(),
+        )
+        .unwrap()
+    }
+
+    fn validate_rules(&self, rules: &MatchRules) -> Result<(), LobbyError> {
+        if rules.min_players == 0 || rules.min_players > rules.max_players {
+            Err(LobbyError::BadRequest("Invalid player count".to_owned()))
+        } else if rules.teams == 0 || rules.teams > rules.max_players {
+            Err(LobbyError::BadRequest("Invalid team count".to_owned()))
+        } else if rules.max_players > self.config.max_players {
+            Err(LobbyError::BadRequest(format!(
+                "Player count is limited to {}",
+                self.config.max_players
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Create a new lobby owned by the given user
+    pub fn create_lobby(&self, user_id: &str, skill: u32, rules: Option<MatchRules>) -> Result<Lobby, LobbyError> {
+        let rules = rules.unwrap_or_else(|| self.config.default_rules.clone());
+        self.validate_rules(&rules)?;
+        let lobby_id = self.generate_lobby_id();
+        self.with_inner(|inner| {
+            if inner.player_lobby.contains_key(user_id) {
+                return Err(LobbyError::AlreadyInLobby);
+            }
+            let lobby = Lobby::new(lobby_id.clone(), user_id, skill, rules);
+            inner.player_lobby.insert(user_id.to_owned(), lobby_id.clone());
+            inner.lobbies.insert(lobby_id.clone(), lobby.clone());
+            log::info!("Lobby {} created by {}", lobby_id, user_id);
+            Ok(lobby)
+        })
+    }
+
+    pub fn join_lobby(&self, lobby_id: &str, user_id: &str, skill: u32) -> Result<Lobby, LobbyError> {
+        self.with_inner(|inner| inner.join(lobby_id, user_id, skill))
+    }
+
+    /// Leave the current lobby of the user. Empty lobbies are closed.
+    pub fn leave_lobby(&self, user_id: &str) -> Result<(), LobbyError> {
+        self.with_inner(|inner| {
+            let lobby_id = inner.player_lobby.remove(user_id).ok_or(LobbyError::NotInLobby)?;
+            log::info!("Player {} left lobby {}", user_id, lobby_id);
+            let lobby = match inner.lobbies.get_mut(&lobby_id) {
+                Some(lobby) => {
+                    lobby.remove_player(user_id);
+                    lobby.clone()
+                }
+                None => return Ok(()),
+            };
+            if lobby.players.is_empty() {
+                inner.close(&lobby_id, LobbyCloseReason::Empty);
+            } else {
+                inner.broadcast(&lobby_id, LobbyUpdate::Updated { lobby });
+            }
+            Ok(())
+        })
+    }
+
+    /// Link the game room to the lobby, only the owner can start the game of a lobby
+    pub fn attach_room(
+        &self,
+        lobby_id: &str,
+        user_id: &str,
+        room_id: &str,
+        room_endpoint: Option<&str>,
+    ) -> Result<Lobby, LobbyError> {
+        self.with_inner(|inner| {
+            let lobby = inner.lobbies.get_mut(lobby_id).ok_or(LobbyError::LobbyNotFound)?;
+            if lobby.owner_id != user_id {
+                return Err(LobbyError::NotOwner);
+            }
+            if lobby.room_id.is_some() {
+                return Err(LobbyError::AlreadyStarted);
+            }
+            lobby.room_id = Some(room_id.to_owned());
+            lobby.room_endpoint = room_endpoint.map(|endpoint| endpoint.to_owned());
+            lobby.last_activity = Utc::now();
+            let lobby = lobby.clone();
+            log::info!("Lobby {} started in room {}", lobby_id, room_id);
+            inner.broadcast(lobby_id, LobbyUpdate::Updated { lobby: lobby.clone() });
+            Ok(lobby)
+        })
+    }
+
+    pub fn get_lobby(&self, lobby_id: &str) -> Result<Lobby, LobbyError> {
+        self.with_inner(|inner| inner.lobbies.get(lobby_id).cloned().ok_or(LobbyError::LobbyNotFound))
+    }
+
+    /// List the lobbies that can be joined
+    pub fn list_lobbies(&self) -> Vec<Lobby> {
+        self.with_inner(|inner| {
+            let mut lobbies: Vec<_> = inner.lobbies.values().filter(|l| !l.is_full()).cloned().collect();
+            lobbies.sort_by(|a, b| a.created.cmp(&b.created));
+            lobbies
+        })
+    }
+
+    /// Find the best matching open lobby (closest average skill, most players) or create a new one
+    /// using the default rules.
+    pub fn matchmake(&self, user_id: &str, skill: u32) -> Result<Lobby, LobbyError> {
+        let candidate = self.with_inner(|inner| {
+            inner
+                .lobbies
+                .values()
+                .filter(|lobby| !lobby.is_full() && lobby.accepts_skill(skill))
+                .min_by_key(|lobby| {
+                    let distance = (lobby.average_skill() as i64 - skill as i64).abs();
+                    (distance, -(lobby.players.len() as i64))
+                })
+                .map(|lobby| lobby.id.clone())
+        });
+
+        match candidate {
+            Some(lobby_id) => match self.join_lobby(&lobby_id, user_id, skill) {
+                // the lobby changed in the meantime, try again
+                Err(LobbyError::LobbyFull) | Err(LobbyError::LobbyNotFound) | Err(LobbyError::SkillOutOfBand) => {
+                    self.matchmake(user_id, skill)
+                }
+                result => result,
+            },
+            None => self.create_lobby(user_id, skill, None),
+        }
+    }
+
+    /// Find the best matching lobby with a team that has room for all the members of a party or create a
+    /// new one using the default rules. The members are placed into the same team.
+    pub fn matchmake_party(&self, party_id: &str, members: &[String], skill: u32) -> Result<Lobby, LobbyError> {
+        if members.is_empty() {
+            return Err(LobbyError::BadRequest("Empty party".to_owned()));
+        }
+        let candidate = self.with_inner(|inner| {
+            inner
+                .lobbies
+                .values()
+                .filter(|lobby| {
+                    lobby.room_id.is_none() && lobby.free_team(members.len()).is_some() && lobby.accepts_skill(skill)
+                })
+                .min_by_key(|lobby| {
+                    let distance = (lobby.average_skill() as i64 - skill as i64).abs();
+                    (distance, -(lobby.players.len() as i64))
+                })
+                .map(|lobby| lobby.id.clone())
+        });
+
+        match candidate {
+            Some(lobby_id) => match self.with_inner(|inner| inner.join_party(&lobby_id, party_id, members, skill)) {
+                // the lobby changed in the meantime, try again
+                Err(LobbyError::LobbyFull) | Err(LobbyError::LobbyNotFound) | Err(LobbyError::SkillOutOfBand) => {
+                    self.matchmake_party(party_id, members, skill)
+                }
+                result => result,
+            },
+            None => {
+                let rules = self.config.default_rules.clone();
+                let lobby_id = self.generate_lobby_id();
+                self.with_inner(|inner| {
+                    if members.iter().any(|user_id| inner.player_lobby.contains_key(user_id)) {
+                        return Err(LobbyError::AlreadyInLobby);
+                    }
+                    let lobby = Lobby::new_party(lobby_id.clone(), party_id, members, skill, rules);
+                    if lobby.players.len() > lobby.team_size() {
+                        return Err(LobbyError::LobbyFull);
+                    }
+                    for user_id in members {
+                        inner.player_lobby.insert(user_id.clone(), lobby_id.clone());
+                    }
+                    inner.lobbies.insert(lobby_id.clone(), lobby.clone());
+                    log::info!("Lobby {} created for party {}", lobby_id, party_id);
+                    Ok(lobby)
+                })
+            }
+        }
+    }
+
+    /// Register a recipient for the changes of a lobby, the current state of the lobby is returned.
+    pub fn subscribe(&self, lobby_id: &str, recipient: Recipient<LobbyUpdate>) -> Result<Lobby, LobbyError> {
+        self.with_inner(|inner| {
+            let lobby = inner.lobbies.get(lobby_id).cloned().ok_or(LobbyError::LobbyNotFound)?;
+            inner
+                .subscribers
+                .entry(lobby_id.to_owned())
+                .or_default()
+                .push(recipient);
+            Ok(lobby)
+        })
+    }
+}
@@ -0,0 +1,11 @@
+mod error;
+mod handler;
+mod lobby_state;
+mod manager;
+mod socket;
+
+pub use self::error::*;
+pub use self::handler::*;
+pub use self::lobby_state::*;
+pub use self::manager::*;
+pub use self::socket::*;
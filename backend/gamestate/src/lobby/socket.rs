@@ -0,0 +1,66 @@
+use crate::lobby::{LobbyManager, LobbyUpdate};
+use actix::{Actor, ActorContext, AsyncContext, Handler, StreamHandler};
+use actix_web_actors::ws;
+
+/// Websocket connection streaming the changes of a lobby
+pub struct LobbySocket {
+    lobby_id: String,
+    manager: LobbyManager,
+}
+
+impl LobbySocket {
+    pub fn new(lobby_id: String, manager: LobbyManager) -> LobbySocket {
+        LobbySocket { lobby_id, manager }
+    }
+
+    fn send(&self, update: &LobbyUpdate, ctx: &mut ws::WebsocketContext<Self>) {
+        match serde_json::to_string(update) {
+            Ok(text) => ctx.text(text),
+            Err(err) => log::warn!("Failed to serialize lobby update: {:?}", err),
+        }
+    }
+}
+
+impl Actor for LobbySocket {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        match self.manager.subscribe(&self.lobby_id, ctx.address().recipient()) {
+            Ok(lobby) => self.send(&LobbyUpdate::Updated { lobby }, ctx),
+            Err(err) => {
+                log::info!("Lobby {} subscription failed: {}", self.lobby_id, err);
+                ctx.close(None);
+                ctx.stop();
+            }
+        }
+    }
+}
+
+impl Handler<LobbyUpdate> for LobbySocket {
+    type Result = ();
+
+    fn handle(&mut self, update: LobbyUpdate, ctx: &mut Self::Context) {
+        self.send(&update, ctx);
+        if let LobbyUpdate::Closed { .. } = update {
+            ctx.close(None);
+            ctx.stop();
+        }
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for LobbySocket {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Ping(msg)) => ctx.pong(&msg),
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            Ok(_) => {}
+            Err(err) => {
+                log::info!("Lobby {} socket error: {:?}", self.lobby_id, err);
+                ctx.stop();
+            }
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use shine_core::kernel::response::APIError;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum PartyError {
+    BadRequest(String),
+    PartyNotFound,
+    AlreadyInParty,
+    NotInParty,
+    /// Only the leader can invite, kick and start the matchmaking
+    NotLeader,
+    NotInvited,
+    PartyFull,
+    /// One of the users blocked the other
+    Blocked,
+    /// Only the friends of the leader can be invited
+    NotFriend,
+}
+
+impl fmt::Display for PartyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl From<PartyError> for APIError {
+    fn from(err: PartyError) -> APIError {
+        match err {
+            PartyError::BadRequest(msg) => APIError::BadRequest(msg),
+            PartyError::PartyNotFound => APIError::RespourceNotFound("Party not found".to_owned()),
+            PartyError::NotInParty => APIError::BadRequest("Not in a party".to_owned()),
+            PartyError::NotLeader | PartyError::NotInvited | PartyError::Blocked | PartyError::NotFriend => {
+                APIError::Forbidden
+            }
+            err => APIError::Conflict(format!("{:?}", err)),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use crate::{party::PartyUpdate, State};
+use actix_web::{http::header, web, web::Bytes, Error as ActixError, HttpResponse};
+use futures::{future, stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use shine_core::{kernel::response::APIResult, requestinfo::TokenIdentity};
+use std::time::Duration;
+
+/// Period of the comments sent on the event stream to keep the connection alive
+const EVENTS_KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatParams {
+    pub text: String,
+}
+
+/// Skill of the party used for the matchmaking. It is provided by the client until the ratings are
+/// tracked by the service.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PartyMatchParams {
+    #[serde(default)]
+    pub skill: u32,
+}
+
+pub async fn create_party(state: web::Data<State>, identity: TokenIdentity) -> APIResult {
+    let party = state.parties().create_party(&identity.user_id)?;
+    Ok(HttpResponse::Ok().json(party))
+}
+
+pub async fn get_party(state: web::Data<State>, identity: TokenIdentity) -> APIResult {
+    let party = state.parties().get_party(&identity.user_id)?;
+    Ok(HttpResponse::Ok().json(party))
+}
+
+pub async fn leave_party(state: web::Data<State>, identity: TokenIdentity) -> APIResult {
+    state.parties().leave(&identity.user_id)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn invite_to_party(state: web::Data<State>, identity: TokenIdentity, user: web::Path<String>) -> APIResult {
+    let party = state.parties().invite(&identity.user_id, &user)?;
+    Ok(HttpResponse::Ok().json(party))
+}
+
+pub async fn join_party(state: web::Data<State>, identity: TokenIdentity, party_id: web::Path<String>) -> APIResult {
+    let party = state.parties().join(&party_id, &identity.user_id)?;
+    Ok(HttpResponse::Ok().json(party))
+}
+
+pub async fn decline_party(state: web::Data<State>, identity: TokenIdentity, party_id: web::Path<String>) -> APIResult {
+    state.parties().decline(&party_id, &identity.user_id)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn kick_party_member(state: web::Data<State>, identity: TokenIdentity, user: web::Path<String>) -> APIResult {
+    let party = state.parties().kick(&identity.user_id, &user)?;
+    Ok(HttpResponse::Ok().json(party))
+}
+
+pub async fn promote_party_member(
+    state: web::Data<State>,
+    identity: TokenIdentity,
+    user: web::Path<String>,
+) -> APIResult {
+    let party = state.parties().promote(&identity.user_id, &user)?;
+    Ok(HttpResponse::Ok().json(party))
+}
+
+pub async fn party_chat(state: web::Data<State>, identity: TokenIdentity, params: web::Json<ChatParams>) -> APIResult {
+    state.parties().chat(&identity.user_id, &params.text)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Place the whole party into a lobby, the members are kept in the same team
+pub async fn matchmake_party(
+    state: web::Data<State>,
+    identity: TokenIdentity,
+    params: web::Json<PartyMatchParams>,
+) -> APIResult {
+    let party = state.parties().party_for_matchmaking(&identity.user_id)?;
+    let lobby = state
+        .lobbies()
+        .matchmake_party(&party.id, &party.member_ids(), params.skill)?;
+    state.parties().notify_matched(&party, &lobby.id);
+    Ok(HttpResponse::Ok().json(lobby))
+}
+
+/// Stream the party updates, invitations and chat messages of the user as server-sent events
+pub async fn party_events(state: web::Data<State>, identity: TokenIdentity) -> APIResult {
+    let updates = state.parties().subscribe(&identity.user_id).map(Some);
+    let keep_alive = actix_rt::time::interval(EVENTS_KEEP_ALIVE).map(|_| None);
+    let body = stream::select(updates, keep_alive).map(|update: Option<PartyUpdate>| {
+        let chunk = match update {
+            Some(update) => match serde_json::to_string(&update) {
+                Ok(data) => format!("event: {}\ndata: {}\n\n", update.event_name(), data),
+                Err(err) => {
+                    log::warn!("Failed to serialize party update: {:?}", err);
+                    String::new()
+                }
+            },
+            None => ":\n\n".to_owned(),
+        };
+        Ok::<_, ActixError>(Bytes::from(chunk))
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .header(header::CACHE_CONTROL, "no-cache")
+        .streaming(Box::pin(body)))
+}
+
+pub async fn get_relations(state: web::Data<State>, identity: TokenIdentity) -> APIResult {
+    Ok(HttpResponse::Ok().json(state.parties().get_relations(&identity.user_id)))
+}
+
+pub async fn add_friend(state: web::Data<State>, identity: TokenIdentity, user: web::Path<String>) -> APIResult {
+    state.parties().add_friend(&identity.user_id, &user)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn remove_friend(state: web::Data<State>, identity: TokenIdentity, user: web::Path<String>) -> APIResult {
+    state.parties().remove_friend(&identity.user_id, &user);
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn block_user(state: web::Data<State>, identity: TokenIdentity, user: web::Path<String>) -> APIResult {
+    state.parties().block(&identity.user_id, &user)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn unblock_user(state: web::Data<State>, identity: TokenIdentity, user: web::Path<String>) -> APIResult {
+    state.parties().unblock(&identity.user_id, &user);
+    Ok(HttpResponse::Ok().finish())
+}
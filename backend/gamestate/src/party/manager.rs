@@ -0,0 +1,382 @@
+use crate::party::{Party, PartyError, PartyUpdate, Relations};
+use chrono::Utc;
+use rand::{self, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+use shine_core::pubsub::{PubSub, Subscription};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+const PARTY_ID_LEN: usize = 12;
+const PARTY_ID_ABC: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyConfig {
+    pub max_members: usize,
+    /// Maximum length of a chat message in characters
+    pub max_chat_length: usize,
+    /// Only the friends of the leader can be invited
+    #[serde(default)]
+    pub friends_only: bool,
+}
+
+impl Default for PartyConfig {
+    fn default() -> PartyConfig {
+        PartyConfig {
+            max_members: 4,
+            max_chat_length: 256,
+            friends_only: false,
+        }
+    }
+}
+
+/// The friend and block lists of a user
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RelationList {
+    pub friends: Vec<String>,
+    pub blocked: Vec<String>,
+}
+
+#[derive(Default)]
+struct Inner {
+    parties: HashMap<String, Party>,
+    /// The party of the members
+    member_party: HashMap<String, String>,
+    relations: Relations,
+}
+
+impl Inner {
+    fn party_of(&self, user_id: &str) -> Result<&Party, PartyError> {
+        let party_id = self.member_party.get(user_id).ok_or(PartyError::NotInParty)?;
+        self.parties.get(party_id).ok_or(PartyError::PartyNotFound)
+    }
+
+    fn party_of_mut(&mut self, user_id: &str) -> Result<&mut Party, PartyError> {
+        let party_id = self.member_party.get(user_id).ok_or(PartyError::NotInParty)?;
+        self.parties.get_mut(party_id).ok_or(PartyError::PartyNotFound)
+    }
+
+    fn leader_party_mut(&mut self, user_id: &str) -> Result<&mut Party, PartyError> {
+        let party = self.party_of_mut(user_id)?;
+        if party.leader_id != user_id {
+            return Err(PartyError::NotLeader);
+        }
+        Ok(party)
+    }
+}
+
+/// Manage the parties and the friend and block lists. The parties are kept in the memory and shared by the
+/// workers, the changes are pushed to the members through the notification bus.
+#[derive(Clone)]
+pub struct PartyManager {
+    config: Arc<PartyConfig>,
+    inner: Arc<Mutex<Inner>>,
+    notifications: PubSub<PartyUpdate>,
+}
+
+impl PartyManager {
+    pub fn new(config: &PartyConfig) -> PartyManager {
+        PartyManager {
+            config: Arc::new(config.clone()),
+            inner: Arc::new(Mutex::new(Inner::default())),
+            notifications: PubSub::default(),
+        }
+    }
+
+    fn with_inner<T, F: FnOnce(&mut Inner) -> T>(&self, f: F) -> T {
+        let mut inner = self.inner.lock().unwrap();
+        f(&mut inner)
+    }
+
+    fn generate_party_id(&self) -> String {
+        let mut rng = rand::thread_rng();
+        String::from_utf8(
+            (0..PARTY_ID_LEN)
+                .map(|_| *PARTY_ID_ABC.choose(&mut rng).unwrap())
+                .collect::<Vec<_>>(),
+        )
+        .unwrap()
+    }
+
+    fn notify(&self, user_id: &str, update: PartyUpdate) {
+        self.notifications.publish(user_id, update);
+    }
+
+    fn notify_members(&self, party: &Party, update: PartyUpdate) {
+        for member in &party.members {
+            self.notify(&member.user_id, update.clone());
+        }
+    }
+
+    /// Stream the party updates and the chat messages of a user
+    pub fn subscribe(&self, user_id: &str) -> Subscription<PartyUpdate> {
+        self.notifications.subscribe(user_id)
+    }
+
+    pub fn create_party(&self, user_id: &str) -> Result<Party, PartyError> {
+        let party_id = self.generate_party_id();
+        self.with_inner(|inner| {
+            if inner.member_party.contains_key(user_id) {
+                return Err(PartyError::AlreadyInParty);
+            }
+            let party = Party::new(party_id.clone(), user_id);
+            inner.member_party.insert(user_id.to_owned(), party_id.clone());
+            inner.parties.insert(party_id.clone(), party.clone());
+            log::info!("Party {} created by {}", party_id, user_id);
+            Ok(party)
+        })
+    }
+
+    pub fn get_party(&self, user_id: &str) -> Result<Party, PartyError> {
+        self.with_inner(|inner| inner.party_of(user_id).cloned())
+    }
+
+    /// Invite a user into the party of the leader
+    pub fn invite(&self, leader_id: &str, user_id: &str) -> Result<Party, PartyError> {
+        let party = self.with_inner(|inner| {
+            let friends_only = self.config.friends_only;
+            let max_members = self.config.max_members;
+            if inner.relations.is_blocked(leader_id, user_id) {
+                return Err(PartyError::Blocked);
+            }
+            if friends_only && !inner.relations.is_friend(leader_id, user_id) {
+                return Err(PartyError::NotFriend);
+            }
+            let party = inner.leader_party_mut(leader_id)?;
+            if party.contains(user_id) {
+                return Err(PartyError::AlreadyInParty);
+            }
+            if party.members.len() + party.invites.len() >= max_members {
+                return Err(PartyError::PartyFull);
+            }
+            if !party.is_invited(user_id) {
+                party.invites.push(user_id.to_owned());
+            }
+            Ok(party.clone())
+        })?;
+
+        log::info!("{} invited to party {}", user_id, party.id);
+        self.notify(
+            user_id,
+            PartyUpdate::Invited {
+                party_id: party.id.clone(),
+                from: leader_id.to_owned(),
+            },
+        );
+        self.notify_members(&party, PartyUpdate::Updated { party: party.clone() });
+        Ok(party)
+    }
+
+    /// Join a party the user was invited to
+    pub fn join(&self, party_id: &str, user_id: &str) -> Result<Party, PartyError> {
+        let party = self.with_inner(|inner| {
+            if inner.member_party.contains_key(user_id) {
+                return Err(PartyError::AlreadyInParty);
+            }
+            let party = inner.parties.get(party_id).ok_or(PartyError::PartyNotFound)?;
+            if !party.is_invited(user_id) {
+                return Err(PartyError::NotInvited);
+            }
+            if party
+                .members
+                .iter()
+                .any(|member| inner.relations.is_blocked(&member.user_id, user_id))
+            {
+                return Err(PartyError::Blocked);
+            }
+            if party.members.len() >= self.config.max_members {
+                return Err(PartyError::PartyFull);
+            }
+
+            let party = inner.parties.get_mut(party_id).unwrap();
+            party.add_member(user_id);
+            let party = party.clone();
+            inner.member_party.insert(user_id.to_owned(), party_id.to_owned());
+            Ok(party)
+        })?;
+
+        log::info!("{} joined party {}", user_id, party_id);
+        self.notify_members(&party, PartyUpdate::Updated { party: party.clone() });
+        Ok(party)
+    }
+
+    /// Decline an invitation
+    pub fn decline(&self, party_id: &str, user_id: &str) -> Result<(), PartyError> {
+        let party = self.with_inner(|inner| {
+            let party = inner.parties.get_mut(party_id).ok_or(PartyError::PartyNotFound)?;
+            if !party.is_invited(user_id) {
+                return Err(PartyError::NotInvited);
+            }
+            party.invites.retain(|u| u != user_id);
+            Ok(party.clone())
+        })?;
+        self.notify_members(&party, PartyUpdate::Updated { party: party.clone() });
+        Ok(())
+    }
+
+    fn remove_member(&self, party_id: &str, user_id: &str) -> Option<Party> {
+        self.with_inner(|inner| {
+            inner.member_party.remove(user_id);
+            let party = inner.parties.get_mut(party_id)?;
+            party.remove_member(user_id);
+            if party.members.is_empty() {
+                inner.parties.remove(party_id);
+                log::info!("Party {} disbanded", party_id);
+                None
+            } else {
+                Some(party.clone())
+            }
+        })
+    }
+
+    /// Leave the party, the leadership is passed to the longest standing member. Empty parties are disbanded.
+    pub fn leave(&self, user_id: &str) -> Result<(), PartyError> {
+        let party_id = self.with_inner(|inner| inner.party_of(user_id).map(|party| party.id.clone()))?;
+        log::info!("{} left party {}", user_id, party_id);
+        match self.remove_member(&party_id, user_id) {
+            Some(party) => self.notify_members(&party, PartyUpdate::Updated { party: party.clone() }),
+            None => self.notify(user_id, PartyUpdate::Disbanded { party_id }),
+        }
+        Ok(())
+    }
+
+    pub fn kick(&self, leader_id: &str, user_id: &str) -> Result<Party, PartyError> {
+        let party_id = self.with_inner(|inner| {
+            let party = inner.leader_party_mut(leader_id)?;
+            if leader_id == user_id || !party.contains(user_id) {
+                return Err(PartyError::BadRequest(format!("{} cannot be kicked", user_id)));
+            }
+            Ok(party.id.clone())
+        })?;
+
+        log::info!("{} kicked from party {}", user_id, party_id);
+        let party = self
+            .remove_member(&party_id, user_id)
+            .ok_or(PartyError::PartyNotFound)?;
+        self.notify(user_id, PartyUpdate::Kicked { party_id });
+        self.notify_members(&party, PartyUpdate::Updated { party: party.clone() });
+        Ok(party)
+    }
+
+    /// Pass the leadership to another member
+    pub fn promote(&self, leader_id: &str, user_id: &str) -> Result<Party, PartyError> {
+        let party = self.with_inner(|inner| {
+            let party = inner.leader_party_mut(leader_id)?;
+            if !party.contains(user_id) {
+                return Err(PartyError::NotInParty);
+            }
+            party.leader_id = user_id.to_owned();
+            Ok(party.clone())
+        })?;
+        self.notify_members(&party, PartyUpdate::Updated { party: party.clone() });
+        Ok(party)
+    }
+
+    /// Send a chat message to the party, members who blocked the sender don't receive it
+    pub fn chat(&self, user_id: &str, text: &str) -> Result<(), PartyError> {
+        let text = text.trim();
+        if text.is_empty() || text.chars().count() > self.config.max_chat_length {
+            return Err(PartyError::BadRequest("Invalid message length".to_owned()));
+        }
+
+        let (party_id, recipients) = self.with_inner(|inner| {
+            let party = inner.party_of(user_id)?;
+            let recipients: Vec<_> = party
+                .members
+                .iter()
+                .filter(|member| !inner.relations.has_blocked(&member.user_id, user_id))
+                .map(|member| member.user_id.clone())
+                .collect();
+            Ok::<_, PartyError>((party.id.clone(), recipients))
+        })?;
+
+        let update = PartyUpdate::Chat {
+            party_id,
+            from: user_id.to_owned(),
+            text: text.to_owned(),
+            time: Utc::now(),
+        };
+        for recipient in recipients {
+            self.notify(&recipient, update.clone());
+        }
+        Ok(())
+    }
+
+    /// Return the party of the leader for the matchmaking
+    pub fn party_for_matchmaking(&self, leader_id: &str) -> Result<Party, PartyError> {
+        self.with_inner(|inner| {
+            let party = inner.party_of(leader_id)?;
+            if party.leader_id != leader_id {
+                return Err(PartyError::NotLeader);
+            }
+            Ok(party.clone())
+        })
+    }
+
+    pub fn notify_matched(&self, party: &Party, lobby_id: &str) {
+        self.notify_members(
+            party,
+            PartyUpdate::Matched {
+                party_id: party.id.clone(),
+                lobby_id: lobby_id.to_owned(),
+            },
+        );
+    }
+
+    pub fn get_relations(&self, user_id: &str) -> RelationList {
+        self.with_inner(|inner| RelationList {
+            friends: inner.relations.friends_of(user_id),
+            blocked: inner.relations.blocked_by(user_id),
+        })
+    }
+
+    /// If any of the users has blocked the other
+    pub fn is_blocked(&self, user_id: &str, other_id: &str) -> bool {
+        self.with_inner(|inner| inner.relations.is_blocked(user_id, other_id))
+    }
+
+    /// If the users are mutual friends
+    pub fn is_friend(&self, user_id: &str, other_id: &str) -> bool {
+        self.with_inner(|inner| inner.relations.is_friend(user_id, other_id))
+    }
+
+    pub fn add_friend(&self, user_id: &str, friend_id: &str) -> Result<(), PartyError> {
+        if user_id == friend_id {
+            return Err(PartyError::BadRequest("Cannot add self as a friend".to_owned()));
+        }
+        self.with_inner(|inner| {
+            if inner.relations.is_blocked(user_id, friend_id) {
+                return Err(PartyError::Blocked);
+            }
+            inner.relations.add_friend(user_id, friend_id);
+            Ok(())
+        })
+    }
+
+    pub fn remove_friend(&self, user_id: &str, friend_id: &str) {
+        self.with_inner(|inner| inner.relations.remove_friend(user_id, friend_id));
+    }
+
+    /// Block a user, the pending invitations between the users are dropped
+    pub fn block(&self, user_id: &str, blocked_id: &str) -> Result<(), PartyError> {
+        if user_id == blocked_id {
+            return Err(PartyError::BadRequest("Cannot block self".to_owned()));
+        }
+        self.with_inner(|inner| {
+            inner.relations.block(user_id, blocked_id);
+            for party in inner.parties.values_mut() {
+                if party.leader_id == user_id {
+                    party.invites.retain(|u| u != blocked_id);
+                } else if party.leader_id == blocked_id {
+                    party.invites.retain(|u| u != user_id);
+                }
+            }
+        });
+        Ok(())
+    }
+
+    pub fn unblock(&self, user_id: &str, blocked_id: &str) {
+        self.with_inner(|inner| inner.relations.unblock(user_id, blocked_id));
+    }
+}
@@ -0,0 +1,11 @@
+mod error;
+mod handler;
+mod manager;
+mod party_state;
+mod relations;
+
+pub use self::error::*;
+pub use self::handler::*;
+pub use self::manager::*;
+pub use self::party_state::*;
+pub use self::relations::*;
@@ -0,0 +1,117 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PartyMember {
+    pub user_id: String,
+    pub joined: DateTime<Utc>,
+}
+
+/// A group of players matched together. Parties outlive the lobbies and the games, they are kept until
+/// the last member leaves.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Party {
+    pub id: String,
+    pub leader_id: String,
+    pub members: Vec<PartyMember>,
+    /// Users invited by the leader who have not joined yet
+    pub invites: Vec<String>,
+    pub created: DateTime<Utc>,
+}
+
+impl Party {
+    pub(crate) fn new(id: String, leader_id: &str) -> Party {
+        let now = Utc::now();
+        Party {
+            id,
+            leader_id: leader_id.to_owned(),
+            members: vec![PartyMember {
+                user_id: leader_id.to_owned(),
+                joined: now,
+            }],
+            invites: Vec::new(),
+            created: now,
+        }
+    }
+
+    pub fn contains(&self, user_id: &str) -> bool {
+        self.members.iter().any(|m| m.user_id == user_id)
+    }
+
+    pub fn is_invited(&self, user_id: &str) -> bool {
+        self.invites.iter().any(|u| u == user_id)
+    }
+
+    pub fn member_ids(&self) -> Vec<String> {
+        self.members.iter().map(|m| m.user_id.clone()).collect()
+    }
+
+    pub(crate) fn add_member(&mut self, user_id: &str) {
+        self.invites.retain(|u| u != user_id);
+        self.members.push(PartyMember {
+            user_id: user_id.to_owned(),
+            joined: Utc::now(),
+        });
+    }
+
+    /// Remove a member and pass the leadership to the longest standing member if the leader left.
+    pub(crate) fn remove_member(&mut self, user_id: &str) {
+        self.members.retain(|m| m.user_id != user_id);
+        if self.leader_id == user_id {
+            if let Some(member) = self.members.first() {
+                self.leader_id = member.user_id.clone();
+            }
+        }
+    }
+}
+
+/// Change of a party pushed to the members and the invited users
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum PartyUpdate {
+    Updated {
+        party: Party,
+    },
+    #[serde(rename_all = "camelCase")]
+    Invited {
+        party_id: String,
+        from: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Kicked {
+        party_id: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Disbanded {
+        party_id: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Chat {
+        party_id: String,
+        from: String,
+        text: String,
+        time: DateTime<Utc>,
+    },
+    /// The leader started the matchmaking and the party was placed into a lobby
+    #[serde(rename_all = "camelCase")]
+    Matched {
+        party_id: String,
+        lobby_id: String,
+    },
+}
+
+impl PartyUpdate {
+    /// Name of the update in the event stream
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            PartyUpdate::Updated { .. } => "updated",
+            PartyUpdate::Invited { .. } => "invited",
+            PartyUpdate::Kicked { .. } => "kicked",
+            PartyUpdate::Disbanded { .. } => "disbanded",
+            PartyUpdate::Chat { .. } => "chat",
+            PartyUpdate::Matched { .. } => "matched",
+        }
+    }
+}
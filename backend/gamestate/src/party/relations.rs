@@ -0,0 +1,76 @@
+use std::collections::{HashMap, HashSet};
+
+/// Friend and block lists of the users. A friendship is mutual, both users have to add the other,
+/// while a block applies in both directions.
+#[derive(Default, Debug)]
+pub struct Relations {
+    friends: HashMap<String, HashSet<String>>,
+    blocks: HashMap<String, HashSet<String>>,
+}
+
+impl Relations {
+    pub fn add_friend(&mut self, user_id: &str, friend_id: &str) {
+        self.friends
+            .entry(user_id.to_owned())
+            .or_default()
+            .insert(friend_id.to_owned());
+    }
+
+    pub fn remove_friend(&mut self, user_id: &str, friend_id: &str) {
+        if let Some(friends) = self.friends.get_mut(user_id) {
+            friends.remove(friend_id);
+        }
+    }
+
+    pub fn block(&mut self, user_id: &str, blocked_id: &str) {
+        self.blocks
+            .entry(user_id.to_owned())
+            .or_default()
+            .insert(blocked_id.to_owned());
+        self.remove_friend(user_id, blocked_id);
+    }
+
+    pub fn unblock(&mut self, user_id: &str, blocked_id: &str) {
+        if let Some(blocks) = self.blocks.get_mut(user_id) {
+            blocks.remove(blocked_id);
+        }
+    }
+
+    fn lists(map: &HashMap<String, HashSet<String>>, user_id: &str, other_id: &str) -> bool {
+        map.get(user_id).map(|set| set.contains(other_id)).unwrap_or(false)
+    }
+
+    pub fn is_friend(&self, user_id: &str, other_id: &str) -> bool {
+        Self::lists(&self.friends, user_id, other_id) && Self::lists(&self.friends, other_id, user_id)
+    }
+
+    /// Check if any of the users has blocked the other
+    pub fn is_blocked(&self, user_id: &str, other_id: &str) -> bool {
+        Self::lists(&self.blocks, user_id, other_id) || Self::lists(&self.blocks, other_id, user_id)
+    }
+
+    /// Check if the user has blocked the other user
+    pub fn has_blocked(&self, user_id: &str, other_id: &str) -> bool {
+        Self::lists(&self.blocks, user_id, other_id)
+    }
+
+    pub fn friends_of(&self, user_id: &str) -> Vec<String> {
+        let mut friends: Vec<_> = self
+            .friends
+            .get(user_id)
+            .map(|friends| friends.iter().filter(|f| self.is_friend(user_id, f)).cloned().collect())
+            .unwrap_or_default();
+        friends.sort();
+        friends
+    }
+
+    pub fn blocked_by(&self, user_id: &str) -> Vec<String> {
+        let mut blocks: Vec<_> = self
+            .blocks
+            .get(user_id)
+            .map(|blocks| blocks.iter().cloned().collect())
+            .unwrap_or_default();
+        blocks.sort();
+        blocks
+    }
+}
@@ -0,0 +1,22 @@
+use actix_web::http::StatusCode;
+use shine_core::kernel::response::APIError;
+
+#[derive(Debug)]
+pub enum RegionError {
+    BadRequest(String),
+    UnknownRegion(String),
+    /// None of the regions is able to host a new room
+    NoHealthyRegion,
+}
+
+impl From<RegionError> for APIError {
+    fn from(err: RegionError) -> APIError {
+        match err {
+            RegionError::BadRequest(msg) => APIError::BadRequest(msg),
+            RegionError::UnknownRegion(region) => APIError::RespourceNotFound(format!("Region {} not found", region)),
+            RegionError::NoHealthyRegion => {
+                APIError::Response(StatusCode::SERVICE_UNAVAILABLE, "No healthy region".to_owned())
+            }
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{region::RegionLatency, State};
+use actix_web::{http::header, web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use shine_core::{kernel::response::APIResult, requestinfo::TokenIdentity};
+
+const REGION_HEADER: &str = "X-Region";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AllocateRoomParams {
+    pub simulation: String,
+    /// Round trip times measured by the client, the nearest healthy region is selected
+    #[serde(default)]
+    pub latencies: Vec<RegionLatency>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AllocateRoomResult {
+    pub region: String,
+    /// Api root of the selected region when it is not served by this instance, or the api root of the
+    /// game server hosting the created room
+    pub endpoint: Option<String>,
+    /// The created room when the selected region is served by this instance, otherwise the room
+    /// has to be created through the endpoint of the region
+    pub room_id: Option<String>,
+}
+
+/// Minimal response for the round trip time measurement of the clients
+pub async fn ping(state: web::Data<State>) -> HttpResponse {
+    HttpResponse::NoContent()
+        .header(header::CACHE_CONTROL, "no-store")
+        .header(REGION_HEADER, state.regions().local_region())
+        .finish()
+}
+
+/// Load of the local region, queried by the instances of the other regions
+pub async fn get_region_health(state: web::Data<State>) -> APIResult {
+    Ok(HttpResponse::Ok().json(state.regions().local_health().await))
+}
+
+pub async fn list_regions(state: web::Data<State>, _identity: TokenIdentity) -> APIResult {
+    let regions = state.regions().list_regions().await;
+    Ok(HttpResponse::Ok().json(regions))
+}
+
+/// Select the region of a new room and create the room if it is served by this instance
+pub async fn allocate_room(
+    state: web::Data<State>,
+    _identity: TokenIdentity,
+    params: web::Json<AllocateRoomParams>,
+) -> APIResult {
+    let params = params.into_inner();
+    let regions = state.regions();
+    let region = regions.select_region(&params.latencies).await?;
+
+    let result = if region == regions.local_region() {
+        let room = state.allocator().allocate(&params.simulation).await?;
+        AllocateRoomResult {
+            region,
+            endpoint: room.endpoint,
+            room_id: Some(room.room_id),
+        }
+    } else {
+        AllocateRoomResult {
+            endpoint: regions.region_endpoint(&region).map(|endpoint| endpoint.to_owned()),
+            region,
+            room_id: None,
+        }
+    };
+    log::info!("Room allocated in region {}: {:?}", result.region, result.room_id);
+    Ok(HttpResponse::Ok().json(result))
+}
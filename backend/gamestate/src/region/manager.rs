@@ -0,0 +1,181 @@
+use crate::{
+    region::{RegionConfig, RegionError, RegionHealth, RegionInfo, RegionLatency},
+    room::RoomAllocator,
+};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RegionsConfig {
+    /// The region served by this instance
+    pub local: String,
+    /// All the regions including the local one. When empty, the local region is the only one.
+    #[serde(default)]
+    pub regions: Vec<RegionConfig>,
+    /// Rooms are not allocated in regions with a latency above this limit
+    #[serde(default)]
+    pub max_rtt_ms: Option<u32>,
+    /// The health of the remote regions is cached for this period
+    pub health_ttl_s: u64,
+}
+
+impl Default for RegionsConfig {
+    fn default() -> RegionsConfig {
+        RegionsConfig {
+            local: "local".to_owned(),
+            regions: Vec::new(),
+            max_rtt_ms: None,
+            health_ttl_s: 30,
+        }
+    }
+}
+
+/// Track the health of the regions and select the region of the new rooms based on the latencies
+/// measured by the clients. The health of the local region is given by the load of the room allocator,
+/// the remote regions are queried through their health endpoint.
+#[derive(Clone)]
+pub struct RegionManager {
+    config: Arc<RegionsConfig>,
+    client: Client,
+    allocator: Arc<dyn RoomAllocator>,
+    health: Arc<Mutex<HashMap<String, (RegionHealth, Instant)>>>,
+}
+
+impl RegionManager {
+    pub fn new(config: &RegionsConfig, allocator: Arc<dyn RoomAllocator>) -> RegionManager {
+        RegionManager {
+            config: Arc::new(config.clone()),
+            client: Client::new(),
+            allocator,
+            health: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn local_region(&self) -> &str {
+        &self.config.local
+    }
+
+    fn find_region(&self, region: &str) -> Option<&RegionConfig> {
+        self.config.regions.iter().find(|r| r.id == region)
+    }
+
+    pub async fn local_health(&self) -> RegionHealth {
+        match self.allocator.health().await {
+            Ok(health) => RegionHealth {
+                region: self.config.local.clone(),
+                healthy: health.healthy,
+                rooms: health.rooms,
+                max_rooms: health.max_rooms,
+            },
+            Err(err) => {
+                log::warn!("Health check of the room allocator failed: {}", err);
+                RegionHealth::unhealthy(&self.config.local)
+            }
+        }
+    }
+
+    async fn query_health(&self, region: &RegionConfig) -> Result<RegionHealth, reqwest::Error> {
+        self.client
+            .get(&format!("{}/regions/health", region.endpoint.trim_end_matches('/')))
+            .timeout(Duration::from_secs(2))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<RegionHealth>()
+            .await
+    }
+
+    /// Get the health of a region, unreachable regions are unhealthy
+    pub async fn region_health(&self, region: &str) -> Result<RegionHealth, RegionError> {
+        if region == self.config.local {
+            return Ok(self.local_health().await);
+        }
+        let config = self
+            .find_region(region)
+            .ok_or_else(|| RegionError::UnknownRegion(region.to_owned()))?;
+
+        let ttl = Duration::from_secs(self.config.health_ttl_s);
+        {
+            let health = self.health.lock().unwrap();
+            if let Some((health, checked)) = health.get(region) {
+                if checked.elapsed() < ttl {
+                    return Ok(health.clone());
+                }
+            }
+        }
+
+        let health = match self.query_health(config).await {
+            Ok(health) => health,
+            Err(err) => {
+                log::warn!("Health check of region {} failed: {}", region, err);
+                RegionHealth::unhealthy(region)
+            }
+        };
+        self.health
+            .lock()
+            .unwrap()
+            .insert(region.to_owned(), (health.clone(), Instant::now()));
+        Ok(health)
+    }
+
+    /// List the regions for the latency measurement of the clients
+    pub async fn list_regions(&self) -> Vec<RegionInfo> {
+        let mut regions = Vec::with_capacity(self.config.regions.len());
+        for region in self.config.regions.iter() {
+            let healthy = self
+                .region_health(&region.id)
+                .await
+                .map(|health| health.healthy)
+                .unwrap_or(false);
+            regions.push(RegionInfo {
+                id: region.id.clone(),
+                endpoint: region.endpoint.clone(),
+                ping_url: region.ping_url.clone(),
+                healthy,
+            });
+        }
+        regions
+    }
+
+    /// Select the healthy region with the lowest latency. Without any measurement the local region is
+    /// selected when it is healthy.
+    pub async fn select_region(&self, latencies: &[RegionLatency]) -> Result<String, RegionError> {
+        if latencies.is_empty() {
+            return if self.local_health().await.healthy {
+                Ok(self.config.local.clone())
+            } else {
+                Err(RegionError::NoHealthyRegion)
+            };
+        }
+
+        let mut latencies: Vec<_> = latencies
+            .iter()
+            .filter(|latency| match self.config.max_rtt_ms {
+                Some(max_rtt) => latency.rtt_ms <= max_rtt,
+                None => true,
+            })
+            .collect();
+        latencies.sort_by_key(|latency| latency.rtt_ms);
+
+        for latency in latencies {
+            if latency.region != self.config.local && self.find_region(&latency.region).is_none() {
+                log::debug!("Ignoring latency of unknown region {}", latency.region);
+                continue;
+            }
+            if self.region_health(&latency.region).await?.healthy {
+                return Ok(latency.region.clone());
+            }
+        }
+        Err(RegionError::NoHealthyRegion)
+    }
+
+    /// The api root of a region
+    pub fn region_endpoint(&self, region: &str) -> Option<&str> {
+        self.find_region(region).map(|r| r.endpoint.as_str())
+    }
+}
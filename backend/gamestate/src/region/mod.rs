@@ -0,0 +1,9 @@
+mod error;
+mod handler;
+mod manager;
+mod region_info;
+
+pub use self::error::*;
+pub use self::handler::*;
+pub use self::manager::*;
+pub use self::region_info::*;
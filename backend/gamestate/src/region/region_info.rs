@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+
+/// A deployment of the game state service
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegionConfig {
+    pub id: String,
+    /// Root of the api of the region, the clients connect to the rooms of the region through it
+    pub endpoint: String,
+    /// Url probed by the clients to measure the round trip time
+    pub ping_url: String,
+}
+
+/// Load of a region reported by the health endpoint
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegionHealth {
+    pub region: String,
+    pub healthy: bool,
+    pub rooms: usize,
+    pub max_rooms: usize,
+}
+
+impl RegionHealth {
+    pub fn unhealthy(region: &str) -> RegionHealth {
+        RegionHealth {
+            region: region.to_owned(),
+            healthy: false,
+            rooms: 0,
+            max_rooms: 0,
+        }
+    }
+}
+
+/// Region listed to the clients for the latency measurement
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegionInfo {
+    pub id: String,
+    pub endpoint: String,
+    pub ping_url: String,
+    pub healthy: bool,
+}
+
+/// Round trip time to a region measured by the client
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegionLatency {
+    pub region: String,
+    pub rtt_ms: u32,
+}
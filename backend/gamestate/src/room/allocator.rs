@@ -0,0 +1,86 @@
+use crate::room::{ExternalAllocatorConfig, RoomError};
+use serde::{Deserialize, Serialize};
+use std::{future::Future, pin::Pin};
+
+pub type AllocatorFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, RoomError>> + 'a>>;
+
+/// Hosting of the game rooms
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum RoomAllocatorConfig {
+    /// Run the rooms in the process of the service
+    Local,
+    /// Run the rooms on dedicated headless game servers
+    External(ExternalAllocatorConfig),
+}
+
+impl Default for RoomAllocatorConfig {
+    fn default() -> Self {
+        RoomAllocatorConfig::Local
+    }
+}
+
+/// A room created by an allocator
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomAllocation {
+    pub room_id: String,
+    /// The server hosting the room, None for the rooms of this instance
+    pub server_id: Option<String>,
+    /// Root of the api of the server the clients connect to, None for the rooms of this instance
+    pub endpoint: Option<String>,
+}
+
+/// Load of a game server
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerHealth {
+    pub server_id: String,
+    pub healthy: bool,
+    /// The server does not accept new rooms, the running rooms are kept until they end
+    pub draining: bool,
+    pub rooms: usize,
+    pub max_rooms: usize,
+}
+
+impl ServerHealth {
+    /// The server is able to host a new room
+    pub fn has_capacity(&self) -> bool {
+        self.healthy && !self.draining && self.rooms < self.max_rooms
+    }
+}
+
+/// Load of all the servers of an allocator
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FleetHealth {
+    /// A new room can be allocated
+    pub healthy: bool,
+    pub rooms: usize,
+    pub max_rooms: usize,
+    pub servers: Vec<ServerHealth>,
+}
+
+impl FleetHealth {
+    pub fn from_servers(servers: Vec<ServerHealth>) -> FleetHealth {
+        FleetHealth {
+            healthy: servers.iter().any(ServerHealth::has_capacity),
+            rooms: servers.iter().map(|s| s.rooms).sum(),
+            max_rooms: servers.iter().filter(|s| s.healthy).map(|s| s.max_rooms).sum(),
+            servers,
+        }
+    }
+}
+
+/// Hosting of the game rooms. The rooms are either run by the service itself or by a fleet of
+/// dedicated servers to scale the rooms horizontally.
+pub trait RoomAllocator: Send + Sync {
+    /// Create a room running the given simulation on a server with free capacity
+    fn allocate<'a>(&'a self, simulation: &'a str) -> AllocatorFuture<'a, RoomAllocation>;
+
+    fn health(&self) -> AllocatorFuture<'_, FleetHealth>;
+
+    /// Stop allocating new rooms on a server, or on all the servers if no server is given.
+    /// The running rooms are not affected.
+    fn drain<'a>(&'a self, server_id: Option<&'a str>) -> AllocatorFuture<'a, ()>;
+}
@@ -0,0 +1,198 @@
+use crate::room::{AnomalyCheck, CheatAction, CheatReport, CheatReportSink, MovementCheck, RateLimitCheck, ReasonCode};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+/// Context of an input validation
+pub struct CheckContext<'a> {
+    pub player_id: &'a str,
+    /// The tick the input is applied before
+    pub tick: u64,
+    pub tick_interval: Duration,
+}
+
+/// Result of an input validation
+#[derive(Debug)]
+pub enum InputVerdict {
+    Accept,
+    /// Drop the input without a report
+    Drop,
+    /// Apply the input and report the finding
+    Flag(ReasonCode, String),
+    /// Drop the input and report the finding, it counts toward the automatic kick
+    Reject(ReasonCode, String),
+}
+
+impl InputVerdict {
+    pub fn flag(reason: ReasonCode, detail: String) -> InputVerdict {
+        InputVerdict::Flag(reason, detail)
+    }
+
+    pub fn reject(reason: ReasonCode, detail: String) -> InputVerdict {
+        InputVerdict::Reject(reason, detail)
+    }
+}
+
+/// A server side heuristic applied to the inputs of the players before the simulation
+pub trait InputValidator: 'static {
+    fn check(&mut self, ctx: &CheckContext, input: &Value) -> InputVerdict;
+
+    /// Forget the state of a player who left the room
+    fn leave(&mut self, _player_id: &str) {}
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AntiCheatConfig {
+    /// Name of the [x, y, z] position field of the inputs
+    pub position_field: String,
+    /// Maximum speed in units per second, None to disable the check
+    pub max_speed: Option<f64>,
+    /// Maximum distance moved between two inputs, None to disable the check
+    pub teleport_distance: Option<f64>,
+    /// None to disable the rate limit
+    pub max_inputs_per_second: Option<u32>,
+    /// Numeric input fields checked for statistical anomalies
+    pub anomaly_fields: Vec<String>,
+    /// Deviation from the mean in standard deviations to flag a value
+    pub anomaly_threshold: f64,
+    /// Number of values collected from a player before the anomaly check starts
+    pub anomaly_min_samples: u64,
+    /// Number of rejected inputs before the player is kicked, None to disable the automatic kicks
+    pub kick_after: Option<u32>,
+}
+
+impl Default for AntiCheatConfig {
+    fn default() -> AntiCheatConfig {
+        AntiCheatConfig {
+            position_field: "position".to_owned(),
+            max_speed: None,
+            teleport_distance: None,
+            max_inputs_per_second: Some(60),
+            anomaly_fields: Vec::new(),
+            anomaly_threshold: 4.,
+            anomaly_min_samples: 30,
+            kick_after: None,
+        }
+    }
+}
+
+/// Validation layer of the room inputs. The inputs are checked by the validators in order, the first
+/// non-accepting verdict decides. The findings are sent to the report sinks.
+pub struct AntiCheat {
+    room_id: String,
+    tick_interval: Duration,
+    kick_after: Option<u32>,
+    validators: Vec<Box<dyn InputValidator>>,
+    sinks: Arc<Vec<Arc<dyn CheatReportSink>>>,
+    rejections: HashMap<String, u32>,
+}
+
+impl AntiCheat {
+    pub fn new(
+        room_id: &str,
+        tick_interval: Duration,
+        config: &AntiCheatConfig,
+        sinks: Arc<Vec<Arc<dyn CheatReportSink>>>,
+    ) -> AntiCheat {
+        let mut validators: Vec<Box<dyn InputValidator>> = Vec::new();
+        if let Some(max_inputs_per_second) = config.max_inputs_per_second {
+            validators.push(Box::new(RateLimitCheck::new(max_inputs_per_second)));
+        }
+        if config.max_speed.is_some() || config.teleport_distance.is_some() {
+            validators.push(Box::new(MovementCheck::new(
+                &config.position_field,
+                config.max_speed,
+                config.teleport_distance,
+            )));
+        }
+        if !config.anomaly_fields.is_empty() {
+            validators.push(Box::new(AnomalyCheck::new(
+                &config.anomaly_fields,
+                config.anomaly_threshold,
+                config.anomaly_min_samples,
+            )));
+        }
+
+        AntiCheat {
+            room_id: room_id.to_owned(),
+            tick_interval,
+            kick_after: config.kick_after,
+            validators,
+            sinks,
+            rejections: HashMap::new(),
+        }
+    }
+
+    pub fn add_validator(&mut self, validator: Box<dyn InputValidator>) {
+        self.validators.push(validator);
+    }
+
+    fn report(&self, player_id: &str, tick: u64, reason: ReasonCode, action: CheatAction, detail: String) {
+        let report = CheatReport {
+            room_id: self.room_id.clone(),
+            player_id: player_id.to_owned(),
+            tick,
+            reason,
+            action,
+            detail,
+            time: Utc::now(),
+        };
+        for sink in self.sinks.iter() {
+            sink.report(&report);
+        }
+    }
+
+    /// Validate an input. Returns if the input shall be applied and the reason of the kick
+    /// if the player has to be removed from the room.
+    pub fn check(&mut self, player_id: &str, tick: u64, input: &Value) -> (bool, Option<ReasonCode>) {
+        let ctx = CheckContext {
+            player_id,
+            tick,
+            tick_interval: self.tick_interval,
+        };
+
+        let mut verdict = InputVerdict::Accept;
+        for validator in self.validators.iter_mut() {
+            verdict = validator.check(&ctx, input);
+            if let InputVerdict::Accept = verdict {
+                continue;
+            }
+            break;
+        }
+
+        match verdict {
+            InputVerdict::Accept => (true, None),
+            InputVerdict::Drop => (false, None),
+            InputVerdict::Flag(reason, detail) => {
+                self.report(player_id, tick, reason, CheatAction::Flagged, detail);
+                (true, None)
+            }
+            InputVerdict::Reject(reason, detail) => {
+                self.report(player_id, tick, reason.clone(), CheatAction::Rejected, detail);
+                let rejections = {
+                    let rejections = self.rejections.entry(player_id.to_owned()).or_insert(0);
+                    *rejections += 1;
+                    *rejections
+                };
+                match self.kick_after {
+                    Some(kick_after) if rejections >= kick_after => {
+                        let detail = format!("{} rejected inputs", rejections);
+                        self.report(player_id, tick, reason.clone(), CheatAction::Kicked, detail);
+                        (false, Some(reason))
+                    }
+                    _ => (false, None),
+                }
+            }
+        }
+    }
+
+    /// Forget the state of a player who left the room
+    pub fn leave(&mut self, player_id: &str) {
+        self.rejections.remove(player_id);
+        for validator in self.validators.iter_mut() {
+            validator.leave(player_id);
+        }
+    }
+}
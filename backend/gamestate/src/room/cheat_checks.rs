@@ -0,0 +1,212 @@
+use crate::room::{CheckContext, InputValidator, InputVerdict, ReasonCode};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Check the movement of the players: positions not reachable in a tick are rejected as teleport,
+/// moving faster than the allowed speed is rejected as speed hack. The position is read from a
+/// [x, y, z] array field of the input, inputs without position are not checked.
+pub struct MovementCheck {
+    position_field: String,
+    max_speed: Option<f64>,
+    teleport_distance: Option<f64>,
+    last_positions: HashMap<String, (u64, [f64; 3])>,
+}
+
+impl MovementCheck {
+    pub fn new(position_field: &str, max_speed: Option<f64>, teleport_distance: Option<f64>) -> MovementCheck {
+        MovementCheck {
+            position_field: position_field.to_owned(),
+            max_speed,
+            teleport_distance,
+            last_positions: HashMap::new(),
+        }
+    }
+
+    fn parse_position(value: &Value) -> Option<[f64; 3]> {
+        let array = value.as_array()?;
+        if array.len() != 3 {
+            return None;
+        }
+        Some([array[0].as_f64()?, array[1].as_f64()?, array[2].as_f64()?])
+    }
+}
+
+impl InputValidator for MovementCheck {
+    fn check(&mut self, ctx: &CheckContext, input: &Value) -> InputVerdict {
+        let position = match input.get(&self.position_field) {
+            Some(position) => match Self::parse_position(position) {
+                Some(position) => position,
+                None => return InputVerdict::reject(ReasonCode::Teleport, "Malformed position".to_owned()),
+            },
+            None => return InputVerdict::Accept,
+        };
+
+        if let Some((last_tick, last)) = self.last_positions.get(ctx.player_id) {
+            let distance = last
+                .iter()
+                .zip(position.iter())
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum::<f64>()
+                .sqrt();
+
+            if let Some(teleport_distance) = self.teleport_distance {
+                if distance > teleport_distance {
+                    return InputVerdict::reject(
+                        ReasonCode::Teleport,
+                        format!("Moved {:.2} in a step (limit: {:.2})", distance, teleport_distance),
+                    );
+                }
+            }
+
+            if let Some(max_speed) = self.max_speed {
+                // inputs of the same tick are measured over a full tick
+                let ticks = (ctx.tick - last_tick).max(1);
+                let elapsed = ctx.tick_interval.as_secs_f64() * ticks as f64;
+                let speed = distance / elapsed;
+                if speed > max_speed {
+                    return InputVerdict::reject(
+                        ReasonCode::SpeedLimit,
+                        format!("Speed {:.2} (limit: {:.2})", speed, max_speed),
+                    );
+                }
+            }
+        }
+
+        // rejected moves are not stored, the next move is measured from the last valid position
+        self.last_positions
+            .insert(ctx.player_id.to_owned(), (ctx.tick, position));
+        InputVerdict::Accept
+    }
+
+    fn leave(&mut self, player_id: &str) {
+        self.last_positions.remove(player_id);
+    }
+}
+
+/// Limit the number of inputs a player can send in a second. The first input above the limit is
+/// reported, the rest of the inputs in the same second are dropped silently.
+pub struct RateLimitCheck {
+    max_inputs_per_second: u32,
+    windows: HashMap<String, (u64, u32)>,
+}
+
+impl RateLimitCheck {
+    pub fn new(max_inputs_per_second: u32) -> RateLimitCheck {
+        RateLimitCheck {
+            max_inputs_per_second,
+            windows: HashMap::new(),
+        }
+    }
+}
+
+impl InputValidator for RateLimitCheck {
+    fn check(&mut self, ctx: &CheckContext, _input: &Value) -> InputVerdict {
+        let ticks_per_second = ((1. / ctx.tick_interval.as_secs_f64()).round() as u64).max(1);
+        let window = ctx.tick / ticks_per_second;
+
+        let (current, count) = self.windows.entry(ctx.player_id.to_owned()).or_insert((window, 0));
+        if *current != window {
+            *current = window;
+            *count = 0;
+        }
+        *count += 1;
+
+        if *count == self.max_inputs_per_second + 1 {
+            InputVerdict::reject(
+                ReasonCode::RateLimit,
+                format!("More than {} inputs in a second", self.max_inputs_per_second),
+            )
+        } else if *count > self.max_inputs_per_second {
+            InputVerdict::Drop
+        } else {
+            InputVerdict::Accept
+        }
+    }
+
+    fn leave(&mut self, player_id: &str) {
+        self.windows.remove(player_id);
+    }
+}
+
+/// Running mean and variance (Welford's algorithm)
+#[derive(Default)]
+struct RunningStats {
+    count: u64,
+    mean: f64,
+    m2: f64,
+}
+
+impl RunningStats {
+    fn add(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    fn std_dev(&self) -> f64 {
+        if self.count < 2 {
+            0.
+        } else {
+            (self.m2 / (self.count - 1) as f64).sqrt()
+        }
+    }
+}
+
+/// Flag the numeric input fields (ex. accuracy, reaction time) deviating from the history of the player
+/// more than the threshold (in standard deviations). The inputs are not rejected, the flags are for review.
+pub struct AnomalyCheck {
+    fields: Vec<String>,
+    threshold: f64,
+    min_samples: u64,
+    stats: HashMap<(String, String), RunningStats>,
+}
+
+impl AnomalyCheck {
+    pub fn new(fields: &[String], threshold: f64, min_samples: u64) -> AnomalyCheck {
+        AnomalyCheck {
+            fields: fields.to_vec(),
+            threshold,
+            min_samples,
+            stats: HashMap::new(),
+        }
+    }
+}
+
+impl InputValidator for AnomalyCheck {
+    fn check(&mut self, ctx: &CheckContext, input: &Value) -> InputVerdict {
+        let mut verdict = InputVerdict::Accept;
+        for field in &self.fields {
+            let value = match input.get(field).and_then(|v| v.as_f64()) {
+                Some(value) => value,
+                None => continue,
+            };
+
+            let stats = self.stats.entry((ctx.player_id.to_owned(), field.clone())).or_default();
+            if stats.count >= self.min_samples {
+                let std_dev = stats.std_dev();
+                let deviation = (value - stats.mean).abs();
+                if std_dev > 0. && deviation / std_dev > self.threshold {
+                    if let InputVerdict::Accept = verdict {
+                        verdict = InputVerdict::flag(
+                            ReasonCode::Anomaly,
+                            format!(
+                                "{}: {} deviates {:.1} sigma from {:.2}",
+                                field,
+                                value,
+                                deviation / std_dev,
+                                stats.mean
+                            ),
+                        );
+                    }
+                }
+            }
+            stats.add(value);
+        }
+        verdict
+    }
+
+    fn leave(&mut self, player_id: &str) {
+        self.stats.retain(|(player, _), _| player != player_id);
+    }
+}
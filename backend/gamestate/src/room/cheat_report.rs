@@ -0,0 +1,94 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shine_core::metrics::Metrics;
+
+/// Reason code of an anti-cheat finding, it is also sent to the client on a kick
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReasonCode {
+    /// Moved faster than the allowed speed
+    SpeedLimit,
+    /// Moved a distance not reachable in a tick
+    Teleport,
+    /// Sent more inputs than allowed
+    RateLimit,
+    /// Statistically unlikely input
+    Anomaly,
+    /// Finding of a validator provided by the simulation
+    Custom(String),
+}
+
+impl ReasonCode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ReasonCode::SpeedLimit => "speedLimit",
+            ReasonCode::Teleport => "teleport",
+            ReasonCode::RateLimit => "rateLimit",
+            ReasonCode::Anomaly => "anomaly",
+            ReasonCode::Custom(code) => code,
+        }
+    }
+}
+
+/// Action taken by the room on a finding
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CheatAction {
+    /// Reported only, the input was applied
+    Flagged,
+    /// The input was dropped
+    Rejected,
+    /// The player was removed from the room
+    Kicked,
+}
+
+/// A finding of the anti-cheat validation
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheatReport {
+    pub room_id: String,
+    pub player_id: String,
+    pub tick: u64,
+    pub reason: ReasonCode,
+    pub action: CheatAction,
+    pub detail: String,
+    pub time: DateTime<Utc>,
+}
+
+/// Destination of the anti-cheat reports
+pub trait CheatReportSink: Send + Sync {
+    fn report(&self, report: &CheatReport);
+}
+
+/// Write the reports into the audit log (log target "audit")
+pub struct AuditLogReportSink;
+
+impl CheatReportSink for AuditLogReportSink {
+    fn report(&self, report: &CheatReport) {
+        match serde_json::to_string(report) {
+            Ok(report) => log::warn!(target: "audit", "anticheat {}", report),
+            Err(err) => log::warn!("Failed to serialize cheat report: {:?}", err),
+        }
+    }
+}
+
+/// Count the reports by reason in the metrics, kicks are counted separately
+pub struct MetricsReportSink {
+    metrics: Metrics,
+}
+
+impl MetricsReportSink {
+    pub fn new(metrics: Metrics) -> MetricsReportSink {
+        MetricsReportSink { metrics }
+    }
+}
+
+impl CheatReportSink for MetricsReportSink {
+    fn report(&self, report: &CheatReport) {
+        let operation = match report.action {
+            CheatAction::Kicked => "kick",
+            _ => report.reason.as_str(),
+        };
+        self.metrics.count_operation("anticheat", operation, false);
+    }
+}
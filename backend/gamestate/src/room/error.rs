@@ -0,0 +1,50 @@
+use actix_web::http::StatusCode;
+use shine_core::kernel::response::APIError;
+use shine_ecs::ECSError;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum RoomError {
+    RoomNotFound,
+    RoomLimit,
+    UnknownSimulation(String),
+    PlayerAlreadyJoined,
+    /// The player was kicked from the room and cannot rejoin
+    PlayerKicked,
+    SpectatorLimit,
+    /// The allocator does not accept new rooms
+    Draining,
+    UnknownServer(String),
+    /// Failed to reach the game servers
+    Allocator(String),
+    /// Error of the simulation (ecs)
+    Simulation(String),
+}
+
+impl fmt::Display for RoomError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl From<ECSError> for RoomError {
+    fn from(err: ECSError) -> RoomError {
+        RoomError::Simulation(format!("{:?}", err))
+    }
+}
+
+impl From<RoomError> for APIError {
+    fn from(err: RoomError) -> APIError {
+        match err {
+            RoomError::RoomNotFound => APIError::RespourceNotFound("Room not found".to_owned()),
+            RoomError::RoomLimit => APIError::TooManyRequests("Room limit reached".to_owned()),
+            RoomError::UnknownSimulation(name) => APIError::BadRequest(format!("Unknown simulation: {}", name)),
+            RoomError::PlayerAlreadyJoined => APIError::Conflict("Player already joined".to_owned()),
+            RoomError::PlayerKicked => APIError::Forbidden,
+            RoomError::SpectatorLimit => APIError::TooManyRequests("Spectator limit reached".to_owned()),
+            RoomError::Draining => APIError::Response(StatusCode::SERVICE_UNAVAILABLE, "Rooms are draining".to_owned()),
+            RoomError::UnknownServer(server) => APIError::RespourceNotFound(format!("Server {} not found", server)),
+            err => APIError::Internal(format!("{:?}", err)),
+        }
+    }
+}
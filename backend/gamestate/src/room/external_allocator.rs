@@ -0,0 +1,434 @@
+//! Allocator of the rooms hosted by dedicated headless game servers (shine-server). A game server
+//! exposes the following api under its endpoint:
+//! - `GET /health` returning the load of the server (`{"rooms": 3, "maxRooms": 50, "draining": false}`)
+//! - `POST /rooms` with `{"simulation": "..."}` creating a room and returning `{"roomId": "..."}`
+//! - `POST /drain` to stop accepting new rooms, the server exits when the last room has ended
+//!
+//! The servers are either listed in the configuration (ex. containers managed by the deployment) or
+//! launched by the allocator as local processes when the listed servers are full.
+
+use crate::room::{AllocatorFuture, FleetHealth, RoomAllocation, RoomAllocator, RoomError, ServerHealth};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::{
+    process::{Child, Command},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// A game server started by the deployment
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalServerConfig {
+    pub id: String,
+    pub endpoint: String,
+}
+
+/// Launch the game servers as processes of the host. In the arguments `{port}` and `{id}` are replaced
+/// by the port and the id of the server.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchConfig {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// The servers are listening on the host on consecutive ports from this port
+    pub base_port: u16,
+    pub max_servers: usize,
+    /// Seconds to wait for a launched server to report healthy
+    #[serde(default = "LaunchConfig::default_startup_timeout_s")]
+    pub startup_timeout_s: u64,
+}
+
+impl LaunchConfig {
+    fn default_startup_timeout_s() -> u64 {
+        10
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalAllocatorConfig {
+    #[serde(default)]
+    pub servers: Vec<ExternalServerConfig>,
+    #[serde(default)]
+    pub launch: Option<LaunchConfig>,
+    /// The health of the servers is cached for this period
+    #[serde(default = "ExternalAllocatorConfig::default_health_ttl_s")]
+    pub health_ttl_s: u64,
+}
+
+impl ExternalAllocatorConfig {
+    fn default_health_ttl_s() -> u64 {
+        5
+    }
+}
+
+/// Health reported by a game server
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ServerStatus {
+    rooms: usize,
+    max_rooms: usize,
+    draining: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct CreateRoomRequest<'a> {
+    simulation: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateRoomResponse {
+    room_id: String,
+}
+
+struct ExternalServer {
+    id: String,
+    endpoint: String,
+    health: Option<(ServerHealth, Instant)>,
+    draining: bool,
+    /// The process and the port of the launched servers
+    process: Option<Child>,
+    port: Option<u16>,
+}
+
+impl ExternalServer {
+    fn new(id: String, endpoint: String, process: Option<Child>) -> ExternalServer {
+        ExternalServer {
+            id,
+            endpoint: endpoint.trim_end_matches('/').to_owned(),
+            health: None,
+            draining: false,
+            process,
+            port: None,
+        }
+    }
+
+    /// The launched process has exited
+    fn has_exited(&mut self) -> bool {
+        match &mut self.process {
+            Some(process) => !matches!(process.try_wait(), Ok(None)),
+            None => false,
+        }
+    }
+}
+
+/// Allocate the rooms on the least loaded game server
+pub struct ExternalRoomAllocator {
+    config: Arc<ExternalAllocatorConfig>,
+    client: Client,
+    servers: Arc<Mutex<Vec<ExternalServer>>>,
+    /// Counter of the launched servers to assign the ids
+    launched: Arc<Mutex<usize>>,
+}
+
+impl ExternalRoomAllocator {
+    pub fn new(config: &ExternalAllocatorConfig) -> ExternalRoomAllocator {
+        let servers = config
+            .servers
+            .iter()
+            .map(|server| ExternalServer::new(server.id.clone(), server.endpoint.clone(), None))
+            .collect();
+        ExternalRoomAllocator {
+            config: Arc::new(config.clone()),
+            client: Client::new(),
+            servers: Arc::new(Mutex::new(servers)),
+            launched: Arc::new(Mutex::new(0)),
+        }
+    }
+
+    fn with_servers<T, F: FnOnce(&mut Vec<ExternalServer>) -> T>(&self, f: F) -> T {
+        let mut servers = self.servers.lock().unwrap();
+        f(&mut servers)
+    }
+
+    async fn query_status(&self, endpoint: &str) -> Result<ServerStatus, reqwest::Error> {
+        self.client
+            .get(&format!("{}/health", endpoint))
+            .timeout(Duration::from_secs(2))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<ServerStatus>()
+            .await
+    }
+
+    /// Refresh the expired health of the servers. The launched servers are removed once they exited
+    /// and the drained ones are stopped when their last room has ended.
+    async fn refresh_health(&self) -> Vec<ServerHealth> {
+        let ttl = Duration::from_secs(self.config.health_ttl_s);
+        let expired: Vec<(String, String)> = self.with_servers(|servers| {
+            let exited: Vec<String> = servers
+                .iter_mut()
+                .filter_map(|server| {
+                    if server.has_exited() {
+                        Some(server.id.clone())
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+            for id in &exited {
+                log::info!("Game server {} exited", id);
+            }
+            servers.retain(|server| !exited.contains(&server.id));
+            servers
+                .iter()
+                .filter(|server| {
+                    server
+                        .health
+                        .as_ref()
+                        .map(|(_, checked)| checked.elapsed() >= ttl)
+                        .unwrap_or(true)
+                })
+                .map(|server| (server.id.clone(), server.endpoint.clone()))
+                .collect()
+        });
+
+        for (id, endpoint) in expired {
+            let health = match self.query_status(&endpoint).await {
+                Ok(status) => ServerHealth {
+                    server_id: id.clone(),
+                    healthy: true,
+                    draining: status.draining,
+                    rooms: status.rooms,
+                    max_rooms: status.max_rooms,
+                },
+                Err(err) => {
+                    log::warn!("Health check of game server {} failed: {}", id, err);
+                    ServerHealth {
+                        server_id: id.clone(),
+                        healthy: false,
+                        draining: false,
+                        rooms: 0,
+                        max_rooms: 0,
+                    }
+                }
+            };
+            self.with_servers(|servers| {
+                if let Some(server) = servers.iter_mut().find(|server| server.id == id) {
+                    server.health = Some((health, Instant::now()));
+                }
+            });
+        }
+
+        self.with_servers(|servers| {
+            for server in servers.iter_mut() {
+                let drained = server.draining
+                    && server
+                        .health
+                        .as_ref()
+                        .map(|(health, _)| health.healthy && health.rooms == 0)
+                        .unwrap_or(false);
+                if drained {
+                    if let Some(process) = &mut server.process {
+                        log::info!("Stopping the drained game server {}", server.id);
+                        let _ = process.kill();
+                        let _ = process.wait();
+                    }
+                }
+            }
+
+            servers
+                .iter()
+                .filter_map(|server| {
+                    server.health.as_ref().map(|(health, _)| ServerHealth {
+                        draining: health.draining || server.draining,
+                        ..health.clone()
+                    })
+                })
+                .collect()
+        })
+    }
+
+    fn invalidate_health(&self, server_id: &str) {
+        self.with_servers(|servers| {
+            if let Some(server) = servers.iter_mut().find(|server| server.id == server_id) {
+                server.health = None;
+            }
+        });
+    }
+
+    fn server_endpoint(&self, server_id: &str) -> Option<String> {
+        self.with_servers(|servers| {
+            servers
+                .iter()
+                .find(|server| server.id == server_id)
+                .map(|server| server.endpoint.clone())
+        })
+    }
+
+    async fn create_room_on(&self, server_id: &str, simulation: &str) -> Result<RoomAllocation, RoomError> {
+        let endpoint = self
+            .server_endpoint(server_id)
+            .ok_or_else(|| RoomError::UnknownServer(server_id.to_owned()))?;
+        let response = self
+            .client
+            .post(&format!("{}/rooms", endpoint))
+            .timeout(Duration::from_secs(5))
+            .json(&CreateRoomRequest { simulation })
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| RoomError::Allocator(format!("Room creation on {} failed: {}", server_id, err)))?
+            .json::<CreateRoomResponse>()
+            .await
+            .map_err(|err| RoomError::Allocator(format!("Invalid response from {}: {}", server_id, err)))?;
+
+        // the load of the server has changed
+        self.invalidate_health(server_id);
+        Ok(RoomAllocation {
+            room_id: response.room_id,
+            server_id: Some(server_id.to_owned()),
+            endpoint: Some(endpoint),
+        })
+    }
+
+    /// Launch a new game server process and wait until it reports healthy
+    async fn launch_server(&self) -> Result<Option<String>, RoomError> {
+        let launch = match &self.config.launch {
+            Some(launch) => launch,
+            None => return Ok(None),
+        };
+
+        // the first port not used by a running server
+        let used_ports: Vec<u16> =
+            self.with_servers(|servers| servers.iter().filter_map(|server| server.port).collect());
+        let port = match (0..launch.max_servers)
+            .map(|i| launch.base_port + i as u16)
+            .find(|port| !used_ports.contains(port))
+        {
+            Some(port) => port,
+            None => return Ok(None),
+        };
+
+        let index = {
+            let mut launched = self.launched.lock().unwrap();
+            *launched += 1;
+            *launched
+        };
+        let id = format!("launched-{}", index);
+        let args: Vec<String> = launch
+            .args
+            .iter()
+            .map(|arg| arg.replace("{port}", &port.to_string()).replace("{id}", &id))
+            .collect();
+
+        log::info!("Launching game server {} on port {}", id, port);
+        let process = Command::new(&launch.command)
+            .args(&args)
+            .spawn()
+            .map_err(|err| RoomError::Allocator(format!("Failed to launch game server: {}", err)))?;
+        let endpoint = format!("http://127.0.0.1:{}", port);
+        self.with_servers(|servers| {
+            let mut server = ExternalServer::new(id.clone(), endpoint.clone(), Some(process));
+            server.port = Some(port);
+            servers.push(server)
+        });
+
+        let started = Instant::now();
+        let timeout = Duration::from_secs(launch.startup_timeout_s);
+        while started.elapsed() < timeout {
+            if self.query_status(&endpoint).await.is_ok() {
+                return Ok(Some(id));
+            }
+            actix_rt::time::delay_for(Duration::from_millis(250)).await;
+        }
+
+        log::warn!("Game server {} did not start in time", id);
+        self.with_servers(|servers| {
+            if let Some(pos) = servers.iter().position(|server| server.id == id) {
+                let mut server = servers.remove(pos);
+                if let Some(process) = &mut server.process {
+                    let _ = process.kill();
+                    let _ = process.wait();
+                }
+            }
+        });
+        Err(RoomError::Allocator(format!(
+            "Game server {} did not start in time",
+            id
+        )))
+    }
+}
+
+impl RoomAllocator for ExternalRoomAllocator {
+    fn allocate<'a>(&'a self, simulation: &'a str) -> AllocatorFuture<'a, RoomAllocation> {
+        Box::pin(async move {
+            let mut candidates: Vec<_> = self
+                .refresh_health()
+                .await
+                .into_iter()
+                .filter(ServerHealth::has_capacity)
+                .collect();
+            // least loaded first
+            candidates.sort_by_key(|health| health.rooms * 1000 / health.max_rooms.max(1));
+
+            for candidate in candidates {
+                match self.create_room_on(&candidate.server_id, simulation).await {
+                    Ok(allocation) => return Ok(allocation),
+                    Err(err) => {
+                        log::warn!("{}", err);
+                        self.invalidate_health(&candidate.server_id);
+                    }
+                }
+            }
+
+            match self.launch_server().await? {
+                Some(server_id) => self.create_room_on(&server_id, simulation).await,
+                None => Err(RoomError::RoomLimit),
+            }
+        })
+    }
+
+    fn health(&self) -> AllocatorFuture<'_, FleetHealth> {
+        Box::pin(async move {
+            let servers = self.refresh_health().await;
+            let launch_capacity = match &self.config.launch {
+                Some(launch) => servers.len() < self.config.servers.len() + launch.max_servers,
+                None => false,
+            };
+            let mut health = FleetHealth::from_servers(servers);
+            health.healthy |= launch_capacity;
+            Ok(health)
+        })
+    }
+
+    fn drain<'a>(&'a self, server_id: Option<&'a str>) -> AllocatorFuture<'a, ()> {
+        Box::pin(async move {
+            let endpoints: Vec<(String, String)> = self.with_servers(|servers| {
+                servers
+                    .iter_mut()
+                    .filter(|server| server_id.map(|id| id == server.id).unwrap_or(true))
+                    .map(|server| {
+                        server.draining = true;
+                        (server.id.clone(), server.endpoint.clone())
+                    })
+                    .collect()
+            });
+            if let Some(server_id) = server_id {
+                if endpoints.is_empty() {
+                    return Err(RoomError::UnknownServer(server_id.to_owned()));
+                }
+            }
+
+            for (id, endpoint) in endpoints {
+                log::info!("Draining game server {}", id);
+                let result = self
+                    .client
+                    .post(&format!("{}/drain", endpoint))
+                    .timeout(Duration::from_secs(2))
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status());
+                if let Err(err) = result {
+                    // the server is not selected for new rooms anyway
+                    log::warn!("Failed to drain game server {}: {}", id, err);
+                }
+            }
+            Ok(())
+        })
+    }
+}
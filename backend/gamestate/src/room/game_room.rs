@@ -0,0 +1,306 @@
+use crate::room::{
+    state_delta, AntiCheat, InputValidator, JoinRoom, LeaveRoom, LeaveSpectate, RoomError, RoomInput, RoomUpdate,
+    SpectateRoom,
+};
+use actix::{Actor, AsyncContext, Context, Handler, Recipient};
+use serde_json::Value;
+use shine_ecs::{
+    resources::Resources,
+    scheduler::{Scheduler, TaskGroup},
+    ECSError,
+};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// The game logic of a room. The state is kept in the ecs resources and advanced by the systems
+/// returned by init, the same way as on the client.
+pub trait RoomSimulation: 'static {
+    /// Register the resources and return the systems of a tick
+    fn init(&mut self, resources: &mut Resources) -> Result<TaskGroup, ECSError>;
+
+    /// A player joined the room
+    fn join(&mut self, _resources: &Resources, _player_id: &str) -> Result<(), ECSError> {
+        Ok(())
+    }
+
+    /// A player left the room
+    fn leave(&mut self, _resources: &Resources, _player_id: &str) -> Result<(), ECSError> {
+        Ok(())
+    }
+
+    /// Additional anti-cheat validators of the game specific inputs
+    fn validators(&self) -> Vec<Box<dyn InputValidator>> {
+        Vec::new()
+    }
+
+    /// Apply the input of a player, the inputs are applied in the order of arrival before the tick
+    fn apply_input(&mut self, resources: &Resources, player_id: &str, input: Value) -> Result<(), ECSError>;
+
+    /// Serialize the state broadcasted to the clients. Null values should be avoided as they are
+    /// interpreted as removal in the deltas.
+    fn snapshot(&self, resources: &Resources) -> Result<Value, ECSError>;
+}
+
+/// Spectator settings of a room
+#[derive(Clone, Debug)]
+pub struct SpectatorConfig {
+    /// Number of ticks the spectators are behind the players to prevent ghosting
+    pub delay_ticks: u64,
+    pub max_spectators: usize,
+    /// Number of the connected spectators, shared with the room manager
+    pub count: Arc<AtomicUsize>,
+}
+
+/// Authoritative state of a room running a fixed-tick simulation. The inputs of the clients are collected
+/// between the ticks and the changes of the state are broadcasted after each tick. Spectators receive the
+/// same stream delayed and cannot send inputs.
+pub struct GameRoom {
+    id: String,
+    tick_interval: Duration,
+    tick: u64,
+    resources: Resources,
+    scheduler: Scheduler,
+    tasks: TaskGroup,
+    simulation: Box<dyn RoomSimulation>,
+    inputs: Vec<(String, Value)>,
+    anti_cheat: AntiCheat,
+    clients: HashMap<String, Recipient<RoomUpdate>>,
+    kicked: HashSet<String>,
+    state: Value,
+    spectator_config: SpectatorConfig,
+    spectators: HashMap<String, Recipient<RoomUpdate>>,
+    /// The states not yet sent to the spectators
+    spectator_queue: VecDeque<(u64, Value)>,
+    spectator_tick: u64,
+    spectator_state: Value,
+}
+
+impl GameRoom {
+    pub fn new(
+        id: String,
+        tick_interval: Duration,
+        mut simulation: Box<dyn RoomSimulation>,
+        mut anti_cheat: AntiCheat,
+        spectator_config: SpectatorConfig,
+    ) -> Result<GameRoom, RoomError> {
+        let mut resources = Resources::default();
+        let tasks = simulation.init(&mut resources)?;
+        let state = simulation.snapshot(&resources)?;
+        for validator in simulation.validators() {
+            anti_cheat.add_validator(validator);
+        }
+        Ok(GameRoom {
+            id,
+            tick_interval,
+            tick: 0,
+            resources,
+            scheduler: Scheduler::default(),
+            tasks,
+            simulation,
+            inputs: Vec::new(),
+            anti_cheat,
+            clients: HashMap::new(),
+            kicked: HashSet::new(),
+            spectator_config,
+            spectators: HashMap::new(),
+            spectator_queue: VecDeque::new(),
+            spectator_tick: 0,
+            spectator_state: state.clone(),
+            state,
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn broadcast(&mut self, update: RoomUpdate) {
+        // drop the disconnected clients
+        self.clients.retain(|_, client| client.do_send(update.clone()).is_ok());
+    }
+
+    /// Send the states older than the delay to the spectators
+    fn broadcast_spectators(&mut self) {
+        let delay = self.spectator_config.delay_ticks;
+        while let Some((tick, _)) = self.spectator_queue.front() {
+            if tick + delay > self.tick {
+                break;
+            }
+            let (tick, state) = self.spectator_queue.pop_front().unwrap();
+            if let Some(patch) = state_delta(&self.spectator_state, &state) {
+                let update = RoomUpdate::Delta { tick, patch };
+                self.spectators
+                    .retain(|_, spectator| spectator.do_send(update.clone()).is_ok());
+            }
+            self.spectator_tick = tick;
+            self.spectator_state = state;
+        }
+        self.spectator_config
+            .count
+            .store(self.spectators.len(), Ordering::Relaxed);
+    }
+
+    /// Remove a player from the room, the player cannot rejoin
+    fn kick(&mut self, player_id: &str, update: RoomUpdate) {
+        log::info!("Player {} kicked from room {}: {:?}", player_id, self.id, update);
+        if let Some(client) = self.clients.remove(player_id) {
+            let _ = client.do_send(update);
+        }
+        self.kicked.insert(player_id.to_owned());
+        self.inputs.retain(|(id, _)| id != player_id);
+        self.anti_cheat.leave(player_id);
+        if let Err(err) = self.simulation.leave(&self.resources, player_id) {
+            log::warn!("Room {} failed to remove {}: {:?}", self.id, player_id, err);
+        }
+    }
+
+    fn step(&mut self) -> Result<(), RoomError> {
+        let mut inputs = std::mem::take(&mut self.inputs);
+        let mut kicks = Vec::new();
+        for (player_id, input) in inputs.drain(..) {
+            if kicks.iter().any(|(id, _)| id == &player_id) {
+                continue;
+            }
+            let (apply, kick) = self.anti_cheat.check(&player_id, self.tick, &input);
+            if let Some(reason) = kick {
+                kicks.push((player_id, reason));
+                continue;
+            }
+            if !apply {
+                continue;
+            }
+            if let Err(err) = self.simulation.apply_input(&self.resources, &player_id, input) {
+                log::warn!("Room {} rejected input of {}: {:?}", self.id, player_id, err);
+            }
+        }
+        for (player_id, reason) in kicks {
+            self.kick(&player_id, RoomUpdate::Kicked { reason });
+        }
+
+        self.scheduler.run(&self.resources, &self.tasks)?;
+        self.tick += 1;
+
+        let state = self.simulation.snapshot(&self.resources)?;
+        if let Some(patch) = state_delta(&self.state, &state) {
+            self.spectator_queue.push_back((self.tick, state.clone()));
+            self.state = state;
+            self.broadcast(RoomUpdate::Delta { tick: self.tick, patch });
+        }
+        self.broadcast_spectators();
+        Ok(())
+    }
+}
+
+impl Actor for GameRoom {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        log::info!("Room {} started", self.id);
+        ctx.run_interval(self.tick_interval, |room, _ctx| {
+            if let Err(err) = room.step() {
+                log::error!("Room {} tick {} failed: {:?}", room.id, room.tick, err);
+            }
+        });
+    }
+
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        log::info!("Room {} stopped after {} ticks", self.id, self.tick);
+        for (_, spectator) in self.spectators.drain() {
+            let _ = spectator.do_send(RoomUpdate::Closed);
+        }
+        self.spectator_config.count.store(0, Ordering::Relaxed);
+    }
+}
+
+impl Handler<JoinRoom> for GameRoom {
+    type Result = Result<(), RoomError>;
+
+    fn handle(&mut self, msg: JoinRoom, _ctx: &mut Self::Context) -> Self::Result {
+        if self.kicked.contains(&msg.player_id) {
+            return Err(RoomError::PlayerKicked);
+        }
+        if self.clients.contains_key(&msg.player_id) {
+            return Err(RoomError::PlayerAlreadyJoined);
+        }
+        self.simulation.join(&self.resources, &msg.player_id)?;
+        log::info!("Player {} joined room {}", msg.player_id, self.id);
+
+        // the join is part of the state only after the next tick, the snapshot is the last broadcasted state
+        let _ = msg.recipient.do_send(RoomUpdate::Snapshot {
+            tick: self.tick,
+            state: self.state.clone(),
+        });
+        self.clients.insert(msg.player_id, msg.recipient);
+        Ok(())
+    }
+}
+
+impl Handler<LeaveRoom> for GameRoom {
+    type Result = ();
+
+    fn handle(&mut self, msg: LeaveRoom, ctx: &mut Self::Context) {
+        if self.clients.remove(&msg.player_id).is_some() {
+            log::info!("Player {} left room {}", msg.player_id, self.id);
+            self.anti_cheat.leave(&msg.player_id);
+            if let Err(err) = self.simulation.leave(&self.resources, &msg.player_id) {
+                log::warn!("Room {} failed to remove {}: {:?}", self.id, msg.player_id, err);
+            }
+        }
+        if self.clients.is_empty() {
+            ctx.stop();
+        }
+    }
+}
+
+impl Handler<RoomInput> for GameRoom {
+    type Result = ();
+
+    fn handle(&mut self, msg: RoomInput, _ctx: &mut Self::Context) {
+        if self.clients.contains_key(&msg.player_id) {
+            self.inputs.push((msg.player_id, msg.input));
+        }
+    }
+}
+
+impl Handler<SpectateRoom> for GameRoom {
+    type Result = Result<(), RoomError>;
+
+    fn handle(&mut self, msg: SpectateRoom, _ctx: &mut Self::Context) -> Self::Result {
+        if self.spectators.len() >= self.spectator_config.max_spectators {
+            return Err(RoomError::SpectatorLimit);
+        }
+        if self.spectators.contains_key(&msg.spectator_id) {
+            return Err(RoomError::PlayerAlreadyJoined);
+        }
+        log::info!("Spectator {} joined room {}", msg.spectator_id, self.id);
+
+        let _ = msg.recipient.do_send(RoomUpdate::Snapshot {
+            tick: self.spectator_tick,
+            state: self.spectator_state.clone(),
+        });
+        self.spectators.insert(msg.spectator_id, msg.recipient);
+        self.spectator_config
+            .count
+            .store(self.spectators.len(), Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+impl Handler<LeaveSpectate> for GameRoom {
+    type Result = ();
+
+    fn handle(&mut self, msg: LeaveSpectate, _ctx: &mut Self::Context) {
+        if self.spectators.remove(&msg.spectator_id).is_some() {
+            log::info!("Spectator {} left room {}", msg.spectator_id, self.id);
+            self.spectator_config
+                .count
+                .store(self.spectators.len(), Ordering::Relaxed);
+        }
+    }
+}
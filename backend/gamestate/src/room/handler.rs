@@ -0,0 +1,100 @@
+use crate::{room::RoomSocket, State};
+use actix_web::{web, Error as ActixError, HttpRequest, HttpResponse};
+use actix_web_actors::ws;
+use serde::{Deserialize, Serialize};
+use shine_core::{
+    kernel::response::{APIError, APIResult},
+    requestinfo::TokenIdentity,
+};
+
+/// Scope required to manage the game servers
+const FLEET_SCOPE: &str = "fleet";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateRoomParams {
+    pub simulation: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateRoomResult {
+    pub room_id: String,
+    /// The region hosting the room
+    pub region: String,
+    /// Root of the api of the game server hosting the room, None if the room is hosted by this service
+    pub endpoint: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DrainParams {
+    /// Drain all the servers if not given
+    #[serde(default)]
+    pub server_id: Option<String>,
+}
+
+pub async fn create_room(
+    state: web::Data<State>,
+    _identity: TokenIdentity,
+    params: web::Json<CreateRoomParams>,
+) -> APIResult {
+    let room = state.allocator().allocate(&params.simulation).await?;
+    let region = state.regions().local_region().to_owned();
+    Ok(HttpResponse::Ok().json(CreateRoomResult {
+        room_id: room.room_id,
+        region,
+        endpoint: room.endpoint,
+    }))
+}
+
+/// Load of the game servers hosting the rooms
+pub async fn get_fleet_health(state: web::Data<State>, identity: TokenIdentity) -> APIResult {
+    if !identity.has_scope(FLEET_SCOPE) {
+        return Err(APIError::Forbidden);
+    }
+    let health = state.allocator().health().await?;
+    Ok(HttpResponse::Ok().json(health))
+}
+
+/// Stop allocating rooms on a game server before it is shut down
+pub async fn drain_fleet(
+    state: web::Data<State>,
+    identity: TokenIdentity,
+    params: web::Json<DrainParams>,
+) -> APIResult {
+    if !identity.has_scope(FLEET_SCOPE) {
+        return Err(APIError::Forbidden);
+    }
+    state.allocator().drain(params.server_id.as_deref()).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Connect to a room over a websocket
+pub async fn room_socket(
+    req: HttpRequest,
+    stream: web::Payload,
+    state: web::Data<State>,
+    identity: TokenIdentity,
+    room_id: web::Path<String>,
+) -> Result<HttpResponse, ActixError> {
+    ws::start(
+        RoomSocket::new(room_id.into_inner(), identity.user_id, state.rooms().clone()),
+        &req,
+        stream,
+    )
+}
+
+/// Connect to a room as a spectator over a websocket
+pub async fn room_spectate_socket(
+    req: HttpRequest,
+    stream: web::Payload,
+    state: web::Data<State>,
+    identity: TokenIdentity,
+    room_id: web::Path<String>,
+) -> Result<HttpResponse, ActixError> {
+    ws::start(
+        RoomSocket::new_spectator(room_id.into_inner(), identity.user_id, state.rooms().clone()),
+        &req,
+        stream,
+    )
+}
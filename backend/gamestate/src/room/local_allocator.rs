@@ -0,0 +1,65 @@
+use crate::room::{AllocatorFuture, FleetHealth, RoomAllocation, RoomAllocator, RoomError, RoomManager, ServerHealth};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+const LOCAL_SERVER_ID: &str = "local";
+
+/// Allocator running the rooms in the process of the service through the room manager
+pub struct LocalRoomAllocator {
+    rooms: RoomManager,
+    draining: Arc<AtomicBool>,
+}
+
+impl LocalRoomAllocator {
+    pub fn new(rooms: RoomManager) -> LocalRoomAllocator {
+        LocalRoomAllocator {
+            rooms,
+            draining: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    fn server_health(&self) -> ServerHealth {
+        ServerHealth {
+            server_id: LOCAL_SERVER_ID.to_owned(),
+            healthy: true,
+            draining: self.draining.load(Ordering::Relaxed),
+            rooms: self.rooms.room_count(),
+            max_rooms: self.rooms.max_rooms(),
+        }
+    }
+}
+
+impl RoomAllocator for LocalRoomAllocator {
+    fn allocate<'a>(&'a self, simulation: &'a str) -> AllocatorFuture<'a, RoomAllocation> {
+        Box::pin(async move {
+            if self.draining.load(Ordering::Relaxed) {
+                return Err(RoomError::Draining);
+            }
+            let room_id = self.rooms.create_room(simulation)?;
+            Ok(RoomAllocation {
+                room_id,
+                server_id: None,
+                endpoint: None,
+            })
+        })
+    }
+
+    fn health(&self) -> AllocatorFuture<'_, FleetHealth> {
+        Box::pin(async move { Ok(FleetHealth::from_servers(vec![self.server_health()])) })
+    }
+
+    fn drain<'a>(&'a self, server_id: Option<&'a str>) -> AllocatorFuture<'a, ()> {
+        Box::pin(async move {
+            match server_id {
+                None | Some(LOCAL_SERVER_ID) => {
+                    log::info!("Draining the local rooms");
+                    self.draining.store(true, Ordering::Relaxed);
+                    Ok(())
+                }
+                Some(server_id) => Err(RoomError::UnknownServer(server_id.to_owned())),
+            }
+        })
+    }
+}
@@ -0,0 +1,226 @@
+use crate::room::{
+    AntiCheat, AntiCheatConfig, AuditLogReportSink, CheatReportSink, GameRoom, JoinRoom, LeaveRoom, LeaveSpectate,
+    MetricsReportSink, RoomError, RoomInput, RoomSimulation, RoomUpdate, SharedStateSimulation, SpectateRoom,
+    SpectatorConfig, SHARED_STATE_SIMULATION,
+};
+use actix::{Actor, Addr, Recipient};
+use rand::{self, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use shine_core::metrics::Metrics;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+const ROOM_ID_LEN: usize = 12;
+const ROOM_ID_ABC: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+
+type SimulationFactory = Box<dyn Fn() -> Box<dyn RoomSimulation> + Send + Sync>;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RoomConfig {
+    /// Number of simulation ticks in a second
+    pub tick_rate: u32,
+    /// Maximum number of the concurrent rooms
+    pub max_rooms: usize,
+    #[serde(default)]
+    pub anti_cheat: AntiCheatConfig,
+    /// Delay of the spectator stream in milliseconds
+    #[serde(default = "RoomConfig::default_spectator_delay_ms")]
+    pub spectator_delay_ms: u64,
+    /// Maximum number of the spectators in a room
+    #[serde(default = "RoomConfig::default_max_spectators")]
+    pub max_spectators: usize,
+}
+
+impl RoomConfig {
+    fn default_spectator_delay_ms() -> u64 {
+        2000
+    }
+
+    fn default_max_spectators() -> usize {
+        32
+    }
+}
+
+impl Default for RoomConfig {
+    fn default() -> RoomConfig {
+        RoomConfig {
+            tick_rate: 20,
+            max_rooms: 100,
+            anti_cheat: AntiCheatConfig::default(),
+            spectator_delay_ms: RoomConfig::default_spectator_delay_ms(),
+            max_spectators: RoomConfig::default_max_spectators(),
+        }
+    }
+}
+
+struct RoomEntry {
+    addr: Addr<GameRoom>,
+    spectators: Arc<AtomicUsize>,
+}
+
+/// Create and look up the game rooms. The rooms are actors running on the arbiter (worker) that
+/// created them, they are accessible from all the workers through the manager.
+#[derive(Clone)]
+pub struct RoomManager {
+    config: Arc<RoomConfig>,
+    simulations: Arc<HashMap<String, SimulationFactory>>,
+    report_sinks: Arc<Vec<Arc<dyn CheatReportSink>>>,
+    rooms: Arc<Mutex<HashMap<String, RoomEntry>>>,
+}
+
+impl RoomManager {
+    /// Create the manager, the anti-cheat reports are written into the audit log and counted in the metrics
+    pub fn new(config: &RoomConfig, metrics: &Metrics) -> RoomManager {
+        let mut simulations: HashMap<String, SimulationFactory> = HashMap::new();
+        simulations.insert(
+            SHARED_STATE_SIMULATION.to_owned(),
+            Box::new(|| Box::new(SharedStateSimulation::default())),
+        );
+
+        let report_sinks: Vec<Arc<dyn CheatReportSink>> = vec![
+            Arc::new(AuditLogReportSink),
+            Arc::new(MetricsReportSink::new(metrics.clone())),
+        ];
+
+        RoomManager {
+            config: Arc::new(config.clone()),
+            simulations: Arc::new(simulations),
+            report_sinks: Arc::new(report_sinks),
+            rooms: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn generate_room_id(&self) -> String {
+        let mut rng = rand::thread_rng();
+        String::from_utf8(
+            (0..ROOM_ID_LEN)
+                .map(|_| *ROOM_ID_ABC.choose(&mut rng).unwrap())
+                .collect::<Vec<_>>(),
+        )
+        .unwrap()
+    }
+
+    pub fn max_rooms(&self) -> usize {
+        self.config.max_rooms
+    }
+
+    /// Number of the running rooms
+    pub fn room_count(&self) -> usize {
+        let mut rooms = self.rooms.lock().unwrap();
+        rooms.retain(|_, room| room.addr.connected());
+        rooms.len()
+    }
+
+    /// Start a new room running the given simulation. The room is stopped when the last player leaves.
+    pub fn create_room(&self, simulation: &str) -> Result<String, RoomError> {
+        let factory = self
+            .simulations
+            .get(simulation)
+            .ok_or_else(|| RoomError::UnknownSimulation(simulation.to_owned()))?;
+
+        let mut rooms = self.rooms.lock().unwrap();
+        rooms.retain(|_, room| room.addr.connected());
+        if rooms.len() >= self.config.max_rooms {
+            return Err(RoomError::RoomLimit);
+        }
+
+        let room_id = self.generate_room_id();
+        let tick_interval = Duration::from_secs(1) / self.config.tick_rate.max(1);
+        let anti_cheat = AntiCheat::new(
+            &room_id,
+            tick_interval,
+            &self.config.anti_cheat,
+            self.report_sinks.clone(),
+        );
+        let spectators = Arc::new(AtomicUsize::new(0));
+        let spectator_config = SpectatorConfig {
+            delay_ticks: self.config.spectator_delay_ms * u64::from(self.config.tick_rate.max(1)) / 1000,
+            max_spectators: self.config.max_spectators,
+            count: spectators.clone(),
+        };
+        let addr = GameRoom::new(room_id.clone(), tick_interval, factory(), anti_cheat, spectator_config)?.start();
+        rooms.insert(room_id.clone(), RoomEntry { addr, spectators });
+        log::info!("Room {} created with simulation {}", room_id, simulation);
+        Ok(room_id)
+    }
+
+    fn find_room(&self, room_id: &str) -> Result<Addr<GameRoom>, RoomError> {
+        let rooms = self.rooms.lock().unwrap();
+        match rooms.get(room_id) {
+            Some(room) if room.addr.connected() => Ok(room.addr.clone()),
+            _ => Err(RoomError::RoomNotFound),
+        }
+    }
+
+    pub async fn join_room(
+        &self,
+        room_id: &str,
+        player_id: &str,
+        recipient: Recipient<RoomUpdate>,
+    ) -> Result<(), RoomError> {
+        let room = self.find_room(room_id)?;
+        room.send(JoinRoom {
+            player_id: player_id.to_owned(),
+            recipient,
+        })
+        .await
+        .map_err(|_| RoomError::RoomNotFound)?
+    }
+
+    pub fn leave_room(&self, room_id: &str, player_id: &str) {
+        if let Ok(room) = self.find_room(room_id) {
+            room.do_send(LeaveRoom {
+                player_id: player_id.to_owned(),
+            });
+        }
+    }
+
+    /// Number of the spectators watching a room, 0 for unknown rooms
+    pub fn spectator_count(&self, room_id: &str) -> usize {
+        let rooms = self.rooms.lock().unwrap();
+        match rooms.get(room_id) {
+            Some(room) if room.addr.connected() => room.spectators.load(Ordering::Relaxed),
+            _ => 0,
+        }
+    }
+
+    /// Join a room as a read-only spectator receiving the delayed state
+    pub async fn spectate_room(
+        &self,
+        room_id: &str,
+        spectator_id: &str,
+        recipient: Recipient<RoomUpdate>,
+    ) -> Result<(), RoomError> {
+        let room = self.find_room(room_id)?;
+        room.send(SpectateRoom {
+            spectator_id: spectator_id.to_owned(),
+            recipient,
+        })
+        .await
+        .map_err(|_| RoomError::RoomNotFound)?
+    }
+
+    pub fn leave_spectate(&self, room_id: &str, spectator_id: &str) {
+        if let Ok(room) = self.find_room(room_id) {
+            room.do_send(LeaveSpectate {
+                spectator_id: spectator_id.to_owned(),
+            });
+        }
+    }
+
+    pub fn send_input(&self, room_id: &str, player_id: &str, input: Value) -> Result<(), RoomError> {
+        let room = self.find_room(room_id)?;
+        room.do_send(RoomInput {
+            player_id: player_id.to_owned(),
+            input,
+        });
+        Ok(())
+    }
+}
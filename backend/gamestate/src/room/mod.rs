@@ -0,0 +1,27 @@
+mod allocator;
+mod anti_cheat;
+mod cheat_checks;
+mod cheat_report;
+mod error;
+mod external_allocator;
+mod game_room;
+mod handler;
+mod local_allocator;
+mod manager;
+mod room_state;
+mod shared_state;
+mod socket;
+
+pub use self::allocator::*;
+pub use self::anti_cheat::*;
+pub use self::cheat_checks::*;
+pub use self::cheat_report::*;
+pub use self::error::*;
+pub use self::external_allocator::*;
+pub use self::game_room::*;
+pub use self::handler::*;
+pub use self::local_allocator::*;
+pub use self::manager::*;
+pub use self::room_state::*;
+pub use self::shared_state::*;
+pub use self::socket::*;
@@ -0,0 +1,90 @@
+use crate::room::{ReasonCode, RoomError};
+use actix::{Message, Recipient};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// State sent to the clients of a room
+#[derive(Clone, Debug, Serialize, Deserialize, Message)]
+#[serde(tag = "type", rename_all = "camelCase")]
+#[rtype(result = "()")]
+pub enum RoomUpdate {
+    /// The full state, sent when a client joins
+    Snapshot { tick: u64, state: Value },
+    /// Changes since the previous tick as a json merge patch (RFC 7386)
+    Delta { tick: u64, patch: Value },
+    /// The player was removed from the room by the anti-cheat validation
+    Kicked { reason: ReasonCode },
+    /// The room was stopped
+    Closed,
+}
+
+/// A client joins a room
+#[derive(Message)]
+#[rtype(result = "Result<(), RoomError>")]
+pub struct JoinRoom {
+    pub player_id: String,
+    pub recipient: Recipient<RoomUpdate>,
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct LeaveRoom {
+    pub player_id: String,
+}
+
+/// A read-only client joins a room, the state is received with the configured delay
+#[derive(Message)]
+#[rtype(result = "Result<(), RoomError>")]
+pub struct SpectateRoom {
+    pub spectator_id: String,
+    pub recipient: Recipient<RoomUpdate>,
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct LeaveSpectate {
+    pub spectator_id: String,
+}
+
+/// Input of a player, applied before the next tick
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct RoomInput {
+    pub player_id: String,
+    pub input: Value,
+}
+
+/// Compute the json merge patch (RFC 7386) transforming the previous state into the current one.
+/// None is returned if the states are identical.
+pub fn state_delta(previous: &Value, current: &Value) -> Option<Value> {
+    match (previous, current) {
+        (Value::Object(previous), Value::Object(current)) => {
+            let mut patch = Map::new();
+            for (key, value) in current {
+                match previous.get(key) {
+                    Some(old) => {
+                        if let Some(delta) = state_delta(old, value) {
+                            patch.insert(key.clone(), delta);
+                        }
+                    }
+                    None => {
+                        patch.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+            for key in previous.keys() {
+                if !current.contains_key(key) {
+                    patch.insert(key.clone(), Value::Null);
+                }
+            }
+            if patch.is_empty() {
+                None
+            } else {
+                Some(Value::Object(patch))
+            }
+        }
+        (previous, current) if previous == current => None,
+        // scalars, arrays and values of different type are replaced
+        (_, current) => Some(current.clone()),
+    }
+}
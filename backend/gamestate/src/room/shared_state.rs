@@ -0,0 +1,65 @@
+use crate::room::RoomSimulation;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use shine_ecs::{
+    resources::{ResMut, Resources},
+    scheduler::{IntoSystem, TaskGroup},
+    ECSError,
+};
+use std::collections::BTreeMap;
+
+/// Name of the shared state simulation
+pub const SHARED_STATE_SIMULATION: &str = "shared";
+
+/// The last input of the players
+#[derive(Default, Debug, Serialize, Deserialize)]
+pub struct PlayerStates(pub BTreeMap<String, Value>);
+
+/// Number of simulated ticks
+#[derive(Default, Debug)]
+pub struct SimulationTick(pub u64);
+
+fn advance_tick(mut tick: ResMut<SimulationTick>) -> Result<TaskGroup, ECSError> {
+    tick.0 += 1;
+    Ok(TaskGroup::default())
+}
+
+/// Simulation for prototypes: the last input of each player is shared with the other players.
+#[derive(Default)]
+pub struct SharedStateSimulation;
+
+impl RoomSimulation for SharedStateSimulation {
+    fn init(&mut self, resources: &mut Resources) -> Result<TaskGroup, ECSError> {
+        resources.register_with_instance(PlayerStates::default())?;
+        resources.register_with_instance(SimulationTick::default())?;
+        Ok(TaskGroup::from_task(advance_tick.into_system()))
+    }
+
+    fn join(&mut self, resources: &Resources, player_id: &str) -> Result<(), ECSError> {
+        let _ = resources
+            .get_mut::<PlayerStates>()?
+            .0
+            .insert(player_id.to_owned(), json!({}));
+        Ok(())
+    }
+
+    fn leave(&mut self, resources: &Resources, player_id: &str) -> Result<(), ECSError> {
+        let _ = resources.get_mut::<PlayerStates>()?.0.remove(player_id);
+        Ok(())
+    }
+
+    fn apply_input(&mut self, resources: &Resources, player_id: &str, input: Value) -> Result<(), ECSError> {
+        let _ = resources
+            .get_mut::<PlayerStates>()?
+            .0
+            .insert(player_id.to_owned(), input);
+        Ok(())
+    }
+
+    fn snapshot(&self, resources: &Resources) -> Result<Value, ECSError> {
+        Ok(json!({
+            "tick": resources.get::<SimulationTick>()?.0,
+            "players": resources.get::<PlayerStates>()?.0,
+        }))
+    }
+}
@@ -0,0 +1,127 @@
+use crate::room::{RoomManager, RoomUpdate};
+use actix::{Actor, ActorContext, ActorFuture, AsyncContext, ContextFutureSpawner, Handler, StreamHandler, WrapFuture};
+use actix_web_actors::ws;
+
+/// Websocket connection of a player to a game room. The text messages are json inputs forwarded to the room,
+/// the room state is received as snapshot and delta messages. Spectators receive the delayed state and
+/// their inputs are ignored.
+pub struct RoomSocket {
+    room_id: String,
+    player_id: String,
+    spectator: bool,
+    manager: RoomManager,
+}
+
+impl RoomSocket {
+    pub fn new(room_id: String, player_id: String, manager: RoomManager) -> RoomSocket {
+        RoomSocket {
+            room_id,
+            player_id,
+            spectator: false,
+            manager,
+        }
+    }
+
+    pub fn new_spectator(room_id: String, spectator_id: String, manager: RoomManager) -> RoomSocket {
+        RoomSocket {
+            room_id,
+            player_id: spectator_id,
+            spectator: true,
+            manager,
+        }
+    }
+}
+
+impl Actor for RoomSocket {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let manager = self.manager.clone();
+        let room_id = self.room_id.clone();
+        let player_id = self.player_id.clone();
+        let recipient = ctx.address().recipient();
+        let spectator = self.spectator;
+        async move {
+            if spectator {
+                manager.spectate_room(&room_id, &player_id, recipient).await
+            } else {
+                manager.join_room(&room_id, &player_id, recipient).await
+            }
+        }
+        .into_actor(self)
+        .map(|result, socket, ctx| {
+            if let Err(err) = result {
+                log::info!(
+                    "Player {} failed to join room {}: {}",
+                    socket.player_id,
+                    socket.room_id,
+                    err
+                );
+                ctx.close(None);
+                ctx.stop();
+            }
+        })
+        .wait(ctx);
+    }
+
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        if self.spectator {
+            self.manager.leave_spectate(&self.room_id, &self.player_id);
+        } else {
+            self.manager.leave_room(&self.room_id, &self.player_id);
+        }
+    }
+}
+
+impl Handler<RoomUpdate> for RoomSocket {
+    type Result = ();
+
+    fn handle(&mut self, update: RoomUpdate, ctx: &mut Self::Context) {
+        match serde_json::to_string(&update) {
+            Ok(text) => ctx.text(text),
+            Err(err) => log::warn!("Failed to serialize room update: {:?}", err),
+        }
+        match update {
+            RoomUpdate::Kicked { reason } => {
+                ctx.close(Some(ws::CloseReason {
+                    code: ws::CloseCode::Policy,
+                    description: Some(reason.as_str().to_owned()),
+                }));
+                ctx.stop();
+            }
+            RoomUpdate::Closed => {
+                ctx.close(Some(ws::CloseCode::Normal.into()));
+                ctx.stop();
+            }
+            _ => {}
+        }
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for RoomSocket {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Text(_)) if self.spectator => {
+                log::debug!("Input from spectator {} ignored", self.player_id);
+            }
+            Ok(ws::Message::Text(text)) => match serde_json::from_str(&text) {
+                Ok(input) => {
+                    if self.manager.send_input(&self.room_id, &self.player_id, input).is_err() {
+                        ctx.stop();
+                    }
+                }
+                Err(err) => log::info!("Invalid input from {}: {:?}", self.player_id, err),
+            },
+            Ok(ws::Message::Ping(msg)) => ctx.pong(&msg),
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            Ok(_) => {}
+            Err(err) => {
+                log::info!("Room {} socket error: {:?}", self.room_id, err);
+                ctx.stop();
+            }
+        }
+    }
+}
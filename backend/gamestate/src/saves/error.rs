@@ -0,0 +1,39 @@
+use shine_core::kernel::response::APIError;
+use sqlx::Error as SqlxError;
+use std::io::Error as IOError;
+
+#[derive(Debug)]
+pub enum SaveError {
+    /// Database related error
+    Internal(String),
+    BadRequest(String),
+    SaveNotFound,
+    /// The version of the stored save differs from the expected one
+    VersionMismatch,
+    /// Size or slot count limit reached
+    QuotaExceeded(String),
+}
+
+impl From<SqlxError> for SaveError {
+    fn from(err: SqlxError) -> SaveError {
+        SaveError::Internal(format!("Sql error: {}", err))
+    }
+}
+
+impl From<IOError> for SaveError {
+    fn from(err: IOError) -> SaveError {
+        SaveError::Internal(format!("Compression error: {}", err))
+    }
+}
+
+impl From<SaveError> for APIError {
+    fn from(err: SaveError) -> APIError {
+        match err {
+            SaveError::BadRequest(msg) => APIError::BadRequest(msg),
+            SaveError::SaveNotFound => APIError::RespourceNotFound("Save not found".to_owned()),
+            SaveError::VersionMismatch => APIError::Conflict("Save modified concurrently".to_owned()),
+            SaveError::QuotaExceeded(msg) => APIError::BadRequest(msg),
+            err => APIError::Internal(format!("{:?}", err)),
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use crate::{
+    saves::{SaveCondition, SaveError},
+    State,
+};
+use actix_web::{
+    http::{header, HeaderMap},
+    web, HttpRequest, HttpResponse,
+};
+use shine_core::{kernel::response::APIResult, requestinfo::TokenIdentity};
+
+const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+
+fn etag(version: u64) -> String {
+    format!("\"{}\"", version)
+}
+
+/// Parse the version of an etag header, weak etags are also accepted
+fn parse_etag(headers: &HeaderMap, name: header::HeaderName) -> Result<Option<String>, SaveError> {
+    match headers.get(name) {
+        Some(value) => {
+            let value = value
+                .to_str()
+                .map_err(|_| SaveError::BadRequest("Invalid etag".to_owned()))?;
+            Ok(Some(value.trim().trim_start_matches("W/").trim_matches('"').to_owned()))
+        }
+        None => Ok(None),
+    }
+}
+
+fn parse_version(etag: &str) -> Result<u64, SaveError> {
+    etag.parse()
+        .map_err(|_| SaveError::BadRequest(format!("Invalid etag: {}", etag)))
+}
+
+/// List the save slots of the user of the bearer token
+pub async fn list_saves(state: web::Data<State>, identity: TokenIdentity) -> APIResult {
+    let saves = state.saves().list_saves(&identity.user_id).await?;
+    Ok(HttpResponse::Ok().json(saves))
+}
+
+/// Download a save, NOT_MODIFIED is returned if the If-None-Match header matches the stored version
+pub async fn get_save(
+    req: HttpRequest,
+    state: web::Data<State>,
+    identity: TokenIdentity,
+    slot: web::Path<String>,
+) -> APIResult {
+    let if_none_match = parse_etag(req.headers(), header::IF_NONE_MATCH)?;
+    let (info, data) = state.saves().get_save(&identity.user_id, &slot).await?;
+
+    if if_none_match == Some(info.version.to_string()) {
+        return Ok(HttpResponse::NotModified()
+            .header(header::ETAG, etag(info.version))
+            .finish());
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type(info.content_type)
+        .header(header::ETAG, etag(info.version))
+        .body(data))
+}
+
+/// Upload a save. With an If-Match header the save is replaced only if the stored version matches,
+/// with If-None-Match: * the save is created only if the slot is empty. A CONFLICT response is
+/// returned if the precondition fails.
+pub async fn put_save(
+    req: HttpRequest,
+    state: web::Data<State>,
+    identity: TokenIdentity,
+    slot: web::Path<String>,
+    body: web::Bytes,
+) -> APIResult {
+    let condition = match (
+        parse_etag(req.headers(), header::IF_MATCH)?,
+        parse_etag(req.headers(), header::IF_NONE_MATCH)?,
+    ) {
+        (Some(etag), _) if etag == "*" => SaveCondition::Any,
+        (Some(etag), _) => SaveCondition::IfMatch(parse_version(&etag)?),
+        (None, Some(etag)) if etag == "*" => SaveCondition::IfNotExists,
+        (None, Some(_)) => {
+            return Err(SaveError::BadRequest("Only * is supported for If-None-Match".to_owned()).into())
+        }
+        (None, None) => SaveCondition::Any,
+    };
+
+    let content_type = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or(DEFAULT_CONTENT_TYPE);
+    if content_type.starts_with("application/json") && serde_json::from_slice::<serde_json::Value>(&body).is_err() {
+        return Err(SaveError::BadRequest("Invalid json".to_owned()).into());
+    }
+
+    let info = state
+        .saves()
+        .put_save(&identity.user_id, &slot, content_type, &body, condition)
+        .await?;
+    Ok(HttpResponse::Ok().header(header::ETAG, etag(info.version)).json(info))
+}
+
+/// Delete a save, the If-Match header is checked if present
+pub async fn delete_save(
+    req: HttpRequest,
+    state: web::Data<State>,
+    identity: TokenIdentity,
+    slot: web::Path<String>,
+) -> APIResult {
+    let version = match parse_etag(req.headers(), header::IF_MATCH)? {
+        Some(etag) if etag != "*" => Some(parse_version(&etag)?),
+        _ => None,
+    };
+    state.saves().delete_save(&identity.user_id, &slot, version).await?;
+    Ok(HttpResponse::Ok().finish())
+}
@@ -0,0 +1,220 @@
+use crate::{
+    saves::{MemorySaveStore, PostgresSaveStore, SaveBlob, SaveError, SaveInfo, SaveStore, SaveStoreConfig},
+    GameStateConfig,
+};
+use chrono::Utc;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use shine_core::backoff::{self, Backoff, BackoffError};
+use std::{
+    io::{Read, Write},
+    sync::Arc,
+    time::Duration,
+};
+
+const MAX_SLOT_LEN: usize = 32;
+const MAX_CONTENT_TYPE_LEN: usize = 128;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveConfig {
+    /// Maximum size of a save in bytes (before compression)
+    pub max_size: u64,
+    /// Maximum number of save slots of a user
+    pub max_slots: usize,
+    /// Saves above this size are stored compressed
+    pub compress_threshold: u64,
+}
+
+impl Default for SaveConfig {
+    fn default() -> SaveConfig {
+        SaveConfig {
+            max_size: 1024 * 1024,
+            max_slots: 16,
+            compress_threshold: 1024,
+        }
+    }
+}
+
+/// Precondition of a save upload
+#[derive(Clone, Copy, Debug)]
+pub enum SaveCondition {
+    /// Overwrite the stored save unconditionally
+    Any,
+    /// Replace the save only if the stored version matches (If-Match)
+    IfMatch(u64),
+    /// Create the save only if the slot is empty (If-None-Match: *)
+    IfNotExists,
+}
+
+impl SaveError {
+    pub fn into_backoff(self) -> BackoffError<SaveError> {
+        match self {
+            SaveError::VersionMismatch => BackoffError::Transient(SaveError::VersionMismatch),
+            e => BackoffError::Permanent(e),
+        }
+    }
+}
+
+/// Manage the versioned save slots of the users
+#[derive(Clone)]
+pub struct SaveManager {
+    store: Arc<dyn SaveStore>,
+    config: Arc<SaveConfig>,
+}
+
+impl SaveManager {
+    pub async fn new(config: &GameStateConfig) -> Result<Self, SaveError> {
+        let store: Arc<dyn SaveStore> = match &config.save_store {
+            SaveStoreConfig::Postgres { url, max_connections } => {
+                Arc::new(PostgresSaveStore::new(url, *max_connections).await?)
+            }
+            SaveStoreConfig::Memory => Arc::new(MemorySaveStore::new()),
+        };
+        log::info!("Save store: {:?}", config.save_store);
+
+        Ok(SaveManager {
+            store,
+            config: Arc::new(config.saves.clone()),
+        })
+    }
+
+    pub fn config(&self) -> &SaveConfig {
+        &self.config
+    }
+
+    fn validate_slot(slot: &str) -> Result<(), SaveError> {
+        if slot.is_empty()
+            || slot.len() > MAX_SLOT_LEN
+            || !slot.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(SaveError::BadRequest(format!("Invalid save slot: {}", slot)));
+        }
+        Ok(())
+    }
+
+    fn compress(&self, data: &[u8]) -> Result<(bool, Vec<u8>), SaveError> {
+        if (data.len() as u64) < self.config.compress_threshold {
+            return Ok((false, data.to_vec()));
+        }
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data)?;
+        let compressed = encoder.finish()?;
+        if compressed.len() < data.len() {
+            Ok((true, compressed))
+        } else {
+            Ok((false, data.to_vec()))
+        }
+    }
+
+    fn decompress(blob: SaveBlob) -> Result<Vec<u8>, SaveError> {
+        if !blob.compressed {
+            return Ok(blob.data);
+        }
+        let mut data = Vec::with_capacity(blob.size as usize);
+        GzDecoder::new(&blob.data[..]).read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    pub async fn list_saves(&self, user_id: &str) -> Result<Vec<SaveInfo>, SaveError> {
+        self.store.list_saves(user_id).await
+    }
+
+    /// Return the uncompressed data of a save
+    pub async fn get_save(&self, user_id: &str, slot: &str) -> Result<(SaveInfo, Vec<u8>), SaveError> {
+        Self::validate_slot(slot)?;
+        let blob = self
+            .store
+            .find_save(user_id, slot)
+            .await?
+            .ok_or(SaveError::SaveNotFound)?;
+        let info = SaveInfo::from(&blob);
+        Ok((info, Self::decompress(blob)?))
+    }
+
+    async fn insert_save(&self, blob: SaveBlob) -> Result<SaveBlob, SaveError> {
+        let saves = self.store.list_saves(&blob.user_id).await?;
+        if saves.len() >= self.config.max_slots {
+            return Err(SaveError::QuotaExceeded(format!(
+                "At most {} save slots are allowed",
+                self.config.max_slots
+            )));
+        }
+        self.store.insert_save(blob).await
+    }
+
+    async fn try_overwrite_save(&self, blob: &SaveBlob) -> Result<SaveBlob, BackoffError<SaveError>> {
+        let stored = self
+            .store
+            .find_save(&blob.user_id, &blob.slot)
+            .await
+            .map_err(SaveError::into_backoff)?;
+        match stored {
+            Some(stored) => {
+                let mut blob = blob.clone();
+                blob.version = stored.version;
+                self.store.update_save(blob).await
+            }
+            None => self.insert_save(blob.clone()).await,
+        }
+        .map_err(SaveError::into_backoff)
+    }
+
+    /// Store a save. The data is compressed above the threshold of the configuration.
+    pub async fn put_save(
+        &self,
+        user_id: &str,
+        slot: &str,
+        content_type: &str,
+        data: &[u8],
+        condition: SaveCondition,
+    ) -> Result<SaveInfo, SaveError> {
+        Self::validate_slot(slot)?;
+        if content_type.len() > MAX_CONTENT_TYPE_LEN {
+            return Err(SaveError::BadRequest("Content type too long".to_owned()));
+        }
+        if data.len() as u64 > self.config.max_size {
+            return Err(SaveError::QuotaExceeded(format!(
+                "Save exceeds the size limit of {} bytes",
+                self.config.max_size
+            )));
+        }
+
+        let (compressed, stored_data) = self.compress(data)?;
+        let blob = SaveBlob {
+            user_id: user_id.to_owned(),
+            slot: slot.to_owned(),
+            version: 0,
+            content_type: content_type.to_owned(),
+            size: data.len() as u64,
+            compressed,
+            data: stored_data,
+            modified: Utc::now(),
+        };
+
+        let blob = match condition {
+            SaveCondition::IfNotExists => self.insert_save(blob).await?,
+            SaveCondition::IfMatch(version) => self.store.update_save(SaveBlob { version, ..blob }).await?,
+            SaveCondition::Any => {
+                backoff::Exponential::new(3, Duration::from_millis(10))
+                    .async_execute(|_| self.try_overwrite_save(&blob))
+                    .await?
+            }
+        };
+
+        log::info!(
+            "Save {} of {} stored, version: {}, size: {}, compressed: {}",
+            slot,
+            user_id,
+            blob.version,
+            blob.size,
+            blob.compressed
+        );
+        Ok(SaveInfo::from(&blob))
+    }
+
+    pub async fn delete_save(&self, user_id: &str, slot: &str, version: Option<u64>) -> Result<(), SaveError> {
+        Self::validate_slot(slot)?;
+        self.store.delete_save(user_id, slot, version).await
+    }
+}
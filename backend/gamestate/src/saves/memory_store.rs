@@ -0,0 +1,89 @@
+use crate::saves::{SaveBlob, SaveError, SaveInfo, SaveStore, StoreFuture};
+use std::{collections::HashMap, sync::Mutex};
+
+/// Save store keeping the saves in the memory of the process, for local development and tests.
+#[derive(Default)]
+pub struct MemorySaveStore {
+    saves: Mutex<HashMap<(String, String), SaveBlob>>,
+}
+
+impl MemorySaveStore {
+    pub fn new() -> Self {
+        MemorySaveStore::default()
+    }
+
+    fn with_saves<T, F>(&self, f: F) -> StoreFuture<'_, T>
+    where
+        T: 'static,
+        F: FnOnce(&mut HashMap<(String, String), SaveBlob>) -> Result<T, SaveError>,
+    {
+        let result = {
+            let mut saves = self.saves.lock().unwrap();
+            f(&mut saves)
+        };
+        Box::pin(async move { result })
+    }
+}
+
+impl SaveStore for MemorySaveStore {
+    fn insert_save(&self, mut blob: SaveBlob) -> StoreFuture<'_, SaveBlob> {
+        self.with_saves(move |saves| {
+            let key = (blob.user_id.clone(), blob.slot.clone());
+            if saves.contains_key(&key) {
+                return Err(SaveError::VersionMismatch);
+            }
+            blob.version = 0;
+            saves.insert(key, blob.clone());
+            Ok(blob)
+        })
+    }
+
+    fn update_save(&self, mut blob: SaveBlob) -> StoreFuture<'_, SaveBlob> {
+        self.with_saves(move |saves| {
+            let key = (blob.user_id.clone(), blob.slot.clone());
+            match saves.get_mut(&key) {
+                Some(stored) => {
+                    if stored.version != blob.version {
+                        return Err(SaveError::VersionMismatch);
+                    }
+                    blob.version += 1;
+                    *stored = blob.clone();
+                    Ok(blob)
+                }
+                None => Err(SaveError::SaveNotFound),
+            }
+        })
+    }
+
+    fn find_save<'a>(&'a self, user_id: &'a str, slot: &'a str) -> StoreFuture<'a, Option<SaveBlob>> {
+        self.with_saves(|saves| Ok(saves.get(&(user_id.to_owned(), slot.to_owned())).cloned()))
+    }
+
+    fn list_saves<'a>(&'a self, user_id: &'a str) -> StoreFuture<'a, Vec<SaveInfo>> {
+        self.with_saves(|saves| {
+            let mut infos: Vec<SaveInfo> = saves
+                .values()
+                .filter(|blob| blob.user_id == user_id)
+                .map(SaveInfo::from)
+                .collect();
+            infos.sort_by(|a, b| a.slot.cmp(&b.slot));
+            Ok(infos)
+        })
+    }
+
+    fn delete_save<'a>(&'a self, user_id: &'a str, slot: &'a str, version: Option<u64>) -> StoreFuture<'a, ()> {
+        self.with_saves(move |saves| {
+            let key = (user_id.to_owned(), slot.to_owned());
+            match saves.get(&key) {
+                Some(stored) if version.map(|v| v != stored.version).unwrap_or(false) => {
+                    Err(SaveError::VersionMismatch)
+                }
+                Some(_) => {
+                    saves.remove(&key);
+                    Ok(())
+                }
+                None => Err(SaveError::SaveNotFound),
+            }
+        })
+    }
+}
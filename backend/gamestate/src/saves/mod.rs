@@ -0,0 +1,15 @@
+mod error;
+mod handler;
+mod manager;
+mod memory_store;
+mod postgres_store;
+mod save_blob;
+mod store;
+
+pub use self::error::*;
+pub use self::handler::*;
+pub use self::manager::*;
+pub use self::memory_store::*;
+pub use self::postgres_store::*;
+pub use self::save_blob::*;
+pub use self::store::*;
@@ -0,0 +1,204 @@
+use crate::saves::{SaveBlob, SaveError, SaveInfo, SaveStore, StoreFuture};
+use chrono::{DateTime, Utc};
+use sqlx::{
+    postgres::{PgDatabaseError, PgPool, PgPoolOptions},
+    FromRow,
+};
+
+const SCHEMA: &[&str] = &["CREATE TABLE IF NOT EXISTS saves (
+        user_id TEXT NOT NULL,
+        slot TEXT NOT NULL,
+        version BIGINT NOT NULL,
+        content_type TEXT NOT NULL,
+        size BIGINT NOT NULL,
+        compressed BOOLEAN NOT NULL,
+        data BYTEA NOT NULL,
+        modified TIMESTAMPTZ NOT NULL,
+        CONSTRAINT saves_pkey PRIMARY KEY (user_id, slot)
+    )"];
+
+const SELECT_SAVE: &str = "SELECT user_id, slot, version, content_type, size, compressed, data, modified FROM saves";
+
+#[derive(FromRow)]
+struct SaveRow {
+    user_id: String,
+    slot: String,
+    version: i64,
+    content_type: String,
+    size: i64,
+    compressed: bool,
+    data: Vec<u8>,
+    modified: DateTime<Utc>,
+}
+
+impl SaveRow {
+    fn into_blob(self) -> SaveBlob {
+        SaveBlob {
+            user_id: self.user_id,
+            slot: self.slot,
+            version: self.version as u64,
+            content_type: self.content_type,
+            size: self.size as u64,
+            compressed: self.compressed,
+            data: self.data,
+            modified: self.modified,
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct SaveInfoRow {
+    slot: String,
+    version: i64,
+    content_type: String,
+    size: i64,
+    modified: DateTime<Utc>,
+}
+
+fn map_unique_violation(err: sqlx::Error) -> SaveError {
+    if let sqlx::Error::Database(ref db_err) = err {
+        if let Some(pg_err) = db_err.try_downcast_ref::<PgDatabaseError>() {
+            if pg_err.code() == "23505" && pg_err.constraint() == Some("saves_pkey") {
+                return SaveError::VersionMismatch;
+            }
+        }
+    }
+    SaveError::from(err)
+}
+
+/// Save store using a Postgres database
+#[derive(Clone)]
+pub struct PostgresSaveStore {
+    pool: PgPool,
+}
+
+impl PostgresSaveStore {
+    pub async fn new(url: &str, max_connections: u32) -> Result<Self, SaveError> {
+        let pool = PgPoolOptions::new()
+            .max_connections(max_connections)
+            .connect(url)
+            .await?;
+
+        for statement in SCHEMA {
+            sqlx::query(statement).execute(&pool).await?;
+        }
+
+        Ok(PostgresSaveStore { pool })
+    }
+
+    async fn exists(&self, user_id: &str, slot: &str) -> Result<bool, SaveError> {
+        let (exists,): (bool,) = sqlx::query_as("SELECT EXISTS(SELECT 1 FROM saves WHERE user_id = $1 AND slot = $2)")
+            .bind(user_id)
+            .bind(slot)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(exists)
+    }
+}
+
+impl SaveStore for PostgresSaveStore {
+    fn insert_save(&self, mut blob: SaveBlob) -> StoreFuture<'_, SaveBlob> {
+        Box::pin(async move {
+            blob.version = 0;
+            sqlx::query(
+                "INSERT INTO saves (user_id, slot, version, content_type, size, compressed, data, modified)
+                VALUES ($1, $2, 0, $3, $4, $5, $6, $7)",
+            )
+            .bind(&blob.user_id)
+            .bind(&blob.slot)
+            .bind(&blob.content_type)
+            .bind(blob.size as i64)
+            .bind(blob.compressed)
+            .bind(&blob.data)
+            .bind(blob.modified)
+            .execute(&self.pool)
+            .await
+            .map_err(map_unique_violation)?;
+            Ok(blob)
+        })
+    }
+
+    fn update_save(&self, mut blob: SaveBlob) -> StoreFuture<'_, SaveBlob> {
+        Box::pin(async move {
+            let result = sqlx::query(
+                "UPDATE saves SET version = version + 1, content_type = $4, size = $5, compressed = $6, data = $7, \
+                 modified = $8 WHERE user_id = $1 AND slot = $2 AND version = $3",
+            )
+            .bind(&blob.user_id)
+            .bind(&blob.slot)
+            .bind(blob.version as i64)
+            .bind(&blob.content_type)
+            .bind(blob.size as i64)
+            .bind(blob.compressed)
+            .bind(&blob.data)
+            .bind(blob.modified)
+            .execute(&self.pool)
+            .await?;
+
+            if result.rows_affected() == 0 {
+                return if self.exists(&blob.user_id, &blob.slot).await? {
+                    Err(SaveError::VersionMismatch)
+                } else {
+                    Err(SaveError::SaveNotFound)
+                };
+            }
+            blob.version += 1;
+            Ok(blob)
+        })
+    }
+
+    fn find_save<'a>(&'a self, user_id: &'a str, slot: &'a str) -> StoreFuture<'a, Option<SaveBlob>> {
+        Box::pin(async move {
+            let query = format!("{} WHERE user_id = $1 AND slot = $2", SELECT_SAVE);
+            let row = sqlx::query_as::<_, SaveRow>(&query)
+                .bind(user_id)
+                .bind(slot)
+                .fetch_optional(&self.pool)
+                .await?;
+            Ok(row.map(SaveRow::into_blob))
+        })
+    }
+
+    fn list_saves<'a>(&'a self, user_id: &'a str) -> StoreFuture<'a, Vec<SaveInfo>> {
+        Box::pin(async move {
+            let rows = sqlx::query_as::<_, SaveInfoRow>(
+                "SELECT slot, version, content_type, size, modified FROM saves WHERE user_id = $1 ORDER BY slot",
+            )
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await?;
+            Ok(rows
+                .into_iter()
+                .map(|row| SaveInfo {
+                    slot: row.slot,
+                    version: row.version as u64,
+                    content_type: row.content_type,
+                    size: row.size as u64,
+                    modified: row.modified,
+                })
+                .collect())
+        })
+    }
+
+    fn delete_save<'a>(&'a self, user_id: &'a str, slot: &'a str, version: Option<u64>) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            let result = sqlx::query(
+                "DELETE FROM saves WHERE user_id = $1 AND slot = $2 AND ($3::BIGINT IS NULL OR version = $3)",
+            )
+            .bind(user_id)
+            .bind(slot)
+            .bind(version.map(|v| v as i64))
+            .execute(&self.pool)
+            .await?;
+
+            if result.rows_affected() == 0 {
+                return if self.exists(user_id, slot).await? {
+                    Err(SaveError::VersionMismatch)
+                } else {
+                    Err(SaveError::SaveNotFound)
+                };
+            }
+            Ok(())
+        })
+    }
+}
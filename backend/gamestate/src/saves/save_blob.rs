@@ -0,0 +1,40 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// A stored save slot of a user. The data is stored compressed if the compressed flag is set.
+#[derive(Clone, Debug)]
+pub struct SaveBlob {
+    pub user_id: String,
+    pub slot: String,
+    /// Incremented on each update, exposed as the etag of the save
+    pub version: u64,
+    pub content_type: String,
+    /// Size of the uncompressed data
+    pub size: u64,
+    pub compressed: bool,
+    pub data: Vec<u8>,
+    pub modified: DateTime<Utc>,
+}
+
+/// Public information of a save slot
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveInfo {
+    pub slot: String,
+    pub version: u64,
+    pub content_type: String,
+    pub size: u64,
+    pub modified: DateTime<Utc>,
+}
+
+impl From<&SaveBlob> for SaveInfo {
+    fn from(blob: &SaveBlob) -> SaveInfo {
+        SaveInfo {
+            slot: blob.slot.clone(),
+            version: blob.version,
+            content_type: blob.content_type.clone(),
+            size: blob.size,
+            modified: blob.modified,
+        }
+    }
+}
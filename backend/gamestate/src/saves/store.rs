@@ -0,0 +1,42 @@
+use crate::saves::{SaveBlob, SaveError, SaveInfo};
+use serde::{Deserialize, Serialize};
+use std::{future::Future, pin::Pin};
+
+pub type StoreFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, SaveError>> + 'a>>;
+
+/// Storage backend of the saves. Table storage is not offered as the entity size limit is below
+/// the usual save sizes.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum SaveStoreConfig {
+    Postgres {
+        url: String,
+        max_connections: u32,
+    },
+    /// Keep the saves in memory, for local development and tests
+    Memory,
+}
+
+impl Default for SaveStoreConfig {
+    fn default() -> Self {
+        SaveStoreConfig::Memory
+    }
+}
+
+/// Persistence of the saves. The versions are checked by the store to implement the optimistic concurrency.
+pub trait SaveStore {
+    /// Insert a new save with version 0, returns VersionMismatch if the slot is already in use
+    fn insert_save(&self, blob: SaveBlob) -> StoreFuture<'_, SaveBlob>;
+
+    /// Replace the save if the stored version matches the version of the blob, the version is incremented.
+    /// Returns VersionMismatch if the versions differ, SaveNotFound if the slot is empty.
+    fn update_save(&self, blob: SaveBlob) -> StoreFuture<'_, SaveBlob>;
+
+    fn find_save<'a>(&'a self, user_id: &'a str, slot: &'a str) -> StoreFuture<'a, Option<SaveBlob>>;
+
+    /// List the saves of a user without the data
+    fn list_saves<'a>(&'a self, user_id: &'a str) -> StoreFuture<'a, Vec<SaveInfo>>;
+
+    /// Delete a save, if a version is given it has to match the stored version
+    fn delete_save<'a>(&'a self, user_id: &'a str, slot: &'a str, version: Option<u64>) -> StoreFuture<'a, ()>;
+}
@@ -0,0 +1,53 @@
+use crate::{
+    settings::{SettingsError, SettingsStore, StoreFuture, UserSettings, UserSettingsData},
+    GameStateConfig,
+};
+use azure_sdk_storage_table::{CloudTable, TableClient};
+use shine_core::azure_utils;
+
+/// Settings store using Azure table storage
+#[derive(Clone)]
+pub struct AzureSettingsStore {
+    db: CloudTable,
+}
+
+impl AzureSettingsStore {
+    pub async fn new(config: &GameStateConfig) -> Result<Self, SettingsError> {
+        let client = TableClient::new(&config.storage_account, &config.storage_account_key)?;
+        let db = CloudTable::new(client, "settings");
+        db.create_if_not_exists().await?;
+
+        Ok(AzureSettingsStore { db })
+    }
+}
+
+impl SettingsStore for AzureSettingsStore {
+    fn insert_settings(&self, settings: UserSettings) -> StoreFuture<'_, UserSettings> {
+        Box::pin(async move {
+            match self.db.insert_entity(settings.into_entity()).await {
+                Ok(settings) => Ok(UserSettings::from_entity(settings)),
+                Err(err) if azure_utils::is_conflict_error(&err) => Err(SettingsError::SettingsConflict),
+                Err(err) => Err(err.into()),
+            }
+        })
+    }
+
+    fn update_settings(&self, settings: UserSettings) -> StoreFuture<'_, UserSettings> {
+        Box::pin(async move {
+            // the etag of the entity is checked
+            match self.db.update_entity(settings.into_entity()).await {
+                Ok(settings) => Ok(UserSettings::from_entity(settings)),
+                Err(err) if azure_utils::is_precodition_error(&err) => Err(SettingsError::SettingsConflict),
+                Err(err) => Err(err.into()),
+            }
+        })
+    }
+
+    fn find_settings<'a>(&'a self, user_id: &'a str) -> StoreFuture<'a, Option<UserSettings>> {
+        Box::pin(async move {
+            let (p, r) = UserSettings::entity_keys(user_id);
+            let settings = self.db.get::<UserSettingsData>(&p, &r, None).await?;
+            Ok(settings.map(UserSettings::from_entity))
+        })
+    }
+}
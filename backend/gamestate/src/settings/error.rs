@@ -0,0 +1,36 @@
+use azure_sdk_core::errors::AzureError;
+use serde_json::Error as JsonError;
+use shine_core::kernel::response::APIError;
+
+#[derive(Debug)]
+pub enum SettingsError {
+    /// Database related error
+    Internal(String),
+    BadRequest(String),
+    SettingsNotFound,
+    /// The settings were modified concurrently
+    SettingsConflict,
+}
+
+impl From<AzureError> for SettingsError {
+    fn from(err: AzureError) -> SettingsError {
+        SettingsError::Internal(format!("Azure error: {:?}", err))
+    }
+}
+
+impl From<JsonError> for SettingsError {
+    fn from(err: JsonError) -> SettingsError {
+        SettingsError::Internal(format!("Json error: {:?}", err))
+    }
+}
+
+impl From<SettingsError> for APIError {
+    fn from(err: SettingsError) -> APIError {
+        match err {
+            SettingsError::BadRequest(msg) => APIError::BadRequest(msg),
+            SettingsError::SettingsNotFound => APIError::RespourceNotFound("Settings not found".to_owned()),
+            SettingsError::SettingsConflict => APIError::Conflict("Settings modified concurrently".to_owned()),
+            err => APIError::Internal(format!("{:?}", err)),
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use crate::{
+    settings::{SettingsInfo, SettingsSync},
+    State,
+};
+use actix_web::{web, HttpResponse};
+use shine_core::{kernel::response::APIResult, requestinfo::TokenIdentity};
+
+/// Return the settings of the user of the bearer token
+pub async fn get_settings(state: web::Data<State>, identity: TokenIdentity) -> APIResult {
+    let settings = state.settings().get_settings(&identity.user_id).await?;
+    Ok(HttpResponse::Ok().json(settings))
+}
+
+/// Upload the settings of the user of the bearer token. If the stored settings are newer,
+/// a CONFLICT response is returned with the stored settings.
+pub async fn put_settings(
+    state: web::Data<State>,
+    identity: TokenIdentity,
+    settings: web::Json<SettingsInfo>,
+) -> APIResult {
+    match state
+        .settings()
+        .sync_settings(&identity.user_id, settings.into_inner())
+        .await?
+    {
+        SettingsSync::Accepted(settings) => Ok(HttpResponse::Ok().json(settings)),
+        SettingsSync::Outdated(settings) => Ok(HttpResponse::Conflict().json(settings)),
+    }
+}
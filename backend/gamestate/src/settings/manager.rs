@@ -0,0 +1,110 @@
+use crate::{
+    settings::{
+        AzureSettingsStore, MemorySettingsStore, SettingsError, SettingsInfo, SettingsStore, SettingsStoreConfig,
+        UserSettings,
+    },
+    GameStateConfig,
+};
+use shine_core::backoff::{self, Backoff, BackoffError};
+use std::{sync::Arc, time::Duration};
+
+/// Result of a settings upload
+#[derive(Debug)]
+pub enum SettingsSync {
+    /// The uploaded settings are stored
+    Accepted(SettingsInfo),
+    /// The stored settings are newer than the uploaded ones, the client shall apply the returned settings
+    Outdated(SettingsInfo),
+}
+
+impl SettingsError {
+    pub fn into_backoff(self) -> BackoffError<SettingsError> {
+        match self {
+            SettingsError::SettingsConflict => BackoffError::Transient(SettingsError::SettingsConflict),
+            e => BackoffError::Permanent(e),
+        }
+    }
+}
+
+fn into_info(settings: &UserSettings) -> Result<SettingsInfo, SettingsError> {
+    Ok(SettingsInfo {
+        settings: serde_json::from_str(&settings.data().settings)?,
+        modified: settings.modified(),
+    })
+}
+
+/// Manage the user settings. On conflicting uploads the settings with the latest modification time win.
+#[derive(Clone)]
+pub struct SettingsManager {
+    store: Arc<dyn SettingsStore>,
+}
+
+impl SettingsManager {
+    pub async fn new(config: &GameStateConfig) -> Result<Self, SettingsError> {
+        let store: Arc<dyn SettingsStore> = match &config.settings_store {
+            SettingsStoreConfig::Azure => Arc::new(AzureSettingsStore::new(config).await?),
+            SettingsStoreConfig::Memory => Arc::new(MemorySettingsStore::new()),
+        };
+        log::info!("Settings store: {:?}", config.settings_store);
+
+        Ok(SettingsManager { store })
+    }
+
+    async fn find_settings(&self, user_id: &str) -> Result<Option<UserSettings>, SettingsError> {
+        if user_id.len() < 2 {
+            return Err(SettingsError::BadRequest(format!("Invalid user id: {}", user_id)));
+        }
+        self.store.find_settings(user_id).await
+    }
+
+    pub async fn get_settings(&self, user_id: &str) -> Result<SettingsInfo, SettingsError> {
+        let settings = self
+            .find_settings(user_id)
+            .await?
+            .ok_or(SettingsError::SettingsNotFound)?;
+        into_info(&settings)
+    }
+
+    async fn try_sync_settings(
+        &self,
+        user_id: &str,
+        settings: &SettingsInfo,
+    ) -> Result<SettingsSync, BackoffError<SettingsError>> {
+        let blob = serde_json::to_string(&settings.settings).map_err(|err| SettingsError::from(err).into_backoff())?;
+        let stored = self.find_settings(user_id).await.map_err(SettingsError::into_backoff)?;
+
+        let stored = match stored {
+            Some(stored) if stored.modified() > settings.modified => {
+                log::info!(
+                    "Settings of {} are outdated ({} < {})",
+                    user_id,
+                    settings.modified,
+                    stored.modified()
+                );
+                return Ok(SettingsSync::Outdated(
+                    into_info(&stored).map_err(SettingsError::into_backoff)?,
+                ));
+            }
+            Some(mut stored) => {
+                stored.update(blob, settings.modified);
+                self.store.update_settings(stored).await
+            }
+            None => {
+                let new = UserSettings::new(user_id, blob, settings.modified);
+                self.store.insert_settings(new).await
+            }
+        }
+        .map_err(SettingsError::into_backoff)?;
+
+        Ok(SettingsSync::Accepted(
+            into_info(&stored).map_err(SettingsError::into_backoff)?,
+        ))
+    }
+
+    /// Upload the settings of a user. If the stored settings are newer, they are kept and returned.
+    pub async fn sync_settings(&self, user_id: &str, settings: SettingsInfo) -> Result<SettingsSync, SettingsError> {
+        backoff::Exponential::new(3, Duration::from_millis(10))
+            .async_execute(|_| self.try_sync_settings(user_id, &settings))
+            .await
+    }
+}
@@ -0,0 +1,73 @@
+use crate::settings::{SettingsError, SettingsStore, StoreFuture, UserSettings, UserSettingsData};
+use azure_sdk_storage_table::TableEntity;
+use std::{collections::HashMap, sync::Mutex};
+
+fn into_settings(user_id: &str, data: UserSettingsData, version: u64) -> UserSettings {
+    let (partition_key, row_key) = UserSettings::entity_keys(user_id);
+    UserSettings::from_entity(TableEntity {
+        partition_key,
+        row_key,
+        etag: Some(version.to_string()),
+        timestamp: None,
+        payload: data,
+    })
+}
+
+/// Settings store keeping the settings in the memory of the process, for local development and tests.
+/// The etag is emulated by a version counter.
+#[derive(Default)]
+pub struct MemorySettingsStore {
+    settings: Mutex<HashMap<String, (UserSettingsData, u64)>>,
+}
+
+impl MemorySettingsStore {
+    pub fn new() -> Self {
+        MemorySettingsStore::default()
+    }
+
+    fn with_settings<T, F>(&self, f: F) -> StoreFuture<'_, T>
+    where
+        T: 'static,
+        F: FnOnce(&mut HashMap<String, (UserSettingsData, u64)>) -> Result<T, SettingsError>,
+    {
+        let result = {
+            let mut settings = self.settings.lock().unwrap();
+            f(&mut settings)
+        };
+        Box::pin(async move { result })
+    }
+}
+
+impl SettingsStore for MemorySettingsStore {
+    fn insert_settings(&self, settings: UserSettings) -> StoreFuture<'_, UserSettings> {
+        self.with_settings(move |store| {
+            if store.contains_key(settings.user_id()) {
+                return Err(SettingsError::SettingsConflict);
+            }
+            store.insert(settings.user_id().to_owned(), (settings.data().clone(), 0));
+            Ok(into_settings(settings.user_id(), settings.data().clone(), 0))
+        })
+    }
+
+    fn update_settings(&self, settings: UserSettings) -> StoreFuture<'_, UserSettings> {
+        self.with_settings(move |store| match store.get_mut(settings.user_id()) {
+            Some((data, version)) => {
+                if settings.etag() != Some(&version.to_string()) {
+                    return Err(SettingsError::SettingsConflict);
+                }
+                *data = settings.data().clone();
+                *version += 1;
+                Ok(into_settings(settings.user_id(), data.clone(), *version))
+            }
+            None => Err(SettingsError::SettingsNotFound),
+        })
+    }
+
+    fn find_settings<'a>(&'a self, user_id: &'a str) -> StoreFuture<'a, Option<UserSettings>> {
+        self.with_settings(|store| {
+            Ok(store
+                .get(user_id)
+                .map(|(data, version)| into_settings(user_id, data.clone(), *version)))
+        })
+    }
+}
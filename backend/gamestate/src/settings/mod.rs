@@ -0,0 +1,15 @@
+mod azure_store;
+mod error;
+mod handler;
+mod manager;
+mod memory_store;
+mod store;
+mod user_settings;
+
+pub use self::azure_store::*;
+pub use self::error::*;
+pub use self::handler::*;
+pub use self::manager::*;
+pub use self::memory_store::*;
+pub use self::store::*;
+pub use self::user_settings::*;
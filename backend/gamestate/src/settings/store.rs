@@ -0,0 +1,32 @@
+use crate::settings::{SettingsError, UserSettings};
+use serde::{Deserialize, Serialize};
+use std::{future::Future, pin::Pin};
+
+pub type StoreFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, SettingsError>> + 'a>>;
+
+/// Storage backend of the user settings
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum SettingsStoreConfig {
+    /// Azure table storage using the storage account of the GameStateConfig
+    Azure,
+    /// Keep the settings in memory, for local development and tests
+    Memory,
+}
+
+impl Default for SettingsStoreConfig {
+    fn default() -> Self {
+        SettingsStoreConfig::Azure
+    }
+}
+
+/// Persistence of the user settings. Conflict resolution is implemented by the SettingsManager.
+pub trait SettingsStore {
+    /// Insert the settings of a new user, returns SettingsConflict if the user already has settings
+    fn insert_settings(&self, settings: UserSettings) -> StoreFuture<'_, UserSettings>;
+
+    /// Update the settings, returns SettingsConflict if the settings were modified since they were read
+    fn update_settings(&self, settings: UserSettings) -> StoreFuture<'_, UserSettings>;
+
+    fn find_settings<'a>(&'a self, user_id: &'a str) -> StoreFuture<'a, Option<UserSettings>>;
+}
@@ -0,0 +1,75 @@
+use azure_sdk_storage_table::TableEntity;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use shine_core::serde_with;
+
+/// Stored preference blob of a user
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct UserSettingsData {
+    /// The json encoded settings, the content is opaque for the service
+    pub settings: String,
+
+    /// Modification time reported by the client, used to resolve the conflicts
+    #[serde(with = "serde_with::datetime")]
+    pub modified: DateTime<Utc>,
+}
+
+/// Preferences (key bindings, quality settings, etc.) of a user roaming across the devices
+#[derive(Debug)]
+pub struct UserSettings(TableEntity<UserSettingsData>);
+
+impl UserSettings {
+    pub fn entity_keys(user_id: &str) -> (String, String) {
+        (format!("settings-{}", &user_id[0..2]), user_id.to_owned())
+    }
+
+    pub fn new(user_id: &str, settings: String, modified: DateTime<Utc>) -> Self {
+        let (partition_key, row_key) = Self::entity_keys(user_id);
+        Self(TableEntity {
+            partition_key,
+            row_key,
+            etag: None,
+            timestamp: None,
+            payload: UserSettingsData { settings, modified },
+        })
+    }
+
+    pub fn from_entity(entity: TableEntity<UserSettingsData>) -> Self {
+        Self(entity)
+    }
+
+    pub fn into_entity(self) -> TableEntity<UserSettingsData> {
+        self.0
+    }
+
+    pub fn data(&self) -> &UserSettingsData {
+        &self.0.payload
+    }
+
+    pub fn user_id(&self) -> &str {
+        &self.0.row_key
+    }
+
+    pub fn etag(&self) -> Option<&str> {
+        self.0.etag.as_deref()
+    }
+
+    pub fn modified(&self) -> DateTime<Utc> {
+        self.0.payload.modified
+    }
+
+    /// Replace the content keeping the etag for the optimistic concurrency
+    pub fn update(&mut self, settings: String, modified: DateTime<Utc>) {
+        self.0.payload = UserSettingsData { settings, modified };
+    }
+}
+
+/// The settings as exchanged with the clients
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsInfo {
+    pub settings: Value,
+    pub modified: DateTime<Utc>,
+}
@@ -0,0 +1,47 @@
+use shine_core::kernel::response::APIError;
+use sqlx::Error as SqlxError;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum TurnError {
+    /// Database related error
+    Internal(String),
+    BadRequest(String),
+    MatchNotFound,
+    NotParticipant,
+    NotYourTurn,
+    MatchFinished,
+    /// The match was modified since the client has seen it
+    VersionMismatch,
+}
+
+impl fmt::Display for TurnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl From<SqlxError> for TurnError {
+    fn from(err: SqlxError) -> TurnError {
+        TurnError::Internal(format!("Sql error: {}", err))
+    }
+}
+
+impl From<serde_json::Error> for TurnError {
+    fn from(err: serde_json::Error) -> TurnError {
+        TurnError::Internal(format!("Serialization error: {}", err))
+    }
+}
+
+impl From<TurnError> for APIError {
+    fn from(err: TurnError) -> APIError {
+        match err {
+            TurnError::BadRequest(msg) => APIError::BadRequest(msg),
+            TurnError::MatchNotFound => APIError::RespourceNotFound("Match not found".to_owned()),
+            TurnError::NotParticipant => APIError::Forbidden,
+            TurnError::NotYourTurn | TurnError::MatchFinished => APIError::Conflict(format!("{:?}", err)),
+            TurnError::VersionMismatch => APIError::Conflict("Match modified concurrently".to_owned()),
+            err => APIError::Internal(format!("{:?}", err)),
+        }
+    }
+}
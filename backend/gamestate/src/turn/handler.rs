@@ -0,0 +1,82 @@
+use crate::{
+    turn::{NewMatch, TurnSocket, TurnSubmission, TurnUpdate},
+    State,
+};
+use actix_web::{http::header, web, web::Bytes, Error as ActixError, HttpRequest, HttpResponse};
+use actix_web_actors::ws;
+use futures::{stream, StreamExt};
+use shine_core::{kernel::response::APIResult, requestinfo::TokenIdentity};
+use std::time::Duration;
+
+/// Period of the comments sent on the event stream to keep the connection alive
+const EVENTS_KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+pub async fn create_match(state: web::Data<State>, identity: TokenIdentity, params: web::Json<NewMatch>) -> APIResult {
+    let turn_match = state
+        .turns()
+        .create_match(&identity.user_id, params.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(turn_match))
+}
+
+pub async fn list_matches(state: web::Data<State>, identity: TokenIdentity) -> APIResult {
+    let matches = state.turns().list_matches(&identity.user_id).await?;
+    Ok(HttpResponse::Ok().json(matches))
+}
+
+pub async fn get_match(state: web::Data<State>, identity: TokenIdentity, match_id: web::Path<String>) -> APIResult {
+    let turn_match = state.turns().get_match(&identity.user_id, &match_id).await?;
+    Ok(HttpResponse::Ok().json(turn_match))
+}
+
+pub async fn submit_turn(
+    state: web::Data<State>,
+    identity: TokenIdentity,
+    match_id: web::Path<String>,
+    params: web::Json<TurnSubmission>,
+) -> APIResult {
+    let turn_match = state
+        .turns()
+        .submit_turn(&identity.user_id, &match_id, params.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(turn_match))
+}
+
+pub async fn resign_match(state: web::Data<State>, identity: TokenIdentity, match_id: web::Path<String>) -> APIResult {
+    let turn_match = state.turns().resign(&identity.user_id, &match_id).await?;
+    Ok(HttpResponse::Ok().json(turn_match))
+}
+
+/// Stream the match updates of the user as server-sent events
+pub async fn turn_events(state: web::Data<State>, identity: TokenIdentity) -> APIResult {
+    let updates = state.turns().subscribe(&identity.user_id).map(Some);
+    let keep_alive = actix_rt::time::interval(EVENTS_KEEP_ALIVE).map(|_| None);
+    let body = stream::select(updates, keep_alive).map(|update: Option<TurnUpdate>| {
+        let chunk = match update {
+            Some(update) => match serde_json::to_string(&update) {
+                Ok(data) => format!("event: {}\ndata: {}\n\n", update.event_name(), data),
+                Err(err) => {
+                    log::warn!("Failed to serialize turn update: {:?}", err);
+                    String::new()
+                }
+            },
+            None => ":\n\n".to_owned(),
+        };
+        Ok::<_, ActixError>(Bytes::from(chunk))
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .header(header::CACHE_CONTROL, "no-cache")
+        .streaming(Box::pin(body)))
+}
+
+/// Stream the match updates of the user on a websocket
+pub async fn turn_socket(
+    req: HttpRequest,
+    stream: web::Payload,
+    state: web::Data<State>,
+    identity: TokenIdentity,
+) -> Result<HttpResponse, ActixError> {
+    ws::start(TurnSocket::new(identity.user_id, state.turns().clone()), &req, stream)
+}
@@ -0,0 +1,337 @@
+use crate::{
+    turn::{MemoryTurnStore, PostgresTurnStore, TurnError, TurnMatch, TurnStore, TurnStoreConfig, TurnUpdate},
+    GameStateConfig,
+};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use rand::{self, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use shine_core::pubsub::{PubSub, Subscription};
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+const MATCH_ID_LEN: usize = 16;
+const MATCH_ID_ABC: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TurnConfig {
+    /// Default seconds a player has to complete a turn
+    pub turn_time_s: u64,
+    pub min_turn_time_s: u64,
+    pub max_turn_time_s: u64,
+    pub max_players: usize,
+    /// Maximum size of the serialized match state in bytes
+    pub max_state_size: usize,
+    /// Seconds between the checks of the turn deadlines
+    pub deadline_check_s: u64,
+    /// Url notified when a player without an open event stream gets the turn (ex. to send an email)
+    #[serde(default)]
+    pub notify_url: Option<String>,
+}
+
+impl Default for TurnConfig {
+    fn default() -> TurnConfig {
+        TurnConfig {
+            turn_time_s: 24 * 60 * 60,
+            min_turn_time_s: 60,
+            max_turn_time_s: 7 * 24 * 60 * 60,
+            max_players: 8,
+            max_state_size: 64 * 1024,
+            deadline_check_s: 30,
+            notify_url: None,
+        }
+    }
+}
+
+/// Parameters of a new match
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewMatch {
+    /// The opponents, the creator of the match takes the first turn
+    pub players: Vec<String>,
+    #[serde(default)]
+    pub state: Value,
+    pub turn_time_s: Option<u64>,
+}
+
+/// The result of a turn
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnSubmission {
+    /// Version of the match the turn was played on
+    pub version: u64,
+    pub state: Value,
+    /// Finish the match with the given winners (empty for a draw)
+    pub winners: Option<Vec<String>>,
+}
+
+/// Payload of the notification hook
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TurnHookPayload<'a> {
+    user_id: &'a str,
+    match_id: &'a str,
+    turn: u64,
+    deadline: DateTime<Utc>,
+}
+
+/// Manage the turn-based matches. The matches are persisted between the turns, the players are notified
+/// through the notification bus and the optional hook when it is their turn.
+#[derive(Clone)]
+pub struct TurnManager {
+    store: Arc<dyn TurnStore>,
+    config: Arc<TurnConfig>,
+    notifications: PubSub<TurnUpdate>,
+    client: reqwest::Client,
+}
+
+impl TurnManager {
+    /// Create the manager and start the periodic check of the deadlines
+    pub async fn new(config: &GameStateConfig) -> Result<Self, TurnError> {
+        let store: Arc<dyn TurnStore> = match &config.turn_store {
+            TurnStoreConfig::Postgres { url, max_connections } => {
+                Arc::new(PostgresTurnStore::new(url, *max_connections).await?)
+            }
+            TurnStoreConfig::Memory => Arc::new(MemoryTurnStore::new()),
+        };
+        log::info!("Turn store: {:?}", config.turn_store);
+
+        let manager = TurnManager {
+            store,
+            config: Arc::new(config.turn.clone()),
+            notifications: PubSub::default(),
+            client: reqwest::Client::new(),
+        };
+
+        let watcher = manager.clone();
+        let period = Duration::from_secs(manager.config.deadline_check_s.max(1));
+        actix_rt::spawn(actix_rt::time::interval(period).for_each(move |_| {
+            let watcher = watcher.clone();
+            async move {
+                if let Err(err) = watcher.expire_deadlines().await {
+                    log::warn!("Failed to check the turn deadlines: {:?}", err);
+                }
+            }
+        }));
+
+        Ok(manager)
+    }
+
+    fn generate_match_id(&self) -> String {
+        let mut rng = rand::thread_rng();
+        String::from_utf8(
+            (0..MATCH_ID_LEN)
+                .map(|_| *MATCH_ID_ABC.choose(&mut rng).unwrap())
+                .collect::<Vec<_>>(),
+        )
+        .unwrap()
+    }
+
+    /// Stream the match updates of a user
+    pub fn subscribe(&self, user_id: &str) -> Subscription<TurnUpdate> {
+        self.notifications.subscribe(user_id)
+    }
+
+    fn notify_players(&self, turn_match: &TurnMatch, update: TurnUpdate) {
+        for player in &turn_match.players {
+            self.notifications.publish(&player.user_id, update.clone());
+        }
+    }
+
+    /// Notify the player on turn, the hook is called only if the player has no open event stream.
+    fn notify_turn(&self, turn_match: &TurnMatch) {
+        let user_id = match turn_match.current_player() {
+            Some(user_id) => user_id,
+            None => return,
+        };
+
+        let update = TurnUpdate::YourTurn {
+            match_id: turn_match.id.clone(),
+            turn: turn_match.turn,
+            deadline: turn_match.deadline,
+        };
+        if self.notifications.publish(user_id, update) > 0 {
+            return;
+        }
+
+        if let Some(url) = &self.config.notify_url {
+            let request = self.client.post(url).json(&TurnHookPayload {
+                user_id,
+                match_id: &turn_match.id,
+                turn: turn_match.turn,
+                deadline: turn_match.deadline,
+            });
+            let match_id = turn_match.id.clone();
+            actix_rt::spawn(async move {
+                match request.send().await.and_then(|response| response.error_for_status()) {
+                    Ok(_) => {}
+                    Err(err) => log::warn!("Turn notification hook failed for match {}: {}", match_id, err),
+                }
+            });
+        }
+    }
+
+    fn notify_finished(&self, turn_match: &TurnMatch) {
+        self.notify_players(
+            turn_match,
+            TurnUpdate::Finished {
+                match_id: turn_match.id.clone(),
+                winners: turn_match.winners.clone(),
+            },
+        );
+    }
+
+    fn check_state(&self, state: &Value) -> Result<(), TurnError> {
+        let size = serde_json::to_string(state)?.len();
+        if size > self.config.max_state_size {
+            return Err(TurnError::BadRequest(format!(
+                "Match state too large, limit: {} bytes",
+                self.config.max_state_size
+            )));
+        }
+        Ok(())
+    }
+
+    pub async fn create_match(&self, user_id: &str, params: NewMatch) -> Result<TurnMatch, TurnError> {
+        let mut players = vec![user_id.to_owned()];
+        let mut seen = HashSet::new();
+        seen.insert(user_id.to_owned());
+        for player in params.players {
+            if !seen.insert(player.clone()) {
+                return Err(TurnError::BadRequest(format!("Duplicate player: {}", player)));
+            }
+            players.push(player);
+        }
+        if players.len() < 2 || players.len() > self.config.max_players {
+            return Err(TurnError::BadRequest(format!(
+                "Number of players must be between 2 and {}",
+                self.config.max_players
+            )));
+        }
+
+        let turn_time_s = params.turn_time_s.unwrap_or(self.config.turn_time_s);
+        if turn_time_s < self.config.min_turn_time_s || turn_time_s > self.config.max_turn_time_s {
+            return Err(TurnError::BadRequest(format!(
+                "Turn time must be between {} and {} seconds",
+                self.config.min_turn_time_s, self.config.max_turn_time_s
+            )));
+        }
+        self.check_state(&params.state)?;
+
+        let turn_match = TurnMatch::new(self.generate_match_id(), players, params.state, turn_time_s);
+        let turn_match = self.store.insert_match(turn_match).await?;
+        self.notify_turn(&turn_match);
+        Ok(turn_match)
+    }
+
+    /// Get a match, only the players can access it
+    pub async fn get_match(&self, user_id: &str, match_id: &str) -> Result<TurnMatch, TurnError> {
+        let turn_match = self.store.find_match(match_id).await?.ok_or(TurnError::MatchNotFound)?;
+        if !turn_match.contains(user_id) {
+            return Err(TurnError::NotParticipant);
+        }
+        Ok(turn_match)
+    }
+
+    pub async fn list_matches(&self, user_id: &str) -> Result<Vec<TurnMatch>, TurnError> {
+        self.store.list_matches(user_id).await
+    }
+
+    /// Complete the turn of the player, the turn is passed on or the match is finished.
+    pub async fn submit_turn(
+        &self,
+        user_id: &str,
+        match_id: &str,
+        submission: TurnSubmission,
+    ) -> Result<TurnMatch, TurnError> {
+        let mut turn_match = self.get_match(user_id, match_id).await?;
+        if !turn_match.is_active() {
+            return Err(TurnError::MatchFinished);
+        }
+        if turn_match.current_player() != Some(user_id) {
+            return Err(TurnError::NotYourTurn);
+        }
+        if turn_match.version != submission.version {
+            return Err(TurnError::VersionMismatch);
+        }
+        self.check_state(&submission.state)?;
+
+        let turn = turn_match.turn;
+        turn_match.state = submission.state;
+        match submission.winners {
+            Some(winners) => {
+                if let Some(winner) = winners.iter().find(|w| !turn_match.contains(w)) {
+                    return Err(TurnError::BadRequest(format!("Unknown winner: {}", winner)));
+                }
+                turn_match.finish(winners);
+            }
+            None => turn_match.advance(),
+        }
+
+        let turn_match = self.store.update_match(turn_match).await?;
+        self.notify_players(
+            &turn_match,
+            TurnUpdate::TurnPlayed {
+                match_id: turn_match.id.clone(),
+                turn,
+                player_id: user_id.to_owned(),
+            },
+        );
+        if turn_match.is_active() {
+            self.notify_turn(&turn_match);
+        } else {
+            self.notify_finished(&turn_match);
+        }
+        Ok(turn_match)
+    }
+
+    async fn forfeit(&self, mut turn_match: TurnMatch, user_id: &str, timeout: bool) -> Result<TurnMatch, TurnError> {
+        let current = turn_match.current_player().map(|p| p.to_owned());
+        turn_match.forfeit(user_id);
+
+        let turn_match = self.store.update_match(turn_match).await?;
+        self.notify_players(
+            &turn_match,
+            TurnUpdate::Forfeited {
+                match_id: turn_match.id.clone(),
+                player_id: user_id.to_owned(),
+                timeout,
+            },
+        );
+        if !turn_match.is_active() {
+            self.notify_finished(&turn_match);
+        } else if turn_match.current_player().map(|p| p.to_owned()) != current {
+            self.notify_turn(&turn_match);
+        }
+        Ok(turn_match)
+    }
+
+    /// Resign from a match, the turns of the player are skipped from now on
+    pub async fn resign(&self, user_id: &str, match_id: &str) -> Result<TurnMatch, TurnError> {
+        let turn_match = self.get_match(user_id, match_id).await?;
+        if !turn_match.is_active() {
+            return Err(TurnError::MatchFinished);
+        }
+        if turn_match.players.iter().any(|p| p.user_id == user_id && p.forfeited) {
+            return Ok(turn_match);
+        }
+        self.forfeit(turn_match, user_id, false).await
+    }
+
+    /// The players on turn forfeit in the matches where the deadline has passed. When multiple instances
+    /// run the check, the versions ensure a single forfeit per turn.
+    pub async fn expire_deadlines(&self) -> Result<(), TurnError> {
+        for turn_match in self.store.list_expired(Utc::now()).await? {
+            let user_id = match turn_match.current_player() {
+                Some(user_id) => user_id.to_owned(),
+                None => continue,
+            };
+            log::info!("Player {} missed the deadline in match {}", user_id, turn_match.id);
+            match self.forfeit(turn_match, &user_id, true).await {
+                Ok(_) | Err(TurnError::VersionMismatch) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+use crate::turn::{StoreFuture, TurnError, TurnMatch, TurnStore};
+use chrono::{DateTime, Utc};
+use std::{collections::HashMap, sync::Mutex};
+
+/// Turn store keeping the matches in the memory of the process, for local development and tests.
+#[derive(Default)]
+pub struct MemoryTurnStore {
+    matches: Mutex<HashMap<String, TurnMatch>>,
+}
+
+impl MemoryTurnStore {
+    pub fn new() -> Self {
+        MemoryTurnStore::default()
+    }
+
+    fn with_matches<T, F>(&self, f: F) -> StoreFuture<'_, T>
+    where
+        T: 'static,
+        F: FnOnce(&mut HashMap<String, TurnMatch>) -> Result<T, TurnError>,
+    {
+        let result = {
+            let mut matches = self.matches.lock().unwrap();
+            f(&mut matches)
+        };
+        Box::pin(async move { result })
+    }
+}
+
+impl TurnStore for MemoryTurnStore {
+    fn insert_match(&self, mut turn_match: TurnMatch) -> StoreFuture<'_, TurnMatch> {
+        self.with_matches(move |matches| {
+            if matches.contains_key(&turn_match.id) {
+                return Err(TurnError::VersionMismatch);
+            }
+            turn_match.version = 0;
+            matches.insert(turn_match.id.clone(), turn_match.clone());
+            Ok(turn_match)
+        })
+    }
+
+    fn update_match(&self, mut turn_match: TurnMatch) -> StoreFuture<'_, TurnMatch> {
+        self.with_matches(move |matches| match matches.get_mut(&turn_match.id) {
+            Some(stored) => {
+                if stored.version != turn_match.version {
+                    return Err(TurnError::VersionMismatch);
+                }
+                turn_match.version += 1;
+                *stored = turn_match.clone();
+                Ok(turn_match)
+            }
+            None => Err(TurnError::MatchNotFound),
+        })
+    }
+
+    fn find_match<'a>(&'a self, match_id: &'a str) -> StoreFuture<'a, Option<TurnMatch>> {
+        self.with_matches(|matches| Ok(matches.get(match_id).cloned()))
+    }
+
+    fn list_matches<'a>(&'a self, user_id: &'a str) -> StoreFuture<'a, Vec<TurnMatch>> {
+        self.with_matches(|matches| {
+            let mut list: Vec<TurnMatch> = matches.values().filter(|m| m.contains(user_id)).cloned().collect();
+            list.sort_by(|a, b| b.modified.cmp(&a.modified));
+            Ok(list)
+        })
+    }
+
+    fn list_expired(&self, now: DateTime<Utc>) -> StoreFuture<'_, Vec<TurnMatch>> {
+        self.with_matches(move |matches| {
+            Ok(matches
+                .values()
+                .filter(|m| m.is_active() && m.deadline < now)
+                .cloned()
+                .collect())
+        })
+    }
+}
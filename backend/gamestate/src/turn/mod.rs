@@ -0,0 +1,17 @@
+mod error;
+mod handler;
+mod manager;
+mod memory_store;
+mod postgres_store;
+mod socket;
+mod store;
+mod turn_match;
+
+pub use self::error::*;
+pub use self::handler::*;
+pub use self::manager::*;
+pub use self::memory_store::*;
+pub use self::postgres_store::*;
+pub use self::socket::*;
+pub use self::store::*;
+pub use self::turn_match::*;
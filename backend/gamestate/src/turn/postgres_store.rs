@@ -0,0 +1,140 @@
+use crate::turn::{StoreFuture, TurnError, TurnMatch, TurnStore};
+use chrono::{DateTime, Utc};
+use sqlx::postgres::{PgPool, PgPoolOptions};
+
+/// The match is stored as a json document, the columns used by the queries are kept next to it.
+const SCHEMA: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS turn_matches (
+        id TEXT NOT NULL PRIMARY KEY,
+        version BIGINT NOT NULL,
+        players TEXT[] NOT NULL,
+        active BOOLEAN NOT NULL,
+        deadline TIMESTAMPTZ NOT NULL,
+        modified TIMESTAMPTZ NOT NULL,
+        data TEXT NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS turn_matches_deadline ON turn_matches (deadline) WHERE active",
+];
+
+/// Turn store using a Postgres database
+#[derive(Clone)]
+pub struct PostgresTurnStore {
+    pool: PgPool,
+}
+
+impl PostgresTurnStore {
+    pub async fn new(url: &str, max_connections: u32) -> Result<Self, TurnError> {
+        let pool = PgPoolOptions::new()
+            .max_connections(max_connections)
+            .connect(url)
+            .await?;
+
+        for statement in SCHEMA {
+            sqlx::query(statement).execute(&pool).await?;
+        }
+
+        Ok(PostgresTurnStore { pool })
+    }
+
+    fn parse_rows(rows: Vec<(String,)>) -> Result<Vec<TurnMatch>, TurnError> {
+        rows.into_iter()
+            .map(|(data,)| serde_json::from_str(&data).map_err(TurnError::from))
+            .collect()
+    }
+
+    async fn exists(&self, match_id: &str) -> Result<bool, TurnError> {
+        let (exists,): (bool,) = sqlx::query_as("SELECT EXISTS(SELECT 1 FROM turn_matches WHERE id = $1)")
+            .bind(match_id)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(exists)
+    }
+}
+
+impl TurnStore for PostgresTurnStore {
+    fn insert_match(&self, mut turn_match: TurnMatch) -> StoreFuture<'_, TurnMatch> {
+        Box::pin(async move {
+            turn_match.version = 0;
+            let data = serde_json::to_string(&turn_match)?;
+            sqlx::query(
+                "INSERT INTO turn_matches (id, version, players, active, deadline, modified, data)
+                VALUES ($1, 0, $2, $3, $4, $5, $6)",
+            )
+            .bind(&turn_match.id)
+            .bind(turn_match.player_ids())
+            .bind(turn_match.is_active())
+            .bind(turn_match.deadline)
+            .bind(turn_match.modified)
+            .bind(data)
+            .execute(&self.pool)
+            .await?;
+            Ok(turn_match)
+        })
+    }
+
+    fn update_match(&self, mut turn_match: TurnMatch) -> StoreFuture<'_, TurnMatch> {
+        Box::pin(async move {
+            let expected_version = turn_match.version;
+            turn_match.version += 1;
+            let data = serde_json::to_string(&turn_match)?;
+            let updated = sqlx::query(
+                "UPDATE turn_matches SET version = version + 1, players = $3, active = $4, deadline = $5, modified = $6,
+                    data = $7
+                WHERE id = $1 AND version = $2",
+            )
+            .bind(&turn_match.id)
+            .bind(expected_version as i64)
+            .bind(turn_match.player_ids())
+            .bind(turn_match.is_active())
+            .bind(turn_match.deadline)
+            .bind(turn_match.modified)
+            .bind(data)
+            .execute(&self.pool)
+            .await?;
+
+            if updated.rows_affected() == 0 {
+                if self.exists(&turn_match.id).await? {
+                    Err(TurnError::VersionMismatch)
+                } else {
+                    Err(TurnError::MatchNotFound)
+                }
+            } else {
+                Ok(turn_match)
+            }
+        })
+    }
+
+    fn find_match<'a>(&'a self, match_id: &'a str) -> StoreFuture<'a, Option<TurnMatch>> {
+        Box::pin(async move {
+            let row: Option<(String,)> = sqlx::query_as("SELECT data FROM turn_matches WHERE id = $1")
+                .bind(match_id)
+                .fetch_optional(&self.pool)
+                .await?;
+            match row {
+                Some((data,)) => Ok(Some(serde_json::from_str(&data)?)),
+                None => Ok(None),
+            }
+        })
+    }
+
+    fn list_matches<'a>(&'a self, user_id: &'a str) -> StoreFuture<'a, Vec<TurnMatch>> {
+        Box::pin(async move {
+            let rows: Vec<(String,)> =
+                sqlx::query_as("SELECT data FROM turn_matches WHERE $1 = ANY(players) ORDER BY modified DESC")
+                    .bind(user_id)
+                    .fetch_all(&self.pool)
+                    .await?;
+            Self::parse_rows(rows)
+        })
+    }
+
+    fn list_expired(&self, now: DateTime<Utc>) -> StoreFuture<'_, Vec<TurnMatch>> {
+        Box::pin(async move {
+            let rows: Vec<(String,)> = sqlx::query_as("SELECT data FROM turn_matches WHERE active AND deadline < $1")
+                .bind(now)
+                .fetch_all(&self.pool)
+                .await?;
+            Self::parse_rows(rows)
+        })
+    }
+}
@@ -0,0 +1,51 @@
+use crate::turn::{TurnManager, TurnUpdate};
+use actix::{Actor, ActorContext, AsyncContext, StreamHandler};
+use actix_web_actors::ws;
+
+/// Websocket connection streaming the match updates of a user
+pub struct TurnSocket {
+    user_id: String,
+    manager: TurnManager,
+}
+
+impl TurnSocket {
+    pub fn new(user_id: String, manager: TurnManager) -> TurnSocket {
+        TurnSocket { user_id, manager }
+    }
+}
+
+impl Actor for TurnSocket {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.add_stream(self.manager.subscribe(&self.user_id));
+    }
+}
+
+impl StreamHandler<TurnUpdate> for TurnSocket {
+    fn handle(&mut self, update: TurnUpdate, ctx: &mut Self::Context) {
+        match serde_json::to_string(&update) {
+            Ok(text) => ctx.text(text),
+            Err(err) => log::warn!("Failed to serialize turn update: {:?}", err),
+        }
+    }
+
+    fn finished(&mut self, _ctx: &mut Self::Context) {}
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for TurnSocket {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Ping(msg)) => ctx.pong(&msg),
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            Ok(_) => {}
+            Err(err) => {
+                log::info!("Turn socket error of {}: {:?}", self.user_id, err);
+                ctx.stop();
+            }
+        }
+    }
+}
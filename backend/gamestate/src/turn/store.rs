@@ -0,0 +1,43 @@
+use crate::turn::{TurnError, TurnMatch};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{future::Future, pin::Pin};
+
+pub type StoreFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, TurnError>> + 'a>>;
+
+/// Storage backend of the turn-based matches
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum TurnStoreConfig {
+    Postgres {
+        url: String,
+        max_connections: u32,
+    },
+    /// Keep the matches in memory, for local development and tests
+    Memory,
+}
+
+impl Default for TurnStoreConfig {
+    fn default() -> Self {
+        TurnStoreConfig::Memory
+    }
+}
+
+/// Persistence of the turn-based matches. The versions are checked by the store to implement the
+/// optimistic concurrency.
+pub trait TurnStore {
+    /// Insert a new match with version 0
+    fn insert_match(&self, turn_match: TurnMatch) -> StoreFuture<'_, TurnMatch>;
+
+    /// Replace the match if the stored version matches the version of the match, the version is incremented.
+    /// Returns VersionMismatch if the versions differ, MatchNotFound if there is no such match.
+    fn update_match(&self, turn_match: TurnMatch) -> StoreFuture<'_, TurnMatch>;
+
+    fn find_match<'a>(&'a self, match_id: &'a str) -> StoreFuture<'a, Option<TurnMatch>>;
+
+    /// List the matches of a user, the most recently modified first
+    fn list_matches<'a>(&'a self, user_id: &'a str) -> StoreFuture<'a, Vec<TurnMatch>>;
+
+    /// List the active matches where the deadline of the current turn has passed
+    fn list_expired(&self, now: DateTime<Utc>) -> StoreFuture<'_, Vec<TurnMatch>>;
+}
@@ -0,0 +1,177 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnPlayer {
+    pub user_id: String,
+    /// The player resigned or missed a turn deadline, the turns of the player are skipped
+    pub forfeited: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MatchStatus {
+    Active,
+    Finished,
+}
+
+/// A turn-based match. The state of the game is opaque for the service, it is replaced by the
+/// player on turn and kept between the turns.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnMatch {
+    pub id: String,
+    pub players: Vec<TurnPlayer>,
+    /// Index of the player on turn
+    pub current: usize,
+    /// Number of the current turn starting from 0
+    pub turn: u64,
+    pub state: Value,
+    pub status: MatchStatus,
+    pub winners: Vec<String>,
+    /// Seconds a player has to complete a turn
+    pub turn_time_s: u64,
+    /// The player on turn forfeits if the turn is not completed until this time
+    pub deadline: DateTime<Utc>,
+    pub created: DateTime<Utc>,
+    pub modified: DateTime<Utc>,
+    /// Version for the optimistic concurrency, incremented by the store on each update
+    pub version: u64,
+}
+
+impl TurnMatch {
+    pub(crate) fn new(id: String, players: Vec<String>, state: Value, turn_time_s: u64) -> TurnMatch {
+        let now = Utc::now();
+        TurnMatch {
+            id,
+            players: players
+                .into_iter()
+                .map(|user_id| TurnPlayer {
+                    user_id,
+                    forfeited: false,
+                })
+                .collect(),
+            current: 0,
+            turn: 0,
+            state,
+            status: MatchStatus::Active,
+            winners: Vec::new(),
+            turn_time_s,
+            deadline: now + Duration::seconds(turn_time_s as i64),
+            created: now,
+            modified: now,
+            version: 0,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.status == MatchStatus::Active
+    }
+
+    pub fn contains(&self, user_id: &str) -> bool {
+        self.players.iter().any(|p| p.user_id == user_id)
+    }
+
+    pub fn player_ids(&self) -> Vec<String> {
+        self.players.iter().map(|p| p.user_id.clone()).collect()
+    }
+
+    /// The player on turn, None if the match is over
+    pub fn current_player(&self) -> Option<&str> {
+        if self.is_active() {
+            self.players.get(self.current).map(|p| p.user_id.as_str())
+        } else {
+            None
+        }
+    }
+
+    fn remaining_players(&self) -> Vec<String> {
+        self.players
+            .iter()
+            .filter(|p| !p.forfeited)
+            .map(|p| p.user_id.clone())
+            .collect()
+    }
+
+    pub(crate) fn finish(&mut self, winners: Vec<String>) {
+        self.status = MatchStatus::Finished;
+        self.winners = winners;
+        self.modified = Utc::now();
+    }
+
+    /// Pass the turn to the next player who has not forfeited and restart the deadline
+    pub(crate) fn advance(&mut self) {
+        let count = self.players.len();
+        for step in 1..=count {
+            let next = (self.current + step) % count;
+            if !self.players[next].forfeited {
+                self.current = next;
+                break;
+            }
+        }
+        let now = Utc::now();
+        self.turn += 1;
+        self.deadline = now + Duration::seconds(self.turn_time_s as i64);
+        self.modified = now;
+    }
+
+    /// Mark a player as forfeited. The match is finished when a single player remains, otherwise
+    /// the turn is passed on if it was the forfeiting player's turn.
+    pub(crate) fn forfeit(&mut self, user_id: &str) {
+        let index = match self.players.iter().position(|p| p.user_id == user_id) {
+            Some(index) => index,
+            None => return,
+        };
+        self.players[index].forfeited = true;
+
+        let remaining = self.remaining_players();
+        if remaining.len() <= 1 {
+            self.finish(remaining);
+        } else if index == self.current {
+            self.advance();
+        } else {
+            self.modified = Utc::now();
+        }
+    }
+}
+
+/// Change of a match pushed to the players
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum TurnUpdate {
+    /// It is the turn of the receiving player
+    #[serde(rename_all = "camelCase")]
+    YourTurn {
+        match_id: String,
+        turn: u64,
+        deadline: DateTime<Utc>,
+    },
+    #[serde(rename_all = "camelCase")]
+    TurnPlayed {
+        match_id: String,
+        turn: u64,
+        player_id: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Forfeited {
+        match_id: String,
+        player_id: String,
+        /// The player missed the deadline of the turn
+        timeout: bool,
+    },
+    #[serde(rename_all = "camelCase")]
+    Finished { match_id: String, winners: Vec<String> },
+}
+
+impl TurnUpdate {
+    /// Name of the update in the event stream
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            TurnUpdate::YourTurn { .. } => "yourTurn",
+            TurnUpdate::TurnPlayed { .. } => "turnPlayed",
+            TurnUpdate::Forfeited { .. } => "forfeited",
+            TurnUpdate::Finished { .. } => "finished",
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use crate::party::PartyError;
+use shine_core::kernel::response::APIError;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum VoiceError {
+    /// Voice chat is opt-in, it is not enabled in the configuration
+    Disabled,
+    ChannelNotFound,
+    ChannelFull,
+    AlreadyJoined,
+    /// The user is not a member of the party or room of the channel
+    NotMember(PartyError),
+    /// Failed to decode or encode the audio for the mixing
+    Codec(String),
+}
+
+impl fmt::Display for VoiceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl From<VoiceError> for APIError {
+    fn from(err: VoiceError) -> APIError {
+        match err {
+            VoiceError::Disabled => APIError::RespourceNotFound("Voice chat is not enabled".to_owned()),
+            VoiceError::ChannelNotFound => APIError::RespourceNotFound("Voice channel not found".to_owned()),
+            VoiceError::ChannelFull => APIError::TooManyRequests("Voice channel is full".to_owned()),
+            VoiceError::AlreadyJoined => APIError::Conflict("Already joined the voice channel".to_owned()),
+            VoiceError::NotMember(err) => err.into(),
+            err => APIError::Internal(format!("{:?}", err)),
+        }
+    }
+}
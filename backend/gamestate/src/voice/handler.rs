@@ -0,0 +1,75 @@
+use crate::{voice::VoiceSocket, State};
+use actix_web::{web, Error as ActixError, HttpRequest, HttpResponse};
+use actix_web_actors::ws;
+use serde::{Deserialize, Serialize};
+use shine_core::{
+    kernel::response::{APIError, APIResult},
+    requestinfo::TokenIdentity,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceQuery {
+    /// Hear only the friends of the user
+    #[serde(default)]
+    pub friends_only: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VoiceMuteList {
+    pub muted: Vec<String>,
+}
+
+/// Connect to the voice channel of the party of the user over a websocket
+pub async fn party_voice_socket(
+    req: HttpRequest,
+    stream: web::Payload,
+    state: web::Data<State>,
+    identity: TokenIdentity,
+    query: web::Query<VoiceQuery>,
+) -> Result<HttpResponse, ActixError> {
+    let voices = state.voices();
+    let channel_id = voices.party_channel(&identity.user_id).map_err(APIError::from)?;
+    ws::start(
+        VoiceSocket::new(channel_id, identity.user_id, query.friends_only, voices.clone()),
+        &req,
+        stream,
+    )
+}
+
+/// Connect to the voice channel of a room over a websocket
+pub async fn room_voice_socket(
+    req: HttpRequest,
+    stream: web::Payload,
+    state: web::Data<State>,
+    identity: TokenIdentity,
+    room_id: web::Path<String>,
+    query: web::Query<VoiceQuery>,
+) -> Result<HttpResponse, ActixError> {
+    let voices = state.voices();
+    let channel_id = voices.room_channel(&room_id);
+    ws::start(
+        VoiceSocket::new(channel_id, identity.user_id, query.friends_only, voices.clone()),
+        &req,
+        stream,
+    )
+}
+
+pub async fn get_voice_mutes(state: web::Data<State>, identity: TokenIdentity) -> APIResult {
+    let muted = state.voices().mutes().muted_by(&identity.user_id);
+    Ok(HttpResponse::Ok().json(VoiceMuteList { muted }))
+}
+
+/// Stop hearing a user in all the voice channels
+pub async fn mute_user(state: web::Data<State>, identity: TokenIdentity, user: web::Path<String>) -> APIResult {
+    if *user == identity.user_id {
+        return Err(APIError::BadRequest("Cannot mute self".to_owned()));
+    }
+    state.voices().mutes().mute(&identity.user_id, &user);
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn unmute_user(state: web::Data<State>, identity: TokenIdentity, user: web::Path<String>) -> APIResult {
+    state.voices().mutes().unmute(&identity.user_id, &user);
+    Ok(HttpResponse::Ok().finish())
+}
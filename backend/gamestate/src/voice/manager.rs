@@ -0,0 +1,211 @@
+use crate::{
+    party::PartyManager,
+    voice::{JoinVoice, LeaveVoice, VoiceChannel, VoiceError, VoiceInput, VoicePacket, VoiceUpdate},
+};
+use actix::{Actor, Addr, Recipient};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+/// Routing of the audio in the voice channels
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VoiceMode {
+    /// Forward the packets of the talkers to the listeners, the clients mix the streams
+    Forward,
+    /// Mix the talkers on the server and send a single stream to each listener
+    Mix,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VoiceConfig {
+    /// Voice chat is opt-in
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "VoiceConfig::default_mode")]
+    pub mode: VoiceMode,
+    #[serde(default = "VoiceConfig::default_max_members")]
+    pub max_members: usize,
+    /// Maximum size of an audio packet in bytes, the larger packets are dropped
+    #[serde(default = "VoiceConfig::default_max_packet_size")]
+    pub max_packet_size: usize,
+    /// Length of an audio frame in milliseconds, the period of the mixing
+    #[serde(default = "VoiceConfig::default_frame_ms")]
+    pub frame_ms: u64,
+}
+
+impl VoiceConfig {
+    fn default_mode() -> VoiceMode {
+        VoiceMode::Forward
+    }
+
+    fn default_max_members() -> usize {
+        16
+    }
+
+    fn default_max_packet_size() -> usize {
+        512
+    }
+
+    fn default_frame_ms() -> u64 {
+        20
+    }
+}
+
+impl Default for VoiceConfig {
+    fn default() -> VoiceConfig {
+        VoiceConfig {
+            enabled: false,
+            mode: VoiceConfig::default_mode(),
+            max_members: VoiceConfig::default_max_members(),
+            max_packet_size: VoiceConfig::default_max_packet_size(),
+            frame_ms: VoiceConfig::default_frame_ms(),
+        }
+    }
+}
+
+/// The users muted by each user, shared by the voice channels
+#[derive(Clone, Default)]
+pub struct VoiceMutes(Arc<Mutex<HashMap<String, HashSet<String>>>>);
+
+impl VoiceMutes {
+    pub fn mute(&self, user_id: &str, muted_id: &str) {
+        let mut mutes = self.0.lock().unwrap();
+        mutes.entry(user_id.to_owned()).or_default().insert(muted_id.to_owned());
+    }
+
+    pub fn unmute(&self, user_id: &str, muted_id: &str) {
+        let mut mutes = self.0.lock().unwrap();
+        if let Some(muted) = mutes.get_mut(user_id) {
+            muted.remove(muted_id);
+        }
+    }
+
+    pub fn is_muted(&self, user_id: &str, other_id: &str) -> bool {
+        let mutes = self.0.lock().unwrap();
+        mutes
+            .get(user_id)
+            .map(|muted| muted.contains(other_id))
+            .unwrap_or(false)
+    }
+
+    pub fn muted_by(&self, user_id: &str) -> Vec<String> {
+        let mutes = self.0.lock().unwrap();
+        let mut muted: Vec<_> = mutes
+            .get(user_id)
+            .map(|muted| muted.iter().cloned().collect())
+            .unwrap_or_default();
+        muted.sort();
+        muted
+    }
+}
+
+/// Create and look up the voice channels of the parties and rooms. The channels are actors running on the
+/// arbiter (worker) of the first member, they are stopped when the last member leaves.
+#[derive(Clone)]
+pub struct VoiceManager {
+    config: Arc<VoiceConfig>,
+    parties: PartyManager,
+    mutes: VoiceMutes,
+    channels: Arc<Mutex<HashMap<String, Addr<VoiceChannel>>>>,
+}
+
+impl VoiceManager {
+    pub fn new(config: &VoiceConfig, parties: PartyManager) -> VoiceManager {
+        VoiceManager {
+            config: Arc::new(config.clone()),
+            parties,
+            mutes: VoiceMutes::default(),
+            channels: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn config(&self) -> &VoiceConfig {
+        &self.config
+    }
+
+    pub fn mutes(&self) -> &VoiceMutes {
+        &self.mutes
+    }
+
+    /// The voice channel of the party of the user
+    pub fn party_channel(&self, user_id: &str) -> Result<String, VoiceError> {
+        let party = self.parties.get_party(user_id).map_err(VoiceError::NotMember)?;
+        Ok(format!("party:{}", party.id))
+    }
+
+    /// The voice channel of a room
+    pub fn room_channel(&self, room_id: &str) -> String {
+        format!("room:{}", room_id)
+    }
+
+    fn find_channel(&self, channel_id: &str) -> Result<Addr<VoiceChannel>, VoiceError> {
+        let channels = self.channels.lock().unwrap();
+        match channels.get(channel_id) {
+            Some(channel) if channel.connected() => Ok(channel.clone()),
+            _ => Err(VoiceError::ChannelNotFound),
+        }
+    }
+
+    fn find_or_create_channel(&self, channel_id: &str) -> Addr<VoiceChannel> {
+        let mut channels = self.channels.lock().unwrap();
+        channels.retain(|_, channel| channel.connected());
+        channels
+            .entry(channel_id.to_owned())
+            .or_insert_with(|| {
+                VoiceChannel::new(
+                    channel_id.to_owned(),
+                    self.config.clone(),
+                    self.mutes.clone(),
+                    self.parties.clone(),
+                )
+                .start()
+            })
+            .clone()
+    }
+
+    pub async fn join(
+        &self,
+        channel_id: &str,
+        user_id: &str,
+        friends_only: bool,
+        recipient: Recipient<VoiceUpdate>,
+    ) -> Result<(), VoiceError> {
+        if !self.config.enabled {
+            return Err(VoiceError::Disabled);
+        }
+        let channel = self.find_or_create_channel(channel_id);
+        channel
+            .send(JoinVoice {
+                user_id: user_id.to_owned(),
+                friends_only,
+                recipient,
+            })
+            .await
+            .map_err(|_| VoiceError::ChannelNotFound)?
+    }
+
+    pub fn leave(&self, channel_id: &str, user_id: &str) {
+        if let Ok(channel) = self.find_channel(channel_id) {
+            channel.do_send(LeaveVoice {
+                user_id: user_id.to_owned(),
+            });
+        }
+    }
+
+    /// Send the audio of a member to the channel, the oversized packets are dropped
+    pub fn send(&self, channel_id: &str, user_id: &str, packet: VoicePacket) -> Result<(), VoiceError> {
+        if packet.payload.len() > self.config.max_packet_size {
+            log::debug!("Oversized voice packet of {} dropped", user_id);
+            return Ok(());
+        }
+        let channel = self.find_channel(channel_id)?;
+        // the audio is unreliable, the packet is dropped if the channel is overloaded
+        let _ = channel.try_send(VoiceInput {
+            user_id: user_id.to_owned(),
+            packet,
+        });
+        Ok(())
+    }
+}
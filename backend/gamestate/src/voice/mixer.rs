@@ -0,0 +1,114 @@
+use crate::voice::{VoiceError, VoicePacket};
+use audiopus::{
+    coder::{Decoder, Encoder},
+    Application, Channels, SampleRate,
+};
+use std::collections::HashMap;
+
+/// Samples of a 20ms mono frame at 48kHz
+pub const VOICE_FRAME_SAMPLES: usize = 960;
+/// Upper limit of an encoded frame
+const MAX_PACKET_SIZE: usize = 1275;
+
+fn codec_error(err: audiopus::Error) -> VoiceError {
+    VoiceError::Codec(format!("{:?}", err))
+}
+
+/// Add the samples of a frame to the mix, the mix is accumulated on 32 bits to avoid the overflow.
+pub fn mix_into(mix: &mut [i32], frame: &[i16]) {
+    for (m, s) in mix.iter_mut().zip(frame.iter()) {
+        *m += *s as i32;
+    }
+}
+
+/// Clamp the accumulated mix into the sample range
+pub fn clamp_mix(mix: &[i32]) -> Vec<i16> {
+    mix.iter()
+        .map(|m| (*m).max(i16::MIN as i32).min(i16::MAX as i32) as i16)
+        .collect()
+}
+
+/// Server side mixing of a channel. The frames of the talkers are decoded as they arrive and at the end
+/// of each frame period a separate mix is encoded for each listener without the own voice of the listener
+/// and the talkers the listener should not hear.
+pub struct VoiceMixer {
+    decoders: HashMap<u16, Decoder>,
+    encoders: HashMap<u16, Encoder>,
+    frames: HashMap<u16, Vec<i16>>,
+    sequence: u16,
+}
+
+impl Default for VoiceMixer {
+    fn default() -> VoiceMixer {
+        VoiceMixer {
+            decoders: HashMap::new(),
+            encoders: HashMap::new(),
+            frames: HashMap::new(),
+            sequence: 0,
+        }
+    }
+}
+
+impl VoiceMixer {
+    pub fn sequence(&self) -> u16 {
+        self.sequence
+    }
+
+    pub fn has_frames(&self) -> bool {
+        !self.frames.is_empty()
+    }
+
+    /// Decode the packet of a talker. If the talker sent multiple packets in a period, the last one is kept.
+    pub fn push(&mut self, slot: u16, packet: &VoicePacket) -> Result<(), VoiceError> {
+        if !self.decoders.contains_key(&slot) {
+            let decoder = Decoder::new(SampleRate::Hz48000, Channels::Mono).map_err(codec_error)?;
+            self.decoders.insert(slot, decoder);
+        }
+        let decoder = self.decoders.get_mut(&slot).unwrap();
+        let mut frame = vec![0i16; VOICE_FRAME_SAMPLES];
+        let len = decoder
+            .decode(Some(&packet.payload[..]), &mut frame[..], false)
+            .map_err(codec_error)?;
+        frame.truncate(len);
+        self.frames.insert(slot, frame);
+        Ok(())
+    }
+
+    /// Release the codec state of a member
+    pub fn remove(&mut self, slot: u16) {
+        self.decoders.remove(&slot);
+        self.encoders.remove(&slot);
+        self.frames.remove(&slot);
+    }
+
+    /// Encode the mix of the talkers audible for the listener, None if no one can be heard
+    pub fn mix<F: Fn(u16) -> bool>(&mut self, listener: u16, can_hear: F) -> Result<Option<Vec<u8>>, VoiceError> {
+        let mut mix = vec![0i32; VOICE_FRAME_SAMPLES];
+        let mut count = 0;
+        for (slot, frame) in &self.frames {
+            if *slot != listener && can_hear(*slot) {
+                mix_into(&mut mix, frame);
+                count += 1;
+            }
+        }
+        if count == 0 {
+            return Ok(None);
+        }
+
+        if !self.encoders.contains_key(&listener) {
+            let encoder = Encoder::new(SampleRate::Hz48000, Channels::Mono, Application::Voip).map_err(codec_error)?;
+            self.encoders.insert(listener, encoder);
+        }
+        let encoder = &self.encoders[&listener];
+        let mut output = vec![0u8; MAX_PACKET_SIZE];
+        let len = encoder.encode(&clamp_mix(&mix), &mut output).map_err(codec_error)?;
+        output.truncate(len);
+        Ok(Some(output))
+    }
+
+    /// Clear the frames of the period
+    pub fn end_frame(&mut self) {
+        self.frames.clear();
+        self.sequence = self.sequence.wrapping_add(1);
+    }
+}
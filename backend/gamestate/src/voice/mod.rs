@@ -0,0 +1,17 @@
+mod error;
+mod handler;
+mod manager;
+mod mixer;
+mod socket;
+mod voice_channel;
+mod voice_packet;
+mod voice_state;
+
+pub use self::error::*;
+pub use self::handler::*;
+pub use self::manager::*;
+pub use self::mixer::*;
+pub use self::socket::*;
+pub use self::voice_channel::*;
+pub use self::voice_packet::*;
+pub use self::voice_state::*;
@@ -0,0 +1,98 @@
+use crate::voice::{VoiceControl, VoiceManager, VoicePacket, VoiceUpdate};
+use actix::{Actor, ActorContext, ActorFuture, AsyncContext, ContextFutureSpawner, Handler, StreamHandler, WrapFuture};
+use actix_web_actors::ws;
+
+/// Websocket connection of a client to a voice channel. The audio is sent as binary `VoicePacket`s, the
+/// client is expected to stop sending when push-to-talk is released. The packets received by the client are
+/// prefixed by the slot of the talker, the control messages (members, talking state) are json texts.
+pub struct VoiceSocket {
+    channel_id: String,
+    user_id: String,
+    friends_only: bool,
+    manager: VoiceManager,
+}
+
+impl VoiceSocket {
+    pub fn new(channel_id: String, user_id: String, friends_only: bool, manager: VoiceManager) -> VoiceSocket {
+        VoiceSocket {
+            channel_id,
+            user_id,
+            friends_only,
+            manager,
+        }
+    }
+}
+
+impl Actor for VoiceSocket {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let manager = self.manager.clone();
+        let channel_id = self.channel_id.clone();
+        let user_id = self.user_id.clone();
+        let friends_only = self.friends_only;
+        let recipient = ctx.address().recipient();
+        async move { manager.join(&channel_id, &user_id, friends_only, recipient).await }
+            .into_actor(self)
+            .map(|result, socket, ctx| {
+                if let Err(err) = result {
+                    log::info!(
+                        "User {} failed to join voice channel {}: {}",
+                        socket.user_id,
+                        socket.channel_id,
+                        err
+                    );
+                    ctx.close(None);
+                    ctx.stop();
+                }
+            })
+            .wait(ctx);
+    }
+
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        self.manager.leave(&self.channel_id, &self.user_id);
+    }
+}
+
+impl Handler<VoiceUpdate> for VoiceSocket {
+    type Result = ();
+
+    fn handle(&mut self, update: VoiceUpdate, ctx: &mut Self::Context) {
+        match update {
+            VoiceUpdate::Control(control) => match serde_json::to_string::<VoiceControl>(&control) {
+                Ok(text) => ctx.text(text),
+                Err(err) => log::warn!("Failed to serialize voice control: {:?}", err),
+            },
+            VoiceUpdate::Audio(data) => ctx.binary(data),
+            VoiceUpdate::Closed => {
+                ctx.close(Some(ws::CloseCode::Normal.into()));
+                ctx.stop();
+            }
+        }
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for VoiceSocket {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Binary(data)) => match VoicePacket::parse(&data) {
+                Some(packet) => {
+                    if self.manager.send(&self.channel_id, &self.user_id, packet).is_err() {
+                        ctx.stop();
+                    }
+                }
+                None => log::debug!("Invalid voice packet from {}", self.user_id),
+            },
+            Ok(ws::Message::Ping(msg)) => ctx.pong(&msg),
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            Ok(_) => {}
+            Err(err) => {
+                log::info!("Voice channel {} socket error: {:?}", self.channel_id, err);
+                ctx.stop();
+            }
+        }
+    }
+}
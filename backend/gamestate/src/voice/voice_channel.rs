@@ -0,0 +1,235 @@
+use crate::{
+    party::PartyManager,
+    voice::{
+        JoinVoice, LeaveVoice, VoiceConfig, VoiceControl, VoiceError, VoiceInput, VoiceMemberInfo, VoiceMixer,
+        VoiceMode, VoiceMutes, VoicePacket, VoiceUpdate, MIXED_SLOT,
+    },
+};
+use actix::{Actor, AsyncContext, Context, Handler, Recipient};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+struct VoiceMember {
+    slot: u16,
+    friends_only: bool,
+    talking: bool,
+    recipient: Recipient<VoiceUpdate>,
+}
+
+/// Check if the listener should receive the audio of the talker: neither of them blocked the other,
+/// the listener has not muted the talker and the talker is a friend if the listener accepts only friends.
+fn can_hear(
+    parties: &PartyManager,
+    mutes: &VoiceMutes,
+    listener_id: &str,
+    listener: &VoiceMember,
+    talker_id: &str,
+) -> bool {
+    listener_id != talker_id
+        && !parties.is_blocked(listener_id, talker_id)
+        && !mutes.is_muted(listener_id, talker_id)
+        && (!listener.friends_only || parties.is_friend(listener_id, talker_id))
+}
+
+/// Voice channel of a party or a room. The audio packets are either forwarded to the listeners
+/// (SFU-style, the clients decode and mix the streams) or mixed on the server into a single stream for
+/// each listener. The packets are dropped instead of queued when a listener cannot keep up, late audio
+/// is useless.
+pub struct VoiceChannel {
+    id: String,
+    config: Arc<VoiceConfig>,
+    mutes: VoiceMutes,
+    parties: PartyManager,
+    members: HashMap<String, VoiceMember>,
+    next_slot: u16,
+    mixer: Option<VoiceMixer>,
+}
+
+impl VoiceChannel {
+    pub fn new(id: String, config: Arc<VoiceConfig>, mutes: VoiceMutes, parties: PartyManager) -> VoiceChannel {
+        let mixer = match config.mode {
+            VoiceMode::Forward => None,
+            VoiceMode::Mix => Some(VoiceMixer::default()),
+        };
+        VoiceChannel {
+            id,
+            config,
+            mutes,
+            parties,
+            members: HashMap::new(),
+            next_slot: MIXED_SLOT + 1,
+            mixer,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn broadcast(&self, control: VoiceControl) {
+        for member in self.members.values() {
+            let _ = member.recipient.do_send(VoiceUpdate::Control(control.clone()));
+        }
+    }
+
+    fn broadcast_members(&self) {
+        let mut members = self
+            .members
+            .iter()
+            .map(|(user_id, member)| VoiceMemberInfo {
+                slot: member.slot,
+                user_id: user_id.clone(),
+            })
+            .collect::<Vec<_>>();
+        members.sort_by_key(|member| member.slot);
+        self.broadcast(VoiceControl::Members { members });
+    }
+
+    fn forward(&self, talker_id: &str, slot: u16, packet: &VoicePacket) {
+        let data = packet.to_forward(slot);
+        for (listener_id, listener) in &self.members {
+            if can_hear(&self.parties, &self.mutes, listener_id, listener, talker_id) {
+                // unreliable: the packet is dropped if the mailbox of the listener is full
+                let _ = listener.recipient.try_send(VoiceUpdate::Audio(data.clone()));
+            }
+        }
+    }
+
+    /// Send the mix of the period to the listeners
+    fn mix(&mut self) {
+        let mixer = match &mut self.mixer {
+            Some(mixer) if mixer.has_frames() => mixer,
+            _ => return,
+        };
+
+        let (parties, mutes) = (&self.parties, &self.mutes);
+        let talkers = self
+            .members
+            .iter()
+            .map(|(user_id, member)| (member.slot, user_id.clone()))
+            .collect::<HashMap<_, _>>();
+        for (listener_id, listener) in &self.members {
+            let audible = |slot: u16| {
+                talkers
+                    .get(&slot)
+                    .map(|talker_id| can_hear(parties, mutes, listener_id, listener, talker_id))
+                    .unwrap_or(false)
+            };
+            match mixer.mix(listener.slot, audible) {
+                Ok(Some(payload)) => {
+                    let packet = VoicePacket {
+                        flags: 0,
+                        sequence: mixer.sequence(),
+                        payload,
+                    };
+                    let _ = listener
+                        .recipient
+                        .try_send(VoiceUpdate::Audio(packet.to_forward(MIXED_SLOT)));
+                }
+                Ok(None) => {}
+                Err(err) => log::warn!("Voice channel {} failed to mix for {}: {:?}", self.id, listener_id, err),
+            }
+        }
+        mixer.end_frame();
+    }
+}
+
+impl Actor for VoiceChannel {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        log::info!("Voice channel {} started ({:?})", self.id, self.config.mode);
+        if self.mixer.is_some() {
+            ctx.run_interval(Duration::from_millis(self.config.frame_ms), |channel, _ctx| {
+                channel.mix()
+            });
+        }
+    }
+
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        log::info!("Voice channel {} stopped", self.id);
+        for (_, member) in self.members.drain() {
+            let _ = member.recipient.do_send(VoiceUpdate::Closed);
+        }
+    }
+}
+
+impl Handler<JoinVoice> for VoiceChannel {
+    type Result = Result<(), VoiceError>;
+
+    fn handle(&mut self, msg: JoinVoice, _ctx: &mut Self::Context) -> Self::Result {
+        if self.members.contains_key(&msg.user_id) {
+            return Err(VoiceError::AlreadyJoined);
+        }
+        if self.members.len() >= self.config.max_members {
+            return Err(VoiceError::ChannelFull);
+        }
+
+        let slot = self.next_slot;
+        self.next_slot = self.next_slot.checked_add(1).unwrap_or(MIXED_SLOT + 1);
+        log::info!("User {} joined voice channel {} in slot {}", msg.user_id, self.id, slot);
+        self.members.insert(
+            msg.user_id,
+            VoiceMember {
+                slot,
+                friends_only: msg.friends_only,
+                talking: false,
+                recipient: msg.recipient,
+            },
+        );
+        self.broadcast_members();
+        Ok(())
+    }
+}
+
+impl Handler<LeaveVoice> for VoiceChannel {
+    type Result = ();
+
+    fn handle(&mut self, msg: LeaveVoice, ctx: &mut Self::Context) {
+        if let Some(member) = self.members.remove(&msg.user_id) {
+            log::info!("User {} left voice channel {}", msg.user_id, self.id);
+            if let Some(mixer) = &mut self.mixer {
+                mixer.remove(member.slot);
+            }
+            self.broadcast_members();
+        }
+        if self.members.is_empty() {
+            ctx.stop();
+        }
+    }
+}
+
+impl Handler<VoiceInput> for VoiceChannel {
+    type Result = ();
+
+    fn handle(&mut self, msg: VoiceInput, _ctx: &mut Self::Context) {
+        let (slot, talking) = match self.members.get_mut(&msg.user_id) {
+            Some(member) => {
+                let talking = !msg.packet.is_end_of_talk();
+                let changed = member.talking != talking;
+                member.talking = talking;
+                (member.slot, if changed { Some(talking) } else { None })
+            }
+            None => return,
+        };
+        if let Some(talking) = talking {
+            self.broadcast(VoiceControl::Talking { slot, talking });
+        }
+
+        if msg.packet.payload.is_empty() {
+            return;
+        }
+        match &mut self.mixer {
+            Some(mixer) => {
+                if let Err(err) = mixer.push(slot, &msg.packet) {
+                    log::debug!(
+                        "Voice channel {} dropped a packet of {}: {:?}",
+                        self.id,
+                        msg.user_id,
+                        err
+                    );
+                }
+            }
+            None => self.forward(&msg.user_id, slot, &msg.packet),
+        }
+    }
+}
@@ -0,0 +1,44 @@
+/// Size of the header of the packets sent by the clients: flags (u8) and sequence (u16 le)
+pub const VOICE_HEADER_LEN: usize = 3;
+/// Size of the header of the packets sent to the clients: slot of the talker (u16 le), flags and sequence
+pub const VOICE_FORWARD_HEADER_LEN: usize = 5;
+/// Slot of the packets of the server side mix
+pub const MIXED_SLOT: u16 = 0;
+/// The last packet of a talk spurt (ex. push-to-talk released)
+pub const END_OF_TALK: u8 = 0x01;
+
+/// An Opus encoded audio frame sent on the binary messages of the voice socket
+#[derive(Clone, Debug, PartialEq)]
+pub struct VoicePacket {
+    pub flags: u8,
+    pub sequence: u16,
+    pub payload: Vec<u8>,
+}
+
+impl VoicePacket {
+    /// Parse a packet of a client, None if the header is incomplete
+    pub fn parse(data: &[u8]) -> Option<VoicePacket> {
+        if data.len() < VOICE_HEADER_LEN {
+            return None;
+        }
+        Some(VoicePacket {
+            flags: data[0],
+            sequence: u16::from_le_bytes([data[1], data[2]]),
+            payload: data[VOICE_HEADER_LEN..].to_vec(),
+        })
+    }
+
+    pub fn is_end_of_talk(&self) -> bool {
+        self.flags & END_OF_TALK != 0
+    }
+
+    /// Serialize the packet for the listeners with the slot of the talker
+    pub fn to_forward(&self, slot: u16) -> Vec<u8> {
+        let mut data = Vec::with_capacity(VOICE_FORWARD_HEADER_LEN + self.payload.len());
+        data.extend_from_slice(&slot.to_le_bytes());
+        data.push(self.flags);
+        data.extend_from_slice(&self.sequence.to_le_bytes());
+        data.extend_from_slice(&self.payload);
+        data
+    }
+}
@@ -0,0 +1,56 @@
+use crate::voice::{VoiceError, VoicePacket};
+use actix::{Message, Recipient};
+use serde::{Deserialize, Serialize};
+
+/// A member of a voice channel, the audio packets of the member are forwarded with the slot
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceMemberInfo {
+    pub slot: u16,
+    pub user_id: String,
+}
+
+/// Control messages sent as json text on the voice socket
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum VoiceControl {
+    /// The members of the channel, sent when a member joins or leaves
+    Members { members: Vec<VoiceMemberInfo> },
+    /// A member started or stopped talking
+    Talking { slot: u16, talking: bool },
+}
+
+/// Update sent to the voice socket of a client
+#[derive(Clone, Debug, Message)]
+#[rtype(result = "()")]
+pub enum VoiceUpdate {
+    Control(VoiceControl),
+    /// A binary audio packet, see `VoicePacket::to_forward`
+    Audio(Vec<u8>),
+    /// The channel was closed
+    Closed,
+}
+
+/// A client joins a voice channel
+#[derive(Message)]
+#[rtype(result = "Result<(), VoiceError>")]
+pub struct JoinVoice {
+    pub user_id: String,
+    /// Only the friends of the user are heard
+    pub friends_only: bool,
+    pub recipient: Recipient<VoiceUpdate>,
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct LeaveVoice {
+    pub user_id: String,
+}
+
+/// Audio of a member
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct VoiceInput {
+    pub user_id: String,
+    pub packet: VoicePacket,
+}
@@ -1,6 +1,6 @@
-use super::Config;
+use super::{config::AUTOMATION_TOKEN_HEADER, Config};
 use reqwest::{Client, StatusCode};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::error::Error;
 
 #[derive(Debug, Serialize)]
@@ -26,7 +26,7 @@ pub async fn populate_roles(cfg: &Config) -> Result<(), Box<dyn Error>> {
 
         let res = client
             .post(&format!("{}/api/roles/{}", cfg.auth, role))
-            .header("x-sh-testing-token", &cfg.test_token)
+            .header(AUTOMATION_TOKEN_HEADER, &cfg.automation_token)
             .send()
             .await?;
 
@@ -43,7 +43,7 @@ pub async fn populate_roles(cfg: &Config) -> Result<(), Box<dyn Error>> {
 
             let res = client
                 .post(&format!("{}/auth/api/roles/{}/inherit/{}", cfg.auth, role, i))
-                .header("x-sh-testing-token", &cfg.test_token)
+                .header(AUTOMATION_TOKEN_HEADER, &cfg.automation_token)
                 .send()
                 .await?;
 
@@ -58,6 +58,14 @@ pub async fn populate_roles(cfg: &Config) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Result of an item of the bulk import
+#[derive(Debug, Deserialize)]
+pub struct ImportResult {
+    index: usize,
+    id: Option<String>,
+    error: Option<String>,
+}
+
 pub async fn populate_users(cfg: &Config) -> Result<(), Box<dyn Error>> {
     let users = vec![
         ("gzp", "123", Some("gzp@example.com")),
@@ -67,27 +75,34 @@ pub async fn populate_users(cfg: &Config) -> Result<(), Box<dyn Error>> {
         ("/#?", "123", None),
     ];
 
-    let client = Client::new();
-    for (user, pass, email) in users {
-        log::info!("registering user {}", user);
-
-        let body = RegistrationParams {
-            name: user.to_owned(),
-            password: pass.to_owned(),
+    let body: Vec<_> = users
+        .iter()
+        .map(|(user, pass, email)| RegistrationParams {
+            name: (*user).to_owned(),
+            password: (*pass).to_owned(),
             email: email.map(|e| e.to_owned()),
-        };
-
-        let res = client
-            .post(&format!("{}/api/users/register", cfg.auth))
-            .header("x-sh-testing-token", &cfg.test_token)
-            .json(&body)
-            .send()
-            .await?;
+        })
+        .collect();
+
+    log::info!("importing {} users", body.len());
+    let res = Client::new()
+        .post(&format!("{}/api/users/import", cfg.auth))
+        .header(AUTOMATION_TOKEN_HEADER, &cfg.automation_token)
+        .json(&body)
+        .send()
+        .await?;
+
+    match res.status() {
+        StatusCode::OK => {}
+        c => return Err(format!("Unexpected status code: {}", c).into()),
+    }
 
-        match res.status() {
-            StatusCode::OK => {}
-            StatusCode::CONFLICT => log::warn!("user {} already registered", user),
-            c => return Err(format!("Unexpected status code: {}", c).into()),
+    for result in res.json::<Vec<ImportResult>>().await? {
+        let (user, _, _) = users[result.index];
+        match (result.id, result.error) {
+            (Some(id), _) => log::info!("user {} registered as {}", user, id),
+            (None, Some(ref error)) if error == "NameTaken" => log::warn!("user {} already registered", user),
+            (None, error) => return Err(format!("Failed to register user {}: {:?}", user, error).into()),
         }
     }
 
@@ -1,12 +1,46 @@
+use super::{load::LoadConfig, scenario_runner::ScenarioConfig};
 use config::{self, ConfigError};
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::path::Path;
 
+/// Header of the automation token granting access to the testing endpoints
+pub const AUTOMATION_TOKEN_HEADER: &str = "x-sh-automation-token";
+
+/// The task of the tool
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Command {
+    /// Register the roles and the users of the test data
+    Populate,
+    /// Measure the capacity of the session endpoints
+    Load,
+    /// Create the entities of a scenario file, or delete them with `--teardown`
+    Scenario,
+}
+
+impl Default for Command {
+    fn default() -> Self {
+        Command::Populate
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Config {
     pub auth: String,
-    pub test_token: String,
+    pub gamestate: String,
+    /// Automation token minted by the `api/automation/tokens` endpoint of the auth service. The token shall
+    /// have the role.write, user.import and automation.user.login scopes, the scenarios also require the
+    /// oauth.write and user.purge scopes.
+    pub automation_token: String,
+    #[serde(default)]
+    pub command: Command,
+    #[serde(default)]
+    pub load: LoadConfig,
+    #[serde(default)]
+    pub scenario: Option<ScenarioConfig>,
+    /// Delete the entities created by the scenario instead of creating them
+    #[serde(skip)]
+    pub teardown: bool,
 }
 
 impl Config {
@@ -17,7 +51,8 @@ impl Config {
         s.merge(File::from_str(
             r#"
             {
-                "auth": "http://localhost:12345/auth"
+                "auth": "http://localhost:12345/auth",
+                "gamestate": "http://localhost:12345/gamestate"
             }
             "#,
             FileFormat::Json,
@@ -25,7 +60,8 @@ impl Config {
 
         s.merge(Environment::new().separator("--"))?;
 
-        if let Some(config_file) = env::args().skip(1).next() {
+        let teardown = env::args().skip(1).any(|arg| arg == "--teardown");
+        if let Some(config_file) = env::args().skip(1).find(|arg| !arg.starts_with("--")) {
             log::info!("Loading cofig file {:?}", config_file);
             match s.merge(File::from(Path::new(&config_file))) {
                 Ok(_) => {}
@@ -33,6 +69,8 @@ impl Config {
             };
         }
 
-        s.try_into()
+        let mut config: Config = s.try_into()?;
+        config.teardown = teardown;
+        Ok(config)
     }
 }
@@ -0,0 +1,401 @@
+use super::{config::AUTOMATION_TOKEN_HEADER, Config};
+use futures::future;
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    future::Future,
+    time::{Duration, Instant},
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LoadConfig {
+    /// Number of the parallel login/validate/refresh loops
+    #[serde(default = "LoadConfig::default_concurrency")]
+    pub concurrency: usize,
+    /// Number of the parallel loops opening and closing notification streams
+    #[serde(default = "LoadConfig::default_churn_concurrency")]
+    pub churn_concurrency: usize,
+    #[serde(default = "LoadConfig::default_duration_s")]
+    pub duration_s: u64,
+    /// Number of validations in a session before it is refreshed
+    #[serde(default = "LoadConfig::default_validate_per_refresh")]
+    pub validate_per_refresh: usize,
+    /// Number of refreshes before the session is closed and a new login is performed
+    #[serde(default = "LoadConfig::default_refresh_per_login")]
+    pub refresh_per_login: usize,
+    /// Time a notification stream is kept open before the session is closed
+    #[serde(default = "LoadConfig::default_stream_hold_ms")]
+    pub stream_hold_ms: u64,
+    #[serde(default = "LoadConfig::default_user_prefix")]
+    pub user_prefix: String,
+    #[serde(default = "LoadConfig::default_password")]
+    pub password: String,
+}
+
+impl LoadConfig {
+    fn default_concurrency() -> usize {
+        10
+    }
+
+    fn default_churn_concurrency() -> usize {
+        2
+    }
+
+    fn default_duration_s() -> u64 {
+        30
+    }
+
+    fn default_validate_per_refresh() -> usize {
+        5
+    }
+
+    fn default_refresh_per_login() -> usize {
+        3
+    }
+
+    fn default_stream_hold_ms() -> u64 {
+        1000
+    }
+
+    fn default_user_prefix() -> String {
+        "load-user-".to_owned()
+    }
+
+    fn default_password() -> String {
+        "Load-Test-Pa55word".to_owned()
+    }
+
+    fn user_count(&self) -> usize {
+        self.concurrency + self.churn_concurrency
+    }
+
+    fn user_name(&self, index: usize) -> String {
+        format!("{}{}", self.user_prefix, index)
+    }
+}
+
+impl Default for LoadConfig {
+    fn default() -> Self {
+        LoadConfig {
+            concurrency: LoadConfig::default_concurrency(),
+            churn_concurrency: LoadConfig::default_churn_concurrency(),
+            duration_s: LoadConfig::default_duration_s(),
+            validate_per_refresh: LoadConfig::default_validate_per_refresh(),
+            refresh_per_login: LoadConfig::default_refresh_per_login(),
+            stream_hold_ms: LoadConfig::default_stream_hold_ms(),
+            user_prefix: LoadConfig::default_user_prefix(),
+            password: LoadConfig::default_password(),
+        }
+    }
+}
+
+/// Latencies and failures of an operation
+#[derive(Default)]
+struct OperationStats {
+    latencies: Vec<Duration>,
+    errors: BTreeMap<String, usize>,
+}
+
+impl OperationStats {
+    fn percentile(sorted: &[Duration], p: f64) -> Duration {
+        let index = ((sorted.len() as f64 - 1.) * p).round() as usize;
+        sorted[index]
+    }
+
+    fn report(&mut self, name: &str, elapsed: Duration) {
+        let errors: usize = self.errors.values().sum();
+        let total = self.latencies.len() + errors;
+        log::info!(
+            "{:<16} count: {:>8}, rate: {:>8.1}/s, errors: {:>6} ({:.2}%)",
+            name,
+            total,
+            total as f64 / elapsed.as_secs_f64(),
+            errors,
+            100. * errors as f64 / (total.max(1) as f64)
+        );
+
+        if !self.latencies.is_empty() {
+            self.latencies.sort();
+            let ms = |d: Duration| d.as_secs_f64() * 1000.;
+            log::info!(
+                "{:<16} p50: {:.1}ms, p90: {:.1}ms, p99: {:.1}ms, max: {:.1}ms",
+                "",
+                ms(Self::percentile(&self.latencies, 0.5)),
+                ms(Self::percentile(&self.latencies, 0.9)),
+                ms(Self::percentile(&self.latencies, 0.99)),
+                ms(*self.latencies.last().unwrap())
+            );
+        }
+        for (error, count) in &self.errors {
+            log::info!("{:<16} {}: {}", "", error, count);
+        }
+    }
+}
+
+/// Statistics collected by a worker, merged at the end of the run
+#[derive(Default)]
+struct LoadStats(HashMap<&'static str, OperationStats>);
+
+impl LoadStats {
+    /// Execute and measure an operation
+    async fn measure<F, T>(&mut self, name: &'static str, operation: F) -> Option<T>
+    where
+        F: Future<Output = Result<T, String>>,
+    {
+        let start = Instant::now();
+        let result = operation.await;
+        let stats = self.0.entry(name).or_default();
+        match result {
+            Ok(value) => {
+                stats.latencies.push(start.elapsed());
+                Some(value)
+            }
+            Err(err) => {
+                *stats.errors.entry(err).or_default() += 1;
+                None
+            }
+        }
+    }
+
+    fn merge(&mut self, other: LoadStats) {
+        for (name, other) in other.0 {
+            let stats = self.0.entry(name).or_default();
+            stats.latencies.extend(other.latencies);
+            for (error, count) in other.errors {
+                *stats.errors.entry(error).or_default() += count;
+            }
+        }
+    }
+
+    fn report(mut self, elapsed: Duration) {
+        let mut names: Vec<_> = self.0.keys().cloned().collect();
+        names.sort();
+        for name in names {
+            self.0.get_mut(name).unwrap().report(name, elapsed);
+        }
+    }
+}
+
+/// Map the failure of a request to the key of the error statistics
+fn check_response(response: Result<reqwest::Response, reqwest::Error>) -> Result<reqwest::Response, String> {
+    match response {
+        Ok(response) if response.status().is_success() => Ok(response),
+        Ok(response) => Err(format!("status {}", response.status())),
+        Err(err) if err.is_timeout() => Err("timeout".to_owned()),
+        Err(err) if err.is_connect() => Err("connect".to_owned()),
+        Err(_) => Err("request".to_owned()),
+    }
+}
+
+#[derive(Serialize)]
+struct LogoutParams {
+    force: bool,
+}
+
+/// A client with its own cookie session
+struct SessionClient<'a> {
+    cfg: &'a Config,
+    client: Client,
+    name: String,
+}
+
+impl<'a> SessionClient<'a> {
+    fn new(cfg: &'a Config, name: String) -> Result<Self, Box<dyn Error>> {
+        let client = Client::builder()
+            .cookie_store(true)
+            .timeout(Duration::from_secs(30))
+            .build()?;
+        Ok(SessionClient { cfg, client, name })
+    }
+
+    async fn login(&self) -> Result<(), String> {
+        let response = self
+            .client
+            .post(&format!("{}/api/users/login", self.cfg.auth))
+            .header(AUTOMATION_TOKEN_HEADER, &self.cfg.automation_token)
+            .basic_auth(&self.name, Some(&self.cfg.load.password))
+            .send()
+            .await;
+        check_response(response).map(|_| ())
+    }
+
+    async fn post(&self, path: &str) -> Result<reqwest::Response, String> {
+        let response = self
+            .client
+            .post(&format!("{}/api/users/{}", self.cfg.auth, path))
+            .send()
+            .await;
+        check_response(response)
+    }
+
+    async fn logout(&self) -> Result<(), String> {
+        let response = self
+            .client
+            .post(&format!("{}/api/users/logout", self.cfg.auth))
+            .json(&LogoutParams { force: false })
+            .send()
+            .await;
+        check_response(response).map(|_| ())
+    }
+
+    async fn open_notifications(&self) -> Result<reqwest::Response, String> {
+        let response = self
+            .client
+            .get(&format!("{}/api/users/me/notifications", self.cfg.auth))
+            .timeout(Duration::from_secs(3600))
+            .send()
+            .await;
+        check_response(response)
+    }
+}
+
+/// Register the users of the load test, the users of a previous run are reused
+async fn prepare_users(cfg: &Config) -> Result<(), Box<dyn Error>> {
+    #[derive(Serialize)]
+    struct ImportedUser {
+        name: String,
+        password: String,
+    }
+
+    #[derive(Deserialize)]
+    struct ImportResult {
+        error: Option<String>,
+    }
+
+    let users: Vec<_> = (0..cfg.load.user_count())
+        .map(|i| ImportedUser {
+            name: cfg.load.user_name(i),
+            password: cfg.load.password.clone(),
+        })
+        .collect();
+
+    log::info!("preparing {} load test users", users.len());
+    let res = Client::new()
+        .post(&format!("{}/api/users/import", cfg.auth))
+        .header(AUTOMATION_TOKEN_HEADER, &cfg.automation_token)
+        .json(&users)
+        .send()
+        .await?;
+
+    match res.status() {
+        StatusCode::OK => {}
+        c => return Err(format!("Unexpected status code: {}", c).into()),
+    }
+
+    for (user, result) in users.iter().zip(res.json::<Vec<ImportResult>>().await?) {
+        match result.error {
+            None => {}
+            Some(ref error) if error == "NameTaken" => {}
+            Some(error) => return Err(format!("Failed to register user {}: {}", user.name, error).into()),
+        }
+    }
+    Ok(())
+}
+
+/// Login, validate and refresh the session in a loop
+async fn session_loop(cfg: &Config, index: usize, deadline: Instant) -> Result<LoadStats, Box<dyn Error>> {
+    let client = SessionClient::new(cfg, cfg.load.user_name(index))?;
+    let mut stats = LoadStats::default();
+
+    while Instant::now() < deadline {
+        if stats.measure("login", client.login()).await.is_none() {
+            continue;
+        }
+
+        'session: for _ in 0..cfg.load.refresh_per_login {
+            for _ in 0..cfg.load.validate_per_refresh {
+                if Instant::now() >= deadline {
+                    break 'session;
+                }
+                stats.measure("validate", client.post("validate")).await;
+            }
+            if stats.measure("refresh", client.post("refresh")).await.is_none() {
+                break;
+            }
+        }
+
+        stats.measure("logout", client.logout()).await;
+    }
+
+    Ok(stats)
+}
+
+/// Open a notification stream, keep it for a while and close the session. The stream is expected to be
+/// ended by the service when the session is closed.
+async fn churn_loop(cfg: &Config, index: usize, deadline: Instant) -> Result<LoadStats, Box<dyn Error>> {
+    let client = SessionClient::new(cfg, cfg.load.user_name(cfg.load.concurrency + index))?;
+    let mut stats = LoadStats::default();
+    let hold = Duration::from_millis(cfg.load.stream_hold_ms);
+
+    while Instant::now() < deadline {
+        if stats.measure("stream_login", client.login()).await.is_none() {
+            continue;
+        }
+
+        let mut stream = match stats.measure("stream_open", client.open_notifications()).await {
+            Some(stream) => stream,
+            None => {
+                stats.measure("stream_logout", client.logout()).await;
+                continue;
+            }
+        };
+        tokio::time::delay_for(hold).await;
+
+        stats.measure("stream_logout", client.logout()).await;
+        stats
+            .measure("stream_close", async {
+                let drain = async {
+                    while let Some(_) = stream.chunk().await.map_err(|_| "stream error".to_owned())? {}
+                    Ok::<(), String>(())
+                };
+                tokio::time::timeout(Duration::from_secs(5), drain)
+                    .await
+                    .unwrap_or_else(|_| Err("not closed".to_owned()))
+            })
+            .await;
+    }
+
+    Ok(stats)
+}
+
+/// Run the session and notification loops in parallel for the configured duration and report the
+/// latencies and the error rates of the operations.
+pub async fn run_load(cfg: &Config) -> Result<(), Box<dyn Error>> {
+    prepare_users(cfg).await?;
+
+    log::info!(
+        "running load for {}s with {} session loops and {} stream loops",
+        cfg.load.duration_s,
+        cfg.load.concurrency,
+        cfg.load.churn_concurrency
+    );
+    let start = Instant::now();
+    let deadline = start + Duration::from_secs(cfg.load.duration_s);
+
+    let mut workers = Vec::new();
+    for index in 0..cfg.load.concurrency {
+        let cfg = cfg.clone();
+        workers.push(tokio::spawn(async move {
+            session_loop(&cfg, index, deadline).await.map_err(|err| err.to_string())
+        }));
+    }
+    for index in 0..cfg.load.churn_concurrency {
+        let cfg = cfg.clone();
+        workers.push(tokio::spawn(async move {
+            churn_loop(&cfg, index, deadline).await.map_err(|err| err.to_string())
+        }));
+    }
+
+    let mut stats = LoadStats::default();
+    for result in future::join_all(workers).await {
+        match result? {
+            Ok(worker_stats) => stats.merge(worker_stats),
+            Err(err) => log::error!("load worker failed: {}", err),
+        }
+    }
+
+    stats.report(start.elapsed());
+    Ok(())
+}
@@ -1,7 +1,10 @@
 mod auth;
 mod config;
+mod load;
+mod scenario;
+mod scenario_runner;
 
-use self::config::Config;
+use self::config::{Command, Config};
 use std::error::Error;
 
 #[tokio::main]
@@ -12,7 +15,20 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     let config = Config::new()?;
 
-    auth::populate_roles(&config).await?;
-    auth::populate_users(&config).await?;
+    match config.command {
+        Command::Populate => {
+            auth::populate_roles(&config).await?;
+            auth::populate_users(&config).await?;
+        }
+        Command::Load => load::run_load(&config).await?,
+        Command::Scenario => {
+            let scenario = config.scenario.as_ref().ok_or("Missing scenario config")?;
+            if config.teardown {
+                scenario_runner::teardown_scenario(&config, scenario).await?;
+            } else {
+                scenario_runner::run_scenario(&config, scenario).await?;
+            }
+        }
+    }
     Ok(())
 }
@@ -0,0 +1,253 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, error::Error, fs, path::Path};
+
+/// Placeholder of the index in the names of the generated users
+const INDEX_PLACEHOLDER: &str = "{}";
+
+/// A role to create with its inherited roles and permissions
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RoleSpec {
+    pub name: String,
+    #[serde(default)]
+    pub inherits: Vec<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+/// A user or, if the count is given, a group of users. The name of a group shall contain a `{}` replaced by
+/// the index of the user. If the password is not given, it is generated from the seed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UserSpec {
+    pub name: String,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(default)]
+    pub count: Option<usize>,
+}
+
+/// Sessions opened for a user (or for each user of a group)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SessionSpec {
+    pub user: String,
+    #[serde(default = "SessionSpec::default_count")]
+    pub count: usize,
+}
+
+impl SessionSpec {
+    fn default_count() -> usize {
+        1
+    }
+}
+
+/// A lobby created by the owner and joined by the members. If the skill is not given, it is generated
+/// from the seed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LobbySpec {
+    pub owner: String,
+    #[serde(default)]
+    pub skill: Option<u32>,
+    #[serde(default)]
+    pub members: Vec<String>,
+}
+
+/// Description of the test data to create. The names of the users and roles are prefixed, thus the
+/// scenarios of the parallel CI runs do not collide.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Scenario {
+    #[serde(default)]
+    pub seed: u64,
+    #[serde(default)]
+    pub prefix: String,
+    #[serde(default)]
+    pub roles: Vec<RoleSpec>,
+    #[serde(default)]
+    pub users: Vec<UserSpec>,
+    #[serde(default)]
+    pub sessions: Vec<SessionSpec>,
+    #[serde(default)]
+    pub lobbies: Vec<LobbySpec>,
+}
+
+/// A user with the final name and password
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PlannedUser {
+    pub name: String,
+    pub password: String,
+    pub email: Option<String>,
+    pub roles: Vec<String>,
+}
+
+/// A lobby with the final names of the participants
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PlannedLobby {
+    pub owner: String,
+    pub skill: u32,
+    pub members: Vec<String>,
+}
+
+/// The expanded scenario, the same scenario and seed always give the same plan
+#[derive(Clone, Debug)]
+pub struct Plan {
+    pub operator: PlannedUser,
+    pub roles: Vec<RoleSpec>,
+    pub users: Vec<PlannedUser>,
+    pub sessions: Vec<(String, usize)>,
+    pub lobbies: Vec<PlannedLobby>,
+}
+
+/// Deterministic random generator of the scenarios (SplitMix64), the std generators are not guaranteed to
+/// give the same sequence across versions.
+pub struct SeededRng(u64);
+
+impl SeededRng {
+    pub fn new(seed: u64) -> SeededRng {
+        SeededRng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    pub fn below(&mut self, limit: u32) -> u32 {
+        (self.next_u64() % u64::from(limit.max(1))) as u32
+    }
+
+    /// A password meeting the usual policies: upper and lower case letters and digits
+    pub fn password(&mut self) -> String {
+        const LOWER: &[u8] = b"abcdefghijkmnopqrstuvwxyz";
+        const UPPER: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ";
+        const DIGIT: &[u8] = b"23456789";
+        let mut password = String::with_capacity(16);
+        for i in 0..16 {
+            let set = match i % 4 {
+                0 => UPPER,
+                3 => DIGIT,
+                _ => LOWER,
+            };
+            password.push(set[self.below(set.len() as u32) as usize] as char);
+        }
+        password
+    }
+}
+
+impl Scenario {
+    /// Load a scenario, the format is selected by the extension: yaml, yml or ron
+    pub fn load(path: &Path) -> Result<Scenario, Box<dyn Error>> {
+        let content = fs::read_to_string(path)?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("yaml") | Some("yml") => Ok(serde_yaml::from_str(&content)?),
+            Some("ron") => Ok(ron::de::from_str(&content)?),
+            _ => Err(format!("Unknown scenario format: {:?}", path).into()),
+        }
+    }
+
+    fn prefixed(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+
+    /// Expand the user groups, generate the missing values and check the references
+    pub fn plan(&self) -> Result<Plan, String> {
+        let mut rng = SeededRng::new(self.seed);
+
+        let roles: Vec<RoleSpec> = self
+            .roles
+            .iter()
+            .map(|role| RoleSpec {
+                name: self.prefixed(&role.name),
+                inherits: role.inherits.iter().map(|name| self.prefixed(name)).collect(),
+                permissions: role.permissions.clone(),
+            })
+            .collect();
+        let role_names: HashSet<&str> = roles.iter().map(|role| role.name.as_str()).collect();
+        for role in &roles {
+            if let Some(unknown) = role.inherits.iter().find(|name| !role_names.contains(name.as_str())) {
+                return Err(format!("Role {} inherits the unknown role {}", role.name, unknown));
+            }
+        }
+
+        let mut users = Vec::new();
+        // groups are referenced by their name template, the members of a group are listed together
+        let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+        for spec in &self.users {
+            let names: Vec<String> = match spec.count {
+                Some(count) => {
+                    if !spec.name.contains(INDEX_PLACEHOLDER) {
+                        return Err(format!("The name of the user group {} has no {{}}", spec.name));
+                    }
+                    (0..count)
+                        .map(|i| self.prefixed(&spec.name.replace(INDEX_PLACEHOLDER, &i.to_string())))
+                        .collect()
+                }
+                None => vec![self.prefixed(&spec.name)],
+            };
+            for role in &spec.roles {
+                if !role_names.contains(self.prefixed(role).as_str()) {
+                    return Err(format!("User {} has the unknown role {}", spec.name, role));
+                }
+            }
+
+            for name in &names {
+                users.push(PlannedUser {
+                    name: name.clone(),
+                    password: spec.password.clone().unwrap_or_else(|| rng.password()),
+                    email: spec.email.clone(),
+                    roles: spec.roles.iter().map(|role| self.prefixed(role)).collect(),
+                });
+            }
+            groups.push((spec.name.clone(), names));
+        }
+
+        let resolve = |name: &str| -> Result<Vec<String>, String> {
+            groups
+                .iter()
+                .find(|(group, _)| group == name)
+                .map(|(_, names)| names.clone())
+                .ok_or_else(|| format!("Unknown user {}", name))
+        };
+
+        let mut sessions = Vec::new();
+        for spec in &self.sessions {
+            for name in resolve(&spec.user)? {
+                sessions.push((name, spec.count));
+            }
+        }
+
+        let mut lobbies = Vec::new();
+        for spec in &self.lobbies {
+            for owner in resolve(&spec.owner)? {
+                let mut members = Vec::new();
+                for member in &spec.members {
+                    members.extend(resolve(member)?.into_iter().filter(|name| *name != owner));
+                }
+                lobbies.push(PlannedLobby {
+                    owner,
+                    skill: spec.skill.unwrap_or_else(|| 800 + rng.below(1200)),
+                    members,
+                });
+            }
+        }
+
+        let operator = PlannedUser {
+            name: self.prefixed("operator"),
+            password: rng.password(),
+            email: None,
+            roles: Vec::new(),
+        };
+
+        Ok(Plan {
+            operator,
+            roles,
+            users,
+            sessions,
+            lobbies,
+        })
+    }
+}
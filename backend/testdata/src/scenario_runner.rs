@@ -0,0 +1,705 @@
+use super::{
+    config::AUTOMATION_TOKEN_HEADER,
+    scenario::{Plan, PlannedLobby, PlannedUser, Scenario},
+    Config,
+};
+use futures::{stream, Future, StreamExt};
+use reqwest::{Client, Method, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fs, path::Path, time::Duration};
+
+/// Maximum number of the users of an import request
+const IMPORT_CHUNK_SIZE: usize = 500;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScenarioConfig {
+    /// The scenario file, yaml or ron
+    pub file: String,
+    /// Number of the parallel requests
+    #[serde(default = "ScenarioConfig::default_concurrency")]
+    pub concurrency: usize,
+    /// Number of the retries of the throttled and the failed requests
+    #[serde(default = "ScenarioConfig::default_max_retries")]
+    pub max_retries: u32,
+    /// Delay before the first retry, it is doubled for each further retry
+    #[serde(default = "ScenarioConfig::default_backoff_ms")]
+    pub backoff_ms: u64,
+    /// File recording the created entities for the teardown, `<file>.created.json` if not given
+    #[serde(default)]
+    pub state_file: Option<String>,
+    /// Redirect uri of the OAuth client used to sign in to the gamestate
+    #[serde(default = "ScenarioConfig::default_redirect_uri")]
+    pub redirect_uri: String,
+}
+
+impl ScenarioConfig {
+    fn default_concurrency() -> usize {
+        8
+    }
+
+    fn default_max_retries() -> u32 {
+        5
+    }
+
+    fn default_backoff_ms() -> u64 {
+        200
+    }
+
+    fn default_redirect_uri() -> String {
+        "http://localhost/testdata/callback".to_owned()
+    }
+
+    fn state_file(&self) -> String {
+        self.state_file
+            .clone()
+            .unwrap_or_else(|| format!("{}.created.json", self.file))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct CreatedUser {
+    id: String,
+    name: String,
+    password: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct CreatedClient {
+    client_id: String,
+    client_secret: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct CreatedLobby {
+    id: String,
+    /// Name of the owner and the members that have joined
+    participants: Vec<String>,
+}
+
+/// The entities created by a run, only these are deleted by the teardown. The pre-existing entities (ex.
+/// users of a previous run) are kept.
+#[derive(Default, Debug, Serialize, Deserialize)]
+struct Created {
+    operator: Option<CreatedUser>,
+    roles: Vec<String>,
+    users: Vec<CreatedUser>,
+    oauth_client: Option<CreatedClient>,
+    lobbies: Vec<CreatedLobby>,
+}
+
+impl Created {
+    fn load(path: &str) -> Result<Created, Box<dyn Error>> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Save after each step, thus a failed run can be torn down too
+    fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    fn find_user(&self, name: &str) -> Option<&CreatedUser> {
+        self.users
+            .iter()
+            .chain(self.operator.iter())
+            .find(|user| user.name == name)
+    }
+}
+
+fn is_transient(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Send a request, the throttled requests and the server and network errors are retried with an exponential
+/// backoff.
+async fn send_with_backoff<F>(cfg: &ScenarioConfig, what: &str, request: F) -> Result<Response, String>
+where
+    F: Fn() -> RequestBuilder,
+{
+    let mut delay = Duration::from_millis(cfg.backoff_ms);
+    for attempt in 0..=cfg.max_retries {
+        match request().send().await {
+            Ok(response) if !is_transient(response.status()) => return Ok(response),
+            Ok(response) => log::warn!("{} failed (attempt {}): {}", what, attempt + 1, response.status()),
+            Err(err) => log::warn!("{} failed (attempt {}): {}", what, attempt + 1, err),
+        }
+        if attempt < cfg.max_retries {
+            tokio::time::delay_for(delay).await;
+            delay *= 2;
+        }
+    }
+    Err(format!("{} failed after {} attempts", what, cfg.max_retries + 1))
+}
+
+async fn expect_success(response: Response, what: &str) -> Result<Response, String> {
+    if response.status().is_success() {
+        Ok(response)
+    } else {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        Err(format!("{} failed: {} {}", what, status, body))
+    }
+}
+
+/// Run the operations with the configured parallelism and collect the results in the original order
+async fn run_parallel<T, F>(cfg: &ScenarioConfig, operations: Vec<F>) -> Vec<T>
+where
+    F: Future<Output = T>,
+{
+    let mut results: Vec<(usize, T)> = stream::iter(operations.into_iter().enumerate())
+        .map(|(index, operation)| async move { (index, operation.await) })
+        .buffer_unordered(cfg.concurrency.max(1))
+        .collect()
+        .await;
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// A cookie session of the auth service and the bearer token of the gamestate
+struct UserClient<'a> {
+    cfg: &'a Config,
+    scenario: &'a ScenarioConfig,
+    client: Client,
+    name: String,
+    token: Option<String>,
+}
+
+impl<'a> UserClient<'a> {
+    fn new(cfg: &'a Config, scenario: &'a ScenarioConfig, name: &str) -> Result<UserClient<'a>, String> {
+        let client = Client::builder()
+            .cookie_store(true)
+            .timeout(Duration::from_secs(30))
+            .build()
+            .map_err(|err| err.to_string())?;
+        Ok(UserClient {
+            cfg,
+            scenario,
+            client,
+            name: name.to_owned(),
+            token: None,
+        })
+    }
+
+    fn auth(&self, method: Method, path: &str) -> RequestBuilder {
+        self.client
+            .request(method, &format!("{}/api/{}", self.cfg.auth, path))
+            .header(AUTOMATION_TOKEN_HEADER, &self.cfg.automation_token)
+    }
+
+    fn gamestate(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self
+            .client
+            .request(method, &format!("{}/api/{}", self.cfg.gamestate, path));
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    async fn send<F>(&self, what: &str, request: F) -> Result<Response, String>
+    where
+        F: Fn() -> RequestBuilder,
+    {
+        let what = format!("{} ({})", what, self.name);
+        let response = send_with_backoff(self.scenario, &what, request).await?;
+        expect_success(response, &what).await
+    }
+
+    async fn login(&self, password: &str) -> Result<(), String> {
+        self.send("login", || {
+            self.auth(Method::POST, "users/login")
+                .basic_auth(&self.name, Some(password))
+        })
+        .await
+        .map(|_| ())
+    }
+
+    async fn af_token(&self) -> Result<String, String> {
+        #[derive(Deserialize)]
+        struct Token {
+            token: String,
+        }
+
+        let response = self.send("af token", || self.auth(Method::POST, "af")).await?;
+        let token: Token = response.json().await.map_err(|err| err.to_string())?;
+        Ok(token.token)
+    }
+
+    /// Authorize the OAuth client and exchange the code for the bearer token of the gamestate
+    async fn sign_in(&mut self, client: &CreatedClient) -> Result<(), String> {
+        #[derive(Serialize)]
+        struct AuthorizeParams<'a> {
+            client_id: &'a str,
+            redirect_uri: &'a str,
+            af: &'a str,
+        }
+
+        #[derive(Deserialize)]
+        struct Authorized {
+            redirect: String,
+        }
+
+        #[derive(Deserialize)]
+        struct AccessToken {
+            access_token: String,
+        }
+
+        let af = self.af_token().await?;
+        let params = AuthorizeParams {
+            client_id: &client.client_id,
+            redirect_uri: &self.scenario.redirect_uri,
+            af: &af,
+        };
+        let response = self
+            .send("authorize", || self.auth(Method::POST, "oauth/authorize").json(&params))
+            .await?;
+        let authorized: Authorized = response.json().await.map_err(|err| err.to_string())?;
+        let code = authorized
+            .redirect
+            .split(|c| c == '?' || c == '&')
+            .find_map(|param| param.strip_prefix("code="))
+            .ok_or_else(|| format!("Missing code in redirect: {}", authorized.redirect))?
+            .to_owned();
+
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code.as_str()),
+            ("redirect_uri", self.scenario.redirect_uri.as_str()),
+            ("client_id", client.client_id.as_str()),
+        ];
+        if let Some(secret) = &client.client_secret {
+            form.push(("client_secret", secret.as_str()));
+        }
+        // the code is single use, the exchange is not retried
+        let response = self
+            .auth(Method::POST, "oauth/token")
+            .form(&form)
+            .send()
+            .await
+            .map_err(|err| err.to_string())?;
+        let token: AccessToken = expect_success(response, "token")
+            .await?
+            .json()
+            .await
+            .map_err(|err| err.to_string())?;
+        self.token = Some(token.access_token);
+        Ok(())
+    }
+
+    async fn delete_me(&self, password: &str) -> Result<(), String> {
+        #[derive(Serialize)]
+        struct DeleteParams<'a> {
+            password: &'a str,
+            af: String,
+        }
+
+        let params = DeleteParams {
+            password,
+            af: self.af_token().await?,
+        };
+        self.send("delete", || self.auth(Method::DELETE, "users/me").json(&params))
+            .await
+            .map(|_| ())
+    }
+}
+
+/// Import the users, the users that are already registered are skipped
+async fn import_users(
+    cfg: &Config,
+    scenario: &ScenarioConfig,
+    users: &[PlannedUser],
+) -> Result<Vec<CreatedUser>, Box<dyn Error>> {
+    #[derive(Deserialize)]
+    struct ImportResult {
+        index: usize,
+        id: Option<String>,
+        error: Option<String>,
+    }
+
+    let client = Client::new();
+    let mut created = Vec::new();
+    for chunk in users.chunks(IMPORT_CHUNK_SIZE) {
+        let response = send_with_backoff(scenario, "import users", || {
+            client
+                .post(&format!("{}/api/users/import", cfg.auth))
+                .header(AUTOMATION_TOKEN_HEADER, &cfg.automation_token)
+                .json(chunk)
+        })
+        .await?;
+        let results: Vec<ImportResult> = expect_success(response, "import users").await?.json().await?;
+        for result in results {
+            let user = &chunk[result.index];
+            match (result.id, result.error) {
+                (Some(id), _) => created.push(CreatedUser {
+                    id,
+                    name: user.name.clone(),
+                    password: user.password.clone(),
+                }),
+                (None, Some(ref error)) if error == "NameTaken" => {
+                    log::warn!(
+                        "user {} already registered, it is not managed by the scenario",
+                        user.name
+                    )
+                }
+                (None, error) => return Err(format!("Failed to import user {}: {:?}", user.name, error).into()),
+            }
+        }
+    }
+    Ok(created)
+}
+
+async fn create_roles(operator: &UserClient<'_>, plan: &Plan, created: &mut Created) -> Result<(), Box<dyn Error>> {
+    let results = run_parallel(
+        operator.scenario,
+        plan.roles
+            .iter()
+            .map(|role| async move {
+                let what = format!("create role {}", role.name);
+                let response = send_with_backoff(operator.scenario, &what, || {
+                    operator.auth(Method::POST, &format!("roles/{}", role.name))
+                })
+                .await?;
+                match response.status() {
+                    StatusCode::CONFLICT => {
+                        log::warn!("role {} already created, it is not managed by the scenario", role.name);
+                        Ok(None)
+                    }
+                    _ => expect_success(response, &what).await.map(|_| Some(role.name.clone())),
+                }
+            })
+            .collect(),
+    )
+    .await;
+    for result in results {
+        if let Some(role) = result? {
+            created.roles.push(role);
+        }
+    }
+
+    // the role graph is built once all the roles exist
+    for role in &plan.roles {
+        for inherited in &role.inherits {
+            let what = format!("role {} inherits {}", role.name, inherited);
+            let response = send_with_backoff(operator.scenario, &what, || {
+                operator.auth(Method::POST, &format!("roles/{}/inherit/{}", role.name, inherited))
+            })
+            .await?;
+            if response.status() != StatusCode::CONFLICT {
+                expect_success(response, &what).await?;
+            }
+        }
+        for permission in &role.permissions {
+            let what = format!("role {} permission {}", role.name, permission);
+            let response = send_with_backoff(operator.scenario, &what, || {
+                operator.auth(Method::POST, &format!("roles/{}/permissions/{}", role.name, permission))
+            })
+            .await?;
+            if response.status() != StatusCode::CONFLICT {
+                expect_success(response, &what).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn open_sessions(cfg: &Config, scenario: &ScenarioConfig, plan: &Plan, created: &Created) -> Result<(), String> {
+    let logins: Vec<_> = plan
+        .sessions
+        .iter()
+        .flat_map(|(name, count)| (0..*count).map(move |_| name))
+        .map(|name| async move {
+            let user = created
+                .find_user(name)
+                .ok_or_else(|| format!("User {} is not managed by the scenario", name))?;
+            UserClient::new(cfg, scenario, name)?.login(&user.password).await
+        })
+        .collect();
+    log::info!("opening {} sessions", logins.len());
+    run_parallel(scenario, logins).await.into_iter().collect()
+}
+
+async fn register_oauth_client(operator: &UserClient<'_>) -> Result<CreatedClient, Box<dyn Error>> {
+    #[derive(Serialize)]
+    struct Params<'a> {
+        name: &'a str,
+        redirect_uris: Vec<&'a str>,
+    }
+
+    let params = Params {
+        name: "testdata",
+        redirect_uris: vec![&operator.scenario.redirect_uri],
+    };
+    let response = operator
+        .send("register oauth client", || {
+            operator.auth(Method::POST, "oauth/clients").json(&params)
+        })
+        .await?;
+    Ok(response.json().await?)
+}
+
+async fn signed_in_client<'a>(
+    cfg: &'a Config,
+    scenario: &'a ScenarioConfig,
+    created: &Created,
+    client: &CreatedClient,
+    name: &str,
+) -> Result<UserClient<'a>, String> {
+    let user = created
+        .find_user(name)
+        .ok_or_else(|| format!("User {} is not managed by the scenario", name))?;
+    let mut user_client = UserClient::new(cfg, scenario, name)?;
+    user_client.login(&user.password).await?;
+    user_client.sign_in(client).await?;
+    Ok(user_client)
+}
+
+async fn create_lobby(
+    cfg: &Config,
+    scenario: &ScenarioConfig,
+    created: &Created,
+    client: &CreatedClient,
+    lobby: &PlannedLobby,
+) -> Result<CreatedLobby, String> {
+    #[derive(Serialize)]
+    struct SkillParams {
+        skill: u32,
+    }
+
+    #[derive(Deserialize)]
+    struct Lobby {
+        id: String,
+    }
+
+    let params = SkillParams { skill: lobby.skill };
+    let owner = signed_in_client(cfg, scenario, created, client, &lobby.owner).await?;
+    let response = owner
+        .send("create lobby", || {
+            owner.gamestate(Method::POST, "lobbies").json(&params)
+        })
+        .await?;
+    let id = response.json::<Lobby>().await.map_err(|err| err.to_string())?.id;
+
+    let mut participants = vec![lobby.owner.clone()];
+    for member in &lobby.members {
+        let member_client = signed_in_client(cfg, scenario, created, client, member).await?;
+        member_client
+            .send("join lobby", || {
+                member_client
+                    .gamestate(Method::POST, &format!("lobbies/{}/join", id))
+                    .json(&params)
+            })
+            .await?;
+        participants.push(member.clone());
+    }
+    Ok(CreatedLobby { id, participants })
+}
+
+/// Create the entities of the scenario and record them for the teardown
+pub async fn run_scenario(cfg: &Config, scenario: &ScenarioConfig) -> Result<(), Box<dyn Error>> {
+    let state_file = scenario.state_file();
+    if Path::new(&state_file).exists() {
+        return Err(format!("{} exists, tear down the previous run first", state_file).into());
+    }
+
+    let plan = Scenario::load(Path::new(&scenario.file))?.plan()?;
+    log::info!(
+        "scenario {}: {} roles, {} users, {} sessions, {} lobbies",
+        scenario.file,
+        plan.roles.len(),
+        plan.users.len(),
+        plan.sessions.iter().map(|(_, count)| count).sum::<usize>(),
+        plan.lobbies.len()
+    );
+
+    let mut created = Created::default();
+    let operator = import_users(cfg, scenario, &[plan.operator.clone()])
+        .await?
+        .pop()
+        .ok_or_else(|| format!("Operator {} already exists", plan.operator.name))?;
+    created.operator = Some(operator.clone());
+    created.save(&state_file)?;
+
+    let operator_client = UserClient::new(cfg, scenario, &operator.name)?;
+    operator_client.login(&operator.password).await?;
+
+    let result = async {
+        log::info!("creating roles");
+        create_roles(&operator_client, &plan, &mut created).await?;
+        created.save(&state_file)?;
+
+        log::info!("importing users");
+        created.users = import_users(cfg, scenario, &plan.users).await?;
+        created.save(&state_file)?;
+
+        open_sessions(cfg, scenario, &plan, &created).await?;
+
+        if !plan.lobbies.is_empty() {
+            log::info!("creating lobbies");
+            let client = register_oauth_client(&operator_client).await?;
+            created.oauth_client = Some(client.clone());
+            created.save(&state_file)?;
+
+            let lobbies = run_parallel(
+                scenario,
+                plan.lobbies
+                    .iter()
+                    .map(|lobby| create_lobby(cfg, scenario, &created, &client, lobby))
+                    .collect(),
+            )
+            .await;
+            let mut first_error = None;
+            let mut created_lobbies = Vec::new();
+            for lobby in lobbies {
+                match lobby {
+                    Ok(lobby) => created_lobbies.push(lobby),
+                    Err(err) => {
+                        first_error.get_or_insert(err);
+                    }
+                }
+            }
+            created.lobbies = created_lobbies;
+            created.save(&state_file)?;
+            if let Some(err) = first_error {
+                return Err(err.into());
+            }
+        }
+        Ok::<(), Box<dyn Error>>(())
+    }
+    .await;
+
+    match &result {
+        Ok(()) => log::info!("scenario created, the created entities are recorded in {}", state_file),
+        Err(err) => log::error!("scenario failed: {}, run the teardown to clean up", err),
+    }
+    result
+}
+
+async fn purge_users(cfg: &Config, scenario: &ScenarioConfig) -> Result<(), Box<dyn Error>> {
+    #[derive(Deserialize)]
+    struct Purged {
+        purged: usize,
+    }
+
+    let client = Client::new();
+    let response = send_with_backoff(scenario, "purge users", || {
+        client
+            .post(&format!("{}/api/users/purge", cfg.auth))
+            .header(AUTOMATION_TOKEN_HEADER, &cfg.automation_token)
+    })
+    .await?;
+    let purged: Purged = expect_success(response, "purge users").await?.json().await?;
+    log::info!("{} users purged", purged.purged);
+    Ok(())
+}
+
+async fn delete_user(cfg: &Config, scenario: &ScenarioConfig, user: &CreatedUser) -> Result<(), String> {
+    let client = UserClient::new(cfg, scenario, &user.name)?;
+    client.login(&user.password).await?;
+    client.delete_me(&user.password).await
+}
+
+/// Delete the entities recorded by the run in the reverse order of the creation. The deleted users are
+/// purged at the end, they are removed immediately only if the deletion grace period of the auth service is
+/// 0, otherwise they are removed by a later purge.
+pub async fn teardown_scenario(cfg: &Config, scenario: &ScenarioConfig) -> Result<(), Box<dyn Error>> {
+    let state_file = scenario.state_file();
+    let mut created = match Created::load(&state_file) {
+        Ok(created) => created,
+        Err(err) => {
+            log::warn!("nothing to tear down, {} cannot be read: {}", state_file, err);
+            return Ok(());
+        }
+    };
+
+    if let Some(client) = created.oauth_client.clone() {
+        let leaves: Vec<_> = created
+            .lobbies
+            .iter()
+            .flat_map(|lobby| lobby.participants.iter())
+            .map(|name| {
+                let created = &created;
+                let client = &client;
+                async move {
+                    let user = signed_in_client(cfg, scenario, created, client, name).await?;
+                    user.send("leave lobby", || user.gamestate(Method::POST, "lobbies/leave"))
+                        .await
+                        .map(|_| ())
+                }
+            })
+            .collect();
+        log::info!("leaving {} lobbies", created.lobbies.len());
+        for result in run_parallel(scenario, leaves).await {
+            if let Err(err) = result {
+                log::warn!("{}", err);
+            }
+        }
+        created.lobbies.clear();
+        created.save(&state_file)?;
+    }
+
+    log::info!("deleting {} users", created.users.len());
+    let users = created.users.clone();
+    let results = run_parallel(
+        scenario,
+        users.iter().map(|user| delete_user(cfg, scenario, user)).collect(),
+    )
+    .await;
+    created.users = users
+        .into_iter()
+        .zip(results)
+        .filter_map(|(user, result)| match result {
+            Ok(()) => None,
+            Err(err) => {
+                log::warn!("{}", err);
+                Some(user)
+            }
+        })
+        .collect();
+    created.save(&state_file)?;
+
+    if let Some(operator) = created.operator.clone() {
+        let operator_client = UserClient::new(cfg, scenario, &operator.name)?;
+        operator_client.login(&operator.password).await?;
+
+        if let Some(client) = created.oauth_client.take() {
+            operator_client
+                .send("revoke oauth client", || {
+                    operator_client.auth(Method::DELETE, &format!("oauth/clients/{}", client.client_id))
+                })
+                .await?;
+            created.save(&state_file)?;
+        }
+
+        log::info!("deleting {} roles", created.roles.len());
+        while let Some(role) = created.roles.pop() {
+            let what = format!("delete role {}", role);
+            let response = send_with_backoff(scenario, &what, || {
+                operator_client.auth(Method::DELETE, &format!("roles/{}", role))
+            })
+            .await?;
+            if response.status() != StatusCode::NOT_FOUND {
+                expect_success(response, &what).await?;
+            }
+            created.save(&state_file)?;
+        }
+
+        operator_client.delete_me(&operator.password).await?;
+        created.operator = None;
+        created.save(&state_file)?;
+    }
+
+    purge_users(cfg, scenario).await?;
+
+    if created.users.is_empty() {
+        fs::remove_file(&state_file)?;
+        log::info!("teardown completed");
+        Ok(())
+    } else {
+        Err(format!(
+            "{} users could not be deleted, they are kept in {}",
+            created.users.len(),
+            state_file
+        )
+        .into())
+    }
+}
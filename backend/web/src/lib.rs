@@ -2,6 +2,7 @@ use actix_files;
 use actix_rt::SystemRunner;
 use actix_web::web;
 use serde::{Deserialize, Serialize};
+use shine_core::metrics::{Metrics, RequestMetrics};
 use std::{
     cell::{Ref, RefCell},
     fmt,
@@ -50,17 +51,24 @@ impl State {
 #[derive(Clone)]
 pub struct WebService {
     tera: Tera,
+    metrics: Metrics,
     web_folder: String,
     web_root: String,
 }
 
 impl WebService {
-    pub fn create(_sys: &mut SystemRunner, config: &WebConfig, web_root: &str) -> Result<WebService, WebCreateError> {
+    pub fn create(
+        _sys: &mut SystemRunner,
+        config: &WebConfig,
+        web_root: &str,
+        metrics: &Metrics,
+    ) -> Result<WebService, WebCreateError> {
         log::info!("Parsing tera templates");
         let tera = Tera::new(&config.tera_templates).map_err(|err| WebCreateError::ConfigureTera(err.into()))?;
 
         Ok(WebService {
             tera,
+            metrics: metrics.clone(),
             web_folder: config.web_folder.clone(),
             web_root: web_root.to_owned(),
         })
@@ -71,6 +79,7 @@ impl WebService {
 
         services.service(
             web::scope(&self.web_root)
+                .wrap(RequestMetrics::new(self.metrics.clone(), "web"))
                 .data(state.clone())
                 .service(actix_files::Files::new("/static", &self.web_folder)),
         );
@@ -1,35 +1,169 @@
 use serde::{Deserialize, Serialize};
-use shine_game::assets::Url;
+use shine_game::assets::{CookedFormat, Url};
 use std::collections::HashMap;
-use std::env;
+use std::net::SocketAddr;
 use std::path::Path;
 
 use crate::CookerError;
 
+/// Cooking profile selecting the output format
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum CookProfile {
+    Release,
+    Debug,
+}
+
+impl Default for CookProfile {
+    fn default() -> Self {
+        CookProfile::Release
+    }
+}
+
+impl CookProfile {
+    pub fn cooked_format(&self) -> CookedFormat {
+        match self {
+            CookProfile::Release => CookedFormat::Binary,
+            CookProfile::Debug => CookedFormat::Ron,
+        }
+    }
+}
+
+/// Settings of the shader compile service used by the browser based shader editing
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ShaderServiceConfig {
+    #[serde(default = "ShaderServiceConfig::default_bind")]
+    pub bind: SocketAddr,
+    /// Base of the returned cooked urls, the bind address is used if not given
+    pub public_url: Option<String>,
+    #[serde(default = "ShaderServiceConfig::default_allowed_origin")]
+    pub allowed_origin: String,
+    /// Number of the cooked shaders kept in memory
+    #[serde(default = "ShaderServiceConfig::default_max_cooked")]
+    pub max_cooked: usize,
+}
+
+impl ShaderServiceConfig {
+    fn default_bind() -> SocketAddr {
+        ([127, 0, 0, 1], 8090).into()
+    }
+
+    fn default_allowed_origin() -> String {
+        "*".to_owned()
+    }
+
+    fn default_max_cooked() -> usize {
+        64
+    }
+}
+
+impl Default for ShaderServiceConfig {
+    fn default() -> Self {
+        ShaderServiceConfig {
+            bind: Self::default_bind(),
+            public_url: None,
+            allowed_origin: Self::default_allowed_origin(),
+            max_cooked: Self::default_max_cooked(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Config {
+    #[serde(default)]
+    pub profile: CookProfile,
+
     pub source_root: Url,
     pub source_virtual_schemes: HashMap<String, Url>,
 
     pub target_db_connection: Option<String>,
     pub target_virtual_schemes: HashMap<String, Url>,
+    /// Location of the manifest listing the cooked assets and the build stamp
+    pub manifest_url: Option<Url>,
+    /// Location of the asset usage aggregated from the runtime reports. It is updated by the service and used
+    /// to order the preload list of the manifest.
+    #[serde(default)]
+    pub usage_url: Option<Url>,
+
+    #[serde(default)]
+    pub shader_service: ShaderServiceConfig,
+}
+
+/// Split the `--set <path>=<value>` overrides from the other command line arguments
+pub fn split_overrides<I>(args: I) -> Result<(Vec<String>, Vec<(String, String)>), CookerError>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut rest = Vec::new();
+    let mut overrides = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--set" {
+            let value = args
+                .next()
+                .ok_or_else(|| CookerError::InvalidConfig("Missing <path>=<value> after --set".to_owned()))?;
+            let mut parts = value.splitn(2, '=');
+            match (parts.next(), parts.next()) {
+                (Some(path), Some(value)) if !path.is_empty() => overrides.push((path.to_owned(), value.to_owned())),
+                _ => {
+                    return Err(CookerError::InvalidConfig(format!(
+                        "Invalid override, <path>=<value> expected: {}",
+                        value
+                    )))
+                }
+            }
+        } else {
+            rest.push(arg);
+        }
+    }
+    Ok((rest, overrides))
 }
 
 impl Config {
-    pub fn new() -> Result<Self, CookerError> {
-        use config::{Environment, File};
+    /// Load the configuration, the layers in the order of precedence: the overrides, the environment variables
+    /// with `--` separated sections and the config file.
+    pub fn new(config_file: Option<&str>, overrides: &[(String, String)]) -> Result<Self, CookerError> {
+        use config::{Environment, File, Value};
         let mut s = config::Config::new();
 
-        s.merge(Environment::new().separator("--"))?;
-
-        if let Some(config_file) = env::args().skip(1).next() {
+        if let Some(config_file) = config_file {
             log::info!("Loading cofig file {:?}", config_file);
             s.merge(File::from(Path::new(&config_file)))?;
         }
 
-        let cfg = s.try_into()?;
+        s.merge(Environment::new().separator("--"))?;
+
+        for (path, value) in overrides {
+            s.set(path, value.as_str())?;
+        }
+
+        let root = Value::new(None, s.collect()?);
+        let cfg: Config = serde_path_to_error::deserialize(root)
+            .map_err(|err| CookerError::InvalidConfig(format!("{}: {}", err.path(), err.inner())))?;
+        cfg.validate()?;
 
         log::info!("configuration: {}", serde_json::to_string_pretty(&cfg).unwrap());
         Ok(cfg)
     }
+
+    fn validate(&self) -> Result<(), CookerError> {
+        let mut issues = Vec::new();
+        for (scheme, url) in self
+            .source_virtual_schemes
+            .iter()
+            .chain(self.target_virtual_schemes.iter())
+        {
+            if url.scheme() == scheme {
+                issues.push(format!("virtual_schemes.{}: scheme is mapped to itself", scheme));
+            }
+        }
+        if self.shader_service.max_cooked == 0 {
+            issues.push("shader_service.max_cooked: must be greater than zero".to_owned());
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(CookerError::InvalidConfig(issues.join("\n")))
+        }
+    }
 }
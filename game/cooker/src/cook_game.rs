@@ -30,7 +30,10 @@ impl Context {
             log::debug!("[{}] Found game type: {:?}", source_url, source.test.ty);
 
             let cooked = source.cook(self.create_scope(source_id.clone())).await?;
-            let cooked_content = bincode::serialize(&cooked).map_err(|err| CookingError::from_err(&source_id, err))?;
+            let cooked_content = self
+                .cooked_format
+                .serialize(&cooked)
+                .map_err(|err| CookingError::from_err(&source_id, err))?;
 
             log::debug!("[{}] Uploading...", source_url);
             let cooked_url = self
@@ -32,8 +32,10 @@ impl<'a> ModelCooker<'a> for Context {
                         ));
                     }
                 };
-                let cooked_content =
-                    bincode::serialize(&cooked).map_err(|err| CookingError::from_err(&source_id, err))?;
+                let cooked_content = context
+                    .cooked_format
+                    .serialize(&cooked)
+                    .map_err(|err| CookingError::from_err(&source_id, err))?;
 
                 log::debug!("[{}] Uploading...", source_url);
                 let cooked_url = context
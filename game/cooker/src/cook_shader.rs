@@ -20,8 +20,10 @@ impl<'a> ShaderCooker<'a> for Context {
                     .map_err(|err| CookingError::from_err(&source_id, err))?;
 
                 let cooked = source.cook().await?;
-                let cooked_content =
-                    bincode::serialize(&cooked).map_err(|err| CookingError::from_err(&source_id, err))?;
+                let cooked_content = context
+                    .cooked_format
+                    .serialize(&cooked)
+                    .map_err(|err| CookingError::from_err(&source_id, err))?;
 
                 log::debug!("[{}] Uploading...", source_url);
                 let cooked_url = context
@@ -0,0 +1,123 @@
+use crate::{Config, CookerError};
+use serde::Serialize;
+use shine_game::{
+    assets::{
+        AssetError, AssetIO, BuildStamp, CookedFormat, CookedModel, CookedPipeline, CookedShader, CookedTexture,
+        ImageDescriptor, PipelineDescriptor, SamplerDescriptor, ShaderType, Url, MODEL_MAX_LOD_COUNT,
+    },
+    game::test1::Test1,
+};
+
+#[derive(Debug, Serialize)]
+pub struct MeshSummary {
+    pub vertex_count: usize,
+    pub index_count: Option<usize>,
+    pub lod: [(usize, usize); MODEL_MAX_LOD_COUNT],
+}
+
+/// Human readable summary of a cooked asset
+#[derive(Debug, Serialize)]
+#[serde(tag = "type")]
+pub enum AssetSummary {
+    Shader {
+        shader_type: ShaderType,
+        binary_size: usize,
+    },
+    Pipeline {
+        descriptor: PipelineDescriptor,
+    },
+    Model {
+        meshes: Vec<MeshSummary>,
+    },
+    Texture {
+        image_descriptor: ImageDescriptor,
+        sampler: SamplerDescriptor,
+        data_size: usize,
+    },
+    Game {
+        game: Test1,
+    },
+}
+
+impl AssetSummary {
+    /// Decode the cooked content using the same deserialization as the runtime loaders.
+    pub fn from_cooked(url: &Url, data: &[u8]) -> Result<AssetSummary, AssetError> {
+        let summary = match url.extension() {
+            "vs" | "fs" | "cs" => {
+                let shader: CookedShader = CookedFormat::deserialize(data)?;
+                AssetSummary::Shader {
+                    shader_type: shader.shader_type,
+                    binary_size: shader.binary.len(),
+                }
+            }
+            "pl" => {
+                let pipeline: CookedPipeline = CookedFormat::deserialize(data)?;
+                AssetSummary::Pipeline {
+                    descriptor: pipeline.descriptor,
+                }
+            }
+            "md" => {
+                let model: CookedModel = CookedFormat::deserialize(data)?;
+                AssetSummary::Model {
+                    meshes: model
+                        .meshes
+                        .iter()
+                        .map(|mesh| MeshSummary {
+                            vertex_count: mesh.vertices.count(),
+                            index_count: mesh.indices.as_ref().map(|indices| indices.len()),
+                            lod: mesh.lod,
+                        })
+                        .collect(),
+                }
+            }
+            "tx" => {
+                let texture: CookedTexture = CookedFormat::deserialize(data)?;
+                AssetSummary::Texture {
+                    image_descriptor: texture.image_descriptor,
+                    sampler: texture.sampler,
+                    data_size: texture.data.len(),
+                }
+            }
+            "g1" => AssetSummary::Game {
+                game: CookedFormat::deserialize(data)?,
+            },
+            ext => return Err(AssetError::UnsupportedFormat(ext.into())),
+        };
+        Ok(summary)
+    }
+}
+
+/// Download a cooked asset and print its content to the standard output.
+pub async fn inspect(config: &Config, cooked_url: &Url, json: bool) -> Result<(), CookerError> {
+    let target_io = AssetIO::new(config.target_virtual_schemes.clone())?;
+
+    log::debug!("[{}] Downloading...", cooked_url);
+    let data = target_io.download_binary(cooked_url).await?;
+    let format = CookedFormat::detect(&data);
+    let (stamp, _) = BuildStamp::split(&data)?;
+    let build_id = stamp.as_ref().map(|stamp| stamp.build_id());
+    let summary = AssetSummary::from_cooked(cooked_url, &data)?;
+
+    if json {
+        let output = serde_json::json!({
+            "url": cooked_url.as_str(),
+            "format": format,
+            "build_id": build_id,
+            "build": stamp,
+            "size": data.len(),
+            "content": summary,
+        });
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+    } else {
+        println!("url: {}", cooked_url.as_str());
+        println!("format: {:?}", format);
+        match (&build_id, &stamp) {
+            (Some(build_id), Some(stamp)) => println!("build: {} {:?}", build_id, stamp),
+            _ => println!("build: unknown (not stamped)"),
+        }
+        println!("size: {}", data.len());
+        println!("{:#?}", summary);
+    }
+
+    Ok(())
+}
@@ -1,8 +1,9 @@
 use color_eyre::{self, Report};
 use shine_game::assets::{
     cooker::{CookingError, ModelCooker, Naming, PipelineCooker, ShaderCooker, TextureCooker},
-    AssetError, AssetIO, AssetId, Url, UrlError,
+    AssetError, AssetIO, AssetId, CookedFormat, Url, UrlError,
 };
+use std::env;
 use thiserror::Error;
 use tokio::runtime::Runtime;
 
@@ -13,9 +14,13 @@ mod cook_model;
 mod cook_pipeline;
 mod cook_shader;
 mod cook_texture;
+mod inspect;
+mod manifest;
+mod shader_service;
 mod target_db;
 
-pub use self::config::Config;
+pub use self::config::{split_overrides, Config, CookProfile};
+pub use self::manifest::CookManifest;
 pub use target_db::TargetDB;
 
 #[derive(Debug, Error)]
@@ -32,11 +37,20 @@ pub enum CookerError {
     #[error(transparent)]
     Config(#[from] ::config::ConfigError),
 
+    #[error("Invalid configuration:\n{0}")]
+    InvalidConfig(String),
+
+    #[error("Assets of mixed cooker builds:\n{0}")]
+    MixedBuilds(String),
+
     #[error("Runtime error")]
     Runtime(#[from] tokio::task::JoinError),
 
     #[error("Database error")]
     SqlDb(#[from] sqlx::Error),
+
+    #[error("Server error")]
+    Server(#[from] hyper::Error),
 }
 
 #[derive(Clone)]
@@ -45,14 +59,28 @@ pub struct Context {
     pub source_root: Url,
     pub source_io: AssetIO,
     pub target_io: TargetDB,
+    pub cooked_format: CookedFormat,
 }
 
 impl Context {
+    pub async fn new(config: &Config) -> Result<Context, CookerError> {
+        let source_io = AssetIO::new(config.source_virtual_schemes.clone())?;
+        let target_io = TargetDB::new(&config).await?;
+        log::info!("Cooking with {:?} profile", config.profile);
+        Ok(Context {
+            source_root: config.source_root.clone(),
+            source_io,
+            target_io,
+            cooked_format: config.profile.cooked_format(),
+        })
+    }
+
     pub fn create_scope(&self, asset_scope: AssetId) -> Context {
         Context {
             source_root: self.source_root.clone(),
             source_io: self.source_io.clone(),
             target_io: self.target_io.create_scope(asset_scope),
+            cooked_format: self.cooked_format,
         }
     }
 }
@@ -87,18 +115,8 @@ async fn cook(context: &Context, source_id: AssetId) -> Result<Url, CookerError>
     Ok(cooked_dependency)
 }
 
-async fn run(assets: Vec<AssetId>) -> Result<(), CookerError> {
-    let config = Config::new().unwrap();
-
-    let context = {
-        let source_io = AssetIO::new(config.source_virtual_schemes.clone())?;
-        let target_io = TargetDB::new(&config).await?;
-        Context {
-            source_root: config.source_root.clone(),
-            source_io,
-            target_io,
-        }
-    };
+async fn run(config: Config, assets: Vec<AssetId>) -> Result<(), CookerError> {
+    let context = Context::new(&config).await?;
 
     //let root_assets = context.target_io.get_affected_roots(&assets[..]).await?;
     //log::info!("Root assets to cook: {:?}", root_assets);
@@ -109,6 +127,25 @@ async fn run(assets: Vec<AssetId>) -> Result<(), CookerError> {
         log::info!("Cooking completed for {:?}", asset_id);
     }
 
+    if let Some(usage_url) = &config.usage_url {
+        let usage = context.target_io.download_usage(usage_url).await?;
+        log::info!("Asset usage of {} sessions", usage.sessions);
+        for (url, summary) in usage.by_load_time().iter().take(10) {
+            log::info!(
+                "  {}: {} loads, {} ms average, {} ms max",
+                url,
+                summary.loads,
+                summary.average_load_time() / 1000,
+                summary.max_load_time / 1000
+            );
+        }
+        context.target_io.order_preload(&usage);
+    }
+
+    if let Some(manifest_url) = &config.manifest_url {
+        context.target_io.upload_manifest(manifest_url).await?;
+    }
+
     Ok(())
 }
 
@@ -123,6 +160,46 @@ fn main() -> Result<(), Report> {
         .try_init();
     let mut rt = Runtime::new()?;
 
+    let (args, overrides) = split_overrides(env::args().skip(1))?;
+    let mut args = args.into_iter().peekable();
+    if args.peek().map(|cmd| cmd == "inspect").unwrap_or(false) {
+        // cooker inspect <cooked-url> [--json] [config]
+        args.next();
+        let cooked_url = args
+            .next()
+            .ok_or_else(|| Report::msg("Missing cooked url to inspect"))?;
+        let cooked_url = Url::parse(&cooked_url)?;
+        let json = args.peek().map(|arg| arg == "--json").unwrap_or(false);
+        if json {
+            args.next();
+        }
+        let config = Config::new(args.next().as_deref(), &overrides)?;
+        rt.block_on(inspect::inspect(&config, &cooked_url, json))?;
+        return Ok(());
+    }
+    if args.peek().map(|cmd| cmd == "verify").unwrap_or(false) {
+        // cooker verify <manifest-url> [config]
+        args.next();
+        let manifest_url = args
+            .next()
+            .ok_or_else(|| Report::msg("Missing manifest url to verify"))?;
+        let manifest_url = Url::parse(&manifest_url)?;
+        let config = Config::new(args.next().as_deref(), &overrides)?;
+        rt.block_on(manifest::verify(&config, &manifest_url))?;
+        return Ok(());
+    }
+    if args.peek().map(|cmd| cmd == "serve").unwrap_or(false) {
+        // cooker serve [config]
+        args.next();
+        let config = Config::new(args.next().as_deref(), &overrides)?;
+        rt.block_on(async move {
+            let context = Context::new(&config).await?;
+            shader_service::serve(context, config.shader_service, config.usage_url).await
+        })?;
+        return Ok(());
+    }
+    let config = Config::new(args.next().as_deref(), &overrides)?;
+
     let assets = [
         //"games/test/test1/hello.fs",
         //"games/test/test3/checker.png",
@@ -138,6 +215,6 @@ fn main() -> Result<(), Report> {
     .map(|x| AssetId::new(x))
     .collect::<Result<Vec<_>, _>>()?;
 
-    rt.block_on(run(assets))?;
+    rt.block_on(run(config, assets))?;
     Ok(())
 }
@@ -0,0 +1,84 @@
+use crate::{Config, CookerError};
+use serde::{Deserialize, Serialize};
+use shine_game::assets::{AssetError, AssetIO, AssetId, AssetUsageStats, BuildStamp, Url};
+use std::collections::{BTreeMap, HashSet};
+
+/// List of the cooked assets along with the build producing them
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CookManifest {
+    pub build_id: String,
+    pub build: BuildStamp,
+    /// Cooked url by source id
+    pub assets: BTreeMap<String, String>,
+    /// Cooked urls in the order of the preload, based on the reported usage
+    #[serde(default)]
+    pub preload: Vec<String>,
+}
+
+impl CookManifest {
+    pub fn new(build: BuildStamp) -> CookManifest {
+        CookManifest {
+            build_id: build.build_id(),
+            build,
+            assets: BTreeMap::new(),
+            preload: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, source_id: &AssetId, cooked_url: &Url) {
+        self.assets
+            .insert(source_id.as_str().to_owned(), cooked_url.as_str().to_owned());
+    }
+
+    /// Order the preload list by the usage, the assets never loaded at runtime are not preloaded
+    pub fn order_preload(&mut self, usage: &AssetUsageStats) {
+        let cooked = self.assets.values().collect::<HashSet<_>>();
+        self.preload = usage
+            .preload_order()
+            .into_iter()
+            .filter(|url| cooked.contains(url))
+            .collect();
+    }
+}
+
+/// Check if all the assets of a manifest were cooked by the build recorded in the manifest.
+pub async fn verify(config: &Config, manifest_url: &Url) -> Result<(), CookerError> {
+    let target_io = AssetIO::new(config.target_virtual_schemes.clone())?;
+
+    log::debug!("[{}] Downloading manifest...", manifest_url);
+    let manifest = target_io.download_string(manifest_url).await?;
+    let manifest: CookManifest =
+        serde_json::from_str(&manifest).map_err(|err| AssetError::load_failed(manifest_url, err))?;
+    log::info!(
+        "Verifying {} assets of build {}",
+        manifest.assets.len(),
+        manifest.build_id
+    );
+
+    let mut issues = Vec::new();
+    for (source_id, cooked_url) in &manifest.assets {
+        let cooked_url = Url::parse(cooked_url)?;
+        let data = target_io.download_binary(&cooked_url).await?;
+        match BuildStamp::split(&data)? {
+            (Some(stamp), _) if stamp.build_id() == manifest.build_id => {}
+            (Some(stamp), _) => issues.push(format!(
+                "{} ({}): cooked by build {} ({:?})",
+                source_id,
+                cooked_url.as_str(),
+                stamp.build_id(),
+                stamp
+            )),
+            (None, _) => issues.push(format!("{} ({}): missing build stamp", source_id, cooked_url.as_str())),
+        }
+    }
+
+    if issues.is_empty() {
+        log::info!("All assets match build {}", manifest.build_id);
+        Ok(())
+    } else {
+        for issue in &issues {
+            log::warn!("{}", issue);
+        }
+        Err(CookerError::MixedBuilds(issues.join("\n")))
+    }
+}
@@ -0,0 +1,224 @@
+use crate::{config::ShaderServiceConfig, Context, CookerError};
+use hyper::{
+    body,
+    header::{self, HeaderValue},
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use serde::Serialize;
+use shine_game::assets::{
+    AssetId, AssetUsageReport, AssetUsageStats, ContentHash, ShaderDiagnostic, ShaderSource, Url,
+};
+use std::{collections::VecDeque, convert::Infallible, sync::Arc, sync::Mutex};
+use tokio::task;
+
+const COMPILE_PATH: &str = "/shader/compile/";
+const COOKED_PATH: &str = "/shader/cooked/";
+const USAGE_PATH: &str = "/usage/report";
+
+#[derive(Serialize)]
+struct CompileResponse {
+    success: bool,
+    diagnostics: Vec<ShaderDiagnostic>,
+    cooked_url: Option<String>,
+}
+
+/// Compile shaders sent by the browser based editor with the same toolchain as the cooker. The cooked shaders
+/// are not uploaded to the target, they are kept in memory and served from a temporary url.
+/// - POST /shader/compile/<asset id>: compile the source in the body, the type is given by the extension
+/// - GET /shader/cooked/<hash>.<ext>: download a cooked shader
+///
+/// The service also collects the asset usage reported by the runtime when the usage url is configured.
+/// - PUT /usage/report: merge the usage report in the body into the statistics
+struct ShaderService {
+    context: Context,
+    config: ShaderServiceConfig,
+    cooked: Mutex<VecDeque<(String, Vec<u8>)>>,
+    usage: Option<(Url, Mutex<AssetUsageStats>)>,
+}
+
+impl ShaderService {
+    fn response(&self, status: StatusCode, content_type: &str, body: Body) -> Response<Body> {
+        let mut response = Response::new(body);
+        *response.status_mut() = status;
+        let headers = response.headers_mut();
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_str(content_type).unwrap());
+        if let Ok(origin) = HeaderValue::from_str(&self.config.allowed_origin) {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+        }
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_METHODS,
+            HeaderValue::from_static("GET, POST, PUT, OPTIONS"),
+        );
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_HEADERS,
+            HeaderValue::from_static("content-type"),
+        );
+        response
+    }
+
+    fn error(&self, status: StatusCode, message: String) -> Response<Body> {
+        self.response(status, "text/plain", Body::from(message))
+    }
+
+    fn cooked_url(&self, name: &str) -> String {
+        let base = match &self.config.public_url {
+            Some(public_url) => public_url.trim_end_matches('/').to_owned(),
+            None => format!("http://{}", self.config.bind),
+        };
+        format!("{}{}{}", base, COOKED_PATH, name)
+    }
+
+    fn store_cooked(&self, name: String, content: Vec<u8>) {
+        let mut cooked = self.cooked.lock().unwrap();
+        cooked.retain(|(n, _)| n != &name);
+        cooked.push_back((name, content));
+        while cooked.len() > self.config.max_cooked {
+            cooked.pop_front();
+        }
+    }
+
+    fn find_cooked(&self, name: &str) -> Option<Vec<u8>> {
+        let cooked = self.cooked.lock().unwrap();
+        cooked
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, content)| content.clone())
+    }
+
+    async fn compile(&self, asset_id: &str, request: Request<Body>) -> Response<Body> {
+        let source_id = match AssetId::new(asset_id) {
+            Ok(source_id) => source_id,
+            Err(err) => return self.error(StatusCode::BAD_REQUEST, format!("Invalid asset id: {}", err)),
+        };
+        let source_url = match source_id.to_url(&self.context.source_root) {
+            Ok(source_url) => source_url,
+            Err(err) => return self.error(StatusCode::BAD_REQUEST, format!("Invalid asset id: {}", err)),
+        };
+        let source = match body::to_bytes(request.into_body()).await {
+            Ok(source) => String::from_utf8_lossy(&source).into_owned(),
+            Err(err) => return self.error(StatusCode::BAD_REQUEST, format!("Failed to read source: {}", err)),
+        };
+        let (source, _) = match ShaderSource::from_string(&source_id, &source_url, source) {
+            Ok(source) => source,
+            Err(err) => return self.error(StatusCode::BAD_REQUEST, format!("{}", err)),
+        };
+
+        log::debug!("[{}] Compiling for the editor...", source_id);
+        let compiled = match task::spawn_blocking(move || source.compile()).await {
+            Ok(compiled) => compiled,
+            Err(err) => return self.error(StatusCode::INTERNAL_SERVER_ERROR, format!("{}", err)),
+        };
+
+        let response = match compiled {
+            Ok((cooked, diagnostics)) => {
+                let content = match self.context.cooked_format.serialize(&cooked) {
+                    Ok(content) => content,
+                    Err(err) => return self.error(StatusCode::INTERNAL_SERVER_ERROR, format!("{}", err)),
+                };
+                let name = format!("{}.{}", ContentHash::from_bytes(&content).hash(), source_id.extension());
+                let cooked_url = self.cooked_url(&name);
+                self.store_cooked(name, content);
+                log::info!("[{}] Compiled to {}", source_id, cooked_url);
+                CompileResponse {
+                    success: true,
+                    diagnostics,
+                    cooked_url: Some(cooked_url),
+                }
+            }
+            Err(diagnostics) => {
+                log::info!("[{}] Compilation failed with {} error(s)", source_id, diagnostics.len());
+                CompileResponse {
+                    success: false,
+                    diagnostics,
+                    cooked_url: None,
+                }
+            }
+        };
+
+        match serde_json::to_string(&response) {
+            Ok(body) => self.response(StatusCode::OK, "application/json", Body::from(body)),
+            Err(err) => self.error(StatusCode::INTERNAL_SERVER_ERROR, format!("{}", err)),
+        }
+    }
+
+    async fn report_usage(&self, request: Request<Body>) -> Response<Body> {
+        let (usage_url, usage) = match &self.usage {
+            Some(usage) => usage,
+            None => return self.error(StatusCode::NOT_FOUND, "Usage reporting is not enabled".to_owned()),
+        };
+        let report = match body::to_bytes(request.into_body()).await {
+            Ok(report) => report,
+            Err(err) => return self.error(StatusCode::BAD_REQUEST, format!("Failed to read report: {}", err)),
+        };
+        let report: AssetUsageReport = match serde_json::from_slice(&report) {
+            Ok(report) => report,
+            Err(err) => return self.error(StatusCode::BAD_REQUEST, format!("Invalid report: {}", err)),
+        };
+
+        log::info!(
+            "Usage report of {} assets (build {:?})",
+            report.assets.len(),
+            report.build_id
+        );
+        let stats = {
+            let mut usage = usage.lock().unwrap();
+            usage.merge(&report);
+            usage.clone()
+        };
+        match self.context.target_io.upload_usage(usage_url, &stats).await {
+            Ok(_) => self.response(StatusCode::NO_CONTENT, "text/plain", Body::empty()),
+            Err(err) => self.error(StatusCode::INTERNAL_SERVER_ERROR, format!("{}", err)),
+        }
+    }
+
+    async fn handle(&self, request: Request<Body>) -> Response<Body> {
+        let path = request.uri().path().to_owned();
+        let method = request.method().clone();
+        match method {
+            Method::OPTIONS => self.response(StatusCode::NO_CONTENT, "text/plain", Body::empty()),
+            Method::POST if path.starts_with(COMPILE_PATH) => self.compile(&path[COMPILE_PATH.len()..], request).await,
+            Method::PUT if path == USAGE_PATH => self.report_usage(request).await,
+            Method::GET if path.starts_with(COOKED_PATH) => match self.find_cooked(&path[COOKED_PATH.len()..]) {
+                Some(content) => self.response(StatusCode::OK, "application/octet-stream", Body::from(content)),
+                None => self.error(
+                    StatusCode::NOT_FOUND,
+                    format!("Cooked shader expired or not found: {}", path),
+                ),
+            },
+            _ => self.error(StatusCode::NOT_FOUND, format!("Not found: {} {}", method, path)),
+        }
+    }
+}
+
+/// Run the shader compile service until the process is terminated
+pub async fn serve(context: Context, config: ShaderServiceConfig, usage_url: Option<Url>) -> Result<(), CookerError> {
+    let bind = config.bind;
+    let usage = match usage_url {
+        Some(usage_url) => {
+            let usage = context.target_io.download_usage(&usage_url).await?;
+            Some((usage_url, Mutex::new(usage)))
+        }
+        None => None,
+    };
+    let service = Arc::new(ShaderService {
+        context,
+        config,
+        cooked: Mutex::new(VecDeque::new()),
+        usage,
+    });
+
+    let make_service = make_service_fn(move |_| {
+        let service = service.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let service = service.clone();
+                async move { Ok::<_, Infallible>(service.handle(request).await) }
+            }))
+        }
+    });
+
+    log::info!("Shader service listening on {}", bind);
+    Server::bind(&bind).serve(make_service).await?;
+    Ok(())
+}
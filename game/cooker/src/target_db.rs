@@ -1,9 +1,10 @@
-use crate::{Config, CookerError};
+use crate::{Config, CookManifest, CookerError};
 use shine_game::assets::{
     cooker::{CookingError, Naming},
-    AssetIO, AssetId, ContentHash, Url,
+    AssetError, AssetIO, AssetId, AssetUsageStats, BuildStamp, ContentHash, Url,
 };
 use sqlx::PgPool;
+use std::sync::{Arc, Mutex};
 
 //Manage local sources to speed up compilation
 #[derive(Clone)]
@@ -11,6 +12,8 @@ pub struct TargetDB {
     pool: Option<PgPool>,
     asset_io: AssetIO,
     scopes: Vec<AssetId>,
+    build_stamp: BuildStamp,
+    manifest: Arc<Mutex<CookManifest>>,
 }
 
 impl TargetDB {
@@ -23,10 +26,14 @@ impl TargetDB {
             None
         };
         let asset_io = AssetIO::new(config.target_virtual_schemes.clone())?;
+        let build_stamp = BuildStamp::current(env!("CARGO_PKG_VERSION"), &format!("{:?}", config.profile));
+        log::info!("Cooker build {} ({:?})", build_stamp.build_id(), build_stamp);
         let db = TargetDB {
             pool,
             asset_io,
             scopes: Vec::new(),
+            manifest: Arc::new(Mutex::new(CookManifest::new(build_stamp.clone()))),
+            build_stamp,
         };
         //db.init().await?;
         log::info!("Db done.");
@@ -38,9 +45,50 @@ impl TargetDB {
             pool: self.pool.clone(),
             asset_io: self.asset_io.clone(),
             scopes: self.scopes.iter().cloned().chain(Some(scope)).collect(),
+            build_stamp: self.build_stamp.clone(),
+            manifest: self.manifest.clone(),
         }
     }
 
+    pub fn build_stamp(&self) -> &BuildStamp {
+        &self.build_stamp
+    }
+
+    /// Upload the manifest of the assets cooked by this (and all the scoped) db.
+    pub async fn upload_manifest(&self, manifest_url: &Url) -> Result<(), CookerError> {
+        let manifest = {
+            let manifest = self.manifest.lock().unwrap();
+            serde_json::to_string_pretty(&*manifest)
+                .map_err(|err| AssetError::other("Failed to serialize manifest", err))?
+        };
+        log::info!("Uploading manifest to {}", manifest_url.as_str());
+        self.asset_io.upload_string(manifest_url, &manifest).await?;
+        Ok(())
+    }
+
+    /// Download the aggregated asset usage, a new statistics is started if it is not available
+    pub async fn download_usage(&self, usage_url: &Url) -> Result<AssetUsageStats, CookerError> {
+        match self.asset_io.download_string(usage_url).await {
+            Ok(usage) => Ok(serde_json::from_str(&usage).map_err(|err| AssetError::load_failed(usage_url, err))?),
+            Err(err) => {
+                log::warn!("Asset usage is not available ({}), starting a new statistics", err);
+                Ok(AssetUsageStats::default())
+            }
+        }
+    }
+
+    pub async fn upload_usage(&self, usage_url: &Url, usage: &AssetUsageStats) -> Result<(), CookerError> {
+        let usage =
+            serde_json::to_string_pretty(usage).map_err(|err| AssetError::other("Failed to serialize usage", err))?;
+        self.asset_io.upload_string(usage_url, &usage).await?;
+        Ok(())
+    }
+
+    /// Order the preload list of the manifest by the asset usage
+    pub fn order_preload(&self, usage: &AssetUsageStats) {
+        self.manifest.lock().unwrap().order_preload(usage);
+    }
+
     pub async fn upload_binary_content(
         &self,
         source_id: AssetId,
@@ -55,7 +103,11 @@ impl TargetDB {
             ));
         }
 
-        let cooked_hash = ContentHash::from_bytes(cooked_content);
+        let cooked_content = self
+            .build_stamp
+            .stamp(cooked_content)
+            .map_err(|err| CookingError::from_err(&source_id, err))?;
+        let cooked_hash = ContentHash::from_bytes(&cooked_content);
         let target_url = naming
             .to_url(&source_id, &cooked_hash)
             .map_err(|err| CookingError::from_err(&source_id, err))?;
@@ -64,6 +116,8 @@ impl TargetDB {
             .await
             .map_err(|err| CookingError::from_err(&source_id, err))?;
 
+        self.manifest.lock().unwrap().add(&source_id, &target_url);
+
         // update dependency of owner_id
         Ok(target_url)
     }
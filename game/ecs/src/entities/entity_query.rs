@@ -0,0 +1,227 @@
+use crate::{
+    entities::{Entities, Entity},
+    resources::{FetchResource, ResourceAccess, ResourceId, ResourceQuery, ResourceRead, Resources},
+    scheduler::{ResourceClaim, ResourceClaims},
+    ECSError,
+};
+use hecs::{Component, QueryBorrow, QueryOne};
+use std::marker::PhantomData;
+
+/// Claim identity of a component type. The entities are claimed immutably by the queries, the component
+/// access is tracked by these claims to order the systems reading and writing the same component.
+pub struct ComponentLock<T>(PhantomData<fn(T)>);
+
+/// Claim of the shared access of the components of type T
+pub struct Read<T>(PhantomData<fn(T)>);
+
+/// Claim of the exclusive access of the components of type T
+pub struct Write<T>(PhantomData<fn(T)>);
+
+/// The component claims of a query
+pub trait ComponentClaim: 'static + Send + Sync {
+    fn add_claims(claims: &mut ResourceClaims);
+}
+
+/// Map the component claims to the query of a given lifetime
+pub trait FetchComponents<'a>: ComponentClaim {
+    type Query: hecs::Query + ComponentAccess<Claim = Self>;
+}
+
+/// Map the component query (ex. `(&A, &mut B)`) to the lifetime independent claims
+pub trait ComponentAccess {
+    type Claim: ComponentClaim + for<'a> FetchComponents<'a>;
+}
+
+impl<T: Component> ComponentClaim for Read<T> {
+    fn add_claims(claims: &mut ResourceClaims) {
+        claims.add_immutable::<ComponentLock<T>, _>(Some(ResourceId::Global))
+    }
+}
+
+impl<'a, T: Component> FetchComponents<'a> for Read<T> {
+    type Query = &'a T;
+}
+
+impl<'x, T: Component> ComponentAccess for &'x T {
+    type Claim = Read<T>;
+}
+
+impl<T: Component> ComponentClaim for Write<T> {
+    fn add_claims(claims: &mut ResourceClaims) {
+        claims.add_mutable::<ComponentLock<T>, _>(Some(ResourceId::Global))
+    }
+}
+
+impl<'a, T: Component> FetchComponents<'a> for Write<T> {
+    type Query = &'a mut T;
+}
+
+impl<'x, T: Component> ComponentAccess for &'x mut T {
+    type Claim = Write<T>;
+}
+
+impl<C: ComponentClaim> ComponentClaim for Option<C> {
+    fn add_claims(claims: &mut ResourceClaims) {
+        C::add_claims(claims)
+    }
+}
+
+impl<'a, C: FetchComponents<'a>> FetchComponents<'a> for Option<C> {
+    type Query = Option<C::Query>;
+}
+
+impl<C: ComponentAccess> ComponentAccess for Option<C> {
+    type Claim = Option<C::Claim>;
+}
+
+macro_rules! impl_component_tuple {
+    ($($c: ident),*) => {
+        impl<$($c: ComponentClaim,)*> ComponentClaim for ($($c,)*) {
+            fn add_claims(claims: &mut ResourceClaims) {
+                $(<$c as ComponentClaim>::add_claims(claims);)*
+            }
+        }
+
+        impl<'a, $($c: FetchComponents<'a>,)*> FetchComponents<'a> for ($($c,)*) {
+            type Query = ($(<$c as FetchComponents<'a>>::Query,)*);
+        }
+
+        impl<$($c: ComponentAccess,)*> ComponentAccess for ($($c,)*) {
+            type Claim = ($(<$c as ComponentAccess>::Claim,)*);
+        }
+    };
+}
+
+impl_component_tuple!(A);
+impl_component_tuple!(A, B);
+impl_component_tuple!(A, B, C);
+impl_component_tuple!(A, B, C, D);
+impl_component_tuple!(A, B, C, D, E);
+impl_component_tuple!(A, B, C, D, E, F);
+impl_component_tuple!(A, B, C, D, E, F, G);
+impl_component_tuple!(A, B, C, D, E, F, G, H);
+
+/// Filter of the entities of a query
+pub trait QueryFilter: 'static + Send + Sync {}
+
+/// Apply the filter on the query Q
+pub trait FilterQuery<Q: hecs::Query>: QueryFilter {
+    type Query: hecs::Query;
+}
+
+/// Filter the entities having a component of type T without accessing the component
+pub struct With<T>(PhantomData<fn(T)>);
+
+/// Filter the entities not having a component of type T
+pub struct Without<T>(PhantomData<fn(T)>);
+
+impl QueryFilter for () {}
+
+impl<Q: hecs::Query> FilterQuery<Q> for () {
+    type Query = Q;
+}
+
+impl<T: Component> QueryFilter for With<T> {}
+
+impl<T: Component, Q: hecs::Query> FilterQuery<Q> for With<T> {
+    type Query = hecs::With<T, Q>;
+}
+
+impl<T: Component> QueryFilter for Without<T> {}
+
+impl<T: Component, Q: hecs::Query> FilterQuery<Q> for Without<T> {
+    type Query = hecs::Without<T, Q>;
+}
+
+/// Combination of the filters, ex: `(With<A>, Without<B>)`
+impl<F1: QueryFilter, F2: QueryFilter> QueryFilter for (F1, F2) {}
+
+impl<Q: hecs::Query, F1: FilterQuery<Q>, F2: FilterQuery<F1::Query>> FilterQuery<Q> for (F1, F2) {
+    type Query = F2::Query;
+}
+
+/// Query the components of the entities
+pub struct EntitiesQuery<C: ComponentClaim, F: QueryFilter>(PhantomData<fn() -> (C, F)>);
+
+impl<C: ComponentClaim, F: QueryFilter> Default for EntitiesQuery<C, F> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<C, F> ResourceQuery for EntitiesQuery<C, F>
+where
+    C: ComponentClaim + for<'a> FetchComponents<'a>,
+    F: QueryFilter,
+{
+    type Fetch = EntitiesFetch<C, F>;
+}
+
+impl<C: ComponentClaim, F: QueryFilter> ResourceClaim for EntitiesQuery<C, F> {
+    fn add_claim(&self, claims: &mut ResourceClaims) {
+        claims.add_immutable::<Entities, _>(Some(ResourceId::Global));
+        C::add_claims(claims);
+    }
+}
+
+/// Fetch for Query<'_, Q, F>
+pub struct EntitiesFetch<C: ComponentClaim, F: QueryFilter>(PhantomData<fn() -> (C, F)>);
+
+impl<'a, C, F> FetchResource<'a, EntitiesQuery<C, F>> for EntitiesFetch<C, F>
+where
+    C: ComponentClaim + for<'b> FetchComponents<'b>,
+    F: QueryFilter,
+{
+    type Item = Query<'a, <C as FetchComponents<'a>>::Query, F>;
+
+    fn fetch<'r: 'a>(resources: &'r Resources, _claim: &'r mut EntitiesQuery<C, F>) -> Result<Self::Item, ECSError> {
+        Ok(Query {
+            entities: resources.get::<Entities>()?,
+            ph: PhantomData,
+        })
+    }
+}
+
+/// Iterate the components of the entities matching the filter, ex: `Query<(&A, &mut B), With<C>>`.
+/// The component access is claimed by the system, thus systems writing the same component are not run in
+/// parallel.
+pub struct Query<'a, Q: ComponentAccess, F: QueryFilter = ()> {
+    entities: ResourceRead<'a, Entities>,
+    ph: PhantomData<fn() -> (Q, F)>,
+}
+
+impl<'a, Q: ComponentAccess, F: QueryFilter> ResourceAccess for Query<'a, Q, F> {
+    type Query = EntitiesQuery<Q::Claim, F>;
+    type Fetch = EntitiesFetch<Q::Claim, F>;
+}
+
+impl<'a, Q, F> Query<'a, Q, F>
+where
+    Q: ComponentAccess + hecs::Query,
+    F: FilterQuery<Q>,
+{
+    /// Borrow the matching components, ex: `for (entity, (a, b)) in &mut query.iter() { .. }`
+    pub fn iter(&self) -> QueryBorrow<'_, F::Query> {
+        self.entities.query::<F::Query>()
+    }
+
+    /// Borrow the components of a single entity
+    pub fn get(&self, entity: Entity) -> Result<QueryOne<'_, F::Query>, ECSError> {
+        self.entities
+            .query_one::<F::Query>(entity)
+            .map_err(|_| ECSError::EntityNotFound(entity))
+    }
+
+    /// Number of the matching entities
+    pub fn count(&self) -> usize {
+        self.iter().iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count() == 0
+    }
+
+    pub fn entities(&self) -> &Entities {
+        &self.entities
+    }
+}
@@ -0,0 +1,7 @@
+mod entity_query;
+pub use self::entity_query::*;
+
+pub use hecs::Entity;
+
+/// The entities of the world, stored as a resource.
+pub type Entities = hecs::World;
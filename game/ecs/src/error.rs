@@ -1,4 +1,4 @@
-use crate::resources::ResourceId;
+use crate::{hierarchy::Entity, resources::ResourceId};
 use std::{borrow::Cow, error::Error as StdError, sync::Arc};
 use thiserror::Error;
 
@@ -16,6 +16,12 @@ pub enum ECSError {
     #[error("Resource {1:?} not found ({0})")]
     ResourceNotFound(Cow<'static, str>, ResourceId),
 
+    #[error("Failed to initialize resource {0}")]
+    ResourceInit(Cow<'static, str>, #[source] Arc<dyn StdError + Send + Sync>),
+
+    #[error("Resource {1:?} ({0}) is not claimed by {2}")]
+    ResourceNotClaimed(Cow<'static, str>, ResourceId, String),
+
     #[error("Resource handle was invalidated")]
     ResourceExpired,
 
@@ -24,4 +30,19 @@ pub enum ECSError {
 
     #[error("System lock could not be claimed")]
     SystemLockError,
+
+    #[error("Cyclic system dependency among {0:?}")]
+    SystemDependencyCycle(Vec<String>),
+
+    #[error("Entity {0:?} not found")]
+    EntityNotFound(Entity),
+
+    #[error("Attaching {0:?} to {1:?} would create a cycle in the hierarchy")]
+    HierarchyCycle(Entity, Entity),
+
+    #[error("Failed to serialize {0} for the snapshot")]
+    SnapshotSerialize(Cow<'static, str>, #[source] Arc<dyn StdError + Send + Sync>),
+
+    #[error("Failed to restore {0} from the snapshot")]
+    SnapshotDeserialize(Cow<'static, str>, #[source] Arc<dyn StdError + Send + Sync>),
 }
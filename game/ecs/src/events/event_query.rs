@@ -0,0 +1,154 @@
+use crate::{
+    events::{EventCursor, Events},
+    resources::{
+        FetchResource, ResMut, Resource, ResourceAccess, ResourceId, ResourceQuery, ResourceRead, ResourceWrite,
+        Resources,
+    },
+    scheduler::{IntoSystem, ResourceClaim, ResourceClaims, System, TaskGroup},
+    ECSError,
+};
+use std::marker::PhantomData;
+
+impl Resources {
+    /// Register an empty event channel for the events of type T
+    pub fn register_events<T: Resource>(&mut self) -> Result<(), ECSError> {
+        self.register_with_instance(Events::<T>::default())
+    }
+}
+
+/// Query the event channel for reading. The query stores the cursor of the reader, thus each system
+/// reads the events independent of the other systems.
+pub struct EventReaderQuery<T: Resource>(EventCursor<T>);
+
+impl<T: Resource> Default for EventReaderQuery<T> {
+    fn default() -> Self {
+        Self(EventCursor::default())
+    }
+}
+
+impl<T: Resource> ResourceQuery for EventReaderQuery<T> {
+    type Fetch = EventReaderFetch<T>;
+}
+
+impl<T: Resource> ResourceClaim for EventReaderQuery<T> {
+    fn add_claim(&self, claims: &mut ResourceClaims) {
+        claims.add_immutable::<Events<T>, _>(Some(ResourceId::Global))
+    }
+}
+
+/// Fetch for EventReader<'_, T>
+pub struct EventReaderFetch<T: Resource>(PhantomData<T>);
+
+impl<'a, T: Resource> FetchResource<'a, EventReaderQuery<T>> for EventReaderFetch<T> {
+    type Item = EventReader<'a, T>;
+
+    fn fetch<'r: 'a>(resources: &'r Resources, claim: &'r mut EventReaderQuery<T>) -> Result<Self::Item, ECSError> {
+        Ok(EventReader {
+            events: resources.get::<Events<T>>()?,
+            cursor: &mut claim.0,
+        })
+    }
+}
+
+/// Read the events not seen by the system yet
+pub struct EventReader<'a, T: Resource> {
+    events: ResourceRead<'a, Events<T>>,
+    cursor: &'a mut EventCursor<T>,
+}
+
+impl<'a, T: Resource> EventReader<'a, T> {
+    /// Iterate the unread events, the events are consumed for this system even if the iteration is not
+    /// completed.
+    pub fn iter(&mut self) -> impl Iterator<Item = &T> {
+        self.cursor.read(&self.events)
+    }
+
+    /// Number of the unread events
+    pub fn len(&self) -> usize {
+        self.cursor.len(&self.events)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cursor.is_empty(&self.events)
+    }
+
+    /// Number of the events dropped before the system could read them
+    pub fn lost(&self) -> usize {
+        self.cursor.lost()
+    }
+}
+
+impl<'a, T: Resource> ResourceAccess for EventReader<'a, T> {
+    type Query = EventReaderQuery<T>;
+    type Fetch = EventReaderFetch<T>;
+}
+
+/// Query the event channel for sending
+pub struct EventWriterQuery<T: Resource>(PhantomData<fn(T)>);
+
+impl<T: Resource> Default for EventWriterQuery<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: Resource> ResourceQuery for EventWriterQuery<T> {
+    type Fetch = EventWriterFetch<T>;
+}
+
+impl<T: Resource> ResourceClaim for EventWriterQuery<T> {
+    fn add_claim(&self, claims: &mut ResourceClaims) {
+        claims.add_mutable::<Events<T>, _>(Some(ResourceId::Global))
+    }
+}
+
+/// Fetch for EventWriter<'_, T>
+pub struct EventWriterFetch<T: Resource>(PhantomData<T>);
+
+impl<'a, T: Resource> FetchResource<'a, EventWriterQuery<T>> for EventWriterFetch<T> {
+    type Item = EventWriter<'a, T>;
+
+    fn fetch<'r: 'a>(resources: &'r Resources, _claim: &'r mut EventWriterQuery<T>) -> Result<Self::Item, ECSError> {
+        Ok(EventWriter(resources.get_mut::<Events<T>>()?))
+    }
+}
+
+/// Send events to the channel
+pub struct EventWriter<'a, T: Resource>(ResourceWrite<'a, Events<T>>);
+
+impl<'a, T: Resource> EventWriter<'a, T> {
+    pub fn send(&mut self, event: T) {
+        self.0.send(event);
+    }
+
+    pub fn send_batch<I: IntoIterator<Item = T>>(&mut self, events: I) {
+        self.0.send_batch(events);
+    }
+}
+
+impl<'a, T: Resource> ResourceAccess for EventWriter<'a, T> {
+    type Query = EventWriterQuery<T>;
+    type Fetch = EventWriterFetch<T>;
+}
+
+fn update_events<T: Resource>(mut events: ResMut<Events<T>>) -> Result<TaskGroup, ECSError> {
+    events.update();
+    Ok(TaskGroup::default())
+}
+
+/// Create a system swapping the buffers of the event channel. It shall run once per frame, usually
+/// at the start or at the end of the frame.
+/// #Example
+/// ```
+/// # use shine_ecs::{events::*, resources::*, scheduler::*};
+/// struct Hit(u32);
+///
+/// let mut resources = Resources::default();
+/// resources.register_events::<Hit>().unwrap();
+///
+/// let tasks = TaskGroup::from_task(clear_events::<Hit>());
+/// Scheduler::default().run(&resources, &tasks).unwrap();
+/// ```
+pub fn clear_events<T: Resource>() -> impl System {
+    update_events::<T>.into_system()
+}
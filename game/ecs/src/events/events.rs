@@ -0,0 +1,156 @@
+use crate::resources::Resource;
+use std::{fmt, marker::PhantomData};
+
+/// Double buffered event channel. The events sent in a frame are kept for the next frame too, thus a
+/// reader running before the writer in a frame can still see all the events. The buffers are swapped by
+/// `update` (see the `clear_events` system), events not read within two frames are lost.
+pub struct Events<T: Resource> {
+    previous: Vec<T>,
+    previous_start: usize,
+    current: Vec<T>,
+    current_start: usize,
+    event_count: usize,
+    /// The events before this count were dropped by clear
+    cleared: usize,
+}
+
+impl<T: Resource> Default for Events<T> {
+    fn default() -> Self {
+        Events {
+            previous: Vec::new(),
+            previous_start: 0,
+            current: Vec::new(),
+            current_start: 0,
+            event_count: 0,
+            cleared: 0,
+        }
+    }
+}
+
+impl<T: Resource> Events<T> {
+    pub fn send(&mut self, event: T) {
+        self.current.push(event);
+        self.event_count += 1;
+    }
+
+    pub fn send_batch<I: IntoIterator<Item = T>>(&mut self, events: I) {
+        for event in events {
+            self.send(event);
+        }
+    }
+
+    /// Number of the events sent since the creation of the channel
+    pub fn event_count(&self) -> usize {
+        self.event_count
+    }
+
+    /// Number of the events available for the readers
+    pub fn len(&self) -> usize {
+        self.previous.len() + self.current.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.previous.is_empty() && self.current.is_empty()
+    }
+
+    /// Swap the buffers, the events of the previous frame are dropped
+    pub fn update(&mut self) {
+        std::mem::swap(&mut self.previous, &mut self.current);
+        self.current.clear();
+        self.previous_start = self.current_start;
+        self.current_start = self.event_count;
+    }
+
+    /// Drop all the events, readers skip the dropped events without reporting them as lost
+    pub fn clear(&mut self) {
+        self.previous.clear();
+        self.current.clear();
+        self.previous_start = self.event_count;
+        self.current_start = self.event_count;
+        self.cleared = self.event_count;
+    }
+
+    /// Create a cursor that reads only the events sent after its creation
+    pub fn cursor(&self) -> EventCursor<T> {
+        EventCursor {
+            next_event: self.event_count,
+            lost: 0,
+            ph: PhantomData,
+        }
+    }
+
+    /// Iterate the events that are available from the given event count
+    fn iter_from(&self, next_event: usize) -> impl Iterator<Item = &T> {
+        let previous = next_event.saturating_sub(self.previous_start).min(self.previous.len());
+        let current = next_event.saturating_sub(self.current_start).min(self.current.len());
+        self.previous[previous..].iter().chain(self.current[current..].iter())
+    }
+}
+
+impl<T: Resource> fmt::Debug for Events<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Events")
+            .field("type", &std::any::type_name::<T>())
+            .field("previous", &self.previous.len())
+            .field("current", &self.current.len())
+            .field("event_count", &self.event_count)
+            .finish()
+    }
+}
+
+/// Read position of a reader in an event channel. Each reader has its own cursor, thus the readers see
+/// the same events independent of each other.
+pub struct EventCursor<T: Resource> {
+    next_event: usize,
+    lost: usize,
+    ph: PhantomData<fn(T)>,
+}
+
+impl<T: Resource> Default for EventCursor<T> {
+    /// Create a cursor that reads all the events available in the channel
+    fn default() -> Self {
+        EventCursor {
+            next_event: 0,
+            lost: 0,
+            ph: PhantomData,
+        }
+    }
+}
+
+impl<T: Resource> EventCursor<T> {
+    /// Iterate the unread events and advance the cursor
+    pub fn read<'e>(&mut self, events: &'e Events<T>) -> impl Iterator<Item = &'e T> {
+        let first_available = events.event_count - events.len();
+        let lost = first_available.saturating_sub(self.next_event.max(events.cleared));
+        if lost > 0 {
+            log::trace!("{} events of {} were lost", lost, std::any::type_name::<T>());
+            self.lost += lost;
+        }
+        let next_event = self.next_event;
+        self.next_event = events.event_count;
+        events.iter_from(next_event)
+    }
+
+    /// Number of the unread events
+    pub fn len(&self, events: &Events<T>) -> usize {
+        events.event_count - self.next_event.max(events.event_count - events.len())
+    }
+
+    pub fn is_empty(&self, events: &Events<T>) -> bool {
+        self.len(events) == 0
+    }
+
+    /// Number of the events that were dropped from the channel before this cursor could read them
+    pub fn lost(&self) -> usize {
+        self.lost
+    }
+}
+
+impl<T: Resource> fmt::Debug for EventCursor<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventCursor")
+            .field("next_event", &self.next_event)
+            .field("lost", &self.lost)
+            .finish()
+    }
+}
@@ -0,0 +1,4 @@
+mod events;
+pub use self::events::*;
+mod event_query;
+pub use self::event_query::*;
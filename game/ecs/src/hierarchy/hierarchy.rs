@@ -0,0 +1,127 @@
+pub use crate::entities::{Entities, Entity};
+use crate::ECSError;
+
+/// The parent of an entity in the hierarchy. It is maintained by the `Hierarchy` commands together
+/// with the `Children` of the parent, thus it cannot be altered directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Parent(Entity);
+
+impl Parent {
+    pub fn entity(&self) -> Entity {
+        self.0
+    }
+}
+
+/// The children of an entity in the order of attachment.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Children(Vec<Entity>);
+
+impl Children {
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.0.contains(&entity)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.0.iter().cloned()
+    }
+}
+
+/// Commands to edit the hierarchy of the entities keeping the `Parent` and `Children` components consistent.
+pub trait Hierarchy {
+    fn parent(&self, entity: Entity) -> Option<Entity>;
+
+    fn children(&self, entity: Entity) -> Vec<Entity>;
+
+    /// Attach an entity to a new parent, it is detached from the previous parent.
+    fn attach(&mut self, child: Entity, parent: Entity) -> Result<(), ECSError>;
+
+    /// Detach an entity from its parent and return the previous parent.
+    fn detach(&mut self, child: Entity) -> Result<Option<Entity>, ECSError>;
+
+    /// Despawn an entity together with all of its descendants.
+    fn despawn_recursive(&mut self, entity: Entity) -> Result<(), ECSError>;
+}
+
+impl Hierarchy for Entities {
+    fn parent(&self, entity: Entity) -> Option<Entity> {
+        self.get::<Parent>(entity).ok().map(|parent| parent.entity())
+    }
+
+    fn children(&self, entity: Entity) -> Vec<Entity> {
+        self.get::<Children>(entity)
+            .map(|children| children.iter().collect())
+            .unwrap_or_default()
+    }
+
+    fn attach(&mut self, child: Entity, parent: Entity) -> Result<(), ECSError> {
+        if !self.contains(child) {
+            return Err(ECSError::EntityNotFound(child));
+        }
+        if !self.contains(parent) {
+            return Err(ECSError::EntityNotFound(parent));
+        }
+
+        // the parent shall not be a descendant of the child (or the child itself)
+        let mut ancestor = Some(parent);
+        while let Some(entity) = ancestor {
+            if entity == child {
+                return Err(ECSError::HierarchyCycle(child, parent));
+            }
+            ancestor = self.parent(entity);
+        }
+
+        self.detach(child)?;
+        self.insert_one(child, Parent(parent))
+            .map_err(|_| ECSError::EntityNotFound(child))?;
+        let has_children = self
+            .get_mut::<Children>(parent)
+            .map(|mut children| children.0.push(child))
+            .is_ok();
+        if !has_children {
+            self.insert_one(parent, Children(vec![child]))
+                .map_err(|_| ECSError::EntityNotFound(parent))?;
+        }
+        Ok(())
+    }
+
+    fn detach(&mut self, child: Entity) -> Result<Option<Entity>, ECSError> {
+        if !self.contains(child) {
+            return Err(ECSError::EntityNotFound(child));
+        }
+
+        let parent = match self.remove_one::<Parent>(child) {
+            Ok(parent) => parent.entity(),
+            Err(_) => return Ok(None),
+        };
+        let is_empty = match self.get_mut::<Children>(parent) {
+            Ok(mut children) => {
+                children.0.retain(|&entity| entity != child);
+                children.is_empty()
+            }
+            Err(_) => false,
+        };
+        if is_empty {
+            let _ = self.remove_one::<Children>(parent);
+        }
+        Ok(Some(parent))
+    }
+
+    fn despawn_recursive(&mut self, entity: Entity) -> Result<(), ECSError> {
+        self.detach(entity)?;
+
+        let mut stack = vec![entity];
+        while let Some(entity) = stack.pop() {
+            stack.extend(self.children(entity));
+            self.despawn(entity).map_err(|_| ECSError::EntityNotFound(entity))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,4 @@
+mod hierarchy;
+pub use self::hierarchy::*;
+mod propagate;
+pub use self::propagate::*;
@@ -0,0 +1,34 @@
+use crate::hierarchy::{Children, Entities, Entity, Parent};
+
+/// Propagate a local property (ex. transformation) along the hierarchy. For each entity with a local
+/// component `L` the global component `G` is computed from the global of the parent (None for the roots)
+/// and the local component. The propagation stops at the entities without a local component.
+pub fn propagate_hierarchy<L, G, F>(entities: &mut Entities, propagate: F)
+where
+    L: hecs::Component,
+    G: hecs::Component + Clone,
+    F: Fn(Option<&G>, &L) -> G,
+{
+    let mut stack: Vec<(Entity, Option<G>)> = entities
+        .query::<(&L, Option<&Parent>)>()
+        .iter()
+        .filter(|(_, (_, parent))| parent.is_none())
+        .map(|(entity, _)| (entity, None))
+        .collect();
+
+    while let Some((entity, parent_global)) = stack.pop() {
+        let global = match entities.get::<L>(entity) {
+            Ok(local) => propagate(parent_global.as_ref(), &*local),
+            Err(_) => continue,
+        };
+
+        if let Ok(children) = entities.get::<Children>(entity) {
+            stack.extend(children.iter().map(|child| (child, Some(global.clone()))));
+        }
+
+        let updated = entities.get_mut::<G>(entity).map(|mut g| *g = global.clone()).is_ok();
+        if !updated {
+            let _ = entities.insert_one(entity, global);
+        }
+    }
+}
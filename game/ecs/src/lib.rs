@@ -6,8 +6,13 @@
 #![allow(clippy::match_like_matches_macro)]
 
 pub mod core;
+pub mod entities;
 mod error;
 pub use self::error::*;
+pub mod events;
+pub mod hierarchy;
 pub mod resources;
 pub mod scheduler;
+pub mod snapshot;
+pub mod testing;
 pub mod utils;
@@ -5,16 +5,28 @@ pub use resource_handle::*;
 mod resource_key_handle;
 pub use resource_key_handle::*;
 
+mod resource_tick;
+pub use resource_tick::*;
 mod resource;
 pub use resource::*;
+mod resource_borrow;
+pub use resource_borrow::*;
 mod resource_store;
 pub use resource_store::*;
 mod resource_query;
 pub use resource_query::*;
+mod resource_change;
+pub use resource_change::*;
 mod resources;
 pub use resources::*;
+mod resource_stats;
+pub use resource_stats::*;
 
 mod resource_config;
 pub use resource_config::*;
 mod resource_loader;
 pub use resource_loader::*;
+mod resource_init;
+pub use resource_init::*;
+mod resource_scope;
+pub use resource_scope::*;
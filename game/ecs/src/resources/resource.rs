@@ -1,4 +1,7 @@
-use crate::{core::rwtoken::RWToken, resources::ResourceStoreRead};
+use crate::{
+    core::rwtoken::RWToken,
+    resources::{BorrowOwners, ResourceStoreRead, ResourceTicks, Tick},
+};
 use std::{
     any::type_name,
     cell::UnsafeCell,
@@ -18,30 +21,40 @@ impl<T> Resource for T where T: 'static {}
 pub(crate) struct ResourceCell<T: Resource> {
     resource: UnsafeCell<Option<T>>,
     rw_token: RWToken,
+    borrows: BorrowOwners,
     handle_count: AtomicUsize,
+    ticks: ResourceTicks,
 }
 
 unsafe impl<T: Resource> Send for ResourceCell<T> {}
 unsafe impl<T: Resource> Sync for ResourceCell<T> {}
 
 impl<T: Resource> ResourceCell<T> {
-    pub fn new_occupied(resource: T) -> Arc<Self> {
+    pub fn new_occupied(resource: T, tick: Tick) -> Arc<Self> {
         Arc::new(ResourceCell {
             resource: UnsafeCell::new(Some(resource)),
             handle_count: AtomicUsize::new(0),
             rw_token: RWToken::new(),
+            borrows: BorrowOwners::default(),
+            ticks: ResourceTicks::new(tick),
         })
     }
 
     /// Creates an empty, write locked resource cell .
-    pub fn new_empty() -> Arc<Self> {
+    pub fn new_empty(tick: Tick) -> Arc<Self> {
         Arc::new(ResourceCell {
             resource: UnsafeCell::new(None),
             handle_count: AtomicUsize::new(0),
             rw_token: RWToken::new_write_locked(),
+            borrows: BorrowOwners::default(),
+            ticks: ResourceTicks::new(tick),
         })
     }
 
+    pub fn ticks(&self) -> &ResourceTicks {
+        &self.ticks
+    }
+
     /// Removes the resource form a cell leaving it empty (and write locked)
     /// Types which are !Send should only be retrieved only on the thread which owns the resource collection.
     pub unsafe fn take(&self) -> T {
@@ -64,12 +77,19 @@ impl<T: Resource> ResourceCell<T> {
     }
 
     pub fn read_lock(&self) {
-        self.rw_token
-            .try_read_lock()
-            .unwrap_or_else(|err| panic!("Immutable borrow of a resource [{}] failed: {}", type_name::<T>(), err));
+        self.rw_token.try_read_lock().unwrap_or_else(|err| {
+            panic!(
+                "Immutable borrow of a resource [{}] failed: {}{}",
+                type_name::<T>(),
+                err,
+                self.borrows.describe_conflict()
+            )
+        });
+        self.borrows.add();
     }
 
     pub fn read_unlock(&self) {
+        self.borrows.remove();
         self.rw_token.read_unlock();
     }
 
@@ -84,12 +104,19 @@ impl<T: Resource> ResourceCell<T> {
     }
 
     pub fn write_lock(&self) {
-        self.rw_token
-            .try_write_lock()
-            .unwrap_or_else(|err| panic!("Mutable borrow of a resource [{}] failed: {}", type_name::<T>(), err))
+        self.rw_token.try_write_lock().unwrap_or_else(|err| {
+            panic!(
+                "Mutable borrow of a resource [{}] failed: {}{}",
+                type_name::<T>(),
+                err,
+                self.borrows.describe_conflict()
+            )
+        });
+        self.borrows.add();
     }
 
     pub fn write_unlock(&self) {
+        self.borrows.remove();
         self.rw_token.write_unlock();
     }
 
@@ -129,6 +156,16 @@ impl<'store, T: Resource> ResourceRead<'store, T> {
         cell.read_lock();
         ResourceRead { _store: store, cell }
     }
+
+    /// The tick when the resource was inserted
+    pub fn added_tick(&self) -> Tick {
+        self.cell.ticks().added()
+    }
+
+    /// The tick when the resource was last mutably dereferenced
+    pub fn changed_tick(&self) -> Tick {
+        self.cell.ticks().changed()
+    }
 }
 
 impl<'store, T: Resource> Deref for ResourceRead<'store, T> {
@@ -154,17 +191,34 @@ impl<'store, T: Resource> Drop for ResourceRead<'store, T> {
     }
 }
 
-/// Unique reference to a resource
+/// Unique reference to a resource. Mutable dereference marks the resource as changed.
 pub struct ResourceWrite<'store, T: Resource> {
     /// Keep a readlock on the store, to avoid any "structural" change in the map
-    _store: ResourceStoreRead<'store, T>,
+    store: ResourceStoreRead<'store, T>,
     cell: Arc<ResourceCell<T>>,
 }
 
 impl<'store, T: Resource> ResourceWrite<'store, T> {
     pub(crate) fn new(store: ResourceStoreRead<'store, T>, cell: Arc<ResourceCell<T>>) -> Self {
         cell.write_lock();
-        ResourceWrite { _store: store, cell }
+        ResourceWrite { store, cell }
+    }
+
+    /// The tick when the resource was inserted
+    pub fn added_tick(&self) -> Tick {
+        self.cell.ticks().added()
+    }
+
+    /// The tick when the resource was last mutably dereferenced
+    pub fn changed_tick(&self) -> Tick {
+        self.cell.ticks().changed()
+    }
+
+    /// Mutable access without marking the resource as changed
+    pub fn bypass_change_detection(&mut self) -> &mut T {
+        // safety:
+        //  this type is constructed only if the T implements the required Send and Sync markers
+        unsafe { self.cell.write() }
     }
 }
 
@@ -182,6 +236,7 @@ impl<'store, T: Resource> Deref for ResourceWrite<'store, T> {
 impl<'store, T: Resource> DerefMut for ResourceWrite<'store, T> {
     #[inline]
     fn deref_mut(&mut self) -> &mut T {
+        self.cell.ticks().set_changed(self.store.current_tick());
         // safety:
         //  this type is constructed only if the T implements the required Send and Sync markers
         unsafe { self.cell.write() }
@@ -220,6 +275,14 @@ impl<'store, T: Resource> ResourceMultiRead<'store, T> {
     pub fn is_empty(&self) -> bool {
         self.cells.is_empty()
     }
+
+    pub fn added_tick(&self, idx: usize) -> Tick {
+        self.cells[idx].ticks().added()
+    }
+
+    pub fn changed_tick(&self, idx: usize) -> Tick {
+        self.cells[idx].ticks().changed()
+    }
 }
 
 impl<'store, T: Resource> Index<usize> for ResourceMultiRead<'store, T> {
@@ -245,17 +308,18 @@ impl<'store, T: Resource> Drop for ResourceMultiRead<'store, T> {
     }
 }
 
-/// Unique reference to multiple resources of the same type (with different id)
+/// Unique reference to multiple resources of the same type (with different id). Mutable indexing marks the
+/// resource as changed.
 pub struct ResourceMultiWrite<'store, T: Resource> {
     /// Keep a readlock on the store, to avoid any "structural" change in the map
-    _store: ResourceStoreRead<'store, T>,
+    store: ResourceStoreRead<'store, T>,
     cells: Vec<Arc<ResourceCell<T>>>,
 }
 
 impl<'store, T: Resource> ResourceMultiWrite<'store, T> {
     pub(crate) fn new(store: ResourceStoreRead<'store, T>, cells: Vec<Arc<ResourceCell<T>>>) -> Self {
         cells.iter().for_each(|cell| cell.write_lock());
-        ResourceMultiWrite { _store: store, cells }
+        ResourceMultiWrite { store, cells }
     }
 
     pub fn len(&self) -> usize {
@@ -265,6 +329,14 @@ impl<'store, T: Resource> ResourceMultiWrite<'store, T> {
     pub fn is_empty(&self) -> bool {
         self.cells.is_empty()
     }
+
+    pub fn added_tick(&self, idx: usize) -> Tick {
+        self.cells[idx].ticks().added()
+    }
+
+    pub fn changed_tick(&self, idx: usize) -> Tick {
+        self.cells[idx].ticks().changed()
+    }
 }
 
 impl<'store, T: Resource> Index<usize> for ResourceMultiWrite<'store, T> {
@@ -281,6 +353,7 @@ impl<'store, T: Resource> Index<usize> for ResourceMultiWrite<'store, T> {
 impl<'store, T: Resource> IndexMut<usize> for ResourceMultiWrite<'store, T> {
     #[inline]
     fn index_mut(&mut self, idx: usize) -> &mut Self::Output {
+        self.cells[idx].ticks().set_changed(self.store.current_tick());
         // safety:
         //  this type is constructed only if the T implements the required Send and Sync markers
         unsafe { self.cells[idx].write() }
@@ -0,0 +1,129 @@
+use crate::{
+    resources::{Resource, ResourceId},
+    scheduler::ResourceClaims,
+    ECSError,
+};
+use std::{any::type_name, marker::PhantomData};
+
+#[cfg(debug_assertions)]
+use std::{any::TypeId, cell::RefCell, sync::Mutex, thread};
+
+/// The owner of the borrows and the resources it may access.
+#[cfg(debug_assertions)]
+struct BorrowContext {
+    owner: String,
+    claims: Option<ResourceClaims>,
+}
+
+#[cfg(debug_assertions)]
+thread_local! {
+    static BORROW_CONTEXT: RefCell<Vec<BorrowContext>> = RefCell::new(Vec::new());
+}
+
+/// Guard to name the owner (ex. a system) of the resource borrows made on the current thread. In debug builds
+/// a borrow conflict names both the requesting and the holding owners, and if claims are given, accessing
+/// a resource not present in the claims results in an error.
+/// In release builds it is a no-op.
+pub struct BorrowContextGuard {
+    // the context is bound to the thread
+    _not_send_sync: PhantomData<*const u8>,
+}
+
+impl BorrowContextGuard {
+    pub fn enter(owner: &str, claims: Option<ResourceClaims>) -> BorrowContextGuard {
+        #[cfg(debug_assertions)]
+        BORROW_CONTEXT.with(|context| {
+            context.borrow_mut().push(BorrowContext {
+                owner: owner.to_owned(),
+                claims,
+            })
+        });
+        #[cfg(not(debug_assertions))]
+        let _ = (owner, claims);
+
+        BorrowContextGuard {
+            _not_send_sync: PhantomData,
+        }
+    }
+}
+
+impl Drop for BorrowContextGuard {
+    fn drop(&mut self) {
+        #[cfg(debug_assertions)]
+        BORROW_CONTEXT.with(|context| {
+            let _ = context.borrow_mut().pop();
+        });
+    }
+}
+
+/// Name of the current borrow owner, the system if there is one or the thread.
+#[cfg(debug_assertions)]
+fn current_owner() -> String {
+    BORROW_CONTEXT
+        .with(|context| context.borrow().last().map(|context| context.owner.clone()))
+        .unwrap_or_else(|| format!("thread {}", thread::current().name().unwrap_or("<unnamed>")))
+}
+
+/// Check if the current borrow owner has claimed the resource.
+pub(crate) fn check_claim<T: Resource>(id: &ResourceId, mutable: bool) -> Result<(), ECSError> {
+    #[cfg(debug_assertions)]
+    {
+        let missing = BORROW_CONTEXT.with(|context| match context.borrow().last() {
+            Some(BorrowContext {
+                owner,
+                claims: Some(claims),
+            }) if !claims.is_claimed(TypeId::of::<T>(), id, mutable) => Some(owner.clone()),
+            _ => None,
+        });
+        if let Some(owner) = missing {
+            return Err(ECSError::ResourceNotClaimed(type_name::<T>().into(), id.clone(), owner));
+        }
+    }
+    #[cfg(not(debug_assertions))]
+    let _ = (type_name::<T>(), id, mutable);
+
+    Ok(())
+}
+
+/// Track the owners of the active borrows of a resource (or store) cell.
+#[derive(Default)]
+pub(crate) struct BorrowOwners {
+    #[cfg(debug_assertions)]
+    owners: Mutex<Vec<String>>,
+}
+
+impl BorrowOwners {
+    pub fn add(&self) {
+        #[cfg(debug_assertions)]
+        self.owners.lock().unwrap().push(current_owner());
+    }
+
+    pub fn remove(&self) {
+        #[cfg(debug_assertions)]
+        {
+            let owner = current_owner();
+            let mut owners = self.owners.lock().unwrap();
+            // borrow may be released on another thread, than where it was created
+            let pos = owners
+                .iter()
+                .rposition(|o| *o == owner)
+                .or_else(|| owners.len().checked_sub(1));
+            if let Some(pos) = pos {
+                let _ = owners.remove(pos);
+            }
+        }
+    }
+
+    /// Describe the parties of a failed borrow.
+    #[cfg(debug_assertions)]
+    pub fn describe_conflict(&self) -> String {
+        let owners = self.owners.lock().unwrap();
+        format!(" (requested by {}, held by {})", current_owner(), owners.join(", "))
+    }
+
+    /// Describe the parties of a failed borrow.
+    #[cfg(not(debug_assertions))]
+    pub fn describe_conflict(&self) -> String {
+        String::new()
+    }
+}
@@ -0,0 +1,115 @@
+use crate::{
+    resources::{FetchResource, Resource, ResourceAccess, ResourceQuery, ResourceRead, Resources, SystemTicks, Tick},
+    ECSError,
+};
+use std::marker::PhantomData;
+
+/// Query a global resource for shared access if it was inserted since the last run of the system
+#[derive(Debug)]
+pub struct AddedQuery<T: Resource>(Option<Tick>, PhantomData<fn(T)>);
+
+impl<T: Resource> Default for AddedQuery<T> {
+    fn default() -> Self {
+        Self(None, PhantomData)
+    }
+}
+
+impl<T: Resource> ResourceQuery for AddedQuery<T> {
+    type Fetch = AddedFetch<T>;
+}
+
+/// Fetch for Added<'_, T>
+pub struct AddedFetch<T: Resource>(PhantomData<T>);
+
+impl<'a, T: Resource> FetchResource<'a, AddedQuery<T>> for AddedFetch<T> {
+    type Item = Added<'a, T>;
+
+    fn fetch<'r: 'a>(resources: &'r Resources, claim: &'r mut AddedQuery<T>) -> Result<Self::Item, ECSError> {
+        let resource = resources.get::<T>()?;
+        let ticks = SystemTicks::advance(&mut claim.0, resources.change_tick());
+        if ticks.is_newer(resource.added_tick()) {
+            Ok(Added(Some(resource)))
+        } else {
+            Ok(Added(None))
+        }
+    }
+}
+
+/// Filtered shared borrow of a global resource, the resource is available only if it was inserted since the
+/// last run of the system.
+pub struct Added<'a, T: Resource>(Option<ResourceRead<'a, T>>);
+
+impl<'a, T: Resource> Added<'a, T> {
+    pub fn is_added(&self) -> bool {
+        self.0.is_some()
+    }
+
+    pub fn get(&self) -> Option<&T> {
+        self.0.as_deref()
+    }
+}
+
+impl<'a, T: Resource> ResourceAccess for Added<'a, T> {
+    type Query = AddedQuery<T>;
+    type Fetch = AddedFetch<T>;
+}
+
+/// Query a global resource for shared access if it was changed since the last run of the system
+#[derive(Debug)]
+pub struct ChangedQuery<T: Resource>(Option<Tick>, PhantomData<fn(T)>);
+
+impl<T: Resource> Default for ChangedQuery<T> {
+    fn default() -> Self {
+        Self(None, PhantomData)
+    }
+}
+
+impl<T: Resource> ResourceQuery for ChangedQuery<T> {
+    type Fetch = ChangedFetch<T>;
+}
+
+/// Fetch for Changed<'_, T>
+pub struct ChangedFetch<T: Resource>(PhantomData<T>);
+
+impl<'a, T: Resource> FetchResource<'a, ChangedQuery<T>> for ChangedFetch<T> {
+    type Item = Changed<'a, T>;
+
+    fn fetch<'r: 'a>(resources: &'r Resources, claim: &'r mut ChangedQuery<T>) -> Result<Self::Item, ECSError> {
+        let resource = resources.get::<T>()?;
+        let ticks = SystemTicks::advance(&mut claim.0, resources.change_tick());
+        if ticks.is_newer(resource.changed_tick()) {
+            Ok(Changed(Some(resource)))
+        } else {
+            Ok(Changed(None))
+        }
+    }
+}
+
+/// Filtered shared borrow of a global resource, the resource is available only if it was changed (or
+/// inserted) since the last run of the system. Systems can return early if their inputs are unchanged.
+/// #Example
+/// ```
+/// # use shine_ecs::{ECSError, resources::*, scheduler::*};
+/// fn rebuild(config: Changed<String>, mut output: ResMut<usize>) -> Result<TaskGroup, ECSError> {
+///     if let Some(config) = config.get() {
+///         *output = config.len();
+///     }
+///     Ok(TaskGroup::default())
+/// }
+/// ```
+pub struct Changed<'a, T: Resource>(Option<ResourceRead<'a, T>>);
+
+impl<'a, T: Resource> Changed<'a, T> {
+    pub fn is_changed(&self) -> bool {
+        self.0.is_some()
+    }
+
+    pub fn get(&self) -> Option<&T> {
+        self.0.as_deref()
+    }
+}
+
+impl<'a, T: Resource> ResourceAccess for Changed<'a, T> {
+    type Query = ChangedQuery<T>;
+    type Fetch = ChangedFetch<T>;
+}
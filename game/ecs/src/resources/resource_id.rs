@@ -2,6 +2,7 @@ use crate::{
     core::{error::ErrorString, ids::SmallStringId},
     ECSError,
 };
+use serde::{Deserialize, Serialize};
 use std::{
     collections::hash_map::DefaultHasher,
     fmt,
@@ -12,7 +13,7 @@ use std::{
 
 pub type ResourceTag = SmallStringId<16>;
 
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum ResourceId {
     Global,
     Tag(ResourceTag),
@@ -0,0 +1,16 @@
+use crate::{
+    resources::{Resource, Resources},
+    ECSError,
+};
+
+/// Construction of a resource with access to the other resources (ex. device, asset io).
+/// It is implemented for all the `Default` resources.
+pub trait FromResources: Resource {
+    fn from_resources(resources: &Resources) -> Result<Self, ECSError>;
+}
+
+impl<T: Resource + Default> FromResources for T {
+    fn from_resources(_resources: &Resources) -> Result<Self, ECSError> {
+        Ok(T::default())
+    }
+}
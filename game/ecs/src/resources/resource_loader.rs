@@ -52,6 +52,14 @@ pub struct ResourceLoadRequester<T: Resource, RQ> {
     request_sender: UnboundedSender<(ResourceHandle<T>, RQ)>,
 }
 
+impl<T: Resource, RQ> Clone for ResourceLoadRequester<T, RQ> {
+    fn clone(&self) -> Self {
+        Self {
+            request_sender: self.request_sender.clone(),
+        }
+    }
+}
+
 impl<T: Resource, RQ> ResourceLoadRequester<T, RQ> {
     pub fn send_request(&self, handle: ResourceHandle<T>, rq: RQ) {
         log::trace!("[{:?}] Sending load request", handle);
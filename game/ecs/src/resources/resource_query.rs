@@ -1,7 +1,7 @@
 use crate::{
     resources::{
         Resource, ResourceId, ResourceMultiRead, ResourceMultiWrite, ResourceRead, ResourceTag, ResourceWrite,
-        Resources,
+        Resources, SystemTicks, Tick,
     },
     ECSError,
 };
@@ -42,17 +42,17 @@ impl Resources {
 
 /// Query a resource by type for shared access
 #[derive(Debug)]
-pub struct ResQuery<T: Resource>(PhantomData<fn(T)>);
+pub struct ResQuery<T: Resource>(Option<Tick>, PhantomData<fn(T)>);
 
 impl<T: Resource> Default for ResQuery<T> {
     fn default() -> Self {
-        Self(PhantomData)
+        Self(None, PhantomData)
     }
 }
 
 impl<T: Resource> ResQuery<T> {
     pub fn new() -> Self {
-        Self(PhantomData)
+        Self(None, PhantomData)
     }
 }
 
@@ -66,13 +66,29 @@ pub struct ResFetch<T: Resource>(PhantomData<T>);
 impl<'a, T: Resource> FetchResource<'a, ResQuery<T>> for ResFetch<T> {
     type Item = Res<'a, T>;
 
-    fn fetch<'r: 'a>(resources: &'r Resources, _claim: &'r mut ResQuery<T>) -> Result<Self::Item, ECSError> {
-        Ok(Res(resources.get::<T>()?))
+    fn fetch<'r: 'a>(resources: &'r Resources, claim: &'r mut ResQuery<T>) -> Result<Self::Item, ECSError> {
+        let resource = resources.get::<T>()?;
+        Ok(Res(
+            resource,
+            SystemTicks::advance(&mut claim.0, resources.change_tick()),
+        ))
     }
 }
 
 /// Shared borrow of a global resource by type
-pub struct Res<'a, T: Resource>(pub ResourceRead<'a, T>);
+pub struct Res<'a, T: Resource>(pub ResourceRead<'a, T>, SystemTicks);
+
+impl<'a, T: Resource> Res<'a, T> {
+    /// Check if the resource was inserted since the last run of the system
+    pub fn is_added(&self) -> bool {
+        self.1.is_newer(self.0.added_tick())
+    }
+
+    /// Check if the resource was changed (or inserted) since the last run of the system
+    pub fn is_changed(&self) -> bool {
+        self.1.is_newer(self.0.changed_tick())
+    }
+}
 
 impl<'a, T: Resource> Deref for Res<'a, T> {
     type Target = T;
@@ -89,17 +105,17 @@ impl<'a, T: Resource> ResourceAccess for Res<'a, T> {
 
 /// Query a resource by type for exclusive access
 #[derive(Debug)]
-pub struct ResMutQuery<T: Resource>(PhantomData<fn(T)>);
+pub struct ResMutQuery<T: Resource>(Option<Tick>, PhantomData<fn(T)>);
 
 impl<T: Resource> Default for ResMutQuery<T> {
     fn default() -> Self {
-        Self(PhantomData)
+        Self(None, PhantomData)
     }
 }
 
 impl<T: Resource> ResMutQuery<T> {
     pub fn new() -> Self {
-        Self(PhantomData)
+        Self(None, PhantomData)
     }
 }
 
@@ -113,13 +129,35 @@ pub struct ResMutFetch<T: Resource>(PhantomData<T>);
 impl<'a, T: Resource> FetchResource<'a, ResMutQuery<T>> for ResMutFetch<T> {
     type Item = ResMut<'a, T>;
 
-    fn fetch<'r: 'a>(resources: &'r Resources, _claim: &'r mut ResMutQuery<T>) -> Result<Self::Item, ECSError> {
-        Ok(ResMut(resources.get_mut::<T>()?))
+    fn fetch<'r: 'a>(resources: &'r Resources, claim: &'r mut ResMutQuery<T>) -> Result<Self::Item, ECSError> {
+        let resource = resources.get_mut::<T>()?;
+        Ok(ResMut(
+            resource,
+            SystemTicks::advance(&mut claim.0, resources.change_tick()),
+        ))
     }
 }
 
-/// Unique borrow of a global resource by type
-pub struct ResMut<'a, T: Resource>(pub ResourceWrite<'a, T>);
+/// Unique borrow of a global resource by type. Mutable dereference marks the resource as changed.
+pub struct ResMut<'a, T: Resource>(pub ResourceWrite<'a, T>, SystemTicks);
+
+impl<'a, T: Resource> ResMut<'a, T> {
+    /// Check if the resource was inserted since the last run of the system
+    pub fn is_added(&self) -> bool {
+        self.1.is_newer(self.0.added_tick())
+    }
+
+    /// Check if the resource was changed (or inserted) since the last run of the system. The changes
+    /// made by the system itself are not reported on its next run.
+    pub fn is_changed(&self) -> bool {
+        self.1.is_newer(self.0.changed_tick())
+    }
+
+    /// Mutable access without marking the resource as changed
+    pub fn bypass_change_detection(&mut self) -> &mut T {
+        self.0.bypass_change_detection()
+    }
+}
 
 impl<'a, T: Resource> Deref for ResMut<'a, T> {
     type Target = T;
@@ -141,17 +179,17 @@ impl<'a, T: Resource> ResourceAccess for ResMut<'a, T> {
 }
 
 /// Query resources of the same type by id for shared access
-pub struct MultiResQuery<T: Resource>(Vec<ResourceId>, PhantomData<fn(T)>);
+pub struct MultiResQuery<T: Resource>(Vec<ResourceId>, Option<Tick>, PhantomData<fn(T)>);
 
 impl<T: Resource> Default for MultiResQuery<T> {
     fn default() -> Self {
-        Self(Vec::new(), PhantomData)
+        Self(Vec::new(), None, PhantomData)
     }
 }
 
 impl<T: Resource> MultiResQuery<T> {
     pub fn new(ids: Vec<ResourceId>) -> Self {
-        Self(ids, PhantomData)
+        Self(ids, None, PhantomData)
     }
 
     pub fn add_ids<I: IntoIterator<Item = ResourceId>>(&mut self, iter: I) {
@@ -211,15 +249,31 @@ impl<'a, T: Resource> FetchResource<'a, MultiResQuery<T>> for MultiResFetch<T> {
     type Item = MultiRes<'a, T>;
 
     fn fetch<'r: 'a>(resources: &'r Resources, claims: &'r mut MultiResQuery<T>) -> Result<Self::Item, ECSError> {
+        let ticks = SystemTicks::advance(&mut claims.1, resources.change_tick());
         let resources = resources.get_with_ids::<T, _>(claims.0.iter())?;
-        Ok(MultiRes(resources, claims))
+        Ok(MultiRes(resources, claims, ticks))
     }
 }
 
 /// Shared borrow of multiple resources of the same type
-pub struct MultiRes<'a, T: Resource>(ResourceMultiRead<'a, T>, &'a MultiResQuery<T>);
+pub struct MultiRes<'a, T: Resource>(ResourceMultiRead<'a, T>, &'a MultiResQuery<T>, SystemTicks);
 
 impl<'a, T: Resource> MultiRes<'a, T> {
+    /// Check if the resource at the given index was inserted since the last run of the system
+    pub fn is_added(&self, idx: usize) -> bool {
+        self.2.is_newer(self.0.added_tick(idx))
+    }
+
+    /// Check if the resource at the given index was changed since the last run of the system
+    pub fn is_changed(&self, idx: usize) -> bool {
+        self.2.is_newer(self.0.changed_tick(idx))
+    }
+
+    /// Iterate the indices of the resources changed since the last run of the system
+    pub fn changed(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len()).filter(move |idx| self.is_changed(*idx))
+    }
+
     pub fn len(&self) -> usize {
         self.0.len()
     }
@@ -254,17 +308,17 @@ impl<'a, T: Resource> ResourceAccess for MultiRes<'a, T> {
 }
 
 /// Query resources of the same type by id for exclusive access
-pub struct MultiResMutQuery<T: Resource>(Vec<ResourceId>, PhantomData<fn(T)>);
+pub struct MultiResMutQuery<T: Resource>(Vec<ResourceId>, Option<Tick>, PhantomData<fn(T)>);
 
 impl<T: Resource> Default for MultiResMutQuery<T> {
     fn default() -> Self {
-        Self(Vec::new(), PhantomData)
+        Self(Vec::new(), None, PhantomData)
     }
 }
 
 impl<T: Resource> MultiResMutQuery<T> {
     pub fn new(ids: Vec<ResourceId>) -> Self {
-        Self(ids, PhantomData)
+        Self(ids, None, PhantomData)
     }
 
     pub fn add_ids<I: IntoIterator<Item = ResourceId>>(&mut self, iter: I) {
@@ -324,15 +378,31 @@ impl<'a, T: Resource> FetchResource<'a, MultiResMutQuery<T>> for MultiResMutFetc
     type Item = MultiResMut<'a, T>;
 
     fn fetch<'r: 'a>(resources: &'r Resources, claims: &'r mut MultiResMutQuery<T>) -> Result<Self::Item, ECSError> {
+        let ticks = SystemTicks::advance(&mut claims.1, resources.change_tick());
         let resources = resources.get_mut_with_ids::<T, _>(&claims.0)?;
-        Ok(MultiResMut(resources, claims))
+        Ok(MultiResMut(resources, claims, ticks))
     }
 }
 
-/// Shared borrow of multiple resources of the same type
-pub struct MultiResMut<'a, T: Resource>(ResourceMultiWrite<'a, T>, &'a MultiResMutQuery<T>);
+/// Unique borrow of multiple resources of the same type. Mutable indexing marks the resource as changed.
+pub struct MultiResMut<'a, T: Resource>(ResourceMultiWrite<'a, T>, &'a MultiResMutQuery<T>, SystemTicks);
 
 impl<'a, T: Resource> MultiResMut<'a, T> {
+    /// Check if the resource at the given index was inserted since the last run of the system
+    pub fn is_added(&self, idx: usize) -> bool {
+        self.2.is_newer(self.0.added_tick(idx))
+    }
+
+    /// Check if the resource at the given index was changed since the last run of the system
+    pub fn is_changed(&self, idx: usize) -> bool {
+        self.2.is_newer(self.0.changed_tick(idx))
+    }
+
+    /// Iterate the indices of the resources changed since the last run of the system
+    pub fn changed(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len()).filter(move |idx| self.is_changed(*idx))
+    }
+
     pub fn len(&self) -> usize {
         self.0.len()
     }
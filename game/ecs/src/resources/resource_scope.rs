@@ -0,0 +1,3 @@
+/// Marker of a group of resources that are released together, ex. the resources of a loaded game.
+/// The scope is identified by the type, thus a unit struct is the usual implementation.
+pub trait ResourceScope: 'static {}
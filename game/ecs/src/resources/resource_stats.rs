@@ -0,0 +1,83 @@
+use std::{fmt, mem};
+
+/// Memory and occupancy statistics of the store of a resource type
+#[derive(Debug, Clone)]
+pub struct ResourceStoreStats {
+    pub type_name: &'static str,
+    /// Size of a resource instance
+    pub item_size: usize,
+    /// Number of the stored instances
+    pub count: usize,
+    /// Number of instances created on demand and not baked yet
+    pub pending_count: usize,
+    /// Number of instances referenced by a handle
+    pub handle_count: usize,
+    /// Number of instances the store can hold without reallocation
+    pub capacity: usize,
+}
+
+impl ResourceStoreStats {
+    /// Estimated memory used by the instances, the heap allocations of the resources are not included.
+    pub fn memory_size(&self) -> usize {
+        // each instance is stored in a separate cell, the map stores only a reference
+        (self.count + self.pending_count) * self.item_size + self.capacity * mem::size_of::<usize>() * 2
+    }
+
+    /// Ratio of the unused slots in the store
+    pub fn fragmentation(&self) -> f32 {
+        if self.capacity == 0 {
+            0.
+        } else {
+            1. - (self.count as f32 / self.capacity as f32)
+        }
+    }
+}
+
+/// Statistics of all the resource stores
+#[derive(Debug, Clone, Default)]
+pub struct ResourcesStats {
+    pub stores: Vec<ResourceStoreStats>,
+}
+
+impl ResourcesStats {
+    pub fn find(&self, type_name: &str) -> Option<&ResourceStoreStats> {
+        self.stores.iter().find(|store| store.type_name == type_name)
+    }
+
+    pub fn total_count(&self) -> usize {
+        self.stores.iter().map(|store| store.count + store.pending_count).sum()
+    }
+
+    pub fn total_memory_size(&self) -> usize {
+        self.stores.iter().map(|store| store.memory_size()).sum()
+    }
+}
+
+impl fmt::Display for ResourcesStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut stores = self.stores.iter().collect::<Vec<_>>();
+        stores.sort_by(|a, b| b.memory_size().cmp(&a.memory_size()));
+        writeln!(
+            f,
+            "stores: {}, resources: {}, memory: {}B",
+            stores.len(),
+            self.total_count(),
+            self.total_memory_size()
+        )?;
+        for store in stores {
+            writeln!(
+                f,
+                "  {}: count: {}, pending: {}, handles: {}, capacity: {}, item: {}B, memory: {}B, fragmentation: {:.2}",
+                store.type_name,
+                store.count,
+                store.pending_count,
+                store.handle_count,
+                store.capacity,
+                store.item_size,
+                store.memory_size(),
+                store.fragmentation()
+            )?;
+        }
+        Ok(())
+    }
+}
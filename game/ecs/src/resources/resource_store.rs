@@ -2,8 +2,8 @@ use crate::{
     core::rwtoken::RWToken,
     dbg_assert,
     resources::{
-        Resource, ResourceCell, ResourceConfig, ResourceHandle, ResourceId, ResourceMultiRead, ResourceMultiWrite,
-        ResourceRead, ResourceWrite,
+        check_claim, BorrowOwners, Resource, ResourceCell, ResourceConfig, ResourceHandle, ResourceId,
+        ResourceMultiRead, ResourceMultiWrite, ResourceRead, ResourceStoreStats, ResourceWrite, Tick, TickCounter,
     },
     ECSError,
 };
@@ -12,6 +12,7 @@ use std::{
     cell::UnsafeCell,
     collections::HashMap,
     marker::PhantomData,
+    mem,
     sync::{
         atomic::{self, AtomicUsize},
         Arc, Mutex,
@@ -52,15 +53,17 @@ pub(crate) struct ResourceStore<T: Resource> {
     config: Box<dyn ResourceConfig<Resource = T>>,
     resource_map: HashMap<ResourceId, Arc<ResourceCell<T>>>,
     pending: Mutex<HashMap<ResourceId, Arc<ResourceCell<T>>>>,
+    ticks: Arc<TickCounter>,
 }
 
 impl<T: Resource> ResourceStore<T> {
-    fn new(config: Box<dyn ResourceConfig<Resource = T>>) -> Self {
+    fn new(config: Box<dyn ResourceConfig<Resource = T>>, ticks: Arc<TickCounter>) -> Self {
         Self {
             generation: STORE_UNIQUE_ID.fetch_add(1, atomic::Ordering::SeqCst),
             resource_map: Default::default(),
             pending: Mutex::new(Default::default()),
             config,
+            ticks,
         }
     }
 
@@ -68,6 +71,23 @@ impl<T: Resource> ResourceStore<T> {
         self.generation
     }
 
+    pub fn current_tick(&self) -> Tick {
+        self.ticks.current()
+    }
+
+    /// Clamp the old change ticks of the instances
+    /// # Safety
+    /// As this operation does not touch the resources itself, it is safe to call for any resources on any thread
+    /// dispite of the Send, Sync properties.
+    pub fn check_ticks(&self, current: Tick) {
+        self.resource_map.values().for_each(|cell| cell.ticks().clamp(current));
+        self.pending
+            .lock()
+            .unwrap()
+            .values()
+            .for_each(|cell| cell.ticks().clamp(current));
+    }
+
     /// Check if requesting the given resource would be successfull. As some resources are created on demand
     /// they are treated as if they were contained in the store.
     /// # Safety
@@ -89,6 +109,34 @@ impl<T: Resource> ResourceStore<T> {
         self.resource_map.contains_key(id) || self.pending.lock().unwrap().contains_key(id)
     }
 
+    /// Return the ids of the stored and pending instances in a sorted order. Iterating the instances in this order
+    /// is independent of the hashing and the insertion order, thus it is deterministic across runs and platforms.
+    /// # Safety
+    /// As this operation does not touch the resources itself, it is safe to call for any resources on any thread
+    /// dispite of the Send, Sync properties.
+    pub fn ids(&self) -> Vec<ResourceId> {
+        let mut ids = self.resource_map.keys().cloned().collect::<Vec<_>>();
+        ids.extend(self.pending.lock().unwrap().keys().cloned());
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+
+    /// Collect the statistics of the store.
+    /// # Safety
+    /// As this operation does not touch the resources itself, it is safe to call for any resources on any thread
+    /// dispite of the Send, Sync properties.
+    pub fn stats(&self) -> ResourceStoreStats {
+        ResourceStoreStats {
+            type_name: type_name::<T>(),
+            item_size: mem::size_of::<T>(),
+            count: self.resource_map.len(),
+            pending_count: self.pending.lock().unwrap().len(),
+            handle_count: self.resource_map.values().filter(|cell| cell.has_handle()).count(),
+            capacity: self.resource_map.capacity(),
+        }
+    }
+
     /// Insert a new resource. If a resource with the given id already exists, all the handles
     /// are invalidated. The other type of references and accessors are not effected as they
     /// should not exist by the design of the API.
@@ -96,7 +144,8 @@ impl<T: Resource> ResourceStore<T> {
     /// Resources which are `!Send` must be inserted only on the thread owning the resources.
     pub unsafe fn insert(&mut self, id: ResourceId, resource: T) -> Option<T> {
         let out = self.remove(&id);
-        self.resource_map.insert(id, ResourceCell::new_occupied(resource));
+        self.resource_map
+            .insert(id, ResourceCell::new_occupied(resource, self.current_tick()));
         out
     }
 
@@ -130,9 +179,10 @@ impl<T: Resource> ResourceStore<T> {
             if self.config.auto_build() {
                 let config = &self.config;
                 let generation = self.generation();
+                let tick = self.current_tick();
                 let mut pending = self.pending.lock().unwrap();
                 let cell = pending.entry(id.clone()).or_insert_with_key(|id| {
-                    let cell = ResourceCell::new_empty();
+                    let cell = ResourceCell::new_empty(tick);
                     let handle = ResourceHandle::new(generation, &cell, &id);
                     cell.set(config.build(handle, id));
                     cell
@@ -170,27 +220,32 @@ impl<T: Resource> ResourceStore<T> {
 pub(crate) struct ResourceStoreCell<T: Resource> {
     store: UnsafeCell<ResourceStore<T>>,
     rw_token: RWToken,
+    borrows: BorrowOwners,
 }
 
 impl<T: Resource> ResourceStoreCell<T> {
-    pub fn new(config: Box<dyn ResourceConfig<Resource = T>>) -> Self {
+    pub fn new(config: Box<dyn ResourceConfig<Resource = T>>, ticks: Arc<TickCounter>) -> Self {
         Self {
-            store: UnsafeCell::new(ResourceStore::new(config)),
+            store: UnsafeCell::new(ResourceStore::new(config, ticks)),
             rw_token: RWToken::new(),
+            borrows: BorrowOwners::default(),
         }
     }
 
     pub fn read_lock(&self) {
         self.rw_token.try_read_lock().unwrap_or_else(|err| {
             panic!(
-                "Immutable borrow of a resource store [{}] failed: {}",
+                "Immutable borrow of a resource store [{}] failed: {}{}",
                 type_name::<T>(),
-                err
+                err,
+                self.borrows.describe_conflict()
             )
         });
+        self.borrows.add();
     }
 
     pub fn read_unlock(&self) {
+        self.borrows.remove();
         self.rw_token.read_unlock();
     }
 
@@ -206,14 +261,17 @@ impl<T: Resource> ResourceStoreCell<T> {
     pub fn write_lock(&self) {
         self.rw_token.try_write_lock().unwrap_or_else(|err| {
             panic!(
-                "Mutable borrow of a resource store [{}] failed: {}",
+                "Mutable borrow of a resource store [{}] failed: {}{}",
                 type_name::<T>(),
-                err
+                err,
+                self.borrows.describe_conflict()
             )
         });
+        self.borrows.add();
     }
 
     pub fn write_unlock(&self) {
+        self.borrows.remove();
         self.rw_token.write_unlock();
     }
 
@@ -280,11 +338,30 @@ impl<'store, T: Resource> ResourceStoreRead<'store, T> {
         self.store().contains(id)
     }
 
+    /// Return the ids of the instances in a deterministic order.
+    pub fn ids(&self) -> Vec<ResourceId> {
+        self.store().ids()
+    }
+
+    pub fn stats(&self) -> ResourceStoreStats {
+        self.store().stats()
+    }
+
+    /// The current change tick of the resources
+    pub fn current_tick(&self) -> Tick {
+        self.store().current_tick()
+    }
+
+    pub(crate) fn check_ticks(&self, current: Tick) {
+        self.store().check_ticks(current)
+    }
+
     pub fn get(&self) -> Result<ResourceRead<'store, T>, ECSError> {
         self.get_with_id(&ResourceId::Global)
     }
 
     pub fn get_with_id(&self, id: &ResourceId) -> Result<ResourceRead<'store, T>, ECSError> {
+        check_claim::<T>(id, false)?;
         let store = self.clone();
         let cell = store
             .get_cell(id)
@@ -301,6 +378,7 @@ impl<'store, T: Resource> ResourceStoreRead<'store, T> {
         let cells = ids
             .into_iter()
             .map(|id| {
+                check_claim::<T>(id.as_ref(), false)?;
                 store
                     .get_cell(id.as_ref())
                     .ok_or_else(|| ECSError::ResourceNotFound(type_name::<T>().into(), id.as_ref().clone()))
@@ -314,6 +392,7 @@ impl<'store, T: Resource> ResourceStoreRead<'store, T> {
     }
 
     pub fn get_mut_with_id(&self, id: &ResourceId) -> Result<ResourceWrite<'store, T>, ECSError> {
+        check_claim::<T>(id, true)?;
         let store = self.clone();
         let cell = store
             .get_cell(id)
@@ -330,6 +409,7 @@ impl<'store, T: Resource> ResourceStoreRead<'store, T> {
         let cells = ids
             .into_iter()
             .map(|id| {
+                check_claim::<T>(id.as_ref(), true)?;
                 store
                     .get_cell(id.as_ref())
                     .ok_or_else(|| ECSError::ResourceNotFound(type_name::<T>().into(), id.as_ref().clone()))
@@ -349,6 +429,7 @@ impl<'store, T: Resource> ResourceStoreRead<'store, T> {
         if handle.generation() != self.generation() {
             Err(ECSError::ResourceExpired)
         } else if let Some(cell) = handle.upgrade() {
+            check_claim::<T>(handle.id(), false)?;
             Ok(ResourceRead::new(self.clone(), cell))
         } else {
             Err(ECSError::ResourceTypeNotFound(type_name::<T>().into()))
@@ -363,6 +444,7 @@ impl<'store, T: Resource> ResourceStoreRead<'store, T> {
         if handle.generation() != self.generation() {
             Err(ECSError::ResourceExpired)
         } else if let Some(cell) = handle.upgrade() {
+            check_claim::<T>(handle.id(), true)?;
             Ok(ResourceWrite::new(self.clone(), cell))
         } else {
             Err(ECSError::ResourceTypeNotFound(type_name::<T>().into()))
@@ -0,0 +1,128 @@
+use std::sync::atomic::{self, AtomicU32};
+
+/// Ticks older than this are clamped to keep the comparison valid after the wrap-around of the counter
+pub const MAX_TICK_AGE: u32 = u32::MAX / 4 * 3;
+
+/// Number of the ticks between two clamping of the old ticks
+pub const TICK_CHECK_PERIOD: u32 = u32::MAX / 8;
+
+/// Change tick of the resources. The tick counter is advanced after each system run and it wraps around,
+/// thus ticks can be compared only relative to the current tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Tick(u32);
+
+impl Tick {
+    pub const fn new(tick: u32) -> Tick {
+        Tick(tick)
+    }
+
+    pub fn get(self) -> u32 {
+        self.0
+    }
+
+    /// Number of the ticks elapsed since this tick
+    pub fn age(self, current: Tick) -> u32 {
+        current.0.wrapping_sub(self.0)
+    }
+
+    /// Check if this tick is more recent than the last run of a system
+    pub fn is_newer_than(self, last_run: Tick, current: Tick) -> bool {
+        self.age(current) < last_run.age(current)
+    }
+
+    /// Clamp the tick if it is older than MAX_TICK_AGE
+    #[must_use]
+    pub fn clamped(self, current: Tick) -> Tick {
+        if self.age(current) > MAX_TICK_AGE {
+            Tick(current.0.wrapping_sub(MAX_TICK_AGE))
+        } else {
+            self
+        }
+    }
+}
+
+/// The tick when a resource instance was inserted and last mutably dereferenced
+pub(crate) struct ResourceTicks {
+    added: AtomicU32,
+    changed: AtomicU32,
+}
+
+impl ResourceTicks {
+    pub fn new(tick: Tick) -> ResourceTicks {
+        ResourceTicks {
+            added: AtomicU32::new(tick.0),
+            changed: AtomicU32::new(tick.0),
+        }
+    }
+
+    pub fn added(&self) -> Tick {
+        Tick(self.added.load(atomic::Ordering::Relaxed))
+    }
+
+    pub fn changed(&self) -> Tick {
+        Tick(self.changed.load(atomic::Ordering::Relaxed))
+    }
+
+    pub fn set_changed(&self, tick: Tick) {
+        self.changed.store(tick.0, atomic::Ordering::Relaxed);
+    }
+
+    pub fn clamp(&self, current: Tick) {
+        self.added
+            .store(self.added().clamped(current).0, atomic::Ordering::Relaxed);
+        self.changed
+            .store(self.changed().clamped(current).0, atomic::Ordering::Relaxed);
+    }
+}
+
+/// The tick counter shared by the stores of the resources
+#[derive(Default)]
+pub(crate) struct TickCounter {
+    tick: AtomicU32,
+    last_check: AtomicU32,
+}
+
+impl TickCounter {
+    pub fn current(&self) -> Tick {
+        Tick(self.tick.load(atomic::Ordering::Relaxed))
+    }
+
+    /// Advance the counter and return the new tick and if the old ticks have to be clamped
+    pub fn increment(&self) -> (Tick, bool) {
+        let tick = Tick(self.tick.fetch_add(1, atomic::Ordering::Relaxed).wrapping_add(1));
+        let last_check = Tick(self.last_check.load(atomic::Ordering::Relaxed));
+        if last_check.age(tick) > TICK_CHECK_PERIOD {
+            self.last_check.store(tick.0, atomic::Ordering::Relaxed);
+            (tick, true)
+        } else {
+            (tick, false)
+        }
+    }
+}
+
+/// The ticks of a resource access by a system: the tick of the previous access and the current tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SystemTicks {
+    /// None on the first access, all the resources are treated as added and changed
+    pub last_run: Option<Tick>,
+    pub current: Tick,
+}
+
+impl SystemTicks {
+    /// Create the ticks of an access and store the current tick for the next access
+    pub fn advance(last_run: &mut Option<Tick>, current: Tick) -> SystemTicks {
+        let ticks = SystemTicks {
+            last_run: *last_run,
+            current,
+        };
+        *last_run = Some(current);
+        ticks
+    }
+
+    pub fn is_newer(&self, tick: Tick) -> bool {
+        match self.last_run {
+            None => true,
+            Some(last_run) => tick.is_newer_than(last_run, self.current),
+        }
+    }
+}
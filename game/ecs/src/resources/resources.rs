@@ -1,8 +1,9 @@
 use crate::resources::ResourceStoreCell;
 use crate::{
     resources::{
-        Resource, ResourceConfig, ResourceHandle, ResourceId, ResourceMultiRead, ResourceMultiWrite, ResourceRead,
-        ResourceStoreRead, ResourceStoreWrite, ResourceWrite, UnmanagedResource,
+        FromResources, Resource, ResourceConfig, ResourceHandle, ResourceId, ResourceMultiRead, ResourceMultiWrite,
+        ResourceRead, ResourceScope, ResourceStoreRead, ResourceStoreStats, ResourceStoreWrite, ResourceWrite,
+        ResourcesStats, Tick, TickCounter, UnmanagedResource,
     },
     ECSError,
 };
@@ -10,12 +11,26 @@ use downcast_rs::{impl_downcast, Downcast};
 use std::{
     any::{type_name, TypeId},
     collections::HashMap,
+    hash::{Hash, Hasher},
     marker::PhantomData,
+    sync::Arc,
 };
 
 /// Helper trait to help implementing downcast for RespurceStore
-trait GeneralResourceStoreCell: Downcast {}
-impl<T: Resource> GeneralResourceStoreCell for ResourceStoreCell<T> {}
+trait GeneralResourceStoreCell: Downcast {
+    fn stats(&self) -> ResourceStoreStats;
+    fn check_ticks(&self, current: Tick);
+}
+
+impl<T: Resource> GeneralResourceStoreCell for ResourceStoreCell<T> {
+    fn stats(&self) -> ResourceStoreStats {
+        ResourceStoreRead::new(self).stats()
+    }
+
+    fn check_ticks(&self, current: Tick) {
+        ResourceStoreRead::new(self).check_ticks(current)
+    }
+}
 impl_downcast!(GeneralResourceStoreCell);
 
 /// Store all the resources. Unsafe as the Send and Sync property of a resource is not
@@ -23,6 +38,7 @@ impl_downcast!(GeneralResourceStoreCell);
 #[derive(Default)]
 struct UnsafeResources {
     store_map: HashMap<TypeId, Box<dyn GeneralResourceStoreCell>>,
+    ticks: Arc<TickCounter>,
 }
 
 unsafe impl Send for UnsafeResources {}
@@ -39,7 +55,8 @@ impl UnsafeResources {
         if self.store_map.get(&ty).is_some() {
             return Err(ECSError::ResourceAlreadyRegistered(type_name::<T>().into()));
         }
-        self.store_map.insert(ty, Box::new(ResourceStoreCell::<T>::new(config)));
+        self.store_map
+            .insert(ty, Box::new(ResourceStoreCell::<T>::new(config, self.ticks.clone())));
         Ok(())
     }
 
@@ -95,10 +112,14 @@ impl UnsafeResources {
     }
 }
 
+/// Release action of a scoped resource
+type ScopeRelease = Box<dyn FnOnce(&mut Resources)>;
+
 /// Resources container.
 #[derive(Default)]
 pub struct Resources {
     internal: UnsafeResources,
+    scopes: HashMap<TypeId, Vec<ScopeRelease>>,
     // marker to make `Resources` !Send and !Sync
     _not_send_sync: PhantomData<*const u8>,
 }
@@ -117,6 +138,33 @@ impl Resources {
         }
     }
 
+    /// Collect the statistics of all the resource stores.
+    pub fn stats(&self) -> ResourcesStats {
+        let mut stats = ResourcesStats {
+            stores: self.internal.store_map.values().map(|cell| cell.stats()).collect(),
+        };
+        // sort for a stable output independent of the hashing
+        stats.stores.sort_by(|a, b| a.type_name.cmp(b.type_name));
+        stats
+    }
+
+    /// The current change tick, the inserted and mutated resources are stamped with this tick.
+    pub fn change_tick(&self) -> Tick {
+        self.internal.ticks.current()
+    }
+
+    /// Advance the change tick, it is called by the scheduler after each system run. The ticks of the
+    /// resources are clamped periodically to keep the change detection valid after the wrap-around
+    /// of the counter.
+    pub fn increment_change_tick(&self) -> Tick {
+        let (tick, check) = self.internal.ticks.increment();
+        if check {
+            log::trace!("Clamping change ticks at {:?}", tick);
+            self.internal.store_map.values().for_each(|cell| cell.check_ticks(tick));
+        }
+        tick
+    }
+
     /// Register a new type of resource with the given configuration.
     /// Resources have to be registered before instances could be inserted.
     pub fn register<T: Resource, TC: 'static + ResourceConfig<Resource = T>>(
@@ -145,6 +193,26 @@ impl Resources {
         Ok(())
     }
 
+    /// Ensure the global instance of `T` exists. If it is missing, the instance is constructed from the
+    /// other resources and the type is registered as unmanaged if it has not been registered yet.
+    /// It returns if a new instance was created.
+    pub fn init_resource<T: FromResources>(&mut self) -> Result<bool, ECSError> {
+        let registered = match self.get_store::<T>() {
+            Some(store) if store.exists(&ResourceId::Global) => return Ok(false),
+            Some(_) => true,
+            None => false,
+        };
+
+        log::debug!("Initializing resource {}", type_name::<T>());
+        let value = T::from_resources(self)?;
+        if registered {
+            self.insert_with_id(ResourceId::Global, value)?;
+        } else {
+            self.register_with_instance(value)?;
+        }
+        Ok(true)
+    }
+
     /// Unregister and release all the resources of the given type. This operation also invalidates
     /// all the handles. The other type of references and accessors
     /// are not effected as they should not exist by the design of the API.
@@ -284,11 +352,99 @@ impl Resources {
         self.try_at_mut(handle).unwrap()
     }
 
+    /// Return the ids of the instances of a resource type in a deterministic order.
+    pub fn ids<T: Resource>(&self) -> Result<Vec<ResourceId>, ECSError> {
+        Ok(self
+            .get_store::<T>()
+            .ok_or_else(|| ECSError::ResourceTypeNotFound(type_name::<T>().into()))?
+            .ids())
+    }
+
+    /// Feed the instances of a resource type into the hasher in a deterministic order.
+    /// Comparing the hash of the state per frame helps to track down the diverging systems in lockstep
+    /// simulations and replays.
+    pub fn hash_resources<T: Resource + Hash, H: Hasher>(&self, state: &mut H) -> Result<(), ECSError> {
+        let store = self
+            .get_store::<T>()
+            .ok_or_else(|| ECSError::ResourceTypeNotFound(type_name::<T>().into()))?;
+        for id in store.ids() {
+            id.hash(state);
+            store.get_with_id(&id)?.hash(state);
+        }
+        Ok(())
+    }
+
     pub fn bake<T: Resource>(&self, gc: bool) {
         if let Some(mut store) = self.get_store_mut::<T>() {
             store.bake(gc);
         }
     }
+
+    fn add_scope_release<S: ResourceScope, F: 'static + FnOnce(&mut Resources)>(&mut self, release: F) {
+        self.scopes
+            .entry(TypeId::of::<S>())
+            .or_default()
+            .push(Box::new(release));
+    }
+
+    /// Register a new type of resource that is unregistered when the scope is released.
+    pub fn register_scoped<S, T, TC>(&mut self, _scope: S, config: TC) -> Result<(), ECSError>
+    where
+        S: ResourceScope,
+        T: Resource,
+        TC: 'static + ResourceConfig<Resource = T>,
+    {
+        self.register::<T, TC>(config)?;
+        self.add_scope_release::<S, _>(|resources| resources.unregister::<T>());
+        Ok(())
+    }
+
+    /// Register an unmanaged resource with the given instance that is unregistered when the scope is released.
+    pub fn register_with_instance_scoped<S: ResourceScope, T: Resource>(
+        &mut self,
+        _scope: S,
+        value: T,
+    ) -> Result<(), ECSError> {
+        self.register_with_instance(value)?;
+        self.add_scope_release::<S, _>(|resources| resources.unregister::<T>());
+        Ok(())
+    }
+
+    /// Inserts a new instance of `T` with the given id into a registered store. The instance is removed when
+    /// the scope is released, the registration of the type is not effected.
+    pub fn insert_scoped_with_id<S: ResourceScope, T: Resource>(
+        &mut self,
+        _scope: S,
+        id: ResourceId,
+        value: T,
+    ) -> Result<Option<T>, ECSError> {
+        let old = self.insert_with_id(id.clone(), value)?;
+        self.add_scope_release::<S, _>(move |resources| {
+            let _ = resources.remove_with_id::<T>(&id);
+        });
+        Ok(old)
+    }
+
+    /// Inserts the instance of `T` into a registered store. The instance is removed when
+    /// the scope is released, the registration of the type is not effected.
+    pub fn insert_scoped<S: ResourceScope, T: Resource>(&mut self, scope: S, value: T) -> Result<Option<T>, ECSError> {
+        self.insert_scoped_with_id(scope, ResourceId::Global, value)
+    }
+
+    /// Check if there are any resources bound to the scope.
+    pub fn has_scope<S: ResourceScope>(&self) -> bool {
+        self.scopes.contains_key(&TypeId::of::<S>())
+    }
+
+    /// Release all the resources of the scope in the reverse order of the registration.
+    pub fn release_scope<S: ResourceScope>(&mut self) {
+        if let Some(releases) = self.scopes.remove(&TypeId::of::<S>()) {
+            log::debug!("Releasing resource scope {}", type_name::<S>());
+            for release in releases.into_iter().rev() {
+                release(self);
+            }
+        }
+    }
 }
 
 /// Accessor for resources which are Send and Sync and can be sent
@@ -389,6 +545,14 @@ impl<'a> SyncResources<'a> {
         self.try_at_mut(handle).unwrap()
     }
 
+    /// Return the ids of the instances of a resource type in a deterministic order.
+    pub fn ids<T: Resource + Sync + Send>(&self) -> Result<Vec<ResourceId>, ECSError> {
+        Ok(self
+            .get_store::<T>()
+            .ok_or_else(|| ECSError::ResourceTypeNotFound(type_name::<T>().into()))?
+            .ids())
+    }
+
     pub fn bake<T: Resource + Sync + Send>(&self, gc: bool) {
         if let Some(mut store) = self.get_store_mut::<T>() {
             store.bake(gc);
@@ -84,6 +84,8 @@ macro_rules! impl_into_system {
                 $sys {
                     debug_name: any::type_name::<Func>().into(),
                     name: None,
+                    after: Vec::new(),
+                    before: Vec::new(),
                     resource_queries: Default::default(),
                     resource_claims: None,
                     func: self
@@ -102,6 +104,8 @@ macro_rules! impl_into_system {
         {
             debug_name: Cow<'static, str>,
             name: Option<SystemName>,
+            after: Vec<SystemName>,
+            before: Vec<SystemName>,
             resource_queries: hlist_type![$(<$resource as ResourceAccess>::Query,)*],
             resource_claims: Option<ResourceClaims>,
             func: Func,
@@ -119,6 +123,18 @@ macro_rules! impl_into_system {
                 self.name = name;
                 self
             }
+
+            /// Run the system after the named system of the stage has completed
+            pub fn run_after(mut self, name: SystemName) -> Self {
+                self.after.push(name);
+                self
+            }
+
+            /// Run the system before the named system of the stage
+            pub fn run_before(mut self, name: SystemName) -> Self {
+                self.before.push(name);
+                self
+            }
         }
 
         impl<HIndex, Func, $($resource,)*> WithMultiRes<hlist_type![$(<$resource as ResourceAccess>::Query,)*], HIndex>
@@ -209,6 +225,14 @@ macro_rules! impl_into_system {
                 self.name.as_ref()
             }
 
+            fn after(&self) -> &[SystemName] {
+                &self.after
+            }
+
+            fn before(&self) -> &[SystemName] {
+                &self.before
+            }
+
             fn resource_claims(&mut self) -> Result<&ResourceClaims, ECSError> {
                 let resource_queries = &self.resource_queries;
                 Ok(self.resource_claims.get_or_insert_with(|| {
@@ -6,6 +6,8 @@ mod task_group;
 pub use self::task_group::*;
 mod scheduler;
 pub use self::scheduler::*;
+mod scheduler_debug;
+pub use self::scheduler_debug::*;
 
 mod resource_claim;
 pub use self::resource_claim::*;
@@ -1,8 +1,10 @@
-use crate::resources::{MultiResMutQuery, MultiResQuery, ResMutQuery, ResQuery, Resource, ResourceId};
+use crate::resources::{
+    AddedQuery, ChangedQuery, MultiResMutQuery, MultiResQuery, ResMutQuery, ResQuery, Resource, ResourceId,
+};
 use std::{any::TypeId, collections::HashSet};
 
 /// Shared an unique resource requests
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct ResourceClaims {
     all_immutable: HashSet<(TypeId, ResourceId)>,
     all_mutable: HashSet<(TypeId, ResourceId)>,
@@ -43,6 +45,21 @@ impl ResourceClaims {
     pub fn add_claim<C: ResourceClaim>(&mut self, claim: &C) {
         claim.add_claim(self);
     }
+
+    /// Check if the two claims prohibit the concurrent execution of the systems, that is a resource is
+    /// claimed mutably by one and claimed (either way) by the other.
+    pub fn is_conflicting(&self, other: &ResourceClaims) -> bool {
+        self.all_mutable
+            .iter()
+            .any(|idx| other.all_mutable.contains(idx) || other.all_immutable.contains(idx))
+            || other.all_mutable.iter().any(|idx| self.all_immutable.contains(idx))
+    }
+
+    /// Check if a resource is claimed. A mutable claim also grants immutable access.
+    pub fn is_claimed(&self, ty: TypeId, id: &ResourceId, mutable: bool) -> bool {
+        let idx = (ty, id.clone());
+        self.all_mutable.contains(&idx) || (!mutable && self.all_immutable.contains(&idx))
+    }
 }
 
 pub trait ResourceClaim {
@@ -72,3 +89,15 @@ impl<T: Resource> ResourceClaim for MultiResMutQuery<T> {
         claims.add_mutable::<T, _>(self.iter().cloned())
     }
 }
+
+impl<T: Resource> ResourceClaim for AddedQuery<T> {
+    fn add_claim(&self, claims: &mut ResourceClaims) {
+        claims.add_immutable::<T, _>(Some(ResourceId::Global))
+    }
+}
+
+impl<T: Resource> ResourceClaim for ChangedQuery<T> {
+    fn add_claim(&self, claims: &mut ResourceClaims) {
+        claims.add_immutable::<T, _>(Some(ResourceId::Global))
+    }
+}
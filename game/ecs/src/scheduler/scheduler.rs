@@ -1,26 +1,469 @@
-use crate::{core::finally, resources::Resources, scheduler::TaskGroup, ECSError};
+use crate::{
+    core::finally,
+    resources::{BorrowContextGuard, Resources},
+    scheduler::{ResourceClaims, SchedulerDebug, StepMode, System, SystemName, SystemTiming, TaskGroup, TaskItem},
+    ECSError,
+};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 /// A collection of systems.
 /// Schedules are essentially the "execution plan" for an App's systems.
 /// They are run on a given [World] and [Resources] reference.
 #[derive(Default)]
-pub struct Scheduler {}
+pub struct Scheduler {
+    claim_check: bool,
+    parallel: bool,
+    debug: Option<SchedulerDebug>,
+    stage_infos: HashMap<String, StageInfo>,
+}
 
 impl Scheduler {
+    /// Enable the validation of the resource accesses against the claims of the systems. Accessing a
+    /// resource that is not claimed by the running system results in an error.
+    /// The check is performed only in debug builds.
+    pub fn set_claim_check(&mut self, enable: bool) {
+        self.claim_check = enable;
+    }
+
+    /// Enable the parallel execution of the systems with non-conflicting resource claims. Without the
+    /// `native` feature the systems are run in order on the calling thread.
+    /// # Safety
+    ///  The resources are accessed from the worker threads, the caller has to ensure that the systems access
+    ///  only resources that are Send and Sync.
+    pub unsafe fn set_parallel(&mut self, enable: bool) {
+        // SAFETY:
+        //  Only the flag is set here. The resources are shared with the worker threads in `run_batch` and
+        //  it is sound only by the guarantee of the caller documented above.
+        self.parallel = enable;
+    }
+
+    pub fn is_parallel(&self) -> bool {
+        self.parallel
+    }
+
+    /// Enable the debug mode. In debug mode the execution can be paused and stepped and
+    /// the wall time of the systems is collected.
+    pub fn set_debug(&mut self, enable: bool) {
+        if !enable {
+            self.debug = None;
+        } else if self.debug.is_none() {
+            self.debug = Some(SchedulerDebug::default());
+        }
+    }
+
+    pub fn is_debug(&self) -> bool {
+        self.debug.is_some()
+    }
+
+    /// Set the execution mode, debug mode is enabled if it is required by the mode.
+    pub fn set_step_mode(&mut self, mode: StepMode) {
+        if mode != StepMode::Continuous {
+            self.set_debug(true);
+        }
+        if let Some(debug) = &mut self.debug {
+            log::debug!("Scheduler step mode: {:?}", mode);
+            debug.mode = mode;
+        }
+    }
+
+    pub fn step_mode(&self) -> StepMode {
+        self.debug.as_ref().map(|debug| debug.mode).unwrap_or_default()
+    }
+
+    pub fn pause(&mut self) {
+        self.set_step_mode(StepMode::Paused);
+    }
+
+    pub fn resume(&mut self) {
+        self.set_step_mode(StepMode::Continuous);
+    }
+
+    pub fn step_stage(&mut self) {
+        self.set_step_mode(StepMode::StepStage);
+    }
+
+    pub fn step_system(&mut self) {
+        self.set_step_mode(StepMode::StepSystem);
+    }
+
+    /// Iterate the wall time of the systems collected in debug mode
+    pub fn system_timings(&self) -> impl Iterator<Item = (&str, &SystemTiming)> {
+        self.debug
+            .iter()
+            .flat_map(|debug| debug.timings.iter().map(|(name, timing)| (name.as_str(), timing)))
+    }
+
+    pub fn reset_system_timings(&mut self) {
+        if let Some(debug) = &mut self.debug {
+            debug.timings.clear();
+        }
+    }
+
+    /// Log the wall time of the systems, sorted by the total time
+    pub fn log_system_timings(&self) {
+        let mut timings = self.system_timings().collect::<Vec<_>>();
+        timings.sort_by(|a, b| b.1.total.cmp(&a.1.total));
+        for (name, timing) in timings {
+            log::info!(
+                "{}: count: {}, last: {:?}, avg: {:?}, min: {:?}, max: {:?}",
+                name,
+                timing.count,
+                timing.last,
+                timing.average(),
+                timing.min,
+                timing.max
+            );
+        }
+    }
+
     pub fn run(&mut self, resources: &Resources, tasks: &TaskGroup) -> Result<(), ECSError> {
-        //todo: it is a very draft, no dependency is checked and performs a lot of clone.
-        let mut tasks = tasks.iter().rev().cloned().collect::<Vec<_>>();
-        while let Some(task) = tasks.pop() {
-            task.lock()?;
-            let new_tasks = {
-                let _unlock_guard = finally(|| task.unlock());
-                // safety:
-                //  task.lock and _unlock_guard ansures the task can be executed
-                let system = unsafe { task.system() };
-                system.run(resources)?
+        self.run_stage("", resources, tasks)
+    }
+
+    /// Run the tasks of a stage. The execution order is deterministic: the tasks are run in the order they were
+    /// added to the group and the tasks spawned by a system are run right after the system (depth first).
+    /// The explicit dependencies (`after`, `before`) of the named systems are respected within the stage.
+    /// In parallel mode the non-conflicting systems are run concurrently, the systems with conflicting
+    /// claims are run in the same order as in serial mode.
+    /// In debug mode the stepping of the systems is tracked by the name of the stage.
+    pub fn run_stage(&mut self, stage: &str, resources: &Resources, tasks: &TaskGroup) -> Result<(), ECSError> {
+        let (mode, pending) = match &mut self.debug {
+            None => (StepMode::Continuous, None),
+            Some(debug) => (debug.mode, debug.pending.remove(stage)),
+        };
+        let stepped = pending.is_some() || mode == StepMode::StepSystem;
+        let parallel = self.parallel && mode != StepMode::StepSystem;
+
+        // a partially executed stage is completed before the stage is started again
+        let (mut tasks, mut infos) = match pending {
+            Some(tasks) => {
+                let infos = tasks
+                    .iter()
+                    .map(|task| TaskInfo::new(task, parallel).map(Arc::new))
+                    .collect::<Result<Vec<_>, _>>()?;
+                (tasks, infos)
+            }
+            None => {
+                let infos = self.stage_infos(stage, tasks, parallel)?.to_vec();
+                (tasks.iter().rev().cloned().collect::<Vec<_>>(), infos)
+            }
+        };
+        match mode {
+            StepMode::Paused => {}
+            StepMode::Continuous | StepMode::StepStage => {
+                while !tasks.is_empty() {
+                    self.run_next(resources, parallel, &mut tasks, &mut infos)?;
+                }
+            }
+            StepMode::StepSystem => {
+                if !tasks.is_empty() {
+                    self.run_next(resources, false, &mut tasks, &mut infos)?;
+                }
+            }
+        }
+
+        if let Some(debug) = &mut self.debug {
+            if mode == StepMode::StepStage || mode == StepMode::StepSystem {
+                debug.mode = StepMode::Paused;
+            }
+            // keep the remaining tasks of a partially executed stage for the next step
+            if stepped && !tasks.is_empty() && (mode == StepMode::Paused || mode == StepMode::StepSystem) {
+                let _ = debug.pending.insert(stage.to_owned(), tasks);
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the scheduling information of the tasks of a stage in the reverse order of the execution.
+    /// The information is collected only when the tasks of the stage or the execution mode have changed.
+    fn stage_infos(&mut self, stage: &str, tasks: &TaskGroup, parallel: bool) -> Result<&[Arc<TaskInfo>], ECSError> {
+        let is_valid = self
+            .stage_infos
+            .get(stage)
+            .map(|info| info.is_valid(tasks, parallel))
+            .unwrap_or(false);
+        if !is_valid {
+            log::debug!("Collecting the scheduling information of stage [{}]", stage);
+            let infos = tasks
+                .iter()
+                .rev()
+                .map(|task| TaskInfo::new(task, parallel).map(Arc::new))
+                .collect::<Result<Vec<_>, _>>()?;
+            let info = StageInfo {
+                tasks: tasks.to_vec(),
+                parallel,
+                infos,
             };
-            tasks.extend(new_tasks.iter().rev().cloned());
+            let _ = self.stage_infos.insert(stage.to_owned(), info);
+        }
+        Ok(&self.stage_infos[stage].infos)
+    }
+
+    /// Run the next task (or tasks in parallel mode) of the pending tasks stored in the reverse order of the
+    /// execution. The spawned tasks are added to the front of the pending tasks.
+    fn run_next(
+        &mut self,
+        resources: &Resources,
+        parallel: bool,
+        tasks: &mut Vec<TaskItem>,
+        infos: &mut Vec<Arc<TaskInfo>>,
+    ) -> Result<(), ECSError> {
+        let mut selected = select_tasks(infos, parallel)?;
+        // remove from the back to keep the indices valid, the order of execution is reversed
+        selected.sort_unstable_by(|a, b| b.cmp(a));
+        let batch = selected
+            .into_iter()
+            .map(|idx| {
+                infos.remove(idx);
+                tasks.remove(idx)
+            })
+            .collect::<Vec<_>>();
+
+        let new_tasks = if batch.len() == 1 {
+            vec![self.run_task(resources, &batch[0])?]
+        } else {
+            self.run_batch(resources, &batch)?
+        };
+
+        // spawned tasks of the first system of the batch are run first
+        // the spawned tasks may change their claims before they are returned (ex. a render pass with a new
+        // render state), thus their information is collected each time they are spawned
+        for new_tasks in new_tasks.iter().rev() {
+            for task in new_tasks.iter().rev() {
+                infos.push(Arc::new(TaskInfo::new(task, parallel)?));
+                tasks.push(task.clone());
+            }
         }
         Ok(())
     }
+
+    fn run_task(&mut self, resources: &Resources, task: &TaskItem) -> Result<TaskGroup, ECSError> {
+        task.lock()?;
+        let _unlock_guard = finally(|| task.unlock());
+        // safety:
+        //  task.lock and _unlock_guard ansures the task can be executed
+        let system = unsafe { task.system() };
+        let (new_tasks, time) = run_system(system, resources, self.claim_check);
+        if let Some(debug) = &mut self.debug {
+            debug
+                .timings
+                .entry(system.debug_name().to_owned())
+                .or_default()
+                .add(time);
+        }
+        new_tasks
+    }
+
+    /// Run the non-conflicting tasks concurrently on the worker threads
+    #[cfg(feature = "native")]
+    fn run_batch(&mut self, resources: &Resources, batch: &[TaskItem]) -> Result<Vec<TaskGroup>, ECSError> {
+        use rayon::prelude::*;
+
+        for (locked, task) in batch.iter().enumerate() {
+            if let Err(err) = task.lock() {
+                batch[..locked].iter().for_each(|task| task.unlock());
+                return Err(err);
+            }
+        }
+        let _unlock_guard = finally(|| batch.iter().for_each(|task| task.unlock()));
+
+        // safety:
+        //  the tasks are locked, the tasks of a batch are distinct
+        let mut systems = batch.iter().map(|task| unsafe { task.system() }).collect::<Vec<_>>();
+        let shared = SharedResources(resources);
+        let claim_check = self.claim_check;
+        let results = systems
+            .par_iter_mut()
+            .map(|system| run_system(&mut **system, shared.0, claim_check))
+            .collect::<Vec<_>>();
+
+        let mut new_tasks = Vec::with_capacity(results.len());
+        for (system, (result, time)) in systems.iter().zip(results) {
+            if let Some(debug) = &mut self.debug {
+                debug
+                    .timings
+                    .entry(system.debug_name().to_owned())
+                    .or_default()
+                    .add(time);
+            }
+            new_tasks.push(result?);
+        }
+        Ok(new_tasks)
+    }
+
+    /// Without the worker threads the tasks of a batch are run in order
+    #[cfg(not(feature = "native"))]
+    fn run_batch(&mut self, resources: &Resources, batch: &[TaskItem]) -> Result<Vec<TaskGroup>, ECSError> {
+        batch.iter().map(|task| self.run_task(resources, task)).collect()
+    }
+}
+
+/// Run a locked system, returns the spawned tasks and the wall time of the system
+fn run_system(
+    system: &mut dyn System,
+    resources: &Resources,
+    claim_check: bool,
+) -> (Result<TaskGroup, ECSError>, Duration) {
+    let claims = if cfg!(debug_assertions) && claim_check {
+        match system.resource_claims() {
+            Ok(claims) => Some(claims.clone()),
+            Err(err) => return (Err(err), Duration::default()),
+        }
+    } else {
+        None
+    };
+    let _borrow_context = BorrowContextGuard::enter(system.debug_name(), claims);
+
+    let start = Instant::now();
+    let new_tasks = system.run(resources);
+    let time = start.elapsed();
+    log::trace!("System {} completed in {:?}", system.debug_name(), time);
+    resources.increment_change_tick();
+    (new_tasks, time)
+}
+
+/// Resources shared with the worker threads.
+/// # Safety
+///  Resources is !Sync as it may store types that are not thread safe. Parallel execution is enabled by the
+///  unsafe `Scheduler::set_parallel` whose caller ensures that the systems access only Send + Sync resources.
+#[cfg(feature = "native")]
+struct SharedResources<'a>(&'a Resources);
+
+// SAFETY:
+//  The resources are accessed only by the systems of a batch, that run with non-conflicting claims, thus a
+//  resource is never borrowed mutably on more than one thread. The caller of the unsafe `set_parallel` ensures
+//  that the systems access only Send + Sync resources, thus the resources may be used from any worker thread.
+#[cfg(feature = "native")]
+unsafe impl<'a> Sync for SharedResources<'a> {}
+
+/// Scheduling information of the tasks of a stage
+struct StageInfo {
+    /// The tasks the information was collected for. The tasks are kept alive so the address of a task cannot
+    /// be reused by another one while the information is cached.
+    tasks: Vec<TaskItem>,
+    parallel: bool,
+    infos: Vec<Arc<TaskInfo>>,
+}
+
+impl StageInfo {
+    fn is_valid(&self, tasks: &TaskGroup, parallel: bool) -> bool {
+        self.parallel == parallel
+            && self.tasks.len() == tasks.len()
+            && self
+                .tasks
+                .iter()
+                .zip(tasks.iter())
+                .all(|(cached, task)| task_ptr(cached) == task_ptr(task))
+    }
+}
+
+fn task_ptr(task: &TaskItem) -> *const u8 {
+    Arc::as_ptr(&**task) as *const u8
+}
+
+/// Scheduling information of a pending task.
+/// The information is collected when the tasks of a stage change or when a task is spawned and it is reused
+/// while the stage is not changed. Thus the name, the dependencies and the claims of a system shall not change
+/// once it is added to a scheduled stage. The claims are collected only in parallel mode, as the serial
+/// execution does not check for conflicts.
+struct TaskInfo {
+    task: *const u8,
+    debug_name: String,
+    name: Option<SystemName>,
+    after: Vec<SystemName>,
+    before: Vec<SystemName>,
+    claims: Option<ResourceClaims>,
+}
+
+impl TaskInfo {
+    fn new(task: &TaskItem, with_claims: bool) -> Result<TaskInfo, ECSError> {
+        task.lock()?;
+        let _unlock_guard = finally(|| task.unlock());
+        // safety:
+        //  task.lock and _unlock_guard ansures the system can be accessed
+        let system = unsafe { task.system() };
+        let claims = if with_claims {
+            Some(system.resource_claims()?.clone())
+        } else {
+            None
+        };
+        Ok(TaskInfo {
+            task: task_ptr(task),
+            debug_name: system.debug_name().to_owned(),
+            name: system.name().cloned(),
+            after: system.after().to_vec(),
+            before: system.before().to_vec(),
+            claims,
+        })
+    }
+
+    /// Check if this task has to wait for the completion of the other task by an explicit dependency
+    fn depends_on(&self, other: &TaskInfo) -> bool {
+        other
+            .name
+            .as_ref()
+            .map(|name| self.after.contains(name))
+            .unwrap_or(false)
+            || self
+                .name
+                .as_ref()
+                .map(|name| other.before.contains(name))
+                .unwrap_or(false)
+    }
+
+    /// Check if the two tasks cannot be run concurrently
+    fn is_conflicting(&self, other: &TaskInfo) -> bool {
+        if self.task == other.task {
+            return true;
+        }
+        match (&self.claims, &other.claims) {
+            (Some(claims), Some(other_claims)) => claims.is_conflicting(other_claims),
+            _ => true,
+        }
+    }
+}
+
+/// Select the indices of the tasks to run next from the pending tasks (stored in the reverse order of the
+/// execution). A task is not selected while it depends on any other pending task. The first ready task is
+/// always selected as in serial mode. In parallel mode the other ready tasks are also selected if they are not
+/// in conflict with a selected or with any preceding (ready or waiting) task, thus the conflicting tasks are
+/// never run before a preceding task.
+fn select_tasks(infos: &[Arc<TaskInfo>], parallel: bool) -> Result<Vec<usize>, ECSError> {
+    let mut selected = Vec::new();
+    let mut skipped = Vec::new();
+    for (idx, info) in infos.iter().enumerate().rev() {
+        let waiting = infos
+            .iter()
+            .enumerate()
+            .any(|(other, other_info)| other != idx && info.depends_on(other_info));
+        if waiting {
+            skipped.push(idx);
+            continue;
+        }
+        let blocked = parallel
+            && !selected.is_empty()
+            && selected
+                .iter()
+                .chain(skipped.iter())
+                .any(|&other: &usize| info.is_conflicting(&infos[other]));
+        if blocked {
+            skipped.push(idx);
+        } else {
+            selected.push(idx);
+            if !parallel {
+                break;
+            }
+        }
+    }
+
+    if selected.is_empty() {
+        let names = infos.iter().rev().map(|info| info.debug_name.clone()).collect();
+        Err(ECSError::SystemDependencyCycle(names))
+    } else {
+        Ok(selected)
+    }
 }
@@ -0,0 +1,62 @@
+use crate::scheduler::TaskItem;
+use std::{collections::HashMap, time::Duration};
+
+/// Execution mode of the scheduler for debugging
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepMode {
+    /// Execute every stage as usual
+    Continuous,
+    /// Skip the execution of the stages
+    Paused,
+    /// Execute the next stage (or the rest of a stepped stage), then pause
+    StepStage,
+    /// Execute the next system, then pause
+    StepSystem,
+}
+
+impl Default for StepMode {
+    fn default() -> Self {
+        StepMode::Continuous
+    }
+}
+
+/// Wall time statistics of a system
+#[derive(Debug, Clone, Default)]
+pub struct SystemTiming {
+    pub count: usize,
+    pub last: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    pub total: Duration,
+}
+
+impl SystemTiming {
+    pub fn add(&mut self, time: Duration) {
+        if self.count == 0 || time < self.min {
+            self.min = time;
+        }
+        if time > self.max {
+            self.max = time;
+        }
+        self.count += 1;
+        self.last = time;
+        self.total += time;
+    }
+
+    pub fn average(&self) -> Duration {
+        if self.count == 0 {
+            Duration::default()
+        } else {
+            self.total / self.count as u32
+        }
+    }
+}
+
+/// State of the scheduler in debug mode
+#[derive(Default)]
+pub(crate) struct SchedulerDebug {
+    pub mode: StepMode,
+    /// The remaining tasks of the partially executed (stepped) stages
+    pub pending: HashMap<String, Vec<TaskItem>>,
+    pub timings: HashMap<String, SystemTiming>,
+}
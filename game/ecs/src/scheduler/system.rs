@@ -14,8 +14,16 @@ pub trait System: Send + Sync {
 
     /// Name of the system to create explicit dependencies
     fn name(&self) -> Option<&SystemName>;
-    // Explicit dependency, those must complete before this system
-    //fn dependencies(&self) -> &Vec<SystemName>;
+
+    /// Explicit dependency, the named systems of the stage must complete before this system
+    fn after(&self) -> &[SystemName] {
+        &[]
+    }
+
+    /// Explicit dependency, the named systems of the stage must wait for the completion of this system
+    fn before(&self) -> &[SystemName] {
+        &[]
+    }
 
     /// Collect and return resources claims.  
     fn resource_claims(&mut self) -> Result<&ResourceClaims, ECSError>;
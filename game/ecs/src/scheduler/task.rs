@@ -15,6 +15,10 @@ pub struct Task<S: System> {
     lock: RWToken,
 }
 
+// safety:
+//  the system is accessed only through the lock, thus a task can be shared with the worker threads
+unsafe impl<S: System> Sync for Task<S> {}
+
 impl<S: System> Task<S> {
     pub fn new(system: S) -> Arc<Task<S>> {
         Arc::new(Task {
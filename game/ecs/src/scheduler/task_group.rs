@@ -4,7 +4,7 @@ use crate::{
 };
 use std::{ops::Deref, sync::Arc};
 
-pub trait Runnable {
+pub trait Runnable: Send + Sync {
     /// Prepare and lock for run
     fn lock(&self) -> Result<(), ECSError>;
     /// Get the system to run.
@@ -0,0 +1,6 @@
+mod snapshot;
+pub use self::snapshot::*;
+mod snapshot_registry;
+pub use self::snapshot_registry::*;
+mod snapshot_history;
+pub use self::snapshot_history::*;
@@ -0,0 +1,110 @@
+use crate::{resources::ResourceId, ECSError};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{borrow::Cow, sync::Arc};
+
+/// Serialization format of the snapshots. Bincode is compact for the rollback and network states, RON is
+/// human readable for the save games and debugging.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SnapshotFormat {
+    Bincode,
+    Ron,
+}
+
+/// A serialized value in the format of the snapshot
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SnapshotValue {
+    Binary(Vec<u8>),
+    Text(String),
+}
+
+impl SnapshotValue {
+    pub fn encode<T: Serialize>(format: SnapshotFormat, type_name: &str, value: &T) -> Result<SnapshotValue, ECSError> {
+        let type_name = Cow::Owned(type_name.to_owned());
+        match format {
+            SnapshotFormat::Bincode => bincode::serialize(value)
+                .map(SnapshotValue::Binary)
+                .map_err(|err| ECSError::SnapshotSerialize(type_name, Arc::new(err))),
+            SnapshotFormat::Ron => ron::ser::to_string(value)
+                .map(SnapshotValue::Text)
+                .map_err(|err| ECSError::SnapshotSerialize(type_name, Arc::new(err))),
+        }
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self, type_name: &str) -> Result<T, ECSError> {
+        let type_name = Cow::Owned(type_name.to_owned());
+        match self {
+            SnapshotValue::Binary(data) => {
+                bincode::deserialize(data).map_err(|err| ECSError::SnapshotDeserialize(type_name, Arc::new(err)))
+            }
+            SnapshotValue::Text(data) => {
+                ron::de::from_str(data).map_err(|err| ECSError::SnapshotDeserialize(type_name, Arc::new(err)))
+            }
+        }
+    }
+}
+
+/// The instances of a resource type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourceSnapshot {
+    pub name: String,
+    pub instances: Vec<(ResourceId, SnapshotValue)>,
+}
+
+/// The components of a type, the entities are referenced by their index in the snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComponentSnapshot {
+    pub name: String,
+    pub values: Vec<(u32, SnapshotValue)>,
+}
+
+/// The entities with the registered components and the hierarchy
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EntitySnapshot {
+    /// The bits of the entities at the time of the snapshot
+    pub entities: Vec<u64>,
+    pub components: Vec<ComponentSnapshot>,
+    /// (child, parent) pairs of the hierarchy
+    pub parents: Vec<(u32, u32)>,
+}
+
+/// Serialized state of the registered resources and components, see `SnapshotRegistry`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub format: SnapshotFormat,
+    pub resources: Vec<ResourceSnapshot>,
+    pub entities: Option<EntitySnapshot>,
+}
+
+impl Snapshot {
+    pub fn new(format: SnapshotFormat) -> Snapshot {
+        Snapshot {
+            format,
+            resources: Vec::new(),
+            entities: None,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ECSError> {
+        let type_name = Cow::Borrowed("Snapshot");
+        match self.format {
+            SnapshotFormat::Bincode => {
+                bincode::serialize(self).map_err(|err| ECSError::SnapshotSerialize(type_name, Arc::new(err)))
+            }
+            SnapshotFormat::Ron => ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+                .map(String::into_bytes)
+                .map_err(|err| ECSError::SnapshotSerialize(type_name, Arc::new(err))),
+        }
+    }
+
+    pub fn from_bytes(format: SnapshotFormat, data: &[u8]) -> Result<Snapshot, ECSError> {
+        let type_name = Cow::Borrowed("Snapshot");
+        match format {
+            SnapshotFormat::Bincode => {
+                bincode::deserialize(data).map_err(|err| ECSError::SnapshotDeserialize(type_name, Arc::new(err)))
+            }
+            SnapshotFormat::Ron => {
+                ron::de::from_bytes(data).map_err(|err| ECSError::SnapshotDeserialize(type_name, Arc::new(err)))
+            }
+        }
+    }
+}
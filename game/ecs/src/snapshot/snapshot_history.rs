@@ -0,0 +1,59 @@
+use crate::snapshot::Snapshot;
+use std::collections::VecDeque;
+
+/// The snapshots of the last few frames for the rollback of the networked state. When the authoritative
+/// input of a past frame arrives, the state is restored from the snapshot of that frame and the later
+/// frames are simulated again.
+pub struct SnapshotHistory {
+    capacity: usize,
+    frames: VecDeque<(u64, Snapshot)>,
+}
+
+impl SnapshotHistory {
+    pub fn new(capacity: usize) -> SnapshotHistory {
+        SnapshotHistory {
+            capacity: capacity.max(1),
+            frames: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// The oldest frame that can be rolled back to
+    pub fn oldest_frame(&self) -> Option<u64> {
+        self.frames.front().map(|(frame, _)| *frame)
+    }
+
+    /// Store the snapshot of a frame, the snapshots of the same or later frames are replaced.
+    pub fn push(&mut self, frame: u64, snapshot: Snapshot) {
+        while self.frames.back().map(|(f, _)| *f >= frame).unwrap_or(false) {
+            self.frames.pop_back();
+        }
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back((frame, snapshot));
+    }
+
+    pub fn get(&self, frame: u64) -> Option<&Snapshot> {
+        self.frames
+            .iter()
+            .find(|(f, _)| *f == frame)
+            .map(|(_, snapshot)| snapshot)
+    }
+
+    /// Drop the snapshots after the frame and return the snapshot of the frame to restore the state from.
+    pub fn rollback(&mut self, frame: u64) -> Option<&Snapshot> {
+        self.get(frame)?;
+        while self.frames.back().map(|(f, _)| *f > frame).unwrap_or(false) {
+            self.frames.pop_back();
+        }
+        self.frames.back().map(|(_, snapshot)| snapshot)
+    }
+}
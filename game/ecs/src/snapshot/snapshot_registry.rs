@@ -0,0 +1,266 @@
+use crate::{
+    core::error::ErrorString,
+    hierarchy::{Entities, Entity, Hierarchy},
+    resources::{Resource, ResourceId, Resources},
+    snapshot::{ComponentSnapshot, EntitySnapshot, ResourceSnapshot, Snapshot, SnapshotFormat, SnapshotValue},
+    ECSError,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{any::type_name, collections::HashMap, sync::Arc};
+
+type SaveResourceFn = fn(&Resources, SnapshotFormat, &str) -> Result<Vec<(ResourceId, SnapshotValue)>, ECSError>;
+type RestoreResourceFn = fn(&mut Resources, &[(ResourceId, SnapshotValue)], &str) -> Result<(), ECSError>;
+type SaveComponentFn =
+    fn(&Entities, &HashMap<Entity, u32>, SnapshotFormat, &str) -> Result<Vec<(u32, SnapshotValue)>, ECSError>;
+type RestoreComponentFn = fn(&mut Entities, &[Entity], &[(u32, SnapshotValue)], &str) -> Result<(), ECSError>;
+
+fn invalid_entity_index(name: &str, index: u32) -> ECSError {
+    ECSError::SnapshotDeserialize(
+        name.to_owned().into(),
+        Arc::new(ErrorString(format!("Invalid entity index: {}", index))),
+    )
+}
+
+struct ResourceReflect {
+    name: String,
+    save: SaveResourceFn,
+    restore: RestoreResourceFn,
+}
+
+struct ComponentReflect {
+    name: String,
+    save: SaveComponentFn,
+    restore: RestoreComponentFn,
+}
+
+fn save_resource<T>(
+    resources: &Resources,
+    format: SnapshotFormat,
+    name: &str,
+) -> Result<Vec<(ResourceId, SnapshotValue)>, ECSError>
+where
+    T: Resource + Serialize,
+{
+    let store = resources
+        .get_store::<T>()
+        .ok_or_else(|| ECSError::ResourceTypeNotFound(type_name::<T>().into()))?;
+    let mut instances = Vec::new();
+    for id in store.ids() {
+        let value = store.get_with_id(&id)?;
+        instances.push((id, SnapshotValue::encode(format, name, &*value)?));
+    }
+    Ok(instances)
+}
+
+fn restore_resource<T>(
+    resources: &mut Resources,
+    instances: &[(ResourceId, SnapshotValue)],
+    name: &str,
+) -> Result<(), ECSError>
+where
+    T: Resource + DeserializeOwned,
+{
+    let existing = resources.ids::<T>()?;
+    for id in &existing {
+        if !instances.iter().any(|(snapshot_id, _)| snapshot_id == id) {
+            let _ = resources.remove_with_id::<T>(id);
+        }
+    }
+    for (id, value) in instances {
+        let value = value.decode::<T>(name)?;
+        if existing.contains(id) {
+            // update in place to keep the handles valid
+            *resources.get_mut_with_id::<T>(id)? = value;
+        } else {
+            let _ = resources.insert_with_id(id.clone(), value)?;
+        }
+    }
+    Ok(())
+}
+
+fn save_component<T>(
+    entities: &Entities,
+    indices: &HashMap<Entity, u32>,
+    format: SnapshotFormat,
+    name: &str,
+) -> Result<Vec<(u32, SnapshotValue)>, ECSError>
+where
+    T: hecs::Component + Serialize,
+{
+    let mut query = entities.query::<&T>();
+    let mut values = query
+        .iter()
+        .map(|(entity, value)| Ok((indices[&entity], SnapshotValue::encode(format, name, value)?)))
+        .collect::<Result<Vec<_>, ECSError>>()?;
+    values.sort_by_key(|(index, _)| *index);
+    Ok(values)
+}
+
+fn restore_component<T>(
+    entities: &mut Entities,
+    spawned: &[Entity],
+    values: &[(u32, SnapshotValue)],
+    name: &str,
+) -> Result<(), ECSError>
+where
+    T: hecs::Component + DeserializeOwned,
+{
+    for (index, value) in values {
+        let entity = *spawned
+            .get(*index as usize)
+            .ok_or_else(|| invalid_entity_index(name, *index))?;
+        let value = value.decode::<T>(name)?;
+        entities
+            .insert_one(entity, value)
+            .map_err(|_| ECSError::EntityNotFound(entity))?;
+    }
+    Ok(())
+}
+
+/// Mapping of the entities of a snapshot to the restored entities
+pub type EntityMap = HashMap<Entity, Entity>;
+
+/// Registry of the serializable resource and component types used to take and restore snapshots of the world,
+/// ex. for the save games or for the rollback of the networked state. The types are identified by the
+/// registered name, thus the types can be renamed in the code without breaking the existing snapshots.
+/// The hierarchy (`Parent`, `Children`) of the entities is always part of the snapshot.
+#[derive(Default)]
+pub struct SnapshotRegistry {
+    resources: Vec<ResourceReflect>,
+    components: Vec<ComponentReflect>,
+}
+
+impl SnapshotRegistry {
+    pub fn new() -> SnapshotRegistry {
+        SnapshotRegistry::default()
+    }
+
+    /// Register a resource type, all the instances of the type are part of the snapshot
+    pub fn register_resource<T>(&mut self, name: &str) -> &mut Self
+    where
+        T: Resource + Serialize + DeserializeOwned,
+    {
+        assert!(
+            !self.resources.iter().any(|r| r.name == name),
+            "Resource {} already registered",
+            name
+        );
+        self.resources.push(ResourceReflect {
+            name: name.to_owned(),
+            save: save_resource::<T>,
+            restore: restore_resource::<T>,
+        });
+        self
+    }
+
+    /// Register a component type of the `Entities`
+    pub fn register_component<T>(&mut self, name: &str) -> &mut Self
+    where
+        T: hecs::Component + Serialize + DeserializeOwned,
+    {
+        assert!(
+            !self.components.iter().any(|c| c.name == name),
+            "Component {} already registered",
+            name
+        );
+        self.components.push(ComponentReflect {
+            name: name.to_owned(),
+            save: save_component::<T>,
+            restore: restore_component::<T>,
+        });
+        self
+    }
+
+    /// Take a snapshot of the registered resources and of the components of the `Entities` resource (if present).
+    pub fn snapshot(&self, resources: &Resources, format: SnapshotFormat) -> Result<Snapshot, ECSError> {
+        let mut snapshot = Snapshot::new(format);
+        for reflect in &self.resources {
+            snapshot.resources.push(ResourceSnapshot {
+                name: reflect.name.clone(),
+                instances: (reflect.save)(resources, format, &reflect.name)?,
+            });
+        }
+
+        if self.components.is_empty() {
+            return Ok(snapshot);
+        }
+        if let Ok(entities) = resources.get::<Entities>() {
+            let mut list = entities.iter().map(|(entity, _)| entity).collect::<Vec<_>>();
+            list.sort_by_key(|entity| entity.to_bits());
+            let indices = list
+                .iter()
+                .enumerate()
+                .map(|(index, entity)| (*entity, index as u32))
+                .collect::<HashMap<_, _>>();
+
+            let mut entity_snapshot = EntitySnapshot {
+                entities: list.iter().map(|entity| entity.to_bits()).collect(),
+                ..Default::default()
+            };
+            for reflect in &self.components {
+                entity_snapshot.components.push(ComponentSnapshot {
+                    name: reflect.name.clone(),
+                    values: (reflect.save)(&entities, &indices, format, &reflect.name)?,
+                });
+            }
+            for (index, entity) in list.iter().enumerate() {
+                for child in entities.children(*entity) {
+                    entity_snapshot.parents.push((indices[&child], index as u32));
+                }
+            }
+            snapshot.entities = Some(entity_snapshot);
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Restore the registered resources and entities from a snapshot. The resources are updated in place,
+    /// the instances missing from the snapshot are removed. The entities are despawned and respawned, the
+    /// returned map gives the new entity for each entity of the snapshot. The types of the snapshot
+    /// that are not registered are skipped.
+    pub fn restore(&self, resources: &mut Resources, snapshot: &Snapshot) -> Result<EntityMap, ECSError> {
+        for resource in &snapshot.resources {
+            match self.resources.iter().find(|r| r.name == resource.name) {
+                Some(reflect) => (reflect.restore)(resources, &resource.instances, &reflect.name)?,
+                None => log::warn!("Resource {} of the snapshot is not registered", resource.name),
+            }
+        }
+
+        let mut entity_map = EntityMap::new();
+        if let Some(entity_snapshot) = &snapshot.entities {
+            let mut entities = resources.get_mut::<Entities>()?;
+            let existing = entities.iter().map(|(entity, _)| entity).collect::<Vec<_>>();
+            for entity in existing {
+                let _ = entities.despawn(entity);
+            }
+            let spawned = entity_snapshot
+                .entities
+                .iter()
+                .map(|bits| {
+                    let entity = entities.spawn(());
+                    entity_map.insert(Entity::from_bits(*bits), entity);
+                    entity
+                })
+                .collect::<Vec<_>>();
+
+            for component in &entity_snapshot.components {
+                match self.components.iter().find(|c| c.name == component.name) {
+                    Some(reflect) => (reflect.restore)(&mut *entities, &spawned, &component.values, &reflect.name)?,
+                    None => log::warn!("Component {} of the snapshot is not registered", component.name),
+                }
+            }
+            for (child, parent) in &entity_snapshot.parents {
+                let entity = |index: u32| {
+                    spawned
+                        .get(index as usize)
+                        .copied()
+                        .ok_or_else(|| invalid_entity_index("Parent", index))
+                };
+                let (child, parent) = (entity(*child)?, entity(*parent)?);
+                entities.attach(child, parent)?;
+            }
+        }
+
+        Ok(entity_map)
+    }
+}
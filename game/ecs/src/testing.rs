@@ -0,0 +1,237 @@
+//! Helpers to test systems without the application, render or asset plugins.
+
+use crate::{
+    resources::{Resource, ResourceRead, ResourceWrite, Resources},
+    scheduler::{Scheduler, TaskGroup, TaskItem},
+    ECSError,
+};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+type StateHasher = Box<dyn Fn(&Resources, &mut DefaultHasher) -> Result<(), ECSError>>;
+
+/// Entities of the test world, stored as a resource.
+pub type TestEntities = hecs::World;
+
+/// A minimal world to run systems and stages synchronously and check the resulting state.
+/// All the operations of the builder panic on failure, as they are intended for tests.
+///
+/// #Example
+/// ```
+/// # use shine_ecs::{ECSError, resources::*, scheduler::*, testing::*};
+/// fn inc(mut value: ResMut<usize>) -> Result<TaskGroup, ECSError> {
+///    *value += 1;
+///    Ok(TaskGroup::default())
+/// }
+///
+/// let mut world = TestWorld::new().with_resource(1usize).with_system("update", inc.into_system());
+/// world.run_stage("update").unwrap();
+/// world.assert_resource(&2usize);
+/// ```
+pub struct TestWorld {
+    resources: Resources,
+    scheduler: Scheduler,
+    stages: HashMap<String, TaskGroup>,
+    state_hashers: Vec<StateHasher>,
+}
+
+impl Default for TestWorld {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestWorld {
+    /// Create an empty world. The resource claims of the systems are validated (in debug builds).
+    pub fn new() -> TestWorld {
+        let mut scheduler = Scheduler::default();
+        scheduler.set_claim_check(true);
+        TestWorld {
+            resources: Resources::default(),
+            scheduler,
+            stages: HashMap::new(),
+            state_hashers: Vec::new(),
+        }
+    }
+
+    pub fn with_resource<T: Resource>(mut self, value: T) -> Self {
+        self.resources
+            .register_with_instance(value)
+            .unwrap_or_else(|err| panic!("Failed to add resource: {}", err));
+        self
+    }
+
+    pub fn with_tagged_resource<T: Resource>(mut self, tag: &str, value: T) -> Self {
+        if self.resources.get_store::<T>().is_none() {
+            self.resources.register_unmanaged::<T>().unwrap();
+        }
+        self.resources
+            .insert_tagged(tag, value)
+            .unwrap_or_else(|err| panic!("Failed to add resource: {}", err));
+        self
+    }
+
+    /// Spawn an entity with the given components.
+    pub fn with_entity<C: hecs::DynamicBundle>(mut self, components: C) -> Self {
+        if self.resources.get_store::<TestEntities>().is_none() {
+            self.resources.register_with_instance(TestEntities::new()).unwrap();
+        }
+        let _ = self.entities_mut().spawn(components);
+        self
+    }
+
+    /// Replace a stage
+    pub fn with_stage(mut self, stage: &str, tasks: TaskGroup) -> Self {
+        let _ = self.stages.insert(stage.to_owned(), tasks);
+        self
+    }
+
+    /// Append a system to a stage
+    pub fn with_system<T: Into<TaskItem>>(mut self, stage: &str, system: T) -> Self {
+        self.stages.entry(stage.to_owned()).or_default().add_task(system);
+        self
+    }
+
+    /// Include the instances of a resource type in the hash of the world state.
+    pub fn with_state_hash<T: Resource + Hash>(mut self) -> Self {
+        self.state_hashers
+            .push(Box::new(|resources: &Resources, state: &mut DefaultHasher| {
+                resources.hash_resources::<T, _>(state)
+            }));
+        self
+    }
+
+    pub fn resources(&self) -> &Resources {
+        &self.resources
+    }
+
+    pub fn resources_mut(&mut self) -> &mut Resources {
+        &mut self.resources
+    }
+
+    pub fn scheduler_mut(&mut self) -> &mut Scheduler {
+        &mut self.scheduler
+    }
+
+    pub fn get<T: Resource>(&self) -> ResourceRead<'_, T> {
+        self.resources
+            .get::<T>()
+            .unwrap_or_else(|err| panic!("Failed to get resource: {}", err))
+    }
+
+    pub fn get_mut<T: Resource>(&self) -> ResourceWrite<'_, T> {
+        self.resources
+            .get_mut::<T>()
+            .unwrap_or_else(|err| panic!("Failed to get resource: {}", err))
+    }
+
+    pub fn entities(&self) -> ResourceRead<'_, TestEntities> {
+        self.get::<TestEntities>()
+    }
+
+    pub fn entities_mut(&self) -> ResourceWrite<'_, TestEntities> {
+        self.get_mut::<TestEntities>()
+    }
+
+    /// Run a single system (and the tasks it spawns)
+    pub fn run_system<T: Into<TaskItem>>(&mut self, system: T) -> Result<(), ECSError> {
+        let tasks = TaskGroup::from_task(system);
+        self.scheduler.run(&self.resources, &tasks)
+    }
+
+    /// Run all the systems of a stage. Running an unknown stage panics to catch typos in the tests.
+    pub fn run_stage(&mut self, stage: &str) -> Result<(), ECSError> {
+        let tasks = self
+            .stages
+            .get(stage)
+            .unwrap_or_else(|| panic!("Unknown stage: {}", stage));
+        self.scheduler.run_stage(stage, &self.resources, tasks)
+    }
+
+    /// Run the stages in the given order for the given number of frames
+    pub fn run_frames(&mut self, stages: &[&str], count: usize) -> Result<(), ECSError> {
+        for _ in 0..count {
+            for stage in stages {
+                self.run_stage(stage)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Calculate the hash of the resources added by [with_state_hash](TestWorld::with_state_hash).
+    /// The hasher uses fixed keys, thus the hash of the same state is identical across runs.
+    pub fn state_hash(&self) -> Result<u64, ECSError> {
+        let mut state = DefaultHasher::new();
+        for hasher in &self.state_hashers {
+            hasher(&self.resources, &mut state)?;
+        }
+        Ok(state.finish())
+    }
+
+    /// Run the stages in the given order for the given number of frames and return the hash of the world
+    /// state after each frame. Comparing the hashes of two runs reveals the first frame the simulation diverged.
+    pub fn run_frames_hashed(&mut self, stages: &[&str], count: usize) -> Result<Vec<u64>, ECSError> {
+        let mut hashes = Vec::with_capacity(count);
+        for _ in 0..count {
+            self.run_frames(stages, 1)?;
+            hashes.push(self.state_hash()?);
+        }
+        Ok(hashes)
+    }
+
+    /// Check the value of a resource, on mismatch it panics with the diff of the debug representations.
+    pub fn assert_resource<T: Resource + fmt::Debug + PartialEq>(&self, expected: &T) {
+        assert_debug_eq(&*self.get::<T>(), expected);
+    }
+}
+
+/// Check the equality of two values, on mismatch it panics with the diff of the debug representations.
+pub fn assert_debug_eq<T: fmt::Debug + PartialEq + ?Sized>(actual: &T, expected: &T) {
+    if actual != expected {
+        let actual = format!("{:#?}", actual);
+        let expected = format!("{:#?}", expected);
+        panic!(
+            "assertion failed: `(actual == expected)`\n{}",
+            debug_diff(&actual, &expected)
+        );
+    }
+}
+
+/// Line based diff of two texts. The missing (expected) lines are prefixed by `-`, the unexpected (actual) lines by `+`.
+pub fn debug_diff(actual: &str, expected: &str) -> String {
+    let actual = actual.lines().collect::<Vec<_>>();
+    let expected = expected.lines().collect::<Vec<_>>();
+
+    // longest common subsequence of the lines
+    let (n, m) = (expected.len(), actual.len());
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if expected[i] == actual[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && expected[i] == actual[j] {
+            diff += &format!("  {}\n", expected[i]);
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            diff += &format!("+ {}\n", actual[j]);
+            j += 1;
+        } else {
+            diff += &format!("- {}\n", expected[i]);
+            i += 1;
+        }
+    }
+    diff
+}
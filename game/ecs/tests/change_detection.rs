@@ -0,0 +1,192 @@
+use shine_ecs::{
+    resources::{
+        Changed, MultiResMutQuery, MultiResQuery, ResMut, ResMutQuery, ResQuery, ResourceId, Resources, Tick,
+        MAX_TICK_AGE,
+    },
+    scheduler::{IntoSystem, Scheduler, TaskGroup},
+    ECSError,
+};
+
+mod utils;
+
+#[test]
+fn tick_wrap_around() {
+    utils::init_logger();
+
+    let current = Tick::new(5);
+    let last_run = Tick::new(u32::MAX - 5);
+    assert_eq!(last_run.age(current), 11);
+
+    // changed after the wrap-around
+    assert!(Tick::new(2).is_newer_than(last_run, current));
+    // changed before the wrap-around but after the last run
+    assert!(Tick::new(u32::MAX - 1).is_newer_than(last_run, current));
+    // changed before the last run
+    assert!(!Tick::new(u32::MAX - 10).is_newer_than(last_run, current));
+    assert!(!last_run.is_newer_than(last_run, current));
+
+    // old ticks are clamped to remain older than any recent run
+    let old = Tick::new(10);
+    let current = Tick::new(9);
+    assert_eq!(old.age(current), u32::MAX);
+    let clamped = old.clamped(current);
+    assert_eq!(clamped.age(current), MAX_TICK_AGE);
+    assert!(!clamped.is_newer_than(Tick::new(0), current));
+    assert_eq!(Tick::new(8).clamped(current), Tick::new(8));
+}
+
+#[test]
+fn res_change_ticks() {
+    utils::init_logger();
+
+    let mut resources = Resources::default();
+    resources.register_with_instance(1usize).unwrap();
+
+    let mut reader = ResQuery::<usize>::new();
+    let mut writer = ResMutQuery::<usize>::new();
+
+    // everything is new on the first access
+    {
+        let r = resources.claim(&mut reader).unwrap();
+        assert!(r.is_added());
+        assert!(r.is_changed());
+    }
+    resources.increment_change_tick();
+
+    {
+        let r = resources.claim(&mut reader).unwrap();
+        assert!(!r.is_added());
+        assert!(!r.is_changed());
+    }
+    resources.increment_change_tick();
+
+    {
+        let mut w = resources.claim(&mut writer).unwrap();
+        assert!(w.is_changed());
+        *w.bypass_change_detection() = 2;
+    }
+    resources.increment_change_tick();
+
+    {
+        let r = resources.claim(&mut reader).unwrap();
+        assert_eq!(*r, 2);
+        assert!(!r.is_changed());
+    }
+    resources.increment_change_tick();
+
+    {
+        let mut w = resources.claim(&mut writer).unwrap();
+        // read only access does not trigger change
+        assert_eq!(*w, 2);
+        assert!(!w.is_changed());
+        *w = 3;
+    }
+    resources.increment_change_tick();
+
+    {
+        let r = resources.claim(&mut reader).unwrap();
+        assert_eq!(*r, 3);
+        assert!(!r.is_added());
+        assert!(r.is_changed());
+    }
+    resources.increment_change_tick();
+
+    {
+        // the own changes of the writer are not reported
+        let w = resources.claim(&mut writer).unwrap();
+        assert!(!w.is_changed());
+    }
+}
+
+#[test]
+fn multi_res_change_ticks() {
+    utils::init_logger();
+
+    let mut resources = Resources::default();
+    resources.register_unmanaged::<String>().unwrap();
+    let ids = (0..4)
+        .map(|i| ResourceId::from_tag(&format!("tag{}", i)))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    for (i, id) in ids.iter().enumerate() {
+        resources.insert_with_id(id.clone(), format!("value {}", i)).unwrap();
+    }
+
+    let mut reader = MultiResQuery::<String>::new(ids.clone());
+    let mut writer = MultiResMutQuery::<String>::new(vec![ids[1].clone(), ids[3].clone()]);
+
+    {
+        let r = resources.claim(&mut reader).unwrap();
+        assert_eq!(r.changed().count(), 4);
+        assert!((0..4).all(|i| r.is_added(i)));
+    }
+    resources.increment_change_tick();
+
+    {
+        let mut w = resources.claim(&mut writer).unwrap();
+        w[1].push_str(" modified");
+    }
+    resources.increment_change_tick();
+
+    {
+        let r = resources.claim(&mut reader).unwrap();
+        assert!(!r.is_changed(1));
+        assert!(r.is_changed(3));
+        assert_eq!(r.changed().collect::<Vec<_>>(), vec![3]);
+        assert_eq!(r[3], "value 3 modified");
+    }
+}
+
+#[derive(Default)]
+struct Config {
+    size: usize,
+}
+
+#[derive(Default)]
+struct Pipeline {
+    size: usize,
+    rebuild_count: usize,
+}
+
+fn rebuild_pipeline(config: Changed<Config>, mut pipeline: ResMut<Pipeline>) -> Result<TaskGroup, ECSError> {
+    if let Some(config) = config.get() {
+        pipeline.size = config.size;
+        pipeline.rebuild_count += 1;
+    }
+    Ok(TaskGroup::default())
+}
+
+#[test]
+fn changed_filter_in_systems() {
+    utils::init_logger();
+
+    let mut resources = Resources::default();
+    resources.register_with_instance(Config::default()).unwrap();
+    resources.register_with_instance(Pipeline::default()).unwrap();
+
+    let mut tasks = TaskGroup::default();
+    tasks.add_task(rebuild_pipeline.into_system());
+
+    let mut scheduler = Scheduler::default();
+    scheduler.set_claim_check(true);
+
+    // the first run always rebuilds
+    scheduler.run(&resources, &tasks).unwrap();
+    assert_eq!(resources.get::<Pipeline>().unwrap().rebuild_count, 1);
+
+    // no change, no rebuild
+    scheduler.run(&resources, &tasks).unwrap();
+    scheduler.run(&resources, &tasks).unwrap();
+    assert_eq!(resources.get::<Pipeline>().unwrap().rebuild_count, 1);
+
+    resources.get_mut::<Config>().unwrap().size = 12;
+    scheduler.run(&resources, &tasks).unwrap();
+    {
+        let pipeline = resources.get::<Pipeline>().unwrap();
+        assert_eq!(pipeline.rebuild_count, 2);
+        assert_eq!(pipeline.size, 12);
+    }
+
+    scheduler.run(&resources, &tasks).unwrap();
+    assert_eq!(resources.get::<Pipeline>().unwrap().rebuild_count, 2);
+}
@@ -0,0 +1,75 @@
+use shine_ecs::{
+    resources::{ResMut, ResourceId},
+    scheduler::{IntoSystem, TaskGroup},
+    testing::TestWorld,
+    ECSError,
+};
+
+mod utils;
+
+#[derive(Debug, Hash, PartialEq, Default)]
+struct Counter(u64);
+
+#[derive(Debug, Hash, PartialEq, Default)]
+struct Trace(Vec<&'static str>);
+
+fn first(mut trace: ResMut<Trace>, mut counter: ResMut<Counter>) -> Result<TaskGroup, ECSError> {
+    trace.0.push("first");
+    counter.0 = counter.0 * 3 + 1;
+    Ok(TaskGroup::default())
+}
+
+fn second(mut trace: ResMut<Trace>, mut counter: ResMut<Counter>) -> Result<TaskGroup, ECSError> {
+    trace.0.push("second");
+    counter.0 *= 2;
+    Ok(TaskGroup::default())
+}
+
+fn create_world() -> TestWorld {
+    let mut world = TestWorld::new()
+        .with_resource(Counter::default())
+        .with_resource(Trace::default())
+        .with_state_hash::<Counter>()
+        .with_state_hash::<Trace>();
+    for tag in &["c", "a", "b"] {
+        world = world.with_tagged_resource(tag, tag.len());
+    }
+    world
+        .with_state_hash::<usize>()
+        .with_system("update", first.into_system())
+        .with_system("update", second.into_system())
+}
+
+#[test]
+fn ordered_ids() {
+    utils::init_logger();
+
+    let world = create_world();
+    let ids = world.resources().ids::<usize>().unwrap();
+    let expected = ["a", "b", "c"]
+        .iter()
+        .map(|tag| ResourceId::from_tag(tag).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, expected);
+}
+
+#[test]
+fn deterministic_frames() {
+    utils::init_logger();
+
+    let mut world = create_world();
+    let hashes = world.run_frames_hashed(&["update"], 4).unwrap();
+    world.assert_resource(&Counter(518));
+    assert_eq!(
+        world.get::<Trace>().0,
+        vec!["first", "second", "first", "second", "first", "second", "first", "second"]
+    );
+
+    let mut other = create_world();
+    let other_hashes = other.run_frames_hashed(&["update"], 4).unwrap();
+    assert_eq!(hashes, other_hashes);
+
+    // any change in the state is reflected in the hash
+    *other.get_mut::<Counter>() = Counter(0);
+    assert_ne!(other.state_hash().unwrap(), hashes[3]);
+}
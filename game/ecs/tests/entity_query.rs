@@ -0,0 +1,116 @@
+use shine_ecs::{
+    entities::{Entities, Query, With, Without},
+    resources::{ResMut, Resources},
+    scheduler::{IntoSystem, Scheduler, System, TaskGroup},
+    ECSError,
+};
+
+mod utils;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Pos(i32);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Vel(i32);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Frozen;
+
+#[derive(Debug, Default)]
+struct Count(usize);
+
+fn create_resources() -> Resources {
+    let mut entities = Entities::new();
+    entities.spawn((Pos(0), Vel(1)));
+    entities.spawn((Pos(10), Vel(2)));
+    entities.spawn((Pos(20), Vel(3), Frozen));
+    entities.spawn((Pos(30),));
+
+    let mut resources = Resources::default();
+    resources.register_with_instance(entities).unwrap();
+    resources.register_with_instance(Count::default()).unwrap();
+    resources
+}
+
+fn integrate(query: Query<(&mut Pos, &Vel), Without<Frozen>>) -> Result<TaskGroup, ECSError> {
+    for (_, (pos, vel)) in &mut query.iter() {
+        pos.0 += vel.0;
+    }
+    Ok(TaskGroup::default())
+}
+
+fn count_frozen(query: Query<(&Pos,), With<Frozen>>, mut count: ResMut<Count>) -> Result<TaskGroup, ECSError> {
+    count.0 = query.count();
+    Ok(TaskGroup::default())
+}
+
+fn count_moving(query: Query<(&Pos, Option<&Vel>)>, mut count: ResMut<Count>) -> Result<TaskGroup, ECSError> {
+    count.0 = query.iter().iter().filter(|(_, (_, vel))| vel.is_some()).count();
+    Ok(TaskGroup::default())
+}
+
+fn read_pos(_query: Query<(&Pos, &Vel)>) -> Result<TaskGroup, ECSError> {
+    Ok(TaskGroup::default())
+}
+
+fn read_vel(_query: Query<(&Vel,), (With<Pos>, Without<Frozen>)>) -> Result<TaskGroup, ECSError> {
+    Ok(TaskGroup::default())
+}
+
+fn write_entities(_entities: ResMut<Entities>) -> Result<TaskGroup, ECSError> {
+    Ok(TaskGroup::default())
+}
+
+#[test]
+fn query_filters() {
+    utils::init_logger();
+
+    let resources = create_resources();
+    let mut tasks = TaskGroup::default();
+    tasks.add_task(integrate.into_system());
+    let mut scheduler = Scheduler::default();
+    scheduler.run(&resources, &tasks).unwrap();
+
+    {
+        let entities = resources.get::<Entities>().unwrap();
+        let mut positions = entities
+            .query::<&Pos>()
+            .iter()
+            .map(|(_, pos)| pos.0)
+            .collect::<Vec<_>>();
+        positions.sort();
+        assert_eq!(positions, vec![1, 12, 20, 30]);
+    }
+
+    let mut tasks = TaskGroup::default();
+    tasks.add_task(count_frozen.into_system());
+    scheduler.run(&resources, &tasks).unwrap();
+    assert_eq!(resources.get::<Count>().unwrap().0, 1);
+
+    let mut tasks = TaskGroup::default();
+    tasks.add_task(count_moving.into_system());
+    scheduler.run(&resources, &tasks).unwrap();
+    assert_eq!(resources.get::<Count>().unwrap().0, 3);
+}
+
+#[test]
+fn query_claims() {
+    utils::init_logger();
+
+    let mut integrate = integrate.into_system();
+    let mut read_pos = read_pos.into_system();
+    let mut read_vel = read_vel.into_system();
+    let mut write_entities = write_entities.into_system();
+
+    let integrate = integrate.resource_claims().unwrap();
+    let read_pos = read_pos.resource_claims().unwrap();
+    let read_vel = read_vel.resource_claims().unwrap();
+    let write_entities = write_entities.resource_claims().unwrap();
+
+    // the entities are shared, the components are claimed by the access
+    assert!(integrate.is_conflicting(read_pos));
+    assert!(!integrate.is_conflicting(read_vel));
+    assert!(!read_pos.is_conflicting(read_vel));
+    // structural changes are exclusive
+    assert!(write_entities.is_conflicting(read_vel));
+}
@@ -0,0 +1,86 @@
+use shine_ecs::{
+    events::{clear_events, EventCursor, EventReader, EventWriter, Events},
+    resources::{Res, ResMut, Resources},
+    scheduler::{IntoSystem, Scheduler, TaskGroup},
+    ECSError,
+};
+
+mod utils;
+
+#[derive(Debug, PartialEq)]
+struct Hit(u32);
+
+#[test]
+fn events_double_buffer() {
+    utils::init_logger();
+
+    let mut events = Events::<Hit>::default();
+    let mut early = events.cursor();
+    let mut late = events.cursor();
+
+    events.send(Hit(1));
+    events.send(Hit(2));
+    assert_eq!(early.read(&events).collect::<Vec<_>>(), vec![&Hit(1), &Hit(2)]);
+    assert!(early.is_empty(&events));
+
+    // the events of the previous frame are still available
+    events.update();
+    events.send(Hit(3));
+    assert_eq!(early.read(&events).collect::<Vec<_>>(), vec![&Hit(3)]);
+    assert_eq!(late.len(&events), 3);
+    assert_eq!(late.read(&events).count(), 3);
+
+    // events not read within two frames are lost
+    let mut slow = EventCursor::default();
+    events.update();
+    events.update();
+    events.send(Hit(4));
+    assert_eq!(slow.read(&events).collect::<Vec<_>>(), vec![&Hit(4)]);
+    assert_eq!(slow.lost(), 3);
+
+    // cleared events are not lost
+    let mut cleared = events.cursor();
+    events.send(Hit(5));
+    events.clear();
+    events.send(Hit(6));
+    assert_eq!(cleared.read(&events).collect::<Vec<_>>(), vec![&Hit(6)]);
+    assert_eq!(cleared.lost(), 0);
+    assert_eq!(events.event_count(), 6);
+}
+
+fn send_hits(mut hits: EventWriter<Hit>, frame: Res<u32>) -> Result<TaskGroup, ECSError> {
+    hits.send_batch(vec![Hit(*frame), Hit(*frame + 100)]);
+    Ok(TaskGroup::default())
+}
+
+fn count_hits(mut hits: EventReader<Hit>, mut count: ResMut<usize>) -> Result<TaskGroup, ECSError> {
+    *count += hits.iter().count();
+    Ok(TaskGroup::default())
+}
+
+#[test]
+fn events_in_systems() {
+    utils::init_logger();
+
+    let mut resources = Resources::default();
+    resources.register_events::<Hit>().unwrap();
+    resources.register_with_instance(0u32).unwrap();
+    resources.register_with_instance(0usize).unwrap();
+
+    // the reader runs before the writer, the events of the previous frame are read
+    let mut tasks = TaskGroup::default();
+    tasks.add_task(count_hits.into_system());
+    tasks.add_task(send_hits.into_system());
+    tasks.add_task(clear_events::<Hit>());
+
+    let mut scheduler = Scheduler::default();
+    scheduler.set_claim_check(true);
+    for frame in 0..4 {
+        *resources.get_mut::<u32>().unwrap() = frame;
+        scheduler.run(&resources, &tasks).unwrap();
+    }
+
+    // the events of the last frame are not read yet
+    assert_eq!(*resources.get::<usize>().unwrap(), 6);
+    assert_eq!(resources.get::<Events<Hit>>().unwrap().len(), 2);
+}
@@ -0,0 +1,104 @@
+use shine_ecs::{
+    hierarchy::{propagate_hierarchy, Children, Entities, Hierarchy, Parent},
+    ECSError,
+};
+
+mod utils;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Local(i32);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Global(i32);
+
+#[test]
+fn attach_detach() {
+    utils::init_logger();
+
+    let mut entities = Entities::new();
+    let root = entities.spawn((Local(1),));
+    let a = entities.spawn((Local(2),));
+    let b = entities.spawn((Local(3),));
+
+    entities.attach(a, root).unwrap();
+    entities.attach(b, root).unwrap();
+    assert_eq!(entities.parent(a), Some(root));
+    assert_eq!(entities.children(root), vec![a, b]);
+    assert!(entities.get::<Parent>(root).is_err());
+
+    // re-attaching removes the child from the previous parent
+    entities.attach(b, a).unwrap();
+    assert_eq!(entities.children(root), vec![a]);
+    assert_eq!(entities.children(a), vec![b]);
+    assert_eq!(entities.get::<Parent>(b).unwrap().entity(), a);
+
+    match entities.attach(root, b) {
+        Err(ECSError::HierarchyCycle(child, parent)) => assert_eq!((child, parent), (root, b)),
+        res => panic!("Unexpected result: {:?}", res),
+    }
+    assert!(entities.attach(a, a).is_err());
+
+    assert_eq!(entities.detach(a).unwrap(), Some(root));
+    assert_eq!(entities.detach(a).unwrap(), None);
+    // the empty children list is removed
+    assert!(entities.get::<Children>(root).is_err());
+    assert_eq!(entities.children(a), vec![b]);
+}
+
+#[test]
+fn despawn_recursive() {
+    utils::init_logger();
+
+    let mut entities = Entities::new();
+    let root = entities.spawn((Local(1),));
+    let a = entities.spawn((Local(2),));
+    let b = entities.spawn((Local(3),));
+    let c = entities.spawn((Local(4),));
+    entities.attach(a, root).unwrap();
+    entities.attach(b, a).unwrap();
+    entities.attach(c, root).unwrap();
+
+    entities.despawn_recursive(a).unwrap();
+    assert!(!entities.contains(a));
+    assert!(!entities.contains(b));
+    assert!(entities.contains(c));
+    assert_eq!(entities.children(root), vec![c]);
+
+    match entities.despawn_recursive(a) {
+        Err(ECSError::EntityNotFound(entity)) => assert_eq!(entity, a),
+        res => panic!("Unexpected result: {:?}", res),
+    }
+    assert!(entities.attach(a, root).is_err());
+}
+
+#[test]
+fn propagate() {
+    utils::init_logger();
+
+    let mut entities = Entities::new();
+    let root = entities.spawn((Local(1),));
+    let a = entities.spawn((Local(10),));
+    let b = entities.spawn((Local(100),));
+    // no local component, the propagation stops here
+    let c = entities.spawn(());
+    let d = entities.spawn((Local(1000),));
+    entities.attach(a, root).unwrap();
+    entities.attach(b, a).unwrap();
+    entities.attach(c, root).unwrap();
+    entities.attach(d, c).unwrap();
+
+    let sum = |parent: Option<&Global>, local: &Local| Global(parent.map(|g| g.0).unwrap_or(0) + local.0);
+    propagate_hierarchy::<Local, Global, _>(&mut entities, sum);
+    assert_eq!(*entities.get::<Global>(root).unwrap(), Global(1));
+    assert_eq!(*entities.get::<Global>(a).unwrap(), Global(11));
+    assert_eq!(*entities.get::<Global>(b).unwrap(), Global(111));
+    assert!(entities.get::<Global>(c).is_err());
+    assert!(entities.get::<Global>(d).is_err());
+
+    // the existing globals are updated
+    *entities.get_mut::<Local>(root).unwrap() = Local(2);
+    entities.detach(b).unwrap();
+    propagate_hierarchy::<Local, Global, _>(&mut entities, sum);
+    assert_eq!(*entities.get::<Global>(a).unwrap(), Global(12));
+    assert_eq!(*entities.get::<Global>(b).unwrap(), Global(100));
+}
@@ -0,0 +1,76 @@
+use shine_ecs::{
+    resources::{BorrowContextGuard, Res, ResMut, ResourceId, Resources},
+    scheduler::{IntoSystem, ResourceClaims, Scheduler, TaskGroup},
+    ECSError,
+};
+
+mod utils;
+
+fn sys(r1: Res<u8>, mut r2: ResMut<u16>) -> Result<TaskGroup, ECSError> {
+    *r2 += u16::from(*r1);
+    Ok(TaskGroup::default())
+}
+
+fn create_resources() -> Resources {
+    let mut resources = Resources::default();
+    resources.register_with_instance(1u8).unwrap();
+    resources.register_with_instance(2u16).unwrap();
+    resources.register_with_instance(3u32).unwrap();
+    resources
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(
+    expected = "Mutable borrow of a resource [u8] failed: Target already borrowed as immutable (requested by system_b, held by system_a)"
+)]
+fn borrow_conflict() {
+    utils::init_logger();
+
+    let resources = create_resources();
+
+    let _a = BorrowContextGuard::enter("system_a", None);
+    let _r1 = resources.get::<u8>().unwrap();
+    {
+        let _b = BorrowContextGuard::enter("system_b", None);
+        let _r2 = resources.get_mut::<u8>().unwrap();
+    }
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn claim_check() {
+    utils::init_logger();
+
+    let resources = create_resources();
+
+    let mut claims = ResourceClaims::default();
+    claims.add_immutable::<u8, _>(Some(ResourceId::Global));
+    claims.add_mutable::<u16, _>(Some(ResourceId::Global));
+
+    let _a = BorrowContextGuard::enter("system_a", Some(claims));
+    assert!(resources.get::<u8>().is_ok());
+    assert!(resources.get::<u16>().is_ok());
+    assert!(resources.get_mut::<u16>().is_ok());
+    match resources.get_mut::<u8>() {
+        Err(ECSError::ResourceNotClaimed(_, _, owner)) => assert_eq!(owner, "system_a"),
+        _ => panic!("Unclaimed mutable access"),
+    }
+    match resources.get::<u32>() {
+        Err(ECSError::ResourceNotClaimed(_, _, owner)) => assert_eq!(owner, "system_a"),
+        _ => panic!("Unclaimed access"),
+    }
+}
+
+#[test]
+fn scheduler_claim_check() {
+    utils::init_logger();
+
+    let resources = create_resources();
+    let tasks = TaskGroup::from_task(sys.into_system());
+
+    let mut scheduler = Scheduler::default();
+    scheduler.set_claim_check(true);
+    scheduler.run(&resources, &tasks).unwrap();
+    assert_eq!(*resources.get::<u16>().unwrap(), 3);
+}
@@ -0,0 +1,83 @@
+use shine_ecs::{
+    resources::{FromResources, ResourceId, Resources},
+    ECSError,
+};
+use std::{fmt, sync::Arc};
+
+mod utils;
+
+#[derive(Debug, Default, PartialEq)]
+struct Config(usize);
+
+#[derive(Debug, PartialEq)]
+struct Device(usize);
+
+impl FromResources for Device {
+    fn from_resources(resources: &Resources) -> Result<Self, ECSError> {
+        let config = resources.get::<Config>()?;
+        Ok(Device(config.0 * 2))
+    }
+}
+
+#[derive(Debug)]
+struct BrokenError;
+
+impl fmt::Display for BrokenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "broken")
+    }
+}
+
+impl std::error::Error for BrokenError {}
+
+#[derive(Debug)]
+struct Broken;
+
+impl FromResources for Broken {
+    fn from_resources(_resources: &Resources) -> Result<Self, ECSError> {
+        Err(ECSError::ResourceInit("Broken".into(), Arc::new(BrokenError)))
+    }
+}
+
+#[test]
+fn init_default() {
+    utils::init_logger();
+
+    let mut resources = Resources::default();
+    assert!(resources.init_resource::<Config>().unwrap());
+    assert_eq!(*resources.get::<Config>().unwrap(), Config(0));
+
+    // existing instance is kept
+    *resources.get_mut::<Config>().unwrap() = Config(3);
+    assert!(!resources.init_resource::<Config>().unwrap());
+    assert_eq!(*resources.get::<Config>().unwrap(), Config(3));
+}
+
+#[test]
+fn init_from_resources() {
+    utils::init_logger();
+
+    let mut resources = Resources::default();
+    assert!(resources.init_resource::<Device>().is_err());
+    assert!(resources.get_store::<Device>().is_none());
+
+    resources.register_with_instance(Config(2)).unwrap();
+    assert!(resources.init_resource::<Device>().unwrap());
+    assert_eq!(*resources.get::<Device>().unwrap(), Device(4));
+}
+
+#[test]
+fn init_registered() {
+    utils::init_logger();
+
+    let mut resources = Resources::default();
+    resources.register_unmanaged::<Config>().unwrap();
+    assert!(!resources.get_store::<Config>().unwrap().exists(&ResourceId::Global));
+    assert!(resources.init_resource::<Config>().unwrap());
+    assert_eq!(*resources.get::<Config>().unwrap(), Config(0));
+
+    match resources.init_resource::<Broken>() {
+        Err(ECSError::ResourceInit(..)) => {}
+        err => panic!("Unexpected result: {:?}", err),
+    }
+}
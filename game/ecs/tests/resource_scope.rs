@@ -0,0 +1,92 @@
+use shine_ecs::resources::{ManagedResource, ResourceId, ResourceScope, Resources};
+
+mod utils;
+
+struct GameScope;
+impl ResourceScope for GameScope {}
+
+struct LevelScope;
+impl ResourceScope for LevelScope {}
+
+#[derive(Debug, PartialEq)]
+struct Counter(usize);
+
+#[derive(Debug, PartialEq)]
+struct Name(String);
+
+#[test]
+fn scope_release() {
+    utils::init_logger();
+
+    let mut resources = Resources::default();
+    assert!(!resources.has_scope::<GameScope>());
+
+    resources.register_with_instance_scoped(GameScope, Counter(1)).unwrap();
+    resources
+        .register_scoped(GameScope, ManagedResource::new(|id| Name(format!("{:?}", id))))
+        .unwrap();
+    assert!(resources.has_scope::<GameScope>());
+    assert!(!resources.has_scope::<LevelScope>());
+
+    assert_eq!(*resources.get::<Counter>().unwrap(), Counter(1));
+    assert!(resources
+        .get_with_id::<Name>(&ResourceId::from_tag("a").unwrap())
+        .is_ok());
+
+    resources.release_scope::<GameScope>();
+    assert!(!resources.has_scope::<GameScope>());
+    assert!(resources.get::<Counter>().is_err());
+    assert!(resources.get_store::<Name>().is_none());
+
+    // types can be registered again after release
+    resources.register_with_instance_scoped(GameScope, Counter(2)).unwrap();
+    assert_eq!(*resources.get::<Counter>().unwrap(), Counter(2));
+    resources.release_scope::<GameScope>();
+    assert!(resources.get::<Counter>().is_err());
+}
+
+#[test]
+fn scoped_instances() {
+    utils::init_logger();
+
+    let mut resources = Resources::default();
+    resources.register_unmanaged::<Counter>().unwrap();
+    let ida = ResourceId::from_tag("a").unwrap();
+
+    resources.insert_scoped(LevelScope, Counter(1)).unwrap();
+    resources
+        .insert_scoped_with_id(GameScope, ida.clone(), Counter(2))
+        .unwrap();
+    assert_eq!(*resources.get::<Counter>().unwrap(), Counter(1));
+    assert_eq!(*resources.get_with_id::<Counter>(&ida).unwrap(), Counter(2));
+
+    // releasing a scope keeps the resources of the other scopes
+    resources.release_scope::<LevelScope>();
+    assert!(resources.get::<Counter>().is_err());
+    assert_eq!(*resources.get_with_id::<Counter>(&ida).unwrap(), Counter(2));
+
+    // instances are released, registration is kept
+    resources.release_scope::<GameScope>();
+    assert!(resources.get_with_id::<Counter>(&ida).is_err());
+    assert!(resources.get_store::<Counter>().is_some());
+}
+
+#[test]
+fn release_order() {
+    utils::init_logger();
+
+    let mut resources = Resources::default();
+    resources.register_unmanaged::<Counter>().unwrap();
+    resources
+        .register_with_instance_scoped(GameScope, Name("game".into()))
+        .unwrap();
+    // the instance is inserted after the registration of Name, thus it is removed first
+    resources.insert_scoped(GameScope, Counter(1)).unwrap();
+
+    resources.release_scope::<GameScope>();
+    assert!(resources.get_store::<Name>().is_none());
+    assert!(resources.get::<Counter>().is_err());
+
+    // releasing an unknown scope is a no-op
+    resources.release_scope::<LevelScope>();
+}
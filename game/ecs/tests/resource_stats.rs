@@ -0,0 +1,42 @@
+use shine_ecs::resources::{ManagedResource, ResourceId, Resources};
+use std::any::type_name;
+
+mod utils;
+
+#[derive(Debug)]
+struct Item([u8; 32]);
+
+#[test]
+fn resource_stats() {
+    utils::init_logger();
+
+    let mut resources = Resources::default();
+    resources.register_with_instance(1usize).unwrap();
+    resources.register(ManagedResource::new(|_| Item([0; 32]))).unwrap();
+    resources.insert_tagged("a", Item([1; 32])).unwrap();
+    resources.insert_tagged("b", Item([2; 32])).unwrap();
+
+    // created on demand, pending until bake
+    let idc = ResourceId::from_tag("c").unwrap();
+    let _handle = resources.get_handle::<Item>(&idc).unwrap();
+
+    let stats = resources.stats();
+    log::info!("{}", stats);
+    assert_eq!(stats.stores.len(), 2);
+    assert_eq!(stats.total_count(), 4);
+
+    let items = stats.find(type_name::<Item>()).unwrap();
+    assert_eq!(items.item_size, 32);
+    assert_eq!(items.count, 2);
+    assert_eq!(items.pending_count, 1);
+    assert!(items.capacity >= 2);
+    assert!(items.memory_size() >= 3 * 32);
+    assert!(items.fragmentation() >= 0. && items.fragmentation() < 1.);
+
+    resources.bake::<Item>(false);
+    let stats = resources.stats();
+    let items = stats.find(type_name::<Item>()).unwrap();
+    assert_eq!(items.count, 3);
+    assert_eq!(items.pending_count, 0);
+    assert_eq!(items.handle_count, 1);
+}
@@ -0,0 +1,241 @@
+use shine_ecs::{
+    resources::{Res, ResMut, ResourceId, Resources},
+    scheduler::{IntoSystem, ResourceClaims, Scheduler, SystemName, TaskGroup},
+    ECSError,
+};
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::Duration,
+};
+
+mod utils;
+
+#[derive(Debug, Default)]
+struct Trace(Vec<&'static str>);
+
+#[derive(Debug, Default)]
+struct Input(usize);
+
+#[derive(Debug, Default)]
+struct OutputA(usize);
+
+#[derive(Debug, Default)]
+struct OutputB(usize);
+
+fn name(name: &str) -> SystemName {
+    name.parse().unwrap()
+}
+
+fn create_resources() -> Resources {
+    let mut resources = Resources::default();
+    resources.register_with_instance(Trace::default()).unwrap();
+    resources.register_with_instance(Input(3)).unwrap();
+    resources.register_with_instance(OutputA::default()).unwrap();
+    resources.register_with_instance(OutputB::default()).unwrap();
+    resources
+}
+
+#[test]
+fn claim_conflicts() {
+    utils::init_logger();
+
+    let mut read = ResourceClaims::default();
+    read.add_immutable::<Input, _>(Some(ResourceId::Global));
+    let mut read2 = ResourceClaims::default();
+    read2.add_immutable::<Input, _>(Some(ResourceId::Global));
+    let mut write = ResourceClaims::default();
+    write.add_mutable::<Input, _>(Some(ResourceId::Global));
+    let mut other = ResourceClaims::default();
+    other.add_mutable::<OutputA, _>(Some(ResourceId::Global));
+
+    assert!(!read.is_conflicting(&read2));
+    assert!(read.is_conflicting(&write));
+    assert!(write.is_conflicting(&read));
+    assert!(write.is_conflicting(&write));
+    assert!(!write.is_conflicting(&other));
+    assert!(!other.is_conflicting(&read));
+}
+
+static RUNNING: AtomicUsize = AtomicUsize::new(0);
+static MAX_RUNNING: AtomicUsize = AtomicUsize::new(0);
+
+fn track_concurrency() {
+    let running = RUNNING.fetch_add(1, Ordering::SeqCst) + 1;
+    MAX_RUNNING.fetch_max(running, Ordering::SeqCst);
+    thread::sleep(Duration::from_millis(50));
+    RUNNING.fetch_sub(1, Ordering::SeqCst);
+}
+
+fn produce_a(input: Res<Input>, mut output: ResMut<OutputA>) -> Result<TaskGroup, ECSError> {
+    track_concurrency();
+    output.0 = input.0 * 2;
+    Ok(TaskGroup::default())
+}
+
+fn produce_b(input: Res<Input>, mut output: ResMut<OutputB>) -> Result<TaskGroup, ECSError> {
+    track_concurrency();
+    output.0 = input.0 * 3;
+    Ok(TaskGroup::default())
+}
+
+fn trace_first(mut trace: ResMut<Trace>) -> Result<TaskGroup, ECSError> {
+    trace.0.push("first");
+    Ok(TaskGroup::default())
+}
+
+fn trace_second(mut trace: ResMut<Trace>, output: Res<OutputA>) -> Result<TaskGroup, ECSError> {
+    assert_eq!(output.0, 6);
+    trace.0.push("second");
+    Ok(TaskGroup::default())
+}
+
+#[test]
+fn parallel_systems() {
+    utils::init_logger();
+
+    let resources = create_resources();
+    let mut tasks = TaskGroup::default();
+    tasks.add_task(produce_a.into_system());
+    tasks.add_task(trace_first.into_system());
+    tasks.add_task(produce_b.into_system());
+    // reads the output of produce_a, thus it waits for it by the claims
+    tasks.add_task(trace_second.into_system());
+
+    let mut scheduler = Scheduler::default();
+    scheduler.set_claim_check(true);
+    // safety: all the resources are Send and Sync
+    unsafe { scheduler.set_parallel(true) };
+    scheduler.run(&resources, &tasks).unwrap();
+
+    assert_eq!(resources.get::<OutputA>().unwrap().0, 6);
+    assert_eq!(resources.get::<OutputB>().unwrap().0, 9);
+    assert_eq!(resources.get::<Trace>().unwrap().0, vec!["first", "second"]);
+    if rayon::current_num_threads() > 1 {
+        assert_eq!(MAX_RUNNING.load(Ordering::SeqCst), 2);
+    }
+}
+
+fn spawn_traces(mut trace: ResMut<Trace>) -> Result<TaskGroup, ECSError> {
+    trace.0.push("spawn");
+    let mut tasks = TaskGroup::default();
+    tasks.add_task(trace_first.into_system());
+    Ok(tasks)
+}
+
+fn trace_late(mut trace: ResMut<Trace>) -> Result<TaskGroup, ECSError> {
+    trace.0.push("late");
+    Ok(TaskGroup::default())
+}
+
+fn trace_early(mut trace: ResMut<Trace>) -> Result<TaskGroup, ECSError> {
+    trace.0.push("early");
+    Ok(TaskGroup::default())
+}
+
+#[test]
+fn explicit_dependencies() {
+    utils::init_logger();
+
+    for &parallel in &[false, true] {
+        let resources = create_resources();
+        let mut tasks = TaskGroup::default();
+        tasks.add_task(trace_late.into_system().run_after(name("early")));
+        tasks.add_task(spawn_traces.into_system().run_before(name("early")));
+        tasks.add_task(trace_early.into_system().with_name(Some(name("early"))));
+
+        let mut scheduler = Scheduler::default();
+        // safety: all the resources are Send and Sync
+        unsafe { scheduler.set_parallel(parallel) };
+        scheduler.run(&resources, &tasks).unwrap();
+        // the spawned tasks are run right after the spawning system
+        assert_eq!(
+            resources.get::<Trace>().unwrap().0,
+            vec!["spawn", "first", "early", "late"]
+        );
+    }
+}
+
+fn produce_slow(input: Res<Input>, mut output: ResMut<OutputB>) -> Result<TaskGroup, ECSError> {
+    thread::sleep(Duration::from_millis(50));
+    output.0 = input.0 * 3;
+    Ok(TaskGroup::default())
+}
+
+#[test]
+fn waiting_conflicts() {
+    utils::init_logger();
+
+    let resources = create_resources();
+    let mut tasks = TaskGroup::default();
+    // waits for the slow system
+    tasks.add_task(trace_late.into_system().run_after(name("slow")));
+    tasks.add_task(produce_slow.into_system().with_name(Some(name("slow"))));
+    // ready and independent of the slow system, but conflicting with the waiting system preceding it
+    tasks.add_task(trace_first.into_system());
+
+    let mut scheduler = Scheduler::default();
+    scheduler.set_claim_check(true);
+    // safety: all the resources are Send and Sync
+    unsafe { scheduler.set_parallel(true) };
+    scheduler.run(&resources, &tasks).unwrap();
+
+    assert_eq!(resources.get::<OutputB>().unwrap().0, 9);
+    assert_eq!(resources.get::<Trace>().unwrap().0, vec!["late", "first"]);
+}
+
+#[test]
+fn dependency_cycle() {
+    utils::init_logger();
+
+    let resources = create_resources();
+    let mut tasks = TaskGroup::default();
+    tasks.add_task(
+        trace_early
+            .into_system()
+            .with_name(Some(name("early")))
+            .run_after(name("late")),
+    );
+    tasks.add_task(
+        trace_late
+            .into_system()
+            .with_name(Some(name("late")))
+            .run_after(name("early")),
+    );
+
+    let mut scheduler = Scheduler::default();
+    match scheduler.run(&resources, &tasks) {
+        Err(ECSError::SystemDependencyCycle(names)) => assert_eq!(names.len(), 2),
+        res => panic!("Unexpected result: {:?}", res),
+    }
+    assert!(resources.get::<Trace>().unwrap().0.is_empty());
+}
+
+#[test]
+fn changed_stage() {
+    utils::init_logger();
+
+    for &parallel in &[false, true] {
+        let resources = create_resources();
+        let mut tasks = TaskGroup::default();
+        tasks.add_task(trace_late.into_system().run_after(name("early")));
+        tasks.add_task(trace_first.into_system());
+
+        let mut scheduler = Scheduler::default();
+        scheduler.set_claim_check(true);
+        // safety: all the resources are Send and Sync
+        unsafe { scheduler.set_parallel(parallel) };
+        scheduler.run_stage("update", &resources, &tasks).unwrap();
+        scheduler.run_stage("update", &resources, &tasks).unwrap();
+        assert_eq!(
+            resources.get::<Trace>().unwrap().0,
+            vec!["late", "first", "late", "first"]
+        );
+
+        // the dependencies of the new task are respected by the next run
+        resources.get_mut::<Trace>().unwrap().0.clear();
+        tasks.add_task(trace_early.into_system().with_name(Some(name("early"))));
+        scheduler.run_stage("update", &resources, &tasks).unwrap();
+        assert_eq!(resources.get::<Trace>().unwrap().0, vec!["first", "early", "late"]);
+    }
+}
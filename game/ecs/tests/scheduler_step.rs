@@ -0,0 +1,102 @@
+use shine_ecs::{
+    resources::{ResMut, Resources},
+    scheduler::{IntoSystem, Scheduler, StepMode, TaskGroup},
+    ECSError,
+};
+
+mod utils;
+
+type Trace = Vec<&'static str>;
+
+fn sys_a(mut trace: ResMut<Trace>) -> Result<TaskGroup, ECSError> {
+    trace.push("a");
+    Ok(TaskGroup::default())
+}
+
+fn sys_b(mut trace: ResMut<Trace>) -> Result<TaskGroup, ECSError> {
+    trace.push("b");
+    Ok(TaskGroup::default())
+}
+
+fn sys_c(mut trace: ResMut<Trace>) -> Result<TaskGroup, ECSError> {
+    trace.push("c");
+    Ok(TaskGroup::default())
+}
+
+fn take_trace(resources: &Resources) -> Trace {
+    std::mem::take(&mut *resources.get_mut::<Trace>().unwrap())
+}
+
+#[test]
+fn step_systems() {
+    utils::init_logger();
+
+    let mut resources = Resources::default();
+    resources.register_with_instance(Trace::new()).unwrap();
+    let mut stage = TaskGroup::default();
+    stage.add_task(sys_a.into_system());
+    stage.add_task(sys_b.into_system());
+    stage.add_task(sys_c.into_system());
+
+    let mut scheduler = Scheduler::default();
+    assert!(!scheduler.is_debug());
+    scheduler.run_stage("stage", &resources, &stage).unwrap();
+    assert_eq!(take_trace(&resources), vec!["a", "b", "c"]);
+
+    scheduler.pause();
+    assert!(scheduler.is_debug());
+    scheduler.run_stage("stage", &resources, &stage).unwrap();
+    assert!(take_trace(&resources).is_empty());
+
+    // step the systems one by one
+    scheduler.step_system();
+    scheduler.run_stage("stage", &resources, &stage).unwrap();
+    assert_eq!(take_trace(&resources), vec!["a"]);
+    assert_eq!(scheduler.step_mode(), StepMode::Paused);
+    scheduler.run_stage("stage", &resources, &stage).unwrap();
+    assert!(take_trace(&resources).is_empty());
+    scheduler.step_system();
+    scheduler.run_stage("stage", &resources, &stage).unwrap();
+    assert_eq!(take_trace(&resources), vec!["b"]);
+
+    // complete the partially executed stage
+    scheduler.step_stage();
+    scheduler.run_stage("stage", &resources, &stage).unwrap();
+    assert_eq!(take_trace(&resources), vec!["c"]);
+    assert_eq!(scheduler.step_mode(), StepMode::Paused);
+
+    // step a whole stage
+    scheduler.step_stage();
+    scheduler.run_stage("stage", &resources, &stage).unwrap();
+    assert_eq!(take_trace(&resources), vec!["a", "b", "c"]);
+
+    scheduler.resume();
+    scheduler.run_stage("stage", &resources, &stage).unwrap();
+    assert_eq!(take_trace(&resources), vec!["a", "b", "c"]);
+}
+
+#[test]
+fn system_timings() {
+    utils::init_logger();
+
+    let mut resources = Resources::default();
+    resources.register_with_instance(Trace::new()).unwrap();
+    let mut stage = TaskGroup::default();
+    stage.add_task(sys_a.into_system());
+    stage.add_task(sys_b.into_system());
+
+    let mut scheduler = Scheduler::default();
+    scheduler.run(&resources, &stage).unwrap();
+    assert_eq!(scheduler.system_timings().count(), 0);
+
+    scheduler.set_debug(true);
+    scheduler.run(&resources, &stage).unwrap();
+    scheduler.run(&resources, &stage).unwrap();
+    let timings = scheduler.system_timings().collect::<Vec<_>>();
+    assert_eq!(timings.len(), 2);
+    assert!(timings.iter().all(|(_, timing)| timing.count == 2));
+    scheduler.log_system_timings();
+
+    scheduler.reset_system_timings();
+    assert_eq!(scheduler.system_timings().count(), 0);
+}
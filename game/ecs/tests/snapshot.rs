@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+use shine_ecs::{
+    hierarchy::{Entities, Entity, Hierarchy},
+    resources::{ResourceId, Resources},
+    snapshot::{Snapshot, SnapshotFormat, SnapshotHistory, SnapshotRegistry},
+};
+
+mod utils;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Score(u32);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Position(f32, f32);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Name(String);
+
+/// Not registered, it is not part of the snapshot
+#[derive(Debug, Clone, PartialEq)]
+struct Cache(u32);
+
+fn create_registry() -> SnapshotRegistry {
+    let mut registry = SnapshotRegistry::new();
+    registry
+        .register_resource::<Score>("score")
+        .register_component::<Position>("position")
+        .register_component::<Name>("name");
+    registry
+}
+
+fn create_resources() -> Resources {
+    let mut resources = Resources::default();
+    resources.register_with_instance(Score(10)).unwrap();
+    resources.insert_tagged("blue", Score(3)).unwrap();
+
+    let mut entities = Entities::new();
+    let root = entities.spawn((Name("root".to_owned()), Position(1., 2.)));
+    let a = entities.spawn((Name("a".to_owned()), Position(3., 4.), Cache(1)));
+    let b = entities.spawn((Position(5., 6.),));
+    entities.attach(a, root).unwrap();
+    entities.attach(b, root).unwrap();
+    resources.register_with_instance(entities).unwrap();
+    resources
+}
+
+fn find_named(entities: &Entities, name: &str) -> Entity {
+    entities
+        .query::<&Name>()
+        .iter()
+        .find(|(_, n)| n.0 == name)
+        .map(|(entity, _)| entity)
+        .unwrap()
+}
+
+#[test]
+fn snapshot_restore() {
+    utils::init_logger();
+
+    let registry = create_registry();
+    for &format in &[SnapshotFormat::Bincode, SnapshotFormat::Ron] {
+        let mut resources = create_resources();
+        let snapshot = registry.snapshot(&resources, format).unwrap();
+        let bytes = snapshot.to_bytes().unwrap();
+        let snapshot = Snapshot::from_bytes(format, &bytes).unwrap();
+        if format == SnapshotFormat::Ron {
+            assert!(String::from_utf8(bytes).unwrap().contains("\"score\""));
+        }
+
+        // alter the state
+        *resources.get_mut::<Score>().unwrap() = Score(0);
+        resources.insert_tagged("red", Score(7)).unwrap();
+        let _ = resources.remove_with_id::<Score>(&ResourceId::from_tag("blue").unwrap());
+        {
+            let mut entities = resources.get_mut::<Entities>().unwrap();
+            let root = find_named(&entities, "root");
+            entities.despawn_recursive(root).unwrap();
+            let _ = entities.spawn((Name("extra".to_owned()),));
+        }
+
+        let entity_map = registry.restore(&mut resources, &snapshot).unwrap();
+        assert_eq!(entity_map.len(), 3);
+        assert_eq!(*resources.get::<Score>().unwrap(), Score(10));
+        assert_eq!(
+            *resources
+                .get_with_id::<Score>(&ResourceId::from_tag("blue").unwrap())
+                .unwrap(),
+            Score(3)
+        );
+        assert!(resources
+            .get_with_id::<Score>(&ResourceId::from_tag("red").unwrap())
+            .is_err());
+
+        let entities = resources.get::<Entities>().unwrap();
+        assert_eq!(entities.iter().count(), 3);
+        let root = find_named(&entities, "root");
+        let a = find_named(&entities, "a");
+        assert_eq!(*entities.get::<Position>(a).unwrap(), Position(3., 4.));
+        assert!(entities.get::<Cache>(a).is_err());
+        let children = entities.children(root);
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0], a);
+        assert_eq!(*entities.get::<Position>(children[1]).unwrap(), Position(5., 6.));
+        assert!(entities.get::<Name>(children[1]).is_err());
+    }
+}
+
+#[test]
+fn snapshot_history() {
+    utils::init_logger();
+
+    let registry = create_registry();
+    let resources = create_resources();
+    let mut history = SnapshotHistory::new(3);
+    for frame in 0..5 {
+        *resources.get_mut::<Score>().unwrap() = Score(frame);
+        history.push(
+            frame as u64,
+            registry.snapshot(&resources, SnapshotFormat::Bincode).unwrap(),
+        );
+    }
+    assert_eq!(history.len(), 3);
+    assert_eq!(history.oldest_frame(), Some(2));
+    assert!(history.get(1).is_none());
+
+    let snapshot = history.rollback(3).unwrap();
+    let (_, score) = snapshot.resources[0]
+        .instances
+        .iter()
+        .find(|(id, _)| *id == ResourceId::Global)
+        .unwrap();
+    assert_eq!(score.decode::<Score>("score").unwrap(), Score(3));
+    assert_eq!(history.len(), 2);
+    assert!(history.rollback(4).is_none());
+}
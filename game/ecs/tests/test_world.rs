@@ -0,0 +1,105 @@
+use shine_ecs::{
+    resources::{MultiRes, Res, ResMut},
+    scheduler::{IntoSystem, TaskGroup, WithMultiRes},
+    testing::{assert_debug_eq, debug_diff, TestWorld},
+    ECSError,
+};
+
+mod utils;
+
+#[derive(Debug, PartialEq)]
+struct Position(f32, f32);
+
+#[derive(Debug, PartialEq)]
+struct Velocity(f32, f32);
+
+#[derive(Debug, PartialEq, Default)]
+struct Stats {
+    frames: usize,
+    total: u32,
+}
+
+fn count_frames(mut stats: ResMut<Stats>) -> Result<TaskGroup, ECSError> {
+    stats.frames += 1;
+    Ok(TaskGroup::default())
+}
+
+fn sum_tagged(values: MultiRes<u32>, mut stats: ResMut<Stats>) -> Result<TaskGroup, ECSError> {
+    stats.total += (0..values.len()).map(|i| values[i]).sum::<u32>();
+    Ok(TaskGroup::default())
+}
+
+fn scale(factor: Res<f32>, mut stats: ResMut<Stats>) -> Result<TaskGroup, ECSError> {
+    stats.total = (stats.total as f32 * *factor) as u32;
+    Ok(TaskGroup::default())
+}
+
+#[test]
+fn run_stages() {
+    utils::init_logger();
+
+    let mut world = TestWorld::new()
+        .with_resource(Stats::default())
+        .with_resource(2.0f32)
+        .with_tagged_resource("one", 1u32)
+        .with_tagged_resource("two", 2u32)
+        .with_system("update", count_frames.into_system())
+        .with_system(
+            "update",
+            sum_tagged
+                .into_system()
+                .try_claim_res::<u32, _>(|claim| claim.try_add_tags(&["one", "two"]))
+                .unwrap(),
+        );
+
+    world.run_frames(&["update"], 3).unwrap();
+    world.assert_resource(&Stats { frames: 3, total: 9 });
+
+    world.run_system(scale.into_system()).unwrap();
+    world.assert_resource(&Stats { frames: 3, total: 18 });
+}
+
+#[test]
+fn entities() {
+    utils::init_logger();
+
+    let world = TestWorld::new()
+        .with_entity((Position(0., 0.), Velocity(1., 0.)))
+        .with_entity((Position(1., 1.),));
+
+    let entities = world.entities();
+    let mut positions = entities
+        .query::<&Position>()
+        .iter()
+        .map(|(_, pos)| (pos.0, pos.1))
+        .collect::<Vec<_>>();
+    positions.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert_eq!(positions, vec![(0., 0.), (1., 1.)]);
+    assert_eq!(entities.query::<(&Position, &Velocity)>().iter().count(), 1);
+}
+
+#[test]
+#[should_panic(expected = "Unknown stage: missing")]
+fn unknown_stage() {
+    utils::init_logger();
+
+    let mut world = TestWorld::new();
+    let _ = world.run_stage("missing");
+}
+
+#[test]
+fn diff() {
+    utils::init_logger();
+
+    assert_eq!(debug_diff("a\nb\nc", "a\nb\nc"), "  a\n  b\n  c\n");
+    assert_eq!(debug_diff("a\nx\nc", "a\nb\nc"), "  a\n+ x\n- b\n  c\n");
+    assert_eq!(debug_diff("a\nc", "a\nb\nc"), "  a\n- b\n  c\n");
+}
+
+#[test]
+#[should_panic(expected = "+     frames: 1,\n-     frames: 2,")]
+fn assert_diff() {
+    utils::init_logger();
+
+    assert_debug_eq(&Stats { frames: 1, total: 0 }, &Stats { frames: 2, total: 0 });
+}
@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AnimationEventError {
+    #[error("Failed to parse animation events")]
+    Parse(#[from] serde_json::Error),
+
+    #[error("Invalid event marker {name} at {time}")]
+    InvalidMarker { name: String, time: f32 },
+}
+
+/// A named marker on the timeline of a clip (ex. footstep, attack hit)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventMarker {
+    pub time: f32,
+    pub name: String,
+}
+
+impl EventMarker {
+    pub fn new<S: Into<String>>(time: f32, name: S) -> EventMarker {
+        EventMarker {
+            time,
+            name: name.into(),
+        }
+    }
+}
+
+/// The event markers of a clip sorted by time
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AnimationEventTrack {
+    markers: Vec<EventMarker>,
+}
+
+/// Event track as given in the glTF extras of an animation: `{"events": [{"time": 0.5, "name": "footstep"}]}`
+#[derive(Deserialize)]
+struct EventTrackSource {
+    #[serde(default)]
+    events: Vec<EventMarker>,
+}
+
+impl AnimationEventTrack {
+    pub fn new(mut markers: Vec<EventMarker>) -> Result<AnimationEventTrack, AnimationEventError> {
+        if let Some(marker) = markers.iter().find(|m| !m.time.is_finite() || m.time < 0.) {
+            return Err(AnimationEventError::InvalidMarker {
+                name: marker.name.clone(),
+                time: marker.time,
+            });
+        }
+        markers.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap());
+        Ok(AnimationEventTrack { markers })
+    }
+
+    /// Parse the track from the extras of a glTF animation
+    pub fn from_gltf_extras(extras: &str) -> Result<AnimationEventTrack, AnimationEventError> {
+        let source: EventTrackSource = serde_json::from_str(extras)?;
+        Self::new(source.events)
+    }
+
+    /// Parse the tracks of the clips from a sidecar file: `{"walk": {"events": [...]}, "attack": {...}}`
+    pub fn from_sidecar(data: &str) -> Result<HashMap<String, AnimationEventTrack>, AnimationEventError> {
+        let source: HashMap<String, EventTrackSource> = serde_json::from_str(data)?;
+        source
+            .into_iter()
+            .map(|(clip, track)| Ok((clip, Self::new(track.events)?)))
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.markers.is_empty()
+    }
+
+    pub fn markers(&self) -> &[EventMarker] {
+        &self.markers
+    }
+
+    /// Merge the markers of the other track, ex. sidecar markers into the cooked ones.
+    pub fn merge(&mut self, other: AnimationEventTrack) {
+        self.markers.extend(other.markers);
+        self.markers.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap());
+    }
+
+    /// Call the visitor with the markers in the [start, end) range (or [start, end] if `inclusive_end`)
+    /// in increasing time order.
+    pub fn visit_forward<F: FnMut(&EventMarker)>(&self, start: f32, end: f32, inclusive_end: bool, mut visitor: F) {
+        let first = self
+            .markers
+            .iter()
+            .position(|m| m.time >= start)
+            .unwrap_or_else(|| self.markers.len());
+        for marker in &self.markers[first..] {
+            if marker.time > end || (!inclusive_end && marker.time == end) {
+                break;
+            }
+            visitor(marker);
+        }
+    }
+
+    /// Call the visitor with the markers in the (end, start] range (or [end, start] if `inclusive_end`)
+    /// in decreasing time order.
+    pub fn visit_backward<F: FnMut(&EventMarker)>(&self, start: f32, end: f32, inclusive_end: bool, mut visitor: F) {
+        let last = self
+            .markers
+            .iter()
+            .position(|m| m.time > start)
+            .unwrap_or_else(|| self.markers.len());
+        for marker in self.markers[..last].iter().rev() {
+            if marker.time < end || (!inclusive_end && marker.time == end) {
+                break;
+            }
+            visitor(marker);
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+/// An event marker crossed by the playback of a clip
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnimationEvent {
+    /// The emitter of the event (ex. entity id)
+    pub source: u64,
+    pub clip: String,
+    pub name: String,
+    /// Time of the marker in the clip
+    pub time: f32,
+}
+
+/// Animation events of the current frame. The events are collected by the animation players and
+/// consumed by the audio and gameplay systems, the queue is cleared at the start of each frame.
+#[derive(Default, Debug)]
+pub struct AnimationEvents {
+    events: Vec<AnimationEvent>,
+}
+
+impl AnimationEvents {
+    pub fn push(&mut self, source: u64, clip: &str, name: &str, time: f32) {
+        log::trace!("Animation event {}:{}:{} at {}", source, clip, name, time);
+        self.events.push(AnimationEvent {
+            source,
+            clip: clip.to_owned(),
+            name: name.to_owned(),
+            time,
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &AnimationEvent> {
+        self.events.iter()
+    }
+
+    /// Iterate the events with the given name
+    pub fn iter_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a AnimationEvent> + 'a {
+        self.events.iter().filter(move |e| e.name == name)
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+}
@@ -0,0 +1,8 @@
+mod event_track;
+pub use self::event_track::*;
+mod events;
+pub use self::events::*;
+mod player;
+pub use self::player::*;
+mod plugin;
+pub use self::plugin::*;
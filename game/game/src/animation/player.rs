@@ -0,0 +1,115 @@
+use crate::animation::{AnimationEventTrack, AnimationEvents, EventMarker};
+use std::sync::Arc;
+
+/// Playback state of a clip emitting the crossed event markers
+pub struct AnimationPlayer {
+    source: u64,
+    clip: String,
+    duration: f32,
+    track: Arc<AnimationEventTrack>,
+    time: f32,
+    speed: f32,
+    looping: bool,
+}
+
+impl AnimationPlayer {
+    /// Create a player, the source is reported in the events to identify the emitter (ex. entity id)
+    pub fn new<S: Into<String>>(source: u64, clip: S, duration: f32, track: Arc<AnimationEventTrack>) -> Self {
+        AnimationPlayer {
+            source,
+            clip: clip.into(),
+            duration: duration.max(0.),
+            track,
+            time: 0.,
+            speed: 1.,
+            looping: false,
+        }
+    }
+
+    pub fn with_looping(self, looping: bool) -> Self {
+        AnimationPlayer { looping, ..self }
+    }
+
+    pub fn with_speed(self, speed: f32) -> Self {
+        AnimationPlayer { speed, ..self }
+    }
+
+    pub fn clip(&self) -> &str {
+        &self.clip
+    }
+
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    /// Jump to a time without emitting the skipped events
+    pub fn seek(&mut self, time: f32) {
+        self.time = time.max(0.).min(self.duration);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        !self.looping && ((self.speed > 0. && self.time >= self.duration) || (self.speed < 0. && self.time <= 0.))
+    }
+
+    /// Advance the playback and emit the events of the crossed markers in the order of crossing.
+    pub fn advance(&mut self, dt: f32, events: &mut AnimationEvents) {
+        if self.duration <= 0. || self.speed == 0. || self.is_finished() {
+            return;
+        }
+
+        let start = self.time;
+        let end = start + dt * self.speed;
+        let source = self.source;
+        let clip = &self.clip;
+        let mut emit = |marker: &EventMarker| {
+            events.push(source, clip, &marker.name, marker.time);
+        };
+
+        if !self.looping {
+            let end = end.max(0.).min(self.duration);
+            if end > start {
+                self.track.visit_forward(start, end, end >= self.duration, &mut emit);
+            } else {
+                self.track.visit_backward(start, end, end <= 0., &mut emit);
+            }
+            self.time = end;
+        } else if end > start {
+            // unroll the loops, cycle by cycle
+            let mut cycle_start = start;
+            let mut remaining = end - start;
+            while remaining > 0. {
+                let cycle_end = (cycle_start + remaining).min(self.duration);
+                if cycle_end <= cycle_start {
+                    break;
+                }
+                self.track
+                    .visit_forward(cycle_start, cycle_end, cycle_end >= self.duration, &mut emit);
+                remaining -= cycle_end - cycle_start;
+                cycle_start = if cycle_end >= self.duration { 0. } else { cycle_end };
+            }
+            self.time = cycle_start;
+        } else {
+            let mut cycle_start = start;
+            let mut remaining = start - end;
+            while remaining > 0. {
+                let cycle_end = (cycle_start - remaining).max(0.);
+                if cycle_end >= cycle_start {
+                    break;
+                }
+                self.track
+                    .visit_backward(cycle_start, cycle_end, cycle_end <= 0., &mut emit);
+                remaining -= cycle_start - cycle_end;
+                cycle_start = if cycle_end <= 0. && remaining > 0. {
+                    self.duration
+                } else {
+                    cycle_end
+                };
+            }
+            self.time = cycle_start;
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use crate::{
+    animation::AnimationEvents,
+    app::{AppError, Plugin, PluginFuture},
+    World,
+};
+use std::{borrow::Cow, error::Error as StdError};
+
+pub const ANIMATION_PLUGIN_NAME: &str = "animation";
+
+pub struct AnimationPlugin;
+
+fn into_plugin_err<E: 'static + StdError>(error: E) -> AppError {
+    AppError::game(ANIMATION_PLUGIN_NAME, error)
+}
+
+impl Plugin for AnimationPlugin {
+    fn name() -> Cow<'static, str> {
+        ANIMATION_PLUGIN_NAME.into()
+    }
+
+    fn init(self, world: &mut World) -> PluginFuture<()> {
+        Box::pin(async move {
+            world
+                .resources
+                .init_resource::<AnimationEvents>()
+                .map_err(into_plugin_err)?;
+            Ok(())
+        })
+    }
+
+    fn deinit(world: &mut World) -> PluginFuture<()> {
+        Box::pin(async move {
+            let _ = world.resources.unregister::<AnimationEvents>();
+            Ok(())
+        })
+    }
+}
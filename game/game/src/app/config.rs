@@ -34,7 +34,8 @@ impl Config {
 
         s.merge(Environment::new().separator("--"))?;
 
-        if let Some(config_file) = env::args().nth(1) {
+        // the options (ex. --smoke-test) follow the config file
+        if let Some(config_file) = env::args().nth(1).filter(|arg| !arg.starts_with("--")) {
             log::info!("Loading cofig file {:?}", config_file);
             s.merge(File::from(Path::new(&config_file)))?;
         }
@@ -1,8 +1,15 @@
 use crate::{app::AppError, World};
+use shine_ecs::resources::ResourceScope;
 use std::{future::Future, pin::Pin};
 
 pub type GameFuture<'a, R> = Pin<Box<dyn Future<Output = R> + 'a>>;
 
+/// Scope of the resources, stages and cleanups of the loaded game. The scope is released when the game
+/// is unloaded, thus nothing is leaked between game loads.
+pub struct GameScope;
+
+impl ResourceScope for GameScope {}
+
 /// Source of a game
 pub trait GameSource {
     fn build(self) -> Result<Box<dyn GameLifecycle>, AppError>
@@ -6,6 +6,8 @@ mod game_lifecycle;
 pub use self::game_lifecycle::*;
 mod plugin;
 pub use self::plugin::*;
+mod smoke_test;
+pub use self::smoke_test::*;
 
 use crate::World;
 use std::collections::HashSet;
@@ -51,15 +53,26 @@ impl App {
         self.deinit_game().await?;
         let mut game_loader = game.build()?;
         log::info!("Creating game {}", game_loader.name());
-        game_loader.create(&mut self.world).await?;
+        if let Err(err) = game_loader.create(&mut self.world).await {
+            self.release_game_scope();
+            return Err(err);
+        }
         self.game_loader = Some(game_loader);
         Ok(())
     }
 
+    /// Release everything bound to the game scope. Stages are owned by the games, thus they are also cleared.
+    fn release_game_scope(&mut self) {
+        self.world.release_scope::<GameScope>();
+        self.world.clear_stages();
+    }
+
     pub async fn deinit_game(&mut self) -> Result<(), AppError> {
         if let Some(mut game_loader) = self.game_loader.take() {
             log::info!("Destroying game {}", game_loader.name());
-            game_loader.destroy(&mut self.world).await?;
+            let result = game_loader.destroy(&mut self.world).await;
+            self.release_game_scope();
+            result?;
         }
         Ok(())
     }
@@ -67,7 +80,9 @@ impl App {
     pub async fn reload_game(&mut self) -> Result<(), AppError> {
         if let Some(game_loader) = &mut self.game_loader {
             log::info!("Reloading game {} - destroy", game_loader.name());
-            game_loader.destroy(&mut self.world).await?;
+            let result = game_loader.destroy(&mut self.world).await;
+            self.release_game_scope();
+            result?;
             log::info!("Reloading game {} - create", game_loader.name());
             game_loader.create(&mut self.world).await?;
         }
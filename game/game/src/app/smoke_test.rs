@@ -0,0 +1,366 @@
+use crate::{app::AppError, render::RenderError};
+use serde::{Deserialize, Serialize};
+use shine_ecs::events::{EventCursor, Events};
+use std::{error::Error as StdError, fmt};
+
+#[derive(Debug)]
+pub struct SmokeTestArgError(String);
+
+impl fmt::Display for SmokeTestArgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid smoke test argument: {}", self.0)
+    }
+}
+
+impl StdError for SmokeTestArgError {}
+
+fn into_smoke_test_err<E: 'static + StdError>(error: E) -> AppError {
+    AppError::game("smoke-test", error)
+}
+
+/// A key event of the scripted input, keys are identified by the scancode as in the input mappers
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScriptedKey {
+    pub frame: u32,
+    pub scancode: u32,
+    pub pressed: bool,
+}
+
+/// Input of the smoke test
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SmokeTestInput {
+    None,
+    /// Random key presses of the given keys, the same seed gives the same input
+    Random {
+        seed: u64,
+        scancodes: Vec<u32>,
+    },
+    /// Recorded key events
+    Script(Vec<ScriptedKey>),
+}
+
+impl Default for SmokeTestInput {
+    fn default() -> SmokeTestInput {
+        SmokeTestInput::None
+    }
+}
+
+fn default_frames() -> u32 {
+    300
+}
+
+/// Configuration of the smoke test. On native it is parsed from the command line, on wasm it is given
+/// as json by the page.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SmokeTestConfig {
+    /// Url of the cooked game
+    pub game: String,
+    #[serde(default = "default_frames")]
+    pub frames: u32,
+    #[serde(default)]
+    pub input: SmokeTestInput,
+    /// Path of the screenshot of the last frame
+    #[serde(default)]
+    pub screenshot: Option<String>,
+    /// The test fails if the average frame time exceeds the limit
+    #[serde(default)]
+    pub max_frame_time_ms: Option<f64>,
+}
+
+/// Default keys of the random input: wasd, space, arrows
+const RANDOM_SCANCODES: [u32; 9] = [17, 30, 31, 32, 57, 72, 75, 77, 80];
+
+impl SmokeTestConfig {
+    pub fn new(game: &str) -> SmokeTestConfig {
+        SmokeTestConfig {
+            game: game.to_owned(),
+            frames: default_frames(),
+            input: SmokeTestInput::None,
+            screenshot: None,
+            max_frame_time_ms: None,
+        }
+    }
+
+    /// Parse the command line, returns None if the `--smoke-test <game url>` option is not present.
+    /// Options: `--frames <n>`, `--seed <n>` for random input, `--input <script.json>` to replay a list of
+    /// `ScriptedKey`s, `--screenshot <path>` and `--max-frame-time <ms>`.
+    pub fn from_args<I: IntoIterator<Item = String>>(args: I) -> Result<Option<SmokeTestConfig>, AppError> {
+        fn value<I: Iterator<Item = String>>(args: &mut I, name: &str) -> Result<String, AppError> {
+            args.next()
+                .ok_or_else(|| into_smoke_test_err(SmokeTestArgError(format!("missing value of {}", name))))
+        }
+
+        fn parse<T: std::str::FromStr>(value: String, name: &str) -> Result<T, AppError> {
+            value
+                .parse()
+                .map_err(|_| into_smoke_test_err(SmokeTestArgError(format!("invalid value of {}: {}", name, value))))
+        }
+
+        let mut game = None;
+        let mut frames = None;
+        let mut seed = None;
+        let mut script = None;
+        let mut screenshot = None;
+        let mut max_frame_time_ms = None;
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--smoke-test" => game = Some(value(&mut args, &arg)?),
+                "--frames" => frames = Some(parse(value(&mut args, &arg)?, &arg)?),
+                "--seed" => seed = Some(parse(value(&mut args, &arg)?, &arg)?),
+                "--input" => script = Some(value(&mut args, &arg)?),
+                "--screenshot" => screenshot = Some(value(&mut args, &arg)?),
+                "--max-frame-time" => max_frame_time_ms = Some(parse(value(&mut args, &arg)?, &arg)?),
+                _ => {}
+            }
+        }
+
+        let game = match game {
+            Some(game) => game,
+            None => return Ok(None),
+        };
+        let input = match (script, seed) {
+            (Some(script), _) => {
+                let data = std::fs::read(&script).map_err(into_smoke_test_err)?;
+                SmokeTestInput::Script(serde_json::from_slice(&data).map_err(into_smoke_test_err)?)
+            }
+            (None, Some(seed)) => SmokeTestInput::Random {
+                seed,
+                scancodes: RANDOM_SCANCODES.to_vec(),
+            },
+            (None, None) => SmokeTestInput::None,
+        };
+
+        Ok(Some(SmokeTestConfig {
+            frames: frames.unwrap_or_else(default_frames),
+            input,
+            screenshot,
+            max_frame_time_ms,
+            ..SmokeTestConfig::new(&game)
+        }))
+    }
+
+    pub fn from_json(config: &str) -> Result<SmokeTestConfig, AppError> {
+        serde_json::from_str(config).map_err(into_smoke_test_err)
+    }
+}
+
+/// Generate the key events of the frames from the input of the smoke test
+pub struct SmokeInputSource {
+    input: SmokeTestInput,
+    rng: u64,
+    held: Vec<(u32, u32)>,
+}
+
+impl SmokeInputSource {
+    /// Max number of frames a random key is held
+    const MAX_HOLD: u64 = 30;
+
+    pub fn new(input: SmokeTestInput) -> SmokeInputSource {
+        let seed = match &input {
+            SmokeTestInput::Random { seed, .. } => *seed,
+            _ => 0,
+        };
+        SmokeInputSource {
+            input,
+            // xorshift requires a non-zero state
+            rng: (seed ^ 0x9e37_79b9_7f4a_7c15).max(1),
+            held: Vec::new(),
+        }
+    }
+
+    fn next_random(&mut self) -> u64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        self.rng
+    }
+
+    /// The key events of a frame, the frames shall be queried in order
+    pub fn events(&mut self, frame: u32) -> Vec<ScriptedKey> {
+        match &self.input {
+            SmokeTestInput::None => Vec::new(),
+            SmokeTestInput::Script(events) => events.iter().filter(|e| e.frame == frame).cloned().collect(),
+            SmokeTestInput::Random { scancodes, .. } => {
+                let scancodes = scancodes.clone();
+                let mut events = Vec::new();
+
+                let (released, held) = self.held.drain(..).partition(|(_, until)| *until <= frame);
+                self.held = held;
+                for (scancode, _) in released {
+                    events.push(ScriptedKey {
+                        frame,
+                        scancode,
+                        pressed: false,
+                    });
+                }
+
+                // press a new key in about every 4th frame
+                if !scancodes.is_empty() && self.next_random() % 4 == 0 {
+                    let scancode = scancodes[(self.next_random() % scancodes.len() as u64) as usize];
+                    if self.held.iter().all(|(s, _)| *s != scancode) {
+                        let hold = 1 + (self.next_random() % Self::MAX_HOLD) as u32;
+                        self.held.push((scancode, frame + hold));
+                        events.push(ScriptedKey {
+                            frame,
+                            scancode,
+                            pressed: true,
+                        });
+                    }
+                }
+                events
+            }
+        }
+    }
+
+    /// Release the keys held at the end of the test
+    pub fn release_all(&mut self, frame: u32) -> Vec<ScriptedKey> {
+        self.held
+            .drain(..)
+            .map(|(scancode, _)| ScriptedKey {
+                frame,
+                scancode,
+                pressed: false,
+            })
+            .collect()
+    }
+}
+
+/// Frame time statistics in milliseconds
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FrameTimeStats {
+    pub min: f64,
+    pub max: f64,
+    pub average: f64,
+}
+
+/// Result of the smoke test, serialized as json for the nightly content validation
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SmokeTestReport {
+    pub game: String,
+    pub frames: u32,
+    pub frame_time: FrameTimeStats,
+    /// The render errors reported during the test
+    pub render_errors: Vec<String>,
+    /// The errors failing the test
+    pub failures: Vec<String>,
+    pub screenshot: Option<String>,
+}
+
+impl SmokeTestReport {
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty() && self.render_errors.is_empty()
+    }
+
+    /// Exit code of the runner: 0 on success, 1 on failure
+    pub fn exit_code(&self) -> i32 {
+        if self.is_success() {
+            0
+        } else {
+            1
+        }
+    }
+}
+
+/// State of a running smoke test. The runner loads the game, then for each frame injects the input and
+/// records the frame. The render errors are read from the `Events<RenderError>` channel.
+pub struct SmokeTest {
+    config: SmokeTestConfig,
+    input: SmokeInputSource,
+    frame: u32,
+    frame_time_sum: f64,
+    report: SmokeTestReport,
+    cursor: EventCursor<RenderError>,
+}
+
+impl SmokeTest {
+    pub fn new(config: SmokeTestConfig) -> SmokeTest {
+        let input = SmokeInputSource::new(config.input.clone());
+        let report = SmokeTestReport {
+            game: config.game.clone(),
+            ..Default::default()
+        };
+        SmokeTest {
+            config,
+            input,
+            frame: 0,
+            frame_time_sum: 0.,
+            report,
+            cursor: EventCursor::default(),
+        }
+    }
+
+    pub fn config(&self) -> &SmokeTestConfig {
+        &self.config
+    }
+
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.frame >= self.config.frames || !self.report.failures.is_empty()
+    }
+
+    /// The key events to be injected before the next frame
+    pub fn frame_input(&mut self) -> Vec<ScriptedKey> {
+        if self.frame + 1 >= self.config.frames {
+            let mut events = self.input.events(self.frame);
+            events.extend(self.input.release_all(self.frame));
+            events
+        } else {
+            self.input.events(self.frame)
+        }
+    }
+
+    /// Record a rendered frame and the render errors of the frame
+    pub fn record_frame(&mut self, frame_time_ms: f64, errors: Option<&Events<RenderError>>) {
+        if let Some(errors) = errors {
+            for error in self.cursor.read(errors) {
+                log::warn!("Smoke test frame {}: {}", self.frame, error);
+                self.report.render_errors.push(error.to_string());
+            }
+        }
+
+        let stats = &mut self.report.frame_time;
+        if self.frame == 0 {
+            stats.min = frame_time_ms;
+            stats.max = frame_time_ms;
+        } else {
+            stats.min = stats.min.min(frame_time_ms);
+            stats.max = stats.max.max(frame_time_ms);
+        }
+        self.frame_time_sum += frame_time_ms;
+        self.frame += 1;
+        stats.average = self.frame_time_sum / self.frame as f64;
+        self.report.frames = self.frame;
+    }
+
+    /// Record an error failing the test (ex. the game could not be loaded or a frame could not be rendered)
+    pub fn fail<E: fmt::Display>(&mut self, error: E) {
+        log::error!("Smoke test failed at frame {}: {}", self.frame, error);
+        self.report.failures.push(error.to_string());
+    }
+
+    pub fn set_screenshot(&mut self, path: &str) {
+        self.report.screenshot = Some(path.to_owned());
+    }
+
+    pub fn finish(mut self) -> SmokeTestReport {
+        if self.report.failures.is_empty() && self.frame < self.config.frames {
+            self.report
+                .failures
+                .push(format!("Only {} of {} frames rendered", self.frame, self.config.frames));
+        }
+        if let Some(limit) = self.config.max_frame_time_ms {
+            if self.frame > 0 && self.report.frame_time.average > limit {
+                self.report.failures.push(format!(
+                    "Average frame time {:.2}ms exceeds the limit of {:.2}ms",
+                    self.report.frame_time.average, limit
+                ));
+            }
+        }
+        self.report
+    }
+}
@@ -1,37 +1,104 @@
 use crate::assets::{Url, UrlError};
 use std::fmt;
 
+/// Canonicalize the path part of an asset id or url:
+/// - separators are normalized to '/' and repeated separators are merged,
+/// - "." segments are removed and ".." segments are resolved,
+/// - extension is converted to lower case.
+/// A leading '/' is preserved and absolute paths may not escape the root using "..". For relative
+/// paths the unresolved ".." segments are kept.
+pub fn canonicalize_path(path: &str) -> Result<String, UrlError> {
+    let path = path.replace('\\', "/");
+    let is_absolute = path.starts_with('/');
+
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => match segments.last() {
+                Some(&"..") | None if !is_absolute => segments.push(".."),
+                Some(&"..") | None => return Err(UrlError::RelativeUrlWithoutBase),
+                Some(_) => {
+                    segments.pop();
+                }
+            },
+            segment => segments.push(segment),
+        }
+    }
+
+    let mut canonical = if is_absolute { "/".to_owned() } else { String::new() };
+    if let Some((file, folders)) = segments.split_last() {
+        for folder in folders {
+            canonical.push_str(folder);
+            canonical.push('/');
+        }
+        let mut parts = file.splitn(2, '.');
+        let stem = parts.next().unwrap_or("");
+        canonical.push_str(stem);
+        if let Some(ext) = parts.next() {
+            canonical.push('.');
+            canonical.push_str(&ext.to_lowercase());
+        }
+    }
+    Ok(canonical)
+}
+
 /// Id of an asset used to identify asset in the container
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct AssetId {
     inner: String,
 }
 
 impl AssetId {
+    /// Create an asset id in the canonical form. Ids with schema (urls) are also accepted, in that case only
+    /// the path is canonicalized.
     pub fn new<S: ToString>(id: S) -> Result<AssetId, UrlError> {
         let id = id.to_string();
         if id.chars().any(|c| c == '?' || c == '&') {
             Err(UrlError::InvalidDomainCharacter)
-        } else if id.starts_with("./") {
+        } else if id.starts_with("./") || id.starts_with("../") {
             Err(UrlError::RelativeUrlWithoutBase)
+        } else if let Some(pos) = id.find("://") {
+            let (scheme, path) = id.split_at(pos + 3);
+            Ok(AssetId {
+                inner: format!("{}{}", scheme.to_lowercase(), canonicalize_path(path)?),
+            })
         } else {
-            Ok(AssetId { inner: id })
+            let inner = canonicalize_path(&id)?;
+            if inner == ".." || inner.starts_with("../") {
+                // id is escaping the root
+                Err(UrlError::RelativeUrlWithoutBase)
+            } else {
+                Ok(AssetId { inner })
+            }
         }
     }
 
+    /// Create an id relative to the folder of this asset if id starts with "./" or "../", otherwise
+    /// id is taken as is.
     pub fn create_relative(&self, id: &str) -> Result<AssetId, UrlError> {
-        if let Some(id) = id.strip_prefix("./") {
+        if id.starts_with("./") || id.starts_with("../") {
             let (folder, _) = self.split_folder();
             if let Some(folder) = folder {
                 AssetId::new(&format!("{}/{}", folder, id))
             } else {
-                AssetId::new(id)
+                AssetId::new(canonicalize_path(id)?)
             }
         } else {
             AssetId::new(id)
         }
     }
 
+    /// Return the id relative to the scope (folder) if the asset is in the given scope.
+    pub fn scope_relative(&self, scope: &AssetId) -> Option<&str> {
+        let scope = scope.as_str().trim_end_matches('/');
+        if scope.is_empty() {
+            Some(&self.inner)
+        } else {
+            self.inner.strip_prefix(scope).and_then(|id| id.strip_prefix('/'))
+        }
+    }
+
     pub fn as_str(&self) -> &str {
         &self.inner
     }
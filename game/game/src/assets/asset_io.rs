@@ -1,11 +1,24 @@
 use crate::assets::io::AssetLowIO;
-use crate::assets::{AssetError, ContentHash, Url};
-use std::collections::HashMap;
-use std::sync::Arc;
+use crate::assets::{
+    self, AssetError, AssetUsageReport, AssetUsageTracker, BuildStampTracker, ContentHash, EntitlementVerifier,
+    PremiumPack, Url,
+};
+#[cfg(feature = "cook")]
+use crate::assets::{DevSource, DEV_SOURCE_SCHEME};
+use shine_input::input_timestamp;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
 
 struct Inner {
     io: AssetLowIO,
     virtual_schemes: HashMap<String, Url>,
+    premium_packs: HashMap<String, PremiumPack>,
+    entitlement_verifier: Option<EntitlementVerifier>,
+    mounted_packs: RwLock<HashSet<String>>,
+    build_stamps: BuildStampTracker,
+    usage: AssetUsageTracker,
+    #[cfg(feature = "cook")]
+    dev_source: RwLock<Option<Arc<DevSource>>>,
 }
 
 #[derive(Clone)]
@@ -15,31 +28,158 @@ pub struct AssetIO {
 
 impl AssetIO {
     pub fn new(virtual_schemes: HashMap<String, Url>) -> Result<AssetIO, AssetError> {
+        AssetIO::with_premium_packs(virtual_schemes, HashMap::new(), None)
+    }
+
+    /// Create an io where the premium packs are virtual schemes accessible only after they are mounted
+    /// by a valid entitlement token. Without a verifier the premium packs cannot be mounted.
+    pub fn with_premium_packs(
+        virtual_schemes: HashMap<String, Url>,
+        premium_packs: HashMap<String, PremiumPack>,
+        entitlement_verifier: Option<EntitlementVerifier>,
+    ) -> Result<AssetIO, AssetError> {
         Ok(AssetIO {
             inner: Arc::new(Inner {
                 io: AssetLowIO::new()?,
                 virtual_schemes,
+                premium_packs,
+                entitlement_verifier,
+                mounted_packs: RwLock::new(HashSet::new()),
+                build_stamps: BuildStampTracker::new(),
+                usage: AssetUsageTracker::new(),
+                #[cfg(feature = "cook")]
+                dev_source: RwLock::new(None),
             }),
         })
     }
 
+    /// Verify the entitlement token and mount the premium packs owned by the user. Packs not listed in
+    /// the token are unmounted. Returns the schemes of the mounted packs.
+    pub fn mount_premium_packs(&self, token: &str) -> Result<Vec<String>, AssetError> {
+        let verifier = self
+            .inner
+            .entitlement_verifier
+            .as_ref()
+            .ok_or_else(|| AssetError::InvalidEntitlement("No entitlement key configured".to_owned()))?;
+        let claims = verifier.verify(token, assets::unix_now())?;
+
+        let mut schemes: Vec<String> = self
+            .inner
+            .premium_packs
+            .iter()
+            .filter(|(_, pack)| claims.has_content(&pack.content_id))
+            .map(|(scheme, _)| scheme.clone())
+            .collect();
+        schemes.sort();
+        log::info!("Premium packs mounted for {}: {:?}", claims.sub, schemes);
+
+        let mut mounted = self.inner.mounted_packs.write().unwrap();
+        *mounted = schemes.iter().cloned().collect();
+        Ok(schemes)
+    }
+
+    pub fn unmount_premium_packs(&self) {
+        self.inner.mounted_packs.write().unwrap().clear();
+    }
+
+    pub fn is_mounted(&self, scheme: &str) -> bool {
+        self.inner.mounted_packs.read().unwrap().contains(scheme)
+    }
+
+    /// Serve the `dev-source` scheme by cooking the sources of the given root on the fly
+    #[cfg(feature = "cook")]
+    pub fn mount_dev_source(&self, source_root: Url) {
+        log::info!("Dev source mounted from {}", source_root);
+        *self.inner.dev_source.write().unwrap() = Some(Arc::new(DevSource::new(source_root)));
+    }
+
+    #[cfg(feature = "cook")]
+    fn dev_source(&self, url: &Url) -> Result<Option<Arc<DevSource>>, AssetError> {
+        if url.scheme() != DEV_SOURCE_SCHEME {
+            return Ok(None);
+        }
+        match &*self.inner.dev_source.read().unwrap() {
+            Some(dev_source) => Ok(Some(dev_source.clone())),
+            None => Err(AssetError::UnsupportedScheme(format!(
+                "{} (not mounted)",
+                DEV_SOURCE_SCHEME
+            ))),
+        }
+    }
+
+    /// Resolve the virtual scheme of the url. The path of the url is also canonicalized to ensure the
+    /// same asset is always accessed through the same location.
     pub fn resolve_virtual_scheme(&self, url: &Url) -> Result<Url, AssetError> {
+        let url = url.to_canonical()?;
         let scheme = url.scheme().to_owned();
         if let Some(base) = self.inner.virtual_schemes.get(&scheme) {
             Ok(url.replace_virtual_scheme(base)?)
+        } else if let Some(pack) = self.inner.premium_packs.get(&scheme) {
+            if self.is_mounted(&scheme) {
+                Ok(url.replace_virtual_scheme(&pack.url)?)
+            } else {
+                Err(AssetError::NotEntitled(scheme))
+            }
         } else {
-            Ok(url.clone())
+            Ok(url)
         }
     }
 
+    /// Record the loaded assets and report them to the asset database service by `upload_usage_report`
+    pub fn enable_usage_report(&self, report_url: Url) {
+        self.inner.usage.enable(report_url);
+    }
+
+    /// Take the usage recorded since the last report
+    pub fn take_usage_report(&self) -> AssetUsageReport {
+        self.inner.usage.set_build_id(self.inner.build_stamps.build_id());
+        self.inner.usage.take_report()
+    }
+
+    /// Send the usage recorded since the last report to the asset database service, it is a nop if the
+    /// reporting is not enabled or no asset was loaded.
+    pub async fn upload_usage_report(&self) -> Result<(), AssetError> {
+        let report_url = match self.inner.usage.report_url() {
+            Some(report_url) => report_url,
+            None => return Ok(()),
+        };
+        let report = self.take_usage_report();
+        if report.is_empty() {
+            return Ok(());
+        }
+
+        log::info!("Reporting the usage of {} assets", report.assets.len());
+        let report =
+            serde_json::to_string(&report).map_err(|err| AssetError::other("Failed to serialize usage report", err))?;
+        let report_url = self.resolve_virtual_scheme(&report_url)?;
+        self.inner.io.upload_binary(&report_url, report.as_bytes()).await
+    }
+
     pub async fn download_hash(&self, url: &Url) -> Result<ContentHash, AssetError> {
+        #[cfg(feature = "cook")]
+        {
+            if let Some(dev_source) = self.dev_source(url)? {
+                return Ok(ContentHash::from_bytes(&dev_source.cook(self, url).await?));
+            }
+        }
         let url = self.resolve_virtual_scheme(url)?;
         self.inner.io.download_hash(&url).await
     }
 
     pub async fn download_binary(&self, url: &Url) -> Result<Vec<u8>, AssetError> {
-        let url = self.resolve_virtual_scheme(url)?;
-        self.inner.io.download_binary(&url).await
+        #[cfg(feature = "cook")]
+        {
+            if let Some(dev_source) = self.dev_source(url)? {
+                return dev_source.cook(self, url).await;
+            }
+        }
+        let resolved_url = self.resolve_virtual_scheme(url)?;
+        let start = input_timestamp();
+        let data = self.inner.io.download_binary(&resolved_url).await?;
+        let load_time = input_timestamp().saturating_sub(start) as u64;
+        self.inner.build_stamps.observe(url, &data);
+        self.inner.usage.observe(url, data.len(), load_time);
+        Ok(data)
     }
 
     pub async fn download_string(&self, url: &Url) -> Result<String, AssetError> {
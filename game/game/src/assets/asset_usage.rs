@@ -0,0 +1,174 @@
+use crate::assets::Url;
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    sync::{Mutex, RwLock},
+};
+
+/// Load statistics of an asset in a session, the times are in microseconds
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AssetLoadStats {
+    pub loads: u32,
+    pub bytes: u64,
+    pub total_load_time: u64,
+    pub max_load_time: u64,
+    /// Index of the first load of the asset in the session
+    pub first_load: u32,
+}
+
+/// The cooked assets loaded in a session, reported to the asset database service
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AssetUsageReport {
+    /// The build of the cooked assets
+    pub build_id: Option<String>,
+    /// Load statistics by the requested url
+    pub assets: BTreeMap<String, AssetLoadStats>,
+}
+
+impl AssetUsageReport {
+    pub fn is_empty(&self) -> bool {
+        self.assets.is_empty()
+    }
+}
+
+/// Track the loaded assets and their load times. Nothing is recorded until the reporting is enabled.
+#[derive(Default)]
+pub struct AssetUsageTracker {
+    report_url: RwLock<Option<Url>>,
+    report: Mutex<AssetUsageReport>,
+}
+
+impl AssetUsageTracker {
+    pub fn new() -> AssetUsageTracker {
+        AssetUsageTracker::default()
+    }
+
+    pub fn enable(&self, report_url: Url) {
+        log::info!("Asset usage is reported to {}", report_url.as_str());
+        *self.report_url.write().unwrap() = Some(report_url);
+    }
+
+    pub fn report_url(&self) -> Option<Url> {
+        self.report_url.read().unwrap().clone()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.report_url.read().unwrap().is_some()
+    }
+
+    /// Record a load of an asset, the load time is given in microseconds
+    pub fn observe(&self, url: &Url, bytes: usize, load_time: u64) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut report = self.report.lock().unwrap();
+        let next_load = report.assets.len() as u32;
+        let stats = report
+            .assets
+            .entry(url.as_str().to_owned())
+            .or_insert_with(|| AssetLoadStats {
+                first_load: next_load,
+                ..Default::default()
+            });
+        stats.loads += 1;
+        stats.bytes = bytes as u64;
+        stats.total_load_time += load_time;
+        stats.max_load_time = stats.max_load_time.max(load_time);
+    }
+
+    pub fn set_build_id(&self, build_id: Option<String>) {
+        self.report.lock().unwrap().build_id = build_id;
+    }
+
+    /// Take the recorded usage, the statistics are restarted
+    pub fn take_report(&self) -> AssetUsageReport {
+        let mut report = self.report.lock().unwrap();
+        AssetUsageReport {
+            build_id: report.build_id.clone(),
+            assets: std::mem::take(&mut report.assets),
+        }
+    }
+}
+
+/// Usage of an asset aggregated over the reported sessions, the times are in microseconds
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AssetUsageSummary {
+    /// Number of sessions loading the asset
+    pub sessions: u32,
+    pub loads: u64,
+    pub bytes: u64,
+    pub total_load_time: u64,
+    pub max_load_time: u64,
+    /// Sum of the first load indices of the sessions
+    pub first_load_sum: u64,
+}
+
+impl AssetUsageSummary {
+    pub fn average_load_time(&self) -> u64 {
+        if self.loads == 0 {
+            0
+        } else {
+            self.total_load_time / self.loads
+        }
+    }
+
+    pub fn average_first_load(&self) -> f64 {
+        if self.sessions == 0 {
+            0.
+        } else {
+            self.first_load_sum as f64 / self.sessions as f64
+        }
+    }
+}
+
+/// Asset usage aggregated from the session reports, stored by the asset database service
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AssetUsageStats {
+    pub sessions: u32,
+    pub assets: BTreeMap<String, AssetUsageSummary>,
+}
+
+impl AssetUsageStats {
+    pub fn merge(&mut self, report: &AssetUsageReport) {
+        self.sessions += 1;
+        for (url, stats) in &report.assets {
+            let summary = self.assets.entry(url.clone()).or_default();
+            summary.sessions += 1;
+            summary.loads += stats.loads as u64;
+            summary.bytes = stats.bytes;
+            summary.total_load_time += stats.total_load_time;
+            summary.max_load_time = summary.max_load_time.max(stats.max_load_time);
+            summary.first_load_sum += stats.first_load as u64;
+        }
+    }
+
+    /// The assets in the preload order: the assets used by the most sessions first, then in the order
+    /// they are usually requested.
+    pub fn preload_order(&self) -> Vec<String> {
+        let mut assets = self.assets.iter().collect::<Vec<_>>();
+        assets.sort_by(|(a_url, a), (b_url, b)| {
+            b.sessions
+                .cmp(&a.sessions)
+                .then(
+                    a.average_first_load()
+                        .partial_cmp(&b.average_first_load())
+                        .unwrap_or(Ordering::Equal),
+                )
+                .then(a_url.cmp(b_url))
+        });
+        assets.into_iter().map(|(url, _)| url.clone()).collect()
+    }
+
+    /// The assets by the total time spent on loading them, the optimization candidates first
+    pub fn by_load_time(&self) -> Vec<(&str, &AssetUsageSummary)> {
+        let mut assets = self
+            .assets
+            .iter()
+            .map(|(url, summary)| (url.as_str(), summary))
+            .collect::<Vec<_>>();
+        assets.sort_by(|(a_url, a), (b_url, b)| b.total_load_time.cmp(&a.total_load_time).then(a_url.cmp(b_url)));
+        assets
+    }
+}
@@ -0,0 +1,120 @@
+use crate::assets::{AssetError, ContentHash, Url};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+/// Header of the stamp line prepended to the cooked content. The line is a comment for the RON documents,
+/// thus the stamped debug assets remain readable by the standard tools.
+const STAMP_HEADER: &str = "//shine-build:";
+
+/// Version of the cooker and the toolchain producing a cooked asset. Assets cooked by different versions
+/// may use different (binary) formats, thus mixing them is warned by the loaders.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildStamp {
+    pub cooker_version: String,
+    /// Hash of the shader compiler version
+    pub shader_compiler: String,
+    /// Hash of the texture encoder version
+    pub texture_encoder: String,
+    pub profile: String,
+}
+
+impl BuildStamp {
+    /// Create a stamp for the toolchain linked into this build.
+    #[cfg(feature = "cook")]
+    pub fn current(cooker_version: &str, profile: &str) -> BuildStamp {
+        use crate::assets::{ShaderSource, TextureSource};
+        BuildStamp {
+            cooker_version: cooker_version.to_owned(),
+            shader_compiler: ContentHash::from_str(&ShaderSource::toolchain()).hash()[..16].to_owned(),
+            texture_encoder: ContentHash::from_str(&TextureSource::toolchain()).hash()[..16].to_owned(),
+            profile: profile.to_owned(),
+        }
+    }
+
+    /// Deterministic id of the build, equal for the stamps of the same cooker version, toolchain and profile.
+    pub fn build_id(&self) -> String {
+        ContentHash::from_multiple_bytes(&[
+            self.cooker_version.as_bytes(),
+            self.shader_compiler.as_bytes(),
+            self.texture_encoder.as_bytes(),
+            self.profile.as_bytes(),
+        ])
+        .hash()[..16]
+            .to_owned()
+    }
+
+    /// Prepend the stamp to some cooked content.
+    pub fn stamp(&self, content: &[u8]) -> Result<Vec<u8>, AssetError> {
+        let stamp = serde_json::to_string(self).map_err(|err| AssetError::other("Failed to serialize stamp", err))?;
+        let mut data = Vec::with_capacity(STAMP_HEADER.len() + stamp.len() + 1 + content.len());
+        data.extend_from_slice(STAMP_HEADER.as_bytes());
+        data.extend_from_slice(stamp.as_bytes());
+        data.push(b'\n');
+        data.extend_from_slice(content);
+        Ok(data)
+    }
+
+    /// Split the stamp from the cooked content. The content of the assets cooked before the stamping was
+    /// introduced is returned as is.
+    pub fn split(data: &[u8]) -> Result<(Option<BuildStamp>, &[u8]), AssetError> {
+        if !data.starts_with(STAMP_HEADER.as_bytes()) {
+            return Ok((None, data));
+        }
+        let end = data
+            .iter()
+            .position(|&c| c == b'\n')
+            .ok_or_else(|| AssetError::load_failed_str("build stamp", "Unterminated stamp line"))?;
+        let stamp = serde_json::from_slice(&data[STAMP_HEADER.len()..end])
+            .map_err(|err| AssetError::other("Failed to deserialize stamp", err))?;
+        Ok((Some(stamp), &data[end + 1..]))
+    }
+}
+
+/// Track the build stamps of the loaded assets and warn once for each build mixed into the first one.
+#[derive(Default)]
+pub struct BuildStampTracker {
+    seen: Mutex<Vec<String>>,
+}
+
+impl BuildStampTracker {
+    pub fn new() -> BuildStampTracker {
+        BuildStampTracker::default()
+    }
+
+    /// The build of the first stamped asset
+    pub fn build_id(&self) -> Option<String> {
+        self.seen.lock().unwrap().first().cloned()
+    }
+
+    /// Record the stamp of the downloaded content, returns false if it differs from the first stamped asset.
+    pub fn observe(&self, url: &Url, data: &[u8]) -> bool {
+        let stamp = match BuildStamp::split(data) {
+            Ok((Some(stamp), _)) => stamp,
+            _ => return true,
+        };
+
+        let build_id = stamp.build_id();
+        let mut seen = self.seen.lock().unwrap();
+        match seen.first() {
+            None => {
+                log::info!("Assets cooked by {} ({:?})", build_id, stamp);
+                seen.push(build_id);
+                true
+            }
+            Some(first) if *first == build_id => true,
+            Some(first) => {
+                if !seen.contains(&build_id) {
+                    log::warn!(
+                        "[{}] Cooked by a different build ({:?}, {}) than the previous assets ({}), formats may be incompatible",
+                        url.as_str(),
+                        stamp,
+                        build_id,
+                        first
+                    );
+                    seen.push(build_id);
+                }
+                false
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::assets::{AssetError, BuildStamp};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Header of the RON encoded cooked content. As RON supports comments, the content remains
+/// a valid RON document that can be inspected by the standard tools.
+const RON_HEADER: &str = "//shine-cooked:ron\n";
+
+/// Serialization format of the cooked assets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CookedFormat {
+    /// Compact binary format used for release builds
+    Binary,
+
+    /// Self-describing, human readable format for debugging
+    Ron,
+}
+
+impl Default for CookedFormat {
+    fn default() -> Self {
+        CookedFormat::Binary
+    }
+}
+
+impl CookedFormat {
+    /// Detect the format of some (optionally stamped) cooked content.
+    pub fn detect(data: &[u8]) -> CookedFormat {
+        let data = BuildStamp::split(data).map(|(_, data)| data).unwrap_or(data);
+        if data.starts_with(RON_HEADER.as_bytes()) {
+            CookedFormat::Ron
+        } else {
+            CookedFormat::Binary
+        }
+    }
+
+    pub fn serialize<T: Serialize>(self, value: &T) -> Result<Vec<u8>, AssetError> {
+        match self {
+            CookedFormat::Binary => {
+                bincode::serialize(value).map_err(|err| AssetError::other("Failed to serialize (bincode)", err))
+            }
+            CookedFormat::Ron => {
+                let content = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+                    .map_err(|err| AssetError::other("Failed to serialize (ron)", err))?;
+                Ok(format!("{}{}", RON_HEADER, content).into_bytes())
+            }
+        }
+    }
+
+    /// Deserialize cooked content, the format is detected automatically and the build stamp is skipped.
+    pub fn deserialize<T: DeserializeOwned>(data: &[u8]) -> Result<T, AssetError> {
+        let (_, data) = BuildStamp::split(data)?;
+        match CookedFormat::detect(data) {
+            CookedFormat::Binary => {
+                bincode::deserialize(data).map_err(|err| AssetError::other("Failed to deserialize (bincode)", err))
+            }
+            CookedFormat::Ron => {
+                let content = std::str::from_utf8(&data[RON_HEADER.len()..])
+                    .map_err(|err| AssetError::other("Failed to deserialize (ron)", err))?;
+                ron::de::from_str(content).map_err(|err| AssetError::other("Failed to deserialize (ron)", err))
+            }
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use crate::assets::{
+    AssetError, AssetIO, AssetId, ContentHash, CookedFormat, GltfSource, ShaderSource, TextureSource, Url,
+};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+
+/// Scheme of the uncooked source assets cooked on the fly
+pub const DEV_SOURCE_SCHEME: &str = "dev-source";
+
+pub type DevSourceFuture<'a> = Pin<Box<dyn Future<Output = Result<Vec<u8>, AssetError>> + Send + 'a>>;
+
+/// Read the raw source assets (shaders, textures, gltf models) and cook them in-process, thus the
+/// `dev-source://<asset id>` urls can be used instead of the cooked urls during development.
+/// The cooked content is cached by the hash of the source, only the changed sources are cooked again.
+pub struct DevSource {
+    source_root: Url,
+    cooked: Mutex<HashMap<String, (ContentHash, Vec<u8>)>>,
+}
+
+impl DevSource {
+    pub fn new(source_root: Url) -> DevSource {
+        DevSource {
+            source_root,
+            cooked: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn source_root(&self) -> &Url {
+        &self.source_root
+    }
+
+    fn find_cooked(&self, source_id: &AssetId, source_hash: &ContentHash) -> Option<Vec<u8>> {
+        let cooked = self.cooked.lock().unwrap();
+        match cooked.get(source_id.as_str()) {
+            Some((hash, content)) if hash.hash() == source_hash.hash() => Some(content.clone()),
+            _ => None,
+        }
+    }
+
+    fn store_cooked(&self, source_id: &AssetId, source_hash: ContentHash, content: &[u8]) {
+        let mut cooked = self.cooked.lock().unwrap();
+        cooked.insert(source_id.as_str().to_owned(), (source_hash, content.to_owned()));
+    }
+
+    /// Download the source of the `dev-source` url and return the cooked content. The sources are downloaded
+    /// through the io, thus they can be located on any (virtual) scheme.
+    pub fn cook<'a>(&'a self, io: &'a AssetIO, url: &'a Url) -> DevSourceFuture<'a> {
+        Box::pin(async move {
+            let source_url = url.replace_virtual_scheme(&self.source_root)?;
+            let source_id = source_url
+                .relative_path(&self.source_root)
+                .ok_or_else(|| AssetError::InvalidAssetId(url.as_str().to_owned()))
+                .and_then(|id| AssetId::new(id).map_err(AssetError::from))?;
+            let format = CookedFormat::Binary;
+            let cook_err = |err| AssetError::load_failed(&source_id, err);
+
+            let content = match source_id.extension() {
+                "vs" | "fs" | "cs" => {
+                    let (source, source_hash) = ShaderSource::load(io, &source_id, &source_url).await?;
+                    if let Some(content) = self.find_cooked(&source_id, &source_hash) {
+                        return Ok(content);
+                    }
+                    let content = format.serialize(&source.cook().await.map_err(cook_err)?)?;
+                    self.store_cooked(&source_id, source_hash, &content);
+                    content
+                }
+                "jpg" | "png" => {
+                    let (source, source_hash) = TextureSource::load(io, &source_id, &source_url).await?;
+                    if let Some(content) = self.find_cooked(&source_id, &source_hash) {
+                        return Ok(content);
+                    }
+                    let content = format.serialize(&source.cook().await.map_err(cook_err)?)?;
+                    self.store_cooked(&source_id, source_hash, &content);
+                    content
+                }
+                "glb" | "gltf" => {
+                    let (source, source_hash) = GltfSource::load(io, &source_id, &source_url).await?;
+                    if let Some(content) = self.find_cooked(&source_id, &source_hash) {
+                        return Ok(content);
+                    }
+                    let content = format.serialize(&source.cook().await.map_err(cook_err)?)?;
+                    self.store_cooked(&source_id, source_hash, &content);
+                    content
+                }
+                ext => return Err(AssetError::UnsupportedFormat(ext.to_owned())),
+            };
+
+            log::debug!("[{}] Cooked on the fly from {}", source_id, source_url);
+            Ok(content)
+        })
+    }
+}
@@ -0,0 +1,92 @@
+use crate::assets::{AssetError, Url};
+use ring::signature::{UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+
+const TOKEN_BASE_ENCODE: data_encoding::Encoding = data_encoding::BASE64URL_NOPAD;
+
+/// A premium content pack mounted under a virtual scheme only if the user owns the content.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PremiumPack {
+    pub url: Url,
+    /// Id of the content (DLC) granted by the entitlement service
+    pub content_id: String,
+}
+
+/// Claims of an entitlement token issued by the backend
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EntitlementClaims {
+    pub sub: String,
+    pub content: Vec<String>,
+    /// Expiration in seconds since the unix epoch
+    pub exp: i64,
+}
+
+impl EntitlementClaims {
+    pub fn has_content(&self, content_id: &str) -> bool {
+        self.content.iter().any(|c| c == content_id)
+    }
+}
+
+/// Seconds since the unix epoch
+#[cfg(feature = "native")]
+pub fn unix_now() -> i64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Seconds since the unix epoch
+#[cfg(feature = "wasm")]
+pub fn unix_now() -> i64 {
+    (js_sys::Date::now() / 1000.) as i64
+}
+
+/// Check the signature of the entitlement tokens with the public key of the entitlement service.
+/// The token is the BASE64URL encoded json claims and the Ed25519 signature of the encoded claims
+/// separated by a dot.
+#[derive(Clone, Debug)]
+pub struct EntitlementVerifier {
+    public_key: Vec<u8>,
+}
+
+impl EntitlementVerifier {
+    pub fn new(public_key: Vec<u8>) -> EntitlementVerifier {
+        EntitlementVerifier { public_key }
+    }
+
+    pub fn from_base64(public_key: &str) -> Result<EntitlementVerifier, AssetError> {
+        let public_key = data_encoding::BASE64
+            .decode(public_key.as_bytes())
+            .map_err(|err| AssetError::InvalidEntitlement(format!("Invalid public key: {}", err)))?;
+        Ok(EntitlementVerifier::new(public_key))
+    }
+
+    /// Verify the signature and the expiration of a token at the given time (seconds since the unix epoch).
+    pub fn verify(&self, token: &str, now: i64) -> Result<EntitlementClaims, AssetError> {
+        let mut parts = token.splitn(2, '.');
+        let (payload, signature) = match (parts.next(), parts.next()) {
+            (Some(payload), Some(signature)) => (payload, signature),
+            _ => return Err(AssetError::InvalidEntitlement("Malformed token".to_owned())),
+        };
+
+        let signature = TOKEN_BASE_ENCODE
+            .decode(signature.as_bytes())
+            .map_err(|err| AssetError::InvalidEntitlement(format!("Malformed signature: {}", err)))?;
+        UnparsedPublicKey::new(&ED25519, &self.public_key)
+            .verify(payload.as_bytes(), &signature)
+            .map_err(|_| AssetError::InvalidEntitlement("Signature mismatch".to_owned()))?;
+
+        let payload = TOKEN_BASE_ENCODE
+            .decode(payload.as_bytes())
+            .map_err(|err| AssetError::InvalidEntitlement(format!("Malformed claims: {}", err)))?;
+        let claims: EntitlementClaims = serde_json::from_slice(&payload)
+            .map_err(|err| AssetError::InvalidEntitlement(format!("Malformed claims: {}", err)))?;
+        if claims.exp <= now {
+            return Err(AssetError::InvalidEntitlement("Token expired".to_owned()));
+        }
+
+        Ok(claims)
+    }
+}
@@ -35,6 +35,12 @@ pub enum AssetError {
         source: Box<dyn 'static + StdError + Sync + Send>,
     },
 
+    #[error("Invalid entitlement: {0}")]
+    InvalidEntitlement(String),
+
+    #[error("Content of the {0} scheme is not owned")]
+    NotEntitled(String),
+
     #[error("Error in content: {0}")]
     Content(String),
 
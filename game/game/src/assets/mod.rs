@@ -9,6 +9,14 @@ mod asset_id;
 pub use self::asset_id::*;
 mod content_hash;
 pub use self::content_hash::*;
+mod cooked_format;
+pub use self::cooked_format::*;
+mod build_stamp;
+pub use self::build_stamp::*;
+mod asset_usage;
+pub use self::asset_usage::*;
+mod entitlement;
+pub use self::entitlement::*;
 mod asset_io;
 pub use self::asset_io::*;
 mod plugin;
@@ -25,3 +33,7 @@ pub use self::pipeline::*;
 
 #[cfg(feature = "cook")]
 pub mod cooker;
+#[cfg(feature = "cook")]
+mod dev_source;
+#[cfg(feature = "cook")]
+pub use self::dev_source::*;
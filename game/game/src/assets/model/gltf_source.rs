@@ -1,9 +1,11 @@
+use crate::animation::AnimationEventTrack;
 use crate::assets::{
-    cooker::CookingError, vertex, AssetError, AssetIO, AssetId, ContentHash, CookedModel, IndexData, MeshData, Url,
-    VertexData,
+    cooker::CookingError, vertex, AlphaMode, AssetError, AssetIO, AssetId, ContentHash, CookedModel, IndexData,
+    MeshData, Url, VertexData,
 };
-use gltf::{accessor::Dimensions, buffer, Document, Gltf, Primitive, Semantic};
+use gltf::{accessor::Dimensions, buffer, material, Document, Gltf, Material, Primitive, Semantic};
 use itertools::izip;
+use std::collections::HashMap;
 
 pub struct GltfSource {
     pub source_id: AssetId,
@@ -35,6 +37,25 @@ impl GltfSource {
         Ok((gltf, source_hash))
     }
 
+    /// Collect the event markers of the animations from the glTF extras. Animations without a name
+    /// are indexed by their position.
+    pub fn animation_event_tracks(&self) -> Result<HashMap<String, AnimationEventTrack>, CookingError> {
+        let mut tracks = HashMap::new();
+        for animation in self.document.animations() {
+            if let Some(extras) = animation.extras() {
+                let name = animation
+                    .name()
+                    .map(|n| n.to_owned())
+                    .unwrap_or_else(|| format!("{}", animation.index()));
+                let track = AnimationEventTrack::from_gltf_extras(extras.get())
+                    .map_err(|err| CookingError::from_err(&self.source_id, err))?;
+                log::debug!("[{}] Animation {} events: {:?}", self.source_id, name, track);
+                tracks.insert(name, track);
+            }
+        }
+        Ok(tracks)
+    }
+
     pub async fn cook(self) -> Result<CookedModel, CookingError> {
         log::debug!("[{}] Compiling...", self.source_id);
 
@@ -99,6 +120,15 @@ impl GltfSource {
 }
 
 ///Load data from url
+/// Alpha mode of a glTF material, the cutoff defaults to 0.5 as defined by the specification.
+pub fn material_alpha_mode(material: &Material<'_>) -> AlphaMode {
+    match material.alpha_mode() {
+        material::AlphaMode::Opaque => AlphaMode::Opaque,
+        material::AlphaMode::Mask => AlphaMode::Mask(material.alpha_cutoff()),
+        material::AlphaMode::Blend => AlphaMode::Blend,
+    }
+}
+
 fn load_source(source_id: &AssetId, uri: &str) -> Result<Vec<u8>, AssetError> {
     if let Some(stripped) = uri.strip_prefix("data:") {
         let mut split = stripped.split(";base64,");
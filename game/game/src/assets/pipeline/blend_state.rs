@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+
+/// Blending of the color attachments
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
+pub enum ColorStage {
+    /// Opaque rendering, source replaces the destination
+    Replace,
+    /// Classic alpha blending: src * src_alpha + dst * (1 - src_alpha)
+    AlphaBlend,
+    /// Alpha blending where the source color is already multiplied by alpha
+    PremultipliedAlpha,
+    /// Additive blending, ex. for particles and light shafts
+    Additive,
+    /// Weighted blended order independent transparency. The first color target accumulates the
+    /// weighted premultiplied color, the second one the revealage.
+    WeightedBlended,
+}
+
+impl Default for ColorStage {
+    fn default() -> Self {
+        ColorStage::Replace
+    }
+}
+
+impl ColorStage {
+    /// Return if the pipeline requires the transparent (sorted) render queue
+    pub fn is_transparent(&self) -> bool {
+        *self != ColorStage::Replace
+    }
+
+    pub fn color_blend(&self) -> wgpu::BlendDescriptor {
+        match self {
+            ColorStage::Replace => wgpu::BlendDescriptor::REPLACE,
+            ColorStage::AlphaBlend => wgpu::BlendDescriptor {
+                src_factor: wgpu::BlendFactor::SrcAlpha,
+                dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                operation: wgpu::BlendOperation::Add,
+            },
+            ColorStage::PremultipliedAlpha => wgpu::BlendDescriptor {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                operation: wgpu::BlendOperation::Add,
+            },
+            ColorStage::Additive => wgpu::BlendDescriptor {
+                src_factor: wgpu::BlendFactor::SrcAlpha,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+            ColorStage::WeightedBlended => wgpu::BlendDescriptor {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+        }
+    }
+
+    pub fn alpha_blend(&self) -> wgpu::BlendDescriptor {
+        match self {
+            ColorStage::Replace => wgpu::BlendDescriptor::REPLACE,
+            ColorStage::AlphaBlend | ColorStage::PremultipliedAlpha => wgpu::BlendDescriptor {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                operation: wgpu::BlendOperation::Add,
+            },
+            ColorStage::Additive => wgpu::BlendDescriptor {
+                src_factor: wgpu::BlendFactor::Zero,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+            ColorStage::WeightedBlended => wgpu::BlendDescriptor {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+        }
+    }
+
+    /// Blending of the revealage target of the weighted blended transparency: dst * (1 - src)
+    pub fn revealage_blend() -> wgpu::BlendDescriptor {
+        wgpu::BlendDescriptor {
+            src_factor: wgpu::BlendFactor::Zero,
+            dst_factor: wgpu::BlendFactor::OneMinusSrcColor,
+            operation: wgpu::BlendOperation::Add,
+        }
+    }
+
+    /// Apply the blending to a color state
+    pub fn apply(&self, state: &mut wgpu::ColorStateDescriptor) {
+        state.color_blend = self.color_blend();
+        state.alpha_blend = self.alpha_blend();
+    }
+
+    /// Apply the blending to all the color targets of a pipeline.
+    /// For the weighted blended transparency the second target is the revealage.
+    pub fn apply_all(&self, states: &mut [wgpu::ColorStateDescriptor]) {
+        for (index, state) in states.iter_mut().enumerate() {
+            if *self == ColorStage::WeightedBlended && index == 1 {
+                state.color_blend = Self::revealage_blend();
+                state.alpha_blend = Self::revealage_blend();
+            } else {
+                self.apply(state);
+            }
+        }
+    }
+}
+
+/// Alpha mode of a material (following the glTF convention)
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub enum AlphaMode {
+    Opaque,
+    /// Alpha tested rendering with the given cutoff. It is rendered in the opaque queue,
+    /// the fragment shader discards the fragments below the cutoff.
+    Mask(f32),
+    Blend,
+}
+
+impl Default for AlphaMode {
+    fn default() -> Self {
+        AlphaMode::Opaque
+    }
+}
+
+impl AlphaMode {
+    pub fn color_stage(&self) -> ColorStage {
+        match self {
+            AlphaMode::Opaque | AlphaMode::Mask(_) => ColorStage::Replace,
+            AlphaMode::Blend => ColorStage::AlphaBlend,
+        }
+    }
+
+    /// Shader defines required by the alpha mode
+    pub fn shader_defines(&self) -> Vec<(String, String)> {
+        match self {
+            AlphaMode::Mask(cutoff) => vec![
+                ("ALPHA_MASK".to_owned(), "1".to_owned()),
+                ("ALPHA_CUTOFF".to_owned(), format!("{:?}", cutoff)),
+            ],
+            _ => Vec::new(),
+        }
+    }
+}
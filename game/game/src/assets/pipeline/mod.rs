@@ -1,3 +1,5 @@
+mod blend_state;
+pub use self::blend_state::*;
 mod pipeline_descriptor;
 pub use self::pipeline_descriptor::*;
 mod uniform_descriptor;
@@ -1,4 +1,4 @@
-use crate::assets::{AssetError, UniformSemantic, VertexBufferLayout, VertexSemantic};
+use crate::assets::{AssetError, ColorStage, UniformSemantic, VertexBufferLayout, VertexSemantic};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashMap, HashSet},
@@ -110,9 +110,18 @@ pub struct PipelineDescriptor {
     pub primitive_topology: wgpu::PrimitiveTopology,
     pub vertex_stage: VertexStage,
     pub fragment_stage: FragmentStage,
+    #[serde(default)]
+    pub color_stage: ColorStage,
+    /// Enable depth write, if not given only the opaque pipelines write the depth
+    #[serde(default)]
+    pub depth_write: Option<bool>,
 }
 
 impl PipelineDescriptor {
+    pub fn is_depth_write_enabled(&self, color_stage: ColorStage) -> bool {
+        self.depth_write.unwrap_or_else(|| !color_stage.is_transparent())
+    }
+
     pub fn get_uniform_layout(&self) -> Result<PipelineUniformLayout, AssetError> {
         // store the uniform info for each location for each group to check validity
         let mut check: HashMap<u32, (UniformSemantic, u32, wgpu::ShaderStage)> = Default::default();
@@ -211,4 +220,15 @@ impl DerefMut for PipelineUniformLayout {
 pub struct PipelineStateDescriptor {
     pub color_states: Vec<wgpu::ColorStateDescriptor>,
     pub depth_state: Option<wgpu::DepthStencilStateDescriptor>,
+    /// Override the blending of the pipeline descriptor, ex. by the alpha mode of a material
+    pub color_stage: Option<ColorStage>,
+}
+
+impl PipelineStateDescriptor {
+    pub fn with_color_stage(self, color_stage: ColorStage) -> Self {
+        PipelineStateDescriptor {
+            color_stage: Some(color_stage),
+            ..self
+        }
+    }
 }
@@ -12,6 +12,18 @@ pub enum TextureSemantic {
     Normal,
     Frame(FrameName),
     Custom(TextureName),
+    /// Cube map sampled by direction
+    Cube(TextureName),
+}
+
+impl TextureSemantic {
+    /// Dimension of the texture view bound to the uniform
+    pub fn view_dimension(&self) -> wgpu::TextureViewDimension {
+        match self {
+            TextureSemantic::Cube(_) => wgpu::TextureViewDimension::Cube,
+            _ => wgpu::TextureViewDimension::D2,
+        }
+    }
 }
 
 /// Shader parameters a.k.a uniforms
@@ -1,6 +1,6 @@
 use crate::{
     app::{AppError, Plugin, PluginFuture},
-    assets::{AssetIO, Url},
+    assets::{AssetIO, EntitlementVerifier, PremiumPack, Url},
     World,
 };
 use serde::{Deserialize, Serialize};
@@ -11,6 +11,19 @@ pub const ASSET_PLUGIN_NAME: &str = "asset";
 #[derive(Clone, Default, Debug, Serialize, Deserialize)]
 pub struct AssetConfig {
     pub virtual_schemes: HashMap<String, Url>,
+    /// Premium content packs by virtual scheme, mounted by an entitlement token
+    #[serde(default)]
+    pub premium_packs: HashMap<String, PremiumPack>,
+    /// BASE64 encoded public key of the entitlement service
+    #[serde(default)]
+    pub entitlement_key: Option<String>,
+    /// Root of the uncooked sources served through the `dev-source` scheme, requires the cook feature
+    #[serde(default)]
+    pub dev_source_root: Option<Url>,
+    /// Endpoint of the asset database service collecting the usage of the cooked assets, the usage is not
+    /// reported if not given
+    #[serde(default)]
+    pub usage_report_url: Option<Url>,
 }
 
 pub struct AssetPlugin {
@@ -34,7 +47,25 @@ impl Plugin for AssetPlugin {
 
     fn init(self, world: &mut World) -> PluginFuture<()> {
         Box::pin(async move {
-            let asset_io = AssetIO::new(self.config.virtual_schemes).map_err(into_plugin_err)?;
+            let verifier = self
+                .config
+                .entitlement_key
+                .as_deref()
+                .map(EntitlementVerifier::from_base64)
+                .transpose()
+                .map_err(into_plugin_err)?;
+            let asset_io =
+                AssetIO::with_premium_packs(self.config.virtual_schemes, self.config.premium_packs, verifier)
+                    .map_err(into_plugin_err)?;
+            if let Some(dev_source_root) = self.config.dev_source_root {
+                #[cfg(feature = "cook")]
+                asset_io.mount_dev_source(dev_source_root);
+                #[cfg(not(feature = "cook"))]
+                log::warn!("Dev source ({}) requires the cook feature", dev_source_root);
+            }
+            if let Some(usage_report_url) = self.config.usage_report_url {
+                asset_io.enable_usage_report(usage_report_url);
+            }
             world
                 .resources
                 .register_with_instance(asset_io)
@@ -45,7 +76,13 @@ impl Plugin for AssetPlugin {
 
     fn deinit(world: &mut World) -> PluginFuture<()> {
         Box::pin(async move {
+            let asset_io = world.resources.get::<AssetIO>().map(|io| AssetIO::clone(&io));
             world.resources.unregister::<AssetIO>();
+            if let Ok(asset_io) = asset_io {
+                if let Err(err) = asset_io.upload_usage_report().await {
+                    log::warn!("Failed to report the asset usage: {:?}", err);
+                }
+            }
             Ok(())
         })
     }
@@ -1,4 +1,54 @@
 use crate::assets::{cooker::CookingError, AssetError, AssetIO, AssetId, ContentHash, CookedShader, ShaderType, Url};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum ShaderSeverity {
+    Error,
+    Warning,
+}
+
+/// A message of the shader compiler with the location parsed from the shaderc output
+#[derive(Debug, Clone, Serialize)]
+pub struct ShaderDiagnostic {
+    pub severity: ShaderSeverity,
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+impl ShaderDiagnostic {
+    /// Parse the `<name>:<line>: <severity>: <message>` formatted lines of the compiler output
+    fn parse(output: &str, default_severity: ShaderSeverity) -> Vec<ShaderDiagnostic> {
+        output
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .filter_map(|line| {
+                let mut parts = line.splitn(3, ':');
+                let (_name, line_no, rest) = (parts.next()?, parts.next(), parts.next());
+                let line_no = line_no.and_then(|line_no| line_no.trim().parse::<u32>().ok());
+                let (severity, message) = match (line_no, rest.map(|rest| rest.trim())) {
+                    (Some(_), Some(rest)) if rest.starts_with("error:") => {
+                        (ShaderSeverity::Error, rest["error:".len()..].trim())
+                    }
+                    (Some(_), Some(rest)) if rest.starts_with("warning:") => {
+                        (ShaderSeverity::Warning, rest["warning:".len()..].trim())
+                    }
+                    (Some(_), Some(rest)) => (default_severity, rest),
+                    _ => (default_severity, line),
+                };
+                // the summary line, ex. "1 error generated."
+                if line_no.is_none() && message.ends_with("generated.") {
+                    return None;
+                }
+                Some(ShaderDiagnostic {
+                    severity,
+                    line: line_no,
+                    message: message.to_owned(),
+                })
+            })
+            .collect()
+    }
+}
 
 pub struct ShaderSource {
     pub source_id: AssetId,
@@ -8,9 +58,24 @@ pub struct ShaderSource {
 }
 
 impl ShaderSource {
+    /// Version of the compiler toolchain, part of the build stamp of the cooked assets
+    pub fn toolchain() -> String {
+        let (major, minor) = shaderc::get_spirv_version();
+        format!("shaderc-0.7/spirv-{}.{}", major, minor)
+    }
+
     pub async fn load(io: &AssetIO, source_id: &AssetId, source_url: &Url) -> Result<(Self, ContentHash), AssetError> {
         log::debug!("[{}] Downloading from {}...", source_id, source_url);
         let source = io.download_string(&source_url).await?;
+        Self::from_string(source_id, source_url, source)
+    }
+
+    /// Create the source from an in-memory string, ex. the content of an editor.
+    pub fn from_string(
+        source_id: &AssetId,
+        source_url: &Url,
+        source: String,
+    ) -> Result<(Self, ContentHash), AssetError> {
         let ext = source_url.extension();
         let shader_type = ShaderType::from_extension(ext)?;
 
@@ -25,33 +90,70 @@ impl ShaderSource {
         Ok((source, source_hash))
     }
 
-    pub async fn cook(self) -> Result<CookedShader, CookingError> {
-        log::debug!("[{}] Compiling...", self.source_id);
+    /// Compile the source and return the cooked shader along with the warnings or the list of errors.
+    pub fn compile(&self) -> Result<(CookedShader, Vec<ShaderDiagnostic>), Vec<ShaderDiagnostic>> {
+        log::trace!("[{}] Source ({:?}):\n{}", self.source_id, self.shader_type, self.source);
 
-        let ShaderSource {
-            source_id,
-            shader_type,
-            source,
-            ..
-        } = self;
-
-        log::trace!("[{}] Source ({:?}):\n{}", source_id, shader_type, source);
-
-        let shader_kind = match shader_type {
+        let shader_kind = match self.shader_type {
             ShaderType::Fragment => shaderc::ShaderKind::Fragment,
             ShaderType::Vertex => shaderc::ShaderKind::Vertex,
             ShaderType::Compute => shaderc::ShaderKind::Compute,
         };
 
-        let mut compiler = shaderc::Compiler::new().unwrap();
-        let options = shaderc::CompileOptions::new().unwrap();
+        let internal_error = |message: &str| {
+            vec![ShaderDiagnostic {
+                severity: ShaderSeverity::Error,
+                line: None,
+                message: message.to_owned(),
+            }]
+        };
+
+        let mut compiler = shaderc::Compiler::new().ok_or_else(|| internal_error("Failed to create compiler"))?;
+        let options = shaderc::CompileOptions::new().ok_or_else(|| internal_error("Failed to create options"))?;
         let compiled_artifact = compiler
-            .compile_into_spirv(&source, shader_kind, source_id.as_str(), "main", Some(&options))
-            .map_err(|err| CookingError::from_err(&source_id, err))?;
+            .compile_into_spirv(
+                &self.source,
+                shader_kind,
+                self.source_id.as_str(),
+                "main",
+                Some(&options),
+            )
+            .map_err(|err| match err {
+                shaderc::Error::CompilationError(_, output) => ShaderDiagnostic::parse(&output, ShaderSeverity::Error),
+                err => internal_error(&format!("{}", err)),
+            })?;
 
-        Ok(CookedShader {
-            shader_type,
-            binary: compiled_artifact.as_binary_u8().to_owned(),
-        })
+        let warnings = ShaderDiagnostic::parse(&compiled_artifact.get_warning_messages(), ShaderSeverity::Warning);
+        Ok((
+            CookedShader {
+                shader_type: self.shader_type,
+                binary: compiled_artifact.as_binary_u8().to_owned(),
+            },
+            warnings,
+        ))
+    }
+
+    pub async fn cook(self) -> Result<CookedShader, CookingError> {
+        log::debug!("[{}] Compiling...", self.source_id);
+
+        match self.compile() {
+            Ok((cooked, warnings)) => {
+                for warning in &warnings {
+                    log::warn!("[{}] {:?}: {}", self.source_id, warning.line, warning.message);
+                }
+                Ok(cooked)
+            }
+            Err(errors) => {
+                let message = errors
+                    .iter()
+                    .map(|error| match error.line {
+                        Some(line) => format!("{}: {}", line, error.message),
+                        None => error.message.clone(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                Err(CookingError::from_str(&self.source_id, message))
+            }
+        }
     }
 }
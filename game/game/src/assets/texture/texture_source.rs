@@ -13,6 +13,11 @@ pub struct TextureSource {
 }
 
 impl TextureSource {
+    /// Version of the encoder toolchain, part of the build stamp of the cooked assets
+    pub fn toolchain() -> String {
+        "image-0.23/png/jpeg-80".to_owned()
+    }
+
     pub async fn load(
         io: &AssetIO,
         source_id: &AssetId,
@@ -1,3 +1,4 @@
+use crate::assets::canonicalize_path;
 use std::{fmt, path::PathBuf};
 pub use url::ParseError as UrlError;
 pub use url::Position;
@@ -15,6 +16,17 @@ impl Url {
         })
     }
 
+    /// Return the url with a canonicalized path, see [canonicalize_path].
+    pub fn to_canonical(&self) -> Result<Url, UrlError> {
+        let path = &self.inner[url::Position::BeforeHost..url::Position::AfterPath];
+        Url::parse(&format!(
+            "{}{}{}",
+            &self.inner[..url::Position::BeforeHost],
+            canonicalize_path(path)?,
+            &self.inner[url::Position::AfterPath..]
+        ))
+    }
+
     /*pub fn from_base_or_current(base: &Url, current: &Url, input: &str) -> Result<Self, UrlError> {
         if input.starts_with('/') {
             // input is relative to the base
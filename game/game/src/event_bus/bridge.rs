@@ -0,0 +1,35 @@
+use crate::{animation::AnimationEvents, event_bus::EventBus, savegame::SaveSync};
+use shine_ecs::{
+    resources::{Res, ResMut},
+    scheduler::TaskGroup,
+    ECSError,
+};
+
+/// Prefix of the name of the animation events on the event bus
+pub const ANIMATION_EVENT_PREFIX: &str = "animation.";
+
+/// Forward the animation events of the frame to the event bus. The name of the event is
+/// prefixed by [ANIMATION_EVENT_PREFIX], the payload is the serialized [AnimationEvent](crate::animation::AnimationEvent).
+pub fn bridge_animation_events(events: Res<AnimationEvents>, mut bus: ResMut<EventBus>) -> Result<TaskGroup, ECSError> {
+    for event in events.iter() {
+        let name = format!("{}{}", ANIMATION_EVENT_PREFIX, event.name);
+        if let Err(err) = bus.emit_typed(&name, event) {
+            log::warn!("Failed to serialize animation event {}: {:?}", name, err);
+        }
+    }
+    Ok(TaskGroup::default())
+}
+
+/// Name of the event emitted for each new save conflict, the payload is the serialized
+/// [SaveConflict](crate::savegame::SaveConflict).
+pub const SAVE_CONFLICT_EVENT: &str = "savegame.conflict";
+
+/// Notify the game (and the listening pages) about the new save conflicts to present the resolution choices.
+pub fn bridge_save_conflicts(mut saves: ResMut<SaveSync>, mut bus: ResMut<EventBus>) -> Result<TaskGroup, ECSError> {
+    for conflict in saves.drain_new_conflicts() {
+        if let Err(err) = bus.emit_typed(SAVE_CONFLICT_EVENT, &conflict) {
+            log::warn!("Failed to serialize save conflict {}: {:?}", conflict.slot(), err);
+        }
+    }
+    Ok(TaskGroup::default())
+}
@@ -0,0 +1,103 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Error as JsonError, Value};
+use std::collections::HashSet;
+
+/// An event identified by its name with a json payload
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DynamicEvent {
+    pub name: String,
+    #[serde(default)]
+    pub payload: Value,
+}
+
+/// Name-keyed event bus to exchange events with the scripts and the hosting (web) page without compile-time
+/// knowledge of the event types. The events of the current frame can be read by any system, the queue is
+/// cleared at the start of each frame. Events with a listened name are also queued for the external listeners
+/// until they are drained by the bridge.
+#[derive(Default, Debug)]
+pub struct EventBus {
+    events: Vec<DynamicEvent>,
+    listened: HashSet<String>,
+    outgoing: Vec<DynamicEvent>,
+}
+
+impl EventBus {
+    pub fn emit(&mut self, name: &str, payload: Value) {
+        log::trace!("Event {}: {}", name, payload);
+        let event = DynamicEvent {
+            name: name.to_owned(),
+            payload,
+        };
+        if self.listened.contains(name) {
+            self.outgoing.push(event.clone());
+        }
+        self.events.push(event);
+    }
+
+    pub fn emit_typed<T: Serialize>(&mut self, name: &str, payload: &T) -> Result<(), JsonError> {
+        let payload = serde_json::to_value(payload)?;
+        self.emit(name, payload);
+        Ok(())
+    }
+
+    /// Emit an event with a payload given as a json string
+    pub fn emit_json(&mut self, name: &str, payload: &str) -> Result<(), JsonError> {
+        let payload = serde_json::from_str(payload)?;
+        self.emit(name, payload);
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &DynamicEvent> {
+        self.events.iter()
+    }
+
+    /// Iterate the events with the given name
+    pub fn iter_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a DynamicEvent> + 'a {
+        self.events.iter().filter(move |e| e.name == name)
+    }
+
+    /// Iterate the payloads of the events with the given name parsed into the given type
+    pub fn iter_typed<'a, T: DeserializeOwned>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = Result<T, JsonError>> + 'a {
+        self.iter_named(name).map(|e| serde_json::from_value(e.payload.clone()))
+    }
+
+    /// Forward the events with the given name to the external listeners
+    pub fn listen(&mut self, name: &str) {
+        self.listened.insert(name.to_owned());
+    }
+
+    pub fn unlisten(&mut self, name: &str) {
+        self.listened.remove(name);
+        self.outgoing.retain(|e| e.name != name);
+    }
+
+    pub fn is_listened(&self, name: &str) -> bool {
+        self.listened.contains(name)
+    }
+
+    /// Take the events queued for the external listeners
+    pub fn drain_outgoing(&mut self) -> Vec<DynamicEvent> {
+        self.outgoing.drain(..).collect()
+    }
+
+    /// Take the events queued for the external listeners as a json array
+    pub fn drain_outgoing_json(&mut self) -> Result<String, JsonError> {
+        serde_json::to_string(&self.drain_outgoing())
+    }
+
+    /// Clear the events of the frame. The events queued for the external listeners are kept.
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+}
@@ -0,0 +1,6 @@
+mod bridge;
+pub use self::bridge::*;
+mod bus;
+pub use self::bus::*;
+mod plugin;
+pub use self::plugin::*;
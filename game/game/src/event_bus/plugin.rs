@@ -0,0 +1,34 @@
+use crate::{
+    app::{AppError, Plugin, PluginFuture},
+    event_bus::EventBus,
+    World,
+};
+use std::{borrow::Cow, error::Error as StdError};
+
+pub const EVENT_BUS_PLUGIN_NAME: &str = "event_bus";
+
+pub struct EventBusPlugin;
+
+fn into_plugin_err<E: 'static + StdError>(error: E) -> AppError {
+    AppError::game(EVENT_BUS_PLUGIN_NAME, error)
+}
+
+impl Plugin for EventBusPlugin {
+    fn name() -> Cow<'static, str> {
+        EVENT_BUS_PLUGIN_NAME.into()
+    }
+
+    fn init(self, world: &mut World) -> PluginFuture<()> {
+        Box::pin(async move {
+            world.resources.init_resource::<EventBus>().map_err(into_plugin_err)?;
+            Ok(())
+        })
+    }
+
+    fn deinit(world: &mut World) -> PluginFuture<()> {
+        Box::pin(async move {
+            let _ = world.resources.unregister::<EventBus>();
+            Ok(())
+        })
+    }
+}
@@ -6,15 +6,16 @@ pub use self::source::*;
 mod technique;
 mod test_pass;
 
-use self::technique::Technique;
+use self::technique::{TestTechnique, TEST_TECHNIQUE};
 use self::test_pass::TestPass;
 use crate::{
-    app::{App, AppError, GameFuture, GameLifecycle, GameSource},
-    assets::{AssetError, AssetIO, Url},
+    app::{App, AppError, GameFuture, GameLifecycle, GameScope, GameSource},
+    assets::{AssetError, AssetIO, CookedFormat, Url},
+    input::{InputConfig, InputWorld},
+    render::{ActiveTechniques, TechniqueConfig, TechniqueRegistry},
     World,
 };
 use serde::{Deserialize, Serialize};
-use shine_ecs::scheduler::{IntoSystem, TaskGroup};
 use std::error::Error as StdError;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -31,14 +32,28 @@ pub struct Test1 {
     #[serde(rename = "type")]
     pub ty: Test1Type,
     pub pipeline: String,
+    /// Techniques composing the render stage in pass order, if empty the test technique is used.
+    #[serde(default)]
+    pub techniques: Vec<TechniqueConfig>,
+    /// Input contexts of the game, if empty the input mapper handles all the inputs.
+    #[serde(default)]
+    pub input: InputConfig,
 }
 
 impl Test1 {
+    fn techniques(&self) -> Vec<TechniqueConfig> {
+        if self.techniques.is_empty() {
+            vec![TechniqueConfig::new(TEST_TECHNIQUE)]
+        } else {
+            self.techniques.clone()
+        }
+    }
+
     pub async fn load_into_app(app: &mut App, url: &Url) -> Result<(), AppError> {
         let game = {
             let assetio = app.world.resources.get::<AssetIO>().map_err(into_game_err)?;
             let data = assetio.download_binary(url).await.map_err(into_game_err)?;
-            bincode::deserialize::<Test1>(&data)
+            CookedFormat::deserialize::<Test1>(&data)
                 .map_err(|err| AssetError::load_failed(&url, err))
                 .map_err(into_game_err)?
         };
@@ -59,23 +74,36 @@ impl GameLifecycle for Test1 {
 
     fn create<'a>(&'a mut self, world: &'a mut World) -> GameFuture<'a, Result<(), AppError>> {
         Box::pin(async move {
-            world
-                .resources
-                .register_with_instance(Technique::new(self.pipeline.clone()))
-                .map_err(into_game_err)?;
+            {
+                let pipeline = self.pipeline.clone();
+                let mut registry = world.resources.get_mut::<TechniqueRegistry>().map_err(into_game_err)?;
+                registry.register(TEST_TECHNIQUE, move |config| {
+                    Ok(Box::new(TestTechnique::from_config(config, &pipeline)))
+                });
+            }
+            world.on_release_scope(GameScope, |world| {
+                if let Ok(mut registry) = world.resources.get_mut::<TechniqueRegistry>() {
+                    registry.unregister(TEST_TECHNIQUE);
+                }
+            });
 
-            world.add_stage("render", TaskGroup::from_task(technique::render.into_system()));
+            ActiveTechniques::create_render_stage(world, &self.techniques()).map_err(into_game_err)?;
+            world.on_release_scope(GameScope, ActiveTechniques::destroy_render_stage);
+
+            if !self.input.contexts.is_empty() {
+                world.set_input_contexts(&self.input)?;
+                world.on_release_scope(GameScope, |world| {
+                    let _ = world.set_input_contexts(&InputConfig::default());
+                });
+            }
 
             Ok(())
         })
     }
 
     fn destroy<'a>(&'a mut self, world: &'a mut World) -> GameFuture<'a, Result<(), AppError>> {
-        Box::pin(async move {
-            world.clear_stages();
-            let _ = world.resources.unregister::<Technique>();
-
-            Ok(())
-        })
+        // everything created by the game is bound to the GameScope and released by the app
+        let _ = world;
+        Box::pin(async move { Ok(()) })
     }
 }
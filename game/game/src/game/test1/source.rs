@@ -47,7 +47,12 @@ impl Source {
         log::debug!("[{}] Compiling...", self.source_url);
 
         let Source { source_id, test, .. } = self;
-        let Test1 { ty, pipeline } = test;
+        let Test1 {
+            ty,
+            pipeline,
+            techniques,
+            input,
+        } = test;
 
         log::debug!("[{}] Checking pipeline ({}) dependency...", source_id, pipeline);
         let pip_id = source_id
@@ -58,6 +63,11 @@ impl Source {
             .await?
             .to_string();
 
-        Ok(Test1 { ty, pipeline })
+        Ok(Test1 {
+            ty,
+            pipeline,
+            techniques,
+            input,
+        })
     }
 }
@@ -1,11 +1,18 @@
-use crate::{game::test1::TestPass, render::FrameTarget};
+use crate::{
+    game::test1::TestPass,
+    render::{FrameTarget, RenderError, RenderTechnique, TechniqueConfig},
+    World,
+};
 use shine_ecs::{
     resources::{Res, ResMut},
-    scheduler::{Task, TaskGroup},
+    scheduler::{IntoSystem, Task, TaskGroup, TaskItem},
     ECSError,
 };
 use std::sync::Arc;
 
+/// Name of the technique in the registry
+pub const TEST_TECHNIQUE: &str = "test1";
+
 pub struct Technique {
     test_pass: Arc<Task<TestPass>>,
 }
@@ -22,3 +29,33 @@ pub fn render(tech: ResMut<Technique>, target: Res<FrameTarget>) -> Result<TaskG
     tech.test_pass.system()?.set_render_state(&target);
     Ok(TaskGroup::from_task(tech.test_pass.clone()))
 }
+
+/// Render technique rendering the test pass with the configured pipeline.
+pub struct TestTechnique {
+    pipeline: String,
+}
+
+impl TestTechnique {
+    /// Create the technique. The pipeline can be overridden by the "pipeline" option.
+    pub fn from_config(config: &TechniqueConfig, default_pipeline: &str) -> TestTechnique {
+        TestTechnique {
+            pipeline: config.option("pipeline").unwrap_or(default_pipeline).to_owned(),
+        }
+    }
+}
+
+impl RenderTechnique for TestTechnique {
+    fn create(&mut self, world: &mut World) -> Result<TaskItem, RenderError> {
+        world
+            .resources
+            .register_with_instance(Technique::new(self.pipeline.clone()))
+            .map_err(|err| RenderError::Technique {
+                message: format!("Failed to register {}: {:?}", TEST_TECHNIQUE, err),
+            })?;
+        Ok(render.into_system().into())
+    }
+
+    fn destroy(&mut self, world: &mut World) {
+        world.resources.unregister::<Technique>();
+    }
+}
@@ -1,5 +1,7 @@
 use thiserror::Error;
 
 #[derive(Debug, Error)]
-#[error("Input error")]
-pub struct InputError {}
+pub enum InputError {
+    #[error("Input context error: {}", message)]
+    Context { message: String },
+}
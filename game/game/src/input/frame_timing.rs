@@ -0,0 +1,81 @@
+/// Running statistics of a latency, the times are in microseconds.
+#[derive(Clone, Debug, Default)]
+pub struct LatencyStats {
+    pub count: u64,
+    pub last: u128,
+    pub min: u128,
+    pub max: u128,
+    /// Exponential moving average of the samples
+    pub average: f64,
+}
+
+impl LatencyStats {
+    const SMOOTHING: f64 = 0.1;
+
+    pub fn add(&mut self, sample: u128) {
+        if self.count == 0 {
+            self.min = sample;
+            self.max = sample;
+            self.average = sample as f64;
+        } else {
+            self.min = self.min.min(sample);
+            self.max = self.max.max(sample);
+            self.average += (sample as f64 - self.average) * Self::SMOOTHING;
+        }
+        self.last = sample;
+        self.count += 1;
+    }
+
+    pub fn reset(&mut self) {
+        *self = LatencyStats::default();
+    }
+}
+
+/// End-to-end latency of the inputs: from the capture of the event through the simulation step consuming it
+/// to the presentation of the frame. Only the oldest event of a frame is tracked, thus the stats
+/// show the worst case latency of the frames.
+#[derive(Clone, Debug, Default)]
+pub struct FrameTiming {
+    /// Capture time of the oldest event consumed by the last simulation step, not presented yet
+    simulated_event: Option<u128>,
+    /// Time of the last simulation step, not presented yet
+    simulated_at: Option<u128>,
+    last_present: Option<u128>,
+
+    pub event_to_simulation: LatencyStats,
+    pub simulation_to_present: LatencyStats,
+    pub event_to_present: LatencyStats,
+    /// Time between the presented frames
+    pub frame_time: LatencyStats,
+}
+
+impl FrameTiming {
+    /// Record a simulation step consuming the inputs. If multiple steps are taken before a present,
+    /// the oldest unpresented event is kept.
+    pub fn record_simulation(&mut self, oldest_event: Option<u128>, time: u128) {
+        if let Some(event) = oldest_event {
+            self.event_to_simulation.add(time.saturating_sub(event));
+            self.simulated_event = Some(self.simulated_event.map_or(event, |e| e.min(event)));
+        }
+        if self.simulated_at.is_none() {
+            self.simulated_at = Some(time);
+        }
+    }
+
+    /// Record the presentation of a frame
+    pub fn record_present(&mut self, time: u128) {
+        if let Some(simulated) = self.simulated_at.take() {
+            self.simulation_to_present.add(time.saturating_sub(simulated));
+        }
+        if let Some(event) = self.simulated_event.take() {
+            self.event_to_present.add(time.saturating_sub(event));
+        }
+        if let Some(last) = self.last_present.replace(time) {
+            self.frame_time.add(time.saturating_sub(last));
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = FrameTiming::default();
+    }
+}
@@ -0,0 +1,322 @@
+use std::{collections::HashMap, time::Duration};
+
+/// The rumble motors of a gamepad
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HapticChannel {
+    /// The low frequency (strong) motor
+    Strong,
+    /// The high frequency (weak) motor
+    Weak,
+    Both,
+}
+
+/// A rumble effect queued by the systems
+#[derive(Clone, Copy, Debug)]
+pub struct HapticEffect {
+    pub duration: Duration,
+    /// Strength in the [0,1] range
+    pub strength: f32,
+    pub channel: HapticChannel,
+}
+
+impl HapticEffect {
+    pub fn new(duration: Duration, strength: f32, channel: HapticChannel) -> HapticEffect {
+        HapticEffect {
+            duration,
+            strength,
+            channel,
+        }
+    }
+}
+
+/// The merged effects of a gamepad as sent to the device
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rumble {
+    pub gamepad: usize,
+    pub strong: f32,
+    pub weak: f32,
+    pub duration: Duration,
+}
+
+/// Device specific implementation of the rumble
+pub trait HapticsBackend: 'static + Send + Sync {
+    /// Play a rumble, replacing the active rumble of the gamepad. A zero duration stops the rumble.
+    fn play(&mut self, rumble: &Rumble);
+}
+
+/// Backend ignoring all the effects, for the platforms without force feedback support
+pub struct NullHaptics;
+
+impl HapticsBackend for NullHaptics {
+    fn play(&mut self, _rumble: &Rumble) {
+        /* nop */
+    }
+}
+
+/// Resource where the systems queue the rumble effects. The effects queued for a gamepad in a frame are
+/// merged (the strongest effect wins on each motor) and sent to the backend when flushed.
+pub struct Haptics {
+    enabled: bool,
+    intensity: f32,
+    queue: Vec<(usize, HapticEffect)>,
+    backend: Box<dyn HapticsBackend>,
+}
+
+impl Default for Haptics {
+    fn default() -> Self {
+        Haptics::new(Box::new(NullHaptics))
+    }
+}
+
+impl Haptics {
+    pub fn new(backend: Box<dyn HapticsBackend>) -> Haptics {
+        Haptics {
+            enabled: true,
+            intensity: 1.,
+            queue: Vec::new(),
+            backend,
+        }
+    }
+
+    /// Create the backend of the platform
+    pub fn with_platform_backend() -> Haptics {
+        #[cfg(feature = "native")]
+        let backend: Box<dyn HapticsBackend> = match GilrsHaptics::new() {
+            Ok(backend) => Box::new(backend),
+            Err(err) => {
+                log::warn!("Force feedback is not available: {}", err);
+                Box::new(NullHaptics)
+            }
+        };
+        #[cfg(all(feature = "wasm", not(feature = "native")))]
+        let backend: Box<dyn HapticsBackend> = Box::new(WebHaptics);
+        #[cfg(not(any(feature = "native", feature = "wasm")))]
+        let backend: Box<dyn HapticsBackend> = Box::new(NullHaptics);
+
+        Haptics::new(backend)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Enable or disable the rumble, ex. from the user settings
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.queue.clear();
+        }
+    }
+
+    pub fn intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    /// Scale of the strength of all the effects, ex. from the user settings
+    pub fn set_intensity(&mut self, intensity: f32) {
+        self.intensity = intensity.max(0.).min(1.);
+    }
+
+    pub fn play(&mut self, gamepad: usize, effect: HapticEffect) {
+        if self.enabled {
+            self.queue.push((gamepad, effect));
+        }
+    }
+
+    /// Stop the rumble of a gamepad, the effects queued for the gamepad in this frame are dropped.
+    pub fn stop(&mut self, gamepad: usize) {
+        self.queue.retain(|(id, _)| *id != gamepad);
+        self.queue.push((
+            gamepad,
+            HapticEffect::new(Duration::from_secs(0), 0., HapticChannel::Both),
+        ));
+    }
+
+    /// Merge the queued effects by gamepad
+    pub fn take_rumbles(&mut self) -> Vec<Rumble> {
+        let mut rumbles = HashMap::<usize, Rumble>::new();
+        for (gamepad, effect) in self.queue.drain(..) {
+            let rumble = rumbles.entry(gamepad).or_insert(Rumble {
+                gamepad,
+                strong: 0.,
+                weak: 0.,
+                duration: Duration::from_secs(0),
+            });
+            let strength = (effect.strength * self.intensity).max(0.).min(1.);
+            if effect.channel != HapticChannel::Weak {
+                rumble.strong = rumble.strong.max(strength);
+            }
+            if effect.channel != HapticChannel::Strong {
+                rumble.weak = rumble.weak.max(strength);
+            }
+            rumble.duration = rumble.duration.max(effect.duration);
+        }
+
+        let mut rumbles: Vec<_> = rumbles.into_iter().map(|(_, rumble)| rumble).collect();
+        rumbles.sort_by_key(|rumble| rumble.gamepad);
+        rumbles
+    }
+
+    /// Send the queued effects to the device
+    pub fn flush(&mut self) {
+        for rumble in self.take_rumbles() {
+            log::trace!("Rumble: {:?}", rumble);
+            self.backend.play(&rumble);
+        }
+    }
+}
+
+#[cfg(feature = "native")]
+mod native {
+    use super::{HapticsBackend, Rumble};
+    use gilrs::{
+        ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder, Replay, Ticks},
+        Gilrs,
+    };
+    use std::{
+        collections::HashMap,
+        sync::{
+            mpsc::{self, RecvTimeoutError, Sender},
+            Mutex,
+        },
+        thread,
+        time::Duration,
+    };
+
+    fn create_effect(gilrs: &mut Gilrs, rumble: &Rumble) -> Result<Option<Effect>, gilrs::ff::Error> {
+        let gamepads: Vec<_> = gilrs
+            .gamepads()
+            .filter(|(id, gamepad)| usize::from(*id) == rumble.gamepad && gamepad.is_ff_supported())
+            .map(|(id, _)| id)
+            .collect();
+        if gamepads.is_empty() {
+            return Ok(None);
+        }
+
+        let scheduling = Replay {
+            play_for: Ticks::from_ms(rumble.duration.as_millis() as u32),
+            ..Default::default()
+        };
+        let magnitude = |strength: f32| (strength * f32::from(u16::MAX)) as u16;
+        let effect = EffectBuilder::new()
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Strong {
+                    magnitude: magnitude(rumble.strong),
+                },
+                scheduling,
+                ..Default::default()
+            })
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Weak {
+                    magnitude: magnitude(rumble.weak),
+                },
+                scheduling,
+                ..Default::default()
+            })
+            .gamepads(&gamepads)
+            .finish(gilrs)?;
+        effect.play()?;
+        Ok(Some(effect))
+    }
+
+    /// Force feedback using gilrs. As Gilrs cannot be shared between the threads, it is owned by a dedicated
+    /// thread receiving the rumbles through a channel.
+    pub struct GilrsHaptics {
+        sender: Mutex<Sender<Rumble>>,
+    }
+
+    impl GilrsHaptics {
+        pub fn new() -> Result<GilrsHaptics, gilrs::Error> {
+            let mut gilrs = Gilrs::new()?;
+            let (sender, receiver) = mpsc::channel::<Rumble>();
+
+            thread::spawn(move || {
+                // the effects are stopped when dropped, keep the active effect of each gamepad
+                let mut effects = HashMap::<usize, Effect>::new();
+                loop {
+                    // process the events to track the connected gamepads
+                    while gilrs.next_event().is_some() {}
+
+                    match receiver.recv_timeout(Duration::from_millis(100)) {
+                        Ok(rumble) => {
+                            effects.remove(&rumble.gamepad);
+                            if rumble.duration.as_millis() > 0 {
+                                match create_effect(&mut gilrs, &rumble) {
+                                    Ok(Some(effect)) => {
+                                        effects.insert(rumble.gamepad, effect);
+                                    }
+                                    Ok(None) => {}
+                                    Err(err) => log::warn!("Failed to play rumble on {}: {}", rumble.gamepad, err),
+                                }
+                            }
+                        }
+                        Err(RecvTimeoutError::Timeout) => {}
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+                log::debug!("Force feedback thread stopped");
+            });
+
+            Ok(GilrsHaptics {
+                sender: Mutex::new(sender),
+            })
+        }
+    }
+
+    impl HapticsBackend for GilrsHaptics {
+        fn play(&mut self, rumble: &Rumble) {
+            let sender = self.sender.get_mut().unwrap();
+            if sender.send(*rumble).is_err() {
+                log::warn!("Force feedback thread is not running");
+            }
+        }
+    }
+}
+#[cfg(feature = "native")]
+pub use self::native::*;
+
+#[cfg(feature = "wasm")]
+mod wasm {
+    use super::{HapticsBackend, Rumble};
+    use js_sys::{Function, Object, Reflect};
+    use wasm_bindgen::{JsCast, JsValue};
+
+    fn play_effect(rumble: &Rumble) -> Result<(), JsValue> {
+        let navigator = Reflect::get(&js_sys::global(), &"navigator".into())?;
+        let get_gamepads: Function = Reflect::get(&navigator, &"getGamepads".into())?.dyn_into()?;
+        let gamepads = get_gamepads.call0(&navigator)?;
+        let gamepad = Reflect::get(&gamepads, &(rumble.gamepad as u32).into())?;
+        if gamepad.is_null() || gamepad.is_undefined() {
+            return Ok(());
+        }
+        let actuator = Reflect::get(&gamepad, &"vibrationActuator".into())?;
+        if actuator.is_null() || actuator.is_undefined() {
+            return Ok(());
+        }
+
+        let params = Object::new();
+        Reflect::set(
+            &params,
+            &"duration".into(),
+            &(rumble.duration.as_millis() as f64).into(),
+        )?;
+        Reflect::set(&params, &"strongMagnitude".into(), &(rumble.strong as f64).into())?;
+        Reflect::set(&params, &"weakMagnitude".into(), &(rumble.weak as f64).into())?;
+        let play_effect: Function = Reflect::get(&actuator, &"playEffect".into())?.dyn_into()?;
+        play_effect.call2(&actuator, &"dual-rumble".into(), &params)?;
+        Ok(())
+    }
+
+    /// Force feedback using the Gamepad Haptics API of the browser
+    pub struct WebHaptics;
+
+    impl HapticsBackend for WebHaptics {
+        fn play(&mut self, rumble: &Rumble) {
+            if let Err(err) = play_effect(rumble) {
+                log::warn!("Failed to play rumble on {}: {:?}", rumble.gamepad, err);
+            }
+        }
+    }
+}
+#[cfg(feature = "wasm")]
+pub use self::wasm::*;
@@ -0,0 +1,325 @@
+use crate::input::{InputError, InputEvent};
+use serde::{Deserialize, Serialize};
+use shine_input::{AccessibilityOptions, InputId, InputIdGenerator, InputState, InputValue, RepeatRate};
+use std::collections::HashMap;
+
+pub const GAMEPLAY_CONTEXT: &str = "gameplay";
+pub const MENU_CONTEXT: &str = "menu";
+pub const TEXT_ENTRY_CONTEXT: &str = "text_entry";
+pub const VEHICLE_CONTEXT: &str = "vehicle";
+
+/// Start of the input ids assigned to the actions, the ids below are left for the input mappers.
+pub const FIRST_ACTION_ID: u32 = 0x1_0000;
+
+/// How a context treats the inputs it does not handle
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContextPolicy {
+    /// No input reaches the contexts below, ex. menu or text entry
+    Consume,
+    /// The inputs bound by the context are consumed, the others are passed to the contexts below
+    PassThrough,
+}
+
+impl Default for ContextPolicy {
+    fn default() -> Self {
+        ContextPolicy::PassThrough
+    }
+}
+
+/// The kind of an action selecting the accessibility processing
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ActionKind {
+    /// Plain button, no accessibility processing
+    Press,
+    /// Action active while the keys are held, converted to a toggle by the hold_to_toggle option
+    Hold,
+    /// Modifier action, it stays active until the next key press by the sticky_modifiers option
+    Modifier,
+    /// Action repeated while the keys are held, ex. menu navigation
+    Repeat,
+}
+
+impl Default for ActionKind {
+    fn default() -> Self {
+        ActionKind::Press
+    }
+}
+
+/// Binding of an action to the keys (scan codes)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ActionBinding {
+    pub action: String,
+    pub keys: Vec<u32>,
+    #[serde(default)]
+    pub kind: ActionKind,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InputContextConfig {
+    pub name: String,
+    #[serde(default)]
+    pub policy: ContextPolicy,
+    pub actions: Vec<ActionBinding>,
+}
+
+/// Accessibility toggles applied by the InputManager uniformly for every game
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccessibilityConfig {
+    /// Convert the Hold actions into toggles
+    pub hold_to_toggle: bool,
+    /// Make the Modifier actions sticky
+    pub sticky_modifiers: bool,
+    /// Scale of the analog inputs
+    pub sensitivity: f32,
+    /// Delay before the first repeat of the Repeat actions
+    pub repeat_delay_ms: u32,
+    /// Time between the repeats of the Repeat actions
+    pub repeat_interval_ms: u32,
+}
+
+impl Default for AccessibilityConfig {
+    fn default() -> Self {
+        AccessibilityConfig {
+            hold_to_toggle: false,
+            sticky_modifiers: false,
+            sensitivity: 1.,
+            repeat_delay_ms: 500,
+            repeat_interval_ms: 100,
+        }
+    }
+}
+
+/// The input contexts as stored in the cooked game config
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct InputConfig {
+    #[serde(default)]
+    pub contexts: Vec<InputContextConfig>,
+    /// The initial stack of the active contexts from the bottom to the top
+    #[serde(default)]
+    pub initial_stack: Vec<String>,
+    #[serde(default)]
+    pub accessibility: AccessibilityConfig,
+}
+
+struct InputContext {
+    policy: ContextPolicy,
+    actions: HashMap<String, InputId>,
+    keys: HashMap<u32, Vec<InputId>>,
+}
+
+/// The input contexts and the stack of the active contexts. Each context maps a distinct set of actions,
+/// the inputs are routed from the top of the stack to the bottom until a context consumes them.
+/// Systems switch the contexts through this resource, the actions that cannot receive their inputs after
+/// a switch are released, thus no key is stuck or leaks from a menu into the gameplay.
+#[derive(Default)]
+pub struct InputContexts {
+    contexts: HashMap<String, InputContext>,
+    stack: Vec<String>,
+    released: Vec<InputId>,
+    accessibility: AccessibilityOptions,
+}
+
+impl InputContexts {
+    pub fn from_config(config: &InputConfig) -> Result<InputContexts, InputError> {
+        let mut gen_id = InputIdGenerator::with_start(FIRST_ACTION_ID);
+        let mut contexts = HashMap::new();
+        let accessibility_config = &config.accessibility;
+        let mut accessibility = AccessibilityOptions {
+            sensitivity: accessibility_config.sensitivity,
+            repeat_rate: RepeatRate {
+                delay: u128::from(accessibility_config.repeat_delay_ms) * 1000,
+                interval: u128::from(accessibility_config.repeat_interval_ms) * 1000,
+            },
+            ..Default::default()
+        };
+
+        for context_config in &config.contexts {
+            let mut context = InputContext {
+                policy: context_config.policy,
+                actions: HashMap::new(),
+                keys: HashMap::new(),
+            };
+            for binding in &context_config.actions {
+                if context.actions.contains_key(&binding.action) {
+                    return Err(InputError::Context {
+                        message: format!(
+                            "Action {} is bound multiple times in context {}",
+                            binding.action, context_config.name
+                        ),
+                    });
+                }
+                let id = gen_id.next();
+                context.actions.insert(binding.action.clone(), id);
+                match binding.kind {
+                    ActionKind::Press => {}
+                    ActionKind::Hold if accessibility_config.hold_to_toggle => {
+                        accessibility.toggle_inputs.insert(id);
+                    }
+                    ActionKind::Hold => {}
+                    ActionKind::Modifier if accessibility_config.sticky_modifiers => {
+                        accessibility.sticky_modifiers.insert(id);
+                    }
+                    ActionKind::Modifier => {}
+                    ActionKind::Repeat => {
+                        accessibility.repeat_inputs.insert(id);
+                    }
+                }
+                for key in &binding.keys {
+                    context.keys.entry(*key).or_insert_with(Vec::new).push(id);
+                }
+            }
+            if contexts.insert(context_config.name.clone(), context).is_some() {
+                return Err(InputError::Context {
+                    message: format!("Context {} is defined multiple times", context_config.name),
+                });
+            }
+        }
+
+        let mut input_contexts = InputContexts {
+            contexts,
+            stack: Vec::new(),
+            released: Vec::new(),
+            accessibility,
+        };
+        input_contexts.set_stack(config.initial_stack.clone())?;
+        Ok(input_contexts)
+    }
+
+    /// The id of an action of a context to query the InputState
+    pub fn action_id(&self, context: &str, action: &str) -> Option<InputId> {
+        self.contexts.get(context).and_then(|c| c.actions.get(action).cloned())
+    }
+
+    /// Return if the action of the context is triggered. The actions of the inactive contexts are never triggered.
+    pub fn is_action_active(&self, state: &InputState, context: &str, action: &str) -> bool {
+        self.is_active(context)
+            && self
+                .action_id(context, action)
+                .and_then(|id| state.get_input(id).as_button())
+                .unwrap_or(false)
+    }
+
+    /// The accessibility processing of the actions to be applied by the InputManager
+    pub fn accessibility(&self) -> &AccessibilityOptions {
+        &self.accessibility
+    }
+
+    /// The active contexts from the bottom to the top
+    pub fn stack(&self) -> &[String] {
+        &self.stack
+    }
+
+    pub fn top(&self) -> Option<&str> {
+        self.stack.last().map(|name| name.as_str())
+    }
+
+    pub fn is_active(&self, context: &str) -> bool {
+        self.stack.iter().any(|name| name == context)
+    }
+
+    fn check_context(&self, context: &str) -> Result<(), InputError> {
+        if self.contexts.contains_key(context) {
+            Ok(())
+        } else {
+            Err(InputError::Context {
+                message: format!("Unknown context: {}", context),
+            })
+        }
+    }
+
+    fn release_context(&mut self, context: &str) {
+        if let Some(context) = self.contexts.get(context) {
+            self.released.extend(context.actions.values().cloned());
+        }
+    }
+
+    /// Activate a context on the top of the stack. The actions below that could not receive the release
+    /// of their keys anymore are released.
+    pub fn push(&mut self, context: &str) -> Result<(), InputError> {
+        self.check_context(context)?;
+        log::debug!("Push input context {}", context);
+        let pushed = &self.contexts[context];
+        let released: Vec<InputId> = if pushed.policy == ContextPolicy::Consume {
+            self.stack
+                .iter()
+                .flat_map(|name| self.contexts[name].actions.values().cloned())
+                .collect()
+        } else {
+            self.stack
+                .iter()
+                .flat_map(|name| self.contexts[name].keys.iter())
+                .filter(|(key, _)| pushed.keys.contains_key(key))
+                .flat_map(|(_, ids)| ids.iter().cloned())
+                .collect()
+        };
+        self.released.extend(released);
+        self.stack.push(context.to_owned());
+        Ok(())
+    }
+
+    /// Deactivate the top context
+    pub fn pop(&mut self) -> Option<String> {
+        let context = self.stack.pop()?;
+        log::debug!("Pop input context {}", context);
+        self.release_context(&context);
+        Some(context)
+    }
+
+    /// Replace the whole stack of the active contexts, ex. when switching from the menu to the gameplay.
+    pub fn set_stack(&mut self, stack: Vec<String>) -> Result<(), InputError> {
+        for context in &stack {
+            self.check_context(context)?;
+        }
+        log::debug!("Set input context stack to {:?}", stack);
+        let old_stack = std::mem::replace(&mut self.stack, stack);
+        for context in old_stack {
+            self.release_context(&context);
+        }
+        Ok(())
+    }
+
+    /// Route a key through the active contexts. Returns true if the key was consumed by a context.
+    pub fn route_key(&self, key: u32, pressed: bool, state: &mut InputState) -> bool {
+        for name in self.stack.iter().rev() {
+            let context = &self.contexts[name];
+            if let Some(actions) = context.keys.get(&key) {
+                for id in actions {
+                    if pressed {
+                        state.set_input(*id, InputValue::D0, false);
+                    } else {
+                        state.clear_input(*id);
+                    }
+                }
+                return true;
+            }
+            if context.policy == ContextPolicy::Consume {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Route an event through the active contexts. Returns true if the event was consumed by a context
+    /// and it shall not reach the input mapper.
+    pub fn update_state(&self, event: &InputEvent<'_>, state: &mut InputState) -> bool {
+        match event {
+            #[cfg(feature = "native")]
+            InputEvent::Winit(input) => self.route_key(
+                input.scancode,
+                input.state == winit::event::ElementState::Pressed,
+                state,
+            ),
+            _ => false,
+        }
+    }
+
+    /// Clear the actions released by the context switches since the last call and return their ids.
+    pub fn release_inactive(&mut self, state: &mut InputState) -> Vec<InputId> {
+        let released = std::mem::take(&mut self.released);
+        for id in &released {
+            state.clear_input(*id);
+        }
+        released
+    }
+}
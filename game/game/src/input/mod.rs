@@ -2,6 +2,12 @@ mod input_mapper;
 pub use self::input_mapper::*;
 mod error;
 pub use self::error::*;
+mod input_context;
+pub use self::input_context::*;
+mod frame_timing;
+pub use self::frame_timing::*;
+mod haptics;
+pub use self::haptics::*;
 mod plugin;
 pub use self::plugin::*;
 
@@ -1,9 +1,9 @@
 use crate::{
     app::{AppError, Plugin, PluginFuture},
-    input::{mappers, InputEvent, InputMapper},
+    input::{mappers, FrameTiming, Haptics, InputConfig, InputContexts, InputEvent, InputMapper},
     World,
 };
-use shine_input::{GuestureManager, InputManager, InputState};
+use shine_input::{input_timestamp, GuestureManager, InputManager, InputState};
 use std::{
     borrow::Cow,
     error::Error as StdError,
@@ -47,16 +47,33 @@ pub struct InputHandler {
     state: InputState,
     manager: InputManager,
     guestures: GuestureManager,
+    /// Capture time of the oldest event injected since the last advance
+    oldest_event: Option<u128>,
 }
 
 impl InputHandler {
-    pub fn inject_input(&mut self, mapper: &WrapInputMapper, event: InputEvent<'_>) {
-        mapper.input.update_state(event, &mut self.state);
+    /// Route the event through the active input contexts, the events not consumed by a context are
+    /// handled by the mapper. The timestamp is the capture time of the event (see `input_timestamp`).
+    pub fn inject_input(
+        &mut self,
+        mapper: &WrapInputMapper,
+        contexts: &InputContexts,
+        event: InputEvent<'_>,
+        timestamp: u128,
+    ) {
+        self.oldest_event = Some(self.oldest_event.map_or(timestamp, |t| t.min(timestamp)));
+        if !contexts.update_state(&event, &mut self.state) {
+            mapper.input.update_state(event, &mut self.state);
+        }
     }
 
-    pub fn advance(&mut self, previous_state: &mut InputState) {
+    /// Prepare the state for the next frame and record the latency of the consumed events.
+    pub fn advance(&mut self, previous_state: &mut InputState, contexts: &mut InputContexts, timing: &mut FrameTiming) {
+        let released = contexts.release_inactive(&mut self.state);
+        self.manager.release_inputs(&released);
         self.manager
             .advance_states_with_guestures(previous_state, &mut self.state, &mut self.guestures);
+        timing.record_simulation(self.oldest_event.take(), self.manager.time());
     }
 }
 
@@ -75,16 +92,28 @@ impl Plugin for InputPlugin {
         Box::pin(async move {
             world
                 .resources
-                .register_with_instance(InputHandler::default())
+                .init_resource::<InputHandler>()
                 .map_err(into_plugin_err)?;
             world
                 .resources
-                .register_with_instance(CurrentInputState::default())
+                .init_resource::<CurrentInputState>()
                 .map_err(into_plugin_err)?;
             world
                 .resources
                 .register_with_instance(WrapInputMapper::wrap(mappers::Unmapped))
                 .map_err(into_plugin_err)?;
+            world
+                .resources
+                .init_resource::<InputContexts>()
+                .map_err(into_plugin_err)?;
+            world
+                .resources
+                .register_with_instance(Haptics::with_platform_backend())
+                .map_err(into_plugin_err)?;
+            world
+                .resources
+                .init_resource::<FrameTiming>()
+                .map_err(into_plugin_err)?;
             Ok(())
         })
     }
@@ -94,6 +123,9 @@ impl Plugin for InputPlugin {
             let _ = world.resources.unregister::<InputHandler>();
             let _ = world.resources.unregister::<CurrentInputState>();
             let _ = world.resources.unregister::<WrapInputMapper>();
+            let _ = world.resources.unregister::<InputContexts>();
+            let _ = world.resources.unregister::<Haptics>();
+            let _ = world.resources.unregister::<FrameTiming>();
             Ok(())
         })
     }
@@ -101,7 +133,13 @@ impl Plugin for InputPlugin {
 
 pub trait InputWorld {
     fn set_input_mapper<I: InputMapper>(&mut self, input_mapper: I) -> Result<(), AppError>;
+    /// Replace the input contexts with the ones defined by the (cooked) config
+    fn set_input_contexts(&mut self, config: &InputConfig) -> Result<(), AppError>;
+    /// Inject an event captured now
     fn inject_input<'e, E: Into<InputEvent<'e>>>(&mut self, event: E) -> Result<(), AppError>;
+    /// Inject an event with the capture time provided by the platform (ex. browser event time converted
+    /// to the `input_timestamp` clock)
+    fn inject_input_at<'e, E: Into<InputEvent<'e>>>(&mut self, event: E, timestamp: u128) -> Result<(), AppError>;
 }
 
 impl InputWorld for World {
@@ -110,18 +148,41 @@ impl InputWorld for World {
         let mut handler = self.resources.get_mut::<InputHandler>().map_err(into_plugin_err)?;
         let mut state = self.resources.get_mut::<CurrentInputState>().map_err(into_plugin_err)?;
 
+        let accessibility = handler.manager.accessibility().clone();
         *handler = InputHandler::default();
+        handler.manager.set_accessibility(accessibility);
         *state = CurrentInputState::default();
+        if let Ok(mut timing) = self.resources.get_mut::<FrameTiming>() {
+            timing.reset();
+        }
         input_mapper.init_guestures(&mut handler.guestures);
         mapper.input = Box::new(input_mapper);
         Ok(())
     }
 
+    fn set_input_contexts(&mut self, config: &InputConfig) -> Result<(), AppError> {
+        let contexts = InputContexts::from_config(config).map_err(into_plugin_err)?;
+        let mut handler = self.resources.get_mut::<InputHandler>().map_err(into_plugin_err)?;
+        let mut current = self.resources.get_mut::<InputContexts>().map_err(into_plugin_err)?;
+
+        // release the actions of the old contexts
+        current.set_stack(Vec::new()).map_err(into_plugin_err)?;
+        current.release_inactive(&mut handler.state);
+        handler.manager.set_accessibility(contexts.accessibility().clone());
+        *current = contexts;
+        Ok(())
+    }
+
     fn inject_input<'e, E: Into<InputEvent<'e>>>(&mut self, event: E) -> Result<(), AppError> {
+        self.inject_input_at(event, input_timestamp())
+    }
+
+    fn inject_input_at<'e, E: Into<InputEvent<'e>>>(&mut self, event: E, timestamp: u128) -> Result<(), AppError> {
         let mapper = self.resources.get::<WrapInputMapper>().map_err(into_plugin_err)?;
+        let contexts = self.resources.get::<InputContexts>().map_err(into_plugin_err)?;
         let mut handler = self.resources.get_mut::<InputHandler>().map_err(into_plugin_err)?;
 
-        handler.inject_input(&mapper, event.into());
+        handler.inject_input(&mapper, &contexts, event.into(), timestamp);
         Ok(())
     }
 }
@@ -1,6 +1,15 @@
-use crate::input::{CurrentInputState, InputHandler};
+use crate::input::{CurrentInputState, FrameTiming, Haptics, InputContexts, InputHandler};
 use shine_ecs::ecs::resources::ResMut;
 
-pub fn advance_input_states(mut prev_states: ResMut<CurrentInputState>, mut handler: ResMut<InputHandler>) {
-    handler.advance(&mut prev_states);
+pub fn advance_input_states(
+    mut prev_states: ResMut<CurrentInputState>,
+    mut handler: ResMut<InputHandler>,
+    mut contexts: ResMut<InputContexts>,
+    mut timing: ResMut<FrameTiming>,
+) {
+    handler.advance(&mut prev_states, &mut contexts, &mut timing);
+}
+
+pub fn flush_haptics(mut haptics: ResMut<Haptics>) {
+    haptics.flush();
 }
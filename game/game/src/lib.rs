@@ -4,11 +4,18 @@
 mod world;
 pub use self::world::*;
 
+pub mod animation;
 pub mod app;
 pub mod assets;
 //pub mod components;
+pub mod event_bus;
 pub mod game;
 pub mod input;
+pub mod localization;
+pub mod matchmaking;
 pub mod render;
+pub mod savegame;
+pub mod spatial;
+pub mod voice;
 
 pub use wgpu;
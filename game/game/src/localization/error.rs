@@ -0,0 +1,19 @@
+use crate::assets::AssetError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum LocalizationError {
+    #[error("Failed to load the {language} locale")]
+    Asset {
+        language: String,
+        #[source]
+        source: AssetError,
+    },
+
+    #[error("Syntax error in the {language} locale at line {line}: {message}")]
+    Parse {
+        language: String,
+        line: usize,
+        message: String,
+    },
+}
@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Writing systems that require a font with the matching glyphs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Script {
+    Latin,
+    Greek,
+    Cyrillic,
+    Hebrew,
+    Arabic,
+    Devanagari,
+    Thai,
+    Hangul,
+    Kana,
+    /// Han ideographs and the CJK symbols, punctuation and fullwidth forms
+    Han,
+}
+
+impl Script {
+    /// The script of a character, None for the common characters (digits, punctuation, whitespace) that all
+    /// the fonts are expected to contain
+    pub fn of(c: char) -> Option<Script> {
+        match c as u32 {
+            0x41..=0x5A | 0x61..=0x7A | 0xC0..=0x24F | 0x1E00..=0x1EFF => Some(Script::Latin),
+            0x370..=0x3FF | 0x1F00..=0x1FFF => Some(Script::Greek),
+            0x400..=0x52F => Some(Script::Cyrillic),
+            0x590..=0x5FF => Some(Script::Hebrew),
+            0x600..=0x6FF | 0x750..=0x77F => Some(Script::Arabic),
+            0x900..=0x97F => Some(Script::Devanagari),
+            0xE00..=0xE7F => Some(Script::Thai),
+            0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF => Some(Script::Hangul),
+            0x3040..=0x30FF | 0x31F0..=0x31FF => Some(Script::Kana),
+            0x3000..=0x303F | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF | 0xFF00..=0xFFEF => {
+                Some(Script::Han)
+            }
+            _ => None,
+        }
+    }
+
+    /// Collect the scripts used by a text
+    pub fn collect(text: &str, scripts: &mut HashSet<Script>) {
+        scripts.extend(text.chars().filter_map(Script::of));
+    }
+}
+
+/// A fallback font for the scripts missing from the primary font
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FontFallback {
+    pub font: String,
+    pub scripts: Vec<Script>,
+    /// The font is preferred for these languages (ex. the japanese glyph variants of the Han ideographs for "ja"),
+    /// an empty list matches any language.
+    #[serde(default)]
+    pub languages: Vec<String>,
+}
+
+impl FontFallback {
+    fn is_preferred_for(&self, language: &str) -> bool {
+        self.languages.iter().any(|lang| {
+            language == lang || (language.starts_with(lang.as_str()) && language[lang.len()..].starts_with('-'))
+        })
+    }
+}
+
+fn default_primary_scripts() -> Vec<Script> {
+    vec![Script::Latin]
+}
+
+/// The fonts of the text rendering. The glyphs of the scripts not covered by the primary font are taken from
+/// the first available font of the fallback chain.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct FontConfig {
+    pub primary: String,
+    #[serde(default = "default_primary_scripts")]
+    pub primary_scripts: Vec<Script>,
+    #[serde(default)]
+    pub fallbacks: Vec<FontFallback>,
+}
+
+impl FontConfig {
+    pub fn is_primary(&self, script: Script) -> bool {
+        self.primary_scripts.contains(&script)
+    }
+
+    /// The fallback fonts of a script in the order of preference: the fonts preferred for the language come
+    /// first, then the generic fonts and finally the fonts preferred for other languages, all in the order of
+    /// the configuration.
+    pub fn fallback_chain(&self, language: &str, script: Script) -> Vec<&str> {
+        let mut chain = self
+            .fallbacks
+            .iter()
+            .filter(|fallback| fallback.scripts.contains(&script))
+            .collect::<Vec<_>>();
+        chain.sort_by_key(
+            |fallback| match (fallback.is_preferred_for(language), fallback.languages.is_empty()) {
+                (true, _) => 0,
+                (false, true) => 1,
+                (false, false) => 2,
+            },
+        );
+        chain.into_iter().map(|fallback| fallback.font.as_str()).collect()
+    }
+}
@@ -0,0 +1,116 @@
+use crate::localization::LocalizationError;
+use std::collections::HashMap;
+
+/// The messages of a language. The message files use the simple message syntax of Fluent (the same format as
+/// the catalog of the backend): `key = value` lines, `#` comments, indented continuation lines and
+/// `{ $arg }` placeables.
+#[derive(Debug, Clone, Default)]
+pub struct Locale {
+    language: String,
+    messages: HashMap<String, String>,
+}
+
+impl Locale {
+    pub fn new(language: &str) -> Locale {
+        Locale {
+            language: language.to_lowercase(),
+            messages: HashMap::new(),
+        }
+    }
+
+    pub fn parse(language: &str, content: &str) -> Result<Locale, LocalizationError> {
+        let parse_error = |line: usize, message: &str| LocalizationError::Parse {
+            language: language.to_owned(),
+            line,
+            message: message.to_owned(),
+        };
+
+        let mut locale = Locale::new(language);
+        let mut current: Option<(String, String)> = None;
+        for (i, line) in content.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with(char::is_whitespace) {
+                let (_, value) = current
+                    .as_mut()
+                    .ok_or_else(|| parse_error(i + 1, "Continuation line without a message"))?;
+                if !value.is_empty() {
+                    value.push('\n');
+                }
+                value.push_str(trimmed);
+                continue;
+            }
+
+            let mut parts = line.splitn(2, '=');
+            match (parts.next().map(|key| key.trim()), parts.next()) {
+                (Some(key), Some(value)) if !key.is_empty() => {
+                    if let Some((key, value)) = current.take() {
+                        locale.messages.insert(key, value);
+                    }
+                    current = Some((key.to_owned(), value.trim().to_owned()));
+                }
+                _ => return Err(parse_error(i + 1, "Expected `key = value`")),
+            }
+        }
+
+        if let Some((key, value)) = current.take() {
+            locale.messages.insert(key, value);
+        }
+        Ok(locale)
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    pub fn insert(&mut self, key: &str, message: &str) {
+        self.messages.insert(key.to_owned(), message.to_owned());
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.messages.get(key).map(|message| message.as_str())
+    }
+
+    pub fn messages(&self) -> impl Iterator<Item = &str> {
+        self.messages.values().map(|message| message.as_str())
+    }
+
+    /// Substitute the `{ $arg }` placeables of a message, the unknown placeables are kept.
+    pub fn format<S: AsRef<str>>(message: &str, args: &[(S, S)]) -> String {
+        let mut result = String::with_capacity(message.len());
+        let mut rest = message;
+        while let Some(start) = rest.find('{') {
+            result.push_str(&rest[..start]);
+            let end = match rest[start..].find('}') {
+                Some(end) => start + end,
+                None => {
+                    rest = &rest[start..];
+                    break;
+                }
+            };
+            let placeable = rest[start + 1..end].trim();
+            let value = placeable
+                .strip_prefix('$')
+                .and_then(|name| args.iter().find(|(arg, _)| arg.as_ref() == name))
+                .map(|(_, value)| value.as_ref());
+            match value {
+                Some(value) => result.push_str(value),
+                None => result.push_str(&rest[start..=end]),
+            }
+            rest = &rest[end + 1..];
+        }
+        result.push_str(rest);
+        result
+    }
+}
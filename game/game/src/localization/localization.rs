@@ -0,0 +1,204 @@
+use crate::localization::{FontConfig, Locale, Script};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, RwLock, Weak},
+};
+
+struct TextEntry {
+    key: String,
+    args: Vec<(String, String)>,
+    text: String,
+    revision: u64,
+}
+
+/// Handle to a localized text. The text is re-resolved when the language is changed, thus the handle
+/// always gives the text in the current language.
+#[derive(Clone)]
+pub struct LocalizedText(Arc<RwLock<TextEntry>>);
+
+impl LocalizedText {
+    pub fn key(&self) -> String {
+        self.0.read().unwrap().key.clone()
+    }
+
+    pub fn text(&self) -> String {
+        self.0.read().unwrap().text.clone()
+    }
+
+    /// The revision is incremented each time the text is resolved, the layout of the text can be cached
+    /// for a revision.
+    pub fn revision(&self) -> u64 {
+        self.0.read().unwrap().revision
+    }
+}
+
+/// Event of the language or the available fonts being changed, the UI shall relayout the texts
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalizationChanged {
+    pub language: String,
+    /// The fallback fonts loaded for the language
+    pub fonts: Vec<String>,
+}
+
+/// The current language with the handles of the localized texts and the loaded fallback fonts.
+pub struct Localization {
+    font_config: FontConfig,
+    default_locale: Locale,
+    locale: Locale,
+    texts: Vec<Weak<RwLock<TextEntry>>>,
+    fonts: HashMap<String, Arc<Vec<u8>>>,
+    failed_fonts: HashSet<String>,
+}
+
+impl Localization {
+    /// Create the localization with the messages of the default language, that are also used for the
+    /// messages missing from the other languages.
+    pub fn new(font_config: FontConfig, default_locale: Locale) -> Localization {
+        Localization {
+            font_config,
+            locale: default_locale.clone(),
+            default_locale,
+            texts: Vec::new(),
+            fonts: HashMap::new(),
+            failed_fonts: HashSet::new(),
+        }
+    }
+
+    pub fn language(&self) -> &str {
+        self.locale.language()
+    }
+
+    pub fn default_language(&self) -> &str {
+        self.default_locale.language()
+    }
+
+    pub fn font_config(&self) -> &FontConfig {
+        &self.font_config
+    }
+
+    /// Translate a message in the current language, the key is returned if the message is not found.
+    pub fn translate<S: AsRef<str>>(&self, key: &str, args: &[(S, S)]) -> String {
+        let message = self
+            .locale
+            .get(key)
+            .or_else(|| self.default_locale.get(key))
+            .unwrap_or(key);
+        Locale::format(message, args)
+    }
+
+    fn resolve(&self, entry: &mut TextEntry) {
+        entry.text = self.translate(&entry.key, &entry.args);
+        entry.revision += 1;
+    }
+
+    /// Create a handle for a localized text
+    pub fn text(&mut self, key: &str, args: &[(&str, &str)]) -> LocalizedText {
+        let mut entry = TextEntry {
+            key: key.to_owned(),
+            args: args
+                .iter()
+                .map(|(name, value)| ((*name).to_owned(), (*value).to_owned()))
+                .collect(),
+            text: String::new(),
+            revision: 0,
+        };
+        self.resolve(&mut entry);
+        let text = Arc::new(RwLock::new(entry));
+        self.texts.push(Arc::downgrade(&text));
+        LocalizedText(text)
+    }
+
+    /// Change the arguments of a text
+    pub fn set_args(&self, text: &LocalizedText, args: &[(&str, &str)]) {
+        let mut entry = text.0.write().unwrap();
+        entry.args = args
+            .iter()
+            .map(|(name, value)| ((*name).to_owned(), (*value).to_owned()))
+            .collect();
+        self.resolve(&mut entry);
+    }
+
+    /// Change the language and re-resolve all the living text handles
+    pub fn set_language(&mut self, locale: Locale) {
+        log::info!("Changing language to {}", locale.language());
+        self.locale = locale;
+        self.texts.retain(|text| text.strong_count() > 0);
+        for text in &self.texts {
+            if let Some(text) = text.upgrade() {
+                self.resolve(&mut text.write().unwrap());
+            }
+        }
+    }
+
+    /// Number of the living text handles
+    pub fn text_count(&self) -> usize {
+        self.texts.iter().filter(|text| text.strong_count() > 0).count()
+    }
+
+    /// The scripts used by the messages of the current language and by the living texts
+    pub fn scripts(&self) -> HashSet<Script> {
+        let mut scripts = HashSet::new();
+        for message in self.locale.messages() {
+            Script::collect(message, &mut scripts);
+        }
+        for text in self.texts.iter().filter_map(|text| text.upgrade()) {
+            Script::collect(&text.read().unwrap().text, &mut scripts);
+        }
+        scripts
+    }
+
+    /// The fonts to load for the scripts missing from the primary font. For each script the first font of the
+    /// fallback chain is returned that has not failed to load, unless a font of the chain is already loaded.
+    pub fn missing_fonts(&self) -> Vec<String> {
+        let language = self.language();
+        let mut scripts = self.scripts().into_iter().collect::<Vec<_>>();
+        scripts.sort();
+
+        let mut missing = Vec::new();
+        for script in scripts {
+            if self.font_config.is_primary(script) {
+                continue;
+            }
+            let chain = self.font_config.fallback_chain(language, script);
+            if chain.iter().any(|font| self.fonts.contains_key(*font)) {
+                continue;
+            }
+            match chain.iter().find(|font| !self.failed_fonts.contains(**font)) {
+                Some(font) => {
+                    if !missing.iter().any(|m| m == *font) {
+                        missing.push((*font).to_owned());
+                    }
+                }
+                None => log::warn!("No font available for {:?} in {}", script, language),
+            }
+        }
+        missing
+    }
+
+    pub fn add_font(&mut self, font: &str, data: Vec<u8>) {
+        log::info!("Fallback font {} loaded", font);
+        self.failed_fonts.remove(font);
+        self.fonts.insert(font.to_owned(), Arc::new(data));
+    }
+
+    /// Mark a font as failed, the next font of the fallback chain is used instead
+    pub fn font_failed(&mut self, font: &str) {
+        self.failed_fonts.insert(font.to_owned());
+    }
+
+    /// The font to render the glyphs of a script with, None if no font is loaded for the script
+    pub fn font(&self, script: Script) -> Option<&str> {
+        if self.font_config.is_primary(script) {
+            Some(&self.font_config.primary)
+        } else {
+            self.font_config
+                .fallback_chain(self.language(), script)
+                .into_iter()
+                .find(|font| self.fonts.contains_key(*font))
+        }
+    }
+
+    pub fn font_data(&self, font: &str) -> Option<Arc<Vec<u8>>> {
+        self.fonts.get(font).cloned()
+    }
+}
@@ -0,0 +1,10 @@
+mod error;
+pub use self::error::*;
+mod locale;
+pub use self::locale::*;
+mod font_fallback;
+pub use self::font_fallback::*;
+mod localization;
+pub use self::localization::*;
+mod plugin;
+pub use self::plugin::*;
@@ -0,0 +1,177 @@
+use crate::{
+    app::{AppError, Plugin, PluginFuture},
+    assets::{AssetError, AssetIO, Url},
+    localization::{FontConfig, Locale, Localization, LocalizationChanged, LocalizationError},
+    World,
+};
+use serde::{Deserialize, Serialize};
+use shine_ecs::events::Events;
+use std::{borrow::Cow, error::Error as StdError};
+
+pub const LOCALIZATION_PLUGIN_NAME: &str = "localization";
+
+fn into_plugin_err<E: 'static + StdError>(error: E) -> AppError {
+    AppError::plugin(LOCALIZATION_PLUGIN_NAME, error)
+}
+
+fn default_language() -> String {
+    "en".to_owned()
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LocalizationConfig {
+    /// Folder of the `<lang>.ftl` message files
+    pub folder: String,
+    #[serde(default = "default_language")]
+    pub default_language: String,
+    #[serde(default)]
+    pub fonts: FontConfig,
+}
+
+/// Load the locales and the fonts through the asset io
+#[derive(Clone)]
+pub struct LocaleSource {
+    io: AssetIO,
+    folder: Url,
+}
+
+impl LocaleSource {
+    pub fn new(io: AssetIO, folder: Url) -> LocaleSource {
+        LocaleSource { io, folder }
+    }
+
+    pub async fn load_locale(&self, language: &str) -> Result<Locale, LocalizationError> {
+        let asset_error = |source| LocalizationError::Asset {
+            language: language.to_owned(),
+            source,
+        };
+        let url = self
+            .folder
+            .join(&format!("{}.ftl", language.to_lowercase()))
+            .map_err(|err| asset_error(AssetError::from(err)))?;
+        let content = self.io.download_string(&url).await.map_err(asset_error)?;
+        let locale = Locale::parse(language, &content)?;
+        log::info!("Locale {} loaded with {} messages", language, locale.len());
+        Ok(locale)
+    }
+
+    pub async fn load_font(&self, font: &str) -> Result<Vec<u8>, AssetError> {
+        let url = Url::parse(font)?;
+        self.io.download_binary(&url).await
+    }
+
+    /// Load the fallback fonts missing for the current language and return the loaded fonts. If a font
+    /// fails to load, the next one of the fallback chain is tried.
+    pub async fn load_missing_fonts(&self, localization: &mut Localization) -> Vec<String> {
+        let mut loaded = Vec::new();
+        loop {
+            let missing = localization.missing_fonts();
+            if missing.is_empty() {
+                break;
+            }
+            for font in missing {
+                match self.load_font(&font).await {
+                    Ok(data) => {
+                        localization.add_font(&font, data);
+                        loaded.push(font);
+                    }
+                    Err(err) => {
+                        log::warn!("Failed to load font {}: {:?}", font, err);
+                        localization.font_failed(&font);
+                    }
+                }
+            }
+        }
+        loaded
+    }
+}
+
+pub struct LocalizationPlugin {
+    config: LocalizationConfig,
+}
+
+impl LocalizationPlugin {
+    pub fn new(config: LocalizationConfig) -> LocalizationPlugin {
+        LocalizationPlugin { config }
+    }
+}
+
+impl Plugin for LocalizationPlugin {
+    fn name() -> Cow<'static, str> {
+        LOCALIZATION_PLUGIN_NAME.into()
+    }
+
+    fn init(self, world: &mut World) -> PluginFuture<()> {
+        Box::pin(async move {
+            let io = world.resources.get::<AssetIO>().map_err(into_plugin_err)?.clone();
+            let folder = Url::parse(&self.config.folder)
+                .map_err(AssetError::from)
+                .map_err(into_plugin_err)?;
+            let source = LocaleSource::new(io, folder);
+
+            let default_locale = source
+                .load_locale(&self.config.default_language)
+                .await
+                .map_err(into_plugin_err)?;
+            let mut localization = Localization::new(self.config.fonts, default_locale);
+            let _ = source.load_missing_fonts(&mut localization).await;
+
+            world
+                .resources
+                .register_with_instance(localization)
+                .map_err(into_plugin_err)?;
+            world
+                .resources
+                .register_with_instance(source)
+                .map_err(into_plugin_err)?;
+            world
+                .resources
+                .register_events::<LocalizationChanged>()
+                .map_err(into_plugin_err)?;
+            Ok(())
+        })
+    }
+
+    fn deinit(world: &mut World) -> PluginFuture<()> {
+        Box::pin(async move {
+            let _ = world.resources.unregister::<Events<LocalizationChanged>>();
+            let _ = world.resources.unregister::<LocaleSource>();
+            let _ = world.resources.unregister::<Localization>();
+            Ok(())
+        })
+    }
+}
+
+pub trait LocalizationWorld {
+    /// Change the language at runtime. The localized texts are re-resolved, the fallback fonts missing for
+    /// the language are loaded and a `LocalizationChanged` event is sent for the relayout of the UI. On failure
+    /// the current language is kept.
+    fn set_language<'a>(&'a mut self, language: &'a str) -> PluginFuture<'a, ()>;
+}
+
+impl LocalizationWorld for World {
+    fn set_language<'a>(&'a mut self, language: &'a str) -> PluginFuture<'a, ()> {
+        Box::pin(async move {
+            let source = self.resources.get::<LocaleSource>().map_err(into_plugin_err)?.clone();
+            let locale = source.load_locale(language).await.map_err(into_plugin_err)?;
+
+            let changed = {
+                let mut localization = self.resources.get_mut::<Localization>().map_err(into_plugin_err)?;
+                localization.set_language(locale);
+                let fonts = source.load_missing_fonts(&mut localization).await;
+                LocalizationChanged {
+                    language: localization.language().to_owned(),
+                    fonts,
+                }
+            };
+
+            let mut events = self
+                .resources
+                .get_mut::<Events<LocalizationChanged>>()
+                .map_err(into_plugin_err)?;
+            events.update();
+            events.send(changed);
+            Ok(())
+        })
+    }
+}
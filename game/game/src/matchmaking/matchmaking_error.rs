@@ -0,0 +1,7 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum MatchmakingError {
+    #[error("Failed to initialize the http client: {0}")]
+    Client(String),
+}
@@ -0,0 +1,4 @@
+mod matchmaking_error;
+pub use self::matchmaking_error::*;
+mod region_probe;
+pub use self::region_probe::*;
@@ -0,0 +1,139 @@
+use crate::matchmaking::MatchmakingError;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Region listed by the game state service
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegionInfo {
+    pub id: String,
+    pub endpoint: String,
+    pub ping_url: String,
+    pub healthy: bool,
+}
+
+/// Measured round trip time to a region, sent with the room allocation request
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegionLatency {
+    pub region: String,
+    pub rtt_ms: u32,
+}
+
+/// Median of the samples, the outliers of a single slow request don't affect the result
+pub fn median_rtt(samples: &mut [u32]) -> Option<u32> {
+    if samples.is_empty() {
+        return None;
+    }
+    samples.sort_unstable();
+    let mid = samples.len() / 2;
+    if samples.len() % 2 == 0 {
+        Some((samples[mid - 1] + samples[mid]) / 2)
+    } else {
+        Some(samples[mid])
+    }
+}
+
+/// Order the latencies by the round trip time, the nearest region first
+pub fn rank_latencies(mut latencies: Vec<RegionLatency>) -> Vec<RegionLatency> {
+    latencies.sort_by(|a, b| a.rtt_ms.cmp(&b.rtt_ms).then_with(|| a.region.cmp(&b.region)));
+    latencies
+}
+
+/// Measure the round trip time to the regions before matchmaking. Each region is probed sequentially
+/// with a number of requests to the ping url of the region, the first request is dropped as it
+/// includes the connection setup.
+pub struct RegionProbe {
+    samples: usize,
+    timeout: Duration,
+    #[cfg(feature = "native")]
+    client: reqwest::Client,
+}
+
+impl RegionProbe {
+    pub fn new(samples: usize, timeout: Duration) -> Result<RegionProbe, MatchmakingError> {
+        Ok(RegionProbe {
+            samples: samples.max(1),
+            timeout,
+            #[cfg(feature = "native")]
+            client: reqwest::Client::builder()
+                .timeout(timeout)
+                .build()
+                .map_err(|err| MatchmakingError::Client(format!("{}", err)))?,
+        })
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    #[cfg(feature = "native")]
+    async fn ping(&self, url: &str) -> Option<u32> {
+        let start = std::time::Instant::now();
+        match self.client.get(url).send().await {
+            Ok(response) if response.status().is_success() => Some(start.elapsed().as_millis() as u32),
+            Ok(response) => {
+                log::debug!("Ping of {} failed with status {}", url, response.status());
+                None
+            }
+            Err(err) => {
+                log::debug!("Ping of {} failed: {}", url, err);
+                None
+            }
+        }
+    }
+
+    #[cfg(feature = "wasm")]
+    async fn ping(&self, url: &str) -> Option<u32> {
+        use wasm_bindgen::JsCast;
+        use wasm_bindgen_futures::JsFuture;
+        use web_sys::{Request, RequestInit, RequestMode, Response};
+
+        let mut opts = RequestInit::new();
+        opts.method("GET");
+        opts.mode(RequestMode::Cors);
+        let request = Request::new_with_str_and_init(url, &opts).ok()?;
+
+        let window = web_sys::window()?;
+        let start = js_sys::Date::now();
+        let response = JsFuture::from(window.fetch_with_request(&request)).await.ok()?;
+        let response = response.dyn_into::<Response>().ok()?;
+        let elapsed = js_sys::Date::now() - start;
+        if response.ok() && elapsed <= self.timeout.as_millis() as f64 {
+            Some(elapsed as u32)
+        } else {
+            log::debug!("Ping of {} failed with status {}", url, response.status());
+            None
+        }
+    }
+
+    /// Measure the round trip time to a region, None if the region is unreachable
+    pub async fn measure(&self, region: &RegionInfo) -> Option<u32> {
+        // warm up the connection
+        self.ping(&region.ping_url).await?;
+
+        let mut samples = Vec::with_capacity(self.samples);
+        for _ in 0..self.samples {
+            if let Some(rtt) = self.ping(&region.ping_url).await {
+                samples.push(rtt);
+            }
+        }
+        median_rtt(&mut samples)
+    }
+
+    /// Measure the healthy regions, the unreachable regions are omitted and the result is ordered by
+    /// the round trip time.
+    pub async fn probe(&self, regions: &[RegionInfo]) -> Vec<RegionLatency> {
+        let mut latencies = Vec::new();
+        for region in regions.iter().filter(|region| region.healthy) {
+            match self.measure(region).await {
+                Some(rtt_ms) => latencies.push(RegionLatency {
+                    region: region.id.clone(),
+                    rtt_ms,
+                }),
+                None => log::info!("Region {} is unreachable", region.id),
+            }
+        }
+        rank_latencies(latencies)
+    }
+}
@@ -1,103 +1,55 @@
 use crate::{
-    assets::{AssetError, PipelineDescriptor, PipelineStateDescriptor, VertexBufferLayout, VertexStage},
+    assets::{
+        AssetError, PipelineDescriptor, PipelineStateDescriptor, PipelineUniform, PipelineUniformLayout,
+        UniformSemantic, VertexBufferLayout, VertexStage,
+    },
     render::Compile,
 };
-/*
-struct PipelineBindGroupLayout {
-    layout: wgpu::BindGroupLayout,
-    uniforms: Vec<PipelineUniform>,
-}
 
-pub struct PipelineBindGroup {
-    pub auto: wgpu::BindGroup,
-    pub global: wgpu::BindGroup,
-    pub local: wgpu::BindGroup,
+/// Layout of a bind group with the uniforms it was created from
+pub struct PipelineBindGroupLayout {
+    pub layout: wgpu::BindGroupLayout,
+    pub uniforms: Vec<PipelineUniform>,
 }
-*/
+
 /// Compiled pipeline with related binding information
 pub struct CompiledPipeline {
     pub vertex_layouts: Vec<VertexBufferLayout>,
+    pub bind_group_layouts: Vec<PipelineBindGroupLayout>,
     pub pipeline: wgpu::RenderPipeline,
 }
 
 impl CompiledPipeline {
-    /*    fn get_uniform_buffer_size(&self, scope: UniformScope) -> usize {
-        let bind_group = self.get_bind_group(scope);
-        bind_group.uniforms.iter().fold(0, |size, u| {
-            if let Uniform::UniformBuffer(b) = u.uniform() {
-                size + b.size()
-            } else {
-                size
-            }
-        })
-    }
-
-    pub fn create_uniform_buffer(&self, scope: UniformScope, device: &wgpu::Device) -> Option<wgpu::Buffer> {
-        let size = self.get_uniform_buffer_size(scope);
-        if size > 0 {
-            Some(device.create_buffer(&wgpu::BufferDescriptor {
-                label: None,
-                size: size as wgpu::BufferAddress,
-                usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
-                mapped_at_creation: false,
-            }))
-        } else {
-            None
-        }
-    }
-
-    pub fn create_bind_group<'a, F>(
+    /// Create the bind groups of the pipeline in the order of the groups. The resources are selected by the
+    /// semantic of the uniforms. If a resource is not provided, the semantic of the first missing uniform is
+    /// returned as an error.
+    pub fn create_bind_groups<'a, F>(
         &self,
         device: &wgpu::Device,
-        scope: UniformScope,
         mut get_value: F,
-    ) -> wgpu::BindGroup
+    ) -> Result<Vec<wgpu::BindGroup>, UniformSemantic>
     where
-        F: FnMut(&Uniform) -> wgpu::BindingResource<'a>,
+        F: FnMut(&UniformSemantic) -> Option<wgpu::BindingResource<'a>>,
     {
-        let bind_group = self.get_bind_group(scope);
-        let mut bindings = Vec::with_capacity(bind_group.uniforms.len());
-        for u in bind_group.uniforms.iter() {
-            let resource = get_value(u.uniform());
-            //todo: check if resource is conforming to uniform
-            bindings.push(wgpu::BindGroupEntry {
-                binding: u.location(),
-                resource,
-            });
-        }
-
-        log::trace!(
-            "create_bind_group for {:?}: {:?}; binding len: {}",
-            scope,
-            bind_group.layout,
-            bindings.len()
-        );
-
-        device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: None,
-            layout: &bind_group.layout,
-            entries: &bindings,
-        })
-    }
+        let mut bind_groups = Vec::with_capacity(self.bind_group_layouts.len());
+        for bind_group in &self.bind_group_layouts {
+            let mut entries = Vec::with_capacity(bind_group.uniforms.len());
+            for uniform in &bind_group.uniforms {
+                let resource = get_value(uniform.semantic()).ok_or_else(|| uniform.semantic().clone())?;
+                entries.push(wgpu::BindGroupEntry {
+                    binding: uniform.location(),
+                    resource,
+                });
+            }
 
-    pub fn create_bind_groups<'a, FA, FG, FL>(
-        &self,
-        device: &wgpu::Device,
-        get_auto: FA,
-        get_global: FG,
-        get_local: FL,
-    ) -> PipelineBindGroup
-    where
-        FA: FnMut(&Uniform) -> wgpu::BindingResource<'a>,
-        FG: FnMut(&Uniform) -> wgpu::BindingResource<'a>,
-        FL: FnMut(&Uniform) -> wgpu::BindingResource<'a>,
-    {
-        PipelineBindGroup {
-            auto: self.create_bind_group(device, UniformScope::Auto, get_auto),
-            global: self.create_bind_group(device, UniformScope::Global, get_global),
-            local: self.create_bind_group(device, UniformScope::Local, get_local),
+            bind_groups.push(device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout: &bind_group.layout,
+                entries: &entries,
+            }));
         }
-    }*/
+        Ok(bind_groups)
+    }
 }
 
 impl PipelineDescriptor {
@@ -131,61 +83,51 @@ impl PipelineDescriptor {
         Ok(descriptors)
     }
 
-    /*fn create_bind_group_layout_entries(
-        layout: &PipelineUniformLayout,
-    ) -> Result<Vec<wgpu::BindGroupLayoutEntry>, AssetError> {
-        let mut descriptor = Vec::new();
-        for (uniform, stages) in layout.iter() {
-            descriptor.push(match uniform.uniform() {
-                Uniform::Texture(_) => wgpu::BindGroupLayoutEntry {
-                    binding: uniform.location(),
-                    visibility: *stages,
-                    ty: wgpu::BindingType::SampledTexture {
+    fn create_bind_group_layout_entries(
+        uniforms: &[(PipelineUniform, wgpu::ShaderStage)],
+    ) -> Vec<wgpu::BindGroupLayoutEntry> {
+        uniforms
+            .iter()
+            .map(|(uniform, stages)| wgpu::BindGroupLayoutEntry {
+                binding: uniform.location(),
+                visibility: *stages,
+                ty: match uniform.semantic() {
+                    UniformSemantic::Texture(texture) => wgpu::BindingType::SampledTexture {
                         multisampled: false,
-                        dimension: wgpu::TextureViewDimension::D2,
+                        dimension: texture.view_dimension(),
                         component_type: wgpu::TextureComponentType::Float,
                     },
-                    count: None,
-                },
-                Uniform::Sampler(_) => wgpu::BindGroupLayoutEntry {
-                    binding: uniform.location(),
-                    visibility: *stages,
-                    ty: wgpu::BindingType::Sampler { comparison: false },
-                    count: None,
-                },
-                Uniform::UniformBuffer(sem) => wgpu::BindGroupLayoutEntry {
-                    binding: uniform.location(),
-                    visibility: *stages,
-                    ty: wgpu::BindingType::UniformBuffer {
+                    UniformSemantic::Sampler(_) => wgpu::BindingType::Sampler { comparison: false },
+                    UniformSemantic::UniformBuffer(_) => wgpu::BindingType::UniformBuffer {
                         dynamic: false,
-                        min_binding_size: Some(wgpu::BufferSize::new(sem.size() as u64)),
+                        min_binding_size: None,
                     },
-                    count: None,
                 },
-            });
-        }
-        Ok(descriptor)
+                count: None,
+            })
+            .collect()
     }
 
-    fn create_bind_group_layout(
-        &self,
+    fn create_bind_group_layouts(
         device: &wgpu::Device,
-        scope: UniformScope,
-    ) -> Result<PipelineBindGroupLayout, AssetError> {
-        let uniform_layout = self.get_uniform_layout(scope)?;
-        log::trace!("Bind group({:?}) layout {:#?}", scope, uniform_layout);
-
-        let bindings = Self::create_bind_group_layout_entries(&uniform_layout)?;
-        let uniforms: Vec<_> = uniform_layout.iter().map(|(u, _)| u).cloned().collect();
-
-        log::trace!("Creating bind group layout with {} entries", bindings.len());
-        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: None,
-            entries: &bindings,
-        });
-
-        Ok(PipelineBindGroupLayout { layout, uniforms })
-    }*/
+        uniform_layout: &PipelineUniformLayout,
+    ) -> Vec<PipelineBindGroupLayout> {
+        uniform_layout
+            .iter()
+            .map(|uniforms| {
+                let entries = Self::create_bind_group_layout_entries(uniforms);
+                log::trace!("Creating bind group layout with {} entries", entries.len());
+                let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: None,
+                    entries: &entries,
+                });
+                PipelineBindGroupLayout {
+                    layout,
+                    uniforms: uniforms.iter().map(|(uniform, _)| uniform.clone()).collect(),
+                }
+            })
+            .collect()
+    }
 }
 
 pub struct PipelineCompile<'a> {
@@ -224,18 +166,29 @@ impl<'a> Compile for PipelineCompile<'a> {
         };
         log::trace!("Vertex state: {:#?}", vertex_state);
 
+        let color_stage = render_states.color_stage.unwrap_or(descriptor.color_stage);
+        let mut color_states = render_states.color_states;
+        color_stage.apply_all(&mut color_states);
+        let depth_stencil_state = render_states.depth_state.map(|mut depth_state| {
+            depth_state.depth_write_enabled = descriptor.is_depth_write_enabled(color_stage);
+            depth_state
+        });
+        log::trace!(
+            "Color stage: {:?}, depth state: {:#?}",
+            color_stage,
+            depth_stencil_state
+        );
+
         let uniform_layout = descriptor.get_uniform_layout()?;
-        /*let auto_bind_group_layout = descriptor.create_bind_group_layout(device, UniformScope::Auto)?;
-        let global_bind_group_layout = descriptor.create_bind_group_layout(device, UniformScope::Global)?;
-        let local_bind_group_layout = descriptor.create_bind_group_layout(device, UniformScope::Local)?;*/
+        log::trace!("Uniform layout: {:#?}", uniform_layout);
+        let bind_group_layouts = PipelineDescriptor::create_bind_group_layouts(device, &uniform_layout);
 
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: None,
-            bind_group_layouts: &[
-                /*&auto_bind_group_layout.layout,
-                &global_bind_group_layout.layout,
-                &local_bind_group_layout.layout,*/
-            ],
+            bind_group_layouts: &bind_group_layouts
+                .iter()
+                .map(|bind_group| &bind_group.layout)
+                .collect::<Vec<_>>(),
             push_constant_ranges: &[],
         });
 
@@ -252,8 +205,8 @@ impl<'a> Compile for PipelineCompile<'a> {
                 entry_point: "main",
             }),
             rasterization_state: None,
-            color_states: &render_states.color_states,
-            depth_stencil_state: render_states.depth_state,
+            color_states: &color_states,
+            depth_stencil_state,
             vertex_state,
             sample_count: 1,
             sample_mask: !0,
@@ -262,6 +215,7 @@ impl<'a> Compile for PipelineCompile<'a> {
 
         Ok(CompiledPipeline {
             vertex_layouts,
+            bind_group_layouts,
             pipeline,
         })
     }
@@ -1,4 +1,4 @@
-use crate::render::{RenderConfig, RenderError, Surface};
+use crate::render::{GpuCapabilities, GpuErrorScope, GpuErrorSink, RenderConfig, RenderError, Surface};
 use std::sync::{Arc, Mutex};
 
 /// Thread safe rendering context.
@@ -9,6 +9,9 @@ pub struct Context {
     commands: Mutex<Vec<wgpu::CommandBuffer>>,
     swap_chain_format: wgpu::TextureFormat,
     swap_chain: Option<(wgpu::SwapChain, wgpu::SwapChainDescriptor)>,
+    capabilities: GpuCapabilities,
+    adapter_info: wgpu::AdapterInfo,
+    errors: GpuErrorSink,
 }
 
 impl Context {
@@ -25,13 +28,22 @@ impl Context {
             .await
             .ok_or_else(|| RenderError::device_error_str("Adapter not found"))?;
 
-        //log::info!("Graphics adapter: {:?}", adapter.get_info());
+        let adapter_info = adapter.get_info();
+        log::info!("Graphics adapter: {:?}", adapter_info);
+
+        let features = GpuCapabilities::negotiate_features(adapter.features(), config);
+        let limits = wgpu::Limits::default();
+        log::info!(
+            "Adapter features: {:?}, requested features: {:?}",
+            adapter.features(),
+            features
+        );
 
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
-                    features: wgpu::Features::empty(),
-                    limits: wgpu::Limits::default(),
+                    features,
+                    limits: limits.clone(),
                     shader_validation: config.enable_validation,
                 },
                 config.wgpu_trace.as_ref().map(std::path::Path::new),
@@ -46,9 +58,26 @@ impl Context {
             commands: Mutex::new(Vec::new()),
             swap_chain_format: config.swap_chain_format,
             swap_chain: None,
+            capabilities: GpuCapabilities::new(features, limits, config),
+            adapter_info,
+            errors: GpuErrorSink::default(),
         })
     }
 
+    /// Capabilities granted during the device creation
+    pub fn capabilities(&self) -> &GpuCapabilities {
+        &self.capabilities
+    }
+
+    pub fn adapter_info(&self) -> &wgpu::AdapterInfo {
+        &self.adapter_info
+    }
+
+    /// Collector of the validation errors captured by the gpu operations
+    pub fn errors(&self) -> &GpuErrorSink {
+        &self.errors
+    }
+
     pub fn device(&self) -> Arc<wgpu::Device> {
         self.device.clone()
     }
@@ -67,15 +96,48 @@ impl Context {
         commands.push(command);
     }
 
+    /// Number of the command buffers waiting for the submission
+    pub fn pending_command_count(&self) -> usize {
+        self.commands.lock().unwrap().len()
+    }
+
     pub fn submit_commands(&mut self) {
         let mut commands = self.commands.lock().unwrap();
-        self.queue.submit(commands.drain(..));
+        let queue = &self.queue;
+        self.errors
+            .capture(GpuErrorScope::Submission, "frame", || queue.submit(commands.drain(..)));
     }
 
     pub fn swap_chain_format(&self) -> wgpu::TextureFormat {
         self.swap_chain_format
     }
 
+    /// Create an offscreen frame in the format of the swap chain that can be copied for a screenshot
+    pub fn create_capture_frame(&self, surface: &Surface) -> (wgpu::Texture, wgpu::SwapChainDescriptor) {
+        let (width, height) = surface.size();
+        let sd = wgpu::SwapChainDescriptor {
+            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT | wgpu::TextureUsage::COPY_SRC,
+            format: self.swap_chain_format,
+            width,
+            height,
+            present_mode: wgpu::PresentMode::Mailbox,
+        };
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("screenshot"),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: sd.format,
+            usage: sd.usage,
+        });
+        (texture, sd)
+    }
+
     pub fn create_frame(
         &mut self,
         surface: &Surface,
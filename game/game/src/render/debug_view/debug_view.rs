@@ -0,0 +1,227 @@
+use crate::assets::Uniform;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// The intermediate target routed to the screen instead of the final frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DebugViewMode {
+    Off,
+    Depth,
+    Normals,
+    Shadow,
+    AmbientOcclusion,
+    Velocity,
+}
+
+impl DebugViewMode {
+    /// All the modes in the order of cycling
+    pub const ALL: [DebugViewMode; 6] = [
+        DebugViewMode::Off,
+        DebugViewMode::Depth,
+        DebugViewMode::Normals,
+        DebugViewMode::Shadow,
+        DebugViewMode::AmbientOcclusion,
+        DebugViewMode::Velocity,
+    ];
+
+    fn index(self) -> usize {
+        DebugViewMode::ALL.iter().position(|mode| *mode == self).unwrap()
+    }
+
+    pub fn next(self) -> DebugViewMode {
+        DebugViewMode::ALL[(self.index() + 1) % DebugViewMode::ALL.len()]
+    }
+
+    pub fn previous(self) -> DebugViewMode {
+        let count = DebugViewMode::ALL.len();
+        DebugViewMode::ALL[(self.index() + count - 1) % count]
+    }
+}
+
+impl Default for DebugViewMode {
+    fn default() -> DebugViewMode {
+        DebugViewMode::Off
+    }
+}
+
+impl fmt::Display for DebugViewMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            DebugViewMode::Off => "off",
+            DebugViewMode::Depth => "depth",
+            DebugViewMode::Normals => "normals",
+            DebugViewMode::Shadow => "shadow",
+            DebugViewMode::AmbientOcclusion => "ao",
+            DebugViewMode::Velocity => "velocity",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for DebugViewMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<DebugViewMode, String> {
+        match s.to_lowercase().as_str() {
+            "off" | "none" => Ok(DebugViewMode::Off),
+            "depth" => Ok(DebugViewMode::Depth),
+            "normal" | "normals" => Ok(DebugViewMode::Normals),
+            "shadow" => Ok(DebugViewMode::Shadow),
+            "ao" | "ssao" | "occlusion" => Ok(DebugViewMode::AmbientOcclusion),
+            "velocity" => Ok(DebugViewMode::Velocity),
+            _ => Err(format!("Unknown debug view: {}", s)),
+        }
+    }
+}
+
+/// Mapping of the raw values of a target into the displayable [0,1] range
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DebugViewNormalization {
+    /// The target is displayed as it is
+    None,
+    /// Linearize the hardware depth between the near and far planes
+    LinearDepth { near: f32, far: f32 },
+    /// Map the [-1,1] range of the unit vectors to [0,1]
+    SignedUnit,
+    /// Scale the absolute value, used for the screen space velocity given in pixels
+    Scale(f32),
+}
+
+impl DebugViewNormalization {
+    pub fn apply(&self, value: [f32; 4]) -> [f32; 4] {
+        match *self {
+            DebugViewNormalization::None => value,
+            DebugViewNormalization::LinearDepth { near, far } => {
+                // perspective depth in [0,1] back to the view distance, then into [0,1] between the planes
+                let z = value[0];
+                let distance = near * far / (far - z * (far - near)).max(1e-6);
+                let d = ((distance - near) / (far - near)).max(0.).min(1.);
+                [d, d, d, 1.]
+            }
+            DebugViewNormalization::SignedUnit => {
+                [value[0] * 0.5 + 0.5, value[1] * 0.5 + 0.5, value[2] * 0.5 + 0.5, 1.]
+            }
+            DebugViewNormalization::Scale(scale) => [
+                (value[0].abs() * scale).min(1.),
+                (value[1].abs() * scale).min(1.),
+                (value[2].abs() * scale).min(1.),
+                1.,
+            ],
+        }
+    }
+}
+
+/// Uniform buffer layout of the debug view shader
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DebugViewUniform {
+    /// Kind of the normalization (0: none, 1: linear depth, 2: signed unit, 3: scale) and the
+    /// replicated channel (-1 for rgb)
+    pub mode: [i32; 4],
+    /// Parameters of the normalization: near, far or the scale
+    pub params: [f32; 4],
+}
+
+unsafe impl bytemuck::Pod for DebugViewUniform {}
+unsafe impl bytemuck::Zeroable for DebugViewUniform {}
+
+impl Uniform for DebugViewUniform {}
+
+impl DebugViewUniform {
+    pub fn new(normalization: &DebugViewNormalization, channel: Option<u32>) -> DebugViewUniform {
+        let channel = channel.map(|c| c as i32).unwrap_or(-1);
+        let (kind, params) = match *normalization {
+            DebugViewNormalization::None => (0, [0.; 4]),
+            DebugViewNormalization::LinearDepth { near, far } => (1, [near, far, 0., 0.]),
+            DebugViewNormalization::SignedUnit => (2, [0.; 4]),
+            DebugViewNormalization::Scale(scale) => (3, [scale, 0., 0., 0.]),
+        };
+        DebugViewUniform {
+            mode: [kind, channel, 0, 0],
+            params,
+        }
+    }
+}
+
+/// Render target inspection. The selected intermediate target is drawn over the frame with a
+/// normalization suitable for the target. The mode is changed by the console or the overlay, the
+/// targets are provided by the active techniques, a mode without a target is skipped with a warning.
+#[derive(Debug, Clone)]
+pub struct DebugView {
+    mode: DebugViewMode,
+    /// Clip planes of the depth linearization
+    pub depth_range: (f32, f32),
+    /// Velocity (in pixels) displayed as full intensity
+    pub velocity_range: f32,
+}
+
+impl Default for DebugView {
+    fn default() -> DebugView {
+        DebugView {
+            mode: DebugViewMode::Off,
+            depth_range: (0.1, 100.),
+            velocity_range: 16.,
+        }
+    }
+}
+
+impl DebugView {
+    pub fn mode(&self) -> DebugViewMode {
+        self.mode
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.mode != DebugViewMode::Off
+    }
+
+    pub fn set_mode(&mut self, mode: DebugViewMode) {
+        if self.mode != mode {
+            log::info!("Debug view: {}", mode);
+            self.mode = mode;
+        }
+    }
+
+    pub fn cycle(&mut self) -> DebugViewMode {
+        self.set_mode(self.mode.next());
+        self.mode
+    }
+
+    pub fn cycle_back(&mut self) -> DebugViewMode {
+        self.set_mode(self.mode.previous());
+        self.mode
+    }
+
+    /// Execute a console command: `next`, `prev` or the name of a mode
+    pub fn execute(&mut self, command: &str) -> Result<DebugViewMode, String> {
+        match command.trim() {
+            "next" | "" => Ok(self.cycle()),
+            "prev" | "previous" => Ok(self.cycle_back()),
+            mode => {
+                self.set_mode(mode.parse()?);
+                Ok(self.mode)
+            }
+        }
+    }
+
+    /// Normalization and the displayed channel of the current mode
+    pub fn normalization(&self) -> (DebugViewNormalization, Option<u32>) {
+        match self.mode {
+            DebugViewMode::Off => (DebugViewNormalization::None, None),
+            DebugViewMode::Depth | DebugViewMode::Shadow => (
+                DebugViewNormalization::LinearDepth {
+                    near: self.depth_range.0,
+                    far: self.depth_range.1,
+                },
+                Some(0),
+            ),
+            DebugViewMode::Normals => (DebugViewNormalization::SignedUnit, None),
+            DebugViewMode::AmbientOcclusion => (DebugViewNormalization::None, Some(0)),
+            DebugViewMode::Velocity => (DebugViewNormalization::Scale(1. / self.velocity_range.max(1e-5)), None),
+        }
+    }
+
+    pub fn uniform(&self) -> DebugViewUniform {
+        let (normalization, channel) = self.normalization();
+        DebugViewUniform::new(&normalization, channel)
+    }
+}
@@ -0,0 +1,284 @@
+use crate::{
+    assets::{vertex, TextureSemantic, UniformSemantic},
+    render::{
+        create_target_sampler, create_uniform_buffer, draw_full_screen, Context, DebugView, DebugViewMode,
+        DebugViewUniform, FrameTarget, FullScreenTarget, MissingBinding, Pipeline, PipelineKey, RenderError,
+        RenderTechnique, Ssao, Taa, TechniqueConfig,
+    },
+    World,
+};
+use shine_ecs::{
+    resources::{ResourceId, Resources},
+    scheduler::{ResourceClaims, System, SystemName, Task, TaskGroup, TaskItem},
+    ECSError,
+};
+use std::sync::Arc;
+
+/// Name of the technique in the registry
+pub const DEBUG_VIEW_TECHNIQUE: &str = "debug_view";
+
+/// Name of the uniform buffer of the debug view pipeline
+pub const DEBUG_VIEW_UNIFORM: &str = "debug_view";
+/// Name of the displayed target in the debug view pipeline
+pub const DEBUG_VIEW_SOURCE_TEXTURE: &str = "source";
+
+/// Full screen pass drawing the selected target over the frame
+pub struct DebugViewPass {
+    pipeline_key: PipelineKey,
+    mode: DebugViewMode,
+    uniform: Option<DebugViewUniform>,
+    sampler: Option<wgpu::Sampler>,
+    missing_binding: MissingBinding,
+    resource_claims: Option<Result<ResourceClaims, ECSError>>,
+}
+
+impl DebugViewPass {
+    pub fn new(pipeline: String) -> DebugViewPass {
+        DebugViewPass {
+            pipeline_key: PipelineKey::new::<vertex::Null>(pipeline, Default::default()),
+            mode: DebugViewMode::Off,
+            uniform: None,
+            sampler: None,
+            missing_binding: MissingBinding::default(),
+            resource_claims: None,
+        }
+    }
+
+    /// The displayed mode and its uniform of the last frame
+    pub fn uniform(&self) -> Option<(DebugViewMode, &DebugViewUniform)> {
+        self.uniform.as_ref().map(|uniform| (self.mode, uniform))
+    }
+
+    pub fn set_render_state(&mut self, target: &FrameTarget) {
+        let pipeline_states = target.get_render_states();
+        if self.pipeline_key.render_state != pipeline_states {
+            self.pipeline_key.render_state = pipeline_states;
+            self.resource_claims = None;
+        }
+    }
+}
+
+impl System for DebugViewPass {
+    fn debug_name(&self) -> &str {
+        "DebugViewPass"
+    }
+
+    fn name(&self) -> Option<&SystemName> {
+        None
+    }
+
+    fn resource_claims(&mut self) -> Result<&ResourceClaims, ECSError> {
+        let pipeline_key = &self.pipeline_key;
+        self.resource_claims
+            .get_or_insert_with(|| {
+                let mut claims = ResourceClaims::default();
+                claims.add_immutable::<Pipeline, _>(Some(ResourceId::from_object(pipeline_key)?));
+                claims.add_immutable::<Ssao, _>(Some(ResourceId::Global));
+                claims.add_immutable::<Taa, _>(Some(ResourceId::Global));
+                claims.add_immutable::<Context, _>(Some(ResourceId::Global));
+                claims.add_immutable::<FrameTarget, _>(Some(ResourceId::Global));
+                Ok(claims)
+            })
+            .as_ref()
+            .map_err(|err| err.clone())
+    }
+
+    fn run(&mut self, resources: &Resources) -> Result<TaskGroup, ECSError> {
+        let ssao = resources.get::<Ssao>().ok();
+        let taa = resources.get::<Taa>().ok();
+        let source = match self.mode {
+            DebugViewMode::Depth => ssao.as_ref().and_then(|ssao| ssao.targets()).map(|t| t.depth_view()),
+            DebugViewMode::Normals => ssao.as_ref().and_then(|ssao| ssao.targets()).map(|t| t.normal_view()),
+            DebugViewMode::AmbientOcclusion => ssao.as_ref().and_then(|ssao| ssao.occlusion_view()),
+            DebugViewMode::Velocity => taa.as_ref().and_then(|taa| taa.targets()).map(|t| t.velocity_view()),
+            DebugViewMode::Off | DebugViewMode::Shadow => None,
+        };
+
+        let context = resources.get::<Context>()?;
+        let target = resources.get::<FrameTarget>()?;
+        let pipeline = resources.get_with_id::<Pipeline>(&ResourceId::from_object(&self.pipeline_key)?)?;
+        let (view, source, pipeline, uniform) =
+            match (target.view(), source, pipeline.pipeline_module(), self.uniform.as_ref()) {
+                (Some(view), Some(source), Some(pipeline), Some(uniform)) => (view, source, pipeline, uniform),
+                _ => return Ok(TaskGroup::default()),
+            };
+
+        let device = context.device();
+        let uniform = create_uniform_buffer(&device, "debug view", uniform);
+        let sampler = &*self
+            .sampler
+            .get_or_insert_with(|| create_target_sampler(&device, "debug view"));
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("debug view"),
+        });
+        let result = draw_full_screen(
+            &device,
+            &mut encoder,
+            pipeline,
+            &[FullScreenTarget::load(view)],
+            |semantic| match semantic {
+                UniformSemantic::UniformBuffer(name) if name.as_str() == DEBUG_VIEW_UNIFORM => {
+                    Some(wgpu::BindingResource::Buffer(uniform.slice(..)))
+                }
+                UniformSemantic::Texture(TextureSemantic::Frame(name))
+                    if name.as_str() == DEBUG_VIEW_SOURCE_TEXTURE =>
+                {
+                    Some(wgpu::BindingResource::TextureView(source))
+                }
+                UniformSemantic::Sampler(_) => Some(wgpu::BindingResource::Sampler(sampler)),
+                _ => None,
+            },
+        );
+        if result.is_ok() {
+            context.add_command(encoder.finish());
+        }
+        self.missing_binding.update("DebugViewPass", result);
+        Ok(TaskGroup::default())
+    }
+}
+
+/// Check if the target of a mode is provided by the active techniques. The shadow map is not rendered by
+/// any of the techniques yet.
+pub fn is_debug_view_available(resources: &Resources, mode: DebugViewMode) -> bool {
+    match mode {
+        DebugViewMode::Off => true,
+        DebugViewMode::Depth | DebugViewMode::Normals | DebugViewMode::AmbientOcclusion => resources
+            .get::<Ssao>()
+            .map(|ssao| ssao.targets().is_some())
+            .unwrap_or(false),
+        DebugViewMode::Velocity => resources
+            .get::<Taa>()
+            .map(|taa| taa.targets().is_some())
+            .unwrap_or(false),
+        DebugViewMode::Shadow => false,
+    }
+}
+
+/// Select the source of the debug view for the frame. The technique resources are optional, thus they
+/// are accessed by this system directly instead of by the parameters of a function system.
+struct DebugViewSelect {
+    pass: Arc<Task<DebugViewPass>>,
+    missing: Option<DebugViewMode>,
+    resource_claims: ResourceClaims,
+}
+
+impl DebugViewSelect {
+    fn new(pass: Arc<Task<DebugViewPass>>) -> DebugViewSelect {
+        let mut resource_claims = ResourceClaims::default();
+        resource_claims.add_immutable::<DebugView, _>(Some(ResourceId::Global));
+        resource_claims.add_immutable::<FrameTarget, _>(Some(ResourceId::Global));
+        resource_claims.add_immutable::<Ssao, _>(Some(ResourceId::Global));
+        resource_claims.add_immutable::<Taa, _>(Some(ResourceId::Global));
+        DebugViewSelect {
+            pass,
+            missing: None,
+            resource_claims,
+        }
+    }
+}
+
+impl System for DebugViewSelect {
+    fn debug_name(&self) -> &str {
+        "DebugViewSelect"
+    }
+
+    fn name(&self) -> Option<&SystemName> {
+        None
+    }
+
+    fn resource_claims(&mut self) -> Result<&ResourceClaims, ECSError> {
+        Ok(&self.resource_claims)
+    }
+
+    fn run(&mut self, resources: &Resources) -> Result<TaskGroup, ECSError> {
+        let (mode, uniform) = {
+            let debug_view = resources.get::<DebugView>()?;
+            (debug_view.mode(), debug_view.uniform())
+        };
+        if mode == DebugViewMode::Off {
+            self.missing = None;
+            return Ok(TaskGroup::default());
+        }
+        if !is_debug_view_available(resources, mode) {
+            if self.missing != Some(mode) {
+                log::warn!(
+                    "Debug view {} is not available, no active technique provides the target",
+                    mode
+                );
+                self.missing = Some(mode);
+            }
+            return Ok(TaskGroup::default());
+        }
+        self.missing = None;
+
+        {
+            let target = resources.get::<FrameTarget>()?;
+            let mut pass = self.pass.system()?;
+            pass.set_render_state(&target);
+            pass.mode = mode;
+            pass.uniform = Some(uniform);
+        }
+        Ok(TaskGroup::from_task(self.pass.clone()))
+    }
+}
+
+/// Render technique of the render target inspection. It shall be the last technique of the render stage
+/// as it draws over the frame. The mode is selected by the DebugView resource.
+/// Options:
+/// - pipeline: the cooked pipeline of the full screen pass. It is bound with the DebugViewUniform as the
+///   "debug_view" uniform buffer and the displayed target as the "source" frame texture.
+/// - near, far: clip planes of the depth linearization (default: 0.1, 100)
+/// - velocity_range: velocity in pixels displayed as full intensity (default: 16)
+/// - mode: the initial mode (default: off)
+pub struct DebugViewTechnique {
+    pipeline: String,
+    view: DebugView,
+}
+
+impl DebugViewTechnique {
+    pub fn from_config(config: &TechniqueConfig) -> Result<DebugViewTechnique, RenderError> {
+        let pipeline = config.option("pipeline").ok_or_else(|| RenderError::Technique {
+            message: format!("Missing pipeline for {}", DEBUG_VIEW_TECHNIQUE),
+        })?;
+
+        let default = DebugView::default();
+        let mut view = DebugView::default();
+        view.depth_range = (
+            config.parse_option("near", default.depth_range.0)?,
+            config.parse_option("far", default.depth_range.1)?,
+        );
+        view.velocity_range = config.parse_option("velocity_range", default.velocity_range)?;
+        view.set_mode(config.parse_option("mode", DebugViewMode::Off)?);
+        if view.depth_range.0 <= 0. || view.depth_range.1 <= view.depth_range.0 || view.velocity_range <= 0. {
+            return Err(RenderError::Technique {
+                message: format!("Invalid settings for {}: {:?}", DEBUG_VIEW_TECHNIQUE, view),
+            });
+        }
+
+        Ok(DebugViewTechnique {
+            pipeline: pipeline.to_owned(),
+            view,
+        })
+    }
+
+    pub fn view(&self) -> &DebugView {
+        &self.view
+    }
+}
+
+impl RenderTechnique for DebugViewTechnique {
+    fn create(&mut self, world: &mut World) -> Result<TaskItem, RenderError> {
+        // keep the mode selected before the render stage was (re)created
+        if let Ok(mut view) = world.resources.get_mut::<DebugView>() {
+            let mode = view.mode();
+            *view = self.view.clone();
+            if mode != DebugViewMode::Off {
+                view.set_mode(mode);
+            }
+        }
+        let pass = Task::new(DebugViewPass::new(self.pipeline.clone()));
+        Ok(DebugViewSelect::new(pass).into())
+    }
+
+    fn destroy(&mut self, _world: &mut World) {}
+}
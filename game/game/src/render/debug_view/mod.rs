@@ -0,0 +1,4 @@
+mod debug_view;
+pub use self::debug_view::*;
+mod debug_view_technique;
+pub use self::debug_view_technique::*;
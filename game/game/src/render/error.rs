@@ -1,3 +1,4 @@
+use crate::render::GpuErrorScope;
 use shine_ecs::core::error::ErrorString;
 use std::error::Error as StdError;
 use thiserror::Error;
@@ -12,6 +13,22 @@ pub enum RenderError {
 
     #[error("Render resource compilation failed: {}", message)]
     Compile { message: String },
+
+    #[error("Missing gpu capability for {}: {:?}", name, features)]
+    MissingCapability { name: String, features: wgpu::Features },
+
+    #[error("Render technique error: {}", message)]
+    Technique { message: String },
+
+    #[error("Gpu validation error in {} {}: {}", scope, asset, message)]
+    Validation {
+        scope: GpuErrorScope,
+        asset: String,
+        message: String,
+    },
+
+    #[error("Screenshot failed: {}", message)]
+    Screenshot { message: String },
 }
 
 impl RenderError {
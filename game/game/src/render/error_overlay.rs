@@ -0,0 +1,119 @@
+use crate::render::{GpuErrorScope, RenderError};
+use shine_ecs::events::{EventCursor, Events};
+use std::collections::VecDeque;
+
+/// A render error listed by the debug overlay, repeated errors are merged
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderErrorEntry {
+    pub scope: Option<GpuErrorScope>,
+    pub asset: Option<String>,
+    pub message: String,
+    pub count: usize,
+    /// The frame of the last occurrence
+    pub frame: u64,
+}
+
+impl RenderErrorEntry {
+    fn from_error(error: &RenderError, frame: u64) -> RenderErrorEntry {
+        match error {
+            RenderError::Validation { scope, asset, message } => RenderErrorEntry {
+                scope: Some(*scope),
+                asset: Some(asset.clone()),
+                message: message.clone(),
+                count: 1,
+                frame,
+            },
+            error => RenderErrorEntry {
+                scope: None,
+                asset: None,
+                message: error.to_string(),
+                count: 1,
+                frame,
+            },
+        }
+    }
+
+    fn is_same(&self, other: &RenderErrorEntry) -> bool {
+        self.scope == other.scope && self.asset == other.asset && self.message == other.message
+    }
+
+    /// The line displayed by the overlay
+    pub fn line(&self) -> String {
+        let mut line = match (&self.scope, &self.asset) {
+            (Some(scope), Some(asset)) => format!("[{}] {}: {}", scope, asset, self.message),
+            _ => self.message.clone(),
+        };
+        if self.count > 1 {
+            line += &format!(" (x{})", self.count);
+        }
+        line
+    }
+}
+
+/// The recent render errors displayed by the debug overlay. Errors are read from the `Events<RenderError>`
+/// channel once per frame and they are dropped after the lifetime or when the list is full.
+pub struct RenderErrorOverlay {
+    entries: VecDeque<RenderErrorEntry>,
+    cursor: EventCursor<RenderError>,
+    frame: u64,
+    /// Maximum number of the listed errors
+    pub capacity: usize,
+    /// Number of frames an error is listed after its last occurrence, 0 to keep the errors until dismissed
+    pub lifetime: u64,
+}
+
+impl Default for RenderErrorOverlay {
+    fn default() -> RenderErrorOverlay {
+        RenderErrorOverlay {
+            entries: VecDeque::new(),
+            cursor: EventCursor::default(),
+            frame: 0,
+            capacity: 8,
+            lifetime: 600,
+        }
+    }
+}
+
+impl RenderErrorOverlay {
+    pub fn is_visible(&self) -> bool {
+        !self.entries.is_empty()
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &RenderErrorEntry> {
+        self.entries.iter()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.entries.iter().map(|entry| entry.line()).collect()
+    }
+
+    pub fn dismiss(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Read the new errors and drop the expired ones, it is called once per frame
+    pub fn update(&mut self, events: &Events<RenderError>) {
+        self.frame += 1;
+        let frame = self.frame;
+
+        for error in self.cursor.read(events) {
+            let entry = RenderErrorEntry::from_error(error, frame);
+            if let Some(pos) = self.entries.iter().position(|e| e.is_same(&entry)) {
+                let mut existing = self.entries.remove(pos).unwrap();
+                existing.count += 1;
+                existing.frame = frame;
+                self.entries.push_back(existing);
+            } else {
+                self.entries.push_back(entry);
+            }
+        }
+
+        if self.lifetime > 0 {
+            let lifetime = self.lifetime;
+            self.entries.retain(|entry| frame - entry.frame < lifetime);
+        }
+        while self.entries.len() > self.capacity {
+            self.entries.pop_front();
+        }
+    }
+}
@@ -0,0 +1,410 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The reason of a frame capture
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CaptureReason {
+    Manual,
+    Console,
+    /// The previous frame took too long, the times are in microseconds
+    Hitch {
+        frame_time: u64,
+        average: u64,
+    },
+}
+
+impl fmt::Display for CaptureReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureReason::Manual => f.write_str("manual"),
+            CaptureReason::Console => f.write_str("console"),
+            CaptureReason::Hitch { frame_time, average } => write!(
+                f,
+                "hitch ({:.1} ms, average {:.1} ms)",
+                *frame_time as f64 / 1000.,
+                *average as f64 / 1000.
+            ),
+        }
+    }
+}
+
+fn default_hitch_factor() -> f32 {
+    3.
+}
+
+fn default_hitch_min_ms() -> u64 {
+    50
+}
+
+fn default_hitch_cooldown() -> u64 {
+    300
+}
+
+fn default_max_hitch_captures() -> usize {
+    5
+}
+
+/// Configuration of the frame capture
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FrameCaptureConfig {
+    /// Capture the frame following a hitch automatically
+    #[serde(default)]
+    pub capture_hitches: bool,
+    /// A frame is a hitch if it is longer than the average frame time by this factor
+    #[serde(default = "default_hitch_factor")]
+    pub hitch_factor: f32,
+    /// Frames shorter than this (in ms) are never considered as hitches
+    #[serde(default = "default_hitch_min_ms")]
+    pub hitch_min_ms: u64,
+    /// Minimum number of frames between two hitch captures
+    #[serde(default = "default_hitch_cooldown")]
+    pub hitch_cooldown: u64,
+    /// Maximum number of the hitch captures in a session
+    #[serde(default = "default_max_hitch_captures")]
+    pub max_hitch_captures: usize,
+    /// Folder of the command logs on native when RenderDoc is not available, if not given the logs are
+    /// written to the log only
+    #[serde(default)]
+    pub log_folder: Option<String>,
+}
+
+impl Default for FrameCaptureConfig {
+    fn default() -> FrameCaptureConfig {
+        FrameCaptureConfig {
+            capture_hitches: false,
+            hitch_factor: default_hitch_factor(),
+            hitch_min_ms: default_hitch_min_ms(),
+            hitch_cooldown: default_hitch_cooldown(),
+            max_hitch_captures: default_max_hitch_captures(),
+            log_folder: None,
+        }
+    }
+}
+
+/// Detect the frames taking much longer than the average
+pub struct HitchDetector {
+    pub enabled: bool,
+    factor: f64,
+    min_frame_time: u128,
+    cooldown: u64,
+    max_hitches: usize,
+    last_time: Option<u128>,
+    average: Option<f64>,
+    frame: u64,
+    last_hitch: Option<u64>,
+    hitch_count: usize,
+}
+
+impl HitchDetector {
+    /// Number of frames to settle the average before hitches are reported
+    const WARMUP: u64 = 30;
+    const SMOOTHING: f64 = 0.1;
+
+    pub fn new(config: &FrameCaptureConfig) -> HitchDetector {
+        HitchDetector {
+            enabled: config.capture_hitches,
+            factor: config.hitch_factor.max(1.) as f64,
+            min_frame_time: config.hitch_min_ms as u128 * 1000,
+            cooldown: config.hitch_cooldown,
+            max_hitches: config.max_hitch_captures,
+            last_time: None,
+            average: None,
+            frame: 0,
+            last_hitch: None,
+            hitch_count: 0,
+        }
+    }
+
+    /// Number of the reported hitches
+    pub fn hitch_count(&self) -> usize {
+        self.hitch_count
+    }
+
+    /// Record the end of a frame (in microseconds) and check the frame time. The hitches are not
+    /// included in the average.
+    pub fn update(&mut self, time: u128) -> Option<CaptureReason> {
+        let last_time = self.last_time.replace(time);
+        let frame_time = time.saturating_sub(last_time?);
+        self.frame += 1;
+
+        let average = match self.average {
+            Some(average) => average,
+            None => {
+                self.average = Some(frame_time as f64);
+                return None;
+            }
+        };
+
+        let is_hitch =
+            self.frame > Self::WARMUP && frame_time >= self.min_frame_time && frame_time as f64 > average * self.factor;
+        if !is_hitch {
+            self.average = Some(average + (frame_time as f64 - average) * Self::SMOOTHING);
+            return None;
+        }
+
+        let cooling = self.last_hitch.map_or(false, |last| self.frame - last < self.cooldown);
+        if !self.enabled || cooling || self.hitch_count >= self.max_hitches {
+            return None;
+        }
+        self.last_hitch = Some(self.frame);
+        self.hitch_count += 1;
+        Some(CaptureReason::Hitch {
+            frame_time: frame_time as u64,
+            average: average as u64,
+        })
+    }
+}
+
+/// The commands recorded for a captured frame
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FrameCommandLog {
+    pub frame: u64,
+    pub reason: CaptureReason,
+    pub size: (u32, u32),
+    pub commands: Vec<String>,
+}
+
+/// Platform specific implementation of the frame capture
+pub trait FrameCaptureBackend: 'static + Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Start the capture before any command of the frame is recorded
+    fn start_capture(&mut self, frame: u64, reason: &CaptureReason);
+
+    /// End the capture after the frame is presented
+    fn end_capture(&mut self, log: &FrameCommandLog);
+}
+
+/// Dump the command log of the captured frame as json
+#[derive(Default)]
+pub struct CommandLogCapture {
+    #[cfg_attr(not(feature = "native"), allow(dead_code))]
+    folder: Option<String>,
+}
+
+impl CommandLogCapture {
+    pub fn new(folder: Option<String>) -> CommandLogCapture {
+        CommandLogCapture { folder }
+    }
+}
+
+impl FrameCaptureBackend for CommandLogCapture {
+    fn name(&self) -> &str {
+        "command log"
+    }
+
+    fn start_capture(&mut self, frame: u64, reason: &CaptureReason) {
+        log::info!("Capturing the command log of frame {} ({})", frame, reason);
+    }
+
+    fn end_capture(&mut self, log: &FrameCommandLog) {
+        let json = match serde_json::to_string_pretty(log) {
+            Ok(json) => json,
+            Err(err) => {
+                log::warn!("Failed to serialize the command log of frame {}: {}", log.frame, err);
+                return;
+            }
+        };
+
+        #[cfg(feature = "native")]
+        {
+            if let Some(folder) = &self.folder {
+                let path = std::path::Path::new(folder).join(format!("frame_{}.json", log.frame));
+                match std::fs::create_dir_all(folder).and_then(|_| std::fs::write(&path, &json)) {
+                    Ok(_) => log::info!("Command log of frame {} written to {:?}", log.frame, path),
+                    Err(err) => log::warn!("Failed to write the command log to {:?}: {}", path, err),
+                }
+                return;
+            }
+        }
+
+        log::info!("Command log of frame {}:\n{}", log.frame, json);
+    }
+}
+
+#[cfg(feature = "gpu-capture")]
+mod renderdoc_capture {
+    use super::{CaptureReason, FrameCaptureBackend, FrameCommandLog};
+    use renderdoc::{RenderDoc, V110};
+    use std::{ptr, sync::Mutex};
+
+    /// Capture the frames with the in-application API of RenderDoc. It is available only when the
+    /// application is launched from (or injected by) RenderDoc.
+    pub struct RenderDocCapture {
+        api: Mutex<RenderDoc<V110>>,
+    }
+
+    // safety: the in-application API of RenderDoc is thread safe
+    unsafe impl Send for RenderDocCapture {}
+    unsafe impl Sync for RenderDocCapture {}
+
+    impl RenderDocCapture {
+        pub fn new() -> Result<RenderDocCapture, String> {
+            let api = RenderDoc::<V110>::new().map_err(|err| format!("{}", err))?;
+            Ok(RenderDocCapture { api: Mutex::new(api) })
+        }
+    }
+
+    impl FrameCaptureBackend for RenderDocCapture {
+        fn name(&self) -> &str {
+            "renderdoc"
+        }
+
+        fn start_capture(&mut self, frame: u64, reason: &CaptureReason) {
+            log::info!("Starting RenderDoc capture of frame {} ({})", frame, reason);
+            // null device and window captures the active ones
+            self.api.lock().unwrap().start_frame_capture(ptr::null(), ptr::null());
+        }
+
+        fn end_capture(&mut self, log: &FrameCommandLog) {
+            self.api.lock().unwrap().end_frame_capture(ptr::null(), ptr::null());
+            log::info!(
+                "RenderDoc capture of frame {} completed with {} commands",
+                log.frame,
+                log.commands.len()
+            );
+        }
+    }
+}
+#[cfg(feature = "gpu-capture")]
+pub use self::renderdoc_capture::*;
+
+/// Resource to capture a frame on request or when a hitch is detected. The capture starts with the next
+/// frame and the commands of the frame are collected into a log for the backends without a gpu debugger.
+pub struct FrameCapture {
+    backend: Box<dyn FrameCaptureBackend>,
+    hitches: HitchDetector,
+    frame: u64,
+    pending: Option<CaptureReason>,
+    active: Option<FrameCommandLog>,
+    last: Option<FrameCommandLog>,
+}
+
+impl Default for FrameCapture {
+    fn default() -> Self {
+        FrameCapture::new(&FrameCaptureConfig::default(), Box::new(CommandLogCapture::default()))
+    }
+}
+
+impl FrameCapture {
+    pub fn new(config: &FrameCaptureConfig, backend: Box<dyn FrameCaptureBackend>) -> FrameCapture {
+        FrameCapture {
+            backend,
+            hitches: HitchDetector::new(config),
+            frame: 0,
+            pending: None,
+            active: None,
+            last: None,
+        }
+    }
+
+    /// Create the capture with the best backend of the platform
+    pub fn with_default_backend(config: &FrameCaptureConfig) -> FrameCapture {
+        #[cfg(feature = "gpu-capture")]
+        let backend: Box<dyn FrameCaptureBackend> = match RenderDocCapture::new() {
+            Ok(renderdoc) => Box::new(renderdoc),
+            Err(err) => {
+                log::info!("RenderDoc is not available ({}), using command logs", err);
+                Box::new(CommandLogCapture::new(config.log_folder.clone()))
+            }
+        };
+        #[cfg(not(feature = "gpu-capture"))]
+        let backend: Box<dyn FrameCaptureBackend> = Box::new(CommandLogCapture::new(config.log_folder.clone()));
+
+        log::info!("Frame capture backend: {}", backend.name());
+        FrameCapture::new(config, backend)
+    }
+
+    pub fn backend_name(&self) -> &str {
+        self.backend.name()
+    }
+
+    pub fn hitches(&self) -> &HitchDetector {
+        &self.hitches
+    }
+
+    pub fn hitches_mut(&mut self) -> &mut HitchDetector {
+        &mut self.hitches
+    }
+
+    /// Request the capture of the next frame, it is ignored if a capture is already requested
+    pub fn trigger(&mut self, reason: CaptureReason) {
+        if self.pending.is_none() {
+            self.pending = Some(reason);
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        self.active.is_some()
+    }
+
+    /// The command log of the last completed capture
+    pub fn last_capture(&self) -> Option<&FrameCommandLog> {
+        self.last.as_ref()
+    }
+
+    /// Execute a console command: `capture` (or empty), `hitch on`, `hitch off` or `status`
+    pub fn execute(&mut self, command: &str) -> Result<String, String> {
+        let mut args = command.split_whitespace();
+        match (args.next(), args.next()) {
+            (None, _) | (Some("capture"), None) => {
+                self.trigger(CaptureReason::Console);
+                Ok("Capturing the next frame".to_owned())
+            }
+            (Some("hitch"), Some("on")) => {
+                self.hitches.enabled = true;
+                Ok("Hitch capture enabled".to_owned())
+            }
+            (Some("hitch"), Some("off")) => {
+                self.hitches.enabled = false;
+                Ok("Hitch capture disabled".to_owned())
+            }
+            (Some("status"), None) => Ok(format!(
+                "Backend: {}, hitch capture: {}, hitches: {}",
+                self.backend.name(),
+                if self.hitches.enabled { "on" } else { "off" },
+                self.hitches.hitch_count()
+            )),
+            _ => Err(format!("Unknown capture command: {}", command)),
+        }
+    }
+
+    /// Start the capture of the frame if it was requested
+    pub fn begin_frame(&mut self, size: (u32, u32)) {
+        self.frame += 1;
+        if let Some(reason) = self.pending.take() {
+            self.backend.start_capture(self.frame, &reason);
+            self.active = Some(FrameCommandLog {
+                frame: self.frame,
+                reason,
+                size,
+                commands: Vec::new(),
+            });
+        }
+    }
+
+    /// Record a command of the captured frame, it is ignored when no capture is in progress
+    pub fn record_command<S: Into<String>>(&mut self, command: S) {
+        if let Some(log) = &mut self.active {
+            log.commands.push(command.into());
+        }
+    }
+
+    /// Complete the capture of the frame and check for hitches. The time of the end of the frame is given
+    /// in microseconds.
+    pub fn end_frame(&mut self, time: u128) {
+        if let Some(log) = self.active.take() {
+            self.backend.end_capture(&log);
+            self.last = Some(log);
+        }
+
+        if let Some(hitch) = self.hitches.update(time) {
+            log::warn!("Frame {}: {}", self.frame, hitch);
+            self.trigger(hitch);
+        }
+    }
+}
@@ -2,8 +2,18 @@ use crate::assets::PipelineStateDescriptor;
 
 //use shine_ecs::resources::{Res, ResMut};
 
+/// The texture the frame is rendered into
+enum FrameOutput {
+    SwapChain(wgpu::SwapChainTexture),
+    /// Offscreen texture of a screenshot, it is copied to a readback buffer instead of presenting
+    Capture {
+        texture: wgpu::Texture,
+        view: wgpu::TextureView,
+    },
+}
+
 struct Inner {
-    frame: wgpu::SwapChainTexture,
+    output: FrameOutput,
     descriptor: wgpu::SwapChainDescriptor,
 }
 
@@ -14,13 +24,41 @@ pub struct FrameTarget {
 
 impl FrameTarget {
     pub fn set(&mut self, frame: wgpu::SwapChainTexture, descriptor: wgpu::SwapChainDescriptor) {
-        self.inner = Some(Inner { frame, descriptor });
+        self.inner = Some(Inner {
+            output: FrameOutput::SwapChain(frame),
+            descriptor,
+        });
+    }
+
+    /// Render the frame into an offscreen texture for a screenshot
+    pub fn set_capture(&mut self, texture: wgpu::Texture, descriptor: wgpu::SwapChainDescriptor) {
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        self.inner = Some(Inner {
+            output: FrameOutput::Capture { texture, view },
+            descriptor,
+        });
     }
 
     pub fn present(&mut self) {
         self.inner = None;
     }
 
+    /// The view of the frame the techniques render into
+    pub fn view(&self) -> Option<&wgpu::TextureView> {
+        self.inner.as_ref().map(|x| match &x.output {
+            FrameOutput::SwapChain(frame) => &frame.view,
+            FrameOutput::Capture { view, .. } => view,
+        })
+    }
+
+    /// The offscreen texture if the frame is captured
+    pub fn capture_texture(&self) -> Option<&wgpu::Texture> {
+        self.inner.as_ref().and_then(|x| match &x.output {
+            FrameOutput::Capture { texture, .. } => Some(texture),
+            _ => None,
+        })
+    }
+
     pub fn size(&self) -> (u32, u32) {
         self.inner
             .as_ref()
@@ -43,6 +81,7 @@ impl FrameTarget {
                     write_mask: wgpu::ColorWrite::ALL,
                 }],
                 depth_state: None,
+                color_stage: None,
             })
             .unwrap_or_default()
     }
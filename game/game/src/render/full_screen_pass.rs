@@ -0,0 +1,112 @@
+use crate::{
+    assets::{Uniform, UniformSemantic},
+    render::CompiledPipeline,
+};
+use wgpu::util::DeviceExt;
+
+/// Color attachment of a full screen pass
+pub struct FullScreenTarget<'a> {
+    pub view: &'a wgpu::TextureView,
+    /// Clear color of the target, if not given the content is preserved
+    pub clear: Option<wgpu::Color>,
+}
+
+impl<'a> FullScreenTarget<'a> {
+    /// Draw over the content of the target
+    pub fn load(view: &'a wgpu::TextureView) -> FullScreenTarget<'a> {
+        FullScreenTarget { view, clear: None }
+    }
+
+    pub fn clear(view: &'a wgpu::TextureView, color: wgpu::Color) -> FullScreenTarget<'a> {
+        FullScreenTarget {
+            view,
+            clear: Some(color),
+        }
+    }
+}
+
+/// Create a uniform buffer initialized with the given value
+pub fn create_uniform_buffer<U: Uniform>(device: &wgpu::Device, label: &str, value: &U) -> wgpu::Buffer {
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(label),
+        contents: bytemuck::bytes_of(value),
+        usage: wgpu::BufferUsage::UNIFORM,
+    })
+}
+
+/// Create a bilinear sampler clamped to the edge to read the render targets
+pub fn create_target_sampler(device: &wgpu::Device, label: &str) -> wgpu::Sampler {
+    device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some(label),
+        address_mode_u: wgpu::AddressMode::ClampToEdge,
+        address_mode_v: wgpu::AddressMode::ClampToEdge,
+        address_mode_w: wgpu::AddressMode::ClampToEdge,
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        mipmap_filter: wgpu::FilterMode::Linear,
+        ..Default::default()
+    })
+}
+
+/// Bind the uniforms of the pipeline and record a render pass drawing a full screen triangle. The vertices
+/// are generated from the vertex index by the vertex shader, no vertex buffer is bound.
+/// If a uniform is not provided, nothing is recorded and the semantic of the uniform is returned.
+pub fn draw_full_screen<'a, F>(
+    device: &wgpu::Device,
+    encoder: &mut wgpu::CommandEncoder,
+    pipeline: &CompiledPipeline,
+    targets: &[FullScreenTarget<'_>],
+    get_value: F,
+) -> Result<(), UniformSemantic>
+where
+    F: FnMut(&UniformSemantic) -> Option<wgpu::BindingResource<'a>>,
+{
+    let bind_groups = pipeline.create_bind_groups(device, get_value)?;
+
+    let color_attachments: Vec<_> = targets
+        .iter()
+        .map(|target| wgpu::RenderPassColorAttachmentDescriptor {
+            attachment: target.view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: match target.clear {
+                    Some(color) => wgpu::LoadOp::Clear(color),
+                    None => wgpu::LoadOp::Load,
+                },
+                store: true,
+            },
+        })
+        .collect();
+    let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        color_attachments: &color_attachments,
+        depth_stencil_attachment: None,
+    });
+    pass.set_pipeline(&pipeline.pipeline);
+    for (index, bind_group) in bind_groups.iter().enumerate() {
+        pass.set_bind_group(index as u32, bind_group, &[]);
+    }
+    pass.draw(0..3, 0..1);
+    Ok(())
+}
+
+/// Report the uniforms not provided by a pass once instead of in every frame
+#[derive(Default)]
+pub struct MissingBinding(Option<UniformSemantic>);
+
+impl MissingBinding {
+    pub fn update(&mut self, pass: &str, result: Result<(), UniformSemantic>) {
+        match result {
+            Ok(()) => self.0 = None,
+            Err(semantic) => {
+                if self.0.as_ref() != Some(&semantic) {
+                    log::warn!(
+                        "[{}] No resource for the uniform {:?}, the pass is skipped",
+                        pass,
+                        semantic
+                    );
+                    self.0 = Some(semantic);
+                }
+            }
+        }
+    }
+}
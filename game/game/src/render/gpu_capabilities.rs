@@ -0,0 +1,113 @@
+use crate::render::{RenderConfig, RenderError};
+use std::num::NonZeroU8;
+
+/// Optional features requested from the adapter if available.
+/// Timestamp queries are not exposed by the current wgpu version, thus they are never granted.
+pub const OPTIONAL_FEATURES: wgpu::Features = wgpu::Features::from_bits_truncate(
+    wgpu::Features::TEXTURE_COMPRESSION_BC.bits()
+        | wgpu::Features::SAMPLED_TEXTURE_BINDING_ARRAY.bits()
+        | wgpu::Features::SAMPLED_TEXTURE_ARRAY_DYNAMIC_INDEXING.bits()
+        | wgpu::Features::DEPTH_CLAMPING.bits()
+        | wgpu::Features::PUSH_CONSTANTS.bits(),
+);
+
+/// The features and limits granted by the device. Techniques and shader variants shall
+/// be selected based on these capabilities instead of assuming the defaults.
+#[derive(Clone, Debug)]
+pub struct GpuCapabilities {
+    features: wgpu::Features,
+    limits: wgpu::Limits,
+    max_anisotropy: u8,
+}
+
+impl GpuCapabilities {
+    /// Return the features to request from an adapter
+    pub fn negotiate_features(adapter_features: wgpu::Features, config: &RenderConfig) -> wgpu::Features {
+        if config.disable_optional_features {
+            wgpu::Features::empty()
+        } else {
+            adapter_features & OPTIONAL_FEATURES
+        }
+    }
+
+    pub fn new(features: wgpu::Features, limits: wgpu::Limits, config: &RenderConfig) -> GpuCapabilities {
+        GpuCapabilities {
+            features,
+            limits,
+            max_anisotropy: config.max_anisotropy,
+        }
+    }
+
+    pub fn features(&self) -> wgpu::Features {
+        self.features
+    }
+
+    pub fn limits(&self) -> &wgpu::Limits {
+        &self.limits
+    }
+
+    /// Maximum anisotropy for the samplers, 0 if anisotropic filtering is disabled
+    pub fn max_anisotropy(&self) -> u8 {
+        self.max_anisotropy
+    }
+
+    pub fn texture_compression_bc(&self) -> bool {
+        self.features.contains(wgpu::Features::TEXTURE_COMPRESSION_BC)
+    }
+
+    pub fn texture_binding_array(&self) -> bool {
+        self.features.contains(
+            wgpu::Features::SAMPLED_TEXTURE_BINDING_ARRAY | wgpu::Features::SAMPLED_TEXTURE_ARRAY_DYNAMIC_INDEXING,
+        )
+    }
+
+    pub fn timestamp_query(&self) -> bool {
+        false
+    }
+
+    /// Clamp the anisotropy of a sampler to the granted limit.
+    pub fn clamp_anisotropy(&self, anisotropy: Option<NonZeroU8>) -> Option<NonZeroU8> {
+        anisotropy.and_then(|a| NonZeroU8::new(a.get().min(self.max_anisotropy)))
+    }
+
+    /// Check if a texture format can be sampled with the granted features.
+    pub fn supports_texture_format(&self, format: wgpu::TextureFormat) -> bool {
+        use wgpu::TextureFormat::*;
+        match format {
+            Bc1RgbaUnorm | Bc1RgbaUnormSrgb | Bc2RgbaUnorm | Bc2RgbaUnormSrgb | Bc3RgbaUnorm | Bc3RgbaUnormSrgb
+            | Bc4RUnorm | Bc4RSnorm | Bc5RgUnorm | Bc5RgSnorm | Bc6hRgbSfloat | Bc6hRgbUfloat | Bc7RgbaUnorm
+            | Bc7RgbaUnormSrgb => self.texture_compression_bc(),
+            _ => true,
+        }
+    }
+
+    /// Check if all the required features were granted.
+    pub fn require(&self, name: &str, features: wgpu::Features) -> Result<(), RenderError> {
+        if self.features.contains(features) {
+            Ok(())
+        } else {
+            Err(RenderError::MissingCapability {
+                name: name.to_owned(),
+                features: features - self.features,
+            })
+        }
+    }
+
+    /// Defines for the shader variant selection based on the granted capabilities.
+    pub fn shader_defines(&self) -> Vec<&'static str> {
+        let mut defines = Vec::new();
+        if self.texture_compression_bc() {
+            defines.push("GPU_TEXTURE_COMPRESSION_BC");
+        }
+        if self.texture_binding_array() {
+            defines.push("GPU_TEXTURE_BINDING_ARRAY");
+        }
+        if self.features.contains(wgpu::Features::PUSH_CONSTANTS) {
+            defines.push("GPU_PUSH_CONSTANTS");
+        }
+        if self.max_anisotropy > 1 {
+            defines.push("GPU_ANISOTROPY");
+        }
+        defines
+    }
+}
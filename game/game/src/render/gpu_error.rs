@@ -0,0 +1,90 @@
+use crate::render::RenderError;
+use serde::{Deserialize, Serialize};
+use std::{
+    any::Any,
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Mutex},
+};
+
+/// The kind of the gpu operation a validation error was captured from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GpuErrorScope {
+    Shader,
+    Pipeline,
+    Submission,
+}
+
+impl fmt::Display for GpuErrorScope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            GpuErrorScope::Shader => "shader",
+            GpuErrorScope::Pipeline => "pipeline",
+            GpuErrorScope::Submission => "submission",
+        };
+        f.write_str(name)
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        (*message).to_owned()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Unknown gpu error".to_owned()
+    }
+}
+
+/// Run a gpu operation in an error scope and convert the captured validation error into a RenderError.
+/// The wgpu (0.6) backend reports the validation errors by a panic instead of an error scope, thus the
+/// panic is caught here before it could poison the rendering. The panic hook still prints the message.
+pub fn capture_gpu_errors<R, F>(scope: GpuErrorScope, asset: &str, operation: F) -> Result<R, RenderError>
+where
+    F: FnOnce() -> R,
+{
+    panic::catch_unwind(AssertUnwindSafe(operation)).map_err(|payload| RenderError::Validation {
+        scope,
+        asset: asset.to_owned(),
+        message: panic_message(&*payload),
+    })
+}
+
+/// Thread safe collector of the gpu validation errors. Errors are captured by the asset loaders and by
+/// the frame submission, and they are published once per frame as `Events<RenderError>`.
+#[derive(Clone, Default)]
+pub struct GpuErrorSink {
+    errors: Arc<Mutex<Vec<RenderError>>>,
+}
+
+impl GpuErrorSink {
+    pub fn report(&self, error: RenderError) {
+        log::error!("{}", error);
+        let mut errors = self.errors.lock().unwrap();
+        errors.push(error);
+    }
+
+    /// Run a gpu operation in an error scope and report the captured error
+    pub fn capture<R, F>(&self, scope: GpuErrorScope, asset: &str, operation: F) -> Option<R>
+    where
+        F: FnOnce() -> R,
+    {
+        match capture_gpu_errors(scope, asset, operation) {
+            Ok(result) => Some(result),
+            Err(err) => {
+                self.report(err);
+                None
+            }
+        }
+    }
+
+    pub fn has_errors(&self) -> bool {
+        let errors = self.errors.lock().unwrap();
+        !errors.is_empty()
+    }
+
+    pub fn take_errors(&self) -> Vec<RenderError> {
+        let mut errors = self.errors.lock().unwrap();
+        errors.drain(..).collect()
+    }
+}
@@ -0,0 +1,344 @@
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, collections::HashMap, fmt};
+
+const MIB: u64 = 1024 * 1024;
+
+/// Category of the gpu allocations for the usage reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GpuMemoryCategory {
+    Texture,
+    Buffer,
+    Model,
+    RenderTarget,
+}
+
+impl GpuMemoryCategory {
+    pub const ALL: [GpuMemoryCategory; 4] = [
+        GpuMemoryCategory::Texture,
+        GpuMemoryCategory::Buffer,
+        GpuMemoryCategory::Model,
+        GpuMemoryCategory::RenderTarget,
+    ];
+
+    fn index(self) -> usize {
+        GpuMemoryCategory::ALL.iter().position(|c| *c == self).unwrap()
+    }
+}
+
+impl fmt::Display for GpuMemoryCategory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            GpuMemoryCategory::Texture => "texture",
+            GpuMemoryCategory::Buffer => "buffer",
+            GpuMemoryCategory::Model => "model",
+            GpuMemoryCategory::RenderTarget => "render target",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Size of a texel block (bytes, block width) of a format, the compressed formats use 4x4 blocks
+fn texel_block(format: wgpu::TextureFormat) -> (u64, u32) {
+    use wgpu::TextureFormat::*;
+    match format {
+        R8Unorm | R8Snorm | R8Uint | R8Sint => (1, 1),
+        R16Uint | R16Sint | R16Float | Rg8Unorm | Rg8Snorm | Rg8Uint | Rg8Sint => (2, 1),
+        Rg32Uint | Rg32Sint | Rg32Float | Rgba16Uint | Rgba16Sint | Rgba16Float => (8, 1),
+        Rgba32Uint | Rgba32Sint | Rgba32Float => (16, 1),
+        Bc1RgbaUnorm | Bc1RgbaUnormSrgb | Bc4RUnorm | Bc4RSnorm => (8, 4),
+        Bc2RgbaUnorm | Bc2RgbaUnormSrgb | Bc3RgbaUnorm | Bc3RgbaUnormSrgb | Bc5RgUnorm | Bc5RgSnorm | Bc6hRgbSfloat
+        | Bc6hRgbUfloat | Bc7RgbaUnorm | Bc7RgbaUnormSrgb => (16, 4),
+        // rgba8, bgra8, packed formats, 32 bit single channel and the depth formats
+        _ => (4, 1),
+    }
+}
+
+/// Estimated memory size of a texture with the given number of mip levels
+pub fn texture_memory_size(format: wgpu::TextureFormat, size: (u32, u32, u32), mip_level_count: u32) -> u64 {
+    let (block_size, block_width) = texel_block(format);
+    let (mut width, mut height, depth) = size;
+    let mut total = 0;
+    for _ in 0..mip_level_count.max(1) {
+        let blocks_x = ((width + block_width - 1) / block_width) as u64;
+        let blocks_y = ((height + block_width - 1) / block_width) as u64;
+        total += blocks_x * blocks_y * depth as u64 * block_size;
+        width = (width / 2).max(1);
+        height = (height / 2).max(1);
+    }
+    total
+}
+
+fn default_high_watermark() -> f32 {
+    0.9
+}
+
+fn default_low_watermark() -> f32 {
+    0.75
+}
+
+/// Configuration of the gpu memory budget
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GpuMemoryConfig {
+    /// The budget in MiB, if not given it is estimated from the type of the adapter
+    #[serde(default)]
+    pub budget_mb: Option<u64>,
+    /// Ratio of the budget above which the low priority allocations are evicted
+    #[serde(default = "default_high_watermark")]
+    pub high_watermark: f32,
+    /// Ratio of the budget the eviction frees the memory down to
+    #[serde(default = "default_low_watermark")]
+    pub low_watermark: f32,
+}
+
+impl Default for GpuMemoryConfig {
+    fn default() -> GpuMemoryConfig {
+        GpuMemoryConfig {
+            budget_mb: None,
+            high_watermark: default_high_watermark(),
+            low_watermark: default_low_watermark(),
+        }
+    }
+}
+
+impl GpuMemoryConfig {
+    /// The budget in bytes. The current wgpu does not report the available memory, thus the budget is a
+    /// conservative estimate based on the adapter type unless it is given explicitly.
+    pub fn budget(&self, adapter: &wgpu::AdapterInfo) -> u64 {
+        match self.budget_mb {
+            Some(budget_mb) => budget_mb * MIB,
+            None => match adapter.device_type {
+                wgpu::DeviceType::DiscreteGpu => 2048 * MIB,
+                wgpu::DeviceType::IntegratedGpu | wgpu::DeviceType::VirtualGpu => 512 * MIB,
+                _ => 256 * MIB,
+            },
+        }
+    }
+}
+
+/// A tracked allocation
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpuAllocation {
+    pub category: GpuMemoryCategory,
+    pub size: u64,
+    /// Importance of the allocation (ex. inverse of the distance of a texture), lower is evicted first
+    pub priority: f32,
+    /// The frame the allocation was last used
+    pub last_used: u64,
+    /// Pinned allocations (ex. render targets) are never evicted
+    pub pinned: bool,
+}
+
+/// Memory usage by category
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GpuMemoryUsage {
+    pub budget: u64,
+    pub total: u64,
+    pub categories: Vec<(GpuMemoryCategory, u64)>,
+}
+
+impl GpuMemoryUsage {
+    pub fn ratio(&self) -> f32 {
+        if self.budget == 0 {
+            0.
+        } else {
+            self.total as f32 / self.budget as f32
+        }
+    }
+}
+
+impl fmt::Display for GpuMemoryUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.1}/{:.1} MiB",
+            self.total as f64 / MIB as f64,
+            self.budget as f64 / MIB as f64
+        )?;
+        for (category, size) in &self.categories {
+            write!(f, ", {}: {:.1} MiB", category, *size as f64 / MIB as f64)?;
+        }
+        Ok(())
+    }
+}
+
+/// Warning of the memory tracker, published as `Events<GpuMemoryWarning>` for the telemetry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GpuMemoryWarning {
+    pub usage: GpuMemoryUsage,
+    /// The keys of the evicted allocations
+    pub evicted: Vec<String>,
+}
+
+impl fmt::Display for GpuMemoryWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Gpu memory budget exceeded ({})", self.usage)?;
+        if !self.evicted.is_empty() {
+            write!(f, ", evicted {} allocations", self.evicted.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// Track the gpu allocations against the budget. When an allocation would exceed the high watermark, the
+/// low priority (then least recently used) allocations are evicted down to the low watermark before the
+/// device runs out of memory. The owner stores poll the evicted keys and release the resources.
+pub struct GpuMemoryTracker {
+    budget: u64,
+    high_watermark: u64,
+    low_watermark: u64,
+    allocations: HashMap<String, GpuAllocation>,
+    usage: [u64; 4],
+    frame: u64,
+    evictions: Vec<(GpuMemoryCategory, String)>,
+    warnings: Vec<GpuMemoryWarning>,
+}
+
+impl GpuMemoryTracker {
+    pub fn new(budget: u64, config: &GpuMemoryConfig) -> GpuMemoryTracker {
+        let high = config.high_watermark.max(0.).min(1.);
+        let low = config.low_watermark.max(0.).min(high);
+        GpuMemoryTracker {
+            budget,
+            high_watermark: (budget as f64 * high as f64) as u64,
+            low_watermark: (budget as f64 * low as f64) as u64,
+            allocations: HashMap::new(),
+            usage: [0; 4],
+            frame: 0,
+            evictions: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn budget(&self) -> u64 {
+        self.budget
+    }
+
+    pub fn total(&self) -> u64 {
+        self.usage.iter().sum()
+    }
+
+    pub fn usage(&self) -> GpuMemoryUsage {
+        GpuMemoryUsage {
+            budget: self.budget,
+            total: self.total(),
+            categories: GpuMemoryCategory::ALL
+                .iter()
+                .map(|category| (*category, self.usage[category.index()]))
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&GpuAllocation> {
+        self.allocations.get(key)
+    }
+
+    /// Register an allocation, the previous allocation of the key is replaced. Low priority allocations are
+    /// evicted if the new allocation would exceed the high watermark.
+    pub fn allocate(&mut self, key: &str, category: GpuMemoryCategory, size: u64, priority: f32) {
+        self.insert(
+            key,
+            GpuAllocation {
+                category,
+                size,
+                priority,
+                last_used: self.frame,
+                pinned: false,
+            },
+        );
+    }
+
+    /// Register an allocation that is never evicted
+    pub fn allocate_pinned(&mut self, key: &str, category: GpuMemoryCategory, size: u64) {
+        self.insert(
+            key,
+            GpuAllocation {
+                category,
+                size,
+                priority: f32::MAX,
+                last_used: self.frame,
+                pinned: true,
+            },
+        );
+    }
+
+    fn insert(&mut self, key: &str, allocation: GpuAllocation) {
+        self.release(key);
+        let evicted = if self.total() + allocation.size > self.high_watermark {
+            let target = self.low_watermark.saturating_sub(allocation.size);
+            Some(self.evict(target))
+        } else {
+            None
+        };
+        self.usage[allocation.category.index()] += allocation.size;
+        self.allocations.insert(key.to_owned(), allocation);
+
+        if let Some(evicted) = evicted {
+            let warning = GpuMemoryWarning {
+                usage: self.usage(),
+                evicted,
+            };
+            log::warn!("{}", warning);
+            self.warnings.push(warning);
+        }
+    }
+
+    /// Update the priority of an allocation and mark it as used in the current frame
+    pub fn touch(&mut self, key: &str, priority: f32) {
+        let frame = self.frame;
+        if let Some(allocation) = self.allocations.get_mut(key) {
+            allocation.last_used = frame;
+            if !allocation.pinned {
+                allocation.priority = priority;
+            }
+        }
+    }
+
+    pub fn release(&mut self, key: &str) -> Option<GpuAllocation> {
+        let allocation = self.allocations.remove(key)?;
+        self.usage[allocation.category.index()] -= allocation.size;
+        Some(allocation)
+    }
+
+    /// Evict the allocations in the order of priority until the usage is below the target
+    fn evict(&mut self, target: u64) -> Vec<String> {
+        let mut candidates = self
+            .allocations
+            .iter()
+            .filter(|(_, allocation)| !allocation.pinned)
+            .map(|(key, allocation)| (key.clone(), allocation.priority, allocation.last_used))
+            .collect::<Vec<_>>();
+        candidates.sort_by(|a, b| {
+            a.1.partial_cmp(&b.1)
+                .unwrap_or(Ordering::Equal)
+                .then(a.2.cmp(&b.2))
+                .then(a.0.cmp(&b.0))
+        });
+
+        let mut evicted = Vec::new();
+        for (key, _, _) in candidates {
+            if self.total() <= target {
+                break;
+            }
+            if let Some(allocation) = self.release(&key) {
+                log::debug!("Evicting {} {} ({} bytes)", allocation.category, key, allocation.size);
+                self.evictions.push((allocation.category, key.clone()));
+                evicted.push(key);
+            }
+        }
+
+        evicted
+    }
+
+    /// Take the keys of the evicted allocations of a category, the owner store shall release them
+    pub fn take_evictions(&mut self, category: GpuMemoryCategory) -> Vec<String> {
+        let (taken, kept) = self.evictions.drain(..).partition(|(c, _)| *c == category);
+        self.evictions = kept;
+        taken.into_iter().map(|(_, key)| key).collect()
+    }
+
+    /// Advance the frame counter and take the warnings of the frame
+    pub fn end_frame(&mut self) -> Vec<GpuMemoryWarning> {
+        self.frame += 1;
+        self.warnings.drain(..).collect()
+    }
+}
@@ -0,0 +1,244 @@
+use crate::render::{GpuLight, Light};
+use nalgebra::{Isometry3, Point3};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Dimension of the froxel grid
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterConfig {
+    pub tiles_x: u32,
+    pub tiles_y: u32,
+    pub slices_z: u32,
+    /// Maximum number of lights per cluster, the extra lights are dropped
+    pub max_lights_per_cluster: u32,
+}
+
+impl Default for ClusterConfig {
+    fn default() -> ClusterConfig {
+        ClusterConfig {
+            tiles_x: 16,
+            tiles_y: 9,
+            slices_z: 24,
+            max_lights_per_cluster: 128,
+        }
+    }
+}
+
+/// View space axis aligned bounding box of a cluster
+#[derive(Debug, Clone, Copy)]
+pub struct ClusterBounds {
+    pub min: Point3<f32>,
+    pub max: Point3<f32>,
+}
+
+impl ClusterBounds {
+    fn intersects_sphere(&self, center: &Point3<f32>, radius: f32) -> bool {
+        let dist2: f32 = (0..3)
+            .map(|i| {
+                let v = center[i];
+                if v < self.min[i] {
+                    (self.min[i] - v).powi(2)
+                } else if v > self.max[i] {
+                    (v - self.max[i]).powi(2)
+                } else {
+                    0.
+                }
+            })
+            .sum();
+        dist2 <= radius * radius
+    }
+}
+
+/// Statistics of the last light assignment
+#[derive(Debug, Clone, Default)]
+pub struct ClusterStats {
+    pub light_count: usize,
+    pub cluster_count: usize,
+    pub occupied_clusters: usize,
+    pub max_lights_per_cluster: usize,
+    pub light_index_count: usize,
+    pub dropped_lights: usize,
+}
+
+impl fmt::Display for ClusterStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "lights: {}, clusters: {}/{}, max per cluster: {}, indices: {}, dropped: {}",
+            self.light_count,
+            self.occupied_clusters,
+            self.cluster_count,
+            self.max_lights_per_cluster,
+            self.light_index_count,
+            self.dropped_lights
+        )
+    }
+}
+
+/// Result of the light assignment in the layout of the shader storage buffers.
+#[derive(Debug, Default)]
+pub struct ClusterLights {
+    /// View space lights
+    pub lights: Vec<GpuLight>,
+    /// (offset, count) into the light_indices for each cluster
+    pub clusters: Vec<[u32; 2]>,
+    /// Light indices of the clusters
+    pub light_indices: Vec<u32>,
+    pub stats: ClusterStats,
+}
+
+/// Froxel grid built from a perspective camera. Depth is sliced exponentially, thus
+/// clusters are close to cubic along the whole frustum.
+/// The assignment is performed on the CPU and mirrors the compute pass, the result is
+/// uploaded to the storage buffers read by the lighting shaders.
+pub struct ClusterGrid {
+    config: ClusterConfig,
+    znear: f32,
+    zfar: f32,
+    bounds: Vec<ClusterBounds>,
+}
+
+impl ClusterGrid {
+    pub fn new(config: ClusterConfig, fovy: f32, aspect: f32, znear: f32, zfar: f32) -> ClusterGrid {
+        let mut grid = ClusterGrid {
+            config,
+            znear,
+            zfar,
+            bounds: Vec::new(),
+        };
+        grid.rebuild(fovy, aspect, znear, zfar);
+        grid
+    }
+
+    pub fn config(&self) -> &ClusterConfig {
+        &self.config
+    }
+
+    pub fn cluster_count(&self) -> usize {
+        self.bounds.len()
+    }
+
+    pub fn bounds(&self) -> &[ClusterBounds] {
+        &self.bounds
+    }
+
+    /// Index of the cluster in the (x, y, z) order used by the shaders.
+    pub fn cluster_index(&self, x: u32, y: u32, z: u32) -> usize {
+        ((z * self.config.tiles_y + y) * self.config.tiles_x + x) as usize
+    }
+
+    /// Depth slice of a positive view space distance.
+    pub fn slice_of_depth(&self, depth: f32) -> u32 {
+        if depth <= self.znear {
+            0
+        } else {
+            let slice = (depth / self.znear).ln() / (self.zfar / self.znear).ln() * self.config.slices_z as f32;
+            (slice as u32).min(self.config.slices_z - 1)
+        }
+    }
+
+    fn slice_depth(&self, z: u32) -> f32 {
+        self.znear * (self.zfar / self.znear).powf(z as f32 / self.config.slices_z as f32)
+    }
+
+    /// Rebuild the froxels when the camera projection changes.
+    pub fn rebuild(&mut self, fovy: f32, aspect: f32, znear: f32, zfar: f32) {
+        self.znear = znear;
+        self.zfar = zfar;
+        let ClusterConfig {
+            tiles_x,
+            tiles_y,
+            slices_z,
+            ..
+        } = self.config;
+        let tan_y = (fovy * 0.5).tan();
+        let tan_x = tan_y * aspect;
+
+        self.bounds.clear();
+        self.bounds.reserve((tiles_x * tiles_y * slices_z) as usize);
+        for z in 0..slices_z {
+            let near = self.slice_depth(z);
+            let far = self.slice_depth(z + 1);
+            for y in 0..tiles_y {
+                let y0 = -1. + 2. * y as f32 / tiles_y as f32;
+                let y1 = -1. + 2. * (y + 1) as f32 / tiles_y as f32;
+                for x in 0..tiles_x {
+                    let x0 = -1. + 2. * x as f32 / tiles_x as f32;
+                    let x1 = -1. + 2. * (x + 1) as f32 / tiles_x as f32;
+
+                    // camera looks along the -z axis
+                    let mut min = Point3::new(std::f32::MAX, std::f32::MAX, -far);
+                    let mut max = Point3::new(std::f32::MIN, std::f32::MIN, -near);
+                    for &d in &[near, far] {
+                        for &(nx, ny) in &[(x0, y0), (x1, y0), (x0, y1), (x1, y1)] {
+                            let px = nx * tan_x * d;
+                            let py = ny * tan_y * d;
+                            min.x = min.x.min(px);
+                            min.y = min.y.min(py);
+                            max.x = max.x.max(px);
+                            max.y = max.y.max(py);
+                        }
+                    }
+                    self.bounds.push(ClusterBounds { min, max });
+                }
+            }
+        }
+    }
+
+    /// Assign the lights to the clusters.
+    pub fn assign(&self, view: &Isometry3<f32>, lights: &[Light]) -> ClusterLights {
+        let max_per_cluster = self.config.max_lights_per_cluster as usize;
+        let mut per_cluster: Vec<Vec<u32>> = vec![Vec::new(); self.bounds.len()];
+        let mut stats = ClusterStats {
+            light_count: lights.len(),
+            cluster_count: self.bounds.len(),
+            ..Default::default()
+        };
+
+        for (light_id, light) in lights.iter().enumerate() {
+            let (center, radius) = light.view_bounds(view);
+            let depth_min = -center.z - radius;
+            let depth_max = -center.z + radius;
+            if depth_max < self.znear || depth_min > self.zfar {
+                continue;
+            }
+            let z0 = self.slice_of_depth(depth_min.max(self.znear));
+            let z1 = self.slice_of_depth(depth_max.min(self.zfar));
+
+            for z in z0..=z1 {
+                for y in 0..self.config.tiles_y {
+                    for x in 0..self.config.tiles_x {
+                        let id = self.cluster_index(x, y, z);
+                        if self.bounds[id].intersects_sphere(&center, radius) {
+                            let cluster = &mut per_cluster[id];
+                            if cluster.len() < max_per_cluster {
+                                cluster.push(light_id as u32);
+                            } else {
+                                stats.dropped_lights += 1;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut clusters = Vec::with_capacity(per_cluster.len());
+        let mut light_indices = Vec::new();
+        for cluster in per_cluster {
+            clusters.push([light_indices.len() as u32, cluster.len() as u32]);
+            if !cluster.is_empty() {
+                stats.occupied_clusters += 1;
+            }
+            stats.max_lights_per_cluster = stats.max_lights_per_cluster.max(cluster.len());
+            light_indices.extend(cluster);
+        }
+        stats.light_index_count = light_indices.len();
+
+        ClusterLights {
+            lights: lights.iter().map(|light| GpuLight::from_light(light, view)).collect(),
+            clusters,
+            light_indices,
+            stats,
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use nalgebra::{Isometry3, Point3, Vector3};
+
+/// Point or spot light in world space
+#[derive(Debug, Clone)]
+pub enum Light {
+    Point {
+        position: Point3<f32>,
+        range: f32,
+        color: [f32; 3],
+        intensity: f32,
+    },
+    Spot {
+        position: Point3<f32>,
+        direction: Vector3<f32>,
+        range: f32,
+        /// Half angle of the outer cone in radians
+        angle: f32,
+        color: [f32; 3],
+        intensity: f32,
+    },
+}
+
+impl Light {
+    pub fn position(&self) -> &Point3<f32> {
+        match self {
+            Light::Point { position, .. } => position,
+            Light::Spot { position, .. } => position,
+        }
+    }
+
+    pub fn range(&self) -> f32 {
+        match self {
+            Light::Point { range, .. } => *range,
+            Light::Spot { range, .. } => *range,
+        }
+    }
+
+    /// Return the bounding sphere (center, radius) of the lit volume in view space.
+    pub fn view_bounds(&self, view: &Isometry3<f32>) -> (Point3<f32>, f32) {
+        match self {
+            Light::Point { position, range, .. } => (view * position, *range),
+            Light::Spot {
+                position,
+                direction,
+                range,
+                angle,
+                ..
+            } => {
+                // bounding sphere of the cone
+                let direction = direction.normalize();
+                let (center, radius) = if *angle > std::f32::consts::FRAC_PI_4 {
+                    (position + direction * (angle.cos() * range), angle.sin() * range)
+                } else {
+                    let radius = range * 0.5 / angle.cos();
+                    (position + direction * radius, radius)
+                };
+                (view * center, radius)
+            }
+        }
+    }
+}
+
+/// Light data as seen by the shaders
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct GpuLight {
+    /// View space position and range
+    pub position_range: [f32; 4],
+    /// Color and intensity
+    pub color_intensity: [f32; 4],
+    /// View space direction and the cosine of the cone angle, cone angle is -1 for point lights
+    pub direction_angle: [f32; 4],
+}
+
+unsafe impl bytemuck::Pod for GpuLight {}
+unsafe impl bytemuck::Zeroable for GpuLight {}
+
+impl GpuLight {
+    pub fn from_light(light: &Light, view: &Isometry3<f32>) -> GpuLight {
+        match light {
+            Light::Point {
+                position,
+                range,
+                color,
+                intensity,
+            } => {
+                let p = view * position;
+                GpuLight {
+                    position_range: [p.x, p.y, p.z, *range],
+                    color_intensity: [color[0], color[1], color[2], *intensity],
+                    direction_angle: [0., 0., 0., -1.],
+                }
+            }
+            Light::Spot {
+                position,
+                direction,
+                range,
+                angle,
+                color,
+                intensity,
+            } => {
+                let p = view * position;
+                let d = view * direction.normalize();
+                GpuLight {
+                    position_range: [p.x, p.y, p.z, *range],
+                    color_intensity: [color[0], color[1], color[2], *intensity],
+                    direction_angle: [d.x, d.y, d.z, angle.cos()],
+                }
+            }
+        }
+    }
+}
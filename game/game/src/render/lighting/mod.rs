@@ -0,0 +1,4 @@
+mod light;
+pub use self::light::*;
+mod cluster_grid;
+pub use self::cluster_grid::*;
@@ -0,0 +1,230 @@
+use crate::{
+    assets::{MeshData, MODEL_MAX_LOD_COUNT},
+    render::CompiledMesh,
+    spatial::{Frustum, Sphere},
+};
+use nalgebra::{Isometry3, Point3};
+use serde::{Deserialize, Serialize};
+use std::{fmt, ops::Range};
+
+/// Configuration of the runtime LOD selection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LodConfig {
+    /// Minimum projected screen size (relative to the viewport height) of the LODs 0..N-1, the
+    /// last LOD is used below the last threshold. Must be in decreasing order.
+    pub thresholds: [f32; MODEL_MAX_LOD_COUNT - 1],
+    /// Relative dead zone around the thresholds to avoid popping when an object is at a boundary
+    pub hysteresis: f32,
+    /// Global bias added to the per-model bias, positive values prefer the more detailed LODs
+    pub bias: f32,
+}
+
+impl Default for LodConfig {
+    fn default() -> LodConfig {
+        LodConfig {
+            thresholds: [0.5, 0.25, 0.1],
+            hysteresis: 0.1,
+            bias: 0.,
+        }
+    }
+}
+
+/// Triangle count of the LOD chain of a mesh
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LodChain {
+    pub triangles: [usize; MODEL_MAX_LOD_COUNT],
+    pub lod_count: usize,
+}
+
+impl LodChain {
+    /// Create the chain from the cooked LOD sections. Trailing LODs repeating the previous section
+    /// are not counted, thus meshes without simplification always use LOD 0.
+    pub fn from_mesh(mesh: &MeshData) -> LodChain {
+        let mut chain = LodChain::default();
+        for (i, &(start, count)) in mesh.lod.iter().enumerate() {
+            if i > 0 && mesh.lod[i - 1] == (start, count) {
+                break;
+            }
+            chain.triangles[i] = count / 3;
+            chain.lod_count = i + 1;
+        }
+        chain
+    }
+}
+
+/// Per instance state of the LOD selection
+#[derive(Debug, Clone)]
+pub struct LodInstance {
+    /// Center of the bounding sphere in world space
+    pub center: Point3<f32>,
+    pub radius: f32,
+    /// Per-model bias, positive values prefer the more detailed LODs
+    pub bias: f32,
+    pub chain: LodChain,
+    /// Result of the culling, invisible instances are skipped
+    pub visible: bool,
+    /// The selected LOD, kept between frames for the hysteresis
+    pub lod: usize,
+}
+
+impl LodInstance {
+    pub fn new(center: Point3<f32>, radius: f32, chain: LodChain) -> LodInstance {
+        LodInstance {
+            center,
+            radius,
+            bias: 0.,
+            chain,
+            visible: true,
+            lod: 0,
+        }
+    }
+
+    pub fn with_bias(self, bias: f32) -> Self {
+        LodInstance { bias, ..self }
+    }
+
+    /// The index range of the selected LOD to draw, None if the instance is culled
+    pub fn draw_range(&self, mesh: &CompiledMesh) -> Option<Range<u32>> {
+        if self.visible {
+            let (start, count) = mesh.lod[self.lod.min(MODEL_MAX_LOD_COUNT - 1)];
+            Some(start as u32..(start + count) as u32)
+        } else {
+            None
+        }
+    }
+}
+
+/// Statistics of the last LOD selection
+#[derive(Debug, Clone, Default)]
+pub struct LodStats {
+    pub instance_count: usize,
+    pub visible_count: usize,
+    pub lod_histogram: [usize; MODEL_MAX_LOD_COUNT],
+    pub full_triangles: usize,
+    pub rendered_triangles: usize,
+}
+
+impl LodStats {
+    pub fn triangles_saved(&self) -> usize {
+        self.full_triangles - self.rendered_triangles
+    }
+}
+
+impl fmt::Display for LodStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "instances: {}/{}, lods: {:?}, triangles: {}/{} (saved {})",
+            self.visible_count,
+            self.instance_count,
+            self.lod_histogram,
+            self.rendered_triangles,
+            self.full_triangles,
+            self.triangles_saved()
+        )
+    }
+}
+
+/// Select the LOD of the instances based on their projected screen size
+pub struct LodSelector {
+    config: LodConfig,
+    /// 1/tan(fovy/2)
+    projection_scale: f32,
+    stats: LodStats,
+}
+
+impl LodSelector {
+    pub fn new(config: LodConfig, fovy: f32) -> LodSelector {
+        LodSelector {
+            config,
+            projection_scale: 1. / (fovy * 0.5).tan(),
+            stats: LodStats::default(),
+        }
+    }
+
+    pub fn config(&self) -> &LodConfig {
+        &self.config
+    }
+
+    pub fn set_fovy(&mut self, fovy: f32) {
+        self.projection_scale = 1. / (fovy * 0.5).tan();
+    }
+
+    pub fn stats(&self) -> &LodStats {
+        &self.stats
+    }
+
+    /// Projected diameter of a sphere relative to the viewport height
+    pub fn screen_size(&self, radius: f32, distance: f32) -> f32 {
+        if distance <= radius {
+            // camera is inside the bounds
+            f32::INFINITY
+        } else {
+            radius * self.projection_scale / distance
+        }
+    }
+
+    fn lod_of_size(&self, size: f32, scale: f32, lod_count: usize) -> usize {
+        let lod = self
+            .config
+            .thresholds
+            .iter()
+            .position(|&t| size >= t * scale)
+            .unwrap_or(MODEL_MAX_LOD_COUNT - 1);
+        lod.min(lod_count.max(1) - 1)
+    }
+
+    /// Select the LOD for the given screen size. The selection changes only if the size crosses
+    /// the threshold by the hysteresis.
+    pub fn select(&self, current: usize, size: f32, bias: f32, lod_count: usize) -> usize {
+        let size = size * (self.config.bias + bias).exp2();
+        let coarser = self.lod_of_size(size, 1. - self.config.hysteresis, lod_count);
+        let finer = self.lod_of_size(size, 1. + self.config.hysteresis, lod_count);
+        if coarser > current {
+            coarser
+        } else if finer < current {
+            finer
+        } else {
+            current.min(lod_count.max(1) - 1)
+        }
+    }
+
+    /// Update the visibility of the instances by the bounding spheres
+    pub fn cull(&self, frustum: &Frustum, instances: &mut [LodInstance]) {
+        for instance in instances.iter_mut() {
+            instance.visible = frustum.intersects_sphere(&Sphere {
+                center: instance.center,
+                radius: instance.radius,
+            });
+        }
+    }
+
+    /// Cull the instances and update the LOD of the visible ones, called by the render stage before the draw
+    /// ranges are collected.
+    pub fn cull_and_update(&mut self, view: &Isometry3<f32>, frustum: &Frustum, instances: &mut [LodInstance]) {
+        self.cull(frustum, instances);
+        self.update(view, instances);
+    }
+
+    /// Update the LOD of the visible instances
+    pub fn update(&mut self, view: &Isometry3<f32>, instances: &mut [LodInstance]) {
+        let mut stats = LodStats {
+            instance_count: instances.len(),
+            ..Default::default()
+        };
+
+        for instance in instances.iter_mut().filter(|i| i.visible) {
+            let distance = (view * instance.center).coords.norm();
+            let size = self.screen_size(instance.radius, distance);
+            instance.lod = self.select(instance.lod, size, instance.bias, instance.chain.lod_count);
+
+            stats.visible_count += 1;
+            stats.lod_histogram[instance.lod] += 1;
+            stats.full_triangles += instance.chain.triangles[0];
+            stats.rendered_triangles += instance.chain.triangles[instance.lod];
+        }
+
+        log::trace!("LOD selection: {}", stats);
+        self.stats = stats;
+    }
+}
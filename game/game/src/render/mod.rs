@@ -1,9 +1,17 @@
 mod error;
 pub use self::error::*;
+mod gpu_error;
+pub use self::gpu_error::*;
+mod error_overlay;
+pub use self::error_overlay::*;
 mod surface;
 pub use self::surface::*;
 mod context;
 pub use self::context::*;
+mod gpu_capabilities;
+pub use self::gpu_capabilities::*;
+mod gpu_memory;
+pub use self::gpu_memory::*;
 mod plugin;
 pub use self::plugin::*;
 
@@ -11,9 +19,41 @@ mod compile;
 pub use self::compile::*;
 mod shader;
 pub use self::shader::*;
+mod shader_dependency;
+pub use self::shader_dependency::*;
 mod pipeline;
 pub use self::pipeline::*;
 mod frame_target;
 pub use self::frame_target::*;
+mod full_screen_pass;
+pub use self::full_screen_pass::*;
+mod screenshot;
+pub use self::screenshot::*;
+mod frame_capture;
+pub use self::frame_capture::*;
+mod render_queue;
+pub use self::render_queue::*;
+mod technique;
+pub use self::technique::*;
+mod quality;
+pub use self::quality::*;
+mod lod;
+pub use self::lod::*;
+mod texture_array;
+pub use self::texture_array::*;
+mod lighting;
+pub use self::lighting::*;
+mod sky;
+pub use self::sky::*;
+mod transparency;
+pub use self::transparency::*;
+mod taa;
+pub use self::taa::*;
+mod reflection;
+pub use self::reflection::*;
+mod ssao;
+pub use self::ssao::*;
+mod debug_view;
+pub use self::debug_view::*;
 
 //pub mod systems;
@@ -1,8 +1,11 @@
 use crate::{
     assets::{
-        AssetIO, AssetId, CookedPipeline, PipelineStateDescriptor, Url, VertexBufferDescriptor, VertexBufferLayout,
+        AssetIO, CookedFormat, CookedPipeline, CookedShader, PipelineStateDescriptor, Url, VertexBufferDescriptor,
+        VertexBufferLayout,
+    },
+    render::{
+        Compile, CompiledPipeline, CompiledShader, GpuErrorScope, GpuErrorSink, PipelineCompile, ShaderDependencies,
     },
-    render::{Compile, CompiledPipeline},
 };
 use serde::{Deserialize, Serialize};
 use shine_ecs::{
@@ -38,14 +41,19 @@ impl PipelineKey {
 #[derive(Debug)]
 pub enum PipelineEvent {
     Loaded,
+    /// The pipeline was rebuilt due to a change in its dependencies
+    Rebuilt,
 }
 
 pub struct Pipeline {
     id: String,
+    key: Option<PipelineKey>,
+    resource_id: ResourceId,
     pipeline: Result<Option<CompiledPipeline>, PipelineError>,
-    //vertex_shader: ShaderDependency,
-    //fragment_shader: ShaderDependency,
+    rebuild_count: usize,
     dispatcher: ObserveDispatcher<PipelineEvent>,
+    requester: ResourceLoadRequester<Pipeline, LoadRequest>,
+    dependencies: ShaderDependencies,
 }
 
 impl Pipeline {
@@ -65,17 +73,38 @@ impl Pipeline {
         }
     }
 
-    /*pub fn pipeline_module(&self) -> Option<&CompiledPipeline> {
+    pub fn pipeline_module(&self) -> Option<&CompiledPipeline> {
         self.pipeline.as_ref().map(|u| u.as_ref()).unwrap_or(None)
-    }*/
+    }
+
+    /// Number of times the pipeline has been rebuilt after the initial load
+    pub fn rebuild_count(&self) -> usize {
+        self.rebuild_count
+    }
+
+    /// Request the pipeline to be rebuilt. The current pipeline is kept bound until the new one is ready.
+    pub fn rebuild(&self, handle: PipelineHandle) {
+        if let Some(key) = &self.key {
+            log::debug!("[{:?}] Rebuilding pipeline", self.id);
+            self.requester.send_request(handle, LoadRequest(key.clone()));
+        }
+    }
+}
+
+impl Drop for Pipeline {
+    fn drop(&mut self) {
+        self.dependencies.remove_dependent(&self.resource_id);
+    }
 }
 
-struct LoadRequest(String);
+struct LoadRequest(PipelineKey);
 
 enum LoadResponse {
-    Compiled(CompiledPipeline),
+    Compiled {
+        pipeline: CompiledPipeline,
+        shaders: [String; 2],
+    },
     Error(PipelineError),
-    RequestShader(AssetId),
 }
 
 /// Implement functions to make it a resource
@@ -84,35 +113,61 @@ impl Pipeline {
         context: &ResourceLoadRequester<Self, LoadRequest>,
         handle: ResourceHandle<Self>,
         id: &ResourceId,
+        dependencies: &ShaderDependencies,
     ) -> Self {
         log::trace!("Creating [{:?}]", id);
-        if let Ok(PipelineKey {
-            id,
-            vertex_layouts,
-            render_state,
-        }) = id.to_object::<PipelineKey>()
-        {
-            context.send_request(handle, LoadRequest(id.clone()));
+        if let Ok(key) = id.to_object::<PipelineKey>() {
+            context.send_request(handle, LoadRequest(key.clone()));
             Pipeline {
-                id,
+                id: key.id.clone(),
+                key: Some(key),
+                resource_id: id.clone(),
                 pipeline: Ok(None),
+                rebuild_count: 0,
                 dispatcher: Default::default(),
+                requester: context.clone(),
+                dependencies: dependencies.clone(),
             }
         } else {
             Pipeline {
                 id: Default::default(),
+                key: None,
+                resource_id: id.clone(),
                 pipeline: Err(PipelineError),
+                rebuild_count: 0,
                 dispatcher: Default::default(),
+                requester: context.clone(),
+                dependencies: dependencies.clone(),
             }
         }
     }
 
+    async fn load_shader(
+        io: &AssetIO,
+        device: &wgpu::Device,
+        errors: &GpuErrorSink,
+        handle: &ResourceHandle<Self>,
+        shader_id: &str,
+    ) -> Result<CompiledShader, PipelineError> {
+        let url = Url::parse(shader_id).map_err(|_| PipelineError)?;
+        let data = io.download_binary(&url).await.map_err(|_| PipelineError)?;
+        handle.check_liveness().map_err(|_| PipelineError)?;
+        let cooked_shader: CookedShader = CookedFormat::deserialize(&data).map_err(|_| PipelineError)?;
+        errors
+            .capture(GpuErrorScope::Shader, shader_id, || cooked_shader.compile(device))
+            .ok_or(PipelineError)
+    }
+
     async fn on_load_impl(
-        responder: &ResourceLoadResponder<Pipeline, LoadResponse>,
-        (io, device): &(AssetIO, Arc<wgpu::Device>),
+        (io, device, errors): &(AssetIO, Arc<wgpu::Device>, GpuErrorSink),
         handle: &ResourceHandle<Self>,
-        pipeline_id: String,
-    ) -> Result<CompiledPipeline, PipelineError> {
+        key: PipelineKey,
+    ) -> Result<(CompiledPipeline, [String; 2]), PipelineError> {
+        let PipelineKey {
+            id: pipeline_id,
+            vertex_layouts,
+            render_state,
+        } = key;
         log::debug!("[{:?}] Loading pipeline...", pipeline_id);
 
         let url = Url::parse(&pipeline_id).map_err(|_| PipelineError)?;
@@ -120,36 +175,42 @@ impl Pipeline {
 
         log::debug!("[{:?}] Extracting pipeline...", pipeline_id);
         handle.check_liveness().map_err(|_| PipelineError)?;
-        let cooked_pipeline: CookedPipeline = bincode::deserialize_from(&*data).map_err(|_| PipelineError)?;
-        handle.check_liveness().map_err(|_| PipelineError)?;
-
-        let fs = AssetId::new(cooked_pipeline.descriptor.fragment_stage.shader).map_err(|_| PipelineError)?;
-        let vs = AssetId::new(cooked_pipeline.descriptor.vertex_stage.shader).map_err(|_| PipelineError)?;
-        responder.send_response(handle.clone(), LoadResponse::RequestShader(fs));
-        responder.send_response(handle.clone(), LoadResponse::RequestShader(vs));
-        handle.check_liveness().map_err(|_| PipelineError)?;
+        let cooked_pipeline: CookedPipeline = CookedFormat::deserialize(&data).map_err(|_| PipelineError)?;
+        let descriptor = cooked_pipeline.descriptor;
 
-        // send request and wait shaders
-        // compile pipeline
+        log::debug!("[{:?}] Loading shaders...", pipeline_id);
+        let vs_id = descriptor.vertex_stage.shader.clone();
+        let fs_id = descriptor.fragment_stage.shader.clone();
+        let vs = Self::load_shader(io, &*device, errors, handle, &vs_id).await?;
+        let fs = Self::load_shader(io, &*device, errors, handle, &fs_id).await?;
 
-        /*log::debug!("[{:?}] Compiling pipeline...", pipeline_id);
+        log::debug!("[{:?}] Compiling pipeline...", pipeline_id);
         handle.check_liveness().map_err(|_| PipelineError)?;
-        let compiled_pipeline = cooked_pipeline.compile(&*device);
+        let compile = PipelineCompile {
+            vertex_layouts,
+            render_states: render_state,
+            descriptor: &descriptor,
+            vertex_shader: &vs.shader,
+            fragment_shader: &fs.shader,
+        };
+        let compiled_pipeline = errors
+            .capture(GpuErrorScope::Pipeline, &pipeline_id, || compile.compile(&*device))
+            .ok_or(PipelineError)?
+            .map_err(|_| PipelineError)?;
 
         log::debug!("[{:?}] Pipeline loaded", pipeline_id);
-        Ok(compiled_pipeline)*/
-        Err(PipelineError)
+        Ok((compiled_pipeline, [vs_id, fs_id]))
     }
 
     async fn on_load(
-        ctx: &(AssetIO, Arc<wgpu::Device>),
+        ctx: &(AssetIO, Arc<wgpu::Device>, GpuErrorSink),
         responder: &ResourceLoadResponder<Pipeline, LoadResponse>,
         handle: ResourceHandle<Pipeline>,
         request: LoadRequest,
     ) {
-        let LoadRequest(pipeline_id) = request;
-        let response = match Self::on_load_impl(responder, ctx, &handle, pipeline_id).await {
-            Ok(pipeline) => LoadResponse::Compiled(pipeline),
+        let LoadRequest(key) = request;
+        let response = match Self::on_load_impl(ctx, &handle, key).await {
+            Ok((pipeline, shaders)) => LoadResponse::Compiled { pipeline, shaders },
             Err(err) => LoadResponse::Error(err),
         };
         responder.send_response(handle, response);
@@ -162,22 +223,42 @@ impl Pipeline {
         response: LoadResponse,
     ) {
         log::debug!("[{:?}] Load response", this.id);
+        let is_rebuild = this.pipeline_module().is_some();
         match response {
-            LoadResponse::Compiled(shader) => this.pipeline = Ok(Some(shader)),
-            LoadResponse::Error(err) => this.pipeline = Err(err),
-            LoadResponse::RequestShader(sh) => unimplemented!(),
+            LoadResponse::Compiled { pipeline, shaders } => {
+                // the previous pipeline is released only when the new one is ready
+                this.pipeline = Ok(Some(pipeline));
+                for shader_id in &shaders {
+                    this.dependencies.add_dependent(shader_id, &this.resource_id);
+                }
+            }
+            LoadResponse::Error(err) => {
+                if is_rebuild {
+                    log::warn!("[{:?}] Pipeline rebuild failed, keeping the previous version", this.id);
+                    return;
+                }
+                this.pipeline = Err(err)
+            }
         };
-        this.dispatcher.notify_all(PipelineEvent::Loaded);
+
+        if is_rebuild {
+            this.rebuild_count += 1;
+            this.dispatcher.notify_all(PipelineEvent::Rebuilt);
+        } else {
+            this.dispatcher.notify_all(PipelineEvent::Loaded);
+        }
     }
 
     pub fn register_resource(
         resources: &mut Resources,
         io: AssetIO,
         device: Arc<wgpu::Device>,
+        errors: GpuErrorSink,
+        dependencies: ShaderDependencies,
     ) -> Result<(), ECSError> {
         resources.register(ResourceLoader::new(
-            Pipeline::build,
-            (io, device),
+            move |context, handle, id| Pipeline::build(context, handle, id, &dependencies),
+            (io, device, errors),
             Pipeline::on_load,
             Pipeline::on_load_response,
         ))
@@ -189,6 +270,27 @@ impl Pipeline {
 
     pub fn bake_resource(resources: &mut Resources, gc: bool) {
         resources.bake::<Pipeline>(gc);
+        Self::rebuild_invalidated(resources);
+    }
+
+    /// Start the rebuild of the pipelines invalidated by a shader reload
+    fn rebuild_invalidated(resources: &Resources) {
+        let pending = match resources.get::<ShaderDependencies>() {
+            Ok(dependencies) if dependencies.has_pending() => dependencies.take_pending(),
+            _ => return,
+        };
+
+        if let Some(store) = resources.get_store::<Pipeline>() {
+            for id in pending {
+                // skip the released pipelines, they should not be recreated
+                if !store.exists(&id) {
+                    continue;
+                }
+                if let Ok(handle) = store.get_handle(&id) {
+                    store.at(&handle).rebuild(handle.clone());
+                }
+            }
+        }
     }
 }
 
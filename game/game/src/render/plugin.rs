@@ -1,10 +1,20 @@
 use crate::{
     app::{AppError, Plugin, PluginFuture},
     assets::AssetIO,
-    render::{Context, FrameTarget, Pipeline, RenderError, Shader, Surface},
+    input::FrameTiming,
+    render::{
+        ActiveTechniques, CaptureReason, Context, DebugView, DebugViewTechnique, FrameCapture, FrameCaptureConfig,
+        FrameTarget, GpuCapabilities, GpuMemoryConfig, GpuMemoryTracker, GpuMemoryWarning, LodConfig, LodSelector,
+        Pipeline, ReflectionTechnique, RenderError, RenderErrorOverlay, RenderQuality, ScreenshotReadback, Shader,
+        ShaderDependencies, SkyTechnique, SsaoTechnique, SunLight, Surface, TaaTechnique, TechniqueRegistry,
+        TransparencyTechnique, DEBUG_VIEW_TECHNIQUE, REFLECTION_TECHNIQUE, SKY_TECHNIQUE, SSAO_TECHNIQUE,
+        TAA_TECHNIQUE, TRANSPARENCY_TECHNIQUE,
+    },
     World,
 };
 use serde::{Deserialize, Serialize};
+use shine_ecs::events::Events;
+use shine_input::input_timestamp;
 use std::{borrow::Cow, error::Error as StdError};
 
 pub const RENDER_PLUGIN_NAME: &str = "render";
@@ -14,6 +24,30 @@ pub struct RenderConfig {
     pub swap_chain_format: wgpu::TextureFormat,
     pub enable_validation: bool,
     pub wgpu_trace: Option<String>,
+
+    /// Request only the default features from the adapter
+    #[serde(default)]
+    pub disable_optional_features: bool,
+
+    /// Maximum anisotropy for texture filtering, 0 to disable
+    #[serde(default)]
+    pub max_anisotropy: u8,
+
+    /// Quality tier of the render techniques
+    #[serde(default)]
+    pub quality: RenderQuality,
+
+    /// Budget of the gpu memory
+    #[serde(default)]
+    pub memory: GpuMemoryConfig,
+
+    /// Frame capture on request and on hitches
+    #[serde(default)]
+    pub capture: FrameCaptureConfig,
+
+    /// Runtime LOD selection of the meshes
+    #[serde(default)]
+    pub lod: LodConfig,
 }
 
 pub struct RenderPlugin {
@@ -30,6 +64,13 @@ impl RenderPlugin {
             surface,
         }
     }
+
+    /// Capture the next rendered frame with RenderDoc if it is attached, or dump the command log of the frame
+    pub fn trigger_capture(world: &World) -> Result<(), AppError> {
+        let mut capture = world.resources.get_mut::<FrameCapture>().map_err(into_plugin_err)?;
+        capture.trigger(CaptureReason::Manual);
+        Ok(())
+    }
 }
 
 fn into_plugin_err<E: 'static + StdError>(error: E) -> AppError {
@@ -50,7 +91,16 @@ impl Plugin for RenderPlugin {
                 .map_err(|err| RenderError::device_error("Failed to create context", err))
                 .map_err(into_plugin_err)?;
             let device = context.device();
+            let errors = context.errors().clone();
+            let capabilities = context.capabilities().clone();
+            log::info!("Gpu capabilities: {:?}", capabilities);
+            let memory_budget = self.config.memory.budget(context.adapter_info());
+            log::info!("Gpu memory budget: {} MiB", memory_budget / (1024 * 1024));
+            let memory_tracker = GpuMemoryTracker::new(memory_budget, &self.config.memory);
             let frame_target = FrameTarget::default();
+            let frame_capture = FrameCapture::with_default_backend(&self.config.capture);
+            // the field of view is updated by the scene from its camera
+            let lod_selector = LodSelector::new(self.config.lod.clone(), std::f32::consts::FRAC_PI_4);
 
             world
                 .resources
@@ -64,10 +114,78 @@ impl Plugin for RenderPlugin {
                 .resources
                 .register_with_instance(frame_target)
                 .map_err(into_plugin_err)?;
+            world
+                .resources
+                .register_with_instance(capabilities)
+                .map_err(into_plugin_err)?;
+            world
+                .resources
+                .register_with_instance(self.config.quality)
+                .map_err(into_plugin_err)?;
+            world
+                .resources
+                .register_with_instance(DebugView::default())
+                .map_err(into_plugin_err)?;
+            world
+                .resources
+                .register_events::<RenderError>()
+                .map_err(into_plugin_err)?;
+            world
+                .resources
+                .register_with_instance(memory_tracker)
+                .map_err(into_plugin_err)?;
+            world
+                .resources
+                .register_events::<GpuMemoryWarning>()
+                .map_err(into_plugin_err)?;
+            world
+                .resources
+                .register_with_instance(RenderErrorOverlay::default())
+                .map_err(into_plugin_err)?;
+            world
+                .resources
+                .register_with_instance(frame_capture)
+                .map_err(into_plugin_err)?;
+            world
+                .resources
+                .register_with_instance(lod_selector)
+                .map_err(into_plugin_err)?;
+            let mut techniques = TechniqueRegistry::default();
+            techniques.register(SKY_TECHNIQUE, |config| Ok(Box::new(SkyTechnique::from_config(config)?)));
+            techniques.register(TRANSPARENCY_TECHNIQUE, |config| {
+                Ok(Box::new(TransparencyTechnique::from_config(config)?))
+            });
+            techniques.register(TAA_TECHNIQUE, |config| Ok(Box::new(TaaTechnique::from_config(config)?)));
+            techniques.register(REFLECTION_TECHNIQUE, |config| {
+                Ok(Box::new(ReflectionTechnique::from_config(config)?))
+            });
+            techniques.register(SSAO_TECHNIQUE, |config| {
+                Ok(Box::new(SsaoTechnique::from_config(config)?))
+            });
+            techniques.register(DEBUG_VIEW_TECHNIQUE, |config| {
+                Ok(Box::new(DebugViewTechnique::from_config(config)?))
+            });
+            world
+                .resources
+                .register_with_instance(techniques)
+                .map_err(into_plugin_err)?;
+            world.resources.init_resource::<SunLight>().map_err(into_plugin_err)?;
 
-            Shader::register_resource(&mut world.resources, assetio.clone(), device.clone())
+            let shader_dependencies = ShaderDependencies::new();
+            world
+                .resources
+                .register_with_instance(shader_dependencies.clone())
+                .map_err(into_plugin_err)?;
+            Shader::register_resource(
+                &mut world.resources,
+                assetio.clone(),
+                device.clone(),
+                errors.clone(),
+                shader_dependencies.clone(),
+            )
+            .map_err(into_plugin_err)?;
+            Pipeline::register_resource(&mut world.resources, assetio, device, errors, shader_dependencies)
                 .map_err(into_plugin_err)?;
-            Pipeline::register_resource(&mut world.resources, assetio, device).map_err(into_plugin_err)?;
 
             Ok(())
         })
@@ -75,12 +193,25 @@ impl Plugin for RenderPlugin {
 
     fn deinit(world: &mut World) -> PluginFuture<()> {
         Box::pin(async move {
+            ActiveTechniques::destroy_render_stage(world);
+            let _ = world.resources.unregister::<SunLight>();
+            let _ = world.resources.unregister::<TechniqueRegistry>();
+            let _ = world.resources.unregister::<Events<GpuMemoryWarning>>();
+            let _ = world.resources.unregister::<GpuMemoryTracker>();
+            let _ = world.resources.unregister::<LodSelector>();
+            let _ = world.resources.unregister::<FrameCapture>();
+            let _ = world.resources.unregister::<RenderErrorOverlay>();
+            let _ = world.resources.unregister::<Events<RenderError>>();
+            let _ = world.resources.unregister::<DebugView>();
+            let _ = world.resources.unregister::<RenderQuality>();
+            let _ = world.resources.unregister::<GpuCapabilities>();
             let _ = world.resources.unregister::<FrameTarget>();
             let _ = world.resources.unregister::<Context>();
             let _ = world.resources.unregister::<Surface>();
 
             Shader::unregister_resource(&mut world.resources);
             Pipeline::unregister_resource(&mut world.resources);
+            let _ = world.resources.unregister::<ShaderDependencies>();
             Ok(())
         })
     }
@@ -91,31 +222,114 @@ impl World {
         let mut surface = self.resources.get_mut::<Surface>().map_err(into_plugin_err)?;
         let mut context = self.resources.get_mut::<Context>().map_err(into_plugin_err)?;
         let mut frame_output = self.resources.get_mut::<FrameTarget>().map_err(into_plugin_err)?;
+        let mut capture = self.resources.get_mut::<FrameCapture>().map_err(into_plugin_err)?;
 
         surface.set_size(size);
+        capture.begin_frame(size);
         let (output_texture, descriptor) = context.create_frame(&surface).map_err(into_plugin_err)?;
         frame_output.set(output_texture, descriptor);
         Ok(())
     }
 
+    fn start_capture_frame(&mut self, size: (u32, u32)) -> Result<(), AppError> {
+        let mut surface = self.resources.get_mut::<Surface>().map_err(into_plugin_err)?;
+        let context = self.resources.get::<Context>().map_err(into_plugin_err)?;
+        let mut frame_output = self.resources.get_mut::<FrameTarget>().map_err(into_plugin_err)?;
+        let mut capture = self.resources.get_mut::<FrameCapture>().map_err(into_plugin_err)?;
+
+        surface.set_size(size);
+        capture.begin_frame(size);
+        let (texture, descriptor) = context.create_capture_frame(&surface);
+        frame_output.set_capture(texture, descriptor);
+        Ok(())
+    }
+
+    /// Record the copy of the captured frame into a readback buffer
+    fn read_capture_frame(&self) -> Result<ScreenshotReadback, AppError> {
+        let context = self.resources.get::<Context>().map_err(into_plugin_err)?;
+        let frame_output = self.resources.get::<FrameTarget>().map_err(into_plugin_err)?;
+
+        let (texture, descriptor) = match (frame_output.capture_texture(), frame_output.descriptor()) {
+            (Some(texture), Some(descriptor)) => (texture, descriptor),
+            _ => {
+                return Err(into_plugin_err(RenderError::Screenshot {
+                    message: "Frame is not captured".to_owned(),
+                }))
+            }
+        };
+        let device = context.device();
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("screenshot"),
+        });
+        let readback = ScreenshotReadback::new(device.clone(), &mut encoder, texture, descriptor);
+        context.add_command(encoder.finish());
+        Ok(readback)
+    }
+
     fn bake_resources(&mut self, gc: bool) {
         //log::trace!("Baking render resources");
         Shader::bake_resource(&mut self.resources, gc);
         Pipeline::bake_resource(&mut self.resources, gc);
     }
 
+    /// Publish the captured gpu errors as events and list them on the debug overlay
+    fn publish_render_errors(&self, context: &Context) -> Result<(), AppError> {
+        let mut events = self
+            .resources
+            .get_mut::<Events<RenderError>>()
+            .map_err(into_plugin_err)?;
+        let mut overlay = self
+            .resources
+            .get_mut::<RenderErrorOverlay>()
+            .map_err(into_plugin_err)?;
+
+        events.update();
+        events.send_batch(context.errors().take_errors());
+        overlay.update(&events);
+        Ok(())
+    }
+
+    /// Publish the warnings of the gpu memory budget for the telemetry
+    fn publish_memory_warnings(&self) -> Result<(), AppError> {
+        let mut tracker = self.resources.get_mut::<GpuMemoryTracker>().map_err(into_plugin_err)?;
+        let mut events = self
+            .resources
+            .get_mut::<Events<GpuMemoryWarning>>()
+            .map_err(into_plugin_err)?;
+
+        events.update();
+        events.send_batch(tracker.end_frame());
+        Ok(())
+    }
+
     fn end_frame(&mut self) -> Result<(), AppError> {
         let mut context = self.resources.get_mut::<Context>().map_err(into_plugin_err)?;
         let mut frame_output = self.resources.get_mut::<FrameTarget>().map_err(into_plugin_err)?;
+        let mut capture = self.resources.get_mut::<FrameCapture>().map_err(into_plugin_err)?;
 
+        capture.record_command(format!("submit {} command buffers", context.pending_command_count()));
         context.submit_commands();
+        capture.record_command("present");
         frame_output.present();
+        self.publish_render_errors(&context)?;
+        self.publish_memory_warnings()?;
+
+        let now = input_timestamp();
+        // the input plugin is optional, present latency is recorded only when it's available
+        if let Ok(mut timing) = self.resources.get_mut::<FrameTiming>() {
+            timing.record_present(now);
+        }
+        capture.end_frame(now);
         Ok(())
     }
 }
 
 pub trait RenderWorld {
     fn render(&mut self, size: (u32, u32)) -> Result<(), AppError>;
+
+    /// Render a frame offscreen instead of presenting it, the returned readback provides the screenshot
+    /// once the frame is submitted.
+    fn render_screenshot(&mut self, size: (u32, u32)) -> Result<ScreenshotReadback, AppError>;
 }
 
 impl RenderWorld for World {
@@ -126,4 +340,14 @@ impl RenderWorld for World {
         self.end_frame()?;
         res
     }
+
+    fn render_screenshot(&mut self, size: (u32, u32)) -> Result<ScreenshotReadback, AppError> {
+        self.start_capture_frame(size)?;
+        self.bake_resources(false);
+        let res = self.run_stage("render");
+        let readback = self.read_capture_frame();
+        self.end_frame()?;
+        res?;
+        readback
+    }
 }
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// Quality tier of the render techniques. The techniques derive their default settings from the tier, the
+/// explicit options of a technique override them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum RenderQuality {
+    Low,
+    Medium,
+    High,
+    Ultra,
+}
+
+impl Default for RenderQuality {
+    fn default() -> RenderQuality {
+        RenderQuality::Medium
+    }
+}
+
+impl fmt::Display for RenderQuality {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            RenderQuality::Low => "low",
+            RenderQuality::Medium => "medium",
+            RenderQuality::High => "high",
+            RenderQuality::Ultra => "ultra",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for RenderQuality {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<RenderQuality, String> {
+        match s.to_lowercase().as_str() {
+            "low" => Ok(RenderQuality::Low),
+            "medium" => Ok(RenderQuality::Medium),
+            "high" => Ok(RenderQuality::High),
+            "ultra" => Ok(RenderQuality::Ultra),
+            _ => Err(format!("Unknown render quality: {}", s)),
+        }
+    }
+}
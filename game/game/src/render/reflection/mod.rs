@@ -0,0 +1,6 @@
+mod reflection_probe;
+pub use self::reflection_probe::*;
+mod probe_cubemaps;
+pub use self::probe_cubemaps::*;
+mod reflection_technique;
+pub use self::reflection_technique::*;
@@ -0,0 +1,122 @@
+use crate::render::prefiltered_mip_count;
+use std::num::NonZeroU32;
+
+/// Format of the prefiltered probe cube maps
+pub const REFLECTION_PROBE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+/// Format of the depth used during the capture
+pub const REFLECTION_CAPTURE_DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+/// Cube map array storing the prefiltered environment of the probes. Each probe owns a layer (6 array
+/// layers of the texture), the mip levels store the environment prefiltered for the increasing roughness.
+pub struct ProbeCubemaps {
+    resolution: u32,
+    capacity: u32,
+    mip_count: u32,
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    capture_depth: wgpu::TextureView,
+}
+
+impl ProbeCubemaps {
+    pub fn new(device: &wgpu::Device, resolution: u32, capacity: u32) -> ProbeCubemaps {
+        log::debug!(
+            "Creating reflection probe cube maps with resolution {}, capacity {}",
+            resolution,
+            capacity
+        );
+        let capacity = capacity.max(1);
+        let mip_count = prefiltered_mip_count(resolution);
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("reflection probes"),
+            size: wgpu::Extent3d {
+                width: resolution,
+                height: resolution,
+                depth: 6 * capacity,
+            },
+            mip_level_count: mip_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: REFLECTION_PROBE_FORMAT,
+            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT | wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("reflection probes"),
+            dimension: Some(wgpu::TextureViewDimension::CubeArray),
+            ..Default::default()
+        });
+
+        let depth = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("reflection capture depth"),
+            size: wgpu::Extent3d {
+                width: resolution,
+                height: resolution,
+                depth: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: REFLECTION_CAPTURE_DEPTH_FORMAT,
+            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+        });
+        let capture_depth = depth.create_view(&wgpu::TextureViewDescriptor::default());
+
+        ProbeCubemaps {
+            resolution,
+            capacity,
+            mip_count,
+            texture,
+            view,
+            capture_depth,
+        }
+    }
+
+    pub fn resolution(&self) -> u32 {
+        self.resolution
+    }
+
+    /// Number of the probes that fit into the array
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    pub fn mip_count(&self) -> u32 {
+        self.mip_count
+    }
+
+    /// The cube array view sampled by the shading
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+
+    pub fn capture_depth_view(&self) -> &wgpu::TextureView {
+        &self.capture_depth
+    }
+
+    /// Cube view of the base level of a probe sampled by the prefilter pass
+    pub fn probe_view(&self, layer: u32) -> wgpu::TextureView {
+        assert!(layer < self.capacity);
+        self.texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("reflection probe"),
+            dimension: Some(wgpu::TextureViewDimension::Cube),
+            base_mip_level: 0,
+            level_count: NonZeroU32::new(1),
+            base_array_layer: layer * 6,
+            array_layer_count: NonZeroU32::new(6),
+            ..Default::default()
+        })
+    }
+
+    /// Render target view of a face and mip level of a probe used by the capture and the prefilter passes
+    pub fn face_view(&self, layer: u32, face: u32, mip: u32) -> wgpu::TextureView {
+        assert!(layer < self.capacity && face < 6 && mip < self.mip_count);
+        self.texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("reflection probe face"),
+            dimension: Some(wgpu::TextureViewDimension::D2),
+            base_mip_level: mip,
+            level_count: NonZeroU32::new(1),
+            base_array_layer: layer * 6 + face,
+            array_layer_count: NonZeroU32::new(1),
+            ..Default::default()
+        })
+    }
+}
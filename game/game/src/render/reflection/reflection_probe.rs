@@ -0,0 +1,165 @@
+use nalgebra::{Isometry3, Matrix4, Point3, Vector3};
+
+/// How the cubemap of a probe is captured
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProbeCapture {
+    /// Captured by the headless renderer of the cooker, the url of the cooked cubemap
+    Cooked(String),
+    /// Captured when the level is loaded
+    LevelLoad,
+}
+
+/// Reflection probe capturing the environment at a point of the world. The captured cubemap is
+/// prefiltered for the roughness levels and it is blended by the objects within the influence radius.
+#[derive(Debug, Clone)]
+pub struct ReflectionProbe {
+    pub position: Point3<f32>,
+    /// Radius of the influence sphere
+    pub radius: f32,
+    /// Width of the band at the edge of the sphere where the influence fades out
+    pub blend_distance: f32,
+    pub capture: ProbeCapture,
+}
+
+impl ReflectionProbe {
+    pub fn new(position: Point3<f32>, radius: f32, capture: ProbeCapture) -> ReflectionProbe {
+        ReflectionProbe {
+            position,
+            radius,
+            blend_distance: radius * 0.25,
+            capture,
+        }
+    }
+
+    /// Influence of the probe at the given point, 1 inside the inner sphere fading to 0 at the radius.
+    pub fn weight(&self, point: &Point3<f32>) -> f32 {
+        let distance = (point - self.position).norm();
+        if distance >= self.radius {
+            0.
+        } else if self.blend_distance <= 0. {
+            1.
+        } else {
+            ((self.radius - distance) / self.blend_distance).min(1.)
+        }
+    }
+}
+
+/// Maximum number of probes blended for an object
+pub const MAX_BLENDED_PROBES: usize = 4;
+
+/// The probes influencing an object with the normalized blend weights. If the weights do not sum up to 1,
+/// the remaining part falls back to the sky.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProbeBlend {
+    pub probes: Vec<(usize, f32)>,
+}
+
+impl ProbeBlend {
+    /// Select the max_count most influencing probes by proximity at the given point.
+    pub fn from_probes(probes: &[ReflectionProbe], point: &Point3<f32>, max_count: usize) -> ProbeBlend {
+        let mut weights: Vec<(usize, f32)> = probes
+            .iter()
+            .enumerate()
+            .map(|(id, probe)| (id, probe.weight(point)))
+            .filter(|(_, weight)| *weight > 0.)
+            .collect();
+        weights.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        weights.truncate(max_count.min(MAX_BLENDED_PROBES));
+
+        // normalize only if the probes cover more than the full weight, a partial coverage is blended with the sky
+        let total: f32 = weights.iter().map(|(_, weight)| weight).sum();
+        if total > 1. {
+            for (_, weight) in &mut weights {
+                *weight /= total;
+            }
+        }
+        ProbeBlend { probes: weights }
+    }
+
+    /// Weight of the sky completing the probes
+    pub fn sky_weight(&self) -> f32 {
+        (1. - self.probes.iter().map(|(_, weight)| weight).sum::<f32>()).max(0.)
+    }
+
+    /// Per-object data of the shader: the probe indices (-1 for unused) and the weights
+    pub fn to_gpu(&self) -> ([i32; MAX_BLENDED_PROBES], [f32; MAX_BLENDED_PROBES]) {
+        let mut indices = [-1; MAX_BLENDED_PROBES];
+        let mut weights = [0.; MAX_BLENDED_PROBES];
+        for (i, (id, weight)) in self.probes.iter().enumerate().take(MAX_BLENDED_PROBES) {
+            indices[i] = *id as i32;
+            weights[i] = *weight;
+        }
+        (indices, weights)
+    }
+}
+
+/// The faces of a cube map in the wgpu layer order (+x, -x, +y, -y, +z, -z) given by the forward and up directions.
+pub const CUBE_FACES: [([f32; 3], [f32; 3]); 6] = [
+    ([1., 0., 0.], [0., -1., 0.]),
+    ([-1., 0., 0.], [0., -1., 0.]),
+    ([0., 1., 0.], [0., 0., 1.]),
+    ([0., -1., 0.], [0., 0., -1.]),
+    ([0., 0., 1.], [0., -1., 0.]),
+    ([0., 0., -1.], [0., -1., 0.]),
+];
+
+/// View transformation to capture a face of the cube map from the given position
+pub fn cube_face_view(position: &Point3<f32>, face: usize) -> Isometry3<f32> {
+    let (forward, up) = CUBE_FACES[face];
+    let target = position + Vector3::from(forward);
+    Isometry3::look_at_rh(position, &target, &Vector3::from(up))
+}
+
+/// Projection of the cube map faces with a 90 degree field of view
+pub fn cube_face_projection(near: f32, far: f32) -> Matrix4<f32> {
+    Matrix4::new_perspective(1., std::f32::consts::FRAC_PI_2, near, far)
+}
+
+/// Number of the prefiltered mip levels of a cube map. The smallest levels are skipped as they carry
+/// too little detail even for the roughest surfaces.
+pub fn prefiltered_mip_count(resolution: u32) -> u32 {
+    let full = 32 - resolution.max(1).leading_zeros();
+    full.saturating_sub(2).max(1)
+}
+
+/// Roughness prefiltered into the given mip level, the roughness grows linearly with the level.
+pub fn mip_roughness(mip: u32, mip_count: u32) -> f32 {
+    if mip_count <= 1 {
+        0.
+    } else {
+        (mip as f32 / (mip_count - 1) as f32).min(1.)
+    }
+}
+
+/// The (fractional) mip level to sample for the given roughness, the inverse of mip_roughness.
+pub fn roughness_mip(roughness: f32, mip_count: u32) -> f32 {
+    roughness.max(0.).min(1.) * (mip_count.max(1) - 1) as f32
+}
+
+/// Probe data as seen by the shaders
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct GpuReflectionProbe {
+    /// World space position and influence radius
+    pub position_radius: [f32; 4],
+    /// Blend distance, layer of the cube map array, number of the mip levels and a flag (1 if captured)
+    pub params: [f32; 4],
+}
+
+unsafe impl bytemuck::Pod for GpuReflectionProbe {}
+unsafe impl bytemuck::Zeroable for GpuReflectionProbe {}
+
+impl GpuReflectionProbe {
+    pub fn from_probe(probe: &ReflectionProbe, layer: u32, mip_count: u32, captured: bool) -> GpuReflectionProbe {
+        let p = &probe.position;
+        GpuReflectionProbe {
+            position_radius: [p.x, p.y, p.z, probe.radius],
+            params: [
+                probe.blend_distance,
+                layer as f32,
+                mip_count as f32,
+                if captured { 1. } else { 0. },
+            ],
+        }
+    }
+}
@@ -0,0 +1,386 @@
+use crate::{
+    assets::{vertex, PipelineStateDescriptor, TextureSemantic, Uniform, UniformSemantic},
+    render::{
+        create_target_sampler, create_uniform_buffer, draw_full_screen, mip_roughness, Context, FullScreenTarget,
+        GpuReflectionProbe, MissingBinding, Pipeline, PipelineKey, ProbeBlend, ProbeCapture, ProbeCubemaps,
+        ReflectionProbe, RenderError, RenderTechnique, TechniqueConfig, MAX_BLENDED_PROBES, REFLECTION_PROBE_FORMAT,
+    },
+    World,
+};
+use nalgebra::Point3;
+use shine_ecs::{
+    resources::{Res, ResMut, ResourceId, Resources},
+    scheduler::{IntoSystem, ResourceClaims, System, SystemName, Task, TaskGroup, TaskItem},
+    ECSError,
+};
+use std::sync::Arc;
+
+/// Name of the technique in the registry
+pub const REFLECTION_TECHNIQUE: &str = "reflection";
+
+/// Name of the uniform buffer of the prefilter pipeline
+pub const REFLECTION_PREFILTER_UNIFORM: &str = "prefilter";
+/// Name of the cube map of the probe in the prefilter pipeline
+pub const REFLECTION_PROBE_TEXTURE: &str = "probe";
+
+/// Parameters of the probe storage and blending
+#[derive(Debug, Clone)]
+pub struct ReflectionSettings {
+    /// Resolution of the cube map faces
+    pub resolution: u32,
+    /// Maximum number of the probes in a level
+    pub max_probes: u32,
+    /// Maximum number of the probes blended for an object
+    pub max_blend: usize,
+}
+
+impl Default for ReflectionSettings {
+    fn default() -> ReflectionSettings {
+        ReflectionSettings {
+            resolution: 128,
+            max_probes: 16,
+            max_blend: 2,
+        }
+    }
+}
+
+/// Uniform buffer layout of the prefilter shader
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct PrefilterUniform {
+    /// Roughness of the mip level, the rendered face, the resolution of the mip level and of the base level
+    pub params: [f32; 4],
+}
+
+unsafe impl bytemuck::Pod for PrefilterUniform {}
+unsafe impl bytemuck::Zeroable for PrefilterUniform {}
+
+impl Uniform for PrefilterUniform {}
+
+impl PrefilterUniform {
+    pub fn new(face: u32, mip: u32, mip_count: u32, resolution: u32) -> PrefilterUniform {
+        PrefilterUniform {
+            params: [
+                mip_roughness(mip, mip_count),
+                face as f32,
+                (resolution >> mip).max(1) as f32,
+                resolution as f32,
+            ],
+        }
+    }
+}
+
+/// Pass prefiltering the mip levels of the pending probes from their base level for the specular term.
+/// The base level is provided by the cooked cube maps, the level load capture of the scene is not
+/// performed as the scene passes cannot render from a custom view yet.
+pub struct ReflectionCapturePass {
+    prefilter_key: PipelineKey,
+    pending: Vec<usize>,
+    sampler: Option<wgpu::Sampler>,
+    missing_binding: MissingBinding,
+    resource_claims: Option<Result<ResourceClaims, ECSError>>,
+}
+
+impl ReflectionCapturePass {
+    pub fn new(prefilter_pipeline: String) -> ReflectionCapturePass {
+        ReflectionCapturePass {
+            prefilter_key: PipelineKey::new::<vertex::Null>(prefilter_pipeline, Self::get_render_states()),
+            pending: Vec::new(),
+            sampler: None,
+            missing_binding: MissingBinding::default(),
+            resource_claims: None,
+        }
+    }
+
+    /// Probes requested for the capture in the last frame
+    pub fn pending(&self) -> &[usize] {
+        &self.pending
+    }
+
+    /// Render states of the prefilter pass writing a face of the probe cube maps
+    pub fn get_render_states() -> PipelineStateDescriptor {
+        PipelineStateDescriptor {
+            color_states: vec![wgpu::ColorStateDescriptor {
+                format: REFLECTION_PROBE_FORMAT,
+                alpha_blend: wgpu::BlendDescriptor::REPLACE,
+                color_blend: wgpu::BlendDescriptor::REPLACE,
+                write_mask: wgpu::ColorWrite::ALL,
+            }],
+            depth_state: None,
+            color_stage: None,
+        }
+    }
+}
+
+impl System for ReflectionCapturePass {
+    fn debug_name(&self) -> &str {
+        "ReflectionCapturePass"
+    }
+
+    fn name(&self) -> Option<&SystemName> {
+        None
+    }
+
+    fn resource_claims(&mut self) -> Result<&ResourceClaims, ECSError> {
+        let prefilter_key = &self.prefilter_key;
+        self.resource_claims
+            .get_or_insert_with(|| {
+                let mut claims = ResourceClaims::default();
+                claims.add_immutable::<Pipeline, _>(Some(ResourceId::from_object(prefilter_key)?));
+                claims.add_mutable::<ReflectionProbes, _>(Some(ResourceId::Global));
+                claims.add_immutable::<Context, _>(Some(ResourceId::Global));
+                Ok(claims)
+            })
+            .as_ref()
+            .map_err(|err| err.clone())
+    }
+
+    fn run(&mut self, resources: &Resources) -> Result<TaskGroup, ECSError> {
+        let mut reflection = resources.get_mut::<ReflectionProbes>()?;
+        let context = resources.get::<Context>()?;
+        let pipeline = resources.get_with_id::<Pipeline>(&ResourceId::from_object(&self.prefilter_key)?)?;
+        let (cubemaps, pipeline) = match (reflection.cubemaps.as_ref(), pipeline.pipeline_module()) {
+            (Some(cubemaps), Some(pipeline)) => (cubemaps, pipeline),
+            _ => return Ok(TaskGroup::default()),
+        };
+
+        let device = context.device();
+        let sampler = &*self
+            .sampler
+            .get_or_insert_with(|| create_target_sampler(&device, "reflection prefilter"));
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("reflection prefilter"),
+        });
+        let (resolution, mip_count) = (cubemaps.resolution(), cubemaps.mip_count());
+        let mut result = Ok(());
+        'probes: for &id in &self.pending {
+            let probe = cubemaps.probe_view(id as u32);
+            for mip in 1..mip_count {
+                for face in 0..6 {
+                    let uniform = PrefilterUniform::new(face, mip, mip_count, resolution);
+                    let uniform = create_uniform_buffer(&device, "reflection prefilter", &uniform);
+                    let target = cubemaps.face_view(id as u32, face, mip);
+                    result = draw_full_screen(
+                        &device,
+                        &mut encoder,
+                        pipeline,
+                        &[FullScreenTarget::clear(&target, wgpu::Color::BLACK)],
+                        |semantic| match semantic {
+                            UniformSemantic::UniformBuffer(name) if name.as_str() == REFLECTION_PREFILTER_UNIFORM => {
+                                Some(wgpu::BindingResource::Buffer(uniform.slice(..)))
+                            }
+                            UniformSemantic::Texture(TextureSemantic::Cube(name))
+                                if name.as_str() == REFLECTION_PROBE_TEXTURE =>
+                            {
+                                Some(wgpu::BindingResource::TextureView(&probe))
+                            }
+                            UniformSemantic::Sampler(_) => Some(wgpu::BindingResource::Sampler(sampler)),
+                            _ => None,
+                        },
+                    );
+                    if result.is_err() {
+                        break 'probes;
+                    }
+                }
+            }
+        }
+
+        if result.is_ok() {
+            context.add_command(encoder.finish());
+            for &id in &self.pending {
+                reflection.set_captured(id);
+            }
+        }
+        self.missing_binding.update("ReflectionCapturePass", result);
+        Ok(TaskGroup::default())
+    }
+}
+
+/// Resource of the reflection technique storing the probes of the level
+pub struct ReflectionProbes {
+    settings: ReflectionSettings,
+    probes: Vec<ReflectionProbe>,
+    captured: Vec<bool>,
+    cubemaps: Option<ProbeCubemaps>,
+    capture: Arc<Task<ReflectionCapturePass>>,
+}
+
+impl ReflectionProbes {
+    pub fn settings(&self) -> &ReflectionSettings {
+        &self.settings
+    }
+
+    pub fn probes(&self) -> &[ReflectionProbe] {
+        &self.probes
+    }
+
+    pub fn cubemaps(&self) -> Option<&ProbeCubemaps> {
+        self.cubemaps.as_ref()
+    }
+
+    /// Add a probe and return its index, that is also the layer in the cube map array.
+    pub fn add_probe(&mut self, probe: ReflectionProbe) -> Result<usize, RenderError> {
+        if self.probes.len() >= self.settings.max_probes as usize {
+            return Err(RenderError::Technique {
+                message: format!("Too many reflection probes, the limit is {}", self.settings.max_probes),
+            });
+        }
+        self.probes.push(probe);
+        self.captured.push(false);
+        Ok(self.probes.len() - 1)
+    }
+
+    /// Remove all the probes, ex. when the level is unloaded
+    pub fn clear(&mut self) {
+        self.probes.clear();
+        self.captured.clear();
+    }
+
+    pub fn is_captured(&self, id: usize) -> bool {
+        self.captured.get(id).cloned().unwrap_or(false)
+    }
+
+    /// Mark a probe captured, ex. when the cooked cube map has been uploaded
+    pub fn set_captured(&mut self, id: usize) {
+        if let Some(captured) = self.captured.get_mut(id) {
+            *captured = true;
+        }
+    }
+
+    /// Request a new capture of the level load probes, ex. when the lighting has changed
+    pub fn invalidate(&mut self) {
+        for (probe, captured) in self.probes.iter().zip(self.captured.iter_mut()) {
+            if probe.capture == ProbeCapture::LevelLoad {
+                *captured = false;
+            }
+        }
+    }
+
+    /// The level load probes waiting for a capture
+    pub fn pending_captures(&self) -> Vec<usize> {
+        self.probes
+            .iter()
+            .zip(self.captured.iter())
+            .enumerate()
+            .filter(|(_, (probe, captured))| !**captured && probe.capture == ProbeCapture::LevelLoad)
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// Blend of the captured probes for an object at the given position
+    pub fn blend(&self, point: &Point3<f32>) -> ProbeBlend {
+        let mut blend = ProbeBlend::from_probes(&self.probes, point, self.settings.max_blend.min(MAX_BLENDED_PROBES));
+        blend.probes.retain(|(id, _)| self.captured[*id]);
+        blend
+    }
+
+    /// Probe data of the shaders
+    pub fn gpu_probes(&self) -> Vec<GpuReflectionProbe> {
+        let mip_count = self.cubemaps.as_ref().map(|c| c.mip_count()).unwrap_or(1);
+        self.probes
+            .iter()
+            .zip(self.captured.iter())
+            .enumerate()
+            .map(|(id, (probe, captured))| GpuReflectionProbe::from_probe(probe, id as u32, mip_count, *captured))
+            .collect()
+    }
+
+    fn update_cubemaps(&mut self, device: &wgpu::Device) {
+        if self.cubemaps.is_none() {
+            self.cubemaps = Some(ProbeCubemaps::new(
+                device,
+                self.settings.resolution,
+                self.settings.max_probes,
+            ));
+        }
+    }
+}
+
+fn render_reflection(mut reflection: ResMut<ReflectionProbes>, context: Res<Context>) -> Result<TaskGroup, ECSError> {
+    reflection.update_cubemaps(&context.device());
+
+    let pending = reflection.pending_captures();
+    if pending.is_empty() {
+        return Ok(TaskGroup::default());
+    }
+
+    // the probes are marked captured by the pass once the prefilter has been recorded
+    reflection.capture.system()?.pending = pending;
+    Ok(TaskGroup::from_task(reflection.capture.clone()))
+}
+
+/// Render technique of the reflection probes. The probes are captured at cook time by the headless
+/// renderer or at level load into a cube map array, prefiltered for the roughness and blended per object
+/// by proximity for the specular term of the shading.
+/// Options:
+/// - prefilter_pipeline: the cooked pipeline of the prefilter pass. It is bound with the PrefilterUniform as
+///   the "prefilter" uniform buffer and the base level of the probe as the "probe" cube texture.
+/// - resolution: resolution of the cube map faces (default: 128)
+/// - max_probes: maximum number of the probes in a level (default: 16)
+/// - max_blend: maximum number of the probes blended for an object (default: 2, at most 4)
+pub struct ReflectionTechnique {
+    prefilter_pipeline: String,
+    settings: ReflectionSettings,
+}
+
+impl ReflectionTechnique {
+    pub fn from_config(config: &TechniqueConfig) -> Result<ReflectionTechnique, RenderError> {
+        let prefilter_pipeline = config
+            .option("prefilter_pipeline")
+            .ok_or_else(|| RenderError::Technique {
+                message: format!("Missing prefilter_pipeline for {}", REFLECTION_TECHNIQUE),
+            })?;
+
+        let default = ReflectionSettings::default();
+        let settings = ReflectionSettings {
+            resolution: config.parse_option("resolution", default.resolution)?,
+            max_probes: config.parse_option("max_probes", default.max_probes)?,
+            max_blend: config.parse_option("max_blend", default.max_blend)?,
+        };
+        if !settings.resolution.is_power_of_two()
+            || settings.max_probes == 0
+            || settings.max_blend == 0
+            || settings.max_blend > MAX_BLENDED_PROBES
+        {
+            return Err(RenderError::Technique {
+                message: format!("Invalid settings for {}: {:?}", REFLECTION_TECHNIQUE, settings),
+            });
+        }
+
+        Ok(ReflectionTechnique {
+            prefilter_pipeline: prefilter_pipeline.to_owned(),
+            settings,
+        })
+    }
+
+    pub fn settings(&self) -> &ReflectionSettings {
+        &self.settings
+    }
+
+    /// Create the resource without the gpu storage
+    pub fn create_probes(&self) -> ReflectionProbes {
+        ReflectionProbes {
+            settings: self.settings.clone(),
+            probes: Vec::new(),
+            captured: Vec::new(),
+            cubemaps: None,
+            capture: Task::new(ReflectionCapturePass::new(self.prefilter_pipeline.clone())),
+        }
+    }
+}
+
+impl RenderTechnique for ReflectionTechnique {
+    fn create(&mut self, world: &mut World) -> Result<TaskItem, RenderError> {
+        world
+            .resources
+            .register_with_instance(self.create_probes())
+            .map_err(|err| RenderError::Technique {
+                message: format!("Failed to register {}: {:?}", REFLECTION_TECHNIQUE, err),
+            })?;
+        Ok(render_reflection.into_system().into())
+    }
+
+    fn destroy(&mut self, world: &mut World) {
+        world.resources.unregister::<ReflectionProbes>();
+    }
+}
@@ -0,0 +1,115 @@
+use crate::assets::ColorStage;
+use std::cmp::Ordering;
+
+/// The queue a draw is submitted to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderQueueKind {
+    /// Opaque and alpha tested draws, sorted by pipeline then front-to-back to reduce overdraw
+    Opaque,
+    /// Blended draws, sorted back-to-front for a correct composition
+    Transparent,
+}
+
+impl From<ColorStage> for RenderQueueKind {
+    fn from(color_stage: ColorStage) -> Self {
+        if color_stage.is_transparent() {
+            RenderQueueKind::Transparent
+        } else {
+            RenderQueueKind::Opaque
+        }
+    }
+}
+
+/// A single draw in the render queue
+#[derive(Debug)]
+pub struct RenderItem<T> {
+    /// Identifies the pipeline (and bindings) to minimize state changes
+    pub pipeline_key: u32,
+    /// Distance from the camera in view space
+    pub depth: f32,
+    pub item: T,
+}
+
+/// Collect the draws of a frame and order them for submission
+pub struct RenderQueue<T> {
+    opaque: Vec<RenderItem<T>>,
+    transparent: Vec<RenderItem<T>>,
+}
+
+impl<T> Default for RenderQueue<T> {
+    fn default() -> Self {
+        RenderQueue {
+            opaque: Vec::new(),
+            transparent: Vec::new(),
+        }
+    }
+}
+
+impl<T> RenderQueue<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.opaque.clear();
+        self.transparent.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.opaque.len() + self.transparent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.opaque.is_empty() && self.transparent.is_empty()
+    }
+
+    pub fn push<K: Into<RenderQueueKind>>(&mut self, kind: K, pipeline_key: u32, depth: f32, item: T) {
+        let item = RenderItem {
+            pipeline_key,
+            depth,
+            item,
+        };
+        match kind.into() {
+            RenderQueueKind::Opaque => self.opaque.push(item),
+            RenderQueueKind::Transparent => self.transparent.push(item),
+        }
+    }
+
+    fn cmp_depth(a: f32, b: f32) -> Ordering {
+        a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+    }
+
+    fn sort_opaque(&mut self) {
+        self.opaque.sort_by(|a, b| {
+            a.pipeline_key
+                .cmp(&b.pipeline_key)
+                .then_with(|| Self::cmp_depth(a.depth, b.depth))
+        });
+    }
+
+    /// Sort the queues for submission. The sort is stable, draws with equal keys keep the submission order.
+    pub fn sort(&mut self) {
+        self.sort_opaque();
+        self.transparent.sort_by(|a, b| Self::cmp_depth(b.depth, a.depth));
+    }
+
+    /// Sort the queues for the order independent transparency. As the composition does not depend on
+    /// the order, the transparent draws are grouped by pipeline to reduce the state changes.
+    pub fn sort_order_independent(&mut self) {
+        self.sort_opaque();
+        self.transparent.sort_by_key(|a| a.pipeline_key);
+    }
+
+    pub fn opaque(&self) -> &[RenderItem<T>] {
+        &self.opaque
+    }
+
+    pub fn transparent(&self) -> &[RenderItem<T>] {
+        &self.transparent
+    }
+
+    /// Iterate the draws in submission order: all the opaque draws followed by the transparent ones.
+    pub fn iter(&self) -> impl Iterator<Item = &RenderItem<T>> {
+        self.opaque.iter().chain(self.transparent.iter())
+    }
+}
@@ -0,0 +1,141 @@
+use crate::render::RenderError;
+use image::{png::PngEncoder, ColorType};
+use std::sync::Arc;
+
+/// A captured frame in rgba8 format
+#[derive(Clone, Debug, PartialEq)]
+pub struct Screenshot {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Screenshot {
+    /// Create a screenshot from the (row padded) texels of a texture. The bgra formats are swizzled to rgba.
+    pub fn from_texels(
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+        bytes_per_row: u32,
+        texels: &[u8],
+    ) -> Result<Screenshot, RenderError> {
+        let bgra = match format {
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            format => {
+                return Err(RenderError::Screenshot {
+                    message: format!("Unsupported frame format: {:?}", format),
+                })
+            }
+        };
+        let row_len = width as usize * 4;
+        if (bytes_per_row as usize) < row_len || texels.len() < bytes_per_row as usize * height as usize {
+            return Err(RenderError::Screenshot {
+                message: "Incomplete frame data".to_owned(),
+            });
+        }
+
+        let mut pixels = Vec::with_capacity(row_len * height as usize);
+        for row in texels.chunks(bytes_per_row as usize).take(height as usize) {
+            pixels.extend_from_slice(&row[..row_len]);
+        }
+        if bgra {
+            for pixel in pixels.chunks_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+        Ok(Screenshot { width, height, pixels })
+    }
+
+    /// Encode the screenshot as png
+    pub fn to_png(&self) -> Result<Vec<u8>, RenderError> {
+        let mut data = Vec::new();
+        PngEncoder::new(&mut data)
+            .encode(&self.pixels, self.width, self.height, ColorType::Rgba8)
+            .map_err(|err| RenderError::Screenshot {
+                message: format!("Failed to encode png: {}", err),
+            })?;
+        Ok(data)
+    }
+}
+
+/// Bytes per row of the copy of a texture, aligned as required by the buffer copies
+pub fn padded_bytes_per_row(width: u32) -> u32 {
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let unpadded = width * 4;
+    (unpadded + align - 1) / align * align
+}
+
+/// The copy of a captured frame in flight, the data is available once the commands of the frame are submitted.
+pub struct ScreenshotReadback {
+    device: Arc<wgpu::Device>,
+    buffer: wgpu::Buffer,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    bytes_per_row: u32,
+}
+
+impl ScreenshotReadback {
+    /// Record the copy of the texture into a readback buffer
+    pub fn new(
+        device: Arc<wgpu::Device>,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+        descriptor: &wgpu::SwapChainDescriptor,
+    ) -> ScreenshotReadback {
+        let (width, height) = (descriptor.width.max(1), descriptor.height.max(1));
+        let bytes_per_row = padded_bytes_per_row(width);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("screenshot"),
+            size: bytes_per_row as u64 * height as u64,
+            usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            wgpu::TextureCopyView {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            wgpu::BufferCopyView {
+                buffer: &buffer,
+                layout: wgpu::TextureDataLayout {
+                    offset: 0,
+                    bytes_per_row,
+                    rows_per_image: height,
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth: 1,
+            },
+        );
+
+        ScreenshotReadback {
+            device,
+            buffer,
+            format: descriptor.format,
+            width,
+            height,
+            bytes_per_row,
+        }
+    }
+
+    /// Wait for the copy and read the screenshot
+    pub async fn read(self) -> Result<Screenshot, RenderError> {
+        let slice = self.buffer.slice(..);
+        let mapping = slice.map_async(wgpu::MapMode::Read);
+        self.device.poll(wgpu::Maintain::Wait);
+        mapping.await.map_err(|err| RenderError::Screenshot {
+            message: format!("Failed to map the readback buffer: {:?}", err),
+        })?;
+        let screenshot = {
+            let texels = slice.get_mapped_range();
+            Screenshot::from_texels(self.format, self.width, self.height, self.bytes_per_row, &texels)
+        };
+        self.buffer.unmap();
+        screenshot
+    }
+}
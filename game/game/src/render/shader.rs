@@ -1,6 +1,6 @@
 use crate::{
-    assets::{AssetIO, CookedShader, Url},
-    render::{Compile, CompiledShader},
+    assets::{AssetIO, CookedFormat, CookedShader, Url},
+    render::{Compile, CompiledShader, GpuErrorScope, GpuErrorSink, ShaderDependencies},
 };
 use serde::{Deserialize, Serialize};
 use shine_ecs::{
@@ -25,12 +25,16 @@ impl ShaderKey {
 #[derive(Debug)]
 pub enum ShaderEvent {
     Loaded,
+    /// A new version of the shader has been compiled and replaced the previous one
+    Reloaded,
 }
 
 pub struct Shader {
     id: String,
     shader: Result<Option<CompiledShader>, ShaderError>,
     dispatcher: ObserveDispatcher<ShaderEvent>,
+    requester: ResourceLoadRequester<Shader, LoadRequest>,
+    dependencies: ShaderDependencies,
 }
 
 impl Shader {
@@ -53,6 +57,13 @@ impl Shader {
     pub fn shader_module(&self) -> Option<&CompiledShader> {
         self.shader.as_ref().map(|u| u.as_ref()).unwrap_or(None)
     }
+
+    /// Request a reload of the shader, ex. when the shader asset has changed. The current shader module is kept
+    /// until the new one is compiled and the dependent pipelines are rebuilt once it is replaced.
+    pub fn reload(&self, handle: ShaderHandle) {
+        log::debug!("[{:?}] Reloading shader", self.id);
+        self.requester.send_request(handle, LoadRequest(self.id.clone()));
+    }
 }
 
 struct LoadRequest(String);
@@ -68,6 +79,7 @@ impl Shader {
         context: &ResourceLoadRequester<Self, LoadRequest>,
         handle: ResourceHandle<Self>,
         id: &ResourceId,
+        dependencies: &ShaderDependencies,
     ) -> Self {
         log::trace!("Creating [{:?}]", id);
         if let Ok(ShaderKey(id)) = id.to_object::<ShaderKey>() {
@@ -76,18 +88,22 @@ impl Shader {
                 id,
                 shader: Ok(None),
                 dispatcher: Default::default(),
+                requester: context.clone(),
+                dependencies: dependencies.clone(),
             }
         } else {
             Shader {
                 id: Default::default(),
                 shader: Err(ShaderError),
                 dispatcher: Default::default(),
+                requester: context.clone(),
+                dependencies: dependencies.clone(),
             }
         }
     }
 
     async fn load_and_compile(
-        (io, device): &(AssetIO, Arc<wgpu::Device>),
+        (io, device, errors): &(AssetIO, Arc<wgpu::Device>, GpuErrorSink),
         handle: &ResourceHandle<Self>,
         shader_id: String,
     ) -> Result<CompiledShader, ShaderError> {
@@ -98,18 +114,20 @@ impl Shader {
 
         log::debug!("[{:?}] Extracting shader...", shader_id);
         handle.check_liveness().map_err(|_| ShaderError)?;
-        let cooked_shader: CookedShader = bincode::deserialize_from(&*data).map_err(|_| ShaderError)?;
+        let cooked_shader: CookedShader = CookedFormat::deserialize(&data).map_err(|_| ShaderError)?;
 
         log::debug!("[{:?}] Compiling shader...", shader_id);
         handle.check_liveness().map_err(|_| ShaderError)?;
-        let compiled_shader = cooked_shader.compile(&*device);
+        let compiled_shader = errors
+            .capture(GpuErrorScope::Shader, &shader_id, || cooked_shader.compile(&*device))
+            .ok_or(ShaderError)?;
 
         log::debug!("[{:?}] Shader loaded", shader_id);
         Ok(compiled_shader)
     }
 
     async fn on_load(
-        ctx: &(AssetIO, Arc<wgpu::Device>),
+        ctx: &(AssetIO, Arc<wgpu::Device>, GpuErrorSink),
         responder: &ResourceLoadResponder<Shader, LoadResponse>,
         handle: ResourceHandle<Self>,
         request: LoadRequest,
@@ -129,21 +147,37 @@ impl Shader {
         response: LoadResponse,
     ) {
         log::debug!("[{:?}] Load completed", this.id);
+        let is_reload = this.shader_module().is_some();
         match response {
             LoadResponse::Compiled(shader) => this.shader = Ok(Some(shader)),
-            LoadResponse::Error(err) => this.shader = Err(err),
+            LoadResponse::Error(err) => {
+                if is_reload {
+                    // keep the previous version, the pipelines using it remain valid
+                    log::warn!("[{:?}] Shader reload failed, keeping the previous version", this.id);
+                    return;
+                }
+                this.shader = Err(err)
+            }
         };
-        this.dispatcher.notify_all(ShaderEvent::Loaded);
+
+        if is_reload {
+            this.dependencies.invalidate(&this.id);
+            this.dispatcher.notify_all(ShaderEvent::Reloaded);
+        } else {
+            this.dispatcher.notify_all(ShaderEvent::Loaded);
+        }
     }
 
     pub fn register_resource(
         resources: &mut Resources,
         io: AssetIO,
         device: Arc<wgpu::Device>,
+        errors: GpuErrorSink,
+        dependencies: ShaderDependencies,
     ) -> Result<(), ECSError> {
         resources.register(ResourceLoader::new(
-            Shader::build,
-            (io, device),
+            move |context, handle, id| Shader::build(context, handle, id, &dependencies),
+            (io, device, errors),
             Shader::on_load,
             Shader::on_load_response,
         ))
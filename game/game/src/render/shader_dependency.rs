@@ -0,0 +1,80 @@
+use shine_ecs::resources::ResourceId;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+#[derive(Default)]
+struct Inner {
+    /// Back-references from the shaders to the dependent pipelines
+    dependents: HashMap<String, HashSet<ResourceId>>,
+    /// Pipelines to rebuild on the next bake
+    pending: HashSet<ResourceId>,
+}
+
+/// Track the pipelines using a shader, thus when a shader is reloaded the dependent pipelines can be rebuilt.
+/// The registry is shared between the shader and pipeline resources, shaders invalidate the dependents and
+/// the pipelines are rebuilt during the bake of the pipeline resources.
+#[derive(Clone, Default)]
+pub struct ShaderDependencies(Arc<Mutex<Inner>>);
+
+impl ShaderDependencies {
+    pub fn new() -> ShaderDependencies {
+        ShaderDependencies::default()
+    }
+
+    /// Register that a pipeline (dependent) is built using the shader.
+    pub fn add_dependent(&self, shader_id: &str, dependent: &ResourceId) {
+        let mut inner = self.0.lock().unwrap();
+        inner
+            .dependents
+            .entry(shader_id.to_owned())
+            .or_insert_with(Default::default)
+            .insert(dependent.clone());
+    }
+
+    /// Remove all the back-references to a dependent, ex. when a pipeline is released
+    pub fn remove_dependent(&self, dependent: &ResourceId) {
+        let mut inner = self.0.lock().unwrap();
+        inner.dependents.retain(|_, dependents| {
+            dependents.remove(dependent);
+            !dependents.is_empty()
+        });
+        inner.pending.remove(dependent);
+    }
+
+    /// Return the dependents of a shader
+    pub fn dependents_of(&self, shader_id: &str) -> Vec<ResourceId> {
+        let inner = self.0.lock().unwrap();
+        inner
+            .dependents
+            .get(shader_id)
+            .map(|dependents| dependents.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Mark all the dependents of the shader for rebuild. Return the number of dependents affected.
+    pub fn invalidate(&self, shader_id: &str) -> usize {
+        let mut inner = self.0.lock().unwrap();
+        let Inner { dependents, pending } = &mut *inner;
+        match dependents.get(shader_id) {
+            Some(dependents) => {
+                log::debug!("Shader [{}] invalidates {} dependents", shader_id, dependents.len());
+                pending.extend(dependents.iter().cloned());
+                dependents.len()
+            }
+            None => 0,
+        }
+    }
+
+    /// Return if there are dependents waiting for a rebuild
+    pub fn has_pending(&self) -> bool {
+        !self.0.lock().unwrap().pending.is_empty()
+    }
+
+    /// Take the list of dependents waiting for a rebuild
+    pub fn take_pending(&self) -> Vec<ResourceId> {
+        let mut inner = self.0.lock().unwrap();
+        inner.pending.drain().collect()
+    }
+}
@@ -0,0 +1,73 @@
+use crate::{assets::Uniform, render::SunLight};
+use serde::{Deserialize, Serialize};
+
+/// Parameters of the single scattering atmosphere model.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AtmosphereParams {
+    pub planet_radius: f32,
+    pub atmosphere_radius: f32,
+    pub rayleigh_scattering: [f32; 3],
+    pub rayleigh_scale_height: f32,
+    pub mie_scattering: f32,
+    pub mie_scale_height: f32,
+    /// Mie preferred scattering direction (anisotropy)
+    pub mie_g: f32,
+}
+
+impl Default for AtmosphereParams {
+    fn default() -> AtmosphereParams {
+        AtmosphereParams {
+            planet_radius: 6_371e3,
+            atmosphere_radius: 6_471e3,
+            rayleigh_scattering: [5.5e-6, 13.0e-6, 22.4e-6],
+            rayleigh_scale_height: 8e3,
+            mie_scattering: 21e-6,
+            mie_scale_height: 1.2e3,
+            mie_g: 0.758,
+        }
+    }
+}
+
+/// Uniform buffer layout of the sky shaders
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct SkyUniform {
+    /// Direction towards the sun and the intensity
+    pub sun_direction_intensity: [f32; 4],
+    pub sun_color: [f32; 4],
+    /// Rayleigh scattering coefficients and scale height
+    pub rayleigh: [f32; 4],
+    /// Mie scattering coefficient, scale height, g and a flag (1 for procedural atmosphere, 0 for cubemap)
+    pub mie: [f32; 4],
+    /// Planet and atmosphere radius
+    pub radius: [f32; 4],
+}
+
+unsafe impl bytemuck::Pod for SkyUniform {}
+unsafe impl bytemuck::Zeroable for SkyUniform {}
+
+impl Uniform for SkyUniform {}
+
+impl SkyUniform {
+    pub fn new(sun: &SunLight, atmosphere: Option<&AtmosphereParams>) -> SkyUniform {
+        let d = sun.direction.normalize();
+        let default_params = AtmosphereParams::default();
+        let (params, procedural) = match atmosphere {
+            Some(params) => (params, 1.),
+            None => (&default_params, 0.),
+        };
+
+        SkyUniform {
+            sun_direction_intensity: [d.x, d.y, d.z, sun.intensity],
+            sun_color: [sun.color[0], sun.color[1], sun.color[2], 1.],
+            rayleigh: [
+                params.rayleigh_scattering[0],
+                params.rayleigh_scattering[1],
+                params.rayleigh_scattering[2],
+                params.rayleigh_scale_height,
+            ],
+            mie: [params.mie_scattering, params.mie_scale_height, params.mie_g, procedural],
+            radius: [params.planet_radius, params.atmosphere_radius, 0., 0.],
+        }
+    }
+}
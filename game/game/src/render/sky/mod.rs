@@ -0,0 +1,6 @@
+mod sun_light;
+pub use self::sun_light::*;
+mod atmosphere;
+pub use self::atmosphere::*;
+mod sky_technique;
+pub use self::sky_technique::*;
@@ -0,0 +1,183 @@
+use crate::{
+    assets::{vertex, UniformSemantic},
+    render::{
+        create_uniform_buffer, draw_full_screen, AtmosphereParams, Context, FrameTarget, FullScreenTarget,
+        MissingBinding, Pipeline, PipelineKey, RenderError, RenderTechnique, SkyUniform, SunLight, TechniqueConfig,
+    },
+    World,
+};
+use shine_ecs::{
+    resources::{Res, ResMut, ResourceId, Resources},
+    scheduler::{IntoSystem, ResourceClaims, System, SystemName, Task, TaskGroup, TaskItem},
+    ECSError,
+};
+use std::sync::Arc;
+
+/// Name of the technique in the registry
+pub const SKY_TECHNIQUE: &str = "sky";
+
+/// Name of the uniform buffer of the sky in the pipeline
+pub const SKY_UNIFORM: &str = "sky";
+
+/// Background pass drawing the sky from a cubemap or the procedural atmosphere.
+/// The pass does not write depth and it shall be placed before the transparent passes.
+pub struct SkyPass {
+    pipeline_key: PipelineKey,
+    cubemap: Option<String>,
+    atmosphere: Option<AtmosphereParams>,
+    uniform: Option<SkyUniform>,
+    missing_binding: MissingBinding,
+    resource_claims: Option<Result<ResourceClaims, ECSError>>,
+}
+
+impl SkyPass {
+    pub fn new(pipeline: String, cubemap: Option<String>, atmosphere: Option<AtmosphereParams>) -> SkyPass {
+        SkyPass {
+            pipeline_key: PipelineKey::new::<vertex::Null>(pipeline, Default::default()),
+            cubemap,
+            atmosphere,
+            uniform: None,
+            missing_binding: MissingBinding::default(),
+            resource_claims: None,
+        }
+    }
+
+    pub fn cubemap(&self) -> Option<&str> {
+        self.cubemap.as_deref()
+    }
+
+    /// Uniform computed for the last frame
+    pub fn uniform(&self) -> Option<&SkyUniform> {
+        self.uniform.as_ref()
+    }
+
+    pub fn set_render_state(&mut self, target: &FrameTarget) {
+        let pipeline_states = target.get_render_states();
+        if self.pipeline_key.render_state != pipeline_states {
+            self.pipeline_key.render_state = pipeline_states;
+            self.resource_claims = None;
+        }
+    }
+}
+
+impl System for SkyPass {
+    fn debug_name(&self) -> &str {
+        "SkyPass"
+    }
+
+    fn name(&self) -> Option<&SystemName> {
+        None
+    }
+
+    fn resource_claims(&mut self) -> Result<&ResourceClaims, ECSError> {
+        let pipeline_key = &self.pipeline_key;
+        self.resource_claims
+            .get_or_insert_with(|| {
+                let mut claims = ResourceClaims::default();
+                claims.add_immutable::<Pipeline, _>(Some(ResourceId::from_object(pipeline_key)?));
+                claims.add_immutable::<SunLight, _>(Some(ResourceId::Global));
+                claims.add_immutable::<Context, _>(Some(ResourceId::Global));
+                claims.add_immutable::<FrameTarget, _>(Some(ResourceId::Global));
+                Ok(claims)
+            })
+            .as_ref()
+            .map_err(|err| err.clone())
+    }
+
+    fn run(&mut self, resources: &Resources) -> Result<TaskGroup, ECSError> {
+        let uniform = {
+            let sun = resources.get::<SunLight>()?;
+            SkyUniform::new(&sun, self.atmosphere.as_ref())
+        };
+        self.uniform = Some(uniform);
+
+        let context = resources.get::<Context>()?;
+        let target = resources.get::<FrameTarget>()?;
+        let pipeline = resources.get_with_id::<Pipeline>(&ResourceId::from_object(&self.pipeline_key)?)?;
+        let (view, pipeline) = match (target.view(), pipeline.pipeline_module()) {
+            (Some(view), Some(pipeline)) => (view, pipeline),
+            _ => return Ok(TaskGroup::default()),
+        };
+
+        // the cooked cubemaps are not loaded as render resources, thus only the procedural atmosphere
+        // can be bound
+        let device = context.device();
+        let uniform = create_uniform_buffer(&device, "sky", &uniform);
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("sky") });
+        let result = draw_full_screen(
+            &device,
+            &mut encoder,
+            pipeline,
+            &[FullScreenTarget::load(view)],
+            |semantic| match semantic {
+                UniformSemantic::UniformBuffer(name) if name.as_str() == SKY_UNIFORM => {
+                    Some(wgpu::BindingResource::Buffer(uniform.slice(..)))
+                }
+                _ => None,
+            },
+        );
+        if result.is_ok() {
+            context.add_command(encoder.finish());
+        }
+        self.missing_binding.update("SkyPass", result);
+        Ok(TaskGroup::default())
+    }
+}
+
+/// Resource of the sky technique
+pub struct Sky {
+    pass: Arc<Task<SkyPass>>,
+}
+
+fn render_sky(sky: ResMut<Sky>, target: Res<FrameTarget>) -> Result<TaskGroup, ECSError> {
+    sky.pass.system()?.set_render_state(&target);
+    Ok(TaskGroup::from_task(sky.pass.clone()))
+}
+
+/// Render technique of the sky. The pipeline is bound with the SkyUniform as the "sky" uniform buffer.
+/// Options:
+/// - pipeline: the cooked pipeline of the sky
+/// - cubemap: the cooked environment texture, if not given the procedural atmosphere is used
+/// - atmosphere: "true" to force the procedural atmosphere
+pub struct SkyTechnique {
+    pipeline: String,
+    cubemap: Option<String>,
+    atmosphere: Option<AtmosphereParams>,
+}
+
+impl SkyTechnique {
+    pub fn from_config(config: &TechniqueConfig) -> Result<SkyTechnique, RenderError> {
+        let pipeline = config.option("pipeline").ok_or_else(|| RenderError::Technique {
+            message: format!("Missing pipeline for {}", SKY_TECHNIQUE),
+        })?;
+        let cubemap = config.option("cubemap").map(|c| c.to_owned());
+        let atmosphere = config.parse_option("atmosphere", cubemap.is_none())?;
+
+        Ok(SkyTechnique {
+            pipeline: pipeline.to_owned(),
+            cubemap,
+            atmosphere: if atmosphere {
+                Some(AtmosphereParams::default())
+            } else {
+                None
+            },
+        })
+    }
+}
+
+impl RenderTechnique for SkyTechnique {
+    fn create(&mut self, world: &mut World) -> Result<TaskItem, RenderError> {
+        let pass = SkyPass::new(self.pipeline.clone(), self.cubemap.clone(), self.atmosphere.clone());
+        world
+            .resources
+            .register_with_instance(Sky { pass: Task::new(pass) })
+            .map_err(|err| RenderError::Technique {
+                message: format!("Failed to register {}: {:?}", SKY_TECHNIQUE, err),
+            })?;
+        Ok(render_sky.into_system().into())
+    }
+
+    fn destroy(&mut self, world: &mut World) {
+        world.resources.unregister::<Sky>();
+    }
+}
@@ -0,0 +1,33 @@
+use nalgebra::Vector3;
+
+/// The directional light of the sun driving the sky and the atmosphere.
+#[derive(Debug, Clone)]
+pub struct SunLight {
+    /// Direction pointing towards the sun
+    pub direction: Vector3<f32>,
+    pub color: [f32; 3],
+    pub intensity: f32,
+}
+
+impl Default for SunLight {
+    fn default() -> SunLight {
+        SunLight::from_angles(std::f32::consts::FRAC_PI_4, 0.)
+    }
+}
+
+impl SunLight {
+    /// Create a sun from the elevation above the horizon and the azimuth (measured from the -z axis), in radians.
+    pub fn from_angles(elevation: f32, azimuth: f32) -> SunLight {
+        let (se, ce) = elevation.sin_cos();
+        let (sa, ca) = azimuth.sin_cos();
+        SunLight {
+            direction: Vector3::new(ce * sa, se, -ce * ca),
+            color: [1., 1., 1.],
+            intensity: 20.,
+        }
+    }
+
+    pub fn elevation(&self) -> f32 {
+        self.direction.normalize().y.asin()
+    }
+}
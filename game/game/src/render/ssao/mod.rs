@@ -0,0 +1,6 @@
+mod ssao_kernel;
+pub use self::ssao_kernel::*;
+mod ssao_targets;
+pub use self::ssao_targets::*;
+mod ssao_technique;
+pub use self::ssao_technique::*;
@@ -0,0 +1,47 @@
+use crate::render::halton;
+use nalgebra::Vector3;
+
+/// Edge length of the tiled rotation noise
+pub const SSAO_NOISE_SIZE: u32 = 4;
+
+/// Sample offsets of the ambient occlusion in the tangent space hemisphere (z is the normal). The samples
+/// follow the Halton sequence and they are distributed more densely close to the shaded point, as the near
+/// occluders contribute more.
+pub fn ssao_kernel(sample_count: u32) -> Vec<Vector3<f32>> {
+    (0..sample_count)
+        .map(|i| {
+            let index = i + 1;
+            let phi = 2. * std::f32::consts::PI * halton(index, 2);
+            let cos_theta = halton(index, 3);
+            let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+            let direction = Vector3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, cos_theta);
+
+            let t = i as f32 / sample_count as f32;
+            let scale = 0.1 + 0.9 * t * t;
+            let length = halton(index, 5).max(0.1);
+            direction * (length * scale)
+        })
+        .collect()
+}
+
+/// Rotation vectors around the normal tiled over the screen to trade the banding of the small kernels
+/// for noise, that is removed by the blur.
+pub fn ssao_noise() -> Vec<[f32; 4]> {
+    let count = SSAO_NOISE_SIZE * SSAO_NOISE_SIZE;
+    (0..count)
+        .map(|i| {
+            let angle = 2. * std::f32::consts::PI * halton(i + 1, 7);
+            [angle.cos(), angle.sin(), 0., 0.]
+        })
+        .collect()
+}
+
+/// Weight of a sample of the depth aware (bilateral) blur. The spatial weight is a gaussian of the pixel
+/// offset, the range weight drops as the depth of the sample differs from the center to keep the edges.
+pub fn bilateral_weight(offset: i32, center_depth: f32, sample_depth: f32, blur_radius: u32, depth_sigma: f32) -> f32 {
+    let sigma = (blur_radius as f32 * 0.5).max(0.5);
+    let spatial = (-((offset * offset) as f32) / (2. * sigma * sigma)).exp();
+    let depth_delta = (center_depth - sample_depth) / center_depth.abs().max(1e-5);
+    let range = (-(depth_delta * depth_delta) / (2. * depth_sigma * depth_sigma).max(1e-10)).exp();
+    spatial * range
+}
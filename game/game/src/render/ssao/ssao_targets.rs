@@ -0,0 +1,124 @@
+use crate::{assets::PipelineStateDescriptor, render::FrameTarget};
+
+/// Format of the view space normal target written by the main pass
+pub const SSAO_NORMAL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgb10a2Unorm;
+/// Format of the depth target of the main pass sampled by the occlusion pass
+pub const SSAO_DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+/// Format of the occlusion targets, 1 is unoccluded
+pub const SSAO_OCCLUSION_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R8Unorm;
+
+struct Target {
+    _texture: wgpu::Texture,
+    view: wgpu::TextureView,
+}
+
+impl Target {
+    fn new(device: &wgpu::Device, label: &str, size: (u32, u32), format: wgpu::TextureFormat) -> Target {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: size.0,
+                height: size.1,
+                depth: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT | wgpu::TextureUsage::SAMPLED,
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Target {
+            _texture: texture,
+            view,
+        }
+    }
+}
+
+/// The off-screen targets of the ambient occlusion: the depth and normal written by the main pass in
+/// full resolution and the occlusion targets in the (optionally reduced) resolution of the occlusion pass.
+/// The separable blur ping-pongs between the two occlusion targets, the result is in the occlusion target.
+pub struct SsaoTargets {
+    size: (u32, u32),
+    occlusion_size: (u32, u32),
+    depth: Target,
+    normal: Target,
+    occlusion: Target,
+    blur: Target,
+}
+
+impl SsaoTargets {
+    pub fn new(device: &wgpu::Device, size: (u32, u32), half_resolution: bool) -> SsaoTargets {
+        let occlusion_size = Self::occlusion_size(size, half_resolution);
+        log::debug!(
+            "Creating SSAO targets with size {:?}, occlusion size {:?}",
+            size,
+            occlusion_size
+        );
+        SsaoTargets {
+            size,
+            occlusion_size,
+            depth: Target::new(device, "ssao depth", size, SSAO_DEPTH_FORMAT),
+            normal: Target::new(device, "ssao normal", size, SSAO_NORMAL_FORMAT),
+            occlusion: Target::new(device, "ssao occlusion", occlusion_size, SSAO_OCCLUSION_FORMAT),
+            blur: Target::new(device, "ssao blur", occlusion_size, SSAO_OCCLUSION_FORMAT),
+        }
+    }
+
+    /// Size of the occlusion targets for the given frame size
+    pub fn occlusion_size(size: (u32, u32), half_resolution: bool) -> (u32, u32) {
+        if half_resolution {
+            ((size.0 / 2).max(1), (size.1 / 2).max(1))
+        } else {
+            size
+        }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    pub fn is_half_resolution(&self) -> bool {
+        self.occlusion_size != self.size
+    }
+
+    pub fn depth_view(&self) -> &wgpu::TextureView {
+        &self.depth.view
+    }
+
+    pub fn normal_view(&self) -> &wgpu::TextureView {
+        &self.normal.view
+    }
+
+    /// The raw occlusion before the blur and the blurred occlusion sampled by the lighting afterwards
+    pub fn occlusion_view(&self) -> &wgpu::TextureView {
+        &self.occlusion.view
+    }
+
+    /// Intermediate target of the horizontal blur
+    pub fn blur_view(&self) -> &wgpu::TextureView {
+        &self.blur.view
+    }
+
+    /// Render states of the main pass: the frame color, the view space normal and the sampled depth
+    pub fn get_render_states(target: &FrameTarget) -> PipelineStateDescriptor {
+        let mut states = target.get_render_states();
+        if states.color_states.is_empty() {
+            // no frame
+            return states;
+        }
+        states.color_states.push(wgpu::ColorStateDescriptor {
+            format: SSAO_NORMAL_FORMAT,
+            alpha_blend: wgpu::BlendDescriptor::REPLACE,
+            color_blend: wgpu::BlendDescriptor::REPLACE,
+            write_mask: wgpu::ColorWrite::ALL,
+        });
+        states.depth_state = Some(wgpu::DepthStencilStateDescriptor {
+            format: SSAO_DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilStateDescriptor::default(),
+        });
+        states
+    }
+}
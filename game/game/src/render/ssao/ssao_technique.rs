@@ -0,0 +1,498 @@
+use crate::{
+    assets::{vertex, PipelineStateDescriptor, TextureSemantic, Uniform, UniformSemantic},
+    render::{
+        create_target_sampler, create_uniform_buffer, draw_full_screen, ssao_kernel, ssao_noise, Context, FrameTarget,
+        FullScreenTarget, MissingBinding, Pipeline, PipelineKey, RenderError, RenderQuality, RenderTechnique,
+        SsaoTargets, TechniqueConfig,
+    },
+    World,
+};
+use shine_ecs::{
+    resources::{Res, ResMut, ResourceId, Resources},
+    scheduler::{IntoSystem, ResourceClaims, System, SystemName, Task, TaskGroup, TaskItem},
+    ECSError,
+};
+use std::sync::Arc;
+
+/// Name of the technique in the registry
+pub const SSAO_TECHNIQUE: &str = "ssao";
+
+/// Maximum number of the kernel samples, it shall match the size of the kernel array of the shaders
+pub const SSAO_MAX_SAMPLE_COUNT: u32 = 64;
+/// Maximum radius of the blur in pixels
+pub const SSAO_MAX_BLUR_RADIUS: u32 = 8;
+
+/// Name of the uniform buffer of the occlusion pipeline
+pub const SSAO_UNIFORM: &str = "ssao";
+/// Name of the uniform buffer of the blur pipeline
+pub const SSAO_BLUR_UNIFORM: &str = "ssao_blur";
+/// Name of the depth of the main pass in the pipelines
+pub const SSAO_DEPTH_TEXTURE: &str = "depth";
+/// Name of the view space normal of the main pass in the occlusion pipeline
+pub const SSAO_NORMAL_TEXTURE: &str = "normal";
+/// Name of the blurred occlusion in the blur pipeline
+pub const SSAO_OCCLUSION_TEXTURE: &str = "occlusion";
+
+/// Parameters of the occlusion and the blur
+#[derive(Debug, Clone, PartialEq)]
+pub struct SsaoSettings {
+    /// Number of the hemisphere samples
+    pub sample_count: u32,
+    /// Radius of the sampled hemisphere in view space units
+    pub radius: f32,
+    /// Strength of the occlusion, 0 disables the effect
+    pub intensity: f32,
+    /// Depth bias to avoid the self occlusion of the flat surfaces
+    pub bias: f32,
+    /// Radius of the bilateral blur in pixels, 0 disables the blur
+    pub blur_radius: u32,
+    /// Relative depth difference where the weight of a blur sample drops
+    pub blur_depth_sigma: f32,
+    /// Compute the occlusion in half resolution
+    pub half_resolution: bool,
+}
+
+impl Default for SsaoSettings {
+    fn default() -> SsaoSettings {
+        SsaoSettings::from_quality(RenderQuality::default())
+    }
+}
+
+impl SsaoSettings {
+    /// Default settings of a quality tier
+    pub fn from_quality(quality: RenderQuality) -> SsaoSettings {
+        let (sample_count, blur_radius, half_resolution) = match quality {
+            RenderQuality::Low => (8, 2, true),
+            RenderQuality::Medium => (16, 4, true),
+            RenderQuality::High => (32, 4, false),
+            RenderQuality::Ultra => (64, 6, false),
+        };
+        SsaoSettings {
+            sample_count,
+            radius: 0.5,
+            intensity: 1.,
+            bias: 0.025,
+            blur_radius,
+            blur_depth_sigma: 0.1,
+            half_resolution,
+        }
+    }
+
+    /// Attenuate the ambient light by the occlusion as done by the lighting shaders. The occlusion is 1 for
+    /// the unoccluded pixels.
+    pub fn apply_occlusion(&self, ambient: [f32; 3], occlusion: f32) -> [f32; 3] {
+        let factor = 1. - self.intensity * (1. - occlusion.max(0.).min(1.));
+        let factor = factor.max(0.);
+        [ambient[0] * factor, ambient[1] * factor, ambient[2] * factor]
+    }
+}
+
+/// Uniform buffer layout of the occlusion shader
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SsaoUniform {
+    /// The hemisphere samples, only the first sample_count is used
+    pub kernel: [[f32; 4]; SSAO_MAX_SAMPLE_COUNT as usize],
+    /// The tiled rotation vectors
+    pub noise: [[f32; 4]; 16],
+    /// Radius, intensity, bias and the number of the samples
+    pub params: [f32; 4],
+    /// Size of the occlusion target and its reciprocal
+    pub screen: [f32; 4],
+}
+
+unsafe impl bytemuck::Pod for SsaoUniform {}
+unsafe impl bytemuck::Zeroable for SsaoUniform {}
+
+impl Uniform for SsaoUniform {}
+
+impl SsaoUniform {
+    pub fn new(settings: &SsaoSettings, occlusion_size: (u32, u32)) -> SsaoUniform {
+        let mut kernel = [[0.; 4]; SSAO_MAX_SAMPLE_COUNT as usize];
+        for (dst, src) in kernel.iter_mut().zip(ssao_kernel(settings.sample_count).iter()) {
+            *dst = [src.x, src.y, src.z, 0.];
+        }
+        let mut noise = [[0.; 4]; 16];
+        for (dst, src) in noise.iter_mut().zip(ssao_noise().iter()) {
+            *dst = *src;
+        }
+        let (w, h) = (occlusion_size.0.max(1) as f32, occlusion_size.1.max(1) as f32);
+        SsaoUniform {
+            kernel,
+            noise,
+            params: [
+                settings.radius,
+                settings.intensity,
+                settings.bias,
+                settings.sample_count as f32,
+            ],
+            screen: [w, h, 1. / w, 1. / h],
+        }
+    }
+}
+
+/// Uniform buffer layout of a direction of the separable bilateral blur
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SsaoBlurUniform {
+    /// Step between the samples in texture coordinates
+    pub direction: [f32; 2],
+    /// Radius in pixels and the depth sigma
+    pub params: [f32; 2],
+}
+
+unsafe impl bytemuck::Pod for SsaoBlurUniform {}
+unsafe impl bytemuck::Zeroable for SsaoBlurUniform {}
+
+impl Uniform for SsaoBlurUniform {}
+
+impl SsaoBlurUniform {
+    /// The uniforms of the horizontal and the vertical blur
+    pub fn new(settings: &SsaoSettings, occlusion_size: (u32, u32)) -> [SsaoBlurUniform; 2] {
+        let (w, h) = (occlusion_size.0.max(1) as f32, occlusion_size.1.max(1) as f32);
+        let params = [settings.blur_radius as f32, settings.blur_depth_sigma];
+        [
+            SsaoBlurUniform {
+                direction: [1. / w, 0.],
+                params,
+            },
+            SsaoBlurUniform {
+                direction: [0., 1. / h],
+                params,
+            },
+        ]
+    }
+}
+
+/// The full screen passes of the technique
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SsaoPassKind {
+    /// Compute the occlusion from the depth and the normal
+    Occlusion,
+    /// Blur the occlusion into the blur target
+    BlurHorizontal,
+    /// Blur the blur target back into the occlusion
+    BlurVertical,
+}
+
+impl SsaoPassKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            SsaoPassKind::Occlusion => "SsaoPass",
+            SsaoPassKind::BlurHorizontal => "SsaoBlurHorizontalPass",
+            SsaoPassKind::BlurVertical => "SsaoBlurVerticalPass",
+        }
+    }
+}
+
+/// A full screen pass of the technique: the occlusion or a direction of the blur
+pub struct SsaoPass {
+    kind: SsaoPassKind,
+    pipeline_key: PipelineKey,
+    sampler: Option<wgpu::Sampler>,
+    missing_binding: MissingBinding,
+    resource_claims: Option<Result<ResourceClaims, ECSError>>,
+}
+
+impl SsaoPass {
+    pub fn new(kind: SsaoPassKind, pipeline: String) -> SsaoPass {
+        SsaoPass {
+            kind,
+            pipeline_key: PipelineKey::new::<vertex::Null>(pipeline, Default::default()),
+            sampler: None,
+            missing_binding: MissingBinding::default(),
+            resource_claims: None,
+        }
+    }
+
+    pub fn kind(&self) -> SsaoPassKind {
+        self.kind
+    }
+
+    /// The passes write a single occlusion target
+    pub fn set_render_state(&mut self) {
+        let pipeline_states = PipelineStateDescriptor {
+            color_states: vec![wgpu::ColorStateDescriptor {
+                format: crate::render::SSAO_OCCLUSION_FORMAT,
+                alpha_blend: wgpu::BlendDescriptor::REPLACE,
+                color_blend: wgpu::BlendDescriptor::REPLACE,
+                write_mask: wgpu::ColorWrite::RED,
+            }],
+            depth_state: None,
+            color_stage: None,
+        };
+        if self.pipeline_key.render_state != pipeline_states {
+            self.pipeline_key.render_state = pipeline_states;
+            self.resource_claims = None;
+        }
+    }
+}
+
+impl System for SsaoPass {
+    fn debug_name(&self) -> &str {
+        self.kind.name()
+    }
+
+    fn name(&self) -> Option<&SystemName> {
+        None
+    }
+
+    fn resource_claims(&mut self) -> Result<&ResourceClaims, ECSError> {
+        let pipeline_key = &self.pipeline_key;
+        self.resource_claims
+            .get_or_insert_with(|| {
+                let mut claims = ResourceClaims::default();
+                claims.add_immutable::<Pipeline, _>(Some(ResourceId::from_object(pipeline_key)?));
+                claims.add_immutable::<Ssao, _>(Some(ResourceId::Global));
+                claims.add_immutable::<Context, _>(Some(ResourceId::Global));
+                Ok(claims)
+            })
+            .as_ref()
+            .map_err(|err| err.clone())
+    }
+
+    fn run(&mut self, resources: &Resources) -> Result<TaskGroup, ECSError> {
+        let ssao = resources.get::<Ssao>()?;
+        let context = resources.get::<Context>()?;
+        let pipeline = resources.get_with_id::<Pipeline>(&ResourceId::from_object(&self.pipeline_key)?)?;
+        let (targets, pipeline) = match (ssao.targets(), pipeline.pipeline_module()) {
+            (Some(targets), Some(pipeline)) => (targets, pipeline),
+            _ => return Ok(TaskGroup::default()),
+        };
+        let device = context.device();
+        let (uniform_name, uniform, source, target) = match (self.kind, ssao.uniform(), ssao.blur_uniforms()) {
+            (SsaoPassKind::Occlusion, Some(uniform), _) => (
+                SSAO_UNIFORM,
+                create_uniform_buffer(&device, "ssao", uniform),
+                None,
+                targets.occlusion_view(),
+            ),
+            (SsaoPassKind::BlurHorizontal, _, Some(blur)) => (
+                SSAO_BLUR_UNIFORM,
+                create_uniform_buffer(&device, "ssao blur", &blur[0]),
+                Some(targets.occlusion_view()),
+                targets.blur_view(),
+            ),
+            (SsaoPassKind::BlurVertical, _, Some(blur)) => (
+                SSAO_BLUR_UNIFORM,
+                create_uniform_buffer(&device, "ssao blur", &blur[1]),
+                Some(targets.blur_view()),
+                targets.occlusion_view(),
+            ),
+            _ => return Ok(TaskGroup::default()),
+        };
+
+        let sampler = &*self
+            .sampler
+            .get_or_insert_with(|| create_target_sampler(&device, "ssao"));
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("ssao") });
+        let result = draw_full_screen(
+            &device,
+            &mut encoder,
+            pipeline,
+            &[FullScreenTarget::clear(target, wgpu::Color::WHITE)],
+            |semantic| match semantic {
+                UniformSemantic::UniformBuffer(name) if name.as_str() == uniform_name => {
+                    Some(wgpu::BindingResource::Buffer(uniform.slice(..)))
+                }
+                UniformSemantic::Texture(TextureSemantic::Frame(name)) => match (name.as_str(), source) {
+                    (SSAO_DEPTH_TEXTURE, _) => Some(wgpu::BindingResource::TextureView(targets.depth_view())),
+                    (SSAO_NORMAL_TEXTURE, None) => Some(wgpu::BindingResource::TextureView(targets.normal_view())),
+                    (SSAO_OCCLUSION_TEXTURE, Some(source)) => Some(wgpu::BindingResource::TextureView(source)),
+                    _ => None,
+                },
+                UniformSemantic::Sampler(_) => Some(wgpu::BindingResource::Sampler(sampler)),
+                _ => None,
+            },
+        );
+        if result.is_ok() {
+            context.add_command(encoder.finish());
+        }
+        self.missing_binding.update(self.kind.name(), result);
+        Ok(TaskGroup::default())
+    }
+}
+
+/// Resource of the SSAO technique. The lighting samples the occlusion target to attenuate the ambient term.
+pub struct Ssao {
+    settings: SsaoSettings,
+    targets: Option<SsaoTargets>,
+    uniform: Option<SsaoUniform>,
+    blur_uniforms: Option<[SsaoBlurUniform; 2]>,
+    occlusion: Arc<Task<SsaoPass>>,
+    blur: Option<[Arc<Task<SsaoPass>>; 2]>,
+}
+
+impl Ssao {
+    pub fn settings(&self) -> &SsaoSettings {
+        &self.settings
+    }
+
+    pub fn targets(&self) -> Option<&SsaoTargets> {
+        self.targets.as_ref()
+    }
+
+    /// The occlusion of the current frame to be sampled by the lighting
+    pub fn occlusion_view(&self) -> Option<&wgpu::TextureView> {
+        self.targets.as_ref().map(|targets| targets.occlusion_view())
+    }
+
+    /// Uniform computed for the last frame
+    pub fn uniform(&self) -> Option<&SsaoUniform> {
+        self.uniform.as_ref()
+    }
+
+    /// Blur uniforms computed for the last frame
+    pub fn blur_uniforms(&self) -> Option<&[SsaoBlurUniform; 2]> {
+        self.blur_uniforms.as_ref()
+    }
+
+    /// Render states of the main pass writing the normal and the depth too
+    pub fn get_render_states(&self, target: &FrameTarget) -> PipelineStateDescriptor {
+        SsaoTargets::get_render_states(target)
+    }
+
+    fn update_targets(&mut self, device: &wgpu::Device, size: (u32, u32)) {
+        if size.0 == 0 || size.1 == 0 {
+            return;
+        }
+        if self.targets.as_ref().map(|targets| targets.size()) != Some(size) {
+            self.targets = Some(SsaoTargets::new(device, size, self.settings.half_resolution));
+            self.uniform = None;
+        }
+    }
+}
+
+fn render_ssao(mut ssao: ResMut<Ssao>, target: Res<FrameTarget>, context: Res<Context>) -> Result<TaskGroup, ECSError> {
+    let size = target.size();
+    ssao.update_targets(&context.device(), size);
+    if ssao.targets.is_none() || ssao.settings.intensity <= 0. {
+        return Ok(TaskGroup::default());
+    }
+
+    // the kernel changes only with the size of the targets
+    if ssao.uniform.is_none() {
+        let occlusion_size = SsaoTargets::occlusion_size(size, ssao.settings.half_resolution);
+        ssao.uniform = Some(SsaoUniform::new(&ssao.settings, occlusion_size));
+        ssao.blur_uniforms = Some(SsaoBlurUniform::new(&ssao.settings, occlusion_size));
+    }
+
+    let mut tasks = TaskGroup::default();
+    ssao.occlusion.system()?.set_render_state();
+    tasks.add_task(ssao.occlusion.clone());
+    if let Some(blur) = &ssao.blur {
+        for pass in blur {
+            pass.system()?.set_render_state();
+            tasks.add_task(pass.clone());
+        }
+    }
+    Ok(tasks)
+}
+
+/// Render technique of the screen-space ambient occlusion. The main pass shall use the render states of
+/// the Ssao resource to output the view space normal and the depth, the technique shall be placed after the
+/// main pass and before the lighting that composites the occlusion into the ambient term.
+/// Options:
+/// - pipeline: the cooked pipeline of the occlusion pass. It is bound with the SsaoUniform as the "ssao" uniform
+///   buffer and the "depth" and "normal" frame textures.
+/// - blur_pipeline: the cooked pipeline of the bilateral blur, the blur is skipped without it. It is bound with
+///   the SsaoBlurUniform as the "ssao_blur" uniform buffer and the "occlusion" and "depth" frame textures.
+/// - quality: low, medium, high or ultra, the default is the quality of the render config
+/// - samples, radius, intensity, bias: override the occlusion settings of the quality
+/// - blur_radius, blur_depth_sigma: override the blur settings of the quality
+/// - half_resolution: compute the occlusion in half resolution
+pub struct SsaoTechnique {
+    pipeline: String,
+    blur_pipeline: Option<String>,
+    quality: Option<RenderQuality>,
+    config: TechniqueConfig,
+}
+
+impl SsaoTechnique {
+    pub fn from_config(config: &TechniqueConfig) -> Result<SsaoTechnique, RenderError> {
+        let pipeline = config.option("pipeline").ok_or_else(|| RenderError::Technique {
+            message: format!("Missing pipeline for {}", SSAO_TECHNIQUE),
+        })?;
+        let quality = match config.option("quality") {
+            Some(_) => Some(config.parse_option("quality", RenderQuality::default())?),
+            None => None,
+        };
+
+        let technique = SsaoTechnique {
+            pipeline: pipeline.to_owned(),
+            blur_pipeline: config.option("blur_pipeline").map(|pipeline| pipeline.to_owned()),
+            quality,
+            config: config.clone(),
+        };
+        // validate the options early
+        technique.settings(quality.unwrap_or_default())?;
+        Ok(technique)
+    }
+
+    /// The settings for the given quality with the explicit options applied
+    pub fn settings(&self, quality: RenderQuality) -> Result<SsaoSettings, RenderError> {
+        let quality = self.quality.unwrap_or(quality);
+        let default = SsaoSettings::from_quality(quality);
+        let config = &self.config;
+        let settings = SsaoSettings {
+            sample_count: config.parse_option("samples", default.sample_count)?,
+            radius: config.parse_option("radius", default.radius)?,
+            intensity: config.parse_option("intensity", default.intensity)?,
+            bias: config.parse_option("bias", default.bias)?,
+            blur_radius: config.parse_option("blur_radius", default.blur_radius)?,
+            blur_depth_sigma: config.parse_option("blur_depth_sigma", default.blur_depth_sigma)?,
+            half_resolution: config.parse_option("half_resolution", default.half_resolution)?,
+        };
+        if settings.sample_count == 0
+            || settings.sample_count > SSAO_MAX_SAMPLE_COUNT
+            || settings.radius <= 0.
+            || settings.intensity < 0.
+            || settings.blur_radius > SSAO_MAX_BLUR_RADIUS
+            || settings.blur_depth_sigma <= 0.
+        {
+            return Err(RenderError::Technique {
+                message: format!("Invalid settings for {}: {:?}", SSAO_TECHNIQUE, settings),
+            });
+        }
+        Ok(settings)
+    }
+}
+
+impl RenderTechnique for SsaoTechnique {
+    fn create(&mut self, world: &mut World) -> Result<TaskItem, RenderError> {
+        let quality = world
+            .resources
+            .get::<RenderQuality>()
+            .map(|quality| *quality)
+            .unwrap_or_default();
+        let settings = self.settings(quality)?;
+        log::info!("SSAO settings ({}): {:?}", quality, settings);
+
+        let blur = match &self.blur_pipeline {
+            Some(pipeline) if settings.blur_radius > 0 => Some([
+                Task::new(SsaoPass::new(SsaoPassKind::BlurHorizontal, pipeline.clone())),
+                Task::new(SsaoPass::new(SsaoPassKind::BlurVertical, pipeline.clone())),
+            ]),
+            _ => None,
+        };
+        let ssao = Ssao {
+            settings,
+            targets: None,
+            uniform: None,
+            blur_uniforms: None,
+            occlusion: Task::new(SsaoPass::new(SsaoPassKind::Occlusion, self.pipeline.clone())),
+            blur,
+        };
+        world
+            .resources
+            .register_with_instance(ssao)
+            .map_err(|err| RenderError::Technique {
+                message: format!("Failed to register {}: {:?}", SSAO_TECHNIQUE, err),
+            })?;
+        Ok(render_ssao.into_system().into())
+    }
+
+    fn destroy(&mut self, world: &mut World) {
+        world.resources.unregister::<Ssao>();
+    }
+}
@@ -0,0 +1,6 @@
+mod temporal_jitter;
+pub use self::temporal_jitter::*;
+mod taa_targets;
+pub use self::taa_targets::*;
+mod taa_technique;
+pub use self::taa_technique::*;
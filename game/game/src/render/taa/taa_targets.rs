@@ -0,0 +1,143 @@
+use crate::{assets::PipelineStateDescriptor, render::FrameTarget};
+
+/// Format of the velocity target, screen space motion of the pixels in NDC
+pub const TAA_VELOCITY_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rg16Float;
+/// Format of the history targets
+pub const TAA_HISTORY_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+struct Target {
+    _texture: wgpu::Texture,
+    view: wgpu::TextureView,
+}
+
+impl Target {
+    fn new(device: &wgpu::Device, label: &str, size: (u32, u32), format: wgpu::TextureFormat) -> Target {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: size.0,
+                height: size.1,
+                depth: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT | wgpu::TextureUsage::SAMPLED,
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Target {
+            _texture: texture,
+            view,
+        }
+    }
+}
+
+/// The off-screen targets of the temporal anti-aliasing: the color and the velocity written by the main
+/// pass and two history targets used in a ping-pong fashion. The targets follow the size and the format of
+/// the frame, when they are recreated the history is invalidated.
+pub struct TaaTargets {
+    size: (u32, u32),
+    format: wgpu::TextureFormat,
+    color: Target,
+    velocity: Target,
+    history: [Target; 2],
+    /// Index of the history written in the current frame
+    current: usize,
+    /// If the history contains a valid frame
+    history_valid: bool,
+}
+
+impl TaaTargets {
+    pub fn new(device: &wgpu::Device, size: (u32, u32), format: wgpu::TextureFormat) -> TaaTargets {
+        log::debug!("Creating TAA targets with size {:?}, format {:?}", size, format);
+        TaaTargets {
+            size,
+            format,
+            color: Target::new(device, "taa color", size, format),
+            velocity: Target::new(device, "taa velocity", size, TAA_VELOCITY_FORMAT),
+            history: [
+                Target::new(device, "taa history 0", size, TAA_HISTORY_FORMAT),
+                Target::new(device, "taa history 1", size, TAA_HISTORY_FORMAT),
+            ],
+            current: 0,
+            history_valid: false,
+        }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// Format of the frame the targets were created for
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    /// The jittered frame rendered by the main pass instead of the frame
+    pub fn color_view(&self) -> &wgpu::TextureView {
+        &self.color.view
+    }
+
+    pub fn velocity_view(&self) -> &wgpu::TextureView {
+        &self.velocity.view
+    }
+
+    /// The history resolved in the previous frame
+    pub fn history_view(&self) -> &wgpu::TextureView {
+        &self.history[1 - self.current].view
+    }
+
+    /// The history to resolve the current frame into
+    pub fn resolve_view(&self) -> &wgpu::TextureView {
+        &self.history[self.current].view
+    }
+
+    /// If the previous frame can be reprojected, false after a resize or a reset
+    pub fn is_history_valid(&self) -> bool {
+        self.history_valid
+    }
+
+    /// Swap the history targets at the end of the frame
+    pub fn swap(&mut self) {
+        self.current = 1 - self.current;
+        self.history_valid = true;
+    }
+
+    /// Drop the history, ex. on a camera cut
+    pub fn invalidate(&mut self) {
+        self.history_valid = false;
+    }
+
+    /// Render states of the resolve pass: the frame color and the history
+    pub fn get_resolve_render_states(target: &FrameTarget) -> PipelineStateDescriptor {
+        let mut states = target.get_render_states();
+        if states.color_states.is_empty() {
+            // no frame
+            return states;
+        }
+        states.color_states.push(wgpu::ColorStateDescriptor {
+            format: TAA_HISTORY_FORMAT,
+            alpha_blend: wgpu::BlendDescriptor::REPLACE,
+            color_blend: wgpu::BlendDescriptor::REPLACE,
+            write_mask: wgpu::ColorWrite::ALL,
+        });
+        states
+    }
+
+    /// Render states of the main pass: the color in the format of the frame and the velocity
+    pub fn get_render_states(target: &FrameTarget) -> PipelineStateDescriptor {
+        let mut states = target.get_render_states();
+        if states.color_states.is_empty() {
+            // no frame
+            return states;
+        }
+        states.color_states.push(wgpu::ColorStateDescriptor {
+            format: TAA_VELOCITY_FORMAT,
+            alpha_blend: wgpu::BlendDescriptor::REPLACE,
+            color_blend: wgpu::BlendDescriptor::REPLACE,
+            write_mask: wgpu::ColorWrite::RED | wgpu::ColorWrite::GREEN,
+        });
+        states
+    }
+}
@@ -0,0 +1,348 @@
+use crate::{
+    assets::{vertex, PipelineStateDescriptor, TextureSemantic, Uniform, UniformSemantic},
+    render::{
+        create_target_sampler, create_uniform_buffer, draw_full_screen, Context, FrameTarget, FullScreenTarget,
+        MissingBinding, Pipeline, PipelineKey, RenderError, RenderTechnique, TaaTargets, TechniqueConfig,
+        TemporalJitter,
+    },
+    World,
+};
+use shine_ecs::{
+    resources::{Res, ResMut, ResourceId, Resources},
+    scheduler::{IntoSystem, ResourceClaims, System, SystemName, Task, TaskGroup, TaskItem},
+    ECSError,
+};
+use std::sync::Arc;
+
+/// Name of the technique in the registry
+pub const TAA_TECHNIQUE: &str = "taa";
+
+/// Name of the uniform buffer of the resolve pipeline
+pub const TAA_UNIFORM: &str = "taa";
+/// Name of the jittered color of the current frame in the resolve pipeline
+pub const TAA_COLOR_TEXTURE: &str = "color";
+/// Name of the history of the previous frame in the resolve pipeline
+pub const TAA_HISTORY_TEXTURE: &str = "history";
+/// Name of the velocity in the resolve pipeline
+pub const TAA_VELOCITY_TEXTURE: &str = "velocity";
+
+/// Parameters of the history blending and rejection
+#[derive(Debug, Clone)]
+pub struct TaaSettings {
+    /// Number of the jitter samples
+    pub sample_count: u32,
+    /// Weight of the history for fast moving pixels
+    pub feedback_min: f32,
+    /// Weight of the history for static pixels
+    pub feedback_max: f32,
+    /// Velocity in pixels where the history weight reaches feedback_min
+    pub velocity_rejection: f32,
+}
+
+impl Default for TaaSettings {
+    fn default() -> TaaSettings {
+        TaaSettings {
+            sample_count: 8,
+            feedback_min: 0.88,
+            feedback_max: 0.97,
+            velocity_rejection: 16.,
+        }
+    }
+}
+
+impl TaaSettings {
+    /// Weight of the history for a pixel moving with the given velocity (in pixels).
+    /// Fast moving pixels rely on the history less to reduce ghosting.
+    pub fn history_feedback(&self, velocity: f32) -> f32 {
+        let t = (velocity / self.velocity_rejection.max(1e-5)).max(0.).min(1.);
+        self.feedback_max + (self.feedback_min - self.feedback_max) * t
+    }
+
+    /// Clamp the reprojected history color into the bounding box of the current 3x3 neighborhood to reject
+    /// the stale (disoccluded or changed) samples.
+    pub fn clamp_history(history: [f32; 3], min: [f32; 3], max: [f32; 3]) -> [f32; 3] {
+        [
+            history[0].max(min[0]).min(max[0]),
+            history[1].max(min[1]).min(max[1]),
+            history[2].max(min[2]).min(max[2]),
+        ]
+    }
+}
+
+/// Uniform buffer layout of the TAA resolve shader
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct TaaUniform {
+    /// Current (xy) and previous (zw) jitter in NDC
+    pub jitter: [f32; 4],
+    /// Minimum and maximum feedback, velocity rejection and a flag (1 if the history is valid)
+    pub feedback: [f32; 4],
+}
+
+unsafe impl bytemuck::Pod for TaaUniform {}
+unsafe impl bytemuck::Zeroable for TaaUniform {}
+
+impl Uniform for TaaUniform {}
+
+impl TaaUniform {
+    pub fn new(settings: &TaaSettings, jitter: &TemporalJitter, history_valid: bool) -> TaaUniform {
+        let (current, previous) = (jitter.current(), jitter.previous());
+        TaaUniform {
+            jitter: [current[0], current[1], previous[0], previous[1]],
+            feedback: [
+                settings.feedback_min,
+                settings.feedback_max,
+                settings.velocity_rejection,
+                if history_valid { 1. } else { 0. },
+            ],
+        }
+    }
+}
+
+/// Resolve pass of the temporal anti-aliasing blending the current frame with the reprojected history.
+/// The result is written into the frame and into the history of the next frame.
+pub struct TaaResolvePass {
+    pipeline_key: PipelineKey,
+    uniform: Option<TaaUniform>,
+    sampler: Option<wgpu::Sampler>,
+    missing_binding: MissingBinding,
+    resource_claims: Option<Result<ResourceClaims, ECSError>>,
+}
+
+impl TaaResolvePass {
+    pub fn new(pipeline: String) -> TaaResolvePass {
+        TaaResolvePass {
+            pipeline_key: PipelineKey::new::<vertex::Null>(pipeline, Default::default()),
+            uniform: None,
+            sampler: None,
+            missing_binding: MissingBinding::default(),
+            resource_claims: None,
+        }
+    }
+
+    /// Uniform computed for the last frame
+    pub fn uniform(&self) -> Option<&TaaUniform> {
+        self.uniform.as_ref()
+    }
+
+    pub fn set_render_state(&mut self, target: &FrameTarget) {
+        let pipeline_states = TaaTargets::get_resolve_render_states(target);
+        if self.pipeline_key.render_state != pipeline_states {
+            self.pipeline_key.render_state = pipeline_states;
+            self.resource_claims = None;
+        }
+    }
+}
+
+impl System for TaaResolvePass {
+    fn debug_name(&self) -> &str {
+        "TaaResolvePass"
+    }
+
+    fn name(&self) -> Option<&SystemName> {
+        None
+    }
+
+    fn resource_claims(&mut self) -> Result<&ResourceClaims, ECSError> {
+        let pipeline_key = &self.pipeline_key;
+        self.resource_claims
+            .get_or_insert_with(|| {
+                let mut claims = ResourceClaims::default();
+                claims.add_immutable::<Pipeline, _>(Some(ResourceId::from_object(pipeline_key)?));
+                claims.add_mutable::<Taa, _>(Some(ResourceId::Global));
+                claims.add_immutable::<Context, _>(Some(ResourceId::Global));
+                claims.add_immutable::<FrameTarget, _>(Some(ResourceId::Global));
+                Ok(claims)
+            })
+            .as_ref()
+            .map_err(|err| err.clone())
+    }
+
+    fn run(&mut self, resources: &Resources) -> Result<TaskGroup, ECSError> {
+        let mut taa = resources.get_mut::<Taa>()?;
+        let context = resources.get::<Context>()?;
+        let target = resources.get::<FrameTarget>()?;
+        let pipeline = resources.get_with_id::<Pipeline>(&ResourceId::from_object(&self.pipeline_key)?)?;
+        let (view, targets, pipeline, uniform) = match (
+            target.view(),
+            taa.targets.as_mut(),
+            pipeline.pipeline_module(),
+            self.uniform.as_ref(),
+        ) {
+            (Some(view), Some(targets), Some(pipeline), Some(uniform)) => (view, targets, pipeline, uniform),
+            _ => return Ok(TaskGroup::default()),
+        };
+
+        let device = context.device();
+        let uniform = create_uniform_buffer(&device, "taa", uniform);
+        let sampler = &*self
+            .sampler
+            .get_or_insert_with(|| create_target_sampler(&device, "taa resolve"));
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("taa resolve"),
+        });
+        let result = draw_full_screen(
+            &device,
+            &mut encoder,
+            pipeline,
+            &[
+                FullScreenTarget::load(view),
+                FullScreenTarget::load(targets.resolve_view()),
+            ],
+            |semantic| match semantic {
+                UniformSemantic::UniformBuffer(name) if name.as_str() == TAA_UNIFORM => {
+                    Some(wgpu::BindingResource::Buffer(uniform.slice(..)))
+                }
+                UniformSemantic::Texture(TextureSemantic::Frame(name)) => match name.as_str() {
+                    TAA_COLOR_TEXTURE => Some(wgpu::BindingResource::TextureView(targets.color_view())),
+                    TAA_HISTORY_TEXTURE => Some(wgpu::BindingResource::TextureView(targets.history_view())),
+                    TAA_VELOCITY_TEXTURE => Some(wgpu::BindingResource::TextureView(targets.velocity_view())),
+                    _ => None,
+                },
+                UniformSemantic::Sampler(_) => Some(wgpu::BindingResource::Sampler(sampler)),
+                _ => None,
+            },
+        );
+        if result.is_ok() {
+            context.add_command(encoder.finish());
+            // the resolved frame is the history of the next frame
+            targets.swap();
+        }
+        self.missing_binding.update("TaaResolvePass", result);
+        Ok(TaskGroup::default())
+    }
+}
+
+/// Resource of the TAA technique
+pub struct Taa {
+    settings: TaaSettings,
+    jitter: TemporalJitter,
+    targets: Option<TaaTargets>,
+    resolve: Arc<Task<TaaResolvePass>>,
+}
+
+impl Taa {
+    pub fn settings(&self) -> &TaaSettings {
+        &self.settings
+    }
+
+    /// The jitter of the current frame, it shall be applied to the camera uniform
+    pub fn jitter(&self) -> &TemporalJitter {
+        &self.jitter
+    }
+
+    pub fn targets(&self) -> Option<&TaaTargets> {
+        self.targets.as_ref()
+    }
+
+    /// Drop the history, ex. on a camera cut
+    pub fn reset(&mut self) {
+        self.jitter.reset();
+        if let Some(targets) = &mut self.targets {
+            targets.invalidate();
+        }
+    }
+
+    /// Render states of the main pass writing the velocity too
+    pub fn get_render_states(&self, target: &FrameTarget) -> PipelineStateDescriptor {
+        TaaTargets::get_render_states(target)
+    }
+
+    fn update_targets(&mut self, device: &wgpu::Device, size: (u32, u32), format: Option<wgpu::TextureFormat>) {
+        let format = match format {
+            Some(format) if size.0 > 0 && size.1 > 0 => format,
+            _ => return,
+        };
+        if self.targets.as_ref().map(|targets| (targets.size(), targets.format())) != Some((size, format)) {
+            self.targets = Some(TaaTargets::new(device, size, format));
+        }
+    }
+}
+
+fn render_taa(mut taa: ResMut<Taa>, target: Res<FrameTarget>, context: Res<Context>) -> Result<TaskGroup, ECSError> {
+    let size = target.size();
+    taa.update_targets(&context.device(), size, target.descriptor().map(|d| d.format));
+    taa.jitter.advance(size);
+
+    let history_valid = taa
+        .targets
+        .as_ref()
+        .map(|targets| targets.is_history_valid())
+        .unwrap_or(false);
+    let uniform = TaaUniform::new(&taa.settings, &taa.jitter, history_valid);
+    {
+        let mut resolve = taa.resolve.system()?;
+        resolve.set_render_state(&target);
+        resolve.uniform = Some(uniform);
+    }
+    Ok(TaskGroup::from_task(taa.resolve.clone()))
+}
+
+/// Render technique of the temporal anti-aliasing. The main pass shall use the jittered camera and the
+/// render states of the Taa resource to output the color and the velocity into the targets of the Taa
+/// resource, the resolve pass writes the frame.
+/// Options:
+/// - pipeline: the cooked pipeline of the resolve pass. It is bound with the TaaUniform as the "taa"
+///   uniform buffer and the "color", "history" and "velocity" frame textures.
+/// - samples: number of the jitter samples (default: 8)
+/// - feedback_min, feedback_max: weight of the history for moving and static pixels (default: 0.88, 0.97)
+/// - velocity_rejection: velocity in pixels where the history weight reaches feedback_min (default: 16)
+pub struct TaaTechnique {
+    pipeline: String,
+    settings: TaaSettings,
+}
+
+impl TaaTechnique {
+    pub fn from_config(config: &TechniqueConfig) -> Result<TaaTechnique, RenderError> {
+        let pipeline = config.option("pipeline").ok_or_else(|| RenderError::Technique {
+            message: format!("Missing pipeline for {}", TAA_TECHNIQUE),
+        })?;
+
+        let default = TaaSettings::default();
+        let settings = TaaSettings {
+            sample_count: config.parse_option("samples", default.sample_count)?,
+            feedback_min: config.parse_option("feedback_min", default.feedback_min)?,
+            feedback_max: config.parse_option("feedback_max", default.feedback_max)?,
+            velocity_rejection: config.parse_option("velocity_rejection", default.velocity_rejection)?,
+        };
+        if settings.sample_count == 0
+            || !(0. ..=1.).contains(&settings.feedback_min)
+            || !(0. ..=1.).contains(&settings.feedback_max)
+        {
+            return Err(RenderError::Technique {
+                message: format!("Invalid settings for {}: {:?}", TAA_TECHNIQUE, settings),
+            });
+        }
+
+        Ok(TaaTechnique {
+            pipeline: pipeline.to_owned(),
+            settings,
+        })
+    }
+
+    pub fn settings(&self) -> &TaaSettings {
+        &self.settings
+    }
+}
+
+impl RenderTechnique for TaaTechnique {
+    fn create(&mut self, world: &mut World) -> Result<TaskItem, RenderError> {
+        let taa = Taa {
+            settings: self.settings.clone(),
+            jitter: TemporalJitter::new(self.settings.sample_count),
+            targets: None,
+            resolve: Task::new(TaaResolvePass::new(self.pipeline.clone())),
+        };
+        world
+            .resources
+            .register_with_instance(taa)
+            .map_err(|err| RenderError::Technique {
+                message: format!("Failed to register {}: {:?}", TAA_TECHNIQUE, err),
+            })?;
+        Ok(render_taa.into_system().into())
+    }
+
+    fn destroy(&mut self, world: &mut World) {
+        world.resources.unregister::<Taa>();
+    }
+}
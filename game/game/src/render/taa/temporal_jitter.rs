@@ -0,0 +1,113 @@
+use nalgebra::{Matrix4, Vector3};
+
+/// Element of the Halton low discrepancy sequence, index starts from 1
+pub fn halton(mut index: u32, base: u32) -> f32 {
+    let mut f = 1.;
+    let mut r = 0.;
+    while index > 0 {
+        f /= base as f32;
+        r += f * (index % base) as f32;
+        index /= base;
+    }
+    r
+}
+
+/// Sub-pixel camera jitter of the temporal anti-aliasing following the Halton(2,3) sequence.
+#[derive(Debug, Clone)]
+pub struct TemporalJitter {
+    sample_count: u32,
+    frame_index: u32,
+    /// Jitter of the current frame in NDC
+    current: [f32; 2],
+    /// Jitter of the previous frame in NDC
+    previous: [f32; 2],
+}
+
+impl TemporalJitter {
+    pub fn new(sample_count: u32) -> TemporalJitter {
+        TemporalJitter {
+            sample_count: sample_count.max(1),
+            frame_index: 0,
+            current: [0., 0.],
+            previous: [0., 0.],
+        }
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    /// Jitter of the given sample in pixels, in the [-0.5, 0.5) range
+    pub fn sample_offset(&self, index: u32) -> [f32; 2] {
+        let index = index % self.sample_count + 1;
+        [halton(index, 2) - 0.5, halton(index, 3) - 0.5]
+    }
+
+    /// Step to the next sample for a frame of the given size
+    pub fn advance(&mut self, size: (u32, u32)) {
+        let offset = self.sample_offset(self.frame_index);
+        self.frame_index = (self.frame_index + 1) % self.sample_count;
+        self.previous = self.current;
+        self.current = if size.0 > 0 && size.1 > 0 {
+            [2. * offset[0] / size.0 as f32, 2. * offset[1] / size.1 as f32]
+        } else {
+            [0., 0.]
+        };
+    }
+
+    /// Remove the jitter history, ex. on a camera cut
+    pub fn reset(&mut self) {
+        self.frame_index = 0;
+        self.current = [0., 0.];
+        self.previous = [0., 0.];
+    }
+
+    pub fn current(&self) -> [f32; 2] {
+        self.current
+    }
+
+    pub fn previous(&self) -> [f32; 2] {
+        self.previous
+    }
+
+    /// Apply the jitter of the current frame to a projection matrix. The offset is applied in clip space,
+    /// thus it works both for the perspective and orthographic projections.
+    pub fn jitter_projection(&self, projection: &Matrix4<f32>) -> Matrix4<f32> {
+        Matrix4::new_translation(&Vector3::new(self.current[0], self.current[1], 0.)) * projection
+    }
+}
+
+/// Uniform buffer layout of the camera with the temporal jitter
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct CameraUniform {
+    /// Jittered view projection matrix used for rasterization
+    pub view_proj: [f32; 16],
+    /// View projection matrix of the previous frame without jitter, used to compute the velocity
+    pub prev_view_proj: [f32; 16],
+    /// Current (xy) and previous (zw) jitter in NDC
+    pub jitter: [f32; 4],
+}
+
+unsafe impl bytemuck::Pod for CameraUniform {}
+unsafe impl bytemuck::Zeroable for CameraUniform {}
+
+impl CameraUniform {
+    pub fn new(view_proj: &Matrix4<f32>, prev_view_proj: &Matrix4<f32>, jitter: &TemporalJitter) -> CameraUniform {
+        let mut uniform = CameraUniform {
+            view_proj: [0.; 16],
+            prev_view_proj: [0.; 16],
+            jitter: [
+                jitter.current[0],
+                jitter.current[1],
+                jitter.previous[0],
+                jitter.previous[1],
+            ],
+        };
+        uniform
+            .view_proj
+            .copy_from_slice(jitter.jitter_projection(view_proj).as_slice());
+        uniform.prev_view_proj.copy_from_slice(prev_view_proj.as_slice());
+        uniform
+    }
+}
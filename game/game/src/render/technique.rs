@@ -0,0 +1,164 @@
+use crate::{render::RenderError, World};
+use serde::{Deserialize, Serialize};
+use shine_ecs::scheduler::{TaskGroup, TaskItem};
+use std::{collections::HashMap, str::FromStr};
+
+/// Name of the stage the render techniques are composed into
+pub const RENDER_STAGE: &str = "render";
+
+/// Selection and parameterization of a render technique as given by the game config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TechniqueConfig {
+    /// Name of the registered technique
+    pub technique: String,
+    /// Technique specific options (ex. quality settings)
+    #[serde(default)]
+    pub options: HashMap<String, String>,
+}
+
+impl TechniqueConfig {
+    pub fn new<S: ToString>(technique: S) -> TechniqueConfig {
+        TechniqueConfig {
+            technique: technique.to_string(),
+            options: HashMap::default(),
+        }
+    }
+
+    pub fn with_option<K: ToString, V: ToString>(mut self, key: K, value: V) -> TechniqueConfig {
+        self.options.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    pub fn option(&self, key: &str) -> Option<&str> {
+        self.options.get(key).map(|v| v.as_str())
+    }
+
+    /// Parse an option, if the option is missing the default value is returned.
+    pub fn parse_option<T: FromStr>(&self, key: &str, default: T) -> Result<T, RenderError> {
+        match self.options.get(key) {
+            None => Ok(default),
+            Some(value) => value.parse::<T>().map_err(|_| RenderError::Technique {
+                message: format!("Invalid value for option {} of {}: {}", key, self.technique, value),
+            }),
+        }
+    }
+}
+
+/// An instance of a render technique contributing a pass to the render stage.
+pub trait RenderTechnique: 'static + Send + Sync {
+    /// Create the resources of the technique and return the task rendering its pass.
+    fn create(&mut self, world: &mut World) -> Result<TaskItem, RenderError>;
+
+    /// Release the resources of the technique.
+    fn destroy(&mut self, world: &mut World);
+}
+
+type TechniqueFactory = Box<dyn Fn(&TechniqueConfig) -> Result<Box<dyn RenderTechnique>, RenderError> + Send + Sync>;
+
+/// Registry of the available render techniques by name.
+#[derive(Default)]
+pub struct TechniqueRegistry {
+    factories: HashMap<String, TechniqueFactory>,
+}
+
+impl TechniqueRegistry {
+    /// Register a technique. If a technique with the same name is already present it is replaced.
+    pub fn register<S, F>(&mut self, name: S, factory: F)
+    where
+        S: ToString,
+        F: 'static + Fn(&TechniqueConfig) -> Result<Box<dyn RenderTechnique>, RenderError> + Send + Sync,
+    {
+        let name = name.to_string();
+        if self.factories.insert(name.clone(), Box::new(factory)).is_some() {
+            log::warn!("Render technique {} replaced", name);
+        }
+    }
+
+    pub fn unregister(&mut self, name: &str) {
+        let _ = self.factories.remove(name);
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.factories.keys().map(|name| name.as_str())
+    }
+
+    /// Instantiate the techniques in the order of the configuration.
+    pub fn build(&self, configs: &[TechniqueConfig]) -> Result<Vec<Box<dyn RenderTechnique>>, RenderError> {
+        configs
+            .iter()
+            .map(|config| {
+                let factory = self
+                    .factories
+                    .get(&config.technique)
+                    .ok_or_else(|| RenderError::Technique {
+                        message: format!("Unknown render technique: {}", config.technique),
+                    })?;
+                factory(config)
+            })
+            .collect()
+    }
+}
+
+/// The techniques composing the render stage in pass order.
+#[derive(Default)]
+pub struct ActiveTechniques {
+    techniques: Vec<Box<dyn RenderTechnique>>,
+}
+
+impl ActiveTechniques {
+    /// Instantiate the selected techniques and compose the render stage from their passes.
+    pub fn create_render_stage(world: &mut World, configs: &[TechniqueConfig]) -> Result<(), RenderError> {
+        Self::destroy_render_stage(world);
+
+        let techniques = world
+            .resources
+            .get::<TechniqueRegistry>()
+            .map_err(|err| RenderError::Technique {
+                message: format!("Technique registry not found: {:?}", err),
+            })?
+            .build(configs)?;
+
+        let mut tasks = TaskGroup::default();
+        let mut created = Vec::with_capacity(techniques.len());
+        for mut technique in techniques {
+            match technique.create(world) {
+                Ok(task) => {
+                    tasks.add_task(task);
+                    created.push(technique);
+                }
+                Err(err) => {
+                    for mut technique in created.into_iter().rev() {
+                        technique.destroy(world);
+                    }
+                    return Err(err);
+                }
+            }
+        }
+
+        world.add_stage(RENDER_STAGE, tasks);
+        world
+            .resources
+            .register_with_instance(ActiveTechniques { techniques: created })
+            .map_err(|err| RenderError::Technique {
+                message: format!("Failed to register techniques: {:?}", err),
+            })
+    }
+
+    /// Remove the render stage and release the active techniques.
+    pub fn destroy_render_stage(world: &mut World) {
+        let techniques = match world.resources.get_mut::<ActiveTechniques>() {
+            Ok(mut active) => std::mem::take(&mut active.techniques),
+            Err(_) => return,
+        };
+
+        world.remove_stage(RENDER_STAGE);
+        for mut technique in techniques.into_iter().rev() {
+            technique.destroy(world);
+        }
+        world.resources.unregister::<ActiveTechniques>();
+    }
+}
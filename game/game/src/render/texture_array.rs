@@ -0,0 +1,299 @@
+use crate::render::GpuCapabilities;
+use std::{collections::HashMap, fmt, num::NonZeroU32};
+
+/// Upper bound of the textures packed into a single binding array
+pub const MAX_TEXTURE_ARRAY_SIZE: u32 = 256;
+
+/// How the material textures are bound
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextureBindingMode {
+    /// Each material has its own bind group
+    PerMaterial,
+    /// The textures are packed into binding arrays and addressed by per-instance indices
+    BindingArray { array_size: u32 },
+}
+
+impl TextureBindingMode {
+    /// Select the binding mode based on the granted capabilities
+    pub fn select(capabilities: &GpuCapabilities) -> TextureBindingMode {
+        if capabilities.texture_binding_array() {
+            // leave some room for the non-material textures of the stage
+            let array_size = (capabilities.limits().max_sampled_textures_per_shader_stage / 2)
+                .min(MAX_TEXTURE_ARRAY_SIZE)
+                .max(1);
+            TextureBindingMode::BindingArray { array_size }
+        } else {
+            TextureBindingMode::PerMaterial
+        }
+    }
+
+    pub fn is_binding_array(&self) -> bool {
+        matches!(self, TextureBindingMode::BindingArray { .. })
+    }
+}
+
+/// Location of a texture in the packed arrays. The page selects the bind group, the index is the per-instance
+/// index used by the shader.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextureSlot {
+    pub page: u32,
+    pub index: u32,
+}
+
+#[derive(Default)]
+struct TexturePage {
+    textures: Vec<Option<String>>,
+    free: Vec<u32>,
+    dirty: bool,
+}
+
+/// Statistics of the packed texture arrays
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TextureArrayStats {
+    pub pages: usize,
+    pub textures: usize,
+    pub free_slots: usize,
+}
+
+impl fmt::Display for TextureArrayStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "pages: {}, textures: {}, free slots: {}",
+            self.pages, self.textures, self.free_slots
+        )
+    }
+}
+
+/// Assign the material textures to the slots of the binding arrays. Textures are reference counted, thus
+/// materials sharing a texture also share the slot. The draw calls can be grouped by page, as all the
+/// materials of a page are served by a single bind group.
+pub struct TextureArrayPacker {
+    array_size: u32,
+    pages: Vec<TexturePage>,
+    slots: HashMap<String, (TextureSlot, usize)>,
+}
+
+impl TextureArrayPacker {
+    pub fn new(array_size: u32) -> TextureArrayPacker {
+        assert!(array_size > 0);
+        TextureArrayPacker {
+            array_size,
+            pages: Vec::new(),
+            slots: HashMap::new(),
+        }
+    }
+
+    pub fn array_size(&self) -> u32 {
+        self.array_size
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Get the slot of a texture
+    pub fn slot(&self, texture_id: &str) -> Option<TextureSlot> {
+        self.slots.get(texture_id).map(|(slot, _)| *slot)
+    }
+
+    /// Get the texture ids of a page in slot order, None for the free slots
+    pub fn page_textures(&self, page: u32) -> &[Option<String>] {
+        self.pages
+            .get(page as usize)
+            .map(|page| &page.textures[..])
+            .unwrap_or(&[])
+    }
+
+    /// Add a reference to a texture and return its slot. A new page is started when all the pages are full.
+    pub fn acquire(&mut self, texture_id: &str) -> TextureSlot {
+        if let Some((slot, count)) = self.slots.get_mut(texture_id) {
+            *count += 1;
+            return *slot;
+        }
+
+        let slot = self.allocate_slot();
+        let page = &mut self.pages[slot.page as usize];
+        page.textures[slot.index as usize] = Some(texture_id.to_owned());
+        page.dirty = true;
+        self.slots.insert(texture_id.to_owned(), (slot, 1));
+        slot
+    }
+
+    /// Remove a reference to a texture, the slot is freed when the last reference is released.
+    pub fn release(&mut self, texture_id: &str) {
+        let slot = match self.slots.get_mut(texture_id) {
+            Some((_, count)) if *count > 1 => {
+                *count -= 1;
+                return;
+            }
+            Some((slot, _)) => *slot,
+            None => return,
+        };
+
+        self.slots.remove(texture_id);
+        let page = &mut self.pages[slot.page as usize];
+        page.textures[slot.index as usize] = None;
+        page.free.push(slot.index);
+        page.dirty = true;
+    }
+
+    fn allocate_slot(&mut self) -> TextureSlot {
+        for (page_id, page) in self.pages.iter_mut().enumerate() {
+            if let Some(index) = page.free.pop() {
+                return TextureSlot {
+                    page: page_id as u32,
+                    index,
+                };
+            }
+            if (page.textures.len() as u32) < self.array_size {
+                page.textures.push(None);
+                return TextureSlot {
+                    page: page_id as u32,
+                    index: page.textures.len() as u32 - 1,
+                };
+            }
+        }
+
+        self.pages.push(TexturePage {
+            textures: vec![None],
+            free: Vec::new(),
+            dirty: true,
+        });
+        TextureSlot {
+            page: self.pages.len() as u32 - 1,
+            index: 0,
+        }
+    }
+
+    /// Return the pages modified since the last call, their bind groups have to be recreated.
+    pub fn take_dirty_pages(&mut self) -> Vec<u32> {
+        self.pages
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(id, page)| {
+                if page.dirty {
+                    page.dirty = false;
+                    Some(id as u32)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    pub fn stats(&self) -> TextureArrayStats {
+        let capacity = self.pages.len() * self.array_size as usize;
+        TextureArrayStats {
+            pages: self.pages.len(),
+            textures: self.slots.len(),
+            free_slots: capacity - self.slots.len(),
+        }
+    }
+}
+
+/// Bind groups of the packed texture arrays. The unused slots of a page are bound to a placeholder texture
+/// as a binding array has to be fully populated.
+pub struct CompiledTextureArray {
+    array_size: u32,
+    layout: wgpu::BindGroupLayout,
+    placeholder: wgpu::Texture,
+    bind_groups: Vec<Option<wgpu::BindGroup>>,
+}
+
+impl CompiledTextureArray {
+    pub fn new(device: &wgpu::Device, array_size: u32) -> CompiledTextureArray {
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("material texture array"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::SampledTexture {
+                        multisampled: false,
+                        dimension: wgpu::TextureViewDimension::D2,
+                        component_type: wgpu::TextureComponentType::Float,
+                    },
+                    count: NonZeroU32::new(array_size),
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler { comparison: false },
+                    count: None,
+                },
+            ],
+        });
+
+        let placeholder = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("texture array placeholder"),
+            size: wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsage::SAMPLED,
+        });
+
+        CompiledTextureArray {
+            array_size,
+            layout,
+            placeholder,
+            bind_groups: Vec::new(),
+        }
+    }
+
+    pub fn layout(&self) -> &wgpu::BindGroupLayout {
+        &self.layout
+    }
+
+    pub fn bind_group(&self, page: u32) -> Option<&wgpu::BindGroup> {
+        self.bind_groups.get(page as usize).and_then(|b| b.as_ref())
+    }
+
+    /// Recreate the bind group of a page. The textures are given in slot order, the missing ones are replaced by
+    /// the placeholder.
+    pub fn update_page(
+        &mut self,
+        device: &wgpu::Device,
+        page: u32,
+        textures: &[Option<&wgpu::Texture>],
+        sampler: &wgpu::Sampler,
+    ) {
+        let views: Vec<_> = (0..self.array_size as usize)
+            .map(|i| {
+                textures
+                    .get(i)
+                    .cloned()
+                    .flatten()
+                    .unwrap_or(&self.placeholder)
+                    .create_view(&wgpu::TextureViewDescriptor::default())
+            })
+            .collect();
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("material texture array"),
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureViewArray(&views),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        });
+
+        let page = page as usize;
+        if self.bind_groups.len() <= page {
+            self.bind_groups.resize_with(page + 1, || None);
+        }
+        self.bind_groups[page] = Some(bind_group);
+    }
+}
@@ -0,0 +1,4 @@
+mod oit_targets;
+pub use self::oit_targets::*;
+mod transparency_technique;
+pub use self::transparency_technique::*;
@@ -0,0 +1,152 @@
+use crate::assets::{ColorStage, PipelineStateDescriptor};
+use std::str::FromStr;
+
+/// Format of the accumulation target, it stores the weighted premultiplied color and the weighted alpha
+pub const OIT_ACCUMULATION_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+/// Format of the revealage target, it stores the product of (1 - alpha) of the transparent fragments
+pub const OIT_REVEALAGE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R16Float;
+
+/// Clear color of the accumulation target
+pub const OIT_ACCUMULATION_CLEAR: wgpu::Color = wgpu::Color::TRANSPARENT;
+/// Clear color of the revealage target, nothing is covered
+pub const OIT_REVEALAGE_CLEAR: wgpu::Color = wgpu::Color::WHITE;
+
+/// The method used to render the transparent queue
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransparencyMode {
+    /// Draws are sorted back-to-front and blended directly into the frame
+    Sorted,
+    /// Weighted blended order independent transparency. The draws are accumulated into off-screen
+    /// targets in any order and composed onto the frame by a resolve pass. It is an approximation, but
+    /// it has no sorting cost and handles the intersecting geometry of particles and foliage.
+    WeightedBlended,
+}
+
+impl Default for TransparencyMode {
+    fn default() -> Self {
+        TransparencyMode::Sorted
+    }
+}
+
+impl FromStr for TransparencyMode {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "sorted" => Ok(TransparencyMode::Sorted),
+            "weighted_blended" | "oit" => Ok(TransparencyMode::WeightedBlended),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Weight of a fragment for the accumulation, it shall match the accumulation shaders.
+/// (Equation 10 of McGuire and Bavoil: Weighted Blended Order-Independent Transparency)
+pub fn oit_weight(depth: f32, alpha: f32) -> f32 {
+    let d = depth / 200.;
+    alpha * (0.03 / (1e-5 + d * d * d * d)).max(1e-2).min(3e3)
+}
+
+/// Compute the color and coverage of a pixel from the accumulated values as done by the resolve pass.
+/// The result is blended onto the frame with the classic alpha blending.
+pub fn oit_resolve(accumulation: [f32; 4], revealage: f32) -> [f32; 4] {
+    let weight = accumulation[3].max(1e-5);
+    [
+        accumulation[0] / weight,
+        accumulation[1] / weight,
+        accumulation[2] / weight,
+        1. - revealage,
+    ]
+}
+
+/// The off-screen targets of the weighted blended transparency.
+/// The targets follow the size of the frame and they are recreated when the frame is resized.
+pub struct OitTargets {
+    size: (u32, u32),
+    accumulation: wgpu::Texture,
+    accumulation_view: wgpu::TextureView,
+    revealage: wgpu::Texture,
+    revealage_view: wgpu::TextureView,
+}
+
+impl OitTargets {
+    fn create_target(
+        device: &wgpu::Device,
+        label: &str,
+        size: (u32, u32),
+        format: wgpu::TextureFormat,
+    ) -> (wgpu::Texture, wgpu::TextureView) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: size.0,
+                height: size.1,
+                depth: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT | wgpu::TextureUsage::SAMPLED,
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (texture, view)
+    }
+
+    pub fn new(device: &wgpu::Device, size: (u32, u32)) -> OitTargets {
+        log::debug!("Creating transparency targets with size {:?}", size);
+        let (accumulation, accumulation_view) =
+            Self::create_target(device, "oit accumulation", size, OIT_ACCUMULATION_FORMAT);
+        let (revealage, revealage_view) = Self::create_target(device, "oit revealage", size, OIT_REVEALAGE_FORMAT);
+        OitTargets {
+            size,
+            accumulation,
+            accumulation_view,
+            revealage,
+            revealage_view,
+        }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    pub fn accumulation(&self) -> &wgpu::Texture {
+        &self.accumulation
+    }
+
+    pub fn accumulation_view(&self) -> &wgpu::TextureView {
+        &self.accumulation_view
+    }
+
+    pub fn revealage(&self) -> &wgpu::Texture {
+        &self.revealage
+    }
+
+    pub fn revealage_view(&self) -> &wgpu::TextureView {
+        &self.revealage_view
+    }
+
+    /// Render states of the transparent draws writing the targets. The depth is tested against the
+    /// opaque geometry, but it is not written.
+    pub fn get_render_states(depth_state: Option<wgpu::DepthStencilStateDescriptor>) -> PipelineStateDescriptor {
+        PipelineStateDescriptor {
+            color_states: vec![
+                wgpu::ColorStateDescriptor {
+                    format: OIT_ACCUMULATION_FORMAT,
+                    alpha_blend: wgpu::BlendDescriptor::REPLACE,
+                    color_blend: wgpu::BlendDescriptor::REPLACE,
+                    write_mask: wgpu::ColorWrite::ALL,
+                },
+                wgpu::ColorStateDescriptor {
+                    format: OIT_REVEALAGE_FORMAT,
+                    alpha_blend: wgpu::BlendDescriptor::REPLACE,
+                    color_blend: wgpu::BlendDescriptor::REPLACE,
+                    write_mask: wgpu::ColorWrite::RED,
+                },
+            ],
+            depth_state,
+            color_stage: Some(ColorStage::WeightedBlended),
+        }
+    }
+}
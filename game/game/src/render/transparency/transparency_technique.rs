@@ -0,0 +1,222 @@
+use crate::{
+    assets::{vertex, ColorStage, PipelineStateDescriptor, TextureSemantic, UniformSemantic},
+    render::{
+        create_target_sampler, draw_full_screen, Context, FrameTarget, FullScreenTarget, MissingBinding, OitTargets,
+        Pipeline, PipelineKey, RenderError, RenderTechnique, TechniqueConfig, TransparencyMode,
+    },
+    World,
+};
+use shine_ecs::{
+    resources::{Res, ResMut, ResourceId, Resources},
+    scheduler::{IntoSystem, ResourceClaims, System, SystemName, Task, TaskGroup, TaskItem},
+    ECSError,
+};
+use std::sync::Arc;
+
+/// Name of the technique in the registry
+pub const TRANSPARENCY_TECHNIQUE: &str = "transparency";
+
+/// Name of the accumulation target in the resolve pipeline
+pub const OIT_ACCUMULATION_TEXTURE: &str = "accumulation";
+/// Name of the revealage target in the resolve pipeline
+pub const OIT_REVEALAGE_TEXTURE: &str = "revealage";
+
+/// Resolve pass of the weighted blended transparency composing the accumulated color onto the frame.
+pub struct OitResolvePass {
+    pipeline_key: PipelineKey,
+    sampler: Option<wgpu::Sampler>,
+    missing_binding: MissingBinding,
+    resource_claims: Option<Result<ResourceClaims, ECSError>>,
+}
+
+impl OitResolvePass {
+    pub fn new(pipeline: String) -> OitResolvePass {
+        OitResolvePass {
+            pipeline_key: PipelineKey::new::<vertex::Null>(pipeline, Default::default()),
+            sampler: None,
+            missing_binding: MissingBinding::default(),
+            resource_claims: None,
+        }
+    }
+
+    pub fn set_render_state(&mut self, target: &FrameTarget) {
+        let pipeline_states = target.get_render_states().with_color_stage(ColorStage::AlphaBlend);
+        if self.pipeline_key.render_state != pipeline_states {
+            self.pipeline_key.render_state = pipeline_states;
+            self.resource_claims = None;
+        }
+    }
+}
+
+impl System for OitResolvePass {
+    fn debug_name(&self) -> &str {
+        "OitResolvePass"
+    }
+
+    fn name(&self) -> Option<&SystemName> {
+        None
+    }
+
+    fn resource_claims(&mut self) -> Result<&ResourceClaims, ECSError> {
+        let pipeline_key = &self.pipeline_key;
+        self.resource_claims
+            .get_or_insert_with(|| {
+                let mut claims = ResourceClaims::default();
+                claims.add_immutable::<Pipeline, _>(Some(ResourceId::from_object(pipeline_key)?));
+                claims.add_immutable::<Transparency, _>(Some(ResourceId::Global));
+                claims.add_immutable::<Context, _>(Some(ResourceId::Global));
+                claims.add_immutable::<FrameTarget, _>(Some(ResourceId::Global));
+                Ok(claims)
+            })
+            .as_ref()
+            .map_err(|err| err.clone())
+    }
+
+    fn run(&mut self, resources: &Resources) -> Result<TaskGroup, ECSError> {
+        let transparency = resources.get::<Transparency>()?;
+        let context = resources.get::<Context>()?;
+        let target = resources.get::<FrameTarget>()?;
+        let pipeline = resources.get_with_id::<Pipeline>(&ResourceId::from_object(&self.pipeline_key)?)?;
+        let (view, targets, pipeline) = match (target.view(), transparency.targets(), pipeline.pipeline_module()) {
+            (Some(view), Some(targets), Some(pipeline)) => (view, targets, pipeline),
+            _ => return Ok(TaskGroup::default()),
+        };
+
+        let device = context.device();
+        let sampler = &*self
+            .sampler
+            .get_or_insert_with(|| create_target_sampler(&device, "oit resolve"));
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("oit resolve"),
+        });
+        let result = draw_full_screen(
+            &device,
+            &mut encoder,
+            pipeline,
+            &[FullScreenTarget::load(view)],
+            |semantic| match semantic {
+                UniformSemantic::Texture(TextureSemantic::Frame(name)) => match name.as_str() {
+                    OIT_ACCUMULATION_TEXTURE => Some(wgpu::BindingResource::TextureView(targets.accumulation_view())),
+                    OIT_REVEALAGE_TEXTURE => Some(wgpu::BindingResource::TextureView(targets.revealage_view())),
+                    _ => None,
+                },
+                UniformSemantic::Sampler(_) => Some(wgpu::BindingResource::Sampler(sampler)),
+                _ => None,
+            },
+        );
+        if result.is_ok() {
+            context.add_command(encoder.finish());
+        }
+        self.missing_binding.update("OitResolvePass", result);
+        Ok(TaskGroup::default())
+    }
+}
+
+/// Resource of the transparency technique
+pub struct Transparency {
+    mode: TransparencyMode,
+    targets: Option<OitTargets>,
+    resolve: Option<Arc<Task<OitResolvePass>>>,
+}
+
+impl Transparency {
+    pub fn mode(&self) -> TransparencyMode {
+        self.mode
+    }
+
+    /// The accumulation targets of the weighted blended transparency, available after the first frame.
+    pub fn targets(&self) -> Option<&OitTargets> {
+        self.targets.as_ref()
+    }
+
+    /// Render states of the draws in the transparent queue
+    pub fn get_render_states(&self, target: &FrameTarget) -> PipelineStateDescriptor {
+        match self.mode {
+            TransparencyMode::Sorted => target.get_render_states(),
+            TransparencyMode::WeightedBlended => OitTargets::get_render_states(target.get_render_states().depth_state),
+        }
+    }
+
+    fn update_targets(&mut self, device: &wgpu::Device, size: (u32, u32)) {
+        if size.0 == 0 || size.1 == 0 {
+            return;
+        }
+        if self.targets.as_ref().map(|targets| targets.size()) != Some(size) {
+            self.targets = Some(OitTargets::new(device, size));
+        }
+    }
+}
+
+fn render_transparency(
+    mut transparency: ResMut<Transparency>,
+    target: Res<FrameTarget>,
+    context: Res<Context>,
+) -> Result<TaskGroup, ECSError> {
+    if transparency.mode != TransparencyMode::WeightedBlended {
+        return Ok(TaskGroup::default());
+    }
+
+    transparency.update_targets(&context.device(), target.size());
+    match &transparency.resolve {
+        Some(resolve) => {
+            resolve.system()?.set_render_state(&target);
+            Ok(TaskGroup::from_task(resolve.clone()))
+        }
+        None => Ok(TaskGroup::default()),
+    }
+}
+
+/// Render technique of the transparent queue.
+/// Options:
+/// - mode: "sorted" (default) or "weighted_blended" for the order independent transparency
+/// - resolve_pipeline: the cooked pipeline composing the accumulated transparency, required by the
+///   weighted_blended mode. The targets are bound as the "accumulation" and "revealage" frame textures.
+pub struct TransparencyTechnique {
+    mode: TransparencyMode,
+    resolve_pipeline: Option<String>,
+}
+
+impl TransparencyTechnique {
+    pub fn from_config(config: &TechniqueConfig) -> Result<TransparencyTechnique, RenderError> {
+        let mode = config.parse_option("mode", TransparencyMode::default())?;
+        let resolve_pipeline = config.option("resolve_pipeline").map(|p| p.to_owned());
+        if mode == TransparencyMode::WeightedBlended && resolve_pipeline.is_none() {
+            return Err(RenderError::Technique {
+                message: format!("Missing resolve_pipeline for {}", TRANSPARENCY_TECHNIQUE),
+            });
+        }
+
+        Ok(TransparencyTechnique { mode, resolve_pipeline })
+    }
+
+    pub fn mode(&self) -> TransparencyMode {
+        self.mode
+    }
+}
+
+impl RenderTechnique for TransparencyTechnique {
+    fn create(&mut self, world: &mut World) -> Result<TaskItem, RenderError> {
+        let resolve = match self.mode {
+            TransparencyMode::Sorted => None,
+            TransparencyMode::WeightedBlended => self
+                .resolve_pipeline
+                .clone()
+                .map(|pipeline| Task::new(OitResolvePass::new(pipeline))),
+        };
+        world
+            .resources
+            .register_with_instance(Transparency {
+                mode: self.mode,
+                targets: None,
+                resolve,
+            })
+            .map_err(|err| RenderError::Technique {
+                message: format!("Failed to register {}: {:?}", TRANSPARENCY_TECHNIQUE, err),
+            })?;
+        Ok(render_transparency.into_system().into())
+    }
+
+    fn destroy(&mut self, world: &mut World) {
+        world.resources.unregister::<Transparency>();
+    }
+}
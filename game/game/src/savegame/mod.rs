@@ -0,0 +1,8 @@
+mod save_error;
+pub use self::save_error::*;
+mod save_metadata;
+pub use self::save_metadata::*;
+mod save_sync;
+pub use self::save_sync::*;
+mod save_data;
+pub use self::save_data::*;
@@ -0,0 +1,50 @@
+use crate::savegame::{SaveGameError, SaveMetadata};
+use serde::{Deserialize, Serialize};
+use shine_ecs::{
+    resources::Resources,
+    snapshot::{EntityMap, Snapshot, SnapshotFormat, SnapshotRegistry},
+};
+
+/// The content of a save: the metadata and the snapshot of the registered game state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SaveData {
+    pub metadata: SaveMetadata,
+    pub snapshot: Snapshot,
+}
+
+impl SaveData {
+    pub fn capture(
+        registry: &SnapshotRegistry,
+        resources: &Resources,
+        metadata: SaveMetadata,
+        format: SnapshotFormat,
+    ) -> Result<SaveData, SaveGameError> {
+        let snapshot = registry.snapshot(resources, format)?;
+        Ok(SaveData { metadata, snapshot })
+    }
+
+    pub fn restore(&self, registry: &SnapshotRegistry, resources: &mut Resources) -> Result<EntityMap, SaveGameError> {
+        Ok(registry.restore(resources, &self.snapshot)?)
+    }
+
+    /// Serialize the save in the format of the snapshot
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SaveGameError> {
+        match self.snapshot.format {
+            SnapshotFormat::Bincode => {
+                bincode::serialize(self).map_err(|err| SaveGameError::InvalidData(err.to_string()))
+            }
+            SnapshotFormat::Ron => ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+                .map(String::into_bytes)
+                .map_err(|err| SaveGameError::InvalidData(err.to_string())),
+        }
+    }
+
+    pub fn from_bytes(format: SnapshotFormat, data: &[u8]) -> Result<SaveData, SaveGameError> {
+        match format {
+            SnapshotFormat::Bincode => {
+                bincode::deserialize(data).map_err(|err| SaveGameError::InvalidData(err.to_string()))
+            }
+            SnapshotFormat::Ron => ron::de::from_bytes(data).map_err(|err| SaveGameError::InvalidData(err.to_string())),
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use shine_ecs::ECSError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SaveGameError {
+    #[error("No conflict for slot {0}")]
+    NoConflict(String),
+
+    #[error("Slot {0} is already in use")]
+    SlotInUse(String),
+
+    #[error("Invalid save data: {0}")]
+    InvalidData(String),
+
+    #[error("Save data error")]
+    Snapshot(#[from] ECSError),
+}
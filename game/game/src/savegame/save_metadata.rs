@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+
+/// Description of a save, presented to the player to choose between the conflicting versions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveMetadata {
+    pub slot: String,
+    /// Time of the save in milliseconds since the unix epoch
+    pub modified: u64,
+    /// Total playtime in seconds
+    pub playtime: u64,
+    /// Base64 encoded png thumbnail
+    #[serde(default)]
+    pub thumbnail: Option<String>,
+}
+
+/// Conflicting local and remote versions of a save. The remote version was uploaded by another
+/// device since the local version was downloaded (etag mismatch).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveConflict {
+    pub local: SaveMetadata,
+    pub remote: SaveMetadata,
+    /// Etag of the remote version
+    pub remote_etag: String,
+}
+
+impl SaveConflict {
+    pub fn slot(&self) -> &str {
+        &self.local.slot
+    }
+
+    /// Check if the local version is the more recent one
+    pub fn is_local_newer(&self) -> bool {
+        self.local.modified >= self.remote.modified
+    }
+}
@@ -0,0 +1,163 @@
+use crate::savegame::{SaveConflict, SaveGameError, SaveMetadata};
+use std::collections::{BTreeMap, HashMap};
+
+/// Precondition of an upload
+#[derive(Debug, Clone, PartialEq)]
+pub enum SaveCondition {
+    /// Upload only if the remote version is unchanged (If-Match)
+    IfMatch(String),
+    /// Upload only if the slot is empty (If-None-Match: *)
+    IfNotExists,
+    /// Overwrite the remote version unconditionally
+    Force,
+}
+
+/// An upload to be performed by the transport
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaveUpload {
+    pub metadata: SaveMetadata,
+    pub condition: SaveCondition,
+}
+
+impl SaveUpload {
+    pub fn slot(&self) -> &str {
+        &self.metadata.slot
+    }
+}
+
+/// Response of the storage for an upload
+#[derive(Debug, Clone, PartialEq)]
+pub enum SaveUploadOutcome {
+    Stored {
+        etag: String,
+    },
+    /// The precondition failed, the current remote version is returned
+    Conflict {
+        remote: SaveMetadata,
+        etag: String,
+    },
+}
+
+/// The choice of the player to resolve a conflict
+#[derive(Debug, Clone, PartialEq)]
+pub enum SaveResolution {
+    /// Overwrite the remote version with the local one
+    ForceOverwrite,
+    /// Drop the local version and keep the remote one
+    KeepRemote,
+    /// Keep both by uploading the local version into a new slot
+    DuplicateSlot(String),
+}
+
+/// Track the remote versions of the saves and the unresolved upload conflicts. The transport (http,
+/// local file, etc.) is not part of the tracking, it performs the uploads prepared here and reports the outcome.
+#[derive(Default, Debug)]
+pub struct SaveSync {
+    etags: HashMap<String, String>,
+    conflicts: BTreeMap<String, SaveConflict>,
+    new_conflicts: Vec<SaveConflict>,
+}
+
+impl SaveSync {
+    /// Record the etag of a downloaded save
+    pub fn set_etag(&mut self, slot: &str, etag: &str) {
+        self.etags.insert(slot.to_owned(), etag.to_owned());
+    }
+
+    pub fn etag(&self, slot: &str) -> Option<&str> {
+        self.etags.get(slot).map(|etag| etag.as_str())
+    }
+
+    /// Prepare the upload of a local save conditioned on the last known remote version
+    pub fn prepare_upload(&self, metadata: SaveMetadata) -> SaveUpload {
+        let condition = match self.etags.get(&metadata.slot) {
+            Some(etag) => SaveCondition::IfMatch(etag.clone()),
+            None => SaveCondition::IfNotExists,
+        };
+        SaveUpload { metadata, condition }
+    }
+
+    /// Process the outcome of an upload. On conflict the structured conflict data is returned
+    /// and kept until it is resolved.
+    pub fn on_upload_result(&mut self, upload: &SaveUpload, outcome: SaveUploadOutcome) -> Option<&SaveConflict> {
+        let slot = upload.slot().to_owned();
+        match outcome {
+            SaveUploadOutcome::Stored { etag } => {
+                log::info!("Save {} uploaded ({})", slot, etag);
+                self.etags.insert(slot.clone(), etag);
+                self.conflicts.remove(&slot);
+                None
+            }
+            SaveUploadOutcome::Conflict { remote, etag } => {
+                log::warn!("Save {} conflicts with the remote version ({})", slot, etag);
+                let conflict = SaveConflict {
+                    local: upload.metadata.clone(),
+                    remote,
+                    remote_etag: etag,
+                };
+                self.new_conflicts.push(conflict.clone());
+                self.conflicts.insert(slot.clone(), conflict);
+                self.conflicts.get(&slot)
+            }
+        }
+    }
+
+    pub fn has_conflicts(&self) -> bool {
+        !self.conflicts.is_empty()
+    }
+
+    pub fn conflict(&self, slot: &str) -> Option<&SaveConflict> {
+        self.conflicts.get(slot)
+    }
+
+    /// Iterate the unresolved conflicts ordered by the slot
+    pub fn conflicts(&self) -> impl Iterator<Item = &SaveConflict> {
+        self.conflicts.values()
+    }
+
+    /// Take the conflicts detected since the last call to notify the game
+    pub fn drain_new_conflicts(&mut self) -> Vec<SaveConflict> {
+        self.new_conflicts.drain(..).collect()
+    }
+
+    /// Resolve a conflict. The returned upload (if any) has to be performed by the transport and reported
+    /// by [on_upload_result](SaveSync::on_upload_result).
+    pub fn resolve(&mut self, slot: &str, resolution: SaveResolution) -> Result<Option<SaveUpload>, SaveGameError> {
+        let conflict = self
+            .conflicts
+            .get(slot)
+            .ok_or_else(|| SaveGameError::NoConflict(slot.to_owned()))?;
+        log::info!("Resolving save conflict of {} by {:?}", slot, resolution);
+
+        let upload = match resolution {
+            SaveResolution::ForceOverwrite => Some(SaveUpload {
+                metadata: conflict.local.clone(),
+                condition: SaveCondition::Force,
+            }),
+            SaveResolution::KeepRemote => {
+                let etag = conflict.remote_etag.clone();
+                self.etags.insert(slot.to_owned(), etag);
+                None
+            }
+            SaveResolution::DuplicateSlot(new_slot) => {
+                if new_slot == slot || self.etags.contains_key(&new_slot) {
+                    return Err(SaveGameError::SlotInUse(new_slot));
+                }
+                let metadata = SaveMetadata {
+                    slot: new_slot,
+                    ..conflict.local.clone()
+                };
+                // the remote version stays in the original slot
+                let etag = conflict.remote_etag.clone();
+                self.etags.insert(slot.to_owned(), etag);
+                Some(SaveUpload {
+                    metadata,
+                    condition: SaveCondition::IfNotExists,
+                })
+            }
+        };
+
+        self.conflicts.remove(slot);
+        Ok(upload)
+    }
+}
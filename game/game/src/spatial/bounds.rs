@@ -0,0 +1,207 @@
+use nalgebra::{Matrix4, Point3, Vector3, Vector4};
+
+/// Axis aligned bounding box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Point3<f32>,
+    pub max: Point3<f32>,
+}
+
+impl Aabb {
+    pub fn new(min: Point3<f32>, max: Point3<f32>) -> Aabb {
+        Aabb { min, max }
+    }
+
+    /// An empty box, the identity of the union
+    pub fn empty() -> Aabb {
+        Aabb {
+            min: Point3::new(f32::MAX, f32::MAX, f32::MAX),
+            max: Point3::new(f32::MIN, f32::MIN, f32::MIN),
+        }
+    }
+
+    pub fn from_sphere(sphere: &Sphere) -> Aabb {
+        let r = Vector3::new(sphere.radius, sphere.radius, sphere.radius);
+        Aabb {
+            min: sphere.center - r,
+            max: sphere.center + r,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn center(&self) -> Point3<f32> {
+        nalgebra::center(&self.min, &self.max)
+    }
+
+    pub fn extent(&self) -> Vector3<f32> {
+        self.max - self.min
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.inf(&other.min),
+            max: self.max.sup(&other.max),
+        }
+    }
+
+    pub fn add_point(&mut self, point: &Point3<f32>) {
+        self.min = self.min.inf(point);
+        self.max = self.max.sup(point);
+    }
+
+    pub fn expand(&self, margin: f32) -> Aabb {
+        let m = Vector3::new(margin, margin, margin);
+        Aabb {
+            min: self.min - m,
+            max: self.max + m,
+        }
+    }
+
+    pub fn contains(&self, other: &Aabb) -> bool {
+        self.min.x <= other.min.x
+            && self.min.y <= other.min.y
+            && self.min.z <= other.min.z
+            && self.max.x >= other.max.x
+            && self.max.y >= other.max.y
+            && self.max.z >= other.max.z
+    }
+
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x
+            && self.min.y <= other.max.y
+            && self.min.z <= other.max.z
+            && self.max.x >= other.min.x
+            && self.max.y >= other.min.y
+            && self.max.z >= other.min.z
+    }
+
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        let closest = sphere.center.sup(&self.min).inf(&self.max);
+        (closest - sphere.center).norm_squared() <= sphere.radius * sphere.radius
+    }
+
+    /// Return the distance along the ray where it enters the box, or None if the box is missed
+    /// within the given range.
+    pub fn ray_intersection(&self, ray: &Ray, max_distance: f32) -> Option<f32> {
+        let t1 = (self.min - ray.origin).component_mul(&ray.inv_direction);
+        let t2 = (self.max - ray.origin).component_mul(&ray.inv_direction);
+        let t_near = t1.inf(&t2).max().max(0.);
+        let t_far = t1.sup(&t2).min().min(max_distance);
+        if t_near <= t_far {
+            Some(t_near)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sphere {
+    pub center: Point3<f32>,
+    pub radius: f32,
+}
+
+impl Sphere {
+    pub fn new(center: Point3<f32>, radius: f32) -> Sphere {
+        Sphere { center, radius }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Ray {
+    pub origin: Point3<f32>,
+    /// Normalized direction
+    pub direction: Vector3<f32>,
+    inv_direction: Vector3<f32>,
+}
+
+impl Ray {
+    pub fn new(origin: Point3<f32>, direction: Vector3<f32>) -> Ray {
+        let direction = direction.normalize();
+        Ray {
+            origin,
+            direction,
+            inv_direction: direction.map(|x| 1. / x),
+        }
+    }
+
+    /// Create a ray between two points, returns the ray and the distance of the points.
+    pub fn between(from: Point3<f32>, to: Point3<f32>) -> (Ray, f32) {
+        let d = to - from;
+        (Ray::new(from, d), d.norm())
+    }
+
+    pub fn at(&self, t: f32) -> Point3<f32> {
+        self.origin + self.direction * t
+    }
+}
+
+/// Plane given by the n*x + d = 0 equation, where n is the unit normal. Points on the positive side
+/// are "inside".
+#[derive(Debug, Clone, Copy)]
+pub struct Plane {
+    pub normal: Vector3<f32>,
+    pub d: f32,
+}
+
+impl Plane {
+    fn from_coefficients(v: Vector4<f32>) -> Plane {
+        let normal = Vector3::new(v.x, v.y, v.z);
+        let len = normal.norm();
+        Plane {
+            normal: normal / len,
+            d: v.w / len,
+        }
+    }
+
+    pub fn signed_distance(&self, point: &Point3<f32>) -> f32 {
+        self.normal.dot(&point.coords) + self.d
+    }
+}
+
+/// View frustum for culling
+#[derive(Debug, Clone, Copy)]
+pub struct Frustum {
+    /// left, right, bottom, top, near, far planes
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Extract the planes from a view-projection matrix with a 0..1 clip space depth range (as used by wgpu).
+    pub fn from_view_projection(m: &Matrix4<f32>) -> Frustum {
+        let row = |i: usize| -> Vector4<f32> { m.row(i).transpose() };
+        let (r0, r1, r2, r3) = (row(0), row(1), row(2), row(3));
+        Frustum {
+            planes: [
+                Plane::from_coefficients(r3 + r0),
+                Plane::from_coefficients(r3 - r0),
+                Plane::from_coefficients(r3 + r1),
+                Plane::from_coefficients(r3 - r1),
+                Plane::from_coefficients(r2),
+                Plane::from_coefficients(r3 - r2),
+            ],
+        }
+    }
+
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        self.planes
+            .iter()
+            .all(|p| p.signed_distance(&sphere.center) >= -sphere.radius)
+    }
+
+    /// Conservative test, some boxes near the corners of the frustum are reported as visible.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|p| {
+            // the corner of the box furthest along the normal
+            let positive = Point3::new(
+                if p.normal.x >= 0. { aabb.max.x } else { aabb.min.x },
+                if p.normal.y >= 0. { aabb.max.y } else { aabb.min.y },
+                if p.normal.z >= 0. { aabb.max.z } else { aabb.min.z },
+            );
+            p.signed_distance(&positive) >= 0.
+        })
+    }
+}
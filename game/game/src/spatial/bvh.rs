@@ -0,0 +1,386 @@
+use crate::spatial::{Aabb, Frustum, Ray, Sphere};
+use nalgebra::Point3;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Configuration of the bounding volume hierarchy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BvhConfig {
+    /// The bounds in the tree are enlarged by this margin, thus small movements don't require an update
+    pub margin: f32,
+    /// Maximum number of proxies in a leaf
+    pub leaf_size: usize,
+    /// Rebuild the tree if the number of pending (not in tree) proxies exceeds this limit
+    pub max_pending: usize,
+    /// Rebuild the tree at least in every N frames if there are pending changes
+    pub rebuild_interval: u32,
+}
+
+impl Default for BvhConfig {
+    fn default() -> BvhConfig {
+        BvhConfig {
+            margin: 0.1,
+            leaf_size: 4,
+            max_pending: 64,
+            rebuild_interval: 30,
+        }
+    }
+}
+
+/// Handle of an object in the hierarchy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProxyId(usize);
+
+struct Proxy<T> {
+    bounds: Aabb,
+    fat_bounds: Aabb,
+    in_tree: bool,
+    data: T,
+}
+
+enum NodeKind {
+    /// Range in the leaf proxy list
+    Leaf { start: usize, count: usize },
+    /// The left child is the next node, the right is given by index
+    Inner { right: usize },
+}
+
+struct Node {
+    bounds: Aabb,
+    kind: NodeKind,
+}
+
+/// Statistics of the hierarchy
+#[derive(Debug, Clone, Default)]
+pub struct BvhStats {
+    pub proxy_count: usize,
+    pub node_count: usize,
+    pub depth: usize,
+    pub pending_count: usize,
+    pub rebuild_count: usize,
+}
+
+impl fmt::Display for BvhStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "proxies: {}, nodes: {}, depth: {}, pending: {}, rebuilds: {}",
+            self.proxy_count, self.node_count, self.depth, self.pending_count, self.rebuild_count
+        )
+    }
+}
+
+/// Bounding volume hierarchy for the spatial queries on the CPU (culling, picking, line of sight).
+///
+/// The tree is built from the enlarged bounds of the proxies. Moving a proxy inside its enlarged bounds
+/// is cheap, others are kept in a pending list (queried by brute force) until the next rebuild. The
+/// rebuilds are triggered by `maintain`, called once per frame, thus the cost is amortized.
+pub struct Bvh<T> {
+    config: BvhConfig,
+    proxies: Vec<Option<Proxy<T>>>,
+    free: Vec<usize>,
+    nodes: Vec<Node>,
+    leaf_proxies: Vec<ProxyId>,
+    pending: Vec<ProxyId>,
+    stale_count: usize,
+    frames_since_rebuild: u32,
+    stats: BvhStats,
+}
+
+impl<T> Default for Bvh<T> {
+    fn default() -> Self {
+        Bvh::new(BvhConfig::default())
+    }
+}
+
+impl<T> Bvh<T> {
+    pub fn new(config: BvhConfig) -> Bvh<T> {
+        Bvh {
+            config,
+            proxies: Vec::new(),
+            free: Vec::new(),
+            nodes: Vec::new(),
+            leaf_proxies: Vec::new(),
+            pending: Vec::new(),
+            stale_count: 0,
+            frames_since_rebuild: 0,
+            stats: BvhStats::default(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.proxies.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stats(&self) -> &BvhStats {
+        &self.stats
+    }
+
+    pub fn get(&self, id: ProxyId) -> Option<&T> {
+        self.proxies.get(id.0).and_then(|p| p.as_ref()).map(|p| &p.data)
+    }
+
+    pub fn get_mut(&mut self, id: ProxyId) -> Option<&mut T> {
+        self.proxies.get_mut(id.0).and_then(|p| p.as_mut()).map(|p| &mut p.data)
+    }
+
+    pub fn bounds(&self, id: ProxyId) -> Option<&Aabb> {
+        self.proxies.get(id.0).and_then(|p| p.as_ref()).map(|p| &p.bounds)
+    }
+
+    pub fn insert(&mut self, bounds: Aabb, data: T) -> ProxyId {
+        let proxy = Proxy {
+            bounds,
+            fat_bounds: bounds.expand(self.config.margin),
+            in_tree: false,
+            data,
+        };
+        let id = if let Some(index) = self.free.pop() {
+            self.proxies[index] = Some(proxy);
+            ProxyId(index)
+        } else {
+            self.proxies.push(Some(proxy));
+            ProxyId(self.proxies.len() - 1)
+        };
+        self.pending.push(id);
+        id
+    }
+
+    /// Update the bounds of a proxy. Returns false if the proxy is not found.
+    pub fn update(&mut self, id: ProxyId, bounds: Aabb) -> bool {
+        let margin = self.config.margin;
+        let proxy = match self.proxies.get_mut(id.0).and_then(|p| p.as_mut()) {
+            Some(proxy) => proxy,
+            None => return false,
+        };
+
+        proxy.bounds = bounds;
+        if !proxy.fat_bounds.contains(&bounds) {
+            proxy.fat_bounds = bounds.expand(margin);
+            if proxy.in_tree {
+                proxy.in_tree = false;
+                self.stale_count += 1;
+                self.pending.push(id);
+            }
+        }
+        true
+    }
+
+    pub fn remove(&mut self, id: ProxyId) -> Option<T> {
+        let proxy = self.proxies.get_mut(id.0).and_then(|p| p.take())?;
+        if proxy.in_tree {
+            self.stale_count += 1;
+        } else {
+            self.pending.retain(|p| *p != id);
+        }
+        self.free.push(id.0);
+        Some(proxy.data)
+    }
+
+    pub fn clear(&mut self) {
+        self.proxies.clear();
+        self.free.clear();
+        self.nodes.clear();
+        self.leaf_proxies.clear();
+        self.pending.clear();
+        self.stale_count = 0;
+    }
+
+    /// Perform the scheduled maintenance, it shall be called once per frame.
+    /// Returns if the tree was rebuilt.
+    pub fn maintain(&mut self) -> bool {
+        self.frames_since_rebuild += 1;
+        let need_rebuild = self.pending.len() > self.config.max_pending
+            || self.stale_count > self.len() / 2
+            || (!self.pending.is_empty() && self.frames_since_rebuild >= self.config.rebuild_interval);
+        if need_rebuild {
+            self.rebuild();
+        }
+        self.stats.proxy_count = self.len();
+        self.stats.pending_count = self.pending.len();
+        need_rebuild
+    }
+
+    /// Rebuild the tree from scratch.
+    pub fn rebuild(&mut self) {
+        self.nodes.clear();
+        self.leaf_proxies.clear();
+        self.pending.clear();
+        self.stale_count = 0;
+        self.frames_since_rebuild = 0;
+
+        let mut items: Vec<(ProxyId, Aabb, Point3<f32>)> = Vec::with_capacity(self.len());
+        for (index, proxy) in self.proxies.iter_mut().enumerate() {
+            if let Some(proxy) = proxy {
+                proxy.in_tree = true;
+                items.push((ProxyId(index), proxy.fat_bounds, proxy.fat_bounds.center()));
+            }
+        }
+
+        let depth = if items.is_empty() {
+            0
+        } else {
+            self.build_node(&mut items[..], 1)
+        };
+
+        self.stats.node_count = self.nodes.len();
+        self.stats.depth = depth;
+        self.stats.rebuild_count += 1;
+        log::trace!("BVH rebuilt: {}", self.stats);
+    }
+
+    /// Build the sub-tree of the items splitting at the median of the longest axis, returns the depth.
+    fn build_node(&mut self, items: &mut [(ProxyId, Aabb, Point3<f32>)], depth: usize) -> usize {
+        let mut bounds = Aabb::empty();
+        let mut centers = Aabb::empty();
+        for (_, b, c) in items.iter() {
+            bounds = bounds.union(b);
+            centers.add_point(c);
+        }
+
+        let node_index = self.nodes.len();
+        if items.len() <= self.config.leaf_size.max(1) {
+            let start = self.leaf_proxies.len();
+            self.leaf_proxies.extend(items.iter().map(|(id, _, _)| *id));
+            self.nodes.push(Node {
+                bounds,
+                kind: NodeKind::Leaf {
+                    start,
+                    count: items.len(),
+                },
+            });
+            return depth;
+        }
+
+        let extent = centers.extent();
+        let axis = if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+        let mid = items.len() / 2;
+        items.sort_unstable_by(|a, b| a.2[axis].partial_cmp(&b.2[axis]).unwrap_or(std::cmp::Ordering::Equal));
+
+        self.nodes.push(Node {
+            bounds,
+            kind: NodeKind::Inner { right: 0 },
+        });
+        let (left, right) = items.split_at_mut(mid);
+        let left_depth = self.build_node(left, depth + 1);
+        let right_index = self.nodes.len();
+        let right_depth = self.build_node(right, depth + 1);
+        self.nodes[node_index].kind = NodeKind::Inner { right: right_index };
+        left_depth.max(right_depth)
+    }
+
+    /// Visit the proxies whose node bounds pass the test. The test is also performed on the bounds
+    /// of the proxies.
+    fn visit<F, V>(&self, mut test: F, mut visitor: V)
+    where
+        F: FnMut(&Aabb) -> bool,
+        V: FnMut(ProxyId, &Proxy<T>),
+    {
+        if !self.nodes.is_empty() {
+            let mut stack = vec![0];
+            while let Some(index) = stack.pop() {
+                let node = &self.nodes[index];
+                if !test(&node.bounds) {
+                    continue;
+                }
+                match node.kind {
+                    NodeKind::Inner { right } => {
+                        stack.push(right);
+                        stack.push(index + 1);
+                    }
+                    NodeKind::Leaf { start, count } => {
+                        for id in &self.leaf_proxies[start..start + count] {
+                            if let Some(proxy) = &self.proxies[id.0] {
+                                // moved and re-inserted proxies are in the pending list
+                                if proxy.in_tree && test(&proxy.bounds) {
+                                    visitor(*id, proxy);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        for id in &self.pending {
+            if let Some(proxy) = &self.proxies[id.0] {
+                if test(&proxy.bounds) {
+                    visitor(*id, proxy);
+                }
+            }
+        }
+    }
+
+    /// Find the proxies intersecting the box
+    pub fn query_aabb<F: FnMut(ProxyId, &T)>(&self, aabb: &Aabb, mut callback: F) {
+        self.visit(|b| b.intersects(aabb), |id, p| callback(id, &p.data));
+    }
+
+    /// Find the proxies intersecting the sphere
+    pub fn query_sphere<F: FnMut(ProxyId, &T)>(&self, sphere: &Sphere, mut callback: F) {
+        self.visit(|b| b.intersects_sphere(sphere), |id, p| callback(id, &p.data));
+    }
+
+    /// Find the proxies (potentially) visible in the frustum
+    pub fn query_frustum<F: FnMut(ProxyId, &T)>(&self, frustum: &Frustum, mut callback: F) {
+        self.visit(|b| frustum.intersects_aabb(b), |id, p| callback(id, &p.data));
+    }
+
+    /// Find the proxies hit by the ray with the entry distance, the order of the hits is not defined.
+    pub fn query_ray<F: FnMut(ProxyId, &T, f32)>(&self, ray: &Ray, max_distance: f32, mut callback: F) {
+        self.visit(
+            |b| b.ray_intersection(ray, max_distance).is_some(),
+            |id, p| {
+                if let Some(t) = p.bounds.ray_intersection(ray, max_distance) {
+                    callback(id, &p.data, t)
+                }
+            },
+        );
+    }
+
+    /// Find the closest proxy hit by the ray accepted by the filter.
+    pub fn raycast<F: FnMut(ProxyId, &T) -> bool>(
+        &self,
+        ray: &Ray,
+        max_distance: f32,
+        mut filter: F,
+    ) -> Option<(ProxyId, f32)> {
+        let mut closest: Option<(ProxyId, f32)> = None;
+        self.query_ray(ray, max_distance, |id, data, t| {
+            if closest.map(|(_, ct)| t < ct).unwrap_or(true) && filter(id, data) {
+                closest = Some((id, t));
+            }
+        });
+        closest
+    }
+
+    /// Check if the segment between the two points is free of proxies accepted by the filter.
+    pub fn line_of_sight<F: FnMut(ProxyId, &T) -> bool>(
+        &self,
+        from: Point3<f32>,
+        to: Point3<f32>,
+        mut filter: F,
+    ) -> bool {
+        let (ray, distance) = Ray::between(from, to);
+        if distance <= 0. {
+            return true;
+        }
+        let mut blocked = false;
+        self.query_ray(&ray, distance, |id, data, _| {
+            if !blocked && filter(id, data) {
+                blocked = true;
+            }
+        });
+        !blocked
+    }
+}
@@ -0,0 +1,109 @@
+use crate::spatial::{WorldPosition, WorldTransform};
+use nalgebra::{Isometry3, Matrix4, Translation3, Vector3};
+
+/// Origin rebasing of the large worlds. The simulation works in f32 coordinates relative to the floating
+/// origin, that is moved (in whole sector steps) to the camera when the camera gets too far from it. The
+/// render matrices are generated relative to the exact camera position each frame, thus no precision is
+/// lost on the GPU regardless of the distance from the world origin.
+#[derive(Debug, Clone)]
+pub struct FloatingOrigin {
+    origin: WorldPosition,
+    /// Distance from the origin triggering a rebase
+    rebase_distance: f32,
+    /// Incremented on each rebase, the systems caching relative positions shall compare it to detect rebases
+    generation: u32,
+    /// Translation of the relative coordinates by the last rebase
+    last_shift: Vector3<f32>,
+    /// Position of the camera for the current frame
+    camera: WorldTransform,
+}
+
+impl Default for FloatingOrigin {
+    fn default() -> Self {
+        FloatingOrigin::new(4096.)
+    }
+}
+
+impl FloatingOrigin {
+    pub fn new(rebase_distance: f32) -> FloatingOrigin {
+        FloatingOrigin {
+            origin: WorldPosition::origin(),
+            rebase_distance,
+            generation: 0,
+            last_shift: Vector3::zeros(),
+            camera: WorldTransform::default(),
+        }
+    }
+
+    pub fn origin(&self) -> &WorldPosition {
+        &self.origin
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// Translation to add to the positions relative to the previous origin to get the positions relative to
+    /// the current origin.
+    pub fn last_shift(&self) -> &Vector3<f32> {
+        &self.last_shift
+    }
+
+    pub fn camera(&self) -> &WorldTransform {
+        &self.camera
+    }
+
+    /// Set the camera of the frame and rebase the origin if the camera is too far from it.
+    /// Returns true if the origin was moved.
+    pub fn update_camera(&mut self, camera: &WorldTransform) -> bool {
+        self.camera = *camera;
+        if camera.position.relative_to(&self.origin).norm() > self.rebase_distance {
+            self.rebase(WorldPosition::new(camera.position.sector, Vector3::zeros()));
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Move the origin to the given position.
+    pub fn rebase(&mut self, origin: WorldPosition) {
+        self.last_shift = self.origin.relative_to(&origin);
+        log::debug!(
+            "Rebasing world origin from {:?} to {:?}, shift: {:?}",
+            self.origin.sector,
+            origin.sector,
+            self.last_shift
+        );
+        self.origin = origin;
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    /// Convert a large world position into the simulation coordinates
+    pub fn to_local(&self, position: &WorldPosition) -> Vector3<f32> {
+        position.relative_to(&self.origin)
+    }
+
+    /// Convert a simulation coordinate back into the large world position
+    pub fn to_world(&self, local: &Vector3<f32>) -> WorldPosition {
+        WorldPosition::from_relative(&self.origin, local)
+    }
+
+    /// View matrix with the camera at the origin, only the rotation of the camera is applied.
+    pub fn camera_relative_view(&self) -> Matrix4<f32> {
+        Isometry3::from_parts(Translation3::identity(), self.camera.rotation)
+            .inverse()
+            .to_homogeneous()
+    }
+
+    /// Model matrix relative to the camera of the frame, use it along with the camera_relative_view.
+    pub fn camera_relative_model(&self, transform: &WorldTransform) -> Matrix4<f32> {
+        transform.relative_matrix(&self.camera.position)
+    }
+
+    /// Model matrix for an object given in the simulation coordinates
+    pub fn camera_relative_local_model(&self, local: &Isometry3<f32>, scale: f32) -> Matrix4<f32> {
+        let camera = self.to_local(&self.camera.position);
+        let relative = Isometry3::from_parts((local.translation.vector - camera).into(), local.rotation);
+        relative.to_homogeneous() * Matrix4::new_scaling(scale)
+    }
+}
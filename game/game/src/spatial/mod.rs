@@ -0,0 +1,10 @@
+mod bounds;
+pub use self::bounds::*;
+mod bvh;
+pub use self::bvh::*;
+mod world_position;
+pub use self::world_position::*;
+mod floating_origin;
+pub use self::floating_origin::*;
+mod transform;
+pub use self::transform::*;
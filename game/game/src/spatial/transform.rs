@@ -0,0 +1,102 @@
+use nalgebra::{Isometry3, Matrix4, Point3, Translation3, UnitQuaternion, Vector3};
+use shine_ecs::{
+    hierarchy::{propagate_hierarchy, Entities},
+    resources::ResMut,
+    scheduler::TaskGroup,
+    ECSError,
+};
+
+/// Transformation of an entity relative to its parent (or to the world for the roots of the hierarchy)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub translation: Vector3<f32>,
+    pub rotation: UnitQuaternion<f32>,
+    pub scale: f32,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform {
+            translation: Vector3::zeros(),
+            rotation: UnitQuaternion::identity(),
+            scale: 1.,
+        }
+    }
+}
+
+impl Transform {
+    pub fn new(translation: Vector3<f32>, rotation: UnitQuaternion<f32>, scale: f32) -> Transform {
+        Transform {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn from_translation(translation: Vector3<f32>) -> Transform {
+        Transform {
+            translation,
+            ..Default::default()
+        }
+    }
+}
+
+/// Transformation of an entity in the world, it is computed by the `propagate_transforms` system
+/// from the `Transform` along the hierarchy.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlobalTransform {
+    pub translation: Vector3<f32>,
+    pub rotation: UnitQuaternion<f32>,
+    pub scale: f32,
+}
+
+impl Default for GlobalTransform {
+    fn default() -> Self {
+        GlobalTransform::from(Transform::default())
+    }
+}
+
+impl From<Transform> for GlobalTransform {
+    fn from(local: Transform) -> Self {
+        GlobalTransform {
+            translation: local.translation,
+            rotation: local.rotation,
+            scale: local.scale,
+        }
+    }
+}
+
+impl GlobalTransform {
+    /// Apply a local transformation in the frame of this transformation
+    pub fn mul_transform(&self, local: &Transform) -> GlobalTransform {
+        GlobalTransform {
+            translation: self.translation + self.rotation * (local.translation * self.scale),
+            rotation: self.rotation * local.rotation,
+            scale: self.scale * local.scale,
+        }
+    }
+
+    pub fn transform_point(&self, point: &Point3<f32>) -> Point3<f32> {
+        self.isometry() * Point3::from(point.coords * self.scale)
+    }
+
+    /// Rigid part of the transformation
+    pub fn isometry(&self) -> Isometry3<f32> {
+        Isometry3::from_parts(Translation3::from(self.translation), self.rotation)
+    }
+
+    /// Model matrix for the render pipeline
+    pub fn matrix(&self) -> Matrix4<f32> {
+        self.isometry().to_homogeneous() * Matrix4::new_scaling(self.scale)
+    }
+}
+
+/// Update the `GlobalTransform` of the entities from their `Transform` and the `GlobalTransform` of the parent.
+/// It should run after the logic moving the entities and before the render stage.
+pub fn propagate_transforms(mut entities: ResMut<Entities>) -> Result<TaskGroup, ECSError> {
+    propagate_hierarchy::<Transform, GlobalTransform, _>(&mut entities, |parent, local| match parent {
+        Some(parent) => parent.mul_transform(local),
+        None => GlobalTransform::from(*local),
+    });
+    Ok(TaskGroup::default())
+}
@@ -0,0 +1,121 @@
+use nalgebra::{Isometry3, Matrix4, Point3, Translation3, UnitQuaternion, Vector3};
+
+/// Edge length of the sectors of the large world coordinates. The offset within a sector stays below this
+/// limit, thus the f32 precision is about 0.1mm everywhere.
+pub const WORLD_SECTOR_SIZE: f64 = 1024.;
+
+/// Position in the large world stored as an integer sector and an f32 offset within the sector.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorldPosition {
+    pub sector: Vector3<i32>,
+    pub offset: Vector3<f32>,
+}
+
+impl Default for WorldPosition {
+    fn default() -> Self {
+        WorldPosition {
+            sector: Vector3::zeros(),
+            offset: Vector3::zeros(),
+        }
+    }
+}
+
+impl WorldPosition {
+    /// Create a position, the offset is normalized into the sector range.
+    pub fn new(sector: Vector3<i32>, offset: Vector3<f32>) -> WorldPosition {
+        let mut position = WorldPosition { sector, offset };
+        position.normalize();
+        position
+    }
+
+    pub fn origin() -> WorldPosition {
+        WorldPosition::default()
+    }
+
+    pub fn from_f64(position: &Point3<f64>) -> WorldPosition {
+        let sector = position.coords.map(|c| (c / WORLD_SECTOR_SIZE).floor());
+        let offset = position.coords - sector * WORLD_SECTOR_SIZE;
+        WorldPosition {
+            sector: sector.map(|c| c as i32),
+            offset: offset.map(|c| c as f32),
+        }
+    }
+
+    pub fn to_f64(&self) -> Point3<f64> {
+        Point3::from(self.sector.map(|c| c as f64 * WORLD_SECTOR_SIZE) + self.offset.map(|c| c as f64))
+    }
+
+    /// Move the whole sectors of the offset into the sector coordinates.
+    pub fn normalize(&mut self) {
+        let size = WORLD_SECTOR_SIZE as f32;
+        for i in 0..3 {
+            let shift = (self.offset[i] / size).floor();
+            if shift != 0. {
+                self.sector[i] += shift as i32;
+                self.offset[i] -= shift * size;
+            }
+        }
+    }
+
+    pub fn translate(&mut self, delta: &Vector3<f32>) {
+        self.offset += delta;
+        self.normalize();
+    }
+
+    /// Position relative to an origin. The sector difference is exact, the precision is lost only for the
+    /// positions far from the origin.
+    pub fn relative_to(&self, origin: &WorldPosition) -> Vector3<f32> {
+        let sector = (self.sector - origin.sector).map(|c| c as f64 * WORLD_SECTOR_SIZE);
+        let offset = (self.offset - origin.offset).map(|c| c as f64);
+        (sector + offset).map(|c| c as f32)
+    }
+
+    /// Inverse of the relative_to
+    pub fn from_relative(origin: &WorldPosition, relative: &Vector3<f32>) -> WorldPosition {
+        let relative = relative.map(|c| c as f64);
+        let sector = (relative / WORLD_SECTOR_SIZE).map(|c| c.floor());
+        let offset = relative - sector * WORLD_SECTOR_SIZE;
+        WorldPosition::new(
+            origin.sector + sector.map(|c| c as i32),
+            origin.offset + offset.map(|c| c as f32),
+        )
+    }
+}
+
+/// Transformation of an object in the large world
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorldTransform {
+    pub position: WorldPosition,
+    pub rotation: UnitQuaternion<f32>,
+    pub scale: f32,
+}
+
+impl Default for WorldTransform {
+    fn default() -> Self {
+        WorldTransform {
+            position: WorldPosition::origin(),
+            rotation: UnitQuaternion::identity(),
+            scale: 1.,
+        }
+    }
+}
+
+impl WorldTransform {
+    pub fn new(position: WorldPosition, rotation: UnitQuaternion<f32>) -> WorldTransform {
+        WorldTransform {
+            position,
+            rotation,
+            scale: 1.,
+        }
+    }
+
+    /// Rigid transformation relative to the origin
+    pub fn relative_isometry(&self, origin: &WorldPosition) -> Isometry3<f32> {
+        Isometry3::from_parts(Translation3::from(self.position.relative_to(origin)), self.rotation)
+    }
+
+    /// Model matrix relative to the origin
+    pub fn relative_matrix(&self, origin: &WorldPosition) -> Matrix4<f32> {
+        self.relative_isometry(origin).to_homogeneous() * Matrix4::new_scaling(self.scale)
+    }
+}
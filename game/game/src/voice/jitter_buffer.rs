@@ -0,0 +1,112 @@
+use crate::voice::{sequence_diff, VoicePacket};
+use std::collections::VecDeque;
+
+/// The next frame of the playback
+#[derive(Clone, Debug, PartialEq)]
+pub enum JitterFrame {
+    Packet(VoicePacket),
+    /// The packet is missing (late or dropped by the unreliable channel), the decoder shall conceal it
+    Lost,
+    /// Nothing to play, the buffer is (re)filling
+    Empty,
+}
+
+/// Reorder the packets of a talker by the sequence number and delay the playback by a few frames to smooth
+/// out the network jitter. The late packets are dropped, the playback is restarted after an underrun
+/// or at the end of a talk.
+pub struct JitterBuffer {
+    delay: usize,
+    capacity: usize,
+    next: Option<u16>,
+    slots: VecDeque<Option<VoicePacket>>,
+    buffering: bool,
+}
+
+impl JitterBuffer {
+    /// Create a buffer delaying the playback by the given number of frames
+    pub fn new(delay: usize) -> JitterBuffer {
+        let delay = delay.max(1);
+        JitterBuffer {
+            delay,
+            capacity: delay * 4,
+            next: None,
+            slots: VecDeque::new(),
+            buffering: true,
+        }
+    }
+
+    pub fn delay(&self) -> usize {
+        self.delay
+    }
+
+    /// Number of the buffered packets
+    pub fn len(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.is_some()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn reset(&mut self) {
+        self.next = None;
+        self.slots.clear();
+        self.buffering = true;
+    }
+
+    /// Add a received packet, returns false if the packet was dropped as it is late or duplicated
+    pub fn push(&mut self, packet: VoicePacket) -> bool {
+        let next = *self.next.get_or_insert(packet.sequence);
+        let diff = sequence_diff(packet.sequence, next);
+        if diff < 0 {
+            log::trace!("Late voice packet {} dropped", packet.sequence);
+            return false;
+        }
+
+        let mut index = diff as usize;
+        if index >= self.capacity {
+            // too far ahead, the talker was lost for a while: restart from this packet
+            log::debug!("Voice packet {} is out of the buffer, restarting", packet.sequence);
+            self.reset();
+            self.next = Some(packet.sequence);
+            index = 0;
+        }
+
+        if self.slots.len() <= index {
+            self.slots.resize(index + 1, None);
+        }
+        if self.slots[index].is_some() {
+            return false;
+        }
+        self.slots[index] = Some(packet);
+        true
+    }
+
+    /// Take the frame to be played, it is called once per frame period
+    pub fn pop(&mut self) -> JitterFrame {
+        if self.buffering {
+            let ready = self.len() >= self.delay || self.slots.iter().flatten().any(|p| p.is_end_of_talk());
+            if !ready {
+                return JitterFrame::Empty;
+            }
+            self.buffering = false;
+        }
+
+        if self.is_empty() {
+            // underrun, wait for the buffer to refill
+            self.reset();
+            return JitterFrame::Empty;
+        }
+
+        self.next = self.next.map(|next| next.wrapping_add(1));
+        match self.slots.pop_front().flatten() {
+            Some(packet) => {
+                if packet.is_end_of_talk() {
+                    self.reset();
+                }
+                JitterFrame::Packet(packet)
+            }
+            None => JitterFrame::Lost,
+        }
+    }
+}
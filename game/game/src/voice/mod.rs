@@ -0,0 +1,17 @@
+mod voice_error;
+pub use self::voice_error::*;
+mod voice_packet;
+pub use self::voice_packet::*;
+mod talk_gate;
+pub use self::talk_gate::*;
+mod jitter_buffer;
+pub use self::jitter_buffer::*;
+
+#[cfg(feature = "voice")]
+mod voice_codec;
+#[cfg(feature = "voice")]
+pub use self::voice_codec::*;
+#[cfg(feature = "voice")]
+mod voice_capture;
+#[cfg(feature = "voice")]
+pub use self::voice_capture::*;
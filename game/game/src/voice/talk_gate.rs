@@ -0,0 +1,110 @@
+use crate::voice::END_OF_TALK;
+use serde::{Deserialize, Serialize};
+
+/// When the captured audio is sent to the voice channel
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TalkMode {
+    /// Send while the push-to-talk button is held
+    PushToTalk,
+    /// Send while the level of the microphone is above the threshold (rms of the normalized samples). The
+    /// talk is continued for the hangover frames to keep the short pauses of the speech.
+    VoiceActivation { threshold: f32, hangover: u32 },
+}
+
+impl Default for TalkMode {
+    fn default() -> TalkMode {
+        TalkMode::PushToTalk
+    }
+}
+
+/// A captured frame to be encoded and sent
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TalkFrame {
+    pub sequence: u16,
+    pub flags: u8,
+}
+
+/// Decide which of the captured frames are sent. The last frame of a talk is flagged with `END_OF_TALK` so
+/// the listeners can flush their buffers and the talking indicator is cleared.
+#[derive(Default)]
+pub struct TalkGate {
+    mode: TalkMode,
+    pressed: bool,
+    muted: bool,
+    talking: bool,
+    hangover: u32,
+    sequence: u16,
+}
+
+impl TalkGate {
+    pub fn new(mode: TalkMode) -> TalkGate {
+        TalkGate {
+            mode,
+            ..Default::default()
+        }
+    }
+
+    pub fn mode(&self) -> TalkMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: TalkMode) {
+        self.mode = mode;
+        self.hangover = 0;
+    }
+
+    /// Update the state of the push-to-talk button
+    pub fn set_push_to_talk(&mut self, pressed: bool) {
+        self.pressed = pressed;
+    }
+
+    /// Mute the microphone, nothing is sent regardless of the mode
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+
+    pub fn is_talking(&self) -> bool {
+        self.talking
+    }
+
+    /// Process a captured frame, returns the header of the packet if the frame is to be sent
+    pub fn process(&mut self, samples: &[f32]) -> Option<TalkFrame> {
+        let active = !self.muted
+            && match self.mode {
+                TalkMode::PushToTalk => self.pressed,
+                TalkMode::VoiceActivation { threshold, hangover } => {
+                    if rms(samples) >= threshold {
+                        self.hangover = hangover;
+                        true
+                    } else if self.hangover > 0 {
+                        self.hangover -= 1;
+                        true
+                    } else {
+                        false
+                    }
+                }
+            };
+
+        let flags = match (active, self.talking) {
+            (true, _) => 0,
+            (false, true) => END_OF_TALK,
+            (false, false) => return None,
+        };
+        self.talking = active;
+        let frame = TalkFrame {
+            sequence: self.sequence,
+            flags,
+        };
+        self.sequence = self.sequence.wrapping_add(1);
+        Some(frame)
+    }
+}
+
+/// Root mean square of the samples
+pub fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        0.
+    } else {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+}
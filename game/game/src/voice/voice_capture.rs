@@ -0,0 +1,56 @@
+use crate::voice::{VoiceError, VOICE_FRAME_SAMPLES, VOICE_SAMPLE_RATE};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use std::sync::mpsc::{self, Receiver};
+
+/// Capture the microphone in mono 20ms frames. The frames are collected on the audio thread and polled
+/// once per game frame, they are passed through the `TalkGate` and the `VoiceEncoder` before sending.
+pub struct VoiceCapture {
+    _stream: cpal::Stream,
+    frames: Receiver<Vec<f32>>,
+}
+
+impl VoiceCapture {
+    /// Start the capture on the default input device
+    pub fn new() -> Result<VoiceCapture, VoiceError> {
+        let host = cpal::default_host();
+        let device = host
+            .default_input_device()
+            .ok_or_else(|| VoiceError::Device("No input device".to_owned()))?;
+        log::info!("Voice capture device: {:?}", device.name());
+
+        let config = cpal::StreamConfig {
+            channels: 1,
+            sample_rate: cpal::SampleRate(VOICE_SAMPLE_RATE),
+            buffer_size: cpal::BufferSize::Default,
+        };
+
+        let (sender, frames) = mpsc::channel();
+        let mut frame = Vec::with_capacity(VOICE_FRAME_SAMPLES);
+        let stream = device
+            .build_input_stream(
+                &config,
+                move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                    for sample in data {
+                        frame.push(*sample);
+                        if frame.len() == VOICE_FRAME_SAMPLES {
+                            let full = std::mem::replace(&mut frame, Vec::with_capacity(VOICE_FRAME_SAMPLES));
+                            let _ = sender.send(full);
+                        }
+                    }
+                },
+                |err| log::warn!("Voice capture error: {:?}", err),
+            )
+            .map_err(|err| VoiceError::Device(format!("{:?}", err)))?;
+        stream.play().map_err(|err| VoiceError::Device(format!("{:?}", err)))?;
+
+        Ok(VoiceCapture {
+            _stream: stream,
+            frames,
+        })
+    }
+
+    /// Take the next captured frame
+    pub fn try_frame(&self) -> Option<Vec<f32>> {
+        self.frames.try_recv().ok()
+    }
+}
@@ -0,0 +1,70 @@
+use crate::voice::{JitterFrame, TalkFrame, VoiceError, VoicePacket};
+use audiopus::{
+    coder::{Decoder, Encoder},
+    Application, Channels, SampleRate,
+};
+
+/// Sample rate of the voice channels
+pub const VOICE_SAMPLE_RATE: u32 = 48000;
+/// Number of samples in a 20ms frame
+pub const VOICE_FRAME_SAMPLES: usize = 960;
+/// Upper limit of an encoded frame
+const MAX_PACKET_SIZE: usize = 1275;
+
+fn codec_error(err: audiopus::Error) -> VoiceError {
+    VoiceError::Codec(format!("{:?}", err))
+}
+
+/// Opus encoder of the captured mono frames
+pub struct VoiceEncoder {
+    encoder: Encoder,
+}
+
+impl VoiceEncoder {
+    pub fn new() -> Result<VoiceEncoder, VoiceError> {
+        let encoder = Encoder::new(SampleRate::Hz48000, Channels::Mono, Application::Voip).map_err(codec_error)?;
+        Ok(VoiceEncoder { encoder })
+    }
+
+    /// Encode a frame passed by the `TalkGate`
+    pub fn encode(&self, frame: TalkFrame, samples: &[f32]) -> Result<VoicePacket, VoiceError> {
+        let mut payload = vec![0u8; MAX_PACKET_SIZE];
+        let len = self.encoder.encode_float(samples, &mut payload).map_err(codec_error)?;
+        payload.truncate(len);
+        Ok(VoicePacket {
+            flags: frame.flags,
+            sequence: frame.sequence,
+            payload,
+        })
+    }
+}
+
+/// Opus decoder of a talker (or of the server mix)
+pub struct VoiceDecoder {
+    decoder: Decoder,
+}
+
+impl VoiceDecoder {
+    pub fn new() -> Result<VoiceDecoder, VoiceError> {
+        let decoder = Decoder::new(SampleRate::Hz48000, Channels::Mono).map_err(codec_error)?;
+        Ok(VoiceDecoder { decoder })
+    }
+
+    /// Decode the next frame of the jitter buffer, the lost packets are concealed by the decoder
+    pub fn decode(&mut self, frame: &JitterFrame) -> Result<Option<Vec<f32>>, VoiceError> {
+        let mut samples = vec![0f32; VOICE_FRAME_SAMPLES];
+        let len = match frame {
+            JitterFrame::Packet(packet) => self
+                .decoder
+                .decode_float(Some(&packet.payload[..]), &mut samples[..], false)
+                .map_err(codec_error)?,
+            JitterFrame::Lost => self
+                .decoder
+                .decode_float(None::<&[u8]>, &mut samples[..], false)
+                .map_err(codec_error)?,
+            JitterFrame::Empty => return Ok(None),
+        };
+        samples.truncate(len);
+        Ok(Some(samples))
+    }
+}
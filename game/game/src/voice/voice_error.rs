@@ -0,0 +1,13 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum VoiceError {
+    #[error("Voice codec error: {0}")]
+    Codec(String),
+
+    #[error("Audio device error: {0}")]
+    Device(String),
+
+    #[error("Invalid voice packet")]
+    InvalidPacket,
+}
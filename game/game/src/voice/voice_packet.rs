@@ -0,0 +1,52 @@
+use crate::voice::VoiceError;
+
+/// Size of the header of the packets sent to the server: flags (u8) and sequence (u16 le)
+pub const VOICE_HEADER_LEN: usize = 3;
+/// Size of the header of the packets received from the server: slot of the talker (u16 le), flags and sequence
+pub const VOICE_FORWARD_HEADER_LEN: usize = 5;
+/// Slot of the packets mixed by the server
+pub const MIXED_SLOT: u16 = 0;
+/// The last packet of a talk spurt (ex. push-to-talk released)
+pub const END_OF_TALK: u8 = 0x01;
+
+/// An Opus encoded audio frame of the voice channel
+#[derive(Clone, Debug, PartialEq)]
+pub struct VoicePacket {
+    pub flags: u8,
+    pub sequence: u16,
+    pub payload: Vec<u8>,
+}
+
+impl VoicePacket {
+    pub fn is_end_of_talk(&self) -> bool {
+        self.flags & END_OF_TALK != 0
+    }
+
+    /// Serialize the packet for the server
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(VOICE_HEADER_LEN + self.payload.len());
+        data.push(self.flags);
+        data.extend_from_slice(&self.sequence.to_le_bytes());
+        data.extend_from_slice(&self.payload);
+        data
+    }
+
+    /// Parse a packet received from the server, returns the slot of the talker and the packet
+    pub fn from_forward(data: &[u8]) -> Result<(u16, VoicePacket), VoiceError> {
+        if data.len() < VOICE_FORWARD_HEADER_LEN {
+            return Err(VoiceError::InvalidPacket);
+        }
+        let slot = u16::from_le_bytes([data[0], data[1]]);
+        let packet = VoicePacket {
+            flags: data[2],
+            sequence: u16::from_le_bytes([data[3], data[4]]),
+            payload: data[VOICE_FORWARD_HEADER_LEN..].to_vec(),
+        };
+        Ok((slot, packet))
+    }
+}
+
+/// Compare two wrapping sequence numbers, positive if `a` is after `b`
+pub fn sequence_diff(a: u16, b: u16) -> i16 {
+    a.wrapping_sub(b) as i16
+}
@@ -1,15 +1,22 @@
 use crate::app::AppError;
 use shine_ecs::{
-    resources::Resources,
+    resources::{ResourceScope, Resources},
     scheduler::{Scheduler, TaskGroup},
 };
-use std::collections::HashMap;
+use std::{
+    any::{type_name, TypeId},
+    collections::HashMap,
+};
+
+/// Release action of a scope
+type WorldScopeRelease = Box<dyn FnOnce(&mut World)>;
 
 #[derive(Default)]
 pub struct World {
     pub resources: Resources,
     scheduler: Scheduler,
     stages: HashMap<String, TaskGroup>,
+    scopes: HashMap<TypeId, Vec<WorldScopeRelease>>,
 }
 
 impl World {
@@ -17,6 +24,13 @@ impl World {
         let _ = self.stages.insert(stage.into(), tasks);
     }
 
+    /// Add a stage that is removed when the scope is released
+    pub fn add_stage_scoped<S: ResourceScope>(&mut self, scope: S, stage: &str, tasks: TaskGroup) {
+        self.add_stage(stage, tasks);
+        let stage = stage.to_owned();
+        self.on_release_scope(scope, move |world| world.remove_stage(&stage));
+    }
+
     pub fn remove_stage(&mut self, stage: &str) {
         let _ = self.stages.remove(stage);
     }
@@ -25,12 +39,44 @@ impl World {
         self.stages.clear();
     }
 
-    pub fn run_stage(&mut self, stage: &str) -> Result<(), AppError> {
-        if let Some(stage) = self.stages.get(stage) {
+    /// Access the scheduler for debugging, ex. to pause or step the execution and query the system timings.
+    pub fn scheduler_mut(&mut self) -> &mut Scheduler {
+        &mut self.scheduler
+    }
+
+    /// Log the resource store statistics and (in scheduler debug mode) the wall time of the systems.
+    pub fn log_stats(&self) {
+        log::info!("Resources: {}", self.resources.stats());
+        if self.scheduler.is_debug() {
+            self.scheduler.log_system_timings();
+        }
+    }
+
+    pub fn run_stage(&mut self, name: &str) -> Result<(), AppError> {
+        if let Some(stage) = self.stages.get(name) {
             self.scheduler
-                .run(&self.resources, stage)
+                .run_stage(name, &self.resources, stage)
                 .map_err(AppError::TaskError)?;
         }
         Ok(())
     }
+
+    /// Register a cleanup requiring access to the whole world (ex. destroy render techniques) for the scope.
+    pub fn on_release_scope<S: ResourceScope, F: 'static + FnOnce(&mut World)>(&mut self, _scope: S, release: F) {
+        self.scopes
+            .entry(TypeId::of::<S>())
+            .or_default()
+            .push(Box::new(release));
+    }
+
+    /// Run the cleanups of the scope in the reverse order of the registration, then release the scoped resources.
+    pub fn release_scope<S: ResourceScope>(&mut self) {
+        if let Some(releases) = self.scopes.remove(&TypeId::of::<S>()) {
+            log::debug!("Releasing world scope {}", type_name::<S>());
+            for release in releases.into_iter().rev() {
+                release(self);
+            }
+        }
+        self.resources.release_scope::<S>();
+    }
 }
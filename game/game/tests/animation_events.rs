@@ -0,0 +1,100 @@
+use shine_game::animation::{AnimationEventTrack, AnimationEvents, AnimationPlayer, EventMarker};
+use std::sync::Arc;
+
+mod utils;
+
+fn create_track() -> Arc<AnimationEventTrack> {
+    let track = AnimationEventTrack::from_gltf_extras(
+        r#"{"events": [
+            {"time": 0.5, "name": "footstep_right"},
+            {"time": 0.0, "name": "footstep_left"},
+            {"time": 0.8, "name": "hit"},
+            {"time": 1.0, "name": "end"}
+        ]}"#,
+    )
+    .unwrap();
+    Arc::new(track)
+}
+
+fn names(events: &mut AnimationEvents) -> Vec<String> {
+    let names = events.iter().map(|e| e.name.clone()).collect();
+    events.clear();
+    names
+}
+
+#[test]
+fn event_track_parse() {
+    utils::init_logger();
+
+    let track = create_track();
+    let times: Vec<_> = track.markers().iter().map(|m| m.time).collect();
+    assert_eq!(times, vec![0., 0.5, 0.8, 1.]);
+
+    let tracks = AnimationEventTrack::from_sidecar(
+        r#"{
+            "walk": {"events": [{"time": 0.25, "name": "footstep"}]},
+            "idle": {}
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(tracks.len(), 2);
+    assert!(tracks["idle"].is_empty());
+    assert_eq!(tracks["walk"].markers(), &[EventMarker::new(0.25, "footstep")]);
+
+    assert!(AnimationEventTrack::new(vec![EventMarker::new(-1., "invalid")]).is_err());
+    assert!(AnimationEventTrack::from_gltf_extras("[1, 2]").is_err());
+}
+
+#[test]
+fn player_once() {
+    utils::init_logger();
+
+    let mut events = AnimationEvents::default();
+    let mut player = AnimationPlayer::new(7, "walk", 1., create_track());
+
+    player.advance(0.4, &mut events);
+    assert_eq!(names(&mut events), vec!["footstep_left"]);
+    player.advance(0.2, &mut events);
+    assert_eq!(names(&mut events), vec!["footstep_right"]);
+    player.advance(0.1, &mut events);
+    assert!(events.is_empty());
+    player.advance(1., &mut events);
+    assert!(player.is_finished());
+    let event = events.iter().next().unwrap().clone();
+    assert_eq!(event.source, 7);
+    assert_eq!(event.clip, "walk");
+    assert_eq!(names(&mut events), vec!["hit", "end"]);
+
+    player.advance(1., &mut events);
+    assert!(events.is_empty());
+}
+
+#[test]
+fn player_looping() {
+    utils::init_logger();
+
+    let mut events = AnimationEvents::default();
+    let mut player = AnimationPlayer::new(1, "walk", 1., create_track()).with_looping(true);
+
+    player.advance(0.9, &mut events);
+    assert_eq!(names(&mut events), vec!["footstep_left", "footstep_right", "hit"]);
+    player.advance(0.2, &mut events);
+    assert_eq!(names(&mut events), vec!["end", "footstep_left"]);
+    assert!((player.time() - 0.1).abs() < 1e-5);
+
+    // multiple cycles in a single step
+    player.advance(2., &mut events);
+    assert_eq!(events.iter_named("hit").count(), 2);
+    assert_eq!(events.iter_named("footstep_left").count(), 2);
+    assert_eq!(events.len(), 8);
+    events.clear();
+
+    // reversed playback
+    player.set_speed(-1.);
+    player.seek(0.6);
+    player.advance(0.3, &mut events);
+    assert_eq!(names(&mut events), vec!["footstep_right"]);
+    player.advance(0.5, &mut events);
+    assert_eq!(names(&mut events), vec!["footstep_left", "end"]);
+    assert!((player.time() - 0.8).abs() < 1e-5);
+}
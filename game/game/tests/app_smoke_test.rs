@@ -0,0 +1,119 @@
+use shine_ecs::events::Events;
+use shine_game::{
+    app::{ScriptedKey, SmokeInputSource, SmokeTest, SmokeTestConfig, SmokeTestInput},
+    render::RenderError,
+};
+
+mod utils;
+
+fn args(args: &str) -> Vec<String> {
+    args.split_whitespace().map(|a| a.to_owned()).collect()
+}
+
+#[test]
+fn smoke_test_args() {
+    utils::init_logger();
+
+    assert_eq!(SmokeTestConfig::from_args(args("config.json")).unwrap(), None);
+
+    let config = SmokeTestConfig::from_args(args(
+        "config.json --smoke-test game://games/test/test1.g1 --frames 20 --seed 7 --screenshot out.png",
+    ))
+    .unwrap()
+    .unwrap();
+    assert_eq!(config.game, "game://games/test/test1.g1");
+    assert_eq!(config.frames, 20);
+    assert_eq!(config.screenshot.as_deref(), Some("out.png"));
+    assert!(matches!(config.input, SmokeTestInput::Random { seed: 7, .. }));
+
+    assert!(SmokeTestConfig::from_args(args("--smoke-test game://a --frames x")).is_err());
+    assert!(SmokeTestConfig::from_args(args("--smoke-test")).is_err());
+
+    let config = SmokeTestConfig::from_json(r#"{"game": "game://a", "input": {"Script": []}}"#).unwrap();
+    assert_eq!(config.frames, 300);
+    assert_eq!(config.input, SmokeTestInput::Script(Vec::new()));
+}
+
+#[test]
+fn smoke_test_input() {
+    utils::init_logger();
+
+    let script = vec![
+        ScriptedKey {
+            frame: 1,
+            scancode: 17,
+            pressed: true,
+        },
+        ScriptedKey {
+            frame: 3,
+            scancode: 17,
+            pressed: false,
+        },
+    ];
+    let mut source = SmokeInputSource::new(SmokeTestInput::Script(script.clone()));
+    let events: Vec<_> = (0..5).flat_map(|frame| source.events(frame)).collect();
+    assert_eq!(events, script);
+
+    // the random input is reproducible and the pressed keys are released
+    let random = SmokeTestInput::Random {
+        seed: 42,
+        scancodes: vec![17, 30, 31],
+    };
+    let mut first = SmokeInputSource::new(random.clone());
+    let mut second = SmokeInputSource::new(random);
+    let mut events: Vec<_> = (0..100).flat_map(|frame| first.events(frame)).collect();
+    assert_eq!(
+        events,
+        (0..100).flat_map(|frame| second.events(frame)).collect::<Vec<_>>()
+    );
+    assert!(events.iter().any(|e| e.pressed));
+    events.extend(first.release_all(100));
+    for scancode in &[17, 30, 31] {
+        let presses = events.iter().filter(|e| e.scancode == *scancode && e.pressed).count();
+        let releases = events.iter().filter(|e| e.scancode == *scancode && !e.pressed).count();
+        assert_eq!(presses, releases);
+    }
+}
+
+#[test]
+fn smoke_test_report() {
+    utils::init_logger();
+
+    let mut config = SmokeTestConfig::new("game://a");
+    config.frames = 3;
+    config.max_frame_time_ms = Some(10.);
+
+    let mut test = SmokeTest::new(config.clone());
+    let mut errors = Events::<RenderError>::default();
+    while !test.is_done() {
+        assert!(test.frame_input().is_empty());
+        test.record_frame(4. + test.frame() as f64, Some(&errors));
+    }
+    let report = test.finish();
+    assert_eq!(report.frames, 3);
+    assert_eq!(report.frame_time.min, 4.);
+    assert_eq!(report.frame_time.max, 6.);
+    assert_eq!(report.frame_time.average, 5.);
+    assert_eq!(report.exit_code(), 0);
+
+    // render errors and slow frames fail the test
+    let mut test = SmokeTest::new(config.clone());
+    test.record_frame(20., Some(&errors));
+    errors.send(RenderError::Technique {
+        message: "missing".to_owned(),
+    });
+    test.record_frame(20., Some(&errors));
+    test.record_frame(20., Some(&errors));
+    let report = test.finish();
+    assert_eq!(report.render_errors.len(), 1);
+    assert_eq!(report.failures.len(), 1);
+    assert_eq!(report.exit_code(), 1);
+
+    // failing to load stops the test
+    let mut test = SmokeTest::new(config);
+    test.fail("Failed to load game");
+    assert!(test.is_done());
+    let report = test.finish();
+    assert_eq!(report.failures, vec!["Failed to load game".to_owned()]);
+    assert_eq!(report.exit_code(), 1);
+}
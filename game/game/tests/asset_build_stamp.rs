@@ -0,0 +1,60 @@
+use shine_game::assets::{BuildStamp, BuildStampTracker, CookedFormat, Url};
+
+mod utils;
+
+fn stamp(cooker_version: &str) -> BuildStamp {
+    BuildStamp {
+        cooker_version: cooker_version.to_owned(),
+        shader_compiler: "shaderc".to_owned(),
+        texture_encoder: "image".to_owned(),
+        profile: "Release".to_owned(),
+    }
+}
+
+#[test]
+fn stamped_content_roundtrip() {
+    utils::init_logger();
+
+    let value = vec![1u32, 2, 3];
+    for format in &[CookedFormat::Binary, CookedFormat::Ron] {
+        let content = format.serialize(&value).unwrap();
+        let data = stamp("0.1.0").stamp(&content).unwrap();
+
+        assert_eq!(CookedFormat::detect(&data), *format);
+        let loaded: Vec<u32> = CookedFormat::deserialize(&data).unwrap();
+        assert_eq!(loaded, value);
+
+        let (loaded_stamp, loaded_content) = BuildStamp::split(&data).unwrap();
+        assert_eq!(loaded_stamp, Some(stamp("0.1.0")));
+        assert_eq!(loaded_content, &content[..]);
+    }
+
+    // content without stamp
+    let content = CookedFormat::Binary.serialize(&value).unwrap();
+    let (loaded_stamp, _) = BuildStamp::split(&content).unwrap();
+    assert!(loaded_stamp.is_none());
+    let loaded: Vec<u32> = CookedFormat::deserialize(&content).unwrap();
+    assert_eq!(loaded, value);
+}
+
+#[test]
+fn build_id_is_deterministic() {
+    utils::init_logger();
+
+    assert_eq!(stamp("0.1.0").build_id(), stamp("0.1.0").build_id());
+    assert_ne!(stamp("0.1.0").build_id(), stamp("0.2.0").build_id());
+}
+
+#[test]
+fn mixed_builds_are_detected() {
+    utils::init_logger();
+
+    let url = Url::parse("file://cooked/asset.tx").unwrap();
+    let tracker = BuildStampTracker::new();
+    let content = CookedFormat::Binary.serialize(&0u32).unwrap();
+
+    assert!(tracker.observe(&url, &stamp("0.1.0").stamp(&content).unwrap()));
+    assert!(tracker.observe(&url, &content));
+    assert!(tracker.observe(&url, &stamp("0.1.0").stamp(&content).unwrap()));
+    assert!(!tracker.observe(&url, &stamp("0.2.0").stamp(&content).unwrap()));
+}
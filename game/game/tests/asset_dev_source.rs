@@ -0,0 +1,24 @@
+#![cfg(feature = "cook")]
+use shine_game::assets::{AssetIO, CookedFormat, CookedShader, ShaderType, Url};
+use std::collections::HashMap;
+
+mod utils;
+
+#[tokio::test(threaded_scheduler)]
+async fn cook_shader_on_the_fly() {
+    utils::init_logger();
+
+    let io = AssetIO::new(HashMap::default()).unwrap();
+    let url = Url::parse("dev-source://hello.fs").unwrap();
+    assert!(io.download_binary(&url).await.is_err());
+
+    io.mount_dev_source(Url::parse("file://../assets/game_test/").unwrap());
+    let cooked = io.download_binary(&url).await.unwrap();
+    let shader: CookedShader = CookedFormat::deserialize(&cooked).unwrap();
+    assert_eq!(shader.shader_type, ShaderType::Fragment);
+    assert!(!shader.binary.is_empty());
+
+    // unchanged source is served from the cache
+    let hash = io.download_hash(&url).await.unwrap();
+    assert_eq!(hash.hash(), shine_game::assets::ContentHash::from_bytes(&cooked).hash());
+}
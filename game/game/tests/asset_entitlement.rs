@@ -0,0 +1,120 @@
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use shine_game::assets::{AssetError, AssetIO, EntitlementClaims, EntitlementVerifier, PremiumPack, Url};
+use std::collections::HashMap;
+
+mod utils;
+
+const ENCODE: data_encoding::Encoding = data_encoding::BASE64URL_NOPAD;
+
+fn key_pair() -> Ed25519KeyPair {
+    Ed25519KeyPair::from_seed_unchecked(&[7u8; 32]).unwrap()
+}
+
+fn sign(key: &Ed25519KeyPair, content: &[&str], exp: i64) -> String {
+    let claims = EntitlementClaims {
+        sub: "user".to_owned(),
+        content: content.iter().map(|c| (*c).to_owned()).collect(),
+        exp,
+    };
+    let payload = ENCODE.encode(&serde_json::to_vec(&claims).unwrap());
+    let signature = key.sign(payload.as_bytes());
+    format!("{}.{}", payload, ENCODE.encode(signature.as_ref()))
+}
+
+fn premium_io(key: &Ed25519KeyPair) -> AssetIO {
+    let mut premium_packs = HashMap::new();
+    premium_packs.insert(
+        "dlc1".to_owned(),
+        PremiumPack {
+            url: Url::parse("file://../assets/dlc1/").unwrap(),
+            content_id: "content-1".to_owned(),
+        },
+    );
+    premium_packs.insert(
+        "dlc2".to_owned(),
+        PremiumPack {
+            url: Url::parse("file://../assets/dlc2/").unwrap(),
+            content_id: "content-2".to_owned(),
+        },
+    );
+    let verifier = EntitlementVerifier::new(key.public_key().as_ref().to_vec());
+    AssetIO::with_premium_packs(HashMap::new(), premium_packs, Some(verifier)).unwrap()
+}
+
+#[test]
+fn verify_token() {
+    utils::init_logger();
+
+    let key = key_pair();
+    let verifier = EntitlementVerifier::new(key.public_key().as_ref().to_vec());
+
+    let token = sign(&key, &["content-1"], 100);
+    let claims = verifier.verify(&token, 50).unwrap();
+    assert_eq!(claims.sub, "user");
+    assert!(claims.has_content("content-1"));
+    assert!(!claims.has_content("content-2"));
+
+    assert!(matches!(
+        verifier.verify(&token, 100),
+        Err(AssetError::InvalidEntitlement(_))
+    ));
+
+    let forged = sign(&key, &["content-1", "content-2"], 100);
+    let tampered = format!(
+        "{}.{}",
+        forged.split('.').next().unwrap(),
+        token.split('.').nth(1).unwrap()
+    );
+    assert!(matches!(
+        verifier.verify(&tampered, 50),
+        Err(AssetError::InvalidEntitlement(_))
+    ));
+
+    let other_key = Ed25519KeyPair::from_seed_unchecked(&[8u8; 32]).unwrap();
+    let foreign = sign(&other_key, &["content-1"], 100);
+    assert!(matches!(
+        verifier.verify(&foreign, 50),
+        Err(AssetError::InvalidEntitlement(_))
+    ));
+}
+
+#[test]
+fn mount_premium_packs() {
+    utils::init_logger();
+
+    let key = key_pair();
+    let io = premium_io(&key);
+    let dlc1 = Url::parse("dlc1://models/ship.glb").unwrap();
+    let dlc2 = Url::parse("dlc2://models/ship.glb").unwrap();
+
+    assert!(matches!(
+        io.resolve_virtual_scheme(&dlc1),
+        Err(AssetError::NotEntitled(_))
+    ));
+
+    let exp = i64::MAX;
+    let mounted = io.mount_premium_packs(&sign(&key, &["content-1"], exp)).unwrap();
+    assert_eq!(mounted, vec!["dlc1".to_owned()]);
+    assert!(io.is_mounted("dlc1"));
+    assert!(io
+        .resolve_virtual_scheme(&dlc1)
+        .unwrap()
+        .as_str()
+        .ends_with("assets/dlc1/models/ship.glb"));
+    assert!(matches!(
+        io.resolve_virtual_scheme(&dlc2),
+        Err(AssetError::NotEntitled(_))
+    ));
+
+    // a token without the content unmounts the pack
+    let mounted = io.mount_premium_packs(&sign(&key, &["content-2"], exp)).unwrap();
+    assert_eq!(mounted, vec!["dlc2".to_owned()]);
+    assert!(!io.is_mounted("dlc1"));
+
+    // an expired token is rejected and the mounted packs are kept
+    assert!(io.mount_premium_packs(&sign(&key, &["content-1"], 1)).is_err());
+    assert!(io.is_mounted("dlc2"));
+
+    io.unmount_premium_packs();
+    assert!(!io.is_mounted("dlc2"));
+}
@@ -1,5 +1,6 @@
 #![cfg(feature = "cook")]
-use shine_game::assets::AssetId;
+use rand::{seq::SliceRandom, Rng};
+use shine_game::assets::{canonicalize_path, AssetId, Url};
 
 mod utils;
 
@@ -46,3 +47,87 @@ async fn test_asset_id() {
     test_extension("global/somet.hing/alma.", "");
     test_extension("global/somet.hing/alma", "");
 }
+
+fn test_canonical(src: &str, tgt: &str) {
+    assert_eq!(AssetId::new(src).unwrap().as_str(), tgt);
+}
+
+fn test_relative(base: &str, id: &str, tgt: &str) {
+    assert_eq!(AssetId::new(base).unwrap().create_relative(id).unwrap().as_str(), tgt);
+}
+
+#[tokio::test(threaded_scheduler)]
+async fn test_asset_id_canonical() {
+    utils::init_logger();
+
+    test_canonical("global/something/alma.txt", "global/something/alma.txt");
+    test_canonical("global//something/alma.txt", "global/something/alma.txt");
+    test_canonical("global\\something\\alma.txt", "global/something/alma.txt");
+    test_canonical("global/./something/alma.txt", "global/something/alma.txt");
+    test_canonical("global/other/../something/alma.txt", "global/something/alma.txt");
+    test_canonical("global/something/alma.TXT", "global/something/alma.txt");
+    test_canonical("Global/Something/Alma.Txt", "Global/Something/Alma.txt");
+    test_canonical("/global/something/alma.txt", "/global/something/alma.txt");
+    test_canonical("shader://global//something/../alma.FS", "shader://global/alma.fs");
+
+    assert!(AssetId::new("../alma.txt").is_err());
+    assert!(AssetId::new("./alma.txt").is_err());
+    assert!(AssetId::new("global/../../alma.txt").is_err());
+    assert!(AssetId::new("/global/../../alma.txt").is_err());
+
+    test_relative("global/something/alma.txt", "./korte.txt", "global/something/korte.txt");
+    test_relative("global/something/alma.txt", "../korte.txt", "global/korte.txt");
+    test_relative("global/something/alma.txt", "other/korte.txt", "other/korte.txt");
+    test_relative("alma.txt", "./korte.txt", "korte.txt");
+    assert!(AssetId::new("alma.txt")
+        .unwrap()
+        .create_relative("../korte.txt")
+        .is_err());
+
+    let scope = AssetId::new("global/something").unwrap();
+    let id = AssetId::new("global/something/other/alma.txt").unwrap();
+    assert_eq!(id.scope_relative(&scope), Some("other/alma.txt"));
+    assert_eq!(scope.scope_relative(&id), None);
+
+    let url = Url::parse("shader://global//something/../alma.FS").unwrap();
+    assert_eq!(url.to_canonical().unwrap().as_str(), "shader://global/alma.fs");
+}
+
+#[tokio::test(threaded_scheduler)]
+async fn test_asset_id_canonical_property() {
+    utils::init_logger();
+
+    let names = ["a", "bb", "c.txt", "D.PNG", "e.tar.gz"];
+    let mut rng = rand::thread_rng();
+
+    for _ in 0..1000 {
+        // generate a random path with noise segments that should not alter the canonical form
+        let len = rng.gen_range(1..6);
+        let segments: Vec<&str> = (0..len).map(|_| *names.choose(&mut rng).unwrap()).collect();
+        let mut noisy = Vec::new();
+        for (i, segment) in segments.iter().enumerate() {
+            let noise = if i > 0 { rng.gen_range(0..4) } else { 3 };
+            match noise {
+                0 => noisy.push(".".to_owned()),
+                1 => {
+                    noisy.push("x".to_owned());
+                    noisy.push("..".to_owned());
+                }
+                2 => noisy.push("".to_owned()),
+                _ => {}
+            }
+            noisy.push(segment.to_string());
+        }
+        let separator = if rng.gen() { "/" } else { "\\" };
+        let clean = segments.join("/");
+        let noisy = noisy.join(separator);
+
+        let canonical = canonicalize_path(&clean).unwrap();
+        assert_eq!(canonicalize_path(&noisy).unwrap(), canonical, "{} vs {}", noisy, clean);
+        // idempotent
+        assert_eq!(canonicalize_path(&canonical).unwrap(), canonical);
+        // only the extension is lowered
+        assert_eq!(canonical.to_lowercase(), clean.to_lowercase());
+        assert_eq!(AssetId::new(&noisy).unwrap(), AssetId::new(&clean).unwrap());
+    }
+}
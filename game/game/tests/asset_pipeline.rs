@@ -1,5 +1,5 @@
 #![cfg(feature = "cook")]
-use shine_game::assets::{cooker, AssetIO, AssetId, ContentHash, PipelineSource, Url};
+use shine_game::assets::{cooker, AssetIO, AssetId, ColorStage, ContentHash, PipelineSource, Url};
 use std::collections::HashMap;
 
 mod utils;
@@ -26,6 +26,10 @@ async fn load_pipeline() {
         "39d34ac02c5543b2f379acc4f7f2f03b994be11297b533bc3e75e087b1247448"
     );
 
+    assert_eq!(source.descriptor.color_stage, ColorStage::Replace);
+    assert!(source.descriptor.is_depth_write_enabled(ColorStage::Replace));
+    assert!(!source.descriptor.is_depth_write_enabled(ColorStage::AlphaBlend));
+
     let cooked = source.cook(cooker::DummyCooker).await.unwrap();
     log::debug!("cooked descriptor: {:#?}", cooked.descriptor);
     let cooked_hash = ContentHash::from_bytes(&bincode::serialize(&cooked).unwrap());
@@ -39,6 +43,6 @@ async fn load_pipeline() {
     );
     assert_eq!(
         cooked_hash.hash(),
-        "9cfcff89ca030161cbf52abbaf186c24e8ef40e526a4f325e640a7e170c607e3"
+        "a129116ea9ff9dc388c659028dedaa4cae90c0c3a0bd8b84c25f40114a2b878b"
     );
 }
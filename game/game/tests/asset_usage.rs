@@ -0,0 +1,87 @@
+use shine_game::assets::{AssetUsageStats, AssetUsageTracker, Url};
+
+mod utils;
+
+fn url(url: &str) -> Url {
+    Url::parse(url).unwrap()
+}
+
+#[test]
+fn usage_tracking() {
+    utils::init_logger();
+
+    let tracker = AssetUsageTracker::new();
+    // nothing is recorded until enabled
+    tracker.observe(&url("game://a.tx"), 100, 10);
+    assert!(tracker.take_report().is_empty());
+
+    tracker.enable(url("http://localhost:8090/usage/report"));
+    assert!(tracker.is_enabled());
+    tracker.observe(&url("game://b.md"), 200, 30);
+    tracker.observe(&url("game://a.tx"), 100, 10);
+    tracker.observe(&url("game://b.md"), 200, 50);
+    tracker.set_build_id(Some("build".to_owned()));
+
+    let report = tracker.take_report();
+    assert_eq!(report.build_id.as_deref(), Some("build"));
+    assert_eq!(report.assets.len(), 2);
+    let b = &report.assets["game://b.md"];
+    assert_eq!((b.loads, b.bytes, b.total_load_time, b.max_load_time), (2, 200, 80, 50));
+    assert_eq!(b.first_load, 0);
+    assert_eq!(report.assets["game://a.tx"].first_load, 1);
+
+    // the statistics are restarted by the report
+    assert!(tracker.take_report().is_empty());
+}
+
+#[test]
+fn usage_aggregation() {
+    utils::init_logger();
+
+    let sessions = [
+        vec![
+            ("game://shader.vs", 10),
+            ("game://common.tx", 400),
+            ("game://level1.md", 900),
+        ],
+        vec![
+            ("game://common.tx", 300),
+            ("game://shader.vs", 10),
+            ("game://level2.md", 100),
+        ],
+        vec![("game://shader.vs", 20), ("game://common.tx", 200)],
+    ];
+
+    let mut stats = AssetUsageStats::default();
+    for session in &sessions {
+        let tracker = AssetUsageTracker::new();
+        tracker.enable(url("http://localhost:8090/usage/report"));
+        for (asset, load_time) in session {
+            tracker.observe(&url(asset), 64, *load_time);
+        }
+        let report = tracker.take_report();
+        let json = serde_json::to_string(&report).unwrap();
+        stats.merge(&serde_json::from_str(&json).unwrap());
+    }
+
+    assert_eq!(stats.sessions, 3);
+    let common = &stats.assets["game://common.tx"];
+    assert_eq!((common.sessions, common.loads), (3, 3));
+    assert_eq!(common.average_load_time(), 300);
+    assert_eq!(common.max_load_time, 400);
+
+    // used by all the sessions, the shader is usually requested first
+    assert_eq!(
+        stats.preload_order(),
+        vec![
+            "game://shader.vs".to_owned(),
+            "game://common.tx".to_owned(),
+            "game://level1.md".to_owned(),
+            "game://level2.md".to_owned(),
+        ]
+    );
+
+    let by_load_time = stats.by_load_time();
+    assert_eq!(by_load_time[0].0, "game://common.tx");
+    assert_eq!(by_load_time[1].0, "game://level1.md");
+}
@@ -0,0 +1,107 @@
+use nalgebra::{Isometry3, Point3, Vector3};
+use shine_game::render::{ClusterConfig, ClusterGrid, Light};
+
+mod utils;
+
+fn point_light(x: f32, y: f32, z: f32, range: f32) -> Light {
+    Light::Point {
+        position: Point3::new(x, y, z),
+        range,
+        color: [1., 1., 1.],
+        intensity: 1.,
+    }
+}
+
+fn create_grid() -> ClusterGrid {
+    let config = ClusterConfig {
+        tiles_x: 4,
+        tiles_y: 4,
+        slices_z: 8,
+        max_lights_per_cluster: 16,
+    };
+    ClusterGrid::new(config, std::f32::consts::FRAC_PI_2, 1., 0.1, 100.)
+}
+
+#[test]
+fn cluster_grid_bounds() {
+    utils::init_logger();
+
+    let grid = create_grid();
+    assert_eq!(grid.cluster_count(), 4 * 4 * 8);
+    assert_eq!(grid.slice_of_depth(0.05), 0);
+    assert_eq!(grid.slice_of_depth(0.1), 0);
+    assert_eq!(grid.slice_of_depth(99.), 7);
+    assert_eq!(grid.slice_of_depth(1000.), 7);
+
+    for bounds in grid.bounds() {
+        assert!(bounds.min.x < bounds.max.x);
+        assert!(bounds.min.y < bounds.max.y);
+        assert!(bounds.min.z < bounds.max.z);
+        assert!(bounds.max.z <= -0.1 + 1e-4);
+    }
+}
+
+#[test]
+fn cluster_assign_lights() {
+    utils::init_logger();
+
+    let grid = create_grid();
+    let view = Isometry3::identity();
+
+    let lights = vec![
+        // in front of the camera, small
+        point_light(0.1, 0.1, -5., 0.5),
+        // behind the camera
+        point_light(0., 0., 10., 1.),
+        // covering the whole near region
+        point_light(0., 0., 0., 1.),
+        Light::Spot {
+            position: Point3::new(0., 0., -1.),
+            direction: Vector3::new(0., 0., -1.),
+            range: 10.,
+            angle: 0.3,
+            color: [1., 0., 0.],
+            intensity: 2.,
+        },
+    ];
+    let result = grid.assign(&view, &lights);
+
+    assert_eq!(result.lights.len(), 4);
+    assert_eq!(result.clusters.len(), grid.cluster_count());
+    assert_eq!(result.stats.light_count, 4);
+    assert_eq!(result.stats.light_index_count, result.light_indices.len());
+    assert_eq!(result.stats.dropped_lights, 0);
+    assert!(result.stats.occupied_clusters > 0);
+
+    // the light behind the camera is not assigned
+    assert!(!result.light_indices.contains(&1));
+
+    // the small light is in the cluster containing its center
+    let z = grid.slice_of_depth(5.);
+    let id = grid.cluster_index(2, 2, z);
+    let [offset, count] = result.clusters[id];
+    let indices = &result.light_indices[offset as usize..(offset + count) as usize];
+    assert!(indices.contains(&0));
+    assert!(indices.contains(&3));
+
+    // offsets are continuous
+    let mut expected_offset = 0;
+    for [offset, count] in &result.clusters {
+        assert_eq!(*offset, expected_offset);
+        expected_offset += count;
+    }
+}
+
+#[test]
+fn cluster_light_limit() {
+    utils::init_logger();
+
+    let grid = create_grid();
+    let view = Isometry3::identity();
+    let lights = (0..32).map(|_| point_light(0., 0., -5., 100.)).collect::<Vec<_>>();
+    let result = grid.assign(&view, &lights);
+
+    assert_eq!(result.stats.max_lights_per_cluster, 16);
+    assert!(result.stats.dropped_lights > 0);
+    assert!(result.clusters.iter().all(|[_, count]| *count <= 16));
+}
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use shine_ecs::{scheduler::IntoSystem, testing::TestWorld};
+use shine_game::{
+    animation::{AnimationEvent, AnimationEvents},
+    event_bus::{bridge_animation_events, DynamicEvent, EventBus},
+};
+
+mod utils;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Score {
+    player: String,
+    points: u32,
+}
+
+#[test]
+fn emit_and_read() {
+    utils::init_logger();
+
+    let mut bus = EventBus::default();
+    bus.emit_typed(
+        "score",
+        &Score {
+            player: "alice".to_owned(),
+            points: 10,
+        },
+    )
+    .unwrap();
+    bus.emit_json("score", r#"{"player": "bob", "points": 3}"#).unwrap();
+    bus.emit("door", json!({"open": true}));
+    assert!(bus.emit_json("door", "{invalid").is_err());
+    assert_eq!(bus.len(), 3);
+
+    let scores = bus.iter_typed::<Score>("score").collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(scores.iter().map(|s| s.points).collect::<Vec<_>>(), vec![10, 3]);
+    assert!(bus.iter_typed::<Score>("door").next().unwrap().is_err());
+
+    bus.clear();
+    assert!(bus.is_empty());
+}
+
+#[test]
+fn external_listeners() {
+    utils::init_logger();
+
+    let mut bus = EventBus::default();
+    bus.listen("door");
+    bus.emit("door", json!({"open": true}));
+    bus.emit("score", json!(1));
+    bus.clear();
+
+    assert_eq!(
+        bus.drain_outgoing(),
+        vec![DynamicEvent {
+            name: "door".to_owned(),
+            payload: json!({"open": true})
+        }]
+    );
+    assert!(bus.drain_outgoing().is_empty());
+
+    bus.emit("door", json!(null));
+    assert_eq!(
+        bus.drain_outgoing_json().unwrap(),
+        r#"[{"name":"door","payload":null}]"#
+    );
+
+    bus.emit("door", json!(null));
+    bus.unlisten("door");
+    assert!(!bus.is_listened("door"));
+    assert!(bus.drain_outgoing().is_empty());
+}
+
+#[test]
+fn animation_bridge() {
+    utils::init_logger();
+
+    let mut events = AnimationEvents::default();
+    events.push(7, "walk", "footstep", 0.5);
+
+    let mut world = TestWorld::new()
+        .with_resource(events)
+        .with_resource(EventBus::default());
+    world.run_system(bridge_animation_events.into_system()).unwrap();
+
+    let bus = world.get::<EventBus>();
+    let forwarded = bus
+        .iter_typed::<AnimationEvent>("animation.footstep")
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(
+        forwarded,
+        vec![AnimationEvent {
+            source: 7,
+            clip: "walk".to_owned(),
+            name: "footstep".to_owned(),
+            time: 0.5
+        }]
+    );
+}
@@ -1,6 +1,6 @@
 #![cfg(feature = "cook")]
 use shine_game::{
-    assets::{cooker, AssetIO, AssetId, ContentHash, Url},
+    assets::{cooker, AssetIO, AssetId, ContentHash, CookedFormat, Url},
     game::test1,
 };
 use std::collections::HashMap;
@@ -26,10 +26,11 @@ async fn load_test1() {
 
     let cooked = source.cook(cooker::DummyCooker).await.unwrap();
     assert_eq!(cooked.pipeline, "hash-pipeline://b128/e929af683c6b4ce763dbffb94124.pl");
+    assert!(cooked.techniques.is_empty());
     let cooked_hash = ContentHash::from_bytes(&bincode::serialize(&cooked).unwrap());
     assert_eq!(
         cooked_hash.hash(),
-        "9a779d2420837d31c57a83d57e28aba671d45c34fa1006189a688b023c729e94"
+        "022494fe4f1139edb43d91eaf3d4d24d3e1c7c3d85260cea96ab6db914465956"
     );
 }
 
@@ -50,3 +51,55 @@ async fn load_test1_invalid() {
         .unwrap_err();
     assert!(format!("{:?}", err).contains("unknown variant `Test2`, expected `Test1`"));
 }
+
+#[tokio::test(threaded_scheduler)]
+async fn cooked_format_test1() {
+    utils::init_logger();
+
+    let source_root = Url::parse("file://../assets/game_test/").unwrap();
+    let virtual_schemes = HashMap::default();
+    let io = AssetIO::new(virtual_schemes).unwrap();
+
+    let id = AssetId::new("test1.game").unwrap();
+    let source_url = id.to_url(&source_root).unwrap();
+
+    let (source, _) = test1::Source::load(&io, &id, &source_url).await.unwrap();
+    let cooked = source.cook(cooker::DummyCooker).await.unwrap();
+
+    for format in &[CookedFormat::Binary, CookedFormat::Ron] {
+        let data = format.serialize(&cooked).unwrap();
+        assert_eq!(CookedFormat::detect(&data), *format);
+        let loaded: test1::Test1 = CookedFormat::deserialize(&data).unwrap();
+        assert_eq!(loaded.pipeline, cooked.pipeline);
+    }
+
+    let data = CookedFormat::Ron.serialize(&cooked).unwrap();
+    assert!(std::str::from_utf8(&data).unwrap().contains(&cooked.pipeline));
+}
+
+#[test]
+fn test1_techniques() {
+    utils::init_logger();
+
+    let source = r#"{
+        "type": "Test1",
+        "pipeline": "hello.pl",
+        "techniques": [
+            { "technique": "test1", "options": { "pipeline": "other.pl", "quality": "2" } },
+            { "technique": "overlay" }
+        ]
+    }"#;
+    let game: test1::Test1 = serde_json::from_str(source).unwrap();
+    assert_eq!(game.techniques.len(), 2);
+    assert_eq!(game.techniques[0].technique, "test1");
+    assert_eq!(game.techniques[0].option("pipeline"), Some("other.pl"));
+    assert_eq!(game.techniques[0].parse_option::<u32>("quality", 0).unwrap(), 2);
+    assert_eq!(game.techniques[1].technique, "overlay");
+    assert_eq!(game.techniques[1].parse_option::<u32>("quality", 1).unwrap(), 1);
+    assert!(game.techniques[0].parse_option::<bool>("quality", false).is_err());
+
+    let data = CookedFormat::Binary.serialize(&game).unwrap();
+    let loaded: test1::Test1 = CookedFormat::deserialize(&data).unwrap();
+    assert_eq!(loaded.techniques.len(), 2);
+    assert_eq!(loaded.techniques[0].option("quality"), Some("2"));
+}
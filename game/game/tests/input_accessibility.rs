@@ -0,0 +1,170 @@
+use shine_game::input::{
+    AccessibilityConfig, ActionBinding, ActionKind, InputConfig, InputContextConfig, InputContexts, GAMEPLAY_CONTEXT,
+};
+use shine_input::{AccessibilityOptions, AccessibilityProcessor, InputId, InputState, InputValue, RepeatRate};
+
+mod utils;
+
+const MS: u128 = 1000;
+
+fn pressed(state: &InputState, id: InputId) -> bool {
+    state.get_input(id) == InputValue::D0
+}
+
+#[test]
+fn hold_to_toggle() {
+    utils::init_logger();
+
+    let run = InputId::new(1);
+    let mut processor = AccessibilityProcessor::new(AccessibilityOptions {
+        toggle_inputs: vec![run].into_iter().collect(),
+        ..Default::default()
+    });
+
+    // the raw state is provided by the input manager, the processed state is computed from it
+    let process = |processor: &mut AccessibilityProcessor, raw: bool, time: u128| {
+        let mut state = InputState::default();
+        if raw {
+            state.set_input(run, InputValue::D0, false);
+        }
+        processor.process(&mut state, time);
+        pressed(&state, run)
+    };
+
+    assert!(process(&mut processor, true, 0));
+    assert!(process(&mut processor, false, 10 * MS));
+    assert!(process(&mut processor, false, 20 * MS));
+    assert!(!process(&mut processor, true, 30 * MS));
+    assert!(!process(&mut processor, false, 40 * MS));
+    assert!(process(&mut processor, true, 50 * MS));
+
+    processor.release_inputs(&[run]);
+    assert!(!process(&mut processor, false, 60 * MS));
+}
+
+#[test]
+fn sticky_modifiers() {
+    utils::init_logger();
+
+    let shift = InputId::new(1);
+    let key = InputId::new(2);
+    let mut processor = AccessibilityProcessor::new(AccessibilityOptions {
+        sticky_modifiers: vec![shift].into_iter().collect(),
+        ..Default::default()
+    });
+
+    let mut process = |raw: &[InputId]| {
+        let mut state = InputState::default();
+        for id in raw {
+            state.set_input(*id, InputValue::D0, false);
+        }
+        processor.process(&mut state, 0);
+        (pressed(&state, shift), pressed(&state, key))
+    };
+
+    assert_eq!(process(&[shift]), (true, false));
+    // released modifier is latched
+    assert_eq!(process(&[]), (true, false));
+    assert_eq!(process(&[]), (true, false));
+    // the next key press is combined with the modifier and releases the latch
+    assert_eq!(process(&[key]), (true, true));
+    assert_eq!(process(&[key]), (false, true));
+    assert_eq!(process(&[]), (false, false));
+}
+
+#[test]
+fn repeat_rate() {
+    utils::init_logger();
+
+    let down = InputId::new(1);
+    let mut processor = AccessibilityProcessor::new(AccessibilityOptions {
+        repeat_inputs: vec![down].into_iter().collect(),
+        repeat_rate: RepeatRate {
+            delay: 300 * MS,
+            interval: 100 * MS,
+        },
+        ..Default::default()
+    });
+
+    let mut process = |raw: bool, time: u128| {
+        let mut state = InputState::default();
+        if raw {
+            state.set_input(down, InputValue::D0, false);
+        }
+        processor.process(&mut state, time);
+        pressed(&state, down)
+    };
+
+    assert!(process(true, 0));
+    assert!(!process(true, 100 * MS));
+    assert!(!process(true, 200 * MS));
+    assert!(process(true, 300 * MS));
+    assert!(!process(true, 350 * MS));
+    assert!(process(true, 400 * MS));
+    assert!(!process(false, 450 * MS));
+    // a new press is reported immediately
+    assert!(process(true, 460 * MS));
+    assert!(!process(true, 500 * MS));
+}
+
+#[test]
+fn sensitivity() {
+    utils::init_logger();
+
+    let look = InputId::new(1);
+    let cursor = InputId::new(2);
+    let mut processor = AccessibilityProcessor::new(AccessibilityOptions {
+        sensitivity: 2.,
+        absolute_inputs: vec![cursor].into_iter().collect(),
+        ..Default::default()
+    });
+
+    let mut state = InputState::default();
+    state.set_input(look, InputValue::D2(0.25, -0.5), true);
+    state.set_input(cursor, InputValue::D2(0.25, -0.5), false);
+    processor.process(&mut state, 0);
+    assert_eq!(state.get_input(look), InputValue::D2(0.5, -1.));
+    assert_eq!(state.get_input(cursor), InputValue::D2(0.25, -0.5));
+}
+
+#[test]
+fn accessibility_from_config() {
+    utils::init_logger();
+
+    let binding = |action: &str, key: u32, kind: ActionKind| ActionBinding {
+        action: action.to_owned(),
+        keys: vec![key],
+        kind,
+    };
+    let config = InputConfig {
+        contexts: vec![InputContextConfig {
+            name: GAMEPLAY_CONTEXT.to_owned(),
+            policy: Default::default(),
+            actions: vec![
+                binding("fire", 1, ActionKind::Press),
+                binding("aim", 2, ActionKind::Hold),
+                binding("crouch", 3, ActionKind::Modifier),
+                binding("next", 4, ActionKind::Repeat),
+            ],
+        }],
+        initial_stack: vec![GAMEPLAY_CONTEXT.to_owned()],
+        accessibility: AccessibilityConfig {
+            hold_to_toggle: true,
+            sticky_modifiers: false,
+            sensitivity: 0.5,
+            repeat_delay_ms: 250,
+            repeat_interval_ms: 50,
+        },
+    };
+
+    let contexts = InputContexts::from_config(&config).unwrap();
+    let id = |action: &str| contexts.action_id(GAMEPLAY_CONTEXT, action).unwrap();
+    let options = contexts.accessibility();
+    assert!(options.toggle_inputs.contains(&id("aim")));
+    assert!(!options.toggle_inputs.contains(&id("fire")));
+    assert!(options.sticky_modifiers.is_empty());
+    assert!(options.repeat_inputs.contains(&id("next")));
+    assert_eq!(options.sensitivity, 0.5);
+    assert_eq!(options.repeat_rate.delay, 250 * MS);
+    assert_eq!(options.repeat_rate.interval, 50 * MS);
+}
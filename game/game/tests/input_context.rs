@@ -0,0 +1,114 @@
+use shine_game::input::{
+    ActionBinding, ActionKind, ContextPolicy, InputConfig, InputContextConfig, InputContexts, GAMEPLAY_CONTEXT,
+    MENU_CONTEXT, TEXT_ENTRY_CONTEXT,
+};
+use shine_input::InputState;
+
+mod utils;
+
+const KEY_W: u32 = 17;
+const KEY_S: u32 = 31;
+const KEY_M: u32 = 50;
+
+fn binding(action: &str, keys: &[u32]) -> ActionBinding {
+    ActionBinding {
+        action: action.to_owned(),
+        keys: keys.to_vec(),
+        kind: ActionKind::Press,
+    }
+}
+
+fn test_config() -> InputConfig {
+    InputConfig {
+        contexts: vec![
+            InputContextConfig {
+                name: GAMEPLAY_CONTEXT.to_owned(),
+                policy: ContextPolicy::PassThrough,
+                actions: vec![binding("forward", &[KEY_W]), binding("backward", &[KEY_S])],
+            },
+            InputContextConfig {
+                name: MENU_CONTEXT.to_owned(),
+                policy: ContextPolicy::Consume,
+                actions: vec![binding("up", &[KEY_W])],
+            },
+            InputContextConfig {
+                name: TEXT_ENTRY_CONTEXT.to_owned(),
+                policy: ContextPolicy::PassThrough,
+                actions: vec![binding("submit", &[KEY_M])],
+            },
+        ],
+        initial_stack: vec![GAMEPLAY_CONTEXT.to_owned()],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn input_context_config() {
+    utils::init_logger();
+
+    let contexts = InputContexts::from_config(&test_config()).unwrap();
+    assert_eq!(contexts.top(), Some(GAMEPLAY_CONTEXT));
+    assert!(contexts.action_id(GAMEPLAY_CONTEXT, "forward").is_some());
+    assert_ne!(
+        contexts.action_id(GAMEPLAY_CONTEXT, "forward"),
+        contexts.action_id(MENU_CONTEXT, "up")
+    );
+    assert!(contexts.action_id(MENU_CONTEXT, "forward").is_none());
+
+    let mut config = test_config();
+    config.initial_stack = vec!["vehicle".to_owned()];
+    assert!(InputContexts::from_config(&config).is_err());
+
+    let mut config = test_config();
+    config.contexts[0].actions.push(binding("forward", &[KEY_M]));
+    assert!(InputContexts::from_config(&config).is_err());
+
+    let config: InputConfig =
+        serde_json::from_str(r#"{"contexts": [{"name": "gameplay", "actions": [{"action": "jump", "keys": [57]}]}]}"#)
+            .unwrap();
+    assert_eq!(config.contexts[0].policy, ContextPolicy::PassThrough);
+    assert!(config.initial_stack.is_empty());
+}
+
+#[test]
+fn input_context_routing() {
+    utils::init_logger();
+
+    let mut contexts = InputContexts::from_config(&test_config()).unwrap();
+    let mut state = InputState::default();
+
+    // gameplay handles its keys, the unbound keys reach the mapper
+    assert!(contexts.route_key(KEY_W, true, &mut state));
+    assert!(contexts.is_action_active(&state, GAMEPLAY_CONTEXT, "forward"));
+    assert!(!contexts.route_key(KEY_M, true, &mut state));
+
+    // menu consumes everything, the held gameplay key is released
+    contexts.push(MENU_CONTEXT).unwrap();
+    contexts.release_inactive(&mut state);
+    assert!(!contexts.is_action_active(&state, GAMEPLAY_CONTEXT, "forward"));
+    assert!(contexts.route_key(KEY_S, true, &mut state));
+    assert!(!contexts.is_action_active(&state, GAMEPLAY_CONTEXT, "backward"));
+    assert!(contexts.route_key(KEY_W, true, &mut state));
+    assert!(contexts.is_action_active(&state, MENU_CONTEXT, "up"));
+    assert!(contexts.route_key(KEY_M, true, &mut state));
+
+    // leaving the menu releases its actions
+    assert_eq!(contexts.pop().as_deref(), Some(MENU_CONTEXT));
+    contexts.release_inactive(&mut state);
+    assert!(!contexts.is_action_active(&state, MENU_CONTEXT, "up"));
+
+    // pass through context handles only its own keys
+    contexts.push(TEXT_ENTRY_CONTEXT).unwrap();
+    assert!(contexts.route_key(KEY_M, true, &mut state));
+    assert!(contexts.is_action_active(&state, TEXT_ENTRY_CONTEXT, "submit"));
+    assert!(contexts.route_key(KEY_S, true, &mut state));
+    assert!(contexts.is_action_active(&state, GAMEPLAY_CONTEXT, "backward"));
+    assert!(contexts.route_key(KEY_S, false, &mut state));
+    assert!(!contexts.is_action_active(&state, GAMEPLAY_CONTEXT, "backward"));
+
+    // switching the stack releases the actions
+    contexts.set_stack(vec![MENU_CONTEXT.to_owned()]).unwrap();
+    contexts.release_inactive(&mut state);
+    assert!(!contexts.is_action_active(&state, TEXT_ENTRY_CONTEXT, "submit"));
+    assert!(contexts.push("unknown").is_err());
+}
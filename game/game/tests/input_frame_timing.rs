@@ -0,0 +1,66 @@
+use shine_game::input::{FrameTiming, LatencyStats};
+
+mod utils;
+
+#[test]
+fn latency_stats() {
+    utils::init_logger();
+
+    let mut stats = LatencyStats::default();
+    stats.add(100);
+    assert_eq!(stats.count, 1);
+    assert_eq!((stats.min, stats.max, stats.last), (100, 100, 100));
+    assert!((stats.average - 100.).abs() < 1e-6);
+
+    stats.add(300);
+    stats.add(50);
+    assert_eq!(stats.count, 3);
+    assert_eq!((stats.min, stats.max, stats.last), (50, 300, 50));
+    assert!(stats.average > 50. && stats.average < 300.);
+
+    stats.reset();
+    assert_eq!(stats.count, 0);
+}
+
+#[test]
+fn event_to_present() {
+    utils::init_logger();
+
+    let mut timing = FrameTiming::default();
+
+    // event captured at 1000, simulated at 1500, presented at 4000
+    timing.record_simulation(Some(1000), 1500);
+    timing.record_present(4000);
+    assert_eq!(timing.event_to_simulation.last, 500);
+    assert_eq!(timing.simulation_to_present.last, 2500);
+    assert_eq!(timing.event_to_present.last, 3000);
+    assert_eq!(timing.frame_time.count, 0);
+
+    // frame without input updates only the simulation and frame stats
+    timing.record_simulation(None, 5000);
+    timing.record_present(6000);
+    assert_eq!(timing.event_to_present.count, 1);
+    assert_eq!(timing.simulation_to_present.last, 1000);
+    assert_eq!(timing.frame_time.last, 2000);
+}
+
+#[test]
+fn multiple_simulations_per_frame() {
+    utils::init_logger();
+
+    let mut timing = FrameTiming::default();
+
+    // the oldest event and the first simulation step of the frame are measured
+    timing.record_simulation(Some(2000), 2100);
+    timing.record_simulation(Some(1000), 2200);
+    timing.record_present(3000);
+    assert_eq!(timing.event_to_simulation.count, 2);
+    assert_eq!(timing.event_to_simulation.max, 1200);
+    assert_eq!(timing.simulation_to_present.last, 900);
+    assert_eq!(timing.event_to_present.last, 2000);
+
+    // presenting the same frame again records no latency
+    timing.record_present(3500);
+    assert_eq!(timing.event_to_present.count, 1);
+    assert_eq!(timing.simulation_to_present.count, 1);
+}
@@ -0,0 +1,72 @@
+use shine_game::input::{HapticChannel, HapticEffect, Haptics, Rumble};
+use std::time::Duration;
+
+mod utils;
+
+#[test]
+fn haptics_merge() {
+    utils::init_logger();
+
+    let mut haptics = Haptics::default();
+    haptics.play(
+        0,
+        HapticEffect::new(Duration::from_millis(100), 0.5, HapticChannel::Strong),
+    );
+    haptics.play(
+        0,
+        HapticEffect::new(Duration::from_millis(300), 0.25, HapticChannel::Both),
+    );
+    haptics.play(1, HapticEffect::new(Duration::from_millis(50), 2., HapticChannel::Weak));
+
+    let rumbles = haptics.take_rumbles();
+    assert_eq!(
+        rumbles,
+        vec![
+            Rumble {
+                gamepad: 0,
+                strong: 0.5,
+                weak: 0.25,
+                duration: Duration::from_millis(300)
+            },
+            Rumble {
+                gamepad: 1,
+                strong: 0.,
+                weak: 1.,
+                duration: Duration::from_millis(50)
+            },
+        ]
+    );
+    assert!(haptics.take_rumbles().is_empty());
+}
+
+#[test]
+fn haptics_settings() {
+    utils::init_logger();
+
+    let mut haptics = Haptics::default();
+    haptics.set_intensity(0.5);
+    haptics.play(
+        0,
+        HapticEffect::new(Duration::from_millis(100), 1., HapticChannel::Both),
+    );
+    let rumbles = haptics.take_rumbles();
+    assert_eq!(rumbles[0].strong, 0.5);
+    assert_eq!(rumbles[0].weak, 0.5);
+
+    // stop drops the pending effects of the gamepad
+    haptics.play(
+        0,
+        HapticEffect::new(Duration::from_millis(100), 1., HapticChannel::Both),
+    );
+    haptics.stop(0);
+    let rumbles = haptics.take_rumbles();
+    assert_eq!(rumbles.len(), 1);
+    assert_eq!(rumbles[0].duration, Duration::from_secs(0));
+
+    haptics.set_enabled(false);
+    haptics.play(
+        0,
+        HapticEffect::new(Duration::from_millis(100), 1., HapticChannel::Both),
+    );
+    assert!(haptics.take_rumbles().is_empty());
+}
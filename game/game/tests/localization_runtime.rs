@@ -0,0 +1,124 @@
+use shine_game::localization::{FontConfig, FontFallback, Locale, Localization, LocalizationError, Script};
+use std::collections::HashSet;
+
+mod utils;
+
+fn font_config() -> FontConfig {
+    let fallback = |font: &str, scripts: &[Script], languages: &[&str]| FontFallback {
+        font: font.to_owned(),
+        scripts: scripts.to_vec(),
+        languages: languages.iter().map(|lang| (*lang).to_owned()).collect(),
+    };
+    FontConfig {
+        primary: "font://latin.ttf".to_owned(),
+        primary_scripts: vec![Script::Latin, Script::Greek, Script::Cyrillic],
+        fallbacks: vec![
+            fallback("font://cjk-sc.ttf", &[Script::Han], &["zh"]),
+            fallback("font://cjk-jp.ttf", &[Script::Han, Script::Kana], &["ja"]),
+            fallback("font://cjk.ttf", &[Script::Han, Script::Kana, Script::Hangul], &[]),
+        ],
+    }
+}
+
+#[test]
+fn locale_parse() {
+    utils::init_logger();
+
+    let locale = Locale::parse(
+        "HU",
+        "# comment\nhello = Szia { $name }!\nmulti = first\n  second\n\nunknown = { $missing } {x",
+    )
+    .unwrap();
+    assert_eq!(locale.language(), "hu");
+    assert_eq!(locale.len(), 3);
+    assert_eq!(locale.get("multi"), Some("first\nsecond"));
+    assert_eq!(
+        Locale::format(locale.get("hello").unwrap(), &[("name", "Bob")]),
+        "Szia Bob!"
+    );
+    assert_eq!(
+        Locale::format::<&str>(locale.get("unknown").unwrap(), &[]),
+        "{ $missing } {x"
+    );
+
+    match Locale::parse("en", "  orphan") {
+        Err(LocalizationError::Parse { line, .. }) => assert_eq!(line, 1),
+        res => panic!("Unexpected result: {:?}", res),
+    }
+}
+
+#[test]
+fn font_fallback_chain() {
+    utils::init_logger();
+
+    assert_eq!(Script::of('a'), Some(Script::Latin));
+    assert_eq!(Script::of('1'), None);
+    assert_eq!(Script::of('字'), Some(Script::Han));
+    assert_eq!(Script::of('か'), Some(Script::Kana));
+    assert_eq!(Script::of('한'), Some(Script::Hangul));
+    let mut scripts = HashSet::new();
+    Script::collect("Привет, 世界!", &mut scripts);
+    assert_eq!(scripts, [Script::Cyrillic, Script::Han].iter().cloned().collect());
+
+    let config = font_config();
+    assert_eq!(
+        config.fallback_chain("ja", Script::Han),
+        vec!["font://cjk-jp.ttf", "font://cjk.ttf", "font://cjk-sc.ttf"]
+    );
+    assert_eq!(
+        config.fallback_chain("zh-tw", Script::Han),
+        vec!["font://cjk-sc.ttf", "font://cjk.ttf", "font://cjk-jp.ttf"]
+    );
+    assert_eq!(config.fallback_chain("ko", Script::Hangul), vec!["font://cjk.ttf"]);
+    assert!(config.fallback_chain("en", Script::Arabic).is_empty());
+}
+
+fn locale(language: &str, messages: &[(&str, &str)]) -> Locale {
+    let mut locale = Locale::new(language);
+    for (key, message) in messages {
+        locale.insert(key, message);
+    }
+    locale
+}
+
+#[test]
+fn language_switch() {
+    utils::init_logger();
+
+    let en = locale("en", &[("start", "Start"), ("greet", "Hello { $name }")]);
+    let mut localization = Localization::new(font_config(), en);
+    let start = localization.text("start", &[]);
+    let greet = localization.text("greet", &[("name", "Anna")]);
+    let missing = localization.text("missing", &[]);
+    assert_eq!(start.text(), "Start");
+    assert_eq!(greet.text(), "Hello Anna");
+    assert_eq!(missing.text(), "missing");
+    assert!(localization.missing_fonts().is_empty());
+
+    // the handles are re-resolved, the missing messages fall back to the default language
+    let revision = start.revision();
+    localization.set_language(locale("ja", &[("start", "スタート")]));
+    assert_eq!(localization.language(), "ja");
+    assert_eq!(start.text(), "スタート");
+    assert!(start.revision() > revision);
+    assert_eq!(greet.text(), "Hello Anna");
+    localization.set_args(&greet, &[("name", "花子")]);
+    assert_eq!(greet.text(), "Hello 花子");
+
+    // the japanese font is preferred, if it fails the next of the chain is loaded
+    assert_eq!(localization.missing_fonts(), vec!["font://cjk-jp.ttf".to_owned()]);
+    localization.font_failed("font://cjk-jp.ttf");
+    assert_eq!(localization.missing_fonts(), vec!["font://cjk.ttf".to_owned()]);
+    localization.add_font("font://cjk.ttf", vec![1, 2, 3]);
+    assert!(localization.missing_fonts().is_empty());
+    assert_eq!(localization.font(Script::Kana), Some("font://cjk.ttf"));
+    assert_eq!(localization.font(Script::Latin), Some("font://latin.ttf"));
+    assert_eq!(localization.font_data("font://cjk.ttf").unwrap().len(), 3);
+
+    // dropped handles are not resolved any more
+    drop(missing);
+    localization.set_language(locale("en", &[]));
+    assert_eq!(localization.text_count(), 2);
+    assert_eq!(start.text(), "Start");
+    assert_eq!(localization.font(Script::Hangul), Some("font://cjk.ttf"));
+}
@@ -0,0 +1,43 @@
+use shine_game::matchmaking::{median_rtt, rank_latencies, RegionInfo, RegionLatency};
+
+mod utils;
+
+fn latency(region: &str, rtt_ms: u32) -> RegionLatency {
+    RegionLatency {
+        region: region.to_owned(),
+        rtt_ms,
+    }
+}
+
+#[test]
+fn median_of_samples() {
+    utils::init_logger();
+
+    assert_eq!(median_rtt(&mut []), None);
+    assert_eq!(median_rtt(&mut [40]), Some(40));
+    assert_eq!(median_rtt(&mut [90, 20, 30]), Some(30));
+    assert_eq!(median_rtt(&mut [500, 20, 40, 30]), Some(35));
+}
+
+#[test]
+fn nearest_region_first() {
+    utils::init_logger();
+
+    let ranked = rank_latencies(vec![latency("us", 120), latency("eu", 30), latency("asia", 30)]);
+    assert_eq!(ranked, vec![latency("asia", 30), latency("eu", 30), latency("us", 120)]);
+}
+
+#[test]
+fn region_list_format() {
+    utils::init_logger();
+
+    let regions: Vec<RegionInfo> = serde_json::from_str(
+        r#"[{"id":"eu","endpoint":"https://eu.example.com/api","pingUrl":"https://eu.example.com/api/regions/ping","healthy":true}]"#,
+    )
+    .unwrap();
+    assert_eq!(regions.len(), 1);
+    assert_eq!(regions[0].ping_url, "https://eu.example.com/api/regions/ping");
+
+    let latency = serde_json::to_string(&latency("eu", 42)).unwrap();
+    assert_eq!(latency, r#"{"region":"eu","rttMs":42}"#);
+}
@@ -0,0 +1,83 @@
+use shine_game::render::{
+    DebugView, DebugViewMode, DebugViewNormalization, DebugViewTechnique, DebugViewUniform, TechniqueConfig,
+};
+
+mod utils;
+
+#[test]
+fn debug_view_cycle() {
+    utils::init_logger();
+
+    let mut view = DebugView::default();
+    assert!(!view.is_enabled());
+    for mode in DebugViewMode::ALL.iter().skip(1) {
+        assert_eq!(view.cycle(), *mode);
+    }
+    assert_eq!(view.cycle(), DebugViewMode::Off);
+    assert_eq!(view.cycle_back(), DebugViewMode::Velocity);
+
+    assert_eq!(view.execute("ao"), Ok(DebugViewMode::AmbientOcclusion));
+    assert_eq!(view.execute("next"), Ok(DebugViewMode::Velocity));
+    assert_eq!(view.execute("prev"), Ok(DebugViewMode::AmbientOcclusion));
+    assert!(view.execute("albedo").is_err());
+    assert_eq!(view.mode(), DebugViewMode::AmbientOcclusion);
+
+    for mode in DebugViewMode::ALL.iter() {
+        assert_eq!(mode.to_string().parse::<DebugViewMode>(), Ok(*mode));
+    }
+}
+
+#[test]
+fn debug_view_normalization() {
+    utils::init_logger();
+
+    let depth = DebugViewNormalization::LinearDepth { near: 1., far: 11. };
+    assert!(depth.apply([0., 0., 0., 0.])[0].abs() < 1e-5);
+    assert!((depth.apply([1., 0., 0., 0.])[0] - 1.).abs() < 1e-5);
+    // the perspective depth is not linear, half of the depth range is close to the near plane
+    let mid = depth.apply([0.5, 0., 0., 0.])[0];
+    assert!(mid > 0. && mid < 0.1);
+
+    assert_eq!(
+        DebugViewNormalization::SignedUnit.apply([-1., 0., 1., 0.]),
+        [0., 0.5, 1., 1.]
+    );
+    assert_eq!(
+        DebugViewNormalization::Scale(0.5).apply([-1., 4., 0., 0.]),
+        [0.5, 1., 0., 1.]
+    );
+
+    let mut view = DebugView::default();
+    view.set_mode(DebugViewMode::Velocity);
+    assert_eq!(
+        view.uniform(),
+        DebugViewUniform::new(&DebugViewNormalization::Scale(1. / 16.), None)
+    );
+    view.set_mode(DebugViewMode::Depth);
+    assert_eq!(view.uniform().mode, [1, 0, 0, 0]);
+}
+
+#[test]
+fn debug_view_technique_config() {
+    utils::init_logger();
+
+    assert!(DebugViewTechnique::from_config(&TechniqueConfig::new("debug_view")).is_err());
+
+    let technique = DebugViewTechnique::from_config(
+        &TechniqueConfig::new("debug_view")
+            .with_option("pipeline", "debug_view.pl")
+            .with_option("far", 500.)
+            .with_option("mode", "normals"),
+    )
+    .unwrap();
+    assert_eq!(technique.view().depth_range, (0.1, 500.));
+    assert_eq!(technique.view().mode(), DebugViewMode::Normals);
+
+    assert!(DebugViewTechnique::from_config(
+        &TechniqueConfig::new("debug_view")
+            .with_option("pipeline", "debug_view.pl")
+            .with_option("near", 10.)
+            .with_option("far", 1.)
+    )
+    .is_err());
+}
@@ -0,0 +1,132 @@
+use shine_game::render::{
+    CaptureReason, FrameCapture, FrameCaptureBackend, FrameCaptureConfig, FrameCommandLog, HitchDetector,
+};
+use std::sync::{Arc, Mutex};
+
+mod utils;
+
+fn hitch_config() -> FrameCaptureConfig {
+    FrameCaptureConfig {
+        capture_hitches: true,
+        hitch_factor: 3.,
+        hitch_min_ms: 50,
+        hitch_cooldown: 10,
+        max_hitch_captures: 2,
+        log_folder: None,
+    }
+}
+
+/// Advance the time with frames of the given length (in ms) and collect the hitches
+fn run_frames(detector: &mut HitchDetector, time: &mut u128, frames: &[u128]) -> Vec<CaptureReason> {
+    let mut hitches = Vec::new();
+    for frame in frames {
+        *time += frame * 1000;
+        hitches.extend(detector.update(*time));
+    }
+    hitches
+}
+
+#[test]
+fn hitch_detection() {
+    utils::init_logger();
+
+    let mut detector = HitchDetector::new(&hitch_config());
+    let mut time = 0;
+
+    // no hitches during the warmup
+    assert!(run_frames(&mut detector, &mut time, &[16, 16, 100, 16]).is_empty());
+    assert!(run_frames(&mut detector, &mut time, &[16; 40]).is_empty());
+
+    // long, but below the minimum
+    assert!(run_frames(&mut detector, &mut time, &[49]).is_empty());
+
+    let hitches = run_frames(&mut detector, &mut time, &[120]);
+    match &hitches[..] {
+        [CaptureReason::Hitch { frame_time, average }] => {
+            assert_eq!(*frame_time, 120_000);
+            assert!(*average >= 16_000 && *average < 25_000);
+        }
+        res => panic!("Unexpected result: {:?}", res),
+    }
+
+    // cooldown, then the session limit
+    assert!(run_frames(&mut detector, &mut time, &[16, 120, 16]).is_empty());
+    assert_eq!(
+        run_frames(&mut detector, &mut time, &[16, 16, 16, 16, 16, 16, 16, 120]).len(),
+        1
+    );
+    assert!(run_frames(&mut detector, &mut time, &[16; 20]).is_empty());
+    assert!(run_frames(&mut detector, &mut time, &[120]).is_empty());
+    assert_eq!(detector.hitch_count(), 2);
+
+    let mut detector = HitchDetector::new(&FrameCaptureConfig::default());
+    let mut time = 0;
+    assert!(run_frames(&mut detector, &mut time, &[16; 40]).is_empty());
+    // hitch capture is disabled by default
+    assert!(run_frames(&mut detector, &mut time, &[120]).is_empty());
+}
+
+#[derive(Clone, Default)]
+struct TraceBackend(Arc<Mutex<Vec<String>>>);
+
+impl FrameCaptureBackend for TraceBackend {
+    fn name(&self) -> &str {
+        "trace"
+    }
+
+    fn start_capture(&mut self, frame: u64, reason: &CaptureReason) {
+        self.0.lock().unwrap().push(format!("start {} {}", frame, reason));
+    }
+
+    fn end_capture(&mut self, log: &FrameCommandLog) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("end {} {}", log.frame, log.commands.len()));
+    }
+}
+
+#[test]
+fn frame_capture() {
+    utils::init_logger();
+
+    let trace = TraceBackend::default();
+    let mut capture = FrameCapture::new(&hitch_config(), Box::new(trace.clone()));
+    assert_eq!(capture.backend_name(), "trace");
+
+    // commands outside of a capture are ignored
+    capture.begin_frame((640, 480));
+    capture.record_command("draw");
+    capture.end_frame(0);
+    assert!(capture.last_capture().is_none());
+
+    assert!(capture.execute("capture").is_ok());
+    // a second request is merged into the pending one
+    capture.trigger(CaptureReason::Manual);
+    assert!(capture.is_pending());
+    capture.begin_frame((640, 480));
+    assert!(capture.is_capturing());
+    capture.record_command("draw");
+    capture.record_command("present");
+    capture.end_frame(16_000);
+    assert!(!capture.is_capturing());
+    assert!(!capture.is_pending());
+
+    let log = capture.last_capture().unwrap();
+    assert_eq!(log.frame, 2);
+    assert_eq!(log.reason, CaptureReason::Console);
+    assert_eq!(log.size, (640, 480));
+    assert_eq!(log.commands, vec!["draw".to_owned(), "present".to_owned()]);
+    let json = serde_json::to_string(log).unwrap();
+    assert_eq!(serde_json::from_str::<FrameCommandLog>(&json).unwrap(), *log);
+
+    assert_eq!(
+        *trace.0.lock().unwrap(),
+        vec!["start 2 console".to_owned(), "end 2 2".to_owned()]
+    );
+
+    assert!(capture.execute("hitch off").is_ok());
+    assert!(!capture.hitches().enabled);
+    assert!(capture.execute("status").unwrap().contains("hitch capture: off"));
+    assert!(capture.execute("replay").is_err());
+}
@@ -0,0 +1,100 @@
+use shine_ecs::events::Events;
+use shine_game::render::{capture_gpu_errors, GpuErrorScope, GpuErrorSink, RenderError, RenderErrorOverlay};
+
+mod utils;
+
+#[test]
+fn gpu_error_capture() {
+    utils::init_logger();
+
+    assert_eq!(capture_gpu_errors(GpuErrorScope::Pipeline, "ok.pl", || 3).unwrap(), 3);
+
+    let err = capture_gpu_errors(GpuErrorScope::Pipeline, "broken.pl", || -> u32 {
+        panic!("Error in Device::create_render_pipeline: incompatible vertex layout")
+    })
+    .unwrap_err();
+    match err {
+        RenderError::Validation { scope, asset, message } => {
+            assert_eq!(scope, GpuErrorScope::Pipeline);
+            assert_eq!(asset, "broken.pl");
+            assert!(message.contains("incompatible vertex layout"));
+        }
+        err => panic!("Unexpected error: {:?}", err),
+    }
+
+    let sink = GpuErrorSink::default();
+    assert_eq!(
+        sink.capture(GpuErrorScope::Shader, "ok.vs", || "module"),
+        Some("module")
+    );
+    assert!(!sink.has_errors());
+    let shared = sink.clone();
+    assert_eq!(
+        shared.capture(GpuErrorScope::Shader, "broken.fs", || panic!("invalid spirv {}", 12)),
+        None::<()>
+    );
+    assert!(sink.has_errors());
+    let errors = sink.take_errors();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].to_string(),
+        "Gpu validation error in shader broken.fs: invalid spirv 12"
+    );
+    assert!(!sink.has_errors());
+}
+
+fn validation_error(asset: &str) -> RenderError {
+    RenderError::Validation {
+        scope: GpuErrorScope::Pipeline,
+        asset: asset.to_owned(),
+        message: "invalid".to_owned(),
+    }
+}
+
+#[test]
+fn render_error_overlay() {
+    utils::init_logger();
+
+    let mut events = Events::<RenderError>::default();
+    let mut overlay = RenderErrorOverlay::default();
+    overlay.capacity = 2;
+    overlay.lifetime = 3;
+
+    overlay.update(&events);
+    assert!(!overlay.is_visible());
+
+    events.send(validation_error("a.pl"));
+    events.send(validation_error("a.pl"));
+    events.send(RenderError::Technique {
+        message: "missing".to_owned(),
+    });
+    overlay.update(&events);
+    assert_eq!(
+        overlay.lines(),
+        vec![
+            "[pipeline] a.pl: invalid (x2)".to_owned(),
+            "Render technique error: missing".to_owned()
+        ]
+    );
+
+    // the events are read only once, the list is limited by the capacity
+    events.update();
+    events.send(validation_error("b.pl"));
+    overlay.update(&events);
+    assert_eq!(overlay.entries().count(), 2);
+    assert_eq!(overlay.entries().last().unwrap().asset.as_deref(), Some("b.pl"));
+    assert_eq!(overlay.entries().next().unwrap().message, "missing");
+
+    // errors expire after the lifetime
+    for _ in 0..3 {
+        events.update();
+        overlay.update(&events);
+    }
+    assert!(!overlay.is_visible());
+
+    events.send(validation_error("c.pl"));
+    overlay.update(&events);
+    assert!(overlay.is_visible());
+    overlay.dismiss();
+    assert!(!overlay.is_visible());
+}
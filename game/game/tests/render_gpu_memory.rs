@@ -0,0 +1,98 @@
+use shine_game::render::{texture_memory_size, GpuMemoryCategory, GpuMemoryConfig, GpuMemoryTracker};
+
+mod utils;
+
+#[test]
+fn texture_sizes() {
+    utils::init_logger();
+
+    let rgba = wgpu::TextureFormat::Rgba8UnormSrgb;
+    assert_eq!(texture_memory_size(rgba, (256, 256, 1), 1), 256 * 256 * 4);
+    // a full mip chain adds about a third
+    assert_eq!(texture_memory_size(rgba, (4, 4, 1), 3), (16 + 4 + 1) * 4);
+    // 4x4 blocks of 8 bytes, the small mips still take a full block
+    let bc1 = wgpu::TextureFormat::Bc1RgbaUnorm;
+    assert_eq!(texture_memory_size(bc1, (8, 8, 1), 1), 4 * 8);
+    assert_eq!(texture_memory_size(bc1, (8, 8, 1), 4), (4 + 1 + 1 + 1) * 8);
+    assert_eq!(
+        texture_memory_size(wgpu::TextureFormat::Rgba16Float, (2, 2, 6), 1),
+        2 * 2 * 6 * 8
+    );
+}
+
+fn create_tracker() -> GpuMemoryTracker {
+    let config = GpuMemoryConfig {
+        budget_mb: None,
+        high_watermark: 0.9,
+        low_watermark: 0.7,
+    };
+    GpuMemoryTracker::new(1000, &config)
+}
+
+#[test]
+fn budget_eviction() {
+    utils::init_logger();
+
+    let mut tracker = create_tracker();
+    tracker.allocate_pinned("frame", GpuMemoryCategory::RenderTarget, 200);
+    tracker.allocate("near.tex", GpuMemoryCategory::Texture, 200, 1.);
+    tracker.allocate("far.tex", GpuMemoryCategory::Texture, 200, 0.1);
+    tracker.allocate("model", GpuMemoryCategory::Model, 200, 0.5);
+    assert_eq!(tracker.total(), 800);
+    assert!(tracker.end_frame().is_empty());
+
+    // the model is used in this frame, the far texture is the least important
+    tracker.touch("model", 0.6);
+    tracker.touch("far.tex", 0.5);
+    tracker.allocate("new.buf", GpuMemoryCategory::Buffer, 100, 1.);
+    // it is still below the high watermark
+    assert_eq!(tracker.total(), 900);
+
+    // exceeding the high watermark evicts down to the low watermark in the order of priority
+    tracker.allocate("big.tex", GpuMemoryCategory::Texture, 150, 1.);
+    assert!(tracker.get("far.tex").is_none());
+    assert!(tracker.get("model").is_none());
+    assert!(tracker.get("frame").is_some());
+    assert!(tracker.get("near.tex").is_some());
+    assert_eq!(tracker.total(), 200 + 200 + 100 + 150);
+
+    assert_eq!(
+        tracker.take_evictions(GpuMemoryCategory::Texture),
+        vec!["far.tex".to_owned()]
+    );
+    assert_eq!(
+        tracker.take_evictions(GpuMemoryCategory::Model),
+        vec!["model".to_owned()]
+    );
+    assert!(tracker.take_evictions(GpuMemoryCategory::Texture).is_empty());
+
+    let warnings = tracker.end_frame();
+    assert_eq!(warnings.len(), 1);
+    let usage = &warnings[0].usage;
+    assert_eq!(usage.budget, 1000);
+    assert_eq!(usage.total, 650);
+    assert!(usage.categories.contains(&(GpuMemoryCategory::Texture, 350)));
+    assert!(usage.categories.contains(&(GpuMemoryCategory::Model, 0)));
+    assert_eq!(warnings[0].evicted, vec!["far.tex".to_owned(), "model".to_owned()]);
+
+    // reallocation replaces the previous size
+    tracker.allocate("big.tex", GpuMemoryCategory::Texture, 50, 1.);
+    assert_eq!(tracker.total(), 550);
+    assert!(tracker.release("big.tex").is_some());
+    assert_eq!(tracker.total(), 500);
+}
+
+#[test]
+fn pinned_allocations() {
+    utils::init_logger();
+
+    let mut tracker = create_tracker();
+    tracker.allocate_pinned("frame", GpuMemoryCategory::RenderTarget, 800);
+    tracker.allocate_pinned("taa", GpuMemoryCategory::RenderTarget, 300);
+    // nothing to evict, the budget is exceeded with a warning
+    assert_eq!(tracker.total(), 1100);
+    assert!(tracker.usage().ratio() > 1.);
+    let warnings = tracker.end_frame();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].evicted.is_empty());
+}
@@ -0,0 +1,120 @@
+use nalgebra::{Isometry3, Perspective3, Point3};
+use shine_game::{
+    assets::{vertex::Pos3fCol3f, IndexData, MeshData, VertexData},
+    render::{LodChain, LodConfig, LodInstance, LodSelector},
+    spatial::Frustum,
+};
+
+mod utils;
+
+fn create_chain() -> LodChain {
+    let vertices = VertexData::from_vec(vec![
+        Pos3fCol3f {
+            position: [0., 0., 0.],
+            color: [1., 1., 1.],
+        };
+        4
+    ]);
+    let mut mesh = MeshData::with_vertices_and_indices(vertices, IndexData::new(vec![0; 1200 + 600 + 300]));
+    mesh.lod = [(0, 1200), (1200, 600), (1800, 300), (1800, 300)];
+    LodChain::from_mesh(&mesh)
+}
+
+#[test]
+fn lod_chain() {
+    utils::init_logger();
+
+    let chain = create_chain();
+    assert_eq!(chain.lod_count, 3);
+    assert_eq!(chain.triangles[..3], [400, 200, 100]);
+
+    let vertices = VertexData::from_vec(vec![
+        Pos3fCol3f {
+            position: [0., 0., 0.],
+            color: [1., 1., 1.],
+        };
+        3
+    ]);
+    let mesh = MeshData::with_vertices(vertices);
+    assert_eq!(LodChain::from_mesh(&mesh).lod_count, 1);
+}
+
+#[test]
+fn lod_selection_hysteresis() {
+    utils::init_logger();
+
+    let selector = LodSelector::new(LodConfig::default(), std::f32::consts::FRAC_PI_2);
+    assert!((selector.screen_size(1., 4.) - 0.25).abs() < 1e-5);
+    assert!(selector.screen_size(1., 0.5).is_infinite());
+
+    assert_eq!(selector.select(0, 1., 0., 4), 0);
+    assert_eq!(selector.select(0, 0.3, 0., 4), 1);
+    assert_eq!(selector.select(0, 0.01, 0., 4), 3);
+    // clamped to the available lods
+    assert_eq!(selector.select(0, 0.01, 0., 2), 1);
+
+    // around the 0.25 boundary the current selection is kept
+    assert_eq!(selector.select(1, 0.24, 0., 4), 1);
+    assert_eq!(selector.select(2, 0.26, 0., 4), 2);
+    assert_eq!(selector.select(1, 0.2, 0., 4), 2);
+    assert_eq!(selector.select(2, 0.3, 0., 4), 1);
+
+    // a positive bias prefers the detailed lods
+    assert_eq!(selector.select(0, 0.3, 1., 4), 0);
+    assert_eq!(selector.select(0, 0.6, -1., 4), 1);
+}
+
+#[test]
+fn lod_update_stats() {
+    utils::init_logger();
+
+    let chain = create_chain();
+    let mut selector = LodSelector::new(LodConfig::default(), std::f32::consts::FRAC_PI_2);
+    let mut instances = vec![
+        LodInstance::new(Point3::new(0., 0., -1.5), 1., chain),
+        LodInstance::new(Point3::new(0., 0., -3.), 1., chain),
+        LodInstance::new(Point3::new(0., 0., -100.), 1., chain),
+        LodInstance::new(Point3::new(0., 0., -100.), 1., chain).with_bias(8.),
+        LodInstance {
+            visible: false,
+            ..LodInstance::new(Point3::new(0., 0., -100.), 1., chain)
+        },
+    ];
+
+    selector.update(&Isometry3::identity(), &mut instances);
+    let lods: Vec<_> = instances.iter().map(|i| i.lod).collect();
+    assert_eq!(lods, vec![0, 1, 2, 0, 0]);
+
+    let stats = selector.stats();
+    log::debug!("{}", stats);
+    assert_eq!(stats.instance_count, 5);
+    assert_eq!(stats.visible_count, 4);
+    assert_eq!(stats.lod_histogram, [2, 1, 1, 0]);
+    assert_eq!(stats.full_triangles, 1600);
+    assert_eq!(stats.rendered_triangles, 400 + 200 + 100 + 400);
+    assert_eq!(stats.triangles_saved(), 500);
+}
+
+#[test]
+fn lod_culling() {
+    utils::init_logger();
+
+    let chain = create_chain();
+    let fovy = std::f32::consts::FRAC_PI_2;
+    let mut selector = LodSelector::new(LodConfig::default(), fovy);
+    let frustum = Frustum::from_view_projection(Perspective3::new(1., fovy, 0.1, 1000.).as_matrix());
+    let mut instances = vec![
+        LodInstance::new(Point3::new(0., 0., -3.), 1., chain),
+        // behind the camera
+        LodInstance::new(Point3::new(0., 0., 5.), 1., chain),
+        // outside, but the bounds touch the frustum
+        LodInstance::new(Point3::new(5.5, 0., -5.), 1., chain),
+        LodInstance::new(Point3::new(100., 0., -5.), 1., chain),
+    ];
+
+    selector.cull_and_update(&Isometry3::identity(), &frustum, &mut instances);
+    let visible: Vec<_> = instances.iter().map(|i| i.visible).collect();
+    assert_eq!(visible, vec![true, false, true, false]);
+    assert_eq!(instances[0].lod, 1);
+    assert_eq!(selector.stats().visible_count, 2);
+}
@@ -0,0 +1,54 @@
+use shine_game::{
+    assets::{AlphaMode, ColorStage},
+    render::{RenderQueue, RenderQueueKind},
+};
+
+mod utils;
+
+#[test]
+fn render_queue_sorting() {
+    utils::init_logger();
+
+    let mut queue = RenderQueue::new();
+    queue.push(ColorStage::AlphaBlend, 1, 5., "glass_near");
+    queue.push(ColorStage::Replace, 2, 10., "wall_far");
+    queue.push(AlphaMode::Mask(0.5).color_stage(), 1, 3., "leaf");
+    queue.push(AlphaMode::Blend.color_stage(), 2, 20., "glass_far");
+    queue.push(RenderQueueKind::Opaque, 2, 1., "wall_near");
+    queue.push(ColorStage::Additive, 3, 10., "particle");
+    assert_eq!(queue.len(), 6);
+
+    queue.sort();
+    let opaque: Vec<_> = queue.opaque().iter().map(|x| x.item).collect();
+    assert_eq!(opaque, vec!["leaf", "wall_near", "wall_far"]);
+    let transparent: Vec<_> = queue.transparent().iter().map(|x| x.item).collect();
+    assert_eq!(transparent, vec!["glass_far", "particle", "glass_near"]);
+    let all: Vec<_> = queue.iter().map(|x| x.item).collect();
+    assert_eq!(
+        all,
+        vec!["leaf", "wall_near", "wall_far", "glass_far", "particle", "glass_near"]
+    );
+
+    queue.clear();
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn blend_state() {
+    utils::init_logger();
+
+    assert!(!ColorStage::Replace.is_transparent());
+    assert!(ColorStage::PremultipliedAlpha.is_transparent());
+    assert_eq!(ColorStage::default(), ColorStage::Replace);
+    assert_eq!(ColorStage::Replace.color_blend(), wgpu::BlendDescriptor::REPLACE);
+    assert_eq!(
+        ColorStage::AlphaBlend.color_blend().src_factor,
+        wgpu::BlendFactor::SrcAlpha
+    );
+    assert_eq!(
+        ColorStage::PremultipliedAlpha.color_blend().src_factor,
+        wgpu::BlendFactor::One
+    );
+    assert!(AlphaMode::Opaque.shader_defines().is_empty());
+    assert_eq!(AlphaMode::Mask(0.5).shader_defines().len(), 2);
+}
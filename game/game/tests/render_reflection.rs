@@ -0,0 +1,148 @@
+use nalgebra::{Point3, Vector3};
+use shine_game::render::{
+    cube_face_projection, cube_face_view, mip_roughness, prefiltered_mip_count, roughness_mip, ProbeBlend,
+    ProbeCapture, ReflectionProbe, ReflectionTechnique, TechniqueConfig,
+};
+
+mod utils;
+
+#[test]
+fn probe_weight() {
+    utils::init_logger();
+
+    let probe = ReflectionProbe::new(Point3::new(0., 0., 0.), 8., ProbeCapture::LevelLoad);
+    assert_eq!(probe.blend_distance, 2.);
+    assert_eq!(probe.weight(&Point3::new(1., 0., 0.)), 1.);
+    assert_eq!(probe.weight(&Point3::new(0., 7., 0.)), 0.5);
+    assert_eq!(probe.weight(&Point3::new(0., 0., 9.)), 0.);
+}
+
+#[test]
+fn probe_blend() {
+    utils::init_logger();
+
+    let probes = vec![
+        ReflectionProbe::new(Point3::new(0., 0., 0.), 8., ProbeCapture::LevelLoad),
+        ReflectionProbe::new(
+            Point3::new(10., 0., 0.),
+            8.,
+            ProbeCapture::Cooked("probe1.tex".to_owned()),
+        ),
+        ReflectionProbe::new(Point3::new(100., 0., 0.), 8., ProbeCapture::LevelLoad),
+    ];
+
+    // inside a single probe
+    let blend = ProbeBlend::from_probes(&probes, &Point3::new(0., 0., 0.), 2);
+    assert_eq!(blend.probes, vec![(0, 1.)]);
+    assert_eq!(blend.sky_weight(), 0.);
+
+    // overlapping probes are normalized
+    let blend = ProbeBlend::from_probes(&probes, &Point3::new(5., 0., 0.), 2);
+    assert_eq!(blend.probes.len(), 2);
+    let total: f32 = blend.probes.iter().map(|(_, w)| w).sum();
+    assert!((total - 1.).abs() < 1e-5);
+
+    // partial coverage falls back to the sky
+    let blend = ProbeBlend::from_probes(&probes, &Point3::new(107., 0., 0.), 2);
+    assert_eq!(blend.probes, vec![(2, 0.5)]);
+    assert_eq!(blend.sky_weight(), 0.5);
+    let (indices, weights) = blend.to_gpu();
+    assert_eq!(indices, [2, -1, -1, -1]);
+    assert_eq!(weights, [0.5, 0., 0., 0.]);
+
+    // outside of every probe
+    let blend = ProbeBlend::from_probes(&probes, &Point3::new(50., 0., 0.), 2);
+    assert!(blend.probes.is_empty());
+    assert_eq!(blend.sky_weight(), 1.);
+}
+
+#[test]
+fn cube_faces() {
+    utils::init_logger();
+
+    let position = Point3::new(1., 2., 3.);
+    let projection = cube_face_projection(0.1, 100.);
+    let directions = [
+        Vector3::x(),
+        -Vector3::x(),
+        Vector3::y(),
+        -Vector3::y(),
+        Vector3::z(),
+        -Vector3::z(),
+    ];
+    for (face, direction) in directions.iter().enumerate() {
+        // the point in the direction of the face is projected to the center of the face
+        let p = cube_face_view(&position, face) * (position + direction * 10.);
+        let p = projection.transform_point(&p);
+        assert!(p.x.abs() < 1e-5 && p.y.abs() < 1e-5, "face {}: {:?}", face, p);
+    }
+}
+
+#[test]
+fn prefiltered_mips() {
+    utils::init_logger();
+
+    assert_eq!(prefiltered_mip_count(128), 6);
+    assert_eq!(prefiltered_mip_count(1), 1);
+    assert_eq!(mip_roughness(0, 6), 0.);
+    assert_eq!(mip_roughness(5, 6), 1.);
+    assert_eq!(roughness_mip(1., 6), 5.);
+    assert_eq!(roughness_mip(mip_roughness(3, 6), 6), 3.);
+}
+
+#[test]
+fn reflection_probes() {
+    utils::init_logger();
+
+    assert!(ReflectionTechnique::from_config(&TechniqueConfig::new("reflection")).is_err());
+    assert!(ReflectionTechnique::from_config(
+        &TechniqueConfig::new("reflection")
+            .with_option("prefilter_pipeline", "prefilter.pl")
+            .with_option("resolution", "100")
+    )
+    .is_err());
+
+    let technique = ReflectionTechnique::from_config(
+        &TechniqueConfig::new("reflection")
+            .with_option("prefilter_pipeline", "prefilter.pl")
+            .with_option("max_probes", "2"),
+    )
+    .unwrap();
+    assert_eq!(technique.settings().resolution, 128);
+
+    let mut probes = technique.create_probes();
+    let a = probes
+        .add_probe(ReflectionProbe::new(
+            Point3::new(0., 0., 0.),
+            8.,
+            ProbeCapture::LevelLoad,
+        ))
+        .unwrap();
+    let b = probes
+        .add_probe(ReflectionProbe::new(
+            Point3::new(4., 0., 0.),
+            8.,
+            ProbeCapture::Cooked("probe.tex".to_owned()),
+        ))
+        .unwrap();
+    assert!(probes
+        .add_probe(ReflectionProbe::new(
+            Point3::new(0., 0., 0.),
+            8.,
+            ProbeCapture::LevelLoad
+        ))
+        .is_err());
+    assert_eq!(probes.pending_captures(), vec![a]);
+
+    // only the captured probes are blended
+    assert!(probes.blend(&Point3::new(2., 0., 0.)).probes.is_empty());
+    probes.set_captured(a);
+    probes.set_captured(b);
+    assert!(probes.pending_captures().is_empty());
+    assert_eq!(probes.blend(&Point3::new(2., 0., 0.)).probes.len(), 2);
+
+    probes.invalidate();
+    assert_eq!(probes.pending_captures(), vec![a]);
+    assert!(probes.is_captured(b));
+    assert_eq!(probes.gpu_probes()[b].params[3], 1.);
+}
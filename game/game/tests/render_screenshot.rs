@@ -0,0 +1,31 @@
+use shine_game::render::{padded_bytes_per_row, Screenshot};
+
+mod utils;
+
+#[test]
+fn screenshot_from_texels() {
+    utils::init_logger();
+
+    assert_eq!(padded_bytes_per_row(2), 256);
+    assert_eq!(padded_bytes_per_row(64), 256);
+    assert_eq!(padded_bytes_per_row(65), 512);
+
+    // 2x2 bgra frame with padded rows
+    let mut texels = vec![0u8; 2 * 256];
+    texels[..8].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+    texels[256..264].copy_from_slice(&[9, 10, 11, 12, 13, 14, 15, 16]);
+    let screenshot = Screenshot::from_texels(wgpu::TextureFormat::Bgra8UnormSrgb, 2, 2, 256, &texels).unwrap();
+    assert_eq!(
+        screenshot.pixels,
+        vec![3, 2, 1, 4, 7, 6, 5, 8, 11, 10, 9, 12, 15, 14, 13, 16]
+    );
+
+    let rgba = Screenshot::from_texels(wgpu::TextureFormat::Rgba8Unorm, 2, 2, 256, &texels).unwrap();
+    assert_eq!(&rgba.pixels[..4], &[1, 2, 3, 4]);
+
+    assert!(Screenshot::from_texels(wgpu::TextureFormat::Rgba16Float, 2, 2, 256, &texels).is_err());
+    assert!(Screenshot::from_texels(wgpu::TextureFormat::Rgba8Unorm, 2, 3, 256, &texels).is_err());
+
+    let png = screenshot.to_png().unwrap();
+    assert_eq!(&png[1..4], b"PNG");
+}
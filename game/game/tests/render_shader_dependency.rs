@@ -0,0 +1,50 @@
+use shine_ecs::resources::ResourceId;
+use shine_game::render::ShaderDependencies;
+
+mod utils;
+
+#[test]
+fn invalidate_cascades_to_dependents() {
+    utils::init_logger();
+
+    let dependencies = ShaderDependencies::new();
+    let p1 = ResourceId::from_tag("p1").unwrap();
+    let p2 = ResourceId::from_tag("p2").unwrap();
+
+    dependencies.add_dependent("vs", &p1);
+    dependencies.add_dependent("fs", &p1);
+    dependencies.add_dependent("vs", &p2);
+    dependencies.add_dependent("vs", &p2);
+    assert_eq!(dependencies.dependents_of("vs").len(), 2);
+    assert_eq!(dependencies.dependents_of("fs"), vec![p1.clone()]);
+    assert!(!dependencies.has_pending());
+
+    assert_eq!(dependencies.invalidate("unknown"), 0);
+    assert!(!dependencies.has_pending());
+
+    assert_eq!(dependencies.invalidate("fs"), 1);
+    assert_eq!(dependencies.invalidate("vs"), 2);
+    let mut pending = dependencies.take_pending();
+    pending.sort();
+    assert_eq!(pending, vec![p1, p2]);
+    assert!(!dependencies.has_pending());
+}
+
+#[test]
+fn removed_dependents_are_not_rebuilt() {
+    utils::init_logger();
+
+    let dependencies = ShaderDependencies::new();
+    let p1 = ResourceId::from_tag("p1").unwrap();
+    let p2 = ResourceId::from_tag("p2").unwrap();
+
+    dependencies.add_dependent("vs", &p1);
+    dependencies.add_dependent("vs", &p2);
+    dependencies.add_dependent("fs", &p2);
+    assert_eq!(dependencies.invalidate("vs"), 2);
+
+    dependencies.remove_dependent(&p2);
+    assert_eq!(dependencies.take_pending(), vec![p1.clone()]);
+    assert!(dependencies.dependents_of("fs").is_empty());
+    assert_eq!(dependencies.dependents_of("vs"), vec![p1]);
+}
@@ -0,0 +1,42 @@
+use shine_game::render::{AtmosphereParams, SkyTechnique, SkyUniform, SunLight, TechniqueConfig};
+
+mod utils;
+
+#[test]
+fn sun_light_angles() {
+    utils::init_logger();
+
+    let sun = SunLight::from_angles(std::f32::consts::FRAC_PI_2, 0.);
+    assert!((sun.direction.y - 1.).abs() < 1e-5);
+
+    let sun = SunLight::from_angles(0.3, 1.2);
+    assert!((sun.direction.norm() - 1.).abs() < 1e-5);
+    assert!((sun.elevation() - 0.3).abs() < 1e-5);
+}
+
+#[test]
+fn sky_uniform() {
+    utils::init_logger();
+
+    let sun = SunLight::default();
+    let uniform = SkyUniform::new(&sun, None);
+    assert_eq!(uniform.mie[3], 0.);
+
+    let uniform = SkyUniform::new(&sun, Some(&AtmosphereParams::default()));
+    assert_eq!(uniform.mie[3], 1.);
+    assert_eq!(uniform.sun_direction_intensity[3], sun.intensity);
+}
+
+#[test]
+fn sky_technique_config() {
+    utils::init_logger();
+
+    assert!(SkyTechnique::from_config(&TechniqueConfig::new("sky")).is_err());
+    assert!(SkyTechnique::from_config(&TechniqueConfig::new("sky").with_option("pipeline", "sky.pl")).is_ok());
+    assert!(SkyTechnique::from_config(
+        &TechniqueConfig::new("sky")
+            .with_option("pipeline", "sky.pl")
+            .with_option("atmosphere", "maybe")
+    )
+    .is_err());
+}
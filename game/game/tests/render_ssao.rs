@@ -0,0 +1,96 @@
+use shine_game::render::{
+    bilateral_weight, ssao_kernel, ssao_noise, RenderQuality, SsaoBlurUniform, SsaoSettings, SsaoTargets,
+    SsaoTechnique, TechniqueConfig, SSAO_NOISE_SIZE,
+};
+
+mod utils;
+
+#[test]
+fn ssao_kernel_samples() {
+    utils::init_logger();
+
+    let kernel = ssao_kernel(16);
+    assert_eq!(kernel.len(), 16);
+    for sample in &kernel {
+        assert!(sample.z >= 0.);
+        assert!(sample.norm() <= 1.);
+    }
+    // deterministic
+    assert_eq!(kernel, ssao_kernel(16));
+
+    let noise = ssao_noise();
+    assert_eq!(noise.len(), (SSAO_NOISE_SIZE * SSAO_NOISE_SIZE) as usize);
+    for n in &noise {
+        assert!(((n[0] * n[0] + n[1] * n[1]) - 1.).abs() < 1e-5);
+        assert_eq!(n[2], 0.);
+    }
+}
+
+#[test]
+fn ssao_bilateral_blur() {
+    utils::init_logger();
+
+    let center = bilateral_weight(0, 10., 10., 4, 0.1);
+    assert!((center - 1.).abs() < 1e-6);
+    let far = bilateral_weight(3, 10., 10., 4, 0.1);
+    assert!(far < center && far > 0.);
+    // samples across a depth edge are rejected
+    let edge = bilateral_weight(1, 10., 20., 4, 0.1);
+    assert!(edge < 1e-3);
+
+    let [h, v] = SsaoBlurUniform::new(&SsaoSettings::default(), (200, 100));
+    assert_eq!(h.direction, [1. / 200., 0.]);
+    assert_eq!(v.direction, [0., 1. / 100.]);
+}
+
+#[test]
+fn ssao_quality_tiers() {
+    utils::init_logger();
+
+    assert_eq!("High".parse::<RenderQuality>(), Ok(RenderQuality::High));
+    assert!("best".parse::<RenderQuality>().is_err());
+
+    let low = SsaoSettings::from_quality(RenderQuality::Low);
+    let ultra = SsaoSettings::from_quality(RenderQuality::Ultra);
+    assert!(low.sample_count < ultra.sample_count);
+    assert!(low.half_resolution && !ultra.half_resolution);
+    assert_eq!(SsaoTargets::occlusion_size((101, 50), true), (50, 25));
+    assert_eq!(SsaoTargets::occlusion_size((101, 50), false), (101, 50));
+
+    let settings = SsaoSettings::default();
+    assert_eq!(settings.apply_occlusion([1., 0.5, 0.], 1.), [1., 0.5, 0.]);
+    assert_eq!(settings.apply_occlusion([1., 0.5, 0.], 0.25), [0.25, 0.125, 0.]);
+}
+
+#[test]
+fn ssao_technique_config() {
+    utils::init_logger();
+
+    assert!(SsaoTechnique::from_config(&TechniqueConfig::new("ssao")).is_err());
+
+    let technique = SsaoTechnique::from_config(
+        &TechniqueConfig::new("ssao")
+            .with_option("pipeline", "ssao.pl")
+            .with_option("radius", 1.5),
+    )
+    .unwrap();
+    let settings = technique.settings(RenderQuality::High).unwrap();
+    assert_eq!(settings.sample_count, 32);
+    assert_eq!(settings.radius, 1.5);
+
+    // explicit quality overrides the quality of the render config
+    let technique = SsaoTechnique::from_config(
+        &TechniqueConfig::new("ssao")
+            .with_option("pipeline", "ssao.pl")
+            .with_option("quality", "low"),
+    )
+    .unwrap();
+    assert_eq!(technique.settings(RenderQuality::Ultra).unwrap().sample_count, 8);
+
+    assert!(SsaoTechnique::from_config(
+        &TechniqueConfig::new("ssao")
+            .with_option("pipeline", "ssao.pl")
+            .with_option("samples", 128)
+    )
+    .is_err());
+}
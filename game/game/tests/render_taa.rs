@@ -0,0 +1,81 @@
+use nalgebra::{Matrix4, Vector4};
+use shine_game::render::{halton, TaaSettings, TaaTechnique, TechniqueConfig, TemporalJitter};
+
+mod utils;
+
+#[test]
+fn halton_sequence() {
+    utils::init_logger();
+
+    assert_eq!(halton(1, 2), 0.5);
+    assert_eq!(halton(2, 2), 0.25);
+    assert_eq!(halton(3, 2), 0.75);
+    assert!((halton(1, 3) - 1. / 3.).abs() < 1e-6);
+    assert!((halton(2, 3) - 2. / 3.).abs() < 1e-6);
+}
+
+#[test]
+fn temporal_jitter() {
+    utils::init_logger();
+
+    let mut jitter = TemporalJitter::new(4);
+    for i in 0..8 {
+        let offset = jitter.sample_offset(i);
+        assert!(offset[0] >= -0.5 && offset[0] < 0.5);
+        assert!(offset[1] >= -0.5 && offset[1] < 0.5);
+    }
+    assert_eq!(jitter.sample_offset(1), jitter.sample_offset(5));
+
+    jitter.advance((100, 50));
+    let first = jitter.current();
+    assert_eq!(first, [2. * 0. / 100., 2. * (1. / 3. - 0.5) / 50.]);
+    jitter.advance((100, 50));
+    assert_eq!(jitter.previous(), first);
+
+    // the jitter translates the projected points by the offset in NDC
+    let projection = Matrix4::new_perspective(1., 1., 0.1, 100.);
+    let p = Vector4::new(1., 2., -5., 1.);
+    let a = projection * p;
+    let b = jitter.jitter_projection(&projection) * p;
+    let current = jitter.current();
+    assert!((b.x / b.w - a.x / a.w - current[0]).abs() < 1e-5);
+    assert!((b.y / b.w - a.y / a.w - current[1]).abs() < 1e-5);
+
+    jitter.reset();
+    assert_eq!(jitter.current(), [0., 0.]);
+}
+
+#[test]
+fn taa_rejection() {
+    utils::init_logger();
+
+    let settings = TaaSettings::default();
+    assert_eq!(settings.history_feedback(0.), settings.feedback_max);
+    assert_eq!(settings.history_feedback(1000.), settings.feedback_min);
+    assert!(settings.history_feedback(4.) < settings.feedback_max);
+
+    assert_eq!(
+        TaaSettings::clamp_history([0.5, 2., -1.], [0., 0., 0.], [1., 1., 1.]),
+        [0.5, 1., 0.]
+    );
+}
+
+#[test]
+fn taa_technique_config() {
+    utils::init_logger();
+
+    assert!(TaaTechnique::from_config(&TechniqueConfig::new("taa")).is_err());
+    let technique = TaaTechnique::from_config(
+        &TechniqueConfig::new("taa")
+            .with_option("pipeline", "taa.pl")
+            .with_option("samples", 16),
+    )
+    .unwrap();
+    assert_eq!(technique.settings().sample_count, 16);
+    assert!(TaaTechnique::from_config(
+        &TechniqueConfig::new("taa")
+            .with_option("pipeline", "taa.pl")
+            .with_option("feedback_max", 2.)
+    )
+    .is_err());
+}
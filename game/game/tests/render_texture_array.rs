@@ -0,0 +1,56 @@
+use shine_game::render::{TextureArrayPacker, TextureArrayStats, TextureSlot};
+
+mod utils;
+
+#[test]
+fn pack_textures_into_pages() {
+    utils::init_logger();
+
+    let mut packer = TextureArrayPacker::new(2);
+    let a = packer.acquire("a");
+    let b = packer.acquire("b");
+    let c = packer.acquire("c");
+    assert_eq!(a, TextureSlot { page: 0, index: 0 });
+    assert_eq!(b, TextureSlot { page: 0, index: 1 });
+    assert_eq!(c, TextureSlot { page: 1, index: 0 });
+    assert_eq!(packer.page_count(), 2);
+    assert_eq!(packer.take_dirty_pages(), vec![0, 1]);
+    assert!(packer.take_dirty_pages().is_empty());
+
+    // shared textures share the slot
+    assert_eq!(packer.acquire("a"), a);
+    assert!(packer.take_dirty_pages().is_empty());
+    assert_eq!(
+        packer.stats(),
+        TextureArrayStats {
+            pages: 2,
+            textures: 3,
+            free_slots: 1
+        }
+    );
+}
+
+#[test]
+fn release_and_reuse_slots() {
+    utils::init_logger();
+
+    let mut packer = TextureArrayPacker::new(2);
+    packer.acquire("a");
+    packer.acquire("a");
+    let b = packer.acquire("b");
+    packer.take_dirty_pages();
+
+    packer.release("a");
+    assert!(packer.slot("a").is_some());
+    assert!(packer.take_dirty_pages().is_empty());
+
+    packer.release("a");
+    assert!(packer.slot("a").is_none());
+    assert_eq!(packer.take_dirty_pages(), vec![0]);
+    assert_eq!(packer.page_textures(0), &[None, Some("b".to_string())]);
+
+    let d = packer.acquire("d");
+    assert_eq!(d, TextureSlot { page: 0, index: 0 });
+    assert_eq!(packer.slot("b"), Some(b));
+    assert_eq!(packer.page_count(), 1);
+}
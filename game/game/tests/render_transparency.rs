@@ -0,0 +1,92 @@
+use shine_game::{
+    assets::ColorStage,
+    render::{
+        oit_resolve, oit_weight, OitTargets, RenderQueue, TechniqueConfig, TransparencyMode, TransparencyTechnique,
+        OIT_ACCUMULATION_FORMAT, OIT_REVEALAGE_FORMAT,
+    },
+};
+
+mod utils;
+
+#[test]
+fn transparency_technique_config() {
+    utils::init_logger();
+
+    let technique = TransparencyTechnique::from_config(&TechniqueConfig::new("transparency")).unwrap();
+    assert_eq!(technique.mode(), TransparencyMode::Sorted);
+
+    assert!(TransparencyTechnique::from_config(
+        &TechniqueConfig::new("transparency").with_option("mode", "weighted_blended")
+    )
+    .is_err());
+    assert!(
+        TransparencyTechnique::from_config(&TechniqueConfig::new("transparency").with_option("mode", "fancy")).is_err()
+    );
+
+    let technique = TransparencyTechnique::from_config(
+        &TechniqueConfig::new("transparency")
+            .with_option("mode", "weighted_blended")
+            .with_option("resolve_pipeline", "oit_resolve.pl"),
+    )
+    .unwrap();
+    assert_eq!(technique.mode(), TransparencyMode::WeightedBlended);
+}
+
+#[test]
+fn oit_render_states() {
+    utils::init_logger();
+
+    let mut states = OitTargets::get_render_states(None);
+    assert_eq!(states.color_stage, Some(ColorStage::WeightedBlended));
+    assert_eq!(states.color_states.len(), 2);
+    assert_eq!(states.color_states[0].format, OIT_ACCUMULATION_FORMAT);
+    assert_eq!(states.color_states[1].format, OIT_REVEALAGE_FORMAT);
+
+    ColorStage::WeightedBlended.apply_all(&mut states.color_states);
+    assert_eq!(states.color_states[0].color_blend.dst_factor, wgpu::BlendFactor::One);
+    assert_eq!(states.color_states[1].color_blend, ColorStage::revealage_blend());
+    assert!(ColorStage::WeightedBlended.is_transparent());
+}
+
+#[test]
+fn oit_composition() {
+    utils::init_logger();
+
+    assert!(oit_weight(1., 0.5) > oit_weight(100., 0.5));
+    assert!(oit_weight(1., 0.5) > oit_weight(1., 0.1));
+
+    // a single layer is resolved to its own color and coverage
+    let (color, alpha) = ([0.2, 0.4, 0.8], 0.6);
+    let weight = oit_weight(10., alpha);
+    let accumulation = [
+        color[0] * alpha * weight,
+        color[1] * alpha * weight,
+        color[2] * alpha * weight,
+        alpha * weight,
+    ];
+    let resolved = oit_resolve(accumulation, 1. - alpha);
+    for i in 0..3 {
+        assert!((resolved[i] - color[i]).abs() < 1e-5);
+    }
+    assert!((resolved[3] - alpha).abs() < 1e-5);
+
+    // nothing accumulated is fully revealed
+    assert_eq!(oit_resolve([0., 0., 0., 0.], 1.)[3], 0.);
+}
+
+#[test]
+fn render_queue_order_independent() {
+    utils::init_logger();
+
+    let mut queue = RenderQueue::new();
+    queue.push(ColorStage::AlphaBlend, 2, 5., "glass");
+    queue.push(ColorStage::WeightedBlended, 1, 20., "leaf_far");
+    queue.push(ColorStage::Replace, 1, 10., "wall");
+    queue.push(ColorStage::WeightedBlended, 1, 2., "leaf_near");
+
+    queue.sort_order_independent();
+    let opaque: Vec<_> = queue.opaque().iter().map(|x| x.item).collect();
+    assert_eq!(opaque, vec!["wall"]);
+    let transparent: Vec<_> = queue.transparent().iter().map(|x| x.item).collect();
+    assert_eq!(transparent, vec!["leaf_far", "leaf_near", "glass"]);
+}
@@ -0,0 +1,107 @@
+use shine_ecs::{scheduler::IntoSystem, testing::TestWorld};
+use shine_game::{
+    event_bus::{bridge_save_conflicts, EventBus, SAVE_CONFLICT_EVENT},
+    savegame::{SaveCondition, SaveConflict, SaveMetadata, SaveResolution, SaveSync, SaveUpload, SaveUploadOutcome},
+};
+
+mod utils;
+
+fn metadata(slot: &str, modified: u64, playtime: u64) -> SaveMetadata {
+    SaveMetadata {
+        slot: slot.to_owned(),
+        modified,
+        playtime,
+        thumbnail: None,
+    }
+}
+
+fn conflicting_upload(saves: &mut SaveSync) -> SaveUpload {
+    saves.set_etag("slot1", "e1");
+    let upload = saves.prepare_upload(metadata("slot1", 200, 60));
+    assert_eq!(upload.condition, SaveCondition::IfMatch("e1".to_owned()));
+
+    let conflict = saves
+        .on_upload_result(
+            &upload,
+            SaveUploadOutcome::Conflict {
+                remote: metadata("slot1", 100, 90),
+                etag: "e2".to_owned(),
+            },
+        )
+        .cloned()
+        .unwrap();
+    assert!(conflict.is_local_newer());
+    assert_eq!(conflict.remote.playtime, 90);
+    upload
+}
+
+#[test]
+fn upload_without_conflict() {
+    utils::init_logger();
+
+    let mut saves = SaveSync::default();
+    let upload = saves.prepare_upload(metadata("slot1", 100, 10));
+    assert_eq!(upload.condition, SaveCondition::IfNotExists);
+    assert!(saves
+        .on_upload_result(&upload, SaveUploadOutcome::Stored { etag: "e1".to_owned() })
+        .is_none());
+    assert_eq!(saves.etag("slot1"), Some("e1"));
+    assert!(!saves.has_conflicts());
+}
+
+#[test]
+fn resolve_force_overwrite() {
+    utils::init_logger();
+
+    let mut saves = SaveSync::default();
+    conflicting_upload(&mut saves);
+    assert!(saves.has_conflicts());
+
+    let upload = saves.resolve("slot1", SaveResolution::ForceOverwrite).unwrap().unwrap();
+    assert_eq!(upload.condition, SaveCondition::Force);
+    assert_eq!(upload.metadata, metadata("slot1", 200, 60));
+    assert!(!saves.has_conflicts());
+    assert!(saves.resolve("slot1", SaveResolution::ForceOverwrite).is_err());
+}
+
+#[test]
+fn resolve_keep_remote_and_duplicate() {
+    utils::init_logger();
+
+    let mut saves = SaveSync::default();
+    conflicting_upload(&mut saves);
+    assert!(saves.resolve("slot1", SaveResolution::KeepRemote).unwrap().is_none());
+    assert_eq!(saves.etag("slot1"), Some("e2"));
+
+    conflicting_upload(&mut saves);
+    assert!(saves
+        .resolve("slot1", SaveResolution::DuplicateSlot("slot1".to_owned()))
+        .is_err());
+    let upload = saves
+        .resolve("slot1", SaveResolution::DuplicateSlot("slot2".to_owned()))
+        .unwrap()
+        .unwrap();
+    assert_eq!(upload.slot(), "slot2");
+    assert_eq!(upload.condition, SaveCondition::IfNotExists);
+    assert_eq!(saves.etag("slot1"), Some("e2"));
+}
+
+#[test]
+fn conflict_event() {
+    utils::init_logger();
+
+    let mut saves = SaveSync::default();
+    conflicting_upload(&mut saves);
+
+    let mut world = TestWorld::new().with_resource(saves).with_resource(EventBus::default());
+    world.run_system(bridge_save_conflicts.into_system()).unwrap();
+
+    let conflicts = world
+        .get::<EventBus>()
+        .iter_typed::<SaveConflict>(SAVE_CONFLICT_EVENT)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].slot(), "slot1");
+    assert!(world.get_mut::<SaveSync>().drain_new_conflicts().is_empty());
+}
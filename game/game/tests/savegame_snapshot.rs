@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+use shine_ecs::{
+    resources::Resources,
+    snapshot::{SnapshotFormat, SnapshotRegistry},
+};
+use shine_game::savegame::{SaveData, SaveMetadata};
+
+mod utils;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Inventory {
+    gold: u32,
+    items: Vec<String>,
+}
+
+#[test]
+fn save_and_load() {
+    utils::init_logger();
+
+    let mut registry = SnapshotRegistry::new();
+    registry.register_resource::<Inventory>("inventory");
+
+    let mut resources = Resources::default();
+    resources
+        .register_with_instance(Inventory {
+            gold: 12,
+            items: vec!["sword".to_owned()],
+        })
+        .unwrap();
+
+    let metadata = SaveMetadata {
+        slot: "slot1".to_owned(),
+        modified: 1000,
+        playtime: 60,
+        thumbnail: None,
+    };
+    let save = SaveData::capture(&registry, &resources, metadata, SnapshotFormat::Ron).unwrap();
+    let bytes = save.to_bytes().unwrap();
+    assert!(String::from_utf8(bytes.clone()).unwrap().contains("slot1"));
+
+    resources.get_mut::<Inventory>().unwrap().gold = 0;
+    let loaded = SaveData::from_bytes(SnapshotFormat::Ron, &bytes).unwrap();
+    assert_eq!(loaded, save);
+    loaded.restore(&registry, &mut resources).unwrap();
+    assert_eq!(resources.get::<Inventory>().unwrap().gold, 12);
+
+    assert!(SaveData::from_bytes(SnapshotFormat::Bincode, &bytes).is_err());
+}
@@ -0,0 +1,179 @@
+use nalgebra::{Isometry3, Matrix4, Perspective3, Point3, Vector3};
+use shine_game::spatial::{Aabb, Bvh, BvhConfig, Frustum, ProxyId, Ray, Sphere};
+use std::collections::HashSet;
+
+mod utils;
+
+fn unit_box(x: f32, y: f32, z: f32) -> Aabb {
+    Aabb::new(
+        Point3::new(x - 0.5, y - 0.5, z - 0.5),
+        Point3::new(x + 0.5, y + 0.5, z + 0.5),
+    )
+}
+
+/// Create a 10x10 grid of unit boxes on the xz plane with a spacing of 2
+fn create_grid(bvh: &mut Bvh<(i32, i32)>) -> Vec<ProxyId> {
+    let mut ids = Vec::new();
+    for x in 0..10 {
+        for z in 0..10 {
+            ids.push(bvh.insert(unit_box(x as f32 * 2., 0., z as f32 * 2.), (x, z)));
+        }
+    }
+    ids
+}
+
+fn collect_sphere(bvh: &Bvh<(i32, i32)>, sphere: &Sphere) -> HashSet<(i32, i32)> {
+    let mut result = HashSet::new();
+    bvh.query_sphere(sphere, |_, data| {
+        assert!(result.insert(*data));
+    });
+    result
+}
+
+#[test]
+fn bvh_sphere_query() {
+    utils::init_logger();
+
+    let mut bvh = Bvh::new(BvhConfig::default());
+    create_grid(&mut bvh);
+    assert_eq!(bvh.len(), 100);
+
+    // pending proxies are also found before the first rebuild
+    let sphere = Sphere::new(Point3::new(4., 0., 4.), 1.);
+    let before = collect_sphere(&bvh, &sphere);
+    assert_eq!(before, [(2, 2)].iter().cloned().collect());
+
+    bvh.rebuild();
+    log::debug!("{}", bvh.stats());
+    assert!(bvh.stats().node_count > 0);
+    assert!(bvh.stats().depth >= 5);
+    assert_eq!(collect_sphere(&bvh, &sphere), before);
+
+    let sphere = Sphere::new(Point3::new(3., 0., 3.), 1.);
+    let expected: HashSet<_> = [(1, 1), (1, 2), (2, 1), (2, 2)].iter().cloned().collect();
+    assert_eq!(collect_sphere(&bvh, &sphere), expected);
+
+    let mut found = HashSet::new();
+    bvh.query_aabb(
+        &Aabb::new(Point3::new(-1., -1., -1.), Point3::new(2.6, 1., 0.)),
+        |_, data| {
+            found.insert(*data);
+        },
+    );
+    assert_eq!(found, [(0, 0), (1, 0)].iter().cloned().collect());
+}
+
+#[test]
+fn bvh_update_and_remove() {
+    utils::init_logger();
+
+    let mut bvh = Bvh::new(BvhConfig {
+        max_pending: 4,
+        ..Default::default()
+    });
+    let ids = create_grid(&mut bvh);
+    assert!(bvh.maintain());
+    assert_eq!(bvh.stats().pending_count, 0);
+
+    // a small move is absorbed by the margin
+    assert!(bvh.update(ids[0], unit_box(0.05, 0., 0.)));
+    assert!(!bvh.maintain());
+    assert_eq!(bvh.stats().pending_count, 0);
+
+    // a large move makes the proxy pending
+    assert!(bvh.update(ids[0], unit_box(50., 0., 50.)));
+    assert!(!bvh.maintain());
+    assert_eq!(bvh.stats().pending_count, 1);
+    assert!(collect_sphere(&bvh, &Sphere::new(Point3::new(0., 0., 0.), 0.2)).is_empty());
+    assert_eq!(
+        collect_sphere(&bvh, &Sphere::new(Point3::new(50., 0., 50.), 0.2)),
+        [(0, 0)].iter().cloned().collect()
+    );
+
+    assert_eq!(bvh.remove(ids[1]), Some((0, 1)));
+    assert_eq!(bvh.remove(ids[1]), None);
+    assert_eq!(bvh.len(), 99);
+    assert!(collect_sphere(&bvh, &Sphere::new(Point3::new(0., 0., 2.), 0.2)).is_empty());
+
+    // the slot of the removed proxy is reused
+    let id = bvh.insert(unit_box(0., 0., 2.), (-1, -1));
+    assert_eq!(id, ids[1]);
+    assert_eq!(
+        collect_sphere(&bvh, &Sphere::new(Point3::new(0., 0., 2.), 0.2)),
+        [(-1, -1)].iter().cloned().collect()
+    );
+
+    for x in 0..5 {
+        bvh.update(ids[10 * x + 5], unit_box(x as f32 * 2., 10., 10.));
+    }
+    assert!(bvh.maintain());
+    assert_eq!(bvh.stats().pending_count, 0);
+    assert_eq!(
+        collect_sphere(&bvh, &Sphere::new(Point3::new(4., 10., 10.), 0.2)),
+        [(2, 5)].iter().cloned().collect()
+    );
+}
+
+#[test]
+fn bvh_raycast() {
+    utils::init_logger();
+
+    let mut bvh = Bvh::default();
+    create_grid(&mut bvh);
+    bvh.rebuild();
+
+    let ray = Ray::new(Point3::new(-5., 0., 4.), Vector3::new(1., 0., 0.));
+    let (id, t) = bvh.raycast(&ray, 100., |_, _| true).unwrap();
+    assert_eq!(bvh.get(id), Some(&(0, 2)));
+    assert!((t - 4.5).abs() < 1e-5);
+
+    let (id, _) = bvh.raycast(&ray, 100., |_, data| data.0 > 3).unwrap();
+    assert_eq!(bvh.get(id), Some(&(4, 2)));
+    assert!(bvh.raycast(&ray, 4., |_, _| true).is_none());
+
+    let mut hits = 0;
+    bvh.query_ray(&ray, 100., |_, _, _| hits += 1);
+    assert_eq!(hits, 10);
+
+    // between the rows
+    assert!(bvh.line_of_sight(Point3::new(-5., 0., 3.), Point3::new(30., 0., 3.), |_, _| true));
+    assert!(!bvh.line_of_sight(Point3::new(-5., 0., 4.), Point3::new(30., 0., 4.), |_, _| true));
+    assert!(bvh.line_of_sight(Point3::new(-5., 0., 4.), Point3::new(30., 0., 4.), |_, _| false));
+}
+
+#[test]
+fn bvh_frustum() {
+    utils::init_logger();
+
+    let mut bvh = Bvh::default();
+    create_grid(&mut bvh);
+    bvh.rebuild();
+
+    // camera at the corner of the grid looking along the x axis
+    let view = Isometry3::look_at_rh(
+        &Point3::new(-2., 0., 0.),
+        &Point3::new(10., 0., 0.),
+        &Vector3::new(0., 1., 0.),
+    );
+    // convert to the 0..1 depth range
+    #[rustfmt::skip]
+    let gl_to_wgpu = Matrix4::new(
+        1., 0., 0., 0.,
+        0., 1., 0., 0.,
+        0., 0., 0.5, 0.5,
+        0., 0., 0., 1.,
+    );
+    let projection = gl_to_wgpu * Perspective3::new(1., 0.5, 0.1, 100.).into_inner();
+    let frustum = Frustum::from_view_projection(&(projection * view.to_homogeneous()));
+
+    let mut visible = HashSet::new();
+    bvh.query_frustum(&frustum, |_, data| {
+        visible.insert(*data);
+    });
+    log::debug!("visible: {:?}", visible);
+    assert!(visible.contains(&(0, 0)));
+    assert!(visible.contains(&(9, 0)));
+    assert!(!visible.contains(&(0, 9)));
+    assert!(!visible.contains(&(9, 9)));
+    assert!(visible.len() < 50);
+}
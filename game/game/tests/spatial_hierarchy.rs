@@ -0,0 +1,70 @@
+use nalgebra::{Point3, UnitQuaternion, Vector3};
+use shine_ecs::{
+    hierarchy::{Entities, Hierarchy},
+    scheduler::IntoSystem,
+    testing::TestWorld,
+};
+use shine_game::spatial::{propagate_transforms, GlobalTransform, Transform};
+use std::f32::consts::FRAC_PI_2;
+
+mod utils;
+
+fn assert_near(a: &Point3<f32>, b: &Point3<f32>) {
+    assert!((a - b).norm() < 1.0e-5, "{:?} != {:?}", a, b);
+}
+
+#[test]
+fn transform_propagation() {
+    utils::init_logger();
+
+    let mut world = TestWorld::new()
+        .with_resource(Entities::new())
+        .with_system("update", propagate_transforms.into_system());
+
+    let (root, arm, hand) = {
+        let mut entities = world.get_mut::<Entities>();
+        let root = entities.spawn((Transform::new(
+            Vector3::new(10., 0., 0.),
+            UnitQuaternion::from_axis_angle(&Vector3::z_axis(), FRAC_PI_2),
+            2.,
+        ),));
+        let arm = entities.spawn((Transform::from_translation(Vector3::new(1., 0., 0.)),));
+        let hand = entities.spawn((Transform::from_translation(Vector3::new(0., 1., 0.)),));
+        entities.attach(arm, root).unwrap();
+        entities.attach(hand, arm).unwrap();
+        (root, arm, hand)
+    };
+
+    world.run_stage("update").unwrap();
+    {
+        let entities = world.get::<Entities>();
+        let origin = Point3::origin();
+        let global = |entity| *entities.get::<GlobalTransform>(entity).unwrap();
+        assert_near(&global(root).transform_point(&origin), &Point3::new(10., 0., 0.));
+        // rotated by 90 degrees and scaled by 2 by the root
+        assert_near(&global(arm).transform_point(&origin), &Point3::new(10., 2., 0.));
+        assert_near(&global(hand).transform_point(&origin), &Point3::new(8., 2., 0.));
+        assert_eq!(global(hand).scale, 2.);
+        assert_near(
+            &global(hand).matrix().transform_point(&Point3::new(1., 0., 0.)),
+            &Point3::new(8., 4., 0.),
+        );
+    }
+
+    // moving the parent moves the children in the next update
+    world
+        .get_mut::<Entities>()
+        .get_mut::<Transform>(root)
+        .unwrap()
+        .translation = Vector3::zeros();
+    world.get_mut::<Entities>().detach(hand).unwrap();
+    world.run_stage("update").unwrap();
+    let entities = world.get::<Entities>();
+    let arm_global = *entities.get::<GlobalTransform>(arm).unwrap();
+    assert_near(&arm_global.transform_point(&Point3::origin()), &Point3::new(0., 2., 0.));
+    let hand_global = *entities.get::<GlobalTransform>(hand).unwrap();
+    assert_eq!(
+        hand_global,
+        GlobalTransform::from(Transform::from_translation(Vector3::new(0., 1., 0.)))
+    );
+}
@@ -0,0 +1,79 @@
+use nalgebra::{Point3, UnitQuaternion, Vector3, Vector4};
+use shine_game::spatial::{FloatingOrigin, WorldPosition, WorldTransform, WORLD_SECTOR_SIZE};
+
+mod utils;
+
+#[test]
+fn world_position_conversion() {
+    utils::init_logger();
+
+    let far = Point3::new(1.0e7 + 0.25, -3.5e6 - 0.125, 42.5);
+    let position = WorldPosition::from_f64(&far);
+    assert!(position
+        .offset
+        .iter()
+        .all(|&c| c >= 0. && (c as f64) < WORLD_SECTOR_SIZE));
+    assert_eq!(position.to_f64(), far);
+
+    let mut moved = position;
+    moved.translate(&Vector3::new(2000., -2000., 0.5));
+    assert!(moved.offset.iter().all(|&c| c >= 0. && (c as f64) < WORLD_SECTOR_SIZE));
+    assert_eq!(moved.to_f64(), Point3::new(far.x + 2000., far.y - 2000., far.z + 0.5));
+
+    // sub-millimeter differences are kept far from the world origin
+    let near = WorldPosition::from_f64(&Point3::new(far.x + 0.001, far.y, far.z));
+    let delta = near.relative_to(&position);
+    assert!((delta.x - 0.001).abs() < 1.0e-4);
+
+    let back = WorldPosition::from_relative(&position, &Vector3::new(5000., 0., 0.));
+    assert_eq!(back.to_f64(), Point3::new(far.x + 5000., far.y, far.z));
+}
+
+#[test]
+fn floating_origin_rebase() {
+    utils::init_logger();
+
+    let mut origin = FloatingOrigin::new(100.);
+    let mut camera = WorldTransform::default();
+    camera.position = WorldPosition::from_f64(&Point3::new(50., 0., 0.));
+    assert!(!origin.update_camera(&camera));
+    assert_eq!(origin.generation(), 0);
+
+    let object = WorldPosition::from_f64(&Point3::new(1.0e6 + 10., 0., 0.));
+    camera.position = WorldPosition::from_f64(&Point3::new(1.0e6, 0., 0.));
+    let before = origin.to_local(&object);
+    assert!(origin.update_camera(&camera));
+    assert_eq!(origin.generation(), 1);
+    let after = origin.to_local(&object);
+    assert!((before + origin.last_shift() - after).norm() < 0.1);
+    assert!(after.norm() < WORLD_SECTOR_SIZE as f32 * 2.);
+    assert_eq!(origin.to_world(&after), object);
+}
+
+#[test]
+fn camera_relative_matrices() {
+    utils::init_logger();
+
+    let mut origin = FloatingOrigin::default();
+    let camera = WorldTransform::new(
+        WorldPosition::from_f64(&Point3::new(1.0e8, 0., -1.0e8)),
+        UnitQuaternion::from_euler_angles(0., 0.5, 0.),
+    );
+    origin.update_camera(&camera);
+
+    let object = WorldTransform::new(
+        WorldPosition::from_f64(&Point3::new(1.0e8 + 1.5, 2., -1.0e8)),
+        UnitQuaternion::identity(),
+    );
+    let model = origin.camera_relative_model(&object);
+    let p = model * Vector4::new(0., 0., 0., 1.);
+    assert!((p.x - 1.5).abs() < 1.0e-5 && (p.y - 2.).abs() < 1.0e-5 && p.z.abs() < 1.0e-5);
+
+    let local = object.relative_isometry(origin.origin());
+    let model_local = origin.camera_relative_local_model(&local, 1.);
+    assert!((model_local - model).norm() < 1.0e-3);
+
+    let view = origin.camera_relative_view();
+    let eye = view * Vector4::new(0., 0., 0., 1.);
+    assert!(eye.xyz().norm() < 1.0e-6);
+}
@@ -0,0 +1,112 @@
+use shine_game::voice::{JitterBuffer, JitterFrame, TalkGate, TalkMode, VoicePacket, END_OF_TALK};
+
+mod utils;
+
+fn packet(sequence: u16, flags: u8) -> VoicePacket {
+    VoicePacket {
+        flags,
+        sequence,
+        payload: vec![sequence as u8; 4],
+    }
+}
+
+#[test]
+fn voice_packet_format() {
+    utils::init_logger();
+
+    let sent = packet(0x1234, END_OF_TALK);
+    let data = sent.to_bytes();
+    assert_eq!(&data[..3], &[END_OF_TALK, 0x34, 0x12]);
+
+    // the server prefixes the packet with the slot of the talker
+    let mut forwarded = vec![7, 0];
+    forwarded.extend_from_slice(&data);
+    let (slot, received) = VoicePacket::from_forward(&forwarded).unwrap();
+    assert_eq!(slot, 7);
+    assert_eq!(received, sent);
+    assert!(received.is_end_of_talk());
+
+    assert!(VoicePacket::from_forward(&[1, 0, 0]).is_err());
+}
+
+#[test]
+fn push_to_talk() {
+    utils::init_logger();
+
+    let silence = vec![0.; 960];
+    let mut gate = TalkGate::new(TalkMode::PushToTalk);
+    assert_eq!(gate.process(&silence), None);
+
+    gate.set_push_to_talk(true);
+    let first = gate.process(&silence).unwrap();
+    assert_eq!(first.flags, 0);
+    let second = gate.process(&silence).unwrap();
+    assert_eq!(second.sequence, first.sequence.wrapping_add(1));
+    assert!(gate.is_talking());
+
+    // the frame after the release closes the talk
+    gate.set_push_to_talk(false);
+    assert_eq!(gate.process(&silence).unwrap().flags, END_OF_TALK);
+    assert!(!gate.is_talking());
+    assert_eq!(gate.process(&silence), None);
+
+    // muting overrides the button
+    gate.set_muted(true);
+    gate.set_push_to_talk(true);
+    assert_eq!(gate.process(&silence), None);
+}
+
+#[test]
+fn voice_activation() {
+    utils::init_logger();
+
+    let silence = vec![0.; 960];
+    let speech = vec![0.5; 960];
+    let mut gate = TalkGate::new(TalkMode::VoiceActivation {
+        threshold: 0.1,
+        hangover: 2,
+    });
+    assert_eq!(gate.process(&silence), None);
+    assert_eq!(gate.process(&speech).unwrap().flags, 0);
+    // short pauses are kept
+    assert_eq!(gate.process(&silence).unwrap().flags, 0);
+    assert_eq!(gate.process(&silence).unwrap().flags, 0);
+    assert_eq!(gate.process(&silence).unwrap().flags, END_OF_TALK);
+    assert_eq!(gate.process(&silence), None);
+}
+
+#[test]
+fn jitter_buffer() {
+    utils::init_logger();
+
+    let mut buffer = JitterBuffer::new(3);
+    assert_eq!(buffer.pop(), JitterFrame::Empty);
+
+    // out of order arrival, the third packet is lost
+    assert!(buffer.push(packet(u16::MAX, 0)));
+    assert_eq!(buffer.pop(), JitterFrame::Empty);
+    assert!(buffer.push(packet(1, 0)));
+    assert!(buffer.push(packet(0, 0)));
+    assert!(!buffer.push(packet(0, 0)));
+
+    assert_eq!(buffer.pop(), JitterFrame::Packet(packet(u16::MAX, 0)));
+    assert_eq!(buffer.pop(), JitterFrame::Packet(packet(0, 0)));
+    assert_eq!(buffer.pop(), JitterFrame::Packet(packet(1, 0)));
+    // late packets are dropped
+    assert!(!buffer.push(packet(0, 0)));
+    assert!(buffer.push(packet(3, 0)));
+    assert_eq!(buffer.pop(), JitterFrame::Lost);
+    assert_eq!(buffer.pop(), JitterFrame::Packet(packet(3, 0)));
+
+    // underrun, the buffer is refilled before the playback continues
+    assert_eq!(buffer.pop(), JitterFrame::Empty);
+    assert!(buffer.push(packet(4, 0)));
+    assert_eq!(buffer.pop(), JitterFrame::Empty);
+
+    // the end of the talk is played without waiting for the delay
+    assert!(buffer.push(packet(5, END_OF_TALK)));
+    assert_eq!(buffer.pop(), JitterFrame::Packet(packet(4, 0)));
+    assert_eq!(buffer.pop(), JitterFrame::Packet(packet(5, END_OF_TALK)));
+    assert!(buffer.is_empty());
+    assert_eq!(buffer.pop(), JitterFrame::Empty);
+}
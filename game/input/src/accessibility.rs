@@ -0,0 +1,160 @@
+use crate::{InputId, InputState, InputValue};
+use std::collections::{HashMap, HashSet};
+
+/// Repeat rate of the held buttons in microseconds
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RepeatRate {
+    /// Time before the first repeat
+    pub delay: u128,
+    /// Time between the repeats
+    pub interval: u128,
+}
+
+impl Default for RepeatRate {
+    fn default() -> Self {
+        RepeatRate {
+            delay: 500_000,
+            interval: 100_000,
+        }
+    }
+}
+
+/// Accessibility processing of the inputs applied uniformly for every game
+#[derive(Clone, Debug)]
+pub struct AccessibilityOptions {
+    /// Buttons converted from hold to toggle: a press activates, the next press deactivates them
+    pub toggle_inputs: HashSet<InputId>,
+    /// Scale of the analog (offset) inputs
+    pub sensitivity: f32,
+    /// The analog inputs not affected by the sensitivity, ex. absolute positions
+    pub absolute_inputs: HashSet<InputId>,
+    /// Modifier buttons that remain active after release until the next button press
+    pub sticky_modifiers: HashSet<InputId>,
+    /// Buttons repeated with the repeat rate while held
+    pub repeat_inputs: HashSet<InputId>,
+    pub repeat_rate: RepeatRate,
+}
+
+impl Default for AccessibilityOptions {
+    fn default() -> Self {
+        AccessibilityOptions {
+            toggle_inputs: HashSet::new(),
+            sensitivity: 1.,
+            absolute_inputs: HashSet::new(),
+            sticky_modifiers: HashSet::new(),
+            repeat_inputs: HashSet::new(),
+            repeat_rate: RepeatRate::default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct RepeatState {
+    next: u128,
+}
+
+/// Apply the accessibility options on the input states. It keeps track of the raw button states to detect
+/// the press and release edges.
+#[derive(Default)]
+pub struct AccessibilityProcessor {
+    options: AccessibilityOptions,
+    pressed: HashSet<InputId>,
+    toggled: HashSet<InputId>,
+    latched: HashSet<InputId>,
+    repeats: HashMap<InputId, RepeatState>,
+}
+
+impl AccessibilityProcessor {
+    pub fn new(options: AccessibilityOptions) -> AccessibilityProcessor {
+        AccessibilityProcessor {
+            options,
+            ..Default::default()
+        }
+    }
+
+    pub fn options(&self) -> &AccessibilityOptions {
+        &self.options
+    }
+
+    /// Forget the state of some inputs, ex. when an input context is deactivated
+    pub fn release_inputs(&mut self, ids: &[InputId]) {
+        for id in ids {
+            self.pressed.remove(id);
+            self.toggled.remove(id);
+            self.latched.remove(id);
+            self.repeats.remove(id);
+        }
+    }
+
+    /// Process the (raw) state of a frame in place. Time is in microseconds.
+    pub fn process(&mut self, state: &mut InputState, time: u128) {
+        let pressed: HashSet<InputId> = state
+            .iter()
+            .filter(|(_, value)| *value == InputValue::D0)
+            .map(|(id, _)| id)
+            .collect();
+        let press_edges: HashSet<InputId> = pressed.difference(&self.pressed).cloned().collect();
+        let release_edges: HashSet<InputId> = self.pressed.difference(&pressed).cloned().collect();
+
+        // hold to toggle
+        for id in press_edges.intersection(&self.options.toggle_inputs) {
+            if !self.toggled.remove(id) {
+                self.toggled.insert(*id);
+            }
+        }
+        for id in &self.options.toggle_inputs {
+            if self.toggled.contains(id) {
+                state.set_input(*id, InputValue::D0, false);
+            } else {
+                state.clear_input(*id);
+            }
+        }
+
+        // sticky modifiers, the latch is released by the press of a non-modifier button
+        let other_pressed = press_edges.iter().any(|id| !self.options.sticky_modifiers.contains(id));
+        for id in release_edges.intersection(&self.options.sticky_modifiers) {
+            self.latched.insert(*id);
+        }
+        for id in &self.latched {
+            state.set_input(*id, InputValue::D0, false);
+        }
+        if other_pressed {
+            self.latched.clear();
+        }
+
+        // repeat rate
+        let repeat_rate = self.options.repeat_rate;
+        self.repeats.retain(|id, _| pressed.contains(id));
+        for id in pressed.intersection(&self.options.repeat_inputs) {
+            let repeat = self.repeats.entry(*id).or_insert(RepeatState {
+                next: time + repeat_rate.delay,
+            });
+            if press_edges.contains(id) {
+                // the first press is always reported
+            } else if time >= repeat.next {
+                repeat.next = time + repeat_rate.interval.max(1);
+            } else {
+                state.clear_input(*id);
+            }
+        }
+
+        // sensitivity
+        let sensitivity = self.options.sensitivity;
+        if (sensitivity - 1.).abs() > f32::EPSILON {
+            let absolute_inputs = &self.options.absolute_inputs;
+            state.map_values(|id, value| {
+                if absolute_inputs.contains(&id) {
+                    return value;
+                }
+                match value {
+                    InputValue::D1(x) => InputValue::D1(x * sensitivity),
+                    InputValue::D2(x, y) => InputValue::D2(x * sensitivity, y * sensitivity),
+                    InputValue::D3(x, y, z) => InputValue::D3(x * sensitivity, y * sensitivity, z * sensitivity),
+                    value => value,
+                }
+            });
+        }
+
+        self.pressed = pressed;
+    }
+}
@@ -1,6 +1,8 @@
 pub mod guestures;
 pub use self::guestures::{Guesture, GuestureManager};
 
+mod accessibility;
+pub use self::accessibility::*;
 mod manager;
 pub use self::manager::*;
 mod state;
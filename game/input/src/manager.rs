@@ -1,4 +1,4 @@
-use crate::{GuestureManager, InputState};
+use crate::{AccessibilityOptions, AccessibilityProcessor, GuestureManager, InputId, InputState};
 use std::mem;
 
 #[cfg(feature = "native")]
@@ -6,22 +6,49 @@ use std::time::SystemTime;
 #[cfg(feature = "wasm")]
 use wasm_timer::SystemTime;
 
+/// Current time in microseconds. Input events shall be timestamped using this clock.
+pub fn input_timestamp() -> u128 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_micros()
+}
+
 pub struct InputManager {
     time: u128,
+    accessibility: AccessibilityProcessor,
 }
 
 impl Default for InputManager {
     fn default() -> Self {
-        Self { time: 0 }
+        Self {
+            time: 0,
+            accessibility: AccessibilityProcessor::default(),
+        }
     }
 }
 
 impl InputManager {
     fn now() -> u128 {
-        SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_micros()
+        input_timestamp()
+    }
+
+    /// Time of the last advance in microseconds
+    pub fn time(&self) -> u128 {
+        self.time
+    }
+
+    pub fn accessibility(&self) -> &AccessibilityOptions {
+        self.accessibility.options()
+    }
+
+    pub fn set_accessibility(&mut self, options: AccessibilityOptions) {
+        self.accessibility = AccessibilityProcessor::new(options);
+    }
+
+    /// Forget the accessibility state of some inputs, ex. a toggled action of a deactivated context
+    pub fn release_inputs(&mut self, ids: &[InputId]) {
+        self.accessibility.release_inputs(ids);
     }
 
     /// Prepare for the next input frame.
@@ -43,6 +70,8 @@ impl InputManager {
         on_update(previous, current);
         mem::swap(previous, current);
         current.init_from(previous, self.time);
+        // process the frame after the copy, thus the next frame starts from the raw state
+        self.accessibility.process(previous, self.time);
     }
 
     /// Prepare for the next input frame.
@@ -169,4 +169,17 @@ impl InputState {
     pub fn get_input(&self, id: InputId) -> InputValue {
         self.inputs.get(&id).map(|a| a.value).unwrap_or(InputValue::Off)
     }
+
+    /// Iterate over the active inputs
+    pub fn iter(&self) -> impl Iterator<Item = (InputId, InputValue)> + '_ {
+        self.inputs.iter().map(|(id, data)| (*id, data.value))
+    }
+
+    /// Update the value of the active inputs in place, the auto reset flags are kept.
+    pub fn map_values<F: FnMut(InputId, InputValue) -> InputValue>(&mut self, mut f: F) {
+        for (id, data) in self.inputs.iter_mut() {
+            data.value = f(*id, data.value);
+        }
+        self.inputs.retain(|_, data| data.value != InputValue::Off);
+    }
 }
@@ -1,12 +1,15 @@
 use shine_game::{
-    app::{App, AppError, Config},
+    app::{App, AppError, Config, SmokeTest, SmokeTestConfig},
     assets::{AssetPlugin, Url},
     game::test1,
     input::{InputPlugin, InputWorld},
     render::{RenderPlugin, RenderWorld, Surface},
     wgpu,
 };
-use std::time::{Duration, Instant};
+use std::{
+    env,
+    time::{Duration, Instant},
+};
 use tokio::runtime::{Handle as RuntimeHandle, Runtime};
 use winit::{
     event::{ElementState, Event, VirtualKeyCode, WindowEvent},
@@ -16,6 +19,8 @@ use winit::{
 #[cfg(windows)]
 use winit::platform::windows::EventLoopExtWindows;
 
+mod smoke_test;
+
 const TARGET_FPS: u64 = 30;
 
 #[derive(Debug, Clone)]
@@ -50,6 +55,7 @@ async fn run() {
 
         let surface = Surface::new(surface, size);
         let config = Config::new().unwrap();
+        let smoke_test_config = SmokeTestConfig::from_args(env::args().skip(1)).unwrap();
         let mut app = App::default();
 
         log::debug!("Init plugins");
@@ -63,6 +69,11 @@ async fn run() {
         })
         .unwrap();
 
+        let mut smoke_test = smoke_test_config.map(SmokeTest::new);
+        if let Some(test) = &mut smoke_test {
+            smoke_test::load_game(&rt, &mut app, test);
+        }
+
         log::debug!("Starting logic thread");
         let event_proxy = event_loop.create_proxy();
         tokio::task::spawn(logic(event_proxy));
@@ -98,6 +109,11 @@ async fn run() {
                                         test1::Test1::load_into_app(&mut app, &url).await
                                     })
                                     .unwrap(),
+                                Some(VirtualKeyCode::F12) => {
+                                    if let Err(err) = RenderPlugin::trigger_capture(&app.world) {
+                                        log::warn!("Failed to trigger frame capture: {:?}", err);
+                                    }
+                                }
                                 //Some(VirtualKeyCode::Key2) => rt.block_on(app.load_game_from_url(&test2_url)).unwrap(),
                                 //Some(VirtualKeyCode::Key3) => rt.block_on(app.load_game_from_url(&test3_url)).unwrap(),
                                 //Some(VirtualKeyCode::Key4) => rt.block_on(app.load_game_from_url(&test4_url)).unwrap(),
@@ -127,6 +143,20 @@ async fn run() {
                 return;
             }
 
+            // the smoke test renders the frames without throttling and exits with the result
+            if let Some(test) = &mut smoke_test {
+                if !test.is_done() {
+                    smoke_test::run_frame(&mut app, test, size);
+                }
+                if test.is_done() {
+                    let test = smoke_test.take().unwrap();
+                    let exit_code = smoke_test::finish(&rt, &mut app, test, size);
+                    std::process::exit(exit_code);
+                }
+                *control_flow = ControlFlow::Poll;
+                return;
+            }
+
             let now = Instant::now();
             let elapsed_time = now.duration_since(prev_render_time).as_millis() as i64;
             let wait_millis = ((1000 / TARGET_FPS) as i64) - elapsed_time;
@@ -0,0 +1,82 @@
+use shine_ecs::events::Events;
+use shine_game::{
+    app::{App, AppError, ScriptedKey, SmokeTest},
+    assets::Url,
+    game::test1,
+    input::InputWorld,
+    render::{RenderError, RenderWorld},
+};
+use std::time::Instant;
+use tokio::runtime::Handle as RuntimeHandle;
+use winit::event::{ElementState, KeyboardInput, ModifiersState};
+
+#[allow(deprecated)]
+fn key_event(key: &ScriptedKey) -> KeyboardInput {
+    KeyboardInput {
+        scancode: key.scancode,
+        state: if key.pressed {
+            ElementState::Pressed
+        } else {
+            ElementState::Released
+        },
+        virtual_keycode: None,
+        modifiers: ModifiersState::default(),
+    }
+}
+
+pub fn load_game(rt: &RuntimeHandle, app: &mut App, test: &mut SmokeTest) {
+    log::info!("Smoke test: loading {}", test.config().game);
+    let result = rt.block_on(async {
+        let url = Url::parse(&test.config().game).map_err(|err| AppError::game("smoke-test", err))?;
+        test1::Test1::load_into_app(app, &url).await
+    });
+    if let Err(err) = result {
+        test.fail(format!("Failed to load game: {:?}", err));
+    }
+}
+
+/// Inject the input of the frame and render it
+pub fn run_frame(app: &mut App, test: &mut SmokeTest, size: (u32, u32)) {
+    for key in test.frame_input() {
+        let _ = app.world.inject_input(&key_event(&key));
+    }
+
+    let start = Instant::now();
+    let result = app.world.render(size);
+    let frame_time = start.elapsed().as_secs_f64() * 1000.;
+    match result {
+        Ok(_) => {
+            let errors = app.world.resources.get::<Events<RenderError>>().ok();
+            test.record_frame(frame_time, errors.as_deref());
+        }
+        Err(err) => test.fail(format!("Failed to render frame: {:?}", err)),
+    }
+}
+
+/// Capture the screenshot, unload the game and print the report. Returns the exit code of the process.
+pub fn finish(rt: &RuntimeHandle, app: &mut App, mut test: SmokeTest, size: (u32, u32)) -> i32 {
+    if let Some(path) = test.config().screenshot.clone() {
+        let screenshot = app
+            .world
+            .render_screenshot(size)
+            .map_err(|err| format!("{:?}", err))
+            .and_then(|readback| rt.block_on(readback.read()).map_err(|err| format!("{:?}", err)))
+            .and_then(|screenshot| screenshot.to_png().map_err(|err| format!("{:?}", err)))
+            .and_then(|png| std::fs::write(&path, png).map_err(|err| format!("{:?}", err)));
+        match screenshot {
+            Ok(_) => test.set_screenshot(&path),
+            Err(err) => test.fail(format!("Failed to capture screenshot: {}", err)),
+        }
+    }
+
+    if let Err(err) = rt.block_on(app.deinit_game()) {
+        test.fail(format!("Failed to unload game: {:?}", err));
+    }
+
+    let report = test.finish();
+    match serde_json::to_string_pretty(&report) {
+        Ok(report) => println!("{}", report),
+        Err(err) => log::error!("Failed to serialize report: {:?}", err),
+    }
+    report.exit_code()
+}